@@ -0,0 +1,77 @@
+//! CalDAV REPORT responses: the multistatus XML clients download on every
+//! sync, sized like a small and a busy calendar
+
+use api::bench::{
+    generate_calendar_multiget_response, generate_calendar_query_response,
+    generate_sync_collection_response,
+};
+use chrono::{TimeZone, Utc};
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use std::hint::black_box;
+use televent_application::{CalDavCalendarState, CalDavEventResource, CalDavTombstone};
+
+const ROOT: &str = "/caldav";
+const USER: &str = "123456789";
+
+fn resources(count: usize) -> Vec<CalDavEventResource> {
+    (0..count)
+        .map(|i| {
+            let uid = format!("bench-{i}@televent");
+            CalDavEventResource {
+                calendar_data: format!(
+                    "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:-//Televent//EN\r\n\
+                     BEGIN:VEVENT\r\nUID:{uid}\r\nDTSTAMP:20260301T090000Z\r\n\
+                     DTSTART:20260302T090000Z\r\nDTEND:20260302T103000Z\r\n\
+                     SUMMARY:Planning session #{i}\r\nLOCATION:Room 4\r\n\
+                     END:VEVENT\r\nEND:VCALENDAR\r\n"
+                ),
+                uid,
+                etag: format!("\"{i:016x}\""),
+                updated_at: Utc.with_ymd_and_hms(2026, 3, 1, 9, 0, 0).unwrap(),
+            }
+        })
+        .collect()
+}
+
+fn reports(c: &mut Criterion) {
+    let calendar = CalDavCalendarState {
+        sync_token: 4200,
+        ctag: 4200,
+    };
+    let mut group = c.benchmark_group("caldav_report");
+    for count in [10, 100, 1000] {
+        let events = resources(count);
+        let tombstones: Vec<_> = (0..count / 10)
+            .map(|i| CalDavTombstone {
+                uid: format!("deleted-{i}@televent"),
+            })
+            .collect();
+        group.throughput(Throughput::Elements(count as u64));
+        group.bench_with_input(BenchmarkId::new("calendar_query", count), &count, |b, _| {
+            b.iter(|| generate_calendar_query_response(ROOT, USER, black_box(&events)).unwrap());
+        });
+        group.bench_with_input(BenchmarkId::new("multiget", count), &count, |b, _| {
+            b.iter(|| generate_calendar_multiget_response(ROOT, USER, black_box(&events)).unwrap());
+        });
+        group.bench_with_input(
+            BenchmarkId::new("sync_collection", count),
+            &count,
+            |b, _| {
+                b.iter(|| {
+                    generate_sync_collection_response(
+                        ROOT,
+                        USER,
+                        &calendar,
+                        black_box(&events),
+                        black_box(&tombstones),
+                    )
+                    .unwrap()
+                });
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, reports);
+criterion_main!(benches);
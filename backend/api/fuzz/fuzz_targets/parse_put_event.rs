@@ -0,0 +1,10 @@
+//! CalDAV `PUT` bodies: arbitrary bytes through the iCalendar parser and the
+//! conversion to an event command. Parse errors are fine; panics are not.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    api::fuzz::fuzz_put_event(data);
+});
@@ -2,15 +2,44 @@
 
 use anyhow::{Context, Result};
 use std::env;
+use std::time::Duration;
+
+use crate::middleware::{
+    base_path::BasePath,
+    cors::{CorsPolicy, DEFAULT_CORS_MAX_AGE},
+    device_budget::DeviceBudget,
+    static_assets::FrontendAssets,
+};
+
+const DEFAULT_TRIGGER_REQUESTS_PER_MINUTE: u32 = 10;
 
 /// Server configuration
 #[derive(Debug, Clone)]
 pub struct Config {
     pub host: String,
     pub port: u16,
-    pub cors_allowed_origin: String,
+    /// Origins browsers may call the API from
+    pub cors: CorsPolicy,
     pub frontend_static_dir: Option<String>,
     pub enable_swagger: bool,
+    /// Whether public event pages accept email signups; needs external
+    /// email delivery for the confirmation links
+    pub email_signups: bool,
+    /// Secret path segment of the email provider webhooks; unset disables them
+    pub email_webhook_secret: Option<String>,
+    /// Whether users can register a phone number for text notifications;
+    /// needs an SMS provider to deliver the confirmation codes
+    pub sms_notifications: bool,
+    /// VAPID public key browsers subscribe with; `None` disables Web Push
+    pub web_push_public_key: Option<String>,
+    /// Per-device request rate and body size limits on CalDAV/CardDAV
+    pub device_budget: DeviceBudget,
+    /// Per-device limits on the polling triggers under `/api/triggers`
+    pub trigger_budget: DeviceBudget,
+    /// Prefix the service is reachable under, from `PUBLIC_BASE_URL`
+    pub base_path: BasePath,
+    /// Content Security Policy of the web app under `/app`
+    pub frontend_assets: FrontendAssets,
 }
 
 impl Config {
@@ -28,17 +57,146 @@ impl Config {
                 .unwrap_or_else(|_| "3000".to_string())
                 .parse()
                 .context("Failed to parse API_PORT/PORT as u16")?,
-            cors_allowed_origin: env::var("CORS_ALLOWED_ORIGIN").unwrap_or_else(|_| {
-                env::var("PUBLIC_BASE_URL").unwrap_or_else(|_| "http://localhost:3000".to_string())
-            }),
+            cors: parse_cors()?,
             frontend_static_dir: env::var("FRONTEND_STATIC_DIR")
                 .ok()
                 .or_else(|| Some("../frontend/out".to_string())),
             enable_swagger: parse_env_bool("ENABLE_SWAGGER").unwrap_or(!is_production),
+            email_signups: parse_env_bool("ENABLE_EXTERNAL_EMAIL").unwrap_or(false),
+            email_webhook_secret: env::var("EMAIL_WEBHOOK_SECRET").ok(),
+            sms_notifications: env::var("SMS_PROVIDER")
+                .is_ok_and(|provider| !matches!(provider.trim(), "" | "none")),
+            web_push_public_key: env::var("VAPID_PUBLIC_KEY")
+                .ok()
+                .filter(|key| !key.trim().is_empty()),
+            device_budget: parse_device_budget()?,
+            trigger_budget: parse_trigger_budget()?,
+            base_path: parse_base_path()?,
+            frontend_assets: parse_frontend_assets()?,
         })
     }
 }
 
+/// Comma-separated Telegram ids of the bootstrap admins, e.g.
+/// `ADMIN_TELEGRAM_IDS=123,456`
+pub fn parse_admin_telegram_ids(value: &str) -> Result<Vec<i64>> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .map(|id| {
+            id.parse()
+                .with_context(|| format!("Invalid Telegram id in ADMIN_TELEGRAM_IDS: {id}"))
+        })
+        .collect()
+}
+
+/// `CALDAV_DEVICE_REQUESTS_PER_MINUTE` and `CALDAV_DEVICE_MAX_BODY_BYTES`,
+/// falling back to the defaults; `0` turns a limit off
+pub fn parse_device_budget() -> Result<DeviceBudget> {
+    let defaults = DeviceBudget::default();
+    Ok(DeviceBudget {
+        requests_per_minute: match env::var("CALDAV_DEVICE_REQUESTS_PER_MINUTE") {
+            Ok(value) => value
+                .parse()
+                .context("Failed to parse CALDAV_DEVICE_REQUESTS_PER_MINUTE as u32")?,
+            Err(_) => defaults.requests_per_minute,
+        },
+        max_body_bytes: match env::var("CALDAV_DEVICE_MAX_BODY_BYTES") {
+            Ok(value) => value
+                .parse()
+                .context("Failed to parse CALDAV_DEVICE_MAX_BODY_BYTES as usize")?,
+            Err(_) => defaults.max_body_bytes,
+        },
+    })
+}
+
+/// `TRIGGER_REQUESTS_PER_MINUTE`, default 10; `0` turns the limit off
+///
+/// Polling platforms check every few minutes, so the trigger budget is kept
+/// well below the CalDAV one.
+pub fn parse_trigger_budget() -> Result<DeviceBudget> {
+    Ok(DeviceBudget {
+        requests_per_minute: match env::var("TRIGGER_REQUESTS_PER_MINUTE") {
+            Ok(value) => value
+                .parse()
+                .context("Failed to parse TRIGGER_REQUESTS_PER_MINUTE as u32")?,
+            Err(_) => DEFAULT_TRIGGER_REQUESTS_PER_MINUTE,
+        },
+        ..DeviceBudget::default()
+    })
+}
+
+/// Path of `PUBLIC_BASE_URL`, e.g. `/televent` for
+/// `https://example.com/televent`, used as the prefix of generated links
+pub fn parse_base_path() -> Result<BasePath> {
+    match env::var("PUBLIC_BASE_URL") {
+        Ok(url) => BasePath::from_base_url(&url)
+            .with_context(|| format!("Invalid path in PUBLIC_BASE_URL: {url}")),
+        Err(_) => Ok(BasePath::default()),
+    }
+}
+
+/// `CORS_ALLOWED_ORIGIN`, a comma-separated list of origins, falling back
+/// to the origin of `PUBLIC_BASE_URL`; `CORS_MAX_AGE_SECS` sets how long
+/// browsers cache preflights, `0` leaving `Access-Control-Max-Age` out
+pub fn parse_cors() -> Result<CorsPolicy> {
+    let (name, origins) = match env::var("CORS_ALLOWED_ORIGIN") {
+        Ok(origins) => ("CORS_ALLOWED_ORIGIN", origins),
+        Err(_) => (
+            "PUBLIC_BASE_URL",
+            env::var("PUBLIC_BASE_URL")
+                .map(|url| origin_of(&url).to_string())
+                .unwrap_or_else(|_| "http://localhost:3000".to_string()),
+        ),
+    };
+    let policy = CorsPolicy::parse(&origins)
+        .map_err(|reason| anyhow::anyhow!("Invalid origin in {name}: {reason}"))?;
+    let max_age = match env::var("CORS_MAX_AGE_SECS") {
+        Ok(value) => {
+            let secs: u64 = value
+                .parse()
+                .context("Failed to parse CORS_MAX_AGE_SECS as u64")?;
+            (secs > 0).then(|| Duration::from_secs(secs))
+        }
+        Err(_) => Some(DEFAULT_CORS_MAX_AGE),
+    };
+    Ok(policy.with_max_age(max_age))
+}
+
+/// `https://example.com` from `https://example.com/televent/`
+fn origin_of(url: &str) -> &str {
+    let authority_start = url.find("://").map_or(0, |scheme_end| scheme_end + 3);
+    match url[authority_start..].find('/') {
+        Some(path_start) => &url[..authority_start + path_start],
+        None => url,
+    }
+}
+
+/// `FRONTEND_CSP_CONNECT_SRC` and `FRONTEND_CSP_FRAME_ANCESTORS` add
+/// space- or comma-separated origins to the web app's policy, e.g. an API or
+/// Telegram client on a custom domain; `FRONTEND_CSP` replaces the policy
+/// entirely.
+pub fn parse_frontend_assets() -> Result<FrontendAssets> {
+    if let Ok(policy) = env::var("FRONTEND_CSP") {
+        return FrontendAssets::with_policy(&policy)
+            .context("FRONTEND_CSP must be a non-empty header value");
+    }
+    let origins = |name: &str| -> Vec<String> {
+        env::var(name)
+            .unwrap_or_default()
+            .split([',', ' '])
+            .filter(|origin| !origin.is_empty())
+            .map(str::to_string)
+            .collect()
+    };
+    FrontendAssets::new(
+        &origins("FRONTEND_CSP_CONNECT_SRC"),
+        &origins("FRONTEND_CSP_FRAME_ANCESTORS"),
+    )
+    .context("FRONTEND_CSP_CONNECT_SRC and FRONTEND_CSP_FRAME_ANCESTORS must list origins")
+}
+
 fn parse_env_bool(name: &str) -> Option<bool> {
     env::var(name).ok().map(|value| {
         matches!(
@@ -57,13 +215,43 @@ mod tests {
         let config = Config {
             host: "0.0.0.0".to_string(),
             port: 3000,
-            cors_allowed_origin: "http://localhost:3000".to_string(),
+            cors: CorsPolicy::parse("http://localhost:3000").unwrap(),
             frontend_static_dir: Some("../frontend/out".to_string()),
             enable_swagger: true,
+            email_signups: false,
+            email_webhook_secret: None,
+            sms_notifications: false,
+            web_push_public_key: None,
+            device_budget: DeviceBudget::default(),
+            trigger_budget: DeviceBudget::default(),
+            base_path: BasePath::default(),
+            frontend_assets: FrontendAssets::default(),
         };
 
         assert_eq!(config.host, "0.0.0.0");
         assert_eq!(config.port, 3000);
-        assert_eq!(config.cors_allowed_origin, "http://localhost:3000");
+        assert_eq!(
+            config.cors,
+            CorsPolicy::parse("http://localhost:3000").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_origin_of_drops_the_path() {
+        assert_eq!(
+            origin_of("https://example.com/televent/"),
+            "https://example.com"
+        );
+        assert_eq!(origin_of("http://localhost:3000"), "http://localhost:3000");
+    }
+
+    #[test]
+    fn test_parse_admin_telegram_ids() {
+        assert_eq!(parse_admin_telegram_ids("").unwrap(), Vec::<i64>::new());
+        assert_eq!(
+            parse_admin_telegram_ids(" 123, 456 ,").unwrap(),
+            vec![123, 456]
+        );
+        assert!(parse_admin_telegram_ids("123,admin").is_err());
     }
 }
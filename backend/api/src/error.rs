@@ -7,6 +7,7 @@ use axum::{
 };
 use serde::Serialize;
 use televent_application::ApplicationError;
+use televent_domain::DomainError;
 
 /// API error response
 #[derive(Debug, Serialize)]
@@ -24,6 +25,7 @@ pub enum ApiError {
     Unauthorized(String),
     Forbidden,
     Conflict(String),
+    ServiceUnavailable(String),
     Internal(String),
 }
 
@@ -35,6 +37,11 @@ impl IntoResponse for ApiError {
             ApiError::Unauthorized(msg) => (StatusCode::UNAUTHORIZED, "Unauthorized", Some(msg)),
             ApiError::Forbidden => (StatusCode::FORBIDDEN, "Forbidden", None),
             ApiError::Conflict(msg) => (StatusCode::CONFLICT, "Conflict", Some(msg)),
+            ApiError::ServiceUnavailable(msg) => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                "Service Unavailable",
+                Some(msg),
+            ),
             ApiError::Internal(msg) => {
                 tracing::error!("Internal server error: {}", msg);
                 (
@@ -64,6 +71,12 @@ impl IntoResponse for ApiError {
     }
 }
 
+impl From<DomainError> for ApiError {
+    fn from(err: DomainError) -> Self {
+        ApiError::BadRequest(err.to_string())
+    }
+}
+
 impl From<ApplicationError> for ApiError {
     fn from(err: ApplicationError) -> Self {
         match err {
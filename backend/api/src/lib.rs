@@ -5,20 +5,45 @@ pub mod error;
 pub mod middleware;
 mod routes;
 
+#[cfg(feature = "google-calendar")]
+pub use routes::google::GoogleState;
+
+/// CalDAV XML writers, public only for the benchmark suite
+#[doc(hidden)]
+pub mod bench {
+    pub use crate::routes::caldav_xml::{
+        generate_calendar_multiget_response, generate_calendar_query_response,
+        generate_sync_collection_response,
+    };
+}
+
+/// CalDAV `PUT` parsing, public only for the `api/fuzz` targets
+#[doc(hidden)]
+pub mod fuzz {
+    pub use crate::routes::caldav_ical::fuzz_put_event;
+}
+
 use axum::extract::FromRef;
 use axum::{Router, middleware as axum_middleware};
-use moka::future::Cache;
-use televent_application::{CalendarService, DeviceService, HealthService, UserId};
+use televent_application::{
+    CalendarService, ChatWebhookService, ContactService, DeviceService, EmailService,
+    EmailVerificationKey, EventService, FeatureFlagService, HealthService, NotificationService,
+    SubscriptionService, UnsubscribeKey, WebPushService,
+};
 use tower_governor::{GovernorLayer, governor::GovernorConfigBuilder};
-use tower_http::cors::{Any, CorsLayer};
 use tower_http::services::{ServeDir, ServeFile};
 use tower_http::trace::TraceLayer;
 
-use crate::middleware::caldav_auth::{LoginId, caldav_basic_auth};
+use crate::middleware::base_path::resolve_base_path;
+use crate::middleware::caldav_auth::{AuthCache, caldav_basic_auth, spawn_auth_cache_invalidation};
+use crate::middleware::device_budget;
 use crate::middleware::rate_limit::{
-    API_BURST_SIZE, API_PERIOD_MS, CALDAV_BURST_SIZE, CALDAV_PERIOD_MS, UserOrIpKeyExtractor,
+    API_BURST_SIZE, API_PERIOD_MS, CALDAV_BURST_SIZE, CALDAV_PERIOD_MS, PUBLIC_PAGE_BURST_SIZE,
+    PUBLIC_PAGE_PERIOD_MS, RATE_LIMIT_HEADERS, UserOrIpKeyExtractor, rate_limit_headers,
 };
+use crate::middleware::read_only::reject_writes_when_read_only;
 use crate::middleware::security_headers::security_headers;
+use crate::middleware::static_assets::frontend_asset_headers;
 use crate::middleware::telegram_auth::telegram_auth;
 use utoipa::OpenApi;
 use utoipa_swagger_ui::SwaggerUi;
@@ -26,9 +51,17 @@ use utoipa_swagger_ui::SwaggerUi;
 #[derive(Clone)]
 pub struct AppState {
     pub calendar_service: CalendarService,
+    pub event_service: EventService,
     pub device_service: DeviceService,
     pub health_service: HealthService,
-    pub auth_cache: Cache<(LoginId, String), UserId>,
+    pub subscription_service: SubscriptionService,
+    pub contact_service: ContactService,
+    pub email_service: EmailService,
+    pub feature_flags: FeatureFlagService,
+    pub chat_webhook_service: ChatWebhookService,
+    pub notification_service: NotificationService,
+    pub web_push_service: WebPushService,
+    pub auth_cache: AuthCache,
     pub telegram_bot_token: String,
 }
 
@@ -36,31 +69,190 @@ pub struct AppState {
 #[openapi(
     paths(
         routes::health::health_check,
+        routes::health::migration_status,
+        routes::auth::telegram_login,
         routes::me::get_me,
+        routes::me::get_slots,
+        routes::me::get_preferences,
+        routes::me::put_preferences,
+        routes::me::get_stats,
+        routes::me::export_settings,
+        routes::me::import_settings,
         routes::events::create_event,
         routes::events::list_events,
+        routes::events::month_grid,
         routes::events::get_event,
         routes::events::update_event,
         routes::events::delete_event_handler,
+        routes::events::list_event_revisions,
+        routes::events::publish_event,
+        routes::events::unpublish_event,
+        routes::events::duplicate_event,
+        routes::events::fix_timezone,
+        routes::scheduled::schedule_reminder,
+        routes::scheduled::snooze_reminder,
+        routes::scheduled::list_scheduled_messages,
+        routes::scheduled::cancel_scheduled_message,
         routes::calendars::list_calendars,
+        routes::calendars::put_default_visibility,
         routes::devices::create_device_password,
         routes::devices::list_device_passwords,
         routes::devices::delete_device_password,
+        routes::devices::get_device_activity,
+        routes::booking_links::list_booking_links,
+        routes::booking_links::create_booking_link,
+        routes::booking_links::delete_booking_link,
+        routes::delegates::list_delegates,
+        routes::delegates::grant_delegate,
+        routes::delegates::revoke_delegate,
+        routes::delegates::list_delegated_calendars,
+        routes::resources::list_resources,
+        routes::resources::get_resource_free_busy,
+        routes::resources::create_resource,
+        routes::resources::delete_resource,
+        routes::resources::get_event_resource,
+        routes::resources::book_resource,
+        routes::resources::release_resource,
+        routes::holidays::list_holiday_calendars,
+        routes::holidays::enable_holiday_calendar,
+        routes::holidays::disable_holiday_calendar,
+        routes::birthdays::get_birthdays_calendar,
+        routes::birthdays::enable_birthdays_calendar,
+        routes::birthdays::disable_birthdays_calendar,
+        routes::tasks::list_tasks,
+        routes::tasks::create_task,
+        routes::tasks::delete_task,
+        routes::tasks::complete_task,
+        routes::tasks::confirm_task_block,
+        routes::tasks::unschedule_task,
+        routes::tasks::plan_tasks,
+        routes::focus::list_focus_rules,
+        routes::focus::create_focus_rule,
+        routes::focus::update_focus_rule,
+        routes::focus::delete_focus_rule,
+        routes::chat_webhooks::list_chat_webhooks,
+        routes::chat_webhooks::add_chat_webhook,
+        routes::chat_webhooks::delete_chat_webhook,
+        routes::notifications::get_notification_preferences,
+        routes::notifications::put_notification_preferences,
+        routes::notifications::get_phone,
+        routes::notifications::put_phone,
+        routes::notifications::verify_phone,
+        routes::notifications::delete_phone,
+        routes::notifications::get_email,
+        routes::notifications::put_email,
+        routes::notifications::verify_email,
+        routes::push::vapid_public_key,
+        routes::push::subscribe,
+        routes::push::unsubscribe,
+        routes::triggers::new_events,
+        routes::triggers::upcoming_events,
+        routes::contacts::search_contacts,
+        routes::contacts::suggest_contacts,
+        routes::flags::list_flags,
+        routes::flags::put_flag,
+        routes::flags::put_override,
+        routes::flags::delete_override,
+        routes::roles::put_user_role,
     ),
     components(
         schemas(
             routes::health::HealthResponse,
+            routes::health::MigrationStatusResponse,
+            routes::health::PendingMigrationResponse,
+            routes::auth::TelegramLoginResponse,
+            crate::middleware::telegram_auth::LoginWidgetData,
             routes::me::MeResponse,
+            routes::me::SlotsQuery,
+            routes::me::SlotResponse,
+            routes::me::PreferencesResponse,
+            routes::me::WorkingDayResponse,
+            routes::me::PutPreferencesRequest,
+            routes::me::StatsQuery,
+            routes::me::StatsResponse,
+            routes::me::WeekdayLoadResponse,
+            routes::me::CollaboratorResponse,
+            routes::me::SettingsArchive,
+            routes::me::ArchivedSubscription,
+            routes::me::SettingsImportResponse,
             routes::events::CreateEventRequest,
             routes::events::EventTimingRequest,
             routes::events::EventStatus,
+            routes::events::EventVisibility,
             routes::events::EventResponse,
             routes::events::UpdateEventRequest,
+            routes::events::EditScope,
             routes::events::ListEventsQuery,
+            routes::events::MonthGridQuery,
+            routes::events::MonthGridResponse,
+            routes::events::GridDayResponse,
+            routes::events::GridEventResponse,
+            routes::events::PublicPageResponse,
+            routes::events::DuplicateEventRequest,
+            routes::events::TimezoneFixRequest,
+            routes::events::TimezoneFixResponse,
+            routes::events::CalendarQuery,
+            routes::events::EventRevisionResponse,
+            routes::scheduled::ScheduleReminderRequest,
+            routes::scheduled::SnoozeReminderRequest,
+            routes::scheduled::ScheduledMessageResponse,
             routes::calendars::CalendarInfo,
+            routes::calendars::PutDefaultVisibilityRequest,
             routes::devices::CreateDeviceRequest,
             routes::devices::DevicePasswordResponse,
             routes::devices::DeviceListItem,
+            routes::devices::ListDevicesQuery,
+            routes::devices::DeviceActivityItem,
+            routes::devices::SequenceConflictItem,
+            routes::booking_links::CreateBookingLinkRequest,
+            routes::booking_links::BookingLinkResponse,
+            routes::delegates::GrantDelegateRequest,
+            routes::delegates::DelegateResponse,
+            routes::resources::ResourceResponse,
+            routes::resources::CreateResourceRequest,
+            routes::resources::BookResourceRequest,
+            routes::resources::ResourceBookingResponse,
+            routes::resources::FreeBusyQuery,
+            routes::resources::BusyIntervalResponse,
+            routes::holidays::HolidayCalendarResponse,
+            routes::birthdays::BirthdaysCalendarResponse,
+            routes::tasks::CreateTaskRequest,
+            routes::tasks::TaskResponse,
+            routes::tasks::TaskBlockResponse,
+            routes::tasks::PlanTasksRequest,
+            routes::tasks::PlannedBlockResponse,
+            routes::tasks::TaskPlanResponse,
+            routes::focus::FocusRuleRequest,
+            routes::focus::FocusRuleResponse,
+            routes::focus::FocusBlockResponse,
+            routes::chat_webhooks::ChatProvider,
+            routes::chat_webhooks::AddChatWebhookRequest,
+            routes::chat_webhooks::ChatWebhookResponse,
+            routes::notifications::Topic,
+            routes::notifications::Channel,
+            routes::notifications::NotificationPreferenceDto,
+            routes::notifications::PutPhoneRequest,
+            routes::notifications::VerifyPhoneRequest,
+            routes::notifications::PhoneNumberResponse,
+            routes::notifications::PutEmailRequest,
+            routes::notifications::VerifyEmailRequest,
+            routes::notifications::EmailAddressResponse,
+            routes::push::VapidPublicKeyResponse,
+            routes::push::PushSubscriptionKeys,
+            routes::push::PushSubscribeRequest,
+            routes::push::PushUnsubscribeRequest,
+            routes::push::PushSubscriptionResponse,
+            routes::triggers::NewEventsQuery,
+            routes::triggers::UpcomingEventsQuery,
+            routes::triggers::NewEventTrigger,
+            routes::contacts::ContactResponse,
+            routes::contacts::ContactSuggestionResponse,
+            routes::flags::FeatureFlagResponse,
+            routes::flags::FeatureFlagOverride,
+            routes::flags::PutFeatureFlagRequest,
+            routes::flags::PutFeatureFlagOverrideRequest,
+            routes::roles::PutUserRoleRequest,
+            routes::roles::UserRoleResponse,
         )
     ),
     tags(
@@ -69,6 +261,16 @@ pub struct AppState {
         (name = "events", description = "Event management endpoints"),
         (name = "calendars", description = "Calendar management endpoints"),
         (name = "devices", description = "Device management endpoints"),
+        (name = "contacts", description = "Contact search endpoints"),
+        (name = "booking", description = "Booking links that let others pick a free slot"),
+        (name = "delegation", description = "Letting other users manage the calendar"),
+        (name = "resources", description = "Meeting rooms and other bookable resources"),
+        (name = "holidays", description = "Built-in public holiday calendars"),
+        (name = "birthdays", description = "Calendar of the contacts' birthdays"),
+        (name = "tasks", description = "Tasks planned into free time as calendar blocks"),
+        (name = "focus", description = "Weekly focus time kept free on the calendar"),
+        (name = "integrations", description = "Slack and Teams webhooks and polling triggers"),
+        (name = "admin", description = "Roles, feature flag and resource administration"),
     ),
     modifiers(&SecurityAddon)
 )]
@@ -87,6 +289,14 @@ impl utoipa::Modify for SecurityAddon {
                     ),
                 ),
             );
+            components.add_security_scheme(
+                "device_password",
+                utoipa::openapi::security::SecurityScheme::Http(
+                    utoipa::openapi::security::Http::new(
+                        utoipa::openapi::security::HttpAuthScheme::Basic,
+                    ),
+                ),
+            );
         }
     }
 }
@@ -97,6 +307,54 @@ impl FromRef<AppState> for CalendarService {
     }
 }
 
+impl FromRef<AppState> for EventService {
+    fn from_ref(state: &AppState) -> Self {
+        state.event_service.clone()
+    }
+}
+
+impl FromRef<AppState> for SubscriptionService {
+    fn from_ref(state: &AppState) -> Self {
+        state.subscription_service.clone()
+    }
+}
+
+impl FromRef<AppState> for ContactService {
+    fn from_ref(state: &AppState) -> Self {
+        state.contact_service.clone()
+    }
+}
+
+impl FromRef<AppState> for EmailService {
+    fn from_ref(state: &AppState) -> Self {
+        state.email_service.clone()
+    }
+}
+
+impl FromRef<AppState> for FeatureFlagService {
+    fn from_ref(state: &AppState) -> Self {
+        state.feature_flags.clone()
+    }
+}
+
+impl FromRef<AppState> for ChatWebhookService {
+    fn from_ref(state: &AppState) -> Self {
+        state.chat_webhook_service.clone()
+    }
+}
+
+impl FromRef<AppState> for NotificationService {
+    fn from_ref(state: &AppState) -> Self {
+        state.notification_service.clone()
+    }
+}
+
+impl FromRef<AppState> for WebPushService {
+    fn from_ref(state: &AppState) -> Self {
+        state.web_push_service.clone()
+    }
+}
+
 impl FromRef<AppState> for DeviceService {
     fn from_ref(state: &AppState) -> Self {
         state.device_service.clone()
@@ -109,14 +367,93 @@ impl FromRef<AppState> for HealthService {
     }
 }
 
+/// A route mounted outside the Telegram and device password middleware
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PublicRoute {
+    /// Mounted path, in the router's own syntax
+    pub path: &'static str,
+    /// What protects it instead
+    pub reason: &'static str,
+}
+
+/// Every route reachable without credentials. Anything else must refuse
+/// anonymous requests; `tests/route_auth_audit.rs` walks the router and holds
+/// it to this list, so a new public route has to be added here on purpose.
+pub const PUBLIC_ROUTES: &[PublicRoute] = &[
+    PublicRoute {
+        path: "/health",
+        reason: "liveness probe",
+    },
+    PublicRoute {
+        path: "/health/migrations",
+        reason: "readiness probe, reports schema versions only",
+    },
+    PublicRoute {
+        path: "/api/auth/telegram-login",
+        reason: "checks the Telegram login widget signature itself",
+    },
+    PublicRoute {
+        path: "/app/login",
+        reason: "checks the Telegram login widget signature itself",
+    },
+    PublicRoute {
+        path: "/app/agenda",
+        reason: "checks Telegram init data or the session cookie itself",
+    },
+    PublicRoute {
+        path: "/e/{slug}",
+        reason: "published event page, behind an unguessable slug",
+    },
+    PublicRoute {
+        path: "/e/{slug}/signup",
+        reason: "published event signup, behind an unguessable slug",
+    },
+    PublicRoute {
+        path: "/e/{slug}/confirm",
+        reason: "signup confirmation, checks the emailed token",
+    },
+    PublicRoute {
+        path: "/b/{slug}",
+        reason: "booking link, behind an unguessable slug",
+    },
+    PublicRoute {
+        path: "/unsubscribe/{token}",
+        reason: "checks the signed unsubscribe token",
+    },
+    PublicRoute {
+        path: "/verify-email/{token}",
+        reason: "checks the signed email verification token",
+    },
+    PublicRoute {
+        path: "/webhooks/email/{provider}/{secret}",
+        reason: "checks the webhook secret in the path",
+    },
+    #[cfg(feature = "google-calendar")]
+    PublicRoute {
+        path: "/google/callback",
+        reason: "OAuth redirect, checks the sign-in state",
+    },
+];
+
 /// Create the application router
+///
+/// Panics if `cors_origin` is not a valid origin list.
 pub fn create_router(state: AppState, cors_origin: &str) -> Router {
     let config = config::Config {
         host: "0.0.0.0".to_string(),
         port: 3000,
-        cors_allowed_origin: cors_origin.to_string(),
+        cors: middleware::cors::CorsPolicy::parse(cors_origin)
+            .unwrap_or_else(|reason| panic!("Invalid CORS origin: {reason}")),
         frontend_static_dir: None,
         enable_swagger: false,
+        email_signups: false,
+        email_webhook_secret: None,
+        sms_notifications: false,
+        web_push_public_key: None,
+        device_budget: Default::default(),
+        trigger_budget: Default::default(),
+        base_path: Default::default(),
+        frontend_assets: Default::default(),
     };
 
     create_router_with_config(state, &config)
@@ -124,37 +461,64 @@ pub fn create_router(state: AppState, cors_origin: &str) -> Router {
 
 /// Create the application router from full runtime configuration.
 pub fn create_router_with_config(state: AppState, config: &config::Config) -> Router {
-    let cors_origin = &config.cors_allowed_origin;
-    let cors = if cors_origin == "*" {
-        CorsLayer::new()
-            .allow_origin(Any)
-            .allow_methods(Any)
-            .allow_headers(Any)
-    } else {
-        match cors_origin.parse::<axum::http::HeaderValue>() {
-            Ok(origin) => CorsLayer::new()
-                .allow_origin(origin)
-                .allow_methods(Any)
-                .allow_headers(Any),
-            Err(e) => {
-                tracing::error!("Invalid CORS origin '{}': {}", cors_origin, e);
-                // Fallback to strict (no origin allowed) or Any?
-                // Safest is to panic or fail startup, but here we return a Router.
-                // Let's fallback to allowing nothing effectively by not adding the layer?
-                // Or panic since this is startup config.
-                panic!("Invalid CORS origin configuration: {}", e);
-            }
-        }
-    };
+    build_router(state, config, Router::new(), Router::new())
+}
+
+/// Create the application router including the Google Calendar endpoints.
+#[cfg(feature = "google-calendar")]
+pub fn create_router_with_google(
+    state: AppState,
+    config: &config::Config,
+    google: GoogleState,
+) -> Router {
+    build_router(
+        state,
+        config,
+        routes::google::routes(google.clone()),
+        routes::google::callback_routes(google),
+    )
+}
+
+/// Assemble the router; optional features contribute authenticated routes
+/// under `/api` and public routes sharing the public page rate limit.
+/// Public routes must also be listed in [`PUBLIC_ROUTES`].
+fn build_router(
+    state: AppState,
+    config: &config::Config,
+    api_routes: Router<AppState>,
+    public_routes: Router<AppState>,
+) -> Router {
+    let cors = config.cors.layer().expose_headers(RATE_LIMIT_HEADERS);
 
     let mut router = Router::new()
         .merge(routes::health::routes())
+        .merge(routes::email_webhooks::routes(
+            config.email_webhook_secret.clone(),
+        ))
         .nest(
             "/api",
             routes::events::routes()
                 .merge(routes::calendars::routes())
                 .merge(routes::devices::routes())
+                .merge(routes::chat_webhooks::routes())
+                .merge(routes::booking_links::routes())
+                .merge(routes::delegates::routes())
+                .merge(routes::resources::routes())
+                .merge(routes::holidays::routes())
+                .merge(routes::birthdays::routes())
+                .merge(routes::tasks::routes())
+                .merge(routes::focus::routes())
+                .merge(routes::notifications::routes(
+                    config.sms_notifications,
+                    config.email_signups,
+                ))
+                .merge(routes::push::routes(config.web_push_public_key.clone()))
                 .merge(routes::me::routes())
+                .merge(routes::contacts::routes())
+                .merge(routes::flags::routes())
+                .merge(routes::roles::routes())
+                .merge(routes::scheduled::routes())
+                .merge(api_routes)
                 .layer(axum_middleware::from_fn_with_state(
                     state.clone(),
                     telegram_auth,
@@ -164,13 +528,61 @@ pub fn create_router_with_config(state: AppState, config: &config::Config) -> Ro
                         .period(std::time::Duration::from_millis(API_PERIOD_MS))
                         .burst_size(API_BURST_SIZE)
                         .key_extractor(UserOrIpKeyExtractor)
+                        .use_headers()
                         .finish()
                         .expect("Failed to create API governor config"),
+                ))
+                .layer(axum_middleware::from_fn_with_state(
+                    std::time::Duration::from_millis(API_PERIOD_MS),
+                    rate_limit_headers,
+                )),
+        )
+        .merge(
+            routes::public_events::routes(config.email_signups)
+                .merge(routes::public_booking::routes())
+                .merge(routes::auth::routes())
+                .merge(routes::agenda::routes())
+                .merge(routes::unsubscribe::routes(UnsubscribeKey::from_bot_token(
+                    &state.telegram_bot_token,
+                )))
+                .merge(routes::email_verification::routes(
+                    EmailVerificationKey::from_bot_token(&state.telegram_bot_token),
+                ))
+                .merge(public_routes)
+                .layer(GovernorLayer::new(
+                    GovernorConfigBuilder::default()
+                        .period(std::time::Duration::from_millis(PUBLIC_PAGE_PERIOD_MS))
+                        .burst_size(PUBLIC_PAGE_BURST_SIZE)
+                        .key_extractor(UserOrIpKeyExtractor)
+                        .use_headers()
+                        .finish()
+                        .expect("Failed to create public page governor config"),
+                ))
+                .layer(axum_middleware::from_fn_with_state(
+                    std::time::Duration::from_millis(PUBLIC_PAGE_PERIOD_MS),
+                    rate_limit_headers,
+                )),
+        )
+        .nest(
+            "/api/triggers",
+            device_budget::apply(routes::triggers::routes(), config.trigger_budget)
+                .layer(axum_middleware::from_fn_with_state(
+                    state.clone(),
+                    caldav_basic_auth,
+                ))
+                // Device passwords cost an Argon2 check, as on CalDAV
+                .layer(GovernorLayer::new(
+                    GovernorConfigBuilder::default()
+                        .period(std::time::Duration::from_millis(CALDAV_PERIOD_MS))
+                        .burst_size(CALDAV_BURST_SIZE)
+                        .key_extractor(UserOrIpKeyExtractor)
+                        .finish()
+                        .expect("Failed to create trigger governor config"),
                 )),
         )
         .nest(
             "/caldav",
-            routes::caldav::routes()
+            device_budget::apply(routes::caldav::routes(), config.device_budget)
                 .layer(axum_middleware::from_fn_with_state(
                     state.clone(),
                     caldav_basic_auth,
@@ -187,6 +599,26 @@ pub fn create_router_with_config(state: AppState, config: &config::Config) -> Ro
                 .layer(axum_middleware::from_fn(
                     crate::middleware::caldav_logging::caldav_logger,
                 )),
+        )
+        .nest(
+            "/carddav",
+            device_budget::apply(routes::carddav::routes(), config.device_budget)
+                .layer(axum_middleware::from_fn_with_state(
+                    state.clone(),
+                    caldav_basic_auth,
+                ))
+                // Same device passwords and Argon2 cost as CalDAV
+                .layer(GovernorLayer::new(
+                    GovernorConfigBuilder::default()
+                        .period(std::time::Duration::from_millis(CALDAV_PERIOD_MS))
+                        .burst_size(CALDAV_BURST_SIZE)
+                        .key_extractor(UserOrIpKeyExtractor)
+                        .finish()
+                        .expect("Failed to create CardDAV governor config"),
+                ))
+                .layer(axum_middleware::from_fn(
+                    crate::middleware::caldav_logging::caldav_logger,
+                )),
         );
 
     if config.enable_swagger {
@@ -198,11 +630,24 @@ pub fn create_router_with_config(state: AppState, config: &config::Config) -> Ro
         let index_path = std::path::Path::new(static_dir).join("index.html");
         router = router.nest_service(
             "/app",
-            ServeDir::new(static_dir).not_found_service(ServeFile::new(index_path)),
+            tower::ServiceBuilder::new()
+                .layer(axum_middleware::from_fn_with_state(
+                    config.frontend_assets.clone(),
+                    frontend_asset_headers,
+                ))
+                .service(ServeDir::new(static_dir).not_found_service(ServeFile::new(index_path))),
         );
     }
 
     router
+        .layer(axum_middleware::from_fn_with_state(
+            state.feature_flags.clone(),
+            reject_writes_when_read_only,
+        ))
+        .layer(axum_middleware::from_fn_with_state(
+            config.base_path.clone(),
+            resolve_base_path,
+        ))
         .layer(cors)
         .layer(axum_middleware::from_fn(
             crate::middleware::caldav_headers::add_caldav_headers,
@@ -258,22 +703,74 @@ pub fn create_router_with_config(state: AppState, config: &config::Config) -> Ro
 
 /// Run the API server
 ///
-/// This function starts the HTTP server and blocks until it exits.
+/// This function starts the HTTP server and blocks until it exits. Once
+/// `shutdown` resolves it stops accepting connections and returns when the
+/// in-flight requests have finished.
 ///
 /// # Arguments
 /// * `state` - Application state containing database pool and caches
 /// * `config` - Server configuration
-pub async fn run_api(state: AppState, config: &config::Config) -> Result<(), std::io::Error> {
-    let app = create_router_with_config(state, config);
-    let addr = format!("{}:{}", config.host, config.port);
+/// * `shutdown` - Resolves when the server should drain and stop
+pub async fn run_api(
+    state: AppState,
+    config: &config::Config,
+    shutdown: impl Future<Output = ()> + Send + 'static,
+) -> Result<(), std::io::Error> {
+    let cache_invalidation =
+        spawn_auth_cache_invalidation(state.device_service.event_bus(), state.auth_cache.clone());
+    let result = serve(create_router_with_config(state, config), config, shutdown).await;
+    cache_invalidation.abort();
+    result
+}
 
-    tracing::info!("API server listening on {}", addr);
+/// Run the API server with the Google Calendar endpoints enabled
+#[cfg(feature = "google-calendar")]
+pub async fn run_api_with_google(
+    state: AppState,
+    config: &config::Config,
+    google: GoogleState,
+    shutdown: impl Future<Output = ()> + Send + 'static,
+) -> Result<(), std::io::Error> {
+    let cache_invalidation =
+        spawn_auth_cache_invalidation(state.device_service.event_bus(), state.auth_cache.clone());
+    let result = serve(
+        create_router_with_google(state, config, google),
+        config,
+        shutdown,
+    )
+    .await;
+    cache_invalidation.abort();
+    result
+}
+
+async fn serve(
+    app: Router,
+    config: &config::Config,
+    shutdown: impl Future<Output = ()> + Send + 'static,
+) -> Result<(), std::io::Error> {
+    // Under systemd socket activation the socket outlives restarts, so
+    // connections queue in its backlog instead of being refused
+    let listener = match listenfd::ListenFd::from_env().take_tcp_listener(0)? {
+        Some(listener) => {
+            listener.set_nonblocking(true)?;
+            tracing::info!(
+                "API server listening on socket-activated {}",
+                listener.local_addr()?
+            );
+            tokio::net::TcpListener::from_std(listener)?
+        }
+        None => {
+            let addr = format!("{}:{}", config.host, config.port);
+            tracing::info!("API server listening on {}", addr);
+            tokio::net::TcpListener::bind(&addr).await?
+        }
+    };
 
-    let listener = tokio::net::TcpListener::bind(&addr).await?;
     axum::serve(
         listener,
         app.into_make_service_with_connect_info::<std::net::SocketAddr>(),
     )
+    .with_graceful_shutdown(shutdown)
     .await
 }
 
@@ -309,17 +806,41 @@ mod tests {
 
         // Create dummy state
         let pool = sqlx::PgPool::connect_lazy("postgres://localhost/dummy").unwrap();
-        let auth_cache = moka::future::Cache::builder().build();
+        let auth_cache = AuthCache::new(std::time::Duration::from_secs(300), 10_000);
         let state = AppState {
             calendar_service: televent_application::CalendarService::new(
                 televent_storage::calendar::CalendarRepository::new(pool.clone()),
             ),
+            event_service: televent_application::EventService::new(
+                televent_storage::calendar::CalendarRepository::new(pool.clone()),
+            ),
             device_service: televent_application::DeviceService::new(
                 televent_storage::device::DeviceRepository::new(pool.clone()),
             ),
             health_service: televent_application::HealthService::new(
                 televent_storage::health::HealthRepository::new(pool.clone()),
             ),
+            subscription_service: televent_application::SubscriptionService::new(
+                televent_storage::subscription::SubscriptionRepository::new(pool.clone()),
+            ),
+            contact_service: televent_application::ContactService::new(
+                televent_storage::contact::ContactRepository::new(pool.clone()),
+            ),
+            email_service: televent_application::EmailService::new(
+                televent_storage::email::EmailRepository::new(pool.clone()),
+            ),
+            feature_flags: televent_application::FeatureFlagService::new(
+                televent_storage::flags::FeatureFlagRepository::new(pool.clone()),
+            ),
+            chat_webhook_service: televent_application::ChatWebhookService::new(
+                televent_storage::chat_webhook::ChatWebhookRepository::new(pool.clone()),
+            ),
+            notification_service: televent_application::NotificationService::new(
+                televent_storage::notification::NotificationRepository::new(pool.clone()),
+            ),
+            web_push_service: televent_application::WebPushService::new(
+                televent_storage::web_push::WebPushRepository::new(pool.clone()),
+            ),
             auth_cache,
             telegram_bot_token: "dummy".to_string(),
         };
@@ -366,27 +887,40 @@ mod tests {
             Some("http://example.com")
         );
 
-        // Test 3: "mirror" should NO LONGER work as a magic value
-        // It will be treated as a literal origin "mirror", which won't match "http://evil.com"
-        let app = create_router(state.clone(), "mirror");
+        // Test 3: "mirror" is not a magic value, nor an origin
+        assert!(middleware::cors::CorsPolicy::parse("mirror").is_err());
+
+        // Test 4: Listed origins, one of them a wildcard
+        let app = create_router(state.clone(), "http://example.com, https://*.example.org");
 
         let req = Request::builder()
             .method(Method::OPTIONS)
             .uri("/api/events")
-            .header(header::ORIGIN, "http://evil.com")
+            .header(header::ORIGIN, "https://pr-7.example.org")
             .header(header::ACCESS_CONTROL_REQUEST_METHOD, "GET")
             .body(Body::empty())
             .unwrap();
 
         let response = app.clone().oneshot(req).await.unwrap();
 
-        // Should NOT allow evil.com
         let allow_origin = response.headers().get(header::ACCESS_CONTROL_ALLOW_ORIGIN);
-        assert_ne!(
+        assert_eq!(
             allow_origin.map(|h| h.to_str().unwrap()),
-            Some("http://evil.com")
+            Some("https://pr-7.example.org")
         );
-        // And definitely not *
-        assert_ne!(allow_origin.map(|h| h.to_str().unwrap()), Some("*"));
+
+        let req = Request::builder()
+            .method(Method::OPTIONS)
+            .uri("/api/events")
+            .header(header::ORIGIN, "http://evil.com")
+            .header(header::ACCESS_CONTROL_REQUEST_METHOD, "GET")
+            .body(Body::empty())
+            .unwrap();
+
+        let response = app.clone().oneshot(req).await.unwrap();
+
+        // Should NOT allow evil.com
+        let allow_origin = response.headers().get(header::ACCESS_CONTROL_ALLOW_ORIGIN);
+        assert!(allow_origin.is_none());
     }
 }
@@ -0,0 +1,125 @@
+//! External path prefix for generated links
+//!
+//! Behind a path-prefixing reverse proxy (`https://host/televent/caldav/...`
+//! forwarded to `/caldav/...`) hrefs must carry the prefix the client sees.
+//! The prefix comes from the path of `PUBLIC_BASE_URL`, and a proxy can
+//! override it per request with `X-Forwarded-Prefix`. Handlers take
+//! [`BasePath`] as an extractor.
+
+use axum::{
+    extract::{FromRequestParts, Request, State},
+    http::request::Parts,
+    middleware::Next,
+    response::Response,
+};
+use std::convert::Infallible;
+
+/// Header a proxy sets to the prefix it strips
+pub const FORWARDED_PREFIX: &str = "x-forwarded-prefix";
+
+/// Path prefix the service is mounted at, without a trailing slash; empty at
+/// the root
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BasePath(String);
+
+impl BasePath {
+    /// Normalise a prefix such as `/televent/`; `None` when it isn't a plain
+    /// absolute path
+    pub fn parse(prefix: &str) -> Option<Self> {
+        let prefix = prefix.trim();
+        if prefix.is_empty() {
+            return Some(Self::default());
+        }
+        let is_safe = prefix.starts_with('/')
+            && !prefix.contains("//")
+            && prefix
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '/' | '-' | '_' | '.' | '~'))
+            && !prefix.split('/').any(|segment| segment == "..");
+        is_safe.then(|| Self(prefix.trim_end_matches('/').to_string()))
+    }
+
+    /// Prefix taken from the path of an external base URL such as
+    /// `https://example.com/televent`
+    pub fn from_base_url(base_url: &str) -> Option<Self> {
+        let rest = base_url
+            .split_once("://")
+            .map_or(base_url, |(_, rest)| rest);
+        let path = rest.find('/').map_or("", |start| &rest[start..]);
+        let path = path.split(['?', '#']).next().unwrap_or_default();
+        Self::parse(path)
+    }
+
+    /// The prefix itself, e.g. `/televent` or `""`
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Absolute path as the client sees it; `path` starts with `/`
+    pub fn join(&self, path: &str) -> String {
+        format!("{}{}", self.0, path)
+    }
+}
+
+/// Resolve the request's [`BasePath`], preferring `X-Forwarded-Prefix` over
+/// the configured one
+pub async fn resolve_base_path(
+    State(configured): State<BasePath>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let forwarded = request
+        .headers()
+        .get(FORWARDED_PREFIX)
+        .and_then(|value| value.to_str().ok())
+        .and_then(BasePath::parse);
+    request
+        .extensions_mut()
+        .insert(forwarded.unwrap_or(configured));
+
+    next.run(request).await
+}
+
+impl<S> FromRequestParts<S> for BasePath
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(parts
+            .extensions
+            .get::<BasePath>()
+            .cloned()
+            .unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base_path_parsing() {
+        assert_eq!(BasePath::parse("").unwrap().as_str(), "");
+        assert_eq!(BasePath::parse("/").unwrap().as_str(), "");
+        assert_eq!(BasePath::parse("/televent/").unwrap().as_str(), "/televent");
+        assert_eq!(
+            BasePath::parse("/a/b").unwrap().join("/caldav/1/"),
+            "/a/b/caldav/1/"
+        );
+        assert!(BasePath::parse("televent").is_none());
+        assert!(BasePath::parse("/x\"><script>").is_none());
+        assert!(BasePath::parse("//evil.example").is_none());
+        assert!(BasePath::parse("/a/../b").is_none());
+
+        assert_eq!(
+            BasePath::from_base_url("https://example.com/televent/?x=1").unwrap(),
+            BasePath::parse("/televent").unwrap()
+        );
+        assert_eq!(
+            BasePath::from_base_url("http://localhost:3000").unwrap(),
+            BasePath::default()
+        );
+    }
+}
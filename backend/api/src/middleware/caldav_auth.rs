@@ -4,16 +4,25 @@
 
 use crate::AppState;
 use crate::error::ApiError;
+use crate::middleware::device_budget::AuthenticatedDevice;
+use argon2::password_hash::rand_core::{OsRng, RngCore};
 use argon2::{Argon2, PasswordHash, PasswordVerifier};
 use axum::{
     extract::{Request, State},
-    http::header::AUTHORIZATION,
+    http::header::{AUTHORIZATION, USER_AGENT},
     middleware::Next,
     response::Response,
 };
 use base64::{Engine, engine::general_purpose::STANDARD};
-use televent_application::UserId;
-use uuid::Uuid;
+use hmac::{Hmac, Mac};
+use moka::future::Cache;
+use sha2::Sha256;
+use std::sync::Arc;
+use std::time::Duration;
+use televent_application::{
+    DeviceActivity, DeviceId, DomainEvent, DomainEventBus, SequenceConflict, UserId,
+};
+use tokio::sync::broadcast::error::RecvError;
 
 /// Login identifier: either a numeric Telegram ID or a username (without @)
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -22,6 +31,129 @@ pub enum LoginId {
     Username(String),
 }
 
+type HmacSha256 = Hmac<Sha256>;
+
+/// HMAC-SHA256 of a login id and password under the cache's secret, so
+/// neither ever sits in the cache in clear
+#[derive(Clone, Eq)]
+pub struct CredentialKey([u8; 32]);
+
+impl PartialEq for CredentialKey {
+    /// Compares every byte, taking the same time wherever the keys differ
+    fn eq(&self, other: &Self) -> bool {
+        self.0
+            .iter()
+            .zip(&other.0)
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+    }
+}
+
+impl std::hash::Hash for CredentialKey {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.0.hash(state);
+    }
+}
+
+impl std::fmt::Debug for CredentialKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("CredentialKey(..)")
+    }
+}
+
+/// Recent successful logins, so a client syncing every few seconds does not
+/// pay for Argon2 on each request. Entries are keyed by a [`CredentialKey`]
+/// under a secret drawn when the cache is created, which never leaves the
+/// process.
+#[derive(Clone)]
+pub struct AuthCache {
+    /// `None` when caching is disabled and every request is verified
+    logins: Option<Cache<CredentialKey, (UserId, DeviceId)>>,
+    secret: Arc<[u8; 32]>,
+}
+
+impl AuthCache {
+    #[must_use]
+    pub fn new(time_to_live: Duration, max_capacity: u64) -> Self {
+        Self::with_logins(Some(
+            Cache::builder()
+                .time_to_live(time_to_live)
+                .max_capacity(max_capacity)
+                .build(),
+        ))
+    }
+
+    /// Verify the password of every request
+    #[must_use]
+    pub fn disabled() -> Self {
+        Self::with_logins(None)
+    }
+
+    fn with_logins(logins: Option<Cache<CredentialKey, (UserId, DeviceId)>>) -> Self {
+        let mut secret = [0u8; 32];
+        OsRng.fill_bytes(&mut secret);
+        Self {
+            logins,
+            secret: Arc::new(secret),
+        }
+    }
+
+    #[must_use]
+    pub const fn is_enabled(&self) -> bool {
+        self.logins.is_some()
+    }
+
+    fn key(&self, login_id: &LoginId, password: &str) -> CredentialKey {
+        let login = match login_id {
+            LoginId::TelegramId(id) => format!("id:{id}"),
+            LoginId::Username(username) => format!("username:{username}"),
+        };
+        let mut mac = HmacSha256::new_from_slice(self.secret.as_slice())
+            .expect("HMAC can take key of any size");
+        // Length-prefixed so no login and password pair runs into another
+        mac.update(&(login.len() as u64).to_be_bytes());
+        mac.update(login.as_bytes());
+        mac.update(password.as_bytes());
+        CredentialKey(mac.finalize().into_bytes().into())
+    }
+
+    async fn get(&self, login_id: &LoginId, password: &str) -> Option<(UserId, DeviceId)> {
+        let logins = self.logins.as_ref()?;
+        logins.get(&self.key(login_id, password)).await
+    }
+
+    async fn insert(&self, login_id: &LoginId, password: &str, login: (UserId, DeviceId)) {
+        if let Some(logins) = &self.logins {
+            logins.insert(self.key(login_id, password), login).await;
+        }
+    }
+
+    fn invalidate_all(&self) {
+        if let Some(logins) = &self.logins {
+            logins.invalidate_all();
+        }
+    }
+
+    async fn invalidate_user(&self, user_id: UserId) {
+        let Some(logins) = &self.logins else {
+            return;
+        };
+        let stale_keys: Vec<_> = logins
+            .iter()
+            .filter(|(_, (cached_user_id, _))| *cached_user_id == user_id)
+            .map(|(key, _)| key)
+            .collect();
+        for key in stale_keys {
+            logins.invalidate(key.as_ref()).await;
+        }
+    }
+}
+
+/// Sync token a handler saw in a sync-collection REPORT, put in the response
+/// extensions so the device's activity log can show it
+#[derive(Debug, Clone)]
+pub struct PresentedSyncToken(pub String);
+
 // Dummy hash for timing attack mitigation.
 // Valid Argon2id hash for "dummy_password"
 const DUMMY_ARGON2_HASH: &str = "$argon2id$v=19$m=19456,t=2,p=1$1l7VcKL3J0c7lGDrMIcOmg$BaSGfGBb632pgVXyZ3jpzuwPa1mAd92EmGa6D0FIZd8";
@@ -46,13 +178,9 @@ pub async fn caldav_basic_auth(
     let (login_id, password) = parse_basic_auth(auth_header)?;
 
     // Check Cache
-    if let Some(user_id) = state
-        .auth_cache
-        .get(&(login_id.clone(), password.clone()))
-        .await
-    {
+    if let Some((user_id, device_id)) = state.auth_cache.get(&login_id, &password).await {
         request.extensions_mut().insert(user_id);
-        return Ok(next.run(request).await);
+        return Ok(run_and_record(&state, device_id, request, next).await);
     }
 
     // Look up user by login_id
@@ -82,7 +210,7 @@ pub async fn caldav_basic_auth(
     // We parallelize this using JoinSet to:
     // 1. Reduce latency (latency is max(Argon2 time) instead of sum(Argon2 time))
     // 2. Mitigate timing attacks (time taken is roughly constant regardless of which device matches)
-    let mut verified_device_id: Option<Uuid> = None;
+    let mut verified: Option<(DeviceId, String)> = None;
     let mut set = tokio::task::JoinSet::new();
 
     for device in &device_passwords {
@@ -91,21 +219,21 @@ pub async fn caldav_basic_auth(
         let device_id_clone = device.id;
 
         set.spawn(async move {
-            let matches = verify_password(password_clone, hashed_password_clone).await;
-            (device_id_clone, matches)
+            let matches = verify_password(password_clone, hashed_password_clone.clone()).await;
+            (device_id_clone, hashed_password_clone, matches)
         });
     }
 
     // Wait for first success or all failures
     while let Some(res) = set.join_next().await {
         match res {
-            Ok((device_id, Ok(true))) => {
-                verified_device_id = Some(device_id);
+            Ok((device_id, hash, Ok(true))) => {
+                verified = Some((device_id, hash));
                 // Abort remaining tasks to save CPU (though blocking tasks may continue running)
                 set.abort_all();
                 break;
             }
-            Ok((_, Err(e))) => {
+            Ok((_, _, Err(e))) => {
                 tracing::error!("Password verification failed: {:?}", e);
             }
             Err(e) => {
@@ -130,8 +258,8 @@ pub async fn caldav_basic_auth(
         let _ = verify_password(password.clone(), DUMMY_ARGON2_HASH.to_string()).await;
     }
 
-    let device_id = verified_device_id
-        .ok_or_else(|| ApiError::Unauthorized("Invalid credentials".to_string()))?;
+    let (device_id, verified_hash) =
+        verified.ok_or_else(|| ApiError::Unauthorized("Invalid credentials".to_string()))?;
 
     let user_id = user_id.ok_or_else(|| {
         ApiError::Internal("Authenticated device has no resolved user".to_string())
@@ -141,13 +269,100 @@ pub async fn caldav_basic_auth(
         tracing::warn!("Failed to update last_used_at: {}", err);
     }
 
+    // Bring hashes made with older Argon2 parameters up to date without
+    // delaying the request
+    let device_service = state.device_service.clone();
+    let upgrade_password = password.clone();
+    tokio::spawn(async move {
+        match device_service
+            .upgrade_password_hash(device_id, upgrade_password, verified_hash)
+            .await
+        {
+            Ok(true) => tracing::info!("Rehashed password of device {}", device_id),
+            Ok(false) => {}
+            Err(err) => tracing::warn!("Failed to rehash device password: {}", err),
+        }
+    });
+
     // Cache success
-    state.auth_cache.insert((login_id, password), user_id).await;
+    state
+        .auth_cache
+        .insert(&login_id, &password, (user_id, device_id))
+        .await;
 
     // Attach user_id to request extensions
     request.extensions_mut().insert(user_id);
 
-    Ok(next.run(request).await)
+    Ok(run_and_record(&state, device_id, request, next).await)
+}
+
+/// Run the request as the device and log it in the device's activity
+async fn run_and_record(
+    state: &AppState,
+    device_id: DeviceId,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let method = request.method().to_string();
+    let path = request.uri().path().to_string();
+    let user_agent = request
+        .headers()
+        .get(USER_AGENT)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+
+    request
+        .extensions_mut()
+        .insert(AuthenticatedDevice(device_id));
+    let response = next.run(request).await;
+
+    let activity = DeviceActivity {
+        device_id,
+        method,
+        path,
+        user_agent,
+        status: response.status().as_u16(),
+        sync_token: response
+            .extensions()
+            .get::<PresentedSyncToken>()
+            .map(|token| token.0.clone()),
+        conflict: response.extensions().get::<SequenceConflict>().cloned(),
+    };
+    if let Err(err) = state.device_service.record_device_activity(activity).await {
+        tracing::warn!("Failed to record device activity: {}", err);
+    }
+
+    response
+}
+
+/// Drop cached CalDAV credentials when a device password is revoked.
+///
+/// Subscribes to the application's domain event bus so revocations made from
+/// any adapter (REST, bot) take effect immediately instead of after the cache
+/// TTL expires.
+pub fn spawn_auth_cache_invalidation(
+    events: &DomainEventBus,
+    auth_cache: AuthCache,
+) -> tokio::task::JoinHandle<()> {
+    let mut receiver = events.subscribe();
+    tokio::spawn(async move {
+        loop {
+            match receiver.recv().await {
+                Ok(DomainEvent::DevicePasswordRevoked { user_id, .. }) => {
+                    auth_cache.invalidate_user(user_id).await;
+                }
+                Ok(_) => {}
+                Err(RecvError::Lagged(skipped)) => {
+                    tracing::warn!(
+                        "Auth cache subscriber lagged by {} events, clearing cache",
+                        skipped
+                    );
+                    auth_cache.invalidate_all();
+                }
+                Err(RecvError::Closed) => break,
+            }
+        }
+    })
 }
 
 /// Parse HTTP Basic Auth header
@@ -202,6 +417,7 @@ async fn verify_password(password: String, hashed_password: String) -> Result<bo
         let parsed_hash = PasswordHash::new(&hashed_password)
             .map_err(|e| ApiError::Internal(format!("Invalid password hash: {}", e)))?;
 
+        // The hash carries its own parameters, so any cost verifies
         Ok(Argon2::default()
             .verify_password(password.as_bytes(), &parsed_hash)
             .is_ok())
@@ -217,6 +433,7 @@ mod tests {
         Argon2,
         password_hash::{PasswordHasher, SaltString, rand_core::OsRng},
     };
+    use uuid::Uuid;
 
     #[test]
     fn test_parse_basic_auth_valid_numeric() {
@@ -379,4 +596,74 @@ mod tests {
         assert!(result.is_ok());
         assert!(result.unwrap());
     }
+
+    #[tokio::test]
+    async fn test_invalidate_user_only_drops_that_user() {
+        let cache = AuthCache::new(Duration::from_secs(300), 100);
+        let revoked = LoginId::TelegramId(1);
+        let other = LoginId::TelegramId(2);
+        let other_device = DeviceId::new(Uuid::new_v4());
+        cache
+            .insert(
+                &revoked,
+                "secret",
+                (UserId::new(1), DeviceId::new(Uuid::new_v4())),
+            )
+            .await;
+        cache
+            .insert(&other, "secret", (UserId::new(2), other_device))
+            .await;
+
+        cache.invalidate_user(UserId::new(1)).await;
+
+        assert!(cache.get(&revoked, "secret").await.is_none());
+        assert_eq!(
+            cache.get(&other, "secret").await,
+            Some((UserId::new(2), other_device))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cache_keys_hide_the_credentials() {
+        let cache = AuthCache::new(Duration::from_secs(300), 100);
+        let login_id = LoginId::Username("prince".to_string());
+        let login = (UserId::new(1), DeviceId::new(Uuid::new_v4()));
+        cache.insert(&login_id, "hunter2", login).await;
+
+        assert_eq!(cache.get(&login_id, "hunter2").await, Some(login));
+        assert!(cache.get(&login_id, "hunter3").await.is_none());
+        assert!(
+            cache
+                .get(&LoginId::Username("prince2".to_string()), "hunter2")
+                .await
+                .is_none()
+        );
+
+        // Another process, with another secret, derives other keys
+        let other = AuthCache::new(Duration::from_secs(300), 100);
+        assert_ne!(
+            cache.key(&login_id, "hunter2"),
+            other.key(&login_id, "hunter2")
+        );
+        assert_eq!(
+            format!("{:?}", cache.key(&login_id, "hunter2")),
+            "CredentialKey(..)"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_disabled_cache_remembers_nothing() {
+        let cache = AuthCache::disabled();
+        let login_id = LoginId::TelegramId(1);
+        cache
+            .insert(
+                &login_id,
+                "secret",
+                (UserId::new(1), DeviceId::new(Uuid::new_v4())),
+            )
+            .await;
+
+        assert!(!cache.is_enabled());
+        assert!(cache.get(&login_id, "secret").await.is_none());
+    }
 }
@@ -0,0 +1,283 @@
+//! Cross-origin access to the API
+//!
+//! `CORS_ALLOWED_ORIGIN` lists the origins browsers may call the API from,
+//! comma-separated: exact origins such as `https://app.example.com`,
+//! subdomain wildcards such as `https://*.example.com`, or `*` for any
+//! origin. Entries are checked when the configuration is loaded, so a typo
+//! stops startup with the offending entry named.
+
+use axum::http::{HeaderValue, request::Parts};
+use std::sync::Arc;
+use std::time::Duration;
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
+
+/// How long browsers may cache a preflight response by default
+pub const DEFAULT_CORS_MAX_AGE: Duration = Duration::from_secs(600);
+
+/// Origins allowed to call the API and how long preflights are cached
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CorsPolicy {
+    origins: AllowedOrigins,
+    max_age: Option<Duration>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum AllowedOrigins {
+    Any,
+    Listed {
+        exact: Vec<HeaderValue>,
+        subdomains: Arc<[SubdomainPattern]>,
+    },
+}
+
+/// `https://*.example.com`: any subdomain of `example.com`, but not
+/// `example.com` itself
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SubdomainPattern {
+    /// `https://`
+    scheme: String,
+    /// `.example.com`, with the port if the pattern has one
+    suffix: String,
+}
+
+impl SubdomainPattern {
+    fn matches(&self, origin: &str) -> bool {
+        origin
+            .strip_prefix(&self.scheme)
+            .and_then(|authority| authority.strip_suffix(&self.suffix))
+            .is_some_and(|subdomain| !subdomain.is_empty() && subdomain.split('.').all(is_label))
+    }
+}
+
+impl CorsPolicy {
+    /// Any origin, as with `CORS_ALLOWED_ORIGIN=*`
+    #[must_use]
+    pub const fn any() -> Self {
+        Self {
+            origins: AllowedOrigins::Any,
+            max_age: Some(DEFAULT_CORS_MAX_AGE),
+        }
+    }
+
+    /// Parse a comma-separated origin list; the error names the first entry
+    /// that is not an origin
+    pub fn parse(origins: &str) -> Result<Self, String> {
+        let entries: Vec<&str> = origins
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .collect();
+        if entries.is_empty() {
+            return Err("no origin given".to_string());
+        }
+        if entries.contains(&"*") {
+            if entries.len() > 1 {
+                return Err("`*` allows every origin and cannot be combined with others".into());
+            }
+            return Ok(Self::any());
+        }
+
+        let mut exact = Vec::new();
+        let mut subdomains = Vec::new();
+        for entry in entries {
+            let entry = entry.to_ascii_lowercase();
+            let (scheme, authority) = split_origin(&entry)
+                .ok_or_else(|| format!("`{entry}` is not an origin like https://example.com"))?;
+            if let Some(suffix) = authority.strip_prefix("*.") {
+                if !is_authority(suffix) || !suffix.split(':').next().unwrap_or("").contains('.') {
+                    return Err(format!(
+                        "`{entry}` must name a domain below the wildcard, like https://*.example.com"
+                    ));
+                }
+                subdomains.push(SubdomainPattern {
+                    scheme: format!("{scheme}://"),
+                    suffix: format!(".{suffix}"),
+                });
+            } else if is_authority(authority) {
+                exact.push(
+                    HeaderValue::from_str(&entry)
+                        .map_err(|_| format!("`{entry}` is not a valid header value"))?,
+                );
+            } else {
+                return Err(format!(
+                    "`{entry}` is not an origin like https://example.com"
+                ));
+            }
+        }
+        Ok(Self {
+            origins: AllowedOrigins::Listed {
+                exact,
+                subdomains: subdomains.into(),
+            },
+            max_age: Some(DEFAULT_CORS_MAX_AGE),
+        })
+    }
+
+    /// Let browsers cache preflight responses for `max_age`; `None` leaves
+    /// `Access-Control-Max-Age` out
+    #[must_use]
+    pub const fn with_max_age(mut self, max_age: Option<Duration>) -> Self {
+        self.max_age = max_age;
+        self
+    }
+
+    /// Whether a request from `origin` may read the response
+    pub fn allows(&self, origin: &HeaderValue) -> bool {
+        match &self.origins {
+            AllowedOrigins::Any => true,
+            AllowedOrigins::Listed { exact, subdomains } => {
+                exact.contains(origin)
+                    || origin.to_str().is_ok_and(|origin| {
+                        subdomains.iter().any(|pattern| pattern.matches(origin))
+                    })
+            }
+        }
+    }
+
+    /// CORS layer for the policy, allowing any method and header
+    pub fn layer(&self) -> CorsLayer {
+        let allow_origin = match &self.origins {
+            AllowedOrigins::Any => AllowOrigin::any(),
+            AllowedOrigins::Listed { exact, subdomains } if subdomains.is_empty() => {
+                AllowOrigin::list(exact.clone())
+            }
+            AllowedOrigins::Listed { .. } => {
+                let policy = self.clone();
+                AllowOrigin::predicate(move |origin: &HeaderValue, _: &Parts| policy.allows(origin))
+            }
+        };
+        let layer = CorsLayer::new()
+            .allow_origin(allow_origin)
+            .allow_methods(Any)
+            .allow_headers(Any);
+        match self.max_age {
+            Some(max_age) => layer.max_age(max_age),
+            None => layer,
+        }
+    }
+}
+
+/// `("https", "example.com:8443")` from `https://example.com:8443`
+fn split_origin(origin: &str) -> Option<(&str, &str)> {
+    let (scheme, authority) = origin.split_once("://")?;
+    matches!(scheme, "http" | "https").then_some((scheme, authority))
+}
+
+/// A host with an optional port, and nothing else: no path, user or query
+fn is_authority(authority: &str) -> bool {
+    let (host, port) = match authority.rsplit_once(':') {
+        Some((host, port)) => (host, Some(port)),
+        None => (authority, None),
+    };
+    let port_ok = port.is_none_or(|port| port.parse::<u16>().is_ok());
+    let host_ok = !host.is_empty() && host.split('.').all(is_label);
+    port_ok && host_ok
+}
+
+/// A lowercase DNS label such as `staging` or `pr-12`
+fn is_label(label: &str) -> bool {
+    !label.is_empty()
+        && label.len() <= 63
+        && label
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+        && !label.starts_with('-')
+        && !label.ends_with('-')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        Router,
+        body::Body,
+        http::{Method, Request, StatusCode, header},
+        routing::get,
+    };
+    use tower::ServiceExt;
+
+    fn origin(value: &str) -> HeaderValue {
+        HeaderValue::from_str(value).unwrap()
+    }
+
+    #[test]
+    fn test_parse_accepts_lists_and_wildcards() {
+        let policy = CorsPolicy::parse(
+            "https://app.example.com, https://*.staging.example.com, http://localhost:3000",
+        )
+        .unwrap();
+
+        assert!(policy.allows(&origin("https://app.example.com")));
+        assert!(policy.allows(&origin("http://localhost:3000")));
+        assert!(policy.allows(&origin("https://pr-12.staging.example.com")));
+        assert!(policy.allows(&origin("https://a.b.staging.example.com")));
+
+        assert!(!policy.allows(&origin("https://staging.example.com")));
+        assert!(!policy.allows(&origin("http://pr-12.staging.example.com")));
+        assert!(!policy.allows(&origin("https://evil-staging.example.com")));
+        assert!(!policy.allows(&origin("https://app.example.com.evil.com")));
+        assert!(!policy.allows(&origin("http://localhost:3001")));
+
+        assert!(
+            CorsPolicy::parse("*")
+                .unwrap()
+                .allows(&origin("https://any.where"))
+        );
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_entries() {
+        for (value, named) in [
+            ("", "no origin"),
+            ("*, https://example.com", "cannot be combined"),
+            ("example.com", "`example.com`"),
+            ("https://example.com/app", "`https://example.com/app`"),
+            ("ftp://example.com", "`ftp://example.com`"),
+            ("https://*.com", "`https://*.com`"),
+            ("https://example.com:99999", "`https://example.com:99999`"),
+            ("https://ok.com,https://bad host", "`https://bad host`"),
+        ] {
+            let err = CorsPolicy::parse(value).unwrap_err();
+            assert!(err.contains(named), "{value}: {err}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_preflight_is_cached_for_max_age() {
+        let policy = CorsPolicy::parse("https://*.example.com")
+            .unwrap()
+            .with_max_age(Some(Duration::from_secs(3600)));
+        let app = Router::new()
+            .route("/", get(|| async { "ok" }))
+            .layer(policy.layer());
+        let preflight = |from: &str| {
+            Request::builder()
+                .method(Method::OPTIONS)
+                .uri("/")
+                .header(header::ORIGIN, from)
+                .header(header::ACCESS_CONTROL_REQUEST_METHOD, "GET")
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let response = app
+            .clone()
+            .oneshot(preflight("https://app.example.com"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[header::ACCESS_CONTROL_ALLOW_ORIGIN],
+            "https://app.example.com"
+        );
+        assert_eq!(response.headers()[header::ACCESS_CONTROL_MAX_AGE], "3600");
+
+        let response = app.oneshot(preflight("https://example.org")).await.unwrap();
+        assert!(
+            response
+                .headers()
+                .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+                .is_none()
+        );
+    }
+}
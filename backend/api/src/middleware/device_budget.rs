@@ -0,0 +1,115 @@
+//! Per-device request budgets for CalDAV and CardDAV
+//!
+//! Runs after Basic auth, so limits follow the device password rather than
+//! the IP: a misbehaving client on one phone can't starve the user's other
+//! devices, and devices behind a shared NAT don't share a budget. Requests
+//! over the rate get `429` with `Retry-After`; bodies over the size cap get
+//! `413` before any handler parses them.
+
+use axum::{
+    Router,
+    body::Body,
+    extract::{Request, State},
+    http::{StatusCode, header::CONTENT_LENGTH},
+    middleware::{self as axum_middleware, Next},
+    response::{IntoResponse, Response},
+};
+use std::time::Duration;
+use televent_application::DeviceId;
+use tower_governor::{
+    GovernorLayer, errors::GovernorError, governor::GovernorConfigBuilder,
+    key_extractor::KeyExtractor,
+};
+
+/// Device password that authenticated the request, set by the Basic auth
+/// middleware
+#[derive(Debug, Clone, Copy)]
+pub struct AuthenticatedDevice(pub DeviceId);
+
+/// Budgets applied to every authenticated DAV request of a device
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeviceBudget {
+    /// Sustained requests per minute, also allowed as a burst; 0 disables
+    pub requests_per_minute: u32,
+    /// Largest request body in bytes; 0 disables
+    pub max_body_bytes: usize,
+}
+
+impl Default for DeviceBudget {
+    fn default() -> Self {
+        Self {
+            requests_per_minute: 60,
+            max_body_bytes: 512 * 1024,
+        }
+    }
+}
+
+#[derive(Clone)]
+struct DeviceKeyExtractor;
+
+impl KeyExtractor for DeviceKeyExtractor {
+    type Key = DeviceId;
+
+    fn extract<B>(&self, req: &axum::http::Request<B>) -> Result<Self::Key, GovernorError> {
+        req.extensions()
+            .get::<AuthenticatedDevice>()
+            .map(|device| device.0)
+            .ok_or(GovernorError::UnableToExtractKey)
+    }
+}
+
+/// Wrap DAV routes in the device budgets
+///
+/// Call before adding the auth layer, so the budgets run after it.
+pub fn apply<S>(routes: Router<S>, budget: DeviceBudget) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    let mut routes = routes;
+    if budget.max_body_bytes > 0 {
+        routes = routes.layer(axum_middleware::from_fn_with_state(
+            budget.max_body_bytes,
+            limit_body,
+        ));
+    }
+    if budget.requests_per_minute > 0 {
+        let config = GovernorConfigBuilder::default()
+            .period(Duration::from_secs(60) / budget.requests_per_minute)
+            .burst_size(budget.requests_per_minute)
+            .key_extractor(DeviceKeyExtractor)
+            .finish()
+            .expect("Failed to create device governor config");
+        routes = routes.layer(GovernorLayer::new(config));
+    }
+    routes
+}
+
+/// Buffer the body up to `max_body_bytes`, refusing larger ones
+///
+/// Handlers read the whole body anyway, so buffering here costs nothing extra
+/// and also catches chunked bodies without a `Content-Length`.
+async fn limit_body(State(max_body_bytes): State<usize>, request: Request, next: Next) -> Response {
+    let declared = request
+        .headers()
+        .get(CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<usize>().ok());
+    if declared.is_some_and(|length| length > max_body_bytes) {
+        return too_large(max_body_bytes);
+    }
+
+    let (parts, body) = request.into_parts();
+    let Ok(bytes) = axum::body::to_bytes(body, max_body_bytes).await else {
+        return too_large(max_body_bytes);
+    };
+    next.run(Request::from_parts(parts, Body::from(bytes)))
+        .await
+}
+
+fn too_large(max_body_bytes: usize) -> Response {
+    (
+        StatusCode::PAYLOAD_TOO_LARGE,
+        format!("Request body exceeds {max_body_bytes} bytes"),
+    )
+        .into_response()
+}
@@ -1,8 +1,14 @@
 //! Middleware modules
 
+pub mod base_path;
 pub mod caldav_auth;
 pub mod caldav_headers;
 pub mod caldav_logging;
+pub mod cors;
+pub mod device_budget;
 pub mod rate_limit;
+pub mod read_only;
+pub mod roles;
 pub mod security_headers;
+pub mod static_assets;
 pub mod telegram_auth;
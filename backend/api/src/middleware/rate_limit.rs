@@ -1,10 +1,19 @@
 //! Rate limiting middleware
 //!
-//! Implements rate limiting using `tower-governor`.
-
-use axum::{extract::ConnectInfo, http::Request};
+//! Implements rate limiting using `tower-governor`. The `/api` limiters also
+//! tell clients where they stand through `X-RateLimit-*` headers, so the
+//! mini app can slow down before it gets a 429.
+
+use axum::{
+    body::Body,
+    extract::{ConnectInfo, Request, State},
+    http::{HeaderName, HeaderValue, StatusCode, header},
+    middleware::Next,
+    response::Response,
+};
 use std::hash::Hash;
 use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
 use tower_governor::{errors::GovernorError, key_extractor::KeyExtractor};
 use uuid::Uuid;
 
@@ -17,6 +26,70 @@ pub const CALDAV_BURST_SIZE: u32 = 100;
 pub const API_PERIOD_MS: u64 = 200;
 pub const API_BURST_SIZE: u32 = 300;
 
+// - Public event pages: 30 requests/minute = 1 request every 2s, since each
+//   signup queues a confirmation email
+pub const PUBLIC_PAGE_PERIOD_MS: u64 = 2000;
+pub const PUBLIC_PAGE_BURST_SIZE: u32 = 30;
+
+pub const RATE_LIMIT_LIMIT: HeaderName = HeaderName::from_static("x-ratelimit-limit");
+pub const RATE_LIMIT_REMAINING: HeaderName = HeaderName::from_static("x-ratelimit-remaining");
+pub const RATE_LIMIT_RESET: HeaderName = HeaderName::from_static("x-ratelimit-reset");
+
+/// Headers a cross-origin client needs to see to back off
+pub const RATE_LIMIT_HEADERS: [HeaderName; 4] = [
+    RATE_LIMIT_LIMIT,
+    RATE_LIMIT_REMAINING,
+    RATE_LIMIT_RESET,
+    header::RETRY_AFTER,
+];
+
+/// Complete the headers of a governor built with `use_headers`
+///
+/// The governor reports `X-RateLimit-Limit` and `X-RateLimit-Remaining`. This
+/// adds `X-RateLimit-Reset`, the seconds until the whole burst is available
+/// again given one request refills every `period`. The governor rounds
+/// `Retry-After` down to whole seconds, which is 0 for the sub-second API
+/// period, so a 429 is told to wait at least one period instead.
+pub async fn rate_limit_headers(
+    State(period): State<Duration>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    let mut response = next.run(request).await;
+    let headers = response.headers_mut();
+
+    let read = |name: &HeaderName| {
+        headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<u32>().ok())
+    };
+    let (Some(limit), Some(remaining)) = (read(&RATE_LIMIT_LIMIT), read(&RATE_LIMIT_REMAINING))
+    else {
+        return response;
+    };
+
+    let reset = whole_seconds(period * limit.saturating_sub(remaining));
+    headers.insert(RATE_LIMIT_RESET, HeaderValue::from(reset));
+
+    if response.status() == StatusCode::TOO_MANY_REQUESTS {
+        let headers = response.headers_mut();
+        let wait = headers
+            .get(header::RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<u64>().ok())
+            .unwrap_or_default()
+            .max(whole_seconds(period));
+        headers.insert(header::RETRY_AFTER, HeaderValue::from(wait));
+    }
+
+    response
+}
+
+fn whole_seconds(duration: Duration) -> u64 {
+    duration.as_secs() + u64::from(duration.subsec_nanos() > 0)
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum RateLimitKey {
     User(Uuid),
@@ -186,6 +259,46 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_rate_limit_headers() {
+        let period = Duration::from_millis(200);
+        let config = GovernorConfigBuilder::default()
+            .period(period)
+            .burst_size(2)
+            .key_extractor(UserOrIpKeyExtractor)
+            .use_headers()
+            .finish()
+            .unwrap();
+        let app = axum::Router::new()
+            .route("/", axum::routing::get(|| async { "ok" }))
+            .layer(GovernorLayer::new(config))
+            .layer(axum::middleware::from_fn_with_state(
+                period,
+                rate_limit_headers,
+            ));
+
+        let addr: SocketAddr = "127.0.0.1:12345".parse().unwrap();
+        let request = || {
+            let mut req = Request::new(Body::empty());
+            req.extensions_mut().insert(ConnectInfo(addr));
+            req
+        };
+
+        let res = app.clone().oneshot(request()).await.unwrap();
+        assert_eq!(res.status(), 200);
+        assert_eq!(res.headers()[&RATE_LIMIT_LIMIT], "2");
+        assert_eq!(res.headers()[&RATE_LIMIT_REMAINING], "1");
+        assert_eq!(res.headers()[&RATE_LIMIT_RESET], "1");
+
+        app.clone().oneshot(request()).await.unwrap();
+        let res = app.clone().oneshot(request()).await.unwrap();
+        assert_eq!(res.status(), 429);
+        assert_eq!(res.headers()[&RATE_LIMIT_REMAINING], "0");
+        // The burst refills in 400ms, rounded up to a second
+        assert_eq!(res.headers()[&RATE_LIMIT_RESET], "1");
+        assert_eq!(res.headers()[header::RETRY_AFTER], "1");
+    }
+
     #[tokio::test]
     async fn test_rate_limit_key_extraction_priority() {
         let extractor = UserOrIpKeyExtractor;
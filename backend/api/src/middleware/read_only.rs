@@ -0,0 +1,76 @@
+//! Read-only maintenance mode
+//!
+//! While the `read_only` flag is on (or the deployment was started with
+//! `READ_ONLY_MODE`), requests that could change data get `503` with
+//! `Retry-After`. Reads, CalDAV/CardDAV discovery and reports, login and the
+//! admin routes that switch the mode back off keep working.
+
+use axum::{
+    extract::{Request, State},
+    http::{Method, StatusCode, header::RETRY_AFTER},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use televent_application::FeatureFlagService;
+
+/// How long clients are asked to wait before retrying a refused write
+pub const READ_ONLY_RETRY_AFTER_SECS: u64 = 300;
+
+/// Paths whose writes stay allowed in maintenance mode
+const WRITABLE_PREFIXES: [&str; 2] = ["/api/admin/", "/api/auth/"];
+
+pub async fn reject_writes_when_read_only(
+    State(flags): State<FeatureFlagService>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if !is_write(request.method())
+        || WRITABLE_PREFIXES
+            .iter()
+            .any(|prefix| request.uri().path().starts_with(prefix))
+    {
+        return next.run(request).await;
+    }
+
+    match flags.is_read_only().await {
+        Ok(true) => read_only_response(),
+        Ok(false) => next.run(request).await,
+        Err(err) => {
+            // Without the flag table the write will likely fail anyway;
+            // let the handler report it
+            tracing::warn!("Failed to read maintenance mode: {err}");
+            next.run(request).await
+        }
+    }
+}
+
+fn is_write(method: &Method) -> bool {
+    !matches!(
+        method.as_str(),
+        "GET" | "HEAD" | "OPTIONS" | "PROPFIND" | "REPORT"
+    )
+}
+
+fn read_only_response() -> Response {
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        [(RETRY_AFTER, READ_ONLY_RETRY_AFTER_SECS.to_string())],
+        "Televent is in read-only maintenance mode; please retry later",
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dav_reads_are_not_writes() {
+        for method in ["GET", "HEAD", "OPTIONS", "PROPFIND", "REPORT"] {
+            assert!(!is_write(&Method::from_bytes(method.as_bytes()).unwrap()));
+        }
+        for method in ["POST", "PUT", "DELETE", "PATCH", "PROPPATCH", "MKCALENDAR"] {
+            assert!(is_write(&Method::from_bytes(method.as_bytes()).unwrap()));
+        }
+    }
+}
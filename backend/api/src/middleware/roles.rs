@@ -0,0 +1,32 @@
+//! Role guard for privileged routes
+//!
+//! `telegram_auth` puts the caller's [`UserRole`] into the request extensions;
+//! routes that need more than a regular user add
+//! `.route_layer(from_fn_with_state(UserRole::Admin, require_role))`.
+
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+use televent_application::UserRole;
+
+use crate::error::ApiError;
+
+/// Let the request through only when the caller's role includes `required`
+pub async fn require_role(
+    State(required): State<UserRole>,
+    request: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    let role = request
+        .extensions()
+        .get::<UserRole>()
+        .copied()
+        .unwrap_or_default();
+    if !role.includes(required) {
+        return Err(ApiError::Forbidden);
+    }
+
+    Ok(next.run(request).await)
+}
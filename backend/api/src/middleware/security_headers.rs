@@ -7,6 +7,7 @@
 //! - X-XSS-Protection: 1; mode=block
 //! - Strict-Transport-Security: max-age=31536000; includeSubDomains
 //! - Referrer-Policy: strict-origin-when-cross-origin
+//! - Content-Security-Policy, unless the response already has one
 
 use axum::{extract::Request, http::HeaderValue, middleware::Next, response::Response};
 
@@ -41,11 +42,11 @@ pub async fn security_headers(req: Request, next: Next) -> Response {
         HeaderValue::from_static("strict-origin-when-cross-origin"),
     );
 
-    // Content Security Policy
-    // - script-src/style-src: unsafe-inline required for Next.js static export + Swagger UI
+    // Content Security Policy, unless the response set its own (the web app
+    // under /app does)
+    // - script-src/style-src: unsafe-inline required for Swagger UI
     // - frame-ancestors: restricted to Telegram domains for Mini App support
-    headers.insert(
-        "Content-Security-Policy",
+    headers.entry("Content-Security-Policy").or_insert(
         HeaderValue::from_static("default-src 'self'; script-src 'self' 'unsafe-inline' 'unsafe-eval'; style-src 'self' 'unsafe-inline'; object-src 'none'; frame-ancestors https://web.telegram.org https://*.telegram.org; img-src 'self' data: https:; connect-src 'self';"),
     );
 
@@ -86,4 +87,22 @@ mod tests {
             "default-src 'self'; script-src 'self' 'unsafe-inline' 'unsafe-eval'; style-src 'self' 'unsafe-inline'; object-src 'none'; frame-ancestors https://web.telegram.org https://*.telegram.org; img-src 'self' data: https:; connect-src 'self';"
         );
     }
+
+    #[tokio::test]
+    async fn test_keeps_a_policy_set_by_the_route() {
+        let app = Router::new()
+            .route(
+                "/",
+                get(|| async { ([("Content-Security-Policy", "default-src 'none'")], "Hello") }),
+            )
+            .layer(axum::middleware::from_fn(security_headers));
+
+        let req = Request::builder().uri("/").body(Body::empty()).unwrap();
+        let response = app.oneshot(req).await.unwrap();
+
+        assert_eq!(
+            response.headers().get("Content-Security-Policy").unwrap(),
+            "default-src 'none'"
+        );
+    }
 }
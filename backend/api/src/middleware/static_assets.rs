@@ -0,0 +1,191 @@
+//! Headers for the web app served under `/app`
+//!
+//! The app is a Next.js static export that opens inside Telegram's webview.
+//! Its responses get a Content Security Policy that lets Telegram Web frame
+//! it, replacing the stricter API one. Build output under `_next/static/`
+//! carries a content hash in its file name, so browsers may keep it for a
+//! year; everything else, `index.html` included, is revalidated on each load
+//! so a deploy shows up at once.
+
+use axum::{
+    extract::{Request, State},
+    http::{HeaderValue, header},
+    middleware::Next,
+    response::Response,
+};
+
+/// Cache policy of files whose name changes with their content
+pub const IMMUTABLE_CACHE_CONTROL: &str = "public, max-age=31536000, immutable";
+
+/// Cache policy of pages and unhashed files
+pub const REVALIDATE_CACHE_CONTROL: &str = "no-cache";
+
+/// Origins allowed to frame the app: Telegram Web and its variants
+const TELEGRAM_FRAME_ANCESTORS: &str = "https://web.telegram.org https://*.telegram.org";
+
+/// Directory of the Next.js build output that is content-hashed
+const HASHED_ASSET_DIR: &str = "/_next/static/";
+
+/// Content Security Policy of the web app
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrontendAssets {
+    content_security_policy: HeaderValue,
+}
+
+impl Default for FrontendAssets {
+    fn default() -> Self {
+        Self::new(&[], &[]).expect("default policy is a valid header")
+    }
+}
+
+impl FrontendAssets {
+    /// The default policy, additionally letting the app call the API at
+    /// `connect_src` origins and be framed by `frame_ancestors` ones, e.g.
+    /// for an API or a Telegram client on a custom domain. `None` when an
+    /// origin is not a single CSP source.
+    pub fn new(connect_src: &[String], frame_ancestors: &[String]) -> Option<Self> {
+        if !connect_src
+            .iter()
+            .chain(frame_ancestors)
+            .all(|source| is_source(source))
+        {
+            return None;
+        }
+        let with_extra = |base: &str, extra: &[String]| {
+            std::iter::once(base)
+                .chain(extra.iter().map(String::as_str))
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        Self::with_policy(&format!(
+            "default-src 'self'; script-src 'self' 'unsafe-inline'; \
+             style-src 'self' 'unsafe-inline'; img-src 'self' data: https:; \
+             font-src 'self' data:; connect-src {}; object-src 'none'; \
+             base-uri 'self'; form-action 'self'; frame-ancestors {}",
+            with_extra("'self'", connect_src),
+            with_extra(TELEGRAM_FRAME_ANCESTORS, frame_ancestors),
+        ))
+    }
+
+    /// A policy written out in full; `None` if it cannot be sent as a header
+    pub fn with_policy(policy: &str) -> Option<Self> {
+        let policy = policy.trim();
+        if policy.is_empty() {
+            return None;
+        }
+        HeaderValue::from_str(policy)
+            .ok()
+            .map(|content_security_policy| Self {
+                content_security_policy,
+            })
+    }
+
+    pub fn content_security_policy(&self) -> &HeaderValue {
+        &self.content_security_policy
+    }
+}
+
+/// A lone source expression such as `https://api.example.com`
+fn is_source(source: &str) -> bool {
+    !source.is_empty()
+        && source
+            .chars()
+            .all(|c| c.is_ascii_graphic() && !matches!(c, ';' | ',' | '\'' | '"'))
+}
+
+/// Set the app's Content Security Policy and cache headers
+pub async fn frontend_asset_headers(
+    State(assets): State<FrontendAssets>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let hashed = request.uri().path().contains(HASHED_ASSET_DIR);
+    let mut response = next.run(request).await;
+
+    // A missing chunk may be deployed later under the same name
+    let cache_control = if hashed && response.status().is_success() {
+        IMMUTABLE_CACHE_CONTROL
+    } else {
+        REVALIDATE_CACHE_CONTROL
+    };
+    let headers = response.headers_mut();
+    headers.insert(
+        header::CACHE_CONTROL,
+        HeaderValue::from_static(cache_control),
+    );
+    headers.insert(
+        header::CONTENT_SECURITY_POLICY,
+        assets.content_security_policy.clone(),
+    );
+
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{Router, body::Body, http::StatusCode, routing::get};
+    use tower::ServiceExt;
+
+    fn app(assets: FrontendAssets) -> Router {
+        Router::new()
+            .route("/app/", get(|| async { "index" }))
+            .route(
+                "/app/_next/static/chunks/main-1a2b3c.js",
+                get(|| async { "js" }),
+            )
+            .layer(axum::middleware::from_fn_with_state(
+                assets,
+                frontend_asset_headers,
+            ))
+    }
+
+    async fn headers(app: Router, uri: &str) -> (StatusCode, axum::http::HeaderMap) {
+        let response = app
+            .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        (response.status(), response.headers().clone())
+    }
+
+    #[tokio::test]
+    async fn test_hashed_assets_are_immutable_and_pages_revalidate() {
+        let app = app(FrontendAssets::default());
+
+        let (_, asset) = headers(app.clone(), "/app/_next/static/chunks/main-1a2b3c.js").await;
+        assert_eq!(asset[header::CACHE_CONTROL], IMMUTABLE_CACHE_CONTROL);
+
+        let (_, page) = headers(app.clone(), "/app/").await;
+        assert_eq!(page[header::CACHE_CONTROL], REVALIDATE_CACHE_CONTROL);
+
+        let (status, missing) = headers(app, "/app/_next/static/chunks/gone-9f8e7d.js").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(missing[header::CACHE_CONTROL], REVALIDATE_CACHE_CONTROL);
+    }
+
+    #[tokio::test]
+    async fn test_policy_lets_telegram_frame_the_app() {
+        let (_, page) = headers(app(FrontendAssets::default()), "/app/").await;
+        let policy = page[header::CONTENT_SECURITY_POLICY].to_str().unwrap();
+
+        assert!(policy.contains("frame-ancestors https://web.telegram.org https://*.telegram.org"));
+        assert!(policy.contains("connect-src 'self';"));
+        assert!(!policy.contains("unsafe-eval"));
+    }
+
+    #[test]
+    fn test_custom_domains_extend_the_policy() {
+        let assets = FrontendAssets::new(
+            &["https://api.example.com".to_string()],
+            &["https://tg.example.com".to_string()],
+        )
+        .unwrap();
+        let policy = assets.content_security_policy().to_str().unwrap();
+
+        assert!(policy.contains("connect-src 'self' https://api.example.com;"));
+        assert!(policy.ends_with("https://*.telegram.org https://tg.example.com"));
+
+        assert!(FrontendAssets::new(&["https://a.com; script-src *".to_string()], &[]).is_none());
+        assert!(FrontendAssets::with_policy("  ").is_none());
+    }
+}
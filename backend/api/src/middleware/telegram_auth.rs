@@ -2,7 +2,7 @@ use crate::AppState;
 use crate::error::ApiError;
 use chrono::Utc;
 use hmac::{Hmac, Mac};
-use sha2::Sha256;
+use sha2::{Digest, Sha256};
 use std::collections::HashMap;
 use std::env;
 use televent_application::UserId;
@@ -10,6 +10,10 @@ use televent_domain::Timezone;
 
 // Constants
 const AUTH_HEADER_PREFIX: &str = "tma ";
+/// How long signed init data stays valid after `auth_date`
+pub const INIT_DATA_MAX_AGE_SECS: i64 = 86400;
+
+type HmacSha256 = Hmac<Sha256>;
 
 /// User information extracted from Telegram initData
 #[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
@@ -47,42 +51,14 @@ pub fn validate_init_data(init_data: &str, bot_token: &str) -> Result<TelegramUs
         .get("hash")
         .ok_or(ApiError::Unauthorized("Missing hash".into()))?;
 
-    // Validate signature
-    // Implementation of data-check-string construction
-    // Specify type explicitly to avoid inference error
-    let mut keys: Vec<&String> = parsed.keys().filter(|k| k.as_str() != "hash").collect();
-    keys.sort();
-
-    let mut data_check_string = String::new();
-    for (i, key) in keys.iter().enumerate() {
-        if i > 0 {
-            data_check_string.push('\n');
-        }
-        data_check_string.push_str(key);
-        data_check_string.push('=');
-        data_check_string.push_str(&parsed[*key]);
-    }
-
     let skip_verification = dev_bypass_enabled(hash);
 
     if !skip_verification {
-        // HMAC-SHA256 signature
-        type HmacSha256 = Hmac<Sha256>;
-
-        let secret_key = HmacSha256::new_from_slice(b"WebAppData")
-            .expect("HMAC can take any key length")
-            .chain_update(bot_token.as_bytes())
-            .finalize()
-            .into_bytes();
-
-        let mut mac =
-            HmacSha256::new_from_slice(&secret_key).expect("HMAC can take any key length");
-        mac.update(data_check_string.as_bytes());
-
         let hash_bytes =
             hex::decode(hash).map_err(|_| ApiError::Unauthorized("Invalid signature".into()))?;
 
-        mac.verify_slice(&hash_bytes)
+        init_data_mac(&data_check_string(&parsed), bot_token)
+            .verify_slice(&hash_bytes)
             .map_err(|_| ApiError::Unauthorized("Invalid signature".into()))?;
     }
 
@@ -95,8 +71,8 @@ pub fn validate_init_data(init_data: &str, bot_token: &str) -> Result<TelegramUs
                 .parse::<i64>()
                 .map_err(|_| ApiError::BadRequest("Invalid auth_date format".into()))?;
             let now = Utc::now().timestamp();
-            // Check if auth_date is older than 24 hours (86400 seconds)
-            if now - auth_date > 86400 {
+            // Check if auth_date is older than 24 hours
+            if now - auth_date > INIT_DATA_MAX_AGE_SECS {
                 return Err(ApiError::Unauthorized("Auth date expired".into()));
             }
             // Check if auth_date is too far in the future (allow 5 minutes clock skew)
@@ -118,6 +94,131 @@ pub fn validate_init_data(init_data: &str, bot_token: &str) -> Result<TelegramUs
     Ok(user)
 }
 
+/// Fields signed by Telegram, minus the hash itself, as `key=value` lines in
+/// key order
+fn data_check_string(fields: &HashMap<String, String>) -> String {
+    let mut keys: Vec<&String> = fields.keys().filter(|k| k.as_str() != "hash").collect();
+    keys.sort();
+
+    let mut data_check_string = String::new();
+    for (i, key) in keys.iter().enumerate() {
+        if i > 0 {
+            data_check_string.push('\n');
+        }
+        data_check_string.push_str(key);
+        data_check_string.push('=');
+        data_check_string.push_str(&fields[*key]);
+    }
+    data_check_string
+}
+
+/// Mini App signature: HMAC-SHA256 keyed with HMAC("WebAppData", bot token)
+fn init_data_mac(data_check_string: &str, bot_token: &str) -> HmacSha256 {
+    let secret_key = HmacSha256::new_from_slice(b"WebAppData")
+        .expect("HMAC can take any key length")
+        .chain_update(bot_token.as_bytes())
+        .finalize()
+        .into_bytes();
+
+    let mut mac = HmacSha256::new_from_slice(&secret_key).expect("HMAC can take any key length");
+    mac.update(data_check_string.as_bytes());
+    mac
+}
+
+/// Data the Telegram Login Widget hands to the page after a browser login
+#[derive(Debug, Clone, serde::Deserialize, utoipa::ToSchema)]
+pub struct LoginWidgetData {
+    #[schema(example = 123456789)]
+    pub id: i64,
+    pub first_name: String,
+    pub last_name: Option<String>,
+    pub username: Option<String>,
+    pub photo_url: Option<String>,
+    /// Unix time of the login
+    pub auth_date: i64,
+    /// Hex HMAC-SHA256 of the other fields
+    pub hash: String,
+}
+
+/// Check a Login Widget payload and return the user it vouches for.
+///
+/// Unlike Mini App init data, the widget signs with SHA256(bot token) as
+/// the key. The same 24 hour freshness window applies.
+pub fn validate_login_widget(
+    data: &LoginWidgetData,
+    bot_token: &str,
+) -> Result<TelegramUser, ApiError> {
+    let mut fields = HashMap::from([
+        ("id".to_string(), data.id.to_string()),
+        ("first_name".to_string(), data.first_name.clone()),
+        ("auth_date".to_string(), data.auth_date.to_string()),
+    ]);
+    for (key, value) in [
+        ("last_name", &data.last_name),
+        ("username", &data.username),
+        ("photo_url", &data.photo_url),
+    ] {
+        if let Some(value) = value {
+            fields.insert(key.to_string(), value.clone());
+        }
+    }
+
+    let hash_bytes =
+        hex::decode(&data.hash).map_err(|_| ApiError::Unauthorized("Invalid signature".into()))?;
+    let mut mac = HmacSha256::new_from_slice(&Sha256::digest(bot_token.as_bytes()))
+        .expect("HMAC can take any key length");
+    mac.update(data_check_string(&fields).as_bytes());
+    mac.verify_slice(&hash_bytes)
+        .map_err(|_| ApiError::Unauthorized("Invalid signature".into()))?;
+
+    let now = Utc::now().timestamp();
+    if now - data.auth_date > INIT_DATA_MAX_AGE_SECS {
+        return Err(ApiError::Unauthorized("Auth date expired".into()));
+    }
+    if data.auth_date - now > 300 {
+        return Err(ApiError::Unauthorized("Auth date in the future".into()));
+    }
+
+    Ok(TelegramUser {
+        id: data.id,
+        first_name: data.first_name.clone(),
+        last_name: data.last_name.clone(),
+        username: data.username.clone(),
+        language_code: None,
+        is_premium: None,
+        allows_write_to_pm: None,
+    })
+}
+
+/// Sign init data for `user` the way a Mini App launch would.
+///
+/// Browsers logged in through the widget send it in the usual
+/// `Authorization: tma ...` header, so no server-side session is kept; it
+/// stops working 24 hours after `auth_date`.
+pub fn issue_init_data(
+    user: &TelegramUser,
+    auth_date: i64,
+    bot_token: &str,
+) -> Result<String, ApiError> {
+    let user_json = serde_json::to_string(user)
+        .map_err(|e| ApiError::Internal(format!("Failed to encode user: {e}")))?;
+    let fields = HashMap::from([
+        ("auth_date".to_string(), auth_date.to_string()),
+        ("user".to_string(), user_json),
+    ]);
+    let hash = hex::encode(
+        init_data_mac(&data_check_string(&fields), bot_token)
+            .finalize()
+            .into_bytes(),
+    );
+
+    Ok(url::form_urlencoded::Serializer::new(String::new())
+        .append_pair("auth_date", &fields["auth_date"])
+        .append_pair("user", &fields["user"])
+        .append_pair("hash", &hash)
+        .finish())
+}
+
 fn dev_bypass_enabled(hash: &str) -> bool {
     if hash != "dev_bypass" || !cfg!(debug_assertions) {
         return false;
@@ -185,6 +286,7 @@ pub async fn telegram_auth(
     // We insert 2 things:
     // 1. TelegramUser (for AuthUser extractor if needed)
     // 2. AuthenticatedTelegramUser (which contains DB ID, used by endpoints)
+    // plus the user's role for `require_role`
 
     request.extensions_mut().insert(user);
     request.extensions_mut().insert(AuthenticatedTelegramUser {
@@ -192,6 +294,7 @@ pub async fn telegram_auth(
         username: db_user.username,
         timezone: db_user.timezone,
     });
+    request.extensions_mut().insert(db_user.role);
 
     Ok(next.run(request).await)
 }
@@ -325,4 +428,58 @@ mod tests {
             _ => panic!("Expected Unauthorized error"),
         }
     }
+
+    fn login_widget_data(bot_token: &str, auth_date: i64) -> LoginWidgetData {
+        let data_check_string =
+            format!("auth_date={auth_date}\nfirst_name=Test\nid=123\nusername=tester");
+        let mut mac =
+            Hmac::<Sha256>::new_from_slice(&Sha256::digest(bot_token.as_bytes())).unwrap();
+        mac.update(data_check_string.as_bytes());
+
+        LoginWidgetData {
+            id: 123,
+            first_name: "Test".to_string(),
+            last_name: None,
+            username: Some("tester".to_string()),
+            photo_url: None,
+            auth_date,
+            hash: hex::encode(mac.finalize().into_bytes()),
+        }
+    }
+
+    #[test]
+    fn test_login_widget_token_is_valid_init_data() {
+        let bot_token = "test_token";
+        let data = login_widget_data(bot_token, Utc::now().timestamp());
+
+        let user = validate_login_widget(&data, bot_token).unwrap();
+        assert_eq!(user.id, 123);
+        assert_eq!(user.username.as_deref(), Some("tester"));
+
+        let token = issue_init_data(&user, data.auth_date, bot_token).unwrap();
+        let from_token = validate_init_data(&token, bot_token).unwrap();
+        assert_eq!(from_token.id, 123);
+        assert!(validate_init_data(&token, "other_token").is_err());
+    }
+
+    #[test]
+    fn test_login_widget_rejects_tampered_or_expired_data() {
+        let bot_token = "test_token";
+        let mut data = login_widget_data(bot_token, Utc::now().timestamp());
+        data.id = 456;
+        assert!(matches!(
+            validate_login_widget(&data, bot_token),
+            Err(ApiError::Unauthorized(msg)) if msg == "Invalid signature"
+        ));
+
+        // Mini App init data signing doesn't verify as widget data
+        let data = login_widget_data("other_token", Utc::now().timestamp());
+        assert!(validate_login_widget(&data, bot_token).is_err());
+
+        let data = login_widget_data(bot_token, Utc::now().timestamp() - 90000);
+        assert!(matches!(
+            validate_login_widget(&data, bot_token),
+            Err(ApiError::Unauthorized(msg)) if msg == "Auth date expired"
+        ));
+    }
 }
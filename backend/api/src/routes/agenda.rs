@@ -0,0 +1,324 @@
+//! Read-only agenda page
+//!
+//! `GET /app/agenda` renders the next seven days as plain HTML, for browsers
+//! without JavaScript or deployments where the web app isn't built. It lists
+//! events through the same query as `GET /api/events`, so loading it also
+//! checks the whole data path.
+//!
+//! The page takes the usual `Authorization: tma ...` init data, or the same
+//! token from a cookie. `GET /app/login` sets that cookie when the Telegram
+//! Login Widget redirects there (its `data-auth-url` mode). Nothing is kept
+//! on the server, and the cookie expires with the token.
+
+use axum::{
+    Router,
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode, header},
+    response::{Html, IntoResponse, Redirect, Response},
+    routing::get,
+};
+use chrono::{Duration, NaiveDate, NaiveTime, TimeZone, Utc};
+use televent_application::EventView;
+use televent_domain::{EventStatus, EventTiming, Timezone};
+
+use crate::AppState;
+use crate::middleware::base_path::BasePath;
+use crate::middleware::telegram_auth::{
+    INIT_DATA_MAX_AGE_SECS, LoginWidgetData, issue_init_data, validate_init_data,
+    validate_login_widget,
+};
+use crate::routes::public_events::{escape_html, page};
+
+/// Cookie carrying signed init data for the pages under `/app`
+const SESSION_COOKIE: &str = "televent_session";
+
+/// Days on the agenda, today included
+const AGENDA_DAYS: i64 = 7;
+
+/// Most events one agenda lists
+const AGENDA_EVENT_LIMIT: i64 = 200;
+
+async fn login(
+    State(state): State<AppState>,
+    base: BasePath,
+    Query(data): Query<LoginWidgetData>,
+) -> Response {
+    let Ok(user) = validate_login_widget(&data, &state.telegram_bot_token) else {
+        return signed_out("This login link is not valid or has expired.");
+    };
+    if let Err(err) = state
+        .calendar_service
+        .get_or_create_user(user.id, user.username.as_deref())
+        .await
+    {
+        return internal_error(&err.to_string());
+    }
+    let token = match issue_init_data(&user, data.auth_date, &state.telegram_bot_token) {
+        Ok(token) => token,
+        Err(err) => return internal_error(&format!("{err:?}")),
+    };
+
+    let max_age = data.auth_date + INIT_DATA_MAX_AGE_SECS - Utc::now().timestamp();
+    let cookie = format!(
+        "{SESSION_COOKIE}={}; Path={}; Max-Age={max_age}; HttpOnly; Secure; SameSite=Lax",
+        urlencoding::encode(&token),
+        base.join("/app")
+    );
+    tracing::info!(telegram_id = user.id, "Agenda page login");
+    (
+        [(header::SET_COOKIE, cookie)],
+        Redirect::to(&base.join("/app/agenda")),
+    )
+        .into_response()
+}
+
+async fn agenda(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    let Some(init_data) = session_init_data(&headers) else {
+        return signed_out("Log in with Telegram to see your agenda.");
+    };
+    let Ok(user) = validate_init_data(&init_data, &state.telegram_bot_token) else {
+        return signed_out("Your login has expired. Log in with Telegram again.");
+    };
+    let calendar = &state.calendar_service;
+    let db_user = match calendar
+        .get_or_create_user(user.id, user.username.as_deref())
+        .await
+    {
+        Ok(db_user) => db_user,
+        Err(err) => return internal_error(&err.to_string()),
+    };
+
+    let tz = db_user.timezone.tz();
+    let today = Utc::now().with_timezone(&tz).date_naive();
+    let days: Vec<NaiveDate> = today.iter_days().take(AGENDA_DAYS as usize).collect();
+    let local_midnight = |day: NaiveDate| {
+        tz.from_local_datetime(&day.and_time(NaiveTime::MIN))
+            .earliest()
+            .map_or_else(
+                || day.and_time(NaiveTime::MIN).and_utc(),
+                |local| local.with_timezone(&Utc),
+            )
+    };
+    let events = match calendar
+        .list_event_views(
+            db_user.id,
+            Some(local_midnight(today)),
+            Some(local_midnight(today + Duration::days(AGENDA_DAYS))),
+            &[EventStatus::Confirmed, EventStatus::Tentative],
+            None,
+            Some(AGENDA_EVENT_LIMIT),
+            None,
+        )
+        .await
+    {
+        Ok(events) => events,
+        Err(err) => return internal_error(&err.to_string()),
+    };
+
+    Html(render_agenda(&days, &db_user.timezone, &events)).into_response()
+}
+
+/// Init data from the `tma` Authorization header, else from the session
+/// cookie
+fn session_init_data(headers: &HeaderMap) -> Option<String> {
+    let header = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("tma "));
+    if let Some(init_data) = header {
+        return Some(init_data.to_string());
+    }
+
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(name, _)| *name == SESSION_COOKIE)
+        .and_then(|(_, value)| urlencoding::decode(value).ok())
+        .map(|value| value.into_owned())
+}
+
+fn render_agenda(days: &[NaiveDate], timezone: &Timezone, events: &[EventView]) -> String {
+    let tz = timezone.tz();
+    let mut body = format!(
+        "<h1>Agenda</h1><p>The next {} days, times in {}.</p>",
+        days.len(),
+        escape_html(timezone.as_str())
+    );
+
+    for day in days {
+        body.push_str(&format!("<h2>{}</h2>", day.format("%a %d %b")));
+        let mut entries = Vec::new();
+        for event in events {
+            let (on_day, time) = match event.timing.pinned_to(timezone) {
+                EventTiming::Timed { start, end, .. } => {
+                    let start = start.with_timezone(&tz);
+                    let end = end.with_timezone(&tz);
+                    let time = if end.date_naive() == start.date_naive() {
+                        format!("{}–{}", start.format("%H:%M"), end.format("%H:%M"))
+                    } else {
+                        format!("{}–{}", start.format("%H:%M"), end.format("%a %H:%M"))
+                    };
+                    (start.date_naive() == *day, time)
+                }
+                EventTiming::AllDay {
+                    start_date,
+                    end_date,
+                } => (start_date <= *day && *day < end_date, "All day".to_string()),
+                EventTiming::Floating { .. } => unreachable!("pinned to the viewer's zone"),
+            };
+            if on_day {
+                entries.push(render_entry(event, &time));
+            }
+        }
+
+        if entries.is_empty() {
+            body.push_str("<p>Nothing planned.</p>");
+        } else {
+            body.push_str(&format!("<ul>{}</ul>", entries.concat()));
+        }
+    }
+
+    page("Agenda", &body)
+}
+
+fn render_entry(event: &EventView, time: &str) -> String {
+    let mut entry = format!(
+        "<li><strong>{}</strong> {}",
+        escape_html(time),
+        escape_html(&event.summary)
+    );
+    if event.status == EventStatus::Tentative {
+        entry.push_str(" <em>(tentative)</em>");
+    }
+    if let Some(location) = &event.location {
+        entry.push_str(&format!("<br>📍 {}", escape_html(location)));
+    }
+    entry.push_str("</li>");
+    entry
+}
+
+fn signed_out(message: &str) -> Response {
+    (
+        StatusCode::UNAUTHORIZED,
+        Html(page(
+            "Agenda",
+            &format!(
+                "<h1>Agenda</h1><p>{}</p>\
+                 <p>Open Televent from Telegram, or use the Telegram Login Widget \
+                 with <code>/app/login</code> as its redirect URL.</p>",
+                escape_html(message)
+            ),
+        )),
+    )
+        .into_response()
+}
+
+fn internal_error(message: &str) -> Response {
+    tracing::error!("Agenda page failed: {}", message);
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Html(page(
+            "Agenda",
+            "<p>Something went wrong. Please try again later.</p>",
+        )),
+    )
+        .into_response()
+}
+
+/// Agenda routes; the handlers check init data themselves so a cookie works
+/// too
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/app/login", get(login))
+        .route("/app/agenda", get(agenda))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::DateTime;
+    use televent_domain::EventVisibility;
+    use uuid::Uuid;
+
+    fn event(summary: &str, timing: EventTiming) -> EventView {
+        EventView {
+            id: Uuid::new_v4(),
+            uid: Uuid::new_v4().to_string(),
+            summary: summary.to_string(),
+            description: None,
+            location: Some("Room <1>".to_string()),
+            timing,
+            status: EventStatus::Confirmed,
+            rrule: None,
+            visibility: EventVisibility::Public,
+            reminder_note: None,
+        }
+    }
+
+    fn at(value: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(value).unwrap().to_utc()
+    }
+
+    #[test]
+    fn test_agenda_groups_by_local_day() {
+        let berlin = Timezone::parse("Europe/Berlin").unwrap();
+        let first = NaiveDate::from_ymd_opt(2026, 11, 2).unwrap();
+        let days: Vec<NaiveDate> = first.iter_days().take(3).collect();
+        let html = render_agenda(
+            &days,
+            &berlin,
+            &[
+                // 23:30 UTC is already Tuesday in Berlin
+                event(
+                    "Late <call>",
+                    EventTiming::Timed {
+                        start: at("2026-11-02T23:30:00Z"),
+                        end: at("2026-11-03T00:30:00Z"),
+                        timezone: Timezone::utc(),
+                    },
+                ),
+                event(
+                    "Offsite",
+                    EventTiming::AllDay {
+                        start_date: first,
+                        end_date: first + Duration::days(2),
+                    },
+                ),
+            ],
+        );
+
+        let monday = html.find("Mon 02 Nov").unwrap();
+        let tuesday = html.find("Tue 03 Nov").unwrap();
+        let wednesday = html.find("Wed 04 Nov").unwrap();
+        let call = html.find("00:30–01:30</strong> Late &lt;call&gt;").unwrap();
+        assert!(tuesday < call && call < wednesday);
+        assert_eq!(html.matches("All day").count(), 2);
+        assert!(html[monday..tuesday].contains("Offsite"));
+        assert!(html[wednesday..].contains("Nothing planned."));
+        assert!(html.contains("Room &lt;1&gt;"));
+        assert!(!html.contains("<call>"));
+    }
+
+    #[test]
+    fn test_session_cookie_or_header_carries_init_data() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(session_init_data(&headers), None);
+
+        headers.insert(
+            header::COOKIE,
+            "theme=dark; televent_session=auth_date%3D1%26hash%3Dab"
+                .parse()
+                .unwrap(),
+        );
+        assert_eq!(
+            session_init_data(&headers).as_deref(),
+            Some("auth_date=1&hash=ab")
+        );
+
+        headers.insert(header::AUTHORIZATION, "tma from_header".parse().unwrap());
+        assert_eq!(session_init_data(&headers).as_deref(), Some("from_header"));
+    }
+}
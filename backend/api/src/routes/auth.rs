@@ -0,0 +1,61 @@
+//! Browser login through the Telegram Login Widget
+//!
+//! Outside Telegram there is no Mini App init data, so the web app posts
+//! what the widget returned and gets init data signed by the server back.
+//! It then authenticates exactly like the Mini App, without a session.
+
+use axum::{Json, Router, extract::State, routing::post};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::AppState;
+use crate::error::ApiError;
+use crate::middleware::telegram_auth::{
+    INIT_DATA_MAX_AGE_SECS, LoginWidgetData, issue_init_data, validate_login_widget,
+};
+
+/// Token for the `Authorization: tma <token>` header
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TelegramLoginResponse {
+    /// Signed init data, used like the Mini App's
+    pub token: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Log in with the Telegram Login Widget
+///
+/// Checks the widget's signature and returns a token that the other
+/// endpoints accept as Telegram init data.
+#[utoipa::path(
+    post,
+    path = "/auth/telegram-login",
+    request_body = LoginWidgetData,
+    responses(
+        (status = 200, description = "Logged in", body = TelegramLoginResponse),
+        (status = 401, description = "Invalid or expired widget data")
+    ),
+    tag = "user"
+)]
+async fn telegram_login(
+    State(state): State<AppState>,
+    Json(data): Json<LoginWidgetData>,
+) -> Result<Json<TelegramLoginResponse>, ApiError> {
+    let user = validate_login_widget(&data, &state.telegram_bot_token)?;
+    state
+        .calendar_service
+        .get_or_create_user(user.id, user.username.as_deref())
+        .await?;
+
+    let expires_at = DateTime::from_timestamp(data.auth_date + INIT_DATA_MAX_AGE_SECS, 0)
+        .ok_or_else(|| ApiError::BadRequest("Invalid auth_date".into()))?;
+    let token = issue_init_data(&user, data.auth_date, &state.telegram_bot_token)?;
+
+    tracing::info!(telegram_id = user.id, "Telegram widget login");
+    Ok(Json(TelegramLoginResponse { token, expires_at }))
+}
+
+/// Login routes (no authentication; the widget signature is the credential)
+pub fn routes() -> Router<AppState> {
+    Router::new().route("/api/auth/telegram-login", post(telegram_login))
+}
@@ -0,0 +1,123 @@
+//! Birthdays calendar endpoints
+//!
+//! The calendar is derived from the `BDAY` of the user's CardDAV contacts.
+//! Once turned on it is a read-only subscription, shown next to the user's
+//! events and at `/caldav/{user}/subscriptions/{id}/`.
+
+use axum::{
+    Extension, Json, Router,
+    extract::{FromRef, State},
+    http::StatusCode,
+    routing::get,
+};
+use serde::Serialize;
+use televent_application::SubscriptionService;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::{error::ApiError, middleware::telegram_auth::AuthenticatedTelegramUser};
+
+/// Whether the user has the birthdays calendar on
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BirthdaysCalendarResponse {
+    pub enabled: bool,
+    /// The user's subscription to the calendar, when on
+    pub subscription_id: Option<Uuid>,
+}
+
+impl BirthdaysCalendarResponse {
+    fn new(subscription_id: Option<Uuid>) -> Self {
+        Self {
+            enabled: subscription_id.is_some(),
+            subscription_id,
+        }
+    }
+}
+
+/// Get the birthdays calendar
+#[utoipa::path(
+    get,
+    path = "/birthdays",
+    responses(
+        (status = 200, description = "Whether the calendar is on", body = BirthdaysCalendarResponse),
+        (status = 401, description = "Unauthorized")
+    ),
+    tag = "birthdays",
+    security(
+        ("telegram_auth" = [])
+    )
+)]
+async fn get_birthdays_calendar(
+    State(subscriptions): State<SubscriptionService>,
+    Extension(auth_user): Extension<AuthenticatedTelegramUser>,
+) -> Result<Json<BirthdaysCalendarResponse>, ApiError> {
+    let subscription = subscriptions.birthdays_subscription(auth_user.id).await?;
+    Ok(Json(BirthdaysCalendarResponse::new(
+        subscription.map(|subscription| subscription.id),
+    )))
+}
+
+/// Turn the birthdays calendar on
+///
+/// Counts towards the subscription limit. The calendar is filled at once
+/// and read from the contacts again every morning.
+#[utoipa::path(
+    put,
+    path = "/birthdays",
+    responses(
+        (status = 200, description = "Calendar on", body = BirthdaysCalendarResponse),
+        (status = 400, description = "Subscription limit reached"),
+        (status = 401, description = "Unauthorized")
+    ),
+    tag = "birthdays",
+    security(
+        ("telegram_auth" = [])
+    )
+)]
+async fn enable_birthdays_calendar(
+    State(subscriptions): State<SubscriptionService>,
+    Extension(auth_user): Extension<AuthenticatedTelegramUser>,
+) -> Result<Json<BirthdaysCalendarResponse>, ApiError> {
+    let subscription = subscriptions
+        .enable_birthdays(auth_user.id, auth_user.username.clone())
+        .await?;
+    Ok(Json(BirthdaysCalendarResponse::new(Some(subscription.id))))
+}
+
+/// Turn the birthdays calendar off
+#[utoipa::path(
+    delete,
+    path = "/birthdays",
+    responses(
+        (status = 204, description = "Calendar off"),
+        (status = 404, description = "Calendar was not on"),
+        (status = 401, description = "Unauthorized")
+    ),
+    tag = "birthdays",
+    security(
+        ("telegram_auth" = [])
+    )
+)]
+async fn disable_birthdays_calendar(
+    State(subscriptions): State<SubscriptionService>,
+    Extension(auth_user): Extension<AuthenticatedTelegramUser>,
+) -> Result<StatusCode, ApiError> {
+    if subscriptions.disable_birthdays(auth_user.id).await? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(ApiError::NotFound("birthdays calendar".to_string()))
+    }
+}
+
+pub fn routes<S>() -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+    SubscriptionService: FromRef<S>,
+{
+    Router::new().route(
+        "/birthdays",
+        get(get_birthdays_calendar)
+            .put(enable_birthdays_calendar)
+            .delete(disable_birthdays_calendar),
+    )
+}
@@ -0,0 +1,200 @@
+//! Booking link endpoints
+//!
+//! A booking link shares a daily window of the user's time at `/b/{slug}`,
+//! where anyone can pick a free slot. Bookings land on the calendar as
+//! tentative events for the user to confirm.
+
+use axum::{
+    Extension, Json, Router,
+    extract::{FromRef, Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::{delete, get},
+};
+use chrono::Duration;
+use serde::{Deserialize, Serialize};
+use televent_application::{
+    BookingLinkView, CreateBookingLinkCommand, DayHours, EXTERNAL_INVITES_FLAG, EventService,
+    FeatureFlagService,
+};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::{
+    error::ApiError, middleware::base_path::BasePath,
+    middleware::telegram_auth::AuthenticatedTelegramUser,
+};
+
+/// Request to share part of the user's day
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateBookingLinkRequest {
+    /// Shown on the page and in the summary of booked events
+    #[schema(example = "Intro call")]
+    pub title: String,
+    /// Slot length, 5 to 480 minutes
+    #[schema(example = 30)]
+    pub duration_minutes: i64,
+    /// Bookable part of each day in the user's timezone
+    #[schema(example = "09:00-17:00")]
+    pub working_hours: String,
+    /// How many days ahead visitors can book, 1 to 31
+    #[schema(example = 14)]
+    pub within_days: i64,
+}
+
+/// Booking link
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BookingLinkResponse {
+    pub id: Uuid,
+    /// Unguessable page identifier
+    #[schema(example = "k3J9xQ2mPz7LwA1c")]
+    pub slug: String,
+    /// Server-relative page URL to share, including any proxy prefix
+    #[schema(example = "/b/k3J9xQ2mPz7LwA1c")]
+    pub path: String,
+    pub title: String,
+    pub duration_minutes: i64,
+    #[schema(example = "09:00-17:00")]
+    pub working_hours: String,
+    pub within_days: i64,
+    pub created_at: String,
+}
+
+impl BookingLinkResponse {
+    fn new(base: &BasePath, link: BookingLinkView) -> Self {
+        Self {
+            id: link.id,
+            path: base.join(&format!("/b/{}", link.slug)),
+            slug: link.slug,
+            title: link.title,
+            duration_minutes: link.duration.num_minutes(),
+            working_hours: link.hours.to_string(),
+            within_days: link.within_days,
+            created_at: link.created_at.to_rfc3339(),
+        }
+    }
+}
+
+/// List the user's booking links
+#[utoipa::path(
+    get,
+    path = "/booking-links",
+    responses(
+        (status = 200, description = "Booking links, newest first", body = Vec<BookingLinkResponse>),
+        (status = 401, description = "Unauthorized")
+    ),
+    tag = "booking",
+    security(
+        ("telegram_auth" = [])
+    )
+)]
+async fn list_booking_links(
+    State(events): State<EventService>,
+    Extension(auth_user): Extension<AuthenticatedTelegramUser>,
+    base: BasePath,
+) -> Result<Json<Vec<BookingLinkResponse>>, ApiError> {
+    let links = events.list_booking_links(auth_user.id).await?;
+
+    Ok(Json(
+        links
+            .into_iter()
+            .map(|link| BookingLinkResponse::new(&base, link))
+            .collect(),
+    ))
+}
+
+/// Create a booking link
+///
+/// Visitors of the page see the free slots inside the working hours, from
+/// the user's own and subscribed calendars, and book them without an account.
+#[utoipa::path(
+    post,
+    path = "/booking-links",
+    request_body = CreateBookingLinkRequest,
+    responses(
+        (status = 201, description = "Booking link created", body = BookingLinkResponse),
+        (status = 400, description = "Invalid title, duration, hours or window, or too many links"),
+        (status = 403, description = "External invites are not enabled for the user"),
+        (status = 401, description = "Unauthorized")
+    ),
+    tag = "booking",
+    security(
+        ("telegram_auth" = [])
+    )
+)]
+async fn create_booking_link(
+    State(events): State<EventService>,
+    State(flags): State<FeatureFlagService>,
+    Extension(auth_user): Extension<AuthenticatedTelegramUser>,
+    base: BasePath,
+    Json(request): Json<CreateBookingLinkRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    // Visitors who book become external guests
+    if !flags
+        .is_enabled(EXTERNAL_INVITES_FLAG, auth_user.id)
+        .await?
+    {
+        return Err(ApiError::Forbidden);
+    }
+
+    let link = events
+        .create_booking_link(CreateBookingLinkCommand {
+            user_id: auth_user.id,
+            title: request.title,
+            duration: Duration::try_minutes(request.duration_minutes).ok_or_else(|| {
+                ApiError::BadRequest("duration_minutes is out of range".to_string())
+            })?,
+            hours: DayHours::parse(&request.working_hours)?,
+            within_days: request.within_days,
+        })
+        .await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(BookingLinkResponse::new(&base, link)),
+    ))
+}
+
+/// Delete a booking link
+///
+/// The page goes away; events already booked through it stay.
+#[utoipa::path(
+    delete,
+    path = "/booking-links/{link_id}",
+    responses(
+        (status = 204, description = "Booking link deleted"),
+        (status = 404, description = "Booking link not found"),
+        (status = 401, description = "Unauthorized")
+    ),
+    params(
+        ("link_id" = Uuid, Path, description = "Booking link ID")
+    ),
+    tag = "booking",
+    security(
+        ("telegram_auth" = [])
+    )
+)]
+async fn delete_booking_link(
+    State(events): State<EventService>,
+    Extension(auth_user): Extension<AuthenticatedTelegramUser>,
+    Path(link_id): Path<Uuid>,
+) -> Result<impl IntoResponse, ApiError> {
+    events.delete_booking_link(auth_user.id, link_id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Booking link routes
+pub fn routes<S>() -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+    EventService: FromRef<S>,
+    FeatureFlagService: FromRef<S>,
+{
+    Router::new()
+        .route(
+            "/booking-links",
+            get(list_booking_links).post(create_booking_link),
+        )
+        .route("/booking-links/{link_id}", delete(delete_booking_link))
+}
@@ -12,9 +12,18 @@ use axum::{
     response::{IntoResponse, Response},
     routing::any,
 };
-use televent_application::{CalDavUser, CalendarService, UserId};
+use chrono::{DateTime, Utc};
+use televent_application::{
+    CalDavCalendarState, CalDavEventMetadata, CalDavEventResource, CalDavUser, CalendarService,
+    EventService, RenderedEventIcal, ResourceView, SubscriptionService, SubscriptionView, UserId,
+    count_queries, parse_calendar_sync_token,
+};
+use uuid::Uuid;
 
 use crate::error::ApiError;
+use crate::middleware::base_path::BasePath;
+use crate::middleware::caldav_auth::PresentedSyncToken;
+use crate::routes::caldav_xml::{RESOURCES_PATH, SUBSCRIPTIONS_PATH};
 use crate::routes::{caldav_ical, caldav_xml};
 
 /// CalDAV OPTIONS handler
@@ -45,6 +54,8 @@ async fn caldav_options() -> Response {
 /// - Depth: 1 - Calendar metadata + event list (hrefs)
 async fn caldav_propfind(
     State(calendar): State<CalendarService>,
+    State(subscriptions): State<SubscriptionService>,
+    root: &str,
     Path(user_identifier): Path<String>,
     auth_user_id: UserId,
     headers: HeaderMap,
@@ -69,20 +80,25 @@ async fn caldav_propfind(
         depth
     );
 
-    // Get events if depth is 1
-    let events = if depth == "1" {
-        calendar
-            .list_caldav_event_metadata(user.id, None, None)
-            .await?
+    // Get events and subscribed calendars if depth is 1
+    let (events, subscribed) = if depth == "1" {
+        (
+            calendar
+                .list_caldav_event_metadata(user.id, None, None)
+                .await?,
+            subscriptions.list_subscriptions(user.id).await?,
+        )
     } else {
-        Vec::new()
+        (Vec::new(), Vec::new())
     };
 
     // Generate XML response
     let response_xml = caldav_xml::generate_propfind_multistatus(
+        root,
         &user_identifier,
         &user.calendar,
         &events,
+        &subscribed,
         depth,
     )?;
 
@@ -135,9 +151,12 @@ const MAX_MULTIGET_HREFS: usize = 200;
 
 /// CalDAV PUT handler
 ///
-/// Creates or updates an event from iCalendar data
+/// Creates or updates an event from iCalendar data. An edit to an out-of-date
+/// copy, one whose `SEQUENCE` is behind the stored event, gets 409 with the
+/// stored event as the body so the client can merge and retry.
 async fn caldav_put_event(
     State(calendar_service): State<CalendarService>,
+    State(event_service): State<EventService>,
     Path((user_identifier, event_uid)): Path<(String, String)>,
     auth_user_id: UserId,
     headers: HeaderMap,
@@ -170,9 +189,25 @@ async fn caldav_put_event(
         })
         .transpose()?;
 
-    let result = calendar_service
+    let result = event_service
         .put_event_by_uid(parsed_event.into_put_command(user.id, expected_etag))
         .await?;
+    if let Some(conflict) = result.conflict {
+        let rendered = calendar_service
+            .render_event_ical_by_uid(user.id, &event_uid)
+            .await?
+            .ok_or_else(|| ApiError::NotFound(format!("Event not found: {}", event_uid)))?;
+        return Ok((
+            StatusCode::CONFLICT,
+            [
+                (header::CONTENT_TYPE, "text/calendar; charset=utf-8"),
+                (header::ETAG, &format!("\"{}\"", rendered.etag)),
+            ],
+            Extension(conflict),
+            rendered.body,
+        )
+            .into_response());
+    }
     let status_code = if result.created {
         StatusCode::CREATED
     } else {
@@ -188,6 +223,7 @@ async fn caldav_put_event(
 /// Handles calendar-query and sync-collection reports (RFC 4791, RFC 6578)
 async fn caldav_report(
     State(calendar): State<CalendarService>,
+    root: &str,
     Path(user_identifier): Path<String>,
     auth_user_id: UserId,
     body: Body,
@@ -235,7 +271,7 @@ async fn caldav_report(
             );
 
             let response_xml =
-                caldav_xml::generate_calendar_query_response(&user_identifier, &events)?;
+                caldav_xml::generate_calendar_query_response(root, &user_identifier, &events)?;
 
             tracing::debug!(
                 "CalendarQuery response XML (first 500 chars): {}",
@@ -253,18 +289,29 @@ async fn caldav_report(
             let resource_changes = calendar
                 .list_caldav_sync_changes(user.id, sync_token.as_deref())
                 .await?;
+            // Deletions since the token were pruned, so the client starts over
+            if resource_changes.token_expired {
+                return Ok((
+                    StatusCode::FORBIDDEN,
+                    [(header::CONTENT_TYPE, "application/xml; charset=utf-8")],
+                    sync_token.map(PresentedSyncToken).map(Extension),
+                    caldav_xml::INVALID_SYNC_TOKEN_ERROR,
+                )
+                    .into_response());
+            }
 
             tracing::info!(
                 "SyncCollection: sync_token={:?}, parsed={}, returning {} events, user sync_token={}",
                 sync_token,
                 resource_changes.last_sync_token,
                 resource_changes.events.len(),
-                user.calendar.sync_token
+                resource_changes.calendar.sync_token
             );
 
             let response_xml = caldav_xml::generate_sync_collection_response(
+                root,
                 &user_identifier,
-                &user.calendar,
+                &resource_changes.calendar,
                 &resource_changes.events,
                 &resource_changes.tombstones,
             )?;
@@ -280,6 +327,7 @@ async fn caldav_report(
             Ok((
                 StatusCode::MULTI_STATUS,
                 [(header::CONTENT_TYPE, "application/xml; charset=utf-8")],
+                sync_token.map(PresentedSyncToken).map(Extension),
                 response_xml,
             )
                 .into_response())
@@ -305,7 +353,7 @@ async fn caldav_report(
                 .await?;
 
             let response_xml =
-                caldav_xml::generate_calendar_multiget_response(&user_identifier, &events)?;
+                caldav_xml::generate_calendar_multiget_response(root, &user_identifier, &events)?;
 
             tracing::info!("CalendarMultiget: returning {} events", events.len());
 
@@ -324,6 +372,7 @@ async fn caldav_report(
 /// Deletes an event
 async fn caldav_delete_event(
     State(calendar): State<CalendarService>,
+    State(event_service): State<EventService>,
     Path((user_identifier, event_uid)): Path<(String, String)>,
     auth_user_id: UserId,
     headers: HeaderMap,
@@ -346,7 +395,7 @@ async fn caldav_delete_event(
         })
         .transpose()?;
 
-    calendar
+    event_service
         .delete_event_by_uid(user.id, &event_uid, expected_etag)
         .await?;
 
@@ -358,7 +407,7 @@ async fn caldav_delete_event(
 /// The identifier can be:
 /// - A numeric Telegram ID (e.g., "123456789")
 /// - A Telegram username (e.g., "myusername")
-async fn resolve_user(
+pub(crate) async fn resolve_user(
     calendar: &CalendarService,
     identifier: &str,
 ) -> Result<CalDavUser, ApiError> {
@@ -405,11 +454,346 @@ fn extract_uids_from_hrefs(hrefs: &[String]) -> Vec<Cow<'_, str>> {
     uids
 }
 
+/// OPTIONS for a read-only calendar
+fn read_only_options() -> Response {
+    (
+        StatusCode::OK,
+        [
+            (HeaderName::from_static("dav"), "1, calendar-access"),
+            (header::ALLOW, "OPTIONS, PROPFIND, REPORT, GET"),
+            (HeaderName::from_static("cal-accessible"), "calendar"),
+        ],
+    )
+        .into_response()
+}
+
+/// A read-only calendar below the user's own
+enum ReadOnlyCalendar {
+    /// Mirror of a remote feed the user subscribed to
+    Subscription(SubscriptionView),
+    /// Bookings of a meeting room or other resource, as busy blocks
+    Resource(ResourceView),
+}
+
+impl ReadOnlyCalendar {
+    fn path(&self, user_identifier: &str) -> String {
+        match self {
+            Self::Subscription(subscription) => {
+                format!("{user_identifier}/{SUBSCRIPTIONS_PATH}{}", subscription.id)
+            }
+            Self::Resource(resource) => {
+                format!("{user_identifier}/{RESOURCES_PATH}{}", resource.id)
+            }
+        }
+    }
+
+    fn name(&self) -> &str {
+        match self {
+            Self::Subscription(subscription) => &subscription.name,
+            Self::Resource(resource) => &resource.name,
+        }
+    }
+
+    const fn state(&self) -> &CalDavCalendarState {
+        match self {
+            Self::Subscription(subscription) => &subscription.calendar,
+            Self::Resource(resource) => &resource.calendar,
+        }
+    }
+}
+
+/// Where the data of read-only calendars comes from
+struct ReadOnlySources {
+    calendar: CalendarService,
+    subscriptions: SubscriptionService,
+}
+
+impl ReadOnlySources {
+    async fn render_event_ical_by_uid(
+        &self,
+        collection: &ReadOnlyCalendar,
+        uid: &str,
+    ) -> Result<Option<RenderedEventIcal>, ApiError> {
+        Ok(match collection {
+            ReadOnlyCalendar::Subscription(subscription) => {
+                self.subscriptions
+                    .render_event_ical_by_uid(subscription.id, uid)
+                    .await?
+            }
+            ReadOnlyCalendar::Resource(resource) => {
+                self.calendar
+                    .render_resource_event_ical_by_uid(resource.id, uid)
+                    .await?
+            }
+        })
+    }
+
+    async fn list_event_metadata(
+        &self,
+        collection: &ReadOnlyCalendar,
+    ) -> Result<Vec<CalDavEventMetadata>, ApiError> {
+        Ok(match collection {
+            ReadOnlyCalendar::Subscription(subscription) => {
+                self.subscriptions
+                    .list_caldav_event_metadata(subscription.id)
+                    .await?
+            }
+            ReadOnlyCalendar::Resource(resource) => {
+                self.calendar
+                    .list_resource_caldav_event_metadata(resource.id)
+                    .await?
+            }
+        })
+    }
+
+    async fn list_event_resources(
+        &self,
+        collection: &ReadOnlyCalendar,
+        start: Option<DateTime<Utc>>,
+        end: Option<DateTime<Utc>>,
+    ) -> Result<Vec<CalDavEventResource>, ApiError> {
+        Ok(match collection {
+            ReadOnlyCalendar::Subscription(subscription) => {
+                self.subscriptions
+                    .list_caldav_event_resources(subscription.id, start, end)
+                    .await?
+            }
+            ReadOnlyCalendar::Resource(resource) => {
+                self.calendar
+                    .list_resource_caldav_event_resources(resource.id, start, end)
+                    .await?
+            }
+        })
+    }
+
+    async fn list_event_resources_by_uids(
+        &self,
+        collection: &ReadOnlyCalendar,
+        uids: &[&str],
+    ) -> Result<Vec<CalDavEventResource>, ApiError> {
+        Ok(match collection {
+            ReadOnlyCalendar::Subscription(subscription) => {
+                self.subscriptions
+                    .list_caldav_event_resources_by_uids(subscription.id, uids)
+                    .await?
+            }
+            ReadOnlyCalendar::Resource(resource) => {
+                self.calendar
+                    .list_resource_caldav_event_resources_by_uids(resource.id, uids)
+                    .await?
+            }
+        })
+    }
+}
+
+/// Read-only calendar addressed by an event resource path, if any.
+///
+/// Only `subscriptions/{id}/…` paths naming one of the user's subscriptions
+/// and `resources/{id}/…` paths naming a resource match; anything else stays
+/// an ordinary resource of the user's calendar, so own events whose names
+/// happen to start with either prefix keep working.
+async fn find_read_only_calendar(
+    sources: &ReadOnlySources,
+    auth_user_id: UserId,
+    user_identifier: &str,
+    resource_path: &str,
+) -> Result<Option<(ReadOnlyCalendar, String)>, ApiError> {
+    let resource_path = resource_path.trim_start_matches('/');
+    let (is_subscription, collection_path) =
+        if let Some(path) = resource_path.strip_prefix(SUBSCRIPTIONS_PATH) {
+            (true, path)
+        } else if let Some(path) = resource_path.strip_prefix(RESOURCES_PATH) {
+            (false, path)
+        } else {
+            return Ok(None);
+        };
+    let (raw_id, resource) = collection_path
+        .split_once('/')
+        .unwrap_or((collection_path, ""));
+    let Ok(collection_id) = Uuid::parse_str(raw_id) else {
+        return Ok(None);
+    };
+
+    let user = resolve_user(&sources.calendar, user_identifier).await?;
+    if user.id != auth_user_id {
+        return Err(ApiError::Forbidden);
+    }
+    let collection = if is_subscription {
+        sources
+            .subscriptions
+            .get_subscription(user.id, collection_id)
+            .await?
+            .map(ReadOnlyCalendar::Subscription)
+    } else {
+        sources
+            .calendar
+            .get_resource(collection_id)
+            .await?
+            .map(ReadOnlyCalendar::Resource)
+    };
+    Ok(collection.map(|collection| (collection, resource.to_string())))
+}
+
+/// Read-only calendar handler
+///
+/// Serves `/caldav/{user}/subscriptions/{id}/` and
+/// `/caldav/{user}/resources/{id}/` and the events below them. Writes are
+/// refused; a mirror only changes when the worker refreshes the remote feed,
+/// a resource when events book it.
+#[allow(clippy::too_many_arguments)]
+async fn read_only_handler(
+    sources: ReadOnlySources,
+    read_only: ReadOnlyCalendar,
+    root: &str,
+    user_identifier: String,
+    resource: &str,
+    headers: HeaderMap,
+    method: Method,
+    body: Body,
+) -> Result<Response, ApiError> {
+    let collection = read_only.path(&user_identifier);
+
+    if !resource.is_empty() {
+        let event_uid = resource.trim_end_matches(".ics");
+        return match method {
+            Method::GET => {
+                let rendered = sources
+                    .render_event_ical_by_uid(&read_only, event_uid)
+                    .await?
+                    .ok_or_else(|| ApiError::NotFound(format!("Event not found: {event_uid}")))?;
+
+                Ok((
+                    StatusCode::OK,
+                    [
+                        (header::CONTENT_TYPE, "text/calendar; charset=utf-8"),
+                        (header::ETAG, &format!("\"{}\"", rendered.etag)),
+                    ],
+                    rendered.body,
+                )
+                    .into_response())
+            }
+            // Subscribed calendars and resources are read-only
+            Method::PUT | Method::DELETE => Err(ApiError::Forbidden),
+            _ => Err(ApiError::BadRequest(format!(
+                "Method {} not supported for event resource",
+                method
+            ))),
+        };
+    }
+
+    match method.as_str() {
+        "OPTIONS" => Ok(read_only_options()),
+        "PROPFIND" => {
+            let depth = headers
+                .get("Depth")
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or("0");
+            let events = if depth == "1" {
+                sources.list_event_metadata(&read_only).await?
+            } else {
+                Vec::new()
+            };
+
+            let response_xml = caldav_xml::generate_read_only_propfind_multistatus(
+                root,
+                &user_identifier,
+                &collection,
+                read_only.name(),
+                read_only.state(),
+                &events,
+                depth,
+            )?;
+
+            Ok((
+                StatusCode::MULTI_STATUS,
+                [(header::CONTENT_TYPE, "application/xml; charset=utf-8")],
+                response_xml,
+            )
+                .into_response())
+        }
+        "REPORT" => {
+            let body_bytes = axum::body::to_bytes(body, MAX_CALDAV_BODY_SIZE)
+                .await
+                .map_err(|e| ApiError::BadRequest(format!("Failed to read body: {}", e)))?;
+            let xml_body = String::from_utf8(body_bytes.to_vec())
+                .map_err(|e| ApiError::BadRequest(format!("Invalid UTF-8: {}", e)))?;
+
+            let mut presented_token = None;
+            let response_xml = match caldav_xml::parse_report_request(&xml_body)? {
+                caldav_xml::ReportType::CalendarQuery { start, end } => {
+                    let events = sources.list_event_resources(&read_only, start, end).await?;
+                    caldav_xml::generate_calendar_query_response(root, &collection, &events)?
+                }
+                caldav_xml::ReportType::SyncCollection { sync_token } => {
+                    // Read-only calendars keep no tombstones: a client on the
+                    // current token gets no changes, a fresh client gets
+                    // everything and any older token is refused so the client
+                    // starts over.
+                    let client_token = parse_calendar_sync_token(sync_token.as_deref());
+                    presented_token = sync_token.map(PresentedSyncToken);
+                    let events = if client_token == read_only.state().sync_token {
+                        Vec::new()
+                    } else if client_token == 0 {
+                        sources.list_event_resources(&read_only, None, None).await?
+                    } else {
+                        return Ok((
+                            StatusCode::FORBIDDEN,
+                            [(header::CONTENT_TYPE, "application/xml; charset=utf-8")],
+                            presented_token.map(Extension),
+                            caldav_xml::INVALID_SYNC_TOKEN_ERROR,
+                        )
+                            .into_response());
+                    };
+
+                    caldav_xml::generate_sync_collection_response(
+                        root,
+                        &collection,
+                        read_only.state(),
+                        &events,
+                        &[],
+                    )?
+                }
+                caldav_xml::ReportType::CalendarMultiget { hrefs } => {
+                    if hrefs.len() > MAX_MULTIGET_HREFS {
+                        return Err(ApiError::BadRequest(format!(
+                            "Too many hrefs requested (max {})",
+                            MAX_MULTIGET_HREFS
+                        )));
+                    }
+
+                    let requested_uids = extract_uids_from_hrefs(&hrefs);
+                    let uid_strs: Vec<&str> = requested_uids.iter().map(|s| s.as_ref()).collect();
+                    let events = sources
+                        .list_event_resources_by_uids(&read_only, &uid_strs)
+                        .await?;
+                    caldav_xml::generate_calendar_multiget_response(root, &collection, &events)?
+                }
+            };
+
+            Ok((
+                StatusCode::MULTI_STATUS,
+                [(header::CONTENT_TYPE, "application/xml; charset=utf-8")],
+                presented_token.map(Extension),
+                response_xml,
+            )
+                .into_response())
+        }
+        // Subscribed calendars and resources are read-only
+        "PUT" | "DELETE" | "MKCALENDAR" | "PROPPATCH" => Err(ApiError::Forbidden),
+        _ => Err(ApiError::BadRequest(format!(
+            "Method {} not supported for calendar collection",
+            method
+        ))),
+    }
+}
+
 /// CalDAV routes
 pub fn routes<S>() -> Router<S>
 where
     S: Clone + Send + Sync + 'static,
     CalendarService: FromRef<S>,
+    EventService: FromRef<S>,
+    SubscriptionService: FromRef<S>,
 {
     Router::new()
         // Calendar collection endpoints
@@ -419,20 +803,27 @@ where
 }
 
 /// Main CalDAV collection handler
+#[allow(clippy::too_many_arguments)]
 async fn caldav_handler(
     State(calendar): State<CalendarService>,
+    State(subscriptions): State<SubscriptionService>,
     Extension(auth_user_id): Extension<UserId>,
+    base: BasePath,
     Path(user_identifier): Path<String>,
     headers: HeaderMap,
     method: Method,
     body: Body,
 ) -> Result<Response, ApiError> {
+    let root = base.join("/caldav");
+
     // Handle WebDAV methods
     match method.as_str() {
         "OPTIONS" => Ok(caldav_options().await),
         "PROPFIND" => {
             caldav_propfind(
                 State(calendar),
+                State(subscriptions),
+                &root,
                 Path(user_identifier),
                 auth_user_id,
                 headers,
@@ -440,7 +831,18 @@ async fn caldav_handler(
             )
             .await
         }
-        "REPORT" => caldav_report(State(calendar), Path(user_identifier), auth_user_id, body).await,
+        "REPORT" => {
+            let (response, queries) = count_queries(caldav_report(
+                State(calendar),
+                &root,
+                Path(user_identifier),
+                auth_user_id,
+                body,
+            ))
+            .await;
+            tracing::debug!(queries, "REPORT finished");
+            response
+        }
         _ => Err(ApiError::BadRequest(format!(
             "Method {} not supported for calendar collection",
             method
@@ -449,14 +851,38 @@ async fn caldav_handler(
 }
 
 /// Event resource handler
+#[allow(clippy::too_many_arguments)]
 async fn event_handler(
     State(calendar): State<CalendarService>,
+    State(event_service): State<EventService>,
+    State(subscriptions): State<SubscriptionService>,
     Extension(auth_user_id): Extension<UserId>,
+    base: BasePath,
     Path((user_identifier, event_uid_raw)): Path<(String, String)>,
     headers: HeaderMap,
     method: Method,
     body: Body,
 ) -> Result<Response, ApiError> {
+    let sources = ReadOnlySources {
+        calendar: calendar.clone(),
+        subscriptions,
+    };
+    if let Some((read_only, resource)) =
+        find_read_only_calendar(&sources, auth_user_id, &user_identifier, &event_uid_raw).await?
+    {
+        return read_only_handler(
+            sources,
+            read_only,
+            &base.join("/caldav"),
+            user_identifier,
+            &resource,
+            headers,
+            method,
+            body,
+        )
+        .await;
+    }
+
     // Strip leading slash and .ics extension from wildcard capture
     let event_uid = event_uid_raw
         .trim_start_matches('/')
@@ -475,6 +901,7 @@ async fn event_handler(
         Method::PUT => {
             caldav_put_event(
                 State(calendar),
+                State(event_service),
                 Path((user_identifier, event_uid)),
                 auth_user_id,
                 headers,
@@ -485,6 +912,7 @@ async fn event_handler(
         Method::DELETE => {
             caldav_delete_event(
                 State(calendar),
+                State(event_service),
                 Path((user_identifier, event_uid)),
                 auth_user_id,
                 headers,
@@ -7,12 +7,12 @@
 use std::collections::HashMap;
 
 use ical::parser::ical::component::IcalEvent;
-use televent_application::{AttendeeCommand, PutEventCommand, UserId, ical as app_ical};
+use televent_application::{
+    AttendeeCommand, PutEventCommand, UserId, ical as app_ical, validate_event_fields,
+};
 use televent_domain::{
-    AttendeeRole, EventStatus, EventTiming, MAX_DESCRIPTION_LENGTH, MAX_LOCATION_LENGTH,
-    MAX_RRULE_LENGTH, MAX_SUMMARY_LENGTH, MAX_UID_LENGTH, ParticipationStatus, Timezone,
-    parse_internal_email_telegram_id, validate_length, validate_no_control_chars, validate_rrule,
-    validate_safe_multiline_text,
+    AttendeeRole, EventStatus, EventTiming, EventVisibility, ParticipationStatus, Timezone,
+    parse_internal_email_telegram_id,
 };
 
 use crate::error::ApiError;
@@ -26,6 +26,10 @@ pub struct ParsedCalDavEvent {
     timing: EventTiming,
     status: EventStatus,
     rrule: Option<String>,
+    /// `None` when the event has no `CLASS`
+    visibility: Option<EventVisibility>,
+    /// `None` when the event has no valid `SEQUENCE`
+    sequence: Option<i32>,
     attendees: Vec<AttendeeCommand>,
 }
 
@@ -44,7 +48,9 @@ impl ParsedCalDavEvent {
             timing: self.timing,
             status: self.status,
             rrule: self.rrule,
+            visibility: self.visibility,
             expected_etag,
+            sequence: self.sequence,
             attendees: self.attendees,
         }
     }
@@ -55,7 +61,7 @@ pub fn parse_put_event(
     expected_uid: &str,
     organizer_user_id: UserId,
 ) -> Result<ParsedCalDavEvent, ApiError> {
-    let parsed_calendar = ical::IcalParser::new(std::io::Cursor::new(ical_str))
+    let parsed_calendar = app_ical::calendar_parser(ical_str)
         .next()
         .ok_or_else(|| ApiError::BadRequest("Empty calendar".to_string()))?
         .map_err(|err| ApiError::BadRequest(format!("Failed to parse calendar: {err}")))?;
@@ -65,10 +71,21 @@ pub fn parse_put_event(
         .first()
         .ok_or_else(|| ApiError::BadRequest("No event found in calendar".to_string()))?;
 
-    let (uid, summary, description, location, start, end, is_all_day, rrule, status, timezone) =
-        app_ical::ical_to_event_data(event)?;
+    let (
+        uid,
+        summary,
+        description,
+        location,
+        start,
+        end,
+        is_all_day,
+        is_floating,
+        rrule,
+        status,
+        timezone,
+    ) = app_ical::ical_to_event_data(event)?;
 
-    validate_event_fields(&uid, &summary, &description, &location, &rrule)?;
+    validate_event_fields(Some(&uid), rrule.as_deref())?;
 
     if uid != expected_uid {
         return Err(ApiError::BadRequest(format!(
@@ -81,6 +98,12 @@ pub fn parse_put_event(
             start_date: start.date_naive(),
             end_date: end.date_naive(),
         }
+    } else if is_floating {
+        // No Z and no TZID: the same wall-clock time wherever the user is
+        EventTiming::Floating {
+            start: start.naive_utc(),
+            end: end.naive_utc(),
+        }
     } else {
         EventTiming::Timed {
             start,
@@ -97,43 +120,12 @@ pub fn parse_put_event(
         timing,
         status,
         rrule,
+        visibility: app_ical::ical_visibility(event),
+        sequence: app_ical::ical_sequence(event),
         attendees: extract_attendees(event, organizer_user_id),
     })
 }
 
-fn validate_event_fields(
-    uid: &str,
-    summary: &str,
-    description: &Option<String>,
-    location: &Option<String>,
-    rrule: &Option<String>,
-) -> Result<(), ApiError> {
-    validate_length("UID", uid, MAX_UID_LENGTH).map_err(ApiError::BadRequest)?;
-    validate_no_control_chars("UID", uid).map_err(ApiError::BadRequest)?;
-
-    validate_length("Summary", summary, MAX_SUMMARY_LENGTH).map_err(ApiError::BadRequest)?;
-    validate_no_control_chars("Summary", summary).map_err(ApiError::BadRequest)?;
-
-    if let Some(description) = description {
-        validate_length("Description", description, MAX_DESCRIPTION_LENGTH)
-            .map_err(ApiError::BadRequest)?;
-        validate_safe_multiline_text("Description", description).map_err(ApiError::BadRequest)?;
-    }
-
-    if let Some(location) = location {
-        validate_length("Location", location, MAX_LOCATION_LENGTH).map_err(ApiError::BadRequest)?;
-        validate_no_control_chars("Location", location).map_err(ApiError::BadRequest)?;
-    }
-
-    if let Some(rrule) = rrule {
-        validate_length("RRule", rrule, MAX_RRULE_LENGTH).map_err(ApiError::BadRequest)?;
-        validate_no_control_chars("RRule", rrule).map_err(ApiError::BadRequest)?;
-        validate_rrule(rrule).map_err(|err| ApiError::BadRequest(err.to_string()))?;
-    }
-
-    Ok(())
-}
-
 fn extract_attendees(event: &IcalEvent, organizer_user_id: UserId) -> Vec<AttendeeCommand> {
     let mut attendees = HashMap::new();
 
@@ -176,6 +168,21 @@ fn attendee_participation_status(property: &ical::property::Property) -> Partici
         .unwrap_or(ParticipationStatus::NeedsAction)
 }
 
+/// Parse arbitrary bytes the way a CalDAV `PUT` body is parsed, for the
+/// `api/fuzz` target. The expected UID comes from the body itself so input
+/// gets past the UID check; errors are fine, panics are the bugs.
+pub fn fuzz_put_event(data: &[u8]) {
+    let Ok(ical_str) = std::str::from_utf8(data) else {
+        return;
+    };
+    let uid = ical_str
+        .lines()
+        .find_map(|line| line.strip_prefix("UID:"))
+        .unwrap_or_default()
+        .trim_end();
+    let _ = parse_put_event(ical_str, uid, UserId::new(1001));
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -265,4 +272,234 @@ mod tests {
 
         assert!(parsed.attendees.is_empty());
     }
+
+    #[test]
+    fn resolves_outlook_windows_timezone() {
+        let parsed = parse_put_event(
+            "BEGIN:VCALENDAR\r\n\
+             BEGIN:VEVENT\r\n\
+             UID:event-1\r\n\
+             DTSTART;TZID=W. Europe Standard Time:20240115T100000\r\n\
+             DTEND;TZID=W. Europe Standard Time:20240115T110000\r\n\
+             DTSTAMP:20240110T120000\r\n\
+             X-MICROSOFT-CDO-BUSYSTATUS:BUSY\r\n\
+             SUMMARY:Team Sync\r\n\
+             END:VEVENT\r\n\
+             END:VCALENDAR\r\n",
+            "event-1",
+            UserId::new(1001),
+        )
+        .expect("parse put event");
+
+        let EventTiming::Timed {
+            start, timezone, ..
+        } = parsed.timing
+        else {
+            panic!("expected a timed event");
+        };
+        assert_eq!(start.to_rfc3339(), "2024-01-15T09:00:00+00:00");
+        assert_eq!(timezone.as_str(), "Europe/Berlin");
+    }
+
+    /// Replay the fuzzer's seed corpus, where crashes it finds get committed
+    #[test]
+    fn fuzz_seeds_do_not_panic() {
+        let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/fuzz/seeds/parse_put_event");
+        let mut count = 0;
+        for entry in std::fs::read_dir(dir).expect("read seed corpus") {
+            let path = entry.expect("seed entry").path();
+            let data = std::fs::read(&path).expect("read seed");
+            fuzz_put_event(&data);
+            count += 1;
+        }
+        assert!(count > 0, "no seeds in {dir}");
+    }
+
+    mod round_trip {
+        //! Whatever the server serves over CalDAV must come back unchanged
+        //! when a client PUTs it again.
+
+        use super::super::parse_put_event;
+        use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeDelta, Utc};
+        use proptest::prelude::*;
+        use televent_application::UserId;
+        use televent_application::ical::{IcalAttendeeRender, IcalEventRender, event_to_ical};
+        use televent_domain::{
+            EventStatus, EventTiming, EventVisibility, MAX_DESCRIPTION_LENGTH, ParticipationStatus,
+            Timezone, internal_email_for_telegram_id,
+        };
+
+        const ORGANIZER: i64 = 1001;
+
+        /// Printable Unicode plus tabs and line breaks, including the `\`,
+        /// `;` and `,` iCalendar escapes. CR is left out because the writer
+        /// strips it; the ical crate drops leading colons and trims
+        /// trailing whitespace, which iCalendar has no escape for.
+        fn text(max: usize) -> impl Strategy<Value = String> {
+            let pattern = format!("[^:\\pC][\\PC\\n\\t]{{0,{}}}[^\\s\\pC]", max - 2);
+            prop_oneof![
+                1 => "[^:\\s\\pC]",
+                9 => proptest::string::string_regex(&pattern).expect("valid regex"),
+            ]
+        }
+
+        /// 1900 until 400 days before the end of year 9999, the last one
+        /// iCalendar can write, weighted towards dates that break naive code
+        fn instant() -> impl Strategy<Value = DateTime<Utc>> {
+            let edge = prop_oneof![
+                Just("1900-01-01T00:00:00Z"),
+                Just("1970-01-01T00:00:00Z"),
+                Just("2000-02-29T23:59:59Z"),
+                Just("2024-02-29T12:00:00Z"),
+                Just("2038-01-19T03:14:08Z"),
+                Just("9998-11-01T00:00:00Z"),
+            ]
+            .prop_map(|at| at.parse().expect("valid instant"));
+            let any = (-2_208_988_800_i64..253_365_494_400)
+                .prop_map(|secs| DateTime::from_timestamp(secs, 0).expect("in range"));
+            prop_oneof![1 => edge, 4 => any]
+        }
+
+        fn timing() -> impl Strategy<Value = EventTiming> {
+            let length = (1_i64..400 * 86_400).prop_map(TimeDelta::seconds);
+            prop_oneof![
+                (instant(), length.clone()).prop_map(|(start, length)| EventTiming::Timed {
+                    start,
+                    end: start + length,
+                    timezone: Timezone::utc(),
+                }),
+                (instant(), length.clone()).prop_map(|(start, length)| {
+                    let start: NaiveDateTime = start.naive_utc();
+                    EventTiming::Floating {
+                        start,
+                        end: start + length,
+                    }
+                }),
+                (instant(), 1_i64..400).prop_map(|(start, days)| {
+                    let start_date: NaiveDate = start.date_naive();
+                    EventTiming::AllDay {
+                        start_date,
+                        end_date: start_date + TimeDelta::days(days),
+                    }
+                }),
+            ]
+        }
+
+        fn status() -> impl Strategy<Value = EventStatus> {
+            prop_oneof![
+                Just(EventStatus::Confirmed),
+                Just(EventStatus::Tentative),
+                Just(EventStatus::Cancelled),
+            ]
+        }
+
+        fn visibility() -> impl Strategy<Value = EventVisibility> {
+            prop_oneof![
+                Just(EventVisibility::Public),
+                Just(EventVisibility::BusyOnly),
+                Just(EventVisibility::Private),
+            ]
+        }
+
+        fn rrule() -> impl Strategy<Value = Option<String>> {
+            proptest::option::of(
+                prop_oneof![
+                    Just("FREQ=DAILY"),
+                    Just("FREQ=WEEKLY;BYDAY=MO,WE,FR"),
+                    Just("FREQ=MONTHLY;BYMONTHDAY=31;COUNT=12"),
+                    Just("FREQ=YEARLY;INTERVAL=2;UNTIL=20301231T000000Z"),
+                ]
+                .prop_map(str::to_string),
+            )
+        }
+
+        fn attendees() -> impl Strategy<Value = Vec<IcalAttendeeRender>> {
+            let partstat = prop_oneof![
+                Just(ParticipationStatus::NeedsAction),
+                Just(ParticipationStatus::Accepted),
+                Just(ParticipationStatus::Declined),
+                Just(ParticipationStatus::Tentative),
+            ];
+            proptest::collection::btree_map(2_i64..1_000_000_000_000, partstat, 0..8).prop_map(
+                |attendees| {
+                    attendees
+                        .into_iter()
+                        .map(|(telegram_id, status)| IcalAttendeeRender {
+                            email: internal_email_for_telegram_id(telegram_id),
+                            status,
+                        })
+                        .collect()
+                },
+            )
+        }
+
+        /// UIDs are validated free of control characters and at most 256 bytes
+        fn uid() -> impl Strategy<Value = String> {
+            "[^:\\s\\pC]\\PC{0,60}[^\\s\\pC]"
+        }
+
+        fn event() -> impl Strategy<Value = IcalEventRender> {
+            (
+                uid(),
+                text(256),
+                proptest::option::of(text(MAX_DESCRIPTION_LENGTH / 4)),
+                proptest::option::of(text(300)),
+                timing(),
+                status(),
+                rrule(),
+                visibility(),
+            )
+                .prop_map(
+                    |(uid, summary, description, location, timing, status, rrule, visibility)| {
+                        IcalEventRender {
+                            uid,
+                            summary,
+                            description,
+                            location,
+                            timing,
+                            status,
+                            rrule,
+                            visibility,
+                            reminder_note: None,
+                            sequence: 0,
+                            created_at: Utc::now(),
+                            updated_at: Utc::now(),
+                        }
+                    },
+                )
+        }
+
+        proptest! {
+            #![proptest_config(ProptestConfig::with_cases(256))]
+
+            #[test]
+            fn served_events_parse_back_unchanged(event in event(), attendees in attendees()) {
+                let ical = event_to_ical(&event, &attendees).expect("render");
+                let parsed = parse_put_event(&ical, &event.uid, UserId::new(ORGANIZER))
+                    .expect("parse what we served");
+
+                prop_assert_eq!(&parsed.uid, &event.uid);
+                prop_assert_eq!(&parsed.summary, &event.summary);
+                prop_assert_eq!(&parsed.description, &event.description);
+                prop_assert_eq!(&parsed.location, &event.location);
+                prop_assert_eq!(&parsed.timing, &event.timing);
+                prop_assert_eq!(parsed.status, event.status);
+                prop_assert_eq!(&parsed.rrule, &event.rrule);
+                prop_assert_eq!(parsed.visibility.unwrap_or_default(), event.visibility);
+
+                let mut served: Vec<_> = attendees
+                    .iter()
+                    .map(|attendee| (attendee.email.clone(), attendee.status))
+                    .collect();
+                let mut returned: Vec<_> = parsed
+                    .attendees
+                    .iter()
+                    .map(|attendee| (attendee.email.clone(), attendee.status))
+                    .collect();
+                served.sort_by(|a, b| a.0.cmp(&b.0));
+                returned.sort_by(|a, b| a.0.cmp(&b.0));
+                prop_assert_eq!(returned, served);
+            }
+        }
+    }
 }
@@ -1,6 +1,8 @@
 //! CalDAV XML generation utilities
 //!
-//! Handles XML generation for CalDAV protocol responses
+//! Handles XML generation for CalDAV protocol responses. Generators take the
+//! `root` hrefs start with as the client sees it: `/caldav`, or
+//! `/prefix/caldav` behind a path-prefixing proxy.
 
 use chrono::{DateTime, Utc};
 use quick_xml::Reader;
@@ -9,6 +11,7 @@ use quick_xml::events::{BytesDecl, BytesEnd, BytesStart, BytesText, Event};
 use std::io::Cursor;
 use televent_application::{
     CalDavCalendarState, CalDavEventMetadata, CalDavEventResource, CalDavTombstone,
+    SubscriptionView,
 };
 use televent_domain::CALENDAR_NAME;
 
@@ -173,6 +176,7 @@ fn parse_caldav_datetime(s: &str) -> Option<DateTime<Utc>> {
 
 /// Generate CalDAV multistatus response for REPORT calendar-query.
 pub fn generate_calendar_query_response(
+    root: &str,
     user_identifier: &str,
     events: &[CalDavEventResource],
 ) -> Result<String, ApiError> {
@@ -196,7 +200,7 @@ pub fn generate_calendar_query_response(
 
     // Write response for each event with calendar-data
     for event in events {
-        write_event_with_data(&mut writer, user_identifier, event)?;
+        write_event_with_data(&mut writer, root, user_identifier, event)?;
     }
 
     // </multistatus>
@@ -210,6 +214,7 @@ pub fn generate_calendar_query_response(
 
 /// Generate CalDAV multistatus response for REPORT sync-collection.
 pub fn generate_sync_collection_response(
+    root: &str,
     user_identifier: &str,
     calendar: &CalDavCalendarState,
     events: &[CalDavEventResource],
@@ -235,11 +240,11 @@ pub fn generate_sync_collection_response(
 
     // Write response for changed/new events with calendar-data
     for event in events {
-        write_event_with_data(&mut writer, user_identifier, event)?;
+        write_event_with_data(&mut writer, root, user_identifier, event)?;
     }
 
     for tombstone in tombstones {
-        write_tombstone_response(&mut writer, user_identifier, tombstone)?;
+        write_tombstone_response(&mut writer, root, user_identifier, tombstone)?;
     }
 
     // <sync-token> - use write! to avoid allocation
@@ -272,8 +277,13 @@ pub fn generate_sync_collection_response(
     String::from_utf8(result).map_err(|e| ApiError::Internal(format!("UTF-8 error: {}", e)))
 }
 
+/// Body of a 403 answer to a sync-collection REPORT whose token can no
+/// longer be served (RFC 6578 Section 3.2); clients resync from scratch.
+pub const INVALID_SYNC_TOKEN_ERROR: &str = "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<d:error xmlns:d=\"DAV:\"><d:valid-sync-token/></d:error>";
+
 fn write_tombstone_response(
     writer: &mut Writer<Cursor<Vec<u8>>>,
+    root: &str,
     user_identifier: &str,
     tombstone: &CalDavTombstone,
 ) -> Result<(), ApiError> {
@@ -283,7 +293,7 @@ fn write_tombstone_response(
 
     write_start_tag(writer, "d:response")?;
 
-    write!(buf, "/caldav/{}/{}.ics", user_identifier, tombstone.uid)
+    write!(buf, "{}/{}/{}.ics", root, user_identifier, tombstone.uid)
         .map_err(|e| ApiError::Internal(format!("Format error: {}", e)))?;
     write_string_tag(writer, "d:href", &buf)?;
 
@@ -294,6 +304,7 @@ fn write_tombstone_response(
 
 /// Generate CalDAV multistatus response for REPORT calendar-multiget.
 pub fn generate_calendar_multiget_response(
+    root: &str,
     user_identifier: &str,
     events: &[CalDavEventResource],
 ) -> Result<String, ApiError> {
@@ -317,7 +328,7 @@ pub fn generate_calendar_multiget_response(
 
     // Write response for each event with calendar-data
     for event in events {
-        write_event_with_data(&mut writer, user_identifier, event)?;
+        write_event_with_data(&mut writer, root, user_identifier, event)?;
     }
 
     // </multistatus>
@@ -330,7 +341,7 @@ pub fn generate_calendar_multiget_response(
 }
 
 /// Write HTTP-date to a string buffer optimally
-fn write_http_date(buf: &mut String, dt: DateTime<Utc>) -> Result<(), std::fmt::Error> {
+pub(crate) fn write_http_date(buf: &mut String, dt: DateTime<Utc>) -> Result<(), std::fmt::Error> {
     use chrono::{Datelike, Timelike};
     use std::fmt::Write;
 
@@ -376,6 +387,7 @@ fn write_http_date(buf: &mut String, dt: DateTime<Utc>) -> Result<(), std::fmt::
 /// Uses a reusable buffer to avoid repeated string allocations in hot loops.
 fn write_event_with_data(
     writer: &mut Writer<Cursor<Vec<u8>>>,
+    root: &str,
     user_identifier: &str,
     event: &CalDavEventResource,
 ) -> Result<(), ApiError> {
@@ -389,7 +401,7 @@ fn write_event_with_data(
 
     // <href> - reuse buffer instead of format!
     buf.clear();
-    write!(buf, "/caldav/{}/{}.ics", user_identifier, event.uid)
+    write!(buf, "{}/{}/{}.ics", root, user_identifier, event.uid)
         .map_err(|e| ApiError::Internal(format!("Format error: {}", e)))?;
     write_string_tag(writer, "d:href", &buf)?;
 
@@ -431,11 +443,103 @@ fn write_event_with_data(
     write_end_tag(writer, "d:response")
 }
 
+/// Calendar collection described by a PROPFIND response
+struct CollectionProps<'a> {
+    /// Collection path below the CalDAV root
+    path: &'a str,
+    /// Principal path below the CalDAV root (the user's own calendar)
+    principal: &'a str,
+    display_name: &'a str,
+    read_only: bool,
+}
+
+/// Path below a user's calendar under which subscribed calendars live
+pub(crate) const SUBSCRIPTIONS_PATH: &str = "subscriptions/";
+
+/// Path below a user's calendar under which bookable resources can be read
+pub(crate) const RESOURCES_PATH: &str = "resources/";
+
 /// Generate CalDAV multistatus response for PROPFIND
+///
+/// The user's calendar doubles as their calendar home, so at Depth 1 the
+/// subscribed calendars are listed next to the events for clients to discover.
 pub fn generate_propfind_multistatus(
+    root: &str,
     user_identifier: &str,
     calendar: &CalDavCalendarState,
     events: &[CalDavEventMetadata],
+    subscriptions: &[SubscriptionView],
+    depth: &str,
+) -> Result<String, ApiError> {
+    let subscription_paths: Vec<String> = subscriptions
+        .iter()
+        .map(|subscription| format!("{user_identifier}/{SUBSCRIPTIONS_PATH}{}", subscription.id))
+        .collect();
+    let children: Vec<_> = subscriptions
+        .iter()
+        .zip(&subscription_paths)
+        .map(|(subscription, path)| {
+            (
+                CollectionProps {
+                    path,
+                    principal: user_identifier,
+                    display_name: &subscription.name,
+                    read_only: true,
+                },
+                &subscription.calendar,
+            )
+        })
+        .collect();
+
+    propfind_multistatus(
+        root,
+        &CollectionProps {
+            path: user_identifier,
+            principal: user_identifier,
+            display_name: CALENDAR_NAME,
+            read_only: false,
+        },
+        calendar,
+        &children,
+        events,
+        depth,
+    )
+}
+
+/// Generate CalDAV multistatus response for PROPFIND on a read-only calendar
+/// below the user's own: a subscribed calendar at
+/// `/caldav/{user}/subscriptions/{id}/` or a resource at
+/// `/caldav/{user}/resources/{id}/`
+pub fn generate_read_only_propfind_multistatus(
+    root: &str,
+    user_identifier: &str,
+    collection_path: &str,
+    display_name: &str,
+    calendar: &CalDavCalendarState,
+    events: &[CalDavEventMetadata],
+    depth: &str,
+) -> Result<String, ApiError> {
+    propfind_multistatus(
+        root,
+        &CollectionProps {
+            path: collection_path,
+            principal: user_identifier,
+            display_name,
+            read_only: true,
+        },
+        calendar,
+        &[],
+        events,
+        depth,
+    )
+}
+
+fn propfind_multistatus(
+    root: &str,
+    collection: &CollectionProps<'_>,
+    calendar: &CalDavCalendarState,
+    children: &[(CollectionProps<'_>, &CalDavCalendarState)],
+    events: &[CalDavEventMetadata],
     depth: &str,
 ) -> Result<String, ApiError> {
     // Pre-allocate buffer if we are returning events (Depth: 1)
@@ -461,12 +565,15 @@ pub fn generate_propfind_multistatus(
         .map_err(|e| ApiError::Internal(format!("XML write error: {}", e)))?;
 
     // Calendar collection response (user = calendar)
-    write_calendar_response(&mut writer, user_identifier, calendar)?;
+    write_calendar_response(&mut writer, root, collection, calendar)?;
 
-    // Event responses (only for Depth: 1)
+    // Child calendars and event responses (only for Depth: 1)
     if depth == "1" {
+        for (child, child_calendar) in children {
+            write_calendar_response(&mut writer, root, child, child_calendar)?;
+        }
         for event in events {
-            write_event_response(&mut writer, user_identifier, event)?;
+            write_event_response(&mut writer, root, collection.path, event)?;
         }
     }
 
@@ -484,7 +591,8 @@ pub fn generate_propfind_multistatus(
 /// Uses a reusable buffer to reduce allocations.
 fn write_calendar_response(
     writer: &mut Writer<Cursor<Vec<u8>>>,
-    user_identifier: &str,
+    root: &str,
+    collection: &CollectionProps<'_>,
     calendar: &CalDavCalendarState,
 ) -> Result<(), ApiError> {
     use std::fmt::Write;
@@ -497,7 +605,7 @@ fn write_calendar_response(
 
     // <href>/caldav/{user_id}/</href> - reuse buffer
     buf.clear();
-    write!(buf, "/caldav/{}/", user_identifier)
+    write!(buf, "{}/{}/", root, collection.path)
         .map_err(|e| ApiError::Internal(format!("Format error: {}", e)))?;
     write_string_tag(writer, "d:href", &buf)?;
 
@@ -514,7 +622,7 @@ fn write_calendar_response(
     write_end_tag(writer, "d:resourcetype")?;
 
     // <displayname>
-    write_string_tag(writer, "d:displayname", CALENDAR_NAME)?;
+    write_string_tag(writer, "d:displayname", collection.display_name)?;
 
     // <getctag>
     buf.clear();
@@ -528,10 +636,10 @@ fn write_calendar_response(
         .map_err(|e| ApiError::Internal(format!("Format error: {}", e)))?;
     write_string_tag(writer, "d:sync-token", &buf)?;
 
-    // <calendar-home-set> - reuse the href we already formatted
+    // <calendar-home-set> - the user's own calendar, which lists subscriptions
     write_start_tag(writer, "cal:calendar-home-set")?;
     buf.clear();
-    write!(buf, "/caldav/{}/", user_identifier)
+    write!(buf, "{}/{}/", root, collection.principal)
         .map_err(|e| ApiError::Internal(format!("Format error: {}", e)))?;
     write_string_tag(writer, "d:href", &buf)?;
     write_end_tag(writer, "cal:calendar-home-set")?;
@@ -576,6 +684,15 @@ fn write_calendar_response(
 
     write_end_tag(writer, "d:supported-report-set")?;
 
+    // <current-user-privilege-set> - subscribed calendars only allow reading
+    if collection.read_only {
+        write_start_tag(writer, "d:current-user-privilege-set")?;
+        write_start_tag(writer, "d:privilege")?;
+        write_empty_tag(writer, "d:read")?;
+        write_end_tag(writer, "d:privilege")?;
+        write_end_tag(writer, "d:current-user-privilege-set")?;
+    }
+
     // </prop>
     write_end_tag(writer, "d:prop")?;
 
@@ -594,6 +711,7 @@ fn write_calendar_response(
 /// Uses a reusable buffer to avoid repeated string allocations in hot loops.
 fn write_event_response(
     writer: &mut Writer<Cursor<Vec<u8>>>,
+    root: &str,
     user_identifier: &str,
     event: &CalDavEventMetadata,
 ) -> Result<(), ApiError> {
@@ -607,7 +725,7 @@ fn write_event_response(
 
     // <href>/caldav/{user_id}/{uid}.ics</href> - reuse buffer
     buf.clear();
-    write!(buf, "/caldav/{}/{}.ics", user_identifier, event.uid)
+    write!(buf, "{}/{}/{}.ics", root, user_identifier, event.uid)
         .map_err(|e| ApiError::Internal(format!("Format error: {}", e)))?;
     write_string_tag(writer, "d:href", &buf)?;
 
@@ -646,7 +764,7 @@ fn write_event_response(
 }
 
 /// Write a simple XML element with text content: <tag>content</tag>
-fn write_string_tag<W: std::io::Write>(
+pub(crate) fn write_string_tag<W: std::io::Write>(
     writer: &mut Writer<W>,
     tag: &str,
     text: &str,
@@ -664,21 +782,30 @@ fn write_string_tag<W: std::io::Write>(
 }
 
 /// Start an element: <tag>
-fn write_start_tag<W: std::io::Write>(writer: &mut Writer<W>, tag: &str) -> Result<(), ApiError> {
+pub(crate) fn write_start_tag<W: std::io::Write>(
+    writer: &mut Writer<W>,
+    tag: &str,
+) -> Result<(), ApiError> {
     writer
         .write_event(Event::Start(BytesStart::new(tag)))
         .map_err(|e| ApiError::Internal(format!("XML write error: {}", e)))
 }
 
 /// End an element: </tag>
-fn write_end_tag<W: std::io::Write>(writer: &mut Writer<W>, tag: &str) -> Result<(), ApiError> {
+pub(crate) fn write_end_tag<W: std::io::Write>(
+    writer: &mut Writer<W>,
+    tag: &str,
+) -> Result<(), ApiError> {
     writer
         .write_event(Event::End(BytesEnd::new(tag)))
         .map_err(|e| ApiError::Internal(format!("XML write error: {}", e)))
 }
 
 /// Write an empty element: <tag/>
-fn write_empty_tag<W: std::io::Write>(writer: &mut Writer<W>, tag: &str) -> Result<(), ApiError> {
+pub(crate) fn write_empty_tag<W: std::io::Write>(
+    writer: &mut Writer<W>,
+    tag: &str,
+) -> Result<(), ApiError> {
     writer
         .write_event(Event::Empty(BytesStart::new(tag)))
         .map_err(|e| ApiError::Internal(format!("XML write error: {}", e)))
@@ -722,7 +849,8 @@ mod tests {
     fn test_generate_propfind_depth_0() {
         let calendar = test_calendar_state();
 
-        let xml = generate_propfind_multistatus("testuser", &calendar, &[], "0").unwrap();
+        let xml =
+            generate_propfind_multistatus("/caldav", "testuser", &calendar, &[], &[], "0").unwrap();
 
         assert!(xml.contains("<?xml"));
         assert!(xml.contains("multistatus"));
@@ -738,7 +866,9 @@ mod tests {
         let calendar = test_calendar_state();
         let event = test_event_metadata("test-event-1");
 
-        let xml = generate_propfind_multistatus("testuser", &calendar, &[event], "1").unwrap();
+        let xml =
+            generate_propfind_multistatus("/caldav", "testuser", &calendar, &[event], &[], "1")
+                .unwrap();
 
         assert!(xml.contains("<?xml"));
         assert!(xml.contains("multistatus"));
@@ -749,6 +879,75 @@ mod tests {
         assert!(xml.contains("text/calendar"));
     }
 
+    #[test]
+    fn test_generate_subscription_propfind_is_read_only() {
+        let calendar = test_calendar_state();
+        let event = test_event_metadata("remote-1");
+
+        let xml = generate_read_only_propfind_multistatus(
+            "/caldav",
+            "testuser",
+            "testuser/subscriptions/abc",
+            "Team <Calendar>",
+            &calendar,
+            &[event],
+            "1",
+        )
+        .unwrap();
+
+        assert!(xml.contains("<d:href>/caldav/testuser/subscriptions/abc/</d:href>"));
+        assert!(xml.contains("<d:href>/caldav/testuser/subscriptions/abc/remote-1.ics</d:href>"));
+        assert!(xml.contains("<d:href>/caldav/testuser/</d:href>"));
+        assert!(xml.contains("Team &lt;Calendar&gt;"));
+        assert!(xml.contains("<d:read/>"));
+        assert!(!xml.contains(&format!("<d:displayname>{CALENDAR_NAME}</d:displayname>")));
+
+        let own =
+            generate_propfind_multistatus("/caldav", "testuser", &calendar, &[], &[], "0").unwrap();
+        assert!(!own.contains("current-user-privilege-set"));
+    }
+
+    #[test]
+    fn test_generate_propfind_lists_subscriptions_at_depth_1() {
+        let calendar = test_calendar_state();
+        let subscription = SubscriptionView {
+            id: uuid::Uuid::nil(),
+            url: "https://example.com/team.ics".to_string(),
+            name: "Team".to_string(),
+            calendar: test_calendar_state(),
+            last_fetched_at: None,
+            last_error: None,
+        };
+        let href = format!(
+            "<d:href>/caldav/testuser/subscriptions/{}/</d:href>",
+            subscription.id
+        );
+
+        let xml = generate_propfind_multistatus(
+            "/caldav",
+            "testuser",
+            &calendar,
+            &[],
+            std::slice::from_ref(&subscription),
+            "1",
+        )
+        .unwrap();
+        assert!(xml.contains(&href));
+        assert!(xml.contains("<d:displayname>Team</d:displayname>"));
+        assert!(xml.contains("<d:read/>"));
+
+        let xml = generate_propfind_multistatus(
+            "/caldav",
+            "testuser",
+            &calendar,
+            &[],
+            &[subscription],
+            "0",
+        )
+        .unwrap();
+        assert!(!xml.contains(&href));
+    }
+
     #[test]
     fn test_xml_structure_valid() {
         let calendar = CalDavCalendarState {
@@ -756,7 +955,8 @@ mod tests {
             ctag: 0,
         };
 
-        let xml = generate_propfind_multistatus("testuser", &calendar, &[], "0").unwrap();
+        let xml =
+            generate_propfind_multistatus("/caldav", "testuser", &calendar, &[], &[], "0").unwrap();
 
         // Check XML declaration
         assert!(xml.starts_with("<?xml version=\"1.0\" encoding=\"utf-8\"?>"));
@@ -872,7 +1072,7 @@ mod tests {
     #[test]
     fn test_generate_calendar_query_response() {
         let event = test_event_resource("event-123");
-        let xml = generate_calendar_query_response("testuser", &[event]).unwrap();
+        let xml = generate_calendar_query_response("/caldav", "testuser", &[event]).unwrap();
 
         assert!(xml.contains("<?xml"));
         assert!(xml.contains("multistatus"));
@@ -892,7 +1092,9 @@ mod tests {
         let mut event = test_event_resource("changed-event");
         event.etag = "new-etag".to_string();
 
-        let xml = generate_sync_collection_response("testuser", &calendar, &[event], &[]).unwrap();
+        let xml =
+            generate_sync_collection_response("/caldav", "testuser", &calendar, &[event], &[])
+                .unwrap();
 
         assert!(xml.contains("<?xml"));
         assert!(xml.contains("multistatus"));
@@ -911,7 +1113,8 @@ mod tests {
             ctag: 123,
         };
 
-        let xml = generate_sync_collection_response("testuser", &calendar, &[], &[]).unwrap();
+        let xml =
+            generate_sync_collection_response("/caldav", "testuser", &calendar, &[], &[]).unwrap();
 
         assert!(xml.contains("<?xml"));
         assert!(xml.contains("multistatus"));
@@ -932,7 +1135,8 @@ mod tests {
         };
 
         let xml =
-            generate_sync_collection_response("testuser", &calendar, &[], &[tombstone]).unwrap();
+            generate_sync_collection_response("/caldav", "testuser", &calendar, &[], &[tombstone])
+                .unwrap();
 
         assert!(xml.contains("deleted-event.ics"));
         assert!(xml.contains("HTTP/1.1 404 Not Found"));
@@ -954,7 +1158,7 @@ mod tests {
         }
 
         let start = std::time::Instant::now();
-        let _ = generate_calendar_query_response("testuser", &events).unwrap();
+        let _ = generate_calendar_query_response("/caldav", "testuser", &events).unwrap();
         let duration = start.elapsed();
 
         println!(
@@ -6,13 +6,17 @@
 use axum::{
     Extension, Json, Router,
     extract::{FromRef, State},
-    routing::get,
+    http::HeaderMap,
+    response::Response,
+    routing::{get, put},
 };
-use serde::Serialize;
-use televent_application::CalendarService;
+use serde::{Deserialize, Serialize};
+use televent_application::{CalendarService, UserIdentity};
 use televent_domain::{CALENDAR_COLOR, CALENDAR_NAME};
 use utoipa::ToSchema;
 
+use super::etag::json_with_etag;
+use super::events::EventVisibility;
 use crate::{error::ApiError, middleware::telegram_auth::AuthenticatedTelegramUser};
 
 /// Calendar response (subset of User relevant to calendar functionality)
@@ -21,6 +25,25 @@ pub struct CalendarInfo {
     pub id: String,
     pub name: String,
     pub color: String,
+    /// Visibility of new events that don't set their own
+    pub default_visibility: EventVisibility,
+}
+
+impl From<UserIdentity> for CalendarInfo {
+    fn from(user: UserIdentity) -> Self {
+        Self {
+            id: user.id.to_string(),
+            name: CALENDAR_NAME.to_string(),
+            color: CALENDAR_COLOR.to_string(),
+            default_visibility: user.default_event_visibility.into(),
+        }
+    }
+}
+
+/// Visibility new events of the calendar get
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct PutDefaultVisibilityRequest {
+    pub visibility: EventVisibility,
 }
 
 /// List user's calendars
@@ -32,6 +55,7 @@ pub struct CalendarInfo {
     path = "/calendars",
     responses(
         (status = 200, description = "List of calendars", body = Vec<CalendarInfo>),
+        (status = 304, description = "Unchanged since the ETag in If-None-Match"),
         (status = 401, description = "Unauthorized")
     ),
     tag = "calendars",
@@ -42,18 +66,45 @@ pub struct CalendarInfo {
 async fn list_calendars(
     State(calendar): State<CalendarService>,
     Extension(auth_user): Extension<AuthenticatedTelegramUser>,
-) -> Result<Json<Vec<CalendarInfo>>, ApiError> {
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let user = calendar
+        .get_or_create_user(auth_user.id.inner(), auth_user.username.as_deref())
+        .await?;
+
+    json_with_etag(&headers, &vec![CalendarInfo::from(user)])
+}
+
+/// Set the calendar's default event visibility
+///
+/// Applies to events created afterwards without a visibility of their own;
+/// existing events keep theirs.
+#[utoipa::path(
+    put,
+    path = "/calendars/default-visibility",
+    request_body = PutDefaultVisibilityRequest,
+    responses(
+        (status = 200, description = "Default saved", body = CalendarInfo),
+        (status = 401, description = "Unauthorized")
+    ),
+    tag = "calendars",
+    security(
+        ("telegram_auth" = [])
+    )
+)]
+async fn put_default_visibility(
+    State(calendar): State<CalendarService>,
+    Extension(auth_user): Extension<AuthenticatedTelegramUser>,
+    Json(request): Json<PutDefaultVisibilityRequest>,
+) -> Result<Json<CalendarInfo>, ApiError> {
     calendar
         .ensure_user_setup(auth_user.id.inner(), auth_user.username.as_deref())
         .await?;
+    let user = calendar
+        .set_default_event_visibility(auth_user.id, request.visibility.into_domain())
+        .await?;
 
-    let calendar_info = CalendarInfo {
-        id: auth_user.id.to_string(),
-        name: CALENDAR_NAME.to_string(),
-        color: CALENDAR_COLOR.to_string(),
-    };
-
-    Ok(Json(vec![calendar_info]))
+    Ok(Json(user.into()))
 }
 
 /// Calendar routes
@@ -62,7 +113,9 @@ where
     S: Clone + Send + Sync + 'static,
     CalendarService: FromRef<S>,
 {
-    Router::new().route("/calendars", get(list_calendars))
+    Router::new()
+        .route("/calendars", get(list_calendars))
+        .route("/calendars/default-visibility", put(put_default_visibility))
 }
 
 #[cfg(test)]
@@ -75,6 +128,7 @@ mod tests {
             id: "123".to_string(),
             name: CALENDAR_NAME.to_string(),
             color: CALENDAR_COLOR.to_string(),
+            default_visibility: EventVisibility::Public,
         })
         .unwrap();
         let object = value.as_object().unwrap();
@@ -0,0 +1,284 @@
+//! CardDAV protocol endpoints
+//!
+//! Serves one address book per user at `/carddav/{user}/` (RFC 6352), behind
+//! the same device-password Basic Auth as CalDAV.
+
+use std::borrow::Cow;
+
+use axum::{
+    Router,
+    body::Body,
+    extract::{Extension, FromRef, Path, State},
+    http::{HeaderMap, HeaderName, Method, StatusCode, header},
+    response::{IntoResponse, Response},
+    routing::any,
+};
+use televent_application::{CalendarService, ContactService, PutContactCommand, UserId};
+
+use crate::error::ApiError;
+use crate::middleware::base_path::BasePath;
+use crate::routes::caldav::resolve_user;
+use crate::routes::carddav_xml::{self, AddressBookReport};
+
+/// Maximum allowed body size for CardDAV requests (1 MB)
+const MAX_CARDDAV_BODY_SIZE: usize = 1024 * 1024;
+
+/// CardDAV OPTIONS handler
+fn carddav_options() -> Response {
+    (
+        StatusCode::OK,
+        [
+            (HeaderName::from_static("dav"), "1, 3, addressbook"),
+            (header::ALLOW, "OPTIONS, PROPFIND, REPORT, GET, PUT, DELETE"),
+        ],
+    )
+        .into_response()
+}
+
+/// CardDAV routes
+pub fn routes<S>() -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+    CalendarService: FromRef<S>,
+    ContactService: FromRef<S>,
+{
+    Router::new()
+        // Address book collection endpoints
+        .route("/{user_identifier}/", any(address_book_handler))
+        // Contact resource endpoints
+        .route("/{user_identifier}/{*contact_uid}", any(contact_handler))
+}
+
+/// Address book collection handler
+#[allow(clippy::too_many_arguments)]
+async fn address_book_handler(
+    State(calendar): State<CalendarService>,
+    State(contacts): State<ContactService>,
+    Extension(auth_user_id): Extension<UserId>,
+    base: BasePath,
+    Path(user_identifier): Path<String>,
+    headers: HeaderMap,
+    method: Method,
+    body: Body,
+) -> Result<Response, ApiError> {
+    if method == Method::OPTIONS {
+        return Ok(carddav_options());
+    }
+
+    let user = resolve_user(&calendar, &user_identifier).await?;
+    if user.id != auth_user_id {
+        return Err(ApiError::Forbidden);
+    }
+
+    match method.as_str() {
+        "PROPFIND" => {
+            let depth = headers
+                .get("Depth")
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or("0");
+            tracing::debug!(
+                "CardDAV PROPFIND for user {} with Depth: {}",
+                user.id,
+                depth
+            );
+
+            let ctag = contacts.address_book_ctag(user.id).await?;
+            let listed = if depth == "1" {
+                contacts.list_contacts(user.id).await?
+            } else {
+                Vec::new()
+            };
+            multistatus(carddav_xml::generate_propfind_multistatus(
+                &base.join("/carddav"),
+                &user_identifier,
+                &ctag,
+                &listed,
+                depth,
+            )?)
+        }
+        "REPORT" => {
+            let body_bytes = axum::body::to_bytes(body, MAX_CARDDAV_BODY_SIZE)
+                .await
+                .map_err(|e| ApiError::BadRequest(format!("Failed to read body: {}", e)))?;
+            let xml_body = String::from_utf8(body_bytes.to_vec())
+                .map_err(|e| ApiError::BadRequest(format!("Invalid UTF-8: {}", e)))?;
+
+            let reported = match carddav_xml::parse_report_request(&xml_body)? {
+                AddressBookReport::Query => contacts.list_contacts(user.id).await?,
+                AddressBookReport::Multiget { hrefs } => {
+                    let uids = extract_uids_from_hrefs(&hrefs);
+                    let uid_strs: Vec<&str> = uids.iter().map(|s| s.as_ref()).collect();
+                    contacts.get_contacts_by_uids(user.id, &uid_strs).await?
+                }
+            };
+            tracing::info!("CardDAV REPORT: returning {} contacts", reported.len());
+
+            multistatus(carddav_xml::generate_report_response(
+                &base.join("/carddav"),
+                &user_identifier,
+                &reported,
+            )?)
+        }
+        _ => Err(ApiError::BadRequest(format!(
+            "Method {} not supported for address book",
+            method
+        ))),
+    }
+}
+
+/// Contact resource handler
+#[allow(clippy::too_many_arguments)]
+async fn contact_handler(
+    State(calendar): State<CalendarService>,
+    State(contacts): State<ContactService>,
+    Extension(auth_user_id): Extension<UserId>,
+    Path((user_identifier, contact_uid_raw)): Path<(String, String)>,
+    headers: HeaderMap,
+    method: Method,
+    body: Body,
+) -> Result<Response, ApiError> {
+    if method == Method::OPTIONS {
+        return Ok(carddav_options());
+    }
+
+    let user = resolve_user(&calendar, &user_identifier).await?;
+    if user.id != auth_user_id {
+        return Err(ApiError::Forbidden);
+    }
+
+    // Strip leading slash and .vcf extension from wildcard capture
+    let contact_uid = contact_uid_raw
+        .trim_start_matches('/')
+        .trim_end_matches(".vcf");
+    let expected_etag = headers
+        .get(header::IF_MATCH)
+        .map(|value| {
+            value
+                .to_str()
+                .map(str::to_string)
+                .map_err(|_| ApiError::BadRequest("Invalid If-Match header".to_string()))
+        })
+        .transpose()?;
+
+    match method {
+        Method::GET => {
+            let contact = contacts
+                .get_contact(user.id, contact_uid)
+                .await?
+                .ok_or_else(|| ApiError::NotFound(format!("Contact not found: {contact_uid}")))?;
+
+            Ok((
+                StatusCode::OK,
+                [
+                    (header::CONTENT_TYPE, "text/vcard; charset=utf-8"),
+                    (header::ETAG, &format!("\"{}\"", contact.etag)),
+                ],
+                contact.vcard,
+            )
+                .into_response())
+        }
+        Method::PUT => {
+            let body_bytes = axum::body::to_bytes(body, MAX_CARDDAV_BODY_SIZE)
+                .await
+                .map_err(|e| ApiError::BadRequest(format!("Failed to read body: {}", e)))?;
+            let vcard = String::from_utf8(body_bytes.to_vec())
+                .map_err(|e| ApiError::BadRequest(format!("Invalid UTF-8: {}", e)))?;
+            let create_only = headers
+                .get(header::IF_NONE_MATCH)
+                .is_some_and(|value| value.as_bytes() == b"*");
+
+            let result = contacts
+                .put_contact(PutContactCommand {
+                    user_id: user.id,
+                    username: None,
+                    uid: contact_uid.to_string(),
+                    vcard,
+                    expected_etag,
+                    create_only,
+                })
+                .await?;
+            let status_code = if result.created {
+                StatusCode::CREATED
+            } else {
+                StatusCode::NO_CONTENT
+            };
+
+            Ok((
+                status_code,
+                [(header::ETAG, format!("\"{}\"", result.etag))],
+                "",
+            )
+                .into_response())
+        }
+        Method::DELETE => {
+            if contacts
+                .delete_contact(user.id, contact_uid, expected_etag.as_deref())
+                .await?
+            {
+                Ok((StatusCode::NO_CONTENT, "").into_response())
+            } else {
+                Err(ApiError::NotFound(format!(
+                    "Contact not found: {contact_uid}"
+                )))
+            }
+        }
+        _ => Err(ApiError::BadRequest(format!(
+            "Method {} not supported for contact resource",
+            method
+        ))),
+    }
+}
+
+fn multistatus(xml: String) -> Result<Response, ApiError> {
+    Ok((
+        StatusCode::MULTI_STATUS,
+        [(header::CONTENT_TYPE, "application/xml; charset=utf-8")],
+        xml,
+    )
+        .into_response())
+}
+
+/// Contact resource names from multiget hrefs, URL-decoded and without `.vcf`
+fn extract_uids_from_hrefs(hrefs: &[String]) -> Vec<Cow<'_, str>> {
+    hrefs
+        .iter()
+        .filter_map(|href| {
+            // The collection's own href ends with a slash and yields no UID
+            let last_segment = href.rsplit('/').next()?;
+            let decoded = urlencoding::decode(last_segment).unwrap_or(Cow::Borrowed(last_segment));
+            let uid = match decoded {
+                Cow::Borrowed(s) => Cow::Borrowed(s.trim_end_matches(".vcf")),
+                Cow::Owned(s) => Cow::Owned(s.trim_end_matches(".vcf").to_string()),
+            };
+            (!uid.is_empty()).then_some(uid)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_carddav_options_advertises_addressbook() {
+        let response = carddav_options();
+        let dav = response
+            .headers()
+            .get(HeaderName::from_static("dav"))
+            .unwrap();
+        assert!(dav.to_str().unwrap().contains("addressbook"));
+    }
+
+    #[test]
+    fn test_extract_uids_from_hrefs() {
+        let hrefs = vec![
+            "/carddav/user/alice.vcf".to_string(),
+            "/carddav/user/bob%20smith.vcf".to_string(),
+            "carol".to_string(),
+            "/carddav/user/".to_string(),
+        ];
+
+        let uids = extract_uids_from_hrefs(&hrefs);
+        assert_eq!(uids, vec!["alice", "bob smith", "carol"]);
+    }
+}
@@ -0,0 +1,317 @@
+//! CardDAV XML generation utilities
+//!
+//! Handles PROPFIND and REPORT bodies for the per-user address book (RFC 6352).
+//! Like the CalDAV generators these take the `root` hrefs start with, e.g.
+//! `/carddav`.
+
+use quick_xml::Reader;
+use quick_xml::Writer;
+use quick_xml::events::{BytesDecl, BytesEnd, BytesStart, Event};
+use std::io::Cursor;
+use televent_application::ContactView;
+
+use crate::error::ApiError;
+use crate::routes::caldav_xml::{
+    write_empty_tag, write_end_tag, write_http_date, write_start_tag, write_string_tag,
+};
+
+/// Maximum number of hrefs allowed in an addressbook-multiget report
+const MAX_MULTIGET_HREFS: usize = 200;
+
+/// Display name of the address book collection
+pub const ADDRESS_BOOK_NAME: &str = "Televent contacts";
+
+/// Parsed CardDAV REPORT request
+#[derive(Debug, PartialEq, Eq)]
+pub enum AddressBookReport {
+    /// addressbook-query: filters are ignored and every contact is returned
+    Query,
+    /// addressbook-multiget: fetch the listed contact resources
+    Multiget { hrefs: Vec<String> },
+}
+
+/// Parse CardDAV REPORT request XML
+pub fn parse_report_request(xml_body: &str) -> Result<AddressBookReport, ApiError> {
+    let mut reader = Reader::from_str(xml_body);
+
+    let mut is_query = false;
+    let mut is_multiget = false;
+    let mut in_href = false;
+    let mut hrefs: Vec<String> = Vec::new();
+
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e) | Event::Empty(e)) => match e.local_name().as_ref() {
+                b"addressbook-query" => is_query = true,
+                b"addressbook-multiget" => is_multiget = true,
+                b"href" => in_href = true,
+                _ => {}
+            },
+            Ok(Event::Text(e)) => {
+                let text = std::str::from_utf8(e.as_ref()).unwrap_or("");
+                if in_href && !text.is_empty() {
+                    if hrefs.len() >= MAX_MULTIGET_HREFS {
+                        return Err(ApiError::BadRequest(format!(
+                            "Too many hrefs in addressbook-multiget (max {})",
+                            MAX_MULTIGET_HREFS
+                        )));
+                    }
+                    hrefs.push(text.to_string());
+                }
+            }
+            Ok(Event::End(e)) if e.local_name().as_ref() == b"href" => in_href = false,
+            Ok(Event::Eof) => break,
+            Ok(Event::DocType(_)) => {
+                return Err(ApiError::BadRequest("DTD not allowed".to_string()));
+            }
+            Err(e) => {
+                return Err(ApiError::BadRequest(format!("XML parse error: {}", e)));
+            }
+            _ => {}
+        }
+    }
+
+    if is_multiget {
+        Ok(AddressBookReport::Multiget { hrefs })
+    } else if is_query {
+        Ok(AddressBookReport::Query)
+    } else {
+        Err(ApiError::BadRequest(
+            "Unknown REPORT type: expected addressbook-query or addressbook-multiget".to_string(),
+        ))
+    }
+}
+
+/// Generate CardDAV multistatus response for PROPFIND
+pub fn generate_propfind_multistatus(
+    root: &str,
+    user_identifier: &str,
+    ctag: &str,
+    contacts: &[ContactView],
+    depth: &str,
+) -> Result<String, ApiError> {
+    let mut writer = start_multistatus(contacts.len())?;
+
+    write_address_book_response(&mut writer, root, user_identifier, ctag)?;
+    if depth == "1" {
+        for contact in contacts {
+            write_contact_response(&mut writer, root, user_identifier, contact, false)?;
+        }
+    }
+
+    finish_multistatus(writer)
+}
+
+/// Generate CardDAV multistatus response for REPORT, including vCard data
+pub fn generate_report_response(
+    root: &str,
+    user_identifier: &str,
+    contacts: &[ContactView],
+) -> Result<String, ApiError> {
+    let mut writer = start_multistatus(contacts.len())?;
+
+    for contact in contacts {
+        write_contact_response(&mut writer, root, user_identifier, contact, true)?;
+    }
+
+    finish_multistatus(writer)
+}
+
+fn start_multistatus(contact_count: usize) -> Result<Writer<Cursor<Vec<u8>>>, ApiError> {
+    let capacity = contact_count * 512 + 2048;
+    let mut writer = Writer::new_with_indent(Cursor::new(Vec::with_capacity(capacity)), b' ', 2);
+
+    writer
+        .write_event(Event::Decl(BytesDecl::new("1.0", Some("utf-8"), None)))
+        .map_err(|e| ApiError::Internal(format!("XML write error: {}", e)))?;
+
+    let mut multistatus = BytesStart::new("d:multistatus");
+    multistatus.push_attribute(("xmlns:d", "DAV:"));
+    multistatus.push_attribute(("xmlns:card", "urn:ietf:params:xml:ns:carddav"));
+    multistatus.push_attribute(("xmlns:cs", "http://calendarserver.org/ns/"));
+    writer
+        .write_event(Event::Start(multistatus))
+        .map_err(|e| ApiError::Internal(format!("XML write error: {}", e)))?;
+
+    Ok(writer)
+}
+
+fn finish_multistatus(mut writer: Writer<Cursor<Vec<u8>>>) -> Result<String, ApiError> {
+    writer
+        .write_event(Event::End(BytesEnd::new("d:multistatus")))
+        .map_err(|e| ApiError::Internal(format!("XML write error: {}", e)))?;
+
+    let result = writer.into_inner().into_inner();
+    String::from_utf8(result).map_err(|e| ApiError::Internal(format!("UTF-8 error: {}", e)))
+}
+
+/// Write the address book collection response
+fn write_address_book_response(
+    writer: &mut Writer<Cursor<Vec<u8>>>,
+    root: &str,
+    user_identifier: &str,
+    ctag: &str,
+) -> Result<(), ApiError> {
+    let home = format!("{root}/{user_identifier}/");
+
+    write_start_tag(writer, "d:response")?;
+    write_string_tag(writer, "d:href", &home)?;
+    write_start_tag(writer, "d:propstat")?;
+    write_start_tag(writer, "d:prop")?;
+
+    // <resourcetype><collection/><addressbook/></resourcetype>
+    write_start_tag(writer, "d:resourcetype")?;
+    write_empty_tag(writer, "d:collection")?;
+    write_empty_tag(writer, "card:addressbook")?;
+    write_end_tag(writer, "d:resourcetype")?;
+
+    write_string_tag(writer, "d:displayname", ADDRESS_BOOK_NAME)?;
+    write_string_tag(writer, "cs:getctag", ctag)?;
+
+    // The user's principal, home set and address book are the same resource
+    for tag in [
+        "card:addressbook-home-set",
+        "d:current-user-principal",
+        "d:owner",
+    ] {
+        write_start_tag(writer, tag)?;
+        write_string_tag(writer, "d:href", &home)?;
+        write_end_tag(writer, tag)?;
+    }
+
+    // <supported-address-data>
+    write_start_tag(writer, "card:supported-address-data")?;
+    for version in ["3.0", "4.0"] {
+        let mut data_type = BytesStart::new("card:address-data-type");
+        data_type.push_attribute(("content-type", "text/vcard"));
+        data_type.push_attribute(("version", version));
+        writer
+            .write_event(Event::Empty(data_type))
+            .map_err(|e| ApiError::Internal(format!("XML write error: {}", e)))?;
+    }
+    write_end_tag(writer, "card:supported-address-data")?;
+
+    // <supported-report-set>
+    write_start_tag(writer, "d:supported-report-set")?;
+    for report in ["card:addressbook-query", "card:addressbook-multiget"] {
+        write_start_tag(writer, "d:supported-report")?;
+        write_start_tag(writer, "d:report")?;
+        write_empty_tag(writer, report)?;
+        write_end_tag(writer, "d:report")?;
+        write_end_tag(writer, "d:supported-report")?;
+    }
+    write_end_tag(writer, "d:supported-report-set")?;
+
+    write_end_tag(writer, "d:prop")?;
+    write_string_tag(writer, "d:status", "HTTP/1.1 200 OK")?;
+    write_end_tag(writer, "d:propstat")?;
+    write_end_tag(writer, "d:response")
+}
+
+/// Write a contact resource response, optionally with its vCard
+fn write_contact_response(
+    writer: &mut Writer<Cursor<Vec<u8>>>,
+    root: &str,
+    user_identifier: &str,
+    contact: &ContactView,
+    with_data: bool,
+) -> Result<(), ApiError> {
+    let mut last_modified = String::with_capacity(32);
+    write_http_date(&mut last_modified, contact.updated_at)
+        .map_err(|e| ApiError::Internal(format!("Format error: {}", e)))?;
+
+    write_start_tag(writer, "d:response")?;
+    write_string_tag(
+        writer,
+        "d:href",
+        &format!(
+            "{}/{}/{}.vcf",
+            root,
+            user_identifier,
+            urlencoding::encode(&contact.uid)
+        ),
+    )?;
+    write_start_tag(writer, "d:propstat")?;
+    write_start_tag(writer, "d:prop")?;
+    write_string_tag(writer, "d:getetag", &format!("\"{}\"", contact.etag))?;
+    write_string_tag(writer, "d:getcontenttype", "text/vcard; charset=utf-8")?;
+    write_string_tag(writer, "d:getlastmodified", &last_modified)?;
+    if with_data {
+        write_string_tag(writer, "card:address-data", &contact.vcard)?;
+    }
+    write_end_tag(writer, "d:prop")?;
+    write_string_tag(writer, "d:status", "HTTP/1.1 200 OK")?;
+    write_end_tag(writer, "d:propstat")?;
+    write_end_tag(writer, "d:response")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn test_contact(uid: &str) -> ContactView {
+        ContactView {
+            uid: uid.to_string(),
+            full_name: "Alice".to_string(),
+            email: Some("alice@example.com".to_string()),
+            telegram_username: None,
+            vcard: "BEGIN:VCARD\r\nVERSION:3.0\r\nFN:Alice\r\nEND:VCARD\r\n".to_string(),
+            etag: "abc".to_string(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_generate_propfind_lists_contacts_at_depth_1() {
+        let contacts = [test_contact("alice")];
+
+        let xml =
+            generate_propfind_multistatus("/carddav", "testuser", "1-42", &contacts, "1").unwrap();
+        assert!(xml.contains("<d:href>/carddav/testuser/</d:href>"));
+        assert!(xml.contains("<card:addressbook/>"));
+        assert!(xml.contains("<cs:getctag>1-42</cs:getctag>"));
+        assert!(xml.contains("<d:href>/carddav/testuser/alice.vcf</d:href>"));
+        assert!(xml.contains("<d:getetag>&quot;abc&quot;</d:getetag>"));
+        assert!(!xml.contains("card:address-data>"));
+
+        let xml =
+            generate_propfind_multistatus("/carddav", "testuser", "1-42", &contacts, "0").unwrap();
+        assert!(!xml.contains("alice.vcf"));
+    }
+
+    #[test]
+    fn test_generate_report_includes_vcard() {
+        let xml =
+            generate_report_response("/carddav", "testuser", &[test_contact("alice")]).unwrap();
+        assert!(xml.contains("<card:address-data>BEGIN:VCARD"));
+    }
+
+    #[test]
+    fn test_parse_report_request() {
+        let multiget = r#"<?xml version="1.0"?>
+            <C:addressbook-multiget xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:carddav">
+              <D:prop><D:getetag/><C:address-data/></D:prop>
+              <D:href>/carddav/testuser/alice.vcf</D:href>
+              <D:href>/carddav/testuser/bob.vcf</D:href>
+            </C:addressbook-multiget>"#;
+        assert_eq!(
+            parse_report_request(multiget).unwrap(),
+            AddressBookReport::Multiget {
+                hrefs: vec![
+                    "/carddav/testuser/alice.vcf".to_string(),
+                    "/carddav/testuser/bob.vcf".to_string(),
+                ]
+            }
+        );
+
+        let query = r#"<C:addressbook-query xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:carddav">
+              <D:prop><D:getetag/></D:prop></C:addressbook-query>"#;
+        assert_eq!(
+            parse_report_request(query).unwrap(),
+            AddressBookReport::Query
+        );
+
+        assert!(parse_report_request("<D:sync-collection xmlns:D=\"DAV:\"/>").is_err());
+    }
+}
@@ -0,0 +1,180 @@
+//! Slack and Microsoft Teams webhook endpoints
+//!
+//! Users paste an incoming-webhook URL from their chat app; the worker then
+//! posts invites, reminders, changes and cancellations there as well.
+
+use axum::{
+    Extension, Json, Router,
+    extract::{FromRef, Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::{delete, get},
+};
+use serde::{Deserialize, Serialize};
+use televent_application::{AddChatWebhookCommand, ChatWebhookService, ChatWebhookView};
+use televent_domain::ChatWebhookProvider;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::{error::ApiError, middleware::telegram_auth::AuthenticatedTelegramUser};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ChatProvider {
+    Slack,
+    Teams,
+}
+
+impl From<ChatProvider> for ChatWebhookProvider {
+    fn from(value: ChatProvider) -> Self {
+        match value {
+            ChatProvider::Slack => Self::Slack,
+            ChatProvider::Teams => Self::Teams,
+        }
+    }
+}
+
+impl From<ChatWebhookProvider> for ChatProvider {
+    fn from(value: ChatWebhookProvider) -> Self {
+        match value {
+            ChatWebhookProvider::Slack => Self::Slack,
+            ChatWebhookProvider::Teams => Self::Teams,
+        }
+    }
+}
+
+/// Request to connect an incoming webhook
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct AddChatWebhookRequest {
+    pub provider: ChatProvider,
+    #[schema(example = "https://hooks.slack.com/services/T000/B000/XXXXXXXX")]
+    pub url: String,
+}
+
+/// Connected webhook; the URL is a secret and only its end is shown
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ChatWebhookResponse {
+    pub id: Uuid,
+    pub provider: ChatProvider,
+    #[schema(example = "https://hooks.slack.com/…XXXX")]
+    pub url: String,
+    /// Why the chat app last refused a post for good, e.g. a deleted channel
+    pub last_error: Option<String>,
+    pub last_error_at: Option<String>,
+    pub created_at: String,
+}
+
+impl From<ChatWebhookView> for ChatWebhookResponse {
+    fn from(view: ChatWebhookView) -> Self {
+        Self {
+            id: view.id,
+            provider: view.provider.into(),
+            url: view.masked_url,
+            last_error: view.last_error,
+            last_error_at: view.last_error_at.map(|t| t.to_rfc3339()),
+            created_at: view.created_at.to_rfc3339(),
+        }
+    }
+}
+
+/// List the user's chat webhooks
+#[utoipa::path(
+    get,
+    path = "/chat-webhooks",
+    responses(
+        (status = 200, description = "Connected webhooks", body = Vec<ChatWebhookResponse>),
+        (status = 401, description = "Unauthorized")
+    ),
+    tag = "integrations",
+    security(
+        ("telegram_auth" = [])
+    )
+)]
+async fn list_chat_webhooks(
+    State(webhooks): State<ChatWebhookService>,
+    Extension(auth_user): Extension<AuthenticatedTelegramUser>,
+) -> Result<Json<Vec<ChatWebhookResponse>>, ApiError> {
+    let webhooks = webhooks.list_webhooks(auth_user.id).await?;
+
+    Ok(Json(webhooks.into_iter().map(Into::into).collect()))
+}
+
+/// Connect a Slack or Teams incoming webhook
+#[utoipa::path(
+    post,
+    path = "/chat-webhooks",
+    request_body = AddChatWebhookRequest,
+    responses(
+        (status = 201, description = "Webhook connected", body = ChatWebhookResponse),
+        (status = 400, description = "Not a webhook URL of the provider, or too many webhooks"),
+        (status = 409, description = "Webhook already connected"),
+        (status = 401, description = "Unauthorized")
+    ),
+    tag = "integrations",
+    security(
+        ("telegram_auth" = [])
+    )
+)]
+async fn add_chat_webhook(
+    State(webhooks): State<ChatWebhookService>,
+    Extension(auth_user): Extension<AuthenticatedTelegramUser>,
+    Json(request): Json<AddChatWebhookRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let webhook = webhooks
+        .add_webhook(AddChatWebhookCommand {
+            user_id: auth_user.id,
+            provider: request.provider.into(),
+            url: request.url,
+        })
+        .await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(ChatWebhookResponse::from(webhook)),
+    ))
+}
+
+/// Disconnect a chat webhook
+#[utoipa::path(
+    delete,
+    path = "/chat-webhooks/{webhook_id}",
+    responses(
+        (status = 204, description = "Webhook disconnected"),
+        (status = 404, description = "Webhook not found"),
+        (status = 401, description = "Unauthorized")
+    ),
+    params(
+        ("webhook_id" = Uuid, Path, description = "Webhook ID")
+    ),
+    tag = "integrations",
+    security(
+        ("telegram_auth" = [])
+    )
+)]
+async fn delete_chat_webhook(
+    State(webhooks): State<ChatWebhookService>,
+    Extension(auth_user): Extension<AuthenticatedTelegramUser>,
+    Path(webhook_id): Path<Uuid>,
+) -> Result<impl IntoResponse, ApiError> {
+    if !webhooks.remove_webhook(auth_user.id, webhook_id).await? {
+        return Err(ApiError::NotFound(format!(
+            "Chat webhook not found: {webhook_id}"
+        )));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Chat webhook routes
+pub fn routes<S>() -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+    ChatWebhookService: FromRef<S>,
+{
+    Router::new()
+        .route(
+            "/chat-webhooks",
+            get(list_chat_webhooks).post(add_chat_webhook),
+        )
+        .route("/chat-webhooks/{webhook_id}", delete(delete_chat_webhook))
+}
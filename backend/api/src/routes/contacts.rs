@@ -0,0 +1,158 @@
+//! Contact search endpoints
+//!
+//! Backs attendee autocomplete with the address book synced over CardDAV
+//! and with the people the user invited to earlier events.
+
+use axum::{
+    Extension, Json, Router,
+    extract::{FromRef, Query, State},
+    routing::get,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use televent_application::{
+    AttendeeSuggestion, ContactService, ContactView, DEFAULT_CONTACT_SEARCH_LIMIT,
+};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::{error::ApiError, middleware::telegram_auth::AuthenticatedTelegramUser};
+
+/// Query parameters for contact search
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ContactSearchQuery {
+    /// Name, email or Telegram username fragment; empty lists all contacts
+    pub q: Option<String>,
+    /// Maximum number of results (default 10, max 50)
+    pub limit: Option<usize>,
+}
+
+/// Contact suggested as an attendee
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ContactResponse {
+    pub uid: String,
+    #[schema(example = "Alice Liddell")]
+    pub name: String,
+    #[schema(example = "alice@example.com")]
+    pub email: Option<String>,
+    /// Telegram username without @
+    #[schema(example = "alice_l")]
+    pub telegram_username: Option<String>,
+}
+
+impl From<ContactView> for ContactResponse {
+    fn from(contact: ContactView) -> Self {
+        Self {
+            uid: contact.uid,
+            name: contact.full_name,
+            email: contact.email,
+            telegram_username: contact.telegram_username,
+        }
+    }
+}
+
+/// Previous invitee suggested for a new event
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ContactSuggestionResponse {
+    /// Address book name when known, else the username or email
+    #[schema(example = "Alice Liddell")]
+    pub name: String,
+    /// External email; absent for Telegram users
+    #[schema(example = "alice@example.com")]
+    pub email: Option<String>,
+    /// Telegram username without @
+    #[schema(example = "alice_l")]
+    pub telegram_username: Option<String>,
+    /// Number of the user's events this person was invited to
+    #[schema(example = 4)]
+    pub invite_count: i64,
+    pub last_invited_at: DateTime<Utc>,
+}
+
+impl From<AttendeeSuggestion> for ContactSuggestionResponse {
+    fn from(suggestion: AttendeeSuggestion) -> Self {
+        Self {
+            name: suggestion.name,
+            email: suggestion.email,
+            telegram_username: suggestion.telegram_username,
+            invite_count: suggestion.invite_count,
+            last_invited_at: suggestion.last_invited_at,
+        }
+    }
+}
+
+/// Search the user's contacts, best match first
+#[utoipa::path(
+    get,
+    path = "/contacts",
+    params(ContactSearchQuery),
+    responses(
+        (status = 200, description = "Matching contacts", body = Vec<ContactResponse>),
+        (status = 401, description = "Unauthorized")
+    ),
+    tag = "contacts",
+    security(
+        ("telegram_auth" = [])
+    )
+)]
+async fn search_contacts(
+    State(contacts): State<ContactService>,
+    Extension(auth_user): Extension<AuthenticatedTelegramUser>,
+    Query(query): Query<ContactSearchQuery>,
+) -> Result<Json<Vec<ContactResponse>>, ApiError> {
+    let found = contacts
+        .search_contacts(
+            auth_user.id,
+            query.q.as_deref().unwrap_or(""),
+            query.limit.unwrap_or(DEFAULT_CONTACT_SEARCH_LIMIT),
+        )
+        .await?;
+
+    Ok(Json(found.into_iter().map(ContactResponse::from).collect()))
+}
+
+/// Frequent past invitees, optionally filtered by a name fragment
+#[utoipa::path(
+    get,
+    path = "/contacts/suggestions",
+    params(ContactSearchQuery),
+    responses(
+        (status = 200, description = "Suggested attendees, most frequent first", body = Vec<ContactSuggestionResponse>),
+        (status = 401, description = "Unauthorized")
+    ),
+    tag = "contacts",
+    security(
+        ("telegram_auth" = [])
+    )
+)]
+async fn suggest_contacts(
+    State(contacts): State<ContactService>,
+    Extension(auth_user): Extension<AuthenticatedTelegramUser>,
+    Query(query): Query<ContactSearchQuery>,
+) -> Result<Json<Vec<ContactSuggestionResponse>>, ApiError> {
+    let suggestions = contacts
+        .suggest_attendees(
+            auth_user.id,
+            query.q.as_deref().unwrap_or(""),
+            query.limit.unwrap_or(DEFAULT_CONTACT_SEARCH_LIMIT),
+        )
+        .await?;
+
+    Ok(Json(
+        suggestions
+            .into_iter()
+            .map(ContactSuggestionResponse::from)
+            .collect(),
+    ))
+}
+
+/// Contact routes
+pub fn routes<S>() -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+    ContactService: FromRef<S>,
+{
+    Router::new()
+        .route("/contacts", get(search_contacts))
+        .route("/contacts/suggestions", get(suggest_contacts))
+}
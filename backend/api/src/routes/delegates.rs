@@ -0,0 +1,165 @@
+//! Calendar delegation endpoints
+//!
+//! A user lets others create, edit and delete events on their calendar.
+//! Delegates write to it through the event endpoints with `?calendar=` set
+//! to the owner's Telegram ID; every change is attributed to them in the
+//! event's revision history.
+
+use axum::{
+    Extension, Json, Router,
+    extract::{FromRef, Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::{delete, get},
+};
+use serde::{Deserialize, Serialize};
+use televent_application::{DelegateView, EventService, UserId};
+use utoipa::ToSchema;
+
+use crate::{error::ApiError, middleware::telegram_auth::AuthenticatedTelegramUser};
+
+/// Request to let another user manage the calendar
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct GrantDelegateRequest {
+    /// Telegram username of a user who has used the bot, with or without `@`
+    #[schema(example = "alice")]
+    pub username: String,
+}
+
+/// The other side of a delegation grant
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DelegateResponse {
+    /// Telegram ID
+    pub user_id: i64,
+    pub username: Option<String>,
+    pub granted_at: String,
+}
+
+impl From<DelegateView> for DelegateResponse {
+    fn from(view: DelegateView) -> Self {
+        Self {
+            user_id: view.user_id.inner(),
+            username: view.username,
+            granted_at: view.granted_at.to_rfc3339(),
+        }
+    }
+}
+
+/// List the user's delegates
+#[utoipa::path(
+    get,
+    path = "/delegates",
+    responses(
+        (status = 200, description = "Users who may manage the calendar, oldest grant first", body = Vec<DelegateResponse>),
+        (status = 401, description = "Unauthorized")
+    ),
+    tag = "delegation",
+    security(
+        ("telegram_auth" = [])
+    )
+)]
+async fn list_delegates(
+    State(events): State<EventService>,
+    Extension(auth_user): Extension<AuthenticatedTelegramUser>,
+) -> Result<Json<Vec<DelegateResponse>>, ApiError> {
+    let delegates = events.list_delegates(auth_user.id).await?;
+    Ok(Json(
+        delegates.into_iter().map(DelegateResponse::from).collect(),
+    ))
+}
+
+/// Add a delegate
+///
+/// Granting access to an existing delegate returns their grant unchanged.
+#[utoipa::path(
+    post,
+    path = "/delegates",
+    request_body = GrantDelegateRequest,
+    responses(
+        (status = 201, description = "Delegate may manage the calendar", body = DelegateResponse),
+        (status = 400, description = "The user themselves, or too many delegates"),
+        (status = 404, description = "No user with that username"),
+        (status = 401, description = "Unauthorized")
+    ),
+    tag = "delegation",
+    security(
+        ("telegram_auth" = [])
+    )
+)]
+async fn grant_delegate(
+    State(events): State<EventService>,
+    Extension(auth_user): Extension<AuthenticatedTelegramUser>,
+    Json(request): Json<GrantDelegateRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let delegate = events
+        .grant_delegate(auth_user.id, &request.username)
+        .await?;
+    Ok((StatusCode::CREATED, Json(DelegateResponse::from(delegate))))
+}
+
+/// Remove a delegate
+///
+/// The changes they made stay, attributed to them.
+#[utoipa::path(
+    delete,
+    path = "/delegates/{user_id}",
+    responses(
+        (status = 204, description = "Delegate removed"),
+        (status = 404, description = "Not a delegate"),
+        (status = 401, description = "Unauthorized")
+    ),
+    params(
+        ("user_id" = i64, Path, description = "Telegram ID of the delegate")
+    ),
+    tag = "delegation",
+    security(
+        ("telegram_auth" = [])
+    )
+)]
+async fn revoke_delegate(
+    State(events): State<EventService>,
+    Extension(auth_user): Extension<AuthenticatedTelegramUser>,
+    Path(user_id): Path<i64>,
+) -> Result<StatusCode, ApiError> {
+    events
+        .revoke_delegate(auth_user.id, UserId::new(user_id))
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// List calendars delegated to the user
+///
+/// Owners who made the user their delegate; pass an owner's `user_id` as
+/// `calendar` to the event endpoints to write to their calendar.
+#[utoipa::path(
+    get,
+    path = "/delegates/calendars",
+    responses(
+        (status = 200, description = "Owners of the calendars, oldest grant first", body = Vec<DelegateResponse>),
+        (status = 401, description = "Unauthorized")
+    ),
+    tag = "delegation",
+    security(
+        ("telegram_auth" = [])
+    )
+)]
+async fn list_delegated_calendars(
+    State(events): State<EventService>,
+    Extension(auth_user): Extension<AuthenticatedTelegramUser>,
+) -> Result<Json<Vec<DelegateResponse>>, ApiError> {
+    let owners = events.list_delegated_calendars(auth_user.id).await?;
+    Ok(Json(
+        owners.into_iter().map(DelegateResponse::from).collect(),
+    ))
+}
+
+pub fn routes<S>() -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+    EventService: FromRef<S>,
+{
+    Router::new()
+        .route("/delegates", get(list_delegates).post(grant_delegate))
+        .route("/delegates/calendars", get(list_delegated_calendars))
+        .route("/delegates/{user_id}", delete(revoke_delegate))
+}
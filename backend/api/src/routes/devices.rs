@@ -4,16 +4,18 @@
 
 use axum::{
     Extension, Json, Router,
-    extract::{FromRef, Path, State},
-    http::StatusCode,
-    response::IntoResponse,
+    extract::{FromRef, Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
     routing::{delete, get, post},
 };
 use serde::{Deserialize, Serialize};
-use televent_application::{CreateDevicePasswordCommand, DeviceService, validate_device_name};
+use televent_application::{
+    CreateDevicePasswordCommand, DeviceId, DeviceService, parse_duration_spec, validate_device_name,
+};
 use utoipa::ToSchema;
-use uuid::Uuid;
 
+use super::etag::json_with_etag;
 use crate::{error::ApiError, middleware::telegram_auth::AuthenticatedTelegramUser};
 
 /// Request to create a new device password
@@ -34,7 +36,7 @@ impl CreateDeviceRequest {
 /// Response containing generated device password
 #[derive(Debug, Serialize, ToSchema)]
 pub struct DevicePasswordResponse {
-    pub id: Uuid,
+    pub id: DeviceId,
     pub name: String,
     /// Plain text password - only shown once at creation
     #[schema(example = "aB1c2D3e4F5g6H7i8J9k0L1m")]
@@ -46,10 +48,50 @@ pub struct DevicePasswordResponse {
 /// Device password list item (without password)
 #[derive(Debug, Serialize, ToSchema)]
 pub struct DeviceListItem {
-    pub id: Uuid,
+    pub id: DeviceId,
     pub name: String,
     pub created_at: String,
     pub last_used_at: Option<String>,
+    /// CalDAV requests made with the password over the last 24 hours
+    #[schema(example = 12)]
+    pub recent_requests: i64,
+}
+
+/// List devices query parameters
+#[derive(Debug, Deserialize, ToSchema, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListDevicesQuery {
+    /// Only devices used within this long, e.g. `7d` or `P7D`
+    #[schema(example = "7d")]
+    pub active_within: Option<String>,
+}
+
+/// Recent CalDAV request made with a device password
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DeviceActivityItem {
+    #[schema(example = "REPORT")]
+    pub method: String,
+    pub path: String,
+    pub user_agent: Option<String>,
+    #[schema(example = 207)]
+    pub status: u16,
+    /// Sync token the client presented, for sync-collection reports
+    pub sync_token: Option<String>,
+    /// Stale edit the request was turned away for
+    pub conflict: Option<SequenceConflictItem>,
+    pub at: String,
+}
+
+/// A PUT rejected because the device's copy of the event was out of date
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SequenceConflictItem {
+    pub uid: String,
+    /// `SEQUENCE` the device sent
+    #[schema(example = 2)]
+    pub client_sequence: i32,
+    /// `SEQUENCE` of the stored event
+    #[schema(example = 3)]
+    pub server_sequence: i32,
 }
 
 /// Create a new device password
@@ -92,12 +134,15 @@ async fn create_device_password(
     ))
 }
 
-/// List all device passwords for a user
+/// List the user's device passwords, most recently used first
 #[utoipa::path(
     get,
     path = "/devices",
+    params(ListDevicesQuery),
     responses(
         (status = 200, description = "List of device passwords", body = Vec<DeviceListItem>),
+        (status = 304, description = "Unchanged since the ETag in If-None-Match"),
+        (status = 400, description = "Invalid active_within"),
         (status = 401, description = "Unauthorized")
     ),
     tag = "devices",
@@ -108,8 +153,17 @@ async fn create_device_password(
 async fn list_device_passwords(
     State(device_service): State<DeviceService>,
     Extension(auth_user): Extension<AuthenticatedTelegramUser>,
-) -> Result<Json<Vec<DeviceListItem>>, ApiError> {
-    let devices = device_service.list_device_passwords(auth_user.id).await?;
+    Query(query): Query<ListDevicesQuery>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let active_within = query
+        .active_within
+        .as_deref()
+        .map(parse_duration_spec)
+        .transpose()?;
+    let devices = device_service
+        .list_device_passwords(auth_user.id, active_within)
+        .await?;
 
     let response: Vec<DeviceListItem> = devices
         .into_iter()
@@ -118,10 +172,11 @@ async fn list_device_passwords(
             name: d.name,
             created_at: d.created_at.to_rfc3339(),
             last_used_at: d.last_used_at.map(|t| t.to_rfc3339()),
+            recent_requests: d.recent_requests,
         })
         .collect();
 
-    Ok(Json(response))
+    json_with_etag(&headers, &response)
 }
 
 /// Delete a device password
@@ -134,7 +189,7 @@ async fn list_device_passwords(
         (status = 401, description = "Unauthorized")
     ),
     params(
-        ("device_id" = Uuid, Path, description = "Device ID")
+        ("device_id" = DeviceId, Path, description = "Device ID")
     ),
     tag = "devices",
     security(
@@ -144,7 +199,7 @@ async fn list_device_passwords(
 async fn delete_device_password(
     State(device_service): State<DeviceService>,
     Extension(auth_user): Extension<AuthenticatedTelegramUser>,
-    Path(device_id): Path<Uuid>,
+    Path(device_id): Path<DeviceId>,
 ) -> Result<impl IntoResponse, ApiError> {
     let revoked = device_service
         .revoke_device_password(auth_user.id, device_id)
@@ -160,6 +215,52 @@ async fn delete_device_password(
     Ok(StatusCode::NO_CONTENT)
 }
 
+/// Recent CalDAV requests made with a device password, newest first
+#[utoipa::path(
+    get,
+    path = "/devices/{device_id}/activity",
+    responses(
+        (status = 200, description = "Recent requests from the device", body = Vec<DeviceActivityItem>),
+        (status = 404, description = "Device password not found"),
+        (status = 401, description = "Unauthorized")
+    ),
+    params(
+        ("device_id" = DeviceId, Path, description = "Device ID")
+    ),
+    tag = "devices",
+    security(
+        ("telegram_auth" = [])
+    )
+)]
+async fn get_device_activity(
+    State(device_service): State<DeviceService>,
+    Extension(auth_user): Extension<AuthenticatedTelegramUser>,
+    Path(device_id): Path<DeviceId>,
+) -> Result<Json<Vec<DeviceActivityItem>>, ApiError> {
+    let activity = device_service
+        .device_activity(auth_user.id, device_id)
+        .await?;
+
+    let response = activity
+        .into_iter()
+        .map(|a| DeviceActivityItem {
+            method: a.method,
+            path: a.path,
+            user_agent: a.user_agent,
+            status: a.status,
+            sync_token: a.sync_token,
+            conflict: a.conflict.map(|c| SequenceConflictItem {
+                uid: c.uid,
+                client_sequence: c.client_sequence,
+                server_sequence: c.server_sequence,
+            }),
+            at: a.at.to_rfc3339(),
+        })
+        .collect();
+
+    Ok(Json(response))
+}
+
 /// Device password routes
 pub fn routes<S>() -> Router<S>
 where
@@ -170,11 +271,121 @@ where
         .route("/devices", post(create_device_password))
         .route("/devices", get(list_device_passwords))
         .route("/devices/{device_id}", delete(delete_device_password))
+        .route("/devices/{device_id}/activity", get(get_device_activity))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use axum::{body::Body, http::Request};
+    use televent_application::UserId;
+    use televent_domain::Timezone;
+    use televent_storage::device::StoredDevicePassword;
+    use televent_storage::memory::MemoryDevices;
+    use televent_storage::repos::DevicesRepo;
+    use tower::ServiceExt;
+
+    const USER: UserId = UserId(42);
+
+    fn app(devices: &MemoryDevices) -> Router {
+        routes()
+            .with_state(DeviceService::new(devices.clone()))
+            .layer(Extension(AuthenticatedTelegramUser {
+                id: USER,
+                username: None,
+                timezone: Timezone::default(),
+            }))
+    }
+
+    async fn send(app: Router, method: &str, uri: &str, body: &str) -> (StatusCode, String) {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    async fn seed(devices: &MemoryDevices, name: &str) -> DeviceId {
+        devices
+            .create_device_password(
+                None,
+                StoredDevicePassword {
+                    user_id: USER,
+                    name: name.to_string(),
+                    password_hash: "hash".to_string(),
+                },
+                i64::MAX,
+            )
+            .await
+            .unwrap()
+            .unwrap()
+            .id
+    }
+
+    #[tokio::test]
+    async fn created_password_is_listed_without_the_secret() {
+        let devices = MemoryDevices::default();
+
+        let (status, body) = send(app(&devices), "POST", "/devices", r#"{"name":"iPhone"}"#).await;
+        assert_eq!(status, StatusCode::CREATED);
+        let created: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(created["password"].as_str().unwrap().len(), 24);
+
+        let (status, body) = send(app(&devices), "GET", "/devices", "").await;
+        assert_eq!(status, StatusCode::OK);
+        let listed: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(listed[0]["id"], created["id"]);
+        assert_eq!(listed[0]["name"], "iPhone");
+        assert!(listed[0].get("password").is_none());
+    }
+
+    #[tokio::test]
+    async fn eleventh_device_is_rejected() {
+        let devices = MemoryDevices::default();
+        for n in 0..10 {
+            seed(&devices, &format!("device {n}")).await;
+        }
+
+        let (status, body) =
+            send(app(&devices), "POST", "/devices", r#"{"name":"one more"}"#).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body.contains("Maximum number of devices"));
+    }
+
+    #[tokio::test]
+    async fn revoking_someone_elses_device_is_not_found() {
+        let devices = MemoryDevices::default();
+        let other = devices
+            .create_device_password(
+                None,
+                StoredDevicePassword {
+                    user_id: UserId(7),
+                    name: "theirs".to_string(),
+                    password_hash: "hash".to_string(),
+                },
+                i64::MAX,
+            )
+            .await
+            .unwrap()
+            .unwrap();
+
+        let uri = format!("/devices/{}", other.id);
+        let (status, _) = send(app(&devices), "DELETE", &uri, "").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let own = seed(&devices, "mine").await;
+        let (status, _) = send(app(&devices), "DELETE", &format!("/devices/{own}"), "").await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let (_, body) = send(app(&devices), "GET", "/devices", "").await;
+        assert_eq!(body, "[]");
+    }
 
     #[test]
     fn test_create_device_request_validation_success() {
@@ -0,0 +1,105 @@
+//! Links from email verification messages
+//!
+//! `GET /verify-email/{token}` only asks for confirmation, like the
+//! unsubscribe page, so link scanners that prefetch URLs in incoming mail
+//! neither confirm the address nor use up its attempts. The form posts back
+//! to the same URL.
+
+use axum::{
+    Extension, Router,
+    extract::{FromRef, Path, State},
+    http::StatusCode,
+    response::{Html, IntoResponse, Response},
+    routing::get,
+};
+use televent_application::{ApplicationError, EmailVerificationKey, NotificationService};
+
+use super::public_events::{escape_html, page};
+
+async fn verification_page(
+    Extension(key): Extension<EmailVerificationKey>,
+    Path(token): Path<String>,
+) -> Response {
+    if key.verify(&token).is_none() {
+        return invalid_link();
+    }
+
+    Html(page(
+        "Confirm your email",
+        &format!(
+            "<h1>Confirm your email</h1>\
+             <p>Use this address for Televent notifications?</p>\
+             <form method=\"post\" action=\"/verify-email/{}\">\
+             <p><button type=\"submit\">Confirm</button></p>\
+             </form>",
+            escape_html(&token)
+        ),
+    ))
+    .into_response()
+}
+
+async fn verify(
+    State(notifications): State<NotificationService>,
+    Extension(key): Extension<EmailVerificationKey>,
+    Path(token): Path<String>,
+) -> Response {
+    let Some((user_id, code)) = key.verify(&token) else {
+        return invalid_link();
+    };
+    match notifications.verify_email(user_id, &code).await {
+        Ok(email) => Html(page(
+            "Email confirmed",
+            &format!(
+                "<h1>Email confirmed</h1>\
+                 <p><strong>{}</strong> can now get your Televent notifications.</p>",
+                escape_html(&email.email)
+            ),
+        ))
+        .into_response(),
+        Err(
+            ApplicationError::NotFound(_)
+            | ApplicationError::BadRequest(_)
+            | ApplicationError::Conflict(_),
+        ) => (
+            StatusCode::GONE,
+            Html(page(
+                "Televent",
+                "<p>This link has expired. Send /email with your address to the bot for a new one.</p>",
+            )),
+        )
+            .into_response(),
+        Err(err) => {
+            tracing::error!("Email verification failed: {}", err);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Html(page(
+                    "Televent",
+                    "<p>Something went wrong. Please try again later.</p>",
+                )),
+            )
+                .into_response()
+        }
+    }
+}
+
+fn invalid_link() -> Response {
+    (
+        StatusCode::NOT_FOUND,
+        Html(page(
+            "Televent",
+            "<p>This confirmation link is not valid.</p>",
+        )),
+    )
+        .into_response()
+}
+
+/// Email verification routes (no authentication; the token is the credential)
+pub fn routes<S>(key: EmailVerificationKey) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+    NotificationService: FromRef<S>,
+{
+    Router::new()
+        .route("/verify-email/{token}", get(verification_page).post(verify))
+        .layer(Extension(key))
+}
@@ -0,0 +1,299 @@
+//! Email provider delivery webhooks
+//!
+//! Amazon SES (through SNS), SendGrid and Mailgun post bounce and complaint
+//! events to `/webhooks/email/{provider}/{secret}`. The secret is the
+//! deployment's `EMAIL_WEBHOOK_SECRET`; configure the full URL at the
+//! provider. Reports are normalized and handed to [`EmailService`], which
+//! suppresses dead addresses and tells affected organizers.
+
+use axum::{
+    Extension, Json, Router,
+    body::Bytes,
+    extract::{FromRef, Path, State},
+    routing::post,
+};
+use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use televent_application::{EmailFeedback, EmailFeedbackKind, EmailService};
+
+use crate::error::ApiError;
+
+/// Shared secret in the webhook URL
+#[derive(Clone)]
+struct WebhookSecret(String);
+
+/// Outcome returned to the provider
+#[derive(Debug, Serialize)]
+pub struct EmailWebhookResponse {
+    pub recorded: usize,
+    pub suppressed: usize,
+}
+
+async fn email_webhook(
+    State(email): State<EmailService>,
+    Extension(WebhookSecret(expected)): Extension<WebhookSecret>,
+    Path((provider, secret)): Path<(String, String)>,
+    body: Bytes,
+) -> Result<Json<EmailWebhookResponse>, ApiError> {
+    // Compare digests so the check does not leak how much of the secret matched
+    if Sha256::digest(secret.as_bytes()) != Sha256::digest(expected.as_bytes()) {
+        return Err(ApiError::NotFound("Not found".to_string()));
+    }
+
+    let payload: Value = serde_json::from_slice(&body)
+        .map_err(|e| ApiError::BadRequest(format!("Invalid JSON: {e}")))?;
+    let reports = match provider.as_str() {
+        "ses" => parse_ses(&payload)?,
+        "sendgrid" => parse_sendgrid(&payload)?,
+        "mailgun" => parse_mailgun(&payload)?,
+        _ => return Err(ApiError::NotFound(format!("Unknown provider: {provider}"))),
+    };
+
+    let outcome = email.record_feedback(&provider, reports).await?;
+    if outcome.suppressed > 0 {
+        tracing::info!(
+            "Suppressed {} address(es) after {} report(s) from {}",
+            outcome.suppressed,
+            outcome.recorded,
+            provider
+        );
+    }
+
+    Ok(Json(EmailWebhookResponse {
+        recorded: outcome.recorded,
+        suppressed: outcome.suppressed,
+    }))
+}
+
+/// SES notifications wrapped in an SNS envelope
+///
+/// SNS first sends a `SubscriptionConfirmation`; it is logged for the operator
+/// to confirm by hand rather than fetched, so the webhook never makes
+/// outbound requests to URLs from a request body.
+fn parse_ses(envelope: &Value) -> Result<Vec<EmailFeedback>, ApiError> {
+    match envelope["Type"].as_str() {
+        Some("SubscriptionConfirmation") => {
+            tracing::warn!(
+                "SES webhook needs confirming; open this URL once: {}",
+                envelope["SubscribeURL"].as_str().unwrap_or("(missing)")
+            );
+            return Ok(Vec::new());
+        }
+        Some("Notification") => {}
+        _ => return Err(ApiError::BadRequest("Unsupported SNS message".to_string())),
+    }
+
+    // SNS delivers the SES notification as a JSON string
+    let message: Value = envelope["Message"]
+        .as_str()
+        .and_then(|message| serde_json::from_str(message).ok())
+        .ok_or_else(|| ApiError::BadRequest("Missing SES notification".to_string()))?;
+
+    let reports = match message["notificationType"].as_str() {
+        Some("Bounce") => {
+            let bounce = &message["bounce"];
+            let kind = if bounce["bounceType"].as_str() == Some("Permanent") {
+                EmailFeedbackKind::HardBounce
+            } else {
+                EmailFeedbackKind::SoftBounce
+            };
+            recipients(&bounce["bouncedRecipients"])
+                .map(|(email, recipient)| EmailFeedback {
+                    email,
+                    kind,
+                    detail: recipient["diagnosticCode"].as_str().map(str::to_string),
+                })
+                .collect()
+        }
+        Some("Complaint") => recipients(&message["complaint"]["complainedRecipients"])
+            .map(|(email, _)| EmailFeedback {
+                email,
+                kind: EmailFeedbackKind::Complaint,
+                detail: message["complaint"]["complaintFeedbackType"]
+                    .as_str()
+                    .map(str::to_string),
+            })
+            .collect(),
+        // Deliveries and other notification types carry no feedback
+        _ => Vec::new(),
+    };
+    Ok(reports)
+}
+
+fn recipients(list: &Value) -> impl Iterator<Item = (String, &Value)> {
+    list.as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|recipient| {
+            recipient["emailAddress"]
+                .as_str()
+                .map(|email| (email.to_string(), recipient))
+        })
+}
+
+/// SendGrid event webhook: a JSON array of events
+fn parse_sendgrid(events: &Value) -> Result<Vec<EmailFeedback>, ApiError> {
+    let events = events
+        .as_array()
+        .ok_or_else(|| ApiError::BadRequest("Expected an array of events".to_string()))?;
+
+    Ok(events
+        .iter()
+        .filter_map(|event| {
+            let kind = match (event["event"].as_str()?, event["type"].as_str()) {
+                // "blocked" bounces are policy refusals, not dead addresses
+                ("bounce", Some("blocked")) | ("deferred", _) => EmailFeedbackKind::SoftBounce,
+                ("bounce", _) => EmailFeedbackKind::HardBounce,
+                ("spamreport", _) => EmailFeedbackKind::Complaint,
+                _ => return None,
+            };
+            Some(EmailFeedback {
+                email: event["email"].as_str()?.to_string(),
+                kind,
+                detail: event["reason"]
+                    .as_str()
+                    .or_else(|| event["response"].as_str())
+                    .map(str::to_string),
+            })
+        })
+        .collect())
+}
+
+/// Mailgun webhook: one event under `event-data`
+fn parse_mailgun(payload: &Value) -> Result<Vec<EmailFeedback>, ApiError> {
+    let event = &payload["event-data"];
+    let name = event["event"]
+        .as_str()
+        .ok_or_else(|| ApiError::BadRequest("Missing event-data".to_string()))?;
+    let kind = match (name, event["severity"].as_str()) {
+        ("failed", Some("permanent")) => EmailFeedbackKind::HardBounce,
+        ("failed", _) => EmailFeedbackKind::SoftBounce,
+        ("complained", _) => EmailFeedbackKind::Complaint,
+        _ => return Ok(Vec::new()),
+    };
+    let Some(email) = event["recipient"].as_str() else {
+        return Ok(Vec::new());
+    };
+
+    Ok(vec![EmailFeedback {
+        email: email.to_string(),
+        kind,
+        detail: event["delivery-status"]["message"]
+            .as_str()
+            .or_else(|| event["reason"].as_str())
+            .map(str::to_string),
+    }])
+}
+
+/// Email webhook routes; not mounted without a secret
+pub fn routes<S>(secret: Option<String>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+    EmailService: FromRef<S>,
+{
+    let Some(secret) = secret.filter(|secret| !secret.is_empty()) else {
+        return Router::new();
+    };
+    Router::new()
+        .route("/webhooks/email/{provider}/{secret}", post(email_webhook))
+        .layer(Extension(WebhookSecret(secret)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_ses_bounce_and_complaint() {
+        let bounce = json!({
+            "bounceType": "Permanent",
+            "bouncedRecipients": [
+                {"emailAddress": "gone@example.com", "diagnosticCode": "550 5.1.1 unknown user"}
+            ]
+        });
+        let envelope = json!({
+            "Type": "Notification",
+            "Message": json!({"notificationType": "Bounce", "bounce": bounce}).to_string(),
+        });
+        assert_eq!(
+            parse_ses(&envelope).unwrap(),
+            vec![EmailFeedback {
+                email: "gone@example.com".to_string(),
+                kind: EmailFeedbackKind::HardBounce,
+                detail: Some("550 5.1.1 unknown user".to_string()),
+            }]
+        );
+
+        let envelope = json!({
+            "Type": "Notification",
+            "Message": json!({
+                "notificationType": "Complaint",
+                "complaint": {"complainedRecipients": [{"emailAddress": "angry@example.com"}]}
+            })
+            .to_string(),
+        });
+        let reports = parse_ses(&envelope).unwrap();
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].kind, EmailFeedbackKind::Complaint);
+
+        let confirmation =
+            json!({"Type": "SubscriptionConfirmation", "SubscribeURL": "https://sns"});
+        assert!(parse_ses(&confirmation).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_parse_sendgrid_events() {
+        let events = json!([
+            {"email": "gone@example.com", "event": "bounce", "type": "bounce", "reason": "550"},
+            {"email": "busy@example.com", "event": "bounce", "type": "blocked"},
+            {"email": "spam@example.com", "event": "spamreport"},
+            {"email": "ok@example.com", "event": "delivered"}
+        ]);
+        let kinds = parse_sendgrid(&events)
+            .unwrap()
+            .into_iter()
+            .map(|report| (report.email, report.kind))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            vec![
+                (
+                    "gone@example.com".to_string(),
+                    EmailFeedbackKind::HardBounce
+                ),
+                (
+                    "busy@example.com".to_string(),
+                    EmailFeedbackKind::SoftBounce
+                ),
+                ("spam@example.com".to_string(), EmailFeedbackKind::Complaint),
+            ]
+        );
+        assert!(parse_sendgrid(&json!({})).is_err());
+    }
+
+    #[test]
+    fn test_parse_mailgun_event() {
+        let payload = json!({
+            "signature": {},
+            "event-data": {
+                "event": "failed",
+                "severity": "permanent",
+                "recipient": "gone@example.com",
+                "delivery-status": {"message": "No such mailbox"}
+            }
+        });
+        assert_eq!(
+            parse_mailgun(&payload).unwrap(),
+            vec![EmailFeedback {
+                email: "gone@example.com".to_string(),
+                kind: EmailFeedbackKind::HardBounce,
+                detail: Some("No such mailbox".to_string()),
+            }]
+        );
+
+        let delivered = json!({"event-data": {"event": "delivered", "recipient": "a@b.c"}});
+        assert!(parse_mailgun(&delivered).unwrap().is_empty());
+    }
+}
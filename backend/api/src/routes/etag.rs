@@ -0,0 +1,95 @@
+//! Conditional GETs for JSON endpoints
+//!
+//! The ETag is a hash of the serialized body, so clients polling a list get
+//! a bodiless 304 until something in it changes.
+
+use axum::{
+    http::{HeaderMap, HeaderValue, StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::error::ApiError;
+
+/// `body` as JSON with an ETag, or 304 when `If-None-Match` already has it
+pub(crate) fn json_with_etag<T: Serialize>(
+    headers: &HeaderMap,
+    body: &T,
+) -> Result<Response, ApiError> {
+    let json = serde_json::to_vec(body)
+        .map_err(|e| ApiError::Internal(format!("Failed to serialize response: {e}")))?;
+    let digest = Sha256::digest(&json);
+    let etag = format!("\"{}\"", hex::encode(&digest[..16]));
+    let etag_value = HeaderValue::from_str(&etag)
+        .map_err(|e| ApiError::Internal(format!("Invalid ETag: {e}")))?;
+    let cache_control = HeaderValue::from_static("private, no-cache");
+
+    if if_none_match(headers, &etag) {
+        return Ok((
+            StatusCode::NOT_MODIFIED,
+            [
+                (header::ETAG, etag_value),
+                (header::CACHE_CONTROL, cache_control),
+            ],
+        )
+            .into_response());
+    }
+
+    Ok((
+        [
+            (
+                header::CONTENT_TYPE,
+                HeaderValue::from_static("application/json"),
+            ),
+            (header::ETAG, etag_value),
+            (header::CACHE_CONTROL, cache_control),
+        ],
+        json,
+    )
+        .into_response())
+}
+
+/// Weak comparison, as RFC 9110 asks for `If-None-Match`
+fn if_none_match(headers: &HeaderMap, etag: &str) -> bool {
+    headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .any(|candidate| candidate == "*" || candidate.trim_start_matches("W/") == etag)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn etag_of(response: &Response) -> String {
+        response.headers()[header::ETAG]
+            .to_str()
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn matching_etag_is_not_modified() {
+        let body = vec!["a", "b"];
+        let fresh = json_with_etag(&HeaderMap::new(), &body).unwrap();
+        assert_eq!(fresh.status(), StatusCode::OK);
+        let etag = etag_of(&fresh);
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::IF_NONE_MATCH,
+            HeaderValue::from_str(&format!("\"other\", W/{etag}")).unwrap(),
+        );
+        let cached = json_with_etag(&headers, &body).unwrap();
+        assert_eq!(cached.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(etag_of(&cached), etag);
+
+        let changed = json_with_etag(&headers, &vec!["a"]).unwrap();
+        assert_eq!(changed.status(), StatusCode::OK);
+        assert_ne!(etag_of(&changed), etag);
+    }
+}
@@ -1,20 +1,29 @@
 //! Event REST API endpoints
 
-use crate::{error::ApiError, middleware::telegram_auth::AuthenticatedTelegramUser};
+use crate::{
+    error::ApiError,
+    middleware::{base_path::BasePath, telegram_auth::AuthenticatedTelegramUser},
+};
 use axum::{
     Extension, Json, Router,
+    body::Bytes,
     extract::{FromRef, Path, Query, State},
     http::{HeaderMap, StatusCode, header},
     response::{IntoResponse, Response},
     routing::{delete, get, post, put},
 };
-use chrono::{DateTime, NaiveDate, Utc};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
 use serde::{Deserialize, Deserializer, Serialize};
-use televent_application::{CalendarService, CreateEventCommand, EventView, UpdateEventCommand};
+use televent_application::{
+    CalendarService, CreateEventCommand, DuplicateEventCommand, EXTERNAL_INVITES_FLAG,
+    EditScope as DomainEditScope, EventRevisionView, EventService, EventView, FeatureFlagService,
+    GridEvent, MonthGrid, TimezoneFixCommand, UpdateEventCommand, UserId, parse_month,
+    validate_event_fields,
+};
 use televent_domain::{
-    EventStatus as DomainEventStatus, EventTiming, MAX_DESCRIPTION_LENGTH, MAX_LOCATION_LENGTH,
-    MAX_RRULE_LENGTH, MAX_SUMMARY_LENGTH, MAX_UID_LENGTH, Timezone, validate_length,
-    validate_no_control_chars, validate_rrule, validate_safe_multiline_text,
+    EventStatus as DomainEventStatus, EventTiming, EventVisibility as DomainEventVisibility,
+    Timezone,
+    tags::{event_tags, normalize_tag},
 };
 use utoipa::ToSchema;
 use uuid::Uuid;
@@ -40,20 +49,39 @@ pub struct CreateEventRequest {
     /// RFC 5545 recurrence rule
     #[schema(example = "FREQ=WEEKLY;BYDAY=MO")]
     pub rrule: Option<String>,
+    /// Who sees the event's details; the user's default when left out
+    pub visibility: Option<EventVisibility>,
+    /// Appended to the event's reminders
+    #[schema(example = "Bring laptop!")]
+    pub reminder_note: Option<String>,
 }
 
+/// When the event happens; datetime and date fields can't be mixed
 #[derive(Debug, Clone, Deserialize, ToSchema)]
-#[serde(tag = "kind", rename_all = "snake_case")]
+#[serde(tag = "kind", rename_all = "snake_case", deny_unknown_fields)]
 pub enum EventTimingRequest {
     Timed {
         start: DateTime<Utc>,
         end: DateTime<Utc>,
+        #[schema(example = "Europe/Berlin")]
         timezone: String,
     },
+    /// Whole days, e.g. a three-day conference is `2026-06-01` to `2026-06-04`
     AllDay {
+        #[schema(example = "2026-06-01")]
         start_date: NaiveDate,
+        /// Day after the last day, as iCalendar's DTEND (exclusive)
+        #[schema(example = "2026-06-04")]
         end_date: NaiveDate,
     },
+    /// Wall-clock times without a zone, e.g. a New Year countdown that
+    /// starts at midnight wherever the user is
+    Floating {
+        #[schema(example = "2026-12-31T23:50:00")]
+        start: NaiveDateTime,
+        #[schema(example = "2027-01-01T00:10:00")]
+        end: NaiveDateTime,
+    },
 }
 
 impl EventTimingRequest {
@@ -76,39 +104,14 @@ impl EventTimingRequest {
                 start_date,
                 end_date,
             }),
+            Self::Floating { start, end } => Ok(EventTiming::Floating { start, end }),
         }
     }
 }
 
 impl CreateEventRequest {
     pub fn validate(&self) -> Result<(), ApiError> {
-        validate_length("UID", &self.uid, MAX_UID_LENGTH).map_err(ApiError::BadRequest)?;
-        validate_no_control_chars("UID", &self.uid).map_err(ApiError::BadRequest)?;
-
-        validate_length("Summary", &self.summary, MAX_SUMMARY_LENGTH)
-            .map_err(ApiError::BadRequest)?;
-        validate_no_control_chars("Summary", &self.summary).map_err(ApiError::BadRequest)?;
-
-        if let Some(description) = &self.description {
-            validate_length("Description", description, MAX_DESCRIPTION_LENGTH)
-                .map_err(ApiError::BadRequest)?;
-            validate_safe_multiline_text("Description", description)
-                .map_err(ApiError::BadRequest)?;
-        }
-
-        if let Some(location) = &self.location {
-            validate_length("Location", location, MAX_LOCATION_LENGTH)
-                .map_err(ApiError::BadRequest)?;
-            validate_no_control_chars("Location", location).map_err(ApiError::BadRequest)?;
-        }
-
-        if let Some(rrule) = &self.rrule {
-            validate_length("RRule", rrule, MAX_RRULE_LENGTH).map_err(ApiError::BadRequest)?;
-            validate_no_control_chars("RRule", rrule).map_err(ApiError::BadRequest)?;
-            validate_rrule(rrule).map_err(|err| ApiError::BadRequest(err.to_string()))?;
-        }
-
-        Ok(())
+        validate_event_fields(Some(&self.uid), self.rrule.as_deref()).map_err(ApiError::from)
     }
 }
 
@@ -124,39 +127,51 @@ pub struct UpdateEventRequest {
     pub status: Option<EventStatus>,
     #[serde(default, deserialize_with = "deserialize_nullable_update")]
     pub rrule: Option<Option<String>>,
+    pub visibility: Option<EventVisibility>,
+    /// Appended to the event's reminders; `null` removes it
+    #[serde(default, deserialize_with = "deserialize_nullable_update")]
+    pub reminder_note: Option<Option<String>>,
+    /// Which occurrences of a recurring event to change; defaults to the
+    /// whole series
+    pub edit_scope: Option<EditScope>,
+    /// Original start of the occurrence being edited, required unless the
+    /// scope is the whole series
+    #[schema(example = "2026-06-08T10:00:00Z")]
+    pub occurrence_start: Option<DateTime<Utc>>,
 }
 
 impl UpdateEventRequest {
     pub fn validate(&self) -> Result<(), ApiError> {
-        if let Some(summary) = &self.summary {
-            validate_length("Summary", summary, MAX_SUMMARY_LENGTH)
-                .map_err(ApiError::BadRequest)?;
-            validate_no_control_chars("Summary", summary).map_err(ApiError::BadRequest)?;
-        }
-
-        if let Some(Some(description)) = &self.description {
-            validate_length("Description", description, MAX_DESCRIPTION_LENGTH)
-                .map_err(ApiError::BadRequest)?;
-            validate_safe_multiline_text("Description", description)
-                .map_err(ApiError::BadRequest)?;
-        }
-
-        if let Some(Some(location)) = &self.location {
-            validate_length("Location", location, MAX_LOCATION_LENGTH)
-                .map_err(ApiError::BadRequest)?;
-            validate_no_control_chars("Location", location).map_err(ApiError::BadRequest)?;
-        }
-
-        if let Some(Some(rrule)) = &self.rrule {
-            validate_length("RRule", rrule, MAX_RRULE_LENGTH).map_err(ApiError::BadRequest)?;
-            validate_no_control_chars("RRule", rrule).map_err(ApiError::BadRequest)?;
-            validate_rrule(rrule).map_err(|err| ApiError::BadRequest(err.to_string()))?;
-        }
+        validate_event_fields(None, self.rrule.as_ref().and_then(Option::as_deref))
+            .map_err(ApiError::from)
+    }
 
-        Ok(())
+    fn scope(&self) -> Result<DomainEditScope, ApiError> {
+        let occurrence_start = || {
+            self.occurrence_start.ok_or_else(|| {
+                ApiError::BadRequest("occurrence_start is required for this edit_scope".to_string())
+            })
+        };
+        Ok(match self.edit_scope.unwrap_or(EditScope::Series) {
+            EditScope::Series => DomainEditScope::Series,
+            EditScope::Occurrence => DomainEditScope::Occurrence(occurrence_start()?),
+            EditScope::Following => DomainEditScope::Following(occurrence_start()?),
+        })
     }
 }
 
+/// Which occurrences of a recurring event an update changes
+///
+/// `occurrence` detaches the one occurrence into an event of its own;
+/// `following` ends the series before it and starts a new one from it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum EditScope {
+    Series,
+    Occurrence,
+    Following,
+}
+
 fn deserialize_nullable_update<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
 where
     D: Deserializer<'de>,
@@ -192,6 +207,38 @@ impl From<DomainEventStatus> for EventStatus {
     }
 }
 
+/// How much of an event people other than its owner see
+///
+/// `busy_only` events show up for others as a "Busy" block without details;
+/// `private` ones only count as busy time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum EventVisibility {
+    Public,
+    BusyOnly,
+    Private,
+}
+
+impl EventVisibility {
+    pub(crate) fn into_domain(self) -> DomainEventVisibility {
+        match self {
+            Self::Public => DomainEventVisibility::Public,
+            Self::BusyOnly => DomainEventVisibility::BusyOnly,
+            Self::Private => DomainEventVisibility::Private,
+        }
+    }
+}
+
+impl From<DomainEventVisibility> for EventVisibility {
+    fn from(value: DomainEventVisibility) -> Self {
+        match value {
+            DomainEventVisibility::Public => Self::Public,
+            DomainEventVisibility::BusyOnly => Self::BusyOnly,
+            DomainEventVisibility::Private => Self::Private,
+        }
+    }
+}
+
 /// List events query parameters
 #[derive(Debug, Deserialize, ToSchema, utoipa::IntoParams)]
 #[into_params(parameter_in = Query)]
@@ -206,6 +253,120 @@ pub struct ListEventsQuery {
     /// Number of events to skip
     #[schema(default = 0)]
     pub offset: Option<i64>,
+    /// Only events with this status; cancelled events are left out otherwise
+    pub status: Option<EventStatus>,
+    /// Only events with this `#hashtag` in their title, with or without the `#`
+    pub tag: Option<String>,
+}
+
+impl ListEventsQuery {
+    fn statuses(&self) -> Vec<DomainEventStatus> {
+        match self.status {
+            Some(status) => vec![status.into_domain()],
+            None => vec![DomainEventStatus::Confirmed, DomainEventStatus::Tentative],
+        }
+    }
+
+    fn tag(&self) -> Result<Option<String>, ApiError> {
+        self.tag
+            .as_deref()
+            .map(|tag| {
+                normalize_tag(tag)
+                    .ok_or_else(|| ApiError::BadRequest(format!("Invalid tag: {tag}")))
+            })
+            .transpose()
+    }
+}
+
+/// Whose calendar a write goes to
+#[derive(Debug, Deserialize, ToSchema, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CalendarQuery {
+    /// Telegram ID of an owner who made the user their delegate; the user's
+    /// own calendar by default
+    pub calendar: Option<i64>,
+}
+
+impl CalendarQuery {
+    pub(crate) fn owner(&self, auth_user: &AuthenticatedTelegramUser) -> UserId {
+        self.calendar.map_or(auth_user.id, UserId::new)
+    }
+}
+
+/// Month grid query parameters
+#[derive(Debug, Deserialize, ToSchema, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct MonthGridQuery {
+    /// Month to show
+    #[schema(example = "2025-03")]
+    pub month: String,
+    /// IANA timezone the days are taken in; the user's timezone by default
+    #[schema(example = "Europe/Berlin")]
+    pub tz: Option<String>,
+}
+
+/// Events of one month bucketed by local day
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MonthGridResponse {
+    #[schema(example = "2025-03")]
+    pub month: String,
+    pub timezone: String,
+    /// Every day of the month, in order
+    pub days: Vec<GridDayResponse>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct GridDayResponse {
+    pub date: NaiveDate,
+    /// Events on this day, including those not listed
+    pub count: usize,
+    /// At most three, all-day events first, then by start time
+    pub events: Vec<GridEventResponse>,
+}
+
+/// One occurrence of an event on a grid day
+#[derive(Debug, Serialize, ToSchema)]
+pub struct GridEventResponse {
+    pub id: Uuid,
+    /// Cut to 40 characters
+    pub summary: String,
+    /// Start of this occurrence; absent for all-day events
+    pub start: Option<DateTime<Utc>>,
+    pub is_all_day: bool,
+    pub status: EventStatus,
+    /// The occurrence began on an earlier day
+    pub continues: bool,
+}
+
+impl From<MonthGrid> for MonthGridResponse {
+    fn from(grid: MonthGrid) -> Self {
+        Self {
+            month: grid.month.format("%Y-%m").to_string(),
+            timezone: grid.timezone.as_str().to_string(),
+            days: grid
+                .days
+                .into_iter()
+                .map(|day| GridDayResponse {
+                    date: day.date,
+                    count: day.count,
+                    events: day.events.into_iter().map(Into::into).collect(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<GridEvent> for GridEventResponse {
+    fn from(event: GridEvent) -> Self {
+        Self {
+            id: event.id,
+            summary: event.summary,
+            start: event.start,
+            is_all_day: event.is_all_day,
+            status: event.status.into(),
+            continues: event.continues,
+        }
+    }
 }
 
 /// Public REST event response.
@@ -224,42 +385,54 @@ pub struct EventResponse {
     pub start_date: Option<NaiveDate>,
     pub end_date: Option<NaiveDate>,
     pub is_all_day: bool,
+    /// Wall-clock event; `start`, `end` and `timezone` are the viewer's
+    pub is_floating: bool,
     pub status: EventStatus,
     pub timezone: String,
     pub rrule: Option<String>,
+    pub visibility: EventVisibility,
+    /// Appended to the event's reminders
+    pub reminder_note: Option<String>,
+    /// `#hashtags` of the summary, lowercased and without the `#`
+    pub tags: Vec<String>,
 }
 
-impl From<EventView> for EventResponse {
-    fn from(event: EventView) -> Self {
-        let (start, end, start_date, end_date, is_all_day, timezone) = match event.timing {
-            EventTiming::Timed {
-                start,
-                end,
-                timezone,
-            } => (
-                Some(start),
-                Some(end),
-                None,
-                None,
-                false,
-                timezone.as_str().to_string(),
-            ),
-            EventTiming::AllDay {
-                start_date,
-                end_date,
-            } => (
-                None,
-                None,
-                Some(start_date),
-                Some(end_date),
-                true,
-                "UTC".to_string(),
-            ),
-        };
+impl EventResponse {
+    /// Render an event for a viewer, placing floating times in their zone
+    pub(crate) fn for_viewer(event: EventView, viewer: &Timezone) -> Self {
+        let is_floating = matches!(event.timing, EventTiming::Floating { .. });
+        let (start, end, start_date, end_date, is_all_day, timezone) =
+            match event.timing.pinned_to(viewer) {
+                EventTiming::Timed {
+                    start,
+                    end,
+                    timezone,
+                } => (
+                    Some(start),
+                    Some(end),
+                    None,
+                    None,
+                    false,
+                    timezone.as_str().to_string(),
+                ),
+                EventTiming::AllDay {
+                    start_date,
+                    end_date,
+                } => (
+                    None,
+                    None,
+                    Some(start_date),
+                    Some(end_date),
+                    true,
+                    "UTC".to_string(),
+                ),
+                EventTiming::Floating { .. } => unreachable!("pinned to the viewer's zone"),
+            };
 
         Self {
             id: event.id,
             uid: event.uid,
+            tags: event_tags(&event.summary),
             summary: event.summary,
             description: event.description,
             location: event.location,
@@ -268,21 +441,29 @@ impl From<EventView> for EventResponse {
             start_date,
             end_date,
             is_all_day,
+            is_floating,
             status: event.status.into(),
             timezone,
             rrule: event.rrule,
+            visibility: event.visibility.into(),
+            reminder_note: event.reminder_note,
         }
     }
 }
 
 /// Create a new event
+///
+/// With `calendar`, the event goes on that owner's calendar instead, which
+/// the user must be a delegate of.
 #[utoipa::path(
     post,
     path = "/events",
+    params(CalendarQuery),
     request_body = CreateEventRequest,
     responses(
         (status = 201, description = "Event created successfully", body = EventResponse),
         (status = 400, description = "Invalid request"),
+        (status = 404, description = "Calendar not found or not delegated to the user"),
         (status = 401, description = "Unauthorized")
     ),
     tag = "events",
@@ -291,26 +472,37 @@ impl From<EventView> for EventResponse {
     )
 )]
 async fn create_event(
-    State(calendar): State<CalendarService>,
+    State(events): State<EventService>,
     Extension(auth_user): Extension<AuthenticatedTelegramUser>,
+    Query(calendar): Query<CalendarQuery>,
     Json(req): Json<CreateEventRequest>,
 ) -> Result<Response, ApiError> {
     req.validate()?;
-    let event = calendar
-        .create_event_view(CreateEventCommand {
-            user_id: auth_user.id,
-            username: auth_user.username,
-            uid: req.uid,
-            summary: req.summary,
-            description: req.description,
-            location: req.location,
-            timing: req.timing.into_domain()?,
-            status: DomainEventStatus::Confirmed,
-            rrule: req.rrule,
-        })
+    let event = events
+        .create_event_view_as(
+            auth_user.id,
+            CreateEventCommand {
+                user_id: calendar.owner(&auth_user),
+                username: auth_user.username,
+                uid: req.uid,
+                summary: req.summary,
+                description: req.description,
+                location: req.location,
+                timing: req.timing.into_domain()?,
+                status: DomainEventStatus::Confirmed,
+                rrule: req.rrule,
+                visibility: req.visibility.map(EventVisibility::into_domain),
+                reminder_note: req.reminder_note,
+                allow_duplicate: true,
+            },
+        )
         .await?;
 
-    Ok((StatusCode::CREATED, Json(EventResponse::from(event))).into_response())
+    Ok((
+        StatusCode::CREATED,
+        Json(EventResponse::for_viewer(event, &auth_user.timezone)),
+    )
+        .into_response())
 }
 
 /// Get event by ID
@@ -356,7 +548,7 @@ async fn get_event(
 
     let event = calendar.get_event_view(auth_user.id, event_id).await?;
 
-    Ok(Json(EventResponse::from(event)).into_response())
+    Ok(Json(EventResponse::for_viewer(event, &auth_user.timezone)).into_response())
 }
 
 /// List events
@@ -381,20 +573,69 @@ async fn list_events(
     // Default limit to 100 to prevent OOM
     let limit = query.limit.unwrap_or(100).clamp(1, MAX_EVENTS_LIMIT);
     let offset = query.offset.unwrap_or(0);
+    let tag = query.tag()?;
 
     let events = calendar
         .list_event_views(
             auth_user.id,
             query.start,
             query.end,
+            &query.statuses(),
+            tag.as_deref(),
             Some(limit),
             Some(offset),
         )
         .await?;
-    Ok(Json(events.into_iter().map(EventResponse::from).collect()))
+    Ok(Json(
+        events
+            .into_iter()
+            .map(|event| EventResponse::for_viewer(event, &auth_user.timezone))
+            .collect(),
+    ))
+}
+
+/// Month grid
+///
+/// Buckets the events of a month into its days for a calendar grid, with
+/// recurrences expanded and events spanning several days shown on each.
+/// Cancelled events are left out.
+#[utoipa::path(
+    get,
+    path = "/events/grid",
+    params(MonthGridQuery),
+    responses(
+        (status = 200, description = "Per-day buckets of the month", body = MonthGridResponse),
+        (status = 400, description = "Invalid month or timezone"),
+        (status = 401, description = "Unauthorized")
+    ),
+    tag = "events",
+    security(
+        ("telegram_auth" = [])
+    )
+)]
+async fn month_grid(
+    State(calendar): State<CalendarService>,
+    Extension(auth_user): Extension<AuthenticatedTelegramUser>,
+    Query(query): Query<MonthGridQuery>,
+) -> Result<Json<MonthGridResponse>, ApiError> {
+    let month = parse_month(&query.month)?;
+    let timezone = match query.tz {
+        Some(tz) => Timezone::parse(tz).map_err(|e| ApiError::BadRequest(e.to_string()))?,
+        None => auth_user.timezone,
+    };
+
+    let grid = calendar.month_grid(auth_user.id, &timezone, month).await?;
+    Ok(Json(grid.into()))
 }
 
 /// Update event
+///
+/// On a recurring event, `edit_scope` limits the change to one occurrence
+/// or to it and those after it; either splits the series, and the response
+/// is the newly created event holding the edited occurrences.
+///
+/// With `calendar`, the event is looked up on that owner's calendar, which
+/// the user must be a delegate of.
 #[utoipa::path(
     put,
     path = "/events/{id}",
@@ -406,7 +647,8 @@ async fn list_events(
         (status = 401, description = "Unauthorized")
     ),
     params(
-        ("id" = Uuid, Path, description = "Event ID")
+        ("id" = Uuid, Path, description = "Event ID"),
+        CalendarQuery
     ),
     tag = "events",
     security(
@@ -414,32 +656,43 @@ async fn list_events(
     )
 )]
 async fn update_event(
-    State(calendar): State<CalendarService>,
+    State(events): State<EventService>,
     Extension(auth_user): Extension<AuthenticatedTelegramUser>,
     Path(event_id): Path<Uuid>,
+    Query(calendar): Query<CalendarQuery>,
     Json(req): Json<UpdateEventRequest>,
 ) -> Result<Json<EventResponse>, ApiError> {
     req.validate()?;
+    let scope = req.scope()?;
 
-    let event = calendar
-        .update_event_view(UpdateEventCommand {
-            user_id: auth_user.id,
-            event_id,
-            summary: req.summary,
-            description: req.description,
-            location: req.location,
-            timing: req
-                .timing
-                .map(EventTimingRequest::into_domain)
-                .transpose()?,
-            status: req.status.map(EventStatus::into_domain),
-            rrule: req.rrule,
-        })
+    let event = events
+        .update_event_view_as(
+            auth_user.id,
+            UpdateEventCommand {
+                user_id: calendar.owner(&auth_user),
+                event_id,
+                summary: req.summary,
+                description: req.description,
+                location: req.location,
+                timing: req
+                    .timing
+                    .map(EventTimingRequest::into_domain)
+                    .transpose()?,
+                status: req.status.map(EventStatus::into_domain),
+                rrule: req.rrule,
+                visibility: req.visibility.map(EventVisibility::into_domain),
+                reminder_note: req.reminder_note,
+                scope,
+            },
+        )
         .await?;
-    Ok(Json(EventResponse::from(event)))
+    Ok(Json(EventResponse::for_viewer(event, &auth_user.timezone)))
 }
 
 /// Delete event
+///
+/// With `calendar`, the event is deleted from that owner's calendar, which
+/// the user must be a delegate of.
 #[utoipa::path(
     delete,
     path = "/events/{id}",
@@ -449,7 +702,8 @@ async fn update_event(
         (status = 401, description = "Unauthorized")
     ),
     params(
-        ("id" = Uuid, Path, description = "Event ID")
+        ("id" = Uuid, Path, description = "Event ID"),
+        CalendarQuery
     ),
     tag = "events",
     security(
@@ -457,31 +711,455 @@ async fn update_event(
     )
 )]
 async fn delete_event_handler(
-    State(calendar): State<CalendarService>,
+    State(events): State<EventService>,
+    Extension(auth_user): Extension<AuthenticatedTelegramUser>,
+    Path(event_id): Path<Uuid>,
+    Query(calendar): Query<CalendarQuery>,
+) -> Result<StatusCode, ApiError> {
+    events
+        .delete_event_by_id_as(auth_user.id, calendar.owner(&auth_user), event_id)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// One change in an event's history
+#[derive(Debug, Serialize, ToSchema)]
+pub struct EventRevisionResponse {
+    /// Telegram ID of whoever made the change, the owner or a delegate
+    pub actor_id: i64,
+    pub actor_username: Option<String>,
+    /// `created`, `updated` or `deleted`
+    #[schema(example = "updated")]
+    pub action: String,
+    pub at: DateTime<Utc>,
+}
+
+impl From<EventRevisionView> for EventRevisionResponse {
+    fn from(revision: EventRevisionView) -> Self {
+        Self {
+            actor_id: revision.actor.inner(),
+            actor_username: revision.actor_username,
+            action: revision.action.as_str().to_string(),
+            at: revision.at,
+        }
+    }
+}
+
+/// Event revision history
+///
+/// Who created, edited and deleted the event, oldest first. Also available
+/// once the event is deleted, and to delegates with `calendar`.
+#[utoipa::path(
+    get,
+    path = "/events/{id}/revisions",
+    responses(
+        (status = 200, description = "Changes to the event", body = Vec<EventRevisionResponse>),
+        (status = 404, description = "Event not found"),
+        (status = 401, description = "Unauthorized")
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Event ID"),
+        CalendarQuery
+    ),
+    tag = "events",
+    security(
+        ("telegram_auth" = [])
+    )
+)]
+async fn list_event_revisions(
+    State(events): State<EventService>,
+    Extension(auth_user): Extension<AuthenticatedTelegramUser>,
+    Path(event_id): Path<Uuid>,
+    Query(calendar): Query<CalendarQuery>,
+) -> Result<Json<Vec<EventRevisionResponse>>, ApiError> {
+    let revisions = events
+        .event_revisions(auth_user.id, calendar.owner(&auth_user), event_id)
+        .await?;
+    Ok(Json(
+        revisions
+            .into_iter()
+            .map(EventRevisionResponse::from)
+            .collect(),
+    ))
+}
+
+/// Public signup page of an event
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PublicPageResponse {
+    /// Unguessable page identifier
+    #[schema(example = "k3J9xQ2mPz7LwA1c")]
+    pub slug: String,
+    /// Server-relative page URL to share, including any proxy prefix
+    #[schema(example = "/e/k3J9xQ2mPz7LwA1c")]
+    pub path: String,
+}
+
+/// Publish event page
+///
+/// Makes the event reachable at `/e/{slug}`, where people without Telegram
+/// can sign up by email. Publishing an already public event returns its
+/// existing slug.
+#[utoipa::path(
+    put,
+    path = "/events/{id}/public",
+    responses(
+        (status = 200, description = "Event is public", body = PublicPageResponse),
+        (status = 403, description = "External invites are not enabled for the user"),
+        (status = 404, description = "Event not found"),
+        (status = 401, description = "Unauthorized")
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Event ID")
+    ),
+    tag = "events",
+    security(
+        ("telegram_auth" = [])
+    )
+)]
+async fn publish_event(
+    State(events): State<EventService>,
+    State(flags): State<FeatureFlagService>,
+    Extension(auth_user): Extension<AuthenticatedTelegramUser>,
+    base: BasePath,
+    Path(event_id): Path<Uuid>,
+) -> Result<Json<PublicPageResponse>, ApiError> {
+    // Public pages take email signups, i.e. external guests
+    if !flags
+        .is_enabled(EXTERNAL_INVITES_FLAG, auth_user.id)
+        .await?
+    {
+        return Err(ApiError::Forbidden);
+    }
+    let slug = events.publish_event(auth_user.id, event_id).await?;
+    Ok(Json(PublicPageResponse {
+        path: base.join(&format!("/e/{slug}")),
+        slug,
+    }))
+}
+
+/// Unpublish event page
+#[utoipa::path(
+    delete,
+    path = "/events/{id}/public",
+    responses(
+        (status = 204, description = "Event is private again"),
+        (status = 404, description = "Event not found"),
+        (status = 401, description = "Unauthorized")
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Event ID")
+    ),
+    tag = "events",
+    security(
+        ("telegram_auth" = [])
+    )
+)]
+async fn unpublish_event(
+    State(events): State<EventService>,
     Extension(auth_user): Extension<AuthenticatedTelegramUser>,
     Path(event_id): Path<Uuid>,
 ) -> Result<StatusCode, ApiError> {
-    calendar.delete_event_by_id(auth_user.id, event_id).await?;
+    events.unpublish_event(auth_user.id, event_id).await?;
     Ok(StatusCode::NO_CONTENT)
 }
 
+/// Options for copying an event
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct DuplicateEventRequest {
+    /// Days to move the copy by, e.g. 7 for next week; negative moves it back
+    #[serde(default)]
+    #[schema(example = 7)]
+    pub offset_days: i64,
+    /// Invite the original guests to the copy; otherwise nobody is notified
+    #[serde(default)]
+    pub include_attendees: bool,
+}
+
+/// Duplicate event
+///
+/// Copies the event under a new UID. The body is optional; without it the
+/// copy keeps the original time and has no guests.
+#[utoipa::path(
+    post,
+    path = "/events/{id}/duplicate",
+    request_body(content = Option<DuplicateEventRequest>),
+    responses(
+        (status = 201, description = "Copy created", body = EventResponse),
+        (status = 400, description = "Offset out of range"),
+        (status = 404, description = "Event not found"),
+        (status = 401, description = "Unauthorized")
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Event ID")
+    ),
+    tag = "events",
+    security(
+        ("telegram_auth" = [])
+    )
+)]
+async fn duplicate_event(
+    State(events): State<EventService>,
+    Extension(auth_user): Extension<AuthenticatedTelegramUser>,
+    Path(event_id): Path<Uuid>,
+    body: Bytes,
+) -> Result<Response, ApiError> {
+    // Read by hand so an empty body works whatever the Content-Type says
+    let request: DuplicateEventRequest = if body.is_empty() {
+        DuplicateEventRequest::default()
+    } else {
+        serde_json::from_slice(&body).map_err(|e| ApiError::BadRequest(e.to_string()))?
+    };
+    let event = events
+        .duplicate_event(DuplicateEventCommand {
+            user_id: auth_user.id,
+            event_id,
+            offset_days: request.offset_days,
+            include_attendees: request.include_attendees,
+        })
+        .await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(EventResponse::for_viewer(event, &auth_user.timezone)),
+    )
+        .into_response())
+}
+
+/// Events saved in the wrong timezone, to move to the right one
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct TimezoneFixRequest {
+    /// Zone the events were saved in by mistake
+    #[schema(example = "Europe/London")]
+    pub from: String,
+    /// Zone they were meant to be in; each keeps its wall-clock times
+    #[schema(example = "Europe/Berlin")]
+    pub to: String,
+    /// Only events starting at or after this
+    pub start: Option<DateTime<Utc>>,
+    /// Only events starting before this
+    pub end: Option<DateTime<Utc>>,
+    /// Only count the events that would move
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// The events a timezone fix moved, or would move
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TimezoneFixResponse {
+    pub events: usize,
+    /// Start of the earliest one, before the fix
+    pub first_start: Option<DateTime<Utc>>,
+    /// Start of the latest one, before the fix
+    pub last_start: Option<DateTime<Utc>>,
+    /// Whether the events were moved, rather than only counted
+    pub applied: bool,
+}
+
+/// Fix events' timezone
+///
+/// Moves the timed events saved in `from` to the same wall-clock times in
+/// `to`, all in one change: the sync token moves once and guests of events
+/// still to come are told the new time. With `dry_run` nothing changes and
+/// the response tells what would move.
+#[utoipa::path(
+    post,
+    path = "/events/timezone-fix",
+    request_body = TimezoneFixRequest,
+    responses(
+        (status = 200, description = "Events moved or counted", body = TimezoneFixResponse),
+        (status = 400, description = "Unknown or equal timezones, a bad range or too many events"),
+        (status = 401, description = "Unauthorized")
+    ),
+    tag = "events",
+    security(
+        ("telegram_auth" = [])
+    )
+)]
+async fn fix_timezone(
+    State(events): State<EventService>,
+    Extension(auth_user): Extension<AuthenticatedTelegramUser>,
+    Json(request): Json<TimezoneFixRequest>,
+) -> Result<Json<TimezoneFixResponse>, ApiError> {
+    let zone =
+        |name: String| Timezone::parse(name).map_err(|e| ApiError::BadRequest(e.to_string()));
+    let command = TimezoneFixCommand {
+        user_id: auth_user.id,
+        from: zone(request.from)?,
+        to: zone(request.to)?,
+        start: request.start,
+        end: request.end,
+    };
+    let summary = if request.dry_run {
+        events.preview_timezone_fix(&command).await?
+    } else {
+        events.fix_timezone(command).await?
+    };
+
+    Ok(Json(TimezoneFixResponse {
+        events: summary.events,
+        first_start: summary.first_start,
+        last_start: summary.last_start,
+        applied: !request.dry_run,
+    }))
+}
+
 /// Event routes
 pub fn routes<S>() -> Router<S>
 where
     S: Clone + Send + Sync + 'static,
     CalendarService: FromRef<S>,
+    EventService: FromRef<S>,
+    FeatureFlagService: FromRef<S>,
 {
     Router::new()
         .route("/events", post(create_event))
         .route("/events", get(list_events))
+        .route("/events/grid", get(month_grid))
+        .route("/events/timezone-fix", post(fix_timezone))
         .route("/events/{id}", get(get_event))
         .route("/events/{id}", put(update_event))
         .route("/events/{id}", delete(delete_event_handler))
+        .route("/events/{id}/revisions", get(list_event_revisions))
+        .route("/events/{id}/public", put(publish_event))
+        .route("/events/{id}/public", delete(unpublish_event))
+        .route("/events/{id}/duplicate", post(duplicate_event))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use axum::{body::Body, http::Request};
+    use std::sync::Arc;
+    use televent_application::UserId;
+    use televent_domain::MAX_UID_LENGTH;
+    use televent_storage::calendar::{CalendarRepository, Event};
+    use televent_storage::memory::MemoryEvents;
+    use tower::ServiceExt;
+
+    const USER: UserId = UserId(42);
+
+    /// Handlers over in-memory events; the pool is never connected
+    fn app(events: &MemoryEvents) -> Router {
+        let pool = sqlx::PgPool::connect_lazy("postgres://localhost/unused").unwrap();
+        let calendar = CalendarService::new(CalendarRepository::new(pool))
+            .with_events(Arc::new(events.clone()));
+        Router::new()
+            .route("/events", get(list_events))
+            .route("/events/{id}", get(get_event))
+            .with_state(calendar)
+            .layer(Extension(AuthenticatedTelegramUser {
+                id: USER,
+                username: None,
+                timezone: Timezone::default(),
+            }))
+    }
+
+    async fn get_json(app: Router, uri: &str) -> (StatusCode, serde_json::Value) {
+        let request = Request::builder().uri(uri).body(Body::empty()).unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or_default())
+    }
+
+    fn stored_event(
+        user_id: UserId,
+        summary: &str,
+        start: &str,
+        status: DomainEventStatus,
+    ) -> Event {
+        let start: DateTime<Utc> = start.parse().unwrap();
+        Event {
+            id: Uuid::new_v4(),
+            user_id,
+            uid: Uuid::new_v4().to_string(),
+            summary: summary.to_string(),
+            description: None,
+            location: None,
+            start: Some(start),
+            end: Some(start + chrono::Duration::hours(1)),
+            start_date: None,
+            end_date: None,
+            is_all_day: false,
+            is_floating: false,
+            status,
+            rrule: None,
+            visibility: DomainEventVisibility::Public,
+            reminder_note: None,
+            timezone: Timezone::default(),
+            version: 1,
+            sequence: 0,
+            sync_version: 1,
+            etag: "etag".to_string(),
+            created_at: start,
+            updated_at: start,
+        }
+    }
+
+    #[tokio::test]
+    async fn get_event_hides_other_users_events() {
+        let events = MemoryEvents::default();
+        let own = stored_event(
+            USER,
+            "Mine",
+            "2026-03-02T09:00:00Z",
+            DomainEventStatus::Confirmed,
+        );
+        let theirs = stored_event(
+            UserId(7),
+            "Theirs",
+            "2026-03-02T09:00:00Z",
+            DomainEventStatus::Confirmed,
+        );
+        let (own_id, their_id) = (own.id, theirs.id);
+        events.insert(own);
+        events.insert(theirs);
+
+        let (status, body) = get_json(app(&events), &format!("/events/{own_id}")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["summary"], "Mine");
+
+        let (status, _) = get_json(app(&events), &format!("/events/{their_id}")).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn list_events_skips_cancelled_unless_asked() {
+        let events = MemoryEvents::default();
+        events.insert(stored_event(
+            USER,
+            "Later",
+            "2026-03-03T09:00:00Z",
+            DomainEventStatus::Confirmed,
+        ));
+        events.insert(stored_event(
+            USER,
+            "Sooner",
+            "2026-03-02T09:00:00Z",
+            DomainEventStatus::Tentative,
+        ));
+        events.insert(stored_event(
+            USER,
+            "Called off",
+            "2026-03-02T12:00:00Z",
+            DomainEventStatus::Cancelled,
+        ));
+
+        let (status, body) = get_json(app(&events), "/events").await;
+        assert_eq!(status, StatusCode::OK);
+        let summaries: Vec<_> = body
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|event| event["summary"].as_str().unwrap())
+            .collect();
+        assert_eq!(summaries, ["Sooner", "Later"]);
+
+        let (_, body) = get_json(app(&events), "/events?status=Cancelled").await;
+        assert_eq!(body[0]["summary"], "Called off");
+    }
 
     #[test]
     fn test_create_event_request_deserialization() {
@@ -506,6 +1184,31 @@ mod tests {
         assert!(req.rrule.is_none());
     }
 
+    #[test]
+    fn test_all_day_timing_rejects_datetime_fields() {
+        let timing: EventTimingRequest = serde_json::from_str(
+            r#"{"kind": "all_day", "start_date": "2026-06-01", "end_date": "2026-06-04"}"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            timing.into_domain().unwrap(),
+            EventTiming::AllDay { start_date, end_date }
+                if start_date == NaiveDate::from_ymd_opt(2026, 6, 1).unwrap()
+                    && end_date == NaiveDate::from_ymd_opt(2026, 6, 4).unwrap()
+        ));
+
+        let mixed = serde_json::from_str::<EventTimingRequest>(
+            r#"{"kind": "all_day", "start_date": "2026-06-01", "end_date": "2026-06-04",
+                "start": "2026-06-01T00:00:00Z"}"#,
+        );
+        assert!(mixed.is_err());
+        let mixed = serde_json::from_str::<EventTimingRequest>(
+            r#"{"kind": "timed", "start": "2026-06-01T10:00:00Z", "end": "2026-06-01T11:00:00Z",
+                "timezone": "UTC", "end_date": "2026-06-02"}"#,
+        );
+        assert!(mixed.is_err());
+    }
+
     #[test]
     fn test_update_event_request_partial() {
         let json = r#"{
@@ -555,6 +1258,10 @@ mod tests {
         let query: ListEventsQuery = serde_json::from_str(json).unwrap();
         assert!(query.limit.is_none());
         assert!(query.offset.is_none());
+        assert!(!query.statuses().contains(&DomainEventStatus::Cancelled));
+
+        let query: ListEventsQuery = serde_json::from_str(r#"{"status": "Cancelled"}"#).unwrap();
+        assert_eq!(query.statuses(), vec![DomainEventStatus::Cancelled]);
     }
 
     #[test]
@@ -570,6 +1277,8 @@ mod tests {
                 timezone: "UTC".to_string(),
             },
             rrule: None,
+            visibility: None,
+            reminder_note: None,
         };
         assert!(req.validate().is_ok());
     }
@@ -587,20 +1296,8 @@ mod tests {
                 timezone: "UTC".to_string(),
             },
             rrule: None,
-        };
-        assert!(req.validate().is_err());
-
-        let req = CreateEventRequest {
-            uid: "valid-uid".to_string(),
-            summary: "a".repeat(MAX_SUMMARY_LENGTH + 1),
-            description: None,
-            location: None,
-            timing: EventTimingRequest::Timed {
-                start: Utc::now(),
-                end: Utc::now(),
-                timezone: "UTC".to_string(),
-            },
-            rrule: None,
+            visibility: None,
+            reminder_note: None,
         };
         assert!(req.validate().is_err());
     }
@@ -608,8 +1305,8 @@ mod tests {
     #[test]
     fn test_create_event_validation_control_chars() {
         let req = CreateEventRequest {
-            uid: "valid-uid".to_string(),
-            summary: "Invalid\nSummary".to_string(),
+            uid: "invalid\nuid".to_string(),
+            summary: "Valid Summary".to_string(),
             description: None,
             location: None,
             timing: EventTimingRequest::Timed {
@@ -618,13 +1315,16 @@ mod tests {
                 timezone: "UTC".to_string(),
             },
             rrule: None,
+            visibility: None,
+            reminder_note: None,
         };
         assert!(req.validate().is_err());
 
+        // Free text is sanitized by the event service rather than rejected
         let req = CreateEventRequest {
             uid: "valid-uid".to_string(),
-            summary: "Valid Summary".to_string(),
-            description: Some("Valid\nDescription".to_string()),
+            summary: "Pasted\nSummary".to_string(),
+            description: Some("Pasted\x07Description".to_string()),
             location: None,
             timing: EventTimingRequest::Timed {
                 start: Utc::now(),
@@ -632,33 +1332,25 @@ mod tests {
                 timezone: "UTC".to_string(),
             },
             rrule: None,
+            visibility: None,
+            reminder_note: None,
         };
         assert!(req.validate().is_ok());
-
-        let req = CreateEventRequest {
-            uid: "valid-uid".to_string(),
-            summary: "Valid Summary".to_string(),
-            description: Some("Invalid\x07Description".to_string()),
-            location: None,
-            timing: EventTimingRequest::Timed {
-                start: Utc::now(),
-                end: Utc::now(),
-                timezone: "UTC".to_string(),
-            },
-            rrule: None,
-        };
-        assert!(req.validate().is_err());
     }
 
     #[test]
     fn test_update_event_validation() {
         let req = UpdateEventRequest {
-            summary: Some("a".repeat(MAX_SUMMARY_LENGTH + 1)),
+            summary: None,
             description: None,
             location: None,
             timing: None,
             status: None,
-            rrule: None,
+            rrule: Some(Some("INVALID=TRUE".to_string())),
+            visibility: None,
+            reminder_note: None,
+            edit_scope: None,
+            occurrence_start: None,
         };
         assert!(req.validate().is_err());
 
@@ -669,8 +1361,13 @@ mod tests {
             timing: None,
             status: None,
             rrule: None,
+            visibility: None,
+            reminder_note: None,
+            edit_scope: Some(EditScope::Occurrence),
+            occurrence_start: None,
         };
         assert!(req.validate().is_ok());
+        assert!(req.scope().is_err());
     }
 
     #[test]
@@ -689,9 +1386,12 @@ mod tests {
             },
             status: DomainEventStatus::Confirmed,
             rrule: None,
+            visibility: DomainEventVisibility::Public,
+            reminder_note: None,
         };
 
-        let value = serde_json::to_value(EventResponse::from(event)).unwrap();
+        let value =
+            serde_json::to_value(EventResponse::for_viewer(event, &Timezone::utc())).unwrap();
         let object = value.as_object().unwrap();
 
         for hidden_field in [
@@ -722,6 +1422,8 @@ mod tests {
                 timezone: "UTC".to_string(),
             },
             rrule: Some("FREQ=DAILY\r\nATTENDEE:EVIL".to_string()),
+            visibility: None,
+            reminder_note: None,
         };
         assert!(req.validate().is_err());
     }
@@ -739,6 +1441,8 @@ mod tests {
                 timezone: "UTC".to_string(),
             },
             rrule: Some("INVALID=TRUE".to_string()),
+            visibility: None,
+            reminder_note: None,
         };
         assert!(req.validate().is_err());
     }
@@ -0,0 +1,219 @@
+//! Feature flag admin endpoints
+//!
+//! Admins can change rollouts and per-user overrides at runtime.
+
+use axum::{
+    Extension, Json, Router,
+    extract::{FromRef, Path, State},
+    http::StatusCode,
+    middleware::from_fn_with_state,
+    routing::{get, put},
+};
+use serde::{Deserialize, Serialize};
+use televent_application::{
+    FeatureFlagService, FeatureFlagView, PutFeatureFlagCommand, UserId, UserRole,
+};
+use utoipa::ToSchema;
+
+use crate::{
+    error::ApiError,
+    middleware::{roles::require_role, telegram_auth::AuthenticatedTelegramUser},
+};
+
+/// Feature flag with its rollout and overrides
+#[derive(Debug, Serialize, ToSchema)]
+pub struct FeatureFlagResponse {
+    #[schema(example = "external_invites")]
+    pub name: String,
+    pub description: String,
+    /// Kill switch; when off only users with an override get the feature
+    pub enabled: bool,
+    /// Share of users (0-100) that get the feature while it is enabled
+    #[schema(example = 25)]
+    pub rollout_percent: u8,
+    pub overrides: Vec<FeatureFlagOverride>,
+    pub updated_at: String,
+}
+
+/// Per-user value that wins over the rollout
+#[derive(Debug, Serialize, ToSchema)]
+pub struct FeatureFlagOverride {
+    pub user_id: i64,
+    pub enabled: bool,
+}
+
+/// Settings of a feature flag to create or replace
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct PutFeatureFlagRequest {
+    #[serde(default)]
+    pub description: String,
+    pub enabled: bool,
+    #[schema(example = 25)]
+    pub rollout_percent: u8,
+}
+
+/// Override value for one user
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct PutFeatureFlagOverrideRequest {
+    pub enabled: bool,
+}
+
+impl From<FeatureFlagView> for FeatureFlagResponse {
+    fn from(flag: FeatureFlagView) -> Self {
+        Self {
+            name: flag.name,
+            description: flag.description,
+            enabled: flag.enabled,
+            rollout_percent: flag.rollout_percent,
+            overrides: flag
+                .overrides
+                .into_iter()
+                .map(|(user_id, enabled)| FeatureFlagOverride {
+                    user_id: user_id.inner(),
+                    enabled,
+                })
+                .collect(),
+            updated_at: flag.updated_at.to_rfc3339(),
+        }
+    }
+}
+
+/// List feature flags
+#[utoipa::path(
+    get,
+    path = "/admin/flags",
+    responses(
+        (status = 200, description = "All feature flags", body = Vec<FeatureFlagResponse>),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Not an admin")
+    ),
+    tag = "admin",
+    security(
+        ("telegram_auth" = [])
+    )
+)]
+async fn list_flags(
+    State(flags): State<FeatureFlagService>,
+) -> Result<Json<Vec<FeatureFlagResponse>>, ApiError> {
+    let flags = flags.list_flags().await?;
+    Ok(Json(flags.into_iter().map(Into::into).collect()))
+}
+
+/// Create or update a feature flag
+#[utoipa::path(
+    put,
+    path = "/admin/flags/{name}",
+    request_body = PutFeatureFlagRequest,
+    responses(
+        (status = 200, description = "Flag saved", body = FeatureFlagResponse),
+        (status = 400, description = "Invalid flag name or rollout"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Not an admin")
+    ),
+    params(
+        ("name" = String, Path, description = "Flag name")
+    ),
+    tag = "admin",
+    security(
+        ("telegram_auth" = [])
+    )
+)]
+async fn put_flag(
+    State(flags): State<FeatureFlagService>,
+    Extension(auth_user): Extension<AuthenticatedTelegramUser>,
+    Path(name): Path<String>,
+    Json(request): Json<PutFeatureFlagRequest>,
+) -> Result<Json<FeatureFlagResponse>, ApiError> {
+    let flag = flags
+        .put_flag(PutFeatureFlagCommand {
+            name,
+            description: request.description,
+            enabled: request.enabled,
+            rollout_percent: request.rollout_percent,
+        })
+        .await?;
+
+    tracing::info!(
+        "Feature flag {} set to enabled={} rollout={}% by {}",
+        flag.name,
+        flag.enabled,
+        flag.rollout_percent,
+        auth_user.id
+    );
+    Ok(Json(flag.into()))
+}
+
+/// Pin a feature flag on or off for one user
+#[utoipa::path(
+    put,
+    path = "/admin/flags/{name}/overrides/{user_id}",
+    request_body = PutFeatureFlagOverrideRequest,
+    responses(
+        (status = 204, description = "Override saved"),
+        (status = 404, description = "Flag or user not found"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Not an admin")
+    ),
+    params(
+        ("name" = String, Path, description = "Flag name"),
+        ("user_id" = i64, Path, description = "Telegram id of the user")
+    ),
+    tag = "admin",
+    security(
+        ("telegram_auth" = [])
+    )
+)]
+async fn put_override(
+    State(flags): State<FeatureFlagService>,
+    Path((name, user_id)): Path<(String, i64)>,
+    Json(request): Json<PutFeatureFlagOverrideRequest>,
+) -> Result<StatusCode, ApiError> {
+    flags
+        .set_override(&name, UserId::new(user_id), Some(request.enabled))
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Remove a user's override so the rollout applies again
+#[utoipa::path(
+    delete,
+    path = "/admin/flags/{name}/overrides/{user_id}",
+    responses(
+        (status = 204, description = "Override removed"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Not an admin")
+    ),
+    params(
+        ("name" = String, Path, description = "Flag name"),
+        ("user_id" = i64, Path, description = "Telegram id of the user")
+    ),
+    tag = "admin",
+    security(
+        ("telegram_auth" = [])
+    )
+)]
+async fn delete_override(
+    State(flags): State<FeatureFlagService>,
+    Path((name, user_id)): Path<(String, i64)>,
+) -> Result<StatusCode, ApiError> {
+    flags
+        .set_override(&name, UserId::new(user_id), None)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Feature flag admin routes
+pub fn routes<S>() -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+    FeatureFlagService: FromRef<S>,
+{
+    Router::new()
+        .route("/admin/flags", get(list_flags))
+        .route("/admin/flags/{name}", put(put_flag))
+        .route(
+            "/admin/flags/{name}/overrides/{user_id}",
+            put(put_override).delete(delete_override),
+        )
+        .route_layer(from_fn_with_state(UserRole::Admin, require_role))
+}
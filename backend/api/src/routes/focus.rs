@@ -0,0 +1,266 @@
+//! Focus-time rule endpoints
+//!
+//! A rule such as "2×2h a week" keeps blocks free in the user's week as
+//! tentative events. Saving a rule places its blocks for the rest of the
+//! week at once; afterwards the worker moves tentative blocks that something
+//! else overlaps and fills in missing ones.
+
+use axum::{
+    Extension, Json, Router,
+    extract::{FromRef, Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::{get, put},
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use televent_application::{
+    CalendarService, EventService, FocusBlockView, FocusRuleCommand, FocusRuleView,
+    SubscriptionService, UserId, WorkingHours, parse_duration_spec, schedule_focus_time,
+};
+use televent_domain::Timezone;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use super::events::EventStatus;
+use crate::{error::ApiError, middleware::telegram_auth::AuthenticatedTelegramUser};
+
+/// A focus rule as the user sets it
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct FocusRuleRequest {
+    /// Title of the blocks; `Focus time` by default
+    #[schema(example = "Deep work")]
+    pub title: Option<String>,
+    /// Blocks to keep free each week, 1 to 7
+    #[schema(example = 2)]
+    pub blocks_per_week: u32,
+    /// Length of each block, e.g. `2h` or `PT90M`; 30m to 4h
+    #[schema(example = "2h")]
+    pub duration: String,
+    /// Working hours blocks must fit in, e.g. `Mon-Fri 09:00-12:00`; the
+    /// user's saved working hours by default
+    #[schema(example = "09:00-17:00")]
+    pub working_hours: Option<String>,
+}
+
+impl FocusRuleRequest {
+    fn command(self, user_id: UserId, timezone: &Timezone) -> Result<FocusRuleCommand, ApiError> {
+        Ok(FocusRuleCommand {
+            user_id,
+            title: self.title,
+            blocks_per_week: self.blocks_per_week,
+            duration: parse_duration_spec(&self.duration)?,
+            working_hours: self
+                .working_hours
+                .as_deref()
+                .map(|hours| WorkingHours::parse(hours, timezone))
+                .transpose()?,
+        })
+    }
+}
+
+/// An event reserved for a rule
+#[derive(Debug, Serialize, ToSchema)]
+pub struct FocusBlockResponse {
+    pub event_id: Uuid,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    /// Tentative blocks may move; confirmed ones stay put
+    pub status: EventStatus,
+}
+
+impl From<FocusBlockView> for FocusBlockResponse {
+    fn from(block: FocusBlockView) -> Self {
+        Self {
+            event_id: block.event_id,
+            start: block.start,
+            end: block.end,
+            status: block.status.into(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct FocusRuleResponse {
+    pub id: Uuid,
+    pub title: String,
+    pub blocks_per_week: u32,
+    pub duration_minutes: i64,
+    pub working_hours: Option<String>,
+    /// Blocks of the current week, earliest first
+    pub blocks: Vec<FocusBlockResponse>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<FocusRuleView> for FocusRuleResponse {
+    fn from(rule: FocusRuleView) -> Self {
+        Self {
+            id: rule.id,
+            title: rule.title,
+            blocks_per_week: rule.blocks_per_week,
+            duration_minutes: rule.duration.num_minutes(),
+            working_hours: rule.working_hours.map(|hours| hours.to_string()),
+            blocks: rule
+                .blocks
+                .into_iter()
+                .map(FocusBlockResponse::from)
+                .collect(),
+            created_at: rule.created_at,
+        }
+    }
+}
+
+/// List focus rules
+#[utoipa::path(
+    get,
+    path = "/focus-rules",
+    responses(
+        (status = 200, description = "Rules with this week's blocks, oldest first", body = Vec<FocusRuleResponse>),
+        (status = 401, description = "Unauthorized")
+    ),
+    tag = "focus",
+    security(
+        ("telegram_auth" = [])
+    )
+)]
+async fn list_focus_rules(
+    State(calendar): State<CalendarService>,
+    Extension(auth_user): Extension<AuthenticatedTelegramUser>,
+) -> Result<Json<Vec<FocusRuleResponse>>, ApiError> {
+    let rules = calendar.list_focus_rules(auth_user.id).await?;
+    Ok(Json(
+        rules.into_iter().map(FocusRuleResponse::from).collect(),
+    ))
+}
+
+/// Add a focus rule
+///
+/// Places the rule's blocks for the rest of the week right away.
+#[utoipa::path(
+    post,
+    path = "/focus-rules",
+    request_body = FocusRuleRequest,
+    responses(
+        (status = 201, description = "Rule added, with its blocks", body = FocusRuleResponse),
+        (status = 400, description = "Invalid rule, or too many rules"),
+        (status = 401, description = "Unauthorized")
+    ),
+    tag = "focus",
+    security(
+        ("telegram_auth" = [])
+    )
+)]
+async fn create_focus_rule(
+    State(calendar): State<CalendarService>,
+    State(events): State<EventService>,
+    State(subscriptions): State<SubscriptionService>,
+    Extension(auth_user): Extension<AuthenticatedTelegramUser>,
+    Json(request): Json<FocusRuleRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let rule = events
+        .create_focus_rule(request.command(auth_user.id, &auth_user.timezone)?)
+        .await?;
+    let rule = scheduled(&calendar, &subscriptions, &events, auth_user.id, rule.id).await?;
+    Ok((StatusCode::CREATED, Json(rule)))
+}
+
+/// Change a focus rule
+///
+/// Its tentative blocks still ahead are placed again under the new rule.
+#[utoipa::path(
+    put,
+    path = "/focus-rules/{rule_id}",
+    request_body = FocusRuleRequest,
+    responses(
+        (status = 200, description = "Rule changed, with its blocks", body = FocusRuleResponse),
+        (status = 400, description = "Invalid rule"),
+        (status = 404, description = "No such rule"),
+        (status = 401, description = "Unauthorized")
+    ),
+    params(
+        ("rule_id" = Uuid, Path, description = "Focus rule ID")
+    ),
+    tag = "focus",
+    security(
+        ("telegram_auth" = [])
+    )
+)]
+async fn update_focus_rule(
+    State(calendar): State<CalendarService>,
+    State(events): State<EventService>,
+    State(subscriptions): State<SubscriptionService>,
+    Extension(auth_user): Extension<AuthenticatedTelegramUser>,
+    Path(rule_id): Path<Uuid>,
+    Json(request): Json<FocusRuleRequest>,
+) -> Result<Json<FocusRuleResponse>, ApiError> {
+    events
+        .update_focus_rule(rule_id, request.command(auth_user.id, &auth_user.timezone)?)
+        .await?;
+    let rule = scheduled(&calendar, &subscriptions, &events, auth_user.id, rule_id).await?;
+    Ok(Json(rule))
+}
+
+/// Delete a focus rule
+///
+/// Its tentative blocks still ahead are deleted; confirmed blocks stay on
+/// the calendar as ordinary events.
+#[utoipa::path(
+    delete,
+    path = "/focus-rules/{rule_id}",
+    responses(
+        (status = 204, description = "Rule deleted"),
+        (status = 404, description = "No such rule"),
+        (status = 401, description = "Unauthorized")
+    ),
+    params(
+        ("rule_id" = Uuid, Path, description = "Focus rule ID")
+    ),
+    tag = "focus",
+    security(
+        ("telegram_auth" = [])
+    )
+)]
+async fn delete_focus_rule(
+    State(events): State<EventService>,
+    Extension(auth_user): Extension<AuthenticatedTelegramUser>,
+    Path(rule_id): Path<Uuid>,
+) -> Result<StatusCode, ApiError> {
+    events.delete_focus_rule(auth_user.id, rule_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Place the user's focus blocks, then read back the rule with its blocks
+async fn scheduled(
+    calendar: &CalendarService,
+    subscriptions: &SubscriptionService,
+    events: &EventService,
+    user_id: UserId,
+    rule_id: Uuid,
+) -> Result<FocusRuleResponse, ApiError> {
+    schedule_focus_time(calendar, subscriptions, events, user_id).await?;
+    calendar
+        .list_focus_rules(user_id)
+        .await?
+        .into_iter()
+        .find(|rule| rule.id == rule_id)
+        .map(FocusRuleResponse::from)
+        .ok_or_else(|| ApiError::NotFound(rule_id.to_string()))
+}
+
+pub fn routes<S>() -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+    CalendarService: FromRef<S>,
+    EventService: FromRef<S>,
+    SubscriptionService: FromRef<S>,
+{
+    Router::new()
+        .route(
+            "/focus-rules",
+            get(list_focus_rules).post(create_focus_rule),
+        )
+        .route(
+            "/focus-rules/{rule_id}",
+            put(update_focus_rule).delete(delete_focus_rule),
+        )
+}
@@ -0,0 +1,210 @@
+//! Google Calendar connection endpoints
+//!
+//! The Mini App asks for a consent link, the user approves access on Google,
+//! and Google redirects back to the public callback which stores the tokens.
+//! The link's state is also set as a cookie, so the callback only completes
+//! in the browser that asked for the link. The worker runs the actual sync.
+
+use axum::{
+    Extension, Json, Router,
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode, header},
+    response::{Html, IntoResponse, Response},
+    routing::get,
+};
+use serde::{Deserialize, Serialize};
+use televent_application::{GOOGLE_OAUTH_STATE_TTL_MINUTES, GoogleSyncService};
+use televent_google::{GoogleConnector, GoogleError};
+
+use crate::error::ApiError;
+use crate::middleware::telegram_auth::AuthenticatedTelegramUser;
+use crate::routes::public_events::{escape_html, page};
+
+/// Cookie binding a consent in progress to the browser that started it
+const STATE_COOKIE: &str = "televent_google_state";
+
+/// Services behind the Google endpoints
+#[derive(Clone)]
+pub struct GoogleState {
+    pub connector: GoogleConnector,
+    pub sync: GoogleSyncService,
+}
+
+/// Connection status of the current user
+#[derive(Debug, Serialize)]
+pub struct GoogleStatusResponse {
+    pub connected: bool,
+    pub last_synced_at: Option<String>,
+    pub last_error: Option<String>,
+}
+
+/// Consent link to open in the browser
+#[derive(Debug, Serialize)]
+pub struct GoogleConnectResponse {
+    pub authorization_url: String,
+}
+
+/// Query Google appends to the redirect URL
+#[derive(Debug, Deserialize)]
+pub struct CallbackQuery {
+    pub code: Option<String>,
+    pub state: Option<String>,
+    pub error: Option<String>,
+}
+
+async fn google_status(
+    State(google): State<GoogleState>,
+    Extension(auth_user): Extension<AuthenticatedTelegramUser>,
+) -> Result<Json<GoogleStatusResponse>, ApiError> {
+    let connection = google.sync.get_connection(auth_user.id).await?;
+    Ok(Json(GoogleStatusResponse {
+        connected: connection.is_some(),
+        last_synced_at: connection
+            .as_ref()
+            .and_then(|connection| connection.last_synced_at)
+            .map(|t| t.to_rfc3339()),
+        last_error: connection.and_then(|connection| connection.last_error),
+    }))
+}
+
+async fn google_connect(
+    State(google): State<GoogleState>,
+    Extension(auth_user): Extension<AuthenticatedTelegramUser>,
+) -> Result<Response, ApiError> {
+    let request = google
+        .connector
+        .authorization_url(&google.sync, auth_user.id)
+        .await
+        .map_err(|err| ApiError::Internal(format!("Failed to start Google consent: {err}")))?;
+
+    let cookie = format!(
+        "{STATE_COOKIE}={}; Path=/google; Max-Age={}; HttpOnly; Secure; SameSite=Lax",
+        request.state,
+        GOOGLE_OAUTH_STATE_TTL_MINUTES * 60
+    );
+    Ok((
+        [(header::SET_COOKIE, cookie)],
+        Json(GoogleConnectResponse {
+            authorization_url: request.url,
+        }),
+    )
+        .into_response())
+}
+
+async fn google_disconnect(
+    State(google): State<GoogleState>,
+    Extension(auth_user): Extension<AuthenticatedTelegramUser>,
+) -> Result<StatusCode, ApiError> {
+    if google.sync.disconnect(auth_user.id).await? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(ApiError::NotFound(
+            "Google Calendar is not connected".to_string(),
+        ))
+    }
+}
+
+async fn google_callback(
+    State(google): State<GoogleState>,
+    headers: HeaderMap,
+    Query(query): Query<CallbackQuery>,
+) -> Response {
+    let (Some(code), Some(state)) = (query.code, query.state) else {
+        let reason = query
+            .error
+            .unwrap_or_else(|| "missing authorization code".to_string());
+        return callback_page(
+            StatusCode::BAD_REQUEST,
+            &format!("Google Calendar was not connected: {reason}."),
+        );
+    };
+
+    match google
+        .connector
+        .complete_authorization(&google.sync, &state, state_cookie(&headers), &code)
+        .await
+    {
+        Ok(_) => callback_page(
+            StatusCode::OK,
+            "Google Calendar is connected. Your events will start syncing within a few minutes; \
+             you can close this window.",
+        ),
+        Err(GoogleError::InvalidState) => callback_page(
+            StatusCode::BAD_REQUEST,
+            "This link has expired or was opened in a different browser. \
+             Please start connecting again from Televent.",
+        ),
+        Err(err) => {
+            tracing::warn!("Google authorization failed: {}", err);
+            callback_page(
+                StatusCode::BAD_GATEWAY,
+                "Google Calendar could not be connected. Please try again later.",
+            )
+        }
+    }
+}
+
+/// State cookie set by [`google_connect`], if the browser sent it
+fn state_cookie(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(name, _)| *name == STATE_COOKIE)
+        .map(|(_, value)| value)
+}
+
+fn callback_page(status: StatusCode, message: &str) -> Response {
+    // The state is used up either way
+    let expired =
+        format!("{STATE_COOKIE}=; Path=/google; Max-Age=0; HttpOnly; Secure; SameSite=Lax");
+    (
+        status,
+        [(header::SET_COOKIE, expired)],
+        Html(page(
+            "Google Calendar",
+            &format!("<p>{}</p>", escape_html(message)),
+        )),
+    )
+        .into_response()
+}
+
+/// Authenticated endpoints, nested under `/api`
+pub fn routes<S>(google: GoogleState) -> Router<S> {
+    Router::new()
+        .route(
+            "/google",
+            get(google_status)
+                .post(google_connect)
+                .delete(google_disconnect),
+        )
+        .with_state(google)
+}
+
+/// OAuth redirect target (no authentication)
+pub fn callback_routes<S>(google: GoogleState) -> Router<S> {
+    Router::new()
+        .route("/google/callback", get(google_callback))
+        .with_state(google)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    #[test]
+    fn test_state_cookie_is_read_from_any_cookie_header() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(state_cookie(&headers), None);
+
+        headers.append(header::COOKIE, HeaderValue::from_static("theme=dark"));
+        headers.append(
+            header::COOKIE,
+            HeaderValue::from_static("a=1; televent_google_state=abc123; b=2"),
+        );
+        assert_eq!(state_cookie(&headers), Some("abc123"));
+    }
+}
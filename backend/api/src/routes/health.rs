@@ -8,7 +8,7 @@ use axum::{
     routing::get,
 };
 use serde::Serialize;
-use televent_application::HealthService;
+use televent_application::{HealthService, MigrationStatus};
 use utoipa::ToSchema;
 
 /// Health check response
@@ -63,13 +63,94 @@ async fn health_check(State(health): State<HealthService>) -> Response {
     (status_code, Json(response)).into_response()
 }
 
+/// A migration compiled into the server but not yet applied
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PendingMigrationResponse {
+    #[schema(example = 20261017120000_i64)]
+    pub version: i64,
+    #[schema(example = "derive calendar ctag")]
+    pub description: String,
+}
+
+/// Schema migration status
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MigrationStatusResponse {
+    /// "current", "pending" or "failed"
+    #[schema(example = "current")]
+    pub status: String,
+    /// Newest applied migration version
+    pub current_version: Option<i64>,
+    /// Newest migration version this build ships
+    pub latest_version: Option<i64>,
+    pub pending: Vec<PendingMigrationResponse>,
+    /// Versions whose last run failed part-way
+    pub failed: Vec<i64>,
+}
+
+impl From<MigrationStatus> for MigrationStatusResponse {
+    fn from(status: MigrationStatus) -> Self {
+        let label = if !status.failed.is_empty() {
+            "failed"
+        } else if !status.pending.is_empty() {
+            "pending"
+        } else {
+            "current"
+        };
+        Self {
+            status: label.to_string(),
+            current_version: status.current_version,
+            latest_version: status.latest_version,
+            pending: status
+                .pending
+                .into_iter()
+                .map(|migration| PendingMigrationResponse {
+                    version: migration.version,
+                    description: migration.description,
+                })
+                .collect(),
+            failed: status.failed,
+        }
+    }
+}
+
+/// Migration status endpoint
+///
+/// Returns 200 OK when every migration this build ships has been applied
+#[utoipa::path(
+    get,
+    path = "/health/migrations",
+    responses(
+        (status = 200, description = "Schema is current", body = MigrationStatusResponse),
+        (status = 503, description = "Migrations are pending or failed, or the database is unreachable", body = MigrationStatusResponse)
+    ),
+    tag = "health"
+)]
+async fn migration_status(State(health): State<HealthService>) -> Response {
+    match health.migration_status().await {
+        Ok(status) => {
+            let status_code = if status.is_current() {
+                StatusCode::OK
+            } else {
+                StatusCode::SERVICE_UNAVAILABLE
+            };
+            (status_code, Json(MigrationStatusResponse::from(status))).into_response()
+        }
+        Err(e) => {
+            tracing::error!("Migration status check failed: {}", e);
+            StatusCode::SERVICE_UNAVAILABLE.into_response()
+        }
+    }
+}
+
 /// Health check routes
 pub fn routes<S>() -> Router<S>
 where
     S: Clone + Send + Sync + 'static,
     HealthService: FromRef<S>,
 {
-    Router::new().route("/health", get(health_check))
+    Router::new()
+        .route("/health", get(health_check))
+        .route("/health/migrations", get(migration_status))
 }
 
 #[cfg(test)]
@@ -100,6 +181,33 @@ mod tests {
         assert!(json.contains("unhealthy"));
     }
 
+    #[test]
+    fn test_migration_status_labels() {
+        let mut status = MigrationStatus {
+            current_version: Some(1),
+            latest_version: Some(2),
+            pending: vec![televent_application::PendingMigration {
+                version: 2,
+                description: "add table".to_string(),
+            }],
+            failed: Vec::new(),
+        };
+        assert_eq!(
+            MigrationStatusResponse::from(status.clone()).status,
+            "pending"
+        );
+
+        status.failed.push(2);
+        assert_eq!(
+            MigrationStatusResponse::from(status.clone()).status,
+            "failed"
+        );
+
+        status.pending.clear();
+        status.failed.clear();
+        assert_eq!(MigrationStatusResponse::from(status).status, "current");
+    }
+
     // Note: Integration test for the actual health endpoint requires a database connection
     // and should be in tests/integration_tests.rs
 }
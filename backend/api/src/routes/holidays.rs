@@ -0,0 +1,158 @@
+//! Built-in holiday calendar endpoints
+//!
+//! Every supported country's public holidays are a calendar subscription
+//! the user turns on or off. Once on, the holidays show up next to the
+//! user's events and as a read-only CalDAV calendar at
+//! `/caldav/{user}/subscriptions/{id}/`.
+
+use axum::{
+    Extension, Json, Router,
+    extract::{FromRef, Path, State},
+    http::StatusCode,
+    routing::{get, put},
+};
+use serde::Serialize;
+use televent_application::{
+    HolidayCountry, SubscriptionService, holiday_countries, holiday_country_for_url,
+};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::{error::ApiError, middleware::telegram_auth::AuthenticatedTelegramUser};
+
+/// A country's holiday calendar and whether the user has it
+#[derive(Debug, Serialize, ToSchema)]
+pub struct HolidayCalendarResponse {
+    /// ISO 3166-1 alpha-2 code
+    #[schema(example = "DE")]
+    pub country: String,
+    #[schema(example = "Germany")]
+    pub name: String,
+    /// The user's subscription to the calendar, when enabled
+    pub subscription_id: Option<Uuid>,
+}
+
+impl HolidayCalendarResponse {
+    fn new(country: &HolidayCountry, subscription_id: Option<Uuid>) -> Self {
+        Self {
+            country: country.code.to_string(),
+            name: country.name.to_string(),
+            subscription_id,
+        }
+    }
+}
+
+/// List holiday calendars
+#[utoipa::path(
+    get,
+    path = "/holidays",
+    responses(
+        (status = 200, description = "Every country with a holiday calendar, by code", body = Vec<HolidayCalendarResponse>),
+        (status = 401, description = "Unauthorized")
+    ),
+    tag = "holidays",
+    security(
+        ("telegram_auth" = [])
+    )
+)]
+async fn list_holiday_calendars(
+    State(subscriptions): State<SubscriptionService>,
+    Extension(auth_user): Extension<AuthenticatedTelegramUser>,
+) -> Result<Json<Vec<HolidayCalendarResponse>>, ApiError> {
+    let subscribed = subscriptions.list_subscriptions(auth_user.id).await?;
+    Ok(Json(
+        holiday_countries()
+            .iter()
+            .map(|country| {
+                let url = country.url();
+                let subscription_id = subscribed
+                    .iter()
+                    .find(|subscription| subscription.url == url)
+                    .map(|subscription| subscription.id);
+                HolidayCalendarResponse::new(country, subscription_id)
+            })
+            .collect(),
+    ))
+}
+
+/// Enable a holiday calendar
+///
+/// Counts towards the subscription limit. Enabling a calendar the user
+/// already has returns the existing subscription.
+#[utoipa::path(
+    put,
+    path = "/holidays/{country}",
+    params(
+        ("country" = String, Path, description = "ISO 3166-1 alpha-2 country code")
+    ),
+    responses(
+        (status = 200, description = "Holiday calendar enabled", body = HolidayCalendarResponse),
+        (status = 400, description = "Subscription limit reached"),
+        (status = 404, description = "No holiday calendar for the country"),
+        (status = 401, description = "Unauthorized")
+    ),
+    tag = "holidays",
+    security(
+        ("telegram_auth" = [])
+    )
+)]
+async fn enable_holiday_calendar(
+    State(subscriptions): State<SubscriptionService>,
+    Extension(auth_user): Extension<AuthenticatedTelegramUser>,
+    Path(country): Path<String>,
+) -> Result<Json<HolidayCalendarResponse>, ApiError> {
+    let subscription = subscriptions
+        .enable_holidays(auth_user.id, auth_user.username.clone(), &country)
+        .await?;
+    let country = holiday_country_for_url(&subscription.url)
+        .ok_or_else(|| ApiError::NotFound(format!("holiday calendar {country}")))?;
+    Ok(Json(HolidayCalendarResponse::new(
+        country,
+        Some(subscription.id),
+    )))
+}
+
+/// Disable a holiday calendar
+#[utoipa::path(
+    delete,
+    path = "/holidays/{country}",
+    params(
+        ("country" = String, Path, description = "ISO 3166-1 alpha-2 country code")
+    ),
+    responses(
+        (status = 204, description = "Holiday calendar disabled"),
+        (status = 404, description = "No holiday calendar for the country, or not enabled"),
+        (status = 401, description = "Unauthorized")
+    ),
+    tag = "holidays",
+    security(
+        ("telegram_auth" = [])
+    )
+)]
+async fn disable_holiday_calendar(
+    State(subscriptions): State<SubscriptionService>,
+    Extension(auth_user): Extension<AuthenticatedTelegramUser>,
+    Path(country): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    if subscriptions
+        .disable_holidays(auth_user.id, &country)
+        .await?
+    {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(ApiError::NotFound(format!("holiday calendar {country}")))
+    }
+}
+
+pub fn routes<S>() -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+    SubscriptionService: FromRef<S>,
+{
+    Router::new()
+        .route("/holidays", get(list_holiday_calendars))
+        .route(
+            "/holidays/{country}",
+            put(enable_holiday_calendar).delete(disable_holiday_calendar),
+        )
+}
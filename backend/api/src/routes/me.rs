@@ -1,6 +1,18 @@
+use crate::error::ApiError;
 use crate::middleware::telegram_auth::AuthenticatedTelegramUser;
-use axum::{Extension, Json};
-use serde::Serialize;
+use axum::{
+    Extension, Json,
+    extract::{Query, State},
+};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use televent_application::{
+    AddSubscriptionCommand, ApplicationError, CalendarService, CalendarStats, DEFAULT_SLOT_COUNT,
+    DEFAULT_SLOT_SEARCH_DAYS, DEFAULT_STATS_DAYS, FreeSlot, SlotSearch, SubscriptionService,
+    UserRole, WorkingHours, normalize_subscription_url, parse_duration_spec, weekday_name,
+};
+use televent_domain::Timezone;
 use utoipa::ToSchema;
 
 #[derive(Debug, Serialize, ToSchema)]
@@ -9,6 +21,9 @@ pub struct MeResponse {
     pub username: Option<String>,
     pub authenticated: bool,
     pub timezone: String,
+    /// `user`, `operator` or `admin`
+    #[schema(example = "user")]
+    pub role: String,
 }
 
 /// Get current user profile
@@ -26,15 +41,527 @@ pub struct MeResponse {
         ("telegram_auth" = [])
     )
 )]
-async fn get_me(Extension(auth_user): Extension<AuthenticatedTelegramUser>) -> Json<MeResponse> {
+async fn get_me(
+    Extension(auth_user): Extension<AuthenticatedTelegramUser>,
+    Extension(role): Extension<UserRole>,
+) -> Json<MeResponse> {
     Json(MeResponse {
         id: auth_user.id.to_string(),
         username: auth_user.username,
         authenticated: true,
         timezone: auth_user.timezone.as_str().to_string(),
+        role: role.as_sql().to_string(),
     })
 }
 
+/// Free slot search query parameters
+#[derive(Debug, Deserialize, ToSchema, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SlotsQuery {
+    /// How long the slot has to be, e.g. `30m`, `1h30m` or `PT45M`
+    #[schema(example = "30m")]
+    pub duration: String,
+    /// How far ahead to look, e.g. `P7D` or `3d`; at most 31 days
+    #[schema(example = "P7D", default = "P7D")]
+    pub within: Option<String>,
+    /// Working hours slots must fit in, in the user's timezone, e.g.
+    /// `09:00-17:00` or `Mon-Fri 09:00-17:00`; the saved working hours by
+    /// default
+    #[schema(example = "Mon-Fri 09:00-17:00")]
+    pub working_hours: Option<String>,
+    /// Number of slots to return, at most 20
+    #[schema(default = 5)]
+    pub count: Option<usize>,
+}
+
+/// A free slot and how long the gap around it stays free
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SlotResponse {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    /// When the next busy period or the end of working hours begins
+    pub free_until: DateTime<Utc>,
+}
+
+impl From<FreeSlot> for SlotResponse {
+    fn from(slot: FreeSlot) -> Self {
+        Self {
+            start: slot.start,
+            end: slot.end,
+            free_until: slot.free_until,
+        }
+    }
+}
+
+/// Suggest free slots
+///
+/// Returns the next free slots of the requested length, one per gap in the
+/// user's own and subscribed calendars.
+#[utoipa::path(
+    get,
+    path = "/me/slots",
+    params(SlotsQuery),
+    responses(
+        (status = 200, description = "Next free slots, earliest first", body = Vec<SlotResponse>),
+        (status = 400, description = "Invalid duration, window or working hours"),
+        (status = 401, description = "Unauthorized")
+    ),
+    tag = "user",
+    security(
+        ("telegram_auth" = [])
+    )
+)]
+async fn get_slots(
+    State(calendar): State<CalendarService>,
+    State(subscriptions): State<SubscriptionService>,
+    Extension(auth_user): Extension<AuthenticatedTelegramUser>,
+    Query(query): Query<SlotsQuery>,
+) -> Result<Json<Vec<SlotResponse>>, ApiError> {
+    let search = SlotSearch {
+        from: Utc::now(),
+        duration: parse_duration_spec(&query.duration)?,
+        within: match query.within.as_deref() {
+            Some(within) => parse_duration_spec(within)?,
+            None => Duration::days(DEFAULT_SLOT_SEARCH_DAYS),
+        },
+        working_hours: calendar
+            .resolve_working_hours(
+                auth_user.id,
+                &auth_user.timezone,
+                query.working_hours.as_deref(),
+            )
+            .await?,
+        count: query.count.unwrap_or(DEFAULT_SLOT_COUNT),
+    }
+    .validated()?;
+
+    let mut free_busy = calendar
+        .free_busy(
+            auth_user.id,
+            &auth_user.timezone,
+            search.from,
+            search.until(),
+        )
+        .await?;
+    subscriptions
+        .mark_busy(auth_user.id, &mut free_busy)
+        .await?;
+
+    Ok(Json(
+        free_busy
+            .free_slots(&search)
+            .into_iter()
+            .map(SlotResponse::from)
+            .collect(),
+    ))
+}
+
+/// Preferences that shape scheduling
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PreferencesResponse {
+    /// Timezone working hours are read in; changed with `/timezone` in the bot
+    #[schema(example = "Europe/Berlin")]
+    pub timezone: String,
+    /// The working week as written, `null` when not set
+    #[schema(example = "Mon-Thu 09:00-17:00, Fri 09:00-13:00")]
+    pub working_hours: Option<String>,
+    /// Working window of each working day from Monday; days off are left out
+    pub working_days: Vec<WorkingDayResponse>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct WorkingDayResponse {
+    #[schema(example = "Monday")]
+    pub weekday: String,
+    #[schema(example = "09:00")]
+    pub start: String,
+    #[schema(example = "17:00")]
+    pub end: String,
+}
+
+impl PreferencesResponse {
+    fn new(timezone: &Timezone, working_hours: Option<WorkingHours>) -> Self {
+        let working_days = working_hours
+            .as_ref()
+            .map(|hours| {
+                hours
+                    .days()
+                    .map(|(weekday, day)| WorkingDayResponse {
+                        weekday: weekday_name(weekday).to_string(),
+                        start: day.start.format("%H:%M").to_string(),
+                        end: day.end.format("%H:%M").to_string(),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        Self {
+            timezone: timezone.as_str().to_string(),
+            working_hours: working_hours.map(|hours| hours.to_string()),
+            working_days,
+        }
+    }
+}
+
+/// Preferences as the user sets them
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct PutPreferencesRequest {
+    /// Working week such as `Mon-Fri 09:00-17:00` or
+    /// `Mon-Thu 09:00-17:00, Fri 09:00-13:00`, read in the user's timezone;
+    /// `null` clears it
+    #[schema(example = "Mon-Fri 09:00-17:00")]
+    pub working_hours: Option<String>,
+}
+
+/// Get scheduling preferences
+///
+/// Slot suggestions, task plans and focus time keep to the working hours
+/// unless told otherwise, and the bot warns about events outside them.
+#[utoipa::path(
+    get,
+    path = "/me/preferences",
+    responses(
+        (status = 200, description = "Current preferences", body = PreferencesResponse),
+        (status = 401, description = "Unauthorized")
+    ),
+    tag = "user",
+    security(
+        ("telegram_auth" = [])
+    )
+)]
+async fn get_preferences(
+    State(calendar): State<CalendarService>,
+    Extension(auth_user): Extension<AuthenticatedTelegramUser>,
+) -> Result<Json<PreferencesResponse>, ApiError> {
+    let working_hours = calendar.working_hours(auth_user.id).await?;
+    Ok(Json(PreferencesResponse::new(
+        &auth_user.timezone,
+        working_hours,
+    )))
+}
+
+/// Change scheduling preferences
+#[utoipa::path(
+    put,
+    path = "/me/preferences",
+    request_body = PutPreferencesRequest,
+    responses(
+        (status = 200, description = "Preferences saved", body = PreferencesResponse),
+        (status = 400, description = "Invalid working hours"),
+        (status = 401, description = "Unauthorized")
+    ),
+    tag = "user",
+    security(
+        ("telegram_auth" = [])
+    )
+)]
+async fn put_preferences(
+    State(calendar): State<CalendarService>,
+    Extension(auth_user): Extension<AuthenticatedTelegramUser>,
+    Json(request): Json<PutPreferencesRequest>,
+) -> Result<Json<PreferencesResponse>, ApiError> {
+    calendar
+        .ensure_user_setup(auth_user.id.inner(), auth_user.username.as_deref())
+        .await?;
+    let working_hours = calendar
+        .set_working_hours(auth_user.id, request.working_hours.as_deref())
+        .await?;
+    Ok(Json(PreferencesResponse::new(
+        &auth_user.timezone,
+        working_hours,
+    )))
+}
+
+/// Calendar statistics query parameters
+#[derive(Debug, Deserialize, ToSchema, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct StatsQuery {
+    /// Start of the range; 30 days before `to` by default
+    pub from: Option<DateTime<Utc>>,
+    /// End of the range; now by default. At most 366 days after `from`
+    pub to: Option<DateTime<Utc>>,
+}
+
+/// Meeting load of the user's calendar over a range
+///
+/// Covers timed events that aren't cancelled; all-day events are left out
+/// and a recurring series counts once.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct StatsResponse {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub event_count: i64,
+    pub total_hours: f64,
+    pub average_daily_events: f64,
+    /// Weekday with the most events in the user's timezone
+    #[schema(example = "Tuesday")]
+    pub busiest_weekday: Option<String>,
+    /// Every weekday from Monday
+    pub weekdays: Vec<WeekdayLoadResponse>,
+    /// Most frequent guests of the user's events, at most five
+    pub top_collaborators: Vec<CollaboratorResponse>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct WeekdayLoadResponse {
+    #[schema(example = "Monday")]
+    pub weekday: String,
+    pub events: i64,
+    pub hours: f64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CollaboratorResponse {
+    pub email: String,
+    pub telegram_username: Option<String>,
+    /// Events of the range they are invited to
+    pub events: i64,
+}
+
+impl From<CalendarStats> for StatsResponse {
+    fn from(stats: CalendarStats) -> Self {
+        Self {
+            from: stats.from,
+            to: stats.to,
+            event_count: stats.event_count,
+            total_hours: stats.total_hours,
+            average_daily_events: stats.average_daily_events,
+            busiest_weekday: stats
+                .busiest_weekday
+                .map(|weekday| weekday_name(weekday).to_string()),
+            weekdays: stats
+                .weekdays
+                .into_iter()
+                .map(|load| WeekdayLoadResponse {
+                    weekday: weekday_name(load.weekday).to_string(),
+                    events: load.events,
+                    hours: load.hours,
+                })
+                .collect(),
+            top_collaborators: stats
+                .top_collaborators
+                .into_iter()
+                .map(|collaborator| CollaboratorResponse {
+                    email: collaborator.email,
+                    telegram_username: collaborator.telegram_username,
+                    events: collaborator.events,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Calendar statistics
+///
+/// Summarises meeting load over a range: event count and hours, the
+/// busiest weekday, average events per day and the most frequent guests.
+#[utoipa::path(
+    get,
+    path = "/me/stats",
+    params(StatsQuery),
+    responses(
+        (status = 200, description = "Meeting load over the range", body = StatsResponse),
+        (status = 400, description = "Empty range or longer than 366 days"),
+        (status = 401, description = "Unauthorized")
+    ),
+    tag = "user",
+    security(
+        ("telegram_auth" = [])
+    )
+)]
+async fn get_stats(
+    State(calendar): State<CalendarService>,
+    Extension(auth_user): Extension<AuthenticatedTelegramUser>,
+    Query(query): Query<StatsQuery>,
+) -> Result<Json<StatsResponse>, ApiError> {
+    let to = query.to.unwrap_or_else(Utc::now);
+    let from = query
+        .from
+        .unwrap_or_else(|| to - Duration::days(DEFAULT_STATS_DAYS));
+
+    let stats = calendar
+        .calendar_stats(auth_user.id, &auth_user.timezone, from, to)
+        .await?;
+    Ok(Json(stats.into()))
+}
+
+/// Version of the settings archive written by this server. Archives with a
+/// newer version are refused rather than half-applied.
+pub const SETTINGS_SCHEMA_VERSION: u32 = 1;
+
+/// Portable account settings, for moving between Televent instances
+///
+/// Events travel separately as iCalendar (`/export` in the bot, or CalDAV).
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct SettingsArchive {
+    #[schema(example = 1)]
+    pub schema_version: u32,
+    #[schema(example = "Europe/Berlin")]
+    pub timezone: String,
+    /// Working week, see `/me/preferences`
+    #[serde(default)]
+    #[schema(example = "Mon-Fri 09:00-17:00")]
+    pub working_hours: Option<String>,
+    #[serde(default)]
+    pub subscriptions: Vec<ArchivedSubscription>,
+}
+
+/// A calendar subscription, without its mirrored events
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ArchivedSubscription {
+    pub url: String,
+    pub name: String,
+}
+
+/// What an import changed
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SettingsImportResponse {
+    pub timezone: String,
+    /// Subscriptions created by this import
+    pub subscriptions_added: usize,
+    /// Subscriptions that already existed or no longer fit the account limit
+    pub subscriptions_skipped: usize,
+}
+
+/// Export account settings
+///
+/// Returns the user's settings as a versioned archive that another Televent
+/// instance can import.
+#[utoipa::path(
+    get,
+    path = "/me/settings/export",
+    responses(
+        (status = 200, description = "Settings archive", body = SettingsArchive),
+        (status = 401, description = "Unauthorized")
+    ),
+    tag = "user",
+    security(
+        ("telegram_auth" = [])
+    )
+)]
+async fn export_settings(
+    State(calendar): State<CalendarService>,
+    State(subscriptions): State<SubscriptionService>,
+    Extension(auth_user): Extension<AuthenticatedTelegramUser>,
+) -> Result<Json<SettingsArchive>, ApiError> {
+    // Read past the auth cache so a just-imported timezone shows up
+    let user = calendar
+        .get_user_identity_by_id(auth_user.id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound(format!("User not found: {}", auth_user.id)))?;
+    let working_hours = calendar.working_hours(auth_user.id).await?;
+    let subscriptions = subscriptions
+        .list_subscriptions(auth_user.id)
+        .await?
+        .into_iter()
+        .map(|subscription| ArchivedSubscription {
+            url: subscription.url,
+            name: subscription.name,
+        })
+        .collect();
+
+    Ok(Json(SettingsArchive {
+        schema_version: SETTINGS_SCHEMA_VERSION,
+        timezone: user.timezone.as_str().to_string(),
+        working_hours: working_hours.map(|hours| hours.to_string()),
+        subscriptions,
+    }))
+}
+
+/// Import account settings
+///
+/// Applies an archive from `/me/settings/export`: the timezone and any
+/// working hours are replaced and subscriptions not already present are
+/// added. Nothing is removed.
+#[utoipa::path(
+    post,
+    path = "/me/settings/import",
+    request_body = SettingsArchive,
+    responses(
+        (status = 200, description = "Settings applied", body = SettingsImportResponse),
+        (status = 400, description = "Unsupported schema version or invalid settings"),
+        (status = 401, description = "Unauthorized")
+    ),
+    tag = "user",
+    security(
+        ("telegram_auth" = [])
+    )
+)]
+async fn import_settings(
+    State(calendar): State<CalendarService>,
+    State(subscriptions): State<SubscriptionService>,
+    Extension(auth_user): Extension<AuthenticatedTelegramUser>,
+    Json(archive): Json<SettingsArchive>,
+) -> Result<Json<SettingsImportResponse>, ApiError> {
+    if archive.schema_version == 0 || archive.schema_version > SETTINGS_SCHEMA_VERSION {
+        return Err(ApiError::BadRequest(format!(
+            "Unsupported settings schema version {} (this server reads up to {})",
+            archive.schema_version, SETTINGS_SCHEMA_VERSION
+        )));
+    }
+    let timezone =
+        Timezone::parse(archive.timezone).map_err(|e| ApiError::BadRequest(e.to_string()))?;
+    let archived_urls = archive
+        .subscriptions
+        .iter()
+        .map(|subscription| normalize_subscription_url(&subscription.url))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if let Some(working_hours) = &archive.working_hours {
+        WorkingHours::parse(working_hours, &timezone)?;
+    }
+
+    let user = calendar.set_user_timezone(auth_user.id, &timezone).await?;
+    if let Some(working_hours) = &archive.working_hours {
+        calendar
+            .set_working_hours(auth_user.id, Some(working_hours))
+            .await?;
+    }
+
+    let mut existing: HashSet<String> = subscriptions
+        .list_subscriptions(auth_user.id)
+        .await?
+        .into_iter()
+        .map(|subscription| subscription.url)
+        .collect();
+    let mut added = 0;
+    let mut skipped = 0;
+    for (subscription, url) in archive.subscriptions.into_iter().zip(archived_urls) {
+        if !existing.insert(url.clone()) {
+            skipped += 1;
+            continue;
+        }
+        match subscriptions
+            .add_subscription(AddSubscriptionCommand {
+                user_id: auth_user.id,
+                username: auth_user.username.clone(),
+                url,
+                name: Some(subscription.name),
+            })
+            .await
+        {
+            Ok(_) => added += 1,
+            // Already subscribed under another spelling, or at the limit
+            Err(ApplicationError::Conflict(_) | ApplicationError::BadRequest(_)) => skipped += 1,
+            Err(err) => return Err(err.into()),
+        }
+    }
+
+    Ok(Json(SettingsImportResponse {
+        timezone: user.timezone.as_str().to_string(),
+        subscriptions_added: added,
+        subscriptions_skipped: skipped,
+    }))
+}
+
 pub fn routes() -> axum::Router<crate::AppState> {
-    axum::Router::new().route("/me", axum::routing::get(get_me))
+    axum::Router::new()
+        .route("/me", axum::routing::get(get_me))
+        .route("/me/slots", axum::routing::get(get_slots))
+        .route(
+            "/me/preferences",
+            axum::routing::get(get_preferences).put(put_preferences),
+        )
+        .route("/me/stats", axum::routing::get(get_stats))
+        .route("/me/settings/export", axum::routing::get(export_settings))
+        .route("/me/settings/import", axum::routing::post(import_settings))
 }
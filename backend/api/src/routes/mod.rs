@@ -1,11 +1,38 @@
 //! API route modules
 
+pub mod agenda;
+pub mod auth;
+pub mod birthdays;
+pub mod booking_links;
 pub mod caldav;
 pub mod calendars;
+pub mod carddav;
+pub mod chat_webhooks;
+pub mod contacts;
+pub mod delegates;
 
-mod caldav_ical;
-mod caldav_xml;
+pub(crate) mod caldav_ical;
+pub(crate) mod caldav_xml;
+mod carddav_xml;
 pub mod devices;
+pub mod email_verification;
+pub mod email_webhooks;
+pub(crate) mod etag;
 pub mod events;
+pub mod flags;
+pub mod focus;
+#[cfg(feature = "google-calendar")]
+pub mod google;
 pub mod health;
+pub mod holidays;
 pub mod me;
+pub mod notifications;
+pub mod public_booking;
+pub mod public_events;
+pub mod push;
+pub mod resources;
+pub mod roles;
+pub mod scheduled;
+pub mod tasks;
+pub mod triggers;
+pub mod unsubscribe;
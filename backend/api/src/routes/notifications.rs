@@ -0,0 +1,491 @@
+//! Notification preferences, the phone number for text messages and the
+//! email address for email copies
+//!
+//! The preference matrix switches copies of each kind of notice to chat
+//! webhooks, SMS, Web Push and email on or off. A phone number gets texts once
+//! its owner confirms it with the code texted to it; an email address gets
+//! notice copies once confirmed the same way, or through the emailed link.
+
+use axum::{
+    Extension, Json, Router,
+    extract::{FromRef, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::{get, post},
+};
+use serde::{Deserialize, Serialize};
+use televent_application::{
+    CalendarService, EmailAddressView, NotificationChannel, NotificationPreference,
+    NotificationService, NotificationTopic, PhoneNumberView,
+};
+use utoipa::ToSchema;
+
+use crate::{error::ApiError, middleware::telegram_auth::AuthenticatedTelegramUser};
+
+/// Whether the deployment can send text messages
+#[derive(Debug, Clone, Copy)]
+struct SmsNotifications(bool);
+
+const SMS_UNAVAILABLE: &str = "Text messages are not available on this server";
+
+/// Whether the deployment can send email
+#[derive(Debug, Clone, Copy)]
+struct EmailNotifications(bool);
+
+const EMAIL_UNAVAILABLE: &str = "Email is not available on this server";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Topic {
+    Invite,
+    Reminder,
+    Update,
+    Cancellation,
+}
+
+impl From<Topic> for NotificationTopic {
+    fn from(value: Topic) -> Self {
+        match value {
+            Topic::Invite => Self::Invite,
+            Topic::Reminder => Self::Reminder,
+            Topic::Update => Self::Update,
+            Topic::Cancellation => Self::Cancellation,
+        }
+    }
+}
+
+impl From<NotificationTopic> for Topic {
+    fn from(value: NotificationTopic) -> Self {
+        match value {
+            NotificationTopic::Invite => Self::Invite,
+            NotificationTopic::Reminder => Self::Reminder,
+            NotificationTopic::Update => Self::Update,
+            NotificationTopic::Cancellation => Self::Cancellation,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Channel {
+    /// Slack and Teams webhooks
+    Chat,
+    Sms,
+    /// Web Push to subscribed browsers
+    Push,
+    /// The confirmed email address
+    Email,
+}
+
+impl From<Channel> for NotificationChannel {
+    fn from(value: Channel) -> Self {
+        match value {
+            Channel::Chat => Self::Chat,
+            Channel::Sms => Self::Sms,
+            Channel::Push => Self::Push,
+            Channel::Email => Self::Email,
+        }
+    }
+}
+
+impl From<NotificationChannel> for Channel {
+    fn from(value: NotificationChannel) -> Self {
+        match value {
+            NotificationChannel::Chat => Self::Chat,
+            NotificationChannel::Sms => Self::Sms,
+            NotificationChannel::Push => Self::Push,
+            NotificationChannel::Email => Self::Email,
+        }
+    }
+}
+
+/// One cell of the preference matrix
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct NotificationPreferenceDto {
+    pub topic: Topic,
+    pub channel: Channel,
+    pub enabled: bool,
+}
+
+impl From<NotificationPreference> for NotificationPreferenceDto {
+    fn from(preference: NotificationPreference) -> Self {
+        Self {
+            topic: preference.topic.into(),
+            channel: preference.channel.into(),
+            enabled: preference.enabled,
+        }
+    }
+}
+
+/// Request to register a phone number
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct PutPhoneRequest {
+    /// International number with the country code
+    #[schema(example = "+44 20 7946 0958")]
+    pub phone_number: String,
+}
+
+/// Request to confirm the phone number
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct VerifyPhoneRequest {
+    #[schema(example = "123456")]
+    pub code: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PhoneNumberResponse {
+    /// E.164 form
+    #[schema(example = "+442079460958")]
+    pub phone_number: String,
+    pub verified: bool,
+    pub verified_at: Option<String>,
+}
+
+impl From<PhoneNumberView> for PhoneNumberResponse {
+    fn from(view: PhoneNumberView) -> Self {
+        Self {
+            phone_number: view.phone_number,
+            verified: view.verified_at.is_some(),
+            verified_at: view.verified_at.map(|t| t.to_rfc3339()),
+        }
+    }
+}
+
+/// Request to set the email address
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct PutEmailRequest {
+    #[schema(example = "ada@example.com")]
+    pub email: String,
+}
+
+/// Request to confirm the email address
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct VerifyEmailRequest {
+    #[schema(example = "123456")]
+    pub code: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct EmailAddressResponse {
+    #[schema(example = "ada@example.com")]
+    pub email: String,
+    pub verified: bool,
+    pub verified_at: Option<String>,
+}
+
+impl From<EmailAddressView> for EmailAddressResponse {
+    fn from(view: EmailAddressView) -> Self {
+        Self {
+            email: view.email,
+            verified: view.verified_at.is_some(),
+            verified_at: view.verified_at.map(|t| t.to_rfc3339()),
+        }
+    }
+}
+
+/// Get the notification preference matrix
+#[utoipa::path(
+    get,
+    path = "/me/notification-preferences",
+    responses(
+        (status = 200, description = "Every topic for every channel", body = Vec<NotificationPreferenceDto>),
+        (status = 401, description = "Unauthorized")
+    ),
+    tag = "user",
+    security(
+        ("telegram_auth" = [])
+    )
+)]
+async fn get_notification_preferences(
+    State(notifications): State<NotificationService>,
+    Extension(auth_user): Extension<AuthenticatedTelegramUser>,
+) -> Result<Json<Vec<NotificationPreferenceDto>>, ApiError> {
+    let preferences = notifications.preferences(auth_user.id).await?;
+
+    Ok(Json(preferences.into_iter().map(Into::into).collect()))
+}
+
+/// Change cells of the notification preference matrix
+///
+/// Cells left out keep their value. Returns the whole matrix.
+#[utoipa::path(
+    put,
+    path = "/me/notification-preferences",
+    request_body = Vec<NotificationPreferenceDto>,
+    responses(
+        (status = 200, description = "Every topic for every channel", body = Vec<NotificationPreferenceDto>),
+        (status = 401, description = "Unauthorized")
+    ),
+    tag = "user",
+    security(
+        ("telegram_auth" = [])
+    )
+)]
+async fn put_notification_preferences(
+    State(notifications): State<NotificationService>,
+    Extension(auth_user): Extension<AuthenticatedTelegramUser>,
+    Json(request): Json<Vec<NotificationPreferenceDto>>,
+) -> Result<Json<Vec<NotificationPreferenceDto>>, ApiError> {
+    let changes = request
+        .into_iter()
+        .map(|cell| NotificationPreference {
+            topic: cell.topic.into(),
+            channel: cell.channel.into(),
+            enabled: cell.enabled,
+        })
+        .collect::<Vec<_>>();
+    let preferences = notifications
+        .set_preferences(auth_user.id, &changes)
+        .await?;
+
+    Ok(Json(preferences.into_iter().map(Into::into).collect()))
+}
+
+/// Get the phone number texts go to
+#[utoipa::path(
+    get,
+    path = "/me/phone",
+    responses(
+        (status = 200, description = "Registered number", body = PhoneNumberResponse),
+        (status = 404, description = "No number registered"),
+        (status = 401, description = "Unauthorized")
+    ),
+    tag = "user",
+    security(
+        ("telegram_auth" = [])
+    )
+)]
+async fn get_phone(
+    State(notifications): State<NotificationService>,
+    Extension(auth_user): Extension<AuthenticatedTelegramUser>,
+) -> Result<Json<PhoneNumberResponse>, ApiError> {
+    let phone = notifications
+        .phone(auth_user.id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("No phone number registered".to_string()))?;
+
+    Ok(Json(phone.into()))
+}
+
+/// Register a phone number and text it a code
+///
+/// Replaces any earlier number. Sending the same number again asks for a
+/// new code, at most once a minute.
+#[utoipa::path(
+    put,
+    path = "/me/phone",
+    request_body = PutPhoneRequest,
+    responses(
+        (status = 202, description = "Code on its way", body = PhoneNumberResponse),
+        (status = 200, description = "Number already confirmed", body = PhoneNumberResponse),
+        (status = 400, description = "Invalid number, or a code was just sent"),
+        (status = 409, description = "Number confirmed by another account"),
+        (status = 503, description = "Text messages are disabled"),
+        (status = 401, description = "Unauthorized")
+    ),
+    tag = "user",
+    security(
+        ("telegram_auth" = [])
+    )
+)]
+async fn put_phone(
+    State(notifications): State<NotificationService>,
+    Extension(SmsNotifications(enabled)): Extension<SmsNotifications>,
+    Extension(auth_user): Extension<AuthenticatedTelegramUser>,
+    Json(request): Json<PutPhoneRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    if !enabled {
+        return Err(ApiError::ServiceUnavailable(SMS_UNAVAILABLE.to_string()));
+    }
+    let phone = notifications
+        .request_phone_verification(auth_user.id, &request.phone_number)
+        .await?;
+    let status = if phone.verified_at.is_some() {
+        StatusCode::OK
+    } else {
+        StatusCode::ACCEPTED
+    };
+
+    Ok((status, Json(PhoneNumberResponse::from(phone))))
+}
+
+/// Confirm the phone number with the texted code
+#[utoipa::path(
+    post,
+    path = "/me/phone/verify",
+    request_body = VerifyPhoneRequest,
+    responses(
+        (status = 200, description = "Number confirmed", body = PhoneNumberResponse),
+        (status = 400, description = "Wrong or expired code"),
+        (status = 404, description = "No number registered"),
+        (status = 409, description = "Number confirmed by another account"),
+        (status = 401, description = "Unauthorized")
+    ),
+    tag = "user",
+    security(
+        ("telegram_auth" = [])
+    )
+)]
+async fn verify_phone(
+    State(notifications): State<NotificationService>,
+    Extension(auth_user): Extension<AuthenticatedTelegramUser>,
+    Json(request): Json<VerifyPhoneRequest>,
+) -> Result<Json<PhoneNumberResponse>, ApiError> {
+    let phone = notifications
+        .verify_phone(auth_user.id, &request.code)
+        .await?;
+
+    Ok(Json(phone.into()))
+}
+
+/// Remove the phone number; texts stop at once
+#[utoipa::path(
+    delete,
+    path = "/me/phone",
+    responses(
+        (status = 204, description = "Number removed"),
+        (status = 404, description = "No number registered"),
+        (status = 401, description = "Unauthorized")
+    ),
+    tag = "user",
+    security(
+        ("telegram_auth" = [])
+    )
+)]
+async fn delete_phone(
+    State(notifications): State<NotificationService>,
+    Extension(auth_user): Extension<AuthenticatedTelegramUser>,
+) -> Result<impl IntoResponse, ApiError> {
+    if !notifications.remove_phone(auth_user.id).await? {
+        return Err(ApiError::NotFound("No phone number registered".to_string()));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Get the email address and whether it is confirmed
+#[utoipa::path(
+    get,
+    path = "/me/email",
+    responses(
+        (status = 200, description = "Saved address", body = EmailAddressResponse),
+        (status = 404, description = "No address saved"),
+        (status = 401, description = "Unauthorized")
+    ),
+    tag = "user",
+    security(
+        ("telegram_auth" = [])
+    )
+)]
+async fn get_email(
+    State(notifications): State<NotificationService>,
+    Extension(auth_user): Extension<AuthenticatedTelegramUser>,
+) -> Result<Json<EmailAddressResponse>, ApiError> {
+    let email = notifications
+        .email(auth_user.id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("No email address saved".to_string()))?;
+
+    Ok(Json(email.into()))
+}
+
+/// Set the email address and email it a code
+///
+/// Invites to the address reach the user on Telegram right away; anything
+/// else is only emailed once the address is confirmed. A new address
+/// replaces the old one unconfirmed. Sending the same address again asks
+/// for a new code, at most once a minute.
+#[utoipa::path(
+    put,
+    path = "/me/email",
+    request_body = PutEmailRequest,
+    responses(
+        (status = 202, description = "Code on its way", body = EmailAddressResponse),
+        (status = 200, description = "Address already confirmed", body = EmailAddressResponse),
+        (status = 400, description = "Invalid address, or a code was just sent"),
+        (status = 409, description = "Address used by another account"),
+        (status = 503, description = "Email is disabled"),
+        (status = 401, description = "Unauthorized")
+    ),
+    tag = "user",
+    security(
+        ("telegram_auth" = [])
+    )
+)]
+async fn put_email(
+    State(calendar): State<CalendarService>,
+    State(notifications): State<NotificationService>,
+    Extension(EmailNotifications(enabled)): Extension<EmailNotifications>,
+    Extension(auth_user): Extension<AuthenticatedTelegramUser>,
+    Json(request): Json<PutEmailRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    if !enabled {
+        return Err(ApiError::ServiceUnavailable(EMAIL_UNAVAILABLE.to_string()));
+    }
+    calendar
+        .set_user_email(auth_user.id, Some(&request.email))
+        .await?;
+    let email = notifications
+        .request_email_verification(auth_user.id)
+        .await?;
+    let status = if email.verified_at.is_some() {
+        StatusCode::OK
+    } else {
+        StatusCode::ACCEPTED
+    };
+
+    Ok((status, Json(EmailAddressResponse::from(email))))
+}
+
+/// Confirm the email address with the emailed code
+#[utoipa::path(
+    post,
+    path = "/me/email/verify",
+    request_body = VerifyEmailRequest,
+    responses(
+        (status = 200, description = "Address confirmed", body = EmailAddressResponse),
+        (status = 400, description = "Wrong or expired code"),
+        (status = 404, description = "No address saved"),
+        (status = 409, description = "The address changed since the code was sent"),
+        (status = 401, description = "Unauthorized")
+    ),
+    tag = "user",
+    security(
+        ("telegram_auth" = [])
+    )
+)]
+async fn verify_email(
+    State(notifications): State<NotificationService>,
+    Extension(auth_user): Extension<AuthenticatedTelegramUser>,
+    Json(request): Json<VerifyEmailRequest>,
+) -> Result<Json<EmailAddressResponse>, ApiError> {
+    let email = notifications
+        .verify_email(auth_user.id, &request.code)
+        .await?;
+
+    Ok(Json(email.into()))
+}
+
+/// Notification routes; `sms_notifications` lets users register a number
+/// and `email_notifications` lets them confirm an address
+pub fn routes<S>(sms_notifications: bool, email_notifications: bool) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+    NotificationService: FromRef<S>,
+    CalendarService: FromRef<S>,
+{
+    Router::new()
+        .route(
+            "/me/notification-preferences",
+            get(get_notification_preferences).put(put_notification_preferences),
+        )
+        .route(
+            "/me/phone",
+            get(get_phone).put(put_phone).delete(delete_phone),
+        )
+        .route("/me/phone/verify", post(verify_phone))
+        .route("/me/email", get(get_email).put(put_email))
+        .route("/me/email/verify", post(verify_email))
+        .layer(Extension(SmsNotifications(sms_notifications)))
+        .layer(Extension(EmailNotifications(email_notifications)))
+}
@@ -0,0 +1,229 @@
+//! Public booking pages
+//!
+//! Server-rendered pages for booking links. Visitors see the next free slots
+//! of the link, in the owner's timezone, and book one with their name and
+//! email. Only slots the page would offer right now are accepted, so a
+//! crafted form can't reach outside the shared hours or into busy time.
+
+use axum::{
+    Form, Router,
+    extract::{FromRef, Path, State},
+    http::StatusCode,
+    response::{Html, IntoResponse, Response},
+    routing::get,
+};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use televent_application::{
+    ApplicationError, BookSlotCommand, BookingLinkView, CalendarService, EventService, FreeSlot,
+    SubscriptionService,
+};
+use televent_domain::Timezone;
+
+use crate::middleware::base_path::BasePath;
+use crate::routes::public_events::{escape_html, page};
+
+/// Booking form posted from the page
+#[derive(Debug, Deserialize)]
+pub struct BookingForm {
+    pub start: DateTime<Utc>,
+    pub name: String,
+    pub email: String,
+}
+
+/// The link and the slots it offers right now
+async fn offered_slots(
+    calendar: &CalendarService,
+    subscriptions: &SubscriptionService,
+    slug: &str,
+) -> Result<(BookingLinkView, Timezone, Vec<FreeSlot>), ApplicationError> {
+    let now = Utc::now();
+    let (link, mut free_busy) = calendar.booking_free_busy(slug, now).await?;
+    subscriptions.mark_busy(link.owner, &mut free_busy).await?;
+    let slots = free_busy.bookable_slots(&link.search(now, free_busy.timezone()));
+
+    Ok((link, free_busy.timezone().clone(), slots))
+}
+
+async fn booking_page(
+    State(calendar): State<CalendarService>,
+    State(subscriptions): State<SubscriptionService>,
+    base: BasePath,
+    Path(slug): Path<String>,
+) -> Response {
+    match offered_slots(&calendar, &subscriptions, &slug).await {
+        Ok((link, timezone, slots)) => {
+            Html(render_booking_page(&base, &link, &timezone, &slots)).into_response()
+        }
+        Err(err) => error_page(err),
+    }
+}
+
+async fn book_slot(
+    State(calendar): State<CalendarService>,
+    State(subscriptions): State<SubscriptionService>,
+    State(events): State<EventService>,
+    Path(slug): Path<String>,
+    Form(form): Form<BookingForm>,
+) -> Response {
+    let (link, timezone, slots) = match offered_slots(&calendar, &subscriptions, &slug).await {
+        Ok(offered) => offered,
+        Err(err) => return error_page(err),
+    };
+    if !slots.iter().any(|slot| slot.start == form.start) {
+        return error_page(ApplicationError::Conflict(
+            "That time is no longer available. Please pick another slot.".to_string(),
+        ));
+    }
+
+    match events
+        .book_slot(BookSlotCommand {
+            slug,
+            start: form.start,
+            name: form.name,
+            email: form.email,
+        })
+        .await
+    {
+        Ok(_) => Html(page(
+            "Booking requested",
+            &format!(
+                "<h1>Booking requested</h1>\
+                 <p><strong>{}</strong> on {} ({}).</p>\
+                 <p>The organizer has been notified and will confirm it.</p>",
+                escape_html(&link.title),
+                escape_html(&format_local(form.start, &timezone)),
+                escape_html(timezone.as_str())
+            ),
+        ))
+        .into_response(),
+        Err(err) => error_page(err),
+    }
+}
+
+fn format_local(start: DateTime<Utc>, timezone: &Timezone) -> String {
+    start
+        .with_timezone(&timezone.tz())
+        .format("%a %d %b, %H:%M")
+        .to_string()
+}
+
+fn render_booking_page(
+    base: &BasePath,
+    link: &BookingLinkView,
+    timezone: &Timezone,
+    slots: &[FreeSlot],
+) -> String {
+    let mut body = format!(
+        "<h1>{}</h1><p>🕒 {} minutes · times in {}</p>",
+        escape_html(&link.title),
+        link.duration.num_minutes(),
+        escape_html(timezone.as_str())
+    );
+
+    if slots.is_empty() {
+        body.push_str("<p>No free slots right now. Please check back later.</p>");
+        return page(&link.title, &body);
+    }
+
+    body.push_str(&format!(
+        "<form method=\"post\" action=\"{}\">",
+        escape_html(&base.join(&format!("/b/{}", link.slug)))
+    ));
+    for (index, slot) in slots.iter().enumerate() {
+        body.push_str(&format!(
+            "<p><label><input type=\"radio\" name=\"start\" value=\"{}\"{}> {}</label></p>",
+            slot.start.to_rfc3339(),
+            if index == 0 { " required" } else { "" },
+            escape_html(&format_local(slot.start, timezone))
+        ));
+    }
+    body.push_str(
+        "<p><label>Name <input name=\"name\" required maxlength=\"128\"></label></p>\
+         <p><label>Email <input name=\"email\" type=\"email\" required maxlength=\"254\"></label></p>\
+         <p><button type=\"submit\">Book</button></p>\
+         </form>",
+    );
+
+    page(&link.title, &body)
+}
+
+fn error_page(err: ApplicationError) -> Response {
+    let (status, message) = match err {
+        ApplicationError::NotFound(_) => (
+            StatusCode::NOT_FOUND,
+            "This page does not exist or the link has been removed.".to_string(),
+        ),
+        ApplicationError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg),
+        ApplicationError::Conflict(msg) => (StatusCode::CONFLICT, msg),
+        ApplicationError::Internal(msg) => {
+            tracing::error!("Public booking page failed: {}", msg);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Something went wrong. Please try again later.".to_string(),
+            )
+        }
+    };
+
+    (
+        status,
+        Html(page(
+            "Televent",
+            &format!("<p>{}</p>", escape_html(&message)),
+        )),
+    )
+        .into_response()
+}
+
+/// Public booking page routes (no authentication)
+pub fn routes<S>() -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+    CalendarService: FromRef<S>,
+    EventService: FromRef<S>,
+    SubscriptionService: FromRef<S>,
+{
+    Router::new().route("/b/{slug}", get(booking_page).post(book_slot))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+    use televent_application::{DayHours, UserId};
+    use uuid::Uuid;
+
+    fn link() -> BookingLinkView {
+        BookingLinkView {
+            id: Uuid::new_v4(),
+            owner: UserId::new(1),
+            slug: "abc".to_string(),
+            title: "Intro <call>".to_string(),
+            duration: Duration::minutes(30),
+            hours: DayHours::parse("09:00-17:00").unwrap(),
+            within_days: 7,
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn booking_page_lists_slots_in_owner_timezone() {
+        let start = Utc.with_ymd_and_hms(2026, 11, 2, 8, 0, 0).unwrap();
+        let slots = [FreeSlot {
+            start,
+            end: start + Duration::minutes(30),
+            free_until: start + Duration::minutes(30),
+        }];
+        let timezone = Timezone::parse("Europe/Berlin").unwrap();
+        let html = render_booking_page(&BasePath::default(), &link(), &timezone, &slots);
+
+        assert!(html.contains("Intro &lt;call&gt;"));
+        assert!(html.contains("Mon 02 Nov, 09:00"));
+        assert!(html.contains("value=\"2026-11-02T08:00:00+00:00\""));
+        assert!(html.contains("action=\"/b/abc\""));
+
+        let html = render_booking_page(&BasePath::default(), &link(), &timezone, &[]);
+        assert!(html.contains("No free slots"));
+        assert!(!html.contains("<form"));
+    }
+}
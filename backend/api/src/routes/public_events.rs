@@ -0,0 +1,324 @@
+//! Public event pages
+//!
+//! Server-rendered pages for events an organizer has published. Visitors
+//! without Telegram sign up with their email address and become attendees
+//! once they open the emailed confirmation link. Without external email
+//! delivery the pages only show the event and point visitors to the organizer.
+
+use axum::{
+    Extension, Form, Router,
+    extract::{FromRef, Path, Query, State},
+    http::StatusCode,
+    response::{Html, IntoResponse, Response},
+    routing::{get, post},
+};
+use serde::Deserialize;
+use televent_application::{
+    ApplicationError, CalendarService, EventService, EventView, PublicSignupCommand,
+};
+use televent_domain::{EventStatus, EventTiming, EventVisibility};
+
+use crate::middleware::base_path::BasePath;
+
+/// Signup form posted from the public page
+#[derive(Debug, Deserialize)]
+pub struct SignupForm {
+    pub email: String,
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+/// Whether the deployment can email confirmation links
+#[derive(Debug, Clone, Copy)]
+struct EmailSignups(bool);
+
+const SIGNUPS_UNAVAILABLE: &str =
+    "Email signup is not available here. Ask the organizer to invite you directly.";
+
+/// Confirmation link query
+#[derive(Debug, Deserialize)]
+pub struct ConfirmQuery {
+    pub token: String,
+}
+
+async fn public_event_page(
+    State(calendar): State<CalendarService>,
+    Extension(EmailSignups(signups)): Extension<EmailSignups>,
+    base: BasePath,
+    Path(slug): Path<String>,
+) -> Response {
+    match calendar.get_public_event_view(&slug).await {
+        Ok(event) => Html(render_event_page(&base, &slug, &event, signups)).into_response(),
+        Err(err) => error_page(err),
+    }
+}
+
+async fn public_event_signup(
+    State(events): State<EventService>,
+    Extension(EmailSignups(signups)): Extension<EmailSignups>,
+    Path(slug): Path<String>,
+    Form(form): Form<SignupForm>,
+) -> Response {
+    if !signups {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Html(page(
+                "Televent",
+                &format!("<p>{}</p>", escape_html(SIGNUPS_UNAVAILABLE)),
+            )),
+        )
+            .into_response();
+    }
+
+    let email = form.email.trim().to_string();
+    match events
+        .request_public_signup(PublicSignupCommand {
+            slug,
+            email: email.clone(),
+            display_name: form.name,
+        })
+        .await
+    {
+        Ok(()) => Html(page(
+            "Check your inbox",
+            &format!(
+                "<h1>Check your inbox</h1>\
+                 <p>We sent a confirmation link to <strong>{}</strong>. \
+                 Open it to complete your signup.</p>",
+                escape_html(&email)
+            ),
+        ))
+        .into_response(),
+        Err(err) => error_page(err),
+    }
+}
+
+async fn public_event_confirm(
+    State(events): State<EventService>,
+    Query(query): Query<ConfirmQuery>,
+) -> Response {
+    match events.confirm_public_signup(&query.token).await {
+        Ok(event) => Html(page(
+            "You're on the list",
+            &format!(
+                "<h1>You're on the list</h1>\
+                 <p>Your spot at <strong>{}</strong> is confirmed.</p>\
+                 <p>{}</p>",
+                escape_html(&event.summary),
+                escape_html(&format_timing(&event.timing))
+            ),
+        ))
+        .into_response(),
+        Err(err) => error_page(err),
+    }
+}
+
+fn render_event_page(base: &BasePath, slug: &str, event: &EventView, signups: bool) -> String {
+    let mut body = format!(
+        "<h1>{}</h1><p>🕒 {}</p>",
+        escape_html(&event.summary),
+        escape_html(&format_timing(&event.timing))
+    );
+    if let Some(location) = &event.location {
+        body.push_str(&format!("<p>📍 {}</p>", escape_html(location)));
+    }
+    if let Some(description) = &event.description {
+        body.push_str(&format!(
+            "<p style=\"white-space: pre-line\">{}</p>",
+            escape_html(description)
+        ));
+    }
+
+    if event.status == EventStatus::Cancelled {
+        body.push_str("<p><strong>This event has been cancelled.</strong></p>");
+    } else if event.visibility != EventVisibility::Public {
+        // A busy-only block: only its time is shown and nobody signs up
+    } else if !signups {
+        body.push_str(&format!("<p>{}</p>", escape_html(SIGNUPS_UNAVAILABLE)));
+    } else {
+        body.push_str(&format!(
+            "<form method=\"post\" action=\"{}\">\
+             <p><label>Name <input name=\"name\" maxlength=\"128\"></label></p>\
+             <p><label>Email <input name=\"email\" type=\"email\" required maxlength=\"254\"></label></p>\
+             <p><button type=\"submit\">Sign up</button></p>\
+             </form>",
+            escape_html(&base.join(&format!("/e/{slug}/signup")))
+        ));
+    }
+
+    page(&event.summary, &body)
+}
+
+fn format_timing(timing: &EventTiming) -> String {
+    match timing {
+        EventTiming::Timed {
+            start,
+            end,
+            timezone,
+        } => format!(
+            "{} – {} ({})",
+            start.format("%a %d %b %Y, %H:%M UTC"),
+            end.format("%H:%M UTC"),
+            timezone.as_str()
+        ),
+        EventTiming::AllDay { start_date, .. } => {
+            format!("{} (all day)", start_date.format("%a %d %b %Y"))
+        } // Visitors have no known zone; the wall-clock time is the same for all
+        EventTiming::Floating { start, end } => format!(
+            "{} – {} (local time)",
+            start.format("%a %d %b %Y, %H:%M"),
+            end.format("%H:%M")
+        ),
+    }
+}
+
+fn error_page(err: ApplicationError) -> Response {
+    let (status, message) = match err {
+        ApplicationError::NotFound(_) => (
+            StatusCode::NOT_FOUND,
+            "This page does not exist or the link has expired.".to_string(),
+        ),
+        ApplicationError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg),
+        ApplicationError::Conflict(msg) => (StatusCode::CONFLICT, msg),
+        ApplicationError::Internal(msg) => {
+            tracing::error!("Public event page failed: {}", msg);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Something went wrong. Please try again later.".to_string(),
+            )
+        }
+    };
+
+    (
+        status,
+        Html(page(
+            "Televent",
+            &format!("<p>{}</p>", escape_html(&message)),
+        )),
+    )
+        .into_response()
+}
+
+pub(crate) fn page(title: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html><html lang=\"en\"><head><meta charset=\"utf-8\">\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\
+         <title>{}</title></head>\
+         <body style=\"font-family: sans-serif; max-width: 36rem; margin: 2rem auto; padding: 0 1rem\">\
+         {body}</body></html>",
+        escape_html(title)
+    )
+}
+
+pub(crate) fn escape_html(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#x27;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Public event page routes (no authentication)
+///
+/// `email_signups` enables the signup form; leave it off unless confirmation
+/// emails are actually delivered.
+pub fn routes<S>(email_signups: bool) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+    CalendarService: FromRef<S>,
+    EventService: FromRef<S>,
+{
+    Router::new()
+        .route("/e/{slug}", get(public_event_page))
+        .route("/e/{slug}/signup", post(public_event_signup))
+        .route("/e/{slug}/confirm", get(public_event_confirm))
+        .layer(Extension(EmailSignups(email_signups)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use uuid::Uuid;
+
+    fn event(status: EventStatus) -> EventView {
+        EventView {
+            id: Uuid::new_v4(),
+            uid: "public-1".to_string(),
+            summary: "Meetup <script>".to_string(),
+            description: Some("Bring \"snacks\"".to_string()),
+            location: None,
+            timing: EventTiming::AllDay {
+                start_date: NaiveDate::from_ymd_opt(2026, 11, 2).unwrap(),
+                end_date: NaiveDate::from_ymd_opt(2026, 11, 3).unwrap(),
+            },
+            status,
+            rrule: None,
+            visibility: EventVisibility::Public,
+            reminder_note: None,
+        }
+    }
+
+    #[test]
+    fn event_page_escapes_content_and_offers_signup() {
+        let html = render_event_page(
+            &BasePath::default(),
+            "abc",
+            &event(EventStatus::Confirmed),
+            true,
+        );
+
+        assert!(html.contains("Meetup &lt;script&gt;"));
+        assert!(!html.contains("<script>"));
+        assert!(html.contains("Bring &quot;snacks&quot;"));
+        assert!(html.contains("Mon 02 Nov 2026 (all day)"));
+        assert!(html.contains("action=\"/e/abc/signup\""));
+
+        let base = BasePath::parse("/televent").unwrap();
+        let html = render_event_page(&base, "abc", &event(EventStatus::Confirmed), true);
+        assert!(html.contains("action=\"/televent/e/abc/signup\""));
+    }
+
+    #[test]
+    fn cancelled_event_page_hides_signup_form() {
+        let html = render_event_page(
+            &BasePath::default(),
+            "abc",
+            &event(EventStatus::Cancelled),
+            true,
+        );
+
+        assert!(html.contains("cancelled"));
+        assert!(!html.contains("<form"));
+    }
+
+    #[test]
+    fn event_page_without_email_points_to_organizer() {
+        let html = render_event_page(
+            &BasePath::default(),
+            "abc",
+            &event(EventStatus::Confirmed),
+            false,
+        );
+
+        assert!(html.contains("Ask the organizer to invite you directly"));
+        assert!(!html.contains("<form"));
+    }
+
+    #[test]
+    fn busy_only_event_page_hides_signup_form() {
+        let mut busy = event(EventStatus::Confirmed);
+        busy.visibility = EventVisibility::BusyOnly;
+        let html = render_event_page(&BasePath::default(), "abc", &busy, true);
+
+        assert!(html.contains("Mon 02 Nov 2026 (all day)"));
+        assert!(!html.contains("<form"));
+    }
+}
@@ -0,0 +1,190 @@
+//! Web Push subscription endpoints
+//!
+//! The web app subscribes the browser with our VAPID public key and posts
+//! the resulting `PushSubscription` here; the worker then pushes invites,
+//! reminders, changes and cancellations to it.
+
+use axum::{
+    Extension, Json, Router,
+    extract::{FromRef, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::{get, post},
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use televent_application::{SubscribeWebPushCommand, WebPushService, WebPushSubscriptionView};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::{error::ApiError, middleware::telegram_auth::AuthenticatedTelegramUser};
+
+/// VAPID public key; `None` while the deployment cannot push
+#[derive(Debug, Clone)]
+struct VapidPublicKey(Option<String>);
+
+const PUSH_UNAVAILABLE: &str = "Push notifications are not available on this server";
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct VapidPublicKeyResponse {
+    /// `applicationServerKey` for `PushManager.subscribe()`, base64url
+    pub public_key: String,
+}
+
+/// Keys of a browser subscription
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct PushSubscriptionKeys {
+    /// Browser public key, base64url
+    pub p256dh: String,
+    /// Authentication secret, base64url
+    pub auth: String,
+}
+
+/// A `PushSubscription` as its `toJSON()` gives it
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PushSubscribeRequest {
+    #[schema(example = "https://fcm.googleapis.com/fcm/send/dGVzdA:APA91b")]
+    pub endpoint: String,
+    /// Milliseconds since the epoch; most browsers send `null`
+    pub expiration_time: Option<i64>,
+    pub keys: PushSubscriptionKeys,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct PushUnsubscribeRequest {
+    pub endpoint: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PushSubscriptionResponse {
+    pub id: Uuid,
+    pub expires_at: Option<String>,
+    pub created_at: String,
+}
+
+impl From<WebPushSubscriptionView> for PushSubscriptionResponse {
+    fn from(view: WebPushSubscriptionView) -> Self {
+        Self {
+            id: view.id,
+            expires_at: view.expires_at.map(|t| t.to_rfc3339()),
+            created_at: view.created_at.to_rfc3339(),
+        }
+    }
+}
+
+/// Get the key the web app subscribes browsers with
+#[utoipa::path(
+    get,
+    path = "/push/vapid-public-key",
+    responses(
+        (status = 200, description = "VAPID public key", body = VapidPublicKeyResponse),
+        (status = 503, description = "Push notifications are disabled"),
+        (status = 401, description = "Unauthorized")
+    ),
+    tag = "user",
+    security(
+        ("telegram_auth" = [])
+    )
+)]
+async fn vapid_public_key(
+    Extension(VapidPublicKey(public_key)): Extension<VapidPublicKey>,
+) -> Result<Json<VapidPublicKeyResponse>, ApiError> {
+    let public_key =
+        public_key.ok_or_else(|| ApiError::ServiceUnavailable(PUSH_UNAVAILABLE.to_string()))?;
+
+    Ok(Json(VapidPublicKeyResponse { public_key }))
+}
+
+/// Subscribe this browser to the user's notifications
+///
+/// Subscribing the same browser again refreshes its keys; a user keeps
+/// their ten latest browsers.
+#[utoipa::path(
+    post,
+    path = "/push/subscribe",
+    request_body = PushSubscribeRequest,
+    responses(
+        (status = 201, description = "Browser subscribed", body = PushSubscriptionResponse),
+        (status = 400, description = "Not a browser push service endpoint, or invalid keys"),
+        (status = 503, description = "Push notifications are disabled"),
+        (status = 401, description = "Unauthorized")
+    ),
+    tag = "user",
+    security(
+        ("telegram_auth" = [])
+    )
+)]
+async fn subscribe(
+    State(push): State<WebPushService>,
+    Extension(VapidPublicKey(public_key)): Extension<VapidPublicKey>,
+    Extension(auth_user): Extension<AuthenticatedTelegramUser>,
+    Json(request): Json<PushSubscribeRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    if public_key.is_none() {
+        return Err(ApiError::ServiceUnavailable(PUSH_UNAVAILABLE.to_string()));
+    }
+    let expires_at = request
+        .expiration_time
+        .map(|millis| {
+            DateTime::<Utc>::from_timestamp_millis(millis)
+                .ok_or_else(|| ApiError::BadRequest("Invalid expirationTime".to_string()))
+        })
+        .transpose()?;
+    let subscription = push
+        .subscribe(SubscribeWebPushCommand {
+            user_id: auth_user.id,
+            endpoint: request.endpoint,
+            p256dh: request.keys.p256dh,
+            auth: request.keys.auth,
+            expires_at,
+        })
+        .await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(PushSubscriptionResponse::from(subscription)),
+    ))
+}
+
+/// Stop pushing to a browser
+#[utoipa::path(
+    post,
+    path = "/push/unsubscribe",
+    request_body = PushUnsubscribeRequest,
+    responses(
+        (status = 204, description = "Browser unsubscribed"),
+        (status = 404, description = "Subscription not found"),
+        (status = 401, description = "Unauthorized")
+    ),
+    tag = "user",
+    security(
+        ("telegram_auth" = [])
+    )
+)]
+async fn unsubscribe(
+    State(push): State<WebPushService>,
+    Extension(auth_user): Extension<AuthenticatedTelegramUser>,
+    Json(request): Json<PushUnsubscribeRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    if !push.unsubscribe(auth_user.id, &request.endpoint).await? {
+        return Err(ApiError::NotFound(
+            "Push subscription not found".to_string(),
+        ));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Web Push routes; without the VAPID `public_key` browsers cannot subscribe
+pub fn routes<S>(public_key: Option<String>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+    WebPushService: FromRef<S>,
+{
+    Router::new()
+        .route("/push/vapid-public-key", get(vapid_public_key))
+        .route("/push/subscribe", post(subscribe))
+        .route("/push/unsubscribe", post(unsubscribe))
+        .layer(Extension(VapidPublicKey(public_key)))
+}
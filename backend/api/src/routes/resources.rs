@@ -0,0 +1,341 @@
+//! Bookable resource endpoints
+//!
+//! Admins add and remove resources such as meeting rooms; every user can list
+//! them, read their free/busy and book one for an event on their calendar.
+//! Each resource is also a read-only CalDAV calendar at
+//! `/caldav/{user}/resources/{id}/` showing its bookings as busy blocks.
+
+use axum::{
+    Extension, Json, Router,
+    extract::{FromRef, Path, Query, State},
+    http::StatusCode,
+    middleware::from_fn_with_state,
+    response::IntoResponse,
+    routing::{delete, get, post},
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use televent_application::{
+    BusyInterval, CalendarService, EventService, ResourceBookingView, ResourceView, UserRole,
+};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::{
+    error::ApiError,
+    middleware::{roles::require_role, telegram_auth::AuthenticatedTelegramUser},
+    routes::events::CalendarQuery,
+};
+
+/// A bookable resource
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ResourceResponse {
+    pub id: Uuid,
+    #[schema(example = "Room 4")]
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<ResourceView> for ResourceResponse {
+    fn from(view: ResourceView) -> Self {
+        Self {
+            id: view.id,
+            name: view.name,
+            created_at: view.created_at,
+        }
+    }
+}
+
+/// Request to add a resource
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateResourceRequest {
+    /// Unique ignoring case; at most 128 characters
+    #[schema(example = "Room 4")]
+    pub name: String,
+}
+
+/// Request to book a resource for an event
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct BookResourceRequest {
+    pub resource_id: Uuid,
+}
+
+/// The resource an event holds and for when
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ResourceBookingResponse {
+    pub event_id: Uuid,
+    pub resource_id: Uuid,
+    /// All-day and floating events hold the resource in the owner's timezone
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+impl From<ResourceBookingView> for ResourceBookingResponse {
+    fn from(view: ResourceBookingView) -> Self {
+        Self {
+            event_id: view.event_id,
+            resource_id: view.resource_id,
+            start: view.start,
+            end: view.end,
+        }
+    }
+}
+
+/// Free/busy query parameters
+#[derive(Debug, Deserialize, ToSchema, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct FreeBusyQuery {
+    pub start: DateTime<Utc>,
+    /// At most 62 days after `start`
+    pub end: DateTime<Utc>,
+}
+
+/// A span of time the resource is booked
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BusyIntervalResponse {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+impl From<BusyInterval> for BusyIntervalResponse {
+    fn from(interval: BusyInterval) -> Self {
+        Self {
+            start: interval.start,
+            end: interval.end,
+        }
+    }
+}
+
+/// List resources
+#[utoipa::path(
+    get,
+    path = "/resources",
+    responses(
+        (status = 200, description = "Every resource, by name", body = Vec<ResourceResponse>),
+        (status = 401, description = "Unauthorized")
+    ),
+    tag = "resources",
+    security(
+        ("telegram_auth" = [])
+    )
+)]
+async fn list_resources(
+    State(calendar): State<CalendarService>,
+) -> Result<Json<Vec<ResourceResponse>>, ApiError> {
+    let resources = calendar.list_resources().await?;
+    Ok(Json(
+        resources.into_iter().map(ResourceResponse::from).collect(),
+    ))
+}
+
+/// Resource free/busy
+///
+/// Times the resource is booked in the window, without the events holding it.
+#[utoipa::path(
+    get,
+    path = "/resources/{id}/freebusy",
+    params(
+        ("id" = Uuid, Path, description = "Resource ID"),
+        FreeBusyQuery
+    ),
+    responses(
+        (status = 200, description = "Busy times overlapping the window, earliest first", body = Vec<BusyIntervalResponse>),
+        (status = 400, description = "Empty or too long window"),
+        (status = 404, description = "Resource not found"),
+        (status = 401, description = "Unauthorized")
+    ),
+    tag = "resources",
+    security(
+        ("telegram_auth" = [])
+    )
+)]
+async fn get_resource_free_busy(
+    State(calendar): State<CalendarService>,
+    Path(resource_id): Path<Uuid>,
+    Query(query): Query<FreeBusyQuery>,
+) -> Result<Json<Vec<BusyIntervalResponse>>, ApiError> {
+    let busy = calendar
+        .resource_free_busy(resource_id, query.start, query.end)
+        .await?;
+    Ok(Json(
+        busy.into_iter().map(BusyIntervalResponse::from).collect(),
+    ))
+}
+
+/// Add a resource
+#[utoipa::path(
+    post,
+    path = "/admin/resources",
+    request_body = CreateResourceRequest,
+    responses(
+        (status = 201, description = "Resource added", body = ResourceResponse),
+        (status = 400, description = "Empty or too long name"),
+        (status = 409, description = "A resource with that name exists"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Not an admin")
+    ),
+    tag = "admin",
+    security(
+        ("telegram_auth" = [])
+    )
+)]
+async fn create_resource(
+    State(events): State<EventService>,
+    Json(request): Json<CreateResourceRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let resource = events.create_resource(&request.name).await?;
+    Ok((StatusCode::CREATED, Json(ResourceResponse::from(resource))))
+}
+
+/// Remove a resource
+///
+/// Events that booked it stay on their calendars, without the resource.
+#[utoipa::path(
+    delete,
+    path = "/admin/resources/{id}",
+    params(
+        ("id" = Uuid, Path, description = "Resource ID")
+    ),
+    responses(
+        (status = 204, description = "Resource removed"),
+        (status = 404, description = "Resource not found"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Not an admin")
+    ),
+    tag = "admin",
+    security(
+        ("telegram_auth" = [])
+    )
+)]
+async fn delete_resource(
+    State(events): State<EventService>,
+    Path(resource_id): Path<Uuid>,
+) -> Result<StatusCode, ApiError> {
+    events.delete_resource(resource_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Get the resource an event holds
+#[utoipa::path(
+    get,
+    path = "/events/{id}/resource",
+    params(
+        ("id" = Uuid, Path, description = "Event ID")
+    ),
+    responses(
+        (status = 200, description = "The event's booking", body = ResourceBookingResponse),
+        (status = 404, description = "Event not found or holds no resource"),
+        (status = 401, description = "Unauthorized")
+    ),
+    tag = "resources",
+    security(
+        ("telegram_auth" = [])
+    )
+)]
+async fn get_event_resource(
+    State(calendar): State<CalendarService>,
+    Extension(auth_user): Extension<AuthenticatedTelegramUser>,
+    Path(event_id): Path<Uuid>,
+) -> Result<Json<ResourceBookingResponse>, ApiError> {
+    let booking = calendar
+        .get_event_resource_booking(auth_user.id, event_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound(format!("resource booking of {event_id}")))?;
+    Ok(Json(ResourceBookingResponse::from(booking)))
+}
+
+/// Book a resource for an event
+///
+/// The event holds the resource for its whole time and keeps it when moved,
+/// as long as the resource is free at the new time. Booking another resource
+/// lets the first one go.
+#[utoipa::path(
+    put,
+    path = "/events/{id}/resource",
+    request_body = BookResourceRequest,
+    params(
+        ("id" = Uuid, Path, description = "Event ID"),
+        CalendarQuery
+    ),
+    responses(
+        (status = 200, description = "Resource booked", body = ResourceBookingResponse),
+        (status = 400, description = "Recurring or cancelled event"),
+        (status = 404, description = "Event or resource not found"),
+        (status = 409, description = "Resource already booked at that time"),
+        (status = 401, description = "Unauthorized")
+    ),
+    tag = "resources",
+    security(
+        ("telegram_auth" = [])
+    )
+)]
+async fn book_resource(
+    State(events): State<EventService>,
+    Extension(auth_user): Extension<AuthenticatedTelegramUser>,
+    Path(event_id): Path<Uuid>,
+    Query(calendar): Query<CalendarQuery>,
+    Json(request): Json<BookResourceRequest>,
+) -> Result<Json<ResourceBookingResponse>, ApiError> {
+    let booking = events
+        .book_resource(
+            auth_user.id,
+            calendar.owner(&auth_user),
+            event_id,
+            request.resource_id,
+        )
+        .await?;
+    Ok(Json(ResourceBookingResponse::from(booking)))
+}
+
+/// Release the resource an event holds
+#[utoipa::path(
+    delete,
+    path = "/events/{id}/resource",
+    params(
+        ("id" = Uuid, Path, description = "Event ID"),
+        CalendarQuery
+    ),
+    responses(
+        (status = 204, description = "Resource released"),
+        (status = 404, description = "Event not found or holds no resource"),
+        (status = 401, description = "Unauthorized")
+    ),
+    tag = "resources",
+    security(
+        ("telegram_auth" = [])
+    )
+)]
+async fn release_resource(
+    State(events): State<EventService>,
+    Extension(auth_user): Extension<AuthenticatedTelegramUser>,
+    Path(event_id): Path<Uuid>,
+    Query(calendar): Query<CalendarQuery>,
+) -> Result<StatusCode, ApiError> {
+    events
+        .release_resource(auth_user.id, calendar.owner(&auth_user), event_id)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub fn routes<S>() -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+    CalendarService: FromRef<S>,
+    EventService: FromRef<S>,
+{
+    let admin = Router::new()
+        .route("/admin/resources", post(create_resource))
+        .route("/admin/resources/{id}", delete(delete_resource))
+        .route_layer(from_fn_with_state(UserRole::Admin, require_role));
+
+    Router::new()
+        .route("/resources", get(list_resources))
+        .route("/resources/{id}/freebusy", get(get_resource_free_busy))
+        .route(
+            "/events/{id}/resource",
+            get(get_event_resource)
+                .put(book_resource)
+                .delete(release_resource),
+        )
+        .merge(admin)
+}
@@ -0,0 +1,98 @@
+//! User role admin endpoints
+//!
+//! Bootstrap admins come from `ADMIN_TELEGRAM_IDS` at startup; from there
+//! admins hand out roles here.
+
+use axum::{
+    Extension, Json, Router,
+    extract::{FromRef, Path, State},
+    middleware::from_fn_with_state,
+    routing::put,
+};
+use serde::{Deserialize, Serialize};
+use televent_application::{CalendarService, UserId, UserRole};
+use utoipa::ToSchema;
+
+use crate::{
+    error::ApiError,
+    middleware::{roles::require_role, telegram_auth::AuthenticatedTelegramUser},
+};
+
+/// Role to give a user
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct PutUserRoleRequest {
+    /// `user`, `operator` or `admin`
+    #[schema(example = "operator")]
+    pub role: String,
+}
+
+/// User with their new role
+#[derive(Debug, Serialize, ToSchema)]
+pub struct UserRoleResponse {
+    pub user_id: i64,
+    pub username: Option<String>,
+    #[schema(example = "operator")]
+    pub role: String,
+}
+
+/// Change a user's role
+#[utoipa::path(
+    put,
+    path = "/admin/users/{user_id}/role",
+    request_body = PutUserRoleRequest,
+    responses(
+        (status = 200, description = "Role saved", body = UserRoleResponse),
+        (status = 400, description = "Unknown role or own role"),
+        (status = 404, description = "User not found"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Not an admin")
+    ),
+    params(
+        ("user_id" = i64, Path, description = "Telegram id of the user")
+    ),
+    tag = "admin",
+    security(
+        ("telegram_auth" = [])
+    )
+)]
+async fn put_user_role(
+    State(calendar): State<CalendarService>,
+    Extension(auth_user): Extension<AuthenticatedTelegramUser>,
+    Path(user_id): Path<i64>,
+    Json(request): Json<PutUserRoleRequest>,
+) -> Result<Json<UserRoleResponse>, ApiError> {
+    let role = UserRole::parse(&request.role).ok_or_else(|| {
+        ApiError::BadRequest("Role must be one of user, operator, admin".to_string())
+    })?;
+    // Keeps the last admin from locking everyone out by accident
+    if auth_user.id.inner() == user_id {
+        return Err(ApiError::BadRequest(
+            "Admins can't change their own role".to_string(),
+        ));
+    }
+
+    let user = calendar.set_user_role(UserId::new(user_id), role).await?;
+
+    tracing::info!(
+        "User {} role set to {} by {}",
+        user.id,
+        role.as_sql(),
+        auth_user.id
+    );
+    Ok(Json(UserRoleResponse {
+        user_id: user.id.inner(),
+        username: user.username,
+        role: user.role.as_sql().to_string(),
+    }))
+}
+
+/// User role admin routes
+pub fn routes<S>() -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+    CalendarService: FromRef<S>,
+{
+    Router::new()
+        .route("/admin/users/{user_id}/role", put(put_user_role))
+        .route_layer(from_fn_with_state(UserRole::Admin, require_role))
+}
@@ -0,0 +1,217 @@
+//! Scheduled message endpoints
+//!
+//! Organizers can have an invite re-sent at a later time and invitees can
+//! snooze a reminder; either can see what is still waiting to go out and
+//! cancel it before the worker sends it.
+
+use axum::{
+    Extension, Json, Router,
+    extract::{FromRef, Path, State},
+    http::StatusCode,
+    routing::{delete, get, post},
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use televent_application::{
+    EventService, OutboxMessageId, ResendInviteCommand, ScheduledMessageView, SnoozeDelay,
+};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::{error::ApiError, middleware::telegram_auth::AuthenticatedTelegramUser};
+
+/// When to remind an invitee who hasn't replied
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ScheduleReminderRequest {
+    /// Invitee's attendee email
+    #[schema(example = "tg_123456789@televent.internal")]
+    pub email: String,
+    /// At most 30 days ahead
+    #[schema(example = "2026-06-01T09:00:00Z")]
+    pub send_at: DateTime<Utc>,
+}
+
+/// How long to put off an invite reminder
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SnoozeReminderRequest {
+    /// "10m", "1h" or "tomorrow" (09:00 in your timezone)
+    #[schema(example = "1h")]
+    pub delay: String,
+}
+
+/// A message waiting for its scheduled time
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ScheduledMessageResponse {
+    pub id: OutboxMessageId,
+    #[schema(example = "invite_reminder")]
+    pub kind: String,
+    pub event_id: Option<Uuid>,
+    pub scheduled_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<ScheduledMessageView> for ScheduledMessageResponse {
+    fn from(view: ScheduledMessageView) -> Self {
+        Self {
+            id: view.id,
+            kind: view.kind.as_str().to_string(),
+            event_id: view.event_id,
+            scheduled_at: view.scheduled_at,
+            created_at: view.created_at,
+        }
+    }
+}
+
+/// Schedule an invite reminder
+///
+/// Re-sends the invite to a Telegram invitee who hasn't replied yet, at the
+/// given time instead of now.
+#[utoipa::path(
+    post,
+    path = "/events/{id}/reminders",
+    request_body = ScheduleReminderRequest,
+    responses(
+        (status = 201, description = "Reminder scheduled", body = ScheduledMessageResponse),
+        (status = 400, description = "Time out of range, or invitee already replied"),
+        (status = 404, description = "Event or invitee not found"),
+        (status = 401, description = "Unauthorized")
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Event ID")
+    ),
+    tag = "events",
+    security(
+        ("telegram_auth" = [])
+    )
+)]
+async fn schedule_reminder(
+    State(events): State<EventService>,
+    Extension(auth_user): Extension<AuthenticatedTelegramUser>,
+    Path(event_id): Path<Uuid>,
+    Json(request): Json<ScheduleReminderRequest>,
+) -> Result<(StatusCode, Json<ScheduledMessageResponse>), ApiError> {
+    let now = events.clock().now();
+    let id = events
+        .schedule_invite_reminder(
+            ResendInviteCommand {
+                organizer_user_id: auth_user.id,
+                event_id,
+                email: request.email,
+            },
+            request.send_at,
+            now,
+        )
+        .await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(ScheduledMessageResponse {
+            id,
+            kind: "invite_reminder".to_string(),
+            event_id: Some(event_id),
+            scheduled_at: request.send_at,
+            created_at: now,
+        }),
+    ))
+}
+
+/// Snooze an invite reminder
+///
+/// For the invitee: have the reminder for an invite you haven't answered
+/// come back later, like the snooze buttons on the Telegram reminder.
+#[utoipa::path(
+    post,
+    path = "/events/{id}/snooze",
+    request_body = SnoozeReminderRequest,
+    responses(
+        (status = 201, description = "Reminder snoozed", body = ScheduledMessageResponse),
+        (status = 400, description = "Unknown delay, or invite already answered"),
+        (status = 404, description = "Not invited to this event"),
+        (status = 401, description = "Unauthorized")
+    ),
+    params(
+        ("id" = Uuid, Path, description = "Event ID")
+    ),
+    tag = "events",
+    security(
+        ("telegram_auth" = [])
+    )
+)]
+async fn snooze_reminder(
+    State(events): State<EventService>,
+    Extension(auth_user): Extension<AuthenticatedTelegramUser>,
+    Path(event_id): Path<Uuid>,
+    Json(request): Json<SnoozeReminderRequest>,
+) -> Result<(StatusCode, Json<ScheduledMessageResponse>), ApiError> {
+    let delay = SnoozeDelay::parse(&request.delay).ok_or_else(|| {
+        ApiError::BadRequest("Delay must be one of 10m, 1h or tomorrow".to_string())
+    })?;
+    let scheduled = events
+        .snooze_invite_reminder(auth_user.id, event_id, delay, events.clock().now())
+        .await?;
+    Ok((StatusCode::CREATED, Json(scheduled.into())))
+}
+
+/// List scheduled messages
+///
+/// Messages the current user scheduled that haven't been sent, soonest first.
+#[utoipa::path(
+    get,
+    path = "/scheduled-messages",
+    responses(
+        (status = 200, description = "Pending scheduled messages", body = Vec<ScheduledMessageResponse>),
+        (status = 401, description = "Unauthorized")
+    ),
+    tag = "events",
+    security(
+        ("telegram_auth" = [])
+    )
+)]
+async fn list_scheduled_messages(
+    State(events): State<EventService>,
+    Extension(auth_user): Extension<AuthenticatedTelegramUser>,
+) -> Result<Json<Vec<ScheduledMessageResponse>>, ApiError> {
+    let messages = events.list_scheduled_messages(auth_user.id).await?;
+    Ok(Json(messages.into_iter().map(Into::into).collect()))
+}
+
+/// Cancel a scheduled message
+#[utoipa::path(
+    delete,
+    path = "/scheduled-messages/{id}",
+    responses(
+        (status = 204, description = "Cancelled"),
+        (status = 404, description = "Not found, already sent or being sent"),
+        (status = 401, description = "Unauthorized")
+    ),
+    params(
+        ("id" = OutboxMessageId, Path, description = "Scheduled message ID")
+    ),
+    tag = "events",
+    security(
+        ("telegram_auth" = [])
+    )
+)]
+async fn cancel_scheduled_message(
+    State(events): State<EventService>,
+    Extension(auth_user): Extension<AuthenticatedTelegramUser>,
+    Path(message_id): Path<OutboxMessageId>,
+) -> Result<StatusCode, ApiError> {
+    events
+        .cancel_scheduled_message(auth_user.id, message_id)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Scheduled message routes
+pub fn routes<S>() -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+    EventService: FromRef<S>,
+{
+    Router::new()
+        .route("/events/{id}/reminders", post(schedule_reminder))
+        .route("/events/{id}/snooze", post(snooze_reminder))
+        .route("/scheduled-messages", get(list_scheduled_messages))
+        .route("/scheduled-messages/{id}", delete(cancel_scheduled_message))
+}
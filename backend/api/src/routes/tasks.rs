@@ -0,0 +1,357 @@
+//! Task and time blocking endpoints
+//!
+//! Tasks are to-dos with a duration. Planning a day gives each open task
+//! without a block the first free slot of its length, as a tentative event
+//! linked to the task; confirming the block confirms the event, and at its
+//! end the bot asks whether the task is done.
+
+use axum::{
+    Extension, Json, Router,
+    extract::{FromRef, Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::{delete, get, post},
+};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use televent_application::{
+    CalendarService, CreateTaskCommand, EventService, PlannedBlock, SubscriptionService,
+    TaskBlockView, TaskView, parse_duration_spec, plan_window,
+};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::{error::ApiError, middleware::telegram_auth::AuthenticatedTelegramUser};
+
+/// Request to add a task
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateTaskRequest {
+    #[schema(example = "Write report")]
+    pub title: String,
+    /// How long the task takes, e.g. `45m`, `1h30m` or `PT2H`; 5m to 8h
+    #[schema(example = "45m")]
+    pub duration: String,
+}
+
+/// The event planned for a task
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TaskBlockResponse {
+    pub event_id: Uuid,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    /// Planned blocks are tentative until confirmed
+    pub confirmed: bool,
+}
+
+impl From<TaskBlockView> for TaskBlockResponse {
+    fn from(block: TaskBlockView) -> Self {
+        Self {
+            event_id: block.event_id,
+            start: block.start,
+            end: block.end,
+            confirmed: block.confirmed,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TaskResponse {
+    pub id: Uuid,
+    pub title: String,
+    pub duration_minutes: i64,
+    /// `null` until the task is planned, and again if its event goes away
+    pub block: Option<TaskBlockResponse>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<TaskView> for TaskResponse {
+    fn from(task: TaskView) -> Self {
+        Self {
+            id: task.id,
+            title: task.title,
+            duration_minutes: task.duration.num_minutes(),
+            block: task.block.map(TaskBlockResponse::from),
+            completed_at: task.completed_at,
+            created_at: task.created_at,
+        }
+    }
+}
+
+/// Request to plan a day
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct PlanTasksRequest {
+    /// Day to plan in the user's timezone; today by default
+    #[schema(example = "2026-03-02")]
+    pub date: Option<NaiveDate>,
+    /// Working hours blocks must fit in; the saved working hours by default
+    #[schema(example = "09:00-17:00")]
+    pub working_hours: Option<String>,
+}
+
+/// A tentative block a plan created
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PlannedBlockResponse {
+    pub task_id: Uuid,
+    pub event_id: Uuid,
+    pub title: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+impl From<PlannedBlock> for PlannedBlockResponse {
+    fn from(block: PlannedBlock) -> Self {
+        Self {
+            task_id: block.task_id,
+            event_id: block.event_id,
+            title: block.title,
+            start: block.start,
+            end: block.end,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TaskPlanResponse {
+    /// New tentative blocks, earliest first
+    pub blocks: Vec<PlannedBlockResponse>,
+    /// Tasks that found no free slot long enough
+    pub unplanned: Vec<TaskResponse>,
+}
+
+/// List open tasks
+#[utoipa::path(
+    get,
+    path = "/tasks",
+    responses(
+        (status = 200, description = "Tasks not done yet, oldest first", body = Vec<TaskResponse>),
+        (status = 401, description = "Unauthorized")
+    ),
+    tag = "tasks",
+    security(
+        ("telegram_auth" = [])
+    )
+)]
+async fn list_tasks(
+    State(calendar): State<CalendarService>,
+    Extension(auth_user): Extension<AuthenticatedTelegramUser>,
+) -> Result<Json<Vec<TaskResponse>>, ApiError> {
+    let tasks = calendar.list_tasks(auth_user.id).await?;
+    Ok(Json(tasks.into_iter().map(TaskResponse::from).collect()))
+}
+
+/// Add a task
+#[utoipa::path(
+    post,
+    path = "/tasks",
+    request_body = CreateTaskRequest,
+    responses(
+        (status = 201, description = "Task added", body = TaskResponse),
+        (status = 400, description = "Invalid title or duration, or too many open tasks"),
+        (status = 401, description = "Unauthorized")
+    ),
+    tag = "tasks",
+    security(
+        ("telegram_auth" = [])
+    )
+)]
+async fn create_task(
+    State(events): State<EventService>,
+    Extension(auth_user): Extension<AuthenticatedTelegramUser>,
+    Json(request): Json<CreateTaskRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let task = events
+        .create_task(CreateTaskCommand {
+            user_id: auth_user.id,
+            title: request.title,
+            duration: parse_duration_spec(&request.duration)?,
+        })
+        .await?;
+    Ok((StatusCode::CREATED, Json(TaskResponse::from(task))))
+}
+
+/// Delete a task
+///
+/// Its block, if any, stays on the calendar.
+#[utoipa::path(
+    delete,
+    path = "/tasks/{task_id}",
+    responses(
+        (status = 204, description = "Task deleted"),
+        (status = 404, description = "No such task"),
+        (status = 401, description = "Unauthorized")
+    ),
+    params(
+        ("task_id" = Uuid, Path, description = "Task ID")
+    ),
+    tag = "tasks",
+    security(
+        ("telegram_auth" = [])
+    )
+)]
+async fn delete_task(
+    State(events): State<EventService>,
+    Extension(auth_user): Extension<AuthenticatedTelegramUser>,
+    Path(task_id): Path<Uuid>,
+) -> Result<StatusCode, ApiError> {
+    events.delete_task(auth_user.id, task_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Mark a task done
+#[utoipa::path(
+    post,
+    path = "/tasks/{task_id}/complete",
+    responses(
+        (status = 204, description = "Task done"),
+        (status = 404, description = "No such open task"),
+        (status = 401, description = "Unauthorized")
+    ),
+    params(
+        ("task_id" = Uuid, Path, description = "Task ID")
+    ),
+    tag = "tasks",
+    security(
+        ("telegram_auth" = [])
+    )
+)]
+async fn complete_task(
+    State(events): State<EventService>,
+    Extension(auth_user): Extension<AuthenticatedTelegramUser>,
+    Path(task_id): Path<Uuid>,
+) -> Result<StatusCode, ApiError> {
+    events.complete_task(auth_user.id, task_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Confirm a task's block
+///
+/// Confirms the tentative event; at its end the bot asks whether the task
+/// is done.
+#[utoipa::path(
+    post,
+    path = "/tasks/{task_id}/confirm",
+    responses(
+        (status = 200, description = "Block confirmed", body = TaskResponse),
+        (status = 400, description = "The task has no block"),
+        (status = 404, description = "No such open task"),
+        (status = 401, description = "Unauthorized")
+    ),
+    params(
+        ("task_id" = Uuid, Path, description = "Task ID")
+    ),
+    tag = "tasks",
+    security(
+        ("telegram_auth" = [])
+    )
+)]
+async fn confirm_task_block(
+    State(events): State<EventService>,
+    Extension(auth_user): Extension<AuthenticatedTelegramUser>,
+    Path(task_id): Path<Uuid>,
+) -> Result<Json<TaskResponse>, ApiError> {
+    let task = events.confirm_task_block(auth_user.id, task_id).await?;
+    Ok(Json(TaskResponse::from(task)))
+}
+
+/// Unschedule a task
+///
+/// Forgets the task's block, leaving its event on the calendar, so the next
+/// plan finds the task a new slot.
+#[utoipa::path(
+    delete,
+    path = "/tasks/{task_id}/block",
+    responses(
+        (status = 204, description = "Task unscheduled"),
+        (status = 404, description = "No such open task"),
+        (status = 401, description = "Unauthorized")
+    ),
+    params(
+        ("task_id" = Uuid, Path, description = "Task ID")
+    ),
+    tag = "tasks",
+    security(
+        ("telegram_auth" = [])
+    )
+)]
+async fn unschedule_task(
+    State(events): State<EventService>,
+    Extension(auth_user): Extension<AuthenticatedTelegramUser>,
+    Path(task_id): Path<Uuid>,
+) -> Result<StatusCode, ApiError> {
+    events.unschedule_task(auth_user.id, task_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Plan a day
+///
+/// Gives each open task without a block the first free slot of its length
+/// left in the day, around the user's own and subscribed calendars, as a
+/// tentative event. Blocks that ended without being confirmed are replaced.
+#[utoipa::path(
+    post,
+    path = "/tasks/plan",
+    request_body = PlanTasksRequest,
+    responses(
+        (status = 200, description = "Blocks created and tasks left over", body = TaskPlanResponse),
+        (status = 400, description = "Invalid working hours, or the day is over"),
+        (status = 401, description = "Unauthorized")
+    ),
+    tag = "tasks",
+    security(
+        ("telegram_auth" = [])
+    )
+)]
+async fn plan_tasks(
+    State(calendar): State<CalendarService>,
+    State(events): State<EventService>,
+    State(subscriptions): State<SubscriptionService>,
+    Extension(auth_user): Extension<AuthenticatedTelegramUser>,
+    Json(request): Json<PlanTasksRequest>,
+) -> Result<Json<TaskPlanResponse>, ApiError> {
+    let now = events.clock().now();
+    let timezone = &auth_user.timezone;
+    let working_hours = calendar
+        .resolve_working_hours(auth_user.id, timezone, request.working_hours.as_deref())
+        .await?;
+    let day = request
+        .date
+        .unwrap_or_else(|| now.with_timezone(&timezone.tz()).date_naive());
+    let (from, until) = plan_window(day, now, timezone)
+        .ok_or_else(|| ApiError::BadRequest(format!("{day} is already over")))?;
+
+    let mut free_busy = calendar
+        .free_busy(auth_user.id, timezone, from, until)
+        .await?;
+    subscriptions
+        .mark_busy(auth_user.id, &mut free_busy)
+        .await?;
+    let plan = events
+        .plan_tasks(auth_user.id, free_busy, working_hours)
+        .await?;
+
+    Ok(Json(TaskPlanResponse {
+        blocks: plan
+            .blocks
+            .into_iter()
+            .map(PlannedBlockResponse::from)
+            .collect(),
+        unplanned: plan.unplanned.into_iter().map(TaskResponse::from).collect(),
+    }))
+}
+
+pub fn routes<S>() -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+    CalendarService: FromRef<S>,
+    EventService: FromRef<S>,
+    SubscriptionService: FromRef<S>,
+{
+    Router::new()
+        .route("/tasks", get(list_tasks).post(create_task))
+        .route("/tasks/plan", post(plan_tasks))
+        .route("/tasks/{task_id}", delete(delete_task))
+        .route("/tasks/{task_id}/complete", post(complete_task))
+        .route("/tasks/{task_id}/confirm", post(confirm_task_block))
+        .route("/tasks/{task_id}/block", delete(unschedule_task))
+}
@@ -0,0 +1,169 @@
+//! Polling triggers for no-code platforms
+//!
+//! Zapier, IFTTT and Make poll an endpoint and fire for every item id they
+//! haven't seen yet, so lists are newest first with stable ids, and
+//! `since_id` lets a client ask only for what came after the last item it
+//! saw. The endpoints take a device password over HTTP Basic, like CalDAV,
+//! and every device password has its own request budget.
+
+use axum::{
+    Extension, Json, Router,
+    extract::{FromRef, Query, State},
+    routing::get,
+};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use televent_application::{CalendarService, UserId};
+use televent_domain::Timezone;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::{error::ApiError, routes::events::EventResponse};
+
+const DEFAULT_TRIGGER_LIMIT: i64 = 50;
+const MAX_TRIGGER_LIMIT: i64 = 100;
+const DEFAULT_UPCOMING_HOURS: i64 = 24;
+const MAX_UPCOMING_HOURS: i64 = 24 * 7;
+
+/// New-event trigger query parameters
+#[derive(Debug, Deserialize, ToSchema, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct NewEventsQuery {
+    /// Only events created after this one
+    pub since_id: Option<Uuid>,
+    /// Maximum number of events to return
+    #[schema(default = 50, maximum = 100)]
+    pub limit: Option<i64>,
+}
+
+/// Upcoming-event trigger query parameters
+#[derive(Debug, Deserialize, ToSchema, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct UpcomingEventsQuery {
+    /// Only events starting after this one
+    pub since_id: Option<Uuid>,
+    /// How far ahead to look
+    #[schema(default = 24, maximum = 168)]
+    pub within_hours: Option<i64>,
+    /// Maximum number of events to return
+    #[schema(default = 50, maximum = 100)]
+    pub limit: Option<i64>,
+}
+
+/// A newly created event
+#[derive(Debug, Serialize, ToSchema)]
+pub struct NewEventTrigger {
+    #[serde(flatten)]
+    pub event: EventResponse,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Events created recently, newest first
+#[utoipa::path(
+    get,
+    path = "/triggers/new-events",
+    params(NewEventsQuery),
+    responses(
+        (status = 200, description = "Newest events first", body = Vec<NewEventTrigger>),
+        (status = 401, description = "Invalid device password"),
+        (status = 429, description = "Request budget of the device password used up")
+    ),
+    tag = "integrations",
+    security(
+        ("device_password" = [])
+    )
+)]
+async fn new_events(
+    State(calendar): State<CalendarService>,
+    Extension(user_id): Extension<UserId>,
+    Query(query): Query<NewEventsQuery>,
+) -> Result<Json<Vec<NewEventTrigger>>, ApiError> {
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_TRIGGER_LIMIT)
+        .clamp(1, MAX_TRIGGER_LIMIT);
+    let timezone = viewer_timezone(&calendar, user_id).await?;
+
+    let events = calendar
+        .list_new_event_views(user_id, query.since_id, limit)
+        .await?;
+    Ok(Json(
+        events
+            .into_iter()
+            .map(|created| NewEventTrigger {
+                event: EventResponse::for_viewer(created.event, &timezone),
+                created_at: created.created_at,
+            })
+            .collect(),
+    ))
+}
+
+/// Events starting soon, latest start first
+#[utoipa::path(
+    get,
+    path = "/triggers/upcoming-events",
+    params(UpcomingEventsQuery),
+    responses(
+        (status = 200, description = "Events starting within the window", body = Vec<EventResponse>),
+        (status = 401, description = "Invalid device password"),
+        (status = 429, description = "Request budget of the device password used up")
+    ),
+    tag = "integrations",
+    security(
+        ("device_password" = [])
+    )
+)]
+async fn upcoming_events(
+    State(calendar): State<CalendarService>,
+    Extension(user_id): Extension<UserId>,
+    Query(query): Query<UpcomingEventsQuery>,
+) -> Result<Json<Vec<EventResponse>>, ApiError> {
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_TRIGGER_LIMIT)
+        .clamp(1, MAX_TRIGGER_LIMIT);
+    let hours = query
+        .within_hours
+        .unwrap_or(DEFAULT_UPCOMING_HOURS)
+        .clamp(1, MAX_UPCOMING_HOURS);
+    let timezone = viewer_timezone(&calendar, user_id).await?;
+
+    let events = calendar
+        .list_upcoming_event_views(
+            user_id,
+            &timezone,
+            Utc::now(),
+            Duration::hours(hours),
+            query.since_id,
+            limit as usize,
+        )
+        .await?;
+    Ok(Json(
+        events
+            .into_iter()
+            .map(|event| EventResponse::for_viewer(event, &timezone))
+            .collect(),
+    ))
+}
+
+async fn viewer_timezone(
+    calendar: &CalendarService,
+    user_id: UserId,
+) -> Result<Timezone, ApiError> {
+    Ok(calendar
+        .get_user_identity_by_id(user_id)
+        .await?
+        .map(|user| user.timezone)
+        .unwrap_or_default())
+}
+
+/// Trigger routes; expects the device password auth layer
+pub fn routes<S>() -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+    CalendarService: FromRef<S>,
+{
+    Router::new()
+        .route("/new-events", get(new_events))
+        .route("/upcoming-events", get(upcoming_events))
+}
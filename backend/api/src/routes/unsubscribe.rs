@@ -0,0 +1,95 @@
+//! Unsubscribe links from outgoing email
+//!
+//! `GET /unsubscribe/{token}` only asks for confirmation, so link scanners
+//! that prefetch URLs in incoming mail do not unsubscribe anyone. The form
+//! posts back to the same URL, which is also the RFC 8058 one-click target
+//! named in the `List-Unsubscribe` header.
+
+use axum::{
+    Extension, Router,
+    extract::{FromRef, Path, State},
+    http::StatusCode,
+    response::{Html, IntoResponse, Response},
+    routing::get,
+};
+use televent_application::{ApplicationError, EmailService, UnsubscribeKey};
+
+use super::public_events::{escape_html, page};
+
+async fn unsubscribe_page(
+    Extension(key): Extension<UnsubscribeKey>,
+    Path(token): Path<String>,
+) -> Response {
+    let Some(email) = key.verify(&token) else {
+        return invalid_link();
+    };
+
+    Html(page(
+        "Unsubscribe",
+        &format!(
+            "<h1>Unsubscribe</h1>\
+             <p>Stop all email from Televent to <strong>{}</strong>?</p>\
+             <form method=\"post\" action=\"/unsubscribe/{}\">\
+             <p><button type=\"submit\">Unsubscribe</button></p>\
+             </form>",
+            escape_html(&email),
+            escape_html(&token)
+        ),
+    ))
+    .into_response()
+}
+
+async fn unsubscribe(
+    State(email): State<EmailService>,
+    Extension(key): Extension<UnsubscribeKey>,
+    Path(token): Path<String>,
+) -> Response {
+    match email.unsubscribe(&key, &token).await {
+        Ok(address) => Html(page(
+            "Unsubscribed",
+            &format!(
+                "<h1>Unsubscribed</h1>\
+                 <p>We won't email <strong>{}</strong> again.</p>",
+                escape_html(&address)
+            ),
+        ))
+        .into_response(),
+        Err(ApplicationError::NotFound(_)) => invalid_link(),
+        Err(err) => {
+            tracing::error!("Unsubscribe failed: {}", err);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Html(page(
+                    "Televent",
+                    "<p>Something went wrong. Please try again later.</p>",
+                )),
+            )
+                .into_response()
+        }
+    }
+}
+
+fn invalid_link() -> Response {
+    (
+        StatusCode::NOT_FOUND,
+        Html(page(
+            "Televent",
+            "<p>This unsubscribe link is not valid.</p>",
+        )),
+    )
+        .into_response()
+}
+
+/// Unsubscribe routes (no authentication; the token is the credential)
+pub fn routes<S>(key: UnsubscribeKey) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+    EmailService: FromRef<S>,
+{
+    Router::new()
+        .route(
+            "/unsubscribe/{token}",
+            get(unsubscribe_page).post(unsubscribe),
+        )
+        .layer(Extension(key))
+}
@@ -1,3 +1,4 @@
+use api::middleware::caldav_auth::AuthCache;
 use api::{AppState, create_router};
 use axum::{
     body::Body,
@@ -17,18 +18,42 @@ async fn setup_app_with_db() -> axum::Router {
         .await
         .expect("Failed to connect to DB");
 
-    let auth_cache = moka::future::Cache::builder().build();
+    let auth_cache = AuthCache::new(std::time::Duration::from_secs(300), 10_000);
 
     let state = AppState {
         calendar_service: televent_application::CalendarService::new(
             televent_storage::calendar::CalendarRepository::new(pool.clone()),
         ),
+        event_service: televent_application::EventService::new(
+            televent_storage::calendar::CalendarRepository::new(pool.clone()),
+        ),
         device_service: televent_application::DeviceService::new(
             televent_storage::device::DeviceRepository::new(pool.clone()),
         ),
         health_service: televent_application::HealthService::new(
             televent_storage::health::HealthRepository::new(pool.clone()),
         ),
+        subscription_service: televent_application::SubscriptionService::new(
+            televent_storage::subscription::SubscriptionRepository::new(pool.clone()),
+        ),
+        contact_service: televent_application::ContactService::new(
+            televent_storage::contact::ContactRepository::new(pool.clone()),
+        ),
+        email_service: televent_application::EmailService::new(
+            televent_storage::email::EmailRepository::new(pool.clone()),
+        ),
+        feature_flags: televent_application::FeatureFlagService::new(
+            televent_storage::flags::FeatureFlagRepository::new(pool.clone()),
+        ),
+        chat_webhook_service: televent_application::ChatWebhookService::new(
+            televent_storage::chat_webhook::ChatWebhookRepository::new(pool.clone()),
+        ),
+        notification_service: televent_application::NotificationService::new(
+            televent_storage::notification::NotificationRepository::new(pool.clone()),
+        ),
+        web_push_service: televent_application::WebPushService::new(
+            televent_storage::web_push::WebPushRepository::new(pool.clone()),
+        ),
         auth_cache,
         telegram_bot_token: "test_token".to_string(),
     };
@@ -84,17 +109,41 @@ fn generate_valid_auth_header(user_id: i64, bot_token: &str) -> String {
 #[tokio::test]
 async fn test_health_check_basic() {
     let pool = PgPool::connect_lazy("postgres://localhost/dummy").unwrap();
-    let auth_cache = moka::future::Cache::builder().build();
+    let auth_cache = AuthCache::new(std::time::Duration::from_secs(300), 10_000);
     let state = AppState {
         calendar_service: televent_application::CalendarService::new(
             televent_storage::calendar::CalendarRepository::new(pool.clone()),
         ),
+        event_service: televent_application::EventService::new(
+            televent_storage::calendar::CalendarRepository::new(pool.clone()),
+        ),
         device_service: televent_application::DeviceService::new(
             televent_storage::device::DeviceRepository::new(pool.clone()),
         ),
         health_service: televent_application::HealthService::new(
             televent_storage::health::HealthRepository::new(pool.clone()),
         ),
+        subscription_service: televent_application::SubscriptionService::new(
+            televent_storage::subscription::SubscriptionRepository::new(pool.clone()),
+        ),
+        contact_service: televent_application::ContactService::new(
+            televent_storage::contact::ContactRepository::new(pool.clone()),
+        ),
+        email_service: televent_application::EmailService::new(
+            televent_storage::email::EmailRepository::new(pool.clone()),
+        ),
+        feature_flags: televent_application::FeatureFlagService::new(
+            televent_storage::flags::FeatureFlagRepository::new(pool.clone()),
+        ),
+        chat_webhook_service: televent_application::ChatWebhookService::new(
+            televent_storage::chat_webhook::ChatWebhookRepository::new(pool.clone()),
+        ),
+        notification_service: televent_application::NotificationService::new(
+            televent_storage::notification::NotificationRepository::new(pool.clone()),
+        ),
+        web_push_service: televent_application::WebPushService::new(
+            televent_storage::web_push::WebPushRepository::new(pool.clone()),
+        ),
         auth_cache,
         telegram_bot_token: "dummy".to_string(),
     };
@@ -116,17 +165,41 @@ async fn test_health_check_basic() {
 #[tokio::test]
 async fn test_api_events_unauthorized() {
     let pool = PgPool::connect_lazy("postgres://localhost/dummy").unwrap();
-    let auth_cache = moka::future::Cache::builder().build();
+    let auth_cache = AuthCache::new(std::time::Duration::from_secs(300), 10_000);
     let state = AppState {
         calendar_service: televent_application::CalendarService::new(
             televent_storage::calendar::CalendarRepository::new(pool.clone()),
         ),
+        event_service: televent_application::EventService::new(
+            televent_storage::calendar::CalendarRepository::new(pool.clone()),
+        ),
         device_service: televent_application::DeviceService::new(
             televent_storage::device::DeviceRepository::new(pool.clone()),
         ),
         health_service: televent_application::HealthService::new(
             televent_storage::health::HealthRepository::new(pool.clone()),
         ),
+        subscription_service: televent_application::SubscriptionService::new(
+            televent_storage::subscription::SubscriptionRepository::new(pool.clone()),
+        ),
+        contact_service: televent_application::ContactService::new(
+            televent_storage::contact::ContactRepository::new(pool.clone()),
+        ),
+        email_service: televent_application::EmailService::new(
+            televent_storage::email::EmailRepository::new(pool.clone()),
+        ),
+        feature_flags: televent_application::FeatureFlagService::new(
+            televent_storage::flags::FeatureFlagRepository::new(pool.clone()),
+        ),
+        chat_webhook_service: televent_application::ChatWebhookService::new(
+            televent_storage::chat_webhook::ChatWebhookRepository::new(pool.clone()),
+        ),
+        notification_service: televent_application::NotificationService::new(
+            televent_storage::notification::NotificationRepository::new(pool.clone()),
+        ),
+        web_push_service: televent_application::WebPushService::new(
+            televent_storage::web_push::WebPushRepository::new(pool.clone()),
+        ),
         auth_cache,
         telegram_bot_token: "dummy".to_string(),
     };
@@ -1,9 +1,9 @@
-use api::{AppState, create_router};
+use api::middleware::caldav_auth::AuthCache;
+use api::{AppState, create_router, create_router_with_config};
 use axum::{
     body::Body,
     http::{Request, StatusCode, header},
 };
-use moka::future::Cache;
 use serde_json::Value;
 use sqlx::PgPool;
 use std::time::Duration;
@@ -62,7 +62,7 @@ fn generate_valid_init_data(bot_token: &str, telegram_id: i64) -> String {
     let user_json = serde_json::json!({
         "id": telegram_id,
         "first_name": "Test User",
-        "username": "test_user"
+        "username": format!("test_user_{telegram_id}")
     })
     .to_string();
 
@@ -121,20 +121,42 @@ async fn test_api_full_flow(pool: PgPool) {
     let bot_token = "dummy_token";
     let init_data = generate_valid_init_data(bot_token, telegram_id);
 
-    let auth_cache = Cache::builder()
-        .time_to_live(Duration::from_secs(300))
-        .build();
+    let auth_cache = AuthCache::new(Duration::from_secs(300), 10_000);
 
     let state = AppState {
         calendar_service: televent_application::CalendarService::new(
             televent_storage::calendar::CalendarRepository::new(pool.clone()),
         ),
+        event_service: televent_application::EventService::new(
+            televent_storage::calendar::CalendarRepository::new(pool.clone()),
+        ),
         device_service: televent_application::DeviceService::new(
             televent_storage::device::DeviceRepository::new(pool.clone()),
         ),
         health_service: televent_application::HealthService::new(
             televent_storage::health::HealthRepository::new(pool.clone()),
         ),
+        subscription_service: televent_application::SubscriptionService::new(
+            televent_storage::subscription::SubscriptionRepository::new(pool.clone()),
+        ),
+        contact_service: televent_application::ContactService::new(
+            televent_storage::contact::ContactRepository::new(pool.clone()),
+        ),
+        email_service: televent_application::EmailService::new(
+            televent_storage::email::EmailRepository::new(pool.clone()),
+        ),
+        feature_flags: televent_application::FeatureFlagService::new(
+            televent_storage::flags::FeatureFlagRepository::new(pool.clone()),
+        ),
+        chat_webhook_service: televent_application::ChatWebhookService::new(
+            televent_storage::chat_webhook::ChatWebhookRepository::new(pool.clone()),
+        ),
+        notification_service: televent_application::NotificationService::new(
+            televent_storage::notification::NotificationRepository::new(pool.clone()),
+        ),
+        web_push_service: televent_application::WebPushService::new(
+            televent_storage::web_push::WebPushRepository::new(pool.clone()),
+        ),
         auth_cache,
         telegram_bot_token: bot_token.to_string(),
     };
@@ -345,3 +367,4071 @@ async fn test_api_full_flow(pool: PgPool) {
     // Let's just assert it failed.
     assert!(response.status() != StatusCode::OK);
 }
+
+async fn body_text(response: axum::response::Response) -> String {
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    String::from_utf8(bytes.to_vec()).unwrap()
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_public_event_signup_flow(pool: PgPool) {
+    let telegram_id = setup_user(&pool).await;
+    let bot_token = "dummy_token";
+    let init_data = generate_valid_init_data(bot_token, telegram_id);
+
+    let state = AppState {
+        calendar_service: televent_application::CalendarService::new(
+            televent_storage::calendar::CalendarRepository::new(pool.clone()),
+        ),
+        event_service: televent_application::EventService::new(
+            televent_storage::calendar::CalendarRepository::new(pool.clone()),
+        ),
+        device_service: televent_application::DeviceService::new(
+            televent_storage::device::DeviceRepository::new(pool.clone()),
+        ),
+        health_service: televent_application::HealthService::new(
+            televent_storage::health::HealthRepository::new(pool.clone()),
+        ),
+        subscription_service: televent_application::SubscriptionService::new(
+            televent_storage::subscription::SubscriptionRepository::new(pool.clone()),
+        ),
+        contact_service: televent_application::ContactService::new(
+            televent_storage::contact::ContactRepository::new(pool.clone()),
+        ),
+        email_service: televent_application::EmailService::new(
+            televent_storage::email::EmailRepository::new(pool.clone()),
+        ),
+        feature_flags: televent_application::FeatureFlagService::new(
+            televent_storage::flags::FeatureFlagRepository::new(pool.clone()),
+        ),
+        chat_webhook_service: televent_application::ChatWebhookService::new(
+            televent_storage::chat_webhook::ChatWebhookRepository::new(pool.clone()),
+        ),
+        notification_service: televent_application::NotificationService::new(
+            televent_storage::notification::NotificationRepository::new(pool.clone()),
+        ),
+        web_push_service: televent_application::WebPushService::new(
+            televent_storage::web_push::WebPushRepository::new(pool.clone()),
+        ),
+        auth_cache: AuthCache::new(Duration::from_secs(300), 10_000),
+        telegram_bot_token: bot_token.to_string(),
+    };
+    let calendar = state.calendar_service.clone();
+    let app_without_email = create_router(state.clone(), "*");
+    let app = create_router_with_config(
+        state,
+        &api::config::Config {
+            host: "0.0.0.0".to_string(),
+            port: 3000,
+            cors: api::middleware::cors::CorsPolicy::any(),
+            frontend_static_dir: None,
+            enable_swagger: false,
+            email_signups: true,
+            email_webhook_secret: None,
+            sms_notifications: false,
+            web_push_public_key: None,
+            device_budget: Default::default(),
+            trigger_budget: Default::default(),
+            base_path: Default::default(),
+            frontend_assets: Default::default(),
+        },
+    );
+
+    let create_body = serde_json::json!({
+        "uid": "public-page-uid",
+        "summary": "Open Meetup",
+        "location": "Library",
+        "timing": {
+            "kind": "timed",
+            "start": "2026-06-01T18:00:00Z",
+            "end": "2026-06-01T20:00:00Z",
+            "timezone": "UTC"
+        }
+    });
+    let response = app
+        .clone()
+        .oneshot(create_request(
+            "POST",
+            "/api/events",
+            Body::from(create_body.to_string()),
+            Some(&init_data),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let created: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    let event_id = Uuid::parse_str(created["id"].as_str().unwrap()).unwrap();
+
+    // 1. Publish; publishing again keeps the shared link stable
+    let mut slugs = Vec::new();
+    for _ in 0..2 {
+        let response = app
+            .clone()
+            .oneshot(create_request(
+                "PUT",
+                format!("/api/events/{event_id}/public"),
+                Body::empty(),
+                Some(&init_data),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let page: Value = serde_json::from_str(&body_text(response).await).unwrap();
+        slugs.push(page["slug"].as_str().unwrap().to_string());
+    }
+    assert_eq!(slugs[0], slugs[1]);
+    let slug = &slugs[0];
+
+    // 2. Anyone can view the page
+    let response = app
+        .clone()
+        .oneshot(create_request(
+            "GET",
+            format!("/e/{slug}"),
+            Body::empty(),
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let html = body_text(response).await;
+    assert!(html.contains("Open Meetup"));
+    assert!(html.contains("Library"));
+
+    // 3. Without email delivery the page offers no signup and refuses one
+    let signup = || {
+        let mut signup = create_request(
+            "POST",
+            format!("/e/{slug}/signup"),
+            Body::from("name=Guest&email=Guest%40Example.com"),
+            None,
+        );
+        signup.headers_mut().insert(
+            header::CONTENT_TYPE,
+            "application/x-www-form-urlencoded".parse().unwrap(),
+        );
+        signup
+    };
+    let response = app_without_email
+        .clone()
+        .oneshot(create_request(
+            "GET",
+            format!("/e/{slug}"),
+            Body::empty(),
+            None,
+        ))
+        .await
+        .unwrap();
+    assert!(!body_text(response).await.contains("<form"));
+    let response = app_without_email.oneshot(signup()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+    // 4. Signing up queues a confirmation email but adds nobody yet
+    let response = app.clone().oneshot(signup()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let attendees: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM event_attendees WHERE event_id = $1")
+            .bind(event_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(attendees, 0);
+    let payload: Value = sqlx::query_scalar(
+        "SELECT payload FROM outbox_messages WHERE kind = 'signup_confirmation'",
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(payload["recipient_email"], "guest@example.com");
+    assert_eq!(payload["event_id"], event_id.to_string());
+    assert!(payload.get("confirmation_path").is_none());
+
+    // The worker mints the link when it sends the email; only the newest works
+    let stale_path = calendar
+        .issue_signup_confirmation(event_id, "guest@example.com")
+        .await
+        .unwrap()
+        .unwrap();
+    let confirmation_path = calendar
+        .issue_signup_confirmation(event_id, "guest@example.com")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_ne!(stale_path, confirmation_path);
+    let response = app
+        .clone()
+        .oneshot(create_request("GET", &stale_path, Body::empty(), None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    // 5. Opening the link accepts the invite and notifies the organizer
+    let sync_version_before = event_sync_version(&pool, event_id).await;
+    let response = app
+        .clone()
+        .oneshot(create_request(
+            "GET",
+            &confirmation_path,
+            Body::empty(),
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(body_text(response).await.contains("Open Meetup"));
+
+    let status: String = sqlx::query_scalar(
+        "SELECT status::text FROM event_attendees WHERE event_id = $1 AND email = 'guest@example.com'",
+    )
+    .bind(event_id)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(status, "ACCEPTED");
+    assert!(event_sync_version(&pool, event_id).await > sync_version_before);
+    let rsvp_notifications: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM outbox_messages WHERE kind = 'rsvp_notification'")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(rsvp_notifications, 1);
+
+    // 6. Links are single use
+    let response = app
+        .clone()
+        .oneshot(create_request(
+            "GET",
+            &confirmation_path,
+            Body::empty(),
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert!(
+        calendar
+            .issue_signup_confirmation(event_id, "guest@example.com")
+            .await
+            .unwrap()
+            .is_none()
+    );
+
+    // 7. Unpublishing takes the page down
+    let response = app
+        .clone()
+        .oneshot(create_request(
+            "DELETE",
+            format!("/api/events/{event_id}/public"),
+            Body::empty(),
+            Some(&init_data),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let response = app
+        .oneshot(create_request(
+            "GET",
+            format!("/e/{slug}"),
+            Body::empty(),
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+fn app_state(pool: &PgPool, bot_token: &str) -> AppState {
+    AppState {
+        calendar_service: televent_application::CalendarService::new(
+            televent_storage::calendar::CalendarRepository::new(pool.clone()),
+        ),
+        event_service: televent_application::EventService::new(
+            televent_storage::calendar::CalendarRepository::new(pool.clone()),
+        ),
+        device_service: televent_application::DeviceService::new(
+            televent_storage::device::DeviceRepository::new(pool.clone()),
+        ),
+        health_service: televent_application::HealthService::new(
+            televent_storage::health::HealthRepository::new(pool.clone()),
+        ),
+        subscription_service: televent_application::SubscriptionService::new(
+            televent_storage::subscription::SubscriptionRepository::new(pool.clone()),
+        ),
+        contact_service: televent_application::ContactService::new(
+            televent_storage::contact::ContactRepository::new(pool.clone()),
+        ),
+        email_service: televent_application::EmailService::new(
+            televent_storage::email::EmailRepository::new(pool.clone()),
+        ),
+        feature_flags: televent_application::FeatureFlagService::new(
+            televent_storage::flags::FeatureFlagRepository::new(pool.clone()),
+        ),
+        chat_webhook_service: televent_application::ChatWebhookService::new(
+            televent_storage::chat_webhook::ChatWebhookRepository::new(pool.clone()),
+        ),
+        notification_service: televent_application::NotificationService::new(
+            televent_storage::notification::NotificationRepository::new(pool.clone()),
+        ),
+        web_push_service: televent_application::WebPushService::new(
+            televent_storage::web_push::WebPushRepository::new(pool.clone()),
+        ),
+        auth_cache: AuthCache::new(Duration::from_secs(300), 10_000),
+        telegram_bot_token: bot_token.to_string(),
+    }
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_admin_routes_require_admin_role(pool: PgPool) {
+    let admin_id = setup_user(&pool).await;
+    let other_id = setup_user(&pool).await;
+    let bot_token = "dummy_token";
+    let admin_init_data = generate_valid_init_data(bot_token, admin_id);
+    let other_init_data = generate_valid_init_data(bot_token, other_id);
+
+    let calendar = televent_application::CalendarService::new(
+        televent_storage::calendar::CalendarRepository::new(pool.clone()),
+    );
+    let app = create_router(app_state(&pool, bot_token), "*");
+
+    // Regular users are turned away
+    let response = app
+        .clone()
+        .oneshot(create_request(
+            "GET",
+            "/api/admin/flags",
+            Body::empty(),
+            Some(&admin_init_data),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    // Bootstrap promotes existing users and creates unknown ones as admins
+    let unknown_id = admin_id.wrapping_add(1).abs();
+    assert_eq!(
+        calendar
+            .bootstrap_admins(&[admin_id, unknown_id])
+            .await
+            .unwrap(),
+        2
+    );
+    assert_eq!(calendar.bootstrap_admins(&[admin_id]).await.unwrap(), 0);
+
+    let response = app
+        .clone()
+        .oneshot(create_request(
+            "GET",
+            "/api/me",
+            Body::empty(),
+            Some(&admin_init_data),
+        ))
+        .await
+        .unwrap();
+    let me: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    assert_eq!(me["role"], "admin");
+
+    let response = app
+        .clone()
+        .oneshot(create_request(
+            "GET",
+            "/api/admin/flags",
+            Body::empty(),
+            Some(&admin_init_data),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let operator_body = serde_json::json!({ "role": "operator" }).to_string();
+    let response = app
+        .clone()
+        .oneshot(create_request(
+            "PUT",
+            format!("/api/admin/users/{other_id}/role"),
+            Body::from(operator_body.clone()),
+            Some(&admin_init_data),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = app
+        .clone()
+        .oneshot(create_request(
+            "PUT",
+            format!("/api/admin/users/{admin_id}/role"),
+            Body::from(operator_body),
+            Some(&admin_init_data),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    // Operators don't get admin routes
+    let response = app
+        .oneshot(create_request(
+            "GET",
+            "/api/admin/flags",
+            Body::empty(),
+            Some(&other_init_data),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_multi_day_all_day_event(pool: PgPool) {
+    use argon2::{Argon2, PasswordHasher, password_hash::SaltString};
+    use base64::{Engine, engine::general_purpose::STANDARD};
+
+    let telegram_id = setup_user(&pool).await;
+    let bot_token = "dummy_token";
+    let init_data = generate_valid_init_data(bot_token, telegram_id);
+    let app = create_router(app_state(&pool, bot_token), "*");
+
+    let event_body = |end_date: &str| {
+        serde_json::json!({
+            "uid": "conference-uid",
+            "summary": "Conference",
+            "timing": {
+                "kind": "all_day",
+                "start_date": "2026-06-01",
+                "end_date": end_date
+            }
+        })
+        .to_string()
+    };
+
+    // The end date is exclusive, so a one-day event can't end on its start
+    let response = app
+        .clone()
+        .oneshot(create_request(
+            "POST",
+            "/api/events",
+            Body::from(event_body("2026-06-01")),
+            Some(&init_data),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = app
+        .clone()
+        .oneshot(create_request(
+            "POST",
+            "/api/events",
+            Body::from(event_body("2026-06-04")),
+            Some(&init_data),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let event: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    assert_eq!(event["is_all_day"], true);
+    assert_eq!(event["start_date"], "2026-06-01");
+    assert_eq!(event["end_date"], "2026-06-04");
+    assert!(event["start"].is_null());
+
+    // CalDAV clients see DATE values with the same exclusive DTEND
+    let salt = SaltString::generate(&mut argon2::password_hash::rand_core::OsRng);
+    let password_hash = Argon2::default()
+        .hash_password(b"device-password", &salt)
+        .unwrap()
+        .to_string();
+    sqlx::query(
+        "INSERT INTO device_passwords (id, user_id, password_hash, device_name) VALUES ($1, $2, $3, 'phone')",
+    )
+    .bind(Uuid::new_v4())
+    .bind(telegram_id)
+    .bind(password_hash)
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let mut request = create_request(
+        "GET",
+        format!("/caldav/{telegram_id}/conference-uid.ics"),
+        Body::empty(),
+        None,
+    );
+    request.headers_mut().insert(
+        header::AUTHORIZATION,
+        format!(
+            "Basic {}",
+            STANDARD.encode(format!("{telegram_id}:device-password"))
+        )
+        .parse()
+        .unwrap(),
+    );
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let ical = body_text(response).await;
+    assert!(ical.contains("DTSTART;VALUE=DATE:20260601"), "{ical}");
+    assert!(ical.contains("DTEND;VALUE=DATE:20260604"), "{ical}");
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_duplicate_event(pool: PgPool) {
+    let telegram_id = setup_user(&pool).await;
+    let guest_id = setup_user(&pool).await;
+    let bot_token = "dummy_token";
+    let init_data = generate_valid_init_data(bot_token, telegram_id);
+    let app = create_router(app_state(&pool, bot_token), "*");
+
+    let create_body = serde_json::json!({
+        "uid": "weekly-sync",
+        "summary": "Weekly sync",
+        "location": "Room 2",
+        "timing": {
+            "kind": "timed",
+            "start": "2026-06-01T10:00:00Z",
+            "end": "2026-06-01T11:00:00Z",
+            "timezone": "UTC"
+        }
+    });
+    let response = app
+        .clone()
+        .oneshot(create_request(
+            "POST",
+            "/api/events",
+            Body::from(create_body.to_string()),
+            Some(&init_data),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let source: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    let source_id = source["id"].as_str().unwrap().to_string();
+
+    sqlx::query(
+        "INSERT INTO event_attendees (event_id, email, user_id, role, status) VALUES ($1, $2, $3, 'ATTENDEE', 'ACCEPTED')",
+    )
+    .bind(Uuid::parse_str(&source_id).unwrap())
+    .bind(format!("tg_{guest_id}@televent.internal"))
+    .bind(guest_id)
+    .execute(&pool)
+    .await
+    .unwrap();
+    let outbox_count = || async {
+        sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM outbox_messages")
+            .fetch_one(&pool)
+            .await
+            .unwrap()
+    };
+    let outbox_before = outbox_count().await;
+
+    // Without a body: same time, new UID, no guests and no notifications
+    let response = app
+        .clone()
+        .oneshot(create_request(
+            "POST",
+            format!("/api/events/{source_id}/duplicate"),
+            Body::empty(),
+            Some(&init_data),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let copy: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    assert_ne!(copy["id"], source["id"]);
+    assert_ne!(copy["uid"], "weekly-sync");
+    assert_eq!(copy["summary"], "Weekly sync");
+    assert_eq!(copy["location"], "Room 2");
+    assert_eq!(copy["start"], source["start"]);
+    assert_eq!(outbox_count().await, outbox_before);
+
+    // Next week with the guests, who get a fresh invitation
+    let response = app
+        .clone()
+        .oneshot(create_request(
+            "POST",
+            format!("/api/events/{source_id}/duplicate"),
+            Body::from(
+                serde_json::json!({ "offset_days": 7, "include_attendees": true }).to_string(),
+            ),
+            Some(&init_data),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let copy: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    assert_eq!(copy["start"], "2026-06-08T10:00:00Z");
+    assert_eq!(copy["end"], "2026-06-08T11:00:00Z");
+    let copy_id = Uuid::parse_str(copy["id"].as_str().unwrap()).unwrap();
+    let guest_status: String = sqlx::query_scalar(
+        "SELECT status::text FROM event_attendees WHERE event_id = $1 AND user_id = $2",
+    )
+    .bind(copy_id)
+    .bind(guest_id)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(guest_status, "NEEDS-ACTION");
+    assert_eq!(outbox_count().await, outbox_before + 1);
+
+    let response = app
+        .clone()
+        .oneshot(create_request(
+            "POST",
+            format!("/api/events/{source_id}/duplicate"),
+            Body::from(serde_json::json!({ "offset_days": 100000 }).to_string()),
+            Some(&init_data),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = app
+        .oneshot(create_request(
+            "POST",
+            format!("/api/events/{}/duplicate", Uuid::new_v4()),
+            Body::empty(),
+            Some(&init_data),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_event_visibility_hides_details_from_visitors(pool: PgPool) {
+    let telegram_id = setup_user(&pool).await;
+    let bot_token = "dummy_token";
+    let init_data = generate_valid_init_data(bot_token, telegram_id);
+    let app = create_router(app_state(&pool, bot_token), "*");
+
+    // 1. New events take the calendar's default
+    let response = app
+        .clone()
+        .oneshot(create_request(
+            "PUT",
+            "/api/calendars/default-visibility",
+            Body::from(r#"{"visibility": "busy_only"}"#),
+            Some(&init_data),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let calendar: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    assert_eq!(calendar["default_visibility"], "busy_only");
+
+    let create_body = serde_json::json!({
+        "uid": "doctor-visit",
+        "summary": "Doctor visit",
+        "location": "Clinic",
+        "timing": {
+            "kind": "timed",
+            "start": "2026-06-03T09:00:00Z",
+            "end": "2026-06-03T10:00:00Z",
+            "timezone": "UTC"
+        }
+    });
+    let response = app
+        .clone()
+        .oneshot(create_request(
+            "POST",
+            "/api/events",
+            Body::from(create_body.to_string()),
+            Some(&init_data),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let created: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    assert_eq!(created["visibility"], "busy_only");
+    assert_eq!(created["summary"], "Doctor visit");
+    let event_id = created["id"].as_str().unwrap().to_string();
+
+    // 2. Visitors of a busy-only page see the time and nothing else
+    let response = app
+        .clone()
+        .oneshot(create_request(
+            "PUT",
+            format!("/api/events/{event_id}/public"),
+            Body::empty(),
+            Some(&init_data),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let page: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    let slug = page["slug"].as_str().unwrap().to_string();
+
+    let response = app
+        .clone()
+        .oneshot(create_request(
+            "GET",
+            format!("/e/{slug}"),
+            Body::empty(),
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let html = body_text(response).await;
+    assert!(html.contains("Busy"));
+    assert!(html.contains("09:00 UTC"));
+    assert!(!html.contains("Doctor visit"));
+    assert!(!html.contains("Clinic"));
+    assert!(!html.contains("<form"));
+
+    // 3. Private events have no page at all
+    let response = app
+        .clone()
+        .oneshot(create_request(
+            "PUT",
+            format!("/api/events/{event_id}"),
+            Body::from(r#"{"visibility": "private"}"#),
+            Some(&init_data),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = app
+        .clone()
+        .oneshot(create_request(
+            "GET",
+            format!("/e/{slug}"),
+            Body::empty(),
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let response = app
+        .oneshot(create_request(
+            "PUT",
+            format!("/api/events/{event_id}/public"),
+            Body::empty(),
+            Some(&init_data),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_free_slots_skip_busy_time(pool: PgPool) {
+    let telegram_id = setup_user(&pool).await;
+    let bot_token = "dummy_token";
+    let init_data = generate_valid_init_data(bot_token, telegram_id);
+    let app = create_router(app_state(&pool, bot_token), "*");
+
+    let busy_end = chrono::Utc::now() + chrono::Duration::hours(2);
+    let create_body = serde_json::json!({
+        "uid": "busy-now",
+        "summary": "Deep work",
+        "timing": {
+            "kind": "timed",
+            "start": chrono::Utc::now() - chrono::Duration::minutes(10),
+            "end": busy_end,
+            "timezone": "UTC"
+        }
+    });
+    let response = app
+        .clone()
+        .oneshot(create_request(
+            "POST",
+            "/api/events",
+            Body::from(create_body.to_string()),
+            Some(&init_data),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    let response = app
+        .clone()
+        .oneshot(create_request(
+            "GET",
+            "/api/me/slots?duration=30m&within=P1D&count=2",
+            Body::empty(),
+            Some(&init_data),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let slots: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    let slots = slots.as_array().unwrap();
+    assert_eq!(slots.len(), 1, "the rest of the day is one free gap");
+    let start: chrono::DateTime<chrono::Utc> = slots[0]["start"].as_str().unwrap().parse().unwrap();
+    let end: chrono::DateTime<chrono::Utc> = slots[0]["end"].as_str().unwrap().parse().unwrap();
+    assert!(start >= busy_end);
+    assert!(start < busy_end + chrono::Duration::minutes(15));
+    assert_eq!(end - start, chrono::Duration::minutes(30));
+
+    for query in [
+        "duration=soon",
+        "duration=30m&within=P60D",
+        "duration=30m&working_hours=17:00-09:00",
+    ] {
+        let response = app
+            .clone()
+            .oneshot(create_request(
+                "GET",
+                format!("/api/me/slots?{query}"),
+                Body::empty(),
+                Some(&init_data),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{query}");
+    }
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_working_hours_preferences(pool: PgPool) {
+    use chrono::{Datelike, Timelike};
+
+    let telegram_id = setup_user(&pool).await;
+    let bot_token = "dummy_token";
+    let init_data = generate_valid_init_data(bot_token, telegram_id);
+    let app = create_router(app_state(&pool, bot_token), "*");
+
+    let response = app
+        .clone()
+        .oneshot(create_request(
+            "GET",
+            "/api/me/preferences",
+            Body::empty(),
+            Some(&init_data),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let preferences: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    assert!(preferences["working_hours"].is_null());
+
+    // Only the day after tomorrow is a working day
+    let day = (chrono::Utc::now() + chrono::Duration::days(2)).weekday();
+    let body = serde_json::json!({ "working_hours": format!("{day} 09:00-12:00") });
+    let response = app
+        .clone()
+        .oneshot(create_request(
+            "PUT",
+            "/api/me/preferences",
+            Body::from(body.to_string()),
+            Some(&init_data),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let preferences: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    assert_eq!(preferences["working_hours"], format!("{day} 09:00-12:00"));
+    assert_eq!(preferences["working_days"].as_array().unwrap().len(), 1);
+    assert_eq!(preferences["working_days"][0]["start"], "09:00");
+
+    let response = app
+        .clone()
+        .oneshot(create_request(
+            "GET",
+            "/api/me/slots?duration=1h&within=P7D&count=1",
+            Body::empty(),
+            Some(&init_data),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let slots: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    let start: chrono::DateTime<chrono::Utc> = slots[0]["start"].as_str().unwrap().parse().unwrap();
+    assert_eq!(start.weekday(), day);
+    assert_eq!(start.hour(), 9);
+
+    // Longer than the saved day
+    let response = app
+        .clone()
+        .oneshot(create_request(
+            "GET",
+            "/api/me/slots?duration=4h",
+            Body::empty(),
+            Some(&init_data),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    for body in [
+        serde_json::json!({ "working_hours": "17:00-09:00" }),
+        serde_json::json!({ "working_hours": "Someday 09:00-17:00" }),
+    ] {
+        let response = app
+            .clone()
+            .oneshot(create_request(
+                "PUT",
+                "/api/me/preferences",
+                Body::from(body.to_string()),
+                Some(&init_data),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{body}");
+    }
+
+    let response = app
+        .oneshot(create_request(
+            "PUT",
+            "/api/me/preferences",
+            Body::from(serde_json::json!({ "working_hours": null }).to_string()),
+            Some(&init_data),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let preferences: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    assert!(preferences["working_hours"].is_null());
+    assert_eq!(preferences["working_days"], serde_json::json!([]));
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_settings_export_and_import(pool: PgPool) {
+    let telegram_id = setup_user(&pool).await;
+    let bot_token = "dummy_token";
+    let init_data = generate_valid_init_data(bot_token, telegram_id);
+    let app = create_router(app_state(&pool, bot_token), "*");
+
+    let archive = serde_json::json!({
+        "schema_version": 1,
+        "timezone": "Europe/Berlin",
+        "subscriptions": [
+            { "url": "https://example.com/team.ics", "name": "Team" },
+            { "url": "https://example.com/team.ics", "name": "Team again" }
+        ]
+    });
+    let response = app
+        .clone()
+        .oneshot(create_request(
+            "POST",
+            "/api/me/settings/import",
+            Body::from(archive.to_string()),
+            Some(&init_data),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let imported: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    assert_eq!(imported["timezone"], "Europe/Berlin");
+    assert_eq!(imported["subscriptions_added"], 1);
+    assert_eq!(imported["subscriptions_skipped"], 1);
+
+    let response = app
+        .clone()
+        .oneshot(create_request(
+            "GET",
+            "/api/me/settings/export",
+            Body::empty(),
+            Some(&init_data),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let exported: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    assert_eq!(exported["schema_version"], 1);
+    assert_eq!(exported["timezone"], "Europe/Berlin");
+    assert_eq!(exported["subscriptions"].as_array().unwrap().len(), 1);
+    assert_eq!(exported["subscriptions"][0]["name"], "Team");
+
+    let future = serde_json::json!({ "schema_version": 99, "timezone": "UTC" });
+    let response = app
+        .oneshot(create_request(
+            "POST",
+            "/api/me/settings/import",
+            Body::from(future.to_string()),
+            Some(&init_data),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_floating_event(pool: PgPool) {
+    use argon2::{Argon2, PasswordHasher, password_hash::SaltString};
+    use base64::{Engine, engine::general_purpose::STANDARD};
+
+    let telegram_id = setup_user(&pool).await;
+    sqlx::query("UPDATE users SET timezone = 'Asia/Tokyo' WHERE telegram_id = $1")
+        .bind(telegram_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+    let bot_token = "dummy_token";
+    let init_data = generate_valid_init_data(bot_token, telegram_id);
+    let app = create_router(app_state(&pool, bot_token), "*");
+
+    let response = app
+        .clone()
+        .oneshot(create_request(
+            "POST",
+            "/api/events",
+            Body::from(
+                serde_json::json!({
+                    "uid": "standup-uid",
+                    "summary": "Stand-up",
+                    "timing": {
+                        "kind": "floating",
+                        "start": "2026-06-01T09:00:00",
+                        "end": "2026-06-01T09:15:00"
+                    }
+                })
+                .to_string(),
+            ),
+            Some(&init_data),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let event: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    assert_eq!(event["is_floating"], true);
+    // Placed at 09:00 in the viewer's zone
+    assert_eq!(event["timezone"], "Asia/Tokyo");
+    assert_eq!(event["start"], "2026-06-01T00:00:00Z");
+    assert_eq!(event["end"], "2026-06-01T00:15:00Z");
+
+    // CalDAV clients get a DTSTART with neither TZID nor a UTC suffix
+    let salt = SaltString::generate(&mut argon2::password_hash::rand_core::OsRng);
+    let password_hash = Argon2::default()
+        .hash_password(b"device-password", &salt)
+        .unwrap()
+        .to_string();
+    sqlx::query(
+        "INSERT INTO device_passwords (id, user_id, password_hash, device_name) VALUES ($1, $2, $3, 'phone')",
+    )
+    .bind(Uuid::new_v4())
+    .bind(telegram_id)
+    .bind(password_hash)
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let mut request = create_request(
+        "GET",
+        format!("/caldav/{telegram_id}/standup-uid.ics"),
+        Body::empty(),
+        None,
+    );
+    request.headers_mut().insert(
+        header::AUTHORIZATION,
+        format!(
+            "Basic {}",
+            STANDARD.encode(format!("{telegram_id}:device-password"))
+        )
+        .parse()
+        .unwrap(),
+    );
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let ical = body_text(response).await;
+    assert!(ical.contains("DTSTART:20260601T090000\r\n"), "{ical}");
+    assert!(ical.contains("DTEND:20260601T091500\r\n"), "{ical}");
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_telegram_login_widget(pool: PgPool) {
+    use hmac::{Hmac, Mac};
+    use sha2::{Digest, Sha256};
+
+    let bot_token = "dummy_token";
+    let app = create_router(app_state(&pool, bot_token), "*");
+    let telegram_id = rand::random::<i64>().abs();
+    let auth_date = chrono::Utc::now().timestamp();
+
+    // The widget keys its HMAC with SHA256(bot token), unlike the Mini App
+    let mut mac = Hmac::<Sha256>::new_from_slice(&Sha256::digest(bot_token.as_bytes())).unwrap();
+    mac.update(format!("auth_date={auth_date}\nfirst_name=Browser\nid={telegram_id}").as_bytes());
+    let mut login = serde_json::json!({
+        "id": telegram_id,
+        "first_name": "Browser",
+        "auth_date": auth_date,
+        "hash": hex::encode(mac.finalize().into_bytes()),
+    });
+
+    let response = app
+        .clone()
+        .oneshot(create_request(
+            "POST",
+            "/api/auth/telegram-login",
+            Body::from(login.to_string()),
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    let token = body["token"].as_str().unwrap();
+
+    let response = app
+        .clone()
+        .oneshot(create_request("GET", "/api/me", Body::empty(), Some(token)))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let me: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    assert_eq!(me["id"], telegram_id.to_string());
+
+    // Changing a signed field breaks the hash
+    login["id"] = serde_json::json!(telegram_id + 1);
+    let response = app
+        .oneshot(create_request(
+            "POST",
+            "/api/auth/telegram-login",
+            Body::from(login.to_string()),
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_read_only_mode_refuses_writes(pool: PgPool) {
+    let telegram_id = setup_user(&pool).await;
+    let bot_token = "dummy_token";
+    let init_data = generate_valid_init_data(bot_token, telegram_id);
+    let app = create_router(app_state(&pool, bot_token), "*");
+
+    sqlx::query(
+        "INSERT INTO feature_flags (name, description, enabled, rollout_percent) VALUES ('read_only', 'Maintenance', TRUE, 0)",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let response = app
+        .clone()
+        .oneshot(create_request(
+            "POST",
+            "/api/events",
+            Body::from(
+                serde_json::json!({
+                    "uid": "maintenance-uid",
+                    "summary": "Blocked",
+                    "timing": {
+                        "kind": "all_day",
+                        "start_date": "2026-06-01",
+                        "end_date": "2026-06-02"
+                    }
+                })
+                .to_string(),
+            ),
+            Some(&init_data),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(response.headers()[header::RETRY_AFTER], "300");
+
+    let response = app
+        .clone()
+        .oneshot(create_request(
+            "GET",
+            "/api/events",
+            Body::empty(),
+            Some(&init_data),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Refused before device authentication runs
+    let response = app
+        .oneshot(create_request(
+            "DELETE",
+            format!("/caldav/{telegram_id}/maintenance-uid.ics"),
+            Body::empty(),
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_scheduled_invite_reminder(pool: PgPool) {
+    let telegram_id = setup_user(&pool).await;
+    let guest_id = setup_user(&pool).await;
+    let bot_token = "dummy_token";
+    let init_data = generate_valid_init_data(bot_token, telegram_id);
+    let app = create_router(app_state(&pool, bot_token), "*");
+
+    let create_body = serde_json::json!({
+        "uid": "planning",
+        "summary": "Planning",
+        "timing": {
+            "kind": "timed",
+            "start": "2026-06-01T10:00:00Z",
+            "end": "2026-06-01T11:00:00Z",
+            "timezone": "UTC"
+        }
+    });
+    let response = app
+        .clone()
+        .oneshot(create_request(
+            "POST",
+            "/api/events",
+            Body::from(create_body.to_string()),
+            Some(&init_data),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let event: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    let event_id = event["id"].as_str().unwrap().to_string();
+    let guest_email = format!("tg_{guest_id}@televent.internal");
+    sqlx::query(
+        "INSERT INTO event_attendees (event_id, email, user_id, role, status) VALUES ($1, $2, $3, 'ATTENDEE', 'NEEDS-ACTION')",
+    )
+    .bind(Uuid::parse_str(&event_id).unwrap())
+    .bind(&guest_email)
+    .bind(guest_id)
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let schedule = |send_at: chrono::DateTime<chrono::Utc>| {
+        create_request(
+            "POST",
+            format!("/api/events/{event_id}/reminders"),
+            Body::from(serde_json::json!({ "email": guest_email, "send_at": send_at }).to_string()),
+            Some(&init_data),
+        )
+    };
+
+    let too_late = chrono::Utc::now() + chrono::Duration::days(31);
+    let response = app.clone().oneshot(schedule(too_late)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let send_at = chrono::Utc::now() + chrono::Duration::hours(12);
+    let response = app.clone().oneshot(schedule(send_at)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let scheduled: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    let message_id = scheduled["id"].as_str().unwrap().to_string();
+
+    // Queued for later, so the worker won't claim it yet
+    let (kind, due): (String, bool) =
+        sqlx::query_as("SELECT kind, scheduled_at <= NOW() FROM outbox_messages WHERE id = $1")
+            .bind(Uuid::parse_str(&message_id).unwrap())
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(kind, "invite_reminder");
+    assert!(!due);
+
+    let response = app
+        .clone()
+        .oneshot(create_request(
+            "GET",
+            "/api/scheduled-messages",
+            Body::empty(),
+            Some(&init_data),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let listed: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    assert_eq!(listed.as_array().unwrap().len(), 1);
+    assert_eq!(listed[0]["id"], message_id.as_str());
+    assert_eq!(listed[0]["event_id"], event_id.as_str());
+
+    // Only the organizer who scheduled it may cancel it
+    let guest_init_data = generate_valid_init_data(bot_token, guest_id);
+    let cancel = |init_data: &str| {
+        create_request(
+            "DELETE",
+            format!("/api/scheduled-messages/{message_id}"),
+            Body::empty(),
+            Some(init_data),
+        )
+    };
+    let response = app.clone().oneshot(cancel(&guest_init_data)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let response = app.clone().oneshot(cancel(&init_data)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let response = app.oneshot(cancel(&init_data)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_snooze_invite_reminder(pool: PgPool) {
+    let organizer_id = setup_user(&pool).await;
+    let guest_id = setup_user(&pool).await;
+    let bot_token = "dummy_token";
+    let organizer_init_data = generate_valid_init_data(bot_token, organizer_id);
+    let guest_init_data = generate_valid_init_data(bot_token, guest_id);
+    let app = create_router(app_state(&pool, bot_token), "*");
+
+    let create_body = serde_json::json!({
+        "uid": "retro",
+        "summary": "Retro",
+        "timing": {
+            "kind": "timed",
+            "start": "2026-06-01T10:00:00Z",
+            "end": "2026-06-01T11:00:00Z",
+            "timezone": "UTC"
+        }
+    });
+    let response = app
+        .clone()
+        .oneshot(create_request(
+            "POST",
+            "/api/events",
+            Body::from(create_body.to_string()),
+            Some(&organizer_init_data),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let event: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    let event_id = Uuid::parse_str(event["id"].as_str().unwrap()).unwrap();
+    sqlx::query(
+        "INSERT INTO event_attendees (event_id, email, user_id, role, status) VALUES ($1, $2, $3, 'ATTENDEE', 'NEEDS-ACTION')",
+    )
+    .bind(event_id)
+    .bind(format!("tg_{guest_id}@televent.internal"))
+    .bind(guest_id)
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let snooze = |delay: &str, init_data: &str| {
+        create_request(
+            "POST",
+            format!("/api/events/{event_id}/snooze"),
+            Body::from(serde_json::json!({ "delay": delay }).to_string()),
+            Some(init_data),
+        )
+    };
+
+    let response = app
+        .clone()
+        .oneshot(snooze("2d", &guest_init_data))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    // The organizer isn't invited to their own event
+    let response = app
+        .clone()
+        .oneshot(snooze("1h", &organizer_init_data))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let before = chrono::Utc::now();
+    let response = app
+        .clone()
+        .oneshot(snooze("1h", &guest_init_data))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let snoozed: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    assert_eq!(snoozed["kind"], "invite_reminder");
+    let scheduled_at: chrono::DateTime<chrono::Utc> =
+        snoozed["scheduled_at"].as_str().unwrap().parse().unwrap();
+    assert!(scheduled_at >= before + chrono::Duration::hours(1));
+
+    let (target, scheduled_by): (String, i64) = sqlx::query_as(
+        "SELECT payload->>'target_user_id', scheduled_by FROM outbox_messages WHERE id = $1",
+    )
+    .bind(Uuid::parse_str(snoozed["id"].as_str().unwrap()).unwrap())
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(target, guest_id.to_string());
+    assert_eq!(scheduled_by, guest_id);
+
+    sqlx::query("UPDATE event_attendees SET status = 'ACCEPTED' WHERE user_id = $1")
+        .bind(guest_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+    let response = app.oneshot(snooze("10m", &guest_init_data)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_cancelled_event_notifies_guests_and_leaves_list(pool: PgPool) {
+    let telegram_id = setup_user(&pool).await;
+    let guest_id = setup_user(&pool).await;
+    let bot_token = "dummy_token";
+    let init_data = generate_valid_init_data(bot_token, telegram_id);
+    let app = create_router(app_state(&pool, bot_token), "*");
+
+    let create_body = serde_json::json!({
+        "uid": "offsite",
+        "summary": "Offsite",
+        "timing": {
+            "kind": "timed",
+            "start": "2026-06-01T10:00:00Z",
+            "end": "2026-06-01T11:00:00Z",
+            "timezone": "UTC"
+        }
+    });
+    let response = app
+        .clone()
+        .oneshot(create_request(
+            "POST",
+            "/api/events",
+            Body::from(create_body.to_string()),
+            Some(&init_data),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let event: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    let event_id = Uuid::parse_str(event["id"].as_str().unwrap()).unwrap();
+    for (email, user_id, status) in [
+        (
+            format!("tg_{guest_id}@televent.internal"),
+            Some(guest_id),
+            "NEEDS-ACTION",
+        ),
+        ("guest@example.com".to_string(), None, "ACCEPTED"),
+        ("gone@example.com".to_string(), None, "DECLINED"),
+    ] {
+        sqlx::query(
+            "INSERT INTO event_attendees (event_id, email, user_id, role, status) VALUES ($1, $2, $3, 'ATTENDEE', $4::attendee_status)",
+        )
+        .bind(event_id)
+        .bind(email)
+        .bind(user_id)
+        .bind(status)
+        .execute(&pool)
+        .await
+        .unwrap();
+    }
+
+    let cancel = || {
+        create_request(
+            "PUT",
+            format!("/api/events/{event_id}"),
+            Body::from(serde_json::json!({ "status": "Cancelled" }).to_string()),
+            Some(&init_data),
+        )
+    };
+    let response = app.clone().oneshot(cancel()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Declined guests are not told again
+    let kinds: Vec<String> = sqlx::query_scalar(
+        "SELECT kind FROM outbox_messages WHERE kind IN ('event_cancelled_notification', 'cancellation_email') ORDER BY kind",
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(
+        kinds,
+        vec!["cancellation_email", "event_cancelled_notification"]
+    );
+
+    // Saving the cancelled event again queues nothing new
+    let response = app.clone().oneshot(cancel()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let queued: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM outbox_messages WHERE kind IN ('event_cancelled_notification', 'cancellation_email')",
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(queued, 2);
+
+    let list = |uri: &str| create_request("GET", uri, Body::empty(), Some(&init_data));
+    let response = app.clone().oneshot(list("/api/events")).await.unwrap();
+    let listed: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    assert!(listed.as_array().unwrap().is_empty());
+
+    let response = app
+        .oneshot(list("/api/events?status=Cancelled"))
+        .await
+        .unwrap();
+    let listed: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    assert_eq!(listed.as_array().unwrap().len(), 1);
+    assert_eq!(listed[0]["status"], "Cancelled");
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_moving_event_notifies_coming_guests_once(pool: PgPool) {
+    let telegram_id = setup_user(&pool).await;
+    let coming_id = setup_user(&pool).await;
+    let pending_id = setup_user(&pool).await;
+    let bot_token = "dummy_token";
+    let init_data = generate_valid_init_data(bot_token, telegram_id);
+    let app = create_router(app_state(&pool, bot_token), "*");
+
+    let timing = |start: &str, end: &str| serde_json::json!({ "kind": "timed", "start": start, "end": end, "timezone": "UTC" });
+    let create_body = serde_json::json!({
+        "uid": "retro",
+        "summary": "Retro",
+        "timing": timing("2026-06-01T10:00:00Z", "2026-06-01T11:00:00Z"),
+    });
+    let response = app
+        .clone()
+        .oneshot(create_request(
+            "POST",
+            "/api/events",
+            Body::from(create_body.to_string()),
+            Some(&init_data),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let event: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    let event_id = Uuid::parse_str(event["id"].as_str().unwrap()).unwrap();
+    for (user_id, status) in [(coming_id, "ACCEPTED"), (pending_id, "NEEDS-ACTION")] {
+        sqlx::query(
+            "INSERT INTO event_attendees (event_id, email, user_id, role, status) VALUES ($1, $2, $3, 'ATTENDEE', $4::attendee_status)",
+        )
+        .bind(event_id)
+        .bind(format!("tg_{user_id}@televent.internal"))
+        .bind(user_id)
+        .bind(status)
+        .execute(&pool)
+        .await
+        .unwrap();
+    }
+
+    let update = |body: Value| {
+        create_request(
+            "PUT",
+            format!("/api/events/{event_id}"),
+            Body::from(body.to_string()),
+            Some(&init_data),
+        )
+    };
+    let queued = || async {
+        sqlx::query_as::<_, (Value, bool)>(
+            "SELECT payload, scheduled_at > NOW() FROM outbox_messages WHERE kind = 'event_updated'",
+        )
+        .fetch_all(&pool)
+        .await
+        .unwrap()
+    };
+
+    // A new title is not worth a message
+    let response = app
+        .clone()
+        .oneshot(update(serde_json::json!({ "summary": "Team retro" })))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(queued().await.is_empty());
+
+    // Two moves in a row reach the guest who is coming as one message
+    for (start, end) in [
+        ("2026-06-01T14:00:00Z", "2026-06-01T15:00:00Z"),
+        ("2026-06-01T15:00:00Z", "2026-06-01T16:00:00Z"),
+    ] {
+        let response = app
+            .clone()
+            .oneshot(update(serde_json::json!({ "timing": timing(start, end) })))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+    let messages = queued().await;
+    assert!(
+        messages
+            .iter()
+            .all(|(payload, _)| payload["target_user_id"] == coming_id)
+    );
+    // Unless the two edits straddled a window boundary, the second folded
+    // into the first
+    let (payload, deferred) = messages
+        .iter()
+        .find(|(payload, _)| payload["previous_timing"]["start"] == "2026-06-01T10:00:00Z")
+        .expect("message for the first move");
+    assert!(*deferred, "sent once the edit window closes");
+    assert_eq!(payload["event_summary"], "Team retro");
+    assert!(messages.len() <= 2);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_moving_event_sends_itip_request_to_external_guests(pool: PgPool) {
+    let telegram_id = setup_user(&pool).await;
+    let bot_token = "dummy_token";
+    let init_data = generate_valid_init_data(bot_token, telegram_id);
+    let app = create_router(app_state(&pool, bot_token), "*");
+
+    let timing = |start: &str, end: &str| serde_json::json!({ "kind": "timed", "start": start, "end": end, "timezone": "UTC" });
+    let create_body = serde_json::json!({
+        "uid": "board-meeting",
+        "summary": "Board meeting",
+        "timing": timing("2026-06-01T10:00:00Z", "2026-06-01T11:00:00Z"),
+    });
+    let response = app
+        .clone()
+        .oneshot(create_request(
+            "POST",
+            "/api/events",
+            Body::from(create_body.to_string()),
+            Some(&init_data),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let event: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    let event_id = Uuid::parse_str(event["id"].as_str().unwrap()).unwrap();
+    for (email, status) in [
+        ("board@example.com", "ACCEPTED"),
+        ("absent@example.com", "DECLINED"),
+    ] {
+        sqlx::query(
+            "INSERT INTO event_attendees (event_id, email, role, status) VALUES ($1, $2, 'ATTENDEE', $3::attendee_status)",
+        )
+        .bind(event_id)
+        .bind(email)
+        .bind(status)
+        .execute(&pool)
+        .await
+        .unwrap();
+    }
+
+    let response = app
+        .clone()
+        .oneshot(create_request(
+            "PUT",
+            format!("/api/events/{event_id}"),
+            Body::from(
+                serde_json::json!({ "timing": timing("2026-06-02T10:00:00Z", "2026-06-02T11:00:00Z") })
+                    .to_string(),
+            ),
+            Some(&init_data),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let sequence = sqlx::query_scalar::<_, i32>("SELECT sequence FROM events WHERE id = $1")
+        .bind(event_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert!(sequence > 0, "a move bumps the iTIP sequence");
+
+    let recipients = sqlx::query_scalar::<_, String>(
+        "SELECT payload->>'recipient_email' FROM outbox_messages WHERE kind = 'event_update_email'",
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(recipients, vec!["board@example.com"]);
+
+    let calendar = televent_application::CalendarService::new(
+        televent_storage::calendar::CalendarRepository::new(pool.clone()),
+    );
+    let itip = calendar
+        .guest_itip(
+            event_id,
+            "board@example.com",
+            televent_application::ItipChange::Rescheduled,
+        )
+        .await
+        .unwrap()
+        .expect("guest is still invited");
+    assert!(itip.contains("METHOD:REQUEST"));
+    assert!(itip.contains(&format!("SEQUENCE:{sequence}")));
+    assert!(itip.contains("PARTSTAT=NEEDS-ACTION;RSVP=TRUE"));
+
+    // The event is not cancelled, so there is nothing to call off
+    assert!(
+        calendar
+            .guest_itip(
+                event_id,
+                "board@example.com",
+                televent_application::ItipChange::Cancelled,
+            )
+            .await
+            .unwrap()
+            .is_none()
+    );
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_chat_webhooks_are_managed_and_receive_copies(pool: PgPool) {
+    let telegram_id = setup_user(&pool).await;
+    let bot_token = "dummy_token";
+    let init_data = generate_valid_init_data(bot_token, telegram_id);
+    let app = create_router(app_state(&pool, bot_token), "*");
+    let add = |body: Value| {
+        create_request(
+            "POST",
+            "/api/chat-webhooks",
+            Body::from(body.to_string()),
+            Some(&init_data),
+        )
+    };
+    let slack_url = "https://hooks.slack.com/services/T000/B000/XXXXabcd";
+
+    // Only the provider's own hosts are accepted
+    let response = app
+        .clone()
+        .oneshot(add(serde_json::json!({
+            "provider": "slack",
+            "url": "https://example.com/services/T000",
+        })))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = app
+        .clone()
+        .oneshot(add(
+            serde_json::json!({ "provider": "slack", "url": slack_url }),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let webhook: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    assert_eq!(webhook["provider"], "slack");
+    assert_eq!(webhook["url"], "https://hooks.slack.com/…abcd");
+    let webhook_id = Uuid::parse_str(webhook["id"].as_str().unwrap()).unwrap();
+
+    let response = app
+        .clone()
+        .oneshot(add(
+            serde_json::json!({ "provider": "slack", "url": slack_url }),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    let response = app
+        .clone()
+        .oneshot(create_request(
+            "GET",
+            "/api/chat-webhooks",
+            Body::empty(),
+            Some(&init_data),
+        ))
+        .await
+        .unwrap();
+    let listed: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    assert_eq!(listed.as_array().unwrap().len(), 1);
+    assert!(!listed.to_string().contains("XXXXabcd"));
+
+    // A notice is copied once per source message, however often it is retried
+    let webhooks = televent_application::ChatWebhookService::new(
+        televent_storage::chat_webhook::ChatWebhookRepository::new(pool.clone()),
+    );
+    let notice = televent_application::ChatNotice {
+        topic: televent_application::NotificationTopic::Invite,
+        title: "📅 New Invite: Retro".to_string(),
+        lines: vec!["🕒 Time: Mon 1 Jun, 10:00".to_string()],
+    };
+    let source_id = Uuid::new_v4();
+    for _ in 0..2 {
+        webhooks
+            .queue_copies(
+                televent_domain::UserId::new(telegram_id),
+                source_id,
+                &notice,
+            )
+            .await
+            .unwrap();
+    }
+    let copies = sqlx::query_scalar::<_, Value>(
+        "SELECT payload FROM outbox_messages WHERE kind = 'chat_webhook'",
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(copies.len(), 1);
+    assert_eq!(copies[0]["webhook_id"], webhook_id.to_string());
+    assert_eq!(copies[0]["title"], "📅 New Invite: Retro");
+
+    let response = app
+        .clone()
+        .oneshot(create_request(
+            "DELETE",
+            format!("/api/chat-webhooks/{webhook_id}"),
+            Body::empty(),
+            Some(&init_data),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    assert!(webhooks.target(webhook_id).await.unwrap().is_none());
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_notification_preferences_and_phone(pool: PgPool) {
+    let telegram_id = setup_user(&pool).await;
+    let bot_token = "dummy_token";
+    let init_data = generate_valid_init_data(bot_token, telegram_id);
+    let state = app_state(&pool, bot_token);
+    let app_without_sms = create_router(state.clone(), "*");
+    let app = create_router_with_config(
+        state,
+        &api::config::Config {
+            host: "0.0.0.0".to_string(),
+            port: 3000,
+            cors: api::middleware::cors::CorsPolicy::any(),
+            frontend_static_dir: None,
+            enable_swagger: false,
+            email_signups: false,
+            email_webhook_secret: None,
+            sms_notifications: true,
+            web_push_public_key: None,
+            device_budget: Default::default(),
+            trigger_budget: Default::default(),
+            base_path: Default::default(),
+            frontend_assets: Default::default(),
+        },
+    );
+    let request = |method: &str, uri: &str, body: Option<Value>| {
+        create_request(
+            method,
+            uri,
+            body.map_or_else(Body::empty, |body| Body::from(body.to_string())),
+            Some(&init_data),
+        )
+    };
+
+    // Chat and push copies are on, texts and email off until the user chooses
+    let response = app
+        .clone()
+        .oneshot(request("GET", "/api/me/notification-preferences", None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let matrix: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    let cells = matrix.as_array().unwrap();
+    assert_eq!(cells.len(), 16);
+    assert!(
+        cells
+            .iter()
+            .all(|cell| cell["enabled"] == (cell["channel"] != "sms" && cell["channel"] != "email"))
+    );
+
+    let response = app
+        .clone()
+        .oneshot(request(
+            "PUT",
+            "/api/me/notification-preferences",
+            Some(serde_json::json!([
+                { "topic": "reminder", "channel": "sms", "enabled": true },
+                { "topic": "invite", "channel": "chat", "enabled": false },
+            ])),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let matrix: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    let enabled = |topic: &str, channel: &str| {
+        matrix
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|cell| cell["topic"] == topic && cell["channel"] == channel)
+            .unwrap()["enabled"]
+            .clone()
+    };
+    assert_eq!(enabled("reminder", "sms"), true);
+    assert_eq!(enabled("invite", "chat"), false);
+    assert_eq!(enabled("update", "chat"), true);
+
+    // A switched-off topic is not copied to chat webhooks
+    let webhooks = televent_application::ChatWebhookService::new(
+        televent_storage::chat_webhook::ChatWebhookRepository::new(pool.clone()),
+    );
+    let response = app
+        .clone()
+        .oneshot(request(
+            "POST",
+            "/api/chat-webhooks",
+            Some(serde_json::json!({
+                "provider": "slack",
+                "url": "https://hooks.slack.com/services/T000/B000/XXXXabcd",
+            })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let notice = televent_application::ChatNotice {
+        topic: televent_application::NotificationTopic::Invite,
+        title: "📅 New Invite: Retro".to_string(),
+        lines: Vec::new(),
+    };
+    let queued = webhooks
+        .queue_copies(
+            televent_domain::UserId::new(telegram_id),
+            Uuid::new_v4(),
+            &notice,
+        )
+        .await
+        .unwrap();
+    assert_eq!(queued, 0);
+
+    // Registering a number needs an SMS provider
+    let phone = serde_json::json!({ "phone_number": "+44 20 7946 0958" });
+    let response = app_without_sms
+        .oneshot(request("PUT", "/api/me/phone", Some(phone.clone())))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+    let response = app
+        .clone()
+        .oneshot(request(
+            "PUT",
+            "/api/me/phone",
+            Some(serde_json::json!({ "phone_number": "12" })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = app
+        .clone()
+        .oneshot(request("PUT", "/api/me/phone", Some(phone)))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
+    let registered: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    assert_eq!(registered["phone_number"], "+442079460958");
+    assert_eq!(registered["verified"], false);
+    let codes =
+        sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM outbox_messages WHERE kind = 'sms'")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(codes, 1);
+
+    // No code has been texted yet, so nothing can match
+    let response = app
+        .clone()
+        .oneshot(request(
+            "POST",
+            "/api/me/phone/verify",
+            Some(serde_json::json!({ "code": "123456" })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = app
+        .clone()
+        .oneshot(request("DELETE", "/api/me/phone", None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let response = app
+        .oneshot(request("GET", "/api/me/phone", None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_email_verification(pool: PgPool) {
+    let telegram_id = setup_user(&pool).await;
+    let bot_token = "dummy_token";
+    let init_data = generate_valid_init_data(bot_token, telegram_id);
+    let state = app_state(&pool, bot_token);
+    let app_without_email = create_router(state.clone(), "*");
+    let app = create_router_with_config(
+        state,
+        &api::config::Config {
+            host: "0.0.0.0".to_string(),
+            port: 3000,
+            cors: api::middleware::cors::CorsPolicy::any(),
+            frontend_static_dir: None,
+            enable_swagger: false,
+            email_signups: true,
+            email_webhook_secret: None,
+            sms_notifications: false,
+            web_push_public_key: None,
+            device_budget: Default::default(),
+            trigger_budget: Default::default(),
+            base_path: Default::default(),
+            frontend_assets: Default::default(),
+        },
+    );
+    let request = |method: &str, uri: &str, body: Option<Value>| {
+        create_request(
+            method,
+            uri,
+            body.map_or_else(Body::empty, |body| Body::from(body.to_string())),
+            Some(&init_data),
+        )
+    };
+    let address = serde_json::json!({ "email": "ada@example.com" });
+    let notifications = televent_application::NotificationService::new(
+        televent_storage::notification::NotificationRepository::new(pool.clone()),
+    );
+    let user = televent_domain::UserId::new(telegram_id);
+
+    // Confirming an address needs email delivery
+    let response = app_without_email
+        .oneshot(request("PUT", "/api/me/email", Some(address.clone())))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+    let response = app
+        .clone()
+        .oneshot(request("PUT", "/api/me/email", Some(address.clone())))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
+    let saved: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    assert_eq!(saved["email"], "ada@example.com");
+    assert_eq!(saved["verified"], false);
+    let codes = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM outbox_messages WHERE kind = 'user_email'",
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(codes, 1);
+
+    // Asking again right away is throttled
+    let response = app
+        .clone()
+        .oneshot(request("PUT", "/api/me/email", Some(address.clone())))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    // Unconfirmed addresses get no notice copies
+    let notice = televent_application::ChatNotice {
+        topic: televent_application::NotificationTopic::Reminder,
+        title: "⏰ Reminder: Retro".to_string(),
+        lines: Vec::new(),
+    };
+    let response = app
+        .clone()
+        .oneshot(request(
+            "PUT",
+            "/api/me/notification-preferences",
+            Some(serde_json::json!([
+                { "topic": "reminder", "channel": "email", "enabled": true },
+            ])),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(
+        !notifications
+            .queue_email_copy(user, Uuid::new_v4(), &notice)
+            .await
+            .unwrap()
+    );
+
+    // No code has been emailed yet, so nothing can match
+    let verify = |code: &str| {
+        request(
+            "POST",
+            "/api/me/email/verify",
+            Some(serde_json::json!({ "code": code })),
+        )
+    };
+    let response = app.clone().oneshot(verify("123456")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    // The worker mints the code right before sending it
+    let code = notifications
+        .issue_email_verification_code(user, "ada@example.com")
+        .await
+        .unwrap()
+        .unwrap();
+    let wrong = if code == "000000" { "111111" } else { "000000" };
+    let response = app.clone().oneshot(verify(wrong)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let response = app.clone().oneshot(verify(&code)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let confirmed: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    assert_eq!(confirmed["verified"], true);
+    assert!(
+        notifications
+            .queue_email_copy(user, Uuid::new_v4(), &notice)
+            .await
+            .unwrap()
+    );
+
+    // The same address stays confirmed; a new one starts over
+    let response = app
+        .clone()
+        .oneshot(request("PUT", "/api/me/email", Some(address)))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = app
+        .clone()
+        .oneshot(request(
+            "PUT",
+            "/api/me/email",
+            Some(serde_json::json!({ "email": "ada@work.example.com" })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
+    let response = app
+        .clone()
+        .oneshot(request("GET", "/api/me/email", None))
+        .await
+        .unwrap();
+    let replaced: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    assert_eq!(replaced["email"], "ada@work.example.com");
+    assert_eq!(replaced["verified"], false);
+
+    // The emailed link confirms it too, but only when opened and posted
+    let code = notifications
+        .issue_email_verification_code(user, "ada@work.example.com")
+        .await
+        .unwrap()
+        .unwrap();
+    let token =
+        televent_application::EmailVerificationKey::from_bot_token(bot_token).token(user, &code);
+    let link = format!("/verify-email/{token}");
+    let response = app
+        .clone()
+        .oneshot(create_request("GET", &link, Body::empty(), None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(notifications.verified_email(user).await.unwrap().is_none());
+    let forged = televent_application::EmailVerificationKey::from_bot_token("other_token")
+        .token(user, &code);
+    let response = app
+        .clone()
+        .oneshot(create_request(
+            "POST",
+            format!("/verify-email/{forged}"),
+            Body::empty(),
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let response = app
+        .oneshot(create_request("POST", &link, Body::empty(), None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        notifications.verified_email(user).await.unwrap().as_deref(),
+        Some("ada@work.example.com")
+    );
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_web_push_subscriptions(pool: PgPool) {
+    let telegram_id = setup_user(&pool).await;
+    let bot_token = "dummy_token";
+    let init_data = generate_valid_init_data(bot_token, telegram_id);
+    let state = app_state(&pool, bot_token);
+    let app_without_push = create_router(state.clone(), "*");
+    let vapid_key =
+        "BP4z9KsN6nGRTbVYI_c7VJSPQTBtkgcy27mlmlMoZIIgDll6e3vCYLocInmYWAmS6TlzAC8wEqKK6PBru3jl7A8";
+    let app = create_router_with_config(
+        state,
+        &api::config::Config {
+            host: "0.0.0.0".to_string(),
+            port: 3000,
+            cors: api::middleware::cors::CorsPolicy::any(),
+            frontend_static_dir: None,
+            enable_swagger: false,
+            email_signups: false,
+            email_webhook_secret: None,
+            sms_notifications: false,
+            web_push_public_key: Some(vapid_key.to_string()),
+            device_budget: Default::default(),
+            trigger_budget: Default::default(),
+            base_path: Default::default(),
+            frontend_assets: Default::default(),
+        },
+    );
+    let request = |method: &str, uri: &str, body: Option<Value>| {
+        create_request(
+            method,
+            uri,
+            body.map_or_else(Body::empty, |body| Body::from(body.to_string())),
+            Some(&init_data),
+        )
+    };
+    let endpoint = "https://fcm.googleapis.com/fcm/send/dGVzdA:APA91b";
+    let subscription = |endpoint: &str| {
+        serde_json::json!({
+            "endpoint": endpoint,
+            "expirationTime": null,
+            "keys": {
+                "p256dh": "BCVxsr7N_eNgVRqvHtD0zTZsEc6-VV-JvLexhqUzORcxaOzi6-AYWXvTBHm4bjyPjs7Vd8pZGH6SRpkNtoIAiw4",
+                "auth": "BTBZMqHH6r4Tts7J_aSIgg",
+            },
+        })
+    };
+
+    let response = app_without_push
+        .clone()
+        .oneshot(request("GET", "/api/push/vapid-public-key", None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let response = app_without_push
+        .oneshot(request(
+            "POST",
+            "/api/push/subscribe",
+            Some(subscription(endpoint)),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+    let response = app
+        .clone()
+        .oneshot(request("GET", "/api/push/vapid-public-key", None))
+        .await
+        .unwrap();
+    let key: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    assert_eq!(key["public_key"], vapid_key);
+
+    // Only push services browsers ship with are accepted
+    let response = app
+        .clone()
+        .oneshot(request(
+            "POST",
+            "/api/push/subscribe",
+            Some(subscription("https://push.example.com/abc")),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let mut ids = Vec::new();
+    for _ in 0..2 {
+        let response = app
+            .clone()
+            .oneshot(request(
+                "POST",
+                "/api/push/subscribe",
+                Some(subscription(endpoint)),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let created: Value = serde_json::from_str(&body_text(response).await).unwrap();
+        ids.push(created["id"].clone());
+    }
+    // Subscribing the same browser again keeps one subscription
+    assert_eq!(ids[0], ids[1]);
+
+    let push = televent_application::WebPushService::new(
+        televent_storage::web_push::WebPushRepository::new(pool.clone()),
+    );
+    let notice = televent_application::ChatNotice {
+        topic: televent_application::NotificationTopic::Reminder,
+        title: "⏰ Reminder: Retro".to_string(),
+        lines: Vec::new(),
+    };
+    let user = televent_domain::UserId::new(telegram_id);
+    assert_eq!(
+        push.queue_copies(user, Uuid::new_v4(), &notice)
+            .await
+            .unwrap(),
+        1
+    );
+
+    // Turning reminders off for push stops the copies
+    let response = app
+        .clone()
+        .oneshot(request(
+            "PUT",
+            "/api/me/notification-preferences",
+            Some(serde_json::json!([
+                { "topic": "reminder", "channel": "push", "enabled": false },
+            ])),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        push.queue_copies(user, Uuid::new_v4(), &notice)
+            .await
+            .unwrap(),
+        0
+    );
+
+    let unsubscribe = serde_json::json!({ "endpoint": endpoint });
+    let response = app
+        .clone()
+        .oneshot(request(
+            "POST",
+            "/api/push/unsubscribe",
+            Some(unsubscribe.clone()),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let response = app
+        .oneshot(request("POST", "/api/push/unsubscribe", Some(unsubscribe)))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_calendar_stats(pool: PgPool) {
+    let telegram_id = setup_user(&pool).await;
+    sqlx::query("UPDATE users SET timezone = 'Asia/Tokyo' WHERE telegram_id = $1")
+        .bind(telegram_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+    let bot_token = "dummy_token";
+    let init_data = generate_valid_init_data(bot_token, telegram_id);
+    let app = create_router(app_state(&pool, bot_token), "*");
+
+    let mut event_ids = Vec::new();
+    // The first two fall on a Tuesday in Tokyo
+    for (start, end) in [
+        ("2026-03-02T20:00:00Z", "2026-03-02T21:00:00Z"),
+        ("2026-03-03T01:00:00Z", "2026-03-03T02:30:00Z"),
+        ("2026-03-05T03:00:00Z", "2026-03-05T03:30:00Z"),
+        ("2026-03-04T03:00:00Z", "2026-03-04T08:00:00Z"),
+    ] {
+        let body = serde_json::json!({
+            "uid": Uuid::new_v4().to_string(),
+            "summary": "Sync",
+            "timing": { "kind": "timed", "start": start, "end": end, "timezone": "UTC" },
+        });
+        let response = app
+            .clone()
+            .oneshot(create_request(
+                "POST",
+                "/api/events",
+                Body::from(body.to_string()),
+                Some(&init_data),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let event: Value = serde_json::from_str(&body_text(response).await).unwrap();
+        event_ids.push(Uuid::parse_str(event["id"].as_str().unwrap()).unwrap());
+    }
+    sqlx::query("UPDATE events SET status = 'CANCELLED' WHERE id = $1")
+        .bind(event_ids[3])
+        .execute(&pool)
+        .await
+        .unwrap();
+    for (event_id, email, role, status) in [
+        (event_ids[0], "alice@example.com", "ATTENDEE", "ACCEPTED"),
+        (
+            event_ids[1],
+            "alice@example.com",
+            "ATTENDEE",
+            "NEEDS-ACTION",
+        ),
+        (event_ids[2], "bob@example.com", "ATTENDEE", "TENTATIVE"),
+        (event_ids[0], "carol@example.com", "ATTENDEE", "DECLINED"),
+        (event_ids[3], "dave@example.com", "ATTENDEE", "ACCEPTED"),
+        (event_ids[0], "owner@example.com", "ORGANIZER", "ACCEPTED"),
+    ] {
+        sqlx::query(
+            "INSERT INTO event_attendees (event_id, email, role, status) VALUES ($1, $2, $3::attendee_role, $4::attendee_status)",
+        )
+        .bind(event_id)
+        .bind(email)
+        .bind(role)
+        .bind(status)
+        .execute(&pool)
+        .await
+        .unwrap();
+    }
+
+    let response = app
+        .clone()
+        .oneshot(create_request(
+            "GET",
+            "/api/me/stats?from=2026-03-02T00:00:00Z&to=2026-03-09T00:00:00Z",
+            Body::empty(),
+            Some(&init_data),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let stats: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    assert_eq!(stats["event_count"], 3);
+    assert_eq!(stats["total_hours"], 3.0);
+    assert_eq!(stats["average_daily_events"], 3.0 / 7.0);
+    assert_eq!(stats["busiest_weekday"], "Tuesday");
+    assert_eq!(stats["weekdays"][1]["events"], 2);
+    assert_eq!(stats["weekdays"][1]["hours"], 2.5);
+    let collaborators: Vec<(&str, i64)> = stats["top_collaborators"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|c| (c["email"].as_str().unwrap(), c["events"].as_i64().unwrap()))
+        .collect();
+    assert_eq!(
+        collaborators,
+        [("alice@example.com", 2), ("bob@example.com", 1)]
+    );
+
+    let response = app
+        .oneshot(create_request(
+            "GET",
+            "/api/me/stats?from=2024-01-01T00:00:00Z&to=2026-03-09T00:00:00Z",
+            Body::empty(),
+            Some(&init_data),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_month_grid(pool: PgPool) {
+    let telegram_id = setup_user(&pool).await;
+    let bot_token = "dummy_token";
+    let init_data = generate_valid_init_data(bot_token, telegram_id);
+    let app = create_router(app_state(&pool, bot_token), "*");
+
+    for body in [
+        serde_json::json!({
+            "uid": Uuid::new_v4().to_string(),
+            "summary": "Weekly planning",
+            "timing": {
+                "kind": "timed",
+                "start": "2026-03-02T23:30:00Z",
+                "end": "2026-03-03T00:30:00Z",
+                "timezone": "UTC"
+            },
+            "rrule": "FREQ=WEEKLY;COUNT=3",
+        }),
+        serde_json::json!({
+            "uid": Uuid::new_v4().to_string(),
+            "summary": "Conference",
+            "timing": {
+                "kind": "all_day",
+                "start_date": "2026-03-10",
+                "end_date": "2026-03-13"
+            },
+        }),
+    ] {
+        let response = app
+            .clone()
+            .oneshot(create_request(
+                "POST",
+                "/api/events",
+                Body::from(body.to_string()),
+                Some(&init_data),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+    }
+
+    let response = app
+        .clone()
+        .oneshot(create_request(
+            "GET",
+            "/api/events/grid?month=2026-03",
+            Body::empty(),
+            Some(&init_data),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let grid: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    assert_eq!(grid["month"], "2026-03");
+    assert_eq!(grid["timezone"], "UTC");
+    let days = grid["days"].as_array().unwrap();
+    assert_eq!(days.len(), 31);
+    let busy: Vec<(&str, u64)> = days
+        .iter()
+        .filter(|day| day["count"].as_u64().unwrap() > 0)
+        .map(|day| {
+            (
+                day["date"].as_str().unwrap(),
+                day["count"].as_u64().unwrap(),
+            )
+        })
+        .collect();
+    // Planning runs past midnight, so it shows on two days each week
+    assert_eq!(
+        busy,
+        [
+            ("2026-03-02", 1),
+            ("2026-03-03", 1),
+            ("2026-03-09", 1),
+            ("2026-03-10", 2),
+            ("2026-03-11", 1),
+            ("2026-03-12", 1),
+            ("2026-03-16", 1),
+            ("2026-03-17", 1),
+        ]
+    );
+    let tenth = &days[9]["events"];
+    assert_eq!(tenth[0]["summary"], "Conference");
+    assert_eq!(tenth[0]["is_all_day"], true);
+    assert_eq!(tenth[0]["continues"], false);
+    assert_eq!(tenth[1]["continues"], true);
+    assert_eq!(days[11]["events"][0]["continues"], true);
+
+    // In Tokyo planning starts and ends on the same local day
+    let response = app
+        .clone()
+        .oneshot(create_request(
+            "GET",
+            "/api/events/grid?month=2026-03&tz=Asia/Tokyo",
+            Body::empty(),
+            Some(&init_data),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let grid: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    assert_eq!(grid["timezone"], "Asia/Tokyo");
+    assert_eq!(grid["days"][1]["count"], 0);
+    assert_eq!(grid["days"][2]["count"], 1);
+
+    for uri in [
+        "/api/events/grid?month=March",
+        "/api/events/grid?month=2026-03&tz=Mars/Olympus",
+    ] {
+        let response = app
+            .clone()
+            .oneshot(create_request("GET", uri, Body::empty(), Some(&init_data)))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{uri}");
+    }
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_agenda_page(pool: PgPool) {
+    use hmac::{Hmac, Mac};
+    use sha2::{Digest, Sha256};
+
+    let telegram_id = setup_user(&pool).await;
+    let bot_token = "dummy_token";
+    let init_data = generate_valid_init_data(bot_token, telegram_id);
+    let app = create_router(app_state(&pool, bot_token), "*");
+
+    let start = chrono::Utc::now() + chrono::Duration::days(1);
+    let body = serde_json::json!({
+        "uid": Uuid::new_v4().to_string(),
+        "summary": "Dentist <3>",
+        "timing": {
+            "kind": "timed",
+            "start": start,
+            "end": start + chrono::Duration::hours(1),
+            "timezone": "UTC"
+        },
+    });
+    let response = app
+        .clone()
+        .oneshot(create_request(
+            "POST",
+            "/api/events",
+            Body::from(body.to_string()),
+            Some(&init_data),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    let response = app
+        .clone()
+        .oneshot(create_request(
+            "GET",
+            "/app/agenda",
+            Body::empty(),
+            Some(&init_data),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let html = body_text(response).await;
+    assert!(html.contains("Dentist &lt;3&gt;"));
+    assert!(html.contains(&start.format("%a %d %b").to_string()));
+
+    let response = app
+        .clone()
+        .oneshot(create_request("GET", "/app/agenda", Body::empty(), None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    // The Login Widget redirect leaves a cookie the page accepts
+    let auth_date = chrono::Utc::now().timestamp();
+    let mut mac = Hmac::<Sha256>::new_from_slice(&Sha256::digest(bot_token.as_bytes())).unwrap();
+    mac.update(format!("auth_date={auth_date}\nfirst_name=Browser\nid={telegram_id}").as_bytes());
+    let uri = format!(
+        "/app/login?id={telegram_id}&first_name=Browser&auth_date={auth_date}&hash={}",
+        hex::encode(mac.finalize().into_bytes())
+    );
+    let response = app
+        .clone()
+        .oneshot(create_request("GET", &uri, Body::empty(), None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::SEE_OTHER);
+    assert_eq!(response.headers()["location"], "/app/agenda");
+    let cookie = response.headers()["set-cookie"].to_str().unwrap();
+    assert!(cookie.contains("HttpOnly"));
+    let session = cookie.split(';').next().unwrap().to_string();
+
+    let mut request = create_request("GET", "/app/agenda", Body::empty(), None);
+    request
+        .headers_mut()
+        .insert("cookie", session.parse().unwrap());
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(body_text(response).await.contains("Dentist &lt;3&gt;"));
+
+    let response = app
+        .oneshot(create_request(
+            "GET",
+            "/app/login?id=1&first_name=Browser&auth_date=1&hash=00",
+            Body::empty(),
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_frontend_assets_headers(pool: PgPool) {
+    let static_dir = tempfile::tempdir().unwrap();
+    std::fs::write(static_dir.path().join("index.html"), "<html>app</html>").unwrap();
+    std::fs::create_dir_all(static_dir.path().join("_next/static/chunks")).unwrap();
+    std::fs::write(
+        static_dir.path().join("_next/static/chunks/main-1a2b3c.js"),
+        "console.log(1)",
+    )
+    .unwrap();
+
+    let app = create_router_with_config(
+        app_state(&pool, "dummy_token"),
+        &api::config::Config {
+            host: "0.0.0.0".to_string(),
+            port: 3000,
+            cors: api::middleware::cors::CorsPolicy::any(),
+            frontend_static_dir: Some(static_dir.path().to_string_lossy().into_owned()),
+            enable_swagger: false,
+            email_signups: false,
+            email_webhook_secret: None,
+            sms_notifications: false,
+            web_push_public_key: None,
+            device_budget: Default::default(),
+            trigger_budget: Default::default(),
+            base_path: Default::default(),
+            frontend_assets: api::middleware::static_assets::FrontendAssets::new(
+                &["https://api.example.com".to_string()],
+                &[],
+            )
+            .unwrap(),
+        },
+    );
+    let get = |uri: &str| {
+        app.clone()
+            .oneshot(create_request("GET", uri, Body::empty(), None))
+    };
+
+    let response = get("/app/_next/static/chunks/main-1a2b3c.js")
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers()[header::CACHE_CONTROL],
+        "public, max-age=31536000, immutable"
+    );
+
+    // Unknown pages fall back to index.html, which is always revalidated
+    for uri in ["/app/", "/app/devices"] {
+        let response = get(uri).await.unwrap();
+        assert_eq!(
+            response.headers()[header::CACHE_CONTROL],
+            "no-cache",
+            "{uri}"
+        );
+        let policy = response.headers()[header::CONTENT_SECURITY_POLICY]
+            .to_str()
+            .unwrap();
+        assert!(policy.contains("connect-src 'self' https://api.example.com;"));
+        assert!(body_text(response).await.contains("app"));
+    }
+
+    // The API keeps its own policy
+    let response = get("/health").await.unwrap();
+    assert!(
+        !response.headers()[header::CONTENT_SECURITY_POLICY]
+            .to_str()
+            .unwrap()
+            .contains("api.example.com")
+    );
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_edit_scopes_split_recurring_series(pool: PgPool) {
+    let telegram_id = setup_user(&pool).await;
+    let bot_token = "dummy_token";
+    let init_data = generate_valid_init_data(bot_token, telegram_id);
+    let app = create_router(app_state(&pool, bot_token), "*");
+
+    let timing = |start: &str, end: &str| serde_json::json!({ "kind": "timed", "start": start, "end": end, "timezone": "UTC" });
+    let create_body = serde_json::json!({
+        "uid": "standup",
+        "summary": "Standup",
+        "timing": timing("2026-06-01T10:00:00Z", "2026-06-01T10:15:00Z"),
+        "rrule": "FREQ=WEEKLY;COUNT=6",
+    });
+    let response = app
+        .clone()
+        .oneshot(create_request(
+            "POST",
+            "/api/events",
+            Body::from(create_body.to_string()),
+            Some(&init_data),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let series: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    let series_id = series["id"].as_str().unwrap().to_string();
+
+    let update = |body: Value| {
+        create_request(
+            "PUT",
+            format!("/api/events/{series_id}"),
+            Body::from(body.to_string()),
+            Some(&init_data),
+        )
+    };
+    let rules = || async {
+        sqlx::query_as::<_, (String, String, Option<String>)>(
+            "SELECT summary, to_char(start AT TIME ZONE 'UTC', 'YYYY-MM-DD HH24:MI'), rrule FROM events WHERE user_id = $1 ORDER BY start, summary",
+        )
+        .bind(telegram_id)
+        .fetch_all(&pool)
+        .await
+        .unwrap()
+    };
+
+    // A scope other than the series needs to know which occurrence
+    let response = app
+        .clone()
+        .oneshot(update(
+            serde_json::json!({ "summary": "Sync", "edit_scope": "following" }),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let response = app
+        .clone()
+        .oneshot(update(serde_json::json!({
+            "summary": "Sync",
+            "edit_scope": "following",
+            "occurrence_start": "2026-06-03T10:00:00Z",
+        })))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    // This and following: the series ends before the third week
+    let response = app
+        .clone()
+        .oneshot(update(serde_json::json!({
+            "summary": "Sync",
+            "edit_scope": "following",
+            "occurrence_start": "2026-06-15T10:00:00Z",
+        })))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let tail: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    assert_ne!(tail["id"], series["id"]);
+    assert_ne!(tail["uid"], "standup");
+    assert_eq!(tail["start"], "2026-06-15T10:00:00Z");
+    assert_eq!(tail["rrule"], "FREQ=WEEKLY;COUNT=4");
+    assert_eq!(
+        rules().await,
+        vec![
+            (
+                "Standup".to_string(),
+                "2026-06-01 10:00".to_string(),
+                Some("FREQ=WEEKLY;UNTIL=20260615T095959Z".to_string())
+            ),
+            (
+                "Sync".to_string(),
+                "2026-06-15 10:00".to_string(),
+                Some("FREQ=WEEKLY;COUNT=4".to_string())
+            ),
+        ]
+    );
+
+    // This occurrence only: a week in the middle of the new series moves,
+    // and the weeks after it carry on unchanged
+    let tail_id = tail["id"].as_str().unwrap();
+    let response = app
+        .clone()
+        .oneshot(create_request(
+            "PUT",
+            format!("/api/events/{tail_id}"),
+            Body::from(
+                serde_json::json!({
+                    "timing": timing("2026-06-23T12:00:00Z", "2026-06-23T12:15:00Z"),
+                    "edit_scope": "occurrence",
+                    "occurrence_start": "2026-06-22T10:00:00Z",
+                })
+                .to_string(),
+            ),
+            Some(&init_data),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let moved: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    assert_eq!(moved["start"], "2026-06-23T12:00:00Z");
+    assert!(moved["rrule"].is_null());
+    assert_eq!(
+        rules().await,
+        vec![
+            (
+                "Standup".to_string(),
+                "2026-06-01 10:00".to_string(),
+                Some("FREQ=WEEKLY;UNTIL=20260615T095959Z".to_string())
+            ),
+            (
+                "Sync".to_string(),
+                "2026-06-15 10:00".to_string(),
+                Some("FREQ=WEEKLY;UNTIL=20260622T095959Z".to_string())
+            ),
+            ("Sync".to_string(), "2026-06-23 12:00".to_string(), None),
+            (
+                "Sync".to_string(),
+                "2026-06-29 10:00".to_string(),
+                Some("FREQ=WEEKLY;COUNT=2".to_string())
+            ),
+        ]
+    );
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_booking_link_flow(pool: PgPool) {
+    let telegram_id = setup_user(&pool).await;
+    let bot_token = "dummy_token";
+    let init_data = generate_valid_init_data(bot_token, telegram_id);
+    let app = create_router(app_state(&pool, bot_token), "*");
+
+    let body = serde_json::json!({
+        "title": "Intro call",
+        "duration_minutes": 30,
+        "working_hours": "00:00-23:30",
+        "within_days": 3,
+    });
+    let response = app
+        .clone()
+        .oneshot(create_request(
+            "POST",
+            "/api/booking-links",
+            Body::from(body.to_string()),
+            Some(&init_data),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let link: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    let slug = link["slug"].as_str().unwrap().to_string();
+    assert_eq!(link["path"], format!("/b/{slug}"));
+    assert_eq!(link["working_hours"], "00:00-23:30");
+
+    let bad = serde_json::json!({
+        "title": "Too long",
+        "duration_minutes": 600,
+        "working_hours": "00:00-23:30",
+        "within_days": 3,
+    });
+    let response = app
+        .clone()
+        .oneshot(create_request(
+            "POST",
+            "/api/booking-links",
+            Body::from(bad.to_string()),
+            Some(&init_data),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    // 1. Anyone can see the offered slots
+    let response = app
+        .clone()
+        .oneshot(create_request(
+            "GET",
+            format!("/b/{slug}"),
+            Body::empty(),
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let html = body_text(response).await;
+    assert!(html.contains("Intro call"));
+    let start = html
+        .split("name=\"start\" value=\"")
+        .nth(1)
+        .and_then(|rest| rest.split('"').next())
+        .unwrap()
+        .to_string();
+
+    let book = |start: &str| {
+        let form = url::form_urlencoded::Serializer::new(String::new())
+            .extend_pairs([
+                ("start", start),
+                ("name", "Visitor"),
+                ("email", "Visitor@Example.com"),
+            ])
+            .finish();
+        let mut request = create_request("POST", format!("/b/{slug}"), Body::from(form), None);
+        request.headers_mut().insert(
+            header::CONTENT_TYPE,
+            "application/x-www-form-urlencoded".parse().unwrap(),
+        );
+        request
+    };
+
+    // 2. A time the page never offered is refused
+    let response = app
+        .clone()
+        .oneshot(book("2020-01-01T09:00:00+00:00"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    // 3. Booking puts a tentative event with the visitor on the calendar
+    // and tells the owner
+    let response = app.clone().oneshot(book(&start)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(body_text(response).await.contains("Booking requested"));
+
+    let (summary, status): (String, String) =
+        sqlx::query_as("SELECT summary, status::text FROM events WHERE user_id = $1")
+            .bind(telegram_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(summary, "Intro call: Visitor");
+    assert_eq!(status, "TENTATIVE");
+    let guest: String = sqlx::query_scalar(
+        "SELECT a.email FROM event_attendees a JOIN events e ON e.id = a.event_id \
+         WHERE e.user_id = $1",
+    )
+    .bind(telegram_id)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(guest, "visitor@example.com");
+    let message: Value = sqlx::query_scalar(
+        "SELECT payload FROM outbox_messages WHERE kind = 'telegram_notification'",
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert!(
+        message["message"]
+            .as_str()
+            .unwrap()
+            .contains("Visitor booked")
+    );
+
+    // 4. The slot is gone for the next visitor
+    let response = app.clone().oneshot(book(&start)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    // 5. Deleting the link takes the page down
+    let link_id = link["id"].as_str().unwrap();
+    let response = app
+        .clone()
+        .oneshot(create_request(
+            "DELETE",
+            format!("/api/booking-links/{link_id}"),
+            Body::empty(),
+            Some(&init_data),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let response = app
+        .oneshot(create_request(
+            "GET",
+            format!("/b/{slug}"),
+            Body::empty(),
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_delegated_writes_are_attributed(pool: PgPool) {
+    let owner = setup_user(&pool).await;
+    let delegate = setup_user(&pool).await;
+    let bot_token = "dummy_token";
+    let owner_auth = generate_valid_init_data(bot_token, owner);
+    let delegate_auth = generate_valid_init_data(bot_token, delegate);
+    let app = create_router(app_state(&pool, bot_token), "*");
+    let send = |method: &str, uri: String, body: Value, auth: &str| {
+        app.clone().oneshot(create_request(
+            method,
+            uri,
+            Body::from(body.to_string()),
+            Some(auth),
+        ))
+    };
+    let event = serde_json::json!({
+        "uid": "delegated-uid",
+        "summary": "Dentist",
+        "timing": {
+            "kind": "timed",
+            "start": "2026-06-01T10:00:00Z",
+            "end": "2026-06-01T11:00:00Z",
+            "timezone": "UTC"
+        }
+    });
+
+    // Without a grant the owner's calendar is out of reach
+    let response = send(
+        "POST",
+        format!("/api/events?calendar={owner}"),
+        event.clone(),
+        &delegate_auth,
+    )
+    .await
+    .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let grant = serde_json::json!({ "username": format!("@test_user_{delegate}") });
+    let response = send("POST", "/api/delegates".to_string(), grant, &owner_auth)
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let granted: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    assert_eq!(granted["user_id"], delegate);
+
+    let response = send(
+        "GET",
+        "/api/delegates/calendars".to_string(),
+        Value::Null,
+        &delegate_auth,
+    )
+    .await
+    .unwrap();
+    let calendars: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    assert_eq!(calendars[0]["user_id"], owner);
+
+    // The delegate creates on the owner's calendar, the owner edits it
+    let response = send(
+        "POST",
+        format!("/api/events?calendar={owner}"),
+        event,
+        &delegate_auth,
+    )
+    .await
+    .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let created: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    let event_id = created["id"].as_str().unwrap().to_string();
+    assert_eq!(calendar_state(&pool, owner).await, (1, 1));
+
+    let update = serde_json::json!({ "summary": "Dentist, bring X-rays" });
+    let response = send(
+        "PUT",
+        format!("/api/events/{event_id}"),
+        update,
+        &owner_auth,
+    )
+    .await
+    .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Revoking cuts the delegate off; what they did stays theirs
+    let response = send(
+        "DELETE",
+        format!("/api/delegates/{delegate}"),
+        Value::Null,
+        &owner_auth,
+    )
+    .await
+    .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let response = send(
+        "DELETE",
+        format!("/api/events/{event_id}?calendar={owner}"),
+        Value::Null,
+        &delegate_auth,
+    )
+    .await
+    .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let response = send(
+        "DELETE",
+        format!("/api/events/{event_id}"),
+        Value::Null,
+        &owner_auth,
+    )
+    .await
+    .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let response = send(
+        "GET",
+        format!("/api/events/{event_id}/revisions"),
+        Value::Null,
+        &owner_auth,
+    )
+    .await
+    .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let revisions: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    let history: Vec<(i64, &str)> = revisions
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|revision| {
+            (
+                revision["actor_id"].as_i64().unwrap(),
+                revision["action"].as_str().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        history,
+        vec![
+            (delegate, "created"),
+            (owner, "updated"),
+            (owner, "deleted")
+        ]
+    );
+    assert_eq!(
+        revisions[0]["actor_username"],
+        format!("test_user_{delegate}")
+    );
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_resource_bookings_never_overlap(pool: PgPool) {
+    let admin = setup_user(&pool).await;
+    let other = setup_user(&pool).await;
+    let bot_token = "dummy_token";
+    let admin_auth = generate_valid_init_data(bot_token, admin);
+    let other_auth = generate_valid_init_data(bot_token, other);
+    let app = create_router(app_state(&pool, bot_token), "*");
+    let send = |method: &str, uri: String, body: Value, auth: &str| {
+        app.clone().oneshot(create_request(
+            method,
+            uri,
+            Body::from(body.to_string()),
+            Some(auth),
+        ))
+    };
+    let timed = |uid: &str, start: &str, end: &str| {
+        serde_json::json!({
+            "uid": uid,
+            "summary": "Standup",
+            "timing": { "kind": "timed", "start": start, "end": end, "timezone": "UTC" }
+        })
+    };
+
+    // Only admins add resources
+    let room = serde_json::json!({ "name": " Room 4 " });
+    let response = send(
+        "POST",
+        "/api/admin/resources".to_string(),
+        room.clone(),
+        &admin_auth,
+    )
+    .await
+    .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    televent_application::CalendarService::new(
+        televent_storage::calendar::CalendarRepository::new(pool.clone()),
+    )
+    .bootstrap_admins(&[admin])
+    .await
+    .unwrap();
+    let response = send(
+        "POST",
+        "/api/admin/resources".to_string(),
+        room.clone(),
+        &admin_auth,
+    )
+    .await
+    .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let resource: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    assert_eq!(resource["name"], "Room 4");
+    let resource_id = resource["id"].as_str().unwrap().to_string();
+    let response = send(
+        "POST",
+        "/api/admin/resources".to_string(),
+        serde_json::json!({ "name": "room 4" }),
+        &admin_auth,
+    )
+    .await
+    .unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    let response = send(
+        "GET",
+        "/api/resources".to_string(),
+        Value::Null,
+        &other_auth,
+    )
+    .await
+    .unwrap();
+    let resources: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    assert_eq!(resources[0]["id"], resource_id.as_str());
+
+    let mut event_ids = Vec::new();
+    for (auth, uid, start, end) in [
+        (
+            &admin_auth,
+            "room-a",
+            "2026-06-01T10:00:00Z",
+            "2026-06-01T11:00:00Z",
+        ),
+        (
+            &other_auth,
+            "room-b",
+            "2026-06-01T10:30:00Z",
+            "2026-06-01T11:30:00Z",
+        ),
+    ] {
+        let response = send(
+            "POST",
+            "/api/events".to_string(),
+            timed(uid, start, end),
+            auth,
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let created: Value = serde_json::from_str(&body_text(response).await).unwrap();
+        event_ids.push(created["id"].as_str().unwrap().to_string());
+    }
+    let book = serde_json::json!({ "resource_id": resource_id });
+
+    let response = send(
+        "PUT",
+        format!("/api/events/{}/resource", event_ids[0]),
+        book.clone(),
+        &admin_auth,
+    )
+    .await
+    .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // The second event overlaps the first, so the room is taken
+    let response = send(
+        "PUT",
+        format!("/api/events/{}/resource", event_ids[1]),
+        book.clone(),
+        &other_auth,
+    )
+    .await
+    .unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    // Back to back is fine
+    let response = send(
+        "PUT",
+        format!("/api/events/{}", event_ids[1]),
+        serde_json::json!({
+            "timing": {
+                "kind": "timed",
+                "start": "2026-06-01T11:00:00Z",
+                "end": "2026-06-01T12:00:00Z",
+                "timezone": "UTC"
+            }
+        }),
+        &other_auth,
+    )
+    .await
+    .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = send(
+        "PUT",
+        format!("/api/events/{}/resource", event_ids[1]),
+        book,
+        &other_auth,
+    )
+    .await
+    .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Moving a booked event onto another booking is refused and changes nothing
+    let response = send(
+        "PUT",
+        format!("/api/events/{}", event_ids[0]),
+        serde_json::json!({
+            "timing": {
+                "kind": "timed",
+                "start": "2026-06-01T10:30:00Z",
+                "end": "2026-06-01T11:30:00Z",
+                "timezone": "UTC"
+            }
+        }),
+        &admin_auth,
+    )
+    .await
+    .unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    let response = send(
+        "GET",
+        format!(
+            "/api/resources/{resource_id}/freebusy?start=2026-06-01T00:00:00Z&end=2026-06-02T00:00:00Z"
+        ),
+        Value::Null,
+        &other_auth,
+    )
+    .await
+    .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let busy: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    let busy: Vec<(&str, &str)> = busy
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|interval| {
+            (
+                interval["start"].as_str().unwrap(),
+                interval["end"].as_str().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        busy,
+        vec![
+            ("2026-06-01T10:00:00Z", "2026-06-01T11:00:00Z"),
+            ("2026-06-01T11:00:00Z", "2026-06-01T12:00:00Z"),
+        ]
+    );
+
+    // Cancelling lets the room go
+    let response = send(
+        "PUT",
+        format!("/api/events/{}", event_ids[0]),
+        serde_json::json!({ "status": "Cancelled" }),
+        &admin_auth,
+    )
+    .await
+    .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = send(
+        "GET",
+        format!("/api/events/{}/resource", event_ids[0]),
+        Value::Null,
+        &admin_auth,
+    )
+    .await
+    .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let response = send(
+        "DELETE",
+        format!("/api/events/{}/resource", event_ids[1]),
+        Value::Null,
+        &other_auth,
+    )
+    .await
+    .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_holiday_calendars_are_enabled_per_country(pool: PgPool) {
+    let user = setup_user(&pool).await;
+    let bot_token = "dummy_token";
+    let auth = generate_valid_init_data(bot_token, user);
+    let app = create_router(app_state(&pool, bot_token), "*");
+    let send = |method: &str, uri: &str| {
+        app.clone()
+            .oneshot(create_request(method, uri, Body::empty(), Some(&auth)))
+    };
+    let subscription_of = |calendars: &Value, country: &str| {
+        calendars
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|calendar| calendar["country"] == country)
+            .map(|calendar| calendar["subscription_id"].clone())
+    };
+
+    let response = send("GET", "/api/holidays").await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let calendars: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    assert_eq!(subscription_of(&calendars, "FR"), Some(Value::Null));
+
+    let response = send("PUT", "/api/holidays/fr").await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let enabled: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    assert_eq!(enabled["country"], "FR");
+    let subscription_id = enabled["subscription_id"].clone();
+    assert!(subscription_id.is_string());
+
+    // Enabling twice keeps the one subscription
+    let response = send("PUT", "/api/holidays/FR").await.unwrap();
+    let again: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    assert_eq!(again["subscription_id"], subscription_id);
+    let response = send("PUT", "/api/holidays/ZZ").await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let response = send("GET", "/api/holidays").await.unwrap();
+    let calendars: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    assert_eq!(subscription_of(&calendars, "FR"), Some(subscription_id));
+
+    let response = send("DELETE", "/api/holidays/fr").await.unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let response = send("DELETE", "/api/holidays/fr").await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_birthdays_calendar_toggle(pool: PgPool) {
+    let user = setup_user(&pool).await;
+    let bot_token = "dummy_token";
+    let auth = generate_valid_init_data(bot_token, user);
+    let app = create_router(app_state(&pool, bot_token), "*");
+    let send = |method: &str| {
+        app.clone().oneshot(create_request(
+            method,
+            "/api/birthdays",
+            Body::empty(),
+            Some(&auth),
+        ))
+    };
+
+    let response = send("GET").await.unwrap();
+    let calendar: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    assert_eq!(calendar["enabled"], false);
+
+    let response = send("PUT").await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let enabled: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    assert_eq!(enabled["enabled"], true);
+
+    let response = send("GET").await.unwrap();
+    let calendar: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    assert_eq!(calendar["subscription_id"], enabled["subscription_id"]);
+
+    let response = send("DELETE").await.unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let response = send("DELETE").await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_events_are_filtered_by_hashtag(pool: PgPool) {
+    let telegram_id = setup_user(&pool).await;
+    let bot_token = "dummy_token";
+    let init_data = generate_valid_init_data(bot_token, telegram_id);
+    let app = create_router(app_state(&pool, bot_token), "*");
+    let send = |method: &str, uri: String, body: Body| {
+        app.clone()
+            .oneshot(create_request(method, uri, body, Some(&init_data)))
+    };
+
+    let mut ids = Vec::new();
+    for (uid, summary) in [
+        ("review", "Sprint review #Work #team-a"),
+        ("dentist", "Dentist"),
+    ] {
+        let body = serde_json::json!({
+            "uid": uid,
+            "summary": summary,
+            "timing": {
+                "kind": "timed",
+                "start": "2026-06-03T09:00:00Z",
+                "end": "2026-06-03T10:00:00Z",
+                "timezone": "UTC"
+            }
+        });
+        let response = send(
+            "POST",
+            "/api/events".to_string(),
+            Body::from(body.to_string()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let created: Value = serde_json::from_str(&body_text(response).await).unwrap();
+        ids.push(created["id"].as_str().unwrap().to_string());
+    }
+
+    let response = send("GET", "/api/events?tag=work".to_string(), Body::empty())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let events: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    assert_eq!(events.as_array().unwrap().len(), 1);
+    assert_eq!(events[0]["uid"], "review");
+    assert_eq!(events[0]["tags"], serde_json::json!(["work", "team-a"]));
+
+    // Renaming the event re-derives its tags
+    let response = send(
+        "PUT",
+        format!("/api/events/{}", ids[1]),
+        Body::from(r#"{"summary": "Dentist #Health"}"#),
+    )
+    .await
+    .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = send(
+        "GET",
+        "/api/events?tag=%23health".to_string(),
+        Body::empty(),
+    )
+    .await
+    .unwrap();
+    let events: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    assert_eq!(events.as_array().unwrap().len(), 1);
+    assert_eq!(events[0]["uid"], "dentist");
+
+    let response = send(
+        "GET",
+        "/api/events?tag=two%20words".to_string(),
+        Body::empty(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_reminder_note_is_exported_as_alarm(pool: PgPool) {
+    use argon2::{Argon2, PasswordHasher, password_hash::SaltString};
+    use base64::{Engine, engine::general_purpose::STANDARD};
+
+    let telegram_id = setup_user(&pool).await;
+    let bot_token = "dummy_token";
+    let init_data = generate_valid_init_data(bot_token, telegram_id);
+    let app = create_router(app_state(&pool, bot_token), "*");
+    let send = |method: &str, uri: String, body: Body| {
+        app.clone()
+            .oneshot(create_request(method, uri, body, Some(&init_data)))
+    };
+
+    let body = serde_json::json!({
+        "uid": "workshop-uid",
+        "summary": "Workshop",
+        "timing": {
+            "kind": "timed",
+            "start": "2026-06-03T09:00:00Z",
+            "end": "2026-06-03T10:00:00Z",
+            "timezone": "UTC"
+        },
+        "reminder_note": "  Bring laptop!  "
+    });
+    let response = send(
+        "POST",
+        "/api/events".to_string(),
+        Body::from(body.to_string()),
+    )
+    .await
+    .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let event: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    assert_eq!(event["reminder_note"], "Bring laptop!");
+    let event_id = event["id"].as_str().unwrap().to_string();
+
+    let salt = SaltString::generate(&mut argon2::password_hash::rand_core::OsRng);
+    let password_hash = Argon2::default()
+        .hash_password(b"device-password", &salt)
+        .unwrap()
+        .to_string();
+    sqlx::query(
+        "INSERT INTO device_passwords (id, user_id, password_hash, device_name) VALUES ($1, $2, $3, 'phone')",
+    )
+    .bind(Uuid::new_v4())
+    .bind(telegram_id)
+    .bind(password_hash)
+    .execute(&pool)
+    .await
+    .unwrap();
+    let caldav_get = || {
+        let mut request = create_request(
+            "GET",
+            format!("/caldav/{telegram_id}/workshop-uid.ics"),
+            Body::empty(),
+            None,
+        );
+        request.headers_mut().insert(
+            header::AUTHORIZATION,
+            format!(
+                "Basic {}",
+                STANDARD.encode(format!("{telegram_id}:device-password"))
+            )
+            .parse()
+            .unwrap(),
+        );
+        app.clone().oneshot(request)
+    };
+
+    let response = caldav_get().await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let etag = response.headers()[header::ETAG].clone();
+    let ical = body_text(response).await;
+    assert!(ical.contains("BEGIN:VALARM"), "{ical}");
+    assert!(
+        ical.contains("DESCRIPTION:Workshop: Bring laptop!"),
+        "{ical}"
+    );
+
+    // Clearing the note drops the alarm and changes the ETag
+    let response = send(
+        "PUT",
+        format!("/api/events/{event_id}"),
+        Body::from(r#"{"reminder_note": null}"#),
+    )
+    .await
+    .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let event: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    assert!(event["reminder_note"].is_null());
+
+    let response = caldav_get().await.unwrap();
+    assert_ne!(response.headers()[header::ETAG], etag);
+    let ical = body_text(response).await;
+    assert!(!ical.contains("VALARM"), "{ical}");
+
+    let response = send(
+        "PUT",
+        format!("/api/events/{event_id}"),
+        Body::from(serde_json::json!({ "reminder_note": "x".repeat(501) }).to_string()),
+    )
+    .await
+    .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_tasks_are_planned_into_free_time(pool: PgPool) {
+    let telegram_id = setup_user(&pool).await;
+    let bot_token = "dummy_token";
+    let init_data = generate_valid_init_data(bot_token, telegram_id);
+    let app = create_router(app_state(&pool, bot_token), "*");
+    let send = |method: &str, uri: String, body: Body| {
+        app.clone()
+            .oneshot(create_request(method, uri, body, Some(&init_data)))
+    };
+    let tomorrow = chrono::Utc::now().date_naive() + chrono::Duration::days(1);
+    let at = |hour: u32| tomorrow.and_hms_opt(hour, 0, 0).unwrap().and_utc();
+
+    let body = serde_json::json!({
+        "uid": "standup",
+        "summary": "Standup",
+        "timing": {"kind": "timed", "start": at(9), "end": at(10), "timezone": "UTC"}
+    });
+    let response = send(
+        "POST",
+        "/api/events".to_string(),
+        Body::from(body.to_string()),
+    )
+    .await
+    .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    let mut task_ids = Vec::new();
+    for (title, duration) in [("Write report", "1h"), ("Inbox zero", "30m")] {
+        let body = serde_json::json!({"title": title, "duration": duration});
+        let response = send(
+            "POST",
+            "/api/tasks".to_string(),
+            Body::from(body.to_string()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let task: Value = serde_json::from_str(&body_text(response).await).unwrap();
+        assert_eq!(task["block"], Value::Null);
+        task_ids.push(task["id"].as_str().unwrap().to_string());
+    }
+    let response = send(
+        "POST",
+        "/api/tasks".to_string(),
+        Body::from(r#"{"title": "Forever", "duration": "12h"}"#),
+    )
+    .await
+    .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    // Blocks go after the standup, one after the other, within working hours
+    let body = serde_json::json!({"date": tomorrow, "working_hours": "09:00-17:00"});
+    let response = send(
+        "POST",
+        "/api/tasks/plan".to_string(),
+        Body::from(body.to_string()),
+    )
+    .await
+    .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let plan: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    let blocks = plan["blocks"].as_array().unwrap();
+    assert_eq!(blocks.len(), 2);
+    assert_eq!(blocks[0]["title"], "Write report");
+    assert_eq!(
+        blocks[0]["start"]
+            .as_str()
+            .unwrap()
+            .parse::<chrono::DateTime<chrono::Utc>>()
+            .unwrap(),
+        at(10)
+    );
+    assert_eq!(
+        blocks[1]["start"]
+            .as_str()
+            .unwrap()
+            .parse::<chrono::DateTime<chrono::Utc>>()
+            .unwrap(),
+        at(11)
+    );
+    assert!(plan["unplanned"].as_array().unwrap().is_empty());
+
+    // The block is a tentative event on the calendar
+    let event_id = blocks[0]["event_id"].as_str().unwrap().to_string();
+    let response = send("GET", format!("/api/events/{event_id}"), Body::empty())
+        .await
+        .unwrap();
+    let event: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    assert_eq!(event["summary"], "Write report");
+    assert_eq!(event["status"], "Tentative");
+
+    let response = send(
+        "POST",
+        format!("/api/tasks/{}/confirm", task_ids[0]),
+        Body::empty(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let task: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    assert_eq!(task["block"]["confirmed"], true);
+    let reminders: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM outbox_messages WHERE kind = 'task_block_reminder'",
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(reminders, 2);
+
+    // Planned tasks are not planned again
+    let response = send(
+        "POST",
+        "/api/tasks/plan".to_string(),
+        Body::from(body.to_string()),
+    )
+    .await
+    .unwrap();
+    let plan: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    assert!(plan["blocks"].as_array().unwrap().is_empty());
+
+    let response = send(
+        "POST",
+        format!("/api/tasks/{}/complete", task_ids[1]),
+        Body::empty(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let response = send(
+        "DELETE",
+        format!("/api/tasks/{}", task_ids[0]),
+        Body::empty(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let response = send("GET", "/api/tasks".to_string(), Body::empty())
+        .await
+        .unwrap();
+    let tasks: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    assert!(tasks.as_array().unwrap().is_empty());
+
+    // Deleting a task leaves its block on the calendar
+    let response = send("GET", format!("/api/events/{event_id}"), Body::empty())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_focus_rules_reserve_and_move_blocks(pool: PgPool) {
+    let telegram_id = setup_user(&pool).await;
+    let bot_token = "dummy_token";
+    let init_data = generate_valid_init_data(bot_token, telegram_id);
+    let app = create_router(app_state(&pool, bot_token), "*");
+    let send = |method: &str, uri: String, body: Body| {
+        app.clone()
+            .oneshot(create_request(method, uri, body, Some(&init_data)))
+    };
+
+    let response = send(
+        "POST",
+        "/api/focus-rules".to_string(),
+        Body::from(r#"{"blocks_per_week": 9, "duration": "2h"}"#),
+    )
+    .await
+    .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let body = serde_json::json!({"title": "Deep work", "blocks_per_week": 1, "duration": "1h"});
+    let response = send(
+        "POST",
+        "/api/focus-rules".to_string(),
+        Body::from(body.to_string()),
+    )
+    .await
+    .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let rule: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    let rule_id = rule["id"].as_str().unwrap().to_string();
+    assert_eq!(rule["duration_minutes"], 60);
+    let blocks = rule["blocks"].as_array().unwrap();
+    // Late on Sunday there may be no room left this week
+    assert!(blocks.len() <= 1);
+
+    if let Some(block) = blocks.first() {
+        assert_eq!(block["status"], "Tentative");
+        let event_id = block["event_id"].as_str().unwrap().to_string();
+        let response = send("GET", format!("/api/events/{event_id}"), Body::empty())
+            .await
+            .unwrap();
+        let event: Value = serde_json::from_str(&body_text(response).await).unwrap();
+        assert_eq!(event["summary"], "Deep work");
+
+        // A meeting over the block moves it
+        let body = serde_json::json!({
+            "uid": "meeting",
+            "summary": "Meeting",
+            "timing": {
+                "kind": "timed",
+                "start": block["start"],
+                "end": block["end"],
+                "timezone": "UTC"
+            }
+        });
+        let response = send(
+            "POST",
+            "/api/events".to_string(),
+            Body::from(body.to_string()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let body =
+            serde_json::json!({"title": "Deep work", "blocks_per_week": 1, "duration": "1h"});
+        let response = send(
+            "PUT",
+            format!("/api/focus-rules/{rule_id}"),
+            Body::from(body.to_string()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let rule: Value = serde_json::from_str(&body_text(response).await).unwrap();
+        assert!(
+            rule["blocks"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .all(|moved| moved["event_id"] != block["event_id"])
+        );
+        let response = send("GET", format!("/api/events/{event_id}"), Body::empty())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    let response = send("GET", "/api/focus-rules".to_string(), Body::empty())
+        .await
+        .unwrap();
+    let rules: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    assert_eq!(rules.as_array().unwrap().len(), 1);
+
+    let response = send(
+        "DELETE",
+        format!("/api/focus-rules/{rule_id}"),
+        Body::empty(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let blocks: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM events WHERE summary = 'Deep work'")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(blocks, 0);
+    let response = send(
+        "DELETE",
+        format!("/api/focus-rules/{rule_id}"),
+        Body::empty(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_timezone_fix_moves_events_once(pool: PgPool) {
+    let telegram_id = setup_user(&pool).await;
+    let guest_id = setup_user(&pool).await;
+    let bot_token = "dummy_token";
+    let init_data = generate_valid_init_data(bot_token, telegram_id);
+    let app = create_router(app_state(&pool, bot_token), "*");
+    let send = |method: &str, uri: &str, body: Value| {
+        app.clone().oneshot(create_request(
+            method,
+            uri,
+            Body::from(body.to_string()),
+            Some(&init_data),
+        ))
+    };
+
+    // 10:00 London time in winter and in summer, one long past, and one
+    // saved in another zone
+    let mut ids = Vec::new();
+    for (uid, start, end, timezone) in [
+        (
+            "winter",
+            "2030-01-15T10:00:00Z",
+            "2030-01-15T11:00:00Z",
+            "Europe/London",
+        ),
+        (
+            "summer",
+            "2030-07-15T09:00:00Z",
+            "2030-07-15T10:00:00Z",
+            "Europe/London",
+        ),
+        (
+            "past",
+            "2020-01-15T10:00:00Z",
+            "2020-01-15T11:00:00Z",
+            "Europe/London",
+        ),
+        (
+            "tokyo",
+            "2030-01-15T01:00:00Z",
+            "2030-01-15T02:00:00Z",
+            "Asia/Tokyo",
+        ),
+    ] {
+        let body = serde_json::json!({
+            "uid": uid,
+            "summary": uid,
+            "timing": { "kind": "timed", "start": start, "end": end, "timezone": timezone },
+        });
+        let response = send("POST", "/api/events", body).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let event: Value = serde_json::from_str(&body_text(response).await).unwrap();
+        ids.push(Uuid::parse_str(event["id"].as_str().unwrap()).unwrap());
+    }
+    // The guest is coming to the winter event and came to the past one
+    for event_id in [ids[0], ids[2]] {
+        sqlx::query(
+            "INSERT INTO event_attendees (event_id, email, user_id, role, status) VALUES ($1, $2, $3, 'ATTENDEE', 'ACCEPTED')",
+        )
+        .bind(event_id)
+        .bind(format!("tg_{guest_id}@televent.internal"))
+        .bind(guest_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+    }
+    let sync_token = || async {
+        sqlx::query_scalar::<_, i64>("SELECT sync_token FROM users WHERE telegram_id = $1")
+            .bind(telegram_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap()
+    };
+    let before = sync_token().await;
+
+    for body in [
+        serde_json::json!({ "from": "Europe/London", "to": "Europe/London" }),
+        serde_json::json!({ "from": "Mars/Base", "to": "Europe/Berlin" }),
+    ] {
+        let response = send("POST", "/api/events/timezone-fix", body)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    // A dry run counts without changing anything
+    let body = serde_json::json!({
+        "from": "Europe/London",
+        "to": "Europe/Berlin",
+        "start": "2030-01-01T00:00:00Z",
+        "dry_run": true,
+    });
+    let response = send("POST", "/api/events/timezone-fix", body)
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let preview: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    assert_eq!(preview["events"], 2);
+    assert_eq!(preview["first_start"], "2030-01-15T10:00:00Z");
+    assert_eq!(preview["last_start"], "2030-07-15T09:00:00Z");
+    assert_eq!(preview["applied"], false);
+    assert_eq!(sync_token().await, before);
+
+    let body = serde_json::json!({ "from": "Europe/London", "to": "Europe/Berlin" });
+    let response = send("POST", "/api/events/timezone-fix", body)
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let applied: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    assert_eq!(applied["events"], 3);
+    assert_eq!(applied["applied"], true);
+    assert_eq!(sync_token().await, before + 1);
+
+    let rows = sqlx::query_as::<_, (Uuid, chrono::DateTime<chrono::Utc>, String, i32)>(
+        "SELECT id, start, timezone, version FROM events WHERE user_id = $1",
+    )
+    .bind(telegram_id)
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    let row = |id: Uuid| rows.iter().find(|row| row.0 == id).unwrap();
+    // Still 10:00, now in Berlin
+    assert_eq!(row(ids[0]).1.to_rfc3339(), "2030-01-15T09:00:00+00:00");
+    assert_eq!(row(ids[1]).1.to_rfc3339(), "2030-07-15T08:00:00+00:00");
+    assert_eq!(row(ids[2]).1.to_rfc3339(), "2020-01-15T09:00:00+00:00");
+    for id in &ids[..3] {
+        assert_eq!(row(*id).2, "Europe/Berlin");
+        assert_eq!(row(*id).3, 2);
+    }
+    assert_eq!(row(ids[3]).2, "Asia/Tokyo");
+    assert_eq!(row(ids[3]).3, 1);
+
+    // Only the guest of the event still to come hears about it
+    let notices = sqlx::query_scalar::<_, Value>(
+        "SELECT payload FROM outbox_messages WHERE kind = 'event_updated'",
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(notices.len(), 1);
+    assert_eq!(notices[0]["target_user_id"], guest_id);
+    assert_eq!(
+        notices[0]["previous_timing"]["start"],
+        "2030-01-15T10:00:00Z"
+    );
+
+    // Nothing is left in London
+    let body =
+        serde_json::json!({ "from": "Europe/London", "to": "Europe/Berlin", "dry_run": true });
+    let response = send("POST", "/api/events/timezone-fix", body)
+        .await
+        .unwrap();
+    let preview: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    assert_eq!(preview["events"], 0);
+    assert_eq!(preview["first_start"], Value::Null);
+}
@@ -1,26 +1,48 @@
+use api::middleware::caldav_auth::AuthCache;
 use api::{AppState, create_router};
-use moka::future::Cache;
 use sqlx::{PgPool, Row};
 use std::time::{Duration, Instant};
 
-#[sqlx::test]
+#[sqlx::test(migrations = "../migrations")]
 #[ignore]
 async fn bench_sync_query(pool: PgPool) {
     // Setup state
-    let auth_cache = Cache::builder()
-        .time_to_live(Duration::from_secs(300))
-        .build();
+    let auth_cache = AuthCache::new(Duration::from_secs(300), 10_000);
 
     let state = AppState {
         calendar_service: televent_application::CalendarService::new(
             televent_storage::calendar::CalendarRepository::new(pool.clone()),
         ),
+        event_service: televent_application::EventService::new(
+            televent_storage::calendar::CalendarRepository::new(pool.clone()),
+        ),
         device_service: televent_application::DeviceService::new(
             televent_storage::device::DeviceRepository::new(pool.clone()),
         ),
         health_service: televent_application::HealthService::new(
             televent_storage::health::HealthRepository::new(pool.clone()),
         ),
+        subscription_service: televent_application::SubscriptionService::new(
+            televent_storage::subscription::SubscriptionRepository::new(pool.clone()),
+        ),
+        contact_service: televent_application::ContactService::new(
+            televent_storage::contact::ContactRepository::new(pool.clone()),
+        ),
+        email_service: televent_application::EmailService::new(
+            televent_storage::email::EmailRepository::new(pool.clone()),
+        ),
+        feature_flags: televent_application::FeatureFlagService::new(
+            televent_storage::flags::FeatureFlagRepository::new(pool.clone()),
+        ),
+        chat_webhook_service: televent_application::ChatWebhookService::new(
+            televent_storage::chat_webhook::ChatWebhookRepository::new(pool.clone()),
+        ),
+        notification_service: televent_application::NotificationService::new(
+            televent_storage::notification::NotificationRepository::new(pool.clone()),
+        ),
+        web_push_service: televent_application::WebPushService::new(
+            televent_storage::web_push::WebPushRepository::new(pool.clone()),
+        ),
         auth_cache,
         telegram_bot_token: "test_token".to_string(),
     };
@@ -64,52 +86,40 @@ async fn bench_sync_query(pool: PgPool) {
     tx.commit().await.expect("Failed to commit transaction");
     println!("Inserted 1000 events.");
 
-    // 1. Analyze Query Plan
-    println!("--- EXPLAIN ANALYZE ---");
-    // This query MUST match api/src/db/events.rs:list_events_since_sync.
-    let query_str = r#"
-        EXPLAIN ANALYZE SELECT * FROM events
-        WHERE user_id = $1
-        AND sync_version > $2
-        ORDER BY sync_version ASC
-    "#;
-
-    // Use query_as not needed for EXPLAIN
-    let rows = sqlx::query(query_str)
-        .bind(user_id)
-        .bind(0) // sync_token = 0
-        .fetch_all(&pool)
-        .await
-        .expect("Failed to run EXPLAIN ANALYZE");
+    // Same path as a sync-collection REPORT: one consolidated query
+    let calendar = televent_application::CalendarService::new(
+        televent_storage::calendar::CalendarRepository::new(pool.clone()),
+    );
+    for (label, token) in [("initial", None), ("delta", Some("500"))] {
+        let start = Instant::now();
+        let (changes, queries) = televent_application::count_queries(
+            calendar.list_caldav_sync_changes(televent_application::UserId::new(user_id), token),
+        )
+        .await;
+        let changes = changes.expect("Failed to list sync changes");
 
-    println!("Query Plan (ORDER BY sync_version):");
-    for row in rows {
-        let line: String = row.get(0);
-        println!("{}", line);
+        println!(
+            "{} sync: {:?}, {} events, {} tombstones, {} queries",
+            label,
+            start.elapsed(),
+            changes.events.len(),
+            changes.tombstones.len(),
+            queries
+        );
+        assert_eq!(queries, 1);
     }
-    println!("-----------------------");
 
-    // 2. Benchmark DB Query execution
-    println!("Benchmarking DB Query execution...");
-    let start = Instant::now();
-
-    // Replicating list_events_since_sync logic
-    // We fetch into generic Row or discard result to measure time
-    let _events = sqlx::query(
-        r#"
-        SELECT * FROM events
-        WHERE user_id = $1
-        AND sync_version > $2
-        ORDER BY sync_version ASC
-        "#,
+    let plan = sqlx::query(
+        "EXPLAIN ANALYZE SELECT * FROM events WHERE user_id = $1 AND sync_version > $2",
     )
     .bind(user_id)
-    .bind(0)
+    .bind(500_i64)
     .fetch_all(&pool)
     .await
-    .expect("Failed to fetch events");
-
-    let duration = start.elapsed();
-    println!("DB Query Duration: {:?}", duration);
-    println!("Fetched {} events", _events.len());
+    .expect("Failed to run EXPLAIN ANALYZE");
+    println!("--- Delta scan plan ---");
+    for row in plan {
+        let line: String = row.get(0);
+        println!("{}", line);
+    }
 }
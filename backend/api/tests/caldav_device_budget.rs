@@ -0,0 +1,192 @@
+use api::middleware::caldav_auth::AuthCache;
+use api::middleware::device_budget::DeviceBudget;
+use api::{AppState, config::Config, create_router_with_config};
+use argon2::{Argon2, PasswordHasher, password_hash::SaltString};
+use axum::Router;
+use axum::body::Body;
+use axum::http::{
+    HeaderValue, Request, StatusCode,
+    header::{CONTENT_LENGTH, RETRY_AFTER},
+};
+use base64::{Engine, engine::general_purpose::STANDARD};
+use sqlx::PgPool;
+use std::time::Duration;
+use tower::ServiceExt;
+
+const TELEGRAM_ID: i64 = 3001;
+
+async fn add_device(pool: &PgPool, name: &str, password: &str) {
+    let salt = SaltString::generate(&mut argon2::password_hash::rand_core::OsRng);
+    let password_hash = Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+
+    sqlx::query(
+        "INSERT INTO device_passwords (id, user_id, password_hash, device_name) VALUES ($1, $2, $3, $4)",
+    )
+    .bind(uuid::Uuid::new_v4())
+    .bind(TELEGRAM_ID)
+    .bind(password_hash)
+    .bind(name)
+    .execute(pool)
+    .await
+    .unwrap();
+}
+
+async fn setup(pool: PgPool, device_budget: DeviceBudget) -> Router {
+    sqlx::query("INSERT INTO users (telegram_id, telegram_username, timezone, sync_token, ctag) VALUES ($1, 'budget_user', 'UTC', 0, 0)")
+        .bind(TELEGRAM_ID)
+        .execute(&pool)
+        .await
+        .unwrap();
+    add_device(&pool, "phone", "phone-password").await;
+    add_device(&pool, "laptop", "laptop-password").await;
+
+    let state = AppState {
+        calendar_service: televent_application::CalendarService::new(
+            televent_storage::calendar::CalendarRepository::new(pool.clone()),
+        ),
+        event_service: televent_application::EventService::new(
+            televent_storage::calendar::CalendarRepository::new(pool.clone()),
+        ),
+        device_service: televent_application::DeviceService::new(
+            televent_storage::device::DeviceRepository::new(pool.clone()),
+        ),
+        health_service: televent_application::HealthService::new(
+            televent_storage::health::HealthRepository::new(pool.clone()),
+        ),
+        subscription_service: televent_application::SubscriptionService::new(
+            televent_storage::subscription::SubscriptionRepository::new(pool.clone()),
+        ),
+        contact_service: televent_application::ContactService::new(
+            televent_storage::contact::ContactRepository::new(pool.clone()),
+        ),
+        email_service: televent_application::EmailService::new(
+            televent_storage::email::EmailRepository::new(pool.clone()),
+        ),
+        feature_flags: televent_application::FeatureFlagService::new(
+            televent_storage::flags::FeatureFlagRepository::new(pool.clone()),
+        ),
+        chat_webhook_service: televent_application::ChatWebhookService::new(
+            televent_storage::chat_webhook::ChatWebhookRepository::new(pool.clone()),
+        ),
+        notification_service: televent_application::NotificationService::new(
+            televent_storage::notification::NotificationRepository::new(pool.clone()),
+        ),
+        web_push_service: televent_application::WebPushService::new(
+            televent_storage::web_push::WebPushRepository::new(pool.clone()),
+        ),
+        auth_cache: AuthCache::new(Duration::from_secs(300), 10_000),
+        telegram_bot_token: "test_token".to_string(),
+    };
+
+    create_router_with_config(
+        state,
+        &Config {
+            host: "0.0.0.0".to_string(),
+            port: 3000,
+            cors: api::middleware::cors::CorsPolicy::any(),
+            frontend_static_dir: None,
+            enable_swagger: false,
+            email_signups: false,
+            email_webhook_secret: None,
+            sms_notifications: false,
+            web_push_public_key: None,
+            device_budget,
+            trigger_budget: Default::default(),
+            base_path: Default::default(),
+            frontend_assets: Default::default(),
+        },
+    )
+}
+
+fn dav_request(method: &str, password: &str, body: Body) -> Request<Body> {
+    let credentials = STANDARD.encode(format!("{TELEGRAM_ID}:{password}"));
+    Request::builder()
+        .method(method)
+        .uri(format!("/caldav/{TELEGRAM_ID}/budget-event.ics"))
+        .header("Authorization", format!("Basic {credentials}"))
+        .header("Content-Type", "text/calendar")
+        .extension(axum::extract::ConnectInfo(std::net::SocketAddr::from((
+            [127, 0, 0, 1],
+            8080,
+        ))))
+        .body(body)
+        .unwrap()
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_device_rate_budget_is_per_device(pool: PgPool) {
+    let app = setup(
+        pool,
+        DeviceBudget {
+            requests_per_minute: 2,
+            max_body_bytes: 0,
+        },
+    )
+    .await;
+
+    for _ in 0..2 {
+        let response = app
+            .clone()
+            .oneshot(dav_request("GET", "phone-password", Body::empty()))
+            .await
+            .unwrap();
+        assert_ne!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    let response = app
+        .clone()
+        .oneshot(dav_request("GET", "phone-password", Body::empty()))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert!(response.headers().contains_key(RETRY_AFTER));
+
+    // Same user and IP, different device: its own budget
+    let response = app
+        .oneshot(dav_request("GET", "laptop-password", Body::empty()))
+        .await
+        .unwrap();
+    assert_ne!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_device_body_budget(pool: PgPool) {
+    let app = setup(
+        pool,
+        DeviceBudget {
+            requests_per_minute: 0,
+            max_body_bytes: 64,
+        },
+    )
+    .await;
+
+    // Refused from the declared length alone
+    let mut request = dav_request("PUT", "phone-password", Body::from("X".repeat(65)));
+    request
+        .headers_mut()
+        .insert(CONTENT_LENGTH, HeaderValue::from(65));
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+    // Without Content-Length the body is still capped while buffering
+    let response = app
+        .clone()
+        .oneshot(dav_request(
+            "PUT",
+            "phone-password",
+            Body::from("X".repeat(65)),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+    // Small bodies reach the handler, which rejects this one as invalid iCalendar
+    let response = app
+        .oneshot(dav_request("PUT", "phone-password", Body::from("X")))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
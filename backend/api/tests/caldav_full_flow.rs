@@ -1,3 +1,4 @@
+use api::middleware::caldav_auth::AuthCache;
 use api::{AppState, create_router};
 use argon2::{
     Argon2,
@@ -9,7 +10,6 @@ use axum::{
     http::{Request, StatusCode, header},
 };
 use base64::{Engine, engine::general_purpose::STANDARD};
-use moka::future::Cache;
 use sqlx::PgPool;
 use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 use std::time::Duration;
@@ -103,20 +103,42 @@ async fn test_caldav_full_flow(pool: PgPool) {
 
     let (telegram_id, _username, auth_header) = setup_user_and_auth(&pool).await;
 
-    let auth_cache = Cache::builder()
-        .time_to_live(Duration::from_secs(300))
-        .build();
+    let auth_cache = AuthCache::new(Duration::from_secs(300), 10_000);
 
     let state = AppState {
         calendar_service: televent_application::CalendarService::new(
             televent_storage::calendar::CalendarRepository::new(pool.clone()),
         ),
+        event_service: televent_application::EventService::new(
+            televent_storage::calendar::CalendarRepository::new(pool.clone()),
+        ),
         device_service: televent_application::DeviceService::new(
             televent_storage::device::DeviceRepository::new(pool.clone()),
         ),
         health_service: televent_application::HealthService::new(
             televent_storage::health::HealthRepository::new(pool.clone()),
         ),
+        subscription_service: televent_application::SubscriptionService::new(
+            televent_storage::subscription::SubscriptionRepository::new(pool.clone()),
+        ),
+        contact_service: televent_application::ContactService::new(
+            televent_storage::contact::ContactRepository::new(pool.clone()),
+        ),
+        email_service: televent_application::EmailService::new(
+            televent_storage::email::EmailRepository::new(pool.clone()),
+        ),
+        feature_flags: televent_application::FeatureFlagService::new(
+            televent_storage::flags::FeatureFlagRepository::new(pool.clone()),
+        ),
+        chat_webhook_service: televent_application::ChatWebhookService::new(
+            televent_storage::chat_webhook::ChatWebhookRepository::new(pool.clone()),
+        ),
+        notification_service: televent_application::NotificationService::new(
+            televent_storage::notification::NotificationRepository::new(pool.clone()),
+        ),
+        web_push_service: televent_application::WebPushService::new(
+            televent_storage::web_push::WebPushRepository::new(pool.clone()),
+        ),
         auth_cache,
         telegram_bot_token: "dummy_token".to_string(),
     };
@@ -1,14 +1,54 @@
+use api::middleware::caldav_auth::AuthCache;
 use api::{AppState, create_router};
 use argon2::{Argon2, PasswordHasher, password_hash::SaltString};
 use axum::body::Body;
 use axum::http::{Request, StatusCode};
 use base64::{Engine, engine::general_purpose::STANDARD};
-use moka::future::Cache;
 use sqlx::{PgPool, Row};
 use std::time::Duration;
 use televent_domain::{UserId, internal_email_for_telegram_id};
 use tower::ServiceExt;
 
+fn app_state(pool: &PgPool) -> AppState {
+    AppState {
+        calendar_service: televent_application::CalendarService::new(
+            televent_storage::calendar::CalendarRepository::new(pool.clone()),
+        ),
+        event_service: televent_application::EventService::new(
+            televent_storage::calendar::CalendarRepository::new(pool.clone()),
+        ),
+        device_service: televent_application::DeviceService::new(
+            televent_storage::device::DeviceRepository::new(pool.clone()),
+        ),
+        health_service: televent_application::HealthService::new(
+            televent_storage::health::HealthRepository::new(pool.clone()),
+        ),
+        subscription_service: televent_application::SubscriptionService::new(
+            televent_storage::subscription::SubscriptionRepository::new(pool.clone()),
+        ),
+        contact_service: televent_application::ContactService::new(
+            televent_storage::contact::ContactRepository::new(pool.clone()),
+        ),
+        email_service: televent_application::EmailService::new(
+            televent_storage::email::EmailRepository::new(pool.clone()),
+        ),
+        feature_flags: televent_application::FeatureFlagService::new(
+            televent_storage::flags::FeatureFlagRepository::new(pool.clone()),
+        ),
+        chat_webhook_service: televent_application::ChatWebhookService::new(
+            televent_storage::chat_webhook::ChatWebhookRepository::new(pool.clone()),
+        ),
+        notification_service: televent_application::NotificationService::new(
+            televent_storage::notification::NotificationRepository::new(pool.clone()),
+        ),
+        web_push_service: televent_application::WebPushService::new(
+            televent_storage::web_push::WebPushRepository::new(pool.clone()),
+        ),
+        auth_cache: AuthCache::new(Duration::from_secs(300), 10_000),
+        telegram_bot_token: "test_token".to_string(),
+    }
+}
+
 #[sqlx::test(migrations = "../migrations")]
 async fn test_caldav_put_with_internal_attendee(pool: PgPool) {
     // 1. Setup Users
@@ -45,24 +85,7 @@ async fn test_caldav_put_with_internal_attendee(pool: PgPool) {
         .unwrap();
 
     // 3. Create Router
-    let auth_cache = Cache::builder()
-        .time_to_live(Duration::from_secs(300))
-        .build();
-
-    let state = AppState {
-        calendar_service: televent_application::CalendarService::new(
-            televent_storage::calendar::CalendarRepository::new(pool.clone()),
-        ),
-        device_service: televent_application::DeviceService::new(
-            televent_storage::device::DeviceRepository::new(pool.clone()),
-        ),
-        health_service: televent_application::HealthService::new(
-            televent_storage::health::HealthRepository::new(pool.clone()),
-        ),
-        auth_cache,
-        telegram_bot_token: "test_token".to_string(),
-    };
-    let app = create_router(state, "*");
+    let app = create_router(app_state(&pool), "*");
 
     // 4. Create PUT Request with Internal Attendee
     let internal_email = internal_email_for_telegram_id(user_b_id.inner());
@@ -177,22 +200,7 @@ async fn test_caldav_put_replaces_attendees(pool: PgPool) {
         .await
         .unwrap();
 
-    let state = AppState {
-        calendar_service: televent_application::CalendarService::new(
-            televent_storage::calendar::CalendarRepository::new(pool.clone()),
-        ),
-        device_service: televent_application::DeviceService::new(
-            televent_storage::device::DeviceRepository::new(pool.clone()),
-        ),
-        health_service: televent_application::HealthService::new(
-            televent_storage::health::HealthRepository::new(pool.clone()),
-        ),
-        auth_cache: Cache::builder()
-            .time_to_live(Duration::from_secs(300))
-            .build(),
-        telegram_bot_token: "test_token".to_string(),
-    };
-    let app = create_router(state, "*");
+    let app = create_router(app_state(&pool), "*");
     let credentials = format!("{}:{}", user_a_id.inner(), password);
     let encoded = STANDARD.encode(credentials.as_bytes());
 
@@ -286,34 +294,1106 @@ async fn test_caldav_put_replaces_attendees(pool: PgPool) {
 
 #[sqlx::test(migrations = "../migrations")]
 async fn test_health_check(pool: PgPool) {
-    let auth_cache = Cache::builder()
-        .time_to_live(Duration::from_secs(300))
-        .build();
+    let app = create_router(app_state(&pool), "*");
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/health")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let migrations = |app: axum::Router| async move {
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/health/migrations")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        (status, body)
+    };
+
+    let (status, body) = migrations(app.clone()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["status"], "current");
+    assert_eq!(body["current_version"], body["latest_version"]);
+
+    // Forget the newest migration as if this build shipped a new one
+    sqlx::query(
+        "DELETE FROM _sqlx_migrations WHERE version = (SELECT MAX(version) FROM _sqlx_migrations)",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    let (status, body) = migrations(app).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(body["status"], "pending");
+    assert_eq!(body["pending"].as_array().unwrap().len(), 1);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_caldav_put_identical_content_keeps_etag(pool: PgPool) {
+    let user_id = UserId::new(1201);
+
+    sqlx::query("INSERT INTO users (telegram_id, telegram_username, timezone, sync_token, ctag) VALUES ($1, 'etag_user', 'UTC', 0, 0)")
+        .bind(user_id.inner())
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let password = "password123";
+    let salt = SaltString::generate(&mut argon2::password_hash::rand_core::OsRng);
+    let password_hash = Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+
+    sqlx::query("INSERT INTO device_passwords (id, user_id, password_hash, device_name) VALUES ($1, $2, $3, 'test_device')")
+        .bind(uuid::Uuid::new_v4())
+        .bind(user_id.inner())
+        .bind(password_hash)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let app = create_router(app_state(&pool), "*");
+    let encoded = STANDARD.encode(format!("1201:{password}").as_bytes());
+
+    let put = |summary: &str| {
+        Request::builder()
+            .method("PUT")
+            .uri("/caldav/1201/etag-event.ics")
+            .header("Authorization", format!("Basic {encoded}"))
+            .header("Content-Type", "text/calendar")
+            .extension(axum::extract::ConnectInfo(std::net::SocketAddr::from((
+                [127, 0, 0, 1],
+                8080,
+            ))))
+            .body(Body::from(format!(
+                "BEGIN:VCALENDAR\r\n\
+                 VERSION:2.0\r\n\
+                 BEGIN:VEVENT\r\n\
+                 UID:etag-event\r\n\
+                 DTSTART:20240101T100000Z\r\n\
+                 DTEND:20240101T110000Z\r\n\
+                 SUMMARY:{summary}\r\n\
+                 END:VEVENT\r\n\
+                 END:VCALENDAR\r\n"
+            )))
+            .unwrap()
+    };
+    let etag_of = |response: &axum::response::Response| {
+        response.headers()["etag"].to_str().unwrap().to_string()
+    };
+    let sync_token = || async {
+        sqlx::query_scalar::<_, i64>("SELECT sync_token FROM users WHERE telegram_id = $1")
+            .bind(user_id.inner())
+            .fetch_one(&pool)
+            .await
+            .unwrap()
+    };
+
+    let created = app.clone().oneshot(put("Standup")).await.unwrap();
+    assert_eq!(created.status(), StatusCode::CREATED);
+    let first_etag = etag_of(&created);
+    let token_after_create = sync_token().await;
+
+    let repeated = app.clone().oneshot(put("Standup")).await.unwrap();
+    assert_eq!(repeated.status(), StatusCode::NO_CONTENT);
+    assert_eq!(etag_of(&repeated), first_etag);
+    assert_eq!(sync_token().await, token_after_create);
+
+    let changed = app.oneshot(put("Retro")).await.unwrap();
+    assert_eq!(changed.status(), StatusCode::NO_CONTENT);
+    assert_ne!(etag_of(&changed), first_etag);
+    assert!(sync_token().await > token_after_create);
+}
+
+/// Router plus one CalDAV device password per name, for the SEQUENCE tests
+async fn sequence_app(pool: &PgPool, user_id: UserId, devices: &[&str]) -> axum::Router {
+    sqlx::query("INSERT INTO users (telegram_id, telegram_username, timezone, sync_token, ctag) VALUES ($1, 'stale_user', 'UTC', 0, 0)")
+        .bind(user_id.inner())
+        .execute(pool)
+        .await
+        .unwrap();
+
+    for device in devices {
+        let salt = SaltString::generate(&mut argon2::password_hash::rand_core::OsRng);
+        let password_hash = Argon2::default()
+            .hash_password(device.as_bytes(), &salt)
+            .unwrap()
+            .to_string();
+        sqlx::query("INSERT INTO device_passwords (id, user_id, password_hash, device_name) VALUES ($1, $2, $3, $4)")
+            .bind(uuid::Uuid::new_v4())
+            .bind(user_id.inner())
+            .bind(password_hash)
+            .bind(device)
+            .execute(pool)
+            .await
+            .unwrap();
+    }
+
+    create_router(app_state(pool), "*")
+}
+
+/// A request for `stale-event.ics` from the device whose password is `device`
+fn sequence_request(device: &str, method: &str) -> axum::http::request::Builder {
+    let encoded = STANDARD.encode(format!("1202:{device}").as_bytes());
+    Request::builder()
+        .method(method)
+        .uri("/caldav/1202/stale-event.ics")
+        .header("Authorization", format!("Basic {encoded}"))
+        .extension(axum::extract::ConnectInfo(std::net::SocketAddr::from((
+            [127, 0, 0, 1],
+            8080,
+        ))))
+}
+
+fn sequence_put(
+    device: &str,
+    sequence: i32,
+    summary: &str,
+    if_match: Option<&str>,
+) -> Request<Body> {
+    let mut request = sequence_request(device, "PUT").header("Content-Type", "text/calendar");
+    if let Some(etag) = if_match {
+        request = request.header("If-Match", etag);
+    }
+    request
+        .body(Body::from(format!(
+            "BEGIN:VCALENDAR\r\n\
+             VERSION:2.0\r\n\
+             BEGIN:VEVENT\r\n\
+             UID:stale-event\r\n\
+             SEQUENCE:{sequence}\r\n\
+             DTSTART:20240101T100000Z\r\n\
+             DTEND:20240101T110000Z\r\n\
+             SUMMARY:{summary}\r\n\
+             END:VEVENT\r\n\
+             END:VCALENDAR\r\n"
+        )))
+        .unwrap()
+}
+
+async fn sequence_get(app: &axum::Router, device: &str) -> String {
+    let response = app
+        .clone()
+        .oneshot(sequence_request(device, "GET").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    String::from_utf8(body.to_vec()).unwrap()
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_caldav_put_with_stale_sequence_returns_server_copy(pool: PgPool) {
+    let app = sequence_app(&pool, UserId::new(1202), &["phone", "laptop"]).await;
+
+    let created = app
+        .clone()
+        .oneshot(sequence_put("phone", 0, "Standup", None))
+        .await
+        .unwrap();
+    assert_eq!(created.status(), StatusCode::CREATED);
+
+    // Both devices read the event
+    for device in ["phone", "laptop"] {
+        let copy = sequence_get(&app, device).await;
+        assert!(copy.contains("SUMMARY:Standup"));
+        assert!(copy.contains("SEQUENCE:0"));
+    }
+
+    // The phone saves an edit and bumps SEQUENCE
+    let edited = app
+        .clone()
+        .oneshot(sequence_put("phone", 1, "Retro", None))
+        .await
+        .unwrap();
+    assert_eq!(edited.status(), StatusCode::NO_CONTENT);
+    let current_etag = edited.headers()["etag"].to_str().unwrap().to_string();
+
+    // The laptop's edit of its older copy would undo it
+    let stale = app
+        .clone()
+        .oneshot(sequence_put("laptop", 0, "Planning", None))
+        .await
+        .unwrap();
+    assert_eq!(stale.status(), StatusCode::CONFLICT);
+    assert_eq!(stale.headers()["etag"].to_str().unwrap(), current_etag);
+    let body = axum::body::to_bytes(stale.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let body = String::from_utf8(body.to_vec()).unwrap();
+    assert!(body.contains("SUMMARY:Retro"));
+    assert!(body.contains("SEQUENCE:1"));
+
+    let conflict: (Option<String>, Option<i32>, Option<i32>, i16) = sqlx::query_as(
+        "SELECT conflict_uid, client_sequence, server_sequence, status
+         FROM device_activity ORDER BY created_at DESC LIMIT 1",
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(
+        conflict,
+        (Some("stale-event".to_string()), Some(0), Some(1), 409)
+    );
+
+    // After merging, the laptop names the copy it merged with
+    let merged = app
+        .clone()
+        .oneshot(sequence_put("laptop", 0, "Planning", Some(&current_etag)))
+        .await
+        .unwrap();
+    assert_eq!(merged.status(), StatusCode::NO_CONTENT);
+    let copy = sequence_get(&app, "phone").await;
+    assert!(copy.contains("SUMMARY:Planning"));
+    assert!(copy.contains("SEQUENCE:1"));
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_caldav_consecutive_edits_from_one_device_are_accepted(pool: PgPool) {
+    let app = sequence_app(&pool, UserId::new(1202), &["phone"]).await;
+
+    let created = app
+        .clone()
+        .oneshot(sequence_put("phone", 0, "Standup", None))
+        .await
+        .unwrap();
+    assert_eq!(created.status(), StatusCode::CREATED);
+
+    // Neither edit bumps SEQUENCE or sends If-Match
+    for summary in ["Retro", "Planning"] {
+        let edited = app
+            .clone()
+            .oneshot(sequence_put("phone", 0, summary, None))
+            .await
+            .unwrap();
+        assert_eq!(edited.status(), StatusCode::NO_CONTENT, "{summary}");
+    }
+
+    let copy = sequence_get(&app, "phone").await;
+    assert!(copy.contains("SUMMARY:Planning"));
+    assert!(copy.contains("SEQUENCE:0"));
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_caldav_put_truncates_oversized_description(pool: PgPool) {
+    let user_id = UserId::new(1251);
+
+    sqlx::query("INSERT INTO users (telegram_id, telegram_username, timezone, sync_token, ctag) VALUES ($1, 'long_user', 'UTC', 0, 0)")
+        .bind(user_id.inner())
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let password = "password123";
+    let salt = SaltString::generate(&mut argon2::password_hash::rand_core::OsRng);
+    let password_hash = Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+
+    sqlx::query("INSERT INTO device_passwords (id, user_id, password_hash, device_name) VALUES ($1, $2, $3, 'test_device')")
+        .bind(uuid::Uuid::new_v4())
+        .bind(user_id.inner())
+        .bind(password_hash)
+        .execute(&pool)
+        .await
+        .unwrap();
 
     let state = AppState {
-        calendar_service: televent_application::CalendarService::new(
+        event_service: televent_application::EventService::new(
             televent_storage::calendar::CalendarRepository::new(pool.clone()),
-        ),
-        device_service: televent_application::DeviceService::new(
-            televent_storage::device::DeviceRepository::new(pool.clone()),
-        ),
-        health_service: televent_application::HealthService::new(
-            televent_storage::health::HealthRepository::new(pool.clone()),
-        ),
-        auth_cache,
-        telegram_bot_token: "test_token".to_string(),
+        )
+        .with_text_limits(televent_application::EventTextLimits {
+            description: 64,
+            ..Default::default()
+        }),
+        ..app_state(&pool)
     };
     let app = create_router(state, "*");
+    let encoded = STANDARD.encode(format!("1251:{password}").as_bytes());
 
     let response = app
         .oneshot(
             Request::builder()
-                .uri("/health")
-                .body(Body::empty())
+                .method("PUT")
+                .uri("/caldav/1251/long-event.ics")
+                .header("Authorization", format!("Basic {encoded}"))
+                .header("Content-Type", "text/calendar")
+                .extension(axum::extract::ConnectInfo(std::net::SocketAddr::from((
+                    [127, 0, 0, 1],
+                    8080,
+                ))))
+                .body(Body::from(format!(
+                    "BEGIN:VCALENDAR\r\n\
+                     VERSION:2.0\r\n\
+                     BEGIN:VEVENT\r\n\
+                     UID:long-event\r\n\
+                     DTSTART:20240101T100000Z\r\n\
+                     DTEND:20240101T110000Z\r\n\
+                     SUMMARY:Pasted notes\r\n\
+                     DESCRIPTION:{}\r\n\
+                     END:VEVENT\r\n\
+                     END:VCALENDAR\r\n",
+                    "x".repeat(100)
+                )))
                 .unwrap(),
         )
         .await
         .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
 
-    assert_eq!(response.status(), StatusCode::OK);
+    let description: String = sqlx::query_scalar(
+        "SELECT description FROM events WHERE user_id = $1 AND uid = 'long-event'",
+    )
+    .bind(user_id.inner())
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(description, "x".repeat(64));
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_ctag_follows_event_changes_and_deletions(pool: PgPool) {
+    let user_id = UserId::new(1301);
+
+    sqlx::query("INSERT INTO users (telegram_id, telegram_username, timezone, sync_token, ctag) VALUES ($1, 'ctag_user', 'UTC', 0, 0)")
+        .bind(user_id.inner())
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let password = "password123";
+    let salt = SaltString::generate(&mut argon2::password_hash::rand_core::OsRng);
+    let password_hash = Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+
+    sqlx::query("INSERT INTO device_passwords (id, user_id, password_hash, device_name) VALUES ($1, $2, $3, 'test_device')")
+        .bind(uuid::Uuid::new_v4())
+        .bind(user_id.inner())
+        .bind(password_hash)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let app = create_router(app_state(&pool), "*");
+    let encoded = STANDARD.encode(format!("1301:{password}").as_bytes());
+
+    let request = |method: &str, body: Body| {
+        Request::builder()
+            .method(method)
+            .uri("/caldav/1301/ctag-event.ics")
+            .header("Authorization", format!("Basic {encoded}"))
+            .header("Content-Type", "text/calendar")
+            .extension(axum::extract::ConnectInfo(std::net::SocketAddr::from((
+                [127, 0, 0, 1],
+                8080,
+            ))))
+            .body(body)
+            .unwrap()
+    };
+    let calendar_state = || async {
+        sqlx::query_as::<_, (i64, i64)>("SELECT sync_token, ctag FROM users WHERE telegram_id = $1")
+            .bind(user_id.inner())
+            .fetch_one(&pool)
+            .await
+            .unwrap()
+    };
+
+    let created = app
+        .clone()
+        .oneshot(request(
+            "PUT",
+            Body::from(
+                "BEGIN:VCALENDAR\r\n\
+                 VERSION:2.0\r\n\
+                 BEGIN:VEVENT\r\n\
+                 UID:ctag-event\r\n\
+                 DTSTART:20240101T100000Z\r\n\
+                 DTEND:20240101T110000Z\r\n\
+                 SUMMARY:Planning\r\n\
+                 END:VEVENT\r\n\
+                 END:VCALENDAR\r\n",
+            ),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(created.status(), StatusCode::CREATED);
+    let (sync_token, ctag) = calendar_state().await;
+    assert!(ctag > 0);
+    assert_eq!(ctag, sync_token);
+
+    let deleted = app
+        .clone()
+        .oneshot(request("DELETE", Body::empty()))
+        .await
+        .unwrap();
+    assert_eq!(deleted.status(), StatusCode::NO_CONTENT);
+    let (sync_token_after_delete, ctag_after_delete) = calendar_state().await;
+    assert!(ctag_after_delete > ctag);
+    assert_eq!(ctag_after_delete, sync_token_after_delete);
+
+    let sync_report = |token: i64| {
+        Request::builder()
+            .method("REPORT")
+            .uri("/caldav/1301/")
+            .header("Authorization", format!("Basic {encoded}"))
+            .header("Content-Type", "application/xml")
+            .extension(axum::extract::ConnectInfo(std::net::SocketAddr::from((
+                [127, 0, 0, 1],
+                8080,
+            ))))
+            .body(Body::from(format!(
+                r#"<d:sync-collection xmlns:d="DAV:">
+  <d:sync-token>http://televent.app/sync/{token}</d:sync-token>
+  <d:sync-level>1</d:sync-level>
+  <d:prop><d:getetag/></d:prop>
+</d:sync-collection>"#
+            )))
+            .unwrap()
+    };
+
+    // A delta sync from before the delete reports the tombstone and the new token
+    let sync = app.clone().oneshot(sync_report(sync_token)).await.unwrap();
+    assert_eq!(sync.status(), StatusCode::MULTI_STATUS);
+    let body = String::from_utf8(
+        axum::body::to_bytes(sync.into_body(), usize::MAX)
+            .await
+            .unwrap()
+            .to_vec(),
+    )
+    .unwrap();
+    assert!(body.contains("/caldav/1301/ctag-event.ics"));
+    assert!(body.contains("404"));
+    assert!(body.contains(&format!("sync/{sync_token_after_delete}")));
+
+    // Once retention prunes the tombstone, that token can't be answered
+    // any more, while the current one still can
+    let pruned = televent_storage::retention::RetentionRepository::new(pool.clone())
+        .prune_event_tombstones(chrono::Utc::now() + chrono::Duration::hours(1), 100)
+        .await
+        .unwrap();
+    assert_eq!(pruned, 1);
+    let expired = app.clone().oneshot(sync_report(sync_token)).await.unwrap();
+    assert_eq!(expired.status(), StatusCode::FORBIDDEN);
+    let body = String::from_utf8(
+        axum::body::to_bytes(expired.into_body(), usize::MAX)
+            .await
+            .unwrap()
+            .to_vec(),
+    )
+    .unwrap();
+    assert!(body.contains("valid-sync-token"));
+    let current = app
+        .oneshot(sync_report(sync_token_after_delete))
+        .await
+        .unwrap();
+    assert_eq!(current.status(), StatusCode::MULTI_STATUS);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_subscribed_calendar_is_served_read_only(pool: PgPool) {
+    let user_id = UserId::new(1401);
+
+    sqlx::query("INSERT INTO users (telegram_id, telegram_username, timezone, sync_token, ctag) VALUES ($1, 'subscriber', 'UTC', 0, 0)")
+        .bind(user_id.inner())
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let password = "password123";
+    let salt = SaltString::generate(&mut argon2::password_hash::rand_core::OsRng);
+    let password_hash = Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+
+    sqlx::query("INSERT INTO device_passwords (id, user_id, password_hash, device_name) VALUES ($1, $2, $3, 'test_device')")
+        .bind(uuid::Uuid::new_v4())
+        .bind(user_id.inner())
+        .bind(password_hash)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let subscriptions = televent_application::SubscriptionService::new(
+        televent_storage::subscription::SubscriptionRepository::new(pool.clone()),
+    );
+    let subscription = subscriptions
+        .add_subscription(televent_application::AddSubscriptionCommand {
+            user_id,
+            username: None,
+            url: "webcal://calendar.example.com/team.ics".to_string(),
+            name: Some("Team".to_string()),
+        })
+        .await
+        .unwrap();
+    let applied = subscriptions
+        .apply_feed(
+            subscription.id,
+            televent_application::FetchedFeed {
+                body: "BEGIN:VCALENDAR\r\n\
+                       VERSION:2.0\r\n\
+                       BEGIN:VEVENT\r\n\
+                       UID:team-offsite\r\n\
+                       DTSTART:20991101T090000Z\r\n\
+                       DTEND:20991101T170000Z\r\n\
+                       SUMMARY:Team offsite\r\n\
+                       END:VEVENT\r\n\
+                       END:VCALENDAR\r\n"
+                    .to_string(),
+                http_etag: Some("\"v1\"".to_string()),
+                http_last_modified: None,
+            },
+        )
+        .await
+        .unwrap();
+    assert!(applied.changed);
+    assert_eq!(applied.imported, 1);
+
+    let state = AppState {
+        subscription_service: subscriptions,
+        ..app_state(&pool)
+    };
+    let app = create_router(state, "*");
+    let encoded = STANDARD.encode(format!("1401:{password}").as_bytes());
+    let collection = format!("/caldav/1401/subscriptions/{}/", subscription.id);
+
+    let request = |method: &str, uri: String, body: &'static str| {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("Authorization", format!("Basic {encoded}"))
+            .header("Depth", "1")
+            .extension(axum::extract::ConnectInfo(std::net::SocketAddr::from((
+                [127, 0, 0, 1],
+                8080,
+            ))))
+            .body(Body::from(body))
+            .unwrap()
+    };
+    let body_text = |response: axum::response::Response| async move {
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        String::from_utf8(bytes.to_vec()).unwrap()
+    };
+
+    let propfind = app
+        .clone()
+        .oneshot(request("PROPFIND", collection.clone(), ""))
+        .await
+        .unwrap();
+    assert_eq!(propfind.status(), StatusCode::MULTI_STATUS);
+    let xml = body_text(propfind).await;
+    assert!(xml.contains("<d:displayname>Team</d:displayname>"));
+    assert!(xml.contains(&format!("{collection}team-offsite.ics")));
+
+    let get = app
+        .clone()
+        .oneshot(request("GET", format!("{collection}team-offsite.ics"), ""))
+        .await
+        .unwrap();
+    assert_eq!(get.status(), StatusCode::OK);
+    assert!(body_text(get).await.contains("SUMMARY:Team offsite"));
+
+    let put = app
+        .clone()
+        .oneshot(request("PUT", format!("{collection}team-offsite.ics"), ""))
+        .await
+        .unwrap();
+    assert_eq!(put.status(), StatusCode::FORBIDDEN);
+
+    let initial_sync = app
+        .clone()
+        .oneshot(request(
+            "REPORT",
+            collection.clone(),
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<d:sync-collection xmlns:d="DAV:">
+  <d:sync-token/>
+  <d:prop><d:getetag/></d:prop>
+</d:sync-collection>"#,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(initial_sync.status(), StatusCode::MULTI_STATUS);
+    assert!(body_text(initial_sync).await.contains("team-offsite.ics"));
+
+    let stale_sync = app
+        .clone()
+        .oneshot(request(
+            "REPORT",
+            collection.clone(),
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<d:sync-collection xmlns:d="DAV:">
+  <d:sync-token>http://televent.app/sync/999</d:sync-token>
+  <d:prop><d:getetag/></d:prop>
+</d:sync-collection>"#,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(stale_sync.status(), StatusCode::FORBIDDEN);
+    assert!(body_text(stale_sync).await.contains("valid-sync-token"));
+
+    // The calendar home lists the subscription as a child calendar, but
+    // mirrored events never leak into the user's own calendar
+    let own = app
+        .clone()
+        .oneshot(request("PROPFIND", "/caldav/1401/".to_string(), ""))
+        .await
+        .unwrap();
+    let own_xml = body_text(own).await;
+    assert!(own_xml.contains(&format!("<d:href>{collection}</d:href>")));
+    assert!(own_xml.contains("<d:displayname>Team</d:displayname>"));
+    assert!(!own_xml.contains("team-offsite"));
+
+    // Own events whose names start with "subscriptions/" are not shadowed
+    let own_put = app
+        .clone()
+        .oneshot(request(
+            "PUT",
+            "/caldav/1401/subscriptions/notes.ics".to_string(),
+            "BEGIN:VCALENDAR\r\n\
+             VERSION:2.0\r\n\
+             BEGIN:VEVENT\r\n\
+             UID:subscriptions/notes\r\n\
+             DTSTART:20991102T090000Z\r\n\
+             DTEND:20991102T100000Z\r\n\
+             SUMMARY:Review subscriptions\r\n\
+             END:VEVENT\r\n\
+             END:VCALENDAR\r\n",
+        ))
+        .await
+        .unwrap();
+    assert_eq!(own_put.status(), StatusCode::CREATED);
+    let own_get = app
+        .oneshot(request(
+            "GET",
+            "/caldav/1401/subscriptions/notes.ics".to_string(),
+            "",
+        ))
+        .await
+        .unwrap();
+    assert_eq!(own_get.status(), StatusCode::OK);
+    assert!(
+        body_text(own_get)
+            .await
+            .contains("SUMMARY:Review subscriptions")
+    );
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_resource_calendar_shows_bookings_as_busy(pool: PgPool) {
+    let viewer = UserId::new(1402);
+    let booker = UserId::new(1403);
+
+    for (user_id, timezone) in [(viewer, "UTC"), (booker, "Europe/Berlin")] {
+        sqlx::query(
+            "INSERT INTO users (telegram_id, timezone, sync_token, ctag) VALUES ($1, $2, 0, 0)",
+        )
+        .bind(user_id.inner())
+        .bind(timezone)
+        .execute(&pool)
+        .await
+        .unwrap();
+    }
+
+    let password = "password123";
+    let salt = SaltString::generate(&mut argon2::password_hash::rand_core::OsRng);
+    let password_hash = Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+
+    sqlx::query("INSERT INTO device_passwords (id, user_id, password_hash, device_name) VALUES ($1, $2, $3, 'test_device')")
+        .bind(uuid::Uuid::new_v4())
+        .bind(viewer.inner())
+        .bind(password_hash)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let events = televent_application::EventService::new(
+        televent_storage::calendar::CalendarRepository::new(pool.clone()),
+    );
+    let room = events.create_resource("Board room").await.unwrap();
+    let event = events
+        .create_event_view(televent_application::CreateEventCommand {
+            user_id: booker,
+            username: None,
+            uid: "board-meeting".to_string(),
+            summary: "Board meeting".to_string(),
+            description: None,
+            location: None,
+            timing: televent_domain::EventTiming::AllDay {
+                start_date: chrono::NaiveDate::from_ymd_opt(2099, 11, 3).unwrap(),
+                end_date: chrono::NaiveDate::from_ymd_opt(2099, 11, 4).unwrap(),
+            },
+            status: televent_domain::EventStatus::Confirmed,
+            rrule: None,
+            visibility: None,
+            reminder_note: None,
+            allow_duplicate: true,
+        })
+        .await
+        .unwrap();
+    events
+        .book_resource(booker, booker, event.id, room.id)
+        .await
+        .unwrap();
+
+    let state = AppState {
+        event_service: events,
+        ..app_state(&pool)
+    };
+    let app = create_router(state, "*");
+    let encoded = STANDARD.encode(format!("1402:{password}").as_bytes());
+    let collection = format!("/caldav/1402/resources/{}/", room.id);
+    let request = |method: &str, uri: String, body: &'static str| {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("Authorization", format!("Basic {encoded}"))
+            .header("Depth", "1")
+            .extension(axum::extract::ConnectInfo(std::net::SocketAddr::from((
+                [127, 0, 0, 1],
+                8080,
+            ))))
+            .body(Body::from(body))
+            .unwrap()
+    };
+    let body_text = |response: axum::response::Response| async move {
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        String::from_utf8(bytes.to_vec()).unwrap()
+    };
+
+    let propfind = app
+        .clone()
+        .oneshot(request("PROPFIND", collection.clone(), ""))
+        .await
+        .unwrap();
+    assert_eq!(propfind.status(), StatusCode::MULTI_STATUS);
+    let xml = body_text(propfind).await;
+    assert!(xml.contains("<d:displayname>Board room</d:displayname>"));
+    let href = format!("{collection}{}.ics", event.id);
+    assert!(xml.contains(&href));
+
+    // Others see when the room is taken, in the booker's timezone, but not why
+    let get = app
+        .clone()
+        .oneshot(request("GET", href.clone(), ""))
+        .await
+        .unwrap();
+    assert_eq!(get.status(), StatusCode::OK);
+    let ical = body_text(get).await;
+    assert!(ical.contains("SUMMARY:Busy"));
+    assert!(ical.contains("DTSTART:20991102T230000Z"));
+    assert!(!ical.contains("Board meeting"));
+
+    let put = app.clone().oneshot(request("PUT", href, "")).await.unwrap();
+    assert_eq!(put.status(), StatusCode::FORBIDDEN);
+
+    let initial_sync = app
+        .clone()
+        .oneshot(request(
+            "REPORT",
+            collection.clone(),
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<d:sync-collection xmlns:d="DAV:">
+  <d:sync-token/>
+  <d:prop><d:getetag/></d:prop>
+</d:sync-collection>"#,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(initial_sync.status(), StatusCode::MULTI_STATUS);
+    assert!(
+        body_text(initial_sync)
+            .await
+            .contains(&event.id.to_string())
+    );
+
+    // An unknown resource is no collection, just an ordinary (missing) path
+    let unknown = app
+        .oneshot(request(
+            "PROPFIND",
+            format!("/caldav/1402/resources/{}/", uuid::Uuid::new_v4()),
+            "",
+        ))
+        .await
+        .unwrap();
+    assert_eq!(unknown.status(), StatusCode::BAD_REQUEST);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_hrefs_carry_external_prefix(pool: PgPool) {
+    use api::config::Config;
+    use api::create_router_with_config;
+    use api::middleware::base_path::BasePath;
+
+    let user_id = UserId::new(1401);
+
+    sqlx::query("INSERT INTO users (telegram_id, telegram_username, timezone, sync_token, ctag) VALUES ($1, 'prefix_user', 'UTC', 0, 0)")
+        .bind(user_id.inner())
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let password = "password123";
+    let salt = SaltString::generate(&mut argon2::password_hash::rand_core::OsRng);
+    let password_hash = Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+
+    sqlx::query("INSERT INTO device_passwords (id, user_id, password_hash, device_name) VALUES ($1, $2, $3, 'test_device')")
+        .bind(uuid::Uuid::new_v4())
+        .bind(user_id.inner())
+        .bind(password_hash)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let app = create_router_with_config(
+        app_state(&pool),
+        &Config {
+            host: "0.0.0.0".to_string(),
+            port: 3000,
+            cors: api::middleware::cors::CorsPolicy::any(),
+            frontend_static_dir: None,
+            enable_swagger: false,
+            email_signups: false,
+            email_webhook_secret: None,
+            sms_notifications: false,
+            web_push_public_key: None,
+            device_budget: Default::default(),
+            trigger_budget: Default::default(),
+            base_path: BasePath::from_base_url("https://example.com/televent").unwrap(),
+            frontend_assets: Default::default(),
+        },
+    );
+    let encoded = STANDARD.encode(format!("1401:{password}").as_bytes());
+
+    let request = |method: &str, uri: &str, body: Body| {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("Authorization", format!("Basic {encoded}"))
+            .header("Content-Type", "text/calendar")
+            .header("Depth", "1")
+            .extension(axum::extract::ConnectInfo(std::net::SocketAddr::from((
+                [127, 0, 0, 1],
+                8080,
+            ))))
+            .body(body)
+            .unwrap()
+    };
+
+    let created = app
+        .clone()
+        .oneshot(request(
+            "PUT",
+            "/caldav/1401/prefix-event.ics",
+            Body::from(
+                "BEGIN:VCALENDAR\r\n\
+                 VERSION:2.0\r\n\
+                 BEGIN:VEVENT\r\n\
+                 UID:prefix-event\r\n\
+                 DTSTART:20240101T100000Z\r\n\
+                 DTEND:20240101T110000Z\r\n\
+                 SUMMARY:Planning\r\n\
+                 END:VEVENT\r\n\
+                 END:VCALENDAR\r\n",
+            ),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(created.status(), StatusCode::CREATED);
+
+    // Prefix from the configured base URL
+    let response = app
+        .clone()
+        .oneshot(request("PROPFIND", "/caldav/1401/", Body::empty()))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::MULTI_STATUS);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let xml = String::from_utf8(body.to_vec()).unwrap();
+    assert!(
+        xml.contains("<d:href>/televent/caldav/1401/</d:href>"),
+        "{xml}"
+    );
+    assert!(
+        xml.contains("<d:href>/televent/caldav/1401/prefix-event.ics</d:href>"),
+        "{xml}"
+    );
+
+    // A proxy's X-Forwarded-Prefix wins; unsafe values are ignored
+    for (prefix, expected) in [
+        ("/proxy/", "<d:href>/proxy/caldav/1401/</d:href>"),
+        ("/x\"><y", "<d:href>/televent/caldav/1401/</d:href>"),
+    ] {
+        let mut forwarded = request("PROPFIND", "/caldav/1401/", Body::empty());
+        forwarded
+            .headers_mut()
+            .insert("X-Forwarded-Prefix", prefix.parse().unwrap());
+        let response = app.clone().oneshot(forwarded).await.unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let xml = String::from_utf8(body.to_vec()).unwrap();
+        assert!(xml.contains(expected), "{xml}");
+    }
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_parallel_caldav_writes_stay_consistent(pool: PgPool) {
+    let user_id = UserId::new(1501);
+
+    sqlx::query("INSERT INTO users (telegram_id, telegram_username, timezone, sync_token, ctag) VALUES ($1, 'race_user', 'UTC', 0, 0)")
+        .bind(user_id.inner())
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let password = "password123";
+    let salt = SaltString::generate(&mut argon2::password_hash::rand_core::OsRng);
+    let password_hash = Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+
+    sqlx::query("INSERT INTO device_passwords (id, user_id, password_hash, device_name) VALUES ($1, $2, $3, 'test_device')")
+        .bind(uuid::Uuid::new_v4())
+        .bind(user_id.inner())
+        .bind(password_hash)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let app = create_router(app_state(&pool), "*");
+    let encoded = STANDARD.encode(format!("1501:{password}").as_bytes());
+
+    let put = |uid: &str, summary: &str, if_match: Option<&str>| {
+        let mut request = Request::builder()
+            .method("PUT")
+            .uri(format!("/caldav/1501/{uid}.ics"))
+            .header("Authorization", format!("Basic {encoded}"))
+            .header("Content-Type", "text/calendar")
+            .extension(axum::extract::ConnectInfo(std::net::SocketAddr::from((
+                [127, 0, 0, 1],
+                8080,
+            ))));
+        if let Some(etag) = if_match {
+            request = request.header("If-Match", etag);
+        }
+        request
+            .body(Body::from(format!(
+                "BEGIN:VCALENDAR\r\n\
+                 VERSION:2.0\r\n\
+                 BEGIN:VEVENT\r\n\
+                 UID:{uid}\r\n\
+                 DTSTART:20240101T100000Z\r\n\
+                 DTEND:20240101T110000Z\r\n\
+                 SUMMARY:{summary}\r\n\
+                 END:VEVENT\r\n\
+                 END:VCALENDAR\r\n"
+            )))
+            .unwrap()
+    };
+    // Send every request at once, as separate devices would
+    let send_all = |requests: Vec<Request<Body>>| {
+        let app = app.clone();
+        async move {
+            let handles: Vec<_> = requests
+                .into_iter()
+                .map(|request| tokio::spawn(app.clone().oneshot(request)))
+                .collect();
+            let mut statuses = Vec::with_capacity(handles.len());
+            for handle in handles {
+                statuses.push(handle.await.unwrap().unwrap().status());
+            }
+            statuses.sort();
+            statuses
+        }
+    };
+
+    // Different events: each gets its own sync token, none is lost
+    let statuses = send_all(
+        (0..8)
+            .map(|n| put(&format!("race-{n}"), "Parallel", None))
+            .collect(),
+    )
+    .await;
+    assert_eq!(statuses, [StatusCode::CREATED; 8]);
+    let sync_versions: Vec<i64> = sqlx::query_scalar(
+        "SELECT sync_version FROM events WHERE user_id = $1 ORDER BY sync_version",
+    )
+    .bind(user_id.inner())
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(sync_versions, (1..=8).collect::<Vec<i64>>());
+    let sync_token: i64 = sqlx::query_scalar("SELECT sync_token FROM users WHERE telegram_id = $1")
+        .bind(user_id.inner())
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(sync_token, 8);
+
+    // The same new UID twice: one creates it, the other replaces it
+    let statuses = send_all(vec![
+        put("shared", "First", None),
+        put("shared", "Second", None),
+    ])
+    .await;
+    assert_eq!(statuses, [StatusCode::CREATED, StatusCode::NO_CONTENT]);
+    let copies: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM events WHERE user_id = $1 AND uid = 'shared'")
+            .bind(user_id.inner())
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(copies, 1);
+
+    // Both devices edit from the same etag: only the first write wins
+    let etag: String =
+        sqlx::query_scalar("SELECT etag FROM events WHERE user_id = $1 AND uid = 'race-0'")
+            .bind(user_id.inner())
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    let if_match = format!("\"{etag}\"");
+    let statuses = send_all(vec![
+        put("race-0", "Edited on phone", Some(&if_match)),
+        put("race-0", "Edited on laptop", Some(&if_match)),
+    ])
+    .await;
+    assert_eq!(statuses, [StatusCode::NO_CONTENT, StatusCode::CONFLICT]);
 }
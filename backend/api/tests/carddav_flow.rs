@@ -0,0 +1,316 @@
+use api::middleware::caldav_auth::AuthCache;
+use api::{AppState, create_router};
+use argon2::{
+    Argon2,
+    password_hash::{PasswordHasher, SaltString, rand_core::OsRng},
+};
+use axum::{
+    body::Body,
+    extract::ConnectInfo,
+    http::{Request, StatusCode, header},
+};
+use base64::{Engine, engine::general_purpose::STANDARD};
+use sqlx::PgPool;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::time::Duration;
+use televent_application::{ContactService, UserId};
+use tower::ServiceExt;
+
+async fn setup_user_and_auth(pool: &PgPool) -> (i64, String) {
+    let telegram_id = rand::random::<i64>().abs();
+
+    sqlx::query(
+        r#"
+        INSERT INTO users (telegram_id, telegram_username, timezone, sync_token, ctag)
+        VALUES ($1, $2, 'UTC', '0', '0')
+        "#,
+    )
+    .bind(telegram_id)
+    .bind(format!("user_{}", telegram_id))
+    .execute(pool)
+    .await
+    .unwrap();
+
+    let password = "test_password";
+    let salt = SaltString::generate(&mut OsRng);
+    let password_hash = Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+
+    sqlx::query(
+        r#"
+        INSERT INTO device_passwords (id, user_id, password_hash, device_name)
+        VALUES (gen_random_uuid(), $1, $2, 'test_device')
+        "#,
+    )
+    .bind(telegram_id)
+    .bind(password_hash)
+    .execute(pool)
+    .await
+    .unwrap();
+
+    let credentials = format!("{}:{}", telegram_id, password);
+    (
+        telegram_id,
+        format!("Basic {}", STANDARD.encode(credentials.as_bytes())),
+    )
+}
+
+fn create_request(
+    method: &str,
+    uri: impl AsRef<str>,
+    auth_header: &str,
+    headers: Vec<(&str, &str)>,
+    body: Body,
+) -> Request<Body> {
+    let mut builder = Request::builder()
+        .method(method)
+        .uri(uri.as_ref())
+        .header(header::AUTHORIZATION, auth_header);
+
+    for (k, v) in headers {
+        builder = builder.header(k, v);
+    }
+
+    let mut req = builder.body(body).unwrap();
+
+    // Add ConnectInfo for rate limiting
+    req.extensions_mut().insert(ConnectInfo(SocketAddr::new(
+        IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)),
+        8080,
+    )));
+
+    req
+}
+
+async fn body_string(response: axum::response::Response) -> String {
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    String::from_utf8(bytes.to_vec()).unwrap()
+}
+
+fn contact_service(pool: &PgPool) -> ContactService {
+    ContactService::new(televent_storage::contact::ContactRepository::new(
+        pool.clone(),
+    ))
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_carddav_contact_lifecycle_and_search(pool: PgPool) {
+    let (telegram_id, auth_header) = setup_user_and_auth(&pool).await;
+
+    let state = AppState {
+        calendar_service: televent_application::CalendarService::new(
+            televent_storage::calendar::CalendarRepository::new(pool.clone()),
+        ),
+        event_service: televent_application::EventService::new(
+            televent_storage::calendar::CalendarRepository::new(pool.clone()),
+        ),
+        device_service: televent_application::DeviceService::new(
+            televent_storage::device::DeviceRepository::new(pool.clone()),
+        ),
+        health_service: televent_application::HealthService::new(
+            televent_storage::health::HealthRepository::new(pool.clone()),
+        ),
+        subscription_service: televent_application::SubscriptionService::new(
+            televent_storage::subscription::SubscriptionRepository::new(pool.clone()),
+        ),
+        contact_service: contact_service(&pool),
+        email_service: televent_application::EmailService::new(
+            televent_storage::email::EmailRepository::new(pool.clone()),
+        ),
+        feature_flags: televent_application::FeatureFlagService::new(
+            televent_storage::flags::FeatureFlagRepository::new(pool.clone()),
+        ),
+        chat_webhook_service: televent_application::ChatWebhookService::new(
+            televent_storage::chat_webhook::ChatWebhookRepository::new(pool.clone()),
+        ),
+        notification_service: televent_application::NotificationService::new(
+            televent_storage::notification::NotificationRepository::new(pool.clone()),
+        ),
+        web_push_service: televent_application::WebPushService::new(
+            televent_storage::web_push::WebPushRepository::new(pool.clone()),
+        ),
+        auth_cache: AuthCache::new(Duration::from_secs(300), 10_000),
+        telegram_bot_token: "test_token".to_string(),
+    };
+    let app = create_router(state, "*");
+    let book = format!("/carddav/{telegram_id}/");
+
+    // Create two contacts
+    for (uid, card) in [
+        (
+            "alice",
+            "BEGIN:VCARD\r\nVERSION:3.0\r\nUID:alice\r\nFN:Alice Liddell\r\n\
+             EMAIL:alice@example.com\r\nEND:VCARD\r\n",
+        ),
+        (
+            "bob",
+            "BEGIN:VCARD\r\nVERSION:3.0\r\nUID:bob\r\nFN:Bob Stone\r\n\
+             X-TELEGRAM-USERNAME:bob_stone\r\nEND:VCARD\r\n",
+        ),
+    ] {
+        let response = app
+            .clone()
+            .oneshot(create_request(
+                "PUT",
+                format!("{book}{uid}.vcf"),
+                &auth_header,
+                vec![("Content-Type", "text/vcard"), ("If-None-Match", "*")],
+                Body::from(card),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+    }
+
+    // GET returns the stored vCard and its etag
+    let response = app
+        .clone()
+        .oneshot(create_request(
+            "GET",
+            format!("{book}alice.vcf"),
+            &auth_header,
+            vec![],
+            Body::empty(),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let etag = response
+        .headers()
+        .get(header::ETAG)
+        .unwrap()
+        .to_str()
+        .unwrap()
+        .to_string();
+    assert!(body_string(response).await.contains("FN:Alice Liddell"));
+
+    // PROPFIND lists both contacts
+    let response = app
+        .clone()
+        .oneshot(create_request(
+            "PROPFIND",
+            &book,
+            &auth_header,
+            vec![("Depth", "1")],
+            Body::empty(),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::MULTI_STATUS);
+    let body = body_string(response).await;
+    assert!(body.contains("addressbook"));
+    assert!(body.contains("alice.vcf") && body.contains("bob.vcf"));
+
+    // Multiget returns the requested card with its data
+    let multiget = format!(
+        r#"<C:addressbook-multiget xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:carddav">
+             <D:prop><D:getetag/><C:address-data/></D:prop>
+             <D:href>{book}bob.vcf</D:href>
+           </C:addressbook-multiget>"#
+    );
+    let response = app
+        .clone()
+        .oneshot(create_request(
+            "REPORT",
+            &book,
+            &auth_header,
+            vec![],
+            Body::from(multiget),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::MULTI_STATUS);
+    let body = body_string(response).await;
+    assert!(body.contains("X-TELEGRAM-USERNAME:bob_stone"));
+    assert!(!body.contains("alice.vcf"));
+
+    // Updates must match the current etag
+    let updated = "BEGIN:VCARD\r\nVERSION:3.0\r\nUID:alice\r\nFN:Alice Hargreaves\r\n\
+                   EMAIL:alice@example.com\r\nEND:VCARD\r\n";
+    let response = app
+        .clone()
+        .oneshot(create_request(
+            "PUT",
+            format!("{book}alice.vcf"),
+            &auth_header,
+            vec![("If-Match", "\"stale\"")],
+            Body::from(updated),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    let response = app
+        .clone()
+        .oneshot(create_request(
+            "PUT",
+            format!("{book}alice.vcf"),
+            &auth_header,
+            vec![("If-Match", etag.as_str())],
+            Body::from(updated),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    // Search matches names, emails and usernames
+    let contacts = contact_service(&pool);
+    let user_id = UserId::new(telegram_id);
+    let found = contacts.search_contacts(user_id, "harg", 10).await.unwrap();
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].email.as_deref(), Some("alice@example.com"));
+    let found = contacts.search_contacts(user_id, "", 10).await.unwrap();
+    assert_eq!(found.len(), 2);
+    let bob = contacts
+        .find_contact(user_id, "bob")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(bob.telegram_username.as_deref(), Some("bob_stone"));
+    assert!(
+        contacts
+            .find_contact(user_id, "zed")
+            .await
+            .unwrap()
+            .is_none()
+    );
+
+    // Another user's credentials cannot read the address book
+    let (_, other_auth) = setup_user_and_auth(&pool).await;
+    let response = app
+        .clone()
+        .oneshot(create_request(
+            "GET",
+            format!("{book}alice.vcf"),
+            &other_auth,
+            vec![],
+            Body::empty(),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    // DELETE removes the contact
+    let response = app
+        .clone()
+        .oneshot(create_request(
+            "DELETE",
+            format!("{book}bob.vcf"),
+            &auth_header,
+            vec![],
+            Body::empty(),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    assert!(
+        contacts
+            .get_contact(user_id, "bob")
+            .await
+            .unwrap()
+            .is_none()
+    );
+}
@@ -0,0 +1,139 @@
+use chrono::{Duration, Utc};
+use sqlx::PgPool;
+use televent_application::{
+    ApplicationError, CalendarService, CreateEventCommand, EmailFeedback, EmailFeedbackKind,
+    EmailSendPermit, EmailService, EventService, PublicSignupCommand, UnsubscribeKey, UserId,
+};
+use televent_domain::{EventStatus, EventTiming, Timezone};
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_hard_bounce_suppresses_and_notifies_organizer(pool: PgPool) {
+    let calendar_repo = || televent_storage::calendar::CalendarRepository::new(pool.clone());
+    let events = EventService::new(calendar_repo());
+    let calendar = CalendarService::new(calendar_repo());
+    let email = EmailService::new(televent_storage::email::EmailRepository::new(pool.clone()));
+    let organizer = UserId::new(890_001);
+
+    let start = Utc::now() + Duration::days(3);
+    let event = events
+        .create_event_view(CreateEventCommand {
+            user_id: organizer,
+            username: None,
+            uid: "bounce-meetup".to_string(),
+            summary: "Bounce Meetup".to_string(),
+            description: None,
+            location: None,
+            timing: EventTiming::Timed {
+                start,
+                end: start + Duration::hours(2),
+                timezone: Timezone::utc(),
+            },
+            status: EventStatus::Confirmed,
+            rrule: None,
+            visibility: None,
+            reminder_note: None,
+            allow_duplicate: true,
+        })
+        .await
+        .unwrap();
+    let slug = events.publish_event(organizer, event.id).await.unwrap();
+    events
+        .request_public_signup(PublicSignupCommand {
+            slug: slug.clone(),
+            email: "gone@example.com".to_string(),
+            display_name: None,
+        })
+        .await
+        .unwrap();
+
+    let bounce = || EmailFeedback {
+        email: "Gone@Example.com".to_string(),
+        kind: EmailFeedbackKind::HardBounce,
+        detail: Some("550 unknown user".to_string()),
+    };
+    let soft = EmailFeedback {
+        email: "busy@example.com".to_string(),
+        kind: EmailFeedbackKind::SoftBounce,
+        detail: None,
+    };
+    let outcome = email
+        .record_feedback("ses", vec![bounce(), soft])
+        .await
+        .unwrap();
+    assert_eq!(outcome.recorded, 2);
+    assert_eq!(outcome.suppressed, 1);
+    assert!(email.is_suppressed("gone@example.com").await.unwrap());
+    assert!(!email.is_suppressed("busy@example.com").await.unwrap());
+
+    // The organizer hears about the bounce once
+    let notify = || async {
+        sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM outbox_messages
+             WHERE kind = 'telegram_notification' AND payload->>'telegram_id' = $1",
+        )
+        .bind(organizer.inner().to_string())
+        .fetch_one(&pool)
+        .await
+        .unwrap()
+    };
+    assert_eq!(notify().await, 1);
+    let outcome = email.record_feedback("ses", vec![bounce()]).await.unwrap();
+    assert_eq!(outcome.suppressed, 0);
+    assert_eq!(notify().await, 1);
+
+    // Suppressed addresses get no link and cannot sign up again
+    assert!(
+        calendar
+            .issue_signup_confirmation(event.id, "gone@example.com")
+            .await
+            .unwrap()
+            .is_none()
+    );
+    assert!(matches!(
+        events
+            .request_public_signup(PublicSignupCommand {
+                slug,
+                email: "gone@example.com".to_string(),
+                display_name: None,
+            })
+            .await,
+        Err(ApplicationError::BadRequest(_))
+    ));
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_hourly_cap_and_unsubscribe(pool: PgPool) {
+    let email = EmailService::new(televent_storage::email::EmailRepository::new(pool.clone()));
+
+    for _ in 0..2 {
+        assert_eq!(
+            email.reserve_send("guest@example.com", 2).await.unwrap(),
+            EmailSendPermit::Granted
+        );
+    }
+    assert!(matches!(
+        email.reserve_send("Guest@Example.com", 2).await.unwrap(),
+        EmailSendPermit::Throttled(until) if until > Utc::now()
+    ));
+    assert_eq!(
+        email.reserve_send("other@example.com", 2).await.unwrap(),
+        EmailSendPermit::Granted
+    );
+
+    let key = UnsubscribeKey::from_bot_token("test_token");
+    assert!(matches!(
+        email.unsubscribe(&key, "forged.token").await,
+        Err(ApplicationError::NotFound(_))
+    ));
+    assert_eq!(
+        email
+            .unsubscribe(&key, &key.token("other@example.com"))
+            .await
+            .unwrap(),
+        "other@example.com"
+    );
+    assert_eq!(
+        email.reserve_send("other@example.com", 2).await.unwrap(),
+        EmailSendPermit::Suppressed
+    );
+}
@@ -0,0 +1,184 @@
+use chrono::{Duration, Utc};
+use sqlx::PgPool;
+use televent_application::{
+    ApplicationError, ConnectGoogleCommand, CreateEventCommand, EventLink, EventService,
+    GoogleSyncService, RemoteEvent, UserId,
+};
+use televent_domain::{EventStatus, EventTiming, Timezone};
+
+fn services(pool: &PgPool) -> (GoogleSyncService, EventService) {
+    let events = EventService::new(televent_storage::calendar::CalendarRepository::new(
+        pool.clone(),
+    ));
+    let google = GoogleSyncService::new(
+        televent_storage::google::GoogleRepository::new(pool.clone()),
+        televent_storage::calendar::CalendarRepository::new(pool.clone()),
+        events.clone(),
+    );
+    (google, events)
+}
+
+fn connect_command(user_id: UserId, refresh_token: Option<&str>) -> ConnectGoogleCommand {
+    ConnectGoogleCommand {
+        user_id,
+        username: Some("google_user".to_string()),
+        access_token: "access".to_string(),
+        refresh_token: refresh_token.map(str::to_string),
+        access_token_expires_at: Utc::now() + Duration::hours(1),
+    }
+}
+
+fn timed(start_in_hours: i64) -> EventTiming {
+    let start = Utc::now() + Duration::hours(start_in_hours);
+    EventTiming::Timed {
+        start,
+        end: start + Duration::hours(1),
+        timezone: Timezone::utc(),
+    }
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_google_connection_tracks_links_and_local_changes(pool: PgPool) {
+    let (google, events) = services(&pool);
+    let user_id = UserId::new(880_001);
+
+    // The first consent must grant offline access
+    assert!(matches!(
+        google.connect(connect_command(user_id, None)).await,
+        Err(ApplicationError::BadRequest(_))
+    ));
+    google
+        .connect(connect_command(user_id, Some("refresh-1")))
+        .await
+        .unwrap();
+    // Reconnecting without a new refresh token keeps the stored one
+    google
+        .connect(connect_command(user_id, None))
+        .await
+        .unwrap();
+    let due = google.due_connections(10).await.unwrap();
+    assert_eq!(due.len(), 1);
+    assert_eq!(due[0].refresh_token, "refresh-1");
+    assert_eq!(due[0].local_sync_version, 0);
+    assert!(due[0].google_sync_token.is_none());
+
+    events
+        .create_event_view(CreateEventCommand {
+            user_id,
+            username: None,
+            uid: "local-event".to_string(),
+            summary: "Local".to_string(),
+            description: None,
+            location: None,
+            timing: timed(2),
+            status: EventStatus::Confirmed,
+            rrule: None,
+            visibility: None,
+            reminder_note: None,
+            allow_duplicate: true,
+        })
+        .await
+        .unwrap();
+
+    let pulled = google
+        .apply_remote_event(
+            user_id,
+            RemoteEvent {
+                uid: "remote@google.com".to_string(),
+                summary: "From Google".to_string(),
+                description: None,
+                location: Some("Office".to_string()),
+                timing: timed(4),
+                status: EventStatus::Tentative,
+                rrule: None,
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(pulled.event.summary, "From Google");
+    google
+        .save_link(
+            user_id,
+            EventLink {
+                event_uid: "remote@google.com".to_string(),
+                google_event_id: "g1".to_string(),
+                google_etag: "\"1\"".to_string(),
+                google_updated_at: Utc::now(),
+                local_etag: pulled.etag.clone(),
+            },
+        )
+        .await
+        .unwrap();
+
+    let changes = google.local_changes(user_id, 0).await.unwrap();
+    assert_eq!(changes.events.len(), 2);
+    assert!(changes.deleted_uids.is_empty());
+
+    google
+        .record_sync_success(user_id, Some("sync-1".to_string()), changes.sync_version)
+        .await
+        .unwrap();
+    assert!(google.due_connections(10).await.unwrap().is_empty());
+
+    // Deleting the remote event's mirror shows up as a tombstone
+    assert!(
+        google
+            .delete_local_event(user_id, "remote@google.com")
+            .await
+            .unwrap()
+    );
+    assert!(
+        !google
+            .delete_local_event(user_id, "remote@google.com")
+            .await
+            .unwrap()
+    );
+    let changes = google
+        .local_changes(user_id, changes.sync_version)
+        .await
+        .unwrap();
+    assert!(changes.events.is_empty());
+    assert_eq!(changes.deleted_uids, vec!["remote@google.com".to_string()]);
+
+    let links = google.list_links(user_id).await.unwrap();
+    assert_eq!(links.len(), 1);
+    assert_eq!(links[0].google_event_id, "g1");
+
+    // Disconnecting drops the links with the connection
+    assert!(google.disconnect(user_id).await.unwrap());
+    assert!(google.get_connection(user_id).await.unwrap().is_none());
+    assert!(google.list_links(user_id).await.unwrap().is_empty());
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_google_oauth_state_is_single_use(pool: PgPool) {
+    let (google, _) = services(&pool);
+    let user_id = UserId::new(880_002);
+
+    let state = google.begin_authorization(user_id).await.unwrap();
+    let other = google.begin_authorization(user_id).await.unwrap();
+    assert_ne!(state, other);
+
+    // Only the hash is stored
+    let stored: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM google_oauth_states WHERE state_hash = $1")
+            .bind(&state)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(stored, 0);
+
+    assert_eq!(
+        google.take_authorization(&state).await.unwrap(),
+        Some(user_id)
+    );
+    assert_eq!(google.take_authorization(&state).await.unwrap(), None);
+    assert_eq!(google.take_authorization("forged").await.unwrap(), None);
+
+    // Expired states are rejected
+    sqlx::query("UPDATE google_oauth_states SET expires_at = NOW() - INTERVAL '1 minute'")
+        .execute(&pool)
+        .await
+        .unwrap();
+    assert_eq!(google.take_authorization(&other).await.unwrap(), None);
+}
@@ -1,9 +1,9 @@
+use api::middleware::caldav_auth::AuthCache;
 use api::{AppState, create_router};
 use argon2::{Argon2, PasswordHasher, password_hash::SaltString};
 use axum::body::Body;
 use axum::http::{Request, StatusCode};
 use base64::{Engine, engine::general_purpose::STANDARD};
-use moka::future::Cache;
 use sqlx::{PgPool, Row};
 use std::time::Duration;
 use televent_application::ConfirmRsvpCommand;
@@ -80,21 +80,43 @@ async fn test_invite_flow_end_to_end(pool: PgPool) {
     // =============================================================================
 
     // Create router
-    let auth_cache = Cache::builder()
-        .time_to_live(Duration::from_secs(300))
-        .build();
+    let auth_cache = AuthCache::new(Duration::from_secs(300), 10_000);
 
-    let calendar_service = televent_application::CalendarService::new(
+    let event_service = televent_application::EventService::new(
         televent_storage::calendar::CalendarRepository::new(pool.clone()),
     );
     let state = AppState {
-        calendar_service: calendar_service.clone(),
+        calendar_service: televent_application::CalendarService::new(
+            televent_storage::calendar::CalendarRepository::new(pool.clone()),
+        ),
+        event_service: event_service.clone(),
         device_service: televent_application::DeviceService::new(
             televent_storage::device::DeviceRepository::new(pool.clone()),
         ),
         health_service: televent_application::HealthService::new(
             televent_storage::health::HealthRepository::new(pool.clone()),
         ),
+        subscription_service: televent_application::SubscriptionService::new(
+            televent_storage::subscription::SubscriptionRepository::new(pool.clone()),
+        ),
+        contact_service: televent_application::ContactService::new(
+            televent_storage::contact::ContactRepository::new(pool.clone()),
+        ),
+        email_service: televent_application::EmailService::new(
+            televent_storage::email::EmailRepository::new(pool.clone()),
+        ),
+        feature_flags: televent_application::FeatureFlagService::new(
+            televent_storage::flags::FeatureFlagRepository::new(pool.clone()),
+        ),
+        chat_webhook_service: televent_application::ChatWebhookService::new(
+            televent_storage::chat_webhook::ChatWebhookRepository::new(pool.clone()),
+        ),
+        notification_service: televent_application::NotificationService::new(
+            televent_storage::notification::NotificationRepository::new(pool.clone()),
+        ),
+        web_push_service: televent_application::WebPushService::new(
+            televent_storage::web_push::WebPushRepository::new(pool.clone()),
+        ),
         auth_cache,
         telegram_bot_token: "test_token".to_string(),
     };
@@ -244,7 +266,7 @@ async fn test_invite_flow_end_to_end(pool: PgPool) {
     // Step 6: Simulate Bot Callback - User B accepts invite
     // =============================================================================
 
-    calendar_service
+    event_service
         .confirm_rsvp(ConfirmRsvpCommand {
             event_id,
             attendee_user_id: user_b_id,
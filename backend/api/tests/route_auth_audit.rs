@@ -0,0 +1,196 @@
+//! Walks the mounted routes and checks each one either refuses anonymous
+//! requests or is allow-listed in `api::PUBLIC_ROUTES`, so a route mounted
+//! outside the authenticated nests fails here instead of shipping open.
+
+use api::middleware::caldav_auth::AuthCache;
+use api::{AppState, PUBLIC_ROUTES, config::Config};
+use axum::Router;
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use sqlx::PgPool;
+use std::collections::BTreeSet;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::time::Duration;
+use tower::ServiceExt;
+
+/// Every optional route mounted, so none escapes the audit
+fn full_config() -> Config {
+    Config {
+        host: "0.0.0.0".to_string(),
+        port: 3000,
+        cors: api::middleware::cors::CorsPolicy::any(),
+        frontend_static_dir: None,
+        enable_swagger: false,
+        email_signups: true,
+        email_webhook_secret: Some("audit-secret".to_string()),
+        sms_notifications: true,
+        web_push_public_key: Some("audit-key".to_string()),
+        device_budget: Default::default(),
+        trigger_budget: Default::default(),
+        base_path: Default::default(),
+        frontend_assets: Default::default(),
+    }
+}
+
+fn app_state(pool: &PgPool) -> AppState {
+    AppState {
+        calendar_service: televent_application::CalendarService::new(
+            televent_storage::calendar::CalendarRepository::new(pool.clone()),
+        ),
+        event_service: televent_application::EventService::new(
+            televent_storage::calendar::CalendarRepository::new(pool.clone()),
+        ),
+        device_service: televent_application::DeviceService::new(
+            televent_storage::device::DeviceRepository::new(pool.clone()),
+        ),
+        health_service: televent_application::HealthService::new(
+            televent_storage::health::HealthRepository::new(pool.clone()),
+        ),
+        subscription_service: televent_application::SubscriptionService::new(
+            televent_storage::subscription::SubscriptionRepository::new(pool.clone()),
+        ),
+        contact_service: televent_application::ContactService::new(
+            televent_storage::contact::ContactRepository::new(pool.clone()),
+        ),
+        email_service: televent_application::EmailService::new(
+            televent_storage::email::EmailRepository::new(pool.clone()),
+        ),
+        feature_flags: televent_application::FeatureFlagService::new(
+            televent_storage::flags::FeatureFlagRepository::new(pool.clone()),
+        ),
+        chat_webhook_service: televent_application::ChatWebhookService::new(
+            televent_storage::chat_webhook::ChatWebhookRepository::new(pool.clone()),
+        ),
+        notification_service: televent_application::NotificationService::new(
+            televent_storage::notification::NotificationRepository::new(pool.clone()),
+        ),
+        web_push_service: televent_application::WebPushService::new(
+            televent_storage::web_push::WebPushRepository::new(pool.clone()),
+        ),
+        auth_cache: AuthCache::new(Duration::from_secs(300), 10_000),
+        telegram_bot_token: "audit_token".to_string(),
+    }
+}
+
+#[cfg(not(feature = "google-calendar"))]
+fn router(pool: &PgPool) -> Router {
+    api::create_router_with_config(app_state(pool), &full_config())
+}
+
+#[cfg(feature = "google-calendar")]
+fn router(pool: &PgPool) -> Router {
+    let google = api::GoogleState {
+        connector: televent_google::GoogleConnector::new(televent_google::GoogleConfig {
+            client_id: "audit-client".to_string(),
+            client_secret: "audit-secret".to_string(),
+            redirect_url: "http://localhost/google/callback".to_string(),
+        })
+        .unwrap(),
+        sync: televent_application::GoogleSyncService::new(
+            televent_storage::google::GoogleRepository::new(pool.clone()),
+            televent_storage::calendar::CalendarRepository::new(pool.clone()),
+            televent_application::EventService::new(
+                televent_storage::calendar::CalendarRepository::new(pool.clone()),
+            ),
+        ),
+    };
+    api::create_router_with_google(app_state(pool), &full_config(), google)
+}
+
+/// Paths the router has mounted, read from its `Debug` output, which lists
+/// every route under its full path once nested routers are flattened. The
+/// fallback router that follows only answers `404`.
+fn mounted_paths(router: &Router) -> BTreeSet<String> {
+    let debug = format!("{router:?}");
+    let (routes, _fallback) = debug
+        .split_once("fallback_router")
+        .expect("router Debug output lists its fallback router");
+    let mut paths = BTreeSet::new();
+    let mut rest = routes;
+    while let Some(start) = rest.find("RouteId(") {
+        rest = &rest[start..];
+        let Some(quote) = rest.find(": \"") else {
+            break;
+        };
+        let path = &rest[quote + 3..];
+        let end = path.find('"').expect("unterminated route path");
+        paths.insert(path[..end].to_string());
+        rest = &path[end..];
+    }
+    paths
+}
+
+/// A concrete URI matching `path`, with every parameter filled in
+fn probe_uri(path: &str) -> String {
+    path.split('/')
+        .map(|segment| {
+            if segment.starts_with("{*") {
+                "probe.ics"
+            } else if segment.starts_with('{') {
+                "1"
+            } else {
+                segment
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_every_route_is_authenticated_or_allow_listed(pool: PgPool) {
+    let app = router(&pool);
+    let paths = mounted_paths(&app);
+
+    // Guards the parsing above against a change in axum's Debug output
+    for expected in ["/api/events", "/caldav/{user_identifier}/", "/health"] {
+        assert!(
+            paths.contains(expected),
+            "{expected} not found among mounted paths {paths:?}"
+        );
+    }
+
+    let public: BTreeSet<&str> = PUBLIC_ROUTES.iter().map(|route| route.path).collect();
+    for route in PUBLIC_ROUTES {
+        assert!(
+            paths.contains(route.path),
+            "allow-listed public route {} is not mounted any more",
+            route.path
+        );
+    }
+
+    let methods = [
+        Method::GET,
+        Method::POST,
+        Method::PUT,
+        Method::DELETE,
+        Method::from_bytes(b"PROPFIND").unwrap(),
+        Method::from_bytes(b"REPORT").unwrap(),
+    ];
+    let mut open = Vec::new();
+    let mut probe = 0u32;
+    for path in paths.iter().filter(|path| !public.contains(path.as_str())) {
+        let uri = probe_uri(path);
+        for method in &methods {
+            // A fresh address each time keeps the per-IP rate limits out
+            probe += 1;
+            let peer = SocketAddr::from((Ipv4Addr::from(0x0a00_0000 + probe), 8080));
+            let request = Request::builder()
+                .method(method.clone())
+                .uri(&uri)
+                .extension(axum::extract::ConnectInfo(peer))
+                .body(Body::empty())
+                .unwrap();
+            let status = app.clone().oneshot(request).await.unwrap().status();
+            if status != StatusCode::UNAUTHORIZED {
+                open.push(format!("{method} {uri} ({path}) answered {status}"));
+            }
+        }
+    }
+
+    assert!(
+        open.is_empty(),
+        "routes answering without credentials; protect them or add them to \
+         api::PUBLIC_ROUTES:\n{}",
+        open.join("\n")
+    );
+}
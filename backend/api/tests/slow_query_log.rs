@@ -0,0 +1,51 @@
+use sqlx::PgPool;
+use std::time::Duration;
+use televent_domain::UserId;
+use televent_storage::{calendar::CalendarRepository, diagnostics::SlowQueryLog};
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_slow_reads_capture_plans(pool: PgPool) {
+    let user_id = 424242_i64;
+    sqlx::query("INSERT INTO users (telegram_id, telegram_username) VALUES ($1, 'slow_user')")
+        .bind(user_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    // Off by default: nothing is recorded
+    let repository = CalendarRepository::new(pool.clone());
+    repository
+        .list_events_since_sync(UserId::new(user_id), 0)
+        .await
+        .unwrap();
+
+    // A zero threshold treats every watched read as slow
+    let repository = CalendarRepository::new(pool.clone())
+        .with_slow_query_log(SlowQueryLog::new(pool.clone(), Some(Duration::ZERO)));
+    for _ in 0..2 {
+        repository
+            .list_events_since_sync(UserId::new(user_id), 0)
+            .await
+            .unwrap();
+    }
+
+    // Plans are captured in the background
+    let mut rows: Vec<(String, serde_json::Value, serde_json::Value)> = Vec::new();
+    for _ in 0..50 {
+        rows = sqlx::query_as("SELECT query_name, params, plan FROM slow_query_plans")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        if !rows.is_empty() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+
+    // Once per query name within the capture interval
+    assert_eq!(rows.len(), 1);
+    let (name, params, plan) = &rows[0];
+    assert_eq!(name, "list_events_since_sync");
+    assert_eq!(params, &serde_json::json!(["bigint", "bigint"]));
+    assert!(plan[0]["Plan"].is_object(), "{plan}");
+}
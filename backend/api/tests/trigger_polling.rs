@@ -0,0 +1,323 @@
+use api::middleware::caldav_auth::AuthCache;
+use api::middleware::device_budget::DeviceBudget;
+use api::{AppState, config::Config, create_router_with_config};
+use argon2::{Argon2, PasswordHasher, password_hash::SaltString};
+use axum::Router;
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use base64::{Engine, engine::general_purpose::STANDARD};
+use chrono::{Duration as ChronoDuration, Utc};
+use serde_json::Value;
+use sqlx::PgPool;
+use std::time::Duration;
+use televent_application::{CreateEventCommand, EventService, UserId};
+use televent_domain::{EventStatus, EventTiming, Timezone};
+use tower::ServiceExt;
+use uuid::Uuid;
+
+const TELEGRAM_ID: i64 = 3101;
+
+async fn add_device(pool: &PgPool, name: &str, password: &str) {
+    let salt = SaltString::generate(&mut argon2::password_hash::rand_core::OsRng);
+    let password_hash = Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+
+    sqlx::query(
+        "INSERT INTO device_passwords (id, user_id, password_hash, device_name) VALUES ($1, $2, $3, $4)",
+    )
+    .bind(Uuid::new_v4())
+    .bind(TELEGRAM_ID)
+    .bind(password_hash)
+    .bind(name)
+    .execute(pool)
+    .await
+    .unwrap();
+}
+
+async fn setup(pool: &PgPool, trigger_budget: DeviceBudget) -> Router {
+    sqlx::query("INSERT INTO users (telegram_id, telegram_username, timezone, sync_token, ctag) VALUES ($1, 'zap_user', 'UTC', 0, 0)")
+        .bind(TELEGRAM_ID)
+        .execute(pool)
+        .await
+        .unwrap();
+    add_device(pool, "zapier", "zapier-password").await;
+    add_device(pool, "ifttt", "ifttt-password").await;
+
+    let state = AppState {
+        calendar_service: televent_application::CalendarService::new(
+            televent_storage::calendar::CalendarRepository::new(pool.clone()),
+        ),
+        event_service: EventService::new(televent_storage::calendar::CalendarRepository::new(
+            pool.clone(),
+        )),
+        device_service: televent_application::DeviceService::new(
+            televent_storage::device::DeviceRepository::new(pool.clone()),
+        ),
+        health_service: televent_application::HealthService::new(
+            televent_storage::health::HealthRepository::new(pool.clone()),
+        ),
+        subscription_service: televent_application::SubscriptionService::new(
+            televent_storage::subscription::SubscriptionRepository::new(pool.clone()),
+        ),
+        contact_service: televent_application::ContactService::new(
+            televent_storage::contact::ContactRepository::new(pool.clone()),
+        ),
+        email_service: televent_application::EmailService::new(
+            televent_storage::email::EmailRepository::new(pool.clone()),
+        ),
+        feature_flags: televent_application::FeatureFlagService::new(
+            televent_storage::flags::FeatureFlagRepository::new(pool.clone()),
+        ),
+        chat_webhook_service: televent_application::ChatWebhookService::new(
+            televent_storage::chat_webhook::ChatWebhookRepository::new(pool.clone()),
+        ),
+        notification_service: televent_application::NotificationService::new(
+            televent_storage::notification::NotificationRepository::new(pool.clone()),
+        ),
+        web_push_service: televent_application::WebPushService::new(
+            televent_storage::web_push::WebPushRepository::new(pool.clone()),
+        ),
+        auth_cache: AuthCache::new(Duration::from_secs(300), 10_000),
+        telegram_bot_token: "test_token".to_string(),
+    };
+
+    create_router_with_config(
+        state,
+        &Config {
+            host: "0.0.0.0".to_string(),
+            port: 3000,
+            cors: api::middleware::cors::CorsPolicy::any(),
+            frontend_static_dir: None,
+            enable_swagger: false,
+            email_signups: false,
+            email_webhook_secret: None,
+            sms_notifications: false,
+            web_push_public_key: None,
+            device_budget: Default::default(),
+            trigger_budget,
+            base_path: Default::default(),
+            frontend_assets: Default::default(),
+        },
+    )
+}
+
+/// Create an event starting `starts_in` from now, recorded as created
+/// `created_ago` ago so the creation order is deterministic
+async fn add_event(
+    pool: &PgPool,
+    summary: &str,
+    starts_in: ChronoDuration,
+    created_ago: ChronoDuration,
+) -> Uuid {
+    let events = EventService::new(televent_storage::calendar::CalendarRepository::new(
+        pool.clone(),
+    ));
+    let start = Utc::now() + starts_in;
+    let event = events
+        .create_event_view(CreateEventCommand {
+            user_id: UserId::new(TELEGRAM_ID),
+            username: None,
+            uid: Uuid::new_v4().to_string(),
+            summary: summary.to_string(),
+            description: None,
+            location: None,
+            timing: EventTiming::Timed {
+                start,
+                end: start + ChronoDuration::hours(1),
+                timezone: Timezone::utc(),
+            },
+            status: EventStatus::Confirmed,
+            rrule: None,
+            visibility: None,
+            reminder_note: None,
+            allow_duplicate: true,
+        })
+        .await
+        .unwrap();
+    sqlx::query("UPDATE events SET created_at = $2 WHERE id = $1")
+        .bind(event.id)
+        .bind(Utc::now() - created_ago)
+        .execute(pool)
+        .await
+        .unwrap();
+    event.id
+}
+
+fn trigger_request(uri: &str, password: &str) -> Request<Body> {
+    let credentials = STANDARD.encode(format!("{TELEGRAM_ID}:{password}"));
+    Request::builder()
+        .uri(uri)
+        .header("Authorization", format!("Basic {credentials}"))
+        .extension(axum::extract::ConnectInfo(std::net::SocketAddr::from((
+            [127, 0, 0, 1],
+            8080,
+        ))))
+        .body(Body::empty())
+        .unwrap()
+}
+
+async fn poll(app: &Router, uri: &str) -> Vec<Value> {
+    let response = app
+        .clone()
+        .oneshot(trigger_request(uri, "zapier-password"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK, "{uri}");
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    serde_json::from_slice::<Value>(&body)
+        .unwrap()
+        .as_array()
+        .unwrap()
+        .clone()
+}
+
+fn summaries(items: &[Value]) -> Vec<&str> {
+    items
+        .iter()
+        .map(|item| item["summary"].as_str().unwrap())
+        .collect()
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_new_events_trigger_is_newest_first_with_since_id(pool: PgPool) {
+    let app = setup(&pool, DeviceBudget::default()).await;
+    let first = add_event(
+        &pool,
+        "First",
+        ChronoDuration::days(3),
+        ChronoDuration::minutes(30),
+    )
+    .await;
+    let second = add_event(
+        &pool,
+        "Second",
+        ChronoDuration::days(1),
+        ChronoDuration::minutes(20),
+    )
+    .await;
+
+    let items = poll(&app, "/api/triggers/new-events").await;
+    assert_eq!(summaries(&items), ["Second", "First"]);
+    assert_eq!(items[0]["id"], second.to_string());
+    assert!(items[0]["created_at"].is_string());
+
+    add_event(
+        &pool,
+        "Third",
+        ChronoDuration::days(2),
+        ChronoDuration::minutes(10),
+    )
+    .await;
+    let items = poll(&app, &format!("/api/triggers/new-events?since_id={second}")).await;
+    assert_eq!(summaries(&items), ["Third"]);
+
+    let items = poll(
+        &app,
+        &format!("/api/triggers/new-events?since_id={first}&limit=1"),
+    )
+    .await;
+    assert_eq!(summaries(&items), ["Third"]);
+
+    // A since_id that no longer exists filters nothing
+    let items = poll(
+        &app,
+        &format!("/api/triggers/new-events?since_id={}", Uuid::new_v4()),
+    )
+    .await;
+    assert_eq!(items.len(), 3);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_upcoming_events_trigger_covers_the_window(pool: PgPool) {
+    let app = setup(&pool, DeviceBudget::default()).await;
+    let soon = add_event(
+        &pool,
+        "Soon",
+        ChronoDuration::hours(2),
+        ChronoDuration::minutes(1),
+    )
+    .await;
+    add_event(
+        &pool,
+        "Tonight",
+        ChronoDuration::hours(10),
+        ChronoDuration::minutes(1),
+    )
+    .await;
+    add_event(
+        &pool,
+        "Next week",
+        ChronoDuration::days(6),
+        ChronoDuration::minutes(1),
+    )
+    .await;
+    add_event(
+        &pool,
+        "Yesterday",
+        ChronoDuration::days(-1),
+        ChronoDuration::minutes(1),
+    )
+    .await;
+
+    let items = poll(&app, "/api/triggers/upcoming-events").await;
+    assert_eq!(summaries(&items), ["Tonight", "Soon"]);
+
+    let items = poll(&app, "/api/triggers/upcoming-events?within_hours=168").await;
+    assert_eq!(summaries(&items), ["Next week", "Tonight", "Soon"]);
+
+    let items = poll(
+        &app,
+        &format!("/api/triggers/upcoming-events?since_id={soon}"),
+    )
+    .await;
+    assert_eq!(summaries(&items), ["Tonight"]);
+}
+
+#[sqlx::test(migrations = "../migrations")]
+async fn test_triggers_need_a_device_password_and_limit_each(pool: PgPool) {
+    let app = setup(
+        &pool,
+        DeviceBudget {
+            requests_per_minute: 2,
+            max_body_bytes: 0,
+        },
+    )
+    .await;
+
+    let response = app
+        .clone()
+        .oneshot(trigger_request(
+            "/api/triggers/new-events",
+            "wrong-password",
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    for _ in 0..2 {
+        poll(&app, "/api/triggers/new-events").await;
+    }
+    let response = app
+        .clone()
+        .oneshot(trigger_request(
+            "/api/triggers/new-events",
+            "zapier-password",
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+
+    // Another device password has its own budget
+    let response = app
+        .oneshot(trigger_request(
+            "/api/triggers/new-events",
+            "ifttt-password",
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
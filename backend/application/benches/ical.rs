@@ -0,0 +1,92 @@
+//! iCalendar serialization: one event as served over CalDAV, and whole
+//! calendars as exported by `/export` and subscription feeds
+
+use chrono::{Duration, TimeZone, Utc};
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use std::hint::black_box;
+use televent_application::ical::{
+    IcalAttendeeRender, IcalCalendarEventRender, IcalEventRender, calendar_to_ical_into,
+    event_to_ical_into,
+};
+use televent_domain::{EventStatus, EventTiming, EventVisibility, ParticipationStatus, Timezone};
+
+fn event(i: usize) -> IcalEventRender {
+    let start = Utc.with_ymd_and_hms(2026, 3, 2, 9, 0, 0).unwrap() + Duration::hours(i as i64);
+    IcalEventRender {
+        uid: format!("bench-{i}@televent"),
+        summary: format!("Planning session #{i}: roadmap, hiring, and the offsite"),
+        description: Some(
+            "Agenda:\n- Review last quarter's numbers, including the churn analysis\n\
+             - Agree on priorities; anything left over moves to the backlog\n\
+             - Dial in: https://meet.example.com/abc-defg-hij"
+                .to_string(),
+        ),
+        location: Some("Room 4, Tverskaya St. 7, Moscow".to_string()),
+        timing: EventTiming::Timed {
+            start,
+            end: start + Duration::minutes(90),
+            timezone: Timezone::default(),
+        },
+        status: EventStatus::Confirmed,
+        rrule: i
+            .is_multiple_of(4)
+            .then(|| "FREQ=WEEKLY;BYDAY=MO".to_string()),
+        visibility: EventVisibility::Public,
+        reminder_note: None,
+        sequence: 2,
+        created_at: start - Duration::days(7),
+        updated_at: start - Duration::days(1),
+    }
+}
+
+fn attendees(count: usize) -> Vec<IcalAttendeeRender> {
+    (0..count)
+        .map(|i| IcalAttendeeRender {
+            email: format!("tg_{}@televent.internal", 100_000 + i),
+            status: ParticipationStatus::Accepted,
+        })
+        .collect()
+}
+
+fn single_event(c: &mut Criterion) {
+    let mut group = c.benchmark_group("event_to_ical");
+    for count in [0, 10, 50] {
+        let event = event(0);
+        let attendees = attendees(count);
+        let mut buf = String::new();
+        group.bench_with_input(BenchmarkId::new("attendees", count), &count, |b, _| {
+            b.iter(|| {
+                buf.clear();
+                event_to_ical_into(black_box(&event), black_box(&attendees), &mut buf).unwrap();
+                black_box(buf.len())
+            });
+        });
+    }
+    group.finish();
+}
+
+fn calendar_export(c: &mut Criterion) {
+    let mut group = c.benchmark_group("calendar_to_ical");
+    for count in [10, 100, 1000] {
+        let events: Vec<_> = (0..count)
+            .map(|i| IcalCalendarEventRender {
+                event: event(i),
+                attendees: attendees(i % 5),
+            })
+            .collect();
+        let mut buf = String::new();
+        group.throughput(Throughput::Elements(count as u64));
+        group.bench_with_input(BenchmarkId::new("events", count), &count, |b, _| {
+            b.iter(|| {
+                buf.clear();
+                calendar_to_ical_into(black_box(&events), Some("Televent"), None, &mut buf)
+                    .unwrap();
+                black_box(buf.len())
+            });
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, single_event, calendar_export);
+criterion_main!(benches);
@@ -0,0 +1,306 @@
+//! Anonymous product metrics.
+//!
+//! [`Analytics`] counts what happens, never who it happens to: a metric is a
+//! fixed name such as `bot.command.today` plus a count, with no user, chat
+//! or event attached. Names are built from `'static` strings only, so
+//! message text can't leak into them.
+//!
+//! Counts go to an [`AnalyticsSink`]: daily totals in Postgres by default,
+//! or a StatsD daemon. Recording never waits for the sink and a failing sink
+//! is only logged, so metrics can't slow down or break what they measure.
+//! [`Analytics::disabled`] collects nothing, for deployments that opt out.
+
+use std::future::Future;
+use std::io;
+use std::net::{ToSocketAddrs, UdpSocket};
+use std::pin::Pin;
+use std::sync::Arc;
+
+use televent_storage::analytics::AnalyticsRepository;
+
+use crate::{ApplicationError, storage_error};
+
+/// Prefix of StatsD metric names unless configured otherwise
+pub const DEFAULT_STATSD_PREFIX: &str = "televent";
+
+/// Future returned by [`AnalyticsSink::increment`]
+pub type SinkFuture<'a> = Pin<Box<dyn Future<Output = Result<(), ApplicationError>> + Send + 'a>>;
+
+/// Somewhere metric counts are kept
+pub trait AnalyticsSink: Send + Sync {
+    /// Add `count` to `metric`
+    fn increment<'a>(&'a self, metric: &'a str, count: u64) -> SinkFuture<'a>;
+}
+
+/// Name of a counted metric
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Metric(String);
+
+impl Metric {
+    /// A bot command was run, e.g. `bot.command.today`
+    #[must_use]
+    pub fn command(name: &'static str) -> Self {
+        Self(format!("bot.command.{name}"))
+    }
+
+    /// A message meant to create an event reached `step`
+    #[must_use]
+    pub fn event(step: EventFunnelStep) -> Self {
+        Self(format!("bot.event.{}", step.as_str()))
+    }
+
+    /// The worker finished an outbox job of `kind`
+    #[must_use]
+    pub fn outbox_job(kind: &'static str, outcome: JobOutcome) -> Self {
+        Self(format!("worker.outbox.{kind}.{}", outcome.as_str()))
+    }
+
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// How far a message meant to create an event got
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventFunnelStep {
+    /// The text could not be read as an event
+    ParseFailed,
+    /// The event was saved
+    Created,
+    /// A near-duplicate was found and the user asked to confirm
+    DuplicatePrompted,
+    /// Saving the event failed
+    CreateFailed,
+}
+
+impl EventFunnelStep {
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::ParseFailed => "parse_failed",
+            Self::Created => "created",
+            Self::DuplicatePrompted => "duplicate_prompted",
+            Self::CreateFailed => "create_failed",
+        }
+    }
+}
+
+/// What became of an outbox job
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobOutcome {
+    Completed,
+    /// Retried or deferred to later
+    Rescheduled,
+    Failed,
+}
+
+impl JobOutcome {
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Completed => "completed",
+            Self::Rescheduled => "rescheduled",
+            Self::Failed => "failed",
+        }
+    }
+}
+
+/// Records metrics to a sink, or nowhere when disabled
+#[derive(Clone, Default)]
+pub struct Analytics {
+    sink: Option<Arc<dyn AnalyticsSink>>,
+}
+
+impl std::fmt::Debug for Analytics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Analytics")
+            .field("enabled", &self.is_enabled())
+            .finish()
+    }
+}
+
+impl Analytics {
+    #[must_use]
+    pub fn new(sink: Arc<dyn AnalyticsSink>) -> Self {
+        Self { sink: Some(sink) }
+    }
+
+    /// Collect nothing
+    #[must_use]
+    pub fn disabled() -> Self {
+        Self { sink: None }
+    }
+
+    #[must_use]
+    pub fn is_enabled(&self) -> bool {
+        self.sink.is_some()
+    }
+
+    /// Count one occurrence of `metric` in the background
+    pub fn record(&self, metric: Metric) {
+        let Some(sink) = self.sink.clone() else {
+            return;
+        };
+        tokio::spawn(async move {
+            if let Err(err) = sink.increment(metric.as_str(), 1).await {
+                tracing::warn!("Failed to record metric {}: {}", metric.as_str(), err);
+            }
+        });
+    }
+}
+
+/// Daily totals in the `analytics_counters` table
+#[derive(Clone)]
+pub struct PostgresAnalyticsSink {
+    counters: AnalyticsRepository,
+}
+
+impl PostgresAnalyticsSink {
+    #[must_use]
+    pub fn new(counters: AnalyticsRepository) -> Self {
+        Self { counters }
+    }
+}
+
+impl AnalyticsSink for PostgresAnalyticsSink {
+    fn increment<'a>(&'a self, metric: &'a str, count: u64) -> SinkFuture<'a> {
+        Box::pin(async move {
+            let count = i64::try_from(count)
+                .map_err(|_| ApplicationError::Internal("Metric count too large".into()))?;
+            self.counters
+                .increment(metric, count)
+                .await
+                .map_err(storage_error)
+        })
+    }
+}
+
+/// Counters sent to a StatsD daemon over UDP, e.g. `televent.bot.command.today:1|c`
+pub struct StatsdAnalyticsSink {
+    socket: UdpSocket,
+    prefix: String,
+}
+
+impl StatsdAnalyticsSink {
+    /// Send to `addr`, a `host:port`, naming metrics `<prefix>.<metric>`
+    pub fn new(addr: &str, prefix: &str) -> io::Result<Self> {
+        let target = addr.to_socket_addrs()?.next().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, format!("{addr} has no address"))
+        })?;
+        let bind = if target.is_ipv4() {
+            "0.0.0.0:0"
+        } else {
+            "[::]:0"
+        };
+        let socket = UdpSocket::bind(bind)?;
+        socket.connect(target)?;
+        // A full send buffer drops the metric instead of stalling a task
+        socket.set_nonblocking(true)?;
+        Ok(Self {
+            socket,
+            prefix: prefix.trim_end_matches('.').to_string(),
+        })
+    }
+
+    fn line(&self, metric: &str, count: u64) -> String {
+        if self.prefix.is_empty() {
+            format!("{metric}:{count}|c")
+        } else {
+            format!("{}.{metric}:{count}|c", self.prefix)
+        }
+    }
+}
+
+impl AnalyticsSink for StatsdAnalyticsSink {
+    fn increment<'a>(&'a self, metric: &'a str, count: u64) -> SinkFuture<'a> {
+        Box::pin(async move {
+            self.socket
+                .send(self.line(metric, count).as_bytes())
+                .map(|_| ())
+                .map_err(|err| ApplicationError::Internal(format!("StatsD send failed: {err}")))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::mpsc;
+
+    struct ChannelSink(mpsc::UnboundedSender<(String, u64)>);
+
+    impl AnalyticsSink for ChannelSink {
+        fn increment<'a>(&'a self, metric: &'a str, count: u64) -> SinkFuture<'a> {
+            Box::pin(async move {
+                self.0.send((metric.to_string(), count)).unwrap();
+                Ok(())
+            })
+        }
+    }
+
+    #[test]
+    fn test_metric_names() {
+        assert_eq!(Metric::command("today").as_str(), "bot.command.today");
+        assert_eq!(
+            Metric::event(EventFunnelStep::ParseFailed).as_str(),
+            "bot.event.parse_failed"
+        );
+        assert_eq!(
+            Metric::outbox_job("sms", JobOutcome::Rescheduled).as_str(),
+            "worker.outbox.sms.rescheduled"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_record_reaches_the_sink() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let analytics = Analytics::new(Arc::new(ChannelSink(tx)));
+
+        analytics.record(Metric::command("help"));
+
+        assert_eq!(
+            rx.recv().await.unwrap(),
+            ("bot.command.help".to_string(), 1)
+        );
+        assert!(!Analytics::disabled().is_enabled());
+    }
+
+    #[tokio::test]
+    async fn test_statsd_sink_sends_counter_lines() {
+        let daemon = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let sink = StatsdAnalyticsSink::new(&daemon.local_addr().unwrap().to_string(), "televent.")
+            .unwrap();
+
+        sink.increment("bot.command.today", 2).await.unwrap();
+
+        let mut buf = [0u8; 128];
+        let len = daemon.recv(&mut buf).unwrap();
+        assert_eq!(&buf[..len], b"televent.bot.command.today:2|c");
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_postgres_sink_keeps_daily_totals(pool: sqlx::PgPool) {
+        let counters = AnalyticsRepository::new(pool);
+        let sink = PostgresAnalyticsSink::new(counters.clone());
+
+        sink.increment("bot.event.created", 1).await.unwrap();
+        sink.increment("bot.event.created", 2).await.unwrap();
+        sink.increment("bot.event.parse_failed", 1).await.unwrap();
+
+        let totals: Vec<(String, i64)> = counters
+            .list_counters(chrono::Utc::now().date_naive() - chrono::Duration::days(1))
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|counter| (counter.metric, counter.count))
+            .collect();
+        assert_eq!(
+            totals,
+            vec![
+                ("bot.event.created".to_string(), 3),
+                ("bot.event.parse_failed".to_string(), 1),
+            ]
+        );
+    }
+}
@@ -0,0 +1,547 @@
+//! Free/busy computation and free slot search.
+//!
+//! [`FreeBusy`] collects the busy intervals of a user's calendars inside a
+//! window, expanding recurrences and pinning all-day events to local days,
+//! and [`FreeBusy::free_slots`] walks the gaps between them to answer "when
+//! can I fit this?".
+
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use televent_domain::{EventTiming, Timezone, WorkingHours, expand_rrule};
+
+use crate::ApplicationError;
+
+/// Slots returned when the caller doesn't ask for a count.
+pub const DEFAULT_SLOT_COUNT: usize = 5;
+
+/// Upper bound on slots returned by one search.
+pub const MAX_SLOT_COUNT: usize = 20;
+
+/// Longest window a slot search may look ahead.
+pub const MAX_SLOT_SEARCH_DAYS: i64 = 31;
+
+/// Window searched when the caller doesn't give one.
+pub const DEFAULT_SLOT_SEARCH_DAYS: i64 = 7;
+
+/// Slot starts are rounded up to this many minutes.
+const SLOT_GRANULARITY_MINUTES: i64 = 15;
+
+/// Cap on occurrences expanded per recurring event inside one window.
+const MAX_BUSY_OCCURRENCES: usize = 1000;
+
+/// A span of time in which the user already has something on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BusyInterval {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+/// A suggested slot and how long the gap it sits in stays free.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FreeSlot {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub free_until: DateTime<Utc>,
+}
+
+/// What a free slot search is looking for.
+#[derive(Debug, Clone)]
+pub struct SlotSearch {
+    pub from: DateTime<Utc>,
+    pub duration: Duration,
+    pub within: Duration,
+    pub working_hours: Option<WorkingHours>,
+    pub count: usize,
+}
+
+impl SlotSearch {
+    /// Check the search against the slot limits and clamp the count.
+    pub fn validated(mut self) -> Result<Self, ApplicationError> {
+        if self.duration < Duration::minutes(1) {
+            return Err(ApplicationError::BadRequest(
+                "duration must be at least one minute".to_string(),
+            ));
+        }
+        if self.within <= Duration::zero() || self.within > Duration::days(MAX_SLOT_SEARCH_DAYS) {
+            return Err(ApplicationError::BadRequest(format!(
+                "search window must be between one minute and {MAX_SLOT_SEARCH_DAYS} days"
+            )));
+        }
+        if self.duration > self.within {
+            return Err(ApplicationError::BadRequest(
+                "duration must fit inside the search window".to_string(),
+            ));
+        }
+        if let Some(hours) = &self.working_hours
+            && self.duration > hours.longest_day()
+        {
+            return Err(ApplicationError::BadRequest(
+                "duration must fit inside the working hours".to_string(),
+            ));
+        }
+        self.count = self.count.clamp(1, MAX_SLOT_COUNT);
+
+        Ok(self)
+    }
+
+    #[must_use]
+    pub fn until(&self) -> DateTime<Utc> {
+        self.from + self.within
+    }
+}
+
+/// Busy intervals of one user inside a window, in their timezone.
+#[derive(Debug, Clone)]
+pub struct FreeBusy {
+    window_start: DateTime<Utc>,
+    window_end: DateTime<Utc>,
+    timezone: Timezone,
+    tz: Tz,
+    busy: Vec<BusyInterval>,
+}
+
+impl FreeBusy {
+    #[must_use]
+    pub fn new(
+        window_start: DateTime<Utc>,
+        window_end: DateTime<Utc>,
+        timezone: &Timezone,
+    ) -> Self {
+        Self {
+            window_start,
+            window_end,
+            timezone: timezone.clone(),
+            tz: timezone.tz(),
+            busy: Vec::new(),
+        }
+    }
+
+    #[must_use]
+    pub fn window_start(&self) -> DateTime<Utc> {
+        self.window_start
+    }
+
+    #[must_use]
+    pub fn window_end(&self) -> DateTime<Utc> {
+        self.window_end
+    }
+
+    /// Timezone working hours are read in
+    #[must_use]
+    pub fn timezone(&self) -> &Timezone {
+        &self.timezone
+    }
+
+    /// Mark an event busy, expanding its recurrence rule inside the window.
+    pub fn add_event(
+        &mut self,
+        timing: &EventTiming,
+        rrule: Option<&str>,
+    ) -> Result<(), ApplicationError> {
+        let (start, end) = self.timing_bounds(timing);
+        let Some(rrule) = rrule else {
+            self.add_interval(start, end);
+            return Ok(());
+        };
+
+        let length = end - start;
+        let occurrences = expand_rrule(
+            rrule,
+            start,
+            self.window_start - length,
+            self.window_end,
+            MAX_BUSY_OCCURRENCES,
+        )?;
+        for occurrence in occurrences {
+            self.add_interval(occurrence, occurrence + length);
+        }
+
+        Ok(())
+    }
+
+    /// Mark `start..end` busy, e.g. a slot that was just taken.
+    pub fn block(&mut self, start: DateTime<Utc>, end: DateTime<Utc>) {
+        self.add_interval(start, end);
+    }
+
+    /// Whether nothing busy overlaps `start..end`.
+    #[must_use]
+    pub fn is_free(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> bool {
+        !self
+            .busy
+            .iter()
+            .any(|interval| interval.start < end && interval.end > start)
+    }
+
+    /// Busy intervals overlapping the window, sorted and merged.
+    #[must_use]
+    pub fn busy(&self) -> Vec<BusyInterval> {
+        let mut intervals = self.busy.clone();
+        intervals.sort_by_key(|interval| interval.start);
+
+        let mut merged: Vec<BusyInterval> = Vec::with_capacity(intervals.len());
+        for interval in intervals {
+            match merged.last_mut() {
+                Some(last) if interval.start <= last.end => last.end = last.end.max(interval.end),
+                _ => merged.push(interval),
+            }
+        }
+        merged
+    }
+
+    /// The first `search.count` gaps that fit `search.duration`, one slot per gap.
+    #[must_use]
+    pub fn free_slots(&self, search: &SlotSearch) -> Vec<FreeSlot> {
+        self.slots(search, false)
+    }
+
+    /// The first `search.count` slots of `search.duration`, back to back
+    /// inside each gap, for a visitor to pick one of.
+    #[must_use]
+    pub fn bookable_slots(&self, search: &SlotSearch) -> Vec<FreeSlot> {
+        self.slots(search, true)
+    }
+
+    fn slots(&self, search: &SlotSearch, back_to_back: bool) -> Vec<FreeSlot> {
+        let busy = self.busy();
+        let until = search.until().min(self.window_end);
+        let mut slots = Vec::new();
+        let mut candidate = round_up(search.from.max(self.window_start));
+
+        while slots.len() < search.count && candidate + search.duration <= until {
+            let mut gap_end = until;
+            if let Some(hours) = &search.working_hours {
+                let day = hours.local_day(candidate);
+                let Some((day_start, day_end)) = hours.window(day) else {
+                    candidate = next_day(day, hours);
+                    continue;
+                };
+                if candidate < day_start {
+                    candidate = day_start;
+                    continue;
+                }
+                if candidate + search.duration > day_end {
+                    candidate = next_day(day, hours);
+                    continue;
+                }
+                gap_end = gap_end.min(day_end);
+            }
+
+            let slot_end = candidate + search.duration;
+            if let Some(blocking) = busy
+                .iter()
+                .find(|interval| interval.start < slot_end && interval.end > candidate)
+            {
+                candidate = round_up(blocking.end);
+                continue;
+            }
+            if let Some(next_busy) = busy.iter().find(|interval| interval.start >= slot_end) {
+                gap_end = gap_end.min(next_busy.start);
+            }
+
+            slots.push(FreeSlot {
+                start: candidate,
+                end: slot_end,
+                free_until: gap_end,
+            });
+            candidate = if back_to_back {
+                round_up(slot_end)
+            } else {
+                round_up(gap_end.max(slot_end))
+            };
+        }
+
+        slots
+    }
+
+    fn timing_bounds(&self, timing: &EventTiming) -> (DateTime<Utc>, DateTime<Utc>) {
+        match timing {
+            EventTiming::Timed { start, end, .. } => (*start, *end),
+            // All-day dates are floating, so they block whole local days
+            EventTiming::AllDay {
+                start_date,
+                end_date,
+            } => (
+                self.local_midnight(*start_date),
+                self.local_midnight(*end_date),
+            ),
+            EventTiming::Floating { .. } => match timing.pinned_to(&self.timezone) {
+                EventTiming::Timed { start, end, .. } => (start, end),
+                _ => unreachable!("floating timings pin to timed ones"),
+            },
+        }
+    }
+
+    fn add_interval(&mut self, start: DateTime<Utc>, end: DateTime<Utc>) {
+        if end > self.window_start && start < self.window_end && end > start {
+            self.busy.push(BusyInterval { start, end });
+        }
+    }
+
+    fn local(&self, day: NaiveDate, time: NaiveTime) -> Option<DateTime<Utc>> {
+        self.tz
+            .from_local_datetime(&day.and_time(time))
+            .earliest()
+            .map(|local| local.with_timezone(&Utc))
+    }
+
+    fn local_midnight(&self, day: NaiveDate) -> DateTime<Utc> {
+        self.local(day, NaiveTime::MIN)
+            .unwrap_or_else(|| day.and_time(NaiveTime::MIN).and_utc())
+    }
+}
+
+/// Start of the working window after `day`, or the next local midnight when
+/// that day is off
+fn next_day(day: NaiveDate, hours: &WorkingHours) -> DateTime<Utc> {
+    let next = day + Duration::days(1);
+    hours.window(next).map_or_else(
+        || {
+            hours
+                .timezone()
+                .tz()
+                .from_local_datetime(&next.and_time(NaiveTime::MIN))
+                .earliest()
+                .map_or_else(
+                    || next.and_time(NaiveTime::MIN).and_utc(),
+                    |local| local.with_timezone(&Utc),
+                )
+        },
+        |(start, _)| start,
+    )
+}
+
+/// Parse a duration given either compactly (`30m`, `1h30m`, `2d`) or in
+/// ISO 8601 (`PT30M`, `P7D`, `P1DT12H`).
+pub fn parse_duration_spec(value: &str) -> Result<Duration, ApplicationError> {
+    let invalid = || {
+        ApplicationError::BadRequest(format!(
+            "duration must look like 30m, 1h30m, 2d or P7D, got {value:?}"
+        ))
+    };
+    let value = value.trim().to_ascii_lowercase();
+    let (iso, body) = match value.strip_prefix('p') {
+        Some(rest) => (true, rest),
+        None => (false, value.as_str()),
+    };
+
+    let mut total = Duration::zero();
+    let mut number = String::new();
+    let mut in_time = !iso;
+    let mut seen_unit = false;
+    for ch in body.chars() {
+        if ch.is_ascii_digit() {
+            number.push(ch);
+            continue;
+        }
+        if iso && ch == 't' && number.is_empty() && !in_time {
+            in_time = true;
+            continue;
+        }
+        let amount: i64 = number.parse().map_err(|_| invalid())?;
+        number.clear();
+        total += match (ch, in_time) {
+            ('w', false) => Duration::try_weeks(amount),
+            ('d', _) if !iso || !in_time => Duration::try_days(amount),
+            ('h', true) => Duration::try_hours(amount),
+            ('m', true) => Duration::try_minutes(amount),
+            ('s', true) if iso => Duration::try_seconds(amount),
+            _ => None,
+        }
+        .ok_or_else(invalid)?;
+        seen_unit = true;
+    }
+    if !number.is_empty() || !seen_unit {
+        return Err(invalid());
+    }
+
+    Ok(total)
+}
+
+fn round_up(at: DateTime<Utc>) -> DateTime<Utc> {
+    let step = SLOT_GRANULARITY_MINUTES * 60;
+    let seconds = at.timestamp();
+    let rounded = if seconds % step == 0 && at.timestamp_subsec_nanos() == 0 {
+        seconds
+    } else {
+        (seconds.div_euclid(step) + 1) * step
+    };
+    DateTime::from_timestamp(rounded, 0).unwrap_or(at)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 3, day, hour, minute, 0).unwrap()
+    }
+
+    fn timed(start: DateTime<Utc>, end: DateTime<Utc>) -> EventTiming {
+        EventTiming::Timed {
+            start,
+            end,
+            timezone: Timezone::utc(),
+        }
+    }
+
+    fn search(from: DateTime<Utc>, minutes: i64, hours: Option<&str>) -> SlotSearch {
+        search_in(from, minutes, hours, &Timezone::utc())
+    }
+
+    fn search_in(
+        from: DateTime<Utc>,
+        minutes: i64,
+        hours: Option<&str>,
+        timezone: &Timezone,
+    ) -> SlotSearch {
+        SlotSearch {
+            from,
+            duration: Duration::minutes(minutes),
+            within: Duration::days(2),
+            working_hours: hours.map(|hours| WorkingHours::parse(hours, timezone).unwrap()),
+            count: 3,
+        }
+        .validated()
+        .unwrap()
+    }
+
+    #[test]
+    fn parses_compact_and_iso_durations() {
+        assert_eq!(parse_duration_spec("30m").unwrap(), Duration::minutes(30));
+        assert_eq!(parse_duration_spec("1h30m").unwrap(), Duration::minutes(90));
+        assert_eq!(parse_duration_spec("2d").unwrap(), Duration::days(2));
+        assert_eq!(parse_duration_spec("P7D").unwrap(), Duration::days(7));
+        assert_eq!(parse_duration_spec("PT45M").unwrap(), Duration::minutes(45));
+        assert_eq!(parse_duration_spec("P1DT12H").unwrap(), Duration::hours(36));
+        assert!(parse_duration_spec("30").is_err());
+        assert!(parse_duration_spec("PT").is_err());
+        assert!(parse_duration_spec("P5M").is_err());
+        assert!(parse_duration_spec("soon").is_err());
+    }
+
+    #[test]
+    fn finds_one_slot_per_gap_between_busy_events() {
+        let from = at(2, 9, 5);
+        let mut free_busy = FreeBusy::new(from, from + Duration::days(2), &Timezone::utc());
+        free_busy
+            .add_event(&timed(at(2, 9, 30), at(2, 11, 0)), None)
+            .unwrap();
+        free_busy
+            .add_event(&timed(at(2, 10, 30), at(2, 12, 0)), None)
+            .unwrap();
+        assert_eq!(free_busy.busy().len(), 1);
+
+        let slots = free_busy.free_slots(&search(from, 60, None));
+        // 09:15-09:30 is too short, so the first gap opens when the meetings end
+        assert_eq!(slots[0].start, at(2, 12, 0));
+        assert_eq!(slots[0].end, at(2, 13, 0));
+        assert_eq!(slots[0].free_until, from + Duration::days(2));
+        assert_eq!(slots.len(), 1);
+    }
+
+    #[test]
+    fn bookable_slots_fill_each_gap_back_to_back() {
+        let from = at(2, 9, 0);
+        let mut free_busy = FreeBusy::new(from, from + Duration::days(2), &Timezone::utc());
+        free_busy
+            .add_event(&timed(at(2, 10, 0), at(2, 11, 0)), None)
+            .unwrap();
+
+        let starts: Vec<_> = free_busy
+            .bookable_slots(&search(from, 30, Some("09:00-17:00")))
+            .iter()
+            .map(|slot| slot.start)
+            .collect();
+        assert_eq!(starts, [at(2, 9, 0), at(2, 9, 30), at(2, 11, 0)]);
+    }
+
+    #[test]
+    fn keeps_slots_inside_working_hours() {
+        let from = at(2, 16, 40);
+        let mut free_busy = FreeBusy::new(from, from + Duration::days(2), &Timezone::utc());
+        free_busy
+            .add_event(&timed(at(3, 9, 0), at(3, 10, 0)), None)
+            .unwrap();
+
+        let slots = free_busy.free_slots(&search(from, 30, Some("09:00-17:00")));
+        assert_eq!(slots[0].start, at(3, 10, 0));
+        assert_eq!(slots[0].free_until, at(3, 17, 0));
+        assert_eq!(slots[1].start, at(4, 9, 0));
+    }
+
+    #[test]
+    fn skips_days_off() {
+        // Friday 6 March, after the short Friday is over
+        let from = at(6, 13, 10);
+        let free_busy = FreeBusy::new(from, from + Duration::days(4), &Timezone::utc());
+        let search = SlotSearch {
+            within: Duration::days(4),
+            ..search(from, 60, Some("Mon-Thu 09:00-17:00, Fri 09:00-13:00"))
+        };
+
+        let slots = free_busy.free_slots(&search);
+        assert_eq!(slots[0].start, at(9, 9, 0));
+        assert_eq!(slots[0].free_until, at(9, 17, 0));
+    }
+
+    #[test]
+    fn expands_recurring_and_all_day_events() {
+        let from = at(2, 0, 0);
+        let berlin = Timezone::parse("Europe/Berlin").unwrap();
+        let mut free_busy = FreeBusy::new(from, from + Duration::days(2), &berlin);
+        // Daily standup 08:00-09:00 UTC since last week
+        free_busy
+            .add_event(&timed(at(1, 8, 0), at(1, 9, 0)), Some("FREQ=DAILY"))
+            .unwrap();
+        // Whole local day of 3 March is off
+        free_busy
+            .add_event(
+                &EventTiming::AllDay {
+                    start_date: NaiveDate::from_ymd_opt(2026, 3, 3).unwrap(),
+                    end_date: NaiveDate::from_ymd_opt(2026, 3, 4).unwrap(),
+                },
+                None,
+            )
+            .unwrap();
+
+        let busy = free_busy.busy();
+        assert_eq!(busy[0].start, at(2, 8, 0));
+        assert_eq!(busy[1].start, at(2, 23, 0));
+        assert_eq!(busy[1].end, at(3, 23, 0));
+
+        let slots =
+            free_busy.free_slots(&search_in(at(2, 7, 30), 60, Some("09:00-17:00"), &berlin));
+        // 09:00 Berlin is 08:00 UTC, taken by the standup
+        assert_eq!(slots[0].start, at(2, 9, 0));
+        assert_eq!(slots.len(), 1);
+    }
+
+    #[test]
+    fn rejects_searches_that_cannot_fit() {
+        let base = SlotSearch {
+            from: at(2, 0, 0),
+            duration: Duration::hours(9),
+            within: Duration::days(1),
+            working_hours: Some(WorkingHours::parse("09:00-17:00", &Timezone::utc()).unwrap()),
+            count: 100,
+        };
+        assert!(base.clone().validated().is_err());
+        assert!(
+            SlotSearch {
+                within: Duration::days(MAX_SLOT_SEARCH_DAYS + 1),
+                working_hours: None,
+                ..base.clone()
+            }
+            .validated()
+            .is_err()
+        );
+        let clamped = SlotSearch {
+            working_hours: None,
+            ..base
+        }
+        .validated()
+        .unwrap();
+        assert_eq!(clamped.count, MAX_SLOT_COUNT);
+    }
+}
@@ -0,0 +1,239 @@
+//! The birthdays calendar derived from the user's contacts.
+//!
+//! Users who turn it on get a read-only subscription at `birthdays:contacts`
+//! whose events are generated from the `BDAY` of their CardDAV contacts
+//! instead of fetched: one yearly all-day event per contact, starting on the
+//! next birthday. The refresh task regenerates it every morning, as the
+//! user's working day starts, which also picks up contact edits, and sends a
+//! note about the birthdays falling on the next day.
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveTime, TimeZone, Utc};
+use televent_domain::{EventTiming, Timezone, WorkingHours};
+use televent_storage::contact::Contact;
+use televent_storage::subscription::SubscribedEventWrite;
+
+use crate::subscription::generated_event;
+use crate::vcard::{Birthday, parse_vcard};
+
+/// Address of the birthdays calendar subscription
+pub const BIRTHDAYS_URL: &str = "birthdays:contacts";
+
+/// Name the calendar gets when subscribed without one
+pub const BIRTHDAYS_CALENDAR_NAME: &str = "Birthdays";
+
+/// Local hour the calendar is regenerated and the next day's birthdays are
+/// announced on days without working hours
+pub const BIRTHDAY_DIGEST_HOUR: u32 = 9;
+
+/// Whether a subscription address is the birthdays calendar
+#[must_use]
+pub fn is_birthdays_url(url: &str) -> bool {
+    url.trim().eq_ignore_ascii_case(BIRTHDAYS_URL)
+}
+
+/// A contact with a known birthday
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ContactBirthday {
+    pub uid: String,
+    pub name: String,
+    pub birthday: Birthday,
+}
+
+/// Contacts whose vCard has a usable `BDAY`
+pub(crate) fn contact_birthdays(contacts: &[Contact]) -> Vec<ContactBirthday> {
+    contacts
+        .iter()
+        .filter_map(|contact| {
+            let birthday = parse_vcard(&contact.vcard).ok()?.birthday?;
+            Some(ContactBirthday {
+                uid: contact.uid.clone(),
+                name: contact.full_name.clone(),
+                birthday,
+            })
+        })
+        .collect()
+}
+
+/// The birthday in `year`; February 29th falls on the 28th in other years
+fn birthday_in(birthday: Birthday, year: i32) -> Option<NaiveDate> {
+    NaiveDate::from_ymd_opt(year, birthday.month, birthday.day).or_else(|| {
+        (birthday.month == 2 && birthday.day == 29)
+            .then(|| NaiveDate::from_ymd_opt(year, 2, 28))
+            .flatten()
+    })
+}
+
+/// First birthday on or after `today`
+fn next_birthday(birthday: Birthday, today: NaiveDate) -> Option<NaiveDate> {
+    birthday_in(birthday, today.year())
+        .filter(|date| *date >= today)
+        .or_else(|| birthday_in(birthday, today.year() + 1))
+}
+
+/// Mirror rows for the birthdays, each recurring yearly from the next one
+pub(crate) fn birthday_events(
+    birthdays: &[ContactBirthday],
+    today: NaiveDate,
+) -> Vec<SubscribedEventWrite> {
+    birthdays
+        .iter()
+        .filter_map(|contact| {
+            let start_date = next_birthday(contact.birthday, today)?;
+            let rrule = if contact.birthday.month == 2 && contact.birthday.day == 29 {
+                "FREQ=YEARLY;BYMONTH=2;BYMONTHDAY=-1"
+            } else {
+                "FREQ=YEARLY"
+            };
+            Some(generated_event(
+                format!("birthday-{}", contact.uid),
+                format!("{}'s birthday", contact.name),
+                contact.birthday.year.map(|year| format!("Born {year}")),
+                EventTiming::AllDay {
+                    start_date,
+                    end_date: start_date + Duration::days(1),
+                },
+                Some(rrule.to_string()),
+            ))
+        })
+        .collect()
+}
+
+/// Note about the birthdays falling on the day after `today`, if any
+pub(crate) fn digest_message(birthdays: &[ContactBirthday], today: NaiveDate) -> Option<String> {
+    let tomorrow = today + Duration::days(1);
+    let lines: Vec<String> = birthdays
+        .iter()
+        .filter(|contact| next_birthday(contact.birthday, today) == Some(tomorrow))
+        .map(|contact| match contact.birthday.year {
+            Some(year) => format!(
+                "🎂 {}'s birthday tomorrow (turns {})",
+                contact.name,
+                tomorrow.year() - year
+            ),
+            None => format!("🎂 {}'s birthday tomorrow", contact.name),
+        })
+        .collect();
+    (!lines.is_empty()).then(|| lines.join("\n"))
+}
+
+/// When the calendar is next regenerated: the coming start of a working day,
+/// or the digest hour on days off, in the user's timezone
+pub(crate) fn next_refresh(
+    now: DateTime<Utc>,
+    timezone: &Timezone,
+    working_hours: Option<&WorkingHours>,
+) -> DateTime<Utc> {
+    let tz = timezone.tz();
+    let digest_at = |day: NaiveDate| {
+        let time = working_hours
+            .and_then(|hours| hours.on(day.weekday()))
+            .map_or_else(
+                || NaiveTime::from_hms_opt(BIRTHDAY_DIGEST_HOUR, 0, 0).expect("valid digest hour"),
+                |hours| hours.start,
+            );
+        tz.from_local_datetime(&day.and_time(time))
+            .earliest()
+            .map(|at| at.with_timezone(&Utc))
+    };
+    let today = now.with_timezone(&tz).date_naive();
+    digest_at(today)
+        .filter(|at| *at > now)
+        .or_else(|| digest_at(today + Duration::days(1)))
+        .unwrap_or(now + Duration::days(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn contact(name: &str, month: u32, day: u32, year: Option<i32>) -> ContactBirthday {
+        ContactBirthday {
+            uid: name.to_lowercase(),
+            name: name.to_string(),
+            birthday: Birthday { month, day, year },
+        }
+    }
+
+    #[test]
+    fn events_start_on_the_next_birthday() {
+        let today = date(2026, 10, 18);
+        let birthdays = [
+            contact("Alice", 10, 19, Some(1990)),
+            contact("Bob", 3, 1, None),
+            contact("Carol", 2, 29, None),
+        ];
+        let events = birthday_events(&birthdays, today);
+
+        let starts: Vec<_> = events
+            .iter()
+            .map(|event| match event.timing {
+                EventTiming::AllDay { start_date, .. } => start_date,
+                _ => unreachable!(),
+            })
+            .collect();
+        assert_eq!(
+            starts,
+            [date(2026, 10, 19), date(2027, 3, 1), date(2027, 2, 28)]
+        );
+        assert_eq!(events[0].summary, "Alice's birthday");
+        assert_eq!(events[0].description.as_deref(), Some("Born 1990"));
+        assert_eq!(events[0].uid, "birthday-alice");
+        assert_eq!(
+            events[2].rrule.as_deref(),
+            Some("FREQ=YEARLY;BYMONTH=2;BYMONTHDAY=-1")
+        );
+    }
+
+    #[test]
+    fn digest_lists_tomorrows_birthdays() {
+        let birthdays = [
+            contact("Alice", 10, 19, Some(1990)),
+            contact("Bob", 10, 19, None),
+            contact("Carol", 10, 20, None),
+        ];
+
+        assert_eq!(
+            digest_message(&birthdays, date(2026, 10, 18)).as_deref(),
+            Some("🎂 Alice's birthday tomorrow (turns 36)\n🎂 Bob's birthday tomorrow")
+        );
+        assert_eq!(digest_message(&birthdays, date(2026, 10, 16)), None);
+    }
+
+    #[test]
+    fn refresh_runs_at_the_local_digest_hour() {
+        let berlin = Timezone::parse("Europe/Berlin").unwrap();
+        let before = "2026-06-01T05:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let after = "2026-06-01T08:00:00Z".parse::<DateTime<Utc>>().unwrap();
+
+        assert_eq!(
+            next_refresh(before, &berlin, None),
+            "2026-06-01T07:00:00Z".parse::<DateTime<Utc>>().unwrap()
+        );
+        assert_eq!(
+            next_refresh(after, &berlin, None),
+            "2026-06-02T07:00:00Z".parse::<DateTime<Utc>>().unwrap()
+        );
+    }
+
+    #[test]
+    fn refresh_follows_the_working_day() {
+        let berlin = Timezone::parse("Europe/Berlin").unwrap();
+        let hours = WorkingHours::parse("Mon-Fri 08:00-16:00", &berlin).unwrap();
+        // Monday 1 June 2026, 07:30 in Berlin
+        let monday = "2026-06-01T05:30:00Z".parse::<DateTime<Utc>>().unwrap();
+        assert_eq!(
+            next_refresh(monday, &berlin, Some(&hours)),
+            "2026-06-01T06:00:00Z".parse::<DateTime<Utc>>().unwrap()
+        );
+        // Friday 5 June after work: Saturday is off, so the digest hour
+        let friday = "2026-06-05T15:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        assert_eq!(
+            next_refresh(friday, &berlin, Some(&hours)),
+            "2026-06-06T07:00:00Z".parse::<DateTime<Utc>>().unwrap()
+        );
+    }
+}
@@ -0,0 +1,160 @@
+//! Booking links, a small "book me" page on top of free/busy.
+//!
+//! A link shares a daily window of the owner's time for a few days ahead.
+//! Visitors are offered the free slots inside it, from the owner's own and
+//! subscribed calendars; booking one puts a tentative event with the visitor
+//! as guest on the owner's calendar and tells the owner in Telegram, who
+//! confirms or cancels it like any other event.
+
+use chrono::{DateTime, Duration, Utc};
+use televent_domain::Timezone;
+use televent_storage::booking::BookingLink;
+use uuid::Uuid;
+
+use crate::{ApplicationError, DayHours, MAX_SLOT_COUNT, SlotSearch, UserId, WorkingHours};
+
+/// Shortest slot a link may offer, in minutes.
+pub const MIN_BOOKING_MINUTES: i64 = 5;
+
+/// Longest slot a link may offer, in minutes.
+pub const MAX_BOOKING_MINUTES: i64 = 480;
+
+/// Furthest ahead a link may let visitors book, in days.
+pub const MAX_BOOKING_DAYS: i64 = 31;
+
+/// Links one user may keep at a time.
+pub const MAX_BOOKING_LINKS_PER_USER: usize = 20;
+
+/// Upper bound on a link's title.
+pub const MAX_BOOKING_TITLE_LENGTH: usize = 128;
+
+/// A booking link as its owner and visitors see it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BookingLinkView {
+    pub id: Uuid,
+    pub owner: UserId,
+    pub slug: String,
+    pub title: String,
+    pub duration: Duration,
+    /// Bookable part of each day, in the owner's timezone
+    pub hours: DayHours,
+    pub within_days: i64,
+    pub created_at: DateTime<Utc>,
+}
+
+impl BookingLinkView {
+    /// Slots visitors can pick from at `now`, one page's worth, with the
+    /// hours read in the owner's timezone
+    #[must_use]
+    pub fn search(&self, now: DateTime<Utc>, timezone: &Timezone) -> SlotSearch {
+        SlotSearch {
+            from: now,
+            duration: self.duration,
+            within: Duration::days(self.within_days),
+            working_hours: Some(WorkingHours::every_day(self.hours, timezone)),
+            count: MAX_SLOT_COUNT,
+        }
+    }
+}
+
+impl From<BookingLink> for BookingLinkView {
+    fn from(link: BookingLink) -> Self {
+        Self {
+            id: link.id,
+            owner: link.user_id,
+            slug: link.slug,
+            title: link.title,
+            duration: Duration::minutes(i64::from(link.duration_minutes)),
+            hours: DayHours {
+                start: link.day_start,
+                end: link.day_end,
+            },
+            within_days: i64::from(link.within_days),
+            created_at: link.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CreateBookingLinkCommand {
+    pub user_id: UserId,
+    pub title: String,
+    pub duration: Duration,
+    pub hours: DayHours,
+    pub within_days: i64,
+}
+
+impl CreateBookingLinkCommand {
+    pub(crate) fn validate(&self) -> Result<(), ApplicationError> {
+        let title = self.title.trim();
+        if title.is_empty() {
+            return Err(ApplicationError::BadRequest(
+                "Title cannot be empty".to_string(),
+            ));
+        }
+        televent_domain::validate_length("Title", title, MAX_BOOKING_TITLE_LENGTH)
+            .map_err(ApplicationError::BadRequest)?;
+        televent_domain::validate_no_control_chars("Title", title)
+            .map_err(ApplicationError::BadRequest)?;
+
+        let minutes = self.duration.num_minutes();
+        if self.duration != Duration::minutes(minutes)
+            || !(MIN_BOOKING_MINUTES..=MAX_BOOKING_MINUTES).contains(&minutes)
+        {
+            return Err(ApplicationError::BadRequest(format!(
+                "Slots must be whole minutes between {MIN_BOOKING_MINUTES} and \
+                 {MAX_BOOKING_MINUTES}"
+            )));
+        }
+        if self.duration > self.hours.length() {
+            return Err(ApplicationError::BadRequest(
+                "Slots must fit inside the bookable hours".to_string(),
+            ));
+        }
+        if !(1..=MAX_BOOKING_DAYS).contains(&self.within_days) {
+            return Err(ApplicationError::BadRequest(format!(
+                "Links can open between 1 and {MAX_BOOKING_DAYS} days ahead"
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+/// A visitor booking a slot on a link's page.
+#[derive(Debug, Clone)]
+pub struct BookSlotCommand {
+    pub slug: String,
+    pub start: DateTime<Utc>,
+    pub name: String,
+    pub email: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn command(minutes: i64, hours: &str, within_days: i64) -> CreateBookingLinkCommand {
+        CreateBookingLinkCommand {
+            user_id: UserId::new(1),
+            title: "Intro call".to_string(),
+            duration: Duration::minutes(minutes),
+            hours: DayHours::parse(hours).unwrap(),
+            within_days,
+        }
+    }
+
+    #[test]
+    fn validates_slot_length_hours_and_reach() {
+        assert!(command(30, "09:00-17:00", 14).validate().is_ok());
+        assert!(command(2, "09:00-17:00", 14).validate().is_err());
+        assert!(command(600, "09:00-19:00", 14).validate().is_err());
+        assert!(command(90, "09:00-10:00", 14).validate().is_err());
+        assert!(command(30, "09:00-17:00", 0).validate().is_err());
+        assert!(command(30, "09:00-17:00", 32).validate().is_err());
+
+        let mut blank = command(30, "09:00-17:00", 14);
+        blank.title = "  ".to_string();
+        assert!(blank.validate().is_err());
+    }
+}
@@ -0,0 +1,337 @@
+//! Slack and Microsoft Teams incoming webhooks.
+//!
+//! Some teams live in a chat app rather than in Telegram. A user can register
+//! incoming webhooks; the worker copies the invites, reminders, changes and
+//! cancellations it sends them to each one as a `chat_webhook` message.
+//!
+//! Webhook URLs are bearer secrets: only hosts of the two providers are
+//! accepted, which also keeps the worker from posting into our own network,
+//! and views only ever show the end of the URL.
+
+use chrono::{DateTime, Utc};
+use televent_domain::{ChatWebhookMessage, ChatWebhookProvider, NotificationTopic, OutboxPayload};
+use televent_storage::chat_webhook::{ChatWebhookRecord, ChatWebhookRepository};
+use url::{Host, Url};
+use uuid::Uuid;
+
+use crate::{ApplicationError, UserId, storage_error};
+
+pub const MAX_CHAT_WEBHOOKS_PER_USER: i64 = 5;
+const MAX_CHAT_WEBHOOK_URL_LENGTH: usize = 2048;
+/// Trailing characters of a webhook URL shown back to its owner
+const VISIBLE_URL_SUFFIX: usize = 4;
+
+#[derive(Clone)]
+pub struct ChatWebhookService {
+    webhooks: ChatWebhookRepository,
+}
+
+#[derive(Debug, Clone)]
+pub struct AddChatWebhookCommand {
+    pub user_id: UserId,
+    pub provider: ChatWebhookProvider,
+    pub url: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct ChatWebhookView {
+    pub id: Uuid,
+    pub provider: ChatWebhookProvider,
+    /// Host and the last few characters; the rest is secret
+    pub masked_url: String,
+    /// Why the provider last refused a post for good
+    pub last_error: Option<String>,
+    pub last_error_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Where the worker posts one `chat_webhook` message
+#[derive(Debug, Clone)]
+pub struct ChatWebhookTarget {
+    pub provider: ChatWebhookProvider,
+    pub url: String,
+}
+
+/// A notice as the chat apps show it: a bold title and plain-text lines.
+/// Text messages get the same notice, as plain text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChatNotice {
+    /// Which row of the notification preference matrix decides the copies
+    pub topic: NotificationTopic,
+    pub title: String,
+    pub lines: Vec<String>,
+}
+
+impl ChatWebhookService {
+    #[must_use]
+    pub fn new(webhooks: ChatWebhookRepository) -> Self {
+        Self { webhooks }
+    }
+
+    pub async fn add_webhook(
+        &self,
+        command: AddChatWebhookCommand,
+    ) -> Result<ChatWebhookView, ApplicationError> {
+        let url = normalize_chat_webhook_url(command.provider, &command.url)?;
+
+        let mut tx = self.webhooks.begin().await.map_err(storage_error)?;
+        tx.ensure_user(command.user_id.inner(), None)
+            .await
+            .map_err(storage_error)?;
+        let count = tx
+            .count_webhooks(command.user_id)
+            .await
+            .map_err(storage_error)?;
+        if count >= MAX_CHAT_WEBHOOKS_PER_USER {
+            return Err(ApplicationError::BadRequest(format!(
+                "Maximum number of chat webhooks ({MAX_CHAT_WEBHOOKS_PER_USER}) reached"
+            )));
+        }
+        let webhook = tx
+            .insert_webhook(command.user_id, command.provider.as_sql(), &url)
+            .await
+            .map_err(storage_error)?
+            .ok_or_else(|| {
+                ApplicationError::Conflict("This webhook is already connected".to_string())
+            })?;
+        tx.commit().await.map_err(storage_error)?;
+
+        ChatWebhookView::try_from(webhook)
+    }
+
+    pub async fn list_webhooks(
+        &self,
+        user_id: UserId,
+    ) -> Result<Vec<ChatWebhookView>, ApplicationError> {
+        self.webhooks
+            .list_webhooks(user_id)
+            .await
+            .map_err(storage_error)?
+            .into_iter()
+            .map(ChatWebhookView::try_from)
+            .collect()
+    }
+
+    pub async fn remove_webhook(
+        &self,
+        user_id: UserId,
+        webhook_id: Uuid,
+    ) -> Result<bool, ApplicationError> {
+        self.webhooks
+            .delete_webhook(user_id, webhook_id)
+            .await
+            .map_err(storage_error)
+    }
+
+    /// Queue a copy of `notice` for each of the user's webhooks, unless they
+    /// turned the notice's topic off for chat apps.
+    ///
+    /// Copies are keyed by `source_id`, so queueing again for the same source
+    /// adds nothing. Returns how many copies were queued.
+    pub async fn queue_copies(
+        &self,
+        user_id: UserId,
+        source_id: Uuid,
+        notice: &ChatNotice,
+    ) -> Result<usize, ApplicationError> {
+        if !self
+            .webhooks
+            .topic_enabled(user_id, notice.topic)
+            .await
+            .map_err(storage_error)?
+        {
+            return Ok(0);
+        }
+        let messages = self
+            .webhooks
+            .list_webhooks(user_id)
+            .await
+            .map_err(storage_error)?
+            .into_iter()
+            .map(|webhook| {
+                OutboxPayload::ChatWebhook(ChatWebhookMessage {
+                    webhook_id: webhook.id,
+                    user_id: user_id.inner(),
+                    source_id,
+                    title: notice.title.clone(),
+                    lines: notice.lines.clone(),
+                })
+            })
+            .collect::<Vec<_>>();
+        self.webhooks
+            .queue_outbox(&messages)
+            .await
+            .map_err(storage_error)?;
+
+        Ok(messages.len())
+    }
+
+    /// Where to post; `None` once the owner removed the webhook
+    pub async fn target(
+        &self,
+        webhook_id: Uuid,
+    ) -> Result<Option<ChatWebhookTarget>, ApplicationError> {
+        let Some(webhook) = self
+            .webhooks
+            .get_webhook(webhook_id)
+            .await
+            .map_err(storage_error)?
+        else {
+            return Ok(None);
+        };
+
+        Ok(Some(ChatWebhookTarget {
+            provider: parse_provider(&webhook.provider)?,
+            url: webhook.url,
+        }))
+    }
+
+    /// Record the outcome of a post: `Some` when the provider refused it for
+    /// good, `None` once a post went through
+    pub async fn record_outcome(
+        &self,
+        webhook_id: Uuid,
+        error: Option<&str>,
+    ) -> Result<(), ApplicationError> {
+        self.webhooks
+            .set_last_error(webhook_id, error)
+            .await
+            .map_err(storage_error)
+    }
+}
+
+impl TryFrom<ChatWebhookRecord> for ChatWebhookView {
+    type Error = ApplicationError;
+
+    fn try_from(webhook: ChatWebhookRecord) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: webhook.id,
+            provider: parse_provider(&webhook.provider)?,
+            masked_url: mask_url(&webhook.url),
+            last_error: webhook.last_error,
+            last_error_at: webhook.last_error_at,
+            created_at: webhook.created_at,
+        })
+    }
+}
+
+fn parse_provider(value: &str) -> Result<ChatWebhookProvider, ApplicationError> {
+    ChatWebhookProvider::parse(value)
+        .ok_or_else(|| ApplicationError::Internal(format!("unknown chat webhook provider {value}")))
+}
+
+/// Check that `raw` is an HTTPS incoming-webhook URL of `provider`.
+///
+/// Slack webhooks live on `hooks.slack.com`; Teams webhooks on
+/// `*.webhook.office.com`, or on Power Automate hosts for the workflow-based
+/// webhooks that replace them.
+pub fn normalize_chat_webhook_url(
+    provider: ChatWebhookProvider,
+    raw: &str,
+) -> Result<String, ApplicationError> {
+    let raw = raw.trim();
+    if raw.len() > MAX_CHAT_WEBHOOK_URL_LENGTH {
+        return Err(ApplicationError::BadRequest(format!(
+            "Webhook URL too long (max {MAX_CHAT_WEBHOOK_URL_LENGTH} characters)"
+        )));
+    }
+    let url =
+        Url::parse(raw).map_err(|_| ApplicationError::BadRequest("Invalid webhook URL".into()))?;
+    if url.scheme() != "https" || url.port().is_some() {
+        return Err(ApplicationError::BadRequest(
+            "Webhook URL must start with https:// and use the default port".to_string(),
+        ));
+    }
+
+    let host = match url.host() {
+        Some(Host::Domain(domain)) => domain.trim_end_matches('.').to_ascii_lowercase(),
+        _ => String::new(),
+    };
+    let allowed = match provider {
+        ChatWebhookProvider::Slack => host == "hooks.slack.com",
+        ChatWebhookProvider::Teams => [
+            ".webhook.office.com",
+            ".logic.azure.com",
+            ".powerplatform.com",
+        ]
+        .iter()
+        .any(|suffix| host.ends_with(suffix)),
+    };
+    if !allowed {
+        return Err(ApplicationError::BadRequest(format!(
+            "Not a {} incoming webhook URL",
+            match provider {
+                ChatWebhookProvider::Slack => "Slack",
+                ChatWebhookProvider::Teams => "Microsoft Teams",
+            }
+        )));
+    }
+
+    Ok(url.to_string())
+}
+
+fn mask_url(url: &str) -> String {
+    let host = Url::parse(url)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_string))
+        .unwrap_or_default();
+    let suffix_start = url
+        .char_indices()
+        .rev()
+        .nth(VISIBLE_URL_SUFFIX - 1)
+        .map_or(0, |(index, _)| index);
+
+    format!("https://{host}/…{}", &url[suffix_start..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_webhook_url_must_belong_to_provider() {
+        let slack = "https://hooks.slack.com/services/T000/B000/XXXXXXXX";
+        assert_eq!(
+            normalize_chat_webhook_url(ChatWebhookProvider::Slack, &format!(" {slack} ")).unwrap(),
+            slack
+        );
+        assert!(
+            normalize_chat_webhook_url(
+                ChatWebhookProvider::Teams,
+                "https://contoso.webhook.office.com/webhookb2/abc"
+            )
+            .is_ok()
+        );
+
+        for (provider, url) in [
+            (ChatWebhookProvider::Teams, slack),
+            (
+                ChatWebhookProvider::Slack,
+                "http://hooks.slack.com/services/T000",
+            ),
+            (
+                ChatWebhookProvider::Slack,
+                "https://hooks.slack.com:8443/services/T000",
+            ),
+            (
+                ChatWebhookProvider::Slack,
+                "https://hooks.slack.com.evil.example/services",
+            ),
+            (ChatWebhookProvider::Teams, "https://10.0.0.1/webhook"),
+            (ChatWebhookProvider::Slack, "not a url"),
+        ] {
+            assert!(
+                normalize_chat_webhook_url(provider, url).is_err(),
+                "{url} accepted for {provider:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_views_hide_the_secret_part_of_the_url() {
+        assert_eq!(
+            mask_url("https://hooks.slack.com/services/T000/B000/XXXXabcd"),
+            "https://hooks.slack.com/…abcd"
+        );
+    }
+}
@@ -0,0 +1,577 @@
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+use televent_domain::{MAX_UID_LENGTH, UserId, parse_internal_email_telegram_id};
+use televent_storage::contact::{Contact, ContactRepository, FrequentAttendee, StoredContact};
+
+use crate::vcard::parse_vcard;
+use crate::{ApplicationError, storage_error};
+
+pub const MAX_CONTACTS_PER_USER: i64 = 2000;
+pub const DEFAULT_CONTACT_SEARCH_LIMIT: usize = 10;
+pub const MAX_CONTACT_SEARCH_LIMIT: usize = 50;
+/// How many past invitees are considered before filtering by the query
+const FREQUENT_ATTENDEE_WINDOW: i64 = 200;
+
+#[derive(Clone)]
+pub struct ContactService {
+    contacts: ContactRepository,
+}
+
+impl ContactService {
+    #[must_use]
+    pub const fn new(contacts: ContactRepository) -> Self {
+        Self { contacts }
+    }
+
+    pub async fn list_contacts(
+        &self,
+        user_id: UserId,
+    ) -> Result<Vec<ContactView>, ApplicationError> {
+        Ok(self
+            .contacts
+            .list_contacts(user_id)
+            .await
+            .map_err(storage_error)?
+            .into_iter()
+            .map(ContactView::from)
+            .collect())
+    }
+
+    pub async fn get_contact(
+        &self,
+        user_id: UserId,
+        uid: &str,
+    ) -> Result<Option<ContactView>, ApplicationError> {
+        Ok(self
+            .contacts
+            .get_contact(user_id, uid)
+            .await
+            .map_err(storage_error)?
+            .map(ContactView::from))
+    }
+
+    pub async fn get_contacts_by_uids(
+        &self,
+        user_id: UserId,
+        uids: &[&str],
+    ) -> Result<Vec<ContactView>, ApplicationError> {
+        Ok(self
+            .contacts
+            .get_contacts_by_uids(user_id, uids)
+            .await
+            .map_err(storage_error)?
+            .into_iter()
+            .map(ContactView::from)
+            .collect())
+    }
+
+    /// CardDAV `getctag` of the user's address book.
+    ///
+    /// Any insert or update moves the newest modification time and any delete
+    /// changes the count, so the pair changes with every write.
+    pub async fn address_book_ctag(&self, user_id: UserId) -> Result<String, ApplicationError> {
+        let state = self
+            .contacts
+            .address_book_state(user_id)
+            .await
+            .map_err(storage_error)?;
+        Ok(format!(
+            "{}-{}",
+            state.contact_count,
+            state
+                .last_modified
+                .map_or(0, |modified| modified.timestamp_micros())
+        ))
+    }
+
+    /// Create or replace the contact stored at `uid` from a client's vCard.
+    pub async fn put_contact(
+        &self,
+        command: PutContactCommand,
+    ) -> Result<PutContactResult, ApplicationError> {
+        validate_contact_uid(&command.uid)?;
+        let parsed = parse_vcard(&command.vcard)?;
+        let etag = contact_etag(&command.vcard);
+
+        let mut tx = self.contacts.begin().await.map_err(storage_error)?;
+        tx.ensure_user(command.user_id.inner(), command.username.as_deref())
+            .await
+            .map_err(storage_error)?;
+
+        let existing = tx
+            .lock_contact(command.user_id, &command.uid)
+            .await
+            .map_err(storage_error)?;
+        check_precondition(
+            existing.as_ref(),
+            command.expected_etag.as_deref(),
+            command.create_only,
+        )?;
+        if let Some(contact) = &existing
+            && contact.etag == etag
+        {
+            return Ok(PutContactResult {
+                etag,
+                created: false,
+            });
+        }
+
+        if existing.is_none() {
+            let count = tx
+                .count_contacts(command.user_id)
+                .await
+                .map_err(storage_error)?;
+            if count >= MAX_CONTACTS_PER_USER {
+                return Err(ApplicationError::BadRequest(format!(
+                    "Maximum number of contacts ({MAX_CONTACTS_PER_USER}) reached"
+                )));
+            }
+        }
+
+        let contact = tx
+            .upsert_contact(StoredContact {
+                user_id: command.user_id,
+                uid: command.uid,
+                full_name: parsed.full_name,
+                email: parsed.email,
+                telegram_username: parsed.telegram_username,
+                vcard: command.vcard,
+                etag,
+            })
+            .await
+            .map_err(storage_error)?;
+        tx.commit().await.map_err(storage_error)?;
+
+        Ok(PutContactResult {
+            etag: contact.etag,
+            created: existing.is_none(),
+        })
+    }
+
+    /// Returns `false` when there was no such contact.
+    pub async fn delete_contact(
+        &self,
+        user_id: UserId,
+        uid: &str,
+        expected_etag: Option<&str>,
+    ) -> Result<bool, ApplicationError> {
+        let mut tx = self.contacts.begin().await.map_err(storage_error)?;
+        let Some(existing) = tx.lock_contact(user_id, uid).await.map_err(storage_error)? else {
+            return Ok(false);
+        };
+        check_precondition(Some(&existing), expected_etag, false)?;
+
+        let deleted = tx
+            .delete_contact(user_id, uid)
+            .await
+            .map_err(storage_error)?;
+        tx.commit().await.map_err(storage_error)?;
+        Ok(deleted)
+    }
+
+    /// Contacts matching `query`, best match first.
+    ///
+    /// An empty query lists the address book alphabetically.
+    pub async fn search_contacts(
+        &self,
+        user_id: UserId,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<ContactView>, ApplicationError> {
+        let limit = limit.clamp(1, MAX_CONTACT_SEARCH_LIMIT);
+        let contacts = self.list_contacts(user_id).await?;
+        if normalize_query(query).is_empty() {
+            return Ok(contacts.into_iter().take(limit).collect());
+        }
+
+        Ok(rank_contacts(contacts, query)
+            .into_iter()
+            .take(limit)
+            .map(|(contact, _)| contact)
+            .collect())
+    }
+
+    /// The one contact `query` clearly refers to, if any.
+    ///
+    /// Ties between the best matches are ambiguous and resolve to nothing, as
+    /// do contacts without an email or Telegram username to invite.
+    pub async fn find_contact(
+        &self,
+        user_id: UserId,
+        query: &str,
+    ) -> Result<Option<ContactView>, ApplicationError> {
+        if normalize_query(query).is_empty() {
+            return Ok(None);
+        }
+
+        let mut ranked = rank_contacts(self.list_contacts(user_id).await?, query)
+            .into_iter()
+            .filter(|(contact, _)| contact.email.is_some() || contact.telegram_username.is_some());
+        let Some((best, best_score)) = ranked.next() else {
+            return Ok(None);
+        };
+        match ranked.next() {
+            Some((_, runner_up)) if runner_up == best_score => Ok(None),
+            _ => Ok(Some(best)),
+        }
+    }
+
+    /// People the user invited before, most frequent first.
+    ///
+    /// Names come from the address book when a contact shares the invitee's
+    /// email or Telegram username. A non-empty query filters and ranks the
+    /// suggestions like [`Self::search_contacts`], ties going to whoever was
+    /// invited more often.
+    pub async fn suggest_attendees(
+        &self,
+        user_id: UserId,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<AttendeeSuggestion>, ApplicationError> {
+        let limit = limit.clamp(1, MAX_CONTACT_SEARCH_LIMIT);
+        let attendees = self
+            .contacts
+            .frequent_attendees(user_id, FREQUENT_ATTENDEE_WINDOW)
+            .await
+            .map_err(storage_error)?;
+        if attendees.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let contacts = self.list_contacts(user_id).await?;
+        let suggestions = attendees
+            .into_iter()
+            .map(|attendee| AttendeeSuggestion::new(attendee, &contacts));
+
+        let query = normalize_query(query);
+        if query.is_empty() {
+            return Ok(suggestions.take(limit).collect());
+        }
+
+        // Stable sort keeps the frequency order among equal scores
+        let mut ranked: Vec<_> = suggestions
+            .filter_map(|suggestion| {
+                let score = [
+                    Some(suggestion.name.as_str()),
+                    suggestion.email.as_deref(),
+                    suggestion.telegram_username.as_deref(),
+                ]
+                .into_iter()
+                .flatten()
+                .filter_map(|field| fuzzy_score(&query, field))
+                .max()?;
+                Some((suggestion, score))
+            })
+            .collect();
+        ranked.sort_by(|(_, left), (_, right)| right.cmp(left));
+
+        Ok(ranked
+            .into_iter()
+            .take(limit)
+            .map(|(suggestion, _)| suggestion)
+            .collect())
+    }
+}
+
+/// Someone worth inviting again
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttendeeSuggestion {
+    /// Address book name, else `@username`, else the email
+    pub name: String,
+    /// External email; `None` for Telegram users
+    pub email: Option<String>,
+    pub telegram_id: Option<i64>,
+    pub telegram_username: Option<String>,
+    pub invite_count: i64,
+    pub last_invited_at: DateTime<Utc>,
+}
+
+impl AttendeeSuggestion {
+    fn new(attendee: FrequentAttendee, contacts: &[ContactView]) -> Self {
+        let telegram_id = attendee
+            .telegram_id
+            .or_else(|| parse_internal_email_telegram_id(&attendee.email));
+        let email = telegram_id.is_none().then_some(attendee.email);
+
+        let contact = contacts.iter().find(|contact| {
+            let same_email = email.is_some() && contact.email == email;
+            let same_username = attendee.telegram_username.as_ref().is_some_and(|username| {
+                contact
+                    .telegram_username
+                    .as_ref()
+                    .is_some_and(|candidate| candidate.eq_ignore_ascii_case(username))
+            });
+            same_email || same_username
+        });
+        let name = contact
+            .map(|contact| contact.full_name.clone())
+            .or_else(|| {
+                attendee
+                    .telegram_username
+                    .as_ref()
+                    .map(|username| format!("@{username}"))
+            })
+            .or_else(|| email.clone())
+            .unwrap_or_else(|| format!("Telegram user {}", telegram_id.unwrap_or_default()));
+
+        Self {
+            name,
+            email,
+            telegram_id,
+            telegram_username: attendee.telegram_username,
+            invite_count: attendee.invite_count,
+            last_invited_at: attendee.last_invited_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PutContactCommand {
+    pub user_id: UserId,
+    pub username: Option<String>,
+    /// Resource name below the address book, without `.vcf`
+    pub uid: String,
+    pub vcard: String,
+    /// `If-Match`: the quoted etag the client last saw, or `*`
+    pub expected_etag: Option<String>,
+    /// `If-None-Match: *`: only create, never overwrite
+    pub create_only: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct PutContactResult {
+    pub etag: String,
+    pub created: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct ContactView {
+    pub uid: String,
+    pub full_name: String,
+    pub email: Option<String>,
+    pub telegram_username: Option<String>,
+    pub vcard: String,
+    pub etag: String,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<Contact> for ContactView {
+    fn from(value: Contact) -> Self {
+        Self {
+            uid: value.uid,
+            full_name: value.full_name,
+            email: value.email,
+            telegram_username: value.telegram_username,
+            vcard: value.vcard,
+            etag: value.etag,
+            updated_at: value.updated_at,
+        }
+    }
+}
+
+fn validate_contact_uid(uid: &str) -> Result<(), ApplicationError> {
+    if uid.is_empty() || uid.len() > MAX_UID_LENGTH {
+        return Err(ApplicationError::BadRequest(format!(
+            "Contact resource name must be 1 to {MAX_UID_LENGTH} bytes"
+        )));
+    }
+    if uid.contains('/') || uid.chars().any(char::is_control) {
+        return Err(ApplicationError::BadRequest(
+            "Contact resource name cannot contain slashes or control characters".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn check_precondition(
+    existing: Option<&Contact>,
+    expected_etag: Option<&str>,
+    create_only: bool,
+) -> Result<(), ApplicationError> {
+    if create_only && existing.is_some() {
+        return Err(ApplicationError::Conflict(
+            "Contact already exists".to_string(),
+        ));
+    }
+    match (existing, expected_etag) {
+        (_, None) | (Some(_), Some("*")) => Ok(()),
+        (Some(contact), Some(expected)) if expected == format!("\"{}\"", contact.etag) => Ok(()),
+        (Some(contact), Some(expected)) => Err(ApplicationError::Conflict(format!(
+            "ETag mismatch: {} != \"{}\"",
+            expected, contact.etag
+        ))),
+        (None, Some(_)) => Err(ApplicationError::Conflict(
+            "Contact does not exist".to_string(),
+        )),
+    }
+}
+
+fn contact_etag(vcard: &str) -> String {
+    format!("{:x}", Sha256::digest(vcard.as_bytes()))
+}
+
+fn normalize_query(query: &str) -> String {
+    query.trim().trim_start_matches('@').to_lowercase()
+}
+
+/// Contacts that match `query` with their scores, best first
+fn rank_contacts(contacts: Vec<ContactView>, query: &str) -> Vec<(ContactView, u32)> {
+    let query = normalize_query(query);
+    let mut ranked: Vec<_> = contacts
+        .into_iter()
+        .filter_map(|contact| {
+            let score = [
+                Some(contact.full_name.as_str()),
+                contact.email.as_deref(),
+                contact.telegram_username.as_deref(),
+            ]
+            .into_iter()
+            .flatten()
+            .filter_map(|field| fuzzy_score(&query, field))
+            .max()?;
+            Some((contact, score))
+        })
+        .collect();
+    ranked.sort_by(|(left, left_score), (right, right_score)| {
+        right_score
+            .cmp(left_score)
+            .then_with(|| left.full_name.cmp(&right.full_name))
+    });
+    ranked
+}
+
+/// How well a lowercase `query` matches `text`, higher is better.
+///
+/// Whole-field and prefix matches beat word prefixes, which beat substrings;
+/// a query whose characters only appear in order (`"jdoe"` in `"John Doe"`)
+/// still matches, ranked by how tightly its characters cluster.
+pub fn fuzzy_score(query: &str, text: &str) -> Option<u32> {
+    if query.is_empty() {
+        return None;
+    }
+    let text = text.to_lowercase();
+
+    if text == query {
+        return Some(1000);
+    }
+    if text.starts_with(query) {
+        return Some(800);
+    }
+    if text
+        .split(|c: char| c.is_whitespace() || matches!(c, '.' | '_' | '-' | '@'))
+        .any(|word| word.starts_with(query))
+    {
+        return Some(600);
+    }
+    if text.contains(query) {
+        return Some(400);
+    }
+
+    // Subsequence match: every query character in order, penalised by gaps
+    let mut query_chars = query.chars().filter(|c| !c.is_whitespace()).peekable();
+    let mut first = None;
+    let mut last = 0;
+    for (index, c) in text.chars().enumerate() {
+        if query_chars.peek() == Some(&c) {
+            query_chars.next();
+            first.get_or_insert(index);
+            last = index;
+        }
+    }
+    if query_chars.peek().is_some() {
+        return None;
+    }
+    let span = last - first.unwrap_or(0) + 1;
+    let gaps = u32::try_from(span.saturating_sub(query.chars().count())).unwrap_or(u32::MAX);
+    Some(200u32.saturating_sub(gaps.saturating_mul(10)).max(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contact(name: &str, email: Option<&str>, username: Option<&str>) -> ContactView {
+        ContactView {
+            uid: name.to_lowercase().replace(' ', "-"),
+            full_name: name.to_string(),
+            email: email.map(str::to_string),
+            telegram_username: username.map(str::to_string),
+            vcard: String::new(),
+            etag: String::new(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_fuzzy_score_orders_match_kinds() {
+        let exact = fuzzy_score("alice", "Alice").unwrap();
+        let prefix = fuzzy_score("ali", "Alice Liddell").unwrap();
+        let word = fuzzy_score("lid", "Alice Liddell").unwrap();
+        let substring = fuzzy_score("idd", "Alice Liddell").unwrap();
+        let subsequence = fuzzy_score("alld", "Alice Liddell").unwrap();
+
+        assert!(exact > prefix && prefix > word && word > substring && substring > subsequence);
+        assert_eq!(fuzzy_score("xyz", "Alice Liddell"), None);
+        assert_eq!(fuzzy_score("", "Alice"), None);
+    }
+
+    #[test]
+    fn test_attendee_suggestion_prefers_address_book_name() {
+        let contacts = vec![
+            contact("Alice Liddell", Some("alice@example.com"), None),
+            contact("Bob Stone", None, Some("Bob_Stone")),
+        ];
+        let attendee =
+            |email: &str, telegram_id: Option<i64>, username: Option<&str>| FrequentAttendee {
+                email: email.to_string(),
+                telegram_id,
+                telegram_username: username.map(str::to_string),
+                invite_count: 1,
+                last_invited_at: Utc::now(),
+            };
+
+        let alice = AttendeeSuggestion::new(attendee("alice@example.com", None, None), &contacts);
+        assert_eq!(alice.name, "Alice Liddell");
+        assert_eq!(alice.email.as_deref(), Some("alice@example.com"));
+
+        let bob = AttendeeSuggestion::new(
+            attendee("tg_7@televent.internal", Some(7), Some("bob_stone")),
+            &contacts,
+        );
+        assert_eq!(bob.name, "Bob Stone");
+        assert_eq!(bob.email, None);
+        assert_eq!(bob.telegram_id, Some(7));
+
+        let carol = AttendeeSuggestion::new(
+            attendee("tg_9@televent.internal", None, Some("carol")),
+            &contacts,
+        );
+        assert_eq!(carol.name, "@carol");
+        assert_eq!(carol.telegram_id, Some(9));
+    }
+
+    #[test]
+    fn test_rank_contacts_matches_any_field() {
+        let contacts = vec![
+            contact("Bob Stone", Some("bob@example.com"), None),
+            contact(
+                "Alice Liddell",
+                Some("al@example.com"),
+                Some("wonder_alice"),
+            ),
+            contact("Alan Turing", None, Some("alan_t")),
+        ];
+
+        let ranked = rank_contacts(contacts, "@wonder");
+        assert_eq!(ranked.len(), 1);
+        assert_eq!(ranked[0].0.full_name, "Alice Liddell");
+
+        let contacts = vec![
+            contact("Alice Liddell", None, None),
+            contact("Alan Turing", None, None),
+        ];
+        let ranked = rank_contacts(contacts, "al");
+        assert_eq!(ranked.len(), 2);
+        assert_eq!(ranked[0].0.full_name, "Alan Turing");
+    }
+}
@@ -0,0 +1,85 @@
+//! Calendar delegation: an owner lets other users write to their calendar.
+//!
+//! A delegate creates, edits and deletes events on the owner's calendar
+//! through the same [`EventService`](crate::EventService) use cases as the
+//! owner, with every change attributed to whoever made it in the event's
+//! revision history. Without a grant the owner's calendar looks like it
+//! doesn't exist.
+
+use chrono::{DateTime, Utc};
+use televent_storage::delegation::DelegateRecord;
+use televent_storage::revision::EventRevision;
+use uuid::Uuid;
+
+use crate::UserId;
+
+/// Delegates one user may keep at a time.
+pub const MAX_DELEGATES_PER_USER: usize = 20;
+
+/// The other side of a delegation grant: a delegate from the owner's point
+/// of view, or an owner from the delegate's.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DelegateView {
+    pub user_id: UserId,
+    pub username: Option<String>,
+    pub granted_at: DateTime<Utc>,
+}
+
+impl From<DelegateRecord> for DelegateView {
+    fn from(record: DelegateRecord) -> Self {
+        Self {
+            user_id: record.user_id,
+            username: record.username,
+            granted_at: record.created_at,
+        }
+    }
+}
+
+/// What a revision did to its event
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RevisionAction {
+    Created,
+    Updated,
+    Deleted,
+}
+
+impl RevisionAction {
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Created => "created",
+            Self::Updated => "updated",
+            Self::Deleted => "deleted",
+        }
+    }
+
+    fn parse(value: &str) -> Self {
+        match value {
+            "created" => Self::Created,
+            "deleted" => Self::Deleted,
+            _ => Self::Updated,
+        }
+    }
+}
+
+/// One change in an event's history and who made it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EventRevisionView {
+    pub event_id: Uuid,
+    pub actor: UserId,
+    pub actor_username: Option<String>,
+    pub action: RevisionAction,
+    pub at: DateTime<Utc>,
+}
+
+impl From<EventRevision> for EventRevisionView {
+    fn from(revision: EventRevision) -> Self {
+        Self {
+            event_id: revision.event_id,
+            actor: revision.actor_id,
+            actor_username: revision.actor_username,
+            action: RevisionAction::parse(&revision.action),
+            at: revision.created_at,
+        }
+    }
+}
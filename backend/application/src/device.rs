@@ -1,28 +1,136 @@
 use argon2::{
-    Argon2,
+    Algorithm, Argon2, Params, PasswordHash, Version,
     password_hash::{PasswordHasher, SaltString},
 };
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
 use rand::RngExt;
-use televent_storage::device::{DevicePasswordHash, DeviceRepository, StoredDevicePassword};
-use uuid::Uuid;
+use std::sync::Arc;
+use televent_domain::DeviceId;
+use televent_storage::device::{DevicePasswordHash, NewDeviceActivity, StoredDevicePassword};
+use televent_storage::repos::{DevicesRepo, SharedDevicesRepo};
 
-use crate::{ApplicationError, UserId, storage_error};
+use crate::{
+    ApplicationError, DomainEvent, DomainEventBus, SequenceConflict, SharedClock, SystemClock,
+    UserId, storage_error,
+};
 
 pub const PASSWORD_LEN: usize = 24;
 const MAX_DEVICE_NAME_LENGTH: usize = 128;
 const MIN_DEVICE_NAME_LENGTH: usize = 1;
 const MAX_DEVICES_PER_USER: i64 = 10;
+/// Requests kept per device for sync diagnostics
+const DEVICE_ACTIVITY_KEPT: i64 = 50;
+/// How far back a device's request count in listings reaches
+const RECENT_ACTIVITY_WINDOW_HOURS: i64 = 24;
+
+/// Argon2id cost of new device password hashes. Each hash records the
+/// parameters it was made with, so older hashes keep verifying after a
+/// change and are rehashed on their next successful login.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PasswordHashParams {
+    /// Memory cost in KiB
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+impl Default for PasswordHashParams {
+    fn default() -> Self {
+        Self {
+            memory_kib: Params::DEFAULT_M_COST,
+            iterations: Params::DEFAULT_T_COST,
+            parallelism: Params::DEFAULT_P_COST,
+        }
+    }
+}
+
+impl PasswordHashParams {
+    /// Fails if Argon2 rejects the combination, e.g. less than
+    /// `8 * parallelism` KiB of memory
+    pub fn new(
+        memory_kib: u32,
+        iterations: u32,
+        parallelism: u32,
+    ) -> Result<Self, ApplicationError> {
+        let params = Self {
+            memory_kib,
+            iterations,
+            parallelism,
+        };
+        params.argon2()?;
+        Ok(params)
+    }
+
+    fn argon2(self) -> Result<Argon2<'static>, ApplicationError> {
+        let params = Params::new(self.memory_kib, self.iterations, self.parallelism, None)
+            .map_err(|err| {
+                ApplicationError::BadRequest(format!("Invalid Argon2 parameters: {err}"))
+            })?;
+        Ok(Argon2::new(Algorithm::Argon2id, Version::V0x13, params))
+    }
+
+    /// Whether `hash` was made with other parameters than these; hashes that
+    /// do not parse are left alone
+    #[must_use]
+    pub fn is_outdated(self, hash: &str) -> bool {
+        let Ok(hash) = PasswordHash::new(hash) else {
+            return false;
+        };
+        if hash.algorithm != Algorithm::Argon2id.ident() || hash.version != Some(0x13) {
+            return true;
+        }
+        Params::try_from(&hash).is_ok_and(|params| {
+            params.m_cost() != self.memory_kib
+                || params.t_cost() != self.iterations
+                || params.p_cost() != self.parallelism
+        })
+    }
+}
 
 #[derive(Clone)]
 pub struct DeviceService {
-    devices: DeviceRepository,
+    devices: SharedDevicesRepo,
+    events: DomainEventBus,
+    clock: SharedClock,
+    hash_params: PasswordHashParams,
 }
 
 impl DeviceService {
     #[must_use]
-    pub fn new(devices: DeviceRepository) -> Self {
-        Self { devices }
+    pub fn new(devices: impl DevicesRepo + 'static) -> Self {
+        Self {
+            devices: Arc::new(devices),
+            events: DomainEventBus::new(),
+            clock: SystemClock::shared(),
+            hash_params: PasswordHashParams::default(),
+        }
+    }
+
+    /// Hash new device passwords, and rehash outdated ones on login, with
+    /// `params` instead of the Argon2 defaults
+    #[must_use]
+    pub const fn with_hash_params(mut self, params: PasswordHashParams) -> Self {
+        self.hash_params = params;
+        self
+    }
+
+    /// Read the time from `clock` instead of the system clock
+    #[must_use]
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Publish committed domain events on a bus shared with other services.
+    #[must_use]
+    pub fn with_event_bus(mut self, events: DomainEventBus) -> Self {
+        self.events = events;
+        self
+    }
+
+    #[must_use]
+    pub const fn event_bus(&self) -> &DomainEventBus {
+        &self.events
     }
 
     pub async fn create_device_password(
@@ -32,34 +140,26 @@ impl DeviceService {
         validate_device_name(&command.name)?;
 
         let password = generate_password(PASSWORD_LEN);
-        let password_hash = hash_password(password.clone()).await?;
+        let password_hash = hash_password(password.clone(), self.hash_params).await?;
 
-        let mut tx = self.devices.begin().await.map_err(storage_error)?;
-        tx.ensure_user(command.user_id.inner(), command.username.as_deref())
-            .await
-            .map_err(storage_error)?;
-
-        let user_id = command.user_id;
-        let count = tx
-            .count_device_passwords(user_id)
-            .await
-            .map_err(storage_error)?;
-        if count >= MAX_DEVICES_PER_USER {
-            return Err(ApplicationError::BadRequest(format!(
-                "Maximum number of devices ({MAX_DEVICES_PER_USER}) reached. Please delete an old device password."
-            )));
-        }
-
-        let device = tx
-            .insert_device_password(StoredDevicePassword {
-                user_id,
-                name: command.name.trim().to_string(),
-                password_hash,
-            })
+        let device = self
+            .devices
+            .create_device_password(
+                command.username.as_deref(),
+                StoredDevicePassword {
+                    user_id: command.user_id,
+                    name: command.name.trim().to_string(),
+                    password_hash,
+                },
+                MAX_DEVICES_PER_USER,
+            )
             .await
-            .map_err(storage_error)?;
-
-        tx.commit().await.map_err(storage_error)?;
+            .map_err(storage_error)?
+            .ok_or_else(|| {
+                ApplicationError::BadRequest(format!(
+                    "Maximum number of devices ({MAX_DEVICES_PER_USER}) reached. Please delete an old device password."
+                ))
+            })?;
 
         Ok(CreatedDevicePassword {
             id: device.id,
@@ -70,13 +170,21 @@ impl DeviceService {
         })
     }
 
+    /// Most recently used first; with `active_within`, only devices used
+    /// within that long
     pub async fn list_device_passwords(
         &self,
         user_id: UserId,
+        active_within: Option<Duration>,
     ) -> Result<Vec<DevicePasswordView>, ApplicationError> {
+        let now = self.clock.now();
         let devices = self
             .devices
-            .list_device_passwords(user_id)
+            .list_device_passwords(
+                user_id,
+                now - Duration::hours(RECENT_ACTIVITY_WINDOW_HOURS),
+                active_within.map(|window| now - window),
+            )
             .await
             .map_err(storage_error)?;
         Ok(devices
@@ -86,6 +194,7 @@ impl DeviceService {
                 name: device.name,
                 created_at: device.created_at,
                 last_used_at: device.last_used_at,
+                recent_requests: device.recent_requests,
             })
             .collect())
     }
@@ -93,12 +202,21 @@ impl DeviceService {
     pub async fn revoke_device_password(
         &self,
         user_id: UserId,
-        device_id: Uuid,
+        device_id: DeviceId,
     ) -> Result<bool, ApplicationError> {
-        self.devices
+        let revoked = self
+            .devices
             .delete_device_password(user_id, device_id)
             .await
-            .map_err(storage_error)
+            .map_err(storage_error)?;
+        if revoked {
+            self.events
+                .publish(vec![DomainEvent::DevicePasswordRevoked {
+                    user_id,
+                    device_id,
+                }]);
+        }
+        Ok(revoked)
     }
 
     pub async fn list_password_hashes_for_auth(
@@ -111,13 +229,89 @@ impl DeviceService {
             .map_err(storage_error)
     }
 
-    pub async fn record_device_used(&self, device_id: Uuid) -> Result<(), ApplicationError> {
+    pub async fn record_device_used(&self, device_id: DeviceId) -> Result<(), ApplicationError> {
         self.devices
             .touch_device_password(device_id)
             .await
             .map_err(storage_error)?;
         Ok(())
     }
+
+    /// Rehash a device password that just verified against `current_hash`
+    /// if that hash was made with other parameters than the configured ones.
+    /// Returns whether the stored hash was replaced.
+    pub async fn upgrade_password_hash(
+        &self,
+        device_id: DeviceId,
+        password: String,
+        current_hash: String,
+    ) -> Result<bool, ApplicationError> {
+        if !self.hash_params.is_outdated(&current_hash) {
+            return Ok(false);
+        }
+        let new_hash = hash_password(password, self.hash_params).await?;
+        self.devices
+            .replace_password_hash(device_id, current_hash, new_hash)
+            .await
+            .map_err(storage_error)
+    }
+
+    /// Log an authenticated DAV request for the device's diagnostics
+    pub async fn record_device_activity(
+        &self,
+        activity: DeviceActivity,
+    ) -> Result<(), ApplicationError> {
+        self.devices
+            .insert_device_activity(
+                NewDeviceActivity {
+                    device_id: activity.device_id,
+                    method: activity.method,
+                    path: activity.path,
+                    user_agent: activity.user_agent,
+                    status: i16::try_from(activity.status).unwrap_or(i16::MAX),
+                    sync_token: activity.sync_token,
+                    conflict_uid: activity.conflict.as_ref().map(|c| c.uid.clone()),
+                    client_sequence: activity.conflict.as_ref().map(|c| c.client_sequence),
+                    server_sequence: activity.conflict.as_ref().map(|c| c.server_sequence),
+                },
+                DEVICE_ACTIVITY_KEPT,
+            )
+            .await
+            .map_err(storage_error)
+    }
+
+    /// Recent requests made with one of the user's devices, newest first
+    pub async fn device_activity(
+        &self,
+        user_id: UserId,
+        device_id: DeviceId,
+    ) -> Result<Vec<DeviceActivityView>, ApplicationError> {
+        let activity = self
+            .devices
+            .list_device_activity(user_id, device_id)
+            .await
+            .map_err(storage_error)?
+            .ok_or_else(|| {
+                ApplicationError::NotFound(format!("Device password not found: {device_id}"))
+            })?;
+
+        Ok(activity
+            .into_iter()
+            .map(|record| DeviceActivityView {
+                method: record.method,
+                path: record.path,
+                user_agent: record.user_agent,
+                status: u16::try_from(record.status).unwrap_or_default(),
+                sync_token: record.sync_token,
+                conflict: record.conflict_uid.map(|uid| SequenceConflict {
+                    uid,
+                    client_sequence: record.client_sequence.unwrap_or_default(),
+                    server_sequence: record.server_sequence.unwrap_or_default(),
+                }),
+                at: record.created_at,
+            })
+            .collect())
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -129,7 +323,7 @@ pub struct CreateDevicePasswordCommand {
 
 #[derive(Debug, Clone)]
 pub struct CreatedDevicePassword {
-    pub id: Uuid,
+    pub id: DeviceId,
     pub name: String,
     pub password: String,
     pub created_at: DateTime<Utc>,
@@ -138,10 +332,37 @@ pub struct CreatedDevicePassword {
 
 #[derive(Debug, Clone)]
 pub struct DevicePasswordView {
-    pub id: Uuid,
+    pub id: DeviceId,
     pub name: String,
     pub created_at: DateTime<Utc>,
     pub last_used_at: Option<DateTime<Utc>>,
+    /// Requests made with the password over the last day
+    pub recent_requests: i64,
+}
+
+/// One authenticated DAV request, as seen by the auth middleware
+#[derive(Debug, Clone)]
+pub struct DeviceActivity {
+    pub device_id: DeviceId,
+    pub method: String,
+    pub path: String,
+    pub user_agent: Option<String>,
+    pub status: u16,
+    /// Token presented in a sync-collection REPORT
+    pub sync_token: Option<String>,
+    /// Stale edit the request was turned away for
+    pub conflict: Option<SequenceConflict>,
+}
+
+#[derive(Debug, Clone)]
+pub struct DeviceActivityView {
+    pub method: String,
+    pub path: String,
+    pub user_agent: Option<String>,
+    pub status: u16,
+    pub sync_token: Option<String>,
+    pub conflict: Option<SequenceConflict>,
+    pub at: DateTime<Utc>,
 }
 
 pub fn validate_device_name(name: &str) -> Result<(), ApplicationError> {
@@ -159,7 +380,7 @@ pub fn validate_device_name(name: &str) -> Result<(), ApplicationError> {
     Ok(())
 }
 
-fn generate_password(length: usize) -> String {
+pub(crate) fn generate_password(length: usize) -> String {
     const CHARSET: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
     let mut rng = rand::rng();
     (0..length)
@@ -170,10 +391,14 @@ fn generate_password(length: usize) -> String {
         .collect()
 }
 
-async fn hash_password(password: String) -> Result<String, ApplicationError> {
+async fn hash_password(
+    password: String,
+    params: PasswordHashParams,
+) -> Result<String, ApplicationError> {
     tokio::task::spawn_blocking(move || {
         let salt = SaltString::generate(&mut argon2::password_hash::rand_core::OsRng);
-        Argon2::default()
+        params
+            .argon2()?
             .hash_password(password.as_bytes(), &salt)
             .map(|hash| hash.to_string())
             .map_err(|err| ApplicationError::Internal(format!("Password hashing failed: {err}")))
@@ -207,4 +432,75 @@ mod tests {
         assert!(validate_device_name("   ").is_err());
         assert!(validate_device_name(&"x".repeat(MAX_DEVICE_NAME_LENGTH + 1)).is_err());
     }
+
+    #[test]
+    fn rejects_invalid_hash_params() {
+        assert!(PasswordHashParams::new(19_456, 2, 1).is_ok());
+        assert!(PasswordHashParams::new(19_456, 0, 1).is_err());
+        assert!(PasswordHashParams::new(8, 1, 4).is_err());
+    }
+
+    #[tokio::test]
+    async fn hash_records_its_params() {
+        let cheap = PasswordHashParams::new(1024, 1, 1).unwrap();
+        let hash = hash_password("secret".to_string(), cheap).await.unwrap();
+
+        assert!(hash.contains("m=1024,t=1,p=1"));
+        assert!(!cheap.is_outdated(&hash));
+        assert!(
+            PasswordHashParams::new(2048, 1, 1)
+                .unwrap()
+                .is_outdated(&hash)
+        );
+        assert!(!cheap.is_outdated("not a hash"));
+    }
+
+    #[tokio::test]
+    async fn login_upgrades_outdated_hash_once() {
+        let devices = televent_storage::memory::MemoryDevices::default();
+        let old = DeviceService::new(devices.clone())
+            .with_hash_params(PasswordHashParams::new(1024, 1, 1).unwrap());
+        let created = old
+            .create_device_password(CreateDevicePasswordCommand {
+                user_id: UserId::new(1),
+                username: None,
+                name: "Phone".to_string(),
+            })
+            .await
+            .unwrap();
+        let device_id = created.id;
+        let stored = old
+            .list_password_hashes_for_auth(UserId::new(1))
+            .await
+            .unwrap()
+            .remove(0)
+            .password_hash;
+
+        let new = DeviceService::new(devices)
+            .with_hash_params(PasswordHashParams::new(2048, 1, 1).unwrap());
+        assert!(
+            new.upgrade_password_hash(device_id, created.password.clone(), stored.clone())
+                .await
+                .unwrap()
+        );
+        let upgraded = new
+            .list_password_hashes_for_auth(UserId::new(1))
+            .await
+            .unwrap()
+            .remove(0)
+            .password_hash;
+        assert!(upgraded.contains("m=2048,t=1,p=1"));
+
+        // The old hash no longer matches, and the new one is current
+        assert!(
+            !new.upgrade_password_hash(device_id, created.password.clone(), stored)
+                .await
+                .unwrap()
+        );
+        assert!(
+            !new.upgrade_password_hash(device_id, created.password, upgraded)
+                .await
+                .unwrap()
+        );
+    }
 }
@@ -0,0 +1,253 @@
+//! Dispatch of domain events raised by calendar write paths.
+//!
+//! Use cases open a [`CalendarWrite`], perform their row changes and record
+//! what happened with [`CalendarWrite::emit`]. Committing the write runs the
+//! transactional subscribers (sync-token bump, tombstones, revision history,
+//! outbox enqueue) inside the same transaction, then publishes the events on the
+//! [`DomainEventBus`] for post-commit subscribers such as cache invalidation.
+
+use std::ops::{Deref, DerefMut};
+
+use televent_domain::{DomainEvent, UserId};
+use televent_storage::calendar::CalendarTransaction;
+use televent_storage::revision::EventRevisionWrite;
+use tokio::sync::broadcast;
+
+use crate::delegation::RevisionAction;
+use crate::{ApplicationError, storage_error};
+
+const EVENT_BUS_CAPACITY: usize = 256;
+
+/// In-process fan-out of committed domain events.
+#[derive(Clone)]
+pub struct DomainEventBus {
+    sender: broadcast::Sender<DomainEvent>,
+}
+
+impl DomainEventBus {
+    #[must_use]
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(EVENT_BUS_CAPACITY);
+        Self { sender }
+    }
+
+    #[must_use]
+    pub fn subscribe(&self) -> broadcast::Receiver<DomainEvent> {
+        self.sender.subscribe()
+    }
+
+    pub(crate) fn publish(&self, events: Vec<DomainEvent>) {
+        for event in events {
+            // Sending only fails when nobody is subscribed.
+            let _ = self.sender.send(event);
+        }
+    }
+}
+
+impl Default for DomainEventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A calendar transaction that collects domain events until commit.
+pub(crate) struct CalendarWrite<'a> {
+    tx: CalendarTransaction<'a>,
+    sync_versions: Vec<(UserId, i64)>,
+    events: Vec<DomainEvent>,
+    actor: Option<UserId>,
+}
+
+impl<'a> CalendarWrite<'a> {
+    pub(crate) const fn new(tx: CalendarTransaction<'a>) -> Self {
+        Self {
+            tx,
+            sync_versions: Vec::new(),
+            events: Vec::new(),
+            actor: None,
+        }
+    }
+
+    /// Attribute the event changes of this write to `actor` rather than to
+    /// the owner of the calendar they are on.
+    pub(crate) const fn acting_as(mut self, actor: UserId) -> Self {
+        self.actor = Some(actor);
+        self
+    }
+
+    /// Sync version for rows written to `calendar_owner` in this transaction.
+    ///
+    /// The calendar state is bumped at most once per transaction, however many
+    /// rows or events the use case touches.
+    pub(crate) async fn sync_version(
+        &mut self,
+        calendar_owner: UserId,
+    ) -> Result<i64, ApplicationError> {
+        if let Some((_, version)) = self
+            .sync_versions
+            .iter()
+            .find(|(owner, _)| *owner == calendar_owner)
+        {
+            return Ok(*version);
+        }
+
+        let version = self
+            .tx
+            .bump_calendar_state(calendar_owner)
+            .await
+            .map_err(storage_error)?;
+        self.sync_versions.push((calendar_owner, version));
+        Ok(version)
+    }
+
+    pub(crate) fn emit(&mut self, event: DomainEvent) {
+        self.events.push(event);
+    }
+
+    pub(crate) async fn commit(mut self, bus: &DomainEventBus) -> Result<(), ApplicationError> {
+        self.apply_sync_state().await?;
+        self.apply_resource_bookings().await?;
+        self.apply_revisions().await?;
+        self.apply_outbox().await?;
+        self.tx.commit().await.map_err(storage_error)?;
+        bus.publish(self.events);
+        Ok(())
+    }
+
+    async fn apply_sync_state(&mut self) -> Result<(), ApplicationError> {
+        let events = std::mem::take(&mut self.events);
+        for event in &events {
+            let Some(calendar_owner) = event.calendar_owner() else {
+                continue;
+            };
+            let sync_version = self.sync_version(calendar_owner).await?;
+            if let DomainEvent::EventDeleted { uid, .. } = event {
+                self.tx
+                    .insert_tombstone(calendar_owner, uid, sync_version)
+                    .await
+                    .map_err(storage_error)?;
+            }
+        }
+        self.events = events;
+        Ok(())
+    }
+
+    /// Move resource bookings along with the events holding them, refusing
+    /// the write when a resource is taken at an event's new time
+    async fn apply_resource_bookings(&mut self) -> Result<(), ApplicationError> {
+        let updated: Vec<_> = self
+            .events
+            .iter()
+            .filter_map(|event| match event {
+                DomainEvent::EventUpdated { event_id, .. } => Some(*event_id),
+                _ => None,
+            })
+            .collect();
+        for event_id in updated {
+            if !self
+                .tx
+                .resync_resource_booking(event_id)
+                .await
+                .map_err(storage_error)?
+            {
+                return Err(ApplicationError::Conflict(
+                    "Resource is already booked at that time".to_string(),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    async fn apply_revisions(&mut self) -> Result<(), ApplicationError> {
+        let revisions: Vec<_> = self
+            .events
+            .iter()
+            .filter_map(|event| {
+                let (calendar_owner, event_id, action) = match event {
+                    DomainEvent::EventCreated {
+                        calendar_owner,
+                        event_id,
+                    } => (*calendar_owner, *event_id, RevisionAction::Created),
+                    DomainEvent::EventUpdated {
+                        calendar_owner,
+                        event_id,
+                    } => (*calendar_owner, *event_id, RevisionAction::Updated),
+                    DomainEvent::EventDeleted {
+                        calendar_owner,
+                        event_id,
+                        ..
+                    } => (*calendar_owner, *event_id, RevisionAction::Deleted),
+                    _ => return None,
+                };
+                Some(EventRevisionWrite {
+                    event_id,
+                    user_id: calendar_owner,
+                    actor_id: self.actor.unwrap_or(calendar_owner),
+                    action: action.as_str(),
+                })
+            })
+            .collect();
+        for revision in revisions {
+            self.tx
+                .insert_event_revision(revision)
+                .await
+                .map_err(storage_error)?;
+        }
+        Ok(())
+    }
+
+    async fn apply_outbox(&mut self) -> Result<(), ApplicationError> {
+        let payloads: Vec<_> = self
+            .events
+            .iter()
+            .filter_map(DomainEvent::outbox_payload)
+            .collect();
+        if payloads.is_empty() {
+            return Ok(());
+        }
+        self.tx.queue_outbox(&payloads).await.map_err(storage_error)
+    }
+}
+
+impl<'a> Deref for CalendarWrite<'a> {
+    type Target = CalendarTransaction<'a>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.tx
+    }
+}
+
+impl DerefMut for CalendarWrite<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.tx
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use televent_domain::DeviceId;
+    use uuid::Uuid;
+
+    #[tokio::test]
+    async fn bus_delivers_published_events_to_subscribers() {
+        let bus = DomainEventBus::new();
+        let mut receiver = bus.subscribe();
+        let event = DomainEvent::DevicePasswordRevoked {
+            user_id: UserId::new(7),
+            device_id: DeviceId::new(Uuid::new_v4()),
+        };
+
+        bus.publish(vec![event.clone()]);
+
+        assert_eq!(receiver.recv().await.ok(), Some(event));
+    }
+
+    #[test]
+    fn publishing_without_subscribers_is_a_no_op() {
+        DomainEventBus::new().publish(vec![DomainEvent::EventCreated {
+            calendar_owner: UserId::new(1),
+            event_id: Uuid::new_v4(),
+        }]);
+    }
+}
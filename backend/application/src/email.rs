@@ -0,0 +1,328 @@
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use televent_domain::{OutboxPayload, TelegramNotification};
+use televent_storage::email::{EmailFeedbackWrite, EmailRepository, SendSlot};
+
+use crate::{ApplicationError, UserId, storage_error};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Longest provider diagnostic kept with a report.
+const MAX_FEEDBACK_DETAIL_LENGTH: usize = 512;
+
+/// What the provider reported about a delivery
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmailFeedbackKind {
+    /// The address does not exist or permanently refuses mail
+    HardBounce,
+    /// A temporary failure; the provider may retry
+    SoftBounce,
+    /// The recipient marked the email as spam
+    Complaint,
+}
+
+impl EmailFeedbackKind {
+    const fn as_str(self) -> &'static str {
+        match self {
+            Self::HardBounce => "hard_bounce",
+            Self::SoftBounce => "soft_bounce",
+            Self::Complaint => "complaint",
+        }
+    }
+
+    /// Reason the address is suppressed for, if this report suppresses it.
+    const fn suppression_reason(self) -> Option<&'static str> {
+        match self {
+            Self::HardBounce => Some("bounced"),
+            Self::Complaint => Some("complained"),
+            Self::SoftBounce => None,
+        }
+    }
+}
+
+/// One recipient-level report from a provider webhook
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmailFeedback {
+    pub email: String,
+    pub kind: EmailFeedbackKind,
+    pub detail: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EmailFeedbackOutcome {
+    pub recorded: usize,
+    pub suppressed: usize,
+}
+
+/// Whether an email may go out now
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmailSendPermit {
+    Granted,
+    /// The address bounced, complained or unsubscribed
+    Suppressed,
+    /// The recipient's hourly cap is used up until the given time
+    Throttled(DateTime<Utc>),
+}
+
+/// Signs and checks the tokens of unsubscribe links
+///
+/// The key is derived from the bot token, which the API and the worker
+/// already share, the same way Telegram derives its Web App key.
+#[derive(Clone)]
+pub struct UnsubscribeKey([u8; 32]);
+
+impl UnsubscribeKey {
+    #[must_use]
+    pub fn from_bot_token(bot_token: &str) -> Self {
+        let mut mac = HmacSha256::new_from_slice(b"TeleventUnsubscribe")
+            .expect("HMAC can take any key length");
+        mac.update(bot_token.as_bytes());
+        Self(mac.finalize().into_bytes().into())
+    }
+
+    /// Token for the address; it does not expire.
+    #[must_use]
+    pub fn token(&self, email: &str) -> String {
+        let email = email.trim().to_lowercase();
+        format!(
+            "{}.{}",
+            URL_SAFE_NO_PAD.encode(email.as_bytes()),
+            URL_SAFE_NO_PAD.encode(self.mac(&email).finalize().into_bytes())
+        )
+    }
+
+    /// The address a token was issued for, if the signature holds.
+    #[must_use]
+    pub fn verify(&self, token: &str) -> Option<String> {
+        let (email, signature) = token.split_once('.')?;
+        let email = String::from_utf8(URL_SAFE_NO_PAD.decode(email).ok()?).ok()?;
+        let signature = URL_SAFE_NO_PAD.decode(signature).ok()?;
+        self.mac(&email).verify_slice(&signature).ok()?;
+        Some(email)
+    }
+
+    fn mac(&self, email: &str) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(&self.0).expect("HMAC can take any key length");
+        mac.update(email.as_bytes());
+        mac
+    }
+}
+
+/// Signs and checks the links in email verification messages
+///
+/// A link carries the same code the message shows, so opening it and typing
+/// the code back are one check. The signature keeps the link endpoint from
+/// being used to guess codes: forged links never reach the attempt counter.
+#[derive(Clone)]
+pub struct EmailVerificationKey([u8; 32]);
+
+impl EmailVerificationKey {
+    #[must_use]
+    pub fn from_bot_token(bot_token: &str) -> Self {
+        let mut mac = HmacSha256::new_from_slice(b"TeleventEmailVerification")
+            .expect("HMAC can take any key length");
+        mac.update(bot_token.as_bytes());
+        Self(mac.finalize().into_bytes().into())
+    }
+
+    /// Token for the code; it lives as long as the code does.
+    #[must_use]
+    pub fn token(&self, user_id: UserId, code: &str) -> String {
+        format!(
+            "{user_id}.{code}.{}",
+            URL_SAFE_NO_PAD.encode(self.mac(user_id, code).finalize().into_bytes())
+        )
+    }
+
+    /// The user and code a token was issued for, if the signature holds.
+    #[must_use]
+    pub fn verify(&self, token: &str) -> Option<(UserId, String)> {
+        let mut parts = token.splitn(3, '.');
+        let user_id = UserId::new(parts.next()?.parse().ok()?);
+        let code = parts.next()?;
+        let signature = URL_SAFE_NO_PAD.decode(parts.next()?).ok()?;
+        self.mac(user_id, code).verify_slice(&signature).ok()?;
+        Some((user_id, code.to_string()))
+    }
+
+    fn mac(&self, user_id: UserId, code: &str) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(&self.0).expect("HMAC can take any key length");
+        mac.update(format!("{user_id}:{code}").as_bytes());
+        mac
+    }
+}
+
+#[derive(Clone)]
+pub struct EmailService {
+    email: EmailRepository,
+}
+
+impl EmailService {
+    #[must_use]
+    pub const fn new(email: EmailRepository) -> Self {
+        Self { email }
+    }
+
+    /// Record provider reports and suppress addresses that must not be
+    /// emailed again.
+    ///
+    /// Organizers waiting on a newly hard-bounced address hear about it on
+    /// Telegram, since the guest never got their link or invite.
+    pub async fn record_feedback(
+        &self,
+        provider: &str,
+        reports: Vec<EmailFeedback>,
+    ) -> Result<EmailFeedbackOutcome, ApplicationError> {
+        let mut outcome = EmailFeedbackOutcome::default();
+        let mut tx = self.email.begin().await.map_err(storage_error)?;
+
+        for report in reports {
+            let email = report.email.trim().to_lowercase();
+            if email.is_empty() {
+                continue;
+            }
+            let detail = report
+                .detail
+                .map(|detail| detail.chars().take(MAX_FEEDBACK_DETAIL_LENGTH).collect());
+            tx.insert_feedback(&EmailFeedbackWrite {
+                provider: provider.to_string(),
+                email: email.clone(),
+                kind: report.kind.as_str(),
+                detail: detail.clone(),
+            })
+            .await
+            .map_err(storage_error)?;
+            outcome.recorded += 1;
+
+            let Some(reason) = report.kind.suppression_reason() else {
+                continue;
+            };
+            if !tx
+                .suppress(&email, reason, detail.as_deref())
+                .await
+                .map_err(storage_error)?
+            {
+                continue;
+            }
+            outcome.suppressed += 1;
+
+            if report.kind == EmailFeedbackKind::HardBounce {
+                let notifications = tx
+                    .affected_events(&email)
+                    .await
+                    .map_err(storage_error)?
+                    .into_iter()
+                    .map(|event| {
+                        OutboxPayload::TelegramNotification(TelegramNotification {
+                            telegram_id: event.organizer_id.inner(),
+                            message: format!(
+                                "📭 Email to {email} bounced, so they did not get the \
+                                 invite for \"{}\". Ask them for another address.",
+                                event.summary
+                            ),
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                tx.queue_outbox(&notifications)
+                    .await
+                    .map_err(storage_error)?;
+            }
+        }
+
+        tx.commit().await.map_err(storage_error)?;
+        Ok(outcome)
+    }
+
+    pub async fn is_suppressed(&self, email: &str) -> Result<bool, ApplicationError> {
+        self.email.is_suppressed(email).await.map_err(storage_error)
+    }
+
+    /// Suppress the address an unsubscribe token was issued for.
+    ///
+    /// Returns the address so the page can name it.
+    pub async fn unsubscribe(
+        &self,
+        key: &UnsubscribeKey,
+        token: &str,
+    ) -> Result<String, ApplicationError> {
+        let email = key
+            .verify(token)
+            .ok_or_else(|| ApplicationError::NotFound("unsubscribe link".to_string()))?;
+        self.email
+            .suppress(&email, "unsubscribed")
+            .await
+            .map_err(storage_error)?;
+        Ok(email)
+    }
+
+    /// Check the suppression list and take one of the recipient's hourly
+    /// sends.
+    ///
+    /// The slot is used even if delivery then fails, so retries of a failing
+    /// email count against the cap too.
+    pub async fn reserve_send(
+        &self,
+        email: &str,
+        hourly_cap: u32,
+    ) -> Result<EmailSendPermit, ApplicationError> {
+        let slot = self
+            .email
+            .reserve_send(email, i64::from(hourly_cap))
+            .await
+            .map_err(storage_error)?;
+        Ok(match slot {
+            SendSlot::Granted => EmailSendPermit::Granted,
+            SendSlot::Suppressed => EmailSendPermit::Suppressed,
+            SendSlot::Throttled { until } => EmailSendPermit::Throttled(until),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unsubscribe_token_round_trip() {
+        let key = UnsubscribeKey::from_bot_token("123:abc");
+        let token = key.token(" Guest@Example.com ");
+
+        assert_eq!(key.verify(&token).as_deref(), Some("guest@example.com"));
+        assert!(
+            UnsubscribeKey::from_bot_token("123:other")
+                .verify(&token)
+                .is_none()
+        );
+
+        let (_, signature) = token.split_once('.').unwrap();
+        let forged = format!(
+            "{}.{signature}",
+            URL_SAFE_NO_PAD.encode("victim@example.com")
+        );
+        assert!(key.verify(&forged).is_none());
+        assert!(key.verify("not-a-token").is_none());
+    }
+
+    #[test]
+    fn test_email_verification_token_round_trip() {
+        let key = EmailVerificationKey::from_bot_token("123:abc");
+        let token = key.token(UserId::new(42), "012345");
+
+        assert_eq!(
+            key.verify(&token),
+            Some((UserId::new(42), "012345".to_string()))
+        );
+        assert!(
+            EmailVerificationKey::from_bot_token("123:other")
+                .verify(&token)
+                .is_none()
+        );
+
+        let (_, signature) = token.rsplit_once('.').unwrap();
+        assert!(key.verify(&format!("42.999999.{signature}")).is_none());
+        assert!(key.verify(&format!("43.012345.{signature}")).is_none());
+        assert!(key.verify("not-a-token").is_none());
+    }
+}
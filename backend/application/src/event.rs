@@ -0,0 +1,3134 @@
+//! Event write use cases shared by the REST API, CalDAV and the bot.
+//!
+//! Every frontend funnels event mutations through [`EventService`] so field
+//! validation, version and etag bookkeeping, all-day handling and attendee
+//! side effects behave the same regardless of where the change came from.
+
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use televent_domain::{
+    AttendeeFingerprint, AttendeeRole, EventEtagInput, EventStatus, EventTiming, EventVisibility,
+    InviteReminder, MAX_DESCRIPTION_LENGTH, MAX_LOCATION_LENGTH, MAX_REMINDER_NOTE_LENGTH,
+    MAX_RRULE_LENGTH, MAX_SUMMARY_LENGTH, MAX_UID_LENGTH, OutboxMessageId, OutboxPayload,
+    ParticipationStatus, SeriesSplit, TaskBlockReminder, Timezone, compute_event_etag,
+    occurrence_after, sanitize_multiline_text, sanitize_single_line_text, split_rrule,
+    truncate_to_length, validate_email_address, validate_length, validate_no_control_chars,
+    validate_rrule,
+};
+use televent_storage::booking::BookingLinkWrite;
+use televent_storage::calendar::{
+    AttendeeWrite, CalendarRepository, Event, EventAttendee, PublicSignupWrite, StoredEventUpdate,
+    StoredEventWrite, User,
+};
+use televent_storage::focus::FocusRuleFields;
+use uuid::Uuid;
+
+use crate::booking::{
+    BookSlotCommand, BookingLinkView, CreateBookingLinkCommand, MAX_BOOKING_LINKS_PER_USER,
+};
+use crate::delegation::{DelegateView, EventRevisionView, MAX_DELEGATES_PER_USER};
+use crate::device::generate_password;
+use crate::domain_events::CalendarWrite;
+use crate::focus::{
+    FocusRuleCommand, FocusRuleView, FocusSchedule, MAX_FOCUS_RULES_PER_USER, place_blocks,
+};
+use crate::resource::{ResourceBookingView, ResourceView, normalize_resource_name};
+use crate::scheduled::{ScheduledMessageView, validate_send_at};
+use crate::snooze::SnoozeDelay;
+use crate::task::{
+    CreateTaskCommand, MAX_OPEN_TASKS_PER_USER, PlannedBlock, TaskPlan, TaskView, next_block,
+};
+use crate::timezone_fix::{
+    MAX_TIMEZONE_FIX_EVENTS, TimezoneFixCommand, TimezoneFixSummary, rezone, rezone_rrule,
+};
+use crate::{
+    ApplicationError, Clock, DomainEvent, DomainEventBus, EventView, FreeBusy, SharedClock,
+    SystemClock, UserId, WorkingHours, storage_error, timing_from_event,
+};
+
+/// Upper bound on invitees accepted by a single bulk invite.
+pub const MAX_INVITEES_PER_REQUEST: usize = 20;
+
+/// How far a duplicated event may be moved, about ten years.
+pub const MAX_DUPLICATE_OFFSET_DAYS: i64 = 3660;
+
+/// Events with the same summary starting this close count as duplicates.
+pub const NEAR_DUPLICATE_WINDOW_SECS: i64 = 60;
+
+/// Upper bound on the name a visitor leaves on a public signup form.
+pub const MAX_SIGNUP_NAME_LENGTH: usize = 128;
+
+const PUBLIC_SLUG_LEN: usize = 16;
+pub(crate) const SIGNUP_TOKEN_LEN: usize = 32;
+pub(crate) const SIGNUP_CONFIRMATION_TTL_HOURS: i64 = 48;
+
+#[derive(Clone)]
+pub struct EventService {
+    calendar: CalendarRepository,
+    events: DomainEventBus,
+    text_limits: EventTextLimits,
+    clock: SharedClock,
+}
+
+impl EventService {
+    #[must_use]
+    pub fn new(calendar: CalendarRepository) -> Self {
+        Self {
+            calendar,
+            events: DomainEventBus::new(),
+            text_limits: EventTextLimits::default(),
+            clock: SystemClock::shared(),
+        }
+    }
+
+    /// Read the time from `clock` instead of the system clock
+    #[must_use]
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// The clock this service reads the time from
+    #[must_use]
+    pub fn clock(&self) -> &dyn Clock {
+        self.clock.as_ref()
+    }
+
+    /// Enforce deployment-specific limits on event free text.
+    #[must_use]
+    pub const fn with_text_limits(mut self, text_limits: EventTextLimits) -> Self {
+        self.text_limits = text_limits;
+        self
+    }
+
+    #[must_use]
+    pub const fn text_limits(&self) -> &EventTextLimits {
+        &self.text_limits
+    }
+
+    /// Publish committed domain events on a bus shared with other services.
+    #[must_use]
+    pub fn with_event_bus(mut self, events: DomainEventBus) -> Self {
+        self.events = events;
+        self
+    }
+
+    #[must_use]
+    pub const fn event_bus(&self) -> &DomainEventBus {
+        &self.events
+    }
+
+    async fn begin_write(&self) -> Result<CalendarWrite<'_>, ApplicationError> {
+        Ok(CalendarWrite::new(
+            self.calendar.begin().await.map_err(storage_error)?,
+        ))
+    }
+
+    /// Open a write to `calendar_owner`'s calendar on behalf of `actor`, who
+    /// must be the owner or one of their delegates.
+    ///
+    /// The grant is read inside the write, so one revoked meanwhile is seen;
+    /// without it the calendar is reported as not found.
+    async fn begin_write_as(
+        &self,
+        calendar_owner: UserId,
+        actor: UserId,
+    ) -> Result<CalendarWrite<'_>, ApplicationError> {
+        let mut write = self.begin_write().await?;
+        if actor != calendar_owner
+            && !write
+                .is_delegate(calendar_owner, actor)
+                .await
+                .map_err(storage_error)?
+        {
+            return Err(ApplicationError::NotFound(format!(
+                "calendar {calendar_owner}"
+            )));
+        }
+        Ok(write.acting_as(actor))
+    }
+
+    /// Visibility new events of the user get when they don't set one
+    async fn default_visibility(
+        &self,
+        user_id: UserId,
+    ) -> Result<EventVisibility, ApplicationError> {
+        Ok(self
+            .calendar
+            .get_user_by_id(user_id)
+            .await
+            .map_err(storage_error)?
+            .map(|user| user.default_event_visibility)
+            .unwrap_or_default())
+    }
+
+    async fn create_event(
+        &self,
+        actor: UserId,
+        mut command: CreateEventCommand,
+    ) -> Result<Event, ApplicationError> {
+        self.text_limits.sanitize(
+            Some(&mut command.summary),
+            command.description.as_mut(),
+            command.location.as_mut(),
+            TextOverflow::Reject,
+        )?;
+        validate_event_fields(Some(&command.uid), command.rrule.as_deref())?;
+        command.timing.validate()?;
+        command.reminder_note = clean_reminder_note(command.reminder_note)?;
+
+        let mut write = self.begin_write_as(command.user_id, actor).await?;
+        let owner = write
+            .ensure_user(command.user_id.inner(), command.username.as_deref())
+            .await
+            .map_err(storage_error)?;
+        let visibility = command.visibility.unwrap_or(owner.default_event_visibility);
+
+        let user_id = command.user_id;
+        write.lock_calendar(user_id).await.map_err(storage_error)?;
+        if !command.allow_duplicate
+            && let Some(existing) = write
+                .find_near_duplicate_event(
+                    user_id,
+                    &command.summary,
+                    &command.timing,
+                    Duration::seconds(NEAR_DUPLICATE_WINDOW_SECS),
+                )
+                .await
+                .map_err(storage_error)?
+        {
+            return Err(ApplicationError::Conflict(format!(
+                "Looks like a duplicate of event {}",
+                existing.id
+            )));
+        }
+
+        let sync_version = write.sync_version(user_id).await?;
+        let version = 1;
+        let etag = compute_event_etag(&EventEtagInput {
+            uid: command.uid.clone(),
+            summary: command.summary.clone(),
+            description: command.description.clone(),
+            location: command.location.clone(),
+            timing: command.timing.clone(),
+            status: command.status,
+            rrule: command.rrule.clone(),
+            visibility,
+            reminder_note: command.reminder_note.clone(),
+            attendees: Vec::new(),
+        });
+
+        let event = write
+            .insert_event(StoredEventWrite {
+                user_id,
+                uid: command.uid,
+                summary: command.summary,
+                description: command.description,
+                location: command.location,
+                timing: command.timing,
+                status: command.status,
+                rrule: command.rrule,
+                version,
+                sequence: 0,
+                sync_version,
+                etag,
+                visibility,
+                reminder_note: command.reminder_note,
+            })
+            .await
+            .map_err(storage_error)?;
+
+        write.emit(DomainEvent::EventCreated {
+            calendar_owner: user_id,
+            event_id: event.id,
+        });
+        write.commit(&self.events).await?;
+        Ok(event)
+    }
+
+    pub async fn create_event_view(
+        &self,
+        command: CreateEventCommand,
+    ) -> Result<EventView, ApplicationError> {
+        EventView::try_from(self.create_event(command.user_id, command).await?)
+    }
+
+    /// Create an event on `command.user_id`'s calendar on behalf of `actor`,
+    /// who must be the owner or one of their delegates
+    pub async fn create_event_view_as(
+        &self,
+        actor: UserId,
+        mut command: CreateEventCommand,
+    ) -> Result<EventView, ApplicationError> {
+        if actor != command.user_id {
+            // The username, if any, is the delegate's and not the owner's
+            command.username = None;
+        }
+        EventView::try_from(self.create_event(actor, command).await?)
+    }
+
+    async fn update_event(
+        &self,
+        actor: UserId,
+        mut command: UpdateEventCommand,
+    ) -> Result<Event, ApplicationError> {
+        self.text_limits.sanitize(
+            command.summary.as_mut(),
+            command.description.as_mut().and_then(Option::as_mut),
+            command.location.as_mut().and_then(Option::as_mut),
+            TextOverflow::Reject,
+        )?;
+        validate_event_fields(None, command.rrule.as_ref().and_then(Option::as_deref))?;
+        command.reminder_note = command.reminder_note.map(clean_reminder_note).transpose()?;
+
+        let mut write = self.begin_write_as(command.user_id, actor).await?;
+        let user_id = command.user_id;
+        write.lock_calendar(user_id).await.map_err(storage_error)?;
+        let current = write
+            .get_event_by_id(user_id, command.event_id)
+            .await
+            .map_err(storage_error)?
+            .ok_or_else(|| ApplicationError::NotFound(command.event_id.to_string()))?;
+
+        let previous_timing = timing_from_event(&current)?;
+        if let (Some(at), Some(rrule)) = (command.scope.cut(), current.rrule.clone()) {
+            let anchor = previous_timing.start_for_display();
+            let split = split_rrule(&rrule, anchor, at)?;
+            let rest = match command.scope {
+                EditScope::Occurrence(_) => occurrence_after(&rrule, anchor, at)?
+                    .map(|next| {
+                        split_rrule(&rrule, anchor, next)
+                            .map(|rest| (previous_timing.shifted(next - anchor), rest.tail))
+                    })
+                    .transpose()?,
+                _ => None,
+            };
+            // Cutting at the first occurrence of what remains is an edit of
+            // the whole series
+            if split.head.is_some() || rest.is_some() {
+                return self
+                    .update_series_part(write, current, command, at, split, rest)
+                    .await;
+            }
+        }
+
+        let timing = command.timing.unwrap_or_else(|| previous_timing.clone());
+        timing.validate()?;
+
+        let status = command.status.unwrap_or(current.status);
+        let summary = command.summary.unwrap_or_else(|| current.summary.clone());
+        let description = command
+            .description
+            .unwrap_or_else(|| current.description.clone());
+        let location = command.location.unwrap_or_else(|| current.location.clone());
+        let rrule = command.rrule.unwrap_or_else(|| current.rrule.clone());
+        let visibility = command.visibility.unwrap_or(current.visibility);
+        let reminder_note = command
+            .reminder_note
+            .unwrap_or_else(|| current.reminder_note.clone());
+        let attendees = write
+            .list_attendees(current.id)
+            .await
+            .map_err(storage_error)?;
+        let etag = etag_for_parts(
+            &current.uid,
+            &summary,
+            description.clone(),
+            location.clone(),
+            timing.clone(),
+            status,
+            rrule.clone(),
+            visibility,
+            reminder_note.clone(),
+            attendee_fingerprints(&attendees),
+        );
+        if etag == current.etag {
+            return Ok(current);
+        }
+
+        let version = current.version + 1;
+        let sync_version = write.sync_version(user_id).await?;
+
+        let event = write
+            .update_event(StoredEventUpdate {
+                id: current.id,
+                user_id,
+                summary,
+                description,
+                location,
+                timing,
+                status,
+                rrule,
+                version,
+                sequence: current.sequence + 1,
+                sync_version,
+                etag,
+                visibility,
+                reminder_note,
+            })
+            .await
+            .map_err(storage_error)?;
+
+        write.emit(DomainEvent::EventUpdated {
+            calendar_owner: user_id,
+            event_id: event.id,
+        });
+        if current.status != EventStatus::Cancelled && event.status == EventStatus::Cancelled {
+            for notice in cancellation_notices(&event, &attendees) {
+                write.emit(notice);
+            }
+        } else if event.status != EventStatus::Cancelled
+            && (timing_from_event(&event)? != previous_timing || event.location != current.location)
+        {
+            for notice in reschedule_notices(
+                &event,
+                &attendees,
+                &previous_timing,
+                current.location.as_ref(),
+                self.clock.now(),
+            ) {
+                write.emit(notice);
+            }
+        }
+        write.commit(&self.events).await?;
+        Ok(event)
+    }
+
+    pub async fn update_event_view(
+        &self,
+        command: UpdateEventCommand,
+    ) -> Result<EventView, ApplicationError> {
+        EventView::try_from(self.update_event(command.user_id, command).await?)
+    }
+
+    /// Update an event on `command.user_id`'s calendar on behalf of `actor`,
+    /// who must be the owner or one of their delegates
+    pub async fn update_event_view_as(
+        &self,
+        actor: UserId,
+        command: UpdateEventCommand,
+    ) -> Result<EventView, ApplicationError> {
+        EventView::try_from(self.update_event(actor, command).await?)
+    }
+
+    /// Apply `command` to the part of a series from the occurrence at `at`.
+    ///
+    /// The original event keeps its UID and the occurrences before the cut;
+    /// the edited part becomes a new event with the original's guests. When
+    /// only one occurrence is edited, the occurrences after it (`rest`, with
+    /// their timing and rule) carry on unchanged as a third event, or in the
+    /// original one when nothing came before the cut.
+    async fn update_series_part(
+        &self,
+        mut write: CalendarWrite<'_>,
+        current: Event,
+        command: UpdateEventCommand,
+        at: DateTime<Utc>,
+        split: SeriesSplit,
+        rest: Option<(EventTiming, String)>,
+    ) -> Result<Event, ApplicationError> {
+        let user_id = command.user_id;
+        let series_timing = timing_from_event(&current)?;
+        let occurrence_timing = series_timing.shifted(at - series_timing.start_for_display());
+        let timing = command.timing.unwrap_or_else(|| occurrence_timing.clone());
+        timing.validate()?;
+
+        let attendees = write
+            .list_attendees(current.id)
+            .await
+            .map_err(storage_error)?;
+        let guests: Vec<AttendeeWrite> = attendees
+            .iter()
+            .map(|attendee| AttendeeWrite {
+                email: attendee.email.clone(),
+                user_id: attendee.user_id,
+                role: attendee.role,
+                status: attendee.status,
+            })
+            .collect();
+        let sync_version = write.sync_version(user_id).await?;
+
+        // The original keeps what came before the cut, or else the rest
+        let (kept_timing, kept_rrule, rest) = match (split.head, rest) {
+            (Some(head), rest) => (series_timing, head, rest),
+            (None, Some((rest_timing, rest_rrule))) => (rest_timing, rest_rrule, None),
+            (None, None) => unreachable!("a cut at the first occurrence edits the whole series"),
+        };
+        let kept_rrule = Some(kept_rrule);
+        let version = current.version + 1;
+        let etag = etag_for_parts(
+            &current.uid,
+            &current.summary,
+            current.description.clone(),
+            current.location.clone(),
+            kept_timing.clone(),
+            current.status,
+            kept_rrule.clone(),
+            current.visibility,
+            current.reminder_note.clone(),
+            attendee_fingerprints(&attendees),
+        );
+        write
+            .update_event(StoredEventUpdate {
+                id: current.id,
+                user_id,
+                summary: current.summary.clone(),
+                description: current.description.clone(),
+                location: current.location.clone(),
+                timing: kept_timing,
+                status: current.status,
+                rrule: kept_rrule,
+                version,
+                sequence: current.sequence + 1,
+                sync_version,
+                etag,
+                visibility: current.visibility,
+                reminder_note: current.reminder_note.clone(),
+            })
+            .await
+            .map_err(storage_error)?;
+        write.emit(DomainEvent::EventUpdated {
+            calendar_owner: user_id,
+            event_id: current.id,
+        });
+
+        let rrule = match command.scope {
+            EditScope::Following(_) => command.rrule.unwrap_or(Some(split.tail)),
+            _ => command.rrule.unwrap_or(None),
+        };
+        let (event, event_attendees) = insert_with_attendees(
+            &mut write,
+            StoredEventWrite {
+                user_id,
+                uid: Uuid::new_v4().to_string(),
+                summary: command.summary.unwrap_or_else(|| current.summary.clone()),
+                description: command
+                    .description
+                    .unwrap_or_else(|| current.description.clone()),
+                location: command.location.unwrap_or_else(|| current.location.clone()),
+                timing,
+                status: command.status.unwrap_or(current.status),
+                rrule,
+                version: 1,
+                sequence: 0,
+                sync_version,
+                etag: "pending".to_string(),
+                visibility: command.visibility.unwrap_or(current.visibility),
+                reminder_note: command
+                    .reminder_note
+                    .unwrap_or_else(|| current.reminder_note.clone()),
+            },
+            &guests,
+        )
+        .await?;
+        write.emit(DomainEvent::EventCreated {
+            calendar_owner: user_id,
+            event_id: event.id,
+        });
+        if current.status != EventStatus::Cancelled && event.status == EventStatus::Cancelled {
+            for notice in cancellation_notices(&event, &event_attendees) {
+                write.emit(notice);
+            }
+        } else if event.status != EventStatus::Cancelled
+            && (timing_from_event(&event)? != occurrence_timing
+                || event.location != current.location)
+        {
+            for notice in reschedule_notices(
+                &event,
+                &event_attendees,
+                &occurrence_timing,
+                current.location.as_ref(),
+                self.clock.now(),
+            ) {
+                write.emit(notice);
+            }
+        }
+
+        if let Some((rest_timing, rest_rrule)) = rest {
+            let (remainder, _) = insert_with_attendees(
+                &mut write,
+                StoredEventWrite {
+                    user_id,
+                    uid: Uuid::new_v4().to_string(),
+                    summary: current.summary.clone(),
+                    description: current.description.clone(),
+                    location: current.location.clone(),
+                    timing: rest_timing,
+                    status: current.status,
+                    rrule: Some(rest_rrule),
+                    version: 1,
+                    sequence: 0,
+                    sync_version,
+                    etag: "pending".to_string(),
+                    visibility: current.visibility,
+                    reminder_note: current.reminder_note.clone(),
+                },
+                &guests,
+            )
+            .await?;
+            write.emit(DomainEvent::EventCreated {
+                calendar_owner: user_id,
+                event_id: remainder.id,
+            });
+        }
+
+        write.commit(&self.events).await?;
+        Ok(event)
+    }
+
+    /// Copy an event under a fresh UID, optionally moved by whole days.
+    ///
+    /// Guests are only carried over, as pending invitations, when asked for;
+    /// otherwise the copy is private and nobody is notified.
+    pub async fn duplicate_event(
+        &self,
+        command: DuplicateEventCommand,
+    ) -> Result<EventView, ApplicationError> {
+        if command.offset_days.abs() > MAX_DUPLICATE_OFFSET_DAYS {
+            return Err(ApplicationError::BadRequest(format!(
+                "Offset must be within {MAX_DUPLICATE_OFFSET_DAYS} days"
+            )));
+        }
+
+        let mut write = self.begin_write().await?;
+        let user_id = command.user_id;
+        let source = write
+            .get_event_by_id(user_id, command.event_id)
+            .await
+            .map_err(storage_error)?
+            .ok_or_else(|| ApplicationError::NotFound(command.event_id.to_string()))?;
+
+        let timing = timing_from_event(&source)?.shifted(Duration::days(command.offset_days));
+
+        let attendees: Vec<AttendeeWrite> = if command.include_attendees {
+            write
+                .list_attendees(source.id)
+                .await
+                .map_err(storage_error)?
+                .into_iter()
+                .map(|attendee| AttendeeWrite {
+                    status: match attendee.role {
+                        AttendeeRole::Organizer => attendee.status,
+                        AttendeeRole::Attendee => ParticipationStatus::NeedsAction,
+                    },
+                    email: attendee.email,
+                    user_id: attendee.user_id,
+                    role: attendee.role,
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        let sync_version = write.sync_version(user_id).await?;
+        let (event, _) = insert_with_attendees(
+            &mut write,
+            StoredEventWrite {
+                user_id,
+                uid: Uuid::new_v4().to_string(),
+                summary: source.summary.clone(),
+                description: source.description.clone(),
+                location: source.location.clone(),
+                timing,
+                status: source.status,
+                rrule: source.rrule.clone(),
+                version: 1,
+                sequence: 0,
+                sync_version,
+                etag: "pending".to_string(),
+                visibility: source.visibility,
+                reminder_note: source.reminder_note.clone(),
+            },
+            &attendees,
+        )
+        .await?;
+
+        write.emit(DomainEvent::EventCreated {
+            calendar_owner: user_id,
+            event_id: event.id,
+        });
+        for attendee in attendees {
+            if attendee.role == AttendeeRole::Organizer {
+                continue;
+            }
+            write.emit(DomainEvent::AttendeeInvited {
+                calendar_owner: user_id,
+                event_id: event.id,
+                event_summary: event.summary.clone(),
+                email: attendee.email,
+                attendee_user_id: attendee.user_id.map(UserId::new),
+            });
+        }
+
+        write.commit(&self.events).await?;
+        EventView::try_from(event)
+    }
+
+    /// The events [`Self::fix_timezone`] would move, without moving them
+    pub async fn preview_timezone_fix(
+        &self,
+        command: &TimezoneFixCommand,
+    ) -> Result<TimezoneFixSummary, ApplicationError> {
+        command.validate()?;
+        let events = self
+            .calendar
+            .list_events_in_timezone(
+                command.user_id,
+                command.from.as_str(),
+                command.start,
+                command.end,
+                MAX_TIMEZONE_FIX_EVENTS as i64 + 1,
+            )
+            .await
+            .map_err(storage_error)?;
+        timezone_fix_summary(&events)
+    }
+
+    /// Move the user's timed events saved in `command.from` to the same
+    /// wall-clock times in `command.to`, all or none of them.
+    ///
+    /// The calendar's sync token moves once for the whole fix. Guests of
+    /// events that haven't ended yet are told when the time moved.
+    pub async fn fix_timezone(
+        &self,
+        command: TimezoneFixCommand,
+    ) -> Result<TimezoneFixSummary, ApplicationError> {
+        command.validate()?;
+        let mut write = self.begin_write().await?;
+        let user_id = command.user_id;
+        write.lock_calendar(user_id).await.map_err(storage_error)?;
+        let events = write
+            .list_events_in_timezone(
+                user_id,
+                command.from.as_str(),
+                command.start,
+                command.end,
+                MAX_TIMEZONE_FIX_EVENTS as i64 + 1,
+            )
+            .await
+            .map_err(storage_error)?;
+        let summary = timezone_fix_summary(&events)?;
+        if events.is_empty() {
+            return Ok(summary);
+        }
+
+        let now = self.clock.now();
+        let event_ids: Vec<Uuid> = events.iter().map(|event| event.id).collect();
+        let mut attendees_by_event = write
+            .get_event_attendees_bulk(&event_ids)
+            .await
+            .map_err(storage_error)?;
+        let sync_version = write.sync_version(user_id).await?;
+        for current in events {
+            let previous_timing = timing_from_event(&current)?;
+            let (Some(start), Some(end)) = (current.start, current.end) else {
+                continue;
+            };
+            let timing = rezone(start, end, &command.from, &command.to);
+            let rrule = current
+                .rrule
+                .as_deref()
+                .map(|rrule| rezone_rrule(rrule, &command.from, &command.to));
+            let attendees = attendees_by_event.remove(&current.id).unwrap_or_default();
+            let etag = etag_for_parts(
+                &current.uid,
+                &current.summary,
+                current.description.clone(),
+                current.location.clone(),
+                timing.clone(),
+                current.status,
+                rrule.clone(),
+                current.visibility,
+                current.reminder_note.clone(),
+                attendee_fingerprints(&attendees),
+            );
+            let event = write
+                .update_event(StoredEventUpdate {
+                    id: current.id,
+                    user_id,
+                    summary: current.summary.clone(),
+                    description: current.description.clone(),
+                    location: current.location.clone(),
+                    timing,
+                    status: current.status,
+                    rrule,
+                    version: current.version + 1,
+                    sequence: current.sequence + 1,
+                    sync_version,
+                    etag,
+                    visibility: current.visibility,
+                    reminder_note: current.reminder_note.clone(),
+                })
+                .await
+                .map_err(storage_error)?;
+            write.emit(DomainEvent::EventUpdated {
+                calendar_owner: user_id,
+                event_id: event.id,
+            });
+
+            // Two zones may share an offset on some dates; then only the
+            // label changed and nobody needs telling
+            let moved = event.start != current.start || event.end != current.end;
+            let coming = event.rrule.is_some() || event.end.is_some_and(|end| end > now);
+            if moved && coming && event.status != EventStatus::Cancelled {
+                for notice in reschedule_notices(
+                    &event,
+                    &attendees,
+                    &previous_timing,
+                    current.location.as_ref(),
+                    now,
+                ) {
+                    write.emit(notice);
+                }
+            }
+        }
+        write.commit(&self.events).await?;
+        Ok(summary)
+    }
+
+    /// Create or replace an event uploaded by a CalDAV client.
+    ///
+    /// Over-long text is truncated rather than rejected, so a calendar synced
+    /// from elsewhere doesn't lose the event.
+    pub async fn put_event_by_uid(
+        &self,
+        mut command: PutEventCommand,
+    ) -> Result<PutEventResult, ApplicationError> {
+        self.text_limits.sanitize(
+            Some(&mut command.summary),
+            command.description.as_mut(),
+            command.location.as_mut(),
+            TextOverflow::Truncate,
+        )?;
+        validate_event_fields(Some(&command.uid), command.rrule.as_deref())?;
+        command.timing.validate()?;
+
+        let mut write = self.begin_write().await?;
+        let user_id = command.user_id;
+        // Two devices writing at once would otherwise both pass the etag
+        // check, or both find the UID free, on the same read
+        write.lock_calendar(user_id).await.map_err(storage_error)?;
+        let existing = write
+            .get_event_by_uid(user_id, &command.uid)
+            .await
+            .map_err(storage_error)?;
+
+        if let (Some(event), Some(expected_etag)) = (&existing, &command.expected_etag) {
+            let current_etag = format!("\"{}\"", event.etag);
+            if expected_etag != "*" && expected_etag != &current_etag {
+                return Err(ApplicationError::Conflict(format!(
+                    "ETag mismatch: {} != {}",
+                    expected_etag, current_etag
+                )));
+            }
+        }
+
+        let visibility = match (command.visibility, &existing) {
+            (Some(visibility), _) => visibility,
+            (None, Some(event)) => event.visibility,
+            (None, None) => self.default_visibility(user_id).await?,
+        };
+        // Notes are set through the API and bot; clients only see them as
+        // alarms, so a PUT keeps the stored one
+        let reminder_note = existing
+            .as_ref()
+            .and_then(|event| event.reminder_note.clone());
+        let requested_etag = etag_for_parts(
+            &command.uid,
+            &command.summary,
+            command.description.clone(),
+            command.location.clone(),
+            command.timing.clone(),
+            command.status,
+            command.rrule.clone(),
+            visibility,
+            reminder_note.clone(),
+            command
+                .attendees
+                .iter()
+                .map(|attendee| AttendeeFingerprint {
+                    email: attendee.email.clone(),
+                    user_id: attendee.user_id.map(UserId::inner),
+                    role: attendee.role,
+                    status: attendee.status,
+                })
+                .collect(),
+        );
+        // Re-PUTting identical content is a no-op: keep the stored revision so
+        // clients holding the etag don't re-download the event.
+        if let Some(event) = &existing
+            && event.etag == requested_etag
+        {
+            return Ok(PutEventResult {
+                etag: requested_etag,
+                created: false,
+                conflict: None,
+            });
+        }
+
+        // A SEQUENCE behind the stored one means the device edited a copy it
+        // fetched before a newer change, and writing it would undo that change.
+        // The stored value only moves past what a device last sent when another
+        // device sends a higher one or the API edits the event, so a device's
+        // own consecutive edits pass. An If-Match on the current etag says the
+        // device has seen the latest copy anyway.
+        if let (Some(event), Some(sequence)) = (&existing, command.sequence)
+            && sequence < event.sequence
+            && command.expected_etag.as_deref() != Some(format!("\"{}\"", event.etag).as_str())
+        {
+            tracing::warn!(
+                "Rejected stale PUT of {} for user {}: SEQUENCE {} behind {}",
+                command.uid,
+                user_id,
+                sequence,
+                event.sequence
+            );
+            return Ok(PutEventResult {
+                etag: event.etag.clone(),
+                created: false,
+                conflict: Some(SequenceConflict {
+                    uid: command.uid,
+                    client_sequence: sequence,
+                    server_sequence: event.sequence,
+                }),
+            });
+        }
+
+        let created = existing.is_none();
+        let newly_cancelled = command.status == EventStatus::Cancelled
+            && existing
+                .as_ref()
+                .is_some_and(|event| event.status != EventStatus::Cancelled);
+        let previous_place = existing
+            .as_ref()
+            .map(|event| {
+                Ok::<_, ApplicationError>((timing_from_event(event)?, event.location.clone()))
+            })
+            .transpose()?;
+        let version = existing.as_ref().map_or(1, |event| event.version + 1);
+        let sync_version = write.sync_version(user_id).await?;
+
+        let provisional_etag = "pending".to_string();
+        let event = if let Some(existing_event) = existing {
+            write
+                .update_event(StoredEventUpdate {
+                    id: existing_event.id,
+                    user_id,
+                    summary: command.summary.clone(),
+                    description: command.description.clone(),
+                    location: command.location.clone(),
+                    timing: command.timing.clone(),
+                    status: command.status,
+                    rrule: command.rrule.clone(),
+                    version,
+                    sequence: command.sequence.unwrap_or(existing_event.sequence),
+                    sync_version,
+                    etag: provisional_etag,
+                    visibility,
+                    reminder_note: reminder_note.clone(),
+                })
+                .await
+                .map_err(storage_error)?
+        } else {
+            write
+                .insert_event(StoredEventWrite {
+                    user_id,
+                    uid: command.uid.clone(),
+                    summary: command.summary.clone(),
+                    description: command.description.clone(),
+                    location: command.location.clone(),
+                    timing: command.timing.clone(),
+                    status: command.status,
+                    rrule: command.rrule.clone(),
+                    version,
+                    sequence: command.sequence.unwrap_or(0),
+                    sync_version,
+                    etag: provisional_etag,
+                    visibility,
+                    reminder_note: reminder_note.clone(),
+                })
+                .await
+                .map_err(storage_error)?
+        };
+
+        let attendee_writes: Vec<_> = command
+            .attendees
+            .iter()
+            .map(|attendee| AttendeeWrite {
+                email: attendee.email.clone(),
+                user_id: attendee.user_id.map(UserId::inner),
+                role: attendee.role,
+                status: attendee.status,
+            })
+            .collect();
+        let upsert_results = write
+            .replace_attendees(event.id, &attendee_writes)
+            .await
+            .map_err(storage_error)?;
+        let final_attendees = write
+            .list_attendees(event.id)
+            .await
+            .map_err(storage_error)?;
+        let etag = etag_for_parts(
+            &command.uid,
+            &command.summary,
+            command.description.clone(),
+            command.location.clone(),
+            command.timing,
+            command.status,
+            command.rrule,
+            visibility,
+            reminder_note,
+            attendee_fingerprints(&final_attendees),
+        );
+        let event = write
+            .set_event_sync_etag(event.id, user_id, version, sync_version, etag)
+            .await
+            .map_err(storage_error)?;
+
+        write.emit(if created {
+            DomainEvent::EventCreated {
+                calendar_owner: user_id,
+                event_id: event.id,
+            }
+        } else {
+            DomainEvent::EventUpdated {
+                calendar_owner: user_id,
+                event_id: event.id,
+            }
+        });
+        for upsert_result in upsert_results {
+            if !upsert_result.is_new {
+                continue;
+            }
+            write.emit(DomainEvent::AttendeeInvited {
+                calendar_owner: user_id,
+                event_id: event.id,
+                event_summary: event.summary.clone(),
+                email: upsert_result.email,
+                attendee_user_id: upsert_result.user_id.map(UserId::new),
+            });
+        }
+        if newly_cancelled {
+            for notice in cancellation_notices(&event, &final_attendees) {
+                write.emit(notice);
+            }
+        } else if let Some((previous_timing, previous_location)) = previous_place
+            && event.status != EventStatus::Cancelled
+            && (timing_from_event(&event)? != previous_timing
+                || event.location != previous_location)
+        {
+            for notice in reschedule_notices(
+                &event,
+                &final_attendees,
+                &previous_timing,
+                previous_location.as_ref(),
+                self.clock.now(),
+            ) {
+                write.emit(notice);
+            }
+        }
+
+        write.commit(&self.events).await?;
+        Ok(PutEventResult {
+            etag: event.etag,
+            created,
+            conflict: None,
+        })
+    }
+
+    pub async fn delete_event_by_id(
+        &self,
+        user_id: UserId,
+        event_id: Uuid,
+    ) -> Result<(), ApplicationError> {
+        self.delete_event_by_id_as(user_id, user_id, event_id).await
+    }
+
+    /// Delete an event from `user_id`'s calendar on behalf of `actor`, who
+    /// must be the owner or one of their delegates
+    pub async fn delete_event_by_id_as(
+        &self,
+        actor: UserId,
+        user_id: UserId,
+        event_id: Uuid,
+    ) -> Result<(), ApplicationError> {
+        let mut write = self.begin_write_as(user_id, actor).await?;
+        write.lock_calendar(user_id).await.map_err(storage_error)?;
+        let deleted = write
+            .delete_event_by_id(user_id, event_id)
+            .await
+            .map_err(storage_error)?
+            .ok_or_else(|| ApplicationError::NotFound(event_id.to_string()))?;
+        write.emit(DomainEvent::EventDeleted {
+            calendar_owner: user_id,
+            event_id: deleted.id,
+            uid: deleted.uid,
+        });
+        write.commit(&self.events).await
+    }
+
+    pub async fn delete_event_by_uid(
+        &self,
+        user_id: UserId,
+        uid: &str,
+        expected_etag: Option<String>,
+    ) -> Result<(), ApplicationError> {
+        let mut write = self.begin_write().await?;
+        write.lock_calendar(user_id).await.map_err(storage_error)?;
+        let current = write
+            .get_event_by_uid(user_id, uid)
+            .await
+            .map_err(storage_error)?
+            .ok_or_else(|| ApplicationError::NotFound(uid.to_string()))?;
+
+        if let Some(expected_etag) = expected_etag {
+            let current_etag = format!("\"{}\"", current.etag);
+            if expected_etag != "*" && expected_etag != current_etag {
+                return Err(ApplicationError::Conflict(format!(
+                    "ETag mismatch: {} != {}",
+                    expected_etag, current_etag
+                )));
+            }
+        }
+
+        let deleted = write
+            .delete_event_by_uid(user_id, uid)
+            .await
+            .map_err(storage_error)?
+            .ok_or_else(|| ApplicationError::NotFound(uid.to_string()))?;
+        write.emit(DomainEvent::EventDeleted {
+            calendar_owner: user_id,
+            event_id: deleted.id,
+            uid: deleted.uid,
+        });
+        write.commit(&self.events).await
+    }
+
+    pub async fn invite_attendee(
+        &self,
+        command: InviteAttendeeCommand,
+    ) -> Result<(), ApplicationError> {
+        let mut write = self.begin_write().await?;
+        let organizer_user_id = command.organizer_user_id;
+        let current = write
+            .get_event_by_id(organizer_user_id, command.event_id)
+            .await
+            .map_err(storage_error)?
+            .ok_or_else(|| ApplicationError::NotFound(command.event_id.to_string()))?;
+
+        let attendees = [AttendeeWrite {
+            email: command.email.clone(),
+            user_id: command.attendee_user_id.map(UserId::inner),
+            role: command.role,
+            status: ParticipationStatus::NeedsAction,
+        }];
+        // Re-inviting someone whose invite is still pending changes nothing:
+        // keep the revision so clients don't re-download the event.
+        let unchanged = write
+            .list_attendees(current.id)
+            .await
+            .map_err(storage_error)?
+            .iter()
+            .any(|existing| {
+                existing.email == attendees[0].email
+                    && existing.user_id == attendees[0].user_id
+                    && existing.role == attendees[0].role
+                    && existing.status == attendees[0].status
+            });
+        if unchanged {
+            return Ok(());
+        }
+
+        let upsert_results = write
+            .upsert_attendees(current.id, &attendees)
+            .await
+            .map_err(storage_error)?;
+
+        let version = current.version + 1;
+        let sync_version = write.sync_version(organizer_user_id).await?;
+        let final_attendees = write
+            .list_attendees(current.id)
+            .await
+            .map_err(storage_error)?;
+        let etag = etag_for_event(&current, &final_attendees)?;
+        let event = write
+            .set_event_sync_etag(current.id, organizer_user_id, version, sync_version, etag)
+            .await
+            .map_err(storage_error)?;
+
+        if upsert_results.iter().any(|result| result.is_new) {
+            write.emit(DomainEvent::AttendeeInvited {
+                calendar_owner: organizer_user_id,
+                event_id: event.id,
+                event_summary: event.summary.clone(),
+                email: command.email,
+                attendee_user_id: command.attendee_user_id,
+            });
+        }
+
+        write.commit(&self.events).await
+    }
+
+    /// Invite several people in one transaction.
+    ///
+    /// People already on the guest list are reported back untouched, so a
+    /// repeated bulk invite never resets their RSVP. The event revision is
+    /// bumped once and all notifications are queued together.
+    pub async fn invite_attendees(
+        &self,
+        command: InviteAttendeesCommand,
+    ) -> Result<InviteAttendeesResult, ApplicationError> {
+        if command.invitees.len() > MAX_INVITEES_PER_REQUEST {
+            return Err(ApplicationError::BadRequest(format!(
+                "Too many invitees (max {MAX_INVITEES_PER_REQUEST})"
+            )));
+        }
+
+        let mut write = self.begin_write().await?;
+        let organizer_user_id = command.organizer_user_id;
+        let current = write
+            .get_event_by_id(organizer_user_id, command.event_id)
+            .await
+            .map_err(storage_error)?
+            .ok_or_else(|| ApplicationError::NotFound(command.event_id.to_string()))?;
+        let existing = write
+            .list_attendees(current.id)
+            .await
+            .map_err(storage_error)?;
+
+        let mut result = InviteAttendeesResult::default();
+        let mut attendees: Vec<AttendeeWrite> = Vec::with_capacity(command.invitees.len());
+        for invitee in command.invitees {
+            let already_listed = existing
+                .iter()
+                .any(|attendee| attendee.email.eq_ignore_ascii_case(&invitee.email))
+                || attendees
+                    .iter()
+                    .any(|attendee| attendee.email.eq_ignore_ascii_case(&invitee.email));
+            if already_listed {
+                result.already_invited.push(invitee.email);
+                continue;
+            }
+            attendees.push(AttendeeWrite {
+                email: invitee.email,
+                user_id: invitee.attendee_user_id.map(UserId::inner),
+                role: invitee.role,
+                status: ParticipationStatus::NeedsAction,
+            });
+        }
+
+        if attendees.is_empty() {
+            return Ok(result);
+        }
+
+        let upsert_results = write
+            .upsert_attendees(current.id, &attendees)
+            .await
+            .map_err(storage_error)?;
+
+        let version = current.version + 1;
+        let sync_version = write.sync_version(organizer_user_id).await?;
+        let final_attendees = write
+            .list_attendees(current.id)
+            .await
+            .map_err(storage_error)?;
+        let etag = etag_for_event(&current, &final_attendees)?;
+        let event = write
+            .set_event_sync_etag(current.id, organizer_user_id, version, sync_version, etag)
+            .await
+            .map_err(storage_error)?;
+
+        for upsert_result in upsert_results {
+            write.emit(DomainEvent::AttendeeInvited {
+                calendar_owner: organizer_user_id,
+                event_id: event.id,
+                event_summary: event.summary.clone(),
+                email: upsert_result.email.clone(),
+                attendee_user_id: upsert_result.user_id.map(UserId::new),
+            });
+            result.invited.push(upsert_result.email);
+        }
+
+        write.commit(&self.events).await?;
+        Ok(result)
+    }
+
+    /// Take someone off the guest list of an event the organizer owns.
+    pub async fn remove_attendee(
+        &self,
+        command: RemoveAttendeeCommand,
+    ) -> Result<(), ApplicationError> {
+        let mut write = self.begin_write().await?;
+        let organizer_user_id = command.organizer_user_id;
+        let current = write
+            .get_event_by_id(organizer_user_id, command.event_id)
+            .await
+            .map_err(storage_error)?
+            .ok_or_else(|| ApplicationError::NotFound(command.event_id.to_string()))?;
+        let removed = write
+            .delete_attendee(current.id, &command.email)
+            .await
+            .map_err(storage_error)?
+            .ok_or_else(|| ApplicationError::NotFound(command.email.clone()))?;
+
+        let version = current.version + 1;
+        let sync_version = write.sync_version(organizer_user_id).await?;
+        let final_attendees = write
+            .list_attendees(current.id)
+            .await
+            .map_err(storage_error)?;
+        let etag = etag_for_event(&current, &final_attendees)?;
+        let event = write
+            .set_event_sync_etag(current.id, organizer_user_id, version, sync_version, etag)
+            .await
+            .map_err(storage_error)?;
+
+        write.emit(DomainEvent::AttendeeRemoved {
+            calendar_owner: organizer_user_id,
+            event_id: event.id,
+            event_summary: event.summary,
+            email: removed.email,
+            attendee_user_id: removed.user_id.map(UserId::new),
+        });
+        write.commit(&self.events).await
+    }
+
+    /// Nudge an internal attendee who has not answered an invite yet.
+    pub async fn resend_invite(
+        &self,
+        command: ResendInviteCommand,
+    ) -> Result<(), ApplicationError> {
+        let mut write = self.begin_write().await?;
+        let (event_id, attendee_user_id) = pending_invitee(&mut write, &command).await?;
+
+        write.emit(DomainEvent::InviteResent {
+            event_id,
+            attendee_user_id,
+        });
+        write.commit(&self.events).await
+    }
+
+    /// Re-send an invite at `send_at` instead of now; returns the scheduled
+    /// message id, which the organizer can cancel until it is sent
+    pub async fn schedule_invite_reminder(
+        &self,
+        command: ResendInviteCommand,
+        send_at: DateTime<Utc>,
+        now: DateTime<Utc>,
+    ) -> Result<OutboxMessageId, ApplicationError> {
+        validate_send_at(send_at, now)?;
+        let mut write = self.begin_write().await?;
+        let (event_id, attendee_user_id) = pending_invitee(&mut write, &command).await?;
+
+        let reminder = OutboxPayload::InviteReminder(InviteReminder {
+            event_id,
+            target_user_id: attendee_user_id.inner(),
+        });
+        let message_id = write
+            .schedule_outbox(&reminder, send_at, command.organizer_user_id)
+            .await
+            .map_err(storage_error)?
+            .ok_or_else(|| {
+                ApplicationError::Conflict("This reminder is already scheduled".to_string())
+            })?;
+        write.commit(&self.events).await?;
+        Ok(message_id)
+    }
+
+    /// Have an invite reminder come back later for the invitee who got it
+    pub async fn snooze_invite_reminder(
+        &self,
+        invitee: UserId,
+        event_id: Uuid,
+        delay: SnoozeDelay,
+        now: DateTime<Utc>,
+    ) -> Result<ScheduledMessageView, ApplicationError> {
+        let timezone = self
+            .calendar
+            .get_user_by_id(invitee)
+            .await
+            .map_err(storage_error)?
+            .ok_or_else(|| ApplicationError::NotFound(invitee.to_string()))?
+            .timezone;
+        let send_at = delay.send_at(now, &timezone);
+        validate_send_at(send_at, now)?;
+
+        let mut write = self.begin_write().await?;
+        let attendee = write
+            .list_attendees(event_id)
+            .await
+            .map_err(storage_error)?
+            .into_iter()
+            .find(|attendee| attendee.user_id == Some(invitee.inner()))
+            .ok_or_else(|| ApplicationError::NotFound(event_id.to_string()))?;
+        if attendee.status != ParticipationStatus::NeedsAction {
+            return Err(ApplicationError::BadRequest(
+                "You already replied to this invite".to_string(),
+            ));
+        }
+
+        let reminder = OutboxPayload::InviteReminder(InviteReminder {
+            event_id,
+            target_user_id: invitee.inner(),
+        });
+        let id = write
+            .schedule_outbox(&reminder, send_at, invitee)
+            .await
+            .map_err(storage_error)?
+            .ok_or_else(|| {
+                ApplicationError::Conflict("This reminder is already scheduled".to_string())
+            })?;
+        write.commit(&self.events).await?;
+
+        Ok(ScheduledMessageView {
+            id,
+            kind: reminder.kind(),
+            event_id: Some(event_id),
+            scheduled_at: send_at,
+            created_at: now,
+        })
+    }
+
+    /// Messages the user scheduled that haven't been sent, soonest first
+    pub async fn list_scheduled_messages(
+        &self,
+        user_id: UserId,
+    ) -> Result<Vec<ScheduledMessageView>, ApplicationError> {
+        self.calendar
+            .list_scheduled_outbox(user_id)
+            .await
+            .map_err(storage_error)?
+            .into_iter()
+            .map(ScheduledMessageView::from_stored)
+            .collect()
+    }
+
+    /// Cancel a message the user scheduled; `NotFound` once it is being sent
+    /// or already gone
+    pub async fn cancel_scheduled_message(
+        &self,
+        user_id: UserId,
+        message_id: OutboxMessageId,
+    ) -> Result<(), ApplicationError> {
+        let cancelled = self
+            .calendar
+            .cancel_scheduled_outbox(user_id, message_id)
+            .await
+            .map_err(storage_error)?;
+        if !cancelled {
+            return Err(ApplicationError::NotFound(message_id.to_string()));
+        }
+        Ok(())
+    }
+
+    pub async fn confirm_rsvp(&self, command: ConfirmRsvpCommand) -> Result<(), ApplicationError> {
+        let mut write = self.begin_write().await?;
+        // Repeating the current answer is a no-op, like an identical PUT
+        let current_status = write
+            .list_attendees(command.event_id)
+            .await
+            .map_err(storage_error)?
+            .into_iter()
+            .find(|attendee| attendee.user_id == Some(command.attendee_user_id.inner()))
+            .map(|attendee| attendee.status);
+        if current_status == Some(command.status) {
+            return Ok(());
+        }
+
+        let updated = write
+            .update_attendee_status(
+                command.event_id,
+                command.attendee_user_id.inner(),
+                command.status,
+            )
+            .await
+            .map_err(storage_error)?;
+
+        if !updated {
+            return Err(ApplicationError::NotFound(command.event_id.to_string()));
+        }
+
+        let current = write
+            .get_event_by_id_any(command.event_id)
+            .await
+            .map_err(storage_error)?
+            .ok_or_else(|| ApplicationError::NotFound(command.event_id.to_string()))?;
+        let organizer_user_id = current.user_id;
+        let version = current.version + 1;
+        let sync_version = write.sync_version(organizer_user_id).await?;
+        let attendees = write
+            .list_attendees(current.id)
+            .await
+            .map_err(storage_error)?;
+        let etag = etag_for_event(&current, &attendees)?;
+        let event = write
+            .set_event_sync_etag(current.id, organizer_user_id, version, sync_version, etag)
+            .await
+            .map_err(storage_error)?;
+
+        write.emit(DomainEvent::RsvpRecorded {
+            calendar_owner: organizer_user_id,
+            event_id: event.id,
+            event_summary: event.summary,
+            attendee_name: command.attendee_name,
+            status: command.status,
+        });
+        write.commit(&self.events).await
+    }
+
+    /// Publish the event under a public signup page and return its slug.
+    ///
+    /// Publishing is idempotent: an already public event keeps its slug so
+    /// links that were shared earlier stay valid. Private events have no
+    /// page; busy-only ones get one that shows only the time.
+    pub async fn publish_event(
+        &self,
+        user_id: UserId,
+        event_id: Uuid,
+    ) -> Result<String, ApplicationError> {
+        let event = self
+            .calendar
+            .get_event_by_id(user_id, event_id)
+            .await
+            .map_err(storage_error)?
+            .ok_or_else(|| ApplicationError::NotFound(event_id.to_string()))?;
+        if event.visibility == EventVisibility::Private {
+            return Err(ApplicationError::BadRequest(
+                "Private events can't be published".to_string(),
+            ));
+        }
+
+        self.calendar
+            .publish_event(user_id, event_id, &generate_password(PUBLIC_SLUG_LEN))
+            .await
+            .map_err(storage_error)?
+            .ok_or_else(|| ApplicationError::NotFound(event_id.to_string()))
+    }
+
+    /// Take the public page down; pending signups can no longer be confirmed.
+    pub async fn unpublish_event(
+        &self,
+        user_id: UserId,
+        event_id: Uuid,
+    ) -> Result<(), ApplicationError> {
+        if self
+            .calendar
+            .unpublish_event(user_id, event_id)
+            .await
+            .map_err(storage_error)?
+        {
+            Ok(())
+        } else {
+            Err(ApplicationError::NotFound(event_id.to_string()))
+        }
+    }
+
+    /// Share a daily window of the user's time on a new booking page
+    pub async fn create_booking_link(
+        &self,
+        command: CreateBookingLinkCommand,
+    ) -> Result<BookingLinkView, ApplicationError> {
+        command.validate()?;
+        let existing = self
+            .calendar
+            .list_booking_links(command.user_id)
+            .await
+            .map_err(storage_error)?;
+        if existing.len() >= MAX_BOOKING_LINKS_PER_USER {
+            return Err(ApplicationError::BadRequest(format!(
+                "You can keep at most {MAX_BOOKING_LINKS_PER_USER} booking links"
+            )));
+        }
+
+        let link = self
+            .calendar
+            .create_booking_link(BookingLinkWrite {
+                user_id: command.user_id,
+                slug: generate_password(PUBLIC_SLUG_LEN),
+                title: command.title.trim().to_string(),
+                duration_minutes: command.duration.num_minutes() as i32,
+                day_start: command.hours.start,
+                day_end: command.hours.end,
+                within_days: command.within_days as i32,
+            })
+            .await
+            .map_err(storage_error)?;
+        Ok(link.into())
+    }
+
+    /// The user's booking links, newest first
+    pub async fn list_booking_links(
+        &self,
+        user_id: UserId,
+    ) -> Result<Vec<BookingLinkView>, ApplicationError> {
+        Ok(self
+            .calendar
+            .list_booking_links(user_id)
+            .await
+            .map_err(storage_error)?
+            .into_iter()
+            .map(BookingLinkView::from)
+            .collect())
+    }
+
+    /// Take a booking page down; events already booked stay
+    pub async fn delete_booking_link(
+        &self,
+        user_id: UserId,
+        link_id: Uuid,
+    ) -> Result<(), ApplicationError> {
+        if self
+            .calendar
+            .delete_booking_link(user_id, link_id)
+            .await
+            .map_err(storage_error)?
+        {
+            Ok(())
+        } else {
+            Err(ApplicationError::NotFound(link_id.to_string()))
+        }
+    }
+
+    /// Add a task to the user's list, to be planned later
+    pub async fn create_task(
+        &self,
+        command: CreateTaskCommand,
+    ) -> Result<TaskView, ApplicationError> {
+        command.validate()?;
+        let open = self
+            .calendar
+            .list_open_tasks(command.user_id)
+            .await
+            .map_err(storage_error)?;
+        if open.len() >= MAX_OPEN_TASKS_PER_USER {
+            return Err(ApplicationError::BadRequest(format!(
+                "You can keep at most {MAX_OPEN_TASKS_PER_USER} open tasks"
+            )));
+        }
+
+        let task = self
+            .calendar
+            .create_task(
+                command.user_id,
+                command.title.trim(),
+                command.duration.num_minutes() as i32,
+            )
+            .await
+            .map_err(storage_error)?;
+        Ok(task.into())
+    }
+
+    /// Remove a task; its block, if any, stays on the calendar
+    pub async fn delete_task(
+        &self,
+        user_id: UserId,
+        task_id: Uuid,
+    ) -> Result<(), ApplicationError> {
+        if self
+            .calendar
+            .delete_task(user_id, task_id)
+            .await
+            .map_err(storage_error)?
+        {
+            Ok(())
+        } else {
+            Err(ApplicationError::NotFound(task_id.to_string()))
+        }
+    }
+
+    /// Tick a task off; its block stays on the calendar
+    pub async fn complete_task(
+        &self,
+        user_id: UserId,
+        task_id: Uuid,
+    ) -> Result<(), ApplicationError> {
+        if self
+            .calendar
+            .complete_task(user_id, task_id)
+            .await
+            .map_err(storage_error)?
+        {
+            Ok(())
+        } else {
+            Err(ApplicationError::NotFound(task_id.to_string()))
+        }
+    }
+
+    /// Put an open task back on the list to plan, leaving its past block on
+    /// the calendar, for when the block wasn't enough to finish it
+    pub async fn unschedule_task(
+        &self,
+        user_id: UserId,
+        task_id: Uuid,
+    ) -> Result<(), ApplicationError> {
+        if self
+            .calendar
+            .unlink_task(user_id, task_id)
+            .await
+            .map_err(storage_error)?
+        {
+            Ok(())
+        } else {
+            Err(ApplicationError::NotFound(task_id.to_string()))
+        }
+    }
+
+    /// Fill the free time in `free_busy`'s window with the user's open tasks
+    /// that have no block yet, oldest first, as tentative events. Tentative
+    /// blocks that ended unconfirmed are deleted and planned again.
+    ///
+    /// `free_busy` holds the user's busy time in the window, own and
+    /// subscribed; blocks start no earlier than now. Each block gets a
+    /// reminder at its end asking whether the task is done, which is only
+    /// sent if the block was confirmed by then.
+    pub async fn plan_tasks(
+        &self,
+        user_id: UserId,
+        mut free_busy: FreeBusy,
+        working_hours: Option<WorkingHours>,
+    ) -> Result<TaskPlan, ApplicationError> {
+        let now = self.clock.now();
+        let user = self
+            .calendar
+            .get_user_by_id(user_id)
+            .await
+            .map_err(storage_error)?
+            .ok_or_else(|| ApplicationError::NotFound(user_id.to_string()))?;
+
+        let mut write = self.begin_write().await?;
+        write.lock_calendar(user_id).await.map_err(storage_error)?;
+        let tasks = write
+            .list_open_tasks_for_update(user_id)
+            .await
+            .map_err(storage_error)?;
+
+        let mut plan = TaskPlan::default();
+        for task in tasks.into_iter().map(TaskView::from) {
+            match task.block {
+                // A tentative block that ended without being confirmed was
+                // skipped; it makes way for a new one
+                Some(block) if !block.confirmed && block.end <= now => {
+                    delete_block(&mut write, user_id, block.event_id).await?;
+                }
+                Some(_) => continue,
+                None => {}
+            }
+            let Some((start, end)) =
+                next_block(&free_busy, now, task.duration, working_hours.as_ref())
+            else {
+                plan.unplanned.push(task);
+                continue;
+            };
+            free_busy.block(start, end);
+
+            let event = insert_tentative_block(
+                &mut write,
+                &user,
+                task.title.clone(),
+                (start, end),
+                free_busy.timezone(),
+            )
+            .await?;
+            write
+                .link_task(task.id, event.id)
+                .await
+                .map_err(storage_error)?;
+            let reminder = OutboxPayload::TaskBlockReminder(TaskBlockReminder {
+                task_id: task.id,
+                event_id: event.id,
+                target_user_id: user_id.inner(),
+            });
+            write
+                .schedule_outbox(&reminder, end, user_id)
+                .await
+                .map_err(storage_error)?;
+            plan.blocks.push(PlannedBlock {
+                task_id: task.id,
+                event_id: event.id,
+                title: task.title,
+                start,
+                end,
+            });
+        }
+
+        write.commit(&self.events).await?;
+        plan.blocks.sort_by_key(|block| block.start);
+        Ok(plan)
+    }
+
+    /// Confirm the tentative block planned for a task
+    pub async fn confirm_task_block(
+        &self,
+        user_id: UserId,
+        task_id: Uuid,
+    ) -> Result<TaskView, ApplicationError> {
+        let task: TaskView = self
+            .calendar
+            .get_task(user_id, task_id)
+            .await
+            .map_err(storage_error)?
+            .filter(|task| task.completed_at.is_none())
+            .ok_or_else(|| ApplicationError::NotFound(task_id.to_string()))?
+            .into();
+        let Some(mut block) = task.block else {
+            return Err(ApplicationError::BadRequest(
+                "This task has no block to confirm; plan it first".to_string(),
+            ));
+        };
+        if !block.confirmed {
+            self.set_block_status(user_id, block.event_id, EventStatus::Confirmed)
+                .await?;
+            block.confirmed = true;
+        }
+        Ok(TaskView {
+            block: Some(block),
+            ..task
+        })
+    }
+
+    /// Confirm every tentative block of the user's open tasks; returns how
+    /// many were confirmed
+    pub async fn confirm_planned_blocks(&self, user_id: UserId) -> Result<usize, ApplicationError> {
+        let mut confirmed = 0;
+        for block in self.tentative_blocks(user_id).await? {
+            self.set_block_status(user_id, block, EventStatus::Confirmed)
+                .await?;
+            confirmed += 1;
+        }
+        Ok(confirmed)
+    }
+
+    /// Delete every tentative block of the user's open tasks, so the tasks
+    /// can be planned again; returns how many were deleted
+    pub async fn discard_planned_blocks(&self, user_id: UserId) -> Result<usize, ApplicationError> {
+        let mut discarded = 0;
+        for block in self.tentative_blocks(user_id).await? {
+            match self.delete_event_by_id(user_id, block).await {
+                Ok(()) => discarded += 1,
+                // Deleted meanwhile
+                Err(ApplicationError::NotFound(_)) => {}
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(discarded)
+    }
+
+    async fn tentative_blocks(&self, user_id: UserId) -> Result<Vec<Uuid>, ApplicationError> {
+        Ok(self
+            .calendar
+            .list_open_tasks(user_id)
+            .await
+            .map_err(storage_error)?
+            .into_iter()
+            .filter_map(|task| TaskView::from(task).block)
+            .filter(|block| !block.confirmed)
+            .map(|block| block.event_id)
+            .collect())
+    }
+
+    async fn set_block_status(
+        &self,
+        user_id: UserId,
+        event_id: Uuid,
+        status: EventStatus,
+    ) -> Result<(), ApplicationError> {
+        self.update_event(
+            user_id,
+            UpdateEventCommand {
+                user_id,
+                event_id,
+                summary: None,
+                description: None,
+                location: None,
+                timing: None,
+                status: Some(status),
+                rrule: None,
+                visibility: None,
+                reminder_note: None,
+                scope: EditScope::Series,
+            },
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Add a focus rule; its blocks are placed by [`crate::schedule_focus_time`]
+    pub async fn create_focus_rule(
+        &self,
+        command: FocusRuleCommand,
+    ) -> Result<FocusRuleView, ApplicationError> {
+        let title = command.validated_title()?;
+        let rules = self
+            .calendar
+            .list_focus_rules(command.user_id)
+            .await
+            .map_err(storage_error)?;
+        if rules.len() >= MAX_FOCUS_RULES_PER_USER {
+            return Err(ApplicationError::BadRequest(format!(
+                "You can keep at most {MAX_FOCUS_RULES_PER_USER} focus rules"
+            )));
+        }
+
+        let working_hours = command.working_hours.as_ref().map(ToString::to_string);
+        let rule = self
+            .calendar
+            .create_focus_rule(
+                command.user_id,
+                focus_fields(&command, &title, working_hours.as_deref()),
+            )
+            .await
+            .map_err(storage_error)?;
+        Ok(command.view(rule))
+    }
+
+    /// Change a focus rule; its tentative blocks still ahead are removed, to
+    /// be placed again by [`crate::schedule_focus_time`]
+    pub async fn update_focus_rule(
+        &self,
+        rule_id: Uuid,
+        command: FocusRuleCommand,
+    ) -> Result<FocusRuleView, ApplicationError> {
+        let title = command.validated_title()?;
+        let user_id = command.user_id;
+        let working_hours = command.working_hours.as_ref().map(ToString::to_string);
+
+        let mut write = self.begin_write().await?;
+        write.lock_calendar(user_id).await.map_err(storage_error)?;
+        let rule = write
+            .update_focus_rule(
+                user_id,
+                rule_id,
+                focus_fields(&command, &title, working_hours.as_deref()),
+            )
+            .await
+            .map_err(storage_error)?
+            .ok_or_else(|| ApplicationError::NotFound(rule_id.to_string()))?;
+        self.clear_focus_blocks(&mut write, user_id, rule_id)
+            .await?;
+        write.commit(&self.events).await?;
+
+        Ok(command.view(rule))
+    }
+
+    /// Remove a focus rule with its tentative blocks still ahead; confirmed
+    /// blocks stay on the calendar as ordinary events
+    pub async fn delete_focus_rule(
+        &self,
+        user_id: UserId,
+        rule_id: Uuid,
+    ) -> Result<(), ApplicationError> {
+        let mut write = self.begin_write().await?;
+        write.lock_calendar(user_id).await.map_err(storage_error)?;
+        self.clear_focus_blocks(&mut write, user_id, rule_id)
+            .await?;
+        if !write
+            .delete_focus_rule(user_id, rule_id)
+            .await
+            .map_err(storage_error)?
+        {
+            return Err(ApplicationError::NotFound(rule_id.to_string()));
+        }
+        write.commit(&self.events).await
+    }
+
+    /// Bring the focus blocks of the week starting at `week_start` in line
+    /// with the user's rules
+    ///
+    /// `free_busy` covers the rest of the week and leaves the blocks out.
+    /// Tentative blocks ahead that now overlap something are deleted; then
+    /// each rule gets the blocks it is short of, one a day where possible.
+    pub(crate) async fn schedule_focus(
+        &self,
+        user_id: UserId,
+        mut free_busy: FreeBusy,
+        week_start: DateTime<Utc>,
+        default_hours: Option<WorkingHours>,
+    ) -> Result<FocusSchedule, ApplicationError> {
+        let now = self.clock.now();
+        let user = self
+            .calendar
+            .get_user_by_id(user_id)
+            .await
+            .map_err(storage_error)?
+            .ok_or_else(|| ApplicationError::NotFound(user_id.to_string()))?;
+        let tz = free_busy.timezone().tz();
+
+        let mut write = self.begin_write().await?;
+        write.lock_calendar(user_id).await.map_err(storage_error)?;
+        let rules = write
+            .list_focus_rules(user_id)
+            .await
+            .map_err(storage_error)?;
+        let blocks = write
+            .list_focus_blocks(user_id, week_start, free_busy.window_end())
+            .await
+            .map_err(storage_error)?;
+
+        let mut schedule = FocusSchedule::default();
+        // Blocks each rule keeps this week, and the local days they are on
+        let mut kept: HashMap<Uuid, (usize, HashSet<NaiveDate>)> = HashMap::new();
+        for block in blocks {
+            if block.status == EventStatus::Tentative
+                && block.start > now
+                && !free_busy.is_free(block.start, block.end)
+            {
+                delete_block(&mut write, user_id, block.event_id).await?;
+                schedule.moved += 1;
+                continue;
+            }
+            // A cancelled block was turned down; it counts without being busy
+            if block.status != EventStatus::Cancelled {
+                free_busy.block(block.start, block.end);
+            }
+            let (count, days) = kept.entry(block.rule_id).or_default();
+            *count += 1;
+            days.insert(block.start.with_timezone(&tz).date_naive());
+        }
+
+        let timezone = free_busy.timezone().clone();
+        for rule in rules
+            .into_iter()
+            .map(|rule| FocusRuleView::new(rule, &[], &timezone))
+        {
+            let (count, mut days) = kept.remove(&rule.id).unwrap_or_default();
+            let missing = (rule.blocks_per_week as usize).saturating_sub(count);
+            if missing == 0 {
+                continue;
+            }
+            let placed = place_blocks(
+                &mut free_busy,
+                now,
+                missing,
+                rule.duration,
+                rule.working_hours.as_ref().or(default_hours.as_ref()),
+                &mut days,
+            );
+            schedule.missing += missing - placed.len();
+            for slot in placed {
+                let event = insert_tentative_block(
+                    &mut write,
+                    &user,
+                    rule.title.clone(),
+                    slot,
+                    free_busy.timezone(),
+                )
+                .await?;
+                write
+                    .link_focus_block(rule.id, event.id)
+                    .await
+                    .map_err(storage_error)?;
+                schedule.placed += 1;
+            }
+        }
+
+        write.commit(&self.events).await?;
+        Ok(schedule)
+    }
+
+    /// Delete the tentative blocks of a focus rule that haven't started
+    async fn clear_focus_blocks(
+        &self,
+        write: &mut CalendarWrite<'_>,
+        user_id: UserId,
+        rule_id: Uuid,
+    ) -> Result<(), ApplicationError> {
+        let now = self.clock.now();
+        // Blocks only ever go into the current week
+        let blocks = write
+            .list_focus_blocks(user_id, now, now + Duration::weeks(2))
+            .await
+            .map_err(storage_error)?;
+        for block in blocks {
+            if block.rule_id == rule_id
+                && block.status == EventStatus::Tentative
+                && block.start > now
+            {
+                delete_block(write, user_id, block.event_id).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Let the user named `username` create, edit and delete events on
+    /// `owner`'s calendar
+    pub async fn grant_delegate(
+        &self,
+        owner: UserId,
+        username: &str,
+    ) -> Result<DelegateView, ApplicationError> {
+        let username = username.trim().trim_start_matches('@');
+        let delegate = self
+            .calendar
+            .get_user_by_username(username)
+            .await
+            .map_err(storage_error)?
+            .ok_or_else(|| ApplicationError::NotFound(format!("@{username}")))?;
+        let delegate_id = delegate.id;
+        if delegate_id == owner {
+            return Err(ApplicationError::BadRequest(
+                "You already manage your own calendar".to_string(),
+            ));
+        }
+
+        let existing = self.list_delegates(owner).await?;
+        if let Some(view) = existing.iter().find(|view| view.user_id == delegate_id) {
+            return Ok(view.clone());
+        }
+        if existing.len() >= MAX_DELEGATES_PER_USER {
+            return Err(ApplicationError::BadRequest(format!(
+                "You can keep at most {MAX_DELEGATES_PER_USER} delegates"
+            )));
+        }
+
+        self.calendar
+            .grant_delegate(owner, delegate_id)
+            .await
+            .map_err(storage_error)?;
+        self.list_delegates(owner)
+            .await?
+            .into_iter()
+            .find(|view| view.user_id == delegate_id)
+            .ok_or_else(|| ApplicationError::Internal("Delegate grant vanished".to_string()))
+    }
+
+    /// Take `delegate`'s access to `owner`'s calendar away; the changes they
+    /// made stay, attributed to them
+    pub async fn revoke_delegate(
+        &self,
+        owner: UserId,
+        delegate: UserId,
+    ) -> Result<(), ApplicationError> {
+        if self
+            .calendar
+            .revoke_delegate(owner, delegate)
+            .await
+            .map_err(storage_error)?
+        {
+            Ok(())
+        } else {
+            Err(ApplicationError::NotFound(delegate.to_string()))
+        }
+    }
+
+    /// Users `owner` lets manage their calendar, oldest grant first
+    pub async fn list_delegates(
+        &self,
+        owner: UserId,
+    ) -> Result<Vec<DelegateView>, ApplicationError> {
+        Ok(self
+            .calendar
+            .list_delegates(owner)
+            .await
+            .map_err(storage_error)?
+            .into_iter()
+            .map(DelegateView::from)
+            .collect())
+    }
+
+    /// Owners whose calendars `delegate` may manage, oldest grant first
+    pub async fn list_delegated_calendars(
+        &self,
+        delegate: UserId,
+    ) -> Result<Vec<DelegateView>, ApplicationError> {
+        Ok(self
+            .calendar
+            .list_delegating_owners(delegate)
+            .await
+            .map_err(storage_error)?
+            .into_iter()
+            .map(DelegateView::from)
+            .collect())
+    }
+
+    /// Who changed an event on `user_id`'s calendar and how, oldest first.
+    ///
+    /// Readable by the owner and their delegates; the history outlives the
+    /// event, so a deleted event's is still there.
+    pub async fn event_revisions(
+        &self,
+        actor: UserId,
+        user_id: UserId,
+        event_id: Uuid,
+    ) -> Result<Vec<EventRevisionView>, ApplicationError> {
+        if actor != user_id
+            && !self
+                .calendar
+                .is_delegate(user_id, actor)
+                .await
+                .map_err(storage_error)?
+        {
+            return Err(ApplicationError::NotFound(format!("calendar {user_id}")));
+        }
+        let revisions = self
+            .calendar
+            .list_event_revisions(user_id, event_id)
+            .await
+            .map_err(storage_error)?;
+        // Events from before revisions were recorded have none yet
+        if revisions.is_empty()
+            && self
+                .calendar
+                .get_event_by_id(user_id, event_id)
+                .await
+                .map_err(storage_error)?
+                .is_none()
+        {
+            return Err(ApplicationError::NotFound(event_id.to_string()));
+        }
+        Ok(revisions.into_iter().map(EventRevisionView::from).collect())
+    }
+
+    /// Add a bookable resource; names are unique ignoring case
+    pub async fn create_resource(&self, name: &str) -> Result<ResourceView, ApplicationError> {
+        let name = normalize_resource_name(name)?;
+        self.calendar
+            .create_resource(&name)
+            .await
+            .map_err(storage_error)?
+            .map(ResourceView::from)
+            .ok_or_else(|| ApplicationError::Conflict(format!("resource {name:?} already exists")))
+    }
+
+    /// Delete the resource; events that booked it stay, without it
+    pub async fn delete_resource(&self, resource_id: Uuid) -> Result<(), ApplicationError> {
+        if !self
+            .calendar
+            .delete_resource(resource_id)
+            .await
+            .map_err(storage_error)?
+        {
+            return Err(ApplicationError::NotFound(format!(
+                "resource {resource_id}"
+            )));
+        }
+        Ok(())
+    }
+
+    /// Book `resource_id` for the time of an event on `user_id`'s calendar,
+    /// on behalf of `actor`, the owner or one of their delegates. An event
+    /// holds one resource at a time; booking another lets the first go.
+    ///
+    /// Recurring and cancelled events can't book, and a resource already
+    /// booked at any point of the event's time is a conflict.
+    pub async fn book_resource(
+        &self,
+        actor: UserId,
+        user_id: UserId,
+        event_id: Uuid,
+        resource_id: Uuid,
+    ) -> Result<ResourceBookingView, ApplicationError> {
+        if self
+            .calendar
+            .get_resource(resource_id)
+            .await
+            .map_err(storage_error)?
+            .is_none()
+        {
+            return Err(ApplicationError::NotFound(format!(
+                "resource {resource_id}"
+            )));
+        }
+
+        let mut write = self.begin_write_as(user_id, actor).await?;
+        let event = write
+            .get_event_by_id(user_id, event_id)
+            .await
+            .map_err(storage_error)?
+            .ok_or_else(|| ApplicationError::NotFound(event_id.to_string()))?;
+        if event.rrule.is_some() {
+            return Err(ApplicationError::BadRequest(
+                "Recurring events can't book a resource".to_string(),
+            ));
+        }
+        if event.status == EventStatus::Cancelled {
+            return Err(ApplicationError::BadRequest(
+                "Cancelled events can't book a resource".to_string(),
+            ));
+        }
+        if !write
+            .book_resource(event_id, resource_id)
+            .await
+            .map_err(storage_error)?
+        {
+            return Err(ApplicationError::Conflict(
+                "Resource is already booked at that time".to_string(),
+            ));
+        }
+        write.commit(&self.events).await?;
+
+        self.calendar
+            .get_resource_booking(event_id)
+            .await
+            .map_err(storage_error)?
+            .map(ResourceBookingView::from)
+            .ok_or_else(|| ApplicationError::NotFound(event_id.to_string()))
+    }
+
+    /// Let go of the resource the event holds
+    pub async fn release_resource(
+        &self,
+        actor: UserId,
+        user_id: UserId,
+        event_id: Uuid,
+    ) -> Result<(), ApplicationError> {
+        let mut write = self.begin_write_as(user_id, actor).await?;
+        if write
+            .get_event_by_id(user_id, event_id)
+            .await
+            .map_err(storage_error)?
+            .is_none()
+            || !write
+                .release_resource(event_id)
+                .await
+                .map_err(storage_error)?
+        {
+            return Err(ApplicationError::NotFound(format!(
+                "resource booking of {event_id}"
+            )));
+        }
+        write.commit(&self.events).await
+    }
+
+    /// Put a visitor's booking on the owner's calendar as a tentative event
+    /// with the visitor as guest, and tell the owner.
+    ///
+    /// The caller checks `command.start` is one of the slots the page
+    /// offered; this only guards against two visitors taking the same time.
+    pub async fn book_slot(&self, command: BookSlotCommand) -> Result<EventView, ApplicationError> {
+        let email = command.email.trim().to_lowercase();
+        validate_email_address(&email).map_err(ApplicationError::BadRequest)?;
+        let name = command.name.trim().to_string();
+        if name.is_empty() {
+            return Err(ApplicationError::BadRequest(
+                "Name cannot be empty".to_string(),
+            ));
+        }
+        validate_length("Name", &name, MAX_SIGNUP_NAME_LENGTH)
+            .map_err(ApplicationError::BadRequest)?;
+        validate_no_control_chars("Name", &name).map_err(ApplicationError::BadRequest)?;
+
+        let mut write = self.begin_write().await?;
+        let link: BookingLinkView = write
+            .get_booking_link_by_slug(&command.slug)
+            .await
+            .map_err(storage_error)?
+            .ok_or_else(|| ApplicationError::NotFound(command.slug.clone()))?
+            .into();
+        let owner = link.owner;
+        let owner_user = self
+            .calendar
+            .get_user_by_id(owner)
+            .await
+            .map_err(storage_error)?
+            .ok_or_else(|| ApplicationError::NotFound(owner.to_string()))?;
+        let timezone = owner_user.timezone;
+
+        let end = command.start + link.duration;
+        write.lock_calendar(owner).await.map_err(storage_error)?;
+        if write
+            .has_overlapping_event(owner, command.start, end)
+            .await
+            .map_err(storage_error)?
+        {
+            return Err(ApplicationError::Conflict(
+                "That time was just taken. Please pick another slot.".to_string(),
+            ));
+        }
+
+        let sync_version = write.sync_version(owner).await?;
+        let (event, _) = insert_with_attendees(
+            &mut write,
+            StoredEventWrite {
+                user_id: owner,
+                uid: Uuid::new_v4().to_string(),
+                summary: format!("{}: {name}", link.title),
+                description: Some(format!("Booked by {name} <{email}>")),
+                location: None,
+                timing: EventTiming::Timed {
+                    start: command.start,
+                    end,
+                    timezone: timezone.clone(),
+                },
+                status: EventStatus::Tentative,
+                rrule: None,
+                version: 1,
+                sequence: 0,
+                sync_version,
+                etag: "pending".to_string(),
+                visibility: owner_user.default_event_visibility,
+                reminder_note: None,
+            },
+            &[AttendeeWrite {
+                email,
+                user_id: None,
+                role: AttendeeRole::Attendee,
+                status: ParticipationStatus::Accepted,
+            }],
+        )
+        .await?;
+
+        write.emit(DomainEvent::EventCreated {
+            calendar_owner: owner,
+            event_id: event.id,
+        });
+        write.emit(DomainEvent::SlotBooked {
+            owner,
+            event_id: event.id,
+            link_title: link.title,
+            booker: name,
+            start: command.start,
+            timezone,
+        });
+        write.commit(&self.events).await?;
+        EventView::try_from(event)
+    }
+
+    /// Record a signup from a public page and queue the confirmation email.
+    ///
+    /// The link itself is minted when the email is sent, see
+    /// [`crate::CalendarService::issue_signup_confirmation`].
+    ///
+    /// People already on the guest list get no new link, but the outcome is
+    /// the same either way so the page does not reveal who is attending.
+    pub async fn request_public_signup(
+        &self,
+        command: PublicSignupCommand,
+    ) -> Result<(), ApplicationError> {
+        let email = command.email.trim().to_lowercase();
+        validate_email_address(&email).map_err(ApplicationError::BadRequest)?;
+        let display_name = command
+            .display_name
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty());
+        if let Some(name) = &display_name {
+            validate_length("Name", name, MAX_SIGNUP_NAME_LENGTH)
+                .map_err(ApplicationError::BadRequest)?;
+            validate_no_control_chars("Name", name).map_err(ApplicationError::BadRequest)?;
+        }
+
+        let mut write = self.begin_write().await?;
+        let event = write
+            .get_event_by_public_slug(&command.slug)
+            .await
+            .map_err(storage_error)?
+            .ok_or_else(|| ApplicationError::NotFound(command.slug.clone()))?;
+        if event.status == EventStatus::Cancelled {
+            return Err(ApplicationError::BadRequest(
+                "This event has been cancelled".to_string(),
+            ));
+        }
+        if event.visibility != EventVisibility::Public {
+            return Err(ApplicationError::BadRequest(
+                "This event is not open for signups".to_string(),
+            ));
+        }
+
+        let already_listed = write
+            .list_attendees(event.id)
+            .await
+            .map_err(storage_error)?
+            .iter()
+            .any(|attendee| attendee.email.eq_ignore_ascii_case(&email));
+        if already_listed {
+            return Ok(());
+        }
+        if write
+            .is_email_suppressed(&email)
+            .await
+            .map_err(storage_error)?
+        {
+            return Err(ApplicationError::BadRequest(
+                "We no longer send email to this address, so we can't send a confirmation \
+                 link. Ask the organizer to invite you directly."
+                    .to_string(),
+            ));
+        }
+
+        write
+            .upsert_public_signup(PublicSignupWrite {
+                event_id: event.id,
+                email: email.clone(),
+                display_name,
+                expires_at: self.clock.now() + Duration::hours(SIGNUP_CONFIRMATION_TTL_HOURS),
+            })
+            .await
+            .map_err(storage_error)?;
+
+        write.emit(DomainEvent::PublicSignupRequested {
+            event_id: event.id,
+            event_summary: event.summary,
+            email,
+        });
+        write.commit(&self.events).await
+    }
+
+    /// Turn a confirmed public signup into an accepted attendee.
+    pub async fn confirm_public_signup(&self, token: &str) -> Result<EventView, ApplicationError> {
+        let mut write = self.begin_write().await?;
+        let signup = write
+            .confirm_public_signup(&hash_signup_token(token))
+            .await
+            .map_err(storage_error)?
+            .ok_or_else(|| {
+                ApplicationError::NotFound("Confirmation link is invalid or expired".to_string())
+            })?;
+        let current = write
+            .get_event_by_id_any(signup.event_id)
+            .await
+            .map_err(storage_error)?
+            .ok_or_else(|| ApplicationError::NotFound(signup.event_id.to_string()))?;
+
+        let attendees = [AttendeeWrite {
+            email: signup.email.clone(),
+            user_id: None,
+            role: AttendeeRole::Attendee,
+            status: ParticipationStatus::Accepted,
+        }];
+        write
+            .upsert_attendees(current.id, &attendees)
+            .await
+            .map_err(storage_error)?;
+
+        let organizer_user_id = current.user_id;
+        let version = current.version + 1;
+        let sync_version = write.sync_version(organizer_user_id).await?;
+        let final_attendees = write
+            .list_attendees(current.id)
+            .await
+            .map_err(storage_error)?;
+        let etag = etag_for_event(&current, &final_attendees)?;
+        let event = write
+            .set_event_sync_etag(current.id, organizer_user_id, version, sync_version, etag)
+            .await
+            .map_err(storage_error)?;
+
+        write.emit(DomainEvent::RsvpRecorded {
+            calendar_owner: organizer_user_id,
+            event_id: event.id,
+            event_summary: event.summary.clone(),
+            attendee_name: signup.display_name.unwrap_or(signup.email),
+            status: ParticipationStatus::Accepted,
+        });
+        write.commit(&self.events).await?;
+        EventView::try_from(event)
+    }
+}
+
+pub(crate) fn hash_signup_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
+
+#[derive(Debug, Clone)]
+pub struct CreateEventCommand {
+    pub user_id: UserId,
+    pub username: Option<String>,
+    pub uid: String,
+    pub summary: String,
+    pub description: Option<String>,
+    pub location: Option<String>,
+    pub timing: EventTiming,
+    pub status: EventStatus,
+    pub rrule: Option<String>,
+    /// The owner's default when `None`
+    pub visibility: Option<EventVisibility>,
+    /// Appended to the event's reminders
+    pub reminder_note: Option<String>,
+    /// Create even when a live event with the same summary starts within
+    /// [`NEAR_DUPLICATE_WINDOW_SECS`] (or on the same day, for all-day
+    /// events); otherwise that is a conflict
+    pub allow_duplicate: bool,
+}
+
+/// Which part of a recurring series an update applies to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EditScope {
+    /// The whole event, every occurrence of a series included
+    #[default]
+    Series,
+    /// Only the occurrence starting at this time, detached from its series
+    Occurrence(DateTime<Utc>),
+    /// The occurrence starting at this time and all later ones, split off
+    /// into a new series
+    Following(DateTime<Utc>),
+}
+
+impl EditScope {
+    /// Where the series is cut, unless the whole of it is edited
+    #[must_use]
+    pub const fn cut(self) -> Option<DateTime<Utc>> {
+        match self {
+            Self::Series => None,
+            Self::Occurrence(at) | Self::Following(at) => Some(at),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct UpdateEventCommand {
+    pub user_id: UserId,
+    pub event_id: Uuid,
+    pub summary: Option<String>,
+    pub description: Option<Option<String>>,
+    pub location: Option<Option<String>>,
+    pub timing: Option<EventTiming>,
+    pub status: Option<EventStatus>,
+    pub rrule: Option<Option<String>>,
+    pub visibility: Option<EventVisibility>,
+    pub reminder_note: Option<Option<String>>,
+    /// Ignored for events that don't recur
+    pub scope: EditScope,
+}
+
+#[derive(Debug, Clone)]
+pub struct DuplicateEventCommand {
+    pub user_id: UserId,
+    pub event_id: Uuid,
+    /// Days to move the copy by, e.g. 7 for the same time next week
+    pub offset_days: i64,
+    /// Invite the original guests to the copy
+    pub include_attendees: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct PutEventCommand {
+    pub user_id: UserId,
+    pub uid: String,
+    pub summary: String,
+    pub description: Option<String>,
+    pub location: Option<String>,
+    pub timing: EventTiming,
+    pub status: EventStatus,
+    pub rrule: Option<String>,
+    /// `None` keeps what a stored event has, or gives a new one the owner's
+    /// default
+    pub visibility: Option<EventVisibility>,
+    pub expected_etag: Option<String>,
+    /// `SEQUENCE` the client sent, if any
+    pub sequence: Option<i32>,
+    pub attendees: Vec<AttendeeCommand>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PutEventResult {
+    pub etag: String,
+    pub created: bool,
+    /// Set when nothing was written because the client's copy is out of
+    /// date; `etag` is then the stored event's
+    pub conflict: Option<SequenceConflict>,
+}
+
+/// A PUT turned away because its `SEQUENCE` is behind the stored event's
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SequenceConflict {
+    pub uid: String,
+    pub client_sequence: i32,
+    pub server_sequence: i32,
+}
+
+#[derive(Debug, Clone)]
+pub struct AttendeeCommand {
+    pub email: String,
+    pub user_id: Option<UserId>,
+    pub role: AttendeeRole,
+    pub status: ParticipationStatus,
+}
+
+#[derive(Debug, Clone)]
+pub struct InviteAttendeeCommand {
+    pub organizer_user_id: UserId,
+    pub event_id: Uuid,
+    pub email: String,
+    pub attendee_user_id: Option<UserId>,
+    pub role: AttendeeRole,
+}
+
+#[derive(Debug, Clone)]
+pub struct InviteeCommand {
+    pub email: String,
+    pub attendee_user_id: Option<UserId>,
+    pub role: AttendeeRole,
+}
+
+#[derive(Debug, Clone)]
+pub struct InviteAttendeesCommand {
+    pub organizer_user_id: UserId,
+    pub event_id: Uuid,
+    pub invitees: Vec<InviteeCommand>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InviteAttendeesResult {
+    /// Emails added to the guest list by this request.
+    pub invited: Vec<String>,
+    /// Emails that were already on the guest list and left unchanged.
+    pub already_invited: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct RemoveAttendeeCommand {
+    pub organizer_user_id: UserId,
+    pub event_id: Uuid,
+    pub email: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct ResendInviteCommand {
+    pub organizer_user_id: UserId,
+    pub event_id: Uuid,
+    pub email: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct PublicSignupCommand {
+    pub slug: String,
+    pub email: String,
+    pub display_name: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ConfirmRsvpCommand {
+    pub event_id: Uuid,
+    pub attendee_user_id: UserId,
+    pub status: ParticipationStatus,
+    pub attendee_name: String,
+}
+
+/// Length limits on event free text, in bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EventTextLimits {
+    pub summary: usize,
+    pub description: usize,
+    pub location: usize,
+}
+
+impl Default for EventTextLimits {
+    fn default() -> Self {
+        Self {
+            summary: MAX_SUMMARY_LENGTH,
+            description: MAX_DESCRIPTION_LENGTH,
+            location: MAX_LOCATION_LENGTH,
+        }
+    }
+}
+
+/// What a write does with text over its limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextOverflow {
+    /// Typed by someone who can shorten it.
+    Reject,
+    /// Imported from another calendar, where failing would drop the event.
+    Truncate,
+}
+
+impl EventTextLimits {
+    /// Clean the free-text fields of an event write in place.
+    ///
+    /// Control characters are stripped, newlines normalized to `\n` (and
+    /// folded into spaces outside the description), then the limits applied.
+    /// `None` means the field is absent or unchanged.
+    pub fn sanitize(
+        &self,
+        summary: Option<&mut String>,
+        description: Option<&mut String>,
+        location: Option<&mut String>,
+        overflow: TextOverflow,
+    ) -> Result<(), ApplicationError> {
+        if let Some(summary) = summary {
+            *summary = sanitize_single_line_text(summary);
+            fit_length("Summary", summary, self.summary, overflow)?;
+        }
+        if let Some(description) = description {
+            *description = sanitize_multiline_text(description);
+            fit_length("Description", description, self.description, overflow)?;
+        }
+        if let Some(location) = location {
+            *location = sanitize_single_line_text(location);
+            fit_length("Location", location, self.location, overflow)?;
+        }
+        Ok(())
+    }
+}
+
+/// The organizer's event and the Telegram invitee who hasn't replied yet
+async fn pending_invitee(
+    write: &mut CalendarWrite<'_>,
+    command: &ResendInviteCommand,
+) -> Result<(Uuid, UserId), ApplicationError> {
+    let current = write
+        .get_event_by_id(command.organizer_user_id, command.event_id)
+        .await
+        .map_err(storage_error)?
+        .ok_or_else(|| ApplicationError::NotFound(command.event_id.to_string()))?;
+    let attendee = write
+        .list_attendees(current.id)
+        .await
+        .map_err(storage_error)?
+        .into_iter()
+        .find(|attendee| attendee.email == command.email)
+        .ok_or_else(|| ApplicationError::NotFound(command.email.clone()))?;
+
+    if attendee.status != ParticipationStatus::NeedsAction {
+        return Err(ApplicationError::BadRequest(format!(
+            "{} already replied to this invite",
+            command.email
+        )));
+    }
+    let attendee_user_id = attendee.user_id.map(UserId::new).ok_or_else(|| {
+        ApplicationError::BadRequest("Invites can only be re-sent to Telegram users".to_string())
+    })?;
+
+    Ok((current.id, attendee_user_id))
+}
+
+fn fit_length(
+    field_name: &str,
+    value: &mut String,
+    max_len: usize,
+    overflow: TextOverflow,
+) -> Result<(), ApplicationError> {
+    match overflow {
+        TextOverflow::Reject => {
+            validate_length(field_name, value, max_len).map_err(ApplicationError::BadRequest)
+        }
+        TextOverflow::Truncate => {
+            truncate_to_length(value, max_len);
+            Ok(())
+        }
+    }
+}
+
+/// Validate the identifier and recurrence fields of an event write.
+///
+/// Free text is cleaned by [`EventTextLimits::sanitize`] instead. `None`
+/// means the field is absent (or unchanged, for partial updates).
+pub fn validate_event_fields(
+    uid: Option<&str>,
+    rrule: Option<&str>,
+) -> Result<(), ApplicationError> {
+    if let Some(uid) = uid {
+        validate_length("UID", uid, MAX_UID_LENGTH).map_err(ApplicationError::BadRequest)?;
+        validate_no_control_chars("UID", uid).map_err(ApplicationError::BadRequest)?;
+    }
+
+    if let Some(rrule) = rrule {
+        validate_length("RRule", rrule, MAX_RRULE_LENGTH).map_err(ApplicationError::BadRequest)?;
+        validate_no_control_chars("RRule", rrule).map_err(ApplicationError::BadRequest)?;
+        validate_rrule(rrule)?;
+    }
+
+    Ok(())
+}
+
+/// Trim a reminder note; a blank one is no note
+fn clean_reminder_note(note: Option<String>) -> Result<Option<String>, ApplicationError> {
+    let Some(note) = note
+        .map(|note| note.trim().to_string())
+        .filter(|note| !note.is_empty())
+    else {
+        return Ok(None);
+    };
+    validate_length("Reminder note", &note, MAX_REMINDER_NOTE_LENGTH)
+        .map_err(ApplicationError::BadRequest)?;
+    validate_no_control_chars("Reminder note", &note).map_err(ApplicationError::BadRequest)?;
+    Ok(Some(note))
+}
+
+fn etag_for_event(event: &Event, attendees: &[EventAttendee]) -> Result<String, ApplicationError> {
+    Ok(etag_for_parts(
+        &event.uid,
+        &event.summary,
+        event.description.clone(),
+        event.location.clone(),
+        timing_from_event(event)?,
+        event.status,
+        event.rrule.clone(),
+        event.visibility,
+        event.reminder_note.clone(),
+        attendee_fingerprints(attendees),
+    ))
+}
+
+/// Insert `event` with `attendees`, stamping the etag once both are stored
+/// Put a tentative block on the user's calendar, e.g. for a task or a focus
+/// rule
+async fn insert_tentative_block(
+    write: &mut CalendarWrite<'_>,
+    user: &User,
+    summary: String,
+    (start, end): (DateTime<Utc>, DateTime<Utc>),
+    timezone: &Timezone,
+) -> Result<Event, ApplicationError> {
+    let sync_version = write.sync_version(user.id).await?;
+    let (event, _) = insert_with_attendees(
+        write,
+        StoredEventWrite {
+            user_id: user.id,
+            uid: Uuid::new_v4().to_string(),
+            summary,
+            description: None,
+            location: None,
+            timing: EventTiming::Timed {
+                start,
+                end,
+                timezone: timezone.clone(),
+            },
+            status: EventStatus::Tentative,
+            rrule: None,
+            version: 1,
+            sequence: 0,
+            sync_version,
+            etag: "pending".to_string(),
+            visibility: user.default_event_visibility,
+            reminder_note: None,
+        },
+        &[],
+    )
+    .await?;
+    write.emit(DomainEvent::EventCreated {
+        calendar_owner: user.id,
+        event_id: event.id,
+    });
+    Ok(event)
+}
+
+/// Delete a block a plan or focus rule put on the calendar
+async fn delete_block(
+    write: &mut CalendarWrite<'_>,
+    user_id: UserId,
+    event_id: Uuid,
+) -> Result<(), ApplicationError> {
+    if let Some(deleted) = write
+        .delete_event_by_id(user_id, event_id)
+        .await
+        .map_err(storage_error)?
+    {
+        write.emit(DomainEvent::EventDeleted {
+            calendar_owner: user_id,
+            event_id: deleted.id,
+            uid: deleted.uid,
+        });
+    }
+    Ok(())
+}
+
+fn focus_fields<'a>(
+    command: &FocusRuleCommand,
+    title: &'a str,
+    working_hours: Option<&'a str>,
+) -> FocusRuleFields<'a> {
+    FocusRuleFields {
+        title,
+        blocks_per_week: command.blocks_per_week as i32,
+        duration_minutes: command.duration.num_minutes() as i32,
+        working_hours,
+    }
+}
+
+async fn insert_with_attendees(
+    write: &mut CalendarWrite<'_>,
+    event: StoredEventWrite,
+    attendees: &[AttendeeWrite],
+) -> Result<(Event, Vec<EventAttendee>), ApplicationError> {
+    let event = write.insert_event(event).await.map_err(storage_error)?;
+    write
+        .upsert_attendees(event.id, attendees)
+        .await
+        .map_err(storage_error)?;
+    let attendees = write
+        .list_attendees(event.id)
+        .await
+        .map_err(storage_error)?;
+    let etag = etag_for_event(&event, &attendees)?;
+    let event = write
+        .set_event_sync_etag(
+            event.id,
+            event.user_id,
+            event.version,
+            event.sync_version,
+            etag,
+        )
+        .await
+        .map_err(storage_error)?;
+    Ok((event, attendees))
+}
+
+#[allow(clippy::too_many_arguments)]
+fn etag_for_parts(
+    uid: &str,
+    summary: &str,
+    description: Option<String>,
+    location: Option<String>,
+    timing: EventTiming,
+    status: EventStatus,
+    rrule: Option<String>,
+    visibility: EventVisibility,
+    reminder_note: Option<String>,
+    attendees: Vec<AttendeeFingerprint>,
+) -> String {
+    compute_event_etag(&EventEtagInput {
+        uid: uid.to_string(),
+        summary: summary.to_string(),
+        description,
+        location,
+        timing,
+        status,
+        rrule,
+        visibility,
+        reminder_note,
+        attendees,
+    })
+}
+
+/// Guests to tell when `event` has just been cancelled; the organizer and
+/// guests who already declined are left alone.
+fn cancellation_notices(event: &Event, attendees: &[EventAttendee]) -> Vec<DomainEvent> {
+    attendees
+        .iter()
+        .filter(|attendee| {
+            attendee.role != AttendeeRole::Organizer
+                && attendee.status != ParticipationStatus::Declined
+        })
+        .map(|attendee| DomainEvent::EventCancelled {
+            event_id: event.id,
+            event_summary: event.summary.clone(),
+            email: attendee.email.clone(),
+            attendee_user_id: attendee.user_id.map(UserId::new),
+        })
+        .collect()
+}
+
+/// Guests to tell that `event` moved or changed place: Telegram users who
+/// accepted or might come, and every external guest who hasn't declined,
+/// since their calendar holds the event either way.
+fn reschedule_notices(
+    event: &Event,
+    attendees: &[EventAttendee],
+    previous_timing: &EventTiming,
+    previous_location: Option<&String>,
+    changed_at: DateTime<Utc>,
+) -> Vec<DomainEvent> {
+    attendees
+        .iter()
+        .filter(|attendee| {
+            attendee.role != AttendeeRole::Organizer
+                && match attendee.user_id {
+                    Some(_) => matches!(
+                        attendee.status,
+                        ParticipationStatus::Accepted | ParticipationStatus::Tentative
+                    ),
+                    None => attendee.status != ParticipationStatus::Declined,
+                }
+        })
+        .map(|attendee| DomainEvent::EventRescheduled {
+            event_id: event.id,
+            event_summary: event.summary.clone(),
+            email: attendee.email.clone(),
+            attendee_user_id: attendee.user_id.map(UserId::new),
+            previous_timing: previous_timing.clone(),
+            previous_location: previous_location.cloned(),
+            changed_at,
+        })
+        .collect()
+}
+
+/// Summary of the events a timezone fix finds, refusing more than one fix
+/// may move
+fn timezone_fix_summary(events: &[Event]) -> Result<TimezoneFixSummary, ApplicationError> {
+    if events.len() > MAX_TIMEZONE_FIX_EVENTS {
+        return Err(ApplicationError::BadRequest(format!(
+            "More than {MAX_TIMEZONE_FIX_EVENTS} events would move; fix a shorter range at a time"
+        )));
+    }
+    let starts: Vec<DateTime<Utc>> = events.iter().filter_map(|event| event.start).collect();
+    Ok(TimezoneFixSummary::of(&starts))
+}
+
+fn attendee_fingerprints(attendees: &[EventAttendee]) -> Vec<AttendeeFingerprint> {
+    attendees
+        .iter()
+        .map(|attendee| AttendeeFingerprint {
+            email: attendee.email.clone(),
+            user_id: attendee.user_id,
+            role: attendee.role,
+            status: attendee.status,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reminder_notes_are_trimmed_and_bounded() {
+        assert_eq!(
+            clean_reminder_note(Some("  Bring laptop! ".to_string())).unwrap(),
+            Some("Bring laptop!".to_string())
+        );
+        assert_eq!(clean_reminder_note(Some("  ".to_string())).unwrap(), None);
+        assert!(clean_reminder_note(Some("a".repeat(MAX_REMINDER_NOTE_LENGTH + 1))).is_err());
+        assert!(clean_reminder_note(Some("two\nlines".to_string())).is_err());
+    }
+
+    #[test]
+    fn validate_event_fields_accepts_absent_fields() {
+        assert!(validate_event_fields(None, None).is_ok());
+    }
+
+    #[test]
+    fn validate_event_fields_rejects_control_chars_in_uid() {
+        let err = validate_event_fields(Some("uid\u{0}"), None)
+            .expect_err("uid with NUL must be rejected");
+        assert!(matches!(err, ApplicationError::BadRequest(_)));
+    }
+
+    #[test]
+    fn validate_event_fields_rejects_invalid_rrule() {
+        let err = validate_event_fields(None, Some("INVALID=TRUE"))
+            .expect_err("invalid rrule must be rejected");
+        assert!(matches!(err, ApplicationError::BadRequest(_)));
+    }
+
+    #[test]
+    fn validate_event_fields_rejects_oversized_uid() {
+        let uid = "u".repeat(MAX_UID_LENGTH + 1);
+        assert!(validate_event_fields(Some(&uid), None).is_err());
+    }
+
+    #[test]
+    fn sanitize_strips_control_chars_and_keeps_description_lines() {
+        let mut summary = "Bad\u{0}\r\nname".to_string();
+        let mut description = "line one\r\nline two\u{7}".to_string();
+        EventTextLimits::default()
+            .sanitize(
+                Some(&mut summary),
+                Some(&mut description),
+                None,
+                TextOverflow::Reject,
+            )
+            .unwrap();
+        assert_eq!(summary, "Bad name");
+        assert_eq!(description, "line one\nline two");
+    }
+
+    #[test]
+    fn sanitize_rejects_or_truncates_over_the_limit() {
+        let limits = EventTextLimits {
+            description: 8,
+            ..EventTextLimits::default()
+        };
+
+        let mut description = "0123456789".to_string();
+        let err = limits
+            .sanitize(None, Some(&mut description), None, TextOverflow::Reject)
+            .expect_err("over-long description must be rejected");
+        assert!(matches!(err, ApplicationError::BadRequest(_)));
+
+        limits
+            .sanitize(None, Some(&mut description), None, TextOverflow::Truncate)
+            .unwrap();
+        assert_eq!(description, "01234567");
+    }
+}
@@ -0,0 +1,319 @@
+//! Runtime feature flags for gradual rollouts.
+//!
+//! A flag is on for a user when an override says so, or when it is enabled
+//! and the user's bucket falls under its rollout percentage. Unknown flags are
+//! off. Evaluations are cached per flag for a short time; writes through this
+//! service drop the cached entry right away.
+//!
+//! [`READ_ONLY_FLAG`] is the one global switch: once enabled it applies to
+//! everyone, whatever its rollout and overrides say.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use moka::future::Cache;
+use sha2::{Digest, Sha256};
+use televent_storage::flags::{FeatureFlagRecord, FeatureFlagRepository, FeatureFlagWrite};
+
+use crate::{ApplicationError, UserId, storage_error};
+
+/// Inviting guests by email address instead of Telegram account
+pub const EXTERNAL_INVITES_FLAG: &str = "external_invites";
+
+/// Maintenance mode: reads keep working, writes are refused
+pub const READ_ONLY_FLAG: &str = "read_only";
+
+/// How long other processes may keep serving a flag after it changed
+const FLAG_CACHE_TTL: Duration = Duration::from_secs(30);
+const MAX_FLAG_NAME_LENGTH: usize = 64;
+const MAX_FLAG_DESCRIPTION_LENGTH: usize = 500;
+
+#[derive(Clone)]
+pub struct FeatureFlagService {
+    flags: FeatureFlagRepository,
+    /// Rules by flag name; `None` caches a flag that doesn't exist
+    cache: Cache<String, Option<Arc<FlagRule>>>,
+    /// Read-only regardless of [`READ_ONLY_FLAG`]
+    forced_read_only: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct FeatureFlagView {
+    pub name: String,
+    pub description: String,
+    pub enabled: bool,
+    pub rollout_percent: u8,
+    /// Per-user values that win over the rollout
+    pub overrides: Vec<(UserId, bool)>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PutFeatureFlagCommand {
+    pub name: String,
+    pub description: String,
+    pub enabled: bool,
+    pub rollout_percent: u8,
+}
+
+#[derive(Debug)]
+struct FlagRule {
+    enabled: bool,
+    rollout_percent: u8,
+    overrides: HashMap<UserId, bool>,
+}
+
+impl FeatureFlagService {
+    #[must_use]
+    pub fn new(flags: FeatureFlagRepository) -> Self {
+        Self {
+            flags,
+            cache: Cache::builder().time_to_live(FLAG_CACHE_TTL).build(),
+            forced_read_only: false,
+        }
+    }
+
+    /// Stay read-only whatever the flag says, e.g. for a whole deployment
+    /// started during a migration
+    #[must_use]
+    pub const fn with_forced_read_only(mut self, read_only: bool) -> Self {
+        self.forced_read_only = read_only;
+        self
+    }
+
+    /// Whether the flag is on for the user
+    pub async fn is_enabled(&self, name: &str, user_id: UserId) -> Result<bool, ApplicationError> {
+        let rule = self.rule(name).await?;
+
+        Ok(rule.is_some_and(|rule| rule.allows(name, user_id)))
+    }
+
+    /// Whether writes are paused for maintenance
+    pub async fn is_read_only(&self) -> Result<bool, ApplicationError> {
+        if self.forced_read_only {
+            return Ok(true);
+        }
+        let rule = self.rule(READ_ONLY_FLAG).await?;
+
+        Ok(rule.is_some_and(|rule| rule.enabled))
+    }
+
+    async fn rule(&self, name: &str) -> Result<Option<Arc<FlagRule>>, ApplicationError> {
+        self.cache
+            .try_get_with(name.to_string(), self.load_rule(name))
+            .await
+            .map_err(|err| ApplicationError::Internal(err.to_string()))
+    }
+
+    pub async fn list_flags(&self) -> Result<Vec<FeatureFlagView>, ApplicationError> {
+        let flags = self.flags.list_flags().await.map_err(storage_error)?;
+
+        let mut views = Vec::with_capacity(flags.len());
+        for flag in flags {
+            views.push(self.flag_view(flag).await?);
+        }
+        Ok(views)
+    }
+
+    /// Create a flag or replace its settings, keeping its overrides
+    pub async fn put_flag(
+        &self,
+        command: PutFeatureFlagCommand,
+    ) -> Result<FeatureFlagView, ApplicationError> {
+        validate_flag_name(&command.name)?;
+        if command.rollout_percent > 100 {
+            return Err(ApplicationError::BadRequest(
+                "Rollout percent must be between 0 and 100".to_string(),
+            ));
+        }
+        if command.description.chars().count() > MAX_FLAG_DESCRIPTION_LENGTH {
+            return Err(ApplicationError::BadRequest(format!(
+                "Description must be at most {MAX_FLAG_DESCRIPTION_LENGTH} characters"
+            )));
+        }
+
+        let flag = self
+            .flags
+            .upsert_flag(&FeatureFlagWrite {
+                name: command.name,
+                description: command.description,
+                enabled: command.enabled,
+                rollout_percent: i16::from(command.rollout_percent),
+            })
+            .await
+            .map_err(storage_error)?;
+        self.cache.invalidate(&flag.name).await;
+
+        self.flag_view(flag).await
+    }
+
+    /// Pin the flag on or off for one user, or clear the pin with `None`
+    pub async fn set_override(
+        &self,
+        name: &str,
+        user_id: UserId,
+        enabled: Option<bool>,
+    ) -> Result<(), ApplicationError> {
+        let updated = self
+            .flags
+            .set_override(name, user_id, enabled)
+            .await
+            .map_err(storage_error)?;
+        if !updated {
+            return Err(ApplicationError::NotFound(format!(
+                "Feature flag {name} or user {user_id} not found"
+            )));
+        }
+        self.cache.invalidate(name).await;
+
+        Ok(())
+    }
+
+    async fn load_rule(&self, name: &str) -> Result<Option<Arc<FlagRule>>, ApplicationError> {
+        let Some(flag) = self.flags.get_flag(name).await.map_err(storage_error)? else {
+            return Ok(None);
+        };
+        let overrides = self
+            .flags
+            .list_overrides(name)
+            .await
+            .map_err(storage_error)?
+            .into_iter()
+            .map(|record| (UserId::new(record.user_id), record.enabled))
+            .collect();
+
+        Ok(Some(Arc::new(FlagRule {
+            enabled: flag.enabled,
+            rollout_percent: rollout_percent(flag.rollout_percent),
+            overrides,
+        })))
+    }
+
+    async fn flag_view(
+        &self,
+        flag: FeatureFlagRecord,
+    ) -> Result<FeatureFlagView, ApplicationError> {
+        let overrides = self
+            .flags
+            .list_overrides(&flag.name)
+            .await
+            .map_err(storage_error)?
+            .into_iter()
+            .map(|record| (UserId::new(record.user_id), record.enabled))
+            .collect();
+
+        Ok(FeatureFlagView {
+            rollout_percent: rollout_percent(flag.rollout_percent),
+            name: flag.name,
+            description: flag.description,
+            enabled: flag.enabled,
+            overrides,
+            updated_at: flag.updated_at,
+        })
+    }
+}
+
+impl FlagRule {
+    fn allows(&self, name: &str, user_id: UserId) -> bool {
+        match self.overrides.get(&user_id) {
+            Some(enabled) => *enabled,
+            None => self.enabled && rollout_bucket(name, user_id) < self.rollout_percent,
+        }
+    }
+}
+
+/// Stable bucket in `0..100` for the user and flag
+///
+/// Hashing the flag name too keeps one flag's early adopters from being the
+/// early adopters of every flag.
+fn rollout_bucket(name: &str, user_id: UserId) -> u8 {
+    let digest = Sha256::digest(format!("{name}:{}", user_id.inner()).as_bytes());
+    let value = u32::from_be_bytes([digest[0], digest[1], digest[2], digest[3]]);
+    (value % 100) as u8
+}
+
+fn rollout_percent(value: i16) -> u8 {
+    u8::try_from(value.clamp(0, 100)).unwrap_or_default()
+}
+
+fn validate_flag_name(name: &str) -> Result<(), ApplicationError> {
+    let valid = !name.is_empty()
+        && name.len() <= MAX_FLAG_NAME_LENGTH
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(ApplicationError::BadRequest(format!(
+            "Flag names use lowercase letters, digits and underscores (at most {MAX_FLAG_NAME_LENGTH})"
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(enabled: bool, rollout_percent: u8) -> FlagRule {
+        FlagRule {
+            enabled,
+            rollout_percent,
+            overrides: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_rollout_percent_bounds() {
+        let users = (1..=500).map(UserId::new);
+
+        assert!(
+            users
+                .clone()
+                .all(|user| rule(true, 100).allows("beta", user))
+        );
+        assert!(!users.clone().any(|user| rule(true, 0).allows("beta", user)));
+        assert!(
+            !users
+                .clone()
+                .any(|user| rule(false, 100).allows("beta", user))
+        );
+
+        let half = users
+            .filter(|user| rule(true, 50).allows("beta", *user))
+            .count();
+        assert!((200..=300).contains(&half), "got {half} of 500");
+    }
+
+    #[test]
+    fn test_raising_rollout_keeps_enabled_users() {
+        for user in (1..=200).map(UserId::new) {
+            if rule(true, 10).allows("beta", user) {
+                assert!(rule(true, 30).allows("beta", user));
+            }
+        }
+    }
+
+    #[test]
+    fn test_override_wins_over_rollout() {
+        let mut pinned = rule(false, 0);
+        pinned.overrides.insert(UserId::new(7), true);
+        assert!(pinned.allows("beta", UserId::new(7)));
+        assert!(!pinned.allows("beta", UserId::new(8)));
+
+        let mut excluded = rule(true, 100);
+        excluded.overrides.insert(UserId::new(7), false);
+        assert!(!excluded.allows("beta", UserId::new(7)));
+    }
+
+    #[test]
+    fn test_validate_flag_name() {
+        assert!(validate_flag_name("external_invites").is_ok());
+        assert!(validate_flag_name("polls2").is_ok());
+        assert!(validate_flag_name("").is_err());
+        assert!(validate_flag_name("External-Invites").is_err());
+        assert!(validate_flag_name(&"a".repeat(65)).is_err());
+    }
+}
@@ -0,0 +1,381 @@
+//! Focus time: weekly blocks kept free for deep work.
+//!
+//! A rule asks for a number of blocks of one length each week, e.g. 2×2h,
+//! inside its own working hours or else the user's. A worker task keeps each rule's blocks
+//! for the rest of the user's local week on the calendar as tentative
+//! events, at most one a day per rule, and moves a tentative block once
+//! something else takes its time. Confirmed blocks stay where they are; a
+//! cancelled one skips its slot for the week.
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveTime, TimeZone, Utc};
+use std::collections::HashSet;
+use televent_domain::{EventStatus, MAX_SUMMARY_LENGTH, Timezone};
+use televent_storage::focus::{FocusBlock, FocusRule};
+use uuid::Uuid;
+
+use crate::{
+    ApplicationError, CalendarService, EventService, FreeBusy, SlotSearch, SubscriptionService,
+    UserId, WorkingHours,
+};
+
+/// Focus rules one user may keep.
+pub const MAX_FOCUS_RULES_PER_USER: usize = 5;
+
+/// Most blocks one rule may ask for each week.
+pub const MAX_FOCUS_BLOCKS_PER_WEEK: u32 = 7;
+
+/// Shortest focus block, in minutes.
+pub const MIN_FOCUS_MINUTES: i64 = 30;
+
+/// Longest focus block, in minutes.
+pub const MAX_FOCUS_MINUTES: i64 = 240;
+
+/// Title of blocks whose rule doesn't name them.
+pub const DEFAULT_FOCUS_TITLE: &str = "Focus time";
+
+/// A focus rule with its blocks this week.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FocusRuleView {
+    pub id: Uuid,
+    pub title: String,
+    pub blocks_per_week: u32,
+    pub duration: Duration,
+    pub working_hours: Option<WorkingHours>,
+    /// Blocks of the current week, earliest first
+    pub blocks: Vec<FocusBlockView>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl FocusRuleView {
+    /// `timezone` is the user's, which the rule's working hours are read in
+    pub(crate) fn new(rule: FocusRule, blocks: &[FocusBlock], timezone: &Timezone) -> Self {
+        Self {
+            id: rule.id,
+            title: rule.title,
+            blocks_per_week: u32::try_from(rule.blocks_per_week).unwrap_or_default(),
+            duration: Duration::minutes(i64::from(rule.duration_minutes)),
+            // Stored after parsing, so it always parses again
+            working_hours: rule
+                .working_hours
+                .as_deref()
+                .and_then(|hours| WorkingHours::parse(hours, timezone).ok()),
+            blocks: blocks
+                .iter()
+                .filter(|block| block.rule_id == rule.id)
+                .map(|block| FocusBlockView::from(*block))
+                .collect(),
+            created_at: rule.created_at,
+        }
+    }
+}
+
+/// An event reserved for a focus rule.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FocusBlockView {
+    pub event_id: Uuid,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub status: EventStatus,
+}
+
+impl From<FocusBlock> for FocusBlockView {
+    fn from(block: FocusBlock) -> Self {
+        Self {
+            event_id: block.event_id,
+            start: block.start,
+            end: block.end,
+            status: block.status,
+        }
+    }
+}
+
+/// A focus rule as the user sets it.
+#[derive(Debug, Clone)]
+pub struct FocusRuleCommand {
+    pub user_id: UserId,
+    /// [`DEFAULT_FOCUS_TITLE`] when `None`
+    pub title: Option<String>,
+    pub blocks_per_week: u32,
+    pub duration: Duration,
+    pub working_hours: Option<WorkingHours>,
+}
+
+impl FocusRuleCommand {
+    /// The title blocks get
+    pub(crate) fn validated_title(&self) -> Result<String, ApplicationError> {
+        let title = self
+            .title
+            .as_deref()
+            .map(str::trim)
+            .filter(|title| !title.is_empty())
+            .unwrap_or(DEFAULT_FOCUS_TITLE);
+        televent_domain::validate_length("Title", title, MAX_SUMMARY_LENGTH)
+            .map_err(ApplicationError::BadRequest)?;
+        televent_domain::validate_no_control_chars("Title", title)
+            .map_err(ApplicationError::BadRequest)?;
+
+        if !(1..=MAX_FOCUS_BLOCKS_PER_WEEK).contains(&self.blocks_per_week) {
+            return Err(ApplicationError::BadRequest(format!(
+                "A rule asks for 1 to {MAX_FOCUS_BLOCKS_PER_WEEK} blocks a week"
+            )));
+        }
+        let minutes = self.duration.num_minutes();
+        if self.duration != Duration::minutes(minutes)
+            || !(MIN_FOCUS_MINUTES..=MAX_FOCUS_MINUTES).contains(&minutes)
+        {
+            return Err(ApplicationError::BadRequest(format!(
+                "Focus blocks last whole minutes between {MIN_FOCUS_MINUTES} and \
+                 {MAX_FOCUS_MINUTES}"
+            )));
+        }
+        if let Some(hours) = &self.working_hours
+            && hours.longest_day() < self.duration
+        {
+            return Err(ApplicationError::BadRequest(format!(
+                "A block doesn't fit in working hours {hours}"
+            )));
+        }
+
+        Ok(title.to_string())
+    }
+
+    /// The rule just saved from this command, without blocks yet
+    pub(crate) fn view(&self, rule: FocusRule) -> FocusRuleView {
+        FocusRuleView {
+            working_hours: self.working_hours.clone(),
+            ..FocusRuleView::new(rule, &[], &Timezone::utc())
+        }
+    }
+}
+
+/// What one scheduling run changed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FocusSchedule {
+    /// New tentative blocks
+    pub placed: usize,
+    /// Tentative blocks removed because something else took their time;
+    /// each is placed again if a slot is left
+    pub moved: usize,
+    /// Blocks the rules ask for that found no free slot this week
+    pub missing: usize,
+}
+
+/// Parse `<blocks>x<duration>`, e.g. `2x2h` or `3x90m`
+pub fn parse_focus_pattern(value: &str) -> Result<(u32, Duration), ApplicationError> {
+    let invalid = || {
+        ApplicationError::BadRequest(format!(
+            "focus time must look like 2x2h (blocks a week x length), got {value:?}"
+        ))
+    };
+    let (count, length) = value
+        .trim()
+        .split_once(['x', 'X', '×'])
+        .ok_or_else(invalid)?;
+    let count = count.trim().parse().map_err(|_| invalid())?;
+    let length = crate::parse_duration_spec(length)?;
+    Ok((count, length))
+}
+
+/// The local week (Monday to Monday) `now` falls in
+#[must_use]
+pub fn focus_week(now: DateTime<Utc>, timezone: &Timezone) -> (DateTime<Utc>, DateTime<Utc>) {
+    let tz = timezone.tz();
+    let today = now.with_timezone(&tz).date_naive();
+    let monday = today - Duration::days(i64::from(today.weekday().num_days_from_monday()));
+    (
+        local_midnight(monday, timezone),
+        local_midnight(monday + Duration::days(7), timezone),
+    )
+}
+
+fn local_midnight(day: NaiveDate, timezone: &Timezone) -> DateTime<Utc> {
+    timezone
+        .tz()
+        .from_local_datetime(&day.and_time(NaiveTime::MIN))
+        .earliest()
+        .map_or_else(
+            || day.and_time(NaiveTime::MIN).and_utc(),
+            |local| local.with_timezone(&Utc),
+        )
+}
+
+/// Up to `count` free slots of `duration` from `from`, each on a local day
+/// not in `taken_days`, earliest first; the slots are marked busy and their
+/// days taken
+pub(crate) fn place_blocks(
+    free_busy: &mut FreeBusy,
+    from: DateTime<Utc>,
+    count: usize,
+    duration: Duration,
+    working_hours: Option<&WorkingHours>,
+    taken_days: &mut HashSet<NaiveDate>,
+) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+    let timezone = free_busy.timezone().clone();
+    let tz = timezone.tz();
+    let mut placed = Vec::new();
+    let mut day = from
+        .max(free_busy.window_start())
+        .with_timezone(&tz)
+        .date_naive();
+
+    while placed.len() < count {
+        let day_start = local_midnight(day, &timezone).max(from);
+        let day_end =
+            local_midnight(day + Duration::days(1), &timezone).min(free_busy.window_end());
+        if day_start >= free_busy.window_end() {
+            break;
+        }
+        if !taken_days.contains(&day) && day_start < day_end {
+            let search = SlotSearch {
+                from: day_start,
+                duration,
+                within: day_end - day_start,
+                working_hours: working_hours.cloned(),
+                count: 1,
+            };
+            if let Some(slot) = free_busy.free_slots(&search).first() {
+                free_busy.block(slot.start, slot.end);
+                taken_days.insert(day);
+                placed.push((slot.start, slot.end));
+            }
+        }
+        day += Duration::days(1);
+    }
+
+    placed
+}
+
+/// Bring the user's focus blocks for the rest of this week in line with
+/// their rules, around their own and subscribed calendars
+pub async fn schedule_focus_time(
+    calendar: &CalendarService,
+    subscriptions: &SubscriptionService,
+    events: &EventService,
+    user_id: UserId,
+) -> Result<FocusSchedule, ApplicationError> {
+    let user = calendar
+        .get_user_by_id(user_id)
+        .await?
+        .ok_or_else(|| ApplicationError::NotFound(user_id.to_string()))?;
+    let now = events.clock().now();
+    let (week_start, week_end) = focus_week(now, &user.timezone);
+
+    // The blocks themselves don't count as busy; a block only has to make
+    // way for everything else
+    let blocks: HashSet<Uuid> = calendar
+        .list_focus_rules(user_id)
+        .await?
+        .iter()
+        .flat_map(|rule| rule.blocks.iter().map(|block| block.event_id))
+        .collect();
+    let mut free_busy = calendar
+        .free_busy_excluding(user_id, &user.timezone, now, week_end, &blocks)
+        .await?;
+    subscriptions.mark_busy(user_id, &mut free_busy).await?;
+    let working_hours = calendar.working_hours(user_id).await?;
+
+    events
+        .schedule_focus(user_id, free_busy, week_start, working_hours)
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Weekday;
+    use televent_domain::EventTiming;
+
+    fn at(day: u32, hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 3, day, hour, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn parses_patterns() {
+        assert_eq!(
+            parse_focus_pattern("2x2h").unwrap(),
+            (2, Duration::hours(2))
+        );
+        assert_eq!(
+            parse_focus_pattern("3×90m").unwrap(),
+            (3, Duration::minutes(90))
+        );
+        assert!(parse_focus_pattern("2h").is_err());
+        assert!(parse_focus_pattern("twox2h").is_err());
+    }
+
+    #[test]
+    fn validates_rules() {
+        let command = |blocks, minutes, hours: Option<&str>| FocusRuleCommand {
+            user_id: UserId::new(1),
+            title: None,
+            blocks_per_week: blocks,
+            duration: Duration::minutes(minutes),
+            working_hours: hours.map(|hours| WorkingHours::parse(hours, &Timezone::utc()).unwrap()),
+        };
+        assert_eq!(
+            command(2, 120, None).validated_title().unwrap(),
+            DEFAULT_FOCUS_TITLE
+        );
+        assert!(command(0, 120, None).validated_title().is_err());
+        assert!(command(8, 120, None).validated_title().is_err());
+        assert!(command(2, 15, None).validated_title().is_err());
+        assert!(
+            command(2, 120, Some("09:00-10:00"))
+                .validated_title()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn week_runs_monday_to_monday_locally() {
+        let berlin = Timezone::parse("Europe/Berlin").unwrap();
+        // Wednesday 4 March 2026
+        let (start, end) = focus_week(at(4, 12), &berlin);
+        assert_eq!(start.with_timezone(&berlin.tz()).weekday(), Weekday::Mon);
+        assert_eq!(start, at(1, 23));
+        assert_eq!(end, at(8, 23));
+    }
+
+    #[test]
+    fn blocks_go_on_separate_free_days() {
+        // Monday 2 March 2026 to the next Monday
+        let mut free_busy = FreeBusy::new(at(2, 8), at(9, 0), &Timezone::utc());
+        free_busy
+            .add_event(
+                &EventTiming::Timed {
+                    start: at(2, 9),
+                    end: at(2, 17),
+                    timezone: Timezone::utc(),
+                },
+                None,
+            )
+            .unwrap();
+        let hours = WorkingHours::parse("Mon-Fri 09:00-17:00", &Timezone::utc()).unwrap();
+
+        let mut taken = HashSet::from([NaiveDate::from_ymd_opt(2026, 3, 3).unwrap()]);
+        let placed = place_blocks(
+            &mut free_busy,
+            at(2, 8),
+            2,
+            Duration::hours(2),
+            Some(&hours),
+            &mut taken,
+        );
+        // Monday is booked and Tuesday already has a block
+        assert_eq!(placed, vec![(at(4, 9), at(4, 11)), (at(5, 9), at(5, 11))]);
+        assert!(!free_busy.is_free(at(4, 10), at(4, 12)));
+        assert_eq!(taken.len(), 3);
+
+        // The week runs out
+        let placed = place_blocks(
+            &mut free_busy,
+            at(2, 8),
+            7,
+            Duration::hours(2),
+            Some(&hours),
+            &mut taken,
+        );
+        // Only Friday is left; the weekend is off
+        assert_eq!(placed, vec![(at(6, 9), at(6, 11))]);
+    }
+}
@@ -0,0 +1,530 @@
+//! Local side of the Google Calendar two-way sync.
+//!
+//! The optional connector crate talks to Google; this service owns what lives
+//! in our database: OAuth tokens, the incremental sync cursors, the links
+//! between local and Google events, and applying remote edits through
+//! [`EventService`] so they bump versions and etags like any other write.
+
+use chrono::{DateTime, Duration, Utc};
+use sha2::{Digest, Sha256};
+use televent_domain::{EventStatus, EventTiming, UserId};
+use televent_storage::calendar::{CalendarRepository, Event};
+use televent_storage::google::{
+    GoogleConnection, GoogleEventLink, GoogleRepository, GoogleSyncCursor, StoredGoogleConnection,
+};
+
+use crate::device::generate_password;
+use crate::{
+    ApplicationError, CreateEventCommand, EditScope, EventService, EventView, TextOverflow,
+    UpdateEventCommand, storage_error,
+};
+
+/// Delay between two syncs of a healthy connection.
+pub const GOOGLE_SYNC_INTERVAL_MINUTES: i64 = 15;
+/// How long a consent link stays valid.
+pub const GOOGLE_OAUTH_STATE_TTL_MINUTES: i64 = 15;
+const OAUTH_STATE_LENGTH: usize = 43;
+const FAILURE_RETRY_MINUTES: i64 = 60;
+const MAX_SYNC_ERROR_LENGTH: usize = 512;
+
+#[derive(Clone)]
+pub struct GoogleSyncService {
+    google: GoogleRepository,
+    calendar: CalendarRepository,
+    events: EventService,
+}
+
+impl GoogleSyncService {
+    #[must_use]
+    pub const fn new(
+        google: GoogleRepository,
+        calendar: CalendarRepository,
+        events: EventService,
+    ) -> Self {
+        Self {
+            google,
+            calendar,
+            events,
+        }
+    }
+
+    /// Store the tokens of a completed OAuth consent.
+    ///
+    /// Any previous connection of the user is reset and synced from scratch.
+    pub async fn connect(
+        &self,
+        command: ConnectGoogleCommand,
+    ) -> Result<GoogleConnectionView, ApplicationError> {
+        self.calendar
+            .ensure_user(command.user_id.inner(), command.username.as_deref())
+            .await
+            .map_err(storage_error)?;
+
+        let connection = self
+            .google
+            .upsert_connection(StoredGoogleConnection {
+                user_id: command.user_id,
+                access_token: command.access_token,
+                refresh_token: command.refresh_token,
+                access_token_expires_at: command.access_token_expires_at,
+            })
+            .await
+            .map_err(storage_error)?
+            .ok_or_else(|| {
+                ApplicationError::BadRequest(
+                    "Google did not grant offline access; please connect again".to_string(),
+                )
+            })?;
+
+        Ok(GoogleConnectionView::from(connection))
+    }
+
+    /// Start a consent: returns the random OAuth state for the consent URL.
+    ///
+    /// Only its hash is stored; [`Self::take_authorization`] accepts it once.
+    pub async fn begin_authorization(&self, user_id: UserId) -> Result<String, ApplicationError> {
+        self.calendar
+            .ensure_user(user_id.inner(), None)
+            .await
+            .map_err(storage_error)?;
+
+        let state = generate_password(OAUTH_STATE_LENGTH);
+        self.google
+            .insert_oauth_state(
+                &hash_oauth_state(&state),
+                user_id,
+                Utc::now() + Duration::minutes(GOOGLE_OAUTH_STATE_TTL_MINUTES),
+            )
+            .await
+            .map_err(storage_error)?;
+        Ok(state)
+    }
+
+    /// User who started the consent with this state, if it is fresh and unused.
+    pub async fn take_authorization(
+        &self,
+        state: &str,
+    ) -> Result<Option<UserId>, ApplicationError> {
+        self.google
+            .take_oauth_state(&hash_oauth_state(state), Utc::now())
+            .await
+            .map_err(storage_error)
+    }
+
+    pub async fn get_connection(
+        &self,
+        user_id: UserId,
+    ) -> Result<Option<GoogleConnectionView>, ApplicationError> {
+        Ok(self
+            .google
+            .get_connection(user_id)
+            .await
+            .map_err(storage_error)?
+            .map(GoogleConnectionView::from))
+    }
+
+    /// Forget the tokens and links; events already synced stay on both sides.
+    pub async fn disconnect(&self, user_id: UserId) -> Result<bool, ApplicationError> {
+        self.google
+            .delete_connection(user_id)
+            .await
+            .map_err(storage_error)
+    }
+
+    /// Connections the sync task should process now.
+    pub async fn due_connections(
+        &self,
+        limit: i64,
+    ) -> Result<Vec<GoogleSyncState>, ApplicationError> {
+        Ok(self
+            .google
+            .list_due_connections(Utc::now(), limit)
+            .await
+            .map_err(storage_error)?
+            .into_iter()
+            .map(GoogleSyncState::from)
+            .collect())
+    }
+
+    pub async fn store_access_token(
+        &self,
+        user_id: UserId,
+        access_token: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<(), ApplicationError> {
+        self.google
+            .update_access_token(user_id, access_token, expires_at)
+            .await
+            .map_err(storage_error)
+    }
+
+    pub async fn list_links(&self, user_id: UserId) -> Result<Vec<EventLink>, ApplicationError> {
+        Ok(self
+            .google
+            .list_links(user_id)
+            .await
+            .map_err(storage_error)?
+            .into_iter()
+            .map(EventLink::from)
+            .collect())
+    }
+
+    pub async fn save_link(
+        &self,
+        user_id: UserId,
+        link: EventLink,
+    ) -> Result<(), ApplicationError> {
+        self.google
+            .upsert_link(GoogleEventLink {
+                user_id: user_id.inner(),
+                event_uid: link.event_uid,
+                google_event_id: link.google_event_id,
+                google_etag: link.google_etag,
+                google_updated_at: link.google_updated_at,
+                local_etag: link.local_etag,
+            })
+            .await
+            .map_err(storage_error)
+    }
+
+    pub async fn remove_link(
+        &self,
+        user_id: UserId,
+        event_uid: &str,
+    ) -> Result<(), ApplicationError> {
+        self.google
+            .delete_link(user_id, event_uid)
+            .await
+            .map_err(storage_error)
+    }
+
+    /// Local events changed and deleted after `since_sync_version`.
+    ///
+    /// The returned `sync_version` is read first, so anything written while
+    /// the changes are listed is picked up again by the next sync.
+    pub async fn local_changes(
+        &self,
+        user_id: UserId,
+        since_sync_version: i64,
+    ) -> Result<LocalChanges, ApplicationError> {
+        let sync_version = self
+            .calendar
+            .get_user_by_id(user_id)
+            .await
+            .map_err(storage_error)?
+            .ok_or_else(|| ApplicationError::NotFound(format!("User not found: {user_id}")))?
+            .sync_token;
+
+        let events = self
+            .calendar
+            .list_events_since_sync(user_id, since_sync_version)
+            .await
+            .map_err(storage_error)?
+            .into_iter()
+            .map(LocalEvent::try_from)
+            .collect::<Result<_, _>>()?;
+        // Nothing was ever pushed before the first sync, so there is nothing to delete
+        let deleted_uids = if since_sync_version == 0 {
+            Vec::new()
+        } else {
+            self.calendar
+                .list_tombstones_since(user_id, since_sync_version)
+                .await
+                .map_err(storage_error)?
+                .into_iter()
+                .map(|tombstone| tombstone.uid)
+                .collect()
+        };
+
+        Ok(LocalChanges {
+            sync_version,
+            events,
+            deleted_uids,
+        })
+    }
+
+    pub async fn local_event(
+        &self,
+        user_id: UserId,
+        uid: &str,
+    ) -> Result<Option<LocalEvent>, ApplicationError> {
+        self.calendar
+            .get_event_by_uid(user_id, uid)
+            .await
+            .map_err(storage_error)?
+            .map(LocalEvent::try_from)
+            .transpose()
+    }
+
+    /// Create or overwrite the local event with the content of a Google event.
+    ///
+    /// Attendees of an existing event are left untouched, and text over the
+    /// local limits is truncated.
+    pub async fn apply_remote_event(
+        &self,
+        user_id: UserId,
+        mut remote: RemoteEvent,
+    ) -> Result<LocalEvent, ApplicationError> {
+        self.events.text_limits().sanitize(
+            Some(&mut remote.summary),
+            remote.description.as_mut(),
+            remote.location.as_mut(),
+            TextOverflow::Truncate,
+        )?;
+        let existing = self
+            .calendar
+            .get_event_by_uid(user_id, &remote.uid)
+            .await
+            .map_err(storage_error)?;
+
+        match existing {
+            Some(event) => {
+                self.events
+                    .update_event_view(UpdateEventCommand {
+                        user_id,
+                        event_id: event.id,
+                        summary: Some(remote.summary),
+                        description: Some(remote.description),
+                        location: Some(remote.location),
+                        timing: Some(remote.timing),
+                        status: Some(remote.status),
+                        rrule: Some(remote.rrule),
+                        visibility: None,
+                        reminder_note: None,
+                        scope: EditScope::Series,
+                    })
+                    .await?;
+            }
+            None => {
+                self.events
+                    .create_event_view(CreateEventCommand {
+                        user_id,
+                        username: None,
+                        uid: remote.uid.clone(),
+                        summary: remote.summary,
+                        description: remote.description,
+                        location: remote.location,
+                        timing: remote.timing,
+                        status: remote.status,
+                        rrule: remote.rrule,
+                        visibility: None,
+                        reminder_note: None,
+                        allow_duplicate: true,
+                    })
+                    .await?;
+            }
+        }
+
+        self.local_event(user_id, &remote.uid)
+            .await?
+            .ok_or(ApplicationError::NotFound(remote.uid))
+    }
+
+    /// Delete the local event of a Google event that was removed remotely.
+    pub async fn delete_local_event(
+        &self,
+        user_id: UserId,
+        uid: &str,
+    ) -> Result<bool, ApplicationError> {
+        match self.events.delete_event_by_uid(user_id, uid, None).await {
+            Ok(()) => Ok(true),
+            Err(ApplicationError::NotFound(_)) => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
+
+    pub async fn record_sync_success(
+        &self,
+        user_id: UserId,
+        google_sync_token: Option<String>,
+        local_sync_version: i64,
+    ) -> Result<(), ApplicationError> {
+        self.google
+            .record_sync_success(
+                user_id,
+                GoogleSyncCursor {
+                    google_sync_token,
+                    local_sync_version,
+                    next_sync_at: Utc::now() + Duration::minutes(GOOGLE_SYNC_INTERVAL_MINUTES),
+                },
+            )
+            .await
+            .map_err(storage_error)
+    }
+
+    /// Keep the cursors where they were and retry later.
+    pub async fn record_sync_failure(
+        &self,
+        user_id: UserId,
+        error: &str,
+    ) -> Result<(), ApplicationError> {
+        let error: String = error.chars().take(MAX_SYNC_ERROR_LENGTH).collect();
+        self.google
+            .record_sync_failure(
+                user_id,
+                &error,
+                Utc::now() + Duration::minutes(FAILURE_RETRY_MINUTES),
+            )
+            .await
+            .map_err(storage_error)
+    }
+}
+
+/// Which side's copy survives when an event changed on both since the last sync.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncWinner {
+    Local,
+    Remote,
+}
+
+fn hash_oauth_state(state: &str) -> String {
+    format!("{:x}", Sha256::digest(state.as_bytes()))
+}
+
+/// Prefer the newest update; on a tie Google wins so both sides converge.
+#[must_use]
+pub fn resolve_sync_conflict(
+    local_updated_at: DateTime<Utc>,
+    remote_updated_at: DateTime<Utc>,
+) -> SyncWinner {
+    if local_updated_at > remote_updated_at {
+        SyncWinner::Local
+    } else {
+        SyncWinner::Remote
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ConnectGoogleCommand {
+    pub user_id: UserId,
+    pub username: Option<String>,
+    pub access_token: String,
+    /// Google only returns a refresh token on the first consent.
+    pub refresh_token: Option<String>,
+    pub access_token_expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GoogleConnectionView {
+    pub calendar_id: String,
+    pub last_synced_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+    pub connected_at: DateTime<Utc>,
+}
+
+impl From<GoogleConnection> for GoogleConnectionView {
+    fn from(connection: GoogleConnection) -> Self {
+        Self {
+            calendar_id: connection.calendar_id,
+            last_synced_at: connection.last_synced_at,
+            last_error: connection.last_error,
+            connected_at: connection.created_at,
+        }
+    }
+}
+
+/// Everything the sync task needs to process one connection.
+#[derive(Debug, Clone)]
+pub struct GoogleSyncState {
+    pub user_id: UserId,
+    pub calendar_id: String,
+    pub access_token: String,
+    pub refresh_token: String,
+    pub access_token_expires_at: DateTime<Utc>,
+    pub google_sync_token: Option<String>,
+    pub local_sync_version: i64,
+}
+
+impl From<GoogleConnection> for GoogleSyncState {
+    fn from(connection: GoogleConnection) -> Self {
+        Self {
+            user_id: UserId::new(connection.user_id),
+            calendar_id: connection.calendar_id,
+            access_token: connection.access_token,
+            refresh_token: connection.refresh_token,
+            access_token_expires_at: connection.access_token_expires_at,
+            google_sync_token: connection.google_sync_token,
+            local_sync_version: connection.local_sync_version,
+        }
+    }
+}
+
+/// A local event paired with its Google counterpart.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EventLink {
+    pub event_uid: String,
+    pub google_event_id: String,
+    /// Google etag as of the last sync; a different one means a remote edit.
+    pub google_etag: String,
+    pub google_updated_at: DateTime<Utc>,
+    /// Local etag as of the last sync; a different one means a local edit.
+    pub local_etag: String,
+}
+
+impl From<GoogleEventLink> for EventLink {
+    fn from(link: GoogleEventLink) -> Self {
+        Self {
+            event_uid: link.event_uid,
+            google_event_id: link.google_event_id,
+            google_etag: link.google_etag,
+            google_updated_at: link.google_updated_at,
+            local_etag: link.local_etag,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocalEvent {
+    pub event: EventView,
+    pub etag: String,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl TryFrom<Event> for LocalEvent {
+    type Error = ApplicationError;
+
+    fn try_from(event: Event) -> Result<Self, Self::Error> {
+        let etag = event.etag.clone();
+        let updated_at = event.updated_at;
+        Ok(Self {
+            event: EventView::try_from(event)?,
+            etag,
+            updated_at,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LocalChanges {
+    /// Calendar sync version the changes are complete up to.
+    pub sync_version: i64,
+    pub events: Vec<LocalEvent>,
+    pub deleted_uids: Vec<String>,
+}
+
+/// Content of a Google event, already mapped to the local model.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteEvent {
+    pub uid: String,
+    pub summary: String,
+    pub description: Option<String>,
+    pub location: Option<String>,
+    pub timing: EventTiming,
+    pub status: EventStatus,
+    pub rrule: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn newest_update_wins_conflicts() {
+        let earlier = Utc::now();
+        let later = earlier + Duration::seconds(5);
+
+        assert_eq!(resolve_sync_conflict(later, earlier), SyncWinner::Local);
+        assert_eq!(resolve_sync_conflict(earlier, later), SyncWinner::Remote);
+        assert_eq!(resolve_sync_conflict(earlier, earlier), SyncWinner::Remote);
+    }
+}
@@ -0,0 +1,344 @@
+//! Month grid for the mini-app calendar view.
+//!
+//! [`MonthGrid`] buckets a user's events into the local days of one month,
+//! expanding recurrences and repeating multi-day events on every day they
+//! cover. Each day keeps its full count but only the first few entries, with
+//! trimmed summaries, which is all a grid cell has room for.
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use televent_domain::{EventStatus, EventTiming, Timezone, expand_rrule};
+use uuid::Uuid;
+
+use crate::{ApplicationError, EventView};
+
+/// Entries kept per day; `count` still covers the rest.
+pub const GRID_EVENTS_PER_DAY: usize = 3;
+
+/// Summaries longer than this many characters are cut with an ellipsis.
+pub const GRID_SUMMARY_CHARS: usize = 40;
+
+/// Cap on occurrences expanded per recurring event inside one month.
+const MAX_GRID_OCCURRENCES: usize = 100;
+
+/// Events of one month, bucketed by local day.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MonthGrid {
+    /// First day of the month
+    pub month: NaiveDate,
+    pub timezone: Timezone,
+    /// Every day of the month, in order
+    pub days: Vec<GridDay>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GridDay {
+    pub date: NaiveDate,
+    /// Events on this day, including those not listed
+    pub count: usize,
+    /// All-day events first, then by start time
+    pub events: Vec<GridEvent>,
+}
+
+/// One occurrence of an event on a day of the grid.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GridEvent {
+    pub id: Uuid,
+    pub summary: String,
+    /// Start of the occurrence; `None` for all-day events
+    pub start: Option<DateTime<Utc>>,
+    pub is_all_day: bool,
+    pub status: EventStatus,
+    /// The occurrence began on an earlier day
+    pub continues: bool,
+}
+
+/// Parse a month given as `YYYY-MM` into its first day.
+pub fn parse_month(value: &str) -> Result<NaiveDate, ApplicationError> {
+    NaiveDate::parse_from_str(&format!("{}-01", value.trim()), "%Y-%m-%d").map_err(|_| {
+        ApplicationError::BadRequest(format!("month must look like 2025-03, got {value:?}"))
+    })
+}
+
+impl MonthGrid {
+    /// Bucket `events` into the days of `month` (any day of it) in
+    /// `timezone`.
+    pub fn build(
+        month: NaiveDate,
+        timezone: &Timezone,
+        events: &[EventView],
+    ) -> Result<Self, ApplicationError> {
+        let first = month.with_day(1).unwrap_or(month);
+        let next = first
+            .checked_add_months(chrono::Months::new(1))
+            .ok_or_else(|| ApplicationError::BadRequest("month out of range".to_string()))?;
+        let mut builder = GridBuilder {
+            first,
+            next,
+            timezone,
+            tz: timezone.tz(),
+            entries: first
+                .iter_days()
+                .take_while(|day| *day < next)
+                .map(|_| Vec::new())
+                .collect(),
+        };
+        for event in events {
+            builder.add_event(event)?;
+        }
+
+        Ok(Self {
+            month: first,
+            timezone: timezone.clone(),
+            days: builder
+                .entries
+                .into_iter()
+                .zip(first.iter_days())
+                .map(|(mut entries, date)| {
+                    entries.sort_by_key(|entry: &GridEvent| (!entry.is_all_day, entry.start));
+                    let count = entries.len();
+                    entries.truncate(GRID_EVENTS_PER_DAY);
+                    GridDay {
+                        date,
+                        count,
+                        events: entries,
+                    }
+                })
+                .collect(),
+        })
+    }
+}
+
+struct GridBuilder<'a> {
+    first: NaiveDate,
+    next: NaiveDate,
+    timezone: &'a Timezone,
+    tz: Tz,
+    entries: Vec<Vec<GridEvent>>,
+}
+
+impl GridBuilder<'_> {
+    fn add_event(&mut self, event: &EventView) -> Result<(), ApplicationError> {
+        match event.timing.pinned_to(self.timezone) {
+            EventTiming::Timed { start, end, .. } => {
+                let length = end - start;
+                let window_start = self.local_midnight(self.first);
+                let window_end = self.local_midnight(self.next);
+                for occurrence in
+                    self.occurrences(event, start, length, window_start, window_end)?
+                {
+                    let first_day = occurrence.with_timezone(&self.tz).date_naive();
+                    // The end is exclusive: an event ending at midnight
+                    // doesn't spill into the next day
+                    let last_day = (occurrence + length - Duration::nanoseconds(1))
+                        .with_timezone(&self.tz)
+                        .date_naive()
+                        .max(first_day);
+                    self.add_days(event, Some(occurrence), first_day, last_day);
+                }
+            }
+            EventTiming::AllDay {
+                start_date,
+                end_date,
+            } => {
+                // Dates are floating, so expand them as if they were UTC
+                let start = start_date.and_time(NaiveTime::MIN).and_utc();
+                let length = end_date - start_date;
+                let window_start = self.first.and_time(NaiveTime::MIN).and_utc();
+                let window_end = self.next.and_time(NaiveTime::MIN).and_utc();
+                for occurrence in
+                    self.occurrences(event, start, length, window_start, window_end)?
+                {
+                    let first_day = occurrence.date_naive();
+                    let last_day = (first_day + length - Duration::days(1)).max(first_day);
+                    self.add_days(event, None, first_day, last_day);
+                }
+            }
+            EventTiming::Floating { .. } => unreachable!("pinned to the grid's zone"),
+        }
+
+        Ok(())
+    }
+
+    /// Starts of the occurrences overlapping `[window_start, window_end)`
+    fn occurrences(
+        &self,
+        event: &EventView,
+        start: DateTime<Utc>,
+        length: Duration,
+        window_start: DateTime<Utc>,
+        window_end: DateTime<Utc>,
+    ) -> Result<Vec<DateTime<Utc>>, ApplicationError> {
+        let Some(rrule) = event.rrule.as_deref() else {
+            return Ok(vec![start]);
+        };
+        Ok(expand_rrule(
+            rrule,
+            start,
+            window_start - length,
+            window_end,
+            MAX_GRID_OCCURRENCES,
+        )?)
+    }
+
+    fn add_days(
+        &mut self,
+        event: &EventView,
+        start: Option<DateTime<Utc>>,
+        first_day: NaiveDate,
+        last_day: NaiveDate,
+    ) {
+        let from = first_day.max(self.first);
+        let until = last_day.min(self.next - Duration::days(1));
+        for day in from.iter_days().take_while(|day| *day <= until) {
+            let index = (day - self.first).num_days() as usize;
+            self.entries[index].push(GridEvent {
+                id: event.id,
+                summary: trim_summary(&event.summary),
+                start,
+                is_all_day: start.is_none(),
+                status: event.status,
+                continues: day > first_day,
+            });
+        }
+    }
+
+    fn local_midnight(&self, day: NaiveDate) -> DateTime<Utc> {
+        self.tz
+            .from_local_datetime(&day.and_time(NaiveTime::MIN))
+            .earliest()
+            .map(|local| local.with_timezone(&Utc))
+            .unwrap_or_else(|| day.and_time(NaiveTime::MIN).and_utc())
+    }
+}
+
+fn trim_summary(summary: &str) -> String {
+    if summary.chars().count() <= GRID_SUMMARY_CHARS {
+        return summary.to_string();
+    }
+    let mut trimmed: String = summary.chars().take(GRID_SUMMARY_CHARS - 1).collect();
+    trimmed.truncate(trimmed.trim_end().len());
+    trimmed.push('…');
+    trimmed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use televent_domain::EventVisibility;
+
+    fn event(summary: &str, timing: EventTiming, rrule: Option<&str>) -> EventView {
+        EventView {
+            id: Uuid::new_v4(),
+            uid: Uuid::new_v4().to_string(),
+            summary: summary.to_string(),
+            description: None,
+            location: None,
+            timing,
+            status: EventStatus::Confirmed,
+            rrule: rrule.map(str::to_string),
+            visibility: EventVisibility::Public,
+            reminder_note: None,
+        }
+    }
+
+    fn timed(start: &str, end: &str) -> EventTiming {
+        EventTiming::Timed {
+            start: DateTime::parse_from_rfc3339(start).unwrap().to_utc(),
+            end: DateTime::parse_from_rfc3339(end).unwrap().to_utc(),
+            timezone: Timezone::utc(),
+        }
+    }
+
+    fn day(date: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_month_grid_buckets_by_local_day() {
+        let tokyo = Timezone::parse("Asia/Tokyo").unwrap();
+        let grid = MonthGrid::build(
+            parse_month("2025-03").unwrap(),
+            &tokyo,
+            &[
+                // Sunday 23:00 UTC is already Monday in Tokyo
+                event(
+                    "Standup",
+                    timed("2025-03-02T23:00:00Z", "2025-03-02T23:15:00Z"),
+                    Some("FREQ=WEEKLY;COUNT=10"),
+                ),
+                event(
+                    "Offsite",
+                    EventTiming::AllDay {
+                        start_date: day("2025-03-30"),
+                        end_date: day("2025-04-02"),
+                    },
+                    None,
+                ),
+                event(
+                    "Late review",
+                    timed("2025-03-10T14:00:00Z", "2025-03-10T16:00:00Z"),
+                    None,
+                ),
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(grid.days.len(), 31);
+        let mondays: Vec<u32> = grid
+            .days
+            .iter()
+            .filter(|d| d.events.iter().any(|e| e.summary == "Standup"))
+            .map(|d| d.date.day())
+            .collect();
+        assert_eq!(mondays, [3, 10, 17, 24, 31]);
+
+        // 23:00-01:00 in Tokyo covers two days
+        let review: Vec<(u32, bool)> = grid
+            .days
+            .iter()
+            .flat_map(|d| {
+                d.events
+                    .iter()
+                    .filter(|e| e.summary == "Late review")
+                    .map(move |e| (d.date.day(), e.continues))
+            })
+            .collect();
+        assert_eq!(review, [(10, false), (11, true)]);
+
+        // Cut at the end of the month; all-day entries come first
+        assert_eq!(grid.days[29].events[0].summary, "Offsite");
+        assert!(grid.days[30].events[0].is_all_day);
+        assert!(grid.days[30].events[0].continues);
+        assert_eq!(grid.days[30].count, 2);
+    }
+
+    #[test]
+    fn test_month_grid_trims_busy_days() {
+        let events: Vec<EventView> = (0..5)
+            .map(|hour| {
+                event(
+                    &format!("Meeting {hour} with a very long title that goes on and on"),
+                    timed(
+                        &format!("2025-02-03T0{hour}:00:00Z"),
+                        &format!("2025-02-03T0{hour}:30:00Z"),
+                    ),
+                    None,
+                )
+            })
+            .collect();
+        let grid = MonthGrid::build(day("2025-02-14"), &Timezone::utc(), &events).unwrap();
+
+        assert_eq!(grid.month, day("2025-02-01"));
+        assert_eq!(grid.days.len(), 28);
+        let busy = &grid.days[2];
+        assert_eq!(busy.count, 5);
+        assert_eq!(busy.events.len(), GRID_EVENTS_PER_DAY);
+        assert!(busy.events[0].summary.starts_with("Meeting 0"));
+        assert!(busy.events[0].summary.ends_with('…'));
+        assert_eq!(busy.events[0].summary.chars().count(), GRID_SUMMARY_CHARS);
+
+        assert!(parse_month("2025-13").is_err());
+        assert!(parse_month("March").is_err());
+    }
+}
@@ -1,4 +1,5 @@
 use televent_storage::health::HealthRepository;
+use televent_storage::migrations::MigrationStatus;
 
 use crate::{ApplicationError, storage_error};
 
@@ -17,4 +18,9 @@ impl HealthService {
         self.health.check_database().await.map_err(storage_error)?;
         Ok(())
     }
+
+    /// Which embedded migrations the database has applied
+    pub async fn migration_status(&self) -> Result<MigrationStatus, ApplicationError> {
+        self.health.migration_status().await.map_err(storage_error)
+    }
 }
@@ -0,0 +1,422 @@
+//! Built-in public holiday calendars.
+//!
+//! Each supported country is a read-only calendar users subscribe to like a
+//! remote feed, under a `holidays:XX` address instead of a URL. Its events
+//! are generated from the rules below rather than fetched, for the current
+//! and the next year, and the refresh task regenerates them every January.
+//!
+//! Only nationwide public holidays are listed, on their calendar date;
+//! regional holidays and substitute days for ones falling on a weekend are
+//! not.
+
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+use televent_domain::EventTiming;
+use televent_storage::subscription::SubscribedEventWrite;
+
+use crate::subscription::generated_event;
+
+/// Address prefix of a holiday calendar subscription, followed by the
+/// ISO 3166-1 country code.
+pub const HOLIDAYS_URL_PREFIX: &str = "holidays:";
+
+/// How a holiday's date is found in a given year
+#[derive(Debug, Clone, Copy)]
+enum Rule {
+    /// Same day every year
+    Fixed { month: u32, day: u32 },
+    /// Days after (or before, when negative) Western Easter Sunday
+    Easter(i64),
+    /// The `n`th `weekday` of the month
+    Nth { month: u32, weekday: Weekday, n: u8 },
+    /// The last `weekday` of the month
+    Last { month: u32, weekday: Weekday },
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Holiday {
+    name: &'static str,
+    rule: Rule,
+    days: u32,
+}
+
+const fn fixed(name: &'static str, month: u32, day: u32) -> Holiday {
+    Holiday {
+        name,
+        rule: Rule::Fixed { month, day },
+        days: 1,
+    }
+}
+
+const fn easter(name: &'static str, offset: i64) -> Holiday {
+    Holiday {
+        name,
+        rule: Rule::Easter(offset),
+        days: 1,
+    }
+}
+
+const fn nth(name: &'static str, month: u32, weekday: Weekday, n: u8) -> Holiday {
+    Holiday {
+        name,
+        rule: Rule::Nth { month, weekday, n },
+        days: 1,
+    }
+}
+
+const fn last(name: &'static str, month: u32, weekday: Weekday) -> Holiday {
+    Holiday {
+        name,
+        rule: Rule::Last { month, weekday },
+        days: 1,
+    }
+}
+
+/// A country whose public holidays can be subscribed to
+#[derive(Debug, Clone, Copy)]
+pub struct HolidayCountry {
+    /// ISO 3166-1 alpha-2 code, upper case
+    pub code: &'static str,
+    pub name: &'static str,
+    holidays: &'static [Holiday],
+}
+
+impl HolidayCountry {
+    /// Subscription address of the country's calendar
+    #[must_use]
+    pub fn url(&self) -> String {
+        format!("{HOLIDAYS_URL_PREFIX}{}", self.code)
+    }
+
+    /// Name the calendar gets when subscribed without one
+    #[must_use]
+    pub fn calendar_name(&self) -> String {
+        format!("Holidays in {}", self.name)
+    }
+
+    /// The country's holidays in `year` as `(first day, day after the last, name)`,
+    /// by date
+    #[must_use]
+    pub fn holidays_in(&self, year: i32) -> Vec<(NaiveDate, NaiveDate, &'static str)> {
+        let mut holidays: Vec<_> = self
+            .holidays
+            .iter()
+            .filter_map(|holiday| {
+                let start = holiday.rule.date_in(year)?;
+                Some((
+                    start,
+                    start + Duration::days(i64::from(holiday.days)),
+                    holiday.name,
+                ))
+            })
+            .collect();
+        holidays.sort_by_key(|(start, _, _)| *start);
+        holidays
+    }
+}
+
+impl Rule {
+    fn date_in(self, year: i32) -> Option<NaiveDate> {
+        match self {
+            Self::Fixed { month, day } => NaiveDate::from_ymd_opt(year, month, day),
+            Self::Easter(offset) => Some(easter_sunday(year)? + Duration::days(offset)),
+            Self::Nth { month, weekday, n } => {
+                NaiveDate::from_weekday_of_month_opt(year, month, weekday, n)
+            }
+            Self::Last { month, weekday } => {
+                let next_month = if month == 12 {
+                    NaiveDate::from_ymd_opt(year + 1, 1, 1)?
+                } else {
+                    NaiveDate::from_ymd_opt(year, month + 1, 1)?
+                };
+                let last_day = next_month.pred_opt()?;
+                let back = (7 + last_day.weekday().num_days_from_monday()
+                    - weekday.num_days_from_monday())
+                    % 7;
+                Some(last_day - Duration::days(i64::from(back)))
+            }
+        }
+    }
+}
+
+/// Western (Gregorian) Easter Sunday, by the anonymous Gregorian algorithm
+fn easter_sunday(year: i32) -> Option<NaiveDate> {
+    let a = year % 19;
+    let b = year / 100;
+    let c = year % 100;
+    let d = b / 4;
+    let e = b % 4;
+    let f = (b + 8) / 25;
+    let g = (b - f + 1) / 3;
+    let h = (19 * a + b - d - g + 15) % 30;
+    let i = c / 4;
+    let k = c % 4;
+    let l = (32 + 2 * e + 2 * i - h - k) % 7;
+    let m = (a + 11 * h + 22 * l) / 451;
+    let month = (h + l - 7 * m + 114) / 31;
+    let day = (h + l - 7 * m + 114) % 31 + 1;
+    NaiveDate::from_ymd_opt(year, u32::try_from(month).ok()?, u32::try_from(day).ok()?)
+}
+
+const COUNTRIES: &[HolidayCountry] = &[
+    HolidayCountry {
+        code: "DE",
+        name: "Germany",
+        holidays: &[
+            fixed("New Year's Day", 1, 1),
+            easter("Good Friday", -2),
+            easter("Easter Monday", 1),
+            fixed("Labour Day", 5, 1),
+            easter("Ascension Day", 39),
+            easter("Whit Monday", 50),
+            fixed("German Unity Day", 10, 3),
+            fixed("Christmas Day", 12, 25),
+            fixed("Second Day of Christmas", 12, 26),
+        ],
+    },
+    HolidayCountry {
+        code: "ES",
+        name: "Spain",
+        holidays: &[
+            fixed("New Year's Day", 1, 1),
+            fixed("Epiphany", 1, 6),
+            easter("Good Friday", -2),
+            fixed("Labour Day", 5, 1),
+            fixed("Assumption Day", 8, 15),
+            fixed("National Day", 10, 12),
+            fixed("All Saints' Day", 11, 1),
+            fixed("Constitution Day", 12, 6),
+            fixed("Immaculate Conception", 12, 8),
+            fixed("Christmas Day", 12, 25),
+        ],
+    },
+    HolidayCountry {
+        code: "FR",
+        name: "France",
+        holidays: &[
+            fixed("New Year's Day", 1, 1),
+            easter("Easter Monday", 1),
+            fixed("Labour Day", 5, 1),
+            fixed("Victory in Europe Day", 5, 8),
+            easter("Ascension Day", 39),
+            easter("Whit Monday", 50),
+            fixed("Bastille Day", 7, 14),
+            fixed("Assumption Day", 8, 15),
+            fixed("All Saints' Day", 11, 1),
+            fixed("Armistice Day", 11, 11),
+            fixed("Christmas Day", 12, 25),
+        ],
+    },
+    HolidayCountry {
+        code: "GB",
+        name: "the United Kingdom",
+        holidays: &[
+            fixed("New Year's Day", 1, 1),
+            easter("Good Friday", -2),
+            easter("Easter Monday", 1),
+            nth("Early May Bank Holiday", 5, Weekday::Mon, 1),
+            last("Spring Bank Holiday", 5, Weekday::Mon),
+            last("Summer Bank Holiday", 8, Weekday::Mon),
+            fixed("Christmas Day", 12, 25),
+            fixed("Boxing Day", 12, 26),
+        ],
+    },
+    HolidayCountry {
+        code: "IT",
+        name: "Italy",
+        holidays: &[
+            fixed("New Year's Day", 1, 1),
+            fixed("Epiphany", 1, 6),
+            easter("Easter Monday", 1),
+            fixed("Liberation Day", 4, 25),
+            fixed("Labour Day", 5, 1),
+            fixed("Republic Day", 6, 2),
+            fixed("Ferragosto", 8, 15),
+            fixed("All Saints' Day", 11, 1),
+            fixed("Immaculate Conception", 12, 8),
+            fixed("Christmas Day", 12, 25),
+            fixed("St. Stephen's Day", 12, 26),
+        ],
+    },
+    HolidayCountry {
+        code: "NL",
+        name: "the Netherlands",
+        holidays: &[
+            fixed("New Year's Day", 1, 1),
+            easter("Easter Monday", 1),
+            fixed("King's Day", 4, 27),
+            fixed("Liberation Day", 5, 5),
+            easter("Ascension Day", 39),
+            easter("Whit Monday", 50),
+            fixed("Christmas Day", 12, 25),
+            fixed("Second Day of Christmas", 12, 26),
+        ],
+    },
+    HolidayCountry {
+        code: "RU",
+        name: "Russia",
+        holidays: &[
+            Holiday {
+                name: "New Year Holidays",
+                rule: Rule::Fixed { month: 1, day: 1 },
+                days: 8,
+            },
+            fixed("Orthodox Christmas", 1, 7),
+            fixed("Defender of the Fatherland Day", 2, 23),
+            fixed("International Women's Day", 3, 8),
+            fixed("Spring and Labour Day", 5, 1),
+            fixed("Victory Day", 5, 9),
+            fixed("Russia Day", 6, 12),
+            fixed("Unity Day", 11, 4),
+        ],
+    },
+    HolidayCountry {
+        code: "US",
+        name: "the United States",
+        holidays: &[
+            fixed("New Year's Day", 1, 1),
+            nth("Martin Luther King Jr. Day", 1, Weekday::Mon, 3),
+            nth("Washington's Birthday", 2, Weekday::Mon, 3),
+            last("Memorial Day", 5, Weekday::Mon),
+            fixed("Juneteenth", 6, 19),
+            fixed("Independence Day", 7, 4),
+            nth("Labor Day", 9, Weekday::Mon, 1),
+            nth("Columbus Day", 10, Weekday::Mon, 2),
+            fixed("Veterans Day", 11, 11),
+            nth("Thanksgiving Day", 11, Weekday::Thu, 4),
+            fixed("Christmas Day", 12, 25),
+        ],
+    },
+];
+
+/// Every country with a holiday calendar, by code
+#[must_use]
+pub fn holiday_countries() -> &'static [HolidayCountry] {
+    COUNTRIES
+}
+
+/// Country by ISO code, ignoring case
+#[must_use]
+pub fn find_holiday_country(code: &str) -> Option<&'static HolidayCountry> {
+    let code = code.trim();
+    COUNTRIES
+        .iter()
+        .find(|country| country.code.eq_ignore_ascii_case(code))
+}
+
+/// Country of a `holidays:XX` subscription address; `None` for any other
+/// address, including `holidays:` ones naming an unknown country
+#[must_use]
+pub fn holiday_country_for_url(url: &str) -> Option<&'static HolidayCountry> {
+    let prefix = url.get(..HOLIDAYS_URL_PREFIX.len())?;
+    if !prefix.eq_ignore_ascii_case(HOLIDAYS_URL_PREFIX) {
+        return None;
+    }
+    find_holiday_country(&url[HOLIDAYS_URL_PREFIX.len()..])
+}
+
+/// Mirror rows for the country's holidays in `years`
+pub(crate) fn holiday_events(
+    country: &HolidayCountry,
+    years: impl IntoIterator<Item = i32>,
+) -> Vec<SubscribedEventWrite> {
+    years
+        .into_iter()
+        .flat_map(|year| country.holidays_in(year))
+        .map(|(start_date, end_date, name)| {
+            // Two holidays can fall on one day, as Ascension and Labour Day
+            // sometimes do, so the name is part of the UID
+            let slug: String = name
+                .chars()
+                .filter(|c| c.is_ascii_alphanumeric() || *c == ' ')
+                .map(|c| {
+                    if c == ' ' {
+                        '-'
+                    } else {
+                        c.to_ascii_lowercase()
+                    }
+                })
+                .collect();
+            let uid = format!(
+                "{}-{slug}@{}.holidays.televent",
+                start_date.format("%Y%m%d"),
+                country.code.to_ascii_lowercase()
+            );
+            generated_event(
+                uid,
+                name.to_string(),
+                None,
+                EventTiming::AllDay {
+                    start_date,
+                    end_date,
+                },
+                None,
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn easter_matches_known_dates() {
+        assert_eq!(easter_sunday(2024), Some(date(2024, 3, 31)));
+        assert_eq!(easter_sunday(2026), Some(date(2026, 4, 5)));
+        assert_eq!(easter_sunday(2027), Some(date(2027, 3, 28)));
+        assert_eq!(easter_sunday(2038), Some(date(2038, 4, 25)));
+    }
+
+    #[test]
+    fn weekday_rules_land_on_the_right_day() {
+        let us = find_holiday_country("us").unwrap();
+        let holidays = us.holidays_in(2026);
+        let on = |name: &str| {
+            holidays
+                .iter()
+                .find(|(_, _, holiday)| *holiday == name)
+                .map(|(start, _, _)| *start)
+        };
+
+        assert_eq!(on("Martin Luther King Jr. Day"), Some(date(2026, 1, 19)));
+        assert_eq!(on("Memorial Day"), Some(date(2026, 5, 25)));
+        assert_eq!(on("Thanksgiving Day"), Some(date(2026, 11, 26)));
+        assert!(holidays.windows(2).all(|pair| pair[0].0 <= pair[1].0));
+    }
+
+    #[test]
+    fn holiday_addresses_name_a_known_country() {
+        assert_eq!(holiday_country_for_url("HOLIDAYS:de").unwrap().code, "DE");
+        assert!(holiday_country_for_url("holidays:xx").is_none());
+        assert!(holiday_country_for_url("https://example.com/holidays:de").is_none());
+    }
+
+    #[test]
+    fn generated_events_have_stable_unique_uids() {
+        let russia = find_holiday_country("RU").unwrap();
+        let events = holiday_events(russia, [2026, 2027]);
+        let mut uids: Vec<_> = events.iter().map(|event| event.uid.as_str()).collect();
+        uids.sort_unstable();
+        uids.dedup();
+
+        assert_eq!(uids.len(), events.len());
+        let again = holiday_events(russia, [2026, 2027]);
+        assert!(
+            events
+                .iter()
+                .zip(&again)
+                .all(|(first, second)| first.uid == second.uid && first.etag == second.etag)
+        );
+        assert_eq!(
+            events[0].timing,
+            EventTiming::AllDay {
+                start_date: date(2026, 1, 1),
+                end_date: date(2026, 1, 9),
+            }
+        );
+    }
+}
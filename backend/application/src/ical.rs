@@ -2,11 +2,21 @@
 //!
 //! Converts between application calendar data and iCalendar (RFC 5545) format
 
+use std::io::Cursor;
+
 use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use ical::IcalParser;
 use ical::parser::ical::component::IcalEvent;
-use televent_domain::{EventStatus, EventTiming, ParticipationStatus};
+use ical::property::Property;
+use televent_domain::tags::event_tags;
+use televent_domain::{EventStatus, EventTiming, EventVisibility, ParticipationStatus};
 
 use crate::ApplicationError;
+use crate::ical_quirks::{local_to_utc, normalize_line_endings, resolve_tzid};
+
+/// When the alarm carrying an event's reminder note goes off
+pub const REMINDER_NOTE_TRIGGER: &str = "-PT15M";
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct IcalEventRender {
@@ -17,6 +27,10 @@ pub struct IcalEventRender {
     pub timing: EventTiming,
     pub status: EventStatus,
     pub rrule: Option<String>,
+    pub visibility: EventVisibility,
+    /// Exported as the text of an alarm [`REMINDER_NOTE_TRIGGER`] before the
+    /// start
+    pub reminder_note: Option<String>,
     pub sequence: i32,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
@@ -135,20 +149,7 @@ fn write_vevent(
     }
 
     // Start and end times
-    match &event.timing {
-        EventTiming::AllDay {
-            start_date,
-            end_date,
-        } => {
-            // All-day events use DATE format (no time component)
-            writer.write_date_property("DTSTART;VALUE=DATE", start_date)?;
-            writer.write_date_property("DTEND;VALUE=DATE", end_date)?;
-        }
-        EventTiming::Timed { start, end, .. } => {
-            writer.write_datetime_property("DTSTART", start)?;
-            writer.write_datetime_property("DTEND", end)?;
-        }
-    }
+    write_timing(writer, &event.timing)?;
 
     // Status
     let status_str = match event.status {
@@ -159,6 +160,18 @@ fn write_vevent(
     // Optimization: Status strings are short and safe
     writer.write_safe_property("STATUS", status_str)?;
 
+    // Class; public is the RFC 5545 default, so it is left out
+    if event.visibility != EventVisibility::Public {
+        writer.write_safe_property("CLASS", event.visibility.ical_class())?;
+    }
+
+    // Categories from the title's hashtags; tags hold no commas or
+    // backslashes, so the list needs no escaping
+    let tags = event_tags(&event.summary);
+    if !tags.is_empty() {
+        writer.write_property_no_escape("CATEGORIES", &tags.join(","))?;
+    }
+
     // Attendees
     for attendee in attendees {
         let partstat = match attendee.status {
@@ -189,31 +202,72 @@ fn write_vevent(
     // Last-Modified
     writer.write_datetime_property("LAST-MODIFIED", &event.updated_at)?;
 
+    // Only events with a note get an alarm, so clients keep their own
+    // defaults for the rest
+    if let Some(ref note) = event.reminder_note {
+        writer.write_line("BEGIN:VALARM")?;
+        writer.write_safe_property("ACTION", "DISPLAY")?;
+        writer.write_safe_property("TRIGGER", REMINDER_NOTE_TRIGGER)?;
+        writer.write_property("DESCRIPTION", &format!("{}: {note}", event.summary))?;
+        writer.write_line("END:VALARM")?;
+    }
+
     writer.write_line("END:VEVENT")?;
 
     Ok(())
 }
 
-struct FoldedWriter<'a> {
+pub(crate) fn write_timing(
+    writer: &mut FoldedWriter<'_>,
+    timing: &EventTiming,
+) -> Result<(), ApplicationError> {
+    match timing {
+        EventTiming::AllDay {
+            start_date,
+            end_date,
+        } => {
+            // All-day events use DATE format (no time component)
+            writer.write_date_property("DTSTART;VALUE=DATE", start_date)?;
+            writer.write_date_property("DTEND;VALUE=DATE", end_date)?;
+        }
+        EventTiming::Timed { start, end, .. } => {
+            writer.write_datetime_property("DTSTART", start)?;
+            writer.write_datetime_property("DTEND", end)?;
+        }
+        EventTiming::Floating { start, end } => {
+            // Floating times carry neither a Z suffix nor a TZID
+            writer.write_floating_datetime_property("DTSTART", start)?;
+            writer.write_floating_datetime_property("DTEND", end)?;
+        }
+    }
+
+    Ok(())
+}
+
+pub(crate) struct FoldedWriter<'a> {
     buf: &'a mut String,
 }
 
 impl<'a> FoldedWriter<'a> {
-    fn new(buf: &'a mut String) -> Self {
+    pub(crate) fn new(buf: &'a mut String) -> Self {
         Self { buf }
     }
 
-    fn write_line(&mut self, line: &str) -> Result<(), ApplicationError> {
+    pub(crate) fn write_line(&mut self, line: &str) -> Result<(), ApplicationError> {
         self.buf.push_str(line);
         self.buf.push_str("\r\n");
         Ok(())
     }
 
-    fn write_property(&mut self, name: &str, value: &str) -> Result<(), ApplicationError> {
+    pub(crate) fn write_property(
+        &mut self,
+        name: &str,
+        value: &str,
+    ) -> Result<(), ApplicationError> {
         self.write_property_impl(name, value, true)
     }
 
-    fn write_property_no_escape(
+    pub(crate) fn write_property_no_escape(
         &mut self,
         name: &str,
         value: &str,
@@ -221,7 +275,11 @@ impl<'a> FoldedWriter<'a> {
         self.write_property_impl(name, value, false)
     }
 
-    fn write_safe_property(&mut self, name: &str, value: &str) -> Result<(), ApplicationError> {
+    pub(crate) fn write_safe_property(
+        &mut self,
+        name: &str,
+        value: &str,
+    ) -> Result<(), ApplicationError> {
         // Optimization: Write directly to buffer without escaping or folding checks.
         // Use ONLY for values known to be safe (no control chars) and short enough to fit on a line.
         self.buf.push_str(name);
@@ -231,7 +289,7 @@ impl<'a> FoldedWriter<'a> {
         Ok(())
     }
 
-    fn write_int_property<T: std::fmt::Display>(
+    pub(crate) fn write_int_property<T: std::fmt::Display>(
         &mut self,
         name: &str,
         value: T,
@@ -248,7 +306,7 @@ impl<'a> FoldedWriter<'a> {
         Ok(())
     }
 
-    fn write_datetime_property(
+    pub(crate) fn write_datetime_property(
         &mut self,
         name: &str,
         datetime: &DateTime<Utc>,
@@ -269,6 +327,22 @@ impl<'a> FoldedWriter<'a> {
         Ok(())
     }
 
+    fn write_floating_datetime_property(
+        &mut self,
+        name: &str,
+        datetime: &chrono::NaiveDateTime,
+    ) -> Result<(), ApplicationError> {
+        self.buf.push_str(name);
+        self.buf.push(':');
+
+        use std::fmt::Write;
+        write!(self.buf, "{}", datetime.format("%Y%m%dT%H%M%S"))
+            .map_err(|e| ApplicationError::Internal(format!("Format error: {}", e)))?;
+
+        self.buf.push_str("\r\n");
+        Ok(())
+    }
+
     fn write_date_property(
         &mut self,
         name: &str,
@@ -292,6 +366,7 @@ impl<'a> FoldedWriter<'a> {
         value: &str,
         escape: bool,
     ) -> Result<(), ApplicationError> {
+        let mut line_start = self.buf.len();
         self.buf.push_str(name);
         self.buf.push(':');
 
@@ -321,8 +396,7 @@ impl<'a> FoldedWriter<'a> {
                 for rc in s.chars() {
                     let len = rc.len_utf8();
                     if current_line_len + len > 75 {
-                        self.buf.push_str("\r\n "); // Fold: CRLF + space
-                        current_line_len = 1;
+                        current_line_len = self.fold(&mut line_start);
                     }
                     self.buf.push(rc);
                     current_line_len += len;
@@ -330,8 +404,7 @@ impl<'a> FoldedWriter<'a> {
             } else {
                 let len = c.len_utf8();
                 if current_line_len + len > 75 {
-                    self.buf.push_str("\r\n "); // Fold: CRLF + space
-                    current_line_len = 1;
+                    current_line_len = self.fold(&mut line_start);
                 }
                 self.buf.push(c);
                 current_line_len += len;
@@ -340,11 +413,42 @@ impl<'a> FoldedWriter<'a> {
         self.buf.push_str("\r\n");
         Ok(())
     }
+
+    /// Fold: CRLF + space, returning the length of the new line
+    ///
+    /// The parser trims whitespace off the end of every physical line, so
+    /// whitespace ending the line moves over to the new one. A line that is
+    /// all whitespace stays unfolded instead, however long it gets.
+    fn fold(&mut self, line_start: &mut usize) -> usize {
+        // Past the first byte: the name's, or the previous fold's space
+        let content_start = *line_start + 1;
+        let kept = self.buf[content_start..].trim_end().len();
+        if kept == 0 {
+            return self.buf.len() - *line_start;
+        }
+
+        let trailing = self.buf.split_off(content_start + kept);
+        self.buf.push_str("\r\n ");
+        *line_start = self.buf.len() - 1;
+        self.buf.push_str(&trailing);
+        1 + trailing.len()
+    }
+}
+
+/// Calendars in `input`, after smoothing over line-ending quirks
+pub fn calendar_parser(input: &str) -> IcalParser<Cursor<String>> {
+    IcalParser::new(Cursor::new(normalize_line_endings(input).into_owned()))
 }
 
 /// Parse iCalendar format into event data using ical crate
 ///
-/// Returns (uid, summary, description, location, start, end, is_all_day, rrule, status, timezone)
+/// Returns (uid, summary, description, location, start, end, is_all_day, is_floating, rrule, status, timezone)
+///
+/// Floating DATE-TIMEs (no `Z` and no `TZID`) come back as their wall-clock
+/// time read as UTC, with `is_floating` set. A `TZID` may be an IANA name, a
+/// Windows name as Outlook writes it, or a vendor-prefixed IANA name; times
+/// in it are converted to UTC and `timezone` is the IANA name. A `TZID` that
+/// can't be resolved is returned as is, with its times read as UTC.
 #[allow(clippy::type_complexity)]
 pub fn ical_to_event_data(
     event: &IcalEvent,
@@ -357,6 +461,7 @@ pub fn ical_to_event_data(
         DateTime<Utc>,
         DateTime<Utc>,
         bool,
+        bool,
         Option<String>,
         EventStatus,
         String,
@@ -370,6 +475,9 @@ pub fn ical_to_event_data(
     let mut dtstart = None;
     let mut dtend = None;
     let mut is_all_day = false;
+    let mut has_tzid = false;
+    let mut start_tz = None;
+    let mut end_tz = None;
     let mut rrule = None;
     let mut status = EventStatus::Confirmed;
     let mut timezone = "UTC".to_string();
@@ -382,7 +490,7 @@ pub fn ical_to_event_data(
         };
 
         match prop.name.as_str() {
-            "UID" => uid = Some(value.to_string()),
+            "UID" => uid = Some(unescape_text(value)),
             "SUMMARY" => summary = Some(unescape_text(value)),
             "DESCRIPTION" => description = Some(unescape_text(value)),
             "LOCATION" => location = Some(unescape_text(value)),
@@ -394,16 +502,18 @@ pub fn ical_to_event_data(
                         if key == "VALUE" && values.iter().any(|v| v == "DATE") {
                             is_all_day = true;
                         }
-                        if key == "TZID"
-                            && let Some(tzid) = values.first()
-                        {
-                            timezone = tzid.clone();
-                        }
                     }
                 }
+                if let Some(tzid) = tzid_param(prop) {
+                    start_tz = resolve_tzid(tzid);
+                    timezone =
+                        start_tz.map_or_else(|| tzid.to_string(), |tz| tz.name().to_string());
+                    has_tzid = true;
+                }
                 dtstart = Some(value.to_string());
             }
             "DTEND" => {
+                end_tz = tzid_param(prop).and_then(resolve_tzid);
                 dtend = Some(value.to_string());
             }
             "RRULE" => {
@@ -432,10 +542,12 @@ pub fn ical_to_event_data(
     let dtstart_str =
         dtstart.ok_or_else(|| ApplicationError::BadRequest("DTSTART is required".to_string()))?;
 
+    let is_floating = !is_all_day && !has_tzid && !dtstart_str.ends_with('Z');
+
     // Parse datetimes
-    let start = parse_datetime(&dtstart_str, is_all_day)?;
+    let start = parse_zoned_datetime(&dtstart_str, is_all_day, start_tz)?;
     let end = if let Some(dtend_str) = dtend {
-        parse_datetime(&dtend_str, is_all_day)?
+        parse_zoned_datetime(&dtend_str, is_all_day, end_tz.or(start_tz))?
     } else if is_all_day {
         start + chrono::Duration::days(1)
     } else {
@@ -451,12 +563,33 @@ pub fn ical_to_event_data(
         start,
         end,
         is_all_day,
+        is_floating,
         rrule,
         status,
         timezone,
     ))
 }
 
+/// Visibility from the event's `CLASS`; `None` when it has none
+pub fn ical_visibility(event: &IcalEvent) -> Option<EventVisibility> {
+    event
+        .properties
+        .iter()
+        .find(|prop| prop.name == "CLASS")
+        .and_then(|prop| prop.value.as_deref())
+        .map(EventVisibility::from_ical_class)
+}
+
+/// The event's `SEQUENCE`; `None` when it has none or it isn't a number
+pub fn ical_sequence(event: &IcalEvent) -> Option<i32> {
+    event
+        .properties
+        .iter()
+        .find(|prop| prop.name == "SEQUENCE")
+        .and_then(|prop| prop.value.as_deref())
+        .and_then(|value| value.trim().parse().ok())
+}
+
 /// Unescape iCalendar text
 fn unescape_text(s: &str) -> String {
     let bytes = s.as_bytes();
@@ -506,6 +639,30 @@ fn unescape_text(s: &str) -> String {
     }
 }
 
+fn tzid_param(prop: &Property) -> Option<&str> {
+    prop.params
+        .as_ref()?
+        .iter()
+        .find(|(key, _)| key == "TZID")
+        .and_then(|(_, values)| values.first())
+        .map(String::as_str)
+}
+
+/// Parse a datetime, reading a local DATE-TIME as wall-clock time in `tz`
+fn parse_zoned_datetime(
+    value: &str,
+    is_all_day: bool,
+    tz: Option<Tz>,
+) -> Result<DateTime<Utc>, ApplicationError> {
+    let datetime = parse_datetime(value, is_all_day)?;
+    match tz {
+        Some(tz) if !is_all_day && !value.ends_with('Z') => {
+            Ok(local_to_utc(tz, datetime.naive_utc()))
+        }
+        _ => Ok(datetime),
+    }
+}
+
 /// Parse a datetime string, handling both DATE and DATE-TIME formats
 fn parse_datetime(value: &str, is_all_day: bool) -> Result<DateTime<Utc>, ApplicationError> {
     if is_all_day {
@@ -538,12 +695,10 @@ fn parse_datetime(value: &str, is_all_day: bool) -> Result<DateTime<Utc>, Applic
 #[cfg(test)]
 mod tests {
     use super::*;
-    use ical::parser::ical::component::IcalEvent;
-    use ical::property::Property;
 
     // Helper to parse ICS string to IcalEvent
     fn parse_ics(ics: &str) -> IcalEvent {
-        let parser = ical::IcalParser::new(std::io::Cursor::new(ics));
+        let parser = calendar_parser(ics);
         let calendar = parser
             .into_iter()
             .next()
@@ -566,6 +721,8 @@ mod tests {
             },
             rrule: None,
             status: EventStatus::Confirmed,
+            visibility: EventVisibility::Public,
+            reminder_note: None,
             sequence: 1,
             created_at: now,
             updated_at: now,
@@ -681,6 +838,56 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_event_to_ical_class_roundtrip() {
+        let mut event = create_test_event();
+        assert!(!event_to_ical(&event, &[]).unwrap().contains("CLASS:"));
+        assert_eq!(
+            ical_visibility(&parse_ics(&event_to_ical(&event, &[]).unwrap())),
+            None
+        );
+
+        event.visibility = EventVisibility::BusyOnly;
+        let ical = event_to_ical(&event, &[]).unwrap();
+        assert!(ical.contains("CLASS:CONFIDENTIAL"));
+        assert_eq!(
+            ical_visibility(&parse_ics(&ical)),
+            Some(EventVisibility::BusyOnly)
+        );
+    }
+
+    #[test]
+    fn test_event_to_ical_categories_from_hashtags() {
+        let mut event = create_test_event();
+        assert!(!event_to_ical(&event, &[]).unwrap().contains("CATEGORIES"));
+
+        event.summary = "Sprint review #Work #team-a #work".to_string();
+        let ical = event_to_ical(&event, &[]).unwrap();
+        assert!(ical.contains("CATEGORIES:work,team-a\r\n"));
+        assert!(ical.contains("SUMMARY:Sprint review #Work #team-a #work\r\n"));
+    }
+
+    #[test]
+    fn test_event_to_ical_alarm_from_reminder_note() {
+        let mut event = create_test_event();
+        assert!(!event_to_ical(&event, &[]).unwrap().contains("VALARM"));
+
+        event.reminder_note = Some("Bring laptop, charger".to_string());
+        let ical = event_to_ical(&event, &[]).unwrap();
+        assert!(ical.contains(
+            "BEGIN:VALARM\r\n\
+             ACTION:DISPLAY\r\n\
+             TRIGGER:-PT15M\r\n\
+             DESCRIPTION:Test Event: Bring laptop\\, charger\r\n\
+             END:VALARM\r\n\
+             END:VEVENT"
+        ));
+
+        // The alarm's text isn't read back as the event's description
+        let (_, _, description, ..) = ical_to_event_data(&parse_ics(&ical)).unwrap();
+        assert_eq!(description, event.description);
+    }
+
     #[test]
     fn test_event_to_ical_with_attendees() {
         let event = create_test_event();
@@ -724,14 +931,26 @@ END:VEVENT
 END:VCALENDAR"#;
 
         let event = parse_ics(ical_str);
-        let (uid, summary, description, location, start, end, is_all_day, rrule, status, timezone) =
-            ical_to_event_data(&event).unwrap();
+        let (
+            uid,
+            summary,
+            description,
+            location,
+            start,
+            end,
+            is_all_day,
+            is_floating,
+            rrule,
+            status,
+            timezone,
+        ) = ical_to_event_data(&event).unwrap();
 
         assert_eq!(uid, "test-123");
         assert_eq!(summary, "Test Event");
         assert_eq!(description, Some("Test Description".to_string()));
         assert_eq!(location, Some("Test Location".to_string()));
         assert!(!is_all_day);
+        assert!(!is_floating);
         assert_eq!(rrule, None);
         assert_eq!(status, EventStatus::Confirmed);
         assert_eq!(timezone, "UTC");
@@ -749,7 +968,7 @@ END:VEVENT
 END:VCALENDAR"#;
 
         let event = parse_ics(ical_str);
-        let (uid, summary, _, _, _, _, _, _, _, _) = ical_to_event_data(&event).unwrap();
+        let (uid, summary, _, _, _, _, _, _, _, _, _) = ical_to_event_data(&event).unwrap();
 
         assert_eq!(uid, "minimal-event");
         assert_eq!(summary, "Untitled Event"); // Default summary
@@ -768,7 +987,7 @@ END:VEVENT
 END:VCALENDAR"#;
 
         let event = parse_ics(ical_str);
-        let (_, _, _, _, start, end, is_all_day, _, _, _) = ical_to_event_data(&event).unwrap();
+        let (_, _, _, _, start, end, is_all_day, _, _, _, _) = ical_to_event_data(&event).unwrap();
 
         assert!(is_all_day);
         assert_eq!(start.format("%Y%m%d").to_string(), "20240101");
@@ -787,7 +1006,7 @@ END:VEVENT
 END:VCALENDAR"#;
 
         let event = parse_ics(ical_str);
-        let (_, _, _, _, start, end, is_all_day, _, _, _) = ical_to_event_data(&event).unwrap();
+        let (_, _, _, _, start, end, is_all_day, _, _, _, _) = ical_to_event_data(&event).unwrap();
 
         assert!(is_all_day);
         assert_eq!(start.format("%Y%m%d").to_string(), "20240101");
@@ -808,7 +1027,7 @@ END:VEVENT
 END:VCALENDAR"#;
 
         let event = parse_ics(ical_str);
-        let (_, _, _, _, _, _, _, rrule, _, _) = ical_to_event_data(&event).unwrap();
+        let (_, _, _, _, _, _, _, _, rrule, _, _) = ical_to_event_data(&event).unwrap();
 
         assert_eq!(rrule, Some("FREQ=WEEKLY;BYDAY=MO".to_string()));
     }
@@ -821,7 +1040,7 @@ END:VCALENDAR"#;
 
         // Parse it back
         let ical_event = parse_ics(&ical_str);
-        let (uid, summary, description, location, _, _, _, _, status, _) =
+        let (uid, summary, description, location, _, _, _, _, _, status, _) =
             ical_to_event_data(&ical_event).unwrap();
 
         assert_eq!(uid, event.uid);
@@ -844,9 +1063,122 @@ END:VEVENT
 END:VCALENDAR"#;
 
         let event = parse_ics(ical_str);
-        let (_, _, _, _, _, _, _, _, _, timezone) = ical_to_event_data(&event).unwrap();
+        let (_, _, _, _, start, end, _, is_floating, _, _, timezone) =
+            ical_to_event_data(&event).unwrap();
 
         assert_eq!(timezone, "America/New_York");
+        assert!(!is_floating);
+        assert_eq!(start.to_rfc3339(), "2024-01-01T15:00:00+00:00");
+        assert_eq!(end.to_rfc3339(), "2024-01-01T16:00:00+00:00");
+    }
+
+    #[test]
+    fn test_outlook_fixture() {
+        // CRLF with a byte order mark, a Windows TZID, a floating DTSTAMP and
+        // a pile of X-MICROSOFT-* properties
+        let event = parse_ics(include_str!(
+            "../tests/fixtures/ical/outlook_teams_meeting.ics"
+        ));
+        let (
+            uid,
+            summary,
+            description,
+            location,
+            start,
+            end,
+            is_all_day,
+            is_floating,
+            _,
+            status,
+            timezone,
+        ) = ical_to_event_data(&event).unwrap();
+
+        assert!(uid.starts_with("040000008200E00074C5B7101A82E008"));
+        assert_eq!(summary, "Quarterly planning");
+        assert!(description.unwrap().starts_with("Agenda:\n- Q3 numbers"));
+        assert_eq!(location.as_deref(), Some("Microsoft Teams Meeting"));
+        assert_eq!(timezone, "Europe/Berlin");
+        assert!(!is_all_day);
+        assert!(!is_floating);
+        // 10:00 in Berlin during summer time
+        assert_eq!(start.to_rfc3339(), "2024-10-15T08:00:00+00:00");
+        assert_eq!(end.to_rfc3339(), "2024-10-15T09:30:00+00:00");
+        assert_eq!(status, EventStatus::Confirmed);
+        assert_eq!(ical_visibility(&event), Some(EventVisibility::Public));
+    }
+
+    #[test]
+    fn test_google_fixture_with_bare_cr_line_endings() {
+        let event = parse_ics(include_str!(
+            "../tests/fixtures/ical/google_recurring_event.ics"
+        ));
+        let (uid, summary, _, _, start, end, _, _, rrule, _, timezone) =
+            ical_to_event_data(&event).unwrap();
+
+        assert_eq!(uid, "7kukuqrfedlm2f9t0vr5ilvfsl@google.com");
+        assert_eq!(summary, "Weekly standup");
+        assert_eq!(rrule.as_deref(), Some("FREQ=WEEKLY;BYDAY=MO"));
+        assert_eq!(timezone, "America/Los_Angeles");
+        // The day after clocks went back: 09:00 PST
+        assert_eq!(start.to_rfc3339(), "2024-11-04T17:00:00+00:00");
+        assert_eq!(end.to_rfc3339(), "2024-11-04T17:30:00+00:00");
+    }
+
+    #[test]
+    fn test_ical_to_event_data_tzid_quirks() {
+        let event = parse_ics(
+            "BEGIN:VCALENDAR\nBEGIN:VEVENT\nUID:prefixed\n\
+             DTSTART;TZID=/mozilla.org/20070129_1/Europe/London:20240701T090000\n\
+             DTEND;TZID=\"Romance Standard Time\":20240701T110000\n\
+             END:VEVENT\nEND:VCALENDAR\n",
+        );
+        let (_, _, _, _, start, end, _, _, _, _, timezone) = ical_to_event_data(&event).unwrap();
+        assert_eq!(timezone, "Europe/London");
+        assert_eq!(start.to_rfc3339(), "2024-07-01T08:00:00+00:00");
+        assert_eq!(end.to_rfc3339(), "2024-07-01T09:00:00+00:00");
+
+        // An unknown zone keeps its name and reads the time as UTC
+        let event = parse_ics(
+            "BEGIN:VCALENDAR\nBEGIN:VEVENT\nUID:custom\n\
+             DTSTART;TZID=Customized Time Zone:20240701T090000\n\
+             END:VEVENT\nEND:VCALENDAR\n",
+        );
+        let (_, _, _, _, start, _, _, is_floating, _, _, timezone) =
+            ical_to_event_data(&event).unwrap();
+        assert_eq!(timezone, "Customized Time Zone");
+        assert!(!is_floating);
+        assert_eq!(start.to_rfc3339(), "2024-07-01T09:00:00+00:00");
+    }
+
+    #[test]
+    fn test_floating_datetime_roundtrip() {
+        let ical_str = r#"BEGIN:VCALENDAR
+VERSION:2.0
+BEGIN:VEVENT
+UID:countdown
+SUMMARY:New Year countdown
+DTSTART:20261231T235000
+DTEND:20270101T001000
+END:VEVENT
+END:VCALENDAR"#;
+
+        let event = parse_ics(ical_str);
+        let (_, _, _, _, start, end, is_all_day, is_floating, _, _, _) =
+            ical_to_event_data(&event).unwrap();
+        assert!(!is_all_day);
+        assert!(is_floating);
+        assert_eq!(start.naive_utc().to_string(), "2026-12-31 23:50:00");
+        assert_eq!(end.naive_utc().to_string(), "2027-01-01 00:10:00");
+
+        let mut event = create_test_event();
+        event.timing = EventTiming::Floating {
+            start: start.naive_utc(),
+            end: end.naive_utc(),
+        };
+        let ical = event_to_ical(&event, &[]).unwrap();
+        assert!(ical.contains("DTSTART:20261231T235000\r\n"));
+        assert!(ical.contains("DTEND:20270101T001000\r\n"));
+        assert!(!ical.contains("TZID"));
     }
 
     #[test]
@@ -873,11 +1205,25 @@ END:VCALENDAR"#;
 
         // Parse it back
         let ical_event = parse_ics(&ical_str);
-        let (_, summary, _, _, _, _, _, _, _, _) = ical_to_event_data(&ical_event).unwrap();
+        let (_, summary, _, _, _, _, _, _, _, _, _) = ical_to_event_data(&ical_event).unwrap();
 
         assert_eq!(summary, event.summary);
     }
 
+    #[test]
+    fn test_folding_keeps_whitespace_at_fold() {
+        let mut event = create_test_event();
+        // Spaces and a tab where the summary line reaches 75 octets
+        event.summary = format!("{} \t  tail", "x".repeat(64));
+
+        let ical_str = event_to_ical(&event, &[]).unwrap();
+        assert!(ical_str.contains("\r\n  \t  tail"));
+
+        let ical_event = parse_ics(&ical_str);
+        let (_, summary, _, _, _, _, _, _, _, _, _) = ical_to_event_data(&ical_event).unwrap();
+        assert_eq!(summary, event.summary);
+    }
+
     #[test]
     fn test_unescape_text_edge_cases() {
         // Simple case
@@ -994,7 +1340,7 @@ END:VCALENDAR"#;
             alarms: vec![],
         };
 
-        let (_, summary, _, _, _, _, _, _, _, _) = ical_to_event_data(&event).unwrap();
+        let (_, summary, _, _, _, _, _, _, _, _, _) = ical_to_event_data(&event).unwrap();
 
         // Should be sanitized (stripped CR)
         assert_eq!(summary, "BadSummary");
@@ -0,0 +1,193 @@
+//! iTIP (RFC 5546) messages for guests who aren't on Telegram.
+//!
+//! Televent only ever speaks as the organizer: a `REQUEST` carries the new
+//! state of an event a guest was invited to, a `CANCEL` calls it off. The
+//! event's stored `SEQUENCE` goes out with them, and edits only ever raise it,
+//! so a guest's calendar applies the messages in order and ignores stale
+//! ones.
+
+use chrono::Utc;
+use televent_domain::{EventStatus, ParticipationStatus};
+
+use crate::ApplicationError;
+use crate::ical::{FoldedWriter, IcalAttendeeRender, IcalEventRender, write_timing};
+
+/// What happened to the event, which decides the iTIP method and what the
+/// guest is asked to do
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ItipChange {
+    /// The time moved; earlier answers no longer hold
+    Rescheduled,
+    /// Something other than the time changed, e.g. the location
+    Updated,
+    Cancelled,
+}
+
+impl ItipChange {
+    #[must_use]
+    pub const fn method(self) -> &'static str {
+        match self {
+            Self::Rescheduled | Self::Updated => "REQUEST",
+            Self::Cancelled => "CANCEL",
+        }
+    }
+
+    /// PARTSTAT to send for the guest and whether a reply is expected
+    const fn participation(self, current: ParticipationStatus) -> (ParticipationStatus, bool) {
+        match self {
+            Self::Rescheduled => (ParticipationStatus::NeedsAction, true),
+            Self::Updated | Self::Cancelled => (current, false),
+        }
+    }
+}
+
+/// Render the iTIP object telling `attendee` about `change`
+pub fn build_itip(
+    change: ItipChange,
+    event: &IcalEventRender,
+    organizer_email: &str,
+    attendee: &IcalAttendeeRender,
+) -> Result<String, ApplicationError> {
+    let mut buf = String::with_capacity(512);
+    let mut writer = FoldedWriter::new(&mut buf);
+    writer.write_line("BEGIN:VCALENDAR")?;
+    writer.write_line("VERSION:2.0")?;
+    writer.write_line("PRODID:-//Televent//Televent//EN")?;
+    writer.write_safe_property("METHOD", change.method())?;
+    writer.write_line("BEGIN:VEVENT")?;
+    writer.write_property("UID", &event.uid)?;
+    writer.write_datetime_property("DTSTAMP", &Utc::now())?;
+    writer.write_int_property("SEQUENCE", event.sequence)?;
+    writer.write_property("ORGANIZER", &format!("mailto:{organizer_email}"))?;
+
+    let attendee_email = format!("mailto:{}", attendee.email);
+    if change == ItipChange::Cancelled {
+        writer.write_property("ATTENDEE", &attendee_email)?;
+    } else {
+        let (partstat, rsvp) = change.participation(attendee.status);
+        writer.write_property(
+            &format!(
+                "ATTENDEE;PARTSTAT={};RSVP={}",
+                partstat.as_sql(),
+                if rsvp { "TRUE" } else { "FALSE" }
+            ),
+            &attendee_email,
+        )?;
+    }
+
+    writer.write_property("SUMMARY", &event.summary)?;
+    if change != ItipChange::Cancelled {
+        if let Some(ref description) = event.description {
+            writer.write_property("DESCRIPTION", description)?;
+        }
+        if let Some(ref location) = event.location {
+            writer.write_property("LOCATION", location)?;
+        }
+    }
+    write_timing(&mut writer, &event.timing)?;
+    if let Some(ref rrule) = event.rrule {
+        writer.write_property_no_escape("RRULE", rrule)?;
+    }
+    let status = match (change, event.status) {
+        (ItipChange::Cancelled, _) | (_, EventStatus::Cancelled) => "CANCELLED",
+        (_, EventStatus::Tentative) => "TENTATIVE",
+        (_, EventStatus::Confirmed) => "CONFIRMED",
+    };
+    writer.write_safe_property("STATUS", status)?;
+    writer.write_line("END:VEVENT")?;
+    writer.write_line("END:VCALENDAR")?;
+
+    Ok(buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use televent_domain::{EventTiming, EventVisibility, Timezone};
+
+    fn event() -> IcalEventRender {
+        let now = Utc::now();
+        IcalEventRender {
+            uid: "offsite-1".to_string(),
+            summary: "Offsite".to_string(),
+            description: None,
+            location: Some("Harbour Hall".to_string()),
+            timing: EventTiming::Timed {
+                start: now,
+                end: now + chrono::Duration::hours(1),
+                timezone: Timezone::utc(),
+            },
+            status: EventStatus::Confirmed,
+            rrule: None,
+            visibility: EventVisibility::Public,
+            reminder_note: None,
+            sequence: 4,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    fn guest() -> IcalAttendeeRender {
+        IcalAttendeeRender {
+            email: "guest@example.com".to_string(),
+            status: ParticipationStatus::Accepted,
+        }
+    }
+
+    #[test]
+    fn test_method_sequence_and_partstat_follow_change() {
+        let moved = build_itip(
+            ItipChange::Rescheduled,
+            &event(),
+            "tg_1@televent.internal",
+            &guest(),
+        )
+        .unwrap();
+        assert!(moved.contains("METHOD:REQUEST"));
+        assert!(moved.contains("SEQUENCE:4"));
+        assert!(
+            moved.contains("ATTENDEE;PARTSTAT=NEEDS-ACTION;RSVP=TRUE:mailto:guest@example.com")
+        );
+        assert!(moved.contains("LOCATION:Harbour Hall"));
+        assert!(moved.contains("STATUS:CONFIRMED"));
+
+        let updated = build_itip(
+            ItipChange::Updated,
+            &event(),
+            "tg_1@televent.internal",
+            &guest(),
+        )
+        .unwrap();
+        assert!(updated.contains("ATTENDEE;PARTSTAT=ACCEPTED;RSVP=FALSE:mailto:guest@example.com"));
+
+        let cancelled = build_itip(
+            ItipChange::Cancelled,
+            &event(),
+            "tg_1@televent.internal",
+            &guest(),
+        )
+        .unwrap();
+        assert!(cancelled.contains("METHOD:CANCEL"));
+        assert!(cancelled.contains("ORGANIZER:mailto:tg_1@televent.internal"));
+        assert!(cancelled.contains("ATTENDEE:mailto:guest@example.com"));
+        assert!(cancelled.contains("STATUS:CANCELLED"));
+        assert!(!cancelled.contains("LOCATION"));
+    }
+
+    #[test]
+    fn test_itip_parses_back_with_uid() {
+        let itip = build_itip(
+            ItipChange::Updated,
+            &event(),
+            "tg_1@televent.internal",
+            &guest(),
+        )
+        .unwrap();
+        let calendar = ical::IcalParser::new(std::io::Cursor::new(itip))
+            .next()
+            .unwrap()
+            .unwrap();
+        let (uid, ..) = crate::ical::ical_to_event_data(&calendar.events[0]).unwrap();
+        assert_eq!(uid, "offsite-1");
+    }
+}
@@ -1,28 +1,151 @@
 //! Application use cases and transaction boundaries for Televent.
 
+mod analytics;
+mod availability;
+mod birthdays;
+mod booking;
+mod chat_webhook;
+mod contact;
+mod delegation;
 mod device;
+mod domain_events;
+mod email;
+mod event;
+mod flags;
+mod focus;
+mod google;
+mod grid;
 mod health;
+mod holidays;
 pub mod ical;
-
+mod ical_quirks;
+pub mod itip;
+mod notification;
+mod resource;
+mod scheduled;
+mod snooze;
+mod stats;
+mod subscription;
+mod task;
+mod timezone_fix;
+pub mod vcard;
+mod web_push;
+
+pub use analytics::{
+    Analytics, AnalyticsSink, DEFAULT_STATSD_PREFIX, EventFunnelStep, JobOutcome, Metric,
+    PostgresAnalyticsSink, SinkFuture, StatsdAnalyticsSink,
+};
+pub use availability::{
+    BusyInterval, DEFAULT_SLOT_COUNT, DEFAULT_SLOT_SEARCH_DAYS, FreeBusy, FreeSlot, MAX_SLOT_COUNT,
+    MAX_SLOT_SEARCH_DAYS, SlotSearch, parse_duration_spec,
+};
+pub use birthdays::{
+    BIRTHDAY_DIGEST_HOUR, BIRTHDAYS_CALENDAR_NAME, BIRTHDAYS_URL, is_birthdays_url,
+};
+pub use booking::{
+    BookSlotCommand, BookingLinkView, CreateBookingLinkCommand, MAX_BOOKING_DAYS,
+    MAX_BOOKING_LINKS_PER_USER, MAX_BOOKING_MINUTES, MAX_BOOKING_TITLE_LENGTH, MIN_BOOKING_MINUTES,
+};
+pub use chat_webhook::{
+    AddChatWebhookCommand, ChatNotice, ChatWebhookService, ChatWebhookTarget, ChatWebhookView,
+    MAX_CHAT_WEBHOOKS_PER_USER, normalize_chat_webhook_url,
+};
+pub use contact::{
+    AttendeeSuggestion, ContactService, ContactView, DEFAULT_CONTACT_SEARCH_LIMIT,
+    MAX_CONTACT_SEARCH_LIMIT, MAX_CONTACTS_PER_USER, PutContactCommand, PutContactResult,
+    fuzzy_score,
+};
+pub use delegation::{DelegateView, EventRevisionView, MAX_DELEGATES_PER_USER, RevisionAction};
 pub use device::{
-    CreateDevicePasswordCommand, CreatedDevicePassword, DevicePasswordView, DeviceService,
-    PASSWORD_LEN, validate_device_name,
+    CreateDevicePasswordCommand, CreatedDevicePassword, DeviceActivity, DeviceActivityView,
+    DevicePasswordView, DeviceService, PASSWORD_LEN, PasswordHashParams, validate_device_name,
+};
+pub use domain_events::DomainEventBus;
+pub use email::{
+    EmailFeedback, EmailFeedbackKind, EmailFeedbackOutcome, EmailSendPermit, EmailService,
+    EmailVerificationKey, UnsubscribeKey,
+};
+pub use event::{
+    AttendeeCommand, ConfirmRsvpCommand, CreateEventCommand, DuplicateEventCommand, EditScope,
+    EventService, EventTextLimits, InviteAttendeeCommand, InviteAttendeesCommand,
+    InviteAttendeesResult, InviteeCommand, MAX_INVITEES_PER_REQUEST, MAX_SIGNUP_NAME_LENGTH,
+    PublicSignupCommand, PutEventCommand, PutEventResult, RemoveAttendeeCommand,
+    ResendInviteCommand, SequenceConflict, TextOverflow, UpdateEventCommand, validate_event_fields,
+};
+pub use flags::{
+    EXTERNAL_INVITES_FLAG, FeatureFlagService, FeatureFlagView, PutFeatureFlagCommand,
+    READ_ONLY_FLAG,
+};
+pub use focus::{
+    DEFAULT_FOCUS_TITLE, FocusBlockView, FocusRuleCommand, FocusRuleView, FocusSchedule,
+    MAX_FOCUS_BLOCKS_PER_WEEK, MAX_FOCUS_MINUTES, MAX_FOCUS_RULES_PER_USER, MIN_FOCUS_MINUTES,
+    focus_week, parse_focus_pattern, schedule_focus_time,
+};
+pub use google::{
+    ConnectGoogleCommand, EventLink, GOOGLE_OAUTH_STATE_TTL_MINUTES, GOOGLE_SYNC_INTERVAL_MINUTES,
+    GoogleConnectionView, GoogleSyncService, GoogleSyncState, LocalChanges, LocalEvent,
+    RemoteEvent, SyncWinner, resolve_sync_conflict,
+};
+pub use grid::{
+    GRID_EVENTS_PER_DAY, GRID_SUMMARY_CHARS, GridDay, GridEvent, MonthGrid, parse_month,
 };
 pub use health::HealthService;
+pub use holidays::{
+    HOLIDAYS_URL_PREFIX, HolidayCountry, find_holiday_country, holiday_countries,
+    holiday_country_for_url,
+};
+pub use itip::ItipChange;
+pub use notification::{
+    EMAIL_CODE_RESEND_SECS, EMAIL_CODE_TTL_MINUTES, EmailAddressView, MAX_EMAIL_CODE_ATTEMPTS,
+    MAX_SMS_CODE_ATTEMPTS, MAX_SMS_NOTICE_LENGTH, NotificationPreference, NotificationService,
+    PhoneNumberView, SMS_CODE_RESEND_SECS, SMS_CODE_TTL_MINUTES, sms_text,
+};
+pub use resource::{
+    MAX_RESOURCE_FREE_BUSY_DAYS, MAX_RESOURCE_NAME_LENGTH, ResourceBookingView, ResourceView,
+};
+pub use scheduled::{MAX_SCHEDULE_DELAY_DAYS, ScheduledMessageView, validate_send_at};
+pub use snooze::{SNOOZE_MORNING_HOUR, SnoozeDelay};
+pub use stats::{
+    CalendarStats, Collaborator, DEFAULT_STATS_DAYS, MAX_STATS_DAYS, TOP_COLLABORATORS,
+    WeekdayLoad, validate_stats_range, weekday_name,
+};
+pub use subscription::{
+    AddSubscriptionCommand, FeedApplied, FetchedFeed, MAX_SUBSCRIBED_EVENTS,
+    MAX_SUBSCRIPTIONS_PER_USER, SubscribedEventView, SubscriptionFetch, SubscriptionService,
+    SubscriptionView, is_public_ip, normalize_subscription_url,
+};
+pub use task::{
+    CreateTaskCommand, MAX_OPEN_TASKS_PER_USER, MAX_TASK_MINUTES, MIN_TASK_MINUTES, PlannedBlock,
+    TaskBlockView, TaskPlan, TaskView, plan_window,
+};
+pub use televent_domain::DomainEvent;
 pub use televent_domain::UserId;
+pub use televent_domain::UserRole;
+pub use televent_domain::{Clock, MockClock, SharedClock, SystemClock};
+pub use televent_domain::{DayHours, WorkingHours};
+pub use televent_domain::{DeviceId, OutboxKind, OutboxMessageId};
+pub use televent_domain::{NotificationChannel, NotificationTopic};
+pub use televent_storage::diagnostics::count_queries;
+pub use televent_storage::migrations::{MigrationStatus, PendingMigration};
+pub use timezone_fix::{MAX_TIMEZONE_FIX_EVENTS, TimezoneFixCommand, TimezoneFixSummary};
+pub use web_push::{
+    MAX_WEB_PUSH_SUBSCRIPTIONS_PER_USER, SubscribeWebPushCommand, WebPushService,
+    WebPushSubscriptionView, WebPushTarget, normalize_push_endpoint,
+};
 
-use chrono::{DateTime, NaiveDate, Utc};
-use std::collections::HashMap;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
 use televent_domain::{
-    AttendeeFingerprint, AttendeeRole, EventEtagInput, EventStatus, EventTiming,
-    ExternalEmailDeferred, InviteNotification, OutboxPayload, ParticipationStatus,
-    RsvpNotification, Timezone, compute_event_etag,
+    AttendeeRole, EventStatus, EventTiming, EventVisibility, INTERNAL_EMAIL_DOMAIN,
+    ParticipationStatus, Timezone, internal_email_for_telegram_id, validate_email_address,
 };
 use televent_storage::StorageError;
 use televent_storage::calendar::{
-    AttendeeDisplayRecord, AttendeeWrite, CalendarRepository, Event, EventAttendee, EventTombstone,
-    PendingInviteRecord, StoredEventUpdate, StoredEventWrite, User,
+    AttendeeDisplayRecord, CalendarRepository, Event, EventAttendee, EventTombstone,
+    PendingInviteRecord, SyncCollection, User,
 };
+use televent_storage::repos::{SharedEventsRepo, SharedUsersRepo};
 use thiserror::Error;
 use uuid::Uuid;
 
@@ -51,12 +174,48 @@ pub(crate) fn storage_error(err: StorageError) -> ApplicationError {
 #[derive(Clone)]
 pub struct CalendarService {
     calendar: CalendarRepository,
+    users: SharedUsersRepo,
+    events: SharedEventsRepo,
+    clock: SharedClock,
 }
 
 impl CalendarService {
     #[must_use]
     pub fn new(calendar: CalendarRepository) -> Self {
-        Self { calendar }
+        Self {
+            users: Arc::new(calendar.clone()),
+            events: Arc::new(calendar.clone()),
+            calendar,
+            clock: SystemClock::shared(),
+        }
+    }
+
+    /// Look users up in `users` instead of the calendar repository
+    #[must_use]
+    pub fn with_users(mut self, users: SharedUsersRepo) -> Self {
+        self.users = users;
+        self
+    }
+
+    /// Read events and attendees from `events` instead of the calendar
+    /// repository
+    #[must_use]
+    pub fn with_events(mut self, events: SharedEventsRepo) -> Self {
+        self.events = events;
+        self
+    }
+
+    /// Read the time from `clock` instead of the system clock
+    #[must_use]
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// The clock this service reads the time from
+    #[must_use]
+    pub fn clock(&self) -> &dyn Clock {
+        self.clock.as_ref()
     }
 
     pub async fn ensure_user_setup(
@@ -74,7 +233,7 @@ impl CalendarService {
         username: Option<&str>,
     ) -> Result<UserIdentity, ApplicationError> {
         let user = self
-            .calendar
+            .users
             .ensure_user(telegram_id, username)
             .await
             .map_err(storage_error)?;
@@ -82,14 +241,14 @@ impl CalendarService {
     }
 
     async fn get_user_by_id(&self, user_id: UserId) -> Result<Option<User>, ApplicationError> {
-        self.calendar
+        self.users
             .get_user_by_id(user_id)
             .await
             .map_err(storage_error)
     }
 
     async fn get_user_by_username(&self, username: &str) -> Result<Option<User>, ApplicationError> {
-        self.calendar
+        self.users
             .get_user_by_username(username)
             .await
             .map_err(storage_error)
@@ -112,6 +271,188 @@ impl CalendarService {
             .map(UserIdentity::from))
     }
 
+    /// Keep a user's stored username in step with Telegram, where it can
+    /// change or be dropped at any time; `true` when it changed
+    pub async fn refresh_username(
+        &self,
+        user_id: UserId,
+        username: Option<&str>,
+    ) -> Result<bool, ApplicationError> {
+        self.users
+            .refresh_username(user_id, username)
+            .await
+            .map_err(storage_error)
+    }
+
+    /// Change a user's role
+    pub async fn set_user_role(
+        &self,
+        user_id: UserId,
+        role: UserRole,
+    ) -> Result<UserIdentity, ApplicationError> {
+        self.users
+            .set_user_role(user_id, role)
+            .await
+            .map_err(storage_error)?
+            .map(UserIdentity::from)
+            .ok_or_else(|| ApplicationError::NotFound(format!("User not found: {user_id}")))
+    }
+
+    /// Change a user's timezone
+    pub async fn set_user_timezone(
+        &self,
+        user_id: UserId,
+        timezone: &Timezone,
+    ) -> Result<UserIdentity, ApplicationError> {
+        self.users
+            .set_user_timezone(user_id, timezone)
+            .await
+            .map_err(storage_error)?
+            .map(UserIdentity::from)
+            .ok_or_else(|| ApplicationError::NotFound(format!("User not found: {user_id}")))
+    }
+
+    /// Change the visibility new events of a user get
+    pub async fn set_default_event_visibility(
+        &self,
+        user_id: UserId,
+        visibility: EventVisibility,
+    ) -> Result<UserIdentity, ApplicationError> {
+        self.users
+            .set_default_event_visibility(user_id, visibility)
+            .await
+            .map_err(storage_error)?
+            .map(UserIdentity::from)
+            .ok_or_else(|| ApplicationError::NotFound(format!("User not found: {user_id}")))
+    }
+
+    /// The user's working week, read in their timezone; `None` when not set
+    pub async fn working_hours(
+        &self,
+        user_id: UserId,
+    ) -> Result<Option<WorkingHours>, ApplicationError> {
+        let Some(user) = self.get_user_by_id(user_id).await? else {
+            return Ok(None);
+        };
+        let spec = self
+            .calendar
+            .get_working_hours(user_id)
+            .await
+            .map_err(storage_error)?;
+        // Stored after parsing, so it always parses again
+        Ok(spec.and_then(|spec| WorkingHours::parse(&spec, &user.timezone).ok()))
+    }
+
+    /// Working hours given for one search, read in `timezone`, or else the
+    /// user's saved working week
+    pub async fn resolve_working_hours(
+        &self,
+        user_id: UserId,
+        timezone: &Timezone,
+        spec: Option<&str>,
+    ) -> Result<Option<WorkingHours>, ApplicationError> {
+        match spec {
+            Some(spec) => Ok(Some(WorkingHours::parse(spec, timezone)?)),
+            None => self.working_hours(user_id).await,
+        }
+    }
+
+    /// Save the user's working week, e.g. `Mon-Fri 09:00-17:00`, or clear it
+    /// with `None`
+    pub async fn set_working_hours(
+        &self,
+        user_id: UserId,
+        spec: Option<&str>,
+    ) -> Result<Option<WorkingHours>, ApplicationError> {
+        let user = self
+            .get_user_by_id(user_id)
+            .await?
+            .ok_or_else(|| ApplicationError::NotFound(format!("User not found: {user_id}")))?;
+        let hours = spec
+            .map(|spec| WorkingHours::parse(spec, &user.timezone))
+            .transpose()?;
+        let stored = hours.as_ref().map(ToString::to_string);
+        self.calendar
+            .set_working_hours(user_id, stored.as_deref())
+            .await
+            .map_err(storage_error)?;
+        Ok(hours)
+    }
+
+    /// Change or clear the email a user can be invited at.
+    ///
+    /// Invites sent to that address reach the user on Telegram, so each
+    /// address belongs to one account at most.
+    pub async fn set_user_email(
+        &self,
+        user_id: UserId,
+        email: Option<&str>,
+    ) -> Result<UserIdentity, ApplicationError> {
+        let email = email.map(str::trim).filter(|email| !email.is_empty());
+        if let Some(email) = email {
+            validate_email_address(email).map_err(ApplicationError::BadRequest)?;
+            if email
+                .to_ascii_lowercase()
+                .ends_with(&format!("@{INTERNAL_EMAIL_DOMAIN}"))
+            {
+                return Err(ApplicationError::BadRequest(
+                    "Use your own email address".to_string(),
+                ));
+            }
+            let owner = self
+                .users
+                .get_user_by_email(email)
+                .await
+                .map_err(storage_error)?;
+            if owner.is_some_and(|owner| owner.id != user_id) {
+                return Err(ApplicationError::Conflict(
+                    "This email is already used by another account".to_string(),
+                ));
+            }
+        }
+
+        self.users
+            .set_user_email(user_id, email)
+            .await
+            .map_err(storage_error)?
+            .map(UserIdentity::from)
+            .ok_or_else(|| ApplicationError::NotFound(format!("User not found: {user_id}")))
+    }
+
+    /// The user who gave `email` during onboarding, if any
+    pub async fn get_user_identity_by_email(
+        &self,
+        email: &str,
+    ) -> Result<Option<UserIdentity>, ApplicationError> {
+        Ok(self
+            .users
+            .get_user_by_email(email.trim())
+            .await
+            .map_err(storage_error)?
+            .map(UserIdentity::from))
+    }
+
+    /// Mark the user onboarded; `true` only the first time, when the
+    /// onboarding questions should be shown
+    pub async fn begin_onboarding(&self, user_id: UserId) -> Result<bool, ApplicationError> {
+        self.users
+            .mark_user_onboarded(user_id)
+            .await
+            .map_err(storage_error)
+    }
+
+    /// Promote the configured bootstrap admins, creating the users that
+    /// haven't talked to the bot yet
+    pub async fn bootstrap_admins(&self, telegram_ids: &[i64]) -> Result<u64, ApplicationError> {
+        if telegram_ids.is_empty() {
+            return Ok(0);
+        }
+        self.users
+            .promote_admins(telegram_ids)
+            .await
+            .map_err(storage_error)
+    }
+
     pub async fn resolve_caldav_user(
         &self,
         identifier: &str,
@@ -126,7 +467,7 @@ impl CalendarService {
     }
 
     async fn get_event(&self, user_id: UserId, event_id: Uuid) -> Result<Event, ApplicationError> {
-        self.calendar
+        self.events
             .get_event_by_id(user_id, event_id)
             .await
             .map_err(storage_error)?
@@ -151,7 +492,7 @@ impl CalendarService {
     }
 
     async fn get_event_by_id_any(&self, event_id: Uuid) -> Result<Option<Event>, ApplicationError> {
-        self.calendar
+        self.events
             .get_event_by_id_any(event_id)
             .await
             .map_err(storage_error)
@@ -177,11 +518,79 @@ impl CalendarService {
             .transpose()
     }
 
+    /// Event published under `slug`, for its public signup page.
+    pub async fn get_public_event_view(&self, slug: &str) -> Result<EventView, ApplicationError> {
+        self.calendar
+            .get_event_by_public_slug(slug)
+            .await
+            .map_err(storage_error)?
+            .ok_or_else(|| ApplicationError::NotFound(slug.to_string()))
+            .and_then(EventView::try_from)
+    }
+
+    /// Mint a fresh confirmation link for a pending public signup.
+    ///
+    /// Called right before the email is sent so the plaintext token only ever
+    /// leaves in that email. Any earlier link for the signup stops working.
+    /// Returns `None` when there is nothing left to confirm.
+    pub async fn issue_signup_confirmation(
+        &self,
+        event_id: Uuid,
+        email: &str,
+    ) -> Result<Option<String>, ApplicationError> {
+        let token = device::generate_password(event::SIGNUP_TOKEN_LEN);
+        let slug = self
+            .calendar
+            .issue_public_signup_token(
+                event_id,
+                email,
+                &event::hash_signup_token(&token),
+                self.clock.now() + Duration::hours(event::SIGNUP_CONFIRMATION_TTL_HOURS),
+            )
+            .await
+            .map_err(storage_error)?;
+        Ok(slug.map(|slug| format!("/e/{slug}/confirm?token={token}")))
+    }
+
+    /// iTIP message telling one guest about `change`, rendered from the
+    /// event as it is now.
+    ///
+    /// Returns `None` when the event is gone, the address is no longer on
+    /// the guest list, or the event's status no longer matches the change
+    /// (reinstated after a cancel, or cancelled after an update).
+    pub async fn guest_itip(
+        &self,
+        event_id: Uuid,
+        attendee_email: &str,
+        change: ItipChange,
+    ) -> Result<Option<String>, ApplicationError> {
+        let Some(event) = self.get_event_by_id_any(event_id).await? else {
+            return Ok(None);
+        };
+        if (event.status == EventStatus::Cancelled) != (change == ItipChange::Cancelled) {
+            return Ok(None);
+        }
+        let attendees = self.get_event_attendees(event.id).await?;
+        let Some(attendee) = ical_attendees(&attendees)
+            .into_iter()
+            .find(|attendee| attendee.email.eq_ignore_ascii_case(attendee_email))
+        else {
+            return Ok(None);
+        };
+        crate::itip::build_itip(
+            change,
+            &ical_event_render_from_event(&event)?,
+            &internal_email_for_telegram_id(event.user_id.inner()),
+            &attendee,
+        )
+        .map(Some)
+    }
+
     async fn get_events_by_ids_any(
         &self,
         event_ids: &[Uuid],
     ) -> Result<Vec<Event>, ApplicationError> {
-        self.calendar
+        self.events
             .get_events_by_ids_any(event_ids)
             .await
             .map_err(storage_error)
@@ -205,7 +614,7 @@ impl CalendarService {
         end: Option<DateTime<Utc>>,
     ) -> Result<Vec<CalDavEventMetadata>, ApplicationError> {
         Ok(self
-            .list_events(user_id, start, end, None, None)
+            .list_events(user_id, start, end, &[], None, None, None)
             .await?
             .iter()
             .map(CalDavEventMetadata::from)
@@ -217,7 +626,7 @@ impl CalendarService {
         user_id: UserId,
         uid: &str,
     ) -> Result<Option<Event>, ApplicationError> {
-        self.calendar
+        self.events
             .get_event_by_uid(user_id, uid)
             .await
             .map_err(storage_error)
@@ -240,7 +649,7 @@ impl CalendarService {
         user_id: UserId,
         uids: &[&str],
     ) -> Result<Vec<Event>, ApplicationError> {
-        self.calendar
+        self.events
             .get_events_by_uids(user_id, uids)
             .await
             .map_err(storage_error)
@@ -250,7 +659,7 @@ impl CalendarService {
         &self,
         event_id: Uuid,
     ) -> Result<Vec<EventAttendee>, ApplicationError> {
-        self.calendar
+        self.events
             .get_event_attendees(event_id)
             .await
             .map_err(storage_error)
@@ -274,7 +683,7 @@ impl CalendarService {
         &self,
         event_ids: &[Uuid],
     ) -> Result<HashMap<Uuid, Vec<EventAttendee>>, ApplicationError> {
-        self.calendar
+        self.events
             .get_event_attendees_bulk(event_ids)
             .await
             .map_err(storage_error)
@@ -307,40 +716,247 @@ impl CalendarService {
             .collect()
     }
 
+    #[allow(clippy::too_many_arguments)]
     async fn list_events(
         &self,
         user_id: UserId,
         start: Option<DateTime<Utc>>,
         end: Option<DateTime<Utc>>,
+        statuses: &[EventStatus],
+        tag: Option<&str>,
         limit: Option<i64>,
         offset: Option<i64>,
     ) -> Result<Vec<Event>, ApplicationError> {
-        self.calendar
-            .list_events(user_id, start, end, limit, offset)
+        self.events
+            .list_events(user_id, start, end, statuses, tag, limit, offset)
             .await
             .map_err(storage_error)
     }
 
+    /// Events in start order, limited to `statuses` unless it is empty and
+    /// to those tagged `tag` when given
+    #[allow(clippy::too_many_arguments)]
     pub async fn list_event_views(
         &self,
         user_id: UserId,
         start: Option<DateTime<Utc>>,
         end: Option<DateTime<Utc>>,
+        statuses: &[EventStatus],
+        tag: Option<&str>,
         limit: Option<i64>,
         offset: Option<i64>,
     ) -> Result<Vec<EventView>, ApplicationError> {
-        self.list_events(user_id, start, end, limit, offset)
+        self.list_events(user_id, start, end, statuses, tag, limit, offset)
             .await?
             .into_iter()
             .map(EventView::try_from)
             .collect()
     }
 
+    /// Events newest first, for polling integrations; with `since_id`, only
+    /// those created after that event
+    pub async fn list_new_event_views(
+        &self,
+        user_id: UserId,
+        since_id: Option<Uuid>,
+        limit: i64,
+    ) -> Result<Vec<CreatedEventView>, ApplicationError> {
+        self.calendar
+            .list_created_events(
+                user_id,
+                &[EventStatus::Confirmed, EventStatus::Tentative],
+                since_id,
+                limit,
+            )
+            .await
+            .map_err(storage_error)?
+            .into_iter()
+            .map(|event| {
+                let created_at = event.created_at;
+                Ok(CreatedEventView {
+                    event: EventView::try_from(event)?,
+                    created_at,
+                })
+            })
+            .collect()
+    }
+
+    /// Events starting within `window` of `now`, latest start first, for
+    /// polling integrations.
+    ///
+    /// With `since_id`, only events starting after that one. Once that event
+    /// has started it drops out of the window and nothing is filtered.
+    pub async fn list_upcoming_event_views(
+        &self,
+        user_id: UserId,
+        timezone: &Timezone,
+        now: DateTime<Utc>,
+        window: Duration,
+        since_id: Option<Uuid>,
+        limit: usize,
+    ) -> Result<Vec<EventView>, ApplicationError> {
+        let mut events = self
+            .list_event_views(
+                user_id,
+                Some(now),
+                Some(now + window),
+                &[EventStatus::Confirmed, EventStatus::Tentative],
+                None,
+                None,
+                None,
+            )
+            .await?;
+        let key = |event: &EventView| {
+            (
+                event.timing.pinned_to(timezone).start_for_display(),
+                event.id,
+            )
+        };
+        events.sort_by_key(|event| std::cmp::Reverse(key(event)));
+        if let Some(since) =
+            since_id.and_then(|id| events.iter().find(|event| event.id == id).map(key))
+        {
+            events.retain(|event| key(event) > since);
+        }
+        events.truncate(limit);
+
+        Ok(events)
+    }
+
+    /// Number of events the user created at or after `since`, whichever
+    /// way they were created
+    pub async fn count_events_created_since(
+        &self,
+        user_id: UserId,
+        since: DateTime<Utc>,
+    ) -> Result<i64, ApplicationError> {
+        self.calendar
+            .count_events_created_since(user_id, since)
+            .await
+            .map_err(storage_error)
+    }
+
+    /// Meeting load of the user's calendar in `[from, to)`, with weekdays
+    /// taken in `timezone`
+    pub async fn calendar_stats(
+        &self,
+        user_id: UserId,
+        timezone: &Timezone,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<CalendarStats, ApplicationError> {
+        validate_stats_range(from, to)?;
+        let loads = self
+            .calendar
+            .weekday_load(user_id, timezone.as_str(), from, to)
+            .await
+            .map_err(storage_error)?;
+        let collaborators = self
+            .calendar
+            .top_collaborators(user_id, from, to, TOP_COLLABORATORS)
+            .await
+            .map_err(storage_error)?;
+
+        Ok(CalendarStats::from_records(from, to, loads, collaborators))
+    }
+
+    /// The user's events bucketed into the days of `month` in `timezone`,
+    /// with recurrences expanded; cancelled events are left out.
+    ///
+    /// Every event is loaded, as a series that began long ago or a long
+    /// event can still fall into the month.
+    pub async fn month_grid(
+        &self,
+        user_id: UserId,
+        timezone: &Timezone,
+        month: NaiveDate,
+    ) -> Result<MonthGrid, ApplicationError> {
+        let events = self
+            .list_event_views(
+                user_id,
+                None,
+                None,
+                &[EventStatus::Confirmed, EventStatus::Tentative],
+                None,
+                None,
+                None,
+            )
+            .await?;
+
+        MonthGrid::build(month, timezone, &events)
+    }
+
+    /// Busy time from the user's own calendar inside a window.
+    ///
+    /// Every event is loaded rather than only those starting in the window,
+    /// since a recurring series that began long ago can still block it.
+    pub async fn free_busy(
+        &self,
+        user_id: UserId,
+        timezone: &Timezone,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<FreeBusy, ApplicationError> {
+        self.free_busy_excluding(user_id, timezone, start, end, &HashSet::new())
+            .await
+    }
+
+    /// Like [`Self::free_busy`], leaving out the events in `skip`
+    pub async fn free_busy_excluding(
+        &self,
+        user_id: UserId,
+        timezone: &Timezone,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        skip: &HashSet<Uuid>,
+    ) -> Result<FreeBusy, ApplicationError> {
+        let mut free_busy = FreeBusy::new(start, end, timezone);
+        for event in self
+            .list_events(user_id, None, None, &[], None, None, None)
+            .await?
+        {
+            if event.status == EventStatus::Cancelled || skip.contains(&event.id) {
+                continue;
+            }
+            free_busy.add_event(&timing_from_event(&event)?, event.rrule.as_deref())?;
+        }
+
+        Ok(free_busy)
+    }
+
+    /// A booking link and its owner's busy time over the days it opens,
+    /// from `now`; subscribed calendars are for the caller to mark.
+    pub async fn booking_free_busy(
+        &self,
+        slug: &str,
+        now: DateTime<Utc>,
+    ) -> Result<(BookingLinkView, FreeBusy), ApplicationError> {
+        let link: BookingLinkView = self
+            .calendar
+            .get_booking_link_by_slug(slug)
+            .await
+            .map_err(storage_error)?
+            .ok_or_else(|| ApplicationError::NotFound(slug.to_string()))?
+            .into();
+        let owner = self
+            .get_user_by_id(link.owner)
+            .await?
+            .ok_or_else(|| ApplicationError::NotFound(link.owner.to_string()))?;
+        let search = link.search(now, &owner.timezone);
+        let free_busy = self
+            .free_busy(link.owner, &owner.timezone, search.from, search.until())
+            .await?;
+
+        Ok((link, free_busy))
+    }
+
     pub async fn export_calendar_ical(
         &self,
         user_id: UserId,
     ) -> Result<CalendarIcalExport, ApplicationError> {
-        let events = self.list_events(user_id, None, None, None, None).await?;
+        let events = self
+            .list_events(user_id, None, None, &[], None, None, None)
+            .await?;
         let active_events = events
             .into_iter()
             .filter(|event| event.status != EventStatus::Cancelled)
@@ -375,28 +991,6 @@ impl CalendarService {
         })
     }
 
-    async fn list_events_since_sync(
-        &self,
-        user_id: UserId,
-        sync_token: i64,
-    ) -> Result<Vec<Event>, ApplicationError> {
-        self.calendar
-            .list_events_since_sync(user_id, sync_token)
-            .await
-            .map_err(storage_error)
-    }
-
-    async fn list_tombstones_since_sync(
-        &self,
-        user_id: UserId,
-        sync_token: i64,
-    ) -> Result<Vec<EventTombstone>, ApplicationError> {
-        self.calendar
-            .list_tombstones_since(user_id, sync_token)
-            .await
-            .map_err(storage_error)
-    }
-
     async fn list_events_with_attendees(
         &self,
         user_id: UserId,
@@ -404,8 +998,8 @@ impl CalendarService {
         end: Option<DateTime<Utc>>,
     ) -> Result<CalendarEventsWithAttendees, ApplicationError> {
         let events = self
-            .calendar
-            .list_events(user_id, start, end, None, None)
+            .events
+            .list_events(user_id, start, end, &[], None, None, None)
             .await
             .map_err(storage_error)?;
         self.attach_attendees(events).await
@@ -453,25 +1047,24 @@ impl CalendarService {
         sync_token: Option<&str>,
     ) -> Result<CalendarSyncChanges, ApplicationError> {
         let last_sync_token = parse_calendar_sync_token(sync_token);
-        let events = if last_sync_token == 0 {
-            self.list_events(user_id, None, None, None, None).await?
-        } else {
-            self.list_events_since_sync(user_id, last_sync_token)
-                .await?
-        };
-        let CalendarEventsWithAttendees {
+        let SyncCollection {
+            sync_token,
+            ctag,
             events,
             attendees_by_event,
-        } = self.attach_attendees(events).await?;
-        let tombstones = if last_sync_token == 0 {
-            Vec::new()
-        } else {
-            self.list_tombstones_since_sync(user_id, last_sync_token)
-                .await?
-        };
+            tombstones,
+            tombstones_pruned_through,
+        } = self
+            .calendar
+            .sync_collection(user_id, last_sync_token)
+            .await
+            .map_err(storage_error)?
+            .ok_or_else(|| ApplicationError::NotFound(format!("User not found: {user_id}")))?;
 
         Ok(CalendarSyncChanges {
             last_sync_token,
+            token_expired: last_sync_token != 0 && last_sync_token < tombstones_pruned_through,
+            calendar: CalDavCalendarState { sync_token, ctag },
             events,
             tombstones,
             attendees_by_event,
@@ -494,506 +1087,240 @@ impl CalendarService {
 
         Ok(CalDavSyncChanges {
             last_sync_token: sync_changes.last_sync_token,
+            token_expired: sync_changes.token_expired,
+            calendar: sync_changes.calendar,
             events,
             tombstones,
         })
     }
 
-    async fn attach_attendees(
-        &self,
-        events: Vec<Event>,
-    ) -> Result<CalendarEventsWithAttendees, ApplicationError> {
-        let event_ids: Vec<_> = events.iter().map(|event| event.id).collect();
-        let attendees_by_event = self.get_event_attendees_bulk(&event_ids).await?;
-        Ok(CalendarEventsWithAttendees {
-            events,
-            attendees_by_event,
-        })
-    }
-
-    async fn create_event(&self, command: CreateEventCommand) -> Result<Event, ApplicationError> {
-        command.timing.validate()?;
-
-        let mut tx = self.calendar.begin().await.map_err(storage_error)?;
-        tx.ensure_user(command.user_id.inner(), command.username.as_deref())
+    /// Every resource, by name
+    pub async fn list_resources(&self) -> Result<Vec<ResourceView>, ApplicationError> {
+        Ok(self
+            .calendar
+            .list_resources()
             .await
-            .map_err(storage_error)?;
+            .map_err(storage_error)?
+            .into_iter()
+            .map(ResourceView::from)
+            .collect())
+    }
 
-        let user_id = command.user_id;
-        let sync_version = tx
-            .bump_calendar_state(user_id)
-            .await
-            .map_err(storage_error)?;
-        let version = 1;
-        let etag = compute_event_etag(&EventEtagInput {
-            uid: command.uid.clone(),
-            summary: command.summary.clone(),
-            description: command.description.clone(),
-            location: command.location.clone(),
-            timing: command.timing.clone(),
-            status: command.status,
-            rrule: command.rrule.clone(),
-            version,
-            attendees: Vec::new(),
-        });
-
-        let event = tx
-            .insert_event(StoredEventWrite {
-                user_id,
-                uid: command.uid,
-                summary: command.summary,
-                description: command.description,
-                location: command.location,
-                timing: command.timing,
-                status: command.status,
-                rrule: command.rrule,
-                version,
-                sync_version,
-                etag,
-            })
+    pub async fn get_resource(
+        &self,
+        resource_id: Uuid,
+    ) -> Result<Option<ResourceView>, ApplicationError> {
+        Ok(self
+            .calendar
+            .get_resource(resource_id)
             .await
-            .map_err(storage_error)?;
-
-        tx.commit().await.map_err(storage_error)?;
-        Ok(event)
+            .map_err(storage_error)?
+            .map(ResourceView::from))
     }
 
-    pub async fn create_event_view(
+    /// Times the resource is booked in `[start, end)`, earliest first,
+    /// without the events holding it
+    pub async fn resource_free_busy(
         &self,
-        command: CreateEventCommand,
-    ) -> Result<EventView, ApplicationError> {
-        EventView::try_from(self.create_event(command).await?)
-    }
+        resource_id: Uuid,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<BusyInterval>, ApplicationError> {
+        if end <= start {
+            return Err(ApplicationError::BadRequest(
+                "end must be after start".to_string(),
+            ));
+        }
+        if end - start > Duration::days(MAX_RESOURCE_FREE_BUSY_DAYS) {
+            return Err(ApplicationError::BadRequest(format!(
+                "Free/busy window too long (max {MAX_RESOURCE_FREE_BUSY_DAYS} days)"
+            )));
+        }
+        if self.get_resource(resource_id).await?.is_none() {
+            return Err(ApplicationError::NotFound(format!(
+                "resource {resource_id}"
+            )));
+        }
 
-    async fn update_event(&self, command: UpdateEventCommand) -> Result<Event, ApplicationError> {
-        let mut tx = self.calendar.begin().await.map_err(storage_error)?;
-        let user_id = command.user_id;
-        let current = tx
-            .get_event_by_id(user_id, command.event_id)
+        Ok(self
+            .calendar
+            .list_resource_bookings(resource_id, Some(start), Some(end))
             .await
             .map_err(storage_error)?
-            .ok_or_else(|| ApplicationError::NotFound(command.event_id.to_string()))?;
-
-        let timing = match command.timing {
-            Some(timing) => timing,
-            None => timing_from_event(&current)?,
-        };
-        timing.validate()?;
-
-        let status = command.status.unwrap_or(current.status);
-        let summary = command.summary.unwrap_or_else(|| current.summary.clone());
-        let description = command
-            .description
-            .unwrap_or_else(|| current.description.clone());
-        let location = command.location.unwrap_or_else(|| current.location.clone());
-        let rrule = command.rrule.unwrap_or_else(|| current.rrule.clone());
-        let version = current.version + 1;
-        let attendees = tx.list_attendees(current.id).await.map_err(storage_error)?;
-        let sync_version = tx
-            .bump_calendar_state(user_id)
-            .await
-            .map_err(storage_error)?;
-        let etag = etag_for_parts(
-            &current.uid,
-            &summary,
-            description.clone(),
-            location.clone(),
-            timing.clone(),
-            status,
-            rrule.clone(),
-            version,
-            &attendees,
-        );
-
-        let event = tx
-            .update_event(StoredEventUpdate {
-                id: current.id,
-                user_id,
-                summary,
-                description,
-                location,
-                timing,
-                status,
-                rrule,
-                version,
-                sync_version,
-                etag,
+            .into_iter()
+            .map(|booking| BusyInterval {
+                start: booking.start,
+                end: booking.end,
             })
-            .await
-            .map_err(storage_error)?;
-
-        tx.commit().await.map_err(storage_error)?;
-        Ok(event)
-    }
-
-    pub async fn update_event_view(
-        &self,
-        command: UpdateEventCommand,
-    ) -> Result<EventView, ApplicationError> {
-        EventView::try_from(self.update_event(command).await?)
+            .collect())
     }
 
-    pub async fn put_event_by_uid(
+    /// The resource `event_id` holds, if any
+    pub async fn get_event_resource_booking(
         &self,
-        command: PutEventCommand,
-    ) -> Result<PutEventResult, ApplicationError> {
-        command.timing.validate()?;
-
-        let mut tx = self.calendar.begin().await.map_err(storage_error)?;
-        let user_id = command.user_id;
-        let existing = tx
-            .get_event_by_uid(user_id, &command.uid)
+        user_id: UserId,
+        event_id: Uuid,
+    ) -> Result<Option<ResourceBookingView>, ApplicationError> {
+        if self
+            .calendar
+            .get_event_by_id(user_id, event_id)
             .await
-            .map_err(storage_error)?;
-
-        if let (Some(event), Some(expected_etag)) = (&existing, &command.expected_etag) {
-            let current_etag = format!("\"{}\"", event.etag);
-            if expected_etag != "*" && expected_etag != &current_etag {
-                return Err(ApplicationError::Conflict(format!(
-                    "ETag mismatch: {} != {}",
-                    expected_etag, current_etag
-                )));
-            }
+            .map_err(storage_error)?
+            .is_none()
+        {
+            return Err(ApplicationError::NotFound(event_id.to_string()));
         }
-
-        let created = existing.is_none();
-        let version = existing.as_ref().map_or(1, |event| event.version + 1);
-        let sync_version = tx
-            .bump_calendar_state(user_id)
+        Ok(self
+            .calendar
+            .get_resource_booking(event_id)
             .await
-            .map_err(storage_error)?;
+            .map_err(storage_error)?
+            .map(ResourceBookingView::from))
+    }
 
-        let provisional_etag = "pending".to_string();
-        let event = if let Some(existing_event) = existing {
-            tx.update_event(StoredEventUpdate {
-                id: existing_event.id,
-                user_id,
-                summary: command.summary.clone(),
-                description: command.description.clone(),
-                location: command.location.clone(),
-                timing: command.timing.clone(),
-                status: command.status,
-                rrule: command.rrule.clone(),
-                version,
-                sync_version,
-                etag: provisional_etag,
-            })
+    pub async fn list_resource_caldav_event_metadata(
+        &self,
+        resource_id: Uuid,
+    ) -> Result<Vec<CalDavEventMetadata>, ApplicationError> {
+        Ok(self
+            .calendar
+            .list_resource_bookings(resource_id, None, None)
             .await
             .map_err(storage_error)?
-        } else {
-            tx.insert_event(StoredEventWrite {
-                user_id,
-                uid: command.uid.clone(),
-                summary: command.summary.clone(),
-                description: command.description.clone(),
-                location: command.location.clone(),
-                timing: command.timing.clone(),
-                status: command.status,
-                rrule: command.rrule.clone(),
-                version,
-                sync_version,
-                etag: provisional_etag,
-            })
+            .iter()
+            .map(resource::caldav_metadata)
+            .collect())
+    }
+
+    /// Bookings of the resource overlapping the range (or all of them) as
+    /// busy blocks
+    pub async fn list_resource_caldav_event_resources(
+        &self,
+        resource_id: Uuid,
+        start: Option<DateTime<Utc>>,
+        end: Option<DateTime<Utc>>,
+    ) -> Result<Vec<CalDavEventResource>, ApplicationError> {
+        self.calendar
+            .list_resource_bookings(resource_id, start, end)
             .await
             .map_err(storage_error)?
-        };
-
-        let attendee_writes: Vec<_> = command
-            .attendees
             .iter()
-            .map(|attendee| AttendeeWrite {
-                email: attendee.email.clone(),
-                user_id: attendee.user_id.map(UserId::inner),
-                role: attendee.role,
-                status: attendee.status,
-            })
-            .collect();
-        let upsert_results = tx
-            .replace_attendees(event.id, &attendee_writes)
-            .await
-            .map_err(storage_error)?;
-        let final_attendees = tx.list_attendees(event.id).await.map_err(storage_error)?;
-        let etag = etag_for_parts(
-            &command.uid,
-            &command.summary,
-            command.description.clone(),
-            command.location.clone(),
-            command.timing,
-            command.status,
-            command.rrule,
-            version,
-            &final_attendees,
-        );
-        let event = tx
-            .set_event_sync_etag(event.id, user_id, version, sync_version, etag)
-            .await
-            .map_err(storage_error)?;
-
-        let mut outbox = Vec::new();
-        for upsert_result in upsert_results {
-            if !upsert_result.is_new {
-                continue;
-            }
-            if let Some(target_user_id) = upsert_result.user_id {
-                outbox.push(OutboxPayload::InviteNotification(InviteNotification {
-                    event_id: event.id,
-                    target_user_id,
-                }));
-            } else if let Some(attendee) = command
-                .attendees
-                .iter()
-                .find(|attendee| attendee.email == upsert_result.email)
-            {
-                outbox.push(OutboxPayload::ExternalEmailDeferred(
-                    ExternalEmailDeferred {
-                        recipient_email: attendee.email.clone(),
-                        event_summary: event.summary.clone(),
-                        reason: "External email delivery is disabled".to_string(),
-                    },
-                ));
-            }
-        }
-        tx.queue_outbox(&outbox).await.map_err(storage_error)?;
-
-        tx.commit().await.map_err(storage_error)?;
-        Ok(PutEventResult {
-            etag: event.etag,
-            created,
-        })
+            .map(resource::render_caldav_resource)
+            .collect()
     }
 
-    pub async fn delete_event_by_id(
+    /// Busy blocks by UID; UIDs that aren't event IDs match nothing
+    pub async fn list_resource_caldav_event_resources_by_uids(
         &self,
-        user_id: UserId,
-        event_id: Uuid,
-    ) -> Result<(), ApplicationError> {
-        let mut tx = self.calendar.begin().await.map_err(storage_error)?;
-        let sync_version = tx
-            .bump_calendar_state(user_id)
-            .await
-            .map_err(storage_error)?;
-        let deleted = tx
-            .delete_event_by_id(user_id, event_id)
+        resource_id: Uuid,
+        uids: &[&str],
+    ) -> Result<Vec<CalDavEventResource>, ApplicationError> {
+        let event_ids: Vec<Uuid> = uids
+            .iter()
+            .filter_map(|uid| Uuid::parse_str(uid).ok())
+            .collect();
+        if event_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        self.calendar
+            .get_resource_bookings_by_event_ids(resource_id, &event_ids)
             .await
             .map_err(storage_error)?
-            .ok_or_else(|| ApplicationError::NotFound(event_id.to_string()))?;
-        tx.insert_tombstone(user_id, &deleted.uid, sync_version)
-            .await
-            .map_err(storage_error)?;
-        tx.commit().await.map_err(storage_error)?;
-        Ok(())
+            .iter()
+            .map(resource::render_caldav_resource)
+            .collect()
     }
 
-    pub async fn delete_event_by_uid(
+    pub async fn render_resource_event_ical_by_uid(
         &self,
-        user_id: UserId,
+        resource_id: Uuid,
         uid: &str,
-        expected_etag: Option<String>,
-    ) -> Result<(), ApplicationError> {
-        let mut tx = self.calendar.begin().await.map_err(storage_error)?;
-        let current = tx
-            .get_event_by_uid(user_id, uid)
+    ) -> Result<Option<RenderedEventIcal>, ApplicationError> {
+        let Ok(event_id) = Uuid::parse_str(uid) else {
+            return Ok(None);
+        };
+        self.calendar
+            .get_resource_bookings_by_event_ids(resource_id, &[event_id])
             .await
             .map_err(storage_error)?
-            .ok_or_else(|| ApplicationError::NotFound(uid.to_string()))?;
-
-        if let Some(expected_etag) = expected_etag {
-            let current_etag = format!("\"{}\"", current.etag);
-            if expected_etag != "*" && expected_etag != current_etag {
-                return Err(ApplicationError::Conflict(format!(
-                    "ETag mismatch: {} != {}",
-                    expected_etag, current_etag
-                )));
-            }
-        }
+            .first()
+            .map(resource::render_booking_ical)
+            .transpose()
+    }
 
-        let sync_version = tx
-            .bump_calendar_state(user_id)
-            .await
-            .map_err(storage_error)?;
-        let deleted = tx
-            .delete_event_by_uid(user_id, uid)
+    /// The user's tasks that are not done yet, oldest first
+    pub async fn list_tasks(&self, user_id: UserId) -> Result<Vec<TaskView>, ApplicationError> {
+        Ok(self
+            .calendar
+            .list_open_tasks(user_id)
             .await
             .map_err(storage_error)?
-            .ok_or_else(|| ApplicationError::NotFound(uid.to_string()))?;
-        tx.insert_tombstone(user_id, &deleted.uid, sync_version)
-            .await
-            .map_err(storage_error)?;
-        tx.commit().await.map_err(storage_error)?;
-        Ok(())
+            .into_iter()
+            .map(TaskView::from)
+            .collect())
     }
 
-    pub async fn invite_attendee(
+    pub async fn get_task(
         &self,
-        command: InviteAttendeeCommand,
-    ) -> Result<(), ApplicationError> {
-        let mut tx = self.calendar.begin().await.map_err(storage_error)?;
-        let organizer_user_id = command.organizer_user_id;
-        let current = tx
-            .get_event_by_id(organizer_user_id, command.event_id)
+        user_id: UserId,
+        task_id: Uuid,
+    ) -> Result<Option<TaskView>, ApplicationError> {
+        Ok(self
+            .calendar
+            .get_task(user_id, task_id)
             .await
             .map_err(storage_error)?
-            .ok_or_else(|| ApplicationError::NotFound(command.event_id.to_string()))?;
-
-        let attendees = [AttendeeWrite {
-            email: command.email.clone(),
-            user_id: command.attendee_user_id.map(UserId::inner),
-            role: command.role,
-            status: ParticipationStatus::NeedsAction,
-        }];
-        let upsert_results = tx
-            .upsert_attendees(current.id, &attendees)
-            .await
-            .map_err(storage_error)?;
-
-        let version = current.version + 1;
-        let sync_version = tx
-            .bump_calendar_state(organizer_user_id)
-            .await
-            .map_err(storage_error)?;
-        let final_attendees = tx.list_attendees(current.id).await.map_err(storage_error)?;
-        let etag = etag_for_event(&current, version, &final_attendees)?;
-        let event = tx
-            .set_event_sync_etag(current.id, organizer_user_id, version, sync_version, etag)
-            .await
-            .map_err(storage_error)?;
-
-        let is_new = upsert_results.iter().any(|result| result.is_new);
-        if is_new {
-            let payload = if let Some(attendee_user_id) = command.attendee_user_id {
-                OutboxPayload::InviteNotification(InviteNotification {
-                    event_id: event.id,
-                    target_user_id: attendee_user_id.inner(),
-                })
-            } else {
-                OutboxPayload::ExternalEmailDeferred(ExternalEmailDeferred {
-                    recipient_email: command.email,
-                    event_summary: event.summary.clone(),
-                    reason: "External email delivery is disabled".to_string(),
-                })
-            };
-            tx.queue_outbox(&[payload]).await.map_err(storage_error)?;
-        }
-
-        tx.commit().await.map_err(storage_error)?;
-        Ok(())
+            .map(TaskView::from))
     }
 
-    pub async fn confirm_rsvp(&self, command: ConfirmRsvpCommand) -> Result<(), ApplicationError> {
-        let mut tx = self.calendar.begin().await.map_err(storage_error)?;
-        let updated = tx
-            .update_attendee_status(
-                command.event_id,
-                command.attendee_user_id.inner(),
-                command.status,
-            )
+    /// The user's focus rules, oldest first, with their blocks this week
+    pub async fn list_focus_rules(
+        &self,
+        user_id: UserId,
+    ) -> Result<Vec<FocusRuleView>, ApplicationError> {
+        let timezone = self
+            .get_user_by_id(user_id)
+            .await?
+            .map_or_else(Timezone::utc, |user| user.timezone);
+        let (from, until) = focus_week(self.clock.now(), &timezone);
+        let blocks = self
+            .calendar
+            .list_focus_blocks(user_id, from, until)
             .await
             .map_err(storage_error)?;
-
-        if !updated {
-            return Err(ApplicationError::NotFound(command.event_id.to_string()));
-        }
-
-        let current = tx
-            .get_event_by_id_any(command.event_id)
+        Ok(self
+            .calendar
+            .list_focus_rules(user_id)
             .await
             .map_err(storage_error)?
-            .ok_or_else(|| ApplicationError::NotFound(command.event_id.to_string()))?;
-        let organizer_user_id = current.user_id;
-        let version = current.version + 1;
-        let sync_version = tx
-            .bump_calendar_state(organizer_user_id)
-            .await
-            .map_err(storage_error)?;
-        let attendees = tx.list_attendees(current.id).await.map_err(storage_error)?;
-        let etag = etag_for_event(&current, version, &attendees)?;
-        let event = tx
-            .set_event_sync_etag(current.id, organizer_user_id, version, sync_version, etag)
-            .await
-            .map_err(storage_error)?;
-
-        tx.queue_outbox(&[OutboxPayload::RsvpNotification(RsvpNotification {
-            organizer_telegram_id: organizer_user_id.inner(),
-            attendee_name: command.attendee_name,
-            event_summary: event.summary,
-            rsvp_status: command.status,
-        })])
-        .await
-        .map_err(storage_error)?;
-
-        tx.commit().await.map_err(storage_error)?;
-        Ok(())
+            .into_iter()
+            .map(|rule| FocusRuleView::new(rule, &blocks, &timezone))
+            .collect())
     }
-}
-
-#[derive(Debug, Clone)]
-pub struct CreateEventCommand {
-    pub user_id: UserId,
-    pub username: Option<String>,
-    pub uid: String,
-    pub summary: String,
-    pub description: Option<String>,
-    pub location: Option<String>,
-    pub timing: EventTiming,
-    pub status: EventStatus,
-    pub rrule: Option<String>,
-}
-
-#[derive(Debug, Clone)]
-pub struct UpdateEventCommand {
-    pub user_id: UserId,
-    pub event_id: Uuid,
-    pub summary: Option<String>,
-    pub description: Option<Option<String>>,
-    pub location: Option<Option<String>>,
-    pub timing: Option<EventTiming>,
-    pub status: Option<EventStatus>,
-    pub rrule: Option<Option<String>>,
-}
 
-#[derive(Debug, Clone)]
-pub struct PutEventCommand {
-    pub user_id: UserId,
-    pub uid: String,
-    pub summary: String,
-    pub description: Option<String>,
-    pub location: Option<String>,
-    pub timing: EventTiming,
-    pub status: EventStatus,
-    pub rrule: Option<String>,
-    pub expected_etag: Option<String>,
-    pub attendees: Vec<AttendeeCommand>,
-}
-
-#[derive(Debug, Clone)]
-pub struct PutEventResult {
-    pub etag: String,
-    pub created: bool,
-}
-
-#[derive(Debug, Clone)]
-pub struct AttendeeCommand {
-    pub email: String,
-    pub user_id: Option<UserId>,
-    pub role: AttendeeRole,
-    pub status: ParticipationStatus,
-}
-
-#[derive(Debug, Clone)]
-pub struct InviteAttendeeCommand {
-    pub organizer_user_id: UserId,
-    pub event_id: Uuid,
-    pub email: String,
-    pub attendee_user_id: Option<UserId>,
-    pub role: AttendeeRole,
-}
+    /// Users with focus rules, a page at a time
+    pub async fn list_focus_users(
+        &self,
+        after: Option<UserId>,
+        limit: i64,
+    ) -> Result<Vec<UserId>, ApplicationError> {
+        self.calendar
+            .list_focus_users(after, limit)
+            .await
+            .map_err(storage_error)
+    }
 
-#[derive(Debug, Clone)]
-pub struct ConfirmRsvpCommand {
-    pub event_id: Uuid,
-    pub attendee_user_id: UserId,
-    pub status: ParticipationStatus,
-    pub attendee_name: String,
+    async fn attach_attendees(
+        &self,
+        events: Vec<Event>,
+    ) -> Result<CalendarEventsWithAttendees, ApplicationError> {
+        let event_ids: Vec<_> = events.iter().map(|event| event.id).collect();
+        let attendees_by_event = self.get_event_attendees_bulk(&event_ids).await?;
+        Ok(CalendarEventsWithAttendees {
+            events,
+            attendees_by_event,
+        })
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -1061,6 +1388,16 @@ pub struct EventView {
     pub timing: EventTiming,
     pub status: EventStatus,
     pub rrule: Option<String>,
+    pub visibility: EventVisibility,
+    /// Appended to reminders, e.g. "Bring laptop!"
+    pub reminder_note: Option<String>,
+}
+
+/// An event with the time it was created
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CreatedEventView {
+    pub event: EventView,
+    pub created_at: DateTime<Utc>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -1068,6 +1405,9 @@ pub struct UserIdentity {
     pub id: UserId,
     pub username: Option<String>,
     pub timezone: Timezone,
+    pub role: UserRole,
+    pub email: Option<String>,
+    pub default_event_visibility: EventVisibility,
 }
 
 impl From<User> for UserIdentity {
@@ -1076,6 +1416,9 @@ impl From<User> for UserIdentity {
             id: user.id,
             username: user.telegram_username,
             timezone: user.timezone,
+            role: user.role,
+            email: user.email,
+            default_event_visibility: user.default_event_visibility,
         }
     }
 }
@@ -1161,6 +1504,12 @@ impl From<EventTombstone> for CalDavTombstone {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct CalDavSyncChanges {
     pub last_sync_token: i64,
+    /// Deletions since the client's token were pruned by retention, so the
+    /// changes are incomplete and the client has to sync from scratch
+    pub token_expired: bool,
+    /// Calendar state read together with the changes, so the new sync token
+    /// covers exactly what is returned
+    pub calendar: CalDavCalendarState,
     pub events: Vec<CalDavEventResource>,
     pub tombstones: Vec<CalDavTombstone>,
 }
@@ -1181,6 +1530,8 @@ impl TryFrom<Event> for EventView {
             timing,
             status,
             rrule: event.rrule,
+            visibility: event.visibility,
+            reminder_note: event.reminder_note,
         })
     }
 }
@@ -1194,6 +1545,8 @@ struct CalendarEventsWithAttendees {
 #[derive(Debug, Clone)]
 struct CalendarSyncChanges {
     last_sync_token: i64,
+    token_expired: bool,
+    calendar: CalDavCalendarState,
     events: Vec<Event>,
     tombstones: Vec<EventTombstone>,
     attendees_by_event: HashMap<Uuid, Vec<EventAttendee>>,
@@ -1253,7 +1606,9 @@ fn ical_event_render_from_event(
         timing: timing_from_event(event)?,
         status: event.status,
         rrule: event.rrule.clone(),
-        sequence: event.version,
+        visibility: event.visibility,
+        reminder_note: event.reminder_note.clone(),
+        sequence: event.sequence,
         created_at: event.created_at,
         updated_at: event.updated_at,
     })
@@ -1278,7 +1633,7 @@ pub fn parse_calendar_sync_token(sync_token: Option<&str>) -> i64 {
         .unwrap_or(0)
 }
 
-fn timing_from_event(event: &Event) -> Result<EventTiming, ApplicationError> {
+pub(crate) fn timing_from_event(event: &Event) -> Result<EventTiming, ApplicationError> {
     if event.is_all_day {
         Ok(EventTiming::AllDay {
             start_date: event.start_date.ok_or_else(|| {
@@ -1288,6 +1643,21 @@ fn timing_from_event(event: &Event) -> Result<EventTiming, ApplicationError> {
                 ApplicationError::BadRequest("all-day event is missing end_date".to_string())
             })?,
         })
+    } else if event.is_floating {
+        Ok(EventTiming::Floating {
+            start: event
+                .start
+                .ok_or_else(|| {
+                    ApplicationError::BadRequest("floating event is missing start".to_string())
+                })?
+                .naive_utc(),
+            end: event
+                .end
+                .ok_or_else(|| {
+                    ApplicationError::BadRequest("floating event is missing end".to_string())
+                })?
+                .naive_utc(),
+        })
     } else {
         Ok(EventTiming::Timed {
             start: event.start.ok_or_else(|| {
@@ -1301,60 +1671,10 @@ fn timing_from_event(event: &Event) -> Result<EventTiming, ApplicationError> {
     }
 }
 
-fn etag_for_event(
-    event: &Event,
-    version: i32,
-    attendees: &[EventAttendee],
-) -> Result<String, ApplicationError> {
-    Ok(etag_for_parts(
-        &event.uid,
-        &event.summary,
-        event.description.clone(),
-        event.location.clone(),
-        timing_from_event(event)?,
-        event.status,
-        event.rrule.clone(),
-        version,
-        attendees,
-    ))
-}
-
-#[allow(clippy::too_many_arguments)]
-fn etag_for_parts(
-    uid: &str,
-    summary: &str,
-    description: Option<String>,
-    location: Option<String>,
-    timing: EventTiming,
-    status: EventStatus,
-    rrule: Option<String>,
-    version: i32,
-    attendees: &[EventAttendee],
-) -> String {
-    compute_event_etag(&EventEtagInput {
-        uid: uid.to_string(),
-        summary: summary.to_string(),
-        description,
-        location,
-        timing,
-        status,
-        rrule,
-        version,
-        attendees: attendees
-            .iter()
-            .map(|attendee| AttendeeFingerprint {
-                email: attendee.email.clone(),
-                user_id: attendee.user_id,
-                role: attendee.role,
-                status: attendee.status,
-            })
-            .collect(),
-    })
-}
-
 #[cfg(test)]
 mod tests {
-    use super::parse_calendar_sync_token;
+    use super::*;
+    use televent_storage::memory::{MemoryUsers, new_user};
 
     #[test]
     fn parse_calendar_sync_token_accepts_caldav_url() {
@@ -1382,4 +1702,34 @@ mod tests {
             0
         );
     }
+
+    fn service_over(users: &MemoryUsers) -> CalendarService {
+        let pool = sqlx::PgPool::connect_lazy("postgres://localhost/unused").unwrap();
+        CalendarService::new(CalendarRepository::new(pool)).with_users(Arc::new(users.clone()))
+    }
+
+    #[tokio::test]
+    async fn set_user_email_keeps_addresses_unique() {
+        let users = MemoryUsers::default();
+        let mut owner = new_user(UserId::new(1), None);
+        owner.email = Some("ada@example.com".to_string());
+        users.insert(owner);
+        users.insert(new_user(UserId::new(2), None));
+        let calendar = service_over(&users);
+
+        let taken = calendar
+            .set_user_email(UserId::new(2), Some("ADA@example.com"))
+            .await;
+        assert!(matches!(taken, Err(ApplicationError::Conflict(_))));
+
+        let own = calendar
+            .set_user_email(UserId::new(1), Some(" ada@example.com "))
+            .await
+            .unwrap();
+        assert_eq!(own.email.as_deref(), Some("ada@example.com"));
+        assert_eq!(
+            users.get(UserId::new(1)).unwrap().email.as_deref(),
+            Some("ada@example.com")
+        );
+    }
 }
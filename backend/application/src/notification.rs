@@ -0,0 +1,604 @@
+//! Text message notifications and the notification preference matrix.
+//!
+//! Telegram gets every notice. Copies to the other channels, Slack and Teams
+//! webhooks and SMS, follow a matrix of topic × channel switches; topics the
+//! user never chose for fall back to the channel default, which is off for
+//! SMS since texts cost money.
+//!
+//! A phone number only gets notices once its owner typed back the code
+//! texted to it. Codes are minted by the worker right before the text goes
+//! out, stored hashed, and die after a few minutes or a few wrong guesses.
+//!
+//! Email, the fallback for users who mute Telegram, works the same way: the
+//! address a user saved gets invites regardless, but notice copies only once
+//! its owner typed back the emailed code or opened the link carrying it.
+
+use chrono::{DateTime, Duration, Utc};
+use rand::RngExt;
+use sha2::{Digest, Sha256};
+use televent_domain::{
+    NotificationChannel, NotificationTopic, OutboxPayload, SmsContent, SmsMessage,
+    UserEmailContent, UserEmailMessage, normalize_phone_number,
+};
+use televent_storage::notification::{
+    EmailAddressRecord, NotificationRepository, PhoneNumberRecord,
+};
+use uuid::Uuid;
+
+use crate::chat_webhook::ChatNotice;
+use crate::{ApplicationError, UserId, storage_error};
+
+pub const SMS_CODE_TTL_MINUTES: i64 = 10;
+pub const MAX_SMS_CODE_ATTEMPTS: i32 = 5;
+/// Wait before the same number can be sent another code
+pub const SMS_CODE_RESEND_SECS: i64 = 60;
+/// Longest notice text; anything longer is cut to stay within a few segments
+pub const MAX_SMS_NOTICE_LENGTH: usize = 300;
+/// Emails can sit unread for a while, so their codes live longer than texted ones
+pub const EMAIL_CODE_TTL_MINUTES: i64 = 60;
+pub const MAX_EMAIL_CODE_ATTEMPTS: i32 = 5;
+/// Wait before the same address can be sent another code
+pub const EMAIL_CODE_RESEND_SECS: i64 = 60;
+
+#[derive(Clone)]
+pub struct NotificationService {
+    notifications: NotificationRepository,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PhoneNumberView {
+    pub phone_number: String,
+    /// `None` until the texted code was typed back
+    pub verified_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmailAddressView {
+    pub email: String,
+    /// `None` until the emailed code was typed back or its link opened
+    pub verified_at: Option<DateTime<Utc>>,
+}
+
+/// One cell of the notification preference matrix
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NotificationPreference {
+    pub topic: NotificationTopic,
+    pub channel: NotificationChannel,
+    pub enabled: bool,
+}
+
+impl NotificationService {
+    #[must_use]
+    pub fn new(notifications: NotificationRepository) -> Self {
+        Self { notifications }
+    }
+
+    /// The whole matrix, every topic for every channel
+    pub async fn preferences(
+        &self,
+        user_id: UserId,
+    ) -> Result<Vec<NotificationPreference>, ApplicationError> {
+        let stored = self
+            .notifications
+            .list_preferences(user_id)
+            .await
+            .map_err(storage_error)?;
+        let stored = &stored;
+
+        Ok(NotificationTopic::ALL
+            .into_iter()
+            .flat_map(|topic| {
+                NotificationChannel::ALL.into_iter().map(move |channel| {
+                    let enabled = stored
+                        .iter()
+                        .find(|row| row.topic == topic.as_sql() && row.channel == channel.as_sql())
+                        .map_or(channel.enabled_by_default(), |row| row.enabled);
+                    NotificationPreference {
+                        topic,
+                        channel,
+                        enabled,
+                    }
+                })
+            })
+            .collect())
+    }
+
+    /// Change some cells of the matrix and return all of it
+    pub async fn set_preferences(
+        &self,
+        user_id: UserId,
+        changes: &[NotificationPreference],
+    ) -> Result<Vec<NotificationPreference>, ApplicationError> {
+        let changes = changes
+            .iter()
+            .map(|change| (change.topic, change.channel, change.enabled))
+            .collect::<Vec<_>>();
+        self.notifications
+            .set_preferences(user_id, &changes)
+            .await
+            .map_err(storage_error)?;
+
+        self.preferences(user_id).await
+    }
+
+    pub async fn phone(
+        &self,
+        user_id: UserId,
+    ) -> Result<Option<PhoneNumberView>, ApplicationError> {
+        Ok(self
+            .notifications
+            .get_phone(user_id)
+            .await
+            .map_err(storage_error)?
+            .map(PhoneNumberView::from))
+    }
+
+    /// Register a phone number, or ask again for a code, and text one to it.
+    ///
+    /// A new number replaces the old one, which stops getting texts at once.
+    pub async fn request_phone_verification(
+        &self,
+        user_id: UserId,
+        phone_number: &str,
+    ) -> Result<PhoneNumberView, ApplicationError> {
+        let phone_number =
+            normalize_phone_number(phone_number).map_err(ApplicationError::BadRequest)?;
+        if let Some(current) = self
+            .notifications
+            .get_phone(user_id)
+            .await
+            .map_err(storage_error)?
+            .filter(|current| current.phone_number == phone_number)
+        {
+            if current.verified_at.is_some() {
+                return Ok(current.into());
+            }
+            if current.requested_at > Utc::now() - Duration::seconds(SMS_CODE_RESEND_SECS) {
+                return Err(ApplicationError::BadRequest(format!(
+                    "A code was just sent; ask again in {SMS_CODE_RESEND_SECS} seconds"
+                )));
+            }
+        }
+        if self
+            .notifications
+            .is_phone_taken(user_id, &phone_number)
+            .await
+            .map_err(storage_error)?
+        {
+            return Err(ApplicationError::Conflict(
+                "This phone number is registered to another account".to_string(),
+            ));
+        }
+
+        let verification = OutboxPayload::Sms(SmsMessage {
+            user_id: user_id.inner(),
+            content: SmsContent::VerificationCode {
+                phone_number: phone_number.clone(),
+            },
+        });
+        let phone = self
+            .notifications
+            .request_phone(user_id, &phone_number, &verification)
+            .await
+            .map_err(storage_error)?;
+
+        Ok(phone.into())
+    }
+
+    /// Confirm the user's number with the code texted to it
+    pub async fn verify_phone(
+        &self,
+        user_id: UserId,
+        code: &str,
+    ) -> Result<PhoneNumberView, ApplicationError> {
+        let phone = self
+            .notifications
+            .get_phone(user_id)
+            .await
+            .map_err(storage_error)?
+            .ok_or_else(|| ApplicationError::NotFound("No phone number to confirm".to_string()))?;
+        if phone.verified_at.is_some() {
+            return Ok(phone.into());
+        }
+        let live = phone
+            .code_expires_at
+            .is_some_and(|expires| expires > Utc::now());
+        if phone.code_hash.is_none() || !live {
+            return Err(ApplicationError::BadRequest(
+                "The code has expired; ask for a new one".to_string(),
+            ));
+        }
+        // Every try is counted before comparing, so parallel guesses can't
+        // get past the cap
+        let code_hash = self
+            .notifications
+            .claim_code_attempt(user_id, MAX_SMS_CODE_ATTEMPTS)
+            .await
+            .map_err(storage_error)?
+            .ok_or_else(|| {
+                ApplicationError::BadRequest("Too many wrong codes; ask for a new one".to_string())
+            })?;
+        if hash_code(user_id, code.trim()) != code_hash {
+            return Err(ApplicationError::BadRequest("Wrong code".to_string()));
+        }
+
+        self.notifications
+            .mark_verified(user_id)
+            .await
+            .map_err(storage_error)?
+            .map(PhoneNumberView::from)
+            .ok_or_else(|| {
+                ApplicationError::Conflict(
+                    "This phone number is registered to another account".to_string(),
+                )
+            })
+    }
+
+    pub async fn remove_phone(&self, user_id: UserId) -> Result<bool, ApplicationError> {
+        self.notifications
+            .delete_phone(user_id)
+            .await
+            .map_err(storage_error)
+    }
+
+    /// Mint a code for `phone_number`, replacing any earlier one.
+    ///
+    /// Called right before the text is sent so the plaintext code only ever
+    /// leaves in it. Returns `None` once the number is confirmed or replaced.
+    pub async fn issue_verification_code(
+        &self,
+        user_id: UserId,
+        phone_number: &str,
+    ) -> Result<Option<String>, ApplicationError> {
+        let code = format!("{:06}", rand::rng().random_range(0..1_000_000));
+        let stored = self
+            .notifications
+            .store_code(
+                user_id,
+                phone_number,
+                &hash_code(user_id, &code),
+                Utc::now() + Duration::minutes(SMS_CODE_TTL_MINUTES),
+            )
+            .await
+            .map_err(storage_error)?;
+
+        Ok(stored.then_some(code))
+    }
+
+    /// Number notices go to; `None` until one is confirmed
+    pub async fn verified_phone(
+        &self,
+        user_id: UserId,
+    ) -> Result<Option<String>, ApplicationError> {
+        Ok(self
+            .notifications
+            .get_phone(user_id)
+            .await
+            .map_err(storage_error)?
+            .filter(|phone| phone.verified_at.is_some())
+            .map(|phone| phone.phone_number))
+    }
+
+    /// Queue a text copy of `notice`, rendered for outbox message
+    /// `source_id`, if the user has a confirmed number and wants the topic
+    /// texted. Returns whether one was queued.
+    pub async fn queue_sms_copy(
+        &self,
+        user_id: UserId,
+        source_id: Uuid,
+        notice: &ChatNotice,
+    ) -> Result<bool, ApplicationError> {
+        if !self
+            .notifications
+            .channel_enabled(user_id, notice.topic, NotificationChannel::Sms)
+            .await
+            .map_err(storage_error)?
+            || self.verified_phone(user_id).await?.is_none()
+        {
+            return Ok(false);
+        }
+
+        self.notifications
+            .queue_outbox(&[OutboxPayload::Sms(SmsMessage {
+                user_id: user_id.inner(),
+                content: SmsContent::Notice {
+                    source_id,
+                    text: sms_text(notice),
+                },
+            })])
+            .await
+            .map_err(storage_error)?;
+
+        Ok(true)
+    }
+
+    pub async fn email(
+        &self,
+        user_id: UserId,
+    ) -> Result<Option<EmailAddressView>, ApplicationError> {
+        Ok(self
+            .notifications
+            .get_email(user_id)
+            .await
+            .map_err(storage_error)?
+            .map(EmailAddressView::from))
+    }
+
+    /// Email a code, and a link carrying it, to the user's saved address.
+    ///
+    /// A confirmed address is returned as is; set the address with
+    /// [`crate::CalendarService::set_user_email`] first.
+    pub async fn request_email_verification(
+        &self,
+        user_id: UserId,
+    ) -> Result<EmailAddressView, ApplicationError> {
+        let email = self
+            .notifications
+            .get_email(user_id)
+            .await
+            .map_err(storage_error)?
+            .ok_or_else(|| ApplicationError::NotFound("No email address to confirm".to_string()))?;
+        if email.verified_at.is_some() {
+            return Ok(email.into());
+        }
+        if email.requested_at.is_some_and(|requested| {
+            requested > Utc::now() - Duration::seconds(EMAIL_CODE_RESEND_SECS)
+        }) {
+            return Err(ApplicationError::BadRequest(format!(
+                "A code was just sent; ask again in {EMAIL_CODE_RESEND_SECS} seconds"
+            )));
+        }
+
+        let verification = OutboxPayload::UserEmail(UserEmailMessage {
+            user_id: user_id.inner(),
+            content: UserEmailContent::VerificationCode {
+                email: email.email.clone(),
+            },
+        });
+        self.notifications
+            .request_email(user_id, &email.email, &verification)
+            .await
+            .map_err(storage_error)?;
+
+        Ok(email.into())
+    }
+
+    /// Confirm the user's email with the code sent to it
+    pub async fn verify_email(
+        &self,
+        user_id: UserId,
+        code: &str,
+    ) -> Result<EmailAddressView, ApplicationError> {
+        let email = self
+            .notifications
+            .get_email(user_id)
+            .await
+            .map_err(storage_error)?
+            .ok_or_else(|| ApplicationError::NotFound("No email address to confirm".to_string()))?;
+        if email.verified_at.is_some() {
+            return Ok(email.into());
+        }
+        let live = email
+            .code_expires_at
+            .is_some_and(|expires| expires > Utc::now());
+        if email.code_hash.is_none() || !live {
+            return Err(ApplicationError::BadRequest(
+                "The code has expired; ask for a new one".to_string(),
+            ));
+        }
+        // Every try is counted before comparing, so parallel guesses can't
+        // get past the cap
+        let code_hash = self
+            .notifications
+            .claim_email_code_attempt(user_id, &email.email, MAX_EMAIL_CODE_ATTEMPTS)
+            .await
+            .map_err(storage_error)?
+            .ok_or_else(|| {
+                ApplicationError::BadRequest("Too many wrong codes; ask for a new one".to_string())
+            })?;
+        if hash_code(user_id, code.trim()) != code_hash {
+            return Err(ApplicationError::BadRequest("Wrong code".to_string()));
+        }
+
+        if !self
+            .notifications
+            .mark_email_verified(user_id, &email.email)
+            .await
+            .map_err(storage_error)?
+        {
+            return Err(ApplicationError::Conflict(
+                "The email address changed; confirm the new one".to_string(),
+            ));
+        }
+        self.email(user_id)
+            .await?
+            .ok_or_else(|| ApplicationError::NotFound("No email address to confirm".to_string()))
+    }
+
+    /// Mint a code for `email`, replacing any earlier one.
+    ///
+    /// Called right before the email is sent so the plaintext code only ever
+    /// leaves in it. Returns `None` once the address is confirmed or replaced.
+    pub async fn issue_email_verification_code(
+        &self,
+        user_id: UserId,
+        email: &str,
+    ) -> Result<Option<String>, ApplicationError> {
+        let code = format!("{:06}", rand::rng().random_range(0..1_000_000));
+        let stored = self
+            .notifications
+            .store_email_code(
+                user_id,
+                email,
+                &hash_code(user_id, &code),
+                Utc::now() + Duration::minutes(EMAIL_CODE_TTL_MINUTES),
+            )
+            .await
+            .map_err(storage_error)?;
+
+        Ok(stored.then_some(code))
+    }
+
+    /// Address notices go to; `None` until one is confirmed
+    pub async fn verified_email(
+        &self,
+        user_id: UserId,
+    ) -> Result<Option<String>, ApplicationError> {
+        Ok(self
+            .notifications
+            .get_email(user_id)
+            .await
+            .map_err(storage_error)?
+            .filter(|email| email.verified_at.is_some())
+            .map(|email| email.email))
+    }
+
+    /// Queue an email copy of `notice`, rendered for outbox message
+    /// `source_id`, if the user has a confirmed address and wants the topic
+    /// emailed. Returns whether one was queued.
+    pub async fn queue_email_copy(
+        &self,
+        user_id: UserId,
+        source_id: Uuid,
+        notice: &ChatNotice,
+    ) -> Result<bool, ApplicationError> {
+        if !self
+            .notifications
+            .channel_enabled(user_id, notice.topic, NotificationChannel::Email)
+            .await
+            .map_err(storage_error)?
+            || self.verified_email(user_id).await?.is_none()
+        {
+            return Ok(false);
+        }
+
+        self.notifications
+            .queue_outbox(&[OutboxPayload::UserEmail(UserEmailMessage {
+                user_id: user_id.inner(),
+                content: UserEmailContent::Notice {
+                    source_id,
+                    title: notice.title.clone(),
+                    lines: notice.lines.clone(),
+                },
+            })])
+            .await
+            .map_err(storage_error)?;
+
+        Ok(true)
+    }
+}
+
+impl From<PhoneNumberRecord> for PhoneNumberView {
+    fn from(phone: PhoneNumberRecord) -> Self {
+        Self {
+            phone_number: phone.phone_number,
+            verified_at: phone.verified_at,
+        }
+    }
+}
+
+impl From<EmailAddressRecord> for EmailAddressView {
+    fn from(email: EmailAddressRecord) -> Self {
+        Self {
+            email: email.email,
+            verified_at: email.verified_at,
+        }
+    }
+}
+
+/// A notice as one plain-text message, cut to [`MAX_SMS_NOTICE_LENGTH`]
+pub fn sms_text(notice: &ChatNotice) -> String {
+    let mut text = notice.title.clone();
+    for line in &notice.lines {
+        text.push('\n');
+        text.push_str(line);
+    }
+    if text.chars().count() > MAX_SMS_NOTICE_LENGTH {
+        text = text.chars().take(MAX_SMS_NOTICE_LENGTH - 1).collect();
+        text.push('…');
+    }
+    text
+}
+
+fn hash_code(user_id: UserId, code: &str) -> String {
+    format!(
+        "{:x}",
+        Sha256::digest(format!("{user_id}:{code}").as_bytes())
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sms_text_joins_lines_and_stays_short() {
+        let notice = ChatNotice {
+            topic: NotificationTopic::Cancellation,
+            title: "❌ Cancelled: Standup".to_string(),
+            lines: vec!["The organizer called this event off.".to_string()],
+        };
+        assert_eq!(
+            sms_text(&notice),
+            "❌ Cancelled: Standup\nThe organizer called this event off."
+        );
+
+        let long = ChatNotice {
+            lines: vec!["x".repeat(MAX_SMS_NOTICE_LENGTH)],
+            ..notice
+        };
+        let text = sms_text(&long);
+        assert_eq!(text.chars().count(), MAX_SMS_NOTICE_LENGTH);
+        assert!(text.ends_with('…'));
+    }
+
+    #[test]
+    fn test_codes_are_hashed_per_user() {
+        assert_ne!(
+            hash_code(UserId::new(1), "123456"),
+            hash_code(UserId::new(2), "123456")
+        );
+        assert_eq!(hash_code(UserId::new(1), "123456").len(), 64);
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_parallel_wrong_codes_stop_at_the_cap(pool: sqlx::PgPool) {
+        let user_id = UserId::new(7);
+        sqlx::query(
+            r#"
+            INSERT INTO users (telegram_id, timezone, sync_token, ctag, email)
+            VALUES ($1, 'UTC', '0', '0', 'guest@example.com')
+            "#,
+        )
+        .bind(user_id.inner())
+        .execute(&pool)
+        .await
+        .unwrap();
+        let service = NotificationService::new(NotificationRepository::new(pool.clone()));
+        service.request_email_verification(user_id).await.unwrap();
+        let code = service
+            .issue_email_verification_code(user_id, "guest@example.com")
+            .await
+            .unwrap()
+            .unwrap();
+        let wrong = if code == "000000" { "111111" } else { "000000" };
+
+        let mut guesses = tokio::task::JoinSet::new();
+        for _ in 0..MAX_EMAIL_CODE_ATTEMPTS * 2 {
+            let service = service.clone();
+            guesses.spawn(async move { service.verify_email(user_id, wrong).await });
+        }
+        let mut wrong_codes = 0;
+        while let Some(result) = guesses.join_next().await {
+            if let Err(ApplicationError::BadRequest(message)) = result.unwrap()
+                && message == "Wrong code"
+            {
+                wrong_codes += 1;
+            }
+        }
+        assert_eq!(wrong_codes, MAX_EMAIL_CODE_ATTEMPTS);
+
+        // The right code is refused too once the tries are used up
+        assert!(service.verify_email(user_id, &code).await.is_err());
+    }
+}
@@ -0,0 +1,168 @@
+//! Bookable resources such as meeting rooms, shared by all users.
+//!
+//! A resource is a calendar nobody writes to directly: an event on a user's
+//! calendar books it for the event's time, and the database refuses a second
+//! booking that overlaps, however the events are created or moved. Anyone can
+//! read a resource's free/busy or subscribe to it over CalDAV, where each
+//! booking shows up as a bare busy block.
+
+use chrono::{DateTime, Utc};
+use televent_domain::{
+    BUSY_SUMMARY, EventStatus, EventTiming, EventVisibility, Timezone, sanitize_single_line_text,
+};
+use televent_storage::resource::{Resource, ResourceBooking};
+use uuid::Uuid;
+
+use crate::{ApplicationError, CalDavCalendarState, CalDavEventResource, RenderedEventIcal};
+
+/// Upper bound on a resource's name.
+pub const MAX_RESOURCE_NAME_LENGTH: usize = 128;
+
+/// Longest window one free/busy request may cover.
+pub const MAX_RESOURCE_FREE_BUSY_DAYS: i64 = 62;
+
+/// A resource and the state of its read-only calendar.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResourceView {
+    pub id: Uuid,
+    pub name: String,
+    pub calendar: CalDavCalendarState,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<Resource> for ResourceView {
+    fn from(resource: Resource) -> Self {
+        Self {
+            id: resource.id,
+            name: resource.name,
+            calendar: CalDavCalendarState {
+                sync_token: resource.sync_token,
+                ctag: resource.sync_token,
+            },
+            created_at: resource.created_at,
+        }
+    }
+}
+
+/// The resource an event holds and for when
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResourceBookingView {
+    pub event_id: Uuid,
+    pub resource_id: Uuid,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+impl From<ResourceBooking> for ResourceBookingView {
+    fn from(booking: ResourceBooking) -> Self {
+        Self {
+            event_id: booking.event_id,
+            resource_id: booking.resource_id,
+            start: booking.start,
+            end: booking.end,
+        }
+    }
+}
+
+/// Trim the name and check it fits
+pub(crate) fn normalize_resource_name(name: &str) -> Result<String, ApplicationError> {
+    let name = sanitize_single_line_text(name).trim().to_string();
+    if name.is_empty() {
+        return Err(ApplicationError::BadRequest(
+            "Resource name cannot be empty".to_string(),
+        ));
+    }
+    if name.chars().count() > MAX_RESOURCE_NAME_LENGTH {
+        return Err(ApplicationError::BadRequest(format!(
+            "Resource name too long (max {MAX_RESOURCE_NAME_LENGTH} characters)"
+        )));
+    }
+    Ok(name)
+}
+
+fn booking_etag(booking: &ResourceBooking) -> String {
+    format!("{:x}", booking.updated_at.timestamp_micros())
+}
+
+/// A booking as a busy block without any details of the event behind it;
+/// the UID is the event's ID so a moved booking keeps its href.
+fn ical_render(booking: &ResourceBooking) -> crate::ical::IcalEventRender {
+    crate::ical::IcalEventRender {
+        uid: booking.event_id.to_string(),
+        summary: BUSY_SUMMARY.to_string(),
+        description: None,
+        location: None,
+        timing: EventTiming::Timed {
+            start: booking.start,
+            end: booking.end,
+            timezone: Timezone::utc(),
+        },
+        status: EventStatus::Confirmed,
+        rrule: None,
+        visibility: EventVisibility::Public,
+        reminder_note: None,
+        sequence: 0,
+        created_at: booking.updated_at,
+        updated_at: booking.updated_at,
+    }
+}
+
+pub(crate) fn render_caldav_resource(
+    booking: &ResourceBooking,
+) -> Result<CalDavEventResource, ApplicationError> {
+    let mut calendar_data = String::with_capacity(512);
+    crate::ical::event_to_ical_into(&ical_render(booking), &[], &mut calendar_data)?;
+
+    Ok(CalDavEventResource {
+        uid: booking.event_id.to_string(),
+        etag: booking_etag(booking),
+        updated_at: booking.updated_at,
+        calendar_data,
+    })
+}
+
+pub(crate) fn render_booking_ical(
+    booking: &ResourceBooking,
+) -> Result<RenderedEventIcal, ApplicationError> {
+    Ok(RenderedEventIcal {
+        etag: booking_etag(booking),
+        body: crate::ical::event_to_ical(&ical_render(booking), &[])?,
+    })
+}
+
+pub(crate) fn caldav_metadata(booking: &ResourceBooking) -> crate::CalDavEventMetadata {
+    crate::CalDavEventMetadata {
+        uid: booking.event_id.to_string(),
+        etag: booking_etag(booking),
+        updated_at: booking.updated_at,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn names_are_trimmed_and_bounded() {
+        assert_eq!(normalize_resource_name("  Room 4\n").unwrap(), "Room 4");
+        assert!(normalize_resource_name(" \t ").is_err());
+        assert!(normalize_resource_name(&"x".repeat(MAX_RESOURCE_NAME_LENGTH + 1)).is_err());
+    }
+
+    #[test]
+    fn bookings_render_as_busy_blocks() {
+        let booking = ResourceBooking {
+            event_id: Uuid::nil(),
+            resource_id: Uuid::nil(),
+            start: Utc.with_ymd_and_hms(2026, 10, 19, 9, 0, 0).unwrap(),
+            end: Utc.with_ymd_and_hms(2026, 10, 19, 10, 0, 0).unwrap(),
+            updated_at: Utc.with_ymd_and_hms(2026, 10, 18, 12, 0, 0).unwrap(),
+        };
+
+        let rendered = render_caldav_resource(&booking).unwrap();
+        assert_eq!(rendered.uid, Uuid::nil().to_string());
+        assert!(rendered.calendar_data.contains("SUMMARY:Busy"));
+        assert!(rendered.calendar_data.contains("DTSTART:20261019T090000Z"));
+    }
+}
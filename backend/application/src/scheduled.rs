@@ -0,0 +1,95 @@
+//! Outbox messages users schedule for a later time.
+//!
+//! Use cases check the time with [`validate_send_at`] and queue the message
+//! through the typed enqueue helper in their own transaction. The user who
+//! scheduled a message can list it and cancel it until the worker claims it.
+
+use chrono::{DateTime, Duration, Utc};
+use televent_domain::{OutboxKind, OutboxMessageId, OutboxPayload};
+use televent_storage::outbox::ScheduledOutboxMessage;
+use uuid::Uuid;
+
+use crate::ApplicationError;
+
+/// Furthest ahead a message may be scheduled
+pub const MAX_SCHEDULE_DELAY_DAYS: i64 = 30;
+
+/// Reject send times in the past or beyond [`MAX_SCHEDULE_DELAY_DAYS`]
+pub fn validate_send_at(
+    send_at: DateTime<Utc>,
+    now: DateTime<Utc>,
+) -> Result<(), ApplicationError> {
+    if send_at <= now {
+        return Err(ApplicationError::BadRequest(
+            "Scheduled time must be in the future".to_string(),
+        ));
+    }
+    if send_at - now > Duration::days(MAX_SCHEDULE_DELAY_DAYS) {
+        return Err(ApplicationError::BadRequest(format!(
+            "Messages can be scheduled at most {MAX_SCHEDULE_DELAY_DAYS} days ahead"
+        )));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone)]
+pub struct ScheduledMessageView {
+    pub id: OutboxMessageId,
+    pub kind: OutboxKind,
+    /// Event the message is about, if any
+    pub event_id: Option<Uuid>,
+    pub scheduled_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl ScheduledMessageView {
+    pub(crate) fn from_stored(message: ScheduledOutboxMessage) -> Result<Self, ApplicationError> {
+        let payload = OutboxPayload::from_parts(&message.kind, message.payload)
+            .map_err(|err| ApplicationError::Internal(err.to_string()))?;
+        let event_id = match &payload {
+            OutboxPayload::InviteNotification(payload) => Some(payload.event_id),
+            OutboxPayload::InviteReminder(payload) => Some(payload.event_id),
+            OutboxPayload::SignupConfirmation(payload) => Some(payload.event_id),
+            OutboxPayload::EventCancelledNotification(payload) => Some(payload.event_id),
+            OutboxPayload::CancellationEmail(payload) => Some(payload.event_id),
+            OutboxPayload::EventUpdated(payload) => Some(payload.event_id),
+            OutboxPayload::EventUpdateEmail(payload) => Some(payload.event_id),
+            OutboxPayload::TaskBlockReminder(payload) => Some(payload.event_id),
+            OutboxPayload::TelegramNotification(_)
+            | OutboxPayload::ExternalEmailDeferred(_)
+            | OutboxPayload::RsvpNotification(_)
+            | OutboxPayload::AttendeeRemovedNotification(_)
+            | OutboxPayload::ChatWebhook(_)
+            | OutboxPayload::Sms(_)
+            | OutboxPayload::WebPush(_)
+            | OutboxPayload::UserEmail(_) => None,
+        };
+        Ok(Self {
+            id: message.id,
+            kind: payload.kind(),
+            event_id,
+            scheduled_at: message.scheduled_at,
+            created_at: message.created_at,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_send_at() {
+        let now = Utc::now();
+        assert!(validate_send_at(now + Duration::hours(1), now).is_ok());
+        assert!(validate_send_at(now + Duration::days(MAX_SCHEDULE_DELAY_DAYS), now).is_ok());
+        assert!(matches!(
+            validate_send_at(now, now),
+            Err(ApplicationError::BadRequest(_))
+        ));
+        assert!(matches!(
+            validate_send_at(now + Duration::days(MAX_SCHEDULE_DELAY_DAYS + 1), now),
+            Err(ApplicationError::BadRequest(_))
+        ));
+    }
+}
@@ -0,0 +1,92 @@
+//! Snoozing invite reminders.
+//!
+//! A reminder card in Telegram offers a few fixed delays; picking one queues
+//! the same reminder again as a scheduled message for the invitee, so it can
+//! be listed and cancelled like any other.
+
+use chrono::{DateTime, Duration, NaiveTime, TimeZone, Utc};
+use televent_domain::Timezone;
+
+/// Local hour a "tomorrow" snooze fires at
+pub const SNOOZE_MORNING_HOUR: u32 = 9;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnoozeDelay {
+    TenMinutes,
+    OneHour,
+    /// Tomorrow morning in the invitee's timezone
+    Tomorrow,
+}
+
+impl SnoozeDelay {
+    pub const ALL: [Self; 3] = [Self::TenMinutes, Self::OneHour, Self::Tomorrow];
+
+    /// Short form used in callback data and the API
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::TenMinutes => "10m",
+            Self::OneHour => "1h",
+            Self::Tomorrow => "tomorrow",
+        }
+    }
+
+    #[must_use]
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|delay| delay.as_str() == value)
+    }
+
+    /// Button caption
+    #[must_use]
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::TenMinutes => "10 min",
+            Self::OneHour => "1 hour",
+            Self::Tomorrow => "Tomorrow",
+        }
+    }
+
+    /// When the snoozed reminder fires
+    #[must_use]
+    pub fn send_at(self, now: DateTime<Utc>, timezone: &Timezone) -> DateTime<Utc> {
+        match self {
+            Self::TenMinutes => now + Duration::minutes(10),
+            Self::OneHour => now + Duration::hours(1),
+            Self::Tomorrow => {
+                let tz = timezone.tz();
+                let day = now.with_timezone(&tz).date_naive() + Duration::days(1);
+                let morning =
+                    NaiveTime::from_hms_opt(SNOOZE_MORNING_HOUR, 0, 0).expect("valid snooze hour");
+                tz.from_local_datetime(&day.and_time(morning))
+                    .earliest()
+                    .map_or(now + Duration::days(1), |local| local.with_timezone(&Utc))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snooze_delays() {
+        let now = "2026-06-01T22:30:00Z".parse::<DateTime<Utc>>().unwrap();
+        let berlin = Timezone::parse("Europe/Berlin").unwrap();
+
+        for delay in SnoozeDelay::ALL {
+            assert_eq!(SnoozeDelay::parse(delay.as_str()), Some(delay));
+        }
+        assert_eq!(SnoozeDelay::parse("2d"), None);
+
+        assert_eq!(
+            SnoozeDelay::TenMinutes.send_at(now, &berlin),
+            "2026-06-01T22:40:00Z".parse::<DateTime<Utc>>().unwrap()
+        );
+        // 00:30 on June 2nd in Berlin, so "tomorrow" is June 3rd at 09:00 CEST
+        assert_eq!(
+            SnoozeDelay::Tomorrow.send_at(now, &berlin),
+            "2026-06-03T07:00:00Z".parse::<DateTime<Utc>>().unwrap()
+        );
+    }
+}
@@ -0,0 +1,199 @@
+//! Meeting load statistics.
+//!
+//! [`CalendarStats`] summarises the timed events a user has in a window: how
+//! many, how long, which weekday carries the most, and who is invited most
+//! often. The numbers come from aggregate queries; stored events count once
+//! each, so recurring series are not expanded and all-day events are left
+//! out as they rarely stand for meetings.
+
+use chrono::{DateTime, Duration, Utc, Weekday};
+use televent_storage::calendar::{CollaboratorRecord, WeekdayLoadRecord};
+
+use crate::ApplicationError;
+
+/// Window summarised when the caller doesn't give one.
+pub const DEFAULT_STATS_DAYS: i64 = 30;
+
+/// Longest window one summary may cover.
+pub const MAX_STATS_DAYS: i64 = 366;
+
+/// Collaborators listed in a summary.
+pub const TOP_COLLABORATORS: i64 = 5;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CalendarStats {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub event_count: i64,
+    pub total_hours: f64,
+    /// Events per day of the window
+    pub average_daily_events: f64,
+    /// Weekday with the most events; `None` for an empty window
+    pub busiest_weekday: Option<Weekday>,
+    /// Every weekday from Monday, including empty ones
+    pub weekdays: Vec<WeekdayLoad>,
+    pub top_collaborators: Vec<Collaborator>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WeekdayLoad {
+    pub weekday: Weekday,
+    pub events: i64,
+    pub hours: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Collaborator {
+    pub email: String,
+    pub telegram_username: Option<String>,
+    /// Events of the window they are invited to
+    pub events: i64,
+}
+
+/// Check that a stats window is non-empty and at most [`MAX_STATS_DAYS`]
+pub fn validate_stats_range(
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Result<(), ApplicationError> {
+    if to <= from {
+        return Err(ApplicationError::BadRequest(
+            "Stats range must end after it starts".to_string(),
+        ));
+    }
+    if to - from > Duration::days(MAX_STATS_DAYS) {
+        return Err(ApplicationError::BadRequest(format!(
+            "Stats range can cover at most {MAX_STATS_DAYS} days"
+        )));
+    }
+    Ok(())
+}
+
+/// English name of a weekday, e.g. `Monday`
+#[must_use]
+pub fn weekday_name(weekday: Weekday) -> &'static str {
+    match weekday {
+        Weekday::Mon => "Monday",
+        Weekday::Tue => "Tuesday",
+        Weekday::Wed => "Wednesday",
+        Weekday::Thu => "Thursday",
+        Weekday::Fri => "Friday",
+        Weekday::Sat => "Saturday",
+        Weekday::Sun => "Sunday",
+    }
+}
+
+impl CalendarStats {
+    pub(crate) fn from_records(
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        loads: Vec<WeekdayLoadRecord>,
+        collaborators: Vec<CollaboratorRecord>,
+    ) -> Self {
+        let weekdays: Vec<WeekdayLoad> = (0u8..7)
+            .map(|from_monday| {
+                let weekday = Weekday::try_from(from_monday).expect("0..7 is a weekday");
+                let load = loads
+                    .iter()
+                    .find(|load| load.weekday as u32 == weekday.number_from_monday());
+                WeekdayLoad {
+                    weekday,
+                    events: load.map_or(0, |load| load.events),
+                    hours: load.map_or(0.0, |load| load.seconds / 3600.0),
+                }
+            })
+            .collect();
+
+        let event_count = weekdays.iter().map(|load| load.events).sum();
+        let total_hours = weekdays.iter().map(|load| load.hours).sum();
+        // Partial days count as whole ones
+        let days = ((to - from).num_seconds() + 86_399) / 86_400;
+        let busiest_weekday = weekdays
+            .iter()
+            .filter(|load| load.events > 0)
+            // Earliest weekday wins ties: `max_by` keeps the last maximum
+            .rev()
+            .max_by(|a, b| a.events.cmp(&b.events).then(a.hours.total_cmp(&b.hours)))
+            .map(|load| load.weekday);
+
+        Self {
+            from,
+            to,
+            event_count,
+            total_hours,
+            average_daily_events: event_count as f64 / days as f64,
+            busiest_weekday,
+            weekdays,
+            top_collaborators: collaborators
+                .into_iter()
+                .map(|collaborator| Collaborator {
+                    email: collaborator.email,
+                    telegram_username: collaborator.telegram_username,
+                    events: collaborator.events,
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load(weekday: i32, events: i64, hours: f64) -> WeekdayLoadRecord {
+        WeekdayLoadRecord {
+            weekday,
+            events,
+            seconds: hours * 3600.0,
+        }
+    }
+
+    #[test]
+    fn test_stats_summarise_weekday_loads() {
+        let from = DateTime::parse_from_rfc3339("2026-06-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let to = from + Duration::days(14);
+        let stats = CalendarStats::from_records(
+            from,
+            to,
+            vec![load(1, 4, 3.0), load(3, 4, 5.5), load(5, 6, 2.0)],
+            Vec::new(),
+        );
+
+        assert_eq!(stats.event_count, 14);
+        assert_eq!(stats.total_hours, 10.5);
+        assert_eq!(stats.average_daily_events, 1.0);
+        assert_eq!(stats.busiest_weekday, Some(Weekday::Fri));
+        assert_eq!(stats.weekdays.len(), 7);
+        assert_eq!(stats.weekdays[0].weekday, Weekday::Mon);
+        assert_eq!(stats.weekdays[1].events, 0);
+
+        // Equal counts: the longer day wins, then the earlier one
+        let stats = CalendarStats::from_records(
+            from,
+            to,
+            vec![load(1, 4, 3.0), load(3, 4, 5.5)],
+            Vec::new(),
+        );
+        assert_eq!(stats.busiest_weekday, Some(Weekday::Wed));
+        let stats = CalendarStats::from_records(
+            from,
+            to,
+            vec![load(2, 2, 1.0), load(4, 2, 1.0)],
+            Vec::new(),
+        );
+        assert_eq!(stats.busiest_weekday, Some(Weekday::Tue));
+
+        let empty = CalendarStats::from_records(from, to, Vec::new(), Vec::new());
+        assert_eq!(empty.busiest_weekday, None);
+        assert_eq!(empty.average_daily_events, 0.0);
+    }
+
+    #[test]
+    fn test_stats_range_is_bounded() {
+        let now = Utc::now();
+        assert!(validate_stats_range(now - Duration::days(30), now).is_ok());
+        assert!(validate_stats_range(now, now).is_err());
+        assert!(validate_stats_range(now - Duration::days(MAX_STATS_DAYS + 1), now).is_err());
+    }
+}
@@ -0,0 +1,1140 @@
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use std::collections::{HashMap, HashSet};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use televent_domain::{
+    EventEtagInput, EventStatus, EventTiming, EventVisibility, OutboxPayload, TelegramNotification,
+    UserId, WorkingHours, compute_event_etag,
+};
+use televent_storage::subscription::{
+    CalendarSubscription, FetchSuccess, StoredSubscription, SubscribedEvent, SubscribedEventWrite,
+    SubscriptionRepository, SubscriptionTransaction,
+};
+use url::{Host, Url};
+use uuid::Uuid;
+
+use crate::birthdays::{
+    BIRTHDAYS_CALENDAR_NAME, BIRTHDAYS_URL, birthday_events, contact_birthdays, digest_message,
+    is_birthdays_url, next_refresh,
+};
+use crate::holidays::{
+    HOLIDAYS_URL_PREFIX, HolidayCountry, find_holiday_country, holiday_country_for_url,
+    holiday_events,
+};
+use crate::{
+    ApplicationError, CalDavCalendarState, CalDavEventMetadata, CalDavEventResource,
+    EventTextLimits, FreeBusy, RenderedEventIcal, TextOverflow, storage_error,
+    validate_event_fields,
+};
+
+pub const MAX_SUBSCRIPTIONS_PER_USER: i64 = 10;
+pub const MAX_SUBSCRIPTION_URL_LENGTH: usize = 2048;
+const MAX_SUBSCRIPTION_NAME_LENGTH: usize = 128;
+/// Events kept per subscription; anything beyond is dropped from the mirror.
+pub const MAX_SUBSCRIBED_EVENTS: usize = 2000;
+/// Non-recurring events that ended longer ago than this are not mirrored.
+const MIRROR_HISTORY_DAYS: i64 = 90;
+const REFRESH_INTERVAL_MINUTES: i64 = 60;
+const FAILURE_RETRY_MINUTES: i64 = 240;
+const MAX_FETCH_ERROR_LENGTH: usize = 512;
+
+#[derive(Clone)]
+pub struct SubscriptionService {
+    subscriptions: SubscriptionRepository,
+    text_limits: EventTextLimits,
+}
+
+impl SubscriptionService {
+    #[must_use]
+    pub fn new(subscriptions: SubscriptionRepository) -> Self {
+        Self {
+            subscriptions,
+            text_limits: EventTextLimits::default(),
+        }
+    }
+
+    /// Truncate mirrored event text to deployment-specific limits.
+    #[must_use]
+    pub const fn with_text_limits(mut self, text_limits: EventTextLimits) -> Self {
+        self.text_limits = text_limits;
+        self
+    }
+
+    pub async fn add_subscription(
+        &self,
+        command: AddSubscriptionCommand,
+    ) -> Result<SubscriptionView, ApplicationError> {
+        let url = normalize_subscription_url(&command.url)?;
+        let name = match command.name.as_deref().map(str::trim) {
+            Some(name) if !name.is_empty() => {
+                validate_subscription_name(name)?;
+                name.to_string()
+            }
+            _ => default_subscription_name(&url),
+        };
+
+        let mut tx = self.subscriptions.begin().await.map_err(storage_error)?;
+        tx.ensure_user(command.user_id.inner(), command.username.as_deref())
+            .await
+            .map_err(storage_error)?;
+
+        let count = tx
+            .count_subscriptions(command.user_id)
+            .await
+            .map_err(storage_error)?;
+        if count >= MAX_SUBSCRIPTIONS_PER_USER {
+            return Err(ApplicationError::BadRequest(format!(
+                "Maximum number of subscriptions ({MAX_SUBSCRIPTIONS_PER_USER}) reached. Please remove one first."
+            )));
+        }
+
+        let holidays = holiday_country_for_url(&url);
+        let birthdays = is_birthdays_url(&url);
+        let subscription = tx
+            .insert_subscription(StoredSubscription {
+                user_id: command.user_id,
+                url,
+                name,
+            })
+            .await
+            .map_err(storage_error)?
+            .ok_or_else(|| {
+                ApplicationError::Conflict("Already subscribed to this calendar".to_string())
+            })?;
+
+        // Holidays and birthdays need no fetch, so the calendar is filled
+        // right away
+        if let Some(country) = holidays {
+            store_holidays(&mut tx, subscription.id, country).await?;
+        }
+        if birthdays {
+            store_birthdays(&mut tx, subscription.id, false).await?;
+        }
+
+        tx.commit().await.map_err(storage_error)?;
+        if holidays.is_some() || birthdays {
+            return self
+                .get_subscription(command.user_id, subscription.id)
+                .await?
+                .ok_or_else(|| {
+                    ApplicationError::NotFound(format!(
+                        "Subscription not found: {}",
+                        subscription.id
+                    ))
+                });
+        }
+        Ok(SubscriptionView::from(subscription))
+    }
+
+    /// Subscribe the user to a country's holiday calendar, or return the
+    /// subscription they already have
+    pub async fn enable_holidays(
+        &self,
+        user_id: UserId,
+        username: Option<String>,
+        country_code: &str,
+    ) -> Result<SubscriptionView, ApplicationError> {
+        let country = find_holiday_country(country_code).ok_or_else(|| {
+            ApplicationError::NotFound(format!("No holiday calendar for {country_code}"))
+        })?;
+        self.enable_built_in(user_id, username, country.url()).await
+    }
+
+    /// Unsubscribe the user from a country's holiday calendar; `false` when
+    /// they weren't subscribed
+    pub async fn disable_holidays(
+        &self,
+        user_id: UserId,
+        country_code: &str,
+    ) -> Result<bool, ApplicationError> {
+        let country = find_holiday_country(country_code).ok_or_else(|| {
+            ApplicationError::NotFound(format!("No holiday calendar for {country_code}"))
+        })?;
+        self.disable_built_in(user_id, &country.url()).await
+    }
+
+    /// Turn on the calendar of the user's contacts' birthdays, or return the
+    /// subscription they already have
+    pub async fn enable_birthdays(
+        &self,
+        user_id: UserId,
+        username: Option<String>,
+    ) -> Result<SubscriptionView, ApplicationError> {
+        self.enable_built_in(user_id, username, BIRTHDAYS_URL.to_string())
+            .await
+    }
+
+    /// Turn the birthdays calendar off; `false` when it wasn't on
+    pub async fn disable_birthdays(&self, user_id: UserId) -> Result<bool, ApplicationError> {
+        self.disable_built_in(user_id, BIRTHDAYS_URL).await
+    }
+
+    /// The user's subscription to the birthdays calendar, if on
+    pub async fn birthdays_subscription(
+        &self,
+        user_id: UserId,
+    ) -> Result<Option<SubscriptionView>, ApplicationError> {
+        self.subscription_by_url(user_id, BIRTHDAYS_URL).await
+    }
+
+    async fn enable_built_in(
+        &self,
+        user_id: UserId,
+        username: Option<String>,
+        url: String,
+    ) -> Result<SubscriptionView, ApplicationError> {
+        if let Some(existing) = self.subscription_by_url(user_id, &url).await? {
+            return Ok(existing);
+        }
+
+        match self
+            .add_subscription(AddSubscriptionCommand {
+                user_id,
+                username,
+                url: url.clone(),
+                name: None,
+            })
+            .await
+        {
+            // Lost a race with another request enabling the same calendar
+            Err(ApplicationError::Conflict(_)) => self
+                .subscription_by_url(user_id, &url)
+                .await?
+                .ok_or_else(|| {
+                    ApplicationError::Conflict("Already subscribed to this calendar".to_string())
+                }),
+            result => result,
+        }
+    }
+
+    async fn disable_built_in(&self, user_id: UserId, url: &str) -> Result<bool, ApplicationError> {
+        match self.subscription_by_url(user_id, url).await? {
+            Some(subscription) => self.remove_subscription(user_id, subscription.id).await,
+            None => Ok(false),
+        }
+    }
+
+    async fn subscription_by_url(
+        &self,
+        user_id: UserId,
+        url: &str,
+    ) -> Result<Option<SubscriptionView>, ApplicationError> {
+        Ok(self
+            .list_subscriptions(user_id)
+            .await?
+            .into_iter()
+            .find(|subscription| subscription.url == url))
+    }
+
+    pub async fn list_subscriptions(
+        &self,
+        user_id: UserId,
+    ) -> Result<Vec<SubscriptionView>, ApplicationError> {
+        Ok(self
+            .subscriptions
+            .list_subscriptions(user_id)
+            .await
+            .map_err(storage_error)?
+            .into_iter()
+            .map(SubscriptionView::from)
+            .collect())
+    }
+
+    pub async fn get_subscription(
+        &self,
+        user_id: UserId,
+        subscription_id: Uuid,
+    ) -> Result<Option<SubscriptionView>, ApplicationError> {
+        Ok(self
+            .subscriptions
+            .get_subscription(user_id, subscription_id)
+            .await
+            .map_err(storage_error)?
+            .map(SubscriptionView::from))
+    }
+
+    pub async fn remove_subscription(
+        &self,
+        user_id: UserId,
+        subscription_id: Uuid,
+    ) -> Result<bool, ApplicationError> {
+        self.subscriptions
+            .delete_subscription(user_id, subscription_id)
+            .await
+            .map_err(storage_error)
+    }
+
+    /// Subscriptions the refresh task should fetch now.
+    pub async fn due_subscriptions(
+        &self,
+        limit: i64,
+    ) -> Result<Vec<SubscriptionFetch>, ApplicationError> {
+        Ok(self
+            .subscriptions
+            .list_due_subscriptions(Utc::now(), limit)
+            .await
+            .map_err(storage_error)?
+            .into_iter()
+            .map(|subscription| SubscriptionFetch {
+                id: subscription.id,
+                url: subscription.url,
+                http_etag: subscription.http_etag,
+                http_last_modified: subscription.http_last_modified,
+            })
+            .collect())
+    }
+
+    /// Replace the mirror of a subscription with the events of a fetched feed.
+    ///
+    /// The sync token only moves when the set of mirrored events or any of
+    /// their contents actually changed.
+    pub async fn apply_feed(
+        &self,
+        subscription_id: Uuid,
+        feed: FetchedFeed,
+    ) -> Result<FeedApplied, ApplicationError> {
+        let parsed = parse_feed(&feed.body, Utc::now(), &self.text_limits)?;
+
+        let mut tx = self.subscriptions.begin().await.map_err(storage_error)?;
+        let changed = replace_mirror(&mut tx, subscription_id, &parsed.events).await?;
+        tx.record_fetch_success(
+            subscription_id,
+            FetchSuccess {
+                http_etag: feed.http_etag,
+                http_last_modified: feed.http_last_modified,
+                next_fetch_at: Utc::now() + Duration::minutes(REFRESH_INTERVAL_MINUTES),
+                changed,
+            },
+        )
+        .await
+        .map_err(storage_error)?;
+        tx.commit().await.map_err(storage_error)?;
+
+        Ok(FeedApplied {
+            imported: parsed.events.len(),
+            skipped: parsed.skipped,
+            changed,
+        })
+    }
+
+    /// Regenerate a holiday calendar for the current and the next year.
+    ///
+    /// Takes the place of a fetch for `holidays:` subscriptions; the next
+    /// refresh is due when the year turns.
+    pub async fn apply_holidays(
+        &self,
+        subscription_id: Uuid,
+        country: &HolidayCountry,
+    ) -> Result<FeedApplied, ApplicationError> {
+        let mut tx = self.subscriptions.begin().await.map_err(storage_error)?;
+        let applied = store_holidays(&mut tx, subscription_id, country).await?;
+        tx.commit().await.map_err(storage_error)?;
+        Ok(applied)
+    }
+
+    /// Regenerate the birthdays calendar from the user's contacts and queue
+    /// a note about the birthdays falling tomorrow.
+    ///
+    /// Takes the place of a fetch for the `birthdays:contacts` subscription;
+    /// the next refresh is due at the digest hour of the following morning.
+    pub async fn apply_birthdays(
+        &self,
+        subscription_id: Uuid,
+    ) -> Result<FeedApplied, ApplicationError> {
+        let mut tx = self.subscriptions.begin().await.map_err(storage_error)?;
+        let applied = store_birthdays(&mut tx, subscription_id, true).await?;
+        tx.commit().await.map_err(storage_error)?;
+        Ok(applied)
+    }
+
+    /// The remote calendar answered 304 Not Modified.
+    pub async fn record_not_modified(&self, subscription_id: Uuid) -> Result<(), ApplicationError> {
+        self.subscriptions
+            .record_not_modified(
+                subscription_id,
+                Utc::now() + Duration::minutes(REFRESH_INTERVAL_MINUTES),
+            )
+            .await
+            .map_err(storage_error)
+    }
+
+    /// Keep the last good mirror and retry the fetch later.
+    pub async fn record_fetch_failure(
+        &self,
+        subscription_id: Uuid,
+        error: &str,
+    ) -> Result<(), ApplicationError> {
+        let error: String = error.chars().take(MAX_FETCH_ERROR_LENGTH).collect();
+        self.subscriptions
+            .record_fetch_failure(
+                subscription_id,
+                &error,
+                Utc::now() + Duration::minutes(FAILURE_RETRY_MINUTES),
+            )
+            .await
+            .map_err(storage_error)
+    }
+
+    /// Mark mirrored events of all the user's subscriptions busy.
+    pub async fn mark_busy(
+        &self,
+        user_id: UserId,
+        free_busy: &mut FreeBusy,
+    ) -> Result<(), ApplicationError> {
+        let subscriptions = self
+            .subscriptions
+            .list_subscriptions(user_id)
+            .await
+            .map_err(storage_error)?;
+        // Birthdays don't keep anyone from meeting
+        for subscription in subscriptions
+            .into_iter()
+            .filter(|subscription| !is_birthdays_url(&subscription.url))
+        {
+            let events = self
+                .subscriptions
+                .list_subscribed_events(subscription.id)
+                .await
+                .map_err(storage_error)?;
+            for event in events {
+                if event.status == EventStatus::Cancelled {
+                    continue;
+                }
+                // A rule a remote feed got wrong shouldn't sink the whole search
+                let _ = free_busy.add_event(&event.timing, event.rrule.as_deref());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Mirrored events of all the user's subscriptions starting in the range.
+    pub async fn list_subscribed_event_views(
+        &self,
+        user_id: UserId,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<SubscribedEventView>, ApplicationError> {
+        let names: HashMap<Uuid, String> = self
+            .subscriptions
+            .list_subscriptions(user_id)
+            .await
+            .map_err(storage_error)?
+            .into_iter()
+            .map(|subscription| (subscription.id, subscription.name))
+            .collect();
+        if names.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        Ok(self
+            .subscriptions
+            .list_subscribed_events_in_range(user_id, start, end)
+            .await
+            .map_err(storage_error)?
+            .into_iter()
+            .map(|event| SubscribedEventView {
+                calendar_name: names
+                    .get(&event.subscription_id)
+                    .cloned()
+                    .unwrap_or_default(),
+                subscription_id: event.subscription_id,
+                uid: event.uid,
+                summary: event.summary,
+                location: event.location,
+                timing: event.timing,
+                status: event.status,
+            })
+            .collect())
+    }
+
+    pub async fn list_caldav_event_metadata(
+        &self,
+        subscription_id: Uuid,
+    ) -> Result<Vec<CalDavEventMetadata>, ApplicationError> {
+        Ok(self
+            .list_subscribed_events(subscription_id)
+            .await?
+            .into_iter()
+            .map(|event| CalDavEventMetadata {
+                uid: event.uid,
+                etag: event.etag,
+                updated_at: event.updated_at,
+            })
+            .collect())
+    }
+
+    /// Mirrored events starting in the range (or all of them) with calendar data.
+    pub async fn list_caldav_event_resources(
+        &self,
+        subscription_id: Uuid,
+        start: Option<DateTime<Utc>>,
+        end: Option<DateTime<Utc>>,
+    ) -> Result<Vec<CalDavEventResource>, ApplicationError> {
+        self.list_subscribed_events(subscription_id)
+            .await?
+            .into_iter()
+            .filter(|event| match (start, end) {
+                (Some(start), Some(end)) => {
+                    let event_start = event.timing.start_for_display();
+                    event_start >= start && event_start < end
+                }
+                _ => true,
+            })
+            .map(render_caldav_resource)
+            .collect()
+    }
+
+    pub async fn list_caldav_event_resources_by_uids(
+        &self,
+        subscription_id: Uuid,
+        uids: &[&str],
+    ) -> Result<Vec<CalDavEventResource>, ApplicationError> {
+        self.subscriptions
+            .get_subscribed_events_by_uids(subscription_id, uids)
+            .await
+            .map_err(storage_error)?
+            .into_iter()
+            .map(render_caldav_resource)
+            .collect()
+    }
+
+    pub async fn render_event_ical_by_uid(
+        &self,
+        subscription_id: Uuid,
+        uid: &str,
+    ) -> Result<Option<RenderedEventIcal>, ApplicationError> {
+        self.subscriptions
+            .get_subscribed_events_by_uids(subscription_id, &[uid])
+            .await
+            .map_err(storage_error)?
+            .into_iter()
+            .next()
+            .map(|event| {
+                let etag = event.etag.clone();
+                let body = crate::ical::event_to_ical(&ical_render(event), &[])?;
+                Ok(RenderedEventIcal { etag, body })
+            })
+            .transpose()
+    }
+
+    async fn list_subscribed_events(
+        &self,
+        subscription_id: Uuid,
+    ) -> Result<Vec<SubscribedEvent>, ApplicationError> {
+        self.subscriptions
+            .list_subscribed_events(subscription_id)
+            .await
+            .map_err(storage_error)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AddSubscriptionCommand {
+    pub user_id: UserId,
+    pub username: Option<String>,
+    pub url: String,
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubscriptionView {
+    pub id: Uuid,
+    pub url: String,
+    pub name: String,
+    pub calendar: CalDavCalendarState,
+    pub last_fetched_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+}
+
+impl From<CalendarSubscription> for SubscriptionView {
+    fn from(subscription: CalendarSubscription) -> Self {
+        Self {
+            id: subscription.id,
+            url: subscription.url,
+            name: subscription.name,
+            calendar: CalDavCalendarState {
+                sync_token: subscription.sync_token,
+                ctag: subscription.sync_token,
+            },
+            last_fetched_at: subscription.last_fetched_at,
+            last_error: subscription.last_error,
+        }
+    }
+}
+
+/// What the refresh task needs to issue a conditional GET.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubscriptionFetch {
+    pub id: Uuid,
+    pub url: String,
+    pub http_etag: Option<String>,
+    pub http_last_modified: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FetchedFeed {
+    pub body: String,
+    pub http_etag: Option<String>,
+    pub http_last_modified: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeedApplied {
+    pub imported: usize,
+    pub skipped: usize,
+    pub changed: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubscribedEventView {
+    pub subscription_id: Uuid,
+    pub calendar_name: String,
+    pub uid: String,
+    pub summary: String,
+    pub location: Option<String>,
+    pub timing: EventTiming,
+    pub status: EventStatus,
+}
+
+/// Validate a subscription URL and return it in the form that gets fetched.
+///
+/// `webcal://` links are rewritten to `https://`. Only public hosts are
+/// accepted so the refresh task cannot be pointed at internal services.
+/// Built-in holiday calendars are addressed as `holidays:XX`, the birthdays
+/// of the user's contacts as `birthdays:contacts`.
+pub fn normalize_subscription_url(raw: &str) -> Result<String, ApplicationError> {
+    let raw = raw.trim();
+    if is_birthdays_url(raw) {
+        return Ok(BIRTHDAYS_URL.to_string());
+    }
+    if raw
+        .get(..HOLIDAYS_URL_PREFIX.len())
+        .is_some_and(|prefix| prefix.eq_ignore_ascii_case(HOLIDAYS_URL_PREFIX))
+    {
+        return holiday_country_for_url(raw)
+            .map(HolidayCountry::url)
+            .ok_or_else(|| {
+                ApplicationError::BadRequest(format!(
+                    "No holiday calendar for {}",
+                    &raw[HOLIDAYS_URL_PREFIX.len()..]
+                ))
+            });
+    }
+    if raw.len() > MAX_SUBSCRIPTION_URL_LENGTH {
+        return Err(ApplicationError::BadRequest(format!(
+            "Calendar URL too long (max {MAX_SUBSCRIPTION_URL_LENGTH} characters)"
+        )));
+    }
+
+    let rewritten;
+    let raw = match raw.get(..9) {
+        Some(prefix) if prefix.eq_ignore_ascii_case("webcal://") => {
+            rewritten = format!("https://{}", &raw[9..]);
+            rewritten.as_str()
+        }
+        _ => raw,
+    };
+
+    let url = Url::parse(raw)
+        .map_err(|_| ApplicationError::BadRequest("Invalid calendar URL".to_string()))?;
+    if !matches!(url.scheme(), "https" | "http") {
+        return Err(ApplicationError::BadRequest(
+            "Calendar URL must start with https://, http:// or webcal://".to_string(),
+        ));
+    }
+
+    let public_host = match url.host() {
+        Some(Host::Domain(domain)) => {
+            let domain = domain.trim_end_matches('.').to_ascii_lowercase();
+            domain.contains('.') && domain != "localhost" && !domain.ends_with(".localhost")
+        }
+        Some(Host::Ipv4(addr)) => is_public_ipv4(addr),
+        Some(Host::Ipv6(addr)) => is_public_ipv6(addr),
+        None => false,
+    };
+    if !public_host {
+        return Err(ApplicationError::BadRequest(
+            "Calendar URL must point to a public host".to_string(),
+        ));
+    }
+
+    Ok(url.into())
+}
+
+/// Whether the refresh task may connect to this address.
+#[must_use]
+pub fn is_public_ip(addr: IpAddr) -> bool {
+    match addr {
+        IpAddr::V4(addr) => is_public_ipv4(addr),
+        IpAddr::V6(addr) => is_public_ipv6(addr),
+    }
+}
+
+const fn is_public_ipv4(addr: Ipv4Addr) -> bool {
+    !(addr.is_private()
+        || addr.is_loopback()
+        || addr.is_link_local()
+        || addr.is_unspecified()
+        || addr.is_broadcast()
+        || addr.is_multicast()
+        || addr.is_documentation()
+        // Carrier-grade NAT (100.64.0.0/10)
+        || (addr.octets()[0] == 100 && addr.octets()[1] & 0xc0 == 64))
+}
+
+const fn is_public_ipv6(addr: Ipv6Addr) -> bool {
+    if let Some(mapped) = addr.to_ipv4_mapped() {
+        return is_public_ipv4(mapped);
+    }
+    !(addr.is_loopback()
+        || addr.is_unspecified()
+        || addr.is_multicast()
+        || addr.is_unique_local()
+        || addr.is_unicast_link_local())
+}
+
+fn validate_subscription_name(name: &str) -> Result<(), ApplicationError> {
+    if name.chars().count() > MAX_SUBSCRIPTION_NAME_LENGTH {
+        return Err(ApplicationError::BadRequest(format!(
+            "Calendar name too long (max {MAX_SUBSCRIPTION_NAME_LENGTH} characters)"
+        )));
+    }
+    if name.chars().any(char::is_control) {
+        return Err(ApplicationError::BadRequest(
+            "Calendar name cannot contain control characters".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn default_subscription_name(url: &str) -> String {
+    if let Some(country) = holiday_country_for_url(url) {
+        return country.calendar_name();
+    }
+    if is_birthdays_url(url) {
+        return BIRTHDAYS_CALENDAR_NAME.to_string();
+    }
+    Url::parse(url)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_string))
+        .unwrap_or_else(|| "Subscribed calendar".to_string())
+}
+
+/// Mirror row for an event generated here rather than read from a feed
+pub(crate) fn generated_event(
+    uid: String,
+    summary: String,
+    description: Option<String>,
+    timing: EventTiming,
+    rrule: Option<String>,
+) -> SubscribedEventWrite {
+    let etag = compute_event_etag(&EventEtagInput {
+        uid: uid.clone(),
+        summary: summary.clone(),
+        description: description.clone(),
+        location: None,
+        timing: timing.clone(),
+        status: EventStatus::Confirmed,
+        rrule: rrule.clone(),
+        visibility: EventVisibility::Public,
+        reminder_note: None,
+        attendees: Vec::new(),
+    });
+    SubscribedEventWrite {
+        uid,
+        summary,
+        description,
+        location: None,
+        timing,
+        status: EventStatus::Confirmed,
+        rrule,
+        etag,
+    }
+}
+
+/// Swap in `events` as the subscription's mirror, leaving it untouched when
+/// nothing changed; whether anything did
+async fn replace_mirror(
+    tx: &mut SubscriptionTransaction<'_>,
+    subscription_id: Uuid,
+    events: &[SubscribedEventWrite],
+) -> Result<bool, ApplicationError> {
+    if tx
+        .lock_subscription(subscription_id)
+        .await
+        .map_err(storage_error)?
+        .is_none()
+    {
+        return Err(ApplicationError::NotFound(format!(
+            "Subscription not found: {subscription_id}"
+        )));
+    }
+
+    let current = tx
+        .subscribed_event_etags(subscription_id)
+        .await
+        .map_err(storage_error)?;
+    let changed = current.len() != events.len()
+        || events
+            .iter()
+            .any(|event| current.get(&event.uid) != Some(&event.etag));
+
+    if changed {
+        tx.replace_subscribed_events(subscription_id, events)
+            .await
+            .map_err(storage_error)?;
+    }
+    Ok(changed)
+}
+
+async fn store_holidays(
+    tx: &mut SubscriptionTransaction<'_>,
+    subscription_id: Uuid,
+    country: &HolidayCountry,
+) -> Result<FeedApplied, ApplicationError> {
+    let now = Utc::now();
+    let year = now.year();
+    let events = holiday_events(country, [year, year + 1]);
+    let changed = replace_mirror(tx, subscription_id, &events).await?;
+
+    let next_year = NaiveDate::from_ymd_opt(year + 1, 1, 1)
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map_or(now + Duration::days(365), |start| start.and_utc());
+    tx.record_fetch_success(
+        subscription_id,
+        FetchSuccess {
+            http_etag: None,
+            http_last_modified: None,
+            next_fetch_at: next_year,
+            changed,
+        },
+    )
+    .await
+    .map_err(storage_error)?;
+
+    Ok(FeedApplied {
+        imported: events.len(),
+        skipped: 0,
+        changed,
+    })
+}
+
+async fn store_birthdays(
+    tx: &mut SubscriptionTransaction<'_>,
+    subscription_id: Uuid,
+    send_digest: bool,
+) -> Result<FeedApplied, ApplicationError> {
+    let subscription = tx
+        .lock_subscription(subscription_id)
+        .await
+        .map_err(storage_error)?
+        .ok_or_else(|| {
+            ApplicationError::NotFound(format!("Subscription not found: {subscription_id}"))
+        })?;
+    let user_id = UserId::new(subscription.user_id);
+    let timezone = tx.user_timezone(user_id).await.map_err(storage_error)?;
+    // Stored after parsing, so it always parses again
+    let working_hours = tx
+        .user_working_hours(user_id)
+        .await
+        .map_err(storage_error)?
+        .and_then(|spec| WorkingHours::parse(&spec, &timezone).ok());
+    let contacts = tx.list_contacts(user_id).await.map_err(storage_error)?;
+
+    let now = Utc::now();
+    let today = now.with_timezone(&timezone.tz()).date_naive();
+    let birthdays = contact_birthdays(&contacts);
+    let events = birthday_events(&birthdays, today);
+    let changed = replace_mirror(tx, subscription_id, &events).await?;
+
+    if send_digest && let Some(message) = digest_message(&birthdays, today) {
+        tx.queue_outbox(&[OutboxPayload::TelegramNotification(TelegramNotification {
+            telegram_id: user_id.inner(),
+            message,
+        })])
+        .await
+        .map_err(storage_error)?;
+    }
+    tx.record_fetch_success(
+        subscription_id,
+        FetchSuccess {
+            http_etag: None,
+            http_last_modified: None,
+            next_fetch_at: next_refresh(now, &timezone, working_hours.as_ref()),
+            changed,
+        },
+    )
+    .await
+    .map_err(storage_error)?;
+
+    Ok(FeedApplied {
+        imported: events.len(),
+        skipped: contacts.len() - birthdays.len(),
+        changed,
+    })
+}
+
+#[derive(Debug)]
+struct ParsedFeed {
+    events: Vec<SubscribedEventWrite>,
+    skipped: usize,
+}
+
+/// Parse every VEVENT of a feed into mirror rows.
+///
+/// Events the calendar could not store (invalid ranges, duplicate UIDs,
+/// recurrence overrides) are counted as skipped instead of failing the feed;
+/// over-long text is truncated.
+fn parse_feed(
+    body: &str,
+    now: DateTime<Utc>,
+    text_limits: &EventTextLimits,
+) -> Result<ParsedFeed, ApplicationError> {
+    let history_cutoff = now - Duration::days(MIRROR_HISTORY_DAYS);
+    let mut events = Vec::new();
+    let mut seen = HashSet::new();
+    let mut skipped = 0;
+    let mut calendars = 0;
+
+    for calendar in crate::ical::calendar_parser(body) {
+        let calendar = calendar
+            .map_err(|err| ApplicationError::BadRequest(format!("Invalid calendar feed: {err}")))?;
+        calendars += 1;
+
+        for event in &calendar.events {
+            if event
+                .properties
+                .iter()
+                .any(|property| property.name == "RECURRENCE-ID")
+            {
+                skipped += 1;
+                continue;
+            }
+
+            let Some(event) = subscribed_event_write(event, text_limits) else {
+                skipped += 1;
+                continue;
+            };
+            if event.rrule.is_none() && event_end(&event.timing) < history_cutoff {
+                continue;
+            }
+            if events.len() >= MAX_SUBSCRIBED_EVENTS || !seen.insert(event.uid.clone()) {
+                skipped += 1;
+                continue;
+            }
+            events.push(event);
+        }
+    }
+
+    if calendars == 0 {
+        return Err(ApplicationError::BadRequest(
+            "Response is not an iCalendar feed".to_string(),
+        ));
+    }
+
+    Ok(ParsedFeed { events, skipped })
+}
+
+fn subscribed_event_write(
+    event: &ical::parser::ical::component::IcalEvent,
+    text_limits: &EventTextLimits,
+) -> Option<SubscribedEventWrite> {
+    let (
+        uid,
+        mut summary,
+        mut description,
+        mut location,
+        start,
+        end,
+        is_all_day,
+        is_floating,
+        rrule,
+        status,
+        timezone,
+    ) = crate::ical::ical_to_event_data(event).ok()?;
+    text_limits
+        .sanitize(
+            Some(&mut summary),
+            description.as_mut(),
+            location.as_mut(),
+            TextOverflow::Truncate,
+        )
+        .ok()?;
+    validate_event_fields(Some(&uid), rrule.as_deref()).ok()?;
+
+    let timing = if is_all_day {
+        EventTiming::AllDay {
+            start_date: start.date_naive(),
+            end_date: end.date_naive(),
+        }
+    } else if is_floating {
+        EventTiming::Floating {
+            start: start.naive_utc(),
+            end: end.naive_utc(),
+        }
+    } else {
+        EventTiming::Timed {
+            start,
+            end,
+            timezone: televent_domain::Timezone::parse(timezone).unwrap_or_default(),
+        }
+    };
+    timing.validate().ok()?;
+
+    let etag = compute_event_etag(&EventEtagInput {
+        uid: uid.clone(),
+        summary: summary.clone(),
+        description: description.clone(),
+        location: location.clone(),
+        timing: timing.clone(),
+        status,
+        rrule: rrule.clone(),
+        visibility: EventVisibility::Public,
+        reminder_note: None,
+        attendees: Vec::new(),
+    });
+
+    Some(SubscribedEventWrite {
+        uid,
+        summary,
+        description,
+        location,
+        timing,
+        status,
+        rrule,
+        etag,
+    })
+}
+
+fn event_end(timing: &EventTiming) -> DateTime<Utc> {
+    match timing {
+        EventTiming::Timed { end, .. } => *end,
+        // Read as UTC, off by at most a day; only used to age out old events
+        EventTiming::Floating { end, .. } => end.and_utc(),
+        EventTiming::AllDay { end_date, .. } => end_date
+            .and_hms_opt(0, 0, 0)
+            .expect("midnight is a valid time")
+            .and_utc(),
+    }
+}
+
+fn ical_render(event: SubscribedEvent) -> crate::ical::IcalEventRender {
+    crate::ical::IcalEventRender {
+        uid: event.uid,
+        summary: event.summary,
+        description: event.description,
+        location: event.location,
+        timing: event.timing,
+        status: event.status,
+        rrule: event.rrule,
+        visibility: EventVisibility::Public,
+        reminder_note: None,
+        sequence: 0,
+        created_at: event.updated_at,
+        updated_at: event.updated_at,
+    }
+}
+
+fn render_caldav_resource(event: SubscribedEvent) -> Result<CalDavEventResource, ApplicationError> {
+    let uid = event.uid.clone();
+    let etag = event.etag.clone();
+    let updated_at = event.updated_at;
+    let mut calendar_data = String::with_capacity(1024);
+    crate::ical::event_to_ical_into(&ical_render(event), &[], &mut calendar_data)?;
+
+    Ok(CalDavEventResource {
+        uid,
+        etag,
+        updated_at,
+        calendar_data,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 10, 17, 12, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn normalizes_webcal_and_rejects_internal_hosts() {
+        assert_eq!(
+            normalize_subscription_url(" webcal://calendar.example.com/team.ics ").unwrap(),
+            "https://calendar.example.com/team.ics"
+        );
+        assert!(normalize_subscription_url("https://calendar.google.com/ical/x/basic.ics").is_ok());
+
+        for url in [
+            "ftp://example.com/cal.ics",
+            "https://localhost/cal.ics",
+            "http://postgres:5432/",
+            "http://127.0.0.1/cal.ics",
+            "http://10.0.0.5/cal.ics",
+            "http://169.254.169.254/latest/meta-data",
+            "http://[::1]/cal.ics",
+            "http://[::ffff:192.168.1.1]/cal.ics",
+            "not a url",
+        ] {
+            assert!(
+                normalize_subscription_url(url).is_err(),
+                "{url} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn parse_feed_keeps_valid_events_and_counts_the_rest() {
+        let feed = "BEGIN:VCALENDAR\r\n\
+            VERSION:2.0\r\n\
+            BEGIN:VEVENT\r\n\
+            UID:standup\r\n\
+            SUMMARY:Standup\r\n\
+            DTSTART:20261020T090000Z\r\n\
+            DTEND:20261020T091500Z\r\n\
+            END:VEVENT\r\n\
+            BEGIN:VEVENT\r\n\
+            UID:standup\r\n\
+            SUMMARY:Duplicate\r\n\
+            DTSTART:20261021T090000Z\r\n\
+            DTEND:20261021T091500Z\r\n\
+            END:VEVENT\r\n\
+            BEGIN:VEVENT\r\n\
+            UID:backwards\r\n\
+            SUMMARY:Ends before it starts\r\n\
+            DTSTART:20261020T100000Z\r\n\
+            DTEND:20261020T090000Z\r\n\
+            END:VEVENT\r\n\
+            BEGIN:VEVENT\r\n\
+            UID:offsite\r\n\
+            SUMMARY:Offsite\r\n\
+            DTSTART;VALUE=DATE:20261102\r\n\
+            DTEND;VALUE=DATE:20261104\r\n\
+            END:VEVENT\r\n\
+            BEGIN:VEVENT\r\n\
+            UID:ancient\r\n\
+            SUMMARY:Long ago\r\n\
+            DTSTART:20240101T090000Z\r\n\
+            DTEND:20240101T100000Z\r\n\
+            END:VEVENT\r\n\
+            END:VCALENDAR\r\n";
+
+        let parsed = parse_feed(feed, now(), &EventTextLimits::default()).unwrap();
+
+        let uids: Vec<&str> = parsed.events.iter().map(|e| e.uid.as_str()).collect();
+        assert_eq!(uids, vec!["standup", "offsite"]);
+        assert_eq!(parsed.skipped, 2);
+        assert!(matches!(
+            parsed.events[1].timing,
+            EventTiming::AllDay { .. }
+        ));
+    }
+
+    #[test]
+    fn parse_feed_rejects_non_calendar_bodies() {
+        assert!(parse_feed("<html>Sign in</html>", now(), &EventTextLimits::default()).is_err());
+        assert!(parse_feed("", now(), &EventTextLimits::default()).is_err());
+    }
+}
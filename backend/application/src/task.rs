@@ -0,0 +1,249 @@
+//! Time blocking: tasks with a duration, planned into free time.
+//!
+//! A plan takes the user's open tasks that have no block yet, oldest first,
+//! and gives each the first free slot of its length left in the day, from
+//! the user's own and subscribed calendars. Each block is a tentative event
+//! linked to its task. Confirming the event, from the bot or any client,
+//! confirms the block; at its end the user is asked whether the task is done.
+
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, TimeZone, Utc};
+use televent_domain::{EventStatus, MAX_SUMMARY_LENGTH, Timezone};
+use televent_storage::task::{Task, TaskBlock};
+use uuid::Uuid;
+
+use crate::{ApplicationError, FreeBusy, SlotSearch, UserId, WorkingHours};
+
+/// Shortest task, in minutes.
+pub const MIN_TASK_MINUTES: i64 = 5;
+
+/// Longest task, in minutes; longer work should be split up.
+pub const MAX_TASK_MINUTES: i64 = 480;
+
+/// Open tasks one user may keep at a time.
+pub const MAX_OPEN_TASKS_PER_USER: usize = 100;
+
+/// A task as its owner sees it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaskView {
+    pub id: Uuid,
+    pub title: String,
+    pub duration: Duration,
+    /// The live block planned for the task; `None` until it is planned, and
+    /// again once its event is cancelled, deleted or no longer timed
+    pub block: Option<TaskBlockView>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// The event planned for a task.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TaskBlockView {
+    pub event_id: Uuid,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    /// Whether the user confirmed the event; planned blocks are tentative
+    pub confirmed: bool,
+}
+
+impl From<TaskBlock> for TaskBlockView {
+    fn from(block: TaskBlock) -> Self {
+        Self {
+            event_id: block.event_id,
+            start: block.start,
+            end: block.end,
+            confirmed: block.status == EventStatus::Confirmed,
+        }
+    }
+}
+
+impl From<Task> for TaskView {
+    fn from(task: Task) -> Self {
+        Self {
+            id: task.id,
+            title: task.title,
+            duration: Duration::minutes(i64::from(task.duration_minutes)),
+            block: task
+                .block
+                .filter(|block| block.status != EventStatus::Cancelled)
+                .map(TaskBlockView::from),
+            completed_at: task.completed_at,
+            created_at: task.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CreateTaskCommand {
+    pub user_id: UserId,
+    pub title: String,
+    pub duration: Duration,
+}
+
+impl CreateTaskCommand {
+    pub(crate) fn validate(&self) -> Result<(), ApplicationError> {
+        let title = self.title.trim();
+        if title.is_empty() {
+            return Err(ApplicationError::BadRequest(
+                "Title cannot be empty".to_string(),
+            ));
+        }
+        // The title becomes the summary of the task's block
+        televent_domain::validate_length("Title", title, MAX_SUMMARY_LENGTH)
+            .map_err(ApplicationError::BadRequest)?;
+        televent_domain::validate_no_control_chars("Title", title)
+            .map_err(ApplicationError::BadRequest)?;
+
+        let minutes = self.duration.num_minutes();
+        if self.duration != Duration::minutes(minutes)
+            || !(MIN_TASK_MINUTES..=MAX_TASK_MINUTES).contains(&minutes)
+        {
+            return Err(ApplicationError::BadRequest(format!(
+                "Tasks must take whole minutes between {MIN_TASK_MINUTES} and \
+                 {MAX_TASK_MINUTES}"
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+/// A block a plan put on the calendar.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlannedBlock {
+    pub task_id: Uuid,
+    pub event_id: Uuid,
+    pub title: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+/// What planning a day did.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TaskPlan {
+    /// New tentative blocks, earliest first
+    pub blocks: Vec<PlannedBlock>,
+    /// Open tasks without a block that found no slot long enough
+    pub unplanned: Vec<TaskView>,
+}
+
+/// The part of `day` (in `timezone`) still ahead at `now`, which a plan for
+/// that day fills; `None` once the day is over
+#[must_use]
+pub fn plan_window(
+    day: NaiveDate,
+    now: DateTime<Utc>,
+    timezone: &Timezone,
+) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+    let tz = timezone.tz();
+    let midnight = |day: NaiveDate| {
+        tz.from_local_datetime(&day.and_time(NaiveTime::MIN))
+            .earliest()
+            .map_or_else(
+                || day.and_time(NaiveTime::MIN).and_utc(),
+                |local| local.with_timezone(&Utc),
+            )
+    };
+    let start = midnight(day).max(now);
+    let end = midnight(day + Duration::days(1));
+
+    (start < end).then_some((start, end))
+}
+
+/// First free slot of `duration` in the window of `free_busy`, from `from`
+pub(crate) fn next_block(
+    free_busy: &FreeBusy,
+    from: DateTime<Utc>,
+    duration: Duration,
+    working_hours: Option<&WorkingHours>,
+) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+    let from = from.max(free_busy.window_start());
+    let search = SlotSearch {
+        from,
+        duration,
+        within: free_busy.window_end() - from,
+        working_hours: working_hours.cloned(),
+        count: 1,
+    };
+    free_busy
+        .free_slots(&search)
+        .first()
+        .map(|slot| (slot.start, slot.end))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use televent_domain::EventTiming;
+
+    fn at(hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 3, 2, hour, minute, 0).unwrap()
+    }
+
+    fn command(title: &str, minutes: i64) -> CreateTaskCommand {
+        CreateTaskCommand {
+            user_id: UserId::new(1),
+            title: title.to_string(),
+            duration: Duration::minutes(minutes),
+        }
+    }
+
+    #[test]
+    fn validates_title_and_length() {
+        assert!(command("Write report", 45).validate().is_ok());
+        assert!(command("  ", 45).validate().is_err());
+        assert!(command("Write report", 2).validate().is_err());
+        assert!(command("Write report", 600).validate().is_err());
+    }
+
+    #[test]
+    fn plan_window_covers_the_rest_of_the_local_day() {
+        let berlin = Timezone::parse("Europe/Berlin").unwrap();
+        let day = NaiveDate::from_ymd_opt(2026, 3, 2).unwrap();
+
+        // 09:00 UTC is 10:00 in Berlin; the day ends at 23:00 UTC
+        assert_eq!(
+            plan_window(day, at(9, 0), &berlin),
+            Some((at(9, 0), at(23, 0)))
+        );
+        // Planning tomorrow ahead of time starts at its midnight
+        let tomorrow = day + Duration::days(1);
+        assert_eq!(
+            plan_window(tomorrow, at(9, 0), &berlin),
+            Some((at(23, 0), at(23, 0) + Duration::days(1)))
+        );
+        assert_eq!(plan_window(day, at(23, 30), &berlin), None);
+    }
+
+    #[test]
+    fn blocks_go_around_busy_time_and_each_other() {
+        let mut free_busy = FreeBusy::new(at(9, 0), at(18, 0), &Timezone::utc());
+        free_busy
+            .add_event(
+                &EventTiming::Timed {
+                    start: at(9, 30),
+                    end: at(11, 0),
+                    timezone: Timezone::utc(),
+                },
+                None,
+            )
+            .unwrap();
+
+        let first = next_block(&free_busy, at(9, 0), Duration::minutes(60), None).unwrap();
+        assert_eq!(first, (at(11, 0), at(12, 0)));
+        free_busy.block(first.0, first.1);
+
+        let second = next_block(&free_busy, at(9, 0), Duration::minutes(30), None).unwrap();
+        assert_eq!(second, (at(9, 0), at(9, 30)));
+        free_busy.block(second.0, second.1);
+
+        let hours = WorkingHours::parse("09:00-12:30", &Timezone::utc()).ok();
+        assert_eq!(
+            next_block(&free_busy, at(9, 0), Duration::minutes(45), hours.as_ref()),
+            None
+        );
+        assert_eq!(
+            next_block(&free_busy, at(9, 0), Duration::minutes(30), hours.as_ref()),
+            Some((at(12, 0), at(12, 30)))
+        );
+    }
+}
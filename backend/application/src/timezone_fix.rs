@@ -0,0 +1,209 @@
+//! Moving events saved in the wrong timezone.
+//!
+//! Someone whose timezone was set wrong for a while has events at the right
+//! wall-clock times in the wrong zone. A fix reads each timed event's start
+//! and end as wall-clock times in the zone it was saved in and keeps those
+//! times in the zone meant, rewriting every event in one transaction that
+//! bumps the calendar's sync token once. A series' UTC `UNTIL` moves with
+//! it, so the series still ends on the same occurrence. Guests of events
+//! still to come are told the time moved. All-day and floating events have
+//! no zone to fix.
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use televent_domain::{EventTiming, Timezone};
+
+use crate::ical_quirks::local_to_utc;
+use crate::{ApplicationError, UserId};
+
+/// Most events one fix may move; a longer stretch is fixed a range at a time.
+pub const MAX_TIMEZONE_FIX_EVENTS: usize = 2000;
+
+/// Move the timed events saved in `from` to the same wall-clock times in `to`
+#[derive(Debug, Clone)]
+pub struct TimezoneFixCommand {
+    pub user_id: UserId,
+    /// Zone the events were saved in by mistake
+    pub from: Timezone,
+    /// Zone the events were meant to be in
+    pub to: Timezone,
+    /// Only events starting at or after this
+    pub start: Option<DateTime<Utc>>,
+    /// Only events starting before this
+    pub end: Option<DateTime<Utc>>,
+}
+
+impl TimezoneFixCommand {
+    pub(crate) fn validate(&self) -> Result<(), ApplicationError> {
+        if self.from == self.to {
+            return Err(ApplicationError::BadRequest(
+                "the two timezones are the same".to_string(),
+            ));
+        }
+        if let (Some(start), Some(end)) = (self.start, self.end)
+            && end <= start
+        {
+            return Err(ApplicationError::BadRequest(
+                "range end must be after its start".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// The events a fix moves, or would move
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TimezoneFixSummary {
+    pub events: usize,
+    /// Start of the earliest one, before the fix
+    pub first_start: Option<DateTime<Utc>>,
+    /// Start of the latest one, before the fix
+    pub last_start: Option<DateTime<Utc>>,
+}
+
+impl TimezoneFixSummary {
+    /// Summary of events with these starts, in start order
+    pub(crate) fn of(starts: &[DateTime<Utc>]) -> Self {
+        Self {
+            events: starts.len(),
+            first_start: starts.first().copied(),
+            last_start: starts.last().copied(),
+        }
+    }
+}
+
+/// The wall-clock times `start` and `end` show in `from`, as times in `to`
+pub(crate) fn rezone(
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    from: &Timezone,
+    to: &Timezone,
+) -> EventTiming {
+    let wall = |instant: DateTime<Utc>| instant.with_timezone(&from.tz()).naive_local();
+    EventTiming::Timed {
+        start: local_to_utc(to.tz(), wall(start)),
+        end: local_to_utc(to.tz(), wall(end)),
+        timezone: to.clone(),
+    }
+}
+
+/// `rrule` with its UTC `UNTIL` shifted like [`rezone`] shifts the start.
+///
+/// Date and local-time `UNTIL`s are wall-clock values already and stay as
+/// they are.
+pub(crate) fn rezone_rrule(rrule: &str, from: &Timezone, to: &Timezone) -> String {
+    rrule
+        .split(';')
+        .map(|part| {
+            let until = part
+                .split_once('=')
+                .filter(|(name, _)| name.trim().eq_ignore_ascii_case("UNTIL"))
+                .and_then(|(_, value)| {
+                    NaiveDateTime::parse_from_str(value.trim(), "%Y%m%dT%H%M%SZ").ok()
+                });
+            match until {
+                Some(until) => {
+                    let wall = until.and_utc().with_timezone(&from.tz()).naive_local();
+                    let moved = local_to_utc(to.tz(), wall);
+                    format!("UNTIL={}", moved.format("%Y%m%dT%H%M%SZ"))
+                }
+                None => part.to_string(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn zone(name: &str) -> Timezone {
+        Timezone::parse(name).unwrap()
+    }
+
+    #[test]
+    fn test_rezone_keeps_wall_clock_times() {
+        let london = zone("Europe/London");
+        let berlin = zone("Europe/Berlin");
+        // 10:00-11:00 in London, in winter and in summer time
+        for (start, moved) in [
+            (
+                Utc.with_ymd_and_hms(2026, 1, 15, 10, 0, 0).unwrap(),
+                Utc.with_ymd_and_hms(2026, 1, 15, 9, 0, 0).unwrap(),
+            ),
+            (
+                Utc.with_ymd_and_hms(2026, 7, 15, 9, 0, 0).unwrap(),
+                Utc.with_ymd_and_hms(2026, 7, 15, 8, 0, 0).unwrap(),
+            ),
+        ] {
+            let timing = rezone(start, start + chrono::Duration::hours(1), &london, &berlin);
+            assert_eq!(
+                timing,
+                EventTiming::Timed {
+                    start: moved,
+                    end: moved + chrono::Duration::hours(1),
+                    timezone: berlin.clone(),
+                }
+            );
+        }
+
+        // 02:30 doesn't exist in Berlin on the night clocks go forward
+        let start = Utc.with_ymd_and_hms(2026, 3, 29, 2, 30, 0).unwrap();
+        let timing = rezone(
+            start,
+            start + chrono::Duration::hours(1),
+            &zone("UTC"),
+            &berlin,
+        );
+        assert_eq!(
+            timing.start_for_display(),
+            Utc.with_ymd_and_hms(2026, 3, 29, 1, 30, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_rezone_rrule_keeps_the_last_occurrence() {
+        let berlin = zone("Europe/Berlin");
+        let london = zone("Europe/London");
+        // Mondays at 10:00 Berlin time, ending with the one on 16 March
+        let start = Utc.with_ymd_and_hms(2026, 3, 2, 9, 0, 0).unwrap();
+        let rrule = "FREQ=WEEKLY;BYDAY=MO;UNTIL=20260316T090000Z";
+
+        let moved = rezone_rrule(rrule, &berlin, &london);
+        assert_eq!(moved, "FREQ=WEEKLY;BYDAY=MO;UNTIL=20260316T100000Z");
+
+        let timing = rezone(start, start + chrono::Duration::hours(1), &berlin, &london);
+        let occurrences =
+            televent_domain::next_occurrences(&moved, timing.start_for_display(), 10).unwrap();
+        assert_eq!(
+            occurrences,
+            [2, 9, 16].map(|day| Utc.with_ymd_and_hms(2026, 3, day, 10, 0, 0).unwrap())
+        );
+
+        // Wall-clock bounds don't depend on the zone
+        for rrule in ["FREQ=WEEKLY;UNTIL=20260316", "FREQ=DAILY;COUNT=3"] {
+            assert_eq!(rezone_rrule(rrule, &berlin, &london), rrule);
+        }
+    }
+
+    #[test]
+    fn test_fix_needs_two_zones_and_an_ordered_range() {
+        let at = Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap();
+        let command = |from: &str, start, end| TimezoneFixCommand {
+            user_id: UserId::new(1),
+            from: zone(from),
+            to: zone("Europe/Berlin"),
+            start,
+            end,
+        };
+        assert!(command("Europe/London", None, None).validate().is_ok());
+        assert!(command("Europe/London", Some(at), None).validate().is_ok());
+        assert!(command("Europe/Berlin", None, None).validate().is_err());
+        assert!(
+            command("Europe/London", Some(at), Some(at))
+                .validate()
+                .is_err()
+        );
+    }
+}
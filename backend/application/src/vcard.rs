@@ -0,0 +1,253 @@
+//! vCard parsing for CardDAV contacts
+//!
+//! Extracts the fields attendee autocomplete and the birthdays calendar need
+//! from a vCard (RFC 6350); the vCard itself is stored and served back
+//! unchanged.
+
+use chrono::NaiveDate;
+use televent_domain::{MAX_EMAIL_LENGTH, validate_email_address};
+
+use crate::ApplicationError;
+
+/// Maximum accepted vCard size; photos make up most of larger cards
+pub const MAX_VCARD_LENGTH: usize = 64 * 1024;
+const MAX_CONTACT_NAME_LENGTH: usize = 256;
+const MAX_TELEGRAM_USERNAME_LENGTH: usize = 32;
+
+/// Searchable fields of one vCard
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsedVcard {
+    pub full_name: String,
+    pub email: Option<String>,
+    pub telegram_username: Option<String>,
+    pub birthday: Option<Birthday>,
+}
+
+/// Day of birth from `BDAY`, with the year when the card has one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Birthday {
+    pub month: u32,
+    pub day: u32,
+    pub year: Option<i32>,
+}
+
+/// Parse a single vCard.
+///
+/// The display name falls back from `FN` to `N` and then to the email or
+/// Telegram username; a card with none of them is rejected.
+pub fn parse_vcard(body: &str) -> Result<ParsedVcard, ApplicationError> {
+    if body.len() > MAX_VCARD_LENGTH {
+        return Err(ApplicationError::BadRequest(format!(
+            "vCard too large (max {MAX_VCARD_LENGTH} bytes)"
+        )));
+    }
+
+    let card = ical::VcardParser::new(std::io::Cursor::new(body))
+        .next()
+        .ok_or_else(|| ApplicationError::BadRequest("Empty vCard".to_string()))?
+        .map_err(|err| ApplicationError::BadRequest(format!("Failed to parse vCard: {err}")))?;
+
+    let mut formatted_name = None;
+    let mut structured_name = None;
+    let mut email = None;
+    let mut telegram_username = None;
+    let mut birthday = None;
+
+    for prop in &card.properties {
+        let Some(value) = prop.value.as_deref().map(str::trim) else {
+            continue;
+        };
+        if value.is_empty() {
+            continue;
+        }
+
+        match prop.name.to_ascii_uppercase().as_str() {
+            "FN" => formatted_name = Some(unescape_value(value)),
+            "N" => structured_name = Some(name_from_components(value)),
+            "EMAIL" if email.is_none() => {
+                let address = value.trim_start_matches("mailto:");
+                if validate_email_address(address).is_ok() {
+                    email = Some(address.to_ascii_lowercase());
+                }
+            }
+            "X-TELEGRAM-USERNAME" | "X-TELEGRAM" | "IMPP" | "URL" | "X-SOCIALPROFILE"
+                if telegram_username.is_none() =>
+            {
+                telegram_username = telegram_username_from(&prop.name, value);
+            }
+            "BDAY" => birthday = birthday_from(value, omitted_year(prop.params.as_deref())),
+            _ => {}
+        }
+    }
+
+    let full_name = formatted_name
+        .filter(|name| !name.is_empty())
+        .or(structured_name.filter(|name| !name.is_empty()))
+        .or_else(|| email.clone())
+        .or_else(|| {
+            telegram_username
+                .as_ref()
+                .map(|username| format!("@{username}"))
+        })
+        .ok_or_else(|| {
+            ApplicationError::BadRequest(
+                "vCard needs a name, an email or a Telegram username".to_string(),
+            )
+        })?;
+    if full_name.chars().any(char::is_control) {
+        return Err(ApplicationError::BadRequest(
+            "Contact name cannot contain control characters".to_string(),
+        ));
+    }
+
+    Ok(ParsedVcard {
+        full_name: full_name.chars().take(MAX_CONTACT_NAME_LENGTH).collect(),
+        email: email.filter(|email| email.len() <= MAX_EMAIL_LENGTH),
+        telegram_username,
+        birthday,
+    })
+}
+
+/// Year Apple Contacts writes in place of one it was not given
+fn omitted_year(params: Option<&[(String, Vec<String>)]>) -> Option<&str> {
+    params?
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("X-APPLE-OMIT-YEAR"))
+        .and_then(|(_, values)| values.first())
+        .map(String::as_str)
+}
+
+/// `BDAY` as `19900415`, `1990-04-15`, either with a time after it, or
+/// `--0415` and `--04-15` without a year
+fn birthday_from(value: &str, omitted_year: Option<&str>) -> Option<Birthday> {
+    let date = value.split(['T', 't']).next().unwrap_or(value);
+    let (year, month_day) = match date.strip_prefix("--") {
+        Some(month_day) => (None, month_day),
+        None if date.len() >= 4 => (Some(&date[..4]), date[4..].trim_start_matches('-')),
+        None => return None,
+    };
+    let digits: String = month_day.chars().filter(|c| *c != '-').collect();
+    if digits.len() != 4 || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let month = digits[..2].parse().ok()?;
+    let day = digits[2..].parse().ok()?;
+    // Any leap year will do to accept February 29th
+    NaiveDate::from_ymd_opt(2000, month, day)?;
+
+    let year = year
+        .filter(|year| Some(*year) != omitted_year)
+        .and_then(|year| year.parse::<i32>().ok())
+        .filter(|year| *year > 0 && NaiveDate::from_ymd_opt(*year, month, day).is_some());
+    Some(Birthday { month, day, year })
+}
+
+/// Telegram username from an explicit property, an `IMPP` URI or a t.me link
+fn telegram_username_from(property: &str, value: &str) -> Option<String> {
+    let candidate = if property.eq_ignore_ascii_case("X-TELEGRAM-USERNAME")
+        || property.eq_ignore_ascii_case("X-TELEGRAM")
+    {
+        value
+    } else {
+        let lower = value.to_ascii_lowercase();
+        ["telegram:", "https://t.me/", "http://t.me/", "t.me/"]
+            .iter()
+            .find(|prefix| lower.starts_with(*prefix))
+            .map(|prefix| &value[prefix.len()..])?
+    };
+
+    let username = candidate
+        .trim_start_matches("//")
+        .trim_start_matches('@')
+        .trim_end_matches('/');
+    let valid = (5..=MAX_TELEGRAM_USERNAME_LENGTH).contains(&username.len())
+        && username
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_');
+    valid.then(|| username.to_string())
+}
+
+/// "Family;Given;Additional;Prefix;Suffix" as "Prefix Given Additional Family Suffix"
+fn name_from_components(value: &str) -> String {
+    let parts: Vec<&str> = value.split(';').map(str::trim).collect();
+    let part = |index: usize| parts.get(index).copied().unwrap_or("");
+    [part(3), part(1), part(2), part(0), part(4)]
+        .iter()
+        .filter(|part| !part.is_empty())
+        .map(|part| unescape_value(part))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn unescape_value(value: &str) -> String {
+    value
+        .replace("\\n", " ")
+        .replace("\\N", " ")
+        .replace("\\,", ",")
+        .replace("\\;", ";")
+        .replace("\\\\", "\\")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_vcard_extracts_searchable_fields() {
+        let card = "BEGIN:VCARD\r\nVERSION:3.0\r\nUID:abc\r\nFN:Alice Liddell\r\n\
+                    N:Liddell;Alice;;;\r\nEMAIL;TYPE=work:Alice@Example.com\r\n\
+                    IMPP:telegram:alice_l\r\nEND:VCARD\r\n";
+
+        let parsed = parse_vcard(card).unwrap();
+        assert_eq!(parsed.full_name, "Alice Liddell");
+        assert_eq!(parsed.email.as_deref(), Some("alice@example.com"));
+        assert_eq!(parsed.telegram_username.as_deref(), Some("alice_l"));
+    }
+
+    #[test]
+    fn test_parse_vcard_name_fallbacks() {
+        let structured = "BEGIN:VCARD\r\nVERSION:4.0\r\nN:Carroll;Lewis;;Rev.;\r\n\
+                          URL:https://t.me/lewis_c\r\nEND:VCARD\r\n";
+        let parsed = parse_vcard(structured).unwrap();
+        assert_eq!(parsed.full_name, "Rev. Lewis Carroll");
+        assert_eq!(parsed.telegram_username.as_deref(), Some("lewis_c"));
+
+        let email_only = "BEGIN:VCARD\r\nVERSION:3.0\r\nEMAIL:bob@example.com\r\nEND:VCARD\r\n";
+        assert_eq!(
+            parse_vcard(email_only).unwrap().full_name,
+            "bob@example.com"
+        );
+
+        let nameless = "BEGIN:VCARD\r\nVERSION:3.0\r\nNOTE:nobody\r\nEND:VCARD\r\n";
+        assert!(matches!(
+            parse_vcard(nameless),
+            Err(ApplicationError::BadRequest(_))
+        ));
+        assert!(parse_vcard("not a vcard").is_err());
+    }
+
+    #[test]
+    fn test_parse_vcard_birthday_formats() {
+        let birthday = |line: &str| {
+            parse_vcard(&format!(
+                "BEGIN:VCARD\r\nVERSION:3.0\r\nFN:Alice\r\n{line}\r\nEND:VCARD\r\n"
+            ))
+            .unwrap()
+            .birthday
+        };
+        let on = |month, day, year| Some(Birthday { month, day, year });
+
+        assert_eq!(birthday("BDAY:19900415"), on(4, 15, Some(1990)));
+        assert_eq!(birthday("BDAY:1990-04-15T00:00:00Z"), on(4, 15, Some(1990)));
+        assert_eq!(birthday("BDAY;VALUE=date:--0415"), on(4, 15, None));
+        assert_eq!(
+            birthday("BDAY;X-APPLE-OMIT-YEAR=1604:1604-02-29"),
+            on(2, 29, None)
+        );
+        // Not a date that year, but still a day to celebrate
+        assert_eq!(birthday("BDAY:2001-02-29"), on(2, 29, None));
+        assert_eq!(birthday("BDAY:1990-13-01"), None);
+        assert_eq!(birthday("BDAY:circa 1990"), None);
+        assert_eq!(birthday("NOTE:no birthday"), None);
+    }
+}
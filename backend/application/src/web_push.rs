@@ -0,0 +1,334 @@
+//! Web Push subscriptions for the web app.
+//!
+//! A browser that allows notifications subscribes with the push service it
+//! comes with and hands us the endpoint and its encryption keys. The worker
+//! copies the invites, reminders, changes and cancellations it sends the user
+//! in Telegram to each browser as a `web_push` message, so they arrive even
+//! with Telegram closed.
+//!
+//! The worker posts to stored endpoints, so only HTTPS URLs of the push
+//! services browsers ship with are accepted. Subscriptions the push service
+//! no longer knows, or whose expiration time has passed, are dropped.
+
+use base64::{
+    Engine,
+    engine::general_purpose::{URL_SAFE, URL_SAFE_NO_PAD},
+};
+use chrono::{DateTime, Utc};
+use televent_domain::{OutboxPayload, WebPushMessage};
+use televent_storage::web_push::{WebPushRepository, WebPushSubscriptionRecord};
+use url::{Host, Url};
+use uuid::Uuid;
+
+use crate::chat_webhook::ChatNotice;
+use crate::{ApplicationError, UserId, storage_error};
+
+/// Browsers per user; subscribing another drops the oldest
+pub const MAX_WEB_PUSH_SUBSCRIPTIONS_PER_USER: i64 = 10;
+const MAX_ENDPOINT_LENGTH: usize = 2048;
+/// Uncompressed P-256 point
+const P256DH_LENGTH: usize = 65;
+const AUTH_SECRET_LENGTH: usize = 16;
+/// Hosts of the push services of Chrome, Firefox, Safari and Edge
+const PUSH_SERVICE_HOSTS: [&str; 5] = [
+    "fcm.googleapis.com",
+    "android.googleapis.com",
+    ".push.services.mozilla.com",
+    ".push.apple.com",
+    ".notify.windows.com",
+];
+
+#[derive(Clone)]
+pub struct WebPushService {
+    subscriptions: WebPushRepository,
+}
+
+/// A browser's `PushSubscription`, as `toJSON()` gives it
+#[derive(Debug, Clone)]
+pub struct SubscribeWebPushCommand {
+    pub user_id: UserId,
+    pub endpoint: String,
+    /// Browser public key, base64url
+    pub p256dh: String,
+    /// Authentication secret, base64url
+    pub auth: String,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct WebPushSubscriptionView {
+    pub id: Uuid,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Where and for whom the worker encrypts one `web_push` message
+#[derive(Debug, Clone)]
+pub struct WebPushTarget {
+    pub endpoint: String,
+    pub p256dh: Vec<u8>,
+    pub auth: Vec<u8>,
+}
+
+impl WebPushService {
+    #[must_use]
+    pub fn new(subscriptions: WebPushRepository) -> Self {
+        Self { subscriptions }
+    }
+
+    /// Store a browser's subscription; subscribing the same browser again
+    /// refreshes its keys
+    pub async fn subscribe(
+        &self,
+        command: SubscribeWebPushCommand,
+    ) -> Result<WebPushSubscriptionView, ApplicationError> {
+        let endpoint = normalize_push_endpoint(&command.endpoint)?;
+        let p256dh = decode_key(&command.p256dh, P256DH_LENGTH, "p256dh")?;
+        if p256dh[0] != 0x04 {
+            return Err(ApplicationError::BadRequest(
+                "p256dh must be an uncompressed P-256 public key".to_string(),
+            ));
+        }
+        let auth = decode_key(&command.auth, AUTH_SECRET_LENGTH, "auth")?;
+        if command
+            .expires_at
+            .is_some_and(|expires| expires <= Utc::now())
+        {
+            return Err(ApplicationError::BadRequest(
+                "Subscription has already expired".to_string(),
+            ));
+        }
+
+        let subscription = self
+            .subscriptions
+            .upsert_subscription(
+                command.user_id,
+                &endpoint,
+                &URL_SAFE_NO_PAD.encode(p256dh),
+                &URL_SAFE_NO_PAD.encode(auth),
+                command.expires_at,
+                MAX_WEB_PUSH_SUBSCRIPTIONS_PER_USER,
+            )
+            .await
+            .map_err(storage_error)?;
+
+        Ok(subscription.into())
+    }
+
+    pub async fn unsubscribe(
+        &self,
+        user_id: UserId,
+        endpoint: &str,
+    ) -> Result<bool, ApplicationError> {
+        self.subscriptions
+            .delete_by_endpoint(user_id, endpoint.trim())
+            .await
+            .map_err(storage_error)
+    }
+
+    /// Queue a copy of `notice` for each of the user's browsers, unless they
+    /// turned the notice's topic off for push. Expired subscriptions are
+    /// dropped first.
+    ///
+    /// Copies are keyed by `source_id`, so queueing again for the same source
+    /// adds nothing. Returns how many copies were queued.
+    pub async fn queue_copies(
+        &self,
+        user_id: UserId,
+        source_id: Uuid,
+        notice: &ChatNotice,
+    ) -> Result<usize, ApplicationError> {
+        if !self
+            .subscriptions
+            .topic_enabled(user_id, notice.topic)
+            .await
+            .map_err(storage_error)?
+        {
+            return Ok(0);
+        }
+        self.subscriptions
+            .prune_expired(user_id)
+            .await
+            .map_err(storage_error)?;
+        let messages = self
+            .subscriptions
+            .list_subscriptions(user_id)
+            .await
+            .map_err(storage_error)?
+            .into_iter()
+            .map(|subscription| {
+                OutboxPayload::WebPush(WebPushMessage {
+                    subscription_id: subscription.id,
+                    user_id: user_id.inner(),
+                    source_id,
+                    title: notice.title.clone(),
+                    lines: notice.lines.clone(),
+                })
+            })
+            .collect::<Vec<_>>();
+        self.subscriptions
+            .queue_outbox(&messages)
+            .await
+            .map_err(storage_error)?;
+
+        Ok(messages.len())
+    }
+
+    /// Where to push; `None` once the subscription is gone or has expired
+    pub async fn target(
+        &self,
+        subscription_id: Uuid,
+    ) -> Result<Option<WebPushTarget>, ApplicationError> {
+        let Some(subscription) = self
+            .subscriptions
+            .get_subscription(subscription_id)
+            .await
+            .map_err(storage_error)?
+            .filter(|subscription| {
+                subscription
+                    .expires_at
+                    .is_none_or(|expires| expires > Utc::now())
+            })
+        else {
+            return Ok(None);
+        };
+        let decode = |value: &str| {
+            URL_SAFE_NO_PAD.decode(value).map_err(|_| {
+                ApplicationError::Internal(format!(
+                    "undecodable key on web push subscription {subscription_id}"
+                ))
+            })
+        };
+
+        Ok(Some(WebPushTarget {
+            p256dh: decode(&subscription.p256dh)?,
+            auth: decode(&subscription.auth)?,
+            endpoint: subscription.endpoint,
+        }))
+    }
+
+    pub async fn record_delivery(&self, subscription_id: Uuid) -> Result<(), ApplicationError> {
+        self.subscriptions
+            .mark_pushed(subscription_id)
+            .await
+            .map_err(storage_error)
+    }
+
+    /// Forget a subscription the push service says is gone
+    pub async fn remove_subscription(&self, subscription_id: Uuid) -> Result<(), ApplicationError> {
+        self.subscriptions
+            .delete_subscription(subscription_id)
+            .await
+            .map_err(storage_error)?;
+        Ok(())
+    }
+}
+
+impl From<WebPushSubscriptionRecord> for WebPushSubscriptionView {
+    fn from(subscription: WebPushSubscriptionRecord) -> Self {
+        Self {
+            id: subscription.id,
+            expires_at: subscription.expires_at,
+            created_at: subscription.created_at,
+        }
+    }
+}
+
+/// Check that `raw` is an HTTPS endpoint of a browser push service
+pub fn normalize_push_endpoint(raw: &str) -> Result<String, ApplicationError> {
+    let raw = raw.trim();
+    if raw.len() > MAX_ENDPOINT_LENGTH {
+        return Err(ApplicationError::BadRequest(format!(
+            "Push endpoint too long (max {MAX_ENDPOINT_LENGTH} characters)"
+        )));
+    }
+    let url = Url::parse(raw)
+        .map_err(|_| ApplicationError::BadRequest("Invalid push endpoint".to_string()))?;
+    if url.scheme() != "https" || url.port().is_some() {
+        return Err(ApplicationError::BadRequest(
+            "Push endpoint must start with https:// and use the default port".to_string(),
+        ));
+    }
+
+    let host = match url.host() {
+        Some(Host::Domain(domain)) => domain.trim_end_matches('.').to_ascii_lowercase(),
+        _ => String::new(),
+    };
+    let known = PUSH_SERVICE_HOSTS
+        .iter()
+        .any(|known| match known.strip_prefix('.') {
+            Some(parent) => host.ends_with(known) || host == parent,
+            None => host == *known,
+        });
+    if !known {
+        return Err(ApplicationError::BadRequest(
+            "Not the endpoint of a browser push service".to_string(),
+        ));
+    }
+
+    Ok(url.to_string())
+}
+
+/// Decode a base64url key of `length` bytes; browsers leave out the padding
+/// but some libraries keep it
+fn decode_key(value: &str, length: usize, name: &str) -> Result<Vec<u8>, ApplicationError> {
+    let value = value.trim();
+    URL_SAFE_NO_PAD
+        .decode(value)
+        .or_else(|_| URL_SAFE.decode(value))
+        .ok()
+        .filter(|key| key.len() == length)
+        .ok_or_else(|| {
+            ApplicationError::BadRequest(format!("{name} must be {length} bytes of base64url"))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_endpoint_must_belong_to_a_push_service() {
+        for endpoint in [
+            "https://fcm.googleapis.com/fcm/send/abc:def",
+            "https://updates.push.services.mozilla.com/wpush/v2/gAAAA",
+            "https://web.push.apple.com/QGv2",
+            "https://wns2-par02p.notify.windows.com/w/?token=abc",
+        ] {
+            assert!(normalize_push_endpoint(endpoint).is_ok(), "{endpoint}");
+        }
+
+        for endpoint in [
+            "http://fcm.googleapis.com/fcm/send/abc",
+            "https://fcm.googleapis.com:8443/fcm/send/abc",
+            "https://fcm.googleapis.com.evil.example/fcm/send/abc",
+            "https://evilpush.apple.com/QGv2",
+            "https://127.0.0.1/push",
+            "not a url",
+        ] {
+            assert!(normalize_push_endpoint(endpoint).is_err(), "{endpoint}");
+        }
+    }
+
+    #[test]
+    fn test_keys_accept_padded_and_unpadded_base64url() {
+        let auth = [7u8; AUTH_SECRET_LENGTH];
+        assert_eq!(
+            decode_key(&URL_SAFE_NO_PAD.encode(auth), AUTH_SECRET_LENGTH, "auth").unwrap(),
+            auth
+        );
+        assert_eq!(
+            decode_key(&URL_SAFE.encode(auth), AUTH_SECRET_LENGTH, "auth").unwrap(),
+            auth
+        );
+        assert!(
+            decode_key(
+                &URL_SAFE_NO_PAD.encode([7u8; 15]),
+                AUTH_SECRET_LENGTH,
+                "auth"
+            )
+            .is_err()
+        );
+        assert!(decode_key("not base64!", AUTH_SECRET_LENGTH, "auth").is_err());
+    }
+}
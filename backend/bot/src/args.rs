@@ -0,0 +1,462 @@
+//! Command arguments
+//!
+//! Each command declares its arguments as a [`Spec`]. [`Spec::parse`] splits
+//! the text after the command the way a shell does, so a "quoted name" stays
+//! one argument, checks every argument against its [`Kind`] and reports what
+//! doesn't fit; [`Spec::usage`] renders the usage line shown with the error.
+
+use televent_domain::tags::normalize_tag;
+use thiserror::Error;
+use uuid::Uuid;
+
+/// What an argument may be
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Kind {
+    /// Any single word
+    Word,
+    /// Every remaining word, joined by spaces; only valid last
+    Text,
+    /// An event, device or subscription id
+    Uuid,
+    /// A Telegram `@username`
+    Username,
+    /// An email address
+    Email,
+    /// A `@username`, an email address or a contact's name
+    Invitee,
+    /// One of a fixed set of words, matched case-insensitively
+    Choice(&'static [&'static str]),
+    /// A `#hashtag`
+    Tag,
+}
+
+impl Kind {
+    /// Whether `value` is one of these
+    fn accepts(self, value: &str) -> bool {
+        match self {
+            Self::Word | Self::Text => true,
+            Self::Uuid => Uuid::parse_str(value).is_ok(),
+            Self::Username => value.strip_prefix('@').is_some_and(is_username),
+            Self::Email => is_email(value),
+            Self::Invitee => match value.strip_prefix('@') {
+                Some(username) => is_username(username),
+                None => !value.contains('@') || is_email(value),
+            },
+            Self::Choice(choices) => choices
+                .iter()
+                .any(|choice| choice.eq_ignore_ascii_case(value)),
+            Self::Tag => value.starts_with('#') && normalize_tag(value).is_some(),
+        }
+    }
+
+    /// What `value` should have been, for errors about the argument `name`
+    fn expected(self, name: &str) -> String {
+        match self {
+            Self::Word | Self::Text => name.to_string(),
+            Self::Uuid => "a valid ID".to_string(),
+            Self::Username => "a @username".to_string(),
+            Self::Email => "an email address".to_string(),
+            Self::Invitee => "a @username, email address or name".to_string(),
+            Self::Choice(_) => format!("one of {}", name.replace('|', ", ")),
+            Self::Tag => "a #tag".to_string(),
+        }
+    }
+}
+
+/// Telegram usernames are 5 to 32 letters, digits and underscores
+fn is_username(value: &str) -> bool {
+    (5..=32).contains(&value.len()) && value.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn is_email(value: &str) -> bool {
+    value.split_once('@').is_some_and(|(local, domain)| {
+        !local.is_empty() && domain.contains('.') && !domain.contains('@')
+    })
+}
+
+/// One argument of a command
+#[derive(Debug, Clone, Copy)]
+pub struct Arg {
+    pub name: &'static str,
+    pub kind: Kind,
+    pub required: bool,
+    /// Takes every remaining argument; only valid last
+    pub repeated: bool,
+}
+
+impl Arg {
+    /// A required argument
+    pub const fn new(name: &'static str, kind: Kind) -> Self {
+        Self {
+            name,
+            kind,
+            required: true,
+            repeated: false,
+        }
+    }
+
+    pub const fn optional(mut self) -> Self {
+        self.required = false;
+        self
+    }
+
+    pub const fn repeated(mut self) -> Self {
+        self.repeated = true;
+        self
+    }
+}
+
+/// The arguments a command takes
+#[derive(Debug, Clone, Copy)]
+pub struct Spec {
+    /// The command and any subcommand, such as `/device add`
+    pub command: &'static str,
+    pub args: &'static [Arg],
+    /// Options such as `--readonly`, without the dashes
+    pub flags: &'static [&'static str],
+}
+
+/// Why arguments don't fit their [`Spec`]
+#[derive(Debug, Error, PartialEq)]
+pub enum ArgError {
+    #[error("Missing {0}")]
+    Missing(&'static str),
+
+    #[error("{value} is not {expected}")]
+    Invalid { value: String, expected: String },
+
+    #[error("Unexpected {0}")]
+    Unexpected(String),
+
+    #[error("Unknown option --{0}")]
+    UnknownFlag(String),
+
+    #[error("A quote is never closed")]
+    UnclosedQuote,
+}
+
+/// Arguments that fit their [`Spec`]
+#[derive(Debug, Default)]
+pub struct Args {
+    values: Vec<(&'static str, String)>,
+    flags: Vec<&'static str>,
+}
+
+impl Args {
+    /// The value of `name`, or its first one if repeated
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.values
+            .iter()
+            .find(|(arg, _)| *arg == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Every value of `name`
+    pub fn all<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.values
+            .iter()
+            .filter(move |(arg, _)| *arg == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The id given as `name`; its [`Kind::Uuid`] was checked by the parser
+    pub fn uuid(&self, name: &str) -> Option<Uuid> {
+        self.get(name).and_then(|value| Uuid::parse_str(value).ok())
+    }
+
+    /// Whether `--name` was given
+    pub fn flag(&self, name: &str) -> bool {
+        self.flags.contains(&name)
+    }
+}
+
+impl Spec {
+    /// Usage line, such as `/invite <event_id> [invitee]...`
+    pub fn usage(&self) -> String {
+        let mut usage = self.command.to_string();
+        for arg in self.args {
+            let name = match (arg.kind, arg.required) {
+                (Kind::Text, true) => format!("<{}...>", arg.name),
+                (Kind::Text, false) => format!("[{}...]", arg.name),
+                (_, true) => format!("<{}>", arg.name),
+                (_, false) => format!("[{}]", arg.name),
+            };
+            usage.push(' ');
+            usage.push_str(&name);
+            if arg.repeated {
+                usage.push_str("...");
+            }
+        }
+        for flag in self.flags {
+            usage.push_str(&format!(" [--{flag}]"));
+        }
+        usage
+    }
+
+    /// Read `input`, the text after the command, against this spec
+    pub fn parse(&self, input: &str) -> Result<Args, ArgError> {
+        let mut args = Args::default();
+        let mut values = Vec::new();
+        for token in tokenize(input)? {
+            match token.text.strip_prefix("--").filter(|_| !token.quoted) {
+                Some(flag) => match self.flags.iter().find(|known| **known == flag) {
+                    Some(known) => args.flags.push(known),
+                    None => return Err(ArgError::UnknownFlag(flag.to_string())),
+                },
+                None => values.push(token.text),
+            }
+        }
+
+        let mut values = values.into_iter().peekable();
+        for arg in self.args {
+            if arg.kind == Kind::Text {
+                let text = values.by_ref().collect::<Vec<_>>().join(" ");
+                if !text.is_empty() {
+                    args.values.push((arg.name, text));
+                }
+            }
+            let mut taken = 0;
+            while let Some(value) = values.next_if(|_| taken == 0 || arg.repeated) {
+                if !arg.kind.accepts(&value) {
+                    return Err(ArgError::Invalid {
+                        expected: arg.kind.expected(arg.name),
+                        value,
+                    });
+                }
+                args.values.push((arg.name, value));
+                taken += 1;
+            }
+            if arg.required && args.get(arg.name).is_none() {
+                return Err(ArgError::Missing(arg.name));
+            }
+        }
+
+        match values.next() {
+            Some(extra) => Err(ArgError::Unexpected(extra)),
+            None => Ok(args),
+        }
+    }
+}
+
+/// The text after the command word of a message, such as `a b` of
+/// `/cmd@bot a b`
+pub fn after_command(text: &str) -> &str {
+    text.trim_start()
+        .split_once(char::is_whitespace)
+        .map_or("", |(_, rest)| rest.trim())
+}
+
+/// The first word of `input` and the text after it, for commands with
+/// subcommands such as `/device add`
+pub fn subcommand(input: &str) -> (Option<&str>, &str) {
+    let input = input.trim();
+    match input.split_once(char::is_whitespace) {
+        Some((word, rest)) => (Some(word), rest.trim_start()),
+        None => ((!input.is_empty()).then_some(input), ""),
+    }
+}
+
+struct Token {
+    text: String,
+    quoted: bool,
+}
+
+/// Split `input` at whitespace outside quotes; `\` keeps the next character
+/// as it is. Quotes only open at the start of a word, so O'Neil needs none,
+/// and phones turn them into “curly” ones, which work too.
+fn tokenize(input: &str) -> Result<Vec<Token>, ArgError> {
+    let mut tokens = Vec::new();
+    let mut current: Option<Token> = None;
+    let mut closing: Option<char> = None;
+    let mut chars = input.chars();
+    while let Some(c) = chars.next() {
+        let token = current.get_or_insert_with(|| Token {
+            text: String::new(),
+            quoted: false,
+        });
+        let fresh = token.text.is_empty() && !token.quoted;
+        match (c, closing) {
+            ('\\', _) => {
+                if let Some(escaped) = chars.next() {
+                    token.text.push(escaped);
+                }
+            }
+            (c, Some(close)) if c == close => closing = None,
+            (_, Some(_)) => token.text.push(c),
+            ('"' | '\'', None) if fresh => {
+                closing = Some(c);
+                token.quoted = true;
+            }
+            ('“', None) if fresh => {
+                closing = Some('”');
+                token.quoted = true;
+            }
+            (c, None) if c.is_whitespace() => {
+                if let Some(token) = current.take().filter(|t| t.quoted || !t.text.is_empty()) {
+                    tokens.push(token);
+                }
+            }
+            (c, None) => token.text.push(c),
+        }
+    }
+    if closing.is_some() {
+        return Err(ArgError::UnclosedQuote);
+    }
+    tokens.extend(current.filter(|token| token.quoted || !token.text.is_empty()));
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STATUS: &[&str] = &["accept", "decline"];
+    const INVITE: Spec = Spec {
+        command: "/invite",
+        args: &[
+            Arg::new("event_id", Kind::Uuid),
+            Arg::new("invitee", Kind::Invitee).optional().repeated(),
+        ],
+        flags: &["readonly"],
+    };
+    const RSVP: Spec = Spec {
+        command: "/rsvp",
+        args: &[
+            Arg::new("event_id", Kind::Uuid),
+            Arg::new("accept|decline", Kind::Choice(STATUS)),
+        ],
+        flags: &[],
+    };
+    const ADD: Spec = Spec {
+        command: "/subscribe add",
+        args: &[
+            Arg::new("url", Kind::Word),
+            Arg::new("name", Kind::Text).optional(),
+        ],
+        flags: &[],
+    };
+    const AGENDA: Spec = Spec {
+        command: "/list",
+        args: &[Arg::new("#tag", Kind::Tag).optional().repeated()],
+        flags: &[],
+    };
+    const EVENT_ID: &str = "6f1c3f0e-9a8b-4c2d-8e7f-6a5b4c3d2e1f";
+
+    fn words(input: &str) -> Vec<String> {
+        tokenize(input)
+            .unwrap()
+            .into_iter()
+            .map(|token| token.text)
+            .collect()
+    }
+
+    #[test]
+    fn test_tokenize_quotes() {
+        assert_eq!(
+            words(r#"  @alice "Jane Doe"  'Bob O\'Neil' “Ann Lee” O'Neil x\ y "" "#),
+            [
+                "@alice",
+                "Jane Doe",
+                "Bob O'Neil",
+                "Ann Lee",
+                "O'Neil",
+                "x y",
+                ""
+            ]
+        );
+        assert!(matches!(
+            tokenize("\"Jane Doe"),
+            Err(ArgError::UnclosedQuote)
+        ));
+    }
+
+    #[test]
+    fn test_parse_against_spec() {
+        let args = INVITE
+            .parse(&format!(
+                "{EVENT_ID} @alice_b \"Jane Doe\" bob@example.com --readonly"
+            ))
+            .unwrap();
+        assert_eq!(args.uuid("event_id").unwrap().to_string(), EVENT_ID);
+        assert_eq!(
+            args.all("invitee").collect::<Vec<_>>(),
+            ["@alice_b", "Jane Doe", "bob@example.com"]
+        );
+        assert!(args.flag("readonly"));
+
+        // A quoted option is a value
+        let args = INVITE.parse(&format!("{EVENT_ID} \"--readonly\"")).unwrap();
+        assert!(!args.flag("readonly"));
+        assert_eq!(args.get("invitee"), Some("--readonly"));
+
+        assert_eq!(INVITE.parse("").unwrap_err(), ArgError::Missing("event_id"));
+        assert_eq!(
+            INVITE.parse("abc @alice").unwrap_err().to_string(),
+            "abc is not a valid ID"
+        );
+        assert_eq!(
+            INVITE
+                .parse(&format!("{EVENT_ID} @al"))
+                .unwrap_err()
+                .to_string(),
+            "@al is not a @username, email address or name"
+        );
+        assert_eq!(
+            INVITE.parse(&format!("{EVENT_ID} --quiet")).unwrap_err(),
+            ArgError::UnknownFlag("quiet".to_string())
+        );
+
+        assert_eq!(
+            RSVP.parse(&format!("{EVENT_ID} maybe"))
+                .unwrap_err()
+                .to_string(),
+            "maybe is not one of accept, decline"
+        );
+        assert_eq!(
+            AGENDA.parse("#work bob").unwrap_err().to_string(),
+            "bob is not a #tag"
+        );
+        assert_eq!(
+            AGENDA.parse("#Work #team-a").unwrap().all("#tag").count(),
+            2
+        );
+        assert_eq!(
+            RSVP.parse(&format!("{EVENT_ID} ACCEPT now")).unwrap_err(),
+            ArgError::Unexpected("now".to_string())
+        );
+        assert_eq!(
+            RSVP.parse(EVENT_ID).unwrap_err(),
+            ArgError::Missing("accept|decline")
+        );
+
+        let args = ADD
+            .parse("https://example.com/cal.ics Team   Holidays")
+            .unwrap();
+        assert_eq!(args.get("name"), Some("Team Holidays"));
+        assert_eq!(
+            ADD.parse("https://example.com/cal.ics")
+                .unwrap()
+                .get("name"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_usage_and_command_text() {
+        assert_eq!(
+            INVITE.usage(),
+            "/invite <event_id> [invitee]... [--readonly]"
+        );
+        assert_eq!(RSVP.usage(), "/rsvp <event_id> <accept|decline>");
+        assert_eq!(ADD.usage(), "/subscribe add <url> [name...]");
+
+        assert_eq!(
+            after_command("/device@televent_bot  add  Phone "),
+            "add  Phone"
+        );
+        assert_eq!(after_command("/list"), "");
+        assert_eq!(subcommand("add  My Phone"), (Some("add"), "My Phone"));
+        assert_eq!(subcommand("list"), (Some("list"), ""));
+        assert_eq!(subcommand(" "), (None, ""));
+    }
+}
@@ -2,6 +2,7 @@
 //!
 //! Defines all Telegram bot commands and their parsing logic
 
+use crate::args::{Arg, Kind, Spec};
 use teloxide::utils::command::BotCommands;
 
 /// All bot commands
@@ -17,24 +18,424 @@ pub enum Command {
     #[command(description = "List upcoming events")]
     List,
 
+    #[command(description = "Show today's agenda")]
+    Today,
+
+    #[command(description = "Show tomorrow's agenda")]
+    Tomorrow,
+
+    #[command(description = "Show the next 7 days")]
+    Week,
+
+    #[command(description = "Show the next 7 days with buttons to act on each event")]
+    Agenda,
+
     #[command(description = "Cancel/delete an event")]
     Cancel,
 
+    #[command(description = "Add a note to an event's reminders, e.g. /note <id> Bring laptop!")]
+    Note,
+
     #[command(description = "Manage CalDAV device passwords")]
     Device,
 
+    #[command(description = "Subscribe to external calendars and holidays")]
+    Subscribe,
+
     #[command(description = "Export calendar as .ics file")]
     Export,
 
-    #[command(description = "Invite someone to an event")]
+    #[command(description = "Invite people to an event")]
     Invite,
 
+    #[command(description = "Show invitees and their RSVP status")]
+    Attendees,
+
     #[command(description = "Respond to event invitations")]
     Rsvp,
 
+    #[command(description = "Find the next free slots, e.g. /slot 30m")]
+    Slot,
+
+    #[command(description = "Keep tasks to plan into your day, e.g. /task 45m Write report")]
+    Task,
+
+    #[command(description = "Plan your tasks into today's free time")]
+    Plan,
+
+    #[command(description = "Reserve weekly focus time, e.g. /focus add 2x2h")]
+    Focus,
+
+    #[command(description = "Show your meeting load, e.g. /stats 7d")]
+    Stats,
+
+    #[command(description = "Set your timezone, e.g. /timezone Europe/Berlin")]
+    Timezone,
+
+    #[command(
+        description = "Move events saved in the wrong timezone, e.g. /fixtz Europe/London Europe/Berlin"
+    )]
+    Fixtz,
+
+    #[command(description = "Set the email invites can reach you at")]
+    Email,
+
+    #[command(description = "Set your working hours, e.g. /hours Mon-Fri 09:00-17:00")]
+    Hours,
+
+    #[command(description = "Let someone else edit your calendar, e.g. /delegate @alice")]
+    Delegate,
+
     #[command(description = "Show help message")]
     Help,
 
     #[command(description = "Delete your account and all data (GDPR)")]
     DeleteAccount,
 }
+
+impl Command {
+    /// Name the command is typed as, without the slash
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Self::Start => "start",
+            Self::List => "list",
+            Self::Today => "today",
+            Self::Tomorrow => "tomorrow",
+            Self::Week => "week",
+            Self::Agenda => "agenda",
+            Self::Cancel => "cancel",
+            Self::Note => "note",
+            Self::Device => "device",
+            Self::Subscribe => "subscribe",
+            Self::Export => "export",
+            Self::Invite => "invite",
+            Self::Attendees => "attendees",
+            Self::Rsvp => "rsvp",
+            Self::Slot => "slot",
+            Self::Task => "task",
+            Self::Plan => "plan",
+            Self::Focus => "focus",
+            Self::Stats => "stats",
+            Self::Timezone => "timezone",
+            Self::Fixtz => "fixtz",
+            Self::Email => "email",
+            Self::Hours => "hours",
+            Self::Delegate => "delegate",
+            Self::Help => "help",
+            Self::DeleteAccount => "deleteaccount",
+        }
+    }
+
+    /// Whether the command with its arguments (`text` is the full message)
+    /// changes data rather than only showing it
+    pub fn mutates(&self, text: &str) -> bool {
+        let args: Vec<&str> = text.split_whitespace().skip(1).collect();
+        match self {
+            Self::Device => matches!(args.first(), Some(&("add" | "revoke"))),
+            Self::Subscribe => match args.first() {
+                Some(&("add" | "remove" | "birthdays")) => true,
+                // `/subscribe holidays` alone only lists the countries
+                Some(&"holidays") => args.len() >= 2,
+                _ => false,
+            },
+            // `/invite <event_id>` alone only shows suggestions, and
+            // `/note <event_id>` the current note
+            Self::Invite | Self::Rsvp | Self::Note => args.len() >= 2,
+            // Without an argument both only ask
+            Self::Timezone | Self::Email | Self::Hours => !args.is_empty(),
+            Self::Delegate => !matches!(args.first(), None | Some(&"list")),
+            Self::Task => !matches!(args.first(), None | Some(&"list")),
+            Self::Plan => true,
+            Self::Focus => matches!(args.first(), Some(&("add" | "remove"))),
+            _ => false,
+        }
+    }
+}
+
+/// Words /rsvp takes for each answer
+pub const RSVP_STATUSES: &[&str] = &[
+    "accept",
+    "accepted",
+    "yes",
+    "decline",
+    "declined",
+    "no",
+    "tentative",
+    "maybe",
+];
+
+pub const AGENDA: Spec = Spec {
+    command: "/list",
+    args: &[Arg::new("#tag", Kind::Tag).optional().repeated()],
+    flags: &[],
+};
+
+pub const TIMEZONE: Spec = Spec {
+    command: "/timezone",
+    args: &[Arg::new("zone", Kind::Word).optional()],
+    flags: &[],
+};
+
+pub const FIXTZ: Spec = Spec {
+    command: "/fixtz",
+    args: &[
+        Arg::new("old_tz", Kind::Word),
+        Arg::new("new_tz", Kind::Word),
+        Arg::new("range", Kind::Word).optional(),
+    ],
+    flags: &[],
+};
+
+pub const EMAIL: Spec = Spec {
+    command: "/email",
+    args: &[Arg::new("address|clear", Kind::Word).optional()],
+    flags: &[],
+};
+
+pub const EMAIL_VERIFY: Spec = Spec {
+    command: "/email verify",
+    args: &[Arg::new("code", Kind::Word)],
+    flags: &[],
+};
+
+pub const HOURS: Spec = Spec {
+    command: "/hours",
+    args: &[Arg::new("week|off", Kind::Text).optional()],
+    flags: &[],
+};
+
+pub const DELEGATE: Spec = Spec {
+    command: "/delegate",
+    args: &[Arg::new("username", Kind::Username)],
+    flags: &[],
+};
+
+pub const DELEGATE_REVOKE: Spec = Spec {
+    command: "/delegate revoke",
+    args: &[Arg::new("username", Kind::Username)],
+    flags: &[],
+};
+
+pub const DEVICE_ADD: Spec = Spec {
+    command: "/device add",
+    args: &[Arg::new("name", Kind::Text).optional()],
+    flags: &[],
+};
+
+pub const DEVICE_LIST: Spec = Spec {
+    command: "/device list",
+    args: &[],
+    flags: &[],
+};
+
+pub const DEVICE_INFO: Spec = Spec {
+    command: "/device info",
+    args: &[Arg::new("device_id", Kind::Uuid)],
+    flags: &[],
+};
+
+pub const DEVICE_REVOKE: Spec = Spec {
+    command: "/device revoke",
+    args: &[Arg::new("device_id", Kind::Uuid)],
+    flags: &[],
+};
+
+pub const SUBSCRIBE_ADD: Spec = Spec {
+    command: "/subscribe add",
+    args: &[
+        Arg::new("url", Kind::Word),
+        Arg::new("name", Kind::Text).optional(),
+    ],
+    flags: &[],
+};
+
+pub const SUBSCRIBE_LIST: Spec = Spec {
+    command: "/subscribe list",
+    args: &[],
+    flags: &[],
+};
+
+pub const SUBSCRIBE_HOLIDAYS: Spec = Spec {
+    command: "/subscribe holidays",
+    args: &[Arg::new("country", Kind::Word).optional()],
+    flags: &[],
+};
+
+pub const SUBSCRIBE_BIRTHDAYS: Spec = Spec {
+    command: "/subscribe birthdays",
+    args: &[Arg::new("on|off", Kind::Choice(&["on", "off"])).optional()],
+    flags: &[],
+};
+
+pub const SUBSCRIBE_REMOVE: Spec = Spec {
+    command: "/subscribe remove",
+    args: &[Arg::new("subscription_id", Kind::Uuid)],
+    flags: &[],
+};
+
+pub const INVITE: Spec = Spec {
+    command: "/invite",
+    args: &[
+        Arg::new("event_id", Kind::Uuid),
+        Arg::new("invitee", Kind::Invitee).optional().repeated(),
+    ],
+    flags: &[],
+};
+
+pub const NOTE: Spec = Spec {
+    command: "/note",
+    args: &[
+        Arg::new("event_id", Kind::Uuid),
+        Arg::new("note|clear", Kind::Text).optional(),
+    ],
+    flags: &[],
+};
+
+pub const ATTENDEES: Spec = Spec {
+    command: "/attendees",
+    args: &[Arg::new("event_id", Kind::Uuid)],
+    flags: &[],
+};
+
+pub const RSVP: Spec = Spec {
+    command: "/rsvp",
+    args: &[
+        Arg::new("event_id", Kind::Uuid),
+        Arg::new("accept|decline|tentative", Kind::Choice(RSVP_STATUSES)),
+    ],
+    flags: &[],
+};
+
+pub const SLOT: Spec = Spec {
+    command: "/slot",
+    args: &[
+        Arg::new("duration", Kind::Word),
+        Arg::new("within", Kind::Word).optional(),
+        Arg::new("working_hours", Kind::Word).optional(),
+    ],
+    flags: &[],
+};
+
+pub const TASK_ADD: Spec = Spec {
+    command: "/task",
+    args: &[
+        Arg::new("duration", Kind::Word),
+        Arg::new("title", Kind::Text),
+    ],
+    flags: &[],
+};
+
+pub const TASK_DONE: Spec = Spec {
+    command: "/task done",
+    args: &[Arg::new("n", Kind::Word)],
+    flags: &[],
+};
+
+pub const TASK_REMOVE: Spec = Spec {
+    command: "/task remove",
+    args: &[Arg::new("n", Kind::Word)],
+    flags: &[],
+};
+
+pub const PLAN: Spec = Spec {
+    command: "/plan",
+    args: &[
+        Arg::new("day", Kind::Word).optional(),
+        Arg::new("working_hours", Kind::Word).optional(),
+    ],
+    flags: &[],
+};
+
+pub const FOCUS_ADD: Spec = Spec {
+    command: "/focus add",
+    args: &[
+        Arg::new("blocks", Kind::Word),
+        Arg::new("title", Kind::Text).optional(),
+    ],
+    flags: &[],
+};
+
+pub const FOCUS_REMOVE: Spec = Spec {
+    command: "/focus remove",
+    args: &[Arg::new("n", Kind::Word)],
+    flags: &[],
+};
+
+pub const STATS: Spec = Spec {
+    command: "/stats",
+    args: &[Arg::new("window", Kind::Word).optional()],
+    flags: &[],
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_names_match_what_users_type() {
+        for listed in Command::bot_commands() {
+            let command = Command::parse(&listed.command, "televent_bot").unwrap();
+            assert_eq!(format!("/{}", command.name()), listed.command);
+        }
+    }
+
+    #[test]
+    fn test_mutating_commands() {
+        assert!(Command::Device.mutates("/device add Phone"));
+        assert!(Command::Device.mutates("/device revoke 1234"));
+        assert!(!Command::Device.mutates("/device list"));
+        assert!(Command::Subscribe.mutates("/subscribe remove 1234"));
+        assert!(!Command::Subscribe.mutates("/subscribe"));
+        assert!(Command::Subscribe.mutates("/subscribe holidays DE"));
+        assert!(!Command::Subscribe.mutates("/subscribe holidays"));
+        assert!(Command::Subscribe.mutates("/subscribe birthdays off"));
+        assert!(!Command::Invite.mutates("/invite 1234"));
+        assert!(Command::Invite.mutates("/invite 1234 @alice"));
+        assert!(Command::Rsvp.mutates("/rsvp 1234 accepted"));
+        assert!(!Command::Rsvp.mutates("/rsvp"));
+        assert!(Command::Note.mutates("/note 1234 Bring laptop!"));
+        assert!(!Command::Note.mutates("/note 1234"));
+        assert!(!Command::List.mutates("/list"));
+        assert!(Command::Timezone.mutates("/timezone Europe/Berlin"));
+        assert!(!Command::Timezone.mutates("/timezone"));
+        // Only asks; the button under the answer moves the events
+        assert!(!Command::Fixtz.mutates("/fixtz Europe/London Europe/Berlin"));
+        assert!(Command::Email.mutates("/email clear"));
+        assert!(Command::Email.mutates("/email verify 123456"));
+        assert!(!Command::Email.mutates("/email"));
+        assert!(Command::Hours.mutates("/hours off"));
+        assert!(!Command::Hours.mutates("/hours"));
+        assert!(Command::Delegate.mutates("/delegate @alice"));
+        assert!(Command::Delegate.mutates("/delegate revoke @alice"));
+        assert!(!Command::Delegate.mutates("/delegate"));
+        assert!(!Command::Delegate.mutates("/delegate list"));
+        assert!(Command::Task.mutates("/task 45m Write report"));
+        assert!(Command::Task.mutates("/task done 1"));
+        assert!(!Command::Task.mutates("/task"));
+        assert!(!Command::Task.mutates("/task list"));
+        assert!(Command::Plan.mutates("/plan"));
+        assert!(Command::Focus.mutates("/focus add 2x2h"));
+        assert!(Command::Focus.mutates("/focus remove 1"));
+        assert!(!Command::Focus.mutates("/focus"));
+    }
+
+    #[test]
+    fn test_command_usage() {
+        assert_eq!(DEVICE_ADD.usage(), "/device add [name...]");
+        assert_eq!(INVITE.usage(), "/invite <event_id> [invitee]...");
+        assert_eq!(RSVP.usage(), "/rsvp <event_id> <accept|decline|tentative>");
+        assert_eq!(SLOT.usage(), "/slot <duration> [within] [working_hours]");
+        assert_eq!(TASK_ADD.usage(), "/task <duration> <title...>");
+        assert_eq!(PLAN.usage(), "/plan [day] [working_hours]");
+        assert_eq!(FOCUS_ADD.usage(), "/focus add <blocks> [title...]");
+        assert_eq!(HOURS.usage(), "/hours [week|off...]");
+        assert_eq!(NOTE.usage(), "/note <event_id> [note|clear...]");
+
+        let args = DEVICE_ADD.parse("\"Work Laptop\"").unwrap();
+        assert_eq!(args.get("name"), Some("Work Laptop"));
+        let args = RSVP
+            .parse("6f1c3f0e-9a8b-4c2d-8e7f-6a5b4c3d2e1f Maybe")
+            .unwrap();
+        assert_eq!(args.get("accept|decline|tentative"), Some("Maybe"));
+    }
+}
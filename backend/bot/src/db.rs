@@ -4,12 +4,19 @@
 
 use chrono::{DateTime, NaiveDate, Utc};
 use televent_application::{
-    ApplicationError, CalendarIcalExport, CalendarService, ConfirmRsvpCommand,
-    CreateDevicePasswordCommand, CreateEventCommand, DeviceService, EventView,
-    InviteAttendeeCommand, UserId,
+    AddSubscriptionCommand, Analytics, ApplicationError, CalendarIcalExport, CalendarService,
+    CalendarStats, ConfirmRsvpCommand, ContactService, CreateDevicePasswordCommand,
+    CreateEventCommand, CreateTaskCommand, DelegateView, DeviceActivityView, DeviceId,
+    DeviceService, DuplicateEventCommand, EditScope, EmailAddressView, EventService, EventView,
+    FeatureFlagService, FocusRuleCommand, FocusRuleView, FocusSchedule, FreeSlot,
+    InviteAttendeeCommand, InviteAttendeesCommand, InviteAttendeesResult, InviteeCommand,
+    NotificationService, RemoveAttendeeCommand, ResendInviteCommand, SlotSearch, SnoozeDelay,
+    SubscriptionService, SubscriptionView, TaskPlan, TaskView, TimezoneFixCommand,
+    TimezoneFixSummary, UpdateEventCommand, UserId, WorkingHours, plan_window, schedule_focus_time,
 };
 use televent_domain::{
     AttendeeRole, EventStatus as DomainEventStatus, EventTiming, ParticipationStatus, Timezone,
+    internal_email_for_telegram_id,
 };
 use uuid::Uuid;
 
@@ -17,7 +24,14 @@ use uuid::Uuid;
 #[derive(Clone)]
 pub struct BotDb {
     calendar: CalendarService,
+    events: EventService,
     device: DeviceService,
+    subscriptions: SubscriptionService,
+    contacts: ContactService,
+    flags: FeatureFlagService,
+    analytics: Analytics,
+    /// Set when the deployment can send email, so addresses get confirmed
+    notifications: Option<NotificationService>,
 }
 
 /// Event data structure for bot display
@@ -30,8 +44,18 @@ pub struct BotEvent {
     pub start_date: Option<NaiveDate>,
     pub end_date: Option<NaiveDate>,
     pub is_all_day: bool,
+    /// Wall-clock event: `start` and `end` hold the local time as if it
+    /// were UTC, so it reads the same wherever the user is
+    pub is_floating: bool,
     pub location: Option<String>,
     pub description: Option<String>,
+    /// Appended to the event's reminders
+    pub reminder_note: Option<String>,
+    /// Name of the subscribed calendar a mirrored event comes from; the id of
+    /// such an event is the subscription id.
+    pub subscription: Option<String>,
+    /// Shown struck through rather than hidden, so guests see it is off
+    pub cancelled: bool,
 }
 
 impl BotEvent {
@@ -71,10 +95,52 @@ impl BotEvent {
 /// Device password information for display
 #[derive(Debug, Clone)]
 pub struct DevicePasswordInfo {
-    pub id: Uuid,
+    pub id: DeviceId,
     pub name: String,
     pub created_at: DateTime<Utc>,
     pub last_used_at: Option<DateTime<Utc>>,
+    /// Requests made over the last day
+    pub recent_requests: i64,
+}
+
+/// Calendar subscription information for display
+#[derive(Debug, Clone)]
+pub struct SubscriptionInfo {
+    pub id: Uuid,
+    pub name: String,
+    pub url: String,
+    pub last_fetched_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+}
+
+impl From<SubscriptionView> for SubscriptionInfo {
+    fn from(subscription: SubscriptionView) -> Self {
+        Self {
+            id: subscription.id,
+            name: subscription.name,
+            url: subscription.url,
+            last_fetched_at: subscription.last_fetched_at,
+            last_error: subscription.last_error,
+        }
+    }
+}
+
+/// Address book contact an /invite argument resolved to
+#[derive(Debug, Clone)]
+pub struct ContactInfo {
+    pub name: String,
+    pub email: Option<String>,
+    pub telegram_username: Option<String>,
+}
+
+/// Someone the organizer invited before, offered as an /invite shortcut
+#[derive(Debug, Clone)]
+pub struct SuggestedAttendee {
+    pub name: String,
+    /// Attendee email to invite: internal for Telegram users
+    pub email: String,
+    pub telegram_id: Option<i64>,
+    pub invite_count: i64,
 }
 
 /// User information for lookups
@@ -97,12 +163,17 @@ pub struct EventInfo {
     pub is_all_day: bool,
     pub location: Option<String>,
     pub user_id: UserId,
+    /// Repeat rule, counted from `series_start`
+    pub rrule: Option<String>,
+    pub series_start: DateTime<Utc>,
 }
 
 impl EventInfo {
     fn from_event(event: EventView, user_id: UserId) -> Self {
         let timing = timing_parts(&event.timing);
         Self {
+            series_start: event.timing.start_for_display(),
+            rrule: event.rrule,
             id: event.id,
             summary: event.summary,
             start: timing.start,
@@ -140,8 +211,57 @@ pub struct AttendeeInfo {
 
 impl BotDb {
     /// Create a new database handle
-    pub fn new(calendar: CalendarService, device: DeviceService) -> Self {
-        Self { calendar, device }
+    pub fn new(
+        calendar: CalendarService,
+        events: EventService,
+        device: DeviceService,
+        subscriptions: SubscriptionService,
+        contacts: ContactService,
+        flags: FeatureFlagService,
+    ) -> Self {
+        Self {
+            calendar,
+            events,
+            device,
+            subscriptions,
+            contacts,
+            flags,
+            analytics: Analytics::disabled(),
+            notifications: None,
+        }
+    }
+
+    /// Email a code confirming each address users set with /email
+    #[must_use]
+    pub fn with_email_verification(mut self, notifications: NotificationService) -> Self {
+        self.notifications = Some(notifications);
+        self
+    }
+
+    /// Count command usage and event creation in `analytics`
+    #[must_use]
+    pub fn with_analytics(mut self, analytics: Analytics) -> Self {
+        self.analytics = analytics;
+        self
+    }
+
+    /// Anonymous product metrics, disabled unless configured
+    pub fn analytics(&self) -> &Analytics {
+        &self.analytics
+    }
+
+    /// Whether a runtime feature flag is on for the user
+    pub async fn is_feature_enabled(
+        &self,
+        flag: &str,
+        telegram_id: i64,
+    ) -> Result<bool, ApplicationError> {
+        self.flags.is_enabled(flag, UserId::new(telegram_id)).await
+    }
+
+    /// Whether the deployment is in read-only maintenance mode
+    pub async fn is_read_only(&self) -> Result<bool, ApplicationError> {
+        self.flags.is_read_only().await
     }
 
     /// Get events for a user within a date range
@@ -157,15 +277,48 @@ impl BotDb {
                 UserId::new(telegram_id),
                 Some(start_range),
                 Some(end_range),
+                &[],
+                None,
                 None,
                 None,
             )
             .await?;
 
+        Ok(events.into_iter().map(BotEvent::from_event).collect())
+    }
+
+    /// Get events mirrored from the user's subscribed calendars within a date range
+    pub async fn get_subscribed_events_for_user(
+        &self,
+        telegram_id: i64,
+        start_range: DateTime<Utc>,
+        end_range: DateTime<Utc>,
+    ) -> Result<Vec<BotEvent>, ApplicationError> {
+        let events = self
+            .subscriptions
+            .list_subscribed_event_views(UserId::new(telegram_id), start_range, end_range)
+            .await?;
+
         Ok(events
             .into_iter()
-            .filter(|event| event.status != DomainEventStatus::Cancelled)
-            .map(BotEvent::from_event)
+            .map(|event| {
+                let timing = timing_parts(&event.timing);
+                BotEvent {
+                    id: event.subscription_id,
+                    summary: event.summary,
+                    start: timing.start,
+                    end: timing.end,
+                    start_date: timing.start_date,
+                    end_date: timing.end_date,
+                    is_all_day: timing.is_all_day,
+                    is_floating: timing.is_floating,
+                    location: event.location,
+                    description: None,
+                    reminder_note: None,
+                    subscription: Some(event.calendar_name),
+                    cancelled: event.status == DomainEventStatus::Cancelled,
+                }
+            })
             .collect())
     }
 
@@ -176,14 +329,18 @@ impl BotDb {
     ) -> Result<Vec<BotEvent>, ApplicationError> {
         let events = self
             .calendar
-            .list_event_views(UserId::new(telegram_id), None, None, None, None)
+            .list_event_views(
+                UserId::new(telegram_id),
+                None,
+                None,
+                &[DomainEventStatus::Confirmed, DomainEventStatus::Tentative],
+                None,
+                None,
+                None,
+            )
             .await?;
 
-        Ok(events
-            .into_iter()
-            .filter(|event| event.status != DomainEventStatus::Cancelled)
-            .map(BotEvent::from_event)
-            .collect())
+        Ok(events.into_iter().map(BotEvent::from_event).collect())
     }
 
     pub async fn export_calendar_ics(
@@ -204,6 +361,125 @@ impl BotDb {
         self.calendar.ensure_user_setup(telegram_id, username).await
     }
 
+    /// Mark the user onboarded; `true` on their very first /start
+    pub async fn begin_onboarding(&self, telegram_id: i64) -> Result<bool, ApplicationError> {
+        self.calendar
+            .begin_onboarding(UserId::new(telegram_id))
+            .await
+    }
+
+    /// The user's timezone; UTC for users the bot hasn't seen
+    pub async fn get_timezone(&self, telegram_id: i64) -> Result<Timezone, ApplicationError> {
+        Ok(self
+            .calendar
+            .get_user_identity_by_id(UserId::new(telegram_id))
+            .await?
+            .map(|identity| identity.timezone)
+            .unwrap_or_default())
+    }
+
+    /// Change the user's timezone
+    pub async fn set_timezone(
+        &self,
+        telegram_id: i64,
+        timezone: &Timezone,
+    ) -> Result<(), ApplicationError> {
+        self.calendar
+            .set_user_timezone(UserId::new(telegram_id), timezone)
+            .await
+            .map(|_| ())
+    }
+
+    /// Change or clear the email invites can reach the user at; returns the
+    /// address now stored
+    pub async fn set_email(
+        &self,
+        telegram_id: i64,
+        email: Option<&str>,
+    ) -> Result<Option<String>, ApplicationError> {
+        Ok(self
+            .calendar
+            .set_user_email(UserId::new(telegram_id), email)
+            .await?
+            .email)
+    }
+
+    /// The user's onboarding email, if they gave one
+    pub async fn get_email(&self, telegram_id: i64) -> Result<Option<String>, ApplicationError> {
+        Ok(self
+            .calendar
+            .get_user_identity_by_id(UserId::new(telegram_id))
+            .await?
+            .and_then(|identity| identity.email))
+    }
+
+    /// The user's email and whether they confirmed it
+    pub async fn get_email_status(
+        &self,
+        telegram_id: i64,
+    ) -> Result<Option<EmailAddressView>, ApplicationError> {
+        match &self.notifications {
+            Some(notifications) => notifications.email(UserId::new(telegram_id)).await,
+            None => Ok(self
+                .get_email(telegram_id)
+                .await?
+                .map(|email| EmailAddressView {
+                    email,
+                    verified_at: None,
+                })),
+        }
+    }
+
+    /// Email a code confirming the user's address; `None` when the
+    /// deployment sends no email
+    pub async fn request_email_verification(
+        &self,
+        telegram_id: i64,
+    ) -> Result<Option<EmailAddressView>, ApplicationError> {
+        match &self.notifications {
+            Some(notifications) => notifications
+                .request_email_verification(UserId::new(telegram_id))
+                .await
+                .map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Confirm the user's address with the emailed code
+    pub async fn verify_email(
+        &self,
+        telegram_id: i64,
+        code: &str,
+    ) -> Result<EmailAddressView, ApplicationError> {
+        let Some(notifications) = &self.notifications else {
+            return Err(ApplicationError::BadRequest(
+                "Email is not available on this server".to_string(),
+            ));
+        };
+        notifications
+            .verify_email(UserId::new(telegram_id), code)
+            .await
+    }
+
+    /// The user's saved working week, if they set one
+    pub async fn get_working_hours(
+        &self,
+        telegram_id: i64,
+    ) -> Result<Option<WorkingHours>, ApplicationError> {
+        self.calendar.working_hours(UserId::new(telegram_id)).await
+    }
+
+    /// Save or clear the user's working week; returns the week now stored
+    pub async fn set_working_hours(
+        &self,
+        telegram_id: i64,
+        week: Option<&str>,
+    ) -> Result<Option<WorkingHours>, ApplicationError> {
+        self.calendar
+            .set_working_hours(UserId::new(telegram_id), week)
+            .await
+    }
+
     /// Generate a new device password for a user
     pub async fn generate_device_password(
         &self,
@@ -229,7 +505,7 @@ impl BotDb {
     ) -> Result<Vec<DevicePasswordInfo>, ApplicationError> {
         let devices = self
             .device
-            .list_device_passwords(UserId::new(telegram_id))
+            .list_device_passwords(UserId::new(telegram_id), None)
             .await?;
 
         Ok(devices
@@ -239,6 +515,7 @@ impl BotDb {
                 name: device.name,
                 created_at: device.created_at,
                 last_used_at: device.last_used_at,
+                recent_requests: device.recent_requests,
             })
             .collect())
     }
@@ -247,13 +524,165 @@ impl BotDb {
     pub async fn revoke_device_password(
         &self,
         telegram_id: i64,
-        device_id: Uuid,
+        device_id: DeviceId,
     ) -> Result<bool, ApplicationError> {
         self.device
             .revoke_device_password(UserId::new(telegram_id), device_id)
             .await
     }
 
+    /// Recent CalDAV requests made with one of the user's devices, newest first
+    ///
+    /// Returns `None` if the device doesn't exist or belongs to someone else.
+    pub async fn device_activity(
+        &self,
+        telegram_id: i64,
+        device_id: DeviceId,
+    ) -> Result<Option<Vec<DeviceActivityView>>, ApplicationError> {
+        match self
+            .device
+            .device_activity(UserId::new(telegram_id), device_id)
+            .await
+        {
+            Ok(activity) => Ok(Some(activity)),
+            Err(ApplicationError::NotFound(_)) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Subscribe the user to a remote ICS calendar
+    pub async fn add_subscription(
+        &self,
+        telegram_id: i64,
+        username: Option<&str>,
+        url: &str,
+        name: Option<&str>,
+    ) -> Result<SubscriptionInfo, ApplicationError> {
+        self.subscriptions
+            .add_subscription(AddSubscriptionCommand {
+                user_id: UserId::new(telegram_id),
+                username: username.map(str::to_string),
+                url: url.to_string(),
+                name: name.map(str::to_string),
+            })
+            .await
+            .map(SubscriptionInfo::from)
+    }
+
+    /// List the user's calendar subscriptions, oldest first
+    pub async fn list_subscriptions(
+        &self,
+        telegram_id: i64,
+    ) -> Result<Vec<SubscriptionInfo>, ApplicationError> {
+        Ok(self
+            .subscriptions
+            .list_subscriptions(UserId::new(telegram_id))
+            .await?
+            .into_iter()
+            .map(SubscriptionInfo::from)
+            .collect())
+    }
+
+    /// Subscribe the user to a country's built-in holiday calendar, or
+    /// return the subscription they already have
+    pub async fn enable_holidays(
+        &self,
+        telegram_id: i64,
+        username: Option<&str>,
+        country: &str,
+    ) -> Result<SubscriptionInfo, ApplicationError> {
+        self.subscriptions
+            .enable_holidays(
+                UserId::new(telegram_id),
+                username.map(str::to_string),
+                country,
+            )
+            .await
+            .map(SubscriptionInfo::from)
+    }
+
+    /// Turn on the calendar of the user's contacts' birthdays, or return the
+    /// subscription they already have
+    pub async fn enable_birthdays(
+        &self,
+        telegram_id: i64,
+        username: Option<&str>,
+    ) -> Result<SubscriptionInfo, ApplicationError> {
+        self.subscriptions
+            .enable_birthdays(UserId::new(telegram_id), username.map(str::to_string))
+            .await
+            .map(SubscriptionInfo::from)
+    }
+
+    /// Turn the birthdays calendar off; `false` when it wasn't on
+    pub async fn disable_birthdays(&self, telegram_id: i64) -> Result<bool, ApplicationError> {
+        self.subscriptions
+            .disable_birthdays(UserId::new(telegram_id))
+            .await
+    }
+
+    /// Remove a calendar subscription together with its mirrored events
+    pub async fn remove_subscription(
+        &self,
+        telegram_id: i64,
+        subscription_id: Uuid,
+    ) -> Result<bool, ApplicationError> {
+        self.subscriptions
+            .remove_subscription(UserId::new(telegram_id), subscription_id)
+            .await
+    }
+
+    /// Let the user named `username` create, edit and delete events on the
+    /// user's calendar
+    pub async fn grant_delegate(
+        &self,
+        telegram_id: i64,
+        username: &str,
+    ) -> Result<DelegateView, ApplicationError> {
+        self.events
+            .grant_delegate(UserId::new(telegram_id), username)
+            .await
+    }
+
+    /// Take the access of the user named `username` away; `false` when they
+    /// had none
+    pub async fn revoke_delegate(
+        &self,
+        telegram_id: i64,
+        username: &str,
+    ) -> Result<bool, ApplicationError> {
+        let Some(delegate) = self.find_user_by_username(username).await? else {
+            return Ok(false);
+        };
+        match self
+            .events
+            .revoke_delegate(UserId::new(telegram_id), UserId::new(delegate.telegram_id))
+            .await
+        {
+            Ok(()) => Ok(true),
+            Err(ApplicationError::NotFound(_)) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Users who may manage the user's calendar, oldest grant first
+    pub async fn list_delegates(
+        &self,
+        telegram_id: i64,
+    ) -> Result<Vec<DelegateView>, ApplicationError> {
+        self.events.list_delegates(UserId::new(telegram_id)).await
+    }
+
+    /// Owners whose calendars the user may manage, oldest grant first
+    pub async fn list_delegated_calendars(
+        &self,
+        telegram_id: i64,
+    ) -> Result<Vec<DelegateView>, ApplicationError> {
+        self.events
+            .list_delegated_calendars(UserId::new(telegram_id))
+            .await
+    }
+
     /// Find user by Telegram username
     pub async fn find_user_by_username(
         &self,
@@ -270,6 +699,92 @@ impl BotDb {
             }))
     }
 
+    /// Find a user by Telegram id, for mentions that carry no usable
+    /// username
+    pub async fn find_user_by_id(
+        &self,
+        telegram_id: i64,
+    ) -> Result<Option<UserInfo>, ApplicationError> {
+        Ok(self
+            .calendar
+            .get_user_identity_by_id(UserId::new(telegram_id))
+            .await?
+            .map(|user| UserInfo {
+                telegram_id: user.id.inner(),
+                telegram_username: user.username,
+            }))
+    }
+
+    /// Store the username Telegram currently reports for a known user;
+    /// `true` when it changed
+    pub async fn refresh_username(
+        &self,
+        telegram_id: i64,
+        username: Option<&str>,
+    ) -> Result<bool, ApplicationError> {
+        self.calendar
+            .refresh_username(UserId::new(telegram_id), username)
+            .await
+    }
+
+    /// Find the user who registered `email` during onboarding
+    pub async fn find_user_by_email(
+        &self,
+        email: &str,
+    ) -> Result<Option<UserInfo>, ApplicationError> {
+        Ok(self
+            .calendar
+            .get_user_identity_by_email(email)
+            .await?
+            .map(|user| UserInfo {
+                telegram_id: user.id.inner(),
+                telegram_username: user.username,
+            }))
+    }
+
+    /// Find the address book contact a free-text /invite argument refers to
+    pub async fn find_contact(
+        &self,
+        telegram_id: i64,
+        query: &str,
+    ) -> Result<Option<ContactInfo>, ApplicationError> {
+        Ok(self
+            .contacts
+            .find_contact(UserId::new(telegram_id), query)
+            .await?
+            .map(|contact| ContactInfo {
+                name: contact.full_name,
+                email: contact.email,
+                telegram_username: contact.telegram_username,
+            }))
+    }
+
+    /// People the organizer invited most often
+    pub async fn suggest_attendees(
+        &self,
+        telegram_id: i64,
+        limit: usize,
+    ) -> Result<Vec<SuggestedAttendee>, ApplicationError> {
+        Ok(self
+            .contacts
+            .suggest_attendees(UserId::new(telegram_id), "", limit)
+            .await?
+            .into_iter()
+            .filter_map(|suggestion| {
+                let email = match suggestion.telegram_id {
+                    Some(id) => internal_email_for_telegram_id(id),
+                    None => suggestion.email?,
+                };
+                Some(SuggestedAttendee {
+                    name: suggestion.name,
+                    email,
+                    telegram_id: suggestion.telegram_id,
+                    invite_count: suggestion.invite_count,
+                })
+            })
+            .collect())
+    }
+
     /// Get event info and verify ownership
     pub async fn get_event_info(
         &self,
@@ -300,7 +815,7 @@ impl BotDb {
             .await?
             .ok_or_else(|| ApplicationError::NotFound(event_id.to_string()))?;
 
-        self.calendar
+        self.events
             .invite_attendee(InviteAttendeeCommand {
                 organizer_user_id: UserId::new(organizer_user_id),
                 event_id,
@@ -314,6 +829,86 @@ impl BotDb {
             .await
     }
 
+    /// Invite several attendees to an event in one transaction
+    ///
+    /// Each invitee is an `(email, telegram_id)` pair; external guests have no
+    /// Telegram ID.
+    pub async fn invite_attendees(
+        &self,
+        event_id: Uuid,
+        invitees: &[(String, Option<i64>)],
+    ) -> Result<InviteAttendeesResult, ApplicationError> {
+        let organizer_user_id = self
+            .get_event_organizer(event_id)
+            .await?
+            .ok_or_else(|| ApplicationError::NotFound(event_id.to_string()))?;
+
+        self.events
+            .invite_attendees(InviteAttendeesCommand {
+                organizer_user_id: UserId::new(organizer_user_id),
+                event_id,
+                invitees: invitees
+                    .iter()
+                    .map(|(email, user_id)| InviteeCommand {
+                        email: email.clone(),
+                        attendee_user_id: user_id.map(UserId::new),
+                        role: AttendeeRole::Attendee,
+                    })
+                    .collect(),
+            })
+            .await
+    }
+
+    /// Remove an attendee from an event owned by `organizer_id`
+    pub async fn remove_attendee(
+        &self,
+        organizer_id: i64,
+        event_id: Uuid,
+        email: &str,
+    ) -> Result<(), ApplicationError> {
+        self.events
+            .remove_attendee(RemoveAttendeeCommand {
+                organizer_user_id: UserId::new(organizer_id),
+                event_id,
+                email: email.to_string(),
+            })
+            .await
+    }
+
+    /// Bring an invite reminder back later for the invitee who got it
+    pub async fn snooze_invite_reminder(
+        &self,
+        telegram_id: i64,
+        event_id: Uuid,
+        delay: SnoozeDelay,
+    ) -> Result<(), ApplicationError> {
+        self.events
+            .snooze_invite_reminder(
+                UserId::new(telegram_id),
+                event_id,
+                delay,
+                self.events.clock().now(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Re-send the invite to an attendee who has not replied yet
+    pub async fn resend_invite(
+        &self,
+        organizer_id: i64,
+        event_id: Uuid,
+        email: &str,
+    ) -> Result<(), ApplicationError> {
+        self.events
+            .resend_invite(ResendInviteCommand {
+                organizer_user_id: UserId::new(organizer_id),
+                event_id,
+                email: email.to_string(),
+            })
+            .await
+    }
+
     /// Update RSVP status for an attendee (simple update)
     pub async fn update_rsvp_status(
         &self,
@@ -347,7 +942,7 @@ impl BotDb {
             ApplicationError::BadRequest(format!("Invalid RSVP status: {status}"))
         })?;
 
-        self.calendar
+        self.events
             .confirm_rsvp(ConfirmRsvpCommand {
                 event_id,
                 attendee_user_id: UserId::new(user_id),
@@ -412,7 +1007,8 @@ impl BotDb {
             .map(UserId::inner))
     }
 
-    /// Create a new event
+    /// Create a new event; without `allow_duplicate`, one that looks like an
+    /// event the user already has is a conflict
     #[allow(clippy::too_many_arguments)]
     pub async fn create_event(
         &self,
@@ -423,30 +1019,12 @@ impl BotDb {
         location: Option<&str>,
         timing: crate::event_parser::ParsedTiming,
         timezone: &str,
+        allow_duplicate: bool,
     ) -> Result<BotEvent, ApplicationError> {
-        let domain_timing = match timing {
-            crate::event_parser::ParsedTiming::Timed {
-                start,
-                duration_minutes,
-            } => {
-                let end = start + chrono::Duration::minutes(i64::from(duration_minutes));
-                EventTiming::Timed {
-                    start,
-                    end,
-                    timezone: Timezone::parse(timezone.to_string()).unwrap_or_default(),
-                }
-            }
-            crate::event_parser::ParsedTiming::AllDay { date } => {
-                let end_date = date + chrono::Duration::days(1);
-                EventTiming::AllDay {
-                    start_date: date,
-                    end_date,
-                }
-            }
-        };
+        let domain_timing = domain_timing(timing, timezone);
 
         let event = self
-            .calendar
+            .events
             .create_event_view(CreateEventCommand {
                 user_id: UserId::new(telegram_id),
                 username: None,
@@ -457,11 +1035,404 @@ impl BotDb {
                 timing: domain_timing,
                 status: DomainEventStatus::Confirmed,
                 rrule: None,
+                visibility: None,
+                reminder_note: None,
+                allow_duplicate,
             })
             .await?;
 
         Ok(BotEvent::from_event(event))
     }
+
+    /// Apply an edit from a reply to an event card
+    ///
+    /// Returns `None` when the event doesn't exist or isn't the user's. For a
+    /// part of a series, the event returned is the one split off for it.
+    pub async fn edit_event(
+        &self,
+        telegram_id: i64,
+        event_id: Uuid,
+        edit: crate::event_parser::EventEdit,
+        scope: EditScope,
+    ) -> Result<Option<BotEvent>, ApplicationError> {
+        use crate::event_parser::EventEdit;
+
+        let mut command = UpdateEventCommand {
+            user_id: UserId::new(telegram_id),
+            event_id,
+            summary: None,
+            description: None,
+            location: None,
+            timing: None,
+            status: None,
+            rrule: None,
+            visibility: None,
+            reminder_note: None,
+            scope,
+        };
+        match edit {
+            EventEdit::Rename(summary) => command.summary = Some(summary),
+            EventEdit::Relocate(location) => command.location = Some(Some(location)),
+            EventEdit::Move(timing) => command.timing = Some(domain_timing(timing, "UTC")),
+        }
+
+        match self.events.update_event_view(command).await {
+            Ok(event) => Ok(Some(BotEvent::from_event(event))),
+            Err(ApplicationError::NotFound(_)) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// One of the user's own events, for display
+    ///
+    /// Returns `None` when the event doesn't exist or isn't the user's.
+    pub async fn get_event(
+        &self,
+        telegram_id: i64,
+        event_id: Uuid,
+    ) -> Result<Option<BotEvent>, ApplicationError> {
+        match self
+            .calendar
+            .get_event_view(UserId::new(telegram_id), event_id)
+            .await
+        {
+            Ok(event) => Ok(Some(BotEvent::from_event(event))),
+            Err(ApplicationError::NotFound(_)) => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Cancel one of the user's events, every occurrence of a series; guests
+    /// are told it is off
+    ///
+    /// Returns `None` when the event doesn't exist or isn't the user's.
+    pub async fn cancel_event(
+        &self,
+        telegram_id: i64,
+        event_id: Uuid,
+    ) -> Result<Option<BotEvent>, ApplicationError> {
+        let command = UpdateEventCommand {
+            user_id: UserId::new(telegram_id),
+            event_id,
+            summary: None,
+            description: None,
+            location: None,
+            timing: None,
+            status: Some(DomainEventStatus::Cancelled),
+            rrule: None,
+            visibility: None,
+            reminder_note: None,
+            scope: EditScope::Series,
+        };
+
+        match self.events.update_event_view(command).await {
+            Ok(event) => Ok(Some(BotEvent::from_event(event))),
+            Err(ApplicationError::NotFound(_)) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Set or, with `None`, remove the note appended to an event's reminders
+    ///
+    /// Returns `None` when the event doesn't exist or isn't the user's.
+    pub async fn set_reminder_note(
+        &self,
+        telegram_id: i64,
+        event_id: Uuid,
+        note: Option<String>,
+    ) -> Result<Option<BotEvent>, ApplicationError> {
+        let command = UpdateEventCommand {
+            user_id: UserId::new(telegram_id),
+            event_id,
+            summary: None,
+            description: None,
+            location: None,
+            timing: None,
+            status: None,
+            rrule: None,
+            visibility: None,
+            reminder_note: Some(note),
+            scope: EditScope::Series,
+        };
+
+        match self.events.update_event_view(command).await {
+            Ok(event) => Ok(Some(BotEvent::from_event(event))),
+            Err(ApplicationError::NotFound(_)) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Copy one of the user's events, moved by whole days, without guests
+    ///
+    /// Returns `None` when the event doesn't exist or isn't the user's.
+    pub async fn duplicate_event(
+        &self,
+        telegram_id: i64,
+        event_id: Uuid,
+        offset_days: i64,
+    ) -> Result<Option<BotEvent>, ApplicationError> {
+        match self
+            .events
+            .duplicate_event(DuplicateEventCommand {
+                user_id: UserId::new(telegram_id),
+                event_id,
+                offset_days,
+                include_attendees: false,
+            })
+            .await
+        {
+            Ok(event) => Ok(Some(BotEvent::from_event(event))),
+            Err(ApplicationError::NotFound(_)) => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Events a timezone fix would move
+    pub async fn preview_timezone_fix(
+        &self,
+        command: &TimezoneFixCommand,
+    ) -> Result<TimezoneFixSummary, ApplicationError> {
+        self.events.preview_timezone_fix(command).await
+    }
+
+    /// Move the events saved in the wrong timezone, telling guests of
+    /// upcoming ones
+    pub async fn fix_timezone(
+        &self,
+        command: TimezoneFixCommand,
+    ) -> Result<TimezoneFixSummary, ApplicationError> {
+        self.events.fix_timezone(command).await
+    }
+
+    /// Next free slots across the user's own and subscribed calendars
+    ///
+    /// A search without working hours keeps to the user's saved ones. Slot
+    /// times come back in UTC alongside the user's timezone for display.
+    pub async fn find_free_slots(
+        &self,
+        telegram_id: i64,
+        mut search: SlotSearch,
+    ) -> Result<(Vec<FreeSlot>, Timezone), ApplicationError> {
+        let user_id = UserId::new(telegram_id);
+        let timezone = self.get_timezone(telegram_id).await?;
+        if search.working_hours.is_none() {
+            search.working_hours = self.calendar.working_hours(user_id).await?;
+            search = search.validated()?;
+        }
+
+        let mut free_busy = self
+            .calendar
+            .free_busy(user_id, &timezone, search.from, search.until())
+            .await?;
+        self.subscriptions
+            .mark_busy(user_id, &mut free_busy)
+            .await?;
+
+        Ok((free_busy.free_slots(&search), timezone))
+    }
+
+    /// The user's focus rules with this week's blocks, and their timezone
+    pub async fn list_focus_rules(
+        &self,
+        telegram_id: i64,
+    ) -> Result<(Vec<FocusRuleView>, Timezone), ApplicationError> {
+        let rules = self
+            .calendar
+            .list_focus_rules(UserId::new(telegram_id))
+            .await?;
+        Ok((rules, self.get_timezone(telegram_id).await?))
+    }
+
+    /// Add a focus rule and place its blocks for this week right away
+    pub async fn add_focus_rule(
+        &self,
+        command: FocusRuleCommand,
+    ) -> Result<(FocusRuleView, FocusSchedule), ApplicationError> {
+        let user_id = command.user_id;
+        let rule = self.events.create_focus_rule(command).await?;
+        let schedule =
+            schedule_focus_time(&self.calendar, &self.subscriptions, &self.events, user_id).await?;
+        Ok((rule, schedule))
+    }
+
+    /// `false` when the user has no such rule
+    pub async fn remove_focus_rule(
+        &self,
+        telegram_id: i64,
+        rule_id: Uuid,
+    ) -> Result<bool, ApplicationError> {
+        match self
+            .events
+            .delete_focus_rule(UserId::new(telegram_id), rule_id)
+            .await
+        {
+            Ok(()) => Ok(true),
+            Err(ApplicationError::NotFound(_)) => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
+
+    pub async fn create_task(
+        &self,
+        telegram_id: i64,
+        title: &str,
+        duration: chrono::Duration,
+    ) -> Result<TaskView, ApplicationError> {
+        self.events
+            .create_task(CreateTaskCommand {
+                user_id: UserId::new(telegram_id),
+                title: title.to_string(),
+                duration,
+            })
+            .await
+    }
+
+    /// The user's open tasks, oldest first, with their timezone for display
+    pub async fn list_tasks(
+        &self,
+        telegram_id: i64,
+    ) -> Result<(Vec<TaskView>, Timezone), ApplicationError> {
+        let tasks = self.calendar.list_tasks(UserId::new(telegram_id)).await?;
+        Ok((tasks, self.get_timezone(telegram_id).await?))
+    }
+
+    /// `false` when the user has no such open task
+    pub async fn complete_task(
+        &self,
+        telegram_id: i64,
+        task_id: Uuid,
+    ) -> Result<bool, ApplicationError> {
+        match self
+            .events
+            .complete_task(UserId::new(telegram_id), task_id)
+            .await
+        {
+            Ok(()) => Ok(true),
+            Err(ApplicationError::NotFound(_)) => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// `false` when the user has no such open task
+    pub async fn unschedule_task(
+        &self,
+        telegram_id: i64,
+        task_id: Uuid,
+    ) -> Result<bool, ApplicationError> {
+        match self
+            .events
+            .unschedule_task(UserId::new(telegram_id), task_id)
+            .await
+        {
+            Ok(()) => Ok(true),
+            Err(ApplicationError::NotFound(_)) => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// `false` when the user has no such task
+    pub async fn delete_task(
+        &self,
+        telegram_id: i64,
+        task_id: Uuid,
+    ) -> Result<bool, ApplicationError> {
+        match self
+            .events
+            .delete_task(UserId::new(telegram_id), task_id)
+            .await
+        {
+            Ok(()) => Ok(true),
+            Err(ApplicationError::NotFound(_)) => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Plan the user's open tasks into the free time left on the local day
+    /// `days_ahead` days from today, within `working_hours` or else the
+    /// user's saved ones
+    ///
+    /// `None` when that day is already over.
+    pub async fn plan_tasks(
+        &self,
+        telegram_id: i64,
+        days_ahead: i64,
+        working_hours: Option<WorkingHours>,
+    ) -> Result<Option<(TaskPlan, Timezone)>, ApplicationError> {
+        let user_id = UserId::new(telegram_id);
+        let timezone = self.get_timezone(telegram_id).await?;
+        let now = self.events.clock().now();
+        let day =
+            now.with_timezone(&timezone.tz()).date_naive() + chrono::Duration::days(days_ahead);
+        let Some((from, until)) = plan_window(day, now, &timezone) else {
+            return Ok(None);
+        };
+
+        let mut free_busy = self
+            .calendar
+            .free_busy(user_id, &timezone, from, until)
+            .await?;
+        self.subscriptions
+            .mark_busy(user_id, &mut free_busy)
+            .await?;
+        let working_hours = match working_hours {
+            Some(hours) => Some(hours),
+            None => self.calendar.working_hours(user_id).await?,
+        };
+        let plan = self
+            .events
+            .plan_tasks(user_id, free_busy, working_hours)
+            .await?;
+
+        Ok(Some((plan, timezone)))
+    }
+
+    /// Confirm the tentative blocks of the user's tasks; returns how many
+    pub async fn confirm_planned_blocks(
+        &self,
+        telegram_id: i64,
+    ) -> Result<usize, ApplicationError> {
+        self.events
+            .confirm_planned_blocks(UserId::new(telegram_id))
+            .await
+    }
+
+    /// Delete the tentative blocks of the user's tasks; returns how many
+    pub async fn discard_planned_blocks(
+        &self,
+        telegram_id: i64,
+    ) -> Result<usize, ApplicationError> {
+        self.events
+            .discard_planned_blocks(UserId::new(telegram_id))
+            .await
+    }
+
+    /// Number of events the user created at or after `since`
+    pub async fn count_events_created_since(
+        &self,
+        telegram_id: i64,
+        since: DateTime<Utc>,
+    ) -> Result<i64, ApplicationError> {
+        self.calendar
+            .count_events_created_since(UserId::new(telegram_id), since)
+            .await
+    }
+
+    /// Meeting load over the `window` before now, with the user's timezone
+    pub async fn calendar_stats(
+        &self,
+        telegram_id: i64,
+        window: chrono::Duration,
+    ) -> Result<(CalendarStats, Timezone), ApplicationError> {
+        let user_id = UserId::new(telegram_id);
+        let timezone = self.get_timezone(telegram_id).await?;
+        let to = Utc::now();
+
+        let stats = self
+            .calendar
+            .calendar_stats(user_id, &timezone, to - window, to)
+            .await?;
+        Ok((stats, timezone))
+    }
 }
 
 impl BotEvent {
@@ -475,8 +1446,35 @@ impl BotEvent {
             start_date: timing.start_date,
             end_date: timing.end_date,
             is_all_day: timing.is_all_day,
+            is_floating: timing.is_floating,
             location: event.location,
             description: event.description,
+            reminder_note: event.reminder_note,
+            subscription: None,
+            cancelled: event.status == DomainEventStatus::Cancelled,
+        }
+    }
+}
+
+fn domain_timing(timing: crate::event_parser::ParsedTiming, timezone: &str) -> EventTiming {
+    match timing {
+        crate::event_parser::ParsedTiming::Timed {
+            start,
+            duration_minutes,
+        } => {
+            let end = start + chrono::Duration::minutes(i64::from(duration_minutes));
+            EventTiming::Timed {
+                start,
+                end,
+                timezone: Timezone::parse(timezone.to_string()).unwrap_or_default(),
+            }
+        }
+        crate::event_parser::ParsedTiming::AllDay { date } => {
+            let end_date = date + chrono::Duration::days(1);
+            EventTiming::AllDay {
+                start_date: date,
+                end_date,
+            }
         }
     }
 }
@@ -487,6 +1485,7 @@ struct TimingParts {
     start_date: Option<NaiveDate>,
     end_date: Option<NaiveDate>,
     is_all_day: bool,
+    is_floating: bool,
 }
 
 fn timing_parts(timing: &EventTiming) -> TimingParts {
@@ -497,6 +1496,7 @@ fn timing_parts(timing: &EventTiming) -> TimingParts {
             start_date: None,
             end_date: None,
             is_all_day: false,
+            is_floating: false,
         },
         EventTiming::AllDay {
             start_date,
@@ -507,6 +1507,17 @@ fn timing_parts(timing: &EventTiming) -> TimingParts {
             start_date: Some(*start_date),
             end_date: Some(*end_date),
             is_all_day: true,
+            is_floating: false,
+        },
+        // The bot shows times as they are stored, which for floating events
+        // is the wall-clock time
+        EventTiming::Floating { start, end } => TimingParts {
+            start: Some(start.and_utc()),
+            end: Some(end.and_utc()),
+            start_date: None,
+            end_date: None,
+            is_all_day: false,
+            is_floating: true,
         },
     }
 }
@@ -516,13 +1527,26 @@ mod tests {
     use super::*;
     use chrono::Duration;
     use sqlx::PgPool;
+    use televent_application::FetchedFeed;
 
     fn bot_db(pool: PgPool) -> BotDb {
         BotDb::new(
             CalendarService::new(televent_storage::calendar::CalendarRepository::new(
                 pool.clone(),
             )),
-            DeviceService::new(televent_storage::device::DeviceRepository::new(pool)),
+            EventService::new(televent_storage::calendar::CalendarRepository::new(
+                pool.clone(),
+            )),
+            DeviceService::new(televent_storage::device::DeviceRepository::new(
+                pool.clone(),
+            )),
+            SubscriptionService::new(televent_storage::subscription::SubscriptionRepository::new(
+                pool.clone(),
+            )),
+            ContactService::new(televent_storage::contact::ContactRepository::new(
+                pool.clone(),
+            )),
+            FeatureFlagService::new(televent_storage::flags::FeatureFlagRepository::new(pool)),
         )
     }
 
@@ -540,6 +1564,164 @@ mod tests {
         assert!(result2.is_ok());
     }
 
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_usernames_follow_renames(pool: PgPool) {
+        let db = bot_db(pool);
+        db.ensure_user_setup(1201, Some("alice")).await.unwrap();
+        db.ensure_user_setup(1202, Some("bob")).await.unwrap();
+        let username_of = |user: Option<UserInfo>| user.and_then(|user| user.telegram_username);
+
+        // Alice renames; her old name no longer finds her
+        assert!(db.refresh_username(1201, Some("alice_w")).await.unwrap());
+        assert!(!db.refresh_username(1201, Some("alice_w")).await.unwrap());
+        assert!(db.find_user_by_username("@alice").await.unwrap().is_none());
+        let found = db.find_user_by_username("@Alice_W").await.unwrap();
+        assert_eq!(found.map(|user| user.telegram_id), Some(1201));
+
+        // Bob takes a name someone had stored before they renamed
+        db.ensure_user_setup(1203, Some("carol")).await.unwrap();
+        db.ensure_user_setup(1202, Some("Carol")).await.unwrap();
+        let found = db.find_user_by_username("carol").await.unwrap();
+        assert_eq!(found.map(|user| user.telegram_id), Some(1202));
+        assert_eq!(username_of(db.find_user_by_id(1203).await.unwrap()), None);
+
+        // Dropping the username clears it, and unknown users stay unknown
+        assert!(db.refresh_username(1202, None).await.unwrap());
+        assert_eq!(username_of(db.find_user_by_id(1202).await.unwrap()), None);
+        assert!(!db.refresh_username(1299, Some("dave")).await.unwrap());
+        assert!(db.find_user_by_id(1299).await.unwrap().is_none());
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_delegates(pool: PgPool) {
+        let db = bot_db(pool);
+        db.ensure_user_setup(1301, Some("alice")).await.unwrap();
+        db.ensure_user_setup(1302, Some("bob_the_pa"))
+            .await
+            .unwrap();
+
+        let granted = db.grant_delegate(1301, "@Bob_the_PA").await.unwrap();
+        assert_eq!(granted.user_id, UserId::new(1302));
+        // Granting twice keeps the one grant
+        db.grant_delegate(1301, "bob_the_pa").await.unwrap();
+        assert_eq!(db.list_delegates(1301).await.unwrap().len(), 1);
+        let calendars = db.list_delegated_calendars(1302).await.unwrap();
+        assert_eq!(calendars[0].username.as_deref(), Some("alice"));
+
+        assert!(matches!(
+            db.grant_delegate(1301, "@alice").await,
+            Err(ApplicationError::BadRequest(_))
+        ));
+        assert!(matches!(
+            db.grant_delegate(1301, "@nobody_here").await,
+            Err(ApplicationError::NotFound(_))
+        ));
+
+        assert!(db.revoke_delegate(1301, "@bob_the_pa").await.unwrap());
+        assert!(!db.revoke_delegate(1301, "@bob_the_pa").await.unwrap());
+        assert!(db.list_delegated_calendars(1302).await.unwrap().is_empty());
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_onboarding_email(pool: PgPool) {
+        let db = bot_db(pool);
+        db.ensure_user_setup(1101, Some("alice")).await.unwrap();
+        db.ensure_user_setup(1102, Some("bob")).await.unwrap();
+
+        // The questions are asked on the first /start only
+        assert!(db.begin_onboarding(1101).await.unwrap());
+        assert!(!db.begin_onboarding(1101).await.unwrap());
+
+        let berlin = Timezone::parse("Europe/Berlin").unwrap();
+        db.set_timezone(1101, &berlin).await.unwrap();
+        let (_, timezone) = db.calendar_stats(1101, Duration::days(1)).await.unwrap();
+        assert_eq!(timezone, berlin);
+
+        assert_eq!(
+            db.set_email(1101, Some(" Alice@Example.com "))
+                .await
+                .unwrap(),
+            Some("Alice@Example.com".to_string())
+        );
+        let found = db.find_user_by_email("alice@example.com").await.unwrap();
+        assert_eq!(found.map(|user| user.telegram_id), Some(1101));
+
+        // One account per address; internal addresses aren't real inboxes
+        assert!(matches!(
+            db.set_email(1102, Some("ALICE@example.com")).await,
+            Err(ApplicationError::Conflict(_))
+        ));
+        assert!(matches!(
+            db.set_email(1102, Some(&internal_email_for_telegram_id(1101)))
+                .await,
+            Err(ApplicationError::BadRequest(_))
+        ));
+
+        assert_eq!(db.set_email(1101, None).await.unwrap(), None);
+        assert!(
+            db.find_user_by_email("alice@example.com")
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_near_duplicate_events(pool: PgPool) {
+        use crate::event_parser::ParsedTiming;
+
+        let db = bot_db(pool);
+        let telegram_id = 1003;
+        db.ensure_user_setup(telegram_id, None).await.unwrap();
+
+        let start = Utc::now() + Duration::days(1);
+        let timed = |offset_secs: i64| ParsedTiming::Timed {
+            start: start + Duration::seconds(offset_secs),
+            duration_minutes: 30,
+        };
+        let create = |summary: &'static str, timing: ParsedTiming, allow_duplicate: bool| {
+            let db = db.clone();
+            async move {
+                db.create_event(
+                    telegram_id,
+                    &Uuid::new_v4().to_string(),
+                    summary,
+                    None,
+                    None,
+                    timing,
+                    "UTC",
+                    allow_duplicate,
+                )
+                .await
+            }
+        };
+
+        create("Dentist", timed(0), false).await.unwrap();
+        // Same summary, ignoring case, within a minute
+        assert!(matches!(
+            create("dentist", timed(45), false).await,
+            Err(ApplicationError::Conflict(_))
+        ));
+        // Outside the window, another title, or confirmed by the user
+        create("Dentist", timed(120), false).await.unwrap();
+        create("Dentist follow-up", timed(0), false).await.unwrap();
+        create("Dentist", timed(0), true).await.unwrap();
+
+        // All-day events match on the day
+        let day = ParsedTiming::AllDay {
+            date: start.date_naive(),
+        };
+        create("Offsite", day.clone(), false).await.unwrap();
+        assert!(matches!(
+            create("Offsite", day, false).await,
+            Err(ApplicationError::Conflict(_))
+        ));
+        assert_eq!(
+            db.get_all_events_for_user(telegram_id).await.unwrap().len(),
+            5
+        );
+    }
+
     #[sqlx::test(migrations = "../migrations")]
     async fn test_event_lifecycle(pool: PgPool) {
         let db = bot_db(pool);
@@ -564,6 +1746,7 @@ mod tests {
                     duration_minutes: 60,
                 },
                 "UTC",
+                true,
             )
             .await
             .expect("Failed to create event");
@@ -584,6 +1767,13 @@ mod tests {
         assert_eq!(events.len(), 1);
         assert_eq!(events[0].summary, "Test Event");
 
+        // The flood guard counts it after a restart
+        let created = db
+            .count_events_created_since(telegram_id, Utc::now() - Duration::minutes(1))
+            .await
+            .unwrap();
+        assert_eq!(created, 1);
+
         // Retrieve event via get_all_events_for_user
         let all_events = db
             .get_all_events_for_user(telegram_id)
@@ -652,6 +1842,104 @@ mod tests {
         assert!(devices_after.is_empty());
     }
 
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_device_activity_keeps_recent_requests(pool: PgPool) {
+        let db = bot_db(pool);
+        let telegram_id = 1004;
+        db.ensure_user_setup(telegram_id, None)
+            .await
+            .expect("Setup failed");
+        db.generate_device_password(telegram_id, "Phone")
+            .await
+            .expect("Generate failed");
+        let device_id = db
+            .list_device_passwords(telegram_id)
+            .await
+            .expect("List failed")[0]
+            .id;
+
+        for idx in 0..55 {
+            db.device
+                .record_device_activity(televent_application::DeviceActivity {
+                    device_id,
+                    method: "REPORT".to_string(),
+                    path: format!("/caldav/{telegram_id}/{idx}"),
+                    user_agent: Some("DAVx5/4.4".to_string()),
+                    status: 207,
+                    sync_token: (idx == 54).then(|| "televent:7".to_string()),
+                    conflict: None,
+                })
+                .await
+                .expect("Record failed");
+        }
+
+        let activity = db
+            .device_activity(telegram_id, device_id)
+            .await
+            .expect("Activity failed")
+            .expect("Device should be found");
+        assert_eq!(activity.len(), 50);
+        assert_eq!(activity[0].path, format!("/caldav/{telegram_id}/54"));
+        assert_eq!(activity[0].sync_token.as_deref(), Some("televent:7"));
+        assert_eq!(activity[49].path, format!("/caldav/{telegram_id}/5"));
+
+        // Another user can't read the device's activity
+        let other = db
+            .device_activity(2004, device_id)
+            .await
+            .expect("Activity failed");
+        assert!(other.is_none());
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_feature_flag_rollout_and_overrides(pool: PgPool) {
+        let db = bot_db(pool);
+        let telegram_id = 1005;
+        db.ensure_user_setup(telegram_id, None)
+            .await
+            .expect("Setup failed");
+
+        // Seeded fully on by the migration; unknown flags are off
+        assert!(
+            db.is_feature_enabled(televent_application::EXTERNAL_INVITES_FLAG, telegram_id)
+                .await
+                .unwrap()
+        );
+        assert!(!db.is_feature_enabled("polls", telegram_id).await.unwrap());
+
+        db.flags
+            .put_flag(televent_application::PutFeatureFlagCommand {
+                name: "polls".to_string(),
+                description: "Scheduling polls".to_string(),
+                enabled: true,
+                rollout_percent: 0,
+            })
+            .await
+            .expect("Put flag failed");
+        assert!(!db.is_feature_enabled("polls", telegram_id).await.unwrap());
+
+        db.flags
+            .set_override("polls", UserId::new(telegram_id), Some(true))
+            .await
+            .expect("Override failed");
+        assert!(db.is_feature_enabled("polls", telegram_id).await.unwrap());
+        assert!(!db.is_feature_enabled("polls", 2005).await.unwrap());
+
+        db.flags
+            .set_override("polls", UserId::new(telegram_id), None)
+            .await
+            .expect("Clear override failed");
+        assert!(!db.is_feature_enabled("polls", telegram_id).await.unwrap());
+
+        // Overrides need an existing flag and user
+        let err = db
+            .flags
+            .set_override("polls", UserId::new(2005), Some(true))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ApplicationError::NotFound(_)));
+    }
+
     #[sqlx::test(migrations = "../migrations")]
     async fn test_invites_and_rsvps(pool: PgPool) {
         let db = bot_db(pool);
@@ -681,6 +1969,7 @@ mod tests {
                     duration_minutes: 60,
                 },
                 "UTC",
+                true,
             )
             .await
             .expect("Create event failed");
@@ -738,6 +2027,247 @@ mod tests {
         assert_eq!(org_id_check, organizer_id);
     }
 
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_bulk_invite_reports_duplicates_and_batches_outbox(pool: PgPool) {
+        let db = bot_db(pool.clone());
+        let organizer_id = 1010;
+        let first_id = 1011;
+        let second_id = 1012;
+
+        for (telegram_id, username) in [
+            (organizer_id, "bulk_organizer"),
+            (first_id, "bulk_first"),
+            (second_id, "bulk_second"),
+        ] {
+            db.ensure_user_setup(telegram_id, Some(username))
+                .await
+                .expect("User setup failed");
+        }
+
+        let event = db
+            .create_event(
+                organizer_id,
+                &Uuid::new_v4().to_string(),
+                "Offsite",
+                None,
+                None,
+                crate::event_parser::ParsedTiming::Timed {
+                    start: Utc::now(),
+                    duration_minutes: 60,
+                },
+                "UTC",
+                true,
+            )
+            .await
+            .expect("Create event failed");
+
+        db.invite_attendee(
+            event.id,
+            &televent_domain::internal_email_for_telegram_id(first_id),
+            Some(first_id),
+            "ATTENDEE",
+        )
+        .await
+        .expect("Single invite failed");
+        db.confirm_rsvp(event.id, first_id, "ACCEPTED")
+            .await
+            .expect("RSVP failed");
+        let outbox_before: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM outbox_messages")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+
+        let outcome = db
+            .invite_attendees(
+                event.id,
+                &[
+                    (
+                        televent_domain::internal_email_for_telegram_id(first_id),
+                        Some(first_id),
+                    ),
+                    (
+                        televent_domain::internal_email_for_telegram_id(second_id),
+                        Some(second_id),
+                    ),
+                    ("guest@example.com".to_string(), None),
+                ],
+            )
+            .await
+            .expect("Bulk invite failed");
+
+        assert_eq!(outcome.invited.len(), 2);
+        assert_eq!(
+            outcome.already_invited,
+            vec![televent_domain::internal_email_for_telegram_id(first_id)]
+        );
+
+        let outbox_after: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM outbox_messages")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(outbox_after - outbox_before, 2);
+
+        // The existing RSVP is left alone
+        let attendees = db.get_event_attendees(event.id).await.unwrap();
+        let first = attendees
+            .iter()
+            .find(|attendee| attendee.telegram_id == Some(first_id))
+            .expect("First attendee missing");
+        assert_eq!(first.status, "ACCEPTED");
+        assert_eq!(db.get_pending_invites(second_id).await.unwrap().len(), 1);
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_suggest_attendees_ranks_past_invitees(pool: PgPool) {
+        let db = bot_db(pool.clone());
+        let organizer_id = 1030;
+        let friend_id = 1031;
+
+        for (telegram_id, username) in [
+            (organizer_id, "suggest_organizer"),
+            (friend_id, "suggest_friend"),
+        ] {
+            db.ensure_user_setup(telegram_id, Some(username))
+                .await
+                .expect("User setup failed");
+        }
+
+        let friend_email = televent_domain::internal_email_for_telegram_id(friend_id);
+        for summary in ["Standup", "Retro"] {
+            let event = db
+                .create_event(
+                    organizer_id,
+                    &Uuid::new_v4().to_string(),
+                    summary,
+                    None,
+                    None,
+                    crate::event_parser::ParsedTiming::Timed {
+                        start: Utc::now(),
+                        duration_minutes: 30,
+                    },
+                    "UTC",
+                    true,
+                )
+                .await
+                .expect("Create event failed");
+
+            let mut invitees = vec![("guest@example.com".to_string(), None)];
+            if summary == "Retro" {
+                invitees.push((friend_email.clone(), Some(friend_id)));
+            }
+            db.invite_attendees(event.id, &invitees)
+                .await
+                .expect("Invite failed");
+        }
+
+        let suggestions = db
+            .suggest_attendees(organizer_id, 10)
+            .await
+            .expect("Suggestions failed");
+        assert_eq!(suggestions.len(), 2);
+        assert_eq!(suggestions[0].email, "guest@example.com");
+        assert_eq!(suggestions[0].invite_count, 2);
+        assert_eq!(suggestions[1].name, "@suggest_friend");
+        assert_eq!(suggestions[1].email, friend_email);
+        assert_eq!(suggestions[1].telegram_id, Some(friend_id));
+
+        // Invitees never see the organizer's guest list
+        assert!(
+            db.suggest_attendees(friend_id, 10)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_resend_and_remove_attendee_queue_notifications(pool: PgPool) {
+        let db = bot_db(pool.clone());
+        let organizer_id = 1020;
+        let pending_id = 1021;
+        let accepted_id = 1022;
+
+        for (telegram_id, username) in [
+            (organizer_id, "dash_organizer"),
+            (pending_id, "dash_pending"),
+            (accepted_id, "dash_accepted"),
+        ] {
+            db.ensure_user_setup(telegram_id, Some(username))
+                .await
+                .expect("User setup failed");
+        }
+
+        let event = db
+            .create_event(
+                organizer_id,
+                &Uuid::new_v4().to_string(),
+                "Review",
+                None,
+                None,
+                crate::event_parser::ParsedTiming::Timed {
+                    start: Utc::now(),
+                    duration_minutes: 30,
+                },
+                "UTC",
+                true,
+            )
+            .await
+            .expect("Create event failed");
+
+        let pending_email = televent_domain::internal_email_for_telegram_id(pending_id);
+        let accepted_email = televent_domain::internal_email_for_telegram_id(accepted_id);
+        db.invite_attendees(
+            event.id,
+            &[
+                (pending_email.clone(), Some(pending_id)),
+                (accepted_email.clone(), Some(accepted_id)),
+            ],
+        )
+        .await
+        .expect("Invite failed");
+        db.confirm_rsvp(event.id, accepted_id, "ACCEPTED")
+            .await
+            .expect("RSVP failed");
+
+        db.resend_invite(organizer_id, event.id, &pending_email)
+            .await
+            .expect("Resend failed");
+        let err = db
+            .resend_invite(organizer_id, event.id, &accepted_email)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ApplicationError::BadRequest(_)));
+        let err = db
+            .remove_attendee(pending_id, event.id, &accepted_email)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ApplicationError::NotFound(_)));
+
+        db.remove_attendee(organizer_id, event.id, &accepted_email)
+            .await
+            .expect("Remove failed");
+
+        let attendees = db.get_event_attendees(event.id).await.unwrap();
+        assert!(
+            attendees
+                .iter()
+                .all(|attendee| attendee.telegram_id != Some(accepted_id))
+        );
+
+        let kinds: Vec<String> = sqlx::query_scalar(
+            "SELECT kind::text FROM outbox_messages \
+             WHERE kind IN ('invite_reminder', 'attendee_removed_notification') \
+             ORDER BY created_at",
+        )
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        assert_eq!(
+            kinds,
+            vec!["invite_reminder", "attendee_removed_notification"]
+        );
+    }
+
     #[sqlx::test(migrations = "../migrations")]
     async fn test_confirm_rsvp_transaction(pool: PgPool) {
         let db = bot_db(pool.clone());
@@ -766,6 +2296,7 @@ mod tests {
                     duration_minutes: 60,
                 },
                 "UTC",
+                true,
             )
             .await
             .expect("Create event failed");
@@ -832,6 +2363,271 @@ mod tests {
             .find(|a| a.telegram_id == Some(attendee_id))
             .unwrap();
         assert_eq!(att.status, "TENTATIVE");
+
+        // Repeating the same answer or re-inviting a pending attendee is a no-op
+        db.confirm_rsvp(event.id, attendee_id, "TENTATIVE")
+            .await
+            .expect("Repeated confirm_rsvp failed");
+        db.invite_attendee(event.id, "pending@tx.com", None, "ATTENDEE")
+            .await
+            .expect("Invite failed");
+        let before: (i64, i32) = sqlx::query_as(
+            "SELECT u.sync_token, e.version FROM users u JOIN events e ON u.telegram_id = e.user_id WHERE e.id = $1"
+        )
+        .bind(event.id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        db.invite_attendee(event.id, "pending@tx.com", None, "ATTENDEE")
+            .await
+            .expect("Repeated invite failed");
+        db.confirm_rsvp(event.id, attendee_id, "TENTATIVE")
+            .await
+            .expect("Repeated confirm_rsvp failed");
+        let after: (i64, i32) = sqlx::query_as(
+            "SELECT u.sync_token, e.version FROM users u JOIN events e ON u.telegram_id = e.user_id WHERE e.id = $1"
+        )
+        .bind(event.id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(after, before, "No-op writes must not bump the revision");
+        assert_eq!(new_sync_token + 1, before.0);
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_subscriptions_mirror_remote_events(pool: PgPool) {
+        let db = bot_db(pool.clone());
+        let subscriptions = SubscriptionService::new(
+            televent_storage::subscription::SubscriptionRepository::new(pool),
+        );
+        let telegram_id = 700_001;
+
+        assert!(matches!(
+            db.add_subscription(telegram_id, None, "http://localhost/cal.ics", None)
+                .await,
+            Err(ApplicationError::BadRequest(_))
+        ));
+
+        let subscription = db
+            .add_subscription(
+                telegram_id,
+                Some("subscriber"),
+                "https://calendar.example.com/team.ics",
+                None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(subscription.name, "calendar.example.com");
+        assert!(subscription.last_fetched_at.is_none());
+        assert!(matches!(
+            db.add_subscription(
+                telegram_id,
+                None,
+                "https://calendar.example.com/team.ics",
+                Some("Again")
+            )
+            .await,
+            Err(ApplicationError::Conflict(_))
+        ));
+
+        let start = Utc::now() + Duration::days(1);
+        let feed = |summary: &str| FetchedFeed {
+            body: format!(
+                "BEGIN:VCALENDAR\r\n\
+                 BEGIN:VEVENT\r\n\
+                 UID:remote-1\r\n\
+                 SUMMARY:{summary}\r\n\
+                 DTSTART:{}\r\n\
+                 DTEND:{}\r\n\
+                 END:VEVENT\r\n\
+                 END:VCALENDAR\r\n",
+                start.format("%Y%m%dT%H%M%SZ"),
+                (start + Duration::hours(1)).format("%Y%m%dT%H%M%SZ")
+            ),
+            http_etag: None,
+            http_last_modified: None,
+        };
+
+        assert!(
+            subscriptions
+                .apply_feed(subscription.id, feed("Planning"))
+                .await
+                .unwrap()
+                .changed
+        );
+        assert!(
+            !subscriptions
+                .apply_feed(subscription.id, feed("Planning"))
+                .await
+                .unwrap()
+                .changed
+        );
+        assert!(
+            subscriptions
+                .apply_feed(subscription.id, feed("Review"))
+                .await
+                .unwrap()
+                .changed
+        );
+
+        let range_start = Utc::now();
+        let range_end = range_start + Duration::days(7);
+        let mirrored = db
+            .get_subscribed_events_for_user(telegram_id, range_start, range_end)
+            .await
+            .unwrap();
+        assert_eq!(mirrored.len(), 1);
+        assert_eq!(mirrored[0].summary, "Review");
+        assert_eq!(
+            mirrored[0].subscription.as_deref(),
+            Some("calendar.example.com")
+        );
+        // Mirrored events stay out of the user's own calendar
+        assert!(
+            db.get_events_for_user(telegram_id, range_start, range_end)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        let listed = db.list_subscriptions(telegram_id).await.unwrap();
+        assert_eq!(listed.len(), 1);
+        assert!(listed[0].last_fetched_at.is_some());
+
+        assert!(
+            db.remove_subscription(telegram_id, subscription.id)
+                .await
+                .unwrap()
+        );
+        assert!(
+            !db.remove_subscription(telegram_id, subscription.id)
+                .await
+                .unwrap()
+        );
+        assert!(
+            db.get_subscribed_events_for_user(telegram_id, range_start, range_end)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_holiday_calendar_shows_up_in_the_week(pool: PgPool) {
+        use chrono::{Datelike, TimeZone};
+
+        let db = bot_db(pool);
+        let telegram_id = 700_002;
+
+        assert!(matches!(
+            db.add_subscription(telegram_id, None, "holidays:xx", None)
+                .await,
+            Err(ApplicationError::BadRequest(_))
+        ));
+
+        // Filled right away, without waiting for the refresh task
+        let holidays = db.enable_holidays(telegram_id, None, "de").await.unwrap();
+        assert_eq!(holidays.name, "Holidays in Germany");
+        assert_eq!(holidays.url, "holidays:DE");
+        assert!(holidays.last_fetched_at.is_some());
+        let again = db
+            .add_subscription(telegram_id, None, "HOLIDAYS:de", None)
+            .await;
+        assert!(matches!(again, Err(ApplicationError::Conflict(_))));
+        assert_eq!(
+            db.enable_holidays(telegram_id, None, "DE")
+                .await
+                .unwrap()
+                .id,
+            holidays.id
+        );
+
+        let year = Utc::now().year();
+        let week_start = Utc.with_ymd_and_hms(year, 12, 22, 0, 0, 0).unwrap();
+        let mirrored = db
+            .get_subscribed_events_for_user(telegram_id, week_start, week_start + Duration::days(7))
+            .await
+            .unwrap();
+        let names: Vec<&str> = mirrored
+            .iter()
+            .map(|event| event.summary.as_str())
+            .collect();
+        assert_eq!(names, ["Christmas Day", "Second Day of Christmas"]);
+        assert_eq!(
+            mirrored[0].subscription.as_deref(),
+            Some("Holidays in Germany")
+        );
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_birthdays_calendar_from_contacts(pool: PgPool) {
+        use televent_application::PutContactCommand;
+
+        let db = bot_db(pool.clone());
+        let contacts = ContactService::new(televent_storage::contact::ContactRepository::new(
+            pool.clone(),
+        ));
+        let subscriptions = SubscriptionService::new(
+            televent_storage::subscription::SubscriptionRepository::new(pool.clone()),
+        );
+        let telegram_id = 700_003;
+        let tomorrow = Utc::now().date_naive() + Duration::days(1);
+        for (uid, name, bday) in [
+            ("alice", "Alice", tomorrow.format("--%m%d").to_string()),
+            ("bob", "Bob", String::new()),
+        ] {
+            let bday = if bday.is_empty() {
+                String::new()
+            } else {
+                format!("BDAY:{bday}\r\n")
+            };
+            contacts
+                .put_contact(PutContactCommand {
+                    user_id: UserId::new(telegram_id),
+                    username: None,
+                    uid: uid.to_string(),
+                    vcard: format!(
+                        "BEGIN:VCARD\r\nVERSION:3.0\r\nUID:{uid}\r\nFN:{name}\r\n{bday}END:VCARD\r\n"
+                    ),
+                    expected_etag: None,
+                    create_only: true,
+                })
+                .await
+                .unwrap();
+        }
+
+        let birthdays = db.enable_birthdays(telegram_id, None).await.unwrap();
+        assert_eq!(birthdays.name, "Birthdays");
+        assert_eq!(
+            db.enable_birthdays(telegram_id, None).await.unwrap().id,
+            birthdays.id
+        );
+
+        let week_start = Utc::now();
+        let week = db
+            .get_subscribed_events_for_user(telegram_id, week_start, week_start + Duration::days(7))
+            .await
+            .unwrap();
+        assert_eq!(week.len(), 1);
+        assert_eq!(week[0].summary, "Alice's birthday");
+        assert_eq!(week[0].start_date, Some(tomorrow));
+
+        // Turning it on only fills the calendar; the morning refresh announces
+        let notices = || async {
+            sqlx::query_scalar::<_, String>(
+                "SELECT payload->>'message' FROM outbox_messages WHERE kind = 'telegram_notification'",
+            )
+            .fetch_all(&pool)
+            .await
+            .unwrap()
+        };
+        assert!(notices().await.is_empty());
+        subscriptions.apply_birthdays(birthdays.id).await.unwrap();
+        assert_eq!(notices().await, ["🎂 Alice's birthday tomorrow"]);
+
+        assert!(db.disable_birthdays(telegram_id).await.unwrap());
+        assert!(!db.disable_birthdays(telegram_id).await.unwrap());
     }
 
     #[sqlx::test(migrations = "../migrations")]
@@ -1,9 +1,12 @@
 //! Event message parser
 //!
-//! Parses multi-line text messages into event data for creation.
+//! Parses multi-line text messages into event data for creation,
+//! sentences such as "Dinner Friday 7pm at Luigi's" found in forwarded
+//! messages, and the text read off event posters.
 
-use chrono::{DateTime, Local, NaiveDate, Utc};
+use chrono::{DateTime, Datelike, Local, NaiveDate, NaiveTime, TimeZone, Utc};
 use chrono_english::{Dialect, parse_date_string};
+use televent_domain::tags::event_tags;
 use thiserror::Error;
 
 /// Errors that can occur during event parsing
@@ -25,8 +28,107 @@ pub enum ParseError {
 
     #[error("Message must have at least 2 lines (title and date/time)")]
     TooFewLines,
+
+    #[error(
+        "Reply with 'move to 4pm', 'move to tomorrow 10:00', 'rename to Sprint Review' or 'location Room B'; for a repeating event, end with ', this only' or ', this and following' to change just the next one or it and all after it"
+    )]
+    UnknownEdit,
+
+    #[error("Say what and when, like 'Dinner Friday 7pm at Luigi's'")]
+    NotAnEvent,
 }
 
+/// Words joining a title, a date and a place rather than belonging to one
+const CONNECTORS: [&str; 7] = ["at", "@", "on", "by", "from", "-", "–"];
+/// Words after which a sentence names the place
+const PLACE_MARKERS: [&str; 2] = ["at", "@"];
+/// Words that give a date phrase a time of day
+const TIME_WORDS: [&str; 6] = [
+    "noon",
+    "midnight",
+    "morning",
+    "afternoon",
+    "evening",
+    "tonight",
+];
+/// Words besides numbers a date/time phrase may be made of; chrono-english
+/// skips words it doesn't know, so they would end up in the phrase
+const DATE_WORDS: &[&str] = &[
+    "today",
+    "tomorrow",
+    "tonight",
+    "yesterday",
+    "next",
+    "this",
+    "last",
+    "in",
+    "at",
+    "on",
+    "the",
+    "of",
+    "am",
+    "pm",
+    "noon",
+    "midnight",
+    "morning",
+    "afternoon",
+    "evening",
+    "minute",
+    "minutes",
+    "hour",
+    "hours",
+    "day",
+    "days",
+    "week",
+    "weeks",
+    "monday",
+    "tuesday",
+    "wednesday",
+    "thursday",
+    "friday",
+    "saturday",
+    "sunday",
+    "mon",
+    "tue",
+    "wed",
+    "thu",
+    "fri",
+    "sat",
+    "sun",
+    "january",
+    "february",
+    "march",
+    "april",
+    "may",
+    "june",
+    "july",
+    "august",
+    "september",
+    "october",
+    "november",
+    "december",
+    "jan",
+    "feb",
+    "mar",
+    "apr",
+    "jun",
+    "jul",
+    "aug",
+    "sep",
+    "sept",
+    "oct",
+    "nov",
+    "dec",
+];
+/// Longest date/time phrase looked for, in words
+const MAX_DATE_WORDS: usize = 6;
+/// Words of a line searched for a date
+const MAX_SENTENCE_WORDS: usize = 40;
+/// Labels a poster puts before its venue
+const VENUE_LABELS: [&str; 5] = ["at ", "@", "venue:", "location:", "where:"];
+/// Lines of text read off a poster that are looked at
+const MAX_POSTER_LINES: usize = 30;
+
 /// Timing information for a parsed event
 #[derive(Debug, Clone, PartialEq)]
 pub enum ParsedTiming {
@@ -68,6 +170,11 @@ impl ParsedEvent {}
 /// 3. Duration in minutes (optional, default: 60)
 /// 4. Location (optional)
 pub fn parse_event_message(text: &str) -> Result<ParsedEvent, ParseError> {
+    parse_event_message_at(text, Local::now())
+}
+
+/// [`parse_event_message`] with relative dates read as of `now`
+fn parse_event_message_at(text: &str, now: DateTime<Local>) -> Result<ParsedEvent, ParseError> {
     let lines: Vec<&str> = text.lines().map(|l| l.trim()).collect();
 
     // Must have at least 2 lines (title and date)
@@ -87,27 +194,7 @@ pub fn parse_event_message(text: &str) -> Result<ParsedEvent, ParseError> {
         return Err(ParseError::MissingDateTime);
     }
 
-    // Parse datetime using chrono-english for natural language
-    let start = parse_datetime(datetime_str)?;
-
-    // Simple heuristic to detect All-Day: if no time-like keywords or symbols are present
-    // and the resulting time is midnight local.
-    // Keywords: "at", ":", "am", "pm", "morning", "afternoon", "evening", "noon"
-    let has_time_marker = {
-        let low = datetime_str.to_lowercase();
-        low.contains(':')
-            || low.contains("at")
-            || low.contains("am")
-            || low.contains("pm")
-            || low.contains("morning")
-            || low.contains("afternoon")
-            || low.contains("evening")
-            || low.contains("noon")
-            || low.contains("h")
-    };
-
-    let is_midnight = start.with_timezone(&Local).time() == chrono::NaiveTime::MIN;
-    let is_all_day = !has_time_marker && is_midnight;
+    let (start, is_all_day) = parse_start(datetime_str, now)?;
 
     // Line 3: Duration in minutes (optional, default: 60)
     let duration_minutes = if lines.len() > 2 && !lines[2].is_empty() {
@@ -147,11 +234,389 @@ pub fn parse_event_message(text: &str) -> Result<ParsedEvent, ParseError> {
     })
 }
 
-/// Parse a date/time string using chrono-english for natural language support
-fn parse_datetime(input: &str) -> Result<DateTime<Utc>, ParseError> {
-    // Get current time as the reference point
-    let now = Local::now();
+/// Parse free text such as "Dinner Friday 7pm at Luigi's" into event data
+///
+/// Relative dates are read as of `written_at`, when the text was written. A
+/// message in the multi-line format is read as such; otherwise the first
+/// line with a date in it is used. The longest run of words there that reads
+/// as a date or time is the start, words after "at" or "@" are the place and
+/// the rest is the title.
+pub fn parse_event_sentence(
+    text: &str,
+    written_at: DateTime<Local>,
+) -> Result<ParsedEvent, ParseError> {
+    if let Ok(event) = parse_event_message_at(text, written_at) {
+        return Ok(event);
+    }
+    text.lines()
+        .find_map(|line| parse_sentence_line(line, written_at))
+        .ok_or(ParseError::NotAnEvent)
+}
+
+/// Reduce the text read off an event poster to one sentence for
+/// [`parse_event_sentence`]
+///
+/// Posters spread an event over lines: the day and the time on lines of
+/// their own, the venue after "at" or a "Venue:" label, and the title
+/// usually first. Returns `None` when no line reads as a date.
+pub fn poster_sentence(text: &str, seen_at: DateTime<Local>) -> Option<String> {
+    let lines: Vec<&str> = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .take(MAX_POSTER_LINES)
+        .collect();
+    let is_date_line = |line: &str| line.split_whitespace().map(bare).all(is_date_word);
+
+    // The first run of date lines with a day in it. chrono-english reads
+    // neither "Friday, March 13" nor a day followed by a time, so the day
+    // and the clock are found apart and written out in full
+    let mut runs = (0..lines.len())
+        .filter(|&i| is_date_line(lines[i]) && (i == 0 || !is_date_line(lines[i - 1])));
+    let (first, end, date, clock) = runs.find_map(|first| {
+        let end = (first..lines.len())
+            .find(|&i| !is_date_line(lines[i]))
+            .unwrap_or(lines.len());
+        let words: Vec<&str> = lines[first..end]
+            .iter()
+            .flat_map(|line| bare_words(line))
+            .collect();
+        let date = poster_date(&words, seen_at)?;
+        Some((first, end, date, poster_clock(&words)))
+    })?;
+
+    let title = lines.iter().enumerate().find_map(|(i, line)| {
+        let usable = !(first..end).contains(&i)
+            && !is_date_line(line)
+            && venue_of(line).is_none()
+            && line.chars().any(char::is_alphabetic);
+        usable.then_some(*line)
+    })?;
+    let when = match clock {
+        Some(clock) => date.and_time(clock).format("%Y-%m-%d %H:%M").to_string(),
+        None => date.format("%Y-%m-%d").to_string(),
+    };
+    Some(match lines.iter().find_map(|line| venue_of(line)) {
+        Some(venue) => format!("{title} {when} at {venue}"),
+        None => format!("{title} {when}"),
+    })
+}
+
+/// Day named by the date words of a poster, without its time
+///
+/// A day without a year that has already passed is taken to be next year's.
+fn poster_date(words: &[&str], seen_at: DateTime<Local>) -> Option<NaiveDate> {
+    let names_clock = |word: &&str| {
+        word.contains(':')
+            || is_one_of(&["am", "pm"], word)
+            || parse_clock(word).is_some() && word.to_ascii_lowercase().ends_with('m')
+    };
+    let date = (1..=MAX_DATE_WORDS.min(words.len()))
+        .rev()
+        .flat_map(|len| (0..=words.len() - len).map(move |from| &words[from..from + len]))
+        .filter(|phrase| !is_one_of(&CONNECTORS, phrase[0]) && !phrase.iter().any(names_clock))
+        .find_map(|phrase| parse_datetime(&phrase.join(" "), seen_at).ok())?
+        .with_timezone(&Local)
+        .date_naive();
+
+    let names_year = words
+        .iter()
+        .any(|word| word.len() == 4 && word.parse::<i32>().is_ok());
+    if date < seen_at.date_naive() && !names_year {
+        return date.with_year(date.year() + 1);
+    }
+    Some(date)
+}
+
+/// Time of day named by the date words of a poster, such as "8 PM" or "19:30"
+fn poster_clock(words: &[&str]) -> Option<NaiveTime> {
+    let pairs = words.windows(2).map(|pair| pair.join(" "));
+    pairs
+        .chain(words.iter().map(|word| word.to_string()))
+        .find_map(|clock| {
+            let lower = clock.to_ascii_lowercase();
+            if lower == "noon" {
+                return NaiveTime::from_hms_opt(12, 0, 0);
+            }
+            let names_clock = lower.contains(':') || lower.ends_with("am") || lower.ends_with("pm");
+            names_clock.then(|| parse_clock(&lower)).flatten()
+        })
+}
+
+/// Venue named by a poster line such as "at Town Hall" or "Venue: Town Hall"
+fn venue_of(line: &str) -> Option<&str> {
+    VENUE_LABELS
+        .iter()
+        .find_map(|label| {
+            line.get(..label.len())
+                .filter(|head| head.eq_ignore_ascii_case(label))
+                .map(|_| line[label.len()..].trim())
+        })
+        .filter(|venue| !venue.is_empty())
+}
+
+fn bare_words(line: &str) -> Vec<&str> {
+    line.split_whitespace().map(bare).collect()
+}
+
+/// A word without the punctuation a sentence puts after it
+fn bare(word: &str) -> &str {
+    word.trim_end_matches([',', '.', ';', '!', '?'])
+}
+
+fn is_one_of(list: &[&str], word: &str) -> bool {
+    list.iter().any(|w| w.eq_ignore_ascii_case(word))
+}
+
+/// Whether a word may be part of a date/time phrase
+fn is_date_word(word: &str) -> bool {
+    word.chars().any(|c| c.is_ascii_digit()) || is_one_of(DATE_WORDS, word)
+}
+
+fn parse_sentence_line(line: &str, written_at: DateTime<Local>) -> Option<ParsedEvent> {
+    let words: Vec<&str> = line.split_whitespace().take(MAX_SENTENCE_WORDS).collect();
+    let bare: Vec<&str> = words.iter().map(|word| bare(word)).collect();
+
+    // Longest phrase first, then the earliest
+    let (from, to, start) = (1..=MAX_DATE_WORDS.min(words.len()))
+        .rev()
+        .flat_map(|len| (0..=words.len() - len).map(move |from| (from, from + len)))
+        .filter(|&(from, to)| {
+            !is_one_of(&CONNECTORS, bare[from])
+                && !is_one_of(&CONNECTORS, bare[to - 1])
+                && bare[from..to].iter().all(|word| is_date_word(word))
+        })
+        .find_map(|(from, to)| {
+            let start = parse_datetime(&bare[from..to].join(" "), written_at).ok()?;
+            Some((from, to, start))
+        })?;
+
+    // "tomorrow" alone names a day, even though it keeps the time of writing
+    let names_time = bare[from..to].iter().any(|word| {
+        word.contains(':')
+            || is_one_of(&TIME_WORDS, word)
+            || parse_clock(word).is_some() && word.to_ascii_lowercase().ends_with('m')
+    });
+    let timing = if !names_time {
+        ParsedTiming::AllDay {
+            date: start.with_timezone(&Local).date_naive(),
+        }
+    } else {
+        ParsedTiming::Timed {
+            start,
+            duration_minutes: 60,
+        }
+    };
+
+    let mut before = words[..from].to_vec();
+    let mut after = words[to..].to_vec();
+    while before
+        .last()
+        .is_some_and(|word| is_one_of(&CONNECTORS, word))
+    {
+        before.pop();
+    }
+    let place = if after
+        .first()
+        .is_some_and(|word| is_one_of(&PLACE_MARKERS, word))
+    {
+        let place = after.split_off(1);
+        after.clear();
+        place
+    } else if let Some(marker) = before
+        .iter()
+        .rposition(|word| is_one_of(&PLACE_MARKERS, word))
+    {
+        before.split_off(marker).split_off(1)
+    } else {
+        Vec::new()
+    };
+
+    let tidy = |words: Vec<&str>| {
+        let joined = words.join(" ");
+        let trimmed = joined.trim_matches(|c: char| {
+            c.is_whitespace() || matches!(c, ',' | '.' | ';' | ':' | '-' | '–')
+        });
+        (!trimmed.is_empty()).then(|| trimmed.to_string())
+    };
+    // "#tags" belong to the title even when written after the place
+    let (tags, place): (Vec<&str>, Vec<&str>) = place
+        .into_iter()
+        .partition(|word| !event_tags(word).is_empty());
+    let title = tidy(before.into_iter().chain(after).chain(tags).collect())?;
+
+    Some(ParsedEvent {
+        title,
+        timing,
+        location: tidy(place),
+    })
+}
+
+/// A change asked for by replying to an event card
+#[derive(Debug, Clone, PartialEq)]
+pub enum EventEdit {
+    Rename(String),
+    Move(ParsedTiming),
+    Relocate(String),
+}
+
+/// How much of a repeating event a reply edit changes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EditReach {
+    /// Every occurrence
+    Series,
+    /// The next occurrence only
+    Next,
+    /// The next occurrence and every one after it
+    NextAndFollowing,
+}
+
+/// Split a trailing ", this only" or ", this and following" off a reply
+///
+/// Without one the edit reaches the whole series.
+pub fn split_edit_reach(text: &str) -> (EditReach, &str) {
+    const SUFFIXES: [(&str, EditReach); 5] = [
+        ("this and following", EditReach::NextAndFollowing),
+        ("and following", EditReach::NextAndFollowing),
+        ("this only", EditReach::Next),
+        ("only this", EditReach::Next),
+        ("just this one", EditReach::Next),
+    ];
+    let text = text.trim();
+    for (suffix, reach) in SUFFIXES {
+        let Some(cut) = text.len().checked_sub(suffix.len()) else {
+            continue;
+        };
+        let (rest, tail) = (text.get(..cut), text.get(cut..));
+        if let (Some(rest), Some(tail)) = (rest, tail)
+            && tail.eq_ignore_ascii_case(suffix)
+            && (rest.is_empty() || rest.ends_with([' ', ',']))
+        {
+            return (reach, rest.trim_end_matches([' ', ',']));
+        }
+    }
+    (EditReach::Series, text)
+}
+
+/// Parse a reply such as "move to 4pm", "rename to Sprint Review" or
+/// "location Room B" against the event's current timing
+///
+/// A bare time keeps the event's date; a move keeps the duration.
+pub fn parse_edit(text: &str, current: &ParsedTiming) -> Result<EventEdit, ParseError> {
+    // A trailing space lets a bare "rename to" match its own prefix
+    let text = format!("{} ", text.trim());
+    let value = |prefixes: &[&str]| {
+        prefixes.iter().find_map(|prefix| {
+            text.get(..prefix.len())
+                .filter(|head| head.eq_ignore_ascii_case(prefix))
+                .map(|_| text[prefix.len()..].trim())
+        })
+    };
+    let non_empty = |value: &str| {
+        if value.is_empty() {
+            Err(ParseError::UnknownEdit)
+        } else {
+            Ok(value.to_string())
+        }
+    };
+
+    if let Some(title) = value(&["rename to ", "rename "]) {
+        return Ok(EventEdit::Rename(non_empty(title)?));
+    }
+    if let Some(location) = value(&["location to ", "location: ", "location "]) {
+        return Ok(EventEdit::Relocate(non_empty(location)?));
+    }
+    let Some(target) = value(&["move to ", "reschedule to ", "move "]) else {
+        return Err(ParseError::UnknownEdit);
+    };
+    let target = non_empty(target)?;
+    let target = target.as_str();
+
+    let (date, duration_minutes) = match current {
+        ParsedTiming::Timed {
+            start,
+            duration_minutes,
+        } => (start.with_timezone(&Local).date_naive(), *duration_minutes),
+        ParsedTiming::AllDay { date } => (*date, 60),
+    };
+    if let Some(time) = parse_clock(target) {
+        let start = Local
+            .from_local_datetime(&date.and_time(time))
+            .earliest()
+            .ok_or_else(|| ParseError::InvalidDateTime(target.to_string()))?
+            .with_timezone(&Utc);
+        return Ok(EventEdit::Move(ParsedTiming::Timed {
+            start,
+            duration_minutes,
+        }));
+    }
+
+    let (start, is_all_day) = parse_start(target, Local::now())?;
+    Ok(EventEdit::Move(if is_all_day {
+        ParsedTiming::AllDay {
+            date: start.with_timezone(&Local).date_naive(),
+        }
+    } else {
+        ParsedTiming::Timed {
+            start,
+            duration_minutes,
+        }
+    }))
+}
+
+/// A bare time of day: "4pm", "4:30 pm", "at 16:00"
+fn parse_clock(input: &str) -> Option<NaiveTime> {
+    let lower = input.trim().to_ascii_lowercase();
+    let clock = lower.strip_prefix("at ").unwrap_or(&lower).trim();
+    let (clock, offset) = match clock.strip_suffix("pm") {
+        Some(clock) => (clock.trim_end(), Some(12)),
+        None => (
+            clock.strip_suffix("am").map_or(clock, str::trim_end),
+            clock.ends_with("am").then_some(0),
+        ),
+    };
+    let (hour, minute) = clock.split_once(':').unwrap_or((clock, "0"));
+    let hour: u32 = hour.parse().ok()?;
+    let minute: u32 = minute.parse().ok()?;
+    let hour = match offset {
+        Some(offset) if (1..=12).contains(&hour) => hour % 12 + offset,
+        Some(_) => return None,
+        None => hour,
+    };
+    NaiveTime::from_hms_opt(hour, minute, 0)
+}
+
+/// Start of a date/time expression, and whether it names a whole day
+fn parse_start(
+    datetime_str: &str,
+    now: DateTime<Local>,
+) -> Result<(DateTime<Utc>, bool), ParseError> {
+    // Parse datetime using chrono-english for natural language
+    let start = parse_datetime(datetime_str, now)?;
+
+    // Simple heuristic to detect All-Day: if no time-like keywords or symbols are present
+    // and the resulting time is midnight local.
+    // Keywords: "at", ":", "am", "pm", "morning", "afternoon", "evening", "noon"
+    let has_time_marker = {
+        let low = datetime_str.to_lowercase();
+        low.contains(':')
+            || low.contains("at")
+            || low.contains("am")
+            || low.contains("pm")
+            || low.contains("morning")
+            || low.contains("afternoon")
+            || low.contains("evening")
+            || low.contains("noon")
+            || low.contains("h")
+    };
+
+    let is_midnight = start.with_timezone(&Local).time() == chrono::NaiveTime::MIN;
+    Ok((start, !has_time_marker && is_midnight))
+}
 
+/// Parse a date/time string using chrono-english for natural language
+/// support, relative to `now`
+fn parse_datetime(input: &str, now: DateTime<Local>) -> Result<DateTime<Utc>, ParseError> {
     // Normalize input for chrono-english:
     // - Remove "at" as chrono-english doesn't need it
     // - Remove "in" prefix for relative times (e.g., "in 2 hours" -> "2 hours")
@@ -342,4 +807,228 @@ mod tests {
         assert_eq!(event.title, "Holidays");
         assert!(matches!(event.timing, ParsedTiming::AllDay { .. }));
     }
+
+    #[test]
+    fn test_parse_clock() {
+        let time = |h, m| NaiveTime::from_hms_opt(h, m, 0);
+        assert_eq!(parse_clock("4pm"), time(16, 0));
+        assert_eq!(parse_clock("4:30 PM"), time(16, 30));
+        assert_eq!(parse_clock("at 9am"), time(9, 0));
+        assert_eq!(parse_clock("12am"), time(0, 0));
+        assert_eq!(parse_clock("12pm"), time(12, 0));
+        assert_eq!(parse_clock("16:45"), time(16, 45));
+        assert_eq!(parse_clock("13pm"), None);
+        assert_eq!(parse_clock("tomorrow"), None);
+    }
+
+    #[test]
+    fn test_parse_event_sentence() {
+        // Tuesday morning
+        let written_at = Local
+            .from_local_datetime(
+                &NaiveDate::from_ymd_opt(2026, 3, 10)
+                    .unwrap()
+                    .and_hms_opt(9, 0, 0)
+                    .unwrap(),
+            )
+            .unwrap();
+        let friday_7pm = Local
+            .from_local_datetime(
+                &NaiveDate::from_ymd_opt(2026, 3, 13)
+                    .unwrap()
+                    .and_hms_opt(19, 0, 0)
+                    .unwrap(),
+            )
+            .unwrap()
+            .with_timezone(&Utc);
+
+        for text in [
+            "Dinner Friday 7pm at Luigi's",
+            "Dinner at Luigi's on Friday at 7pm",
+            "Hey all!\nDinner, Friday 7pm @ Luigi's",
+        ] {
+            let event = parse_event_sentence(text, written_at).unwrap();
+            assert_eq!(event.title, "Dinner", "{text}");
+            assert_eq!(event.location.as_deref(), Some("Luigi's"), "{text}");
+            assert_eq!(
+                event.timing,
+                ParsedTiming::Timed {
+                    start: friday_7pm,
+                    duration_minutes: 60
+                },
+                "{text}"
+            );
+        }
+
+        let event =
+            parse_event_sentence("Dinner #family Friday 7pm at Luigi's #friends", written_at)
+                .unwrap();
+        assert_eq!(event.title, "Dinner #family #friends");
+        assert_eq!(event.location.as_deref(), Some("Luigi's"));
+
+        // Relative dates count from when the message was written
+        let event = parse_event_sentence("Team offsite tomorrow", written_at).unwrap();
+        assert_eq!(event.title, "Team offsite");
+        assert_eq!(event.location, None);
+        assert_eq!(
+            event.timing,
+            ParsedTiming::AllDay {
+                date: NaiveDate::from_ymd_opt(2026, 3, 11).unwrap()
+            }
+        );
+
+        // The multi-line format still applies
+        let event = parse_event_sentence("Lunch\ntomorrow 1pm\n30\nCafe", written_at).unwrap();
+        assert_eq!(event.title, "Lunch");
+        assert_eq!(event.location.as_deref(), Some("Cafe"));
+
+        assert!(matches!(
+            parse_event_sentence("See you there!", written_at),
+            Err(ParseError::NotAnEvent)
+        ));
+        assert!(matches!(
+            parse_event_sentence("Friday 7pm", written_at),
+            Err(ParseError::NotAnEvent)
+        ));
+    }
+
+    #[test]
+    fn test_poster_sentence() {
+        // Tuesday morning
+        let seen_at = Local
+            .from_local_datetime(
+                &NaiveDate::from_ymd_opt(2026, 3, 10)
+                    .unwrap()
+                    .and_hms_opt(9, 0, 0)
+                    .unwrap(),
+            )
+            .unwrap();
+
+        let poster = "JAZZ NIGHT\nwith the Blue Trio\n\nFriday, March 13\n8 PM\nat Blue Note Cafe\nTickets $10";
+        let sentence = poster_sentence(poster, seen_at).unwrap();
+        assert_eq!(sentence, "JAZZ NIGHT 2026-03-13 20:00 at Blue Note Cafe");
+        let event = parse_event_sentence(&sentence, seen_at).unwrap();
+        assert_eq!(event.title, "JAZZ NIGHT");
+        assert_eq!(event.location.as_deref(), Some("Blue Note Cafe"));
+        assert_eq!(
+            event.timing,
+            ParsedTiming::Timed {
+                start: Local
+                    .from_local_datetime(
+                        &NaiveDate::from_ymd_opt(2026, 3, 13)
+                            .unwrap()
+                            .and_hms_opt(20, 0, 0)
+                            .unwrap(),
+                    )
+                    .unwrap()
+                    .with_timezone(&Utc),
+                duration_minutes: 60,
+            }
+        );
+
+        assert_eq!(
+            poster_sentence("Bake Sale\nVenue: Town Hall\nSaturday 10am", seen_at).as_deref(),
+            Some("Bake Sale 2026-03-14 10:00 at Town Hall")
+        );
+        // A day alone makes an all-day event
+        let sentence = poster_sentence("Spring Fair\nSaturday", seen_at).unwrap();
+        assert!(matches!(
+            parse_event_sentence(&sentence, seen_at).unwrap().timing,
+            ParsedTiming::AllDay { .. }
+        ));
+        // Posters are about what's coming
+        assert_eq!(
+            poster_sentence("Winter Gala\nJanuary 5\n7:30 pm", seen_at).as_deref(),
+            Some("Winter Gala 2027-01-05 19:30")
+        );
+        assert_eq!(poster_sentence("SALE\nEverything must go", seen_at), None);
+        assert_eq!(poster_sentence("Friday 8pm", seen_at), None);
+    }
+
+    #[test]
+    fn test_parse_edit() {
+        let date = NaiveDate::from_ymd_opt(2026, 3, 10).unwrap();
+        let start = Local
+            .from_local_datetime(&date.and_hms_opt(9, 0, 0).unwrap())
+            .unwrap()
+            .with_timezone(&Utc);
+        let current = ParsedTiming::Timed {
+            start,
+            duration_minutes: 45,
+        };
+
+        assert_eq!(
+            parse_edit("Rename to Sprint Review", &current).unwrap(),
+            EventEdit::Rename("Sprint Review".to_string())
+        );
+        assert_eq!(
+            parse_edit("location: Room B", &current).unwrap(),
+            EventEdit::Relocate("Room B".to_string())
+        );
+
+        // A bare time keeps the date and the duration
+        let EventEdit::Move(ParsedTiming::Timed {
+            start: moved,
+            duration_minutes,
+        }) = parse_edit("move to 4pm", &current).unwrap()
+        else {
+            panic!("expected a timed move");
+        };
+        let moved = moved.with_timezone(&Local);
+        assert_eq!(moved.date_naive(), date);
+        assert_eq!(moved.time(), NaiveTime::from_hms_opt(16, 0, 0).unwrap());
+        assert_eq!(duration_minutes, 45);
+
+        // A time on an all-day event makes it an hour long
+        let all_day = ParsedTiming::AllDay { date };
+        assert!(matches!(
+            parse_edit("move to 10:00", &all_day).unwrap(),
+            EventEdit::Move(ParsedTiming::Timed {
+                duration_minutes: 60,
+                ..
+            })
+        ));
+        assert!(matches!(
+            parse_edit("move to 2026-03-12", &current).unwrap(),
+            EventEdit::Move(ParsedTiming::AllDay { .. })
+        ));
+
+        assert!(matches!(
+            parse_edit("rename to ", &current),
+            Err(ParseError::UnknownEdit)
+        ));
+        assert!(matches!(
+            parse_edit("thanks!", &current),
+            Err(ParseError::UnknownEdit)
+        ));
+        assert!(matches!(
+            parse_edit("move to someday", &current),
+            Err(ParseError::InvalidDateTime(_))
+        ));
+    }
+
+    #[test]
+    fn test_split_edit_reach() {
+        assert_eq!(
+            split_edit_reach("move to 4pm, this only"),
+            (EditReach::Next, "move to 4pm")
+        );
+        assert_eq!(
+            split_edit_reach("Rename to Retro This And Following"),
+            (EditReach::NextAndFollowing, "Rename to Retro")
+        );
+        assert_eq!(
+            split_edit_reach("location Room B and following"),
+            (EditReach::NextAndFollowing, "location Room B")
+        );
+        assert_eq!(
+            split_edit_reach(" move to 5pm "),
+            (EditReach::Series, "move to 5pm")
+        );
+        // Only whole words count
+        assert_eq!(
+            split_edit_reach("rename to Sandfollowing"),
+            (EditReach::Series, "rename to Sandfollowing")
+        );
+    }
 }
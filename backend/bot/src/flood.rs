@@ -0,0 +1,258 @@
+//! Flood protection for event creation
+//!
+//! Every chat gets a token bucket in memory: a message that would create an
+//! event takes a token, and tokens come back at a steady rate. A chat the
+//! bucket map hasn't seen yet, after a restart or on another replica, starts
+//! from the events its user created over the last refill window, read from
+//! the database once. Throttled messages never reach the database. A group
+//! that becomes a supergroup keeps its bucket under the new chat id.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use teloxide::types::{ChatId, Message};
+
+use crate::db::BotDb;
+
+// Target rate: 10 events/minute = 1 every 6s, also allowed as a burst
+pub const EVENT_BURST_SIZE: u32 = 10;
+pub const EVENT_REFILL_PERIOD: Duration = Duration::from_secs(6);
+
+/// Buckets kept before full ones are dropped
+const MAX_TRACKED_CHATS: usize = 10_000;
+
+/// A group that became a supergroup, announced by a service message in the
+/// old chat and another in the new one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChatMigration {
+    pub from: ChatId,
+    pub to: ChatId,
+}
+
+impl ChatMigration {
+    pub fn of(msg: &Message) -> Option<Self> {
+        if let Some(&to) = msg.migrate_to_chat_id() {
+            return Some(Self {
+                from: msg.chat.id,
+                to,
+            });
+        }
+        msg.migrate_from_chat_id().map(|&from| Self {
+            from,
+            to: msg.chat.id,
+        })
+    }
+}
+
+/// A message refused by the guard
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Throttled {
+    /// Until the next token
+    pub retry_after: Duration,
+    /// First refusal since the chat was last let through; later ones stay
+    /// silent so a flood doesn't get a reply per message
+    pub notify: bool,
+}
+
+#[derive(Debug, Clone)]
+struct Bucket {
+    tokens: u32,
+    refilled_at: Instant,
+    notified: bool,
+}
+
+impl Bucket {
+    fn new(tokens: u32, now: Instant) -> Self {
+        Self {
+            tokens,
+            refilled_at: now,
+            notified: false,
+        }
+    }
+
+    fn refill(&mut self, now: Instant, burst: u32, period: Duration) {
+        let elapsed = now.saturating_duration_since(self.refilled_at);
+        let earned = (elapsed.as_nanos() / period.as_nanos()).min(u128::from(burst)) as u32;
+        if self.tokens + earned >= burst {
+            self.tokens = burst;
+            self.refilled_at = now;
+        } else if earned > 0 {
+            self.tokens += earned;
+            self.refilled_at += period * earned;
+        }
+    }
+
+    fn take(&mut self, now: Instant, burst: u32, period: Duration) -> Result<(), Throttled> {
+        self.refill(now, burst, period);
+        if self.tokens > 0 {
+            self.tokens -= 1;
+            self.notified = false;
+            return Ok(());
+        }
+
+        let notify = !self.notified;
+        self.notified = true;
+        Err(Throttled {
+            retry_after: (self.refilled_at + period).saturating_duration_since(now),
+            notify,
+        })
+    }
+
+    fn is_full(&self, now: Instant, burst: u32, period: Duration) -> bool {
+        let elapsed = now.saturating_duration_since(self.refilled_at);
+        u128::from(self.tokens) + elapsed.as_nanos() / period.as_nanos() >= u128::from(burst)
+    }
+}
+
+/// Per-chat token buckets, shared by every handler through the dispatcher
+#[derive(Clone)]
+pub struct FloodGuard {
+    buckets: Arc<Mutex<HashMap<ChatId, Bucket>>>,
+    burst: u32,
+    period: Duration,
+}
+
+impl Default for FloodGuard {
+    fn default() -> Self {
+        Self::new(EVENT_BURST_SIZE, EVENT_REFILL_PERIOD)
+    }
+}
+
+impl FloodGuard {
+    pub fn new(burst: u32, period: Duration) -> Self {
+        Self {
+            buckets: Arc::default(),
+            burst,
+            period,
+        }
+    }
+
+    /// Take a token for an event-creating message, or say how long to wait
+    pub async fn check(&self, msg: &Message, db: &BotDb) -> Result<(), Throttled> {
+        let chat_id = msg.chat.id;
+        let known = self.lock().contains_key(&chat_id);
+        let seed = if known {
+            self.burst
+        } else {
+            self.seed_tokens(msg, db).await
+        };
+
+        let now = Instant::now();
+        let mut buckets = self.lock();
+        if !buckets.contains_key(&chat_id) && buckets.len() >= MAX_TRACKED_CHATS {
+            buckets.retain(|_, bucket| !bucket.is_full(now, self.burst, self.period));
+        }
+        buckets
+            .entry(chat_id)
+            .or_insert_with(|| Bucket::new(seed, now))
+            .take(now, self.burst, self.period)
+    }
+
+    /// Carry a migrated chat's bucket over to its new id; the second
+    /// announcement of the same migration finds nothing left to move
+    pub fn migrate(&self, migration: ChatMigration) {
+        let mut buckets = self.lock();
+        if let Some(bucket) = buckets.remove(&migration.from) {
+            buckets.entry(migration.to).or_insert(bucket);
+        }
+    }
+
+    /// Tokens left for a chat seen for the first time, going by the events
+    /// its sender created over the last refill window; a failed lookup
+    /// starts full
+    async fn seed_tokens(&self, msg: &Message, db: &BotDb) -> u32 {
+        let Some(user) = msg.from.as_ref() else {
+            return self.burst;
+        };
+        let window = self.period * self.burst;
+        let since = chrono::Utc::now()
+            - chrono::Duration::from_std(window).unwrap_or(chrono::Duration::zero());
+        match db.count_events_created_since(user.id.0 as i64, since).await {
+            Ok(created) => self
+                .burst
+                .saturating_sub(u32::try_from(created).unwrap_or(u32::MAX)),
+            Err(e) => {
+                tracing::warn!("Failed to read recent events for the flood guard: {e}");
+                self.burst
+            }
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<ChatId, Bucket>> {
+        self.buckets
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Whether the message is one the text handler turns into an event
+pub fn creates_event(msg: &Message) -> bool {
+    msg.text()
+        .is_some_and(|text| !text.starts_with('/') && text.lines().count() >= 2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PERIOD: Duration = Duration::from_secs(6);
+
+    #[test]
+    fn test_bucket_burst_then_refill() {
+        let start = Instant::now();
+        let mut bucket = Bucket::new(3, start);
+
+        for _ in 0..3 {
+            assert!(bucket.take(start, 3, PERIOD).is_ok());
+        }
+        let throttled = bucket.take(start, 3, PERIOD).unwrap_err();
+        assert!(throttled.notify);
+        assert_eq!(throttled.retry_after, PERIOD);
+        // Only the first refusal gets a reply
+        let later = start + Duration::from_secs(2);
+        let throttled = bucket.take(later, 3, PERIOD).unwrap_err();
+        assert!(!throttled.notify);
+        assert_eq!(throttled.retry_after, Duration::from_secs(4));
+
+        // One token per period, and the next refusal is announced again
+        let refilled = start + PERIOD;
+        assert!(bucket.take(refilled, 3, PERIOD).is_ok());
+        assert!(bucket.take(refilled, 3, PERIOD).unwrap_err().notify);
+
+        // Never more than the burst
+        let idle = start + PERIOD * 100;
+        assert!(bucket.is_full(idle, 3, PERIOD));
+        for _ in 0..3 {
+            assert!(bucket.take(idle, 3, PERIOD).is_ok());
+        }
+        assert!(bucket.take(idle, 3, PERIOD).is_err());
+    }
+
+    #[test]
+    fn test_migrated_chat_keeps_its_bucket() {
+        let guard = FloodGuard::new(3, PERIOD);
+        let (group, supergroup) = (ChatId(-42), ChatId(-1_000_000_042));
+        guard.lock().insert(group, Bucket::new(0, Instant::now()));
+
+        let migration = ChatMigration {
+            from: group,
+            to: supergroup,
+        };
+        guard.migrate(migration);
+        guard.migrate(migration);
+
+        let buckets = guard.lock();
+        assert!(!buckets.contains_key(&group));
+        assert_eq!(buckets[&supergroup].tokens, 0);
+    }
+
+    #[test]
+    fn test_seeded_bucket_starts_short() {
+        let start = Instant::now();
+        // Two events already created this window leave one of three tokens
+        let mut bucket = Bucket::new(1, start);
+        assert!(!bucket.is_full(start, 3, PERIOD));
+        assert!(bucket.take(start, 3, PERIOD).is_ok());
+        assert!(bucket.take(start, 3, PERIOD).is_err());
+    }
+}
@@ -2,14 +2,78 @@
 //!
 //! Implementation of all bot command handlers
 
-use crate::db::BotDb;
-use crate::event_parser::{format_example, parse_event_message};
+use crate::args::{self, Args, Spec};
+use crate::commands;
+use crate::db::{AttendeeInfo, BotDb, BotEvent, DevicePasswordInfo, SuggestedAttendee};
+use crate::event_parser::{
+    EditReach, ParsedEvent, ParsedTiming, format_example, parse_edit, parse_event_message,
+    parse_event_sentence, poster_sentence, split_edit_reach,
+};
+use crate::ocr::Posters;
+use crate::quick_actions::{self, QuickAction};
+use crate::voice::{MAX_TRANSCRIPT_CHARS, MAX_VOICE_SECONDS, VoiceNotes};
 use anyhow::Result;
-use chrono::{Duration, Utc};
-use televent_domain::internal_email_for_telegram_id;
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use sha2::{Digest, Sha256};
+use televent_application::{
+    ApplicationError, CalendarStats, DEFAULT_SLOT_COUNT, DEFAULT_SLOT_SEARCH_DAYS,
+    DEFAULT_STATS_DAYS, DelegateView, DeviceId, EXTERNAL_INVITES_FLAG, EditScope, EventFunnelStep,
+    FocusRuleCommand, FocusRuleView, FreeSlot, MAX_CONTACT_SEARCH_LIMIT, MAX_INVITEES_PER_REQUEST,
+    Metric, SlotSearch, SnoozeDelay, TaskPlan, TaskView, TimezoneFixCommand, UserId, WorkingHours,
+    holiday_countries, parse_duration_spec, parse_focus_pattern, weekday_name,
+};
+use televent_domain::tags::{event_tags, normalize_tag};
+use televent_domain::telegram_html::{escape, inline};
+use televent_domain::{
+    EventStatus, MAX_DESCRIPTION_LENGTH, Timezone, internal_email_for_telegram_id,
+    occurrence_after, timezone_near,
+};
 use teloxide::prelude::*;
-use teloxide::types::ParseMode;
-use teloxide::utils::html::escape;
+use teloxide::types::{
+    ButtonRequest, CallbackQueryId, InlineKeyboardButton, InlineKeyboardButtonKind,
+    InlineKeyboardMarkup, KeyboardButton, KeyboardMarkup, KeyboardRemove, MaybeInaccessibleMessage,
+    MessageEntityKind, MessageId, MessageOrigin, ParseMode, ReplyParameters,
+};
+use uuid::Uuid;
+
+/// Reply to mutations while the deployment is in maintenance mode
+pub const READ_ONLY_NOTICE: &str = "🛠 Televent is under maintenance right now, so changes are paused. \
+     Your calendar is still readable; please try again in a few minutes.";
+
+/// Whether mutations should be refused; a failed lookup lets them through
+/// like the API does
+pub async fn is_read_only(db: &BotDb) -> bool {
+    db.is_read_only().await.unwrap_or_else(|e| {
+        tracing::warn!("Failed to read maintenance mode: {e}");
+        false
+    })
+}
+
+/// Arguments of a command read against `spec`, or `None` once the user has
+/// been told what doesn't fit and how the command is used
+async fn command_args(
+    bot: &Bot,
+    chat_id: ChatId,
+    spec: &Spec,
+    input: &str,
+) -> Result<Option<Args>> {
+    match spec.parse(input) {
+        Ok(args) => Ok(Some(args)),
+        Err(err) => {
+            bot.send_message(
+                chat_id,
+                format!(
+                    "❌ {}\n\nUsage: <code>{}</code>",
+                    escape(&err.to_string()),
+                    escape(&spec.usage())
+                ),
+            )
+            .parse_mode(ParseMode::Html)
+            .await?;
+            Ok(None)
+        }
+    }
+}
 
 /// Handle the /start command
 pub async fn handle_start(bot: Bot, msg: Message, db: BotDb) -> Result<()> {
@@ -52,7 +116,7 @@ pub async fn handle_start(bot: Bot, msg: Message, db: BotDb) -> Result<()> {
          30\n\
          Starbucks\n\n\
          Commands:\n\
-         /list - View upcoming events\n\
+         /week - View upcoming events\n\
          /device - Manage CalDAV device passwords\n\
          /help - Show all commands";
 
@@ -60,6 +124,13 @@ pub async fn handle_start(bot: Bot, msg: Message, db: BotDb) -> Result<()> {
         .parse_mode(ParseMode::Html)
         .await?;
 
+    // Ask for a timezone once; everyone starts on UTC
+    match db.begin_onboarding(telegram_id).await {
+        Ok(true) => send_timezone_prompt(&bot, msg.chat.id).await?,
+        Ok(false) => {}
+        Err(e) => tracing::warn!("Failed to check onboarding for {}: {}", telegram_id, e),
+    }
+
     tracing::info!("User {} started the bot", telegram_id);
 
     Ok(())
@@ -69,12 +140,27 @@ pub async fn handle_start(bot: Bot, msg: Message, db: BotDb) -> Result<()> {
 pub async fn handle_help(bot: Bot, msg: Message) -> Result<()> {
     let help_text = "<b>Televent Commands</b>\n\n\
          <b>Event Management:</b>\n\
-         /list - List upcoming events\n\
+         /today - Show today's agenda\n\
+         /tomorrow - Show tomorrow's agenda\n\
+         /week - Show the next 7 days (also /list, /agenda)\n\
+         /list #work - Only events with #work in their title\n\
+         Tap 📝 ❌ 👥 🔔 under an agenda to edit, cancel, see guests or remind them\n\
+         /slot 30m - Find the next free slots\n\
+         /task 45m Write report - Add a task; /plan fits tasks into today\n\
+         /focus add 2x2h - Keep two 2-hour focus blocks free every week\n\
+         /stats - Show your meeting load\n\
+         /note - Add a note to an event's reminders, e.g. \"Bring laptop!\"\n\
          /cancel - Cancel an event\n\n\
          <b>CalDAV Sync:</b>\n\
          /device - Manage device passwords for CalDAV clients\n\
+         /subscribe - Subscribe to external calendars and holidays\n\
          /export - Export calendar as .ics file\n\n\
          <b>Account:</b>\n\
+         /timezone - Set your timezone\n\
+         /fixtz - Move events saved in the wrong timezone\n\
+         /email - Set and confirm your email for invites and notices\n\
+         /hours - Set your working hours, e.g. /hours Mon-Fri 09:00-17:00\n\
+         /delegate - Let someone else edit your calendar\n\
          /deleteaccount - Delete your account and all data\n\n\
          For detailed help, visit: https://github.com/kirilledition/televent";
 
@@ -85,221 +171,500 @@ pub async fn handle_help(bot: Bot, msg: Message) -> Result<()> {
     Ok(())
 }
 
-/// Handle the /device command
-pub async fn handle_device(bot: Bot, msg: Message, db: BotDb) -> Result<()> {
+/// Zones offered as buttons when asking for a timezone
+const TIMEZONE_CHOICES: &[&str] = &[
+    "Europe/London",
+    "Europe/Berlin",
+    "Europe/Moscow",
+    "Asia/Dubai",
+    "Asia/Kolkata",
+    "Asia/Singapore",
+    "Asia/Tokyo",
+    "Australia/Sydney",
+    "America/Sao_Paulo",
+    "America/New_York",
+    "America/Chicago",
+    "America/Los_Angeles",
+];
+
+/// Ask for a location, with common zones as a fallback
+async fn send_timezone_prompt(bot: &Bot, chat_id: ChatId) -> Result<()> {
+    let location_keyboard = KeyboardMarkup::new(vec![vec![
+        KeyboardButton::new("📍 Share my location").request(ButtonRequest::Location),
+    ]])
+    .resize_keyboard()
+    .one_time_keyboard();
+    bot.send_message(
+        chat_id,
+        "🌍 Which timezone are you in?\n\n\
+         Share your location and I'll work it out; only the timezone is kept.",
+    )
+    .reply_markup(location_keyboard)
+    .await?;
+
+    let mut rows: Vec<Vec<InlineKeyboardButton>> = TIMEZONE_CHOICES
+        .chunks(2)
+        .map(|pair| {
+            pair.iter()
+                .map(|zone| {
+                    InlineKeyboardButton::callback(zone.replace('_', " "), format!("tz:{zone}"))
+                })
+                .collect()
+        })
+        .collect();
+    rows.push(vec![InlineKeyboardButton::callback("Keep UTC", "tz:UTC")]);
+    bot.send_message(
+        chat_id,
+        "Or pick one below. For any other zone, send e.g. /timezone Asia/Yerevan",
+    )
+    .reply_markup(InlineKeyboardMarkup::new(rows))
+    .await?;
+
+    Ok(())
+}
+
+/// Store the timezone and confirm it, offering the optional email step to
+/// users who haven't given one
+async fn apply_timezone(
+    bot: &Bot,
+    chat_id: ChatId,
+    telegram_id: i64,
+    timezone: &Timezone,
+    db: &BotDb,
+) -> Result<()> {
+    db.set_timezone(telegram_id, timezone).await?;
+
+    let now = Utc::now().with_timezone(&timezone.tz());
+    let mut response = format!(
+        "✅ Timezone set to <b>{}</b> (it's {} there now).\nChange it any time with /timezone.",
+        escape(timezone.as_str()),
+        now.format("%H:%M")
+    );
+    if db.get_email(telegram_id).await?.is_none() {
+        response.push_str(
+            "\n\n✉️ Optional: send /email you@example.com so invites \
+             to that address reach you here instead.",
+        );
+    }
+    bot.send_message(chat_id, response)
+        .parse_mode(ParseMode::Html)
+        .reply_markup(KeyboardRemove::new())
+        .await?;
+
+    tracing::info!("User {} set timezone {}", telegram_id, timezone.as_str());
+
+    Ok(())
+}
+
+/// Handle the /timezone command
+pub async fn handle_timezone(bot: Bot, msg: Message, db: BotDb) -> Result<()> {
     let user = msg
         .from
-        .clone()
+        .as_ref()
         .ok_or_else(|| anyhow::anyhow!("No user in message"))?;
     let telegram_id = user.id.0 as i64;
 
-    // Get text after command (if any)
-    let text = msg.text().unwrap_or("");
-    let parts: Vec<&str> = text.split_whitespace().collect();
+    // Parse command arguments: /timezone [<zone>]
+    let input = args::after_command(msg.text().unwrap_or(""));
+    let Some(args) = command_args(&bot, msg.chat.id, &commands::TIMEZONE, input).await? else {
+        return Ok(());
+    };
+    let Some(zone) = args.get("zone") else {
+        return send_timezone_prompt(&bot, msg.chat.id).await;
+    };
+    match Timezone::parse(zone) {
+        Ok(timezone) => apply_timezone(&bot, msg.chat.id, telegram_id, &timezone, &db).await,
+        Err(_) => {
+            bot.send_message(
+                msg.chat.id,
+                format!(
+                    "❌ Unknown timezone: {}\n\nUse an IANA name such as Europe/Berlin \
+                     or America/New_York, or send /timezone to pick one.",
+                    inline(zone)
+                ),
+            )
+            .parse_mode(ParseMode::Html)
+            .await?;
+            Ok(())
+        }
+    }
+}
 
-    match parts.get(1).copied() {
-        Some("add") => {
-            let device_name = parts
-                .get(2..)
-                .map(|s| s.join(" "))
-                .unwrap_or_else(|| "My Device".to_string());
-
-            match db.generate_device_password(telegram_id, &device_name).await {
-                Ok(password) => {
-                    let base_url = std::env::var("PUBLIC_BASE_URL")
-                        .unwrap_or_else(|_| "http://localhost:3000".to_string());
-                    let caldav_url = format!("{}/caldav", base_url.trim_end_matches('/'));
-                    let response = format!(
-                        "✅ <b>Device Password Created!</b>\n\n\
-                         🏷️ Device: {}\n\
-                         🔑 Password: <code>{}</code>\n\n\
-                         <b>CalDAV Setup:</b>\n\
-                         Server: <code>{}</code>\n\
-                         Username: <code>{}</code>\n\
-                         Password: Use the password above\n\n\
-                         ⚠️ <b>Important:</b> Save this password securely! \
-                         You won't be able to see it again.",
-                        escape(&device_name),
-                        password,
-                        escape(&caldav_url),
-                        telegram_id
-                    );
+/// Handle a shared location: detect the timezone from it
+pub async fn handle_location(bot: Bot, msg: Message, db: BotDb) -> Result<()> {
+    let user = msg
+        .from
+        .as_ref()
+        .ok_or_else(|| anyhow::anyhow!("No user in message"))?;
+    let telegram_id = user.id.0 as i64;
+    let Some(location) = msg.location() else {
+        return Ok(());
+    };
 
-                    bot.send_message(msg.chat.id, response)
-                        .parse_mode(ParseMode::Html)
-                        .await?;
+    if is_read_only(&db).await {
+        bot.send_message(msg.chat.id, READ_ONLY_NOTICE)
+            .reply_markup(KeyboardRemove::new())
+            .await?;
+        return Ok(());
+    }
 
-                    tracing::info!(
-                        "Device password created for user {}: {}",
-                        telegram_id,
-                        device_name
-                    );
-                }
-                Err(e) => {
-                    bot.send_message(
-                        msg.chat.id,
-                        format!("❌ Failed to create device password: {}", e),
-                    )
-                    .await?;
-                }
-            }
+    match timezone_near(location.latitude, location.longitude) {
+        Some(timezone) => apply_timezone(&bot, msg.chat.id, telegram_id, &timezone, &db).await,
+        None => {
+            bot.send_message(
+                msg.chat.id,
+                "❌ Couldn't tell the timezone from that location. Send /timezone to pick one.",
+            )
+            .reply_markup(KeyboardRemove::new())
+            .await?;
+            Ok(())
         }
-        Some("list") => match db.list_device_passwords(telegram_id).await {
-            Ok(devices) if devices.is_empty() => {
-                bot.send_message(
-                    msg.chat.id,
-                    "📱 You don't have any device passwords yet.\n\n\
-                         Create one with: <code>/device add Device Name</code>",
-                )
+    }
+}
+
+/// Handle the /fixtz command: show which events a timezone fix would move,
+/// with buttons to go ahead or leave them
+pub async fn handle_fixtz(bot: Bot, msg: Message, db: BotDb) -> Result<()> {
+    let user = msg
+        .from
+        .as_ref()
+        .ok_or_else(|| anyhow::anyhow!("No user in message"))?;
+    let telegram_id = user.id.0 as i64;
+
+    // Parse command arguments: /fixtz <old_tz> <new_tz> [<range>]
+    let input = args::after_command(msg.text().unwrap_or(""));
+    let Some(args) = command_args(&bot, msg.chat.id, &commands::FIXTZ, input).await? else {
+        return Ok(());
+    };
+    let command = match timezone_fix_command(telegram_id, &args) {
+        Ok(command) => command,
+        Err(message) => {
+            bot.send_message(
+                msg.chat.id,
+                format!(
+                    "❌ {}\n\nUsage: <code>{}</code>, e.g. \
+                     /fixtz Europe/London Europe/Berlin 2026-01-01..2026-06-30",
+                    escape(&message),
+                    escape(&commands::FIXTZ.usage())
+                ),
+            )
+            .parse_mode(ParseMode::Html)
+            .await?;
+            return Ok(());
+        }
+    };
+
+    let summary = match db.preview_timezone_fix(&command).await {
+        Ok(summary) => summary,
+        Err(ApplicationError::BadRequest(message)) => {
+            bot.send_message(msg.chat.id, format!("❌ {}", escape(&message)))
                 .parse_mode(ParseMode::Html)
                 .await?;
-            }
-            Ok(devices) => {
-                let mut response = format!("📱 <b>Your Devices</b> ({})\n\n", devices.len());
+            return Ok(());
+        }
+        Err(err) => return Err(err.into()),
+    };
+    let (Some(first), Some(last)) = (summary.first_start, summary.last_start) else {
+        bot.send_message(
+            msg.chat.id,
+            format!(
+                "No timed events in that range are saved in <b>{}</b>; nothing to fix.",
+                escape(command.from.as_str())
+            ),
+        )
+        .parse_mode(ParseMode::Html)
+        .await?;
+        return Ok(());
+    };
 
-                for (idx, device) in devices.iter().enumerate() {
-                    response.push_str(&format!(
-                        "{}. <b>{}</b>\n   🆔 <code>{}</code>\n   📅 Created: {}\n",
-                        idx + 1,
-                        escape(&device.name),
-                        device.id,
-                        device.created_at.format("%Y-%m-%d %H:%M")
-                    ));
+    let day = |at: DateTime<Utc>| at.with_timezone(&command.from.tz()).format("%b %d, %Y");
+    // The prompt replies to the command, so the button can read it again
+    let keyboard = InlineKeyboardMarkup::new(vec![vec![
+        InlineKeyboardButton::callback("✅ Move them", "fixtz:apply"),
+        InlineKeyboardButton::callback("✖️ Leave them", "fixtz:cancel"),
+    ]]);
+    bot.send_message(
+        msg.chat.id,
+        format!(
+            "🌍 {} {} from {} to {} {} saved in <b>{}</b>.\n\n\
+             Each keeps its clock time in <b>{}</b>, so 10:00 stays 10:00, and \
+             guests of upcoming ones are told the new time. Move them?",
+            summary.events,
+            if summary.events == 1 {
+                "event"
+            } else {
+                "events"
+            },
+            day(first),
+            day(last),
+            if summary.events == 1 { "is" } else { "are" },
+            escape(command.from.as_str()),
+            escape(command.to.as_str()),
+        ),
+    )
+    .parse_mode(ParseMode::Html)
+    .reply_parameters(ReplyParameters::new(msg.id))
+    .reply_markup(keyboard)
+    .await?;
 
-                    if let Some(last_used) = device.last_used_at {
-                        response.push_str(&format!(
-                            "   🕐 Last used: {}\n",
-                            last_used.format("%Y-%m-%d %H:%M")
-                        ));
-                    }
+    Ok(())
+}
 
-                    response.push('\n');
-                }
+/// A timezone fix from /fixtz arguments; dates in the range are days in the
+/// old zone, both included
+fn timezone_fix_command(
+    telegram_id: i64,
+    args: &Args,
+) -> std::result::Result<TimezoneFixCommand, String> {
+    let zone = |name: &str| {
+        Timezone::parse(name).map_err(|_| {
+            format!("Unknown timezone: {name}. Use an IANA name such as Europe/Berlin")
+        })
+    };
+    let from = zone(args.get("old_tz").unwrap_or_default())?;
+    let to = zone(args.get("new_tz").unwrap_or_default())?;
+
+    let (first_day, last_day) = match args.get("range") {
+        None => (None, None),
+        Some(range) => {
+            let invalid = || format!("{range} is not a range like 2026-01-01..2026-06-30");
+            let (first, last) = range.split_once("..").ok_or_else(invalid)?;
+            let day = |value: &str| {
+                (!value.is_empty())
+                    .then(|| NaiveDate::parse_from_str(value, "%Y-%m-%d").map_err(|_| invalid()))
+                    .transpose()
+            };
+            (day(first)?, day(last)?)
+        }
+    };
+    let tz = from.tz();
+    let local_midnight = |day: NaiveDate| {
+        tz.from_local_datetime(&day.and_time(NaiveTime::MIN))
+            .earliest()
+            .map_or_else(
+                || day.and_time(NaiveTime::MIN).and_utc(),
+                |local| local.with_timezone(&Utc),
+            )
+    };
 
-                response.push_str("To revoke a device: <code>/device revoke &lt;ID&gt;</code>");
+    Ok(TimezoneFixCommand {
+        user_id: UserId::new(telegram_id),
+        start: first_day.map(local_midnight),
+        end: last_day.map(|day| local_midnight(day + Duration::days(1))),
+        from,
+        to,
+    })
+}
 
-                bot.send_message(msg.chat.id, response)
-                    .parse_mode(ParseMode::Html)
-                    .await?;
-            }
-            Err(e) => {
-                bot.send_message(msg.chat.id, format!("❌ Failed to list devices: {}", e))
-                    .await?;
-            }
-        },
-        Some("revoke") => {
-            if let Some(device_id_str) = parts.get(2) {
-                match device_id_str.parse::<uuid::Uuid>() {
-                    Ok(device_id) => {
-                        match db.revoke_device_password(telegram_id, device_id).await {
-                            Ok(true) => {
-                                bot.send_message(
-                                    msg.chat.id,
-                                    "✅ Device password revoked successfully!",
-                                )
-                                .await?;
-
-                                tracing::info!(
-                                    "Device password revoked for user {}: {}",
-                                    telegram_id,
-                                    device_id
-                                );
-                            }
-                            Ok(false) => {
-                                bot.send_message(
-                                    msg.chat.id,
-                                    "❌ Device not found or already revoked.",
-                                )
-                                .await?;
-                            }
-                            Err(e) => {
-                                bot.send_message(
-                                    msg.chat.id,
-                                    format!("❌ Failed to revoke device: {}", e),
-                                )
-                                .await?;
-                            }
-                        }
-                    }
-                    Err(_) => {
-                        bot.send_message(msg.chat.id, "❌ Invalid device ID format.")
-                            .await?;
+/// Answer to the /fixtz prompt: `fixtz:apply` or `fixtz:cancel`
+async fn handle_timezone_fix_callback(
+    bot: Bot,
+    callback_id: CallbackQueryId,
+    user_id: i64,
+    message: Option<MaybeInaccessibleMessage>,
+    data: &str,
+    db: BotDb,
+) -> Result<()> {
+    let Some(MaybeInaccessibleMessage::Regular(prompt)) = message else {
+        bot.answer_callback_query(callback_id)
+            .text("❌ This prompt has expired")
+            .await?;
+        return Ok(());
+    };
+    let command = prompt
+        .reply_to_message()
+        .filter(|original| original.from.as_ref().map(|user| user.id.0 as i64) == Some(user_id))
+        .and_then(|original| original.text())
+        .and_then(|text| commands::FIXTZ.parse(args::after_command(text)).ok())
+        .and_then(|args| timezone_fix_command(user_id, &args).ok());
+    let Some(command) = command else {
+        bot.answer_callback_query(callback_id)
+            .text("❌ The /fixtz message is gone; please send it again")
+            .show_alert(true)
+            .await?;
+        return Ok(());
+    };
+
+    let outcome = if data == "fixtz:apply" {
+        match db.fix_timezone(command).await {
+            Ok(summary) => {
+                tracing::info!(
+                    "User {} moved {} events to another timezone",
+                    user_id,
+                    summary.events
+                );
+                format!(
+                    "✅ Moved {} {}.",
+                    summary.events,
+                    if summary.events == 1 {
+                        "event"
+                    } else {
+                        "events"
                     }
-                }
-            } else {
-                bot.send_message(
-                    msg.chat.id,
-                    "❌ Please provide a device ID: <code>/device revoke &lt;ID&gt;</code>",
                 )
-                .parse_mode(ParseMode::Html)
-                .await?;
             }
+            Err(ApplicationError::BadRequest(message)) => format!("❌ {message}"),
+            Err(err) => return Err(err.into()),
         }
-        _ => {
-            let response = "🔐 <b>CalDAV Device Management</b>\n\n\
-                            Device passwords allow you to sync your calendar with CalDAV clients.\n\n\
-                            <b>Commands:</b>\n\
-                            <code>/device add [name]</code> - Create a new device password\n\
-                            <code>/device list</code> - List all your devices\n\
-                            <code>/device revoke &lt;id&gt;</code> - Revoke a device password\n\n\
-                            <b>Supported Clients:</b>\n\
-                            • Apple Calendar (iOS, macOS)\n\
-                            • Thunderbird\n\
-                            • DAVx⁵ (Android)\n\
-                            • Any CalDAV-compatible client";
-
-            bot.send_message(msg.chat.id, response)
-                .parse_mode(ParseMode::Html)
-                .await?;
-        }
-    }
+    } else {
+        "Left as they were; nothing was moved.".to_string()
+    };
+    bot.answer_callback_query(callback_id).await?;
+    bot.edit_message_text(
+        prompt.chat.id,
+        prompt.id,
+        format!("{}\n\n{}", prompt.text().unwrap_or_default(), outcome),
+    )
+    .await?;
 
     Ok(())
 }
 
-/// Handle the /export command
-pub async fn handle_export(bot: Bot, msg: Message, db: BotDb) -> Result<()> {
+/// Handle the /email command
+pub async fn handle_email(bot: Bot, msg: Message, db: BotDb) -> Result<()> {
     let user = msg
         .from
+        .as_ref()
         .ok_or_else(|| anyhow::anyhow!("No user in message"))?;
     let telegram_id = user.id.0 as i64;
 
-    let export = db.export_calendar_ics(telegram_id).await?;
-
-    if export.event_count == 0 {
-        bot.send_message(msg.chat.id, "📅 You don't have any events to export yet.")
+    // Parse command arguments: /email [<address> | clear | verify <code>]
+    let input = args::after_command(msg.text().unwrap_or(""));
+    let (subcommand, rest) = args::subcommand(input);
+    if subcommand.is_some_and(|word| word.eq_ignore_ascii_case("verify")) {
+        let Some(args) = command_args(&bot, msg.chat.id, &commands::EMAIL_VERIFY, rest).await?
+        else {
+            return Ok(());
+        };
+        let response = match db
+            .verify_email(telegram_id, args.get("code").unwrap_or_default())
+            .await
+        {
+            Ok(email) => {
+                tracing::info!("User {} confirmed their email", telegram_id);
+                format!(
+                    "✅ {} is confirmed. Turn email on for a kind of notice in the \
+                     notification settings to get copies there.",
+                    inline(&email.email)
+                )
+            }
+            Err(
+                ApplicationError::BadRequest(message)
+                | ApplicationError::NotFound(message)
+                | ApplicationError::Conflict(message),
+            ) => format!("❌ {}", escape(&message)),
+            Err(err) => return Err(err.into()),
+        };
+        bot.send_message(msg.chat.id, response)
+            .parse_mode(ParseMode::Html)
             .await?;
         return Ok(());
     }
 
-    // Send as file
-    let file =
-        teloxide::types::InputFile::memory(export.body.into_bytes()).file_name("calendar.ics");
-
-    bot.send_document(msg.chat.id, file)
-        .caption("📤 Here is your calendar export.")
+    let Some(args) = command_args(&bot, msg.chat.id, &commands::EMAIL, input).await? else {
+        return Ok(());
+    };
+    let response = match args.get("address|clear") {
+        None => match db.get_email_status(telegram_id).await? {
+            Some(email) => {
+                let status = if email.verified_at.is_some() {
+                    "It's confirmed, so notices you turn on for email go there too."
+                } else {
+                    "It's not confirmed yet: send /email verify followed by the emailed code."
+                };
+                format!(
+                    "✉️ Invites to {} reach you here. {status}\n\n\
+                     Send /email new@example.com to change it or /email clear to remove it.",
+                    inline(&email.email)
+                )
+            }
+            None => "✉️ No email set.\n\n\
+                     Send /email you@example.com so invites to that address reach you here."
+                .to_string(),
+        },
+        Some(arg) => {
+            let email = (!arg.eq_ignore_ascii_case("clear")).then_some(arg);
+            match db.set_email(telegram_id, email).await {
+                Ok(Some(email)) => {
+                    tracing::info!("User {} set their email", telegram_id);
+                    let mut response =
+                        format!("✅ Invites to {} will reach you here.", inline(&email));
+                    match db.request_email_verification(telegram_id).await {
+                        Ok(Some(view)) if view.verified_at.is_none() => response.push_str(
+                            "\n\n📨 We emailed it a code; send /email verify followed by \
+                             the code, or open the link in the email, to get notices there too.",
+                        ),
+                        Ok(_) => {}
+                        Err(ApplicationError::BadRequest(message)) => {
+                            response.push_str(&format!("\n\n⏳ {}", escape(&message)));
+                        }
+                        Err(err) => return Err(err.into()),
+                    }
+                    response
+                }
+                Ok(None) => "✅ Email removed.".to_string(),
+                Err(
+                    ApplicationError::BadRequest(message) | ApplicationError::Conflict(message),
+                ) => {
+                    format!("❌ {}", escape(&message))
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+    };
+    bot.send_message(msg.chat.id, response)
+        .parse_mode(ParseMode::Html)
         .await?;
 
-    tracing::info!(
-        "User {} exported {} events",
-        telegram_id,
-        export.event_count
-    );
-
     Ok(())
 }
 
-/// Handle the /deleteaccount command
-pub async fn handle_delete_account(bot: Bot, msg: Message) -> Result<()> {
-    let response = "⚠️ <b>Delete Account</b>\n\n\
-                    This will permanently delete:\n\
-                    • All your events\n\
-                    • Your calendar\n\
-                    • All device passwords\n\
-                    • All associated data\n\n\
-                    This action CANNOT be undone.\n\n\
-                    To confirm deletion, use the web UI and follow the GDPR deletion process.";
+/// Handle the /hours command
+pub async fn handle_hours(bot: Bot, msg: Message, db: BotDb) -> Result<()> {
+    let user = msg
+        .from
+        .as_ref()
+        .ok_or_else(|| anyhow::anyhow!("No user in message"))?;
+    let telegram_id = user.id.0 as i64;
 
+    // Parse command arguments: /hours [<week> | off]
+    let input = args::after_command(msg.text().unwrap_or(""));
+    let Some(args) = command_args(&bot, msg.chat.id, &commands::HOURS, input).await? else {
+        return Ok(());
+    };
+    let response = match args.get("week|off") {
+        None => match db.get_working_hours(telegram_id).await? {
+            Some(hours) => format!(
+                "🕘 You work {} ({}).\n\n\
+                 Send /hours Mon-Fri 09:00-17:00 to change them or /hours off to clear them.",
+                inline(&hours.to_string()),
+                hours.timezone().as_str()
+            ),
+            None => "🕘 No working hours set.\n\n\
+                     Send /hours Mon-Fri 09:00-17:00 so /slot, /plan and focus time stay \
+                     inside them and I warn about events outside them."
+                .to_string(),
+        },
+        Some(arg) => {
+            let week = (!arg.eq_ignore_ascii_case("off")).then_some(arg);
+            match db.set_working_hours(telegram_id, week).await {
+                Ok(Some(hours)) => {
+                    tracing::info!("User {} set their working hours", telegram_id);
+                    format!(
+                        "✅ Working hours set: {} ({}).",
+                        inline(&hours.to_string()),
+                        hours.timezone().as_str()
+                    )
+                }
+                Ok(None) => "✅ Working hours cleared.".to_string(),
+                Err(ApplicationError::BadRequest(message)) => format!(
+                    "❌ {}\n\nExample: <code>/hours Mon-Thu 09:00-17:00, Fri 09:00-13:00</code>",
+                    escape(&message)
+                ),
+                Err(err) => return Err(err.into()),
+            }
+        }
+    };
     bot.send_message(msg.chat.id, response)
         .parse_mode(ParseMode::Html)
         .await?;
@@ -307,81 +672,93 @@ pub async fn handle_delete_account(bot: Bot, msg: Message) -> Result<()> {
     Ok(())
 }
 
-/// Handle the /list command
-pub async fn handle_list(bot: Bot, msg: Message, db: BotDb) -> Result<()> {
+/// How a delegate or calendar owner is named in messages
+fn delegate_name(view: &DelegateView) -> String {
+    match &view.username {
+        Some(username) => escape(&format!("@{username}")),
+        None => format!("<code>{}</code>", view.user_id),
+    }
+}
+
+/// Handle the /delegate command
+pub async fn handle_delegate(bot: Bot, msg: Message, db: BotDb) -> Result<()> {
     let user = msg
         .from
+        .as_ref()
         .ok_or_else(|| anyhow::anyhow!("No user in message"))?;
     let telegram_id = user.id.0 as i64;
 
-    // Get upcoming events (next 7 days)
-    let now = Utc::now();
-    let start_range = now
-        .date_naive()
-        .and_hms_opt(0, 0, 0)
-        .ok_or_else(|| anyhow::anyhow!("Invalid time"))?
-        .and_utc();
-    let end_range = start_range + Duration::days(7);
-
-    // Query events from database
-    let events = db
-        .get_events_for_user(telegram_id, start_range, end_range)
-        .await?;
-
-    if events.is_empty() {
-        bot.send_message(msg.chat.id, "📅 No upcoming events in the next 7 days.")
-            .await?;
-    } else {
-        let mut response = format!(
-            "📅 <b>Upcoming Events (Next 7 Days)</b> ({})\n\n",
-            events.len()
-        );
-
-        for (idx, event) in events.iter().enumerate() {
-            let start = event.display_start();
-            let time_str = if event.is_all_day {
-                "All Day".to_string()
+    // /delegate [list] | /delegate @username | /delegate revoke @username
+    let input = args::after_command(msg.text().unwrap_or(""));
+    let (subcommand, rest) = args::subcommand(input);
+    let response = match subcommand {
+        None | Some("list") => {
+            let delegates = db.list_delegates(telegram_id).await?;
+            let calendars = db.list_delegated_calendars(telegram_id).await?;
+            let mut response = "👥 <b>Delegates</b>\n\n".to_string();
+            if delegates.is_empty() {
+                response.push_str("Nobody else can edit your calendar.\n");
+            }
+            for delegate in &delegates {
+                response.push_str(&format!(
+                    "• {} since {}\n",
+                    delegate_name(delegate),
+                    delegate.granted_at.format("%Y-%m-%d")
+                ));
+            }
+            if !calendars.is_empty() {
+                response.push_str("\n<b>Calendars you can edit:</b>\n");
+                for owner in &calendars {
+                    response.push_str(&format!("• {}\n", delegate_name(owner)));
+                }
+            }
+            response.push_str(
+                "\n<code>/delegate @username</code> - Let someone create, edit and delete your events\n\
+                 <code>/delegate revoke @username</code> - Take that back\n\n\
+                 Every change shows in the event's history under the name of whoever made it.",
+            );
+            response
+        }
+        Some("revoke") => {
+            let Some(args) =
+                command_args(&bot, msg.chat.id, &commands::DELEGATE_REVOKE, rest).await?
+            else {
+                return Ok(());
+            };
+            let username = args.get("username").unwrap_or_default();
+            if db.revoke_delegate(telegram_id, username).await? {
+                tracing::info!("User {} revoked a calendar delegate", telegram_id);
+                format!("✅ {} can no longer edit your calendar.", escape(username))
             } else {
-                start.format("%H:%M").to_string()
+                format!("❌ {} is not one of your delegates.", escape(username))
+            }
+        }
+        Some(_) => {
+            let Some(args) = command_args(&bot, msg.chat.id, &commands::DELEGATE, input).await?
+            else {
+                return Ok(());
             };
-
-            response.push_str(&format!(
-                "{}. <b>{}</b>\n   📆 {}\n   🕐 {}\n",
-                idx + 1,
-                escape(&event.summary),
-                start.format("%a, %b %d"),
-                time_str
-            ));
-
-            if let Some(location) = &event.location {
-                response.push_str(&format!("   📍 {}\n", escape(location)));
+            let username = args.get("username").unwrap_or_default();
+            match db.grant_delegate(telegram_id, username).await {
+                Ok(delegate) => {
+                    tracing::info!("User {} granted a calendar delegate", telegram_id);
+                    format!(
+                        "✅ {} can now create, edit and delete events on your calendar.\n\n\
+                         Their changes are recorded under their name. \
+                         Undo with <code>/delegate revoke {}</code>",
+                        delegate_name(&delegate),
+                        escape(username)
+                    )
+                }
+                Err(ApplicationError::NotFound(_)) => format!(
+                    "❌ I don't know {} yet. They need to start the bot first.",
+                    escape(username)
+                ),
+                Err(ApplicationError::BadRequest(message)) => format!("❌ {}", escape(&message)),
+                Err(err) => return Err(err.into()),
             }
-
-            response.push('\n');
         }
-
-        bot.send_message(msg.chat.id, response)
-            .parse_mode(ParseMode::Html)
-            .await?;
-    }
-
-    tracing::info!(
-        "User {} queried list events: {} found",
-        telegram_id,
-        events.len()
-    );
-
-    Ok(())
-}
-
-/// Handle the /cancel command
-pub async fn handle_cancel(bot: Bot, msg: Message) -> Result<()> {
-    let response = "❌ <b>Cancel Event</b>\n\n\
-                    To cancel an event, use the web UI where you can:\n\
-                    • View all your events\n\
-                    • Select events to cancel\n\
-                    • See event details before deletion";
-
+    };
     bot.send_message(msg.chat.id, response)
         .parse_mode(ParseMode::Html)
         .await?;
@@ -389,130 +766,197 @@ pub async fn handle_cancel(bot: Bot, msg: Message) -> Result<()> {
     Ok(())
 }
 
-/// Handle the /invite command
-pub async fn handle_invite(bot: Bot, msg: Message, db: BotDb) -> Result<()> {
-    use uuid::Uuid;
+/// Absolute URL of a server path as users reach it, keeping any path prefix
+/// of `PUBLIC_BASE_URL` (e.g. `https://example.com/televent`)
+fn public_url(path: &str) -> String {
+    let base_url =
+        std::env::var("PUBLIC_BASE_URL").unwrap_or_else(|_| "http://localhost:3000".to_string());
+    format!("{}{path}", base_url.trim_end_matches('/'))
+}
 
+/// Number of recent requests shown by `/device info`
+const DEVICE_INFO_ENTRIES: usize = 10;
+
+/// Handle the /device command
+pub async fn handle_device(bot: Bot, msg: Message, db: BotDb) -> Result<()> {
     let user = msg
         .from
-        .as_ref()
+        .clone()
         .ok_or_else(|| anyhow::anyhow!("No user in message"))?;
     let telegram_id = user.id.0 as i64;
 
-    // Parse command arguments: /invite <event_id> <@username or email>
-    let text = msg.text().unwrap_or("");
-    let parts: Vec<&str> = text.split_whitespace().collect();
-
-    if parts.len() < 3 {
-        let help_text = "📨 <b>Invite Someone to an Event</b>\n\n\
-                        <b>Usage:</b>\n\
-                        /invite &lt;event_id&gt; @username\n\
-                        /invite &lt;event_id&gt; email@example.com\n\n\
-                        <b>Example:</b>\n\
-                        /invite abc123... @alice\n\
-                        /invite abc123... user@gmail.com";
-
-        bot.send_message(msg.chat.id, help_text)
-            .parse_mode(ParseMode::Html)
-            .await?;
-        return Ok(());
-    }
-
-    let event_id_str = parts[1];
-    let invitee_str = parts[2];
+    let (subcommand, input) = args::subcommand(args::after_command(msg.text().unwrap_or("")));
+    let spec = match subcommand {
+        Some("add") => &commands::DEVICE_ADD,
+        None | Some("list") => &commands::DEVICE_LIST,
+        Some("info") => &commands::DEVICE_INFO,
+        Some("revoke") => &commands::DEVICE_REVOKE,
+        _ => {
+            let response = "🔐 <b>CalDAV Device Management</b>\n\n\
+                            Device passwords allow you to sync your calendar with CalDAV clients.\n\n\
+                            <b>Commands:</b>\n\
+                            <code>/device add [name]</code> - Create a new device password\n\
+                            <code>/device</code> - List your devices, with buttons to add or revoke them\n\
+                            <code>/device info &lt;id&gt;</code> - Show a device's recent sync requests\n\
+                            <code>/device revoke &lt;id&gt;</code> - Revoke a device password\n\n\
+                            Quote names with spaces, e.g. <code>/device add \"Work Laptop\"</code>\n\n\
+                            <b>Supported Clients:</b>\n\
+                            • Apple Calendar (iOS, macOS)\n\
+                            • Thunderbird\n\
+                            • DAVx⁵ (Android)\n\
+                            • Any CalDAV-compatible client";
 
-    // Parse event UUID
-    let event_id = match Uuid::parse_str(event_id_str) {
-        Ok(id) => id,
-        Err(_) => {
-            bot.send_message(msg.chat.id, "❌ Invalid event ID format")
+            bot.send_message(msg.chat.id, response)
+                .parse_mode(ParseMode::Html)
                 .await?;
             return Ok(());
         }
     };
+    let Some(args) = command_args(&bot, msg.chat.id, spec, input).await? else {
+        return Ok(());
+    };
 
-    // Verify user owns this event
-    let event_info = match db.get_event_info(event_id, telegram_id).await? {
-        Some(info) => info,
-        None => {
-            bot.send_message(
-                msg.chat.id,
-                "❌ Event not found or you don't have permission to invite others",
-            )
-            .await?;
-            return Ok(());
+    match (subcommand, args.uuid("device_id").map(DeviceId::new)) {
+        (Some("add"), _) => {
+            let device_name = args.get("name").unwrap_or("My Device");
+            send_device_password(&bot, msg.chat.id, telegram_id, device_name, &db).await?;
         }
-    };
+        (None | Some("list"), _) => match db.list_device_passwords(telegram_id).await {
+            Ok(devices) => {
+                let (text, keyboard) = render_device_list(&devices);
+                bot.send_message(msg.chat.id, text)
+                    .parse_mode(ParseMode::Html)
+                    .reply_markup(keyboard)
+                    .await?;
+            }
+            Err(e) => {
+                bot.send_message(msg.chat.id, format!("❌ Failed to list devices: {}", e))
+                    .await?;
+            }
+        },
+        (Some("revoke"), Some(device_id)) => {
+            match db.revoke_device_password(telegram_id, device_id).await {
+                Ok(true) => {
+                    bot.send_message(msg.chat.id, "✅ Device password revoked successfully!")
+                        .await?;
 
-    // Determine if invitee is internal (@ username) or external (email)
-    let (invitee_email, invitee_telegram_id) = if invitee_str.starts_with('@') {
-        // Internal invite - lookup Telegram user
-        let username = invitee_str.trim_start_matches('@');
-        match db.find_user_by_username(username).await? {
-            Some(user_info) => (
-                internal_email_for_telegram_id(user_info.telegram_id),
-                Some(user_info.telegram_id),
-            ),
-            None => {
+                    tracing::info!(
+                        "Device password revoked for user {}: {}",
+                        telegram_id,
+                        device_id
+                    );
+                }
+                Ok(false) => {
+                    bot.send_message(msg.chat.id, "❌ Device not found or already revoked.")
+                        .await?;
+                }
+                Err(e) => {
+                    bot.send_message(msg.chat.id, format!("❌ Failed to revoke device: {}", e))
+                        .await?;
+                }
+            }
+        }
+        (Some("info"), Some(device_id)) => match db.device_activity(telegram_id, device_id).await {
+            Ok(Some(activity)) if activity.is_empty() => {
                 bot.send_message(
                     msg.chat.id,
-                    format!(
-                        "❌ User @{} not found. They need to /start the bot first.",
-                        username
-                    ),
+                    "📭 This device hasn't made any CalDAV requests yet.",
                 )
                 .await?;
-                return Ok(());
             }
-        }
-    } else {
-        // External invite - use email as-is
-        (invitee_str.to_string(), None)
-    };
+            Ok(Some(activity)) => {
+                let mut response = String::from("🔍 <b>Recent Requests</b> (newest first)\n\n");
+                for entry in activity.iter().take(DEVICE_INFO_ENTRIES) {
+                    response.push_str(&format!(
+                        "{} <code>{} {}</code> → {}\n",
+                        entry.at.format("%Y-%m-%d %H:%M:%S"),
+                        escape(&entry.method),
+                        inline(&entry.path),
+                        entry.status
+                    ));
+                    if let Some(user_agent) = &entry.user_agent {
+                        response.push_str(&format!("   🖥 {}\n", inline(user_agent)));
+                    }
+                    if let Some(sync_token) = &entry.sync_token {
+                        response.push_str(&format!(
+                            "   🔁 Sync token: <code>{}</code>\n",
+                            inline(sync_token)
+                        ));
+                    }
+                    if let Some(conflict) = &entry.conflict {
+                        response.push_str(&format!(
+                            "   ⚠️ Stale edit of <code>{}</code>: SEQUENCE {} behind {}\n",
+                            inline(&conflict.uid),
+                            conflict.client_sequence,
+                            conflict.server_sequence
+                        ));
+                    }
+                }
 
-    // Create attendee record
-    match db
-        .invite_attendee(event_id, &invitee_email, invitee_telegram_id, "ATTENDEE")
-        .await
-    {
-        Ok(_) => {
-            let success_msg = if invitee_telegram_id.is_some() {
-                format!(
-                    "✅ Invited {} to event: <b>{}</b>\n\nThey will receive a Telegram notification.",
-                    escape(invitee_str),
-                    escape(&event_info.summary)
-                )
-            } else {
-                format!(
-                    "✅ Invited {} to event: <b>{}</b>\n\n⚠️ External email delivery is deferred.",
-                    escape(invitee_str),
-                    escape(&event_info.summary)
+                bot.send_message(msg.chat.id, response)
+                    .parse_mode(ParseMode::Html)
+                    .await?;
+            }
+            Ok(None) => {
+                bot.send_message(msg.chat.id, "❌ Device not found.")
+                    .await?;
+            }
+            Err(e) => {
+                bot.send_message(
+                    msg.chat.id,
+                    format!("❌ Failed to load device activity: {}", e),
                 )
-            };
+                .await?;
+            }
+        },
+        // Anything else was answered above
+        _ => {}
+    }
+
+    Ok(())
+}
+
+/// Create a device password and send it with the CalDAV setup details
+async fn send_device_password(
+    bot: &Bot,
+    chat_id: ChatId,
+    telegram_id: i64,
+    device_name: &str,
+    db: &BotDb,
+) -> Result<()> {
+    match db.generate_device_password(telegram_id, device_name).await {
+        Ok(password) => {
+            let caldav_url = public_url("/caldav");
+            let response = format!(
+                "✅ <b>Device Password Created!</b>\n\n\
+                 🏷️ Device: {}\n\
+                 🔑 Password: <code>{}</code>\n\n\
+                 <b>CalDAV Setup:</b>\n\
+                 Server: <code>{}</code>\n\
+                 Username: <code>{}</code>\n\
+                 Password: Use the password above\n\n\
+                 ⚠️ <b>Important:</b> Save this password securely! \
+                 You won't be able to see it again.",
+                inline(device_name),
+                password,
+                escape(&caldav_url),
+                telegram_id
+            );
 
-            bot.send_message(msg.chat.id, success_msg)
+            bot.send_message(chat_id, response)
                 .parse_mode(ParseMode::Html)
                 .await?;
 
             tracing::info!(
-                "User {} invited {} to event {}",
+                "Device password created for user {}: {}",
                 telegram_id,
-                invitee_email,
-                event_id
+                device_name
             );
         }
-        Err(televent_application::ApplicationError::NotFound(_)) => {
-            bot.send_message(
-                msg.chat.id,
-                format!("⚠️ {} is already invited to this event", invitee_str),
-            )
-            .await?;
-        }
         Err(e) => {
-            tracing::error!("Failed to invite attendee: {}", e);
             bot.send_message(
-                msg.chat.id,
-                "❌ Failed to send invite. Please try again later.",
+                chat_id,
+                format!("❌ Failed to create device password: {}", e),
             )
             .await?;
         }
@@ -521,321 +965,3832 @@ pub async fn handle_invite(bot: Bot, msg: Message, db: BotDb) -> Result<()> {
     Ok(())
 }
 
-/// Handle the /rsvp command
-pub async fn handle_rsvp(bot: Bot, msg: Message, db: BotDb) -> Result<()> {
-    let user = msg
-        .from
-        .as_ref()
-        .ok_or_else(|| anyhow::anyhow!("No user in message"))?;
-    let telegram_id = user.id.0 as i64;
-    let username = user
-        .username
-        .clone()
-        .unwrap_or_else(|| format!("User_{}", telegram_id));
+/// Text of the message asking for a new device's name; a reply to it
+/// creates the device
+const DEVICE_NAME_PROMPT: &str = "🏷 <b>New device</b>\n\n\
+     Reply to this message with a name for it, e.g. Work Laptop.";
+
+/// Render the device list with a Revoke button per device and a Create
+/// button
+fn render_device_list(devices: &[DevicePasswordInfo]) -> (String, InlineKeyboardMarkup) {
+    let create = vec![InlineKeyboardButton::callback(
+        "➕ New device",
+        "dev:create",
+    )];
+    if devices.is_empty() {
+        let text = "📱 You don't have any device passwords yet.\n\n\
+                    Tap ➕ New device or send <code>/device add Device Name</code>."
+            .to_string();
+        return (text, InlineKeyboardMarkup::new([create]));
+    }
 
-    // Parse command arguments: /rsvp [<event_id> <status>]
-    let text = msg.text().unwrap_or("");
-    let parts: Vec<&str> = text.split_whitespace().collect();
+    let mut text = format!("📱 <b>Your Devices</b> ({})\n\n", devices.len());
+    let mut rows = Vec::with_capacity(devices.len() + 1);
+    for (idx, device) in devices.iter().enumerate() {
+        text.push_str(&format!(
+            "{}. <b>{}</b>\n   🆔 <code>{}</code>\n   📅 Created: {}\n",
+            idx + 1,
+            inline(&device.name),
+            device.id,
+            device.created_at.format("%Y-%m-%d %H:%M")
+        ));
+
+        if let Some(last_used) = device.last_used_at {
+            text.push_str(&format!(
+                "   🕐 Last used: {}\n",
+                last_used.format("%Y-%m-%d %H:%M")
+            ));
+        }
+        if device.recent_requests > 0 {
+            text.push_str(&format!(
+                "   🔄 Requests in the last 24h: {}\n",
+                device.recent_requests
+            ));
+        }
 
-    // If no arguments, list pending invites
-    if parts.len() < 2 {
-        let pending = db.get_pending_invites(telegram_id).await?;
+        text.push('\n');
+        rows.push(vec![InlineKeyboardButton::callback(
+            format!("🗑 Revoke {}", device.name),
+            format!("dev:revoke:{}", device.id.inner().simple()),
+        )]);
+    }
+    rows.push(create);
 
-        if pending.is_empty() {
-            bot.send_message(msg.chat.id, "📭 No pending invitations")
-                .await?;
-            return Ok(());
-        }
+    text.push_str("To see recent syncs: <code>/device info &lt;ID&gt;</code>");
 
-        let mut response = format!("📨 <b>Pending Invitations</b> ({})\n\n", pending.len());
-        for invite in pending.iter().take(10) {
-            let organizer = invite
-                .organizer_username
-                .as_ref()
-                .map(|u| format!("@{}", escape(u)))
-                .unwrap_or_else(|| "Unknown".to_string());
+    (text, InlineKeyboardMarkup::new(rows))
+}
 
-            let location_text = invite
-                .location
-                .as_ref()
-                .map(|loc| format!("\n📍 {}", escape(loc)))
-                .unwrap_or_default();
+/// Show the user's current devices in the list message `message`
+async fn refresh_device_list(
+    bot: &Bot,
+    chat_id: ChatId,
+    message: MessageId,
+    telegram_id: i64,
+    db: &BotDb,
+) -> Result<()> {
+    let devices = db.list_device_passwords(telegram_id).await?;
+    let (text, keyboard) = render_device_list(&devices);
+    bot.edit_message_text(chat_id, message, text)
+        .parse_mode(ParseMode::Html)
+        .reply_markup(keyboard)
+        .await?;
 
-            let start = invite.start.unwrap_or_else(|| {
-                invite
-                    .start_date
-                    .and_then(|date| date.and_hms_opt(0, 0, 0))
-                    .map(|date_time| date_time.and_utc())
-                    .unwrap_or_else(Utc::now)
-            });
-            let time_str = if invite.is_all_day {
-                "All Day".to_string()
-            } else {
-                start.format("%H:%M UTC").to_string()
-            };
+    Ok(())
+}
 
-            response.push_str(&format!(
-                "🔹 <b>{}</b>\n   🕒 {} {}\n   👤 From: {}{}\n   <code>/rsvp {} accept</code>\n\n",
-                escape(&invite.summary),
-                start.format("%a %b %d"),
-                time_str,
-                organizer,
-                location_text,
-                invite.event_id
-            ));
-        }
+/// Handle device list buttons. Format: dev:<create|cancel|list> or
+/// dev:<revoke|confirm>:<device_id>
+async fn handle_device_callback(
+    bot: Bot,
+    callback_id: CallbackQueryId,
+    user_id: i64,
+    message: Option<MaybeInaccessibleMessage>,
+    data: &str,
+    db: BotDb,
+) -> Result<()> {
+    let Some(msg) = message else {
+        bot.answer_callback_query(callback_id)
+            .text("❌ This message is too old; send /device again")
+            .await?;
+        return Ok(());
+    };
+    let (chat_id, message_id) = (msg.chat().id, msg.id());
 
-        response.push_str("\n<b>To respond:</b>\n");
-        response.push_str("/rsvp &lt;event_id&gt; accept\n");
-        response.push_str("/rsvp &lt;event_id&gt; decline\n");
-        response.push_str("/rsvp &lt;event_id&gt; tentative");
+    let parts: Vec<&str> = data.split(':').collect();
+    let (action, device_id) = match parts.as_slice() {
+        ["dev", action @ ("create" | "cancel" | "list")] => (*action, None),
+        ["dev", action @ ("revoke" | "confirm"), device_id] => match Uuid::parse_str(device_id) {
+            Ok(device_id) => (*action, Some(DeviceId::new(device_id))),
+            Err(_) => {
+                bot.answer_callback_query(callback_id)
+                    .text("❌ Invalid device ID")
+                    .await?;
+                return Ok(());
+            }
+        },
+        _ => {
+            bot.answer_callback_query(callback_id)
+                .text("❌ Invalid data")
+                .await?;
+            return Ok(());
+        }
+    };
 
-        bot.send_message(msg.chat.id, response)
+    if action == "create" {
+        bot.edit_message_text(chat_id, message_id, DEVICE_NAME_PROMPT)
             .parse_mode(ParseMode::Html)
+            .reply_markup(InlineKeyboardMarkup::new([[
+                InlineKeyboardButton::callback("✖️ Cancel", "dev:cancel"),
+            ]]))
+            .await?;
+        bot.answer_callback_query(callback_id)
+            .text("Reply with a name for the device")
             .await?;
-
         return Ok(());
     }
 
-    // Parse RSVP response: /rsvp <event_id> <status>
-    if parts.len() < 3 {
+    let Some(device_id) = device_id else {
+        // Cancel and Keep go back to the list
+        refresh_device_list(&bot, chat_id, message_id, user_id, &db).await?;
+        bot.answer_callback_query(callback_id).await?;
+        return Ok(());
+    };
+
+    let devices = db.list_device_passwords(user_id).await?;
+    let Some(device) = devices.iter().find(|device| device.id == device_id) else {
+        bot.answer_callback_query(callback_id)
+            .text("This device was already revoked")
+            .await?;
+        refresh_device_list(&bot, chat_id, message_id, user_id, &db).await?;
+        return Ok(());
+    };
+
+    if action == "revoke" {
+        let text = format!(
+            "🗑 Revoke <b>{}</b>?\n\n\
+             CalDAV clients signed in with its password stop syncing.",
+            inline(&device.name)
+        );
+        bot.edit_message_text(chat_id, message_id, text)
+            .parse_mode(ParseMode::Html)
+            .reply_markup(InlineKeyboardMarkup::new([[
+                InlineKeyboardButton::callback(
+                    "✅ Revoke",
+                    format!("dev:confirm:{}", device_id.inner().simple()),
+                ),
+                InlineKeyboardButton::callback("↩️ Keep", "dev:list"),
+            ]]))
+            .await?;
+        bot.answer_callback_query(callback_id).await?;
+        return Ok(());
+    }
+
+    match db.revoke_device_password(user_id, device_id).await {
+        Ok(true) => {
+            tracing::info!(
+                "Device password revoked for user {}: {}",
+                user_id,
+                device_id
+            );
+            bot.answer_callback_query(callback_id)
+                .text(format!("🗑 Revoked {}", device.name))
+                .await?;
+        }
+        Ok(false) => {
+            bot.answer_callback_query(callback_id)
+                .text("This device was already revoked")
+                .await?;
+        }
+        Err(e) => {
+            tracing::error!("Failed to revoke device: {}", e);
+            bot.answer_callback_query(callback_id)
+                .text("❌ Failed to revoke the device. Please try again.")
+                .show_alert(true)
+                .await?;
+            return Ok(());
+        }
+    }
+
+    // Refresh the list in place
+    refresh_device_list(&bot, chat_id, message_id, user_id, &db).await
+}
+
+/// Device list message a message replies to, when it answers the bot's
+/// request for a new device's name
+pub fn device_name_target(msg: &Message) -> Option<MessageId> {
+    let prompt = msg.reply_to_message()?;
+    if !prompt.from.as_ref().is_some_and(|user| user.is_bot) {
+        return None;
+    }
+    let asks_for_name = prompt
+        .reply_markup()?
+        .inline_keyboard
+        .iter()
+        .flatten()
+        .any(|button| {
+            matches!(&button.kind, InlineKeyboardButtonKind::CallbackData(data) if data == "dev:cancel")
+        });
+    asks_for_name.then_some(prompt.id)
+}
+
+/// Handle a reply naming a new device: create it and show it in the list
+pub async fn handle_device_name_reply(
+    bot: Bot,
+    msg: Message,
+    db: BotDb,
+    list: MessageId,
+) -> Result<()> {
+    let user = msg
+        .from
+        .as_ref()
+        .ok_or_else(|| anyhow::anyhow!("No user in message"))?;
+    let telegram_id = user.id.0 as i64;
+
+    if is_read_only(&db).await {
+        bot.send_message(msg.chat.id, READ_ONLY_NOTICE).await?;
+        return Ok(());
+    }
+
+    let device_name = msg.text().unwrap_or_default().trim();
+    if device_name.is_empty() {
+        return Ok(());
+    }
+
+    send_device_password(&bot, msg.chat.id, telegram_id, device_name, &db).await?;
+    refresh_device_list(&bot, msg.chat.id, list, telegram_id, &db).await
+}
+
+/// Handle the /subscribe command
+pub async fn handle_subscribe(bot: Bot, msg: Message, db: BotDb) -> Result<()> {
+    let user = msg
+        .from
+        .clone()
+        .ok_or_else(|| anyhow::anyhow!("No user in message"))?;
+    let telegram_id = user.id.0 as i64;
+
+    let (subcommand, input) = args::subcommand(args::after_command(msg.text().unwrap_or("")));
+    let spec = match subcommand {
+        Some("add") => &commands::SUBSCRIBE_ADD,
+        Some("list") => &commands::SUBSCRIBE_LIST,
+        Some("holidays") => &commands::SUBSCRIBE_HOLIDAYS,
+        Some("birthdays") => &commands::SUBSCRIBE_BIRTHDAYS,
+        Some("remove") => &commands::SUBSCRIBE_REMOVE,
+        _ => {
+            let response = "🔗 <b>Calendar Subscriptions</b>\n\n\
+                            Subscribe to an external calendar (e.g. a public Google Calendar \
+                            ICS link) to see its events next to yours. Subscribed events are read-only.\n\n\
+                            <b>Commands:</b>\n\
+                            <code>/subscribe add &lt;url&gt; [name]</code> - Subscribe to an ICS URL\n\
+                            <code>/subscribe list</code> - List your subscriptions\n\
+                            <code>/subscribe holidays [country]</code> - Add a country's public holidays\n\
+                            <code>/subscribe birthdays [off]</code> - Show your contacts' birthdays\n\
+                            <code>/subscribe remove &lt;id&gt;</code> - Unsubscribe";
+
+            bot.send_message(msg.chat.id, response)
+                .parse_mode(ParseMode::Html)
+                .await?;
+            return Ok(());
+        }
+    };
+    let Some(args) = command_args(&bot, msg.chat.id, spec, input).await? else {
+        return Ok(());
+    };
+
+    match (subcommand, args.uuid("subscription_id")) {
+        (Some("add"), _) => {
+            let url = args.get("url").unwrap_or_default();
+            match db
+                .add_subscription(telegram_id, user.username.as_deref(), url, args.get("name"))
+                .await
+            {
+                Ok(subscription) => {
+                    let response = format!(
+                        "✅ <b>Subscribed to {}</b>\n\n\
+                         Its events will show up in /list and in your CalDAV client \
+                         within a few minutes. They are read-only and refresh every hour.",
+                        inline(&subscription.name)
+                    );
+                    bot.send_message(msg.chat.id, response)
+                        .parse_mode(ParseMode::Html)
+                        .await?;
+
+                    tracing::info!(
+                        "User {} subscribed to calendar {}",
+                        telegram_id,
+                        subscription.id
+                    );
+                }
+                Err(ApplicationError::BadRequest(reason) | ApplicationError::Conflict(reason)) => {
+                    bot.send_message(msg.chat.id, format!("❌ {}", reason))
+                        .await?;
+                }
+                Err(e) => {
+                    tracing::error!("Failed to add subscription: {}", e);
+                    bot.send_message(msg.chat.id, "❌ Failed to add the subscription.")
+                        .await?;
+                }
+            }
+        }
+        (Some("holidays"), _) => match args.get("country") {
+            Some(country) => {
+                match db
+                    .enable_holidays(telegram_id, user.username.as_deref(), country)
+                    .await
+                {
+                    Ok(subscription) => {
+                        let response = format!(
+                            "✅ <b>Subscribed to {}</b>\n\n\
+                             The holidays of this year and the next show up in /week and in \
+                             your CalDAV client. To drop them: \
+                             <code>/subscribe remove {}</code>",
+                            inline(&subscription.name),
+                            subscription.id
+                        );
+                        bot.send_message(msg.chat.id, response)
+                            .parse_mode(ParseMode::Html)
+                            .await?;
+
+                        tracing::info!(
+                            "User {} subscribed to holiday calendar {}",
+                            telegram_id,
+                            subscription.id
+                        );
+                    }
+                    Err(
+                        ApplicationError::NotFound(_)
+                        | ApplicationError::BadRequest(_)
+                        | ApplicationError::Conflict(_),
+                    ) => {
+                        bot.send_message(
+                            msg.chat.id,
+                            format!(
+                                "❌ No holiday calendar for {}. See <code>/subscribe holidays</code> \
+                                 for the countries available.",
+                                inline(country)
+                            ),
+                        )
+                        .parse_mode(ParseMode::Html)
+                        .await?;
+                    }
+                    Err(e) => {
+                        tracing::error!("Failed to add holiday calendar: {}", e);
+                        bot.send_message(msg.chat.id, "❌ Failed to add the holiday calendar.")
+                            .await?;
+                    }
+                }
+            }
+            None => {
+                let enabled: Vec<String> = db
+                    .list_subscriptions(telegram_id)
+                    .await
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|subscription| subscription.url)
+                    .collect();
+                let mut response = String::from("🎉 <b>Holiday Calendars</b>\n\n");
+                for country in holiday_countries() {
+                    let mark = if enabled.contains(&country.url()) {
+                        " ✅"
+                    } else {
+                        ""
+                    };
+                    response.push_str(&format!(
+                        "<code>{}</code> {}{}\n",
+                        country.code,
+                        escape(country.name),
+                        mark
+                    ));
+                }
+                response.push_str("\nAdd one with: <code>/subscribe holidays &lt;code&gt;</code>");
+                bot.send_message(msg.chat.id, response)
+                    .parse_mode(ParseMode::Html)
+                    .await?;
+            }
+        },
+        (Some("birthdays"), _) if args.get("on|off") == Some("off") => {
+            match db.disable_birthdays(telegram_id).await {
+                Ok(true) => {
+                    bot.send_message(msg.chat.id, "✅ Birthdays calendar turned off.")
+                        .await?;
+                }
+                Ok(false) => {
+                    bot.send_message(msg.chat.id, "The birthdays calendar is already off.")
+                        .await?;
+                }
+                Err(e) => {
+                    tracing::error!("Failed to turn off birthdays calendar: {}", e);
+                    bot.send_message(msg.chat.id, "❌ Failed to turn off the birthdays calendar.")
+                        .await?;
+                }
+            }
+        }
+        (Some("birthdays"), _) => {
+            match db
+                .enable_birthdays(telegram_id, user.username.as_deref())
+                .await
+            {
+                Ok(subscription) => {
+                    let response = "🎂 <b>Birthdays calendar on</b>\n\n\
+                         Birthdays from your synced contacts show up in /week and in your \
+                         CalDAV client, and each morning I'll tell you whose birthday is \
+                         tomorrow. Contacts are read again every day.\n\n\
+                         To turn it off: <code>/subscribe birthdays off</code>";
+                    bot.send_message(msg.chat.id, response)
+                        .parse_mode(ParseMode::Html)
+                        .await?;
+
+                    tracing::info!(
+                        "User {} turned on birthdays calendar {}",
+                        telegram_id,
+                        subscription.id
+                    );
+                }
+                Err(ApplicationError::BadRequest(reason) | ApplicationError::Conflict(reason)) => {
+                    bot.send_message(msg.chat.id, format!("❌ {}", reason))
+                        .await?;
+                }
+                Err(e) => {
+                    tracing::error!("Failed to turn on birthdays calendar: {}", e);
+                    bot.send_message(msg.chat.id, "❌ Failed to turn on the birthdays calendar.")
+                        .await?;
+                }
+            }
+        }
+        (Some("list"), _) => match db.list_subscriptions(telegram_id).await {
+            Ok(subscriptions) if subscriptions.is_empty() => {
+                bot.send_message(
+                    msg.chat.id,
+                    "🔗 You don't have any calendar subscriptions yet.\n\n\
+                     Add one with: <code>/subscribe add &lt;url&gt; [name]</code>",
+                )
+                .parse_mode(ParseMode::Html)
+                .await?;
+            }
+            Ok(subscriptions) => {
+                let mut response =
+                    format!("🔗 <b>Your Subscriptions</b> ({})\n\n", subscriptions.len());
+
+                for (idx, subscription) in subscriptions.iter().enumerate() {
+                    response.push_str(&format!(
+                        "{}. <b>{}</b>\n   🌐 {}\n   🆔 <code>{}</code>\n   📂 CalDAV: <code>{}</code>\n",
+                        idx + 1,
+                        inline(&subscription.name),
+                        escape(&subscription.url),
+                        subscription.id,
+                        escape(&public_url(&format!(
+                            "/caldav/{telegram_id}/subscriptions/{}/",
+                            subscription.id
+                        ))),
+                    ));
+
+                    match (&subscription.last_error, subscription.last_fetched_at) {
+                        (Some(error), _) => {
+                            response.push_str(&format!(
+                                "   ⚠️ Last refresh failed: {}\n",
+                                escape(error)
+                            ));
+                        }
+                        (None, Some(fetched_at)) => {
+                            response.push_str(&format!(
+                                "   🕐 Refreshed: {}\n",
+                                fetched_at.format("%Y-%m-%d %H:%M")
+                            ));
+                        }
+                        (None, None) => response.push_str("   ⏳ Waiting for first refresh\n"),
+                    }
+
+                    response.push('\n');
+                }
+
+                response.push_str("To unsubscribe: <code>/subscribe remove &lt;ID&gt;</code>");
+
+                bot.send_message(msg.chat.id, response)
+                    .parse_mode(ParseMode::Html)
+                    .await?;
+            }
+            Err(e) => {
+                bot.send_message(
+                    msg.chat.id,
+                    format!("❌ Failed to list subscriptions: {}", e),
+                )
+                .await?;
+            }
+        },
+        (Some("remove"), Some(subscription_id)) => {
+            match db.remove_subscription(telegram_id, subscription_id).await {
+                Ok(true) => {
+                    bot.send_message(msg.chat.id, "✅ Subscription removed.")
+                        .await?;
+
+                    tracing::info!(
+                        "User {} removed calendar subscription {}",
+                        telegram_id,
+                        subscription_id
+                    );
+                }
+                Ok(false) => {
+                    bot.send_message(msg.chat.id, "❌ Subscription not found.")
+                        .await?;
+                }
+                Err(e) => {
+                    bot.send_message(
+                        msg.chat.id,
+                        format!("❌ Failed to remove subscription: {}", e),
+                    )
+                    .await?;
+                }
+            }
+        }
+        // Anything else was answered above
+        _ => {}
+    }
+
+    Ok(())
+}
+
+/// Handle the /export command
+pub async fn handle_export(bot: Bot, msg: Message, db: BotDb) -> Result<()> {
+    let user = msg
+        .from
+        .ok_or_else(|| anyhow::anyhow!("No user in message"))?;
+    let telegram_id = user.id.0 as i64;
+
+    let export = db.export_calendar_ics(telegram_id).await?;
+
+    if export.event_count == 0 {
+        bot.send_message(msg.chat.id, "📅 You don't have any events to export yet.")
+            .await?;
+        return Ok(());
+    }
+
+    // Send as file
+    let file =
+        teloxide::types::InputFile::memory(export.body.into_bytes()).file_name("calendar.ics");
+
+    bot.send_document(msg.chat.id, file)
+        .caption("📤 Here is your calendar export.")
+        .await?;
+
+    tracing::info!(
+        "User {} exported {} events",
+        telegram_id,
+        export.event_count
+    );
+
+    Ok(())
+}
+
+/// Handle the /deleteaccount command
+pub async fn handle_delete_account(bot: Bot, msg: Message) -> Result<()> {
+    let response = "⚠️ <b>Delete Account</b>\n\n\
+                    This will permanently delete:\n\
+                    • All your events\n\
+                    • Your calendar\n\
+                    • All device passwords\n\
+                    • All associated data\n\n\
+                    This action CANNOT be undone.\n\n\
+                    To confirm deletion, use the web UI and follow the GDPR deletion process.";
+
+    bot.send_message(msg.chat.id, response)
+        .parse_mode(ParseMode::Html)
+        .await?;
+
+    Ok(())
+}
+
+/// Days an agenda command covers, counted from the user's today
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AgendaRange {
+    Today,
+    Tomorrow,
+    /// Today and the six days after it; also what /list shows
+    Week,
+}
+
+impl AgendaRange {
+    /// Offset of the first day from today, and the number of days
+    fn days(self) -> (i64, i64) {
+        match self {
+            Self::Today => (0, 1),
+            Self::Tomorrow => (1, 1),
+            Self::Week => (0, 7),
+        }
+    }
+
+    fn title(self) -> &'static str {
+        match self {
+            Self::Today => "Today",
+            Self::Tomorrow => "Tomorrow",
+            Self::Week => "Next 7 days",
+        }
+    }
+
+    /// What to say when nothing is planned, with a creation example that
+    /// lands in the range
+    fn empty_state(self) -> &'static str {
+        match self {
+            Self::Today => {
+                "Nothing planned for today. 🎉\n\n\
+                 💡 Create one by sending me two lines:\n\
+                 <pre>Coffee with Alice\ntoday 5pm</pre>"
+            }
+            Self::Tomorrow => {
+                "Nothing planned for tomorrow.\n\n\
+                 💡 Create one by sending me two lines:\n\
+                 <pre>Dentist\ntomorrow 10am</pre>"
+            }
+            Self::Week => {
+                "Nothing planned for the next 7 days.\n\n\
+                 💡 Create one by sending me two lines:\n\
+                 <pre>Team sync\nnext Monday 10:00</pre>\n\
+                 Already keep a calendar elsewhere? Add it with /subscribe."
+            }
+        }
+    }
+}
+
+/// Handle /today, /tomorrow, /week, /list and /agenda
+pub async fn handle_agenda(bot: Bot, msg: Message, db: BotDb, range: AgendaRange) -> Result<()> {
+    let user = msg
+        .from
+        .as_ref()
+        .ok_or_else(|| anyhow::anyhow!("No user in message"))?;
+    let telegram_id = user.id.0 as i64;
+
+    // Parse command arguments: /list [#tag]...
+    let input = args::after_command(msg.text().unwrap_or(""));
+    let Some(args) = command_args(&bot, msg.chat.id, &commands::AGENDA, input).await? else {
+        return Ok(());
+    };
+    let tags: Vec<String> = args.all("#tag").filter_map(normalize_tag).collect();
+
+    // Days run midnight to midnight where the user is
+    let timezone = db.get_timezone(telegram_id).await?;
+    let tz = timezone.tz();
+    let (offset, days) = range.days();
+    let first_day = Utc::now().with_timezone(&tz).date_naive() + Duration::days(offset);
+    let local_midnight = |day: NaiveDate| {
+        tz.from_local_datetime(&day.and_time(NaiveTime::MIN))
+            .earliest()
+            .map_or_else(
+                || day.and_time(NaiveTime::MIN).and_utc(),
+                |local| local.with_timezone(&Utc),
+            )
+    };
+    let start_range = local_midnight(first_day);
+    let end_range = local_midnight(first_day + Duration::days(days));
+
+    // Query events from database, including mirrored subscription events
+    let mut events = db
+        .get_events_for_user(telegram_id, start_range, end_range)
+        .await?;
+    events.extend(
+        db.get_subscribed_events_for_user(telegram_id, start_range, end_range)
+            .await?,
+    );
+    if !tags.is_empty() {
+        events.retain(|event| {
+            let event_tags = event_tags(&event.summary);
+            tags.iter().all(|tag| event_tags.contains(tag))
+        });
+    }
+
+    let response = render_agenda(range, first_day, &timezone, &tags, &events);
+    let actions = render_agenda_actions(
+        bot.token().as_bytes(),
+        telegram_id,
+        range,
+        first_day,
+        &timezone,
+        &events,
+    );
+    bot.send_message(msg.chat.id, response)
+        .parse_mode(ParseMode::Html)
+        .reply_markup(actions)
+        .await?;
+
+    tracing::info!(
+        "User {} queried {:?} agenda: {} found",
+        telegram_id,
+        range,
+        events.len()
+    );
+
+    Ok(())
+}
+
+/// Start and end of an event on the user's wall clock
+fn local_span(event: &BotEvent, timezone: &Timezone) -> (NaiveDateTime, NaiveDateTime) {
+    let tz = timezone.tz();
+    let local = |instant: Option<DateTime<Utc>>| {
+        let instant = instant.unwrap_or_else(|| event.display_start());
+        if event.is_floating {
+            // Already wall-clock time
+            instant.naive_utc()
+        } else {
+            instant.with_timezone(&tz).naive_local()
+        }
+    };
+    if event.is_all_day {
+        let start = event.display_start().date_naive();
+        let end = event.end_date.unwrap_or(start + Duration::days(1));
+        (start.and_time(NaiveTime::MIN), end.and_time(NaiveTime::MIN))
+    } else {
+        (local(event.start), local(event.end))
+    }
+}
+
+/// Agenda lines in display order with the local day each sits under and its
+/// local span; all-day events lead their day, then by start time
+fn agenda_entries<'a>(
+    first_day: NaiveDate,
+    timezone: &Timezone,
+    events: &'a [BotEvent],
+) -> Vec<(NaiveDate, NaiveDateTime, NaiveDateTime, &'a BotEvent)> {
+    let mut entries: Vec<_> = events
+        .iter()
+        .map(|event| {
+            let (start, end) = local_span(event, timezone);
+            (start.date().max(first_day), start, end, event)
+        })
+        .collect();
+    entries.sort_by_key(|(day, start, _, event)| (*day, !event.is_all_day, *start));
+    entries
+}
+
+/// Most events of one agenda that get quick action buttons
+const MAX_AGENDA_ACTION_ROWS: usize = 20;
+
+/// Longest event summary on a quick action button, in characters
+const MAX_ACTION_LABEL_CHARS: usize = 24;
+
+/// One row of quick action buttons per event the user can act on, in agenda
+/// order: edit, cancel, attendees and remind. Subscribed and cancelled
+/// events get none.
+fn render_agenda_actions(
+    key: &[u8],
+    user_id: i64,
+    range: AgendaRange,
+    first_day: NaiveDate,
+    timezone: &Timezone,
+    events: &[BotEvent],
+) -> InlineKeyboardMarkup {
+    let rows = agenda_entries(first_day, timezone, events)
+        .into_iter()
+        .filter(|(_, _, _, event)| event.subscription.is_none() && !event.cancelled)
+        .take(MAX_AGENDA_ACTION_ROWS)
+        .map(|(_, start, _, event)| {
+            let when = match (range, event.is_all_day) {
+                (AgendaRange::Week, true) => start.format("%a ").to_string(),
+                (AgendaRange::Week, false) => start.format("%a %H:%M ").to_string(),
+                (_, true) => String::new(),
+                (_, false) => start.format("%H:%M ").to_string(),
+            };
+            let mut summary: String = event.summary.chars().take(MAX_ACTION_LABEL_CHARS).collect();
+            if summary.len() < event.summary.len() {
+                summary.push('…');
+            }
+            let button = |label: String, action| {
+                InlineKeyboardButton::callback(
+                    label,
+                    quick_actions::encode(key, user_id, action, event.id),
+                )
+            };
+            vec![
+                button(format!("📝 {when}{summary}"), QuickAction::Edit),
+                button("❌".to_string(), QuickAction::Cancel),
+                button("👥".to_string(), QuickAction::Attendees),
+                button("🔔".to_string(), QuickAction::Remind),
+            ]
+        })
+        .collect::<Vec<_>>();
+
+    InlineKeyboardMarkup::new(rows)
+}
+
+/// Render an agenda grouped by local day, earliest first; events that began
+/// before the range sit under its first day
+fn render_agenda(
+    range: AgendaRange,
+    first_day: NaiveDate,
+    timezone: &Timezone,
+    tags: &[String],
+    events: &[BotEvent],
+) -> String {
+    let mut response = format!("📅 <b>{}</b>", range.title());
+    let tags = tags
+        .iter()
+        .map(|tag| format!("#{}", escape(tag)))
+        .collect::<Vec<_>>()
+        .join(" ");
+    if !tags.is_empty() {
+        response.push_str(&format!(" · {tags}"));
+    }
+    if range == AgendaRange::Week {
+        response.push_str(&format!(" · {}", escape(timezone.as_str())));
+    } else {
+        response.push_str(&format!(
+            " · {} · {}",
+            first_day.format("%a, %b %d"),
+            escape(timezone.as_str())
+        ));
+    }
+    response.push_str("\n\n");
+
+    if events.is_empty() && !tags.is_empty() {
+        response.push_str(&format!("No events tagged {tags}."));
+        return response;
+    }
+    if events.is_empty() {
+        response.push_str(range.empty_state());
+        return response;
+    }
+
+    let mut current_day = None;
+    for (day, start, end, event) in agenda_entries(first_day, timezone, events) {
+        if range == AgendaRange::Week && current_day != Some(day) {
+            if current_day.is_some() {
+                response.push('\n');
+            }
+            response.push_str(&format!("<b>{}</b>\n", day.format("%a, %b %d")));
+            current_day = Some(day);
+        }
+
+        let time = if event.is_all_day {
+            "📆 All day".to_string()
+        } else if end.date() != start.date() {
+            format!("🕐 {}–{}", start.format("%H:%M"), end.format("%a %H:%M"))
+        } else {
+            format!("🕐 {}–{}", start.format("%H:%M"), end.format("%H:%M"))
+        };
+        response.push_str(&format!("{} <b>{}</b>", time, summary_html(event)));
+        if event.is_floating {
+            response.push_str(" (local time)");
+        }
+        response.push('\n');
+
+        if event.cancelled {
+            response.push_str("   ❌ Cancelled\n");
+        }
+        if let Some(location) = &event.location {
+            response.push_str(&format!("   📍 {}\n", inline(location)));
+        }
+        if let Some(calendar_name) = &event.subscription {
+            response.push_str(&format!("   🔗 {}\n", inline(calendar_name)));
+        }
+    }
+
+    response
+}
+
+/// Handle the /note command
+pub async fn handle_note(bot: Bot, msg: Message, db: BotDb) -> Result<()> {
+    let user = msg
+        .from
+        .as_ref()
+        .ok_or_else(|| anyhow::anyhow!("No user in message"))?;
+    let telegram_id = user.id.0 as i64;
+
+    // Parse command arguments: /note <event_id> [<note> | clear]
+    let input = args::after_command(msg.text().unwrap_or(""));
+    if input.is_empty() {
+        bot.send_message(
+            msg.chat.id,
+            "📝 <b>Reminder Notes</b>\n\n\
+             <b>Usage:</b>\n\
+             /note &lt;event_id&gt; Bring laptop!\n\
+             /note &lt;event_id&gt; clear\n\n\
+             The note is added to the event's reminders and to its alarm in calendar apps.",
+        )
+        .parse_mode(ParseMode::Html)
+        .await?;
+        return Ok(());
+    }
+
+    let Some(args) = command_args(&bot, msg.chat.id, &commands::NOTE, input).await? else {
+        return Ok(());
+    };
+    let Some(event_id) = args.uuid("event_id") else {
+        return Ok(());
+    };
+
+    let response = match args.get("note|clear") {
+        None => match db.get_event(telegram_id, event_id).await? {
+            Some(event) => match event.reminder_note {
+                Some(note) => format!(
+                    "📝 Reminders for <b>{}</b> end with: {}\n\n\
+                     Send /note {event_id} clear to remove it.",
+                    escape(&event.summary),
+                    inline(&note)
+                ),
+                None => format!("📝 <b>{}</b> has no reminder note.", escape(&event.summary)),
+            },
+            None => "❌ Event not found or you are not its organizer".to_string(),
+        },
+        Some(arg) => {
+            let note = (!arg.eq_ignore_ascii_case("clear")).then(|| arg.to_string());
+            match db.set_reminder_note(telegram_id, event_id, note).await {
+                Ok(Some(event)) => {
+                    tracing::info!("User {} set the reminder note of {}", telegram_id, event_id);
+                    match event.reminder_note {
+                        Some(note) => format!(
+                            "✅ Reminders for <b>{}</b> will end with: {}",
+                            escape(&event.summary),
+                            inline(&note)
+                        ),
+                        None => format!(
+                            "✅ Reminder note of <b>{}</b> removed.",
+                            escape(&event.summary)
+                        ),
+                    }
+                }
+                Ok(None) => "❌ Event not found or you are not its organizer".to_string(),
+                Err(ApplicationError::BadRequest(message)) => format!("❌ {}", escape(&message)),
+                Err(err) => return Err(err.into()),
+            }
+        }
+    };
+    bot.send_message(msg.chat.id, response)
+        .parse_mode(ParseMode::Html)
+        .await?;
+
+    Ok(())
+}
+
+/// Handle the /cancel command
+pub async fn handle_cancel(bot: Bot, msg: Message) -> Result<()> {
+    let response = "❌ <b>Cancel Event</b>\n\n\
+                    To cancel an event, use the web UI where you can:\n\
+                    • View all your events\n\
+                    • Select events to cancel\n\
+                    • See event details before deletion";
+
+    bot.send_message(msg.chat.id, response)
+        .parse_mode(ParseMode::Html)
+        .await?;
+
+    Ok(())
+}
+
+/// Handle the /invite command
+pub async fn handle_invite(bot: Bot, msg: Message, db: BotDb) -> Result<()> {
+    let user = msg
+        .from
+        .as_ref()
+        .ok_or_else(|| anyhow::anyhow!("No user in message"))?;
+    let telegram_id = user.id.0 as i64;
+
+    // Parse command arguments: /invite <event_id> <@username or email>...
+    let (text, mentioned_names) = with_text_mentions(&msg);
+    let input = args::after_command(&text);
+    if input.is_empty() {
+        let help_text = format!(
+            "📨 <b>Invite People to an Event</b>\n\n\
+             <b>Usage:</b>\n\
+             /invite &lt;event_id&gt; @username\n\
+             /invite &lt;event_id&gt; email@example.com\n\
+             /invite &lt;event_id&gt; @alice @bob carol@example.com\n\
+             /invite &lt;event_id&gt; dave\n\n\
+             Plain names are matched against the contacts you sync over CardDAV; \
+             quote names with spaces, e.g. \"Jane Doe\". \
+             Pick someone from the mention list to invite them without a username.\n\
+             Send just /invite &lt;event_id&gt; to pick from people you invited before.\n\
+             Up to {MAX_INVITEES_PER_REQUEST} people per command.\n\n\
+             <b>Example:</b>\n\
+             /invite abc123... @alice\n\
+             /invite abc123... user@gmail.com"
+        );
+
+        bot.send_message(msg.chat.id, help_text)
+            .parse_mode(ParseMode::Html)
+            .await?;
+        return Ok(());
+    }
+
+    let Some(args) = command_args(&bot, msg.chat.id, &commands::INVITE, input).await? else {
+        return Ok(());
+    };
+    let Some(event_id) = args.uuid("event_id") else {
+        return Ok(());
+    };
+
+    // `/invite <event_id>` alone offers the people invited most often
+    if args.get("invitee").is_none() {
+        return send_invite_suggestions(&bot, msg.chat.id, telegram_id, event_id, &db).await;
+    }
+
+    // Deduplicate while keeping the order the organizer typed
+    let mut invitee_strs: Vec<&str> = Vec::new();
+    for invitee_str in args.all("invitee") {
+        if !invitee_strs
+            .iter()
+            .any(|seen| seen.eq_ignore_ascii_case(invitee_str))
+        {
+            invitee_strs.push(invitee_str);
+        }
+    }
+
+    if invitee_strs.len() > MAX_INVITEES_PER_REQUEST {
+        bot.send_message(
+            msg.chat.id,
+            format!("❌ You can invite at most {MAX_INVITEES_PER_REQUEST} people at once"),
+        )
+        .await?;
+        return Ok(());
+    }
+
+    // Verify user owns this event
+    let event_info = match db.get_event_info(event_id, telegram_id).await? {
+        Some(info) => info,
+        None => {
+            bot.send_message(
+                msg.chat.id,
+                "❌ Event not found or you don't have permission to invite others",
+            )
+            .await?;
+            return Ok(());
+        }
+    };
+
+    // Resolve internal (@username) invitees; emails are used as-is and any
+    // other word is looked up in the organizer's address book
+    let mut invitees: Vec<(String, Option<i64>)> = Vec::with_capacity(invitee_strs.len());
+    let mut labels: Vec<(String, String)> = Vec::with_capacity(invitee_strs.len());
+    let mut not_found: Vec<&str> = Vec::new();
+    let mut unmatched: Vec<&str> = Vec::new();
+    for invitee_str in invitee_strs {
+        if let Some(mentioned_id) = mentioned_user_id(invitee_str) {
+            // A mention picked from the list finds them even without a
+            // username, or with one we haven't seen yet
+            let label = mentioned_names
+                .iter()
+                .find(|(id, _)| *id == mentioned_id)
+                .map_or(invitee_str, |(_, name)| name.as_str());
+            match db.find_user_by_id(mentioned_id).await? {
+                Some(user_info) => {
+                    let email = internal_email_for_telegram_id(user_info.telegram_id);
+                    labels.push((email.clone(), label.to_string()));
+                    invitees.push((email, Some(user_info.telegram_id)));
+                }
+                None => not_found.push(label),
+            }
+        } else if let Some(username) = invitee_str.strip_prefix('@') {
+            match db.find_user_by_username(username).await? {
+                Some(user_info) => {
+                    let email = internal_email_for_telegram_id(user_info.telegram_id);
+                    labels.push((email.clone(), invitee_str.to_string()));
+                    invitees.push((email, Some(user_info.telegram_id)));
+                }
+                None => not_found.push(invitee_str),
+            }
+        } else if invitee_str.contains('@') {
+            // An address someone registered with /email reaches them on Telegram
+            let invitee = match db.find_user_by_email(invitee_str).await? {
+                Some(user_info) => (
+                    internal_email_for_telegram_id(user_info.telegram_id),
+                    Some(user_info.telegram_id),
+                ),
+                None => (invitee_str.to_string(), None),
+            };
+            labels.push((invitee.0.clone(), invitee_str.to_string()));
+            invitees.push(invitee);
+        } else {
+            let Some(contact) = db.find_contact(telegram_id, invitee_str).await? else {
+                unmatched.push(invitee_str);
+                continue;
+            };
+            // Prefer the contact's Telegram account, then whoever registered
+            // their email, then the email itself
+            let telegram_user = match (&contact.telegram_username, &contact.email) {
+                (Some(username), _) => db.find_user_by_username(username).await?,
+                (None, Some(email)) => db.find_user_by_email(email).await?,
+                (None, None) => None,
+            };
+            match (telegram_user, contact.email) {
+                (Some(user_info), _) => {
+                    let email = internal_email_for_telegram_id(user_info.telegram_id);
+                    labels.push((email.clone(), contact.name));
+                    invitees.push((email, Some(user_info.telegram_id)));
+                }
+                (None, Some(email)) => {
+                    labels.push((email.clone(), contact.name));
+                    invitees.push((email, None));
+                }
+                (None, None) => not_found.push(invitee_str),
+            }
+        }
+    }
+
+    // Email guests are rolled out behind a feature flag
+    let mut email_blocked: Vec<String> = Vec::new();
+    if invitees.iter().any(|(_, user_id)| user_id.is_none())
+        && !db
+            .is_feature_enabled(EXTERNAL_INVITES_FLAG, telegram_id)
+            .await?
+    {
+        invitees.retain(|(email, user_id)| {
+            if user_id.is_none() {
+                email_blocked.push(email.clone());
+            }
+            user_id.is_some()
+        });
+    }
+
+    let outcome = if invitees.is_empty() {
+        televent_application::InviteAttendeesResult::default()
+    } else {
+        match db.invite_attendees(event_id, &invitees).await {
+            Ok(outcome) => outcome,
+            Err(e) => {
+                tracing::error!("Failed to invite attendees: {}", e);
+                bot.send_message(
+                    msg.chat.id,
+                    "❌ Failed to send invites. Please try again later.",
+                )
+                .await?;
+                return Ok(());
+            }
+        }
+    };
+
+    // Echo invitees back the way the organizer typed them
+    let join_labels = |emails: &[String]| {
+        emails
+            .iter()
+            .map(|email| {
+                labels
+                    .iter()
+                    .find(|(candidate, _)| candidate == email)
+                    .map_or_else(|| inline(email), |(_, label)| inline(label))
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    let mut summary_msg = format!("📨 Invites for <b>{}</b>\n", inline(&event_info.summary));
+    if !outcome.invited.is_empty() {
+        summary_msg.push_str(&format!("\n✅ Invited: {}", join_labels(&outcome.invited)));
+    }
+    if !outcome.already_invited.is_empty() {
+        summary_msg.push_str(&format!(
+            "\n⚠️ Already invited: {}",
+            join_labels(&outcome.already_invited)
+        ));
+    }
+    if !not_found.is_empty() {
+        summary_msg.push_str(&format!(
+            "\n❌ Not found: {}\nThey need to /start the bot first.",
+            not_found
+                .iter()
+                .map(|label| inline(label))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+    if !email_blocked.is_empty() {
+        summary_msg.push_str(&format!(
+            "\n🚫 Inviting by email isn't available for your account yet: {}",
+            join_labels(&email_blocked)
+        ));
+    }
+    if !unmatched.is_empty() {
+        summary_msg.push_str(&format!(
+            "\n❓ No contact matches: {}",
+            unmatched
+                .iter()
+                .map(|label| inline(label))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+    if outcome.invited.iter().any(|email| {
+        invitees
+            .iter()
+            .any(|(candidate, user_id)| candidate == email && user_id.is_none())
+    }) {
+        summary_msg.push_str("\n\n⚠️ External email delivery is deferred.");
+    }
+
+    bot.send_message(msg.chat.id, summary_msg)
+        .parse_mode(ParseMode::Html)
+        .await?;
+
+    tracing::info!(
+        "User {} invited {} attendee(s) to event {} ({} already invited, {} not found)",
+        telegram_id,
+        outcome.invited.len(),
+        event_id,
+        outcome.already_invited.len(),
+        not_found.len() + unmatched.len()
+    );
+
+    Ok(())
+}
+
+/// Number of frequent invitees offered as buttons
+const INVITE_SUGGESTION_COUNT: usize = 8;
+
+/// Offer the organizer's frequent invitees who are not on this event yet
+async fn send_invite_suggestions(
+    bot: &Bot,
+    chat_id: ChatId,
+    telegram_id: i64,
+    event_id: Uuid,
+    db: &BotDb,
+) -> Result<()> {
+    let Some(event_info) = db.get_event_info(event_id, telegram_id).await? else {
+        bot.send_message(
+            chat_id,
+            "❌ Event not found or you don't have permission to invite others",
+        )
+        .await?;
+        return Ok(());
+    };
+
+    let attendees = db.get_event_attendees(event_id).await?;
+    let suggestions: Vec<SuggestedAttendee> = db
+        .suggest_attendees(telegram_id, INVITE_SUGGESTION_COUNT + attendees.len())
+        .await?
+        .into_iter()
+        .filter(|suggestion| {
+            !attendees
+                .iter()
+                .any(|attendee| attendee.email.eq_ignore_ascii_case(&suggestion.email))
+        })
+        .take(INVITE_SUGGESTION_COUNT)
+        .collect();
+
+    let (text, keyboard) = render_invite_suggestions(event_id, &event_info.summary, &suggestions);
+    bot.send_message(chat_id, text)
+        .parse_mode(ParseMode::Html)
+        .reply_markup(keyboard)
+        .await?;
+
+    Ok(())
+}
+
+/// Render one invite button per suggested attendee
+fn render_invite_suggestions(
+    event_id: Uuid,
+    summary: &str,
+    suggestions: &[SuggestedAttendee],
+) -> (String, InlineKeyboardMarkup) {
+    let mut text = format!("📨 <b>Invite to:</b> {}\n\n", inline(summary));
+    if suggestions.is_empty() {
+        text.push_str(&format!(
+            "No suggestions yet: people you invite will show up here.\n\
+             <code>/invite {event_id} @username</code>"
+        ));
+        return (text, InlineKeyboardMarkup::default());
+    }
+
+    text.push_str(&format!(
+        "Tap someone you invited before, or send\n<code>/invite {event_id} @username</code>"
+    ));
+    let rows = suggestions
+        .iter()
+        .map(|suggestion| {
+            vec![InlineKeyboardButton::callback(
+                format!("➕ {} ({})", suggestion.name, suggestion.invite_count),
+                format!(
+                    "inv:{}:{}",
+                    event_id.simple(),
+                    attendee_ref(&suggestion.email)
+                ),
+            )]
+        })
+        .collect::<Vec<_>>();
+
+    (text, InlineKeyboardMarkup::new(rows))
+}
+
+/// Handle suggestion buttons. Format: inv:<event_id>:<attendee_ref>
+async fn handle_invite_suggestion_callback(
+    bot: Bot,
+    callback_id: CallbackQueryId,
+    user_id: i64,
+    data: &str,
+    db: BotDb,
+) -> Result<()> {
+    let parts: Vec<&str> = data.split(':').collect();
+    let (event_id, reference) = match parts.as_slice() {
+        ["inv", event_id, reference] => match Uuid::parse_str(event_id) {
+            Ok(event_id) => (event_id, *reference),
+            Err(_) => {
+                bot.answer_callback_query(callback_id)
+                    .text("❌ Invalid event ID")
+                    .await?;
+                return Ok(());
+            }
+        },
+        _ => {
+            bot.answer_callback_query(callback_id)
+                .text("❌ Invalid data")
+                .await?;
+            return Ok(());
+        }
+    };
+
+    if db.get_event_info(event_id, user_id).await?.is_none() {
+        bot.answer_callback_query(callback_id)
+            .text("❌ Event not found")
+            .show_alert(true)
+            .await?;
+        return Ok(());
+    }
+
+    let Some(suggestion) = db
+        .suggest_attendees(user_id, MAX_CONTACT_SEARCH_LIMIT)
+        .await?
+        .into_iter()
+        .find(|suggestion| attendee_ref(&suggestion.email) == reference)
+    else {
+        bot.answer_callback_query(callback_id)
+            .text("❌ Suggestion expired, use /invite with a name instead")
+            .await?;
+        return Ok(());
+    };
+
+    if suggestion.telegram_id.is_none()
+        && !db
+            .is_feature_enabled(EXTERNAL_INVITES_FLAG, user_id)
+            .await?
+    {
+        bot.answer_callback_query(callback_id)
+            .text("🚫 Inviting by email isn't available for your account yet")
+            .await?;
+        return Ok(());
+    }
+
+    let invitees = [(suggestion.email, suggestion.telegram_id)];
+    let reply = match db.invite_attendees(event_id, &invitees).await {
+        Ok(outcome) if outcome.invited.is_empty() => {
+            format!("⚠️ {} is already invited", suggestion.name)
+        }
+        Ok(_) => format!("✅ Invited {}", suggestion.name),
+        Err(ApplicationError::BadRequest(reason)) => format!("❌ {reason}"),
+        Err(e) => {
+            tracing::error!("Failed to invite suggested attendee: {}", e);
+            "❌ Failed to send invites. Please try again later.".to_string()
+        }
+    };
+
+    bot.answer_callback_query(callback_id).text(reply).await?;
+    Ok(())
+}
+
+/// Handle /attendees command - RSVP dashboard for an event the user organizes
+pub async fn handle_attendees(bot: Bot, msg: Message, db: BotDb) -> Result<()> {
+    let user = msg
+        .from
+        .as_ref()
+        .ok_or_else(|| anyhow::anyhow!("No user in message"))?;
+    let telegram_id = user.id.0 as i64;
+
+    // Parse command arguments: /attendees <event_id>
+    let input = args::after_command(msg.text().unwrap_or(""));
+    if input.is_empty() {
+        bot.send_message(
+            msg.chat.id,
+            "👥 <b>Event Attendees</b>\n\n\
+             <b>Usage:</b>\n\
+             /attendees &lt;event_id&gt;\n\n\
+             Shows who replied to your invites and lets you remind or remove people.",
+        )
+        .parse_mode(ParseMode::Html)
+        .await?;
+        return Ok(());
+    }
+
+    let Some(args) = command_args(&bot, msg.chat.id, &commands::ATTENDEES, input).await? else {
+        return Ok(());
+    };
+    let Some(event_id) = args.uuid("event_id") else {
+        return Ok(());
+    };
+
+    let Some(event_info) = db.get_event_info(event_id, telegram_id).await? else {
         bot.send_message(
             msg.chat.id,
-            "❌ Usage: /rsvp &lt;event_id&gt; &lt;accept|decline|tentative&gt;",
+            "❌ Event not found or you are not its organizer",
+        )
+        .await?;
+        return Ok(());
+    };
+
+    let attendees = db.get_event_attendees(event_id).await?;
+    let (text, keyboard) = render_attendee_dashboard(event_id, &event_info.summary, &attendees);
+
+    bot.send_message(msg.chat.id, text)
+        .parse_mode(ParseMode::Html)
+        .reply_markup(keyboard)
+        .await?;
+
+    Ok(())
+}
+
+/// Short reference to an attendee that fits in Telegram's 64-byte callback data
+fn attendee_ref(email: &str) -> String {
+    let digest = Sha256::digest(email.to_lowercase().as_bytes());
+    hex::encode(&digest[..8])
+}
+
+fn partstat_emoji(status: &str) -> &'static str {
+    match status {
+        "ACCEPTED" => "✅",
+        "DECLINED" => "❌",
+        "TENTATIVE" => "🤔",
+        _ => "⏳",
+    }
+}
+
+fn attendee_label(attendee: &AttendeeInfo) -> String {
+    attendee
+        .telegram_username
+        .as_ref()
+        .map_or_else(|| attendee.email.clone(), |username| format!("@{username}"))
+}
+
+/// Render the attendee list, RSVP counts and reminder/removal buttons
+fn render_attendee_dashboard(
+    event_id: Uuid,
+    summary: &str,
+    attendees: &[AttendeeInfo],
+) -> (String, InlineKeyboardMarkup) {
+    let invitees: Vec<&AttendeeInfo> = attendees
+        .iter()
+        .filter(|attendee| attendee.role != "ORGANIZER")
+        .collect();
+
+    let mut text = format!("👥 <b>Attendees:</b> {}\n", inline(summary));
+
+    if invitees.is_empty() {
+        text.push_str(&format!(
+            "\nNobody has been invited yet.\n<code>/invite {event_id} @username</code>"
+        ));
+        return (text, InlineKeyboardMarkup::default());
+    }
+
+    let count = |status: &str| {
+        invitees
+            .iter()
+            .filter(|attendee| attendee.status == status)
+            .count()
+    };
+    let counts: Vec<String> = [
+        ("ACCEPTED", "accepted"),
+        ("TENTATIVE", "tentative"),
+        ("DECLINED", "declined"),
+        ("NEEDS-ACTION", "pending"),
+    ]
+    .into_iter()
+    .filter_map(|(status, label)| match count(status) {
+        0 => None,
+        n => Some(format!("{} {n} {label}", partstat_emoji(status))),
+    })
+    .collect();
+    text.push_str(&counts.join(" · "));
+    text.push('\n');
+
+    let mut rows = Vec::with_capacity(invitees.len());
+    for attendee in &invitees {
+        let label = attendee_label(attendee);
+        text.push_str(&format!(
+            "\n{} {}",
+            partstat_emoji(&attendee.status),
+            inline(&label)
+        ));
+
+        let reference = attendee_ref(&attendee.email);
+        let mut row = Vec::with_capacity(2);
+        if attendee.status == "NEEDS-ACTION" && attendee.telegram_id.is_some() {
+            row.push(InlineKeyboardButton::callback(
+                format!("🔁 Remind {label}"),
+                format!("att:remind:{}:{reference}", event_id.simple()),
+            ));
+        }
+        row.push(InlineKeyboardButton::callback(
+            format!("🗑 Remove {label}"),
+            format!("att:remove:{}:{reference}", event_id.simple()),
+        ));
+        rows.push(row);
+    }
+
+    (text, InlineKeyboardMarkup::new(rows))
+}
+
+/// Handle dashboard buttons. Format: att:<remind|remove>:<event_id>:<attendee_ref>
+async fn handle_attendee_callback(
+    bot: Bot,
+    callback_id: CallbackQueryId,
+    user_id: i64,
+    message: Option<MaybeInaccessibleMessage>,
+    data: &str,
+    db: BotDb,
+) -> Result<()> {
+    let parts: Vec<&str> = data.split(':').collect();
+    let (action, event_id, reference) = match parts.as_slice() {
+        ["att", action @ ("remind" | "remove"), event_id, reference] => {
+            match Uuid::parse_str(event_id) {
+                Ok(event_id) => (*action, event_id, *reference),
+                Err(_) => {
+                    bot.answer_callback_query(callback_id)
+                        .text("❌ Invalid event ID")
+                        .await?;
+                    return Ok(());
+                }
+            }
+        }
+        _ => {
+            bot.answer_callback_query(callback_id)
+                .text("❌ Invalid data")
+                .await?;
+            return Ok(());
+        }
+    };
+
+    let Some(event_info) = db.get_event_info(event_id, user_id).await? else {
+        bot.answer_callback_query(callback_id)
+            .text("❌ Event not found")
+            .show_alert(true)
+            .await?;
+        return Ok(());
+    };
+
+    let attendees = db.get_event_attendees(event_id).await?;
+    let Some(attendee) = attendees
+        .iter()
+        .find(|attendee| attendee_ref(&attendee.email) == reference)
+    else {
+        bot.answer_callback_query(callback_id)
+            .text("This person is no longer on the guest list")
+            .await?;
+        return Ok(());
+    };
+
+    let (result, done) = if action == "remind" {
+        (
+            db.resend_invite(user_id, event_id, &attendee.email).await,
+            format!("🔁 Reminder sent to {}", attendee_label(attendee)),
+        )
+    } else {
+        (
+            db.remove_attendee(user_id, event_id, &attendee.email).await,
+            format!("🗑 Removed {}", attendee_label(attendee)),
+        )
+    };
+
+    match result {
+        Ok(()) => {
+            bot.answer_callback_query(callback_id).text(done).await?;
+        }
+        Err(ApplicationError::BadRequest(reason)) => {
+            bot.answer_callback_query(callback_id)
+                .text(format!("❌ {reason}"))
+                .show_alert(true)
+                .await?;
+            return Ok(());
+        }
+        Err(e) => {
+            tracing::error!("Failed to {} attendee: {}", action, e);
+            bot.answer_callback_query(callback_id)
+                .text("❌ Failed to update attendees. Please try again.")
+                .show_alert(true)
+                .await?;
+            return Ok(());
+        }
+    }
+
+    // Refresh the dashboard in place
+    if let Some(msg) = message {
+        let attendees = db.get_event_attendees(event_id).await?;
+        let (text, keyboard) = render_attendee_dashboard(event_id, &event_info.summary, &attendees);
+        bot.edit_message_text(msg.chat().id, msg.id(), text)
+            .parse_mode(ParseMode::Html)
+            .reply_markup(keyboard)
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Handle the /rsvp command
+pub async fn handle_rsvp(bot: Bot, msg: Message, db: BotDb) -> Result<()> {
+    let user = msg
+        .from
+        .as_ref()
+        .ok_or_else(|| anyhow::anyhow!("No user in message"))?;
+    let telegram_id = user.id.0 as i64;
+    let username = user
+        .username
+        .clone()
+        .unwrap_or_else(|| format!("User_{}", telegram_id));
+
+    // Parse command arguments: /rsvp [<event_id> <status>]
+    let input = args::after_command(msg.text().unwrap_or(""));
+
+    // If no arguments, list pending invites
+    if input.is_empty() {
+        let pending = db.get_pending_invites(telegram_id).await?;
+
+        if pending.is_empty() {
+            bot.send_message(msg.chat.id, "📭 No pending invitations")
+                .await?;
+            return Ok(());
+        }
+
+        let mut response = format!("📨 <b>Pending Invitations</b> ({})\n\n", pending.len());
+        for invite in pending.iter().take(10) {
+            let organizer = invite
+                .organizer_username
+                .as_ref()
+                .map(|u| format!("@{}", escape(u)))
+                .unwrap_or_else(|| "Unknown".to_string());
+
+            let location_text = invite
+                .location
+                .as_ref()
+                .map(|loc| format!("\n📍 {}", inline(loc)))
+                .unwrap_or_default();
+
+            let start = invite.start.unwrap_or_else(|| {
+                invite
+                    .start_date
+                    .and_then(|date| date.and_hms_opt(0, 0, 0))
+                    .map(|date_time| date_time.and_utc())
+                    .unwrap_or_else(Utc::now)
+            });
+            let time_str = if invite.is_all_day {
+                "All Day".to_string()
+            } else {
+                start.format("%H:%M UTC").to_string()
+            };
+
+            response.push_str(&format!(
+                "🔹 <b>{}</b>\n   🕒 {} {}\n   👤 From: {}{}\n   <code>/rsvp {} accept</code>\n\n",
+                inline(&invite.summary),
+                start.format("%a %b %d"),
+                time_str,
+                organizer,
+                location_text,
+                invite.event_id
+            ));
+        }
+
+        response.push_str("\n<b>To respond:</b>\n");
+        response.push_str("/rsvp &lt;event_id&gt; accept\n");
+        response.push_str("/rsvp &lt;event_id&gt; decline\n");
+        response.push_str("/rsvp &lt;event_id&gt; tentative");
+
+        bot.send_message(msg.chat.id, response)
+            .parse_mode(ParseMode::Html)
+            .await?;
+
+        return Ok(());
+    }
+
+    // Parse RSVP response: /rsvp <event_id> <status>
+    let Some(args) = command_args(&bot, msg.chat.id, &commands::RSVP, input).await? else {
+        return Ok(());
+    };
+    let (Some(event_id), Some(status_str)) =
+        (args.uuid("event_id"), args.get("accept|decline|tentative"))
+    else {
+        return Ok(());
+    };
+
+    // Map user input to participation status; RSVP_STATUSES lists these
+    let status = match status_str.to_lowercase().as_str() {
+        "accept" | "accepted" | "yes" => "ACCEPTED",
+        "decline" | "declined" | "no" => "DECLINED",
+        _ => "TENTATIVE",
+    };
+
+    match db
+        .confirm_rsvp_named(event_id, telegram_id, status, username.clone())
+        .await
+    {
+        Ok(()) => {
+            let emoji = match status {
+                "ACCEPTED" => "✅",
+                "DECLINED" => "❌",
+                "TENTATIVE" => "🤔",
+                _ => "✉️",
+            };
+
+            bot.send_message(
+                msg.chat.id,
+                format!(
+                    "{} Your response has been recorded: <b>{}</b>",
+                    emoji, status
+                ),
+            )
+            .parse_mode(ParseMode::Html)
+            .await?;
+
+            tracing::info!(
+                "User {} responded {} to event {}",
+                telegram_id,
+                status,
+                event_id
+            );
+        }
+        Err(televent_application::ApplicationError::NotFound(_)) => {
+            bot.send_message(msg.chat.id, "❌ Invitation not found")
+                .await?;
+        }
+        Err(e) => {
+            tracing::error!("Failed to update RSVP: {}", e);
+            bot.send_message(
+                msg.chat.id,
+                "❌ Failed to update your response. Please try again later.",
+            )
+            .await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Handle the /slot command
+pub async fn handle_slot(bot: Bot, msg: Message, db: BotDb) -> Result<()> {
+    let user = msg
+        .from
+        .as_ref()
+        .ok_or_else(|| anyhow::anyhow!("No user in message"))?;
+    let telegram_id = user.id.0 as i64;
+
+    // Parse command arguments: /slot <duration> [<within>] [<HH:MM-HH:MM>]
+    let input = args::after_command(msg.text().unwrap_or(""));
+    let Some(args) = command_args(&bot, msg.chat.id, &commands::SLOT, input).await? else {
+        return Ok(());
+    };
+    let parts: Vec<&str> = ["duration", "within", "working_hours"]
+        .into_iter()
+        .filter_map(|name| args.get(name))
+        .collect();
+    let timezone = db.get_timezone(telegram_id).await?;
+    let searched = match parse_slot_args(&parts, &timezone) {
+        Ok(search) => {
+            let duration = search.duration;
+            match db.find_free_slots(telegram_id, search).await {
+                Ok((slots, timezone)) => Ok((duration, slots, timezone)),
+                // Saved working hours may be too short for the slot
+                Err(ApplicationError::BadRequest(message)) => Err(message),
+                Err(err) => return Err(err.into()),
+            }
+        }
+        Err(err) => Err(err),
+    };
+    let (duration, slots, timezone) = match searched {
+        Ok(found) => found,
+        Err(err) => {
+            let response = format!(
+                "❌ {}\n\n\
+                 Usage: /slot &lt;duration&gt; [within] [working hours]\n\
+                 Examples:\n\
+                 /slot 30m\n\
+                 /slot 1h 3d\n\
+                 /slot 45m 7d 09:00-17:00",
+                escape(&err)
+            );
+            bot.send_message(msg.chat.id, response)
+                .parse_mode(ParseMode::Html)
+                .await?;
+            return Ok(());
+        }
+    };
+
+    let response = render_free_slots(duration, &slots, &timezone);
+    bot.send_message(msg.chat.id, response)
+        .parse_mode(ParseMode::Html)
+        .await?;
+
+    tracing::info!(
+        "User {} searched free slots: {} found",
+        telegram_id,
+        slots.len()
+    );
+
+    Ok(())
+}
+
+/// Turn `/slot` arguments into a search starting now
+///
+/// The first argument is the slot length; an optional `HH:MM-HH:MM` token
+/// sets working hours in `timezone` and any other token the search window.
+fn parse_slot_args(args: &[&str], timezone: &Timezone) -> std::result::Result<SlotSearch, String> {
+    let (duration, rest) = args
+        .split_first()
+        .ok_or_else(|| "Tell me how long the slot should be".to_string())?;
+    let mut search = SlotSearch {
+        from: Utc::now(),
+        duration: parse_duration_spec(duration).map_err(|err| err.to_string())?,
+        within: Duration::days(DEFAULT_SLOT_SEARCH_DAYS),
+        working_hours: None,
+        count: DEFAULT_SLOT_COUNT,
+    };
+    for arg in rest {
+        if arg.contains(':') {
+            search.working_hours =
+                Some(WorkingHours::parse(arg, timezone).map_err(|err| err.to_string())?);
+        } else {
+            search.within = parse_duration_spec(arg).map_err(|err| err.to_string())?;
+        }
+    }
+
+    search.validated().map_err(|err| err.to_string())
+}
+
+/// Render free slots in the user's timezone
+fn render_free_slots(duration: Duration, slots: &[FreeSlot], timezone: &Timezone) -> String {
+    let tz = timezone.tz();
+    let length = duration_label(duration);
+
+    if slots.is_empty() {
+        return format!(
+            "😕 No free {length} slot found. Try a longer window, e.g. /slot {length} 14d"
+        );
+    }
+
+    let mut response = format!("🕐 <b>Free {length} slots</b> ({})\n\n", timezone.as_str());
+    for (idx, slot) in slots.iter().enumerate() {
+        let start = slot.start.with_timezone(&tz);
+        let free_until = slot.free_until.with_timezone(&tz);
+        let until = if free_until.date_naive() == start.date_naive() {
+            free_until.format("%H:%M").to_string()
+        } else {
+            free_until.format("%a %H:%M").to_string()
+        };
+        response.push_str(&format!(
+            "{}. {} {}–{}\n   free until {}\n",
+            idx + 1,
+            start.format("%a, %b %d"),
+            start.format("%H:%M"),
+            slot.end.with_timezone(&tz).format("%H:%M"),
+            until
+        ));
+    }
+    response
+}
+
+/// A length the way /slot and /task take it, e.g. `30m`, `2h` or `1h30m`
+fn duration_label(duration: Duration) -> String {
+    let minutes = duration.num_minutes();
+    if minutes % 60 == 0 {
+        format!("{}h", minutes / 60)
+    } else if minutes > 60 {
+        format!("{}h{}m", minutes / 60, minutes % 60)
+    } else {
+        format!("{minutes}m")
+    }
+}
+
+/// Handle the /task command
+pub async fn handle_task(bot: Bot, msg: Message, db: BotDb) -> Result<()> {
+    let user = msg
+        .from
+        .as_ref()
+        .ok_or_else(|| anyhow::anyhow!("No user in message"))?;
+    let telegram_id = user.id.0 as i64;
+
+    // /task [list] | /task <duration> <title> | /task done|remove <n>
+    let input = args::after_command(msg.text().unwrap_or(""));
+    let (subcommand, rest) = args::subcommand(input);
+    let response = match subcommand {
+        None | Some("list") => {
+            let (tasks, timezone) = db.list_tasks(telegram_id).await?;
+            render_tasks(&tasks, &timezone)
+        }
+        Some(action @ ("done" | "remove")) => {
+            let spec = if action == "done" {
+                &commands::TASK_DONE
+            } else {
+                &commands::TASK_REMOVE
+            };
+            let Some(args) = command_args(&bot, msg.chat.id, spec, rest).await? else {
+                return Ok(());
+            };
+            let (tasks, _) = db.list_tasks(telegram_id).await?;
+            let Some(task) = args
+                .get("n")
+                .and_then(|n| n.parse::<usize>().ok())
+                .and_then(|n| n.checked_sub(1))
+                .and_then(|idx| tasks.get(idx))
+            else {
+                bot.send_message(
+                    msg.chat.id,
+                    "❌ No such task. Send /task to see the numbers.",
+                )
+                .await?;
+                return Ok(());
+            };
+            let changed = if action == "done" {
+                db.complete_task(telegram_id, task.id).await?
+            } else {
+                db.delete_task(telegram_id, task.id).await?
+            };
+            match (changed, action) {
+                (false, _) => "❌ That task is already gone.".to_string(),
+                (true, "done") => format!("✅ Done: {}", escape(&task.title)),
+                (true, _) => format!("🗑 Removed: {}", escape(&task.title)),
+            }
+        }
+        Some(_) => {
+            let Some(args) = command_args(&bot, msg.chat.id, &commands::TASK_ADD, input).await?
+            else {
+                return Ok(());
+            };
+            let duration = match parse_duration_spec(args.get("duration").unwrap_or_default()) {
+                Ok(duration) => duration,
+                Err(err) => {
+                    bot.send_message(
+                        msg.chat.id,
+                        format!(
+                            "❌ {}\n\nUsage: <code>{}</code>",
+                            escape(&err.to_string()),
+                            escape(&commands::TASK_ADD.usage())
+                        ),
+                    )
+                    .parse_mode(ParseMode::Html)
+                    .await?;
+                    return Ok(());
+                }
+            };
+            let title = args.get("title").unwrap_or_default();
+            match db.create_task(telegram_id, title, duration).await {
+                Ok(task) => {
+                    tracing::info!("User {} added a task", telegram_id);
+                    format!(
+                        "📝 Added <b>{}</b> ({}). Send /plan to find it a slot today.",
+                        escape(&task.title),
+                        duration_label(task.duration)
+                    )
+                }
+                Err(ApplicationError::BadRequest(message)) => format!("❌ {}", escape(&message)),
+                Err(err) => return Err(err.into()),
+            }
+        }
+    };
+    bot.send_message(msg.chat.id, response)
+        .parse_mode(ParseMode::Html)
+        .await?;
+
+    Ok(())
+}
+
+/// Render open tasks, numbered for `/task done <n>`, with their blocks
+fn render_tasks(tasks: &[TaskView], timezone: &Timezone) -> String {
+    let usage = "<code>/task 45m Write report</code> - Add a task\n\
+                 <code>/task done 1</code> - Tick one off; <code>/task remove 1</code> drops it\n\
+                 /plan - Fit unplanned tasks into today's free time";
+    if tasks.is_empty() {
+        return format!("📝 <b>Tasks</b>\n\nNothing on your list.\n\n{usage}");
+    }
+
+    let tz = timezone.tz();
+    let mut response = "📝 <b>Tasks</b>\n\n".to_string();
+    for (idx, task) in tasks.iter().enumerate() {
+        response.push_str(&format!(
+            "{}. {} · {}\n",
+            idx + 1,
+            escape(&task.title),
+            duration_label(task.duration)
+        ));
+        if let Some(block) = task.block {
+            response.push_str(&format!(
+                "   {} {} {}–{}\n",
+                if block.confirmed { "📌" } else { "🕓" },
+                block.start.with_timezone(&tz).format("%a %d %b"),
+                block.start.with_timezone(&tz).format("%H:%M"),
+                block.end.with_timezone(&tz).format("%H:%M"),
+            ));
+        }
+    }
+    response.push_str(&format!("\n📌 confirmed · 🕓 tentative\n\n{usage}"));
+    response
+}
+
+/// Handle the /plan command
+pub async fn handle_plan(bot: Bot, msg: Message, db: BotDb) -> Result<()> {
+    let user = msg
+        .from
+        .as_ref()
+        .ok_or_else(|| anyhow::anyhow!("No user in message"))?;
+    let telegram_id = user.id.0 as i64;
+
+    // /plan [today|tomorrow] [HH:MM-HH:MM]
+    let input = args::after_command(msg.text().unwrap_or(""));
+    let Some(args) = command_args(&bot, msg.chat.id, &commands::PLAN, input).await? else {
+        return Ok(());
+    };
+    let parts: Vec<&str> = ["day", "working_hours"]
+        .into_iter()
+        .filter_map(|name| args.get(name))
+        .collect();
+    let timezone = db.get_timezone(telegram_id).await?;
+    let (days_ahead, working_hours) = match parse_plan_args(&parts, &timezone) {
+        Ok(parsed) => parsed,
+        Err(err) => {
+            bot.send_message(
+                msg.chat.id,
+                format!(
+                    "❌ {}\n\nUsage: <code>{}</code>",
+                    escape(&err),
+                    escape(&commands::PLAN.usage())
+                ),
+            )
+            .parse_mode(ParseMode::Html)
+            .await?;
+            return Ok(());
+        }
+    };
+
+    let Some((plan, timezone)) = db
+        .plan_tasks(telegram_id, days_ahead, working_hours)
+        .await?
+    else {
+        bot.send_message(msg.chat.id, "🌙 Today is over. Try /plan tomorrow")
+            .await?;
+        return Ok(());
+    };
+    let (response, keyboard) = render_task_plan(&plan, &timezone);
+    let mut request = bot
+        .send_message(msg.chat.id, response)
+        .parse_mode(ParseMode::Html);
+    if let Some(keyboard) = keyboard {
+        request = request.reply_markup(keyboard);
+    }
+    request.await?;
+
+    tracing::info!(
+        "User {} planned {} task blocks, {} left over",
+        telegram_id,
+        plan.blocks.len(),
+        plan.unplanned.len()
+    );
+
+    Ok(())
+}
+
+/// Turn `/plan` arguments into the day (0 for today) and working hours
+///
+/// An `HH:MM-HH:MM` token sets working hours in `timezone`; any other names
+/// the day.
+fn parse_plan_args(
+    args: &[&str],
+    timezone: &Timezone,
+) -> std::result::Result<(i64, Option<WorkingHours>), String> {
+    let mut days_ahead = 0;
+    let mut working_hours = None;
+    for arg in args {
+        if arg.contains(':') {
+            working_hours =
+                Some(WorkingHours::parse(arg, timezone).map_err(|err| err.to_string())?);
+        } else if arg.eq_ignore_ascii_case("tomorrow") {
+            days_ahead = 1;
+        } else if !arg.eq_ignore_ascii_case("today") {
+            return Err(format!("{arg} is not today, tomorrow or working hours"));
+        }
+    }
+    Ok((days_ahead, working_hours))
+}
+
+/// Render a plan, with buttons to confirm or discard its blocks if it made any
+fn render_task_plan(
+    plan: &TaskPlan,
+    timezone: &Timezone,
+) -> (String, Option<InlineKeyboardMarkup>) {
+    let tz = timezone.tz();
+    let mut response = if plan.blocks.is_empty() {
+        "🗓 <b>Nothing new to plan</b>\n".to_string()
+    } else {
+        let mut response = format!("🗓 <b>Planned</b> ({})\n\n", timezone.as_str());
+        for block in &plan.blocks {
+            response.push_str(&format!(
+                "🕓 {} {}–{} {}\n",
+                block.start.with_timezone(&tz).format("%a"),
+                block.start.with_timezone(&tz).format("%H:%M"),
+                block.end.with_timezone(&tz).format("%H:%M"),
+                escape(&block.title)
+            ));
+        }
+        response.push_str(
+            "\nThe blocks are on your calendar as tentative. \
+             Confirm them and I'll ask at the end of each whether you're done.\n",
+        );
+        response
+    };
+    if !plan.unplanned.is_empty() {
+        response.push_str("\n<b>No room left for:</b>\n");
+        for task in &plan.unplanned {
+            response.push_str(&format!(
+                "• {} · {}\n",
+                escape(&task.title),
+                duration_label(task.duration)
+            ));
+        }
+    }
+    if plan.blocks.is_empty() && plan.unplanned.is_empty() {
+        response.push_str(
+            "\nEvery open task already has a block. Add one with \
+             <code>/task 45m Write report</code>",
+        );
+    }
+
+    let keyboard = (!plan.blocks.is_empty()).then(|| {
+        InlineKeyboardMarkup::new(vec![vec![
+            InlineKeyboardButton::callback("✅ Confirm", "plan:confirm"),
+            InlineKeyboardButton::callback("🗑 Discard", "plan:discard"),
+        ]])
+    });
+    (response, keyboard)
+}
+
+/// Handle the buttons of a plan and of a block's end-of-block reminder
+async fn handle_task_callback(
+    bot: Bot,
+    callback_id: CallbackQueryId,
+    user_id: i64,
+    message: Option<MaybeInaccessibleMessage>,
+    data: &str,
+    db: BotDb,
+) -> Result<()> {
+    let parts: Vec<&str> = data.split(':').collect();
+    let outcome = match parts.as_slice() {
+        ["plan", "confirm"] => {
+            let confirmed = db.confirm_planned_blocks(user_id).await?;
+            tracing::info!("User {} confirmed {} task blocks", user_id, confirmed);
+            format!("📌 Confirmed {confirmed} block(s)")
+        }
+        ["plan", "discard"] => {
+            let discarded = db.discard_planned_blocks(user_id).await?;
+            tracing::info!("User {} discarded {} task blocks", user_id, discarded);
+            format!("🗑 Removed {discarded} block(s)")
+        }
+        ["task", action @ ("done" | "later"), task_id] => {
+            let Ok(task_id) = Uuid::parse_str(task_id) else {
+                bot.answer_callback_query(callback_id)
+                    .text("❌ Invalid data")
+                    .await?;
+                return Ok(());
+            };
+            let changed = if *action == "done" {
+                db.complete_task(user_id, task_id).await?
+            } else {
+                db.unschedule_task(user_id, task_id).await?
+            };
+            match (changed, *action) {
+                (false, _) => "This task is already done or gone".to_string(),
+                (true, "done") => "✅ Nice work!".to_string(),
+                (true, _) => "↩️ Back on your list for the next /plan".to_string(),
+            }
+        }
+        _ => {
+            bot.answer_callback_query(callback_id)
+                .text("❌ Invalid data")
+                .await?;
+            return Ok(());
+        }
+    };
+
+    bot.answer_callback_query(callback_id)
+        .text(outcome.clone())
+        .await?;
+    // The buttons did their job; leave the outcome in their place
+    if let Some(MaybeInaccessibleMessage::Regular(message)) = message {
+        let text = format!("{}\n\n{}", message.text().unwrap_or_default(), outcome);
+        bot.edit_message_text(message.chat.id, message.id, text)
+            .await?;
+    }
+    Ok(())
+}
+
+/// Handle the /focus command
+pub async fn handle_focus(bot: Bot, msg: Message, db: BotDb) -> Result<()> {
+    let user = msg
+        .from
+        .as_ref()
+        .ok_or_else(|| anyhow::anyhow!("No user in message"))?;
+    let telegram_id = user.id.0 as i64;
+
+    // /focus | /focus add <n>x<length> [HH:MM-HH:MM] [title...] | /focus remove <n>
+    let input = args::after_command(msg.text().unwrap_or(""));
+    let (subcommand, rest) = args::subcommand(input);
+    let response = match subcommand {
+        None | Some("list") => {
+            let (rules, timezone) = db.list_focus_rules(telegram_id).await?;
+            render_focus_rules(&rules, &timezone)
+        }
+        Some("add") => {
+            let Some(args) = command_args(&bot, msg.chat.id, &commands::FOCUS_ADD, rest).await?
+            else {
+                return Ok(());
+            };
+            let timezone = db.get_timezone(telegram_id).await?;
+            let added = match parse_focus_rule(
+                telegram_id,
+                args.get("blocks").unwrap_or_default(),
+                args.get("title"),
+                &timezone,
+            ) {
+                Ok(command) => db.add_focus_rule(command).await,
+                Err(err) => Err(err),
+            };
+            match added {
+                Ok((rule, schedule)) => {
+                    tracing::info!("User {} added a focus rule", telegram_id);
+                    let mut response = format!(
+                        "🎯 Reserving {}× {} of <b>{}</b> a week",
+                        rule.blocks_per_week,
+                        duration_label(rule.duration),
+                        escape(&rule.title)
+                    );
+                    if let Some(hours) = &rule.working_hours {
+                        response.push_str(&format!(" within {hours}"));
+                    }
+                    response.push_str(&format!(
+                        ".\n\n{} tentative block(s) added this week.",
+                        schedule.placed
+                    ));
+                    if schedule.missing > 0 {
+                        response.push_str(&format!(
+                            " No room left for {} more; I'll keep looking as your week changes.",
+                            schedule.missing
+                        ));
+                    }
+                    response.push_str(" Send /focus to see them.");
+                    response
+                }
+                Err(ApplicationError::BadRequest(message)) => format!(
+                    "❌ {}\n\nUsage: <code>/focus add 2x2h [09:00-17:00] [title]</code>",
+                    escape(&message)
+                ),
+                Err(err) => return Err(err.into()),
+            }
+        }
+        Some("remove") => {
+            let Some(args) = command_args(&bot, msg.chat.id, &commands::FOCUS_REMOVE, rest).await?
+            else {
+                return Ok(());
+            };
+            let (rules, _) = db.list_focus_rules(telegram_id).await?;
+            let Some(rule) = args
+                .get("n")
+                .and_then(|n| n.parse::<usize>().ok())
+                .and_then(|n| n.checked_sub(1))
+                .and_then(|idx| rules.get(idx))
+            else {
+                bot.send_message(
+                    msg.chat.id,
+                    "❌ No such rule. Send /focus to see the numbers.",
+                )
+                .await?;
+                return Ok(());
+            };
+            if db.remove_focus_rule(telegram_id, rule.id).await? {
+                format!(
+                    "🗑 Stopped reserving <b>{}</b>. Its tentative blocks are gone; \
+                     confirmed ones stay.",
+                    escape(&rule.title)
+                )
+            } else {
+                "❌ That rule is already gone.".to_string()
+            }
+        }
+        Some(other) => format!(
+            "❌ Unknown option {}. Use <code>/focus add 2x2h</code> or \
+             <code>/focus remove 1</code>",
+            inline(other)
+        ),
+    };
+    bot.send_message(msg.chat.id, response)
+        .parse_mode(ParseMode::Html)
+        .await?;
+
+    Ok(())
+}
+
+/// Turn `/focus add` arguments into a rule; a leading `HH:MM-HH:MM` in the
+/// title sets working hours in `timezone`
+fn parse_focus_rule(
+    telegram_id: i64,
+    blocks: &str,
+    title: Option<&str>,
+    timezone: &Timezone,
+) -> std::result::Result<FocusRuleCommand, ApplicationError> {
+    let (blocks_per_week, duration) = parse_focus_pattern(blocks)?;
+    let mut title = title.unwrap_or_default().trim();
+    let mut working_hours = None;
+    let (first, rest) = args::subcommand(title);
+    if let Some(first) = first
+        && first.contains(':')
+    {
+        working_hours = Some(WorkingHours::parse(first, timezone)?);
+        title = rest;
+    }
+    Ok(FocusRuleCommand {
+        user_id: UserId::new(telegram_id),
+        title: (!title.is_empty()).then(|| title.to_string()),
+        blocks_per_week,
+        duration,
+        working_hours,
+    })
+}
+
+/// Render focus rules, numbered for `/focus remove <n>`, with this week's blocks
+fn render_focus_rules(rules: &[FocusRuleView], timezone: &Timezone) -> String {
+    let usage = "<code>/focus add 2x2h</code> - Two 2-hour blocks a week\n\
+                 <code>/focus add 3x90m 09:00-17:00 Writing</code> - Within working hours\n\
+                 <code>/focus remove 1</code> - Stop reserving one";
+    if rules.is_empty() {
+        return format!(
+            "🎯 <b>Focus time</b>\n\nNo focus time reserved. I can keep blocks free in \
+             your week and move them when meetings land on them.\n\n{usage}"
+        );
+    }
+
+    let tz = timezone.tz();
+    let mut response = format!("🎯 <b>Focus time</b> ({})\n\n", timezone.as_str());
+    for (idx, rule) in rules.iter().enumerate() {
+        response.push_str(&format!(
+            "{}. <b>{}</b> · {}× {} a week",
+            idx + 1,
+            escape(&rule.title),
+            rule.blocks_per_week,
+            duration_label(rule.duration)
+        ));
+        if let Some(hours) = &rule.working_hours {
+            response.push_str(&format!(" · {hours}"));
+        }
+        response.push('\n');
+        for block in &rule.blocks {
+            let marker = match block.status {
+                EventStatus::Confirmed => "📌",
+                EventStatus::Tentative => "🕓",
+                EventStatus::Cancelled => "✖️",
+            };
+            response.push_str(&format!(
+                "   {} {} {}–{}\n",
+                marker,
+                block.start.with_timezone(&tz).format("%a %d %b"),
+                block.start.with_timezone(&tz).format("%H:%M"),
+                block.end.with_timezone(&tz).format("%H:%M"),
+            ));
+        }
+    }
+    response.push_str(&format!(
+        "\n🕓 tentative blocks move when something else takes their time; \
+         confirm one in your calendar to keep it (📌).\n\n{usage}"
+    ));
+    response
+}
+
+/// Handle the /stats command
+pub async fn handle_stats(bot: Bot, msg: Message, db: BotDb) -> Result<()> {
+    let user = msg
+        .from
+        .as_ref()
+        .ok_or_else(|| anyhow::anyhow!("No user in message"))?;
+    let telegram_id = user.id.0 as i64;
+
+    // Parse command arguments: /stats [<window>]
+    let input = args::after_command(msg.text().unwrap_or(""));
+    let Some(args) = command_args(&bot, msg.chat.id, &commands::STATS, input).await? else {
+        return Ok(());
+    };
+    let window = match args.get("window") {
+        Some(arg) => parse_duration_spec(arg),
+        None => Ok(Duration::days(DEFAULT_STATS_DAYS)),
+    };
+    let stats = match window {
+        Ok(window) => db.calendar_stats(telegram_id, window).await,
+        Err(err) => Err(err),
+    };
+    let response = match stats {
+        Ok((stats, timezone)) => render_stats(&stats, &timezone),
+        Err(ApplicationError::BadRequest(message)) => format!(
+            "❌ {}\n\nUsage: /stats [window], e.g. /stats 7d or /stats 90d",
+            escape(&message)
+        ),
+        Err(err) => return Err(err.into()),
+    };
+    bot.send_message(msg.chat.id, response)
+        .parse_mode(ParseMode::Html)
+        .await?;
+
+    tracing::info!("User {} viewed calendar stats", telegram_id);
+
+    Ok(())
+}
+
+/// Render a compact meeting load summary in the user's timezone
+fn render_stats(stats: &CalendarStats, timezone: &Timezone) -> String {
+    let tz = timezone.tz();
+    let mut response = format!(
+        "📊 <b>Your calendar</b> ({} – {})\n\n",
+        stats.from.with_timezone(&tz).format("%b %d"),
+        stats.to.with_timezone(&tz).format("%b %d")
+    );
+    if stats.event_count == 0 {
+        response.push_str("No meetings in this period.");
+        return response;
+    }
+
+    response.push_str(&format!(
+        "🗓 {} events, {:.1} h in total\n📈 {:.1} events per day\n",
+        stats.event_count, stats.total_hours, stats.average_daily_events
+    ));
+    if let Some(weekday) = stats.busiest_weekday {
+        response.push_str(&format!("🔥 Busiest day: {}\n", weekday_name(weekday)));
+    }
+    if !stats.top_collaborators.is_empty() {
+        response.push_str("\n<b>Top collaborators</b>\n");
+        for (idx, collaborator) in stats.top_collaborators.iter().enumerate() {
+            let name = match &collaborator.telegram_username {
+                Some(username) => format!("@{username}"),
+                None => collaborator.email.clone(),
+            };
+            response.push_str(&format!(
+                "{}. {} – {}\n",
+                idx + 1,
+                escape(&name),
+                collaborator.events
+            ));
+        }
+    }
+    response
+}
+
+/// Handle non-command text messages (event creation)
+///
+/// This handler processes multi-line text messages as potential event creation requests.
+/// Messages must have at least 2 lines (title and date/time) to be considered.
+pub async fn handle_text_message(bot: Bot, msg: Message, db: BotDb) -> Result<()> {
+    let text = match msg.text() {
+        Some(t) => t,
+        None => return Ok(()), // Not a text message
+    };
+
+    // Skip commands
+    if text.starts_with('/') {
+        return Ok(());
+    }
+
+    // Skip messages with fewer than 2 lines (not enough for event creation), but send help
+    let line_count = text.lines().count();
+    if line_count < 2 {
+        let help_text = "To create an event, please use the following format:\n\n\
+             [Exact Syntax]\n\
+             Team Meeting\n\
+             2026-01-25 14:00\n\
+             60\n\
+             Conference Room A\n\n\
+             [Natural Language]\n\
+             Coffee with Alice\n\
+             tomorrow at 3pm\n\
+             30\n\
+             Starbucks";
+
+        bot.send_message(msg.chat.id, help_text).await?;
+        return Ok(());
+    }
+
+    if is_read_only(&db).await {
+        bot.send_message(msg.chat.id, READ_ONLY_NOTICE).await?;
+        return Ok(());
+    }
+
+    let user = msg
+        .from
+        .as_ref()
+        .ok_or_else(|| anyhow::anyhow!("No user in message"))?;
+    let telegram_id = user.id.0 as i64;
+
+    create_event_from_text(&bot, &msg, telegram_id, text, false, &db).await
+}
+
+/// Parse and create an event from a multi-line message, replying with the
+/// event card; a near-duplicate gets a confirmation prompt instead unless
+/// `allow_duplicate`
+async fn create_event_from_text(
+    bot: &Bot,
+    msg: &Message,
+    telegram_id: i64,
+    text: &str,
+    allow_duplicate: bool,
+    db: &BotDb,
+) -> Result<()> {
+    let parsed_event = match parse_event_message(text) {
+        Ok(parsed_event) => parsed_event,
+        Err(parse_error) => {
+            db.analytics()
+                .record(Metric::event(EventFunnelStep::ParseFailed));
+
+            // Send helpful error message
+            let response = format!(
+                "❌ <b>Could not create event</b>\n\n{}\n\n{}",
+                escape(&parse_error.to_string()),
+                format_example()
+            );
+
+            bot.send_message(msg.chat.id, response)
+                .parse_mode(ParseMode::Html)
+                .await?;
+
+            tracing::info!(
+                "User {} sent invalid event format: {}",
+                telegram_id,
+                parse_error
+            );
+            return Ok(());
+        }
+    };
+
+    create_parsed_event(
+        bot,
+        msg,
+        telegram_id,
+        &parsed_event,
+        None,
+        allow_duplicate,
+        db,
+    )
+    .await
+}
+
+/// Create a parsed event, replying to `msg` with the event card; a
+/// near-duplicate gets a confirmation prompt instead unless `allow_duplicate`
+async fn create_parsed_event(
+    bot: &Bot,
+    msg: &Message,
+    telegram_id: i64,
+    parsed_event: &ParsedEvent,
+    description: Option<&str>,
+    allow_duplicate: bool,
+    db: &BotDb,
+) -> Result<()> {
+    // Generate unique UID for the event
+    let uid = format!("{}@televent.bot", uuid::Uuid::new_v4());
+
+    // Create event in database
+    match db
+        .create_event(
+            telegram_id,
+            &uid,
+            &parsed_event.title,
+            description,
+            parsed_event.location.as_deref(),
+            parsed_event.timing.clone(),
+            "UTC",
+            allow_duplicate,
         )
+        .await
+    {
+        Ok(event) => {
+            db.analytics()
+                .record(Metric::event(EventFunnelStep::Created));
+            // The event exists either way, so the warning is best effort
+            let hours = db.get_working_hours(telegram_id).await.unwrap_or_default();
+            let (text, keyboard) =
+                render_event_card("✅ <b>Event Created!</b>", &event, hours.as_ref());
+            bot.send_message(msg.chat.id, text)
+                .parse_mode(ParseMode::Html)
+                .reply_markup(keyboard)
+                .await?;
+
+            tracing::info!(
+                "User {} created event: {} at {}",
+                telegram_id,
+                event.summary,
+                event.display_start()
+            );
+        }
+        Err(ApplicationError::Conflict(reason)) => {
+            db.analytics()
+                .record(Metric::event(EventFunnelStep::DuplicatePrompted));
+            tracing::info!("Possible duplicate from user {}: {}", telegram_id, reason);
+            // The prompt replies to the message, so the button can find it
+            let keyboard = InlineKeyboardMarkup::new(vec![vec![
+                InlineKeyboardButton::callback("✅ Create anyway", "dupe:create"),
+                InlineKeyboardButton::callback("✖️ Skip", "dupe:skip"),
+            ]]);
+            bot.send_message(
+                msg.chat.id,
+                format!(
+                    "🤔 <b>{}</b> looks like a duplicate: you already have it at that time.\n\n\
+                     Create it anyway?",
+                    inline(&parsed_event.title)
+                ),
+            )
+            .parse_mode(ParseMode::Html)
+            .reply_parameters(ReplyParameters::new(msg.id))
+            .reply_markup(keyboard)
+            .await?;
+        }
+        Err(e) => {
+            db.analytics()
+                .record(Metric::event(EventFunnelStep::CreateFailed));
+            tracing::error!("Failed to create event for user {}: {}", telegram_id, e);
+
+            bot.send_message(
+                msg.chat.id,
+                "❌ Failed to create event. Please try again later.",
+            )
+            .await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Answer to the near-duplicate prompt: `dupe:create` or `dupe:skip`
+async fn handle_near_duplicate_callback(
+    bot: Bot,
+    callback_id: CallbackQueryId,
+    user_id: i64,
+    message: Option<MaybeInaccessibleMessage>,
+    data: &str,
+    db: BotDb,
+) -> Result<()> {
+    // Relative dates in the original message are read again as of now
+    let Some(MaybeInaccessibleMessage::Regular(prompt)) = message else {
+        bot.answer_callback_query(callback_id)
+            .text("❌ This prompt has expired")
+            .await?;
+        return Ok(());
+    };
+    let original = prompt
+        .reply_to_message()
+        .filter(|original| original.from.as_ref().map(|user| user.id.0 as i64) == Some(user_id))
+        .and_then(|original| Some((original, original.text().or(original.caption())?)));
+    let Some((original, text)) = original else {
+        bot.answer_callback_query(callback_id)
+            .text("❌ The original message is gone; please send it again")
+            .show_alert(true)
+            .await?;
+        return Ok(());
+    };
+
+    bot.answer_callback_query(callback_id).await?;
+    let outcome = match data {
+        "dupe:create" => "Creating it anyway.",
+        _ => "Skipped; nothing was created.",
+    };
+    bot.edit_message_text(
+        prompt.chat.id,
+        prompt.id,
+        format!("{}\n\n{}", prompt.text().unwrap_or_default(), outcome),
+    )
+    .await?;
+
+    if data == "dupe:create" {
+        if forwarded(original).is_some() {
+            create_event_from_forward(&bot, original, user_id, true, &db).await?;
+        } else {
+            create_event_from_text(&bot, original, user_id, text, true, &db).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Where a forwarded message came from and its text or caption
+pub fn forwarded(msg: &Message) -> Option<(&MessageOrigin, &str)> {
+    Some((msg.forward_origin()?, msg.text().or(msg.caption())?))
+}
+
+/// Who wrote a forwarded message, as far as Telegram tells
+fn forward_source(origin: &MessageOrigin) -> String {
+    match origin {
+        MessageOrigin::User { sender_user, .. } => match &sender_user.username {
+            Some(username) => format!("{} (@{username})", sender_user.full_name()),
+            None => sender_user.full_name(),
+        },
+        MessageOrigin::HiddenUser {
+            sender_user_name, ..
+        } => sender_user_name.clone(),
+        MessageOrigin::Chat {
+            sender_chat: chat,
+            author_signature,
+            ..
+        }
+        | MessageOrigin::Channel {
+            chat,
+            author_signature,
+            ..
+        } => {
+            let name = chat.title().or(chat.username()).unwrap_or("a chat");
+            match author_signature {
+                Some(author) => format!("{author} in {name}"),
+                None => name.to_string(),
+            }
+        }
+    }
+}
+
+/// Description of an event made from a forwarded message: who wrote it and
+/// when, a link for public channel posts, then the message itself
+fn forward_description(origin: &MessageOrigin, text: &str) -> String {
+    let mut description = format!(
+        "Forwarded from {}, {}",
+        forward_source(origin),
+        origin.date().format("%B %d, %Y %H:%M UTC")
+    );
+    if let MessageOrigin::Channel {
+        chat, message_id, ..
+    } = origin
+        && let Some(username) = chat.username()
+    {
+        description.push_str(&format!("\nhttps://t.me/{username}/{}", message_id.0));
+    }
+    description.push_str("\n\n");
+    description.push_str(text);
+    description.chars().take(MAX_DESCRIPTION_LENGTH).collect()
+}
+
+/// Where a previewed event was found
+#[derive(Debug, Clone, Copy)]
+enum PreviewSource<'a> {
+    /// A forwarded message, from this author
+    Forward(&'a str),
+    /// A transcribed voice note
+    Voice(&'a str),
+    /// A poster photo, as reduced to one sentence
+    Poster(&'a str),
+}
+
+impl PreviewSource<'_> {
+    /// First part of the preview's callback data
+    fn callback_prefix(self) -> &'static str {
+        match self {
+            Self::Forward(_) => "fwd",
+            Self::Voice(_) => "voice",
+            Self::Poster(_) => "poster",
+        }
+    }
+
+    fn heading(self) -> &'static str {
+        match self {
+            Self::Forward(_) => "📨",
+            Self::Voice(_) => "🎙",
+            Self::Poster(_) => "🖼",
+        }
+    }
+
+    /// Last line of the preview; a voice note's or poster's is read back by
+    /// its buttons
+    fn line(self) -> String {
+        match self {
+            Self::Forward(author) => format!("↪️ From {}", inline(author)),
+            Self::Voice(transcript) => format!("{VOICE_TRANSCRIPT_PREFIX}{}", escape(transcript)),
+            Self::Poster(sentence) => format!("{POSTER_SENTENCE_PREFIX}{}", escape(sentence)),
+        }
+    }
+}
+
+/// Start of the preview line quoting a voice note's transcript
+const VOICE_TRANSCRIPT_PREFIX: &str = "🎙 ";
+/// Start of the preview line quoting what was read off a poster
+const POSTER_SENTENCE_PREFIX: &str = "🖼 Read as: ";
+
+/// Render the card offering to create the event found in a forwarded
+/// message, voice note or poster
+fn render_event_preview(
+    event: &ParsedEvent,
+    source: PreviewSource<'_>,
+    now: DateTime<Utc>,
+) -> (String, InlineKeyboardMarkup) {
+    let location_text = event
+        .location
+        .as_ref()
+        .map(|loc| format!("\n📍 <b>Location:</b> {}", inline(loc)))
+        .unwrap_or_default();
+    let passed = match &event.timing {
+        ParsedTiming::Timed { start, .. } => *start < now,
+        ParsedTiming::AllDay { date } => *date < now.date_naive(),
+    };
+    let passed_text = if passed {
+        "\n\n⚠️ That time has already passed."
+    } else {
+        ""
+    };
+
+    let text = format!(
+        "{} <b>Create this event?</b>\n\n\
+         📌 <b>{}</b>\n\
+         {}{}\n\
+         {}{}",
+        source.heading(),
+        inline(&event.title),
+        timing_lines(&event.timing),
+        location_text,
+        source.line(),
+        passed_text
+    );
+    let prefix = source.callback_prefix();
+    let keyboard = InlineKeyboardMarkup::new(vec![vec![
+        InlineKeyboardButton::callback("✅ Create", format!("{prefix}:create")),
+        InlineKeyboardButton::callback("✖️ Cancel", format!("{prefix}:cancel")),
+    ]]);
+
+    (text, keyboard)
+}
+
+/// Handle a forwarded message: offer the event it mentions, with dates read
+/// as of when it was first sent
+pub async fn handle_forwarded_message(bot: Bot, msg: Message, db: BotDb) -> Result<()> {
+    let Some((origin, text)) = forwarded(&msg) else {
+        return Ok(());
+    };
+    if is_read_only(&db).await {
+        bot.send_message(msg.chat.id, READ_ONLY_NOTICE).await?;
+        return Ok(());
+    }
+
+    let event = match parse_event_sentence(text, origin.date().with_timezone(&chrono::Local)) {
+        Ok(event) => event,
+        Err(parse_error) => {
+            db.analytics()
+                .record(Metric::event(EventFunnelStep::ParseFailed));
+            bot.send_message(
+                msg.chat.id,
+                format!(
+                    "🤷 <b>No event found in that message</b>\n\n{}",
+                    escape(&parse_error.to_string())
+                ),
+            )
+            .parse_mode(ParseMode::Html)
+            .reply_parameters(ReplyParameters::new(msg.id))
+            .await?;
+            return Ok(());
+        }
+    };
+
+    // The preview replies to the forward, so the button can read it again
+    let (text, keyboard) = render_event_preview(
+        &event,
+        PreviewSource::Forward(&forward_source(origin)),
+        Utc::now(),
+    );
+    bot.send_message(msg.chat.id, text)
+        .parse_mode(ParseMode::Html)
+        .reply_parameters(ReplyParameters::new(msg.id))
+        .reply_markup(keyboard)
+        .await?;
+
+    Ok(())
+}
+
+/// Create the event a forwarded message mentions, described as coming from
+/// its source
+async fn create_event_from_forward(
+    bot: &Bot,
+    msg: &Message,
+    telegram_id: i64,
+    allow_duplicate: bool,
+    db: &BotDb,
+) -> Result<()> {
+    let Some((origin, text)) = forwarded(msg) else {
+        return Ok(());
+    };
+    let event = match parse_event_sentence(text, origin.date().with_timezone(&chrono::Local)) {
+        Ok(event) => event,
+        Err(parse_error) => {
+            bot.send_message(
+                msg.chat.id,
+                format!(
+                    "❌ <b>Could not create event</b>\n\n{}",
+                    escape(&parse_error.to_string())
+                ),
+            )
+            .parse_mode(ParseMode::Html)
+            .await?;
+            return Ok(());
+        }
+    };
+    let description = forward_description(origin, text);
+
+    create_parsed_event(
+        bot,
+        msg,
+        telegram_id,
+        &event,
+        Some(&description),
+        allow_duplicate,
+        db,
+    )
+    .await
+}
+
+/// Answer to a forwarded message's preview: `fwd:create` or `fwd:cancel`
+async fn handle_forward_callback(
+    bot: Bot,
+    callback_id: CallbackQueryId,
+    user_id: i64,
+    message: Option<MaybeInaccessibleMessage>,
+    data: &str,
+    db: BotDb,
+) -> Result<()> {
+    let Some(MaybeInaccessibleMessage::Regular(prompt)) = message else {
+        bot.answer_callback_query(callback_id)
+            .text("❌ This preview has expired")
+            .await?;
+        return Ok(());
+    };
+    let original = prompt
+        .reply_to_message()
+        .filter(|original| original.from.as_ref().map(|user| user.id.0 as i64) == Some(user_id))
+        .filter(|original| forwarded(original).is_some());
+    let Some(original) = original else {
+        bot.answer_callback_query(callback_id)
+            .text("❌ The forwarded message is gone; please forward it again")
+            .show_alert(true)
+            .await?;
+        return Ok(());
+    };
+
+    bot.answer_callback_query(callback_id).await?;
+    let outcome = match data {
+        "fwd:create" => "Creating it.",
+        _ => "Cancelled; nothing was created.",
+    };
+    bot.edit_message_text(
+        prompt.chat.id,
+        prompt.id,
+        format!("{}\n\n{}", prompt.text().unwrap_or_default(), outcome),
+    )
+    .await?;
+
+    if data == "fwd:create" {
+        create_event_from_forward(&bot, original, user_id, false, &db).await?;
+    }
+
+    Ok(())
+}
+
+/// Handle a voice note: transcribe it and offer the event it mentions
+pub async fn handle_voice_message(
+    bot: Bot,
+    msg: Message,
+    db: BotDb,
+    voice_notes: VoiceNotes,
+) -> Result<()> {
+    let Some(voice) = msg.voice() else {
+        return Ok(());
+    };
+    if is_read_only(&db).await {
+        bot.send_message(msg.chat.id, READ_ONLY_NOTICE).await?;
+        return Ok(());
+    }
+
+    let too_long = format!(
+        "🎙 Keep voice notes under {MAX_VOICE_SECONDS} seconds and say what and when, \
+         like \"Dinner Friday 7pm at Luigi's\"."
+    );
+    if voice.duration.seconds() > MAX_VOICE_SECONDS {
+        bot.send_message(msg.chat.id, too_long)
+            .reply_parameters(ReplyParameters::new(msg.id))
+            .await?;
+        return Ok(());
+    }
+
+    let transcript = match voice_notes.transcribe(&bot, voice).await {
+        Ok(transcript) if !transcript.is_empty() => transcript,
+        result => {
+            if let Err(e) = result {
+                tracing::warn!("Failed to transcribe voice note: {e:#}");
+            }
+            bot.send_message(
+                msg.chat.id,
+                "❌ Could not make out that voice note; please try again or type the event.",
+            )
+            .reply_parameters(ReplyParameters::new(msg.id))
+            .await?;
+            return Ok(());
+        }
+    };
+    if transcript.chars().count() > MAX_TRANSCRIPT_CHARS {
+        bot.send_message(msg.chat.id, too_long)
+            .reply_parameters(ReplyParameters::new(msg.id))
+            .await?;
+        return Ok(());
+    }
+
+    let event = match parse_event_sentence(&transcript, msg.date.with_timezone(&chrono::Local)) {
+        Ok(event) => event,
+        Err(parse_error) => {
+            db.analytics()
+                .record(Metric::event(EventFunnelStep::ParseFailed));
+            bot.send_message(
+                msg.chat.id,
+                format!(
+                    "🤷 <b>No event found in that voice note</b>\n\n{}{}\n\n{}",
+                    VOICE_TRANSCRIPT_PREFIX,
+                    escape(&transcript),
+                    escape(&parse_error.to_string())
+                ),
+            )
+            .parse_mode(ParseMode::Html)
+            .reply_parameters(ReplyParameters::new(msg.id))
+            .await?;
+            return Ok(());
+        }
+    };
+
+    // The preview quotes the transcript, so the button need not transcribe
+    // the note again
+    let (text, keyboard) =
+        render_event_preview(&event, PreviewSource::Voice(&transcript), Utc::now());
+    bot.send_message(msg.chat.id, text)
         .parse_mode(ParseMode::Html)
+        .reply_parameters(ReplyParameters::new(msg.id))
+        .reply_markup(keyboard)
         .await?;
+
+    Ok(())
+}
+
+/// Handle a photo: read it and offer the event on the poster it shows
+pub async fn handle_photo_message(
+    bot: Bot,
+    msg: Message,
+    db: BotDb,
+    posters: Posters,
+) -> Result<()> {
+    let Some(photo) = msg.photo() else {
+        return Ok(());
+    };
+    if is_read_only(&db).await {
+        bot.send_message(msg.chat.id, READ_ONLY_NOTICE).await?;
         return Ok(());
     }
 
-    let event_id_str = parts[1];
-    let status_str = parts[2].to_lowercase();
+    let text = match posters.read(&bot, photo).await {
+        Ok(text) => text,
+        Err(e) => {
+            tracing::warn!("Failed to read photo: {e:#}");
+            String::new()
+        }
+    };
+    let seen_at = msg.date.with_timezone(&chrono::Local);
+    let found = poster_sentence(&text, seen_at).and_then(|sentence| {
+        let event = parse_event_sentence(&sentence, seen_at).ok()?;
+        Some((sentence, event))
+    });
+    let Some((sentence, event)) = found else {
+        // Groups share plenty of photos that are not posters
+        if msg.chat.is_private() {
+            bot.send_message(
+                msg.chat.id,
+                "🤷 No event found on that photo. Make sure the title and date are legible, \
+                 or type the event instead.",
+            )
+            .reply_parameters(ReplyParameters::new(msg.id))
+            .await?;
+        }
+        return Ok(());
+    };
 
-    // Parse event UUID
-    let event_id = match uuid::Uuid::parse_str(event_id_str) {
-        Ok(id) => id,
-        Err(_) => {
-            bot.send_message(msg.chat.id, "❌ Invalid event ID format")
-                .await?;
+    let (text, keyboard) =
+        render_event_preview(&event, PreviewSource::Poster(&sentence), Utc::now());
+    bot.send_message(msg.chat.id, text)
+        .parse_mode(ParseMode::Html)
+        .reply_parameters(ReplyParameters::new(msg.id))
+        .reply_markup(keyboard)
+        .await?;
+
+    Ok(())
+}
+
+/// Text quoted by a preview after `prefix`, on the last line that starts
+/// like it; the heading shares the emoji
+fn previewed_quote<'a>(preview: &'a str, prefix: &str) -> Option<&'a str> {
+    preview
+        .lines()
+        .rev()
+        .find_map(|line| line.strip_prefix(prefix))
+        .filter(|quote| !quote.is_empty())
+}
+
+/// Answer to the preview of a voice note (`voice:create` or `voice:cancel`)
+/// or a poster (`poster:…`), which quotes the text to create the event from
+async fn handle_quoted_preview_callback(
+    bot: Bot,
+    callback_id: CallbackQueryId,
+    user_id: i64,
+    message: Option<MaybeInaccessibleMessage>,
+    data: &str,
+    db: BotDb,
+) -> Result<()> {
+    let Some(MaybeInaccessibleMessage::Regular(prompt)) = message else {
+        bot.answer_callback_query(callback_id)
+            .text("❌ This preview has expired")
+            .await?;
+        return Ok(());
+    };
+    let voice = data.starts_with("voice:");
+    let (media, quote_prefix, label) = if voice {
+        ("voice note", VOICE_TRANSCRIPT_PREFIX, "Voice note")
+    } else {
+        ("photo", POSTER_SENTENCE_PREFIX, "Read off a poster")
+    };
+    let original = prompt
+        .reply_to_message()
+        .filter(|original| original.from.as_ref().map(|user| user.id.0 as i64) == Some(user_id))
+        .filter(|original| {
+            if voice {
+                original.voice().is_some()
+            } else {
+                original.photo().is_some()
+            }
+        });
+    let quote = prompt
+        .text()
+        .and_then(|preview| previewed_quote(preview, quote_prefix));
+    let (Some(original), Some(quote)) = (original, quote) else {
+        bot.answer_callback_query(callback_id)
+            .text(format!("❌ The {media} is gone; please send it again"))
+            .show_alert(true)
+            .await?;
+        return Ok(());
+    };
+
+    bot.answer_callback_query(callback_id).await?;
+    let create = data.ends_with(":create");
+    let outcome = if create {
+        "Creating it."
+    } else {
+        "Cancelled; nothing was created."
+    };
+    bot.edit_message_text(
+        prompt.chat.id,
+        prompt.id,
+        format!("{}\n\n{}", prompt.text().unwrap_or_default(), outcome),
+    )
+    .await?;
+    if !create {
+        return Ok(());
+    }
+
+    let event = match parse_event_sentence(quote, original.date.with_timezone(&chrono::Local)) {
+        Ok(event) => event,
+        Err(parse_error) => {
+            bot.send_message(
+                prompt.chat.id,
+                format!(
+                    "❌ <b>Could not create event</b>\n\n{}",
+                    escape(&parse_error.to_string())
+                ),
+            )
+            .parse_mode(ParseMode::Html)
+            .await?;
             return Ok(());
         }
     };
+    let description = format!("{label}: {quote}");
+    // The preview already asked; a near-duplicate prompt could not read the
+    // voice note or photo again anyway
+    create_parsed_event(
+        &bot,
+        original,
+        user_id,
+        &event,
+        Some(&description),
+        true,
+        &db,
+    )
+    .await
+}
 
-    // Map user input to participation status
-    let status = match status_str.as_str() {
-        "accept" | "accepted" | "yes" => "ACCEPTED",
-        "decline" | "declined" | "no" => "DECLINED",
-        "tentative" | "maybe" => "TENTATIVE",
-        _ => {
+/// Event title as HTML, struck through once the event is cancelled
+fn summary_html(event: &BotEvent) -> String {
+    if event.cancelled {
+        format!("<s>{}</s>", inline(&event.summary))
+    } else {
+        inline(&event.summary)
+    }
+}
+
+/// Shortcuts offered under an event card: (label, days to move the copy by)
+const DUPLICATE_SHORTCUTS: [(&str, i64); 2] =
+    [("📋 Copy to tomorrow", 1), ("📋 Copy to next week", 7)];
+
+/// Date and time lines of an event card
+fn timing_lines(timing: &ParsedTiming) -> String {
+    let (start, details) = match timing {
+        ParsedTiming::Timed {
+            start,
+            duration_minutes,
+        } => {
+            let end_time = *start + Duration::minutes(i64::from(*duration_minutes));
+            let details = format!(
+                "{} - {} ({} min)",
+                start.format("%H:%M"),
+                end_time.format("%H:%M"),
+                duration_minutes
+            );
+            (*start, details)
+        }
+        ParsedTiming::AllDay { date } => (
+            date.and_time(NaiveTime::MIN).and_utc(),
+            "All Day".to_string(),
+        ),
+    };
+    format!("📅 {}\n🕐 {}", start.format("%A, %B %d, %Y"), details)
+}
+
+/// Render an event with buttons to duplicate it, warning when a timed event
+/// falls outside `working_hours`
+fn render_event_card(
+    heading: &str,
+    event: &BotEvent,
+    working_hours: Option<&WorkingHours>,
+) -> (String, InlineKeyboardMarkup) {
+    let location_text = event
+        .location
+        .as_ref()
+        .map(|loc| format!("\n📍 <b>Location:</b> {}", inline(loc)))
+        .unwrap_or_default();
+
+    let tags: Vec<String> = event_tags(&event.summary)
+        .iter()
+        .map(|tag| format!("#{}", escape(tag)))
+        .collect();
+    let tags_text = if tags.is_empty() {
+        String::new()
+    } else {
+        format!("\n🏷 {}", tags.join(" "))
+    };
+
+    let note_text = event
+        .reminder_note
+        .as_ref()
+        .map(|note| format!("\n📝 <b>Note:</b> {}", inline(note)))
+        .unwrap_or_default();
+
+    let cancelled_text = if event.cancelled {
+        "\n❌ <b>Cancelled</b>"
+    } else {
+        ""
+    };
+
+    // Floating times are wall-clock only, so there's no instant to check
+    let outside = match (working_hours, event.start, event.end) {
+        (Some(hours), Some(start), Some(end)) if !event.is_all_day && !event.is_floating => {
+            !hours.contains(start, end)
+        }
+        _ => false,
+    };
+    let hours_text = if outside {
+        "\n⚠️ Outside your working hours"
+    } else {
+        ""
+    };
+
+    let text = format!(
+        "{heading}\n\n\
+         📌 <b>{}</b>\n\
+         {}{}{}{}{}{}\n\n\
+         Reply to this message to change it, e.g. \"move to 4pm\".\n\
+         Use /list to view your upcoming events.",
+        summary_html(event),
+        timing_lines(&event.timing()),
+        location_text,
+        note_text,
+        tags_text,
+        cancelled_text,
+        hours_text
+    );
+    let row: Vec<_> = DUPLICATE_SHORTCUTS
+        .iter()
+        .map(|(label, days)| {
+            InlineKeyboardButton::callback(*label, format!("dup:{}:{days}", event.id.simple()))
+        })
+        .collect();
+
+    (text, InlineKeyboardMarkup::new(vec![row]))
+}
+
+/// Token a text mention stands for among /invite arguments
+const MENTION_TOKEN_PREFIX: &str = "tg://user?id=";
+
+/// Text of `msg` with each text mention, the mention Telegram sends for
+/// someone picked from the list, turned into one `tg://user?id=` token;
+/// also returns the mentioned names by Telegram id, to echo back
+fn with_text_mentions(msg: &Message) -> (String, Vec<(i64, String)>) {
+    let text = msg.text().unwrap_or("");
+    let mut rewritten = String::with_capacity(text.len());
+    let mut names = Vec::new();
+    let mut copied = 0;
+    for entity in msg.parse_entities().unwrap_or_default() {
+        let MessageEntityKind::TextMention { user } = entity.kind() else {
+            continue;
+        };
+        if entity.start() < copied {
+            continue;
+        }
+        let telegram_id = user.id.0 as i64;
+        rewritten.push_str(&text[copied..entity.start()]);
+        rewritten.push_str(&format!(" {MENTION_TOKEN_PREFIX}{telegram_id} "));
+        names.push((telegram_id, entity.text().trim().to_string()));
+        copied = entity.end();
+    }
+    rewritten.push_str(&text[copied..]);
+
+    (rewritten, names)
+}
+
+/// Telegram id behind a `tg://user?id=` token
+fn mentioned_user_id(token: &str) -> Option<i64> {
+    token.strip_prefix(MENTION_TOKEN_PREFIX)?.parse().ok()
+}
+
+/// Event a message replies to, when it replies to one of the bot's event
+/// cards; the card's copy buttons carry the event id
+pub fn edit_target(msg: &Message) -> Option<Uuid> {
+    let card = msg.reply_to_message()?;
+    if !card.from.as_ref().is_some_and(|user| user.is_bot) {
+        return None;
+    }
+    card.reply_markup()?
+        .inline_keyboard
+        .iter()
+        .flatten()
+        .find_map(|button| match &button.kind {
+            InlineKeyboardButtonKind::CallbackData(data) => data
+                .strip_prefix("dup:")
+                .and_then(|rest| rest.split(':').next())
+                .and_then(|event_id| Uuid::parse_str(event_id).ok()),
+            _ => None,
+        })
+}
+
+/// Handle a reply to an event card, e.g. "move to 4pm" or "rename to Sprint
+/// Review"
+pub async fn handle_edit_reply(bot: Bot, msg: Message, db: BotDb, event_id: Uuid) -> Result<()> {
+    let user = msg
+        .from
+        .as_ref()
+        .ok_or_else(|| anyhow::anyhow!("No user in message"))?;
+    let telegram_id = user.id.0 as i64;
+    let text = msg.text().unwrap_or_default();
+
+    if is_read_only(&db).await {
+        bot.send_message(msg.chat.id, READ_ONLY_NOTICE).await?;
+        return Ok(());
+    }
+
+    let Some(event) = db.get_event_info(event_id, telegram_id).await? else {
+        bot.send_message(msg.chat.id, "❌ Event not found. It may have been deleted.")
+            .await?;
+        return Ok(());
+    };
+    // A repeating event is edited from its next occurrence on, when asked
+    let (reach, text) = split_edit_reach(text);
+    let scope = match (event.rrule.as_deref(), reach) {
+        (None, _) | (_, EditReach::Series) => EditScope::Series,
+        (Some(rrule), reach) => {
+            let next =
+                occurrence_after(rrule, event.series_start, Utc::now() - Duration::seconds(1))
+                    .ok()
+                    .flatten();
+            let Some(next) = next else {
+                bot.send_message(msg.chat.id, "❌ This event has no upcoming occurrences.")
+                    .await?;
+                return Ok(());
+            };
+            if reach == EditReach::Next {
+                EditScope::Occurrence(next)
+            } else {
+                EditScope::Following(next)
+            }
+        }
+    };
+    let offset = scope
+        .cut()
+        .map_or_else(Duration::zero, |at| at - event.series_start);
+    let current = match (event.is_all_day, event.start, event.end, event.start_date) {
+        (false, Some(start), Some(end), _) => crate::event_parser::ParsedTiming::Timed {
+            start: start + offset,
+            duration_minutes: u32::try_from((end - start).num_minutes()).unwrap_or(60),
+        },
+        (_, _, _, Some(date)) => crate::event_parser::ParsedTiming::AllDay {
+            date: date + offset,
+        },
+        _ => return Err(anyhow::anyhow!("Event {event_id} has no timing")),
+    };
+
+    let edit = match parse_edit(text, &current) {
+        Ok(edit) => edit,
+        Err(parse_error) => {
             bot.send_message(
                 msg.chat.id,
-                "❌ Invalid status. Use: accept, decline, or tentative",
+                format!("✏️ {}", escape(&parse_error.to_string())),
             )
+            .parse_mode(ParseMode::Html)
+            .reply_parameters(ReplyParameters::new(msg.id))
             .await?;
             return Ok(());
         }
     };
 
-    match db
-        .confirm_rsvp_named(event_id, telegram_id, status, username.clone())
-        .await
-    {
-        Ok(()) => {
-            let emoji = match status {
-                "ACCEPTED" => "✅",
-                "DECLINED" => "❌",
-                "TENTATIVE" => "🤔",
-                _ => "✉️",
+    match db.edit_event(telegram_id, event_id, edit, scope).await {
+        Ok(Some(event)) => {
+            let hours = db.get_working_hours(telegram_id).await.unwrap_or_default();
+            let (text, keyboard) =
+                render_event_card("✏️ <b>Event Updated</b>", &event, hours.as_ref());
+            bot.send_message(msg.chat.id, text)
+                .parse_mode(ParseMode::Html)
+                .reply_markup(keyboard)
+                .await?;
+            tracing::info!("User {} edited event {} by reply", telegram_id, event_id);
+        }
+        Ok(None) => {
+            bot.send_message(msg.chat.id, "❌ Event not found. It may have been deleted.")
+                .await?;
+        }
+        Err(ApplicationError::BadRequest(message)) => {
+            bot.send_message(msg.chat.id, format!("❌ {}", escape(&message)))
+                .parse_mode(ParseMode::Html)
+                .await?;
+        }
+        Err(err) => {
+            tracing::error!("Failed to edit event {}: {}", event_id, err);
+            bot.send_message(
+                msg.chat.id,
+                "❌ Failed to update the event. Please try again.",
+            )
+            .await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Handle duplicate buttons. Format: dup:<event_id>:<offset_days>
+async fn handle_duplicate_callback(
+    bot: Bot,
+    callback_id: CallbackQueryId,
+    user_id: i64,
+    message: Option<MaybeInaccessibleMessage>,
+    data: &str,
+    db: BotDb,
+) -> Result<()> {
+    let parts: Vec<&str> = data.split(':').collect();
+    let parsed = match parts.as_slice() {
+        ["dup", event_id, days] => Uuid::parse_str(event_id).ok().zip(days.parse::<i64>().ok()),
+        _ => None,
+    };
+    let Some((event_id, offset_days)) = parsed else {
+        bot.answer_callback_query(callback_id)
+            .text("❌ Invalid data")
+            .await?;
+        return Ok(());
+    };
+
+    let copy = match db.duplicate_event(user_id, event_id, offset_days).await {
+        Ok(Some(copy)) => copy,
+        Ok(None) => {
+            bot.answer_callback_query(callback_id)
+                .text("❌ Event not found")
+                .show_alert(true)
+                .await?;
+            return Ok(());
+        }
+        Err(err) => {
+            tracing::error!("Failed to duplicate event {}: {}", event_id, err);
+            bot.answer_callback_query(callback_id)
+                .text("❌ Failed to duplicate event. Please try again.")
+                .show_alert(true)
+                .await?;
+            return Ok(());
+        }
+    };
+
+    bot.answer_callback_query(callback_id)
+        .text("📋 Event duplicated")
+        .await?;
+    if let Some(message) = message {
+        let hours = db.get_working_hours(user_id).await.unwrap_or_default();
+        let (text, keyboard) =
+            render_event_card("📋 <b>Event Duplicated!</b>", &copy, hours.as_ref());
+        bot.send_message(message.chat().id, text)
+            .parse_mode(ParseMode::Html)
+            .reply_markup(keyboard)
+            .await?;
+    }
+
+    tracing::info!(
+        "User {} duplicated event {} as {} ({:+} days)",
+        user_id,
+        event_id,
+        copy.id,
+        offset_days
+    );
+    Ok(())
+}
+
+/// Agenda quick action buttons; see [`quick_actions`] for the format
+async fn handle_quick_action_callback(
+    bot: Bot,
+    callback_id: CallbackQueryId,
+    user_id: i64,
+    message: Option<MaybeInaccessibleMessage>,
+    data: &str,
+    db: BotDb,
+) -> Result<()> {
+    let Some((action, event_id)) = quick_actions::decode(bot.token().as_bytes(), user_id, data)
+    else {
+        bot.answer_callback_query(callback_id)
+            .text("❌ This button isn't yours; send /agenda for your own")
+            .show_alert(true)
+            .await?;
+        return Ok(());
+    };
+    let chat_id = message
+        .as_ref()
+        .map_or(ChatId(user_id), |message| message.chat().id);
+
+    let Some(event) = db.get_event_info(event_id, user_id).await? else {
+        bot.answer_callback_query(callback_id)
+            .text("❌ Event not found. It may have been deleted.")
+            .show_alert(true)
+            .await?;
+        return Ok(());
+    };
+
+    match action {
+        QuickAction::Edit => {
+            let Some(event) = db.get_event(user_id, event_id).await? else {
+                bot.answer_callback_query(callback_id)
+                    .text("❌ Event not found")
+                    .await?;
+                return Ok(());
+            };
+            let (text, keyboard) = render_event_card("📝 <b>Edit Event</b>", &event, None);
+            bot.send_message(chat_id, text)
+                .parse_mode(ParseMode::Html)
+                .reply_markup(keyboard)
+                .await?;
+            bot.answer_callback_query(callback_id)
+                .text("Reply to the card with the change")
+                .await?;
+        }
+        QuickAction::Cancel => {
+            let repeats = if event.rrule.is_some() {
+                " This cancels every occurrence."
+            } else {
+                ""
+            };
+            let text = format!(
+                "❌ Cancel <b>{}</b>?\n\nGuests are told it is off.{repeats}",
+                inline(&event.summary)
+            );
+            let confirm = quick_actions::encode(
+                bot.token().as_bytes(),
+                user_id,
+                QuickAction::ConfirmCancel,
+                event_id,
+            );
+            bot.send_message(chat_id, text)
+                .parse_mode(ParseMode::Html)
+                .reply_markup(InlineKeyboardMarkup::new([[
+                    InlineKeyboardButton::callback("✅ Cancel event", confirm),
+                ]]))
+                .await?;
+            bot.answer_callback_query(callback_id).await?;
+        }
+        QuickAction::ConfirmCancel => match db.cancel_event(user_id, event_id).await {
+            Ok(Some(_)) => {
+                if let Some(message) = message {
+                    bot.edit_message_text(
+                        message.chat().id,
+                        message.id(),
+                        format!("❌ <b>{}</b> is cancelled.", inline(&event.summary)),
+                    )
+                    .parse_mode(ParseMode::Html)
+                    .await?;
+                }
+                bot.answer_callback_query(callback_id)
+                    .text("❌ Event cancelled")
+                    .await?;
+                tracing::info!("User {} cancelled event {}", user_id, event_id);
+            }
+            Ok(None) => {
+                bot.answer_callback_query(callback_id)
+                    .text("❌ Event not found")
+                    .show_alert(true)
+                    .await?;
+            }
+            Err(e) => {
+                tracing::error!("Failed to cancel event {}: {}", event_id, e);
+                bot.answer_callback_query(callback_id)
+                    .text("❌ Failed to cancel event. Please try again.")
+                    .show_alert(true)
+                    .await?;
+            }
+        },
+        QuickAction::Attendees => {
+            let attendees = db.get_event_attendees(event_id).await?;
+            let (text, keyboard) = render_attendee_dashboard(event_id, &event.summary, &attendees);
+            bot.send_message(chat_id, text)
+                .parse_mode(ParseMode::Html)
+                .reply_markup(keyboard)
+                .await?;
+            bot.answer_callback_query(callback_id).await?;
+        }
+        QuickAction::Remind => {
+            let attendees = db.get_event_attendees(event_id).await?;
+            let mut reminded = 0;
+            for attendee in attendees.iter().filter(|attendee| {
+                attendee.role != "ORGANIZER"
+                    && attendee.status == "NEEDS-ACTION"
+                    && attendee.telegram_id.is_some()
+            }) {
+                match db.resend_invite(user_id, event_id, &attendee.email).await {
+                    Ok(()) => reminded += 1,
+                    Err(e) => tracing::warn!(
+                        "Failed to remind {} about event {}: {}",
+                        attendee.email,
+                        event_id,
+                        e
+                    ),
+                }
+            }
+            let text = match reminded {
+                0 => "Nobody on Telegram is waiting to answer".to_string(),
+                1 => "🔔 Reminded 1 guest".to_string(),
+                n => format!("🔔 Reminded {n} guests"),
             };
+            bot.answer_callback_query(callback_id).text(text).await?;
+        }
+    }
+
+    Ok(())
+}
 
-            bot.send_message(
-                msg.chat.id,
-                format!(
-                    "{} Your response has been recorded: <b>{}</b>",
-                    emoji, status
-                ),
-            )
-            .parse_mode(ParseMode::Html)
+/// Snooze buttons on an invite reminder: `snz:<event_id>:<delay>`
+async fn handle_snooze_callback(
+    bot: Bot,
+    callback_id: CallbackQueryId,
+    user_id: i64,
+    message: Option<MaybeInaccessibleMessage>,
+    data: &str,
+    db: BotDb,
+) -> Result<()> {
+    let parts: Vec<&str> = data.split(':').collect();
+    let parsed = match parts.as_slice() {
+        ["snz", event_id, delay] => Uuid::parse_str(event_id)
+            .ok()
+            .zip(SnoozeDelay::parse(delay)),
+        _ => None,
+    };
+    let Some((event_id, delay)) = parsed else {
+        bot.answer_callback_query(callback_id)
+            .text("❌ Invalid data")
             .await?;
+        return Ok(());
+    };
 
+    match db.snooze_invite_reminder(user_id, event_id, delay).await {
+        Ok(()) => {
+            bot.answer_callback_query(callback_id)
+                .text(snooze_confirmation(delay))
+                .await?;
+            // Keep the RSVP row; the reminder comes back with fresh snooze buttons
+            if let Some(MaybeInaccessibleMessage::Regular(message)) = message
+                && let Some(keyboard) = message.reply_markup()
+            {
+                let rows = keyboard
+                    .inline_keyboard
+                    .iter()
+                    .filter(|row| !row.iter().any(is_snooze_button))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                bot.edit_message_reply_markup(message.chat.id, message.id)
+                    .reply_markup(InlineKeyboardMarkup::new(rows))
+                    .await?;
+            }
             tracing::info!(
-                "User {} responded {} to event {}",
-                telegram_id,
-                status,
-                event_id
+                "User {} snoozed the reminder for event {} ({})",
+                user_id,
+                event_id,
+                delay.as_str()
             );
         }
-        Err(televent_application::ApplicationError::NotFound(_)) => {
-            bot.send_message(msg.chat.id, "❌ Invitation not found")
+        Err(ApplicationError::BadRequest(reason) | ApplicationError::NotFound(reason)) => {
+            tracing::debug!("Snooze refused for event {}: {}", event_id, reason);
+            bot.answer_callback_query(callback_id)
+                .text("❌ This invite no longer needs a reply")
+                .show_alert(true)
                 .await?;
         }
-        Err(e) => {
-            tracing::error!("Failed to update RSVP: {}", e);
-            bot.send_message(
-                msg.chat.id,
-                "❌ Failed to update your response. Please try again later.",
-            )
-            .await?;
+        Err(err) => {
+            tracing::error!("Failed to snooze reminder for event {}: {}", event_id, err);
+            bot.answer_callback_query(callback_id)
+                .text("❌ Failed to snooze. Please try again.")
+                .show_alert(true)
+                .await?;
         }
     }
-
     Ok(())
 }
 
-/// Handle non-command text messages (event creation)
-///
-/// This handler processes multi-line text messages as potential event creation requests.
-/// Messages must have at least 2 lines (title and date/time) to be considered.
-pub async fn handle_text_message(bot: Bot, msg: Message, db: BotDb) -> Result<()> {
-    let text = match msg.text() {
-        Some(t) => t,
-        None => return Ok(()), // Not a text message
-    };
+fn snooze_confirmation(delay: SnoozeDelay) -> String {
+    match delay {
+        SnoozeDelay::Tomorrow => "⏰ I'll remind you again tomorrow morning".to_string(),
+        SnoozeDelay::TenMinutes | SnoozeDelay::OneHour => {
+            format!("⏰ I'll remind you again in {}", delay.label())
+        }
+    }
+}
 
-    // Skip commands
-    if text.starts_with('/') {
+fn is_snooze_button(button: &InlineKeyboardButton) -> bool {
+    matches!(
+        &button.kind,
+        teloxide::types::InlineKeyboardButtonKind::CallbackData(data) if data.starts_with("snz:")
+    )
+}
+
+/// Timezone buttons from the onboarding prompt: `tz:<zone>`
+async fn handle_timezone_callback(
+    bot: Bot,
+    callback_id: CallbackQueryId,
+    user_id: i64,
+    message: Option<MaybeInaccessibleMessage>,
+    zone: &str,
+    db: BotDb,
+) -> Result<()> {
+    let Ok(timezone) = Timezone::parse(zone) else {
+        bot.answer_callback_query(callback_id)
+            .text("❌ Invalid data")
+            .await?;
         return Ok(());
+    };
+
+    bot.answer_callback_query(callback_id).await?;
+    // One choice per prompt
+    if let Some(MaybeInaccessibleMessage::Regular(message)) = &message {
+        bot.edit_message_reply_markup(message.chat.id, message.id)
+            .await?;
     }
+    let chat_id = message.map_or(ChatId(user_id), |message| message.chat().id);
+    apply_timezone(&bot, chat_id, user_id, &timezone, &db).await
+}
 
-    // Skip messages with fewer than 2 lines (not enough for event creation), but send help
-    let line_count = text.lines().count();
-    if line_count < 2 {
-        let help_text = "To create an event, please use the following format:\n\n\
-             [Exact Syntax]\n\
-             Team Meeting\n\
-             2026-01-25 14:00\n\
-             60\n\
-             Conference Room A\n\n\
-             [Natural Language]\n\
-             Coffee with Alice\n\
-             tomorrow at 3pm\n\
-             30\n\
-             Starbucks";
+/// Handle callback queries (RSVP buttons)
+pub async fn handle_callback_query(bot: Bot, q: CallbackQuery, db: BotDb) -> Result<()> {
+    let data = match q.data {
+        Some(d) => d,
+        None => return Ok(()),
+    };
 
-        bot.send_message(msg.chat.id, help_text).await?;
+    let user_id = q.from.id.0 as i64;
+
+    // Every inline button changes something
+    if is_read_only(&db).await {
+        bot.answer_callback_query(q.id)
+            .text(READ_ONLY_NOTICE)
+            .show_alert(true)
+            .await?;
         return Ok(());
     }
 
-    let user = msg
-        .from
-        .as_ref()
-        .ok_or_else(|| anyhow::anyhow!("No user in message"))?;
-    let telegram_id = user.id.0 as i64;
+    if data.starts_with("dev:") {
+        return handle_device_callback(bot, q.id, user_id, q.message, &data, db).await;
+    }
 
-    // Try to parse as event
-    match parse_event_message(text) {
-        Ok(parsed_event) => {
-            // Generate unique UID for the event
-            let uid = format!("{}@televent.bot", uuid::Uuid::new_v4());
+    if data.starts_with("att:") {
+        return handle_attendee_callback(bot, q.id, user_id, q.message, &data, db).await;
+    }
 
-            // Create event in database
-            match db
-                .create_event(
-                    telegram_id,
-                    &uid,
-                    &parsed_event.title,
-                    None, // description
-                    parsed_event.location.as_deref(),
-                    parsed_event.timing.clone(),
-                    "UTC",
-                )
-                .await
-            {
-                Ok(event) => {
-                    // Format confirmation message
-                    let location_text = parsed_event
-                        .location
-                        .as_ref()
-                        .map(|loc| format!("\n📍 <b>Location:</b> {}", escape(loc)))
-                        .unwrap_or_default();
-
-                    let start = event.display_start();
-                    let timing_details = match event.timing() {
-                        crate::event_parser::ParsedTiming::Timed {
-                            duration_minutes, ..
-                        } => {
-                            let end_time =
-                                start + chrono::Duration::minutes(i64::from(duration_minutes));
-                            format!(
-                                "{} - {} ({} min)",
-                                start.format("%H:%M"),
-                                end_time.format("%H:%M"),
-                                duration_minutes
-                            )
-                        }
-                        crate::event_parser::ParsedTiming::AllDay { .. } => "All Day".to_string(),
-                    };
+    if data.starts_with("inv:") {
+        return handle_invite_suggestion_callback(bot, q.id, user_id, &data, db).await;
+    }
 
-                    let response = format!(
-                        "✅ <b>Event Created!</b>\n\n\
-                         📌 <b>{}</b>\n\
-                         📅 {}\n\
-                         🕐 {}{}\n\n\
-                         Use /list to view your upcoming events.",
-                        escape(&event.summary),
-                        start.format("%A, %B %d, %Y"),
-                        timing_details,
-                        location_text
-                    );
+    if data.starts_with("fixtz:") {
+        return handle_timezone_fix_callback(bot, q.id, user_id, q.message, &data, db).await;
+    }
 
-                    bot.send_message(msg.chat.id, response)
-                        .parse_mode(ParseMode::Html)
-                        .await?;
+    if data.starts_with("dupe:") {
+        return handle_near_duplicate_callback(bot, q.id, user_id, q.message, &data, db).await;
+    }
 
-                    tracing::info!(
-                        "User {} created event: {} at {}",
-                        telegram_id,
-                        event.summary,
-                        start
-                    );
-                }
-                Err(e) => {
-                    tracing::error!("Failed to create event for user {}: {}", telegram_id, e);
+    if data.starts_with("fwd:") {
+        return handle_forward_callback(bot, q.id, user_id, q.message, &data, db).await;
+    }
 
-                    bot.send_message(
-                        msg.chat.id,
-                        "❌ Failed to create event. Please try again later.",
-                    )
-                    .await?;
-                }
-            }
-        }
-        Err(parse_error) => {
-            // Send helpful error message
-            let response = format!(
-                "❌ <b>Could not create event</b>\n\n{}\n\n{}",
-                escape(&parse_error.to_string()),
-                format_example()
-            );
+    if data.starts_with("voice:") || data.starts_with("poster:") {
+        return handle_quoted_preview_callback(bot, q.id, user_id, q.message, &data, db).await;
+    }
 
-            bot.send_message(msg.chat.id, response)
-                .parse_mode(ParseMode::Html)
-                .await?;
+    if data.starts_with("dup:") {
+        return handle_duplicate_callback(bot, q.id, user_id, q.message, &data, db).await;
+    }
 
-            tracing::info!(
-                "User {} sent invalid event format: {}",
-                telegram_id,
-                parse_error
-            );
-        }
+    if data.starts_with(quick_actions::PREFIX) {
+        return handle_quick_action_callback(bot, q.id, user_id, q.message, &data, db).await;
     }
 
-    Ok(())
-}
+    if data.starts_with("task:") || data.starts_with("plan:") {
+        return handle_task_callback(bot, q.id, user_id, q.message, &data, db).await;
+    }
 
-/// Handle callback queries (RSVP buttons)
-pub async fn handle_callback_query(bot: Bot, q: CallbackQuery, db: BotDb) -> Result<()> {
-    let data = match q.data {
-        Some(d) => d,
-        None => return Ok(()),
-    };
+    if data.starts_with("snz:") {
+        return handle_snooze_callback(bot, q.id, user_id, q.message, &data, db).await;
+    }
+
+    if let Some(zone) = data.strip_prefix("tz:") {
+        return handle_timezone_callback(bot, q.id, user_id, q.message, zone, db).await;
+    }
 
     // Check if it's an RSVP callback
     if !data.starts_with("rsvp:") {
         return Ok(());
     }
 
-    let user_id = q.from.id.0 as i64;
     let parts: Vec<&str> = data.split(':').collect();
 
     // Format: rsvp:<event_id>:<status>
@@ -909,10 +4864,16 @@ pub async fn handle_callback_query(bot: Bot, q: CallbackQuery, db: BotDb) -> Res
 mod tests {
     use crate::commands::Command;
     use crate::db::BotDb;
+    use crate::event_parser::ParsedTiming;
+    use crate::quick_actions::{self, QuickAction};
+    use crate::testing::MockTelegram;
+    use chrono::{Duration, Utc};
     use sqlx::PgPool;
-    use televent_application::{CalendarService, DeviceService};
-    use teloxide::Bot;
-    use teloxide::types::Message;
+    use televent_application::{
+        CalendarService, ContactService, DeviceId, DeviceService, EventService, FeatureFlagService,
+        SubscriptionService,
+    };
+    use teloxide::types::{CallbackQueryId, Message};
     use teloxide::utils::command::BotCommands;
 
     fn bot_db(pool: PgPool) -> BotDb {
@@ -920,23 +4881,772 @@ mod tests {
             CalendarService::new(televent_storage::calendar::CalendarRepository::new(
                 pool.clone(),
             )),
-            DeviceService::new(televent_storage::device::DeviceRepository::new(pool)),
+            EventService::new(televent_storage::calendar::CalendarRepository::new(
+                pool.clone(),
+            )),
+            DeviceService::new(televent_storage::device::DeviceRepository::new(
+                pool.clone(),
+            )),
+            SubscriptionService::new(televent_storage::subscription::SubscriptionRepository::new(
+                pool.clone(),
+            )),
+            ContactService::new(televent_storage::contact::ContactRepository::new(
+                pool.clone(),
+            )),
+            FeatureFlagService::new(televent_storage::flags::FeatureFlagRepository::new(pool)),
+        )
+    }
+
+    #[test]
+    fn test_command_descriptions() {
+        // Verify commands can be parsed
+        let cmds = Command::descriptions();
+        let cmds_str = cmds.to_string();
+        assert!(cmds_str.contains("start"), "Should contain /start command");
+        assert!(cmds_str.contains("list"), "Should contain /list command");
+    }
+
+    #[test]
+    fn test_invite_suggestions_fit_callback_data() {
+        let event_id = uuid::Uuid::new_v4();
+        let suggestions = vec![crate::db::SuggestedAttendee {
+            name: "Alice Liddell".to_string(),
+            email: "a.very.long.address.for.testing@subdomain.example.com".to_string(),
+            telegram_id: None,
+            invite_count: 3,
+        }];
+
+        let (text, keyboard) = super::render_invite_suggestions(event_id, "Lunch", &suggestions);
+        assert!(text.contains("Lunch"));
+        let button = &keyboard.inline_keyboard[0][0];
+        assert_eq!(button.text, "➕ Alice Liddell (3)");
+        match &button.kind {
+            teloxide::types::InlineKeyboardButtonKind::CallbackData(data) => {
+                assert!(data.starts_with("inv:"));
+                assert!(data.len() <= 64);
+            }
+            _ => panic!("Expected callback button"),
+        }
+
+        let (text, keyboard) = super::render_invite_suggestions(event_id, "Lunch", &[]);
+        assert!(text.contains("No suggestions yet"));
+        assert!(keyboard.inline_keyboard.is_empty());
+    }
+
+    #[test]
+    fn test_edit_target_reads_card_buttons() {
+        let reply = |card_from_bot: bool| {
+            format!(
+                r#"{{
+                "message_id": 2,
+                "date": 1600000000,
+                "chat": {{"id": 123456789, "type": "private", "first_name": "Test"}},
+                "from": {{"id": 123456789, "is_bot": false, "first_name": "Test"}},
+                "text": "move to 4pm",
+                "reply_to_message": {{
+                    "message_id": 1,
+                    "date": 1600000000,
+                    "chat": {{"id": 123456789, "type": "private", "first_name": "Test"}},
+                    "from": {{"id": 42, "is_bot": {card_from_bot}, "first_name": "Televent"}},
+                    "text": "Event Created",
+                    "reply_markup": {{"inline_keyboard": [[
+                        {{"text": "Copy to tomorrow", "callback_data": "dup:6f1c3f0e9a8b4c2d8e7f6a5b4c3d2e1f:1"}}
+                    ]]}}
+                }}
+            }}"#
+            )
+        };
+
+        let msg: Message = serde_json::from_str(&reply(true)).unwrap();
+        assert_eq!(
+            super::edit_target(&msg),
+            Some(uuid::Uuid::parse_str("6f1c3f0e-9a8b-4c2d-8e7f-6a5b4c3d2e1f").unwrap())
+        );
+
+        // Only the bot's own cards are edit targets
+        let msg: Message = serde_json::from_str(&reply(false)).unwrap();
+        assert_eq!(super::edit_target(&msg), None);
+    }
+
+    #[test]
+    fn test_device_list_buttons_and_name_prompt() {
+        let (text, keyboard) = super::render_device_list(&[]);
+        assert!(text.contains("don't have any device passwords"));
+        let buttons: Vec<_> = keyboard.inline_keyboard.iter().flatten().collect();
+        assert_eq!(buttons.len(), 1);
+
+        let device = |name: &str| crate::db::DevicePasswordInfo {
+            id: DeviceId::new(uuid::Uuid::new_v4()),
+            name: name.to_string(),
+            created_at: chrono::Utc::now(),
+            last_used_at: None,
+            recent_requests: 0,
+        };
+        let mut devices = [device("Work <Laptop>"), device("Phone")];
+        devices[1].recent_requests = 7;
+        let (text, keyboard) = super::render_device_list(&devices);
+        assert!(text.contains("Work &lt;Laptop&gt;"));
+        assert_eq!(text.matches("Requests in the last 24h").count(), 1);
+        assert!(text.contains("Requests in the last 24h: 7"));
+
+        // A Revoke button per device, then Create
+        let data: Vec<&str> = keyboard
+            .inline_keyboard
+            .iter()
+            .flatten()
+            .map(|button| match &button.kind {
+                teloxide::types::InlineKeyboardButtonKind::CallbackData(data) => data.as_str(),
+                _ => panic!("Expected callback button"),
+            })
+            .collect();
+        assert_eq!(
+            data,
+            [
+                format!("dev:revoke:{}", devices[0].id.inner().simple()).as_str(),
+                &format!("dev:revoke:{}", devices[1].id.inner().simple()),
+                "dev:create"
+            ]
+        );
+        assert!(data.iter().all(|data| data.len() <= 64));
+
+        // Only a reply to the bot's name prompt names a device
+        let reply = |callback_data: &str| {
+            format!(
+                r#"{{
+                "message_id": 2,
+                "date": 1600000000,
+                "chat": {{"id": 123456789, "type": "private", "first_name": "Test"}},
+                "from": {{"id": 123456789, "is_bot": false, "first_name": "Test"}},
+                "text": "Work Laptop",
+                "reply_to_message": {{
+                    "message_id": 1,
+                    "date": 1600000000,
+                    "chat": {{"id": 123456789, "type": "private", "first_name": "Test"}},
+                    "from": {{"id": 42, "is_bot": true, "first_name": "Televent"}},
+                    "text": "New device",
+                    "reply_markup": {{"inline_keyboard": [[
+                        {{"text": "Cancel", "callback_data": "{callback_data}"}}
+                    ]]}}
+                }}
+            }}"#
+            )
+        };
+        let msg: Message = serde_json::from_str(&reply("dev:cancel")).unwrap();
+        assert_eq!(super::device_name_target(&msg), Some(super::MessageId(1)));
+        let msg: Message = serde_json::from_str(&reply("dev:list")).unwrap();
+        assert_eq!(super::device_name_target(&msg), None);
+    }
+
+    #[test]
+    fn test_forwarded_message_preview_and_attribution() {
+        let forward = |origin: &str, text: &str| -> Message {
+            serde_json::from_str(&format!(
+                r#"{{
+                "message_id": 21,
+                "date": 1773000000,
+                "chat": {{ "id": 777777201, "type": "private", "first_name": "Ann" }},
+                "from": {{ "id": 777777201, "is_bot": false, "first_name": "Ann" }},
+                "forward_origin": {origin},
+                "text": "{text}"
+            }}"#
+            ))
+            .unwrap()
+        };
+
+        let msg = forward(
+            r#"{ "type": "user", "date": 1773000000,
+                 "sender_user": { "id": 777777202, "is_bot": false, "first_name": "Bob",
+                                  "last_name": "Stone", "username": "bobstone" } }"#,
+            "Dinner Friday 7pm at Luigi's",
+        );
+        let (origin, text) = super::forwarded(&msg).unwrap();
+        assert_eq!(super::forward_source(origin), "Bob Stone (@bobstone)");
+        let description = super::forward_description(origin, text);
+        assert!(description.starts_with("Forwarded from Bob Stone (@bobstone), March"));
+        assert!(description.ends_with("\n\nDinner Friday 7pm at Luigi's"));
+
+        let event = crate::event_parser::parse_event_sentence(
+            text,
+            origin.date().with_timezone(&chrono::Local),
+        )
+        .unwrap();
+        let written = origin.date();
+        let (preview, keyboard) =
+            super::render_event_preview(&event, super::PreviewSource::Forward("Bob <3"), written);
+        assert!(preview.contains("<b>Dinner</b>"));
+        assert!(preview.contains("📍 <b>Location:</b> Luigi's"));
+        assert!(preview.contains("↪️ From Bob &lt;3"));
+        assert!(!preview.contains("already passed"));
+        assert_eq!(keyboard.inline_keyboard[0].len(), 2);
+        let (preview, _) = super::render_event_preview(
+            &event,
+            super::PreviewSource::Forward("Bob"),
+            written + chrono::Duration::days(30),
+        );
+        assert!(preview.contains("already passed"));
+
+        // Public channel posts link back to the post
+        let msg = forward(
+            r#"{ "type": "channel", "date": 1773000000, "message_id": 55,
+                 "chat": { "id": -1001234567890, "type": "channel",
+                           "title": "Meetups", "username": "meetups" },
+                 "author_signature": "Eve" }"#,
+            "Picnic tomorrow noon",
+        );
+        let (origin, text) = super::forwarded(&msg).unwrap();
+        assert_eq!(super::forward_source(origin), "Eve in Meetups");
+        assert!(super::forward_description(origin, text).contains("\nhttps://t.me/meetups/55\n"));
+
+        // Ordinary messages are not forwards
+        let plain: Message = serde_json::from_str(
+            r#"{
+            "message_id": 22,
+            "date": 1773000000,
+            "chat": { "id": 777777201, "type": "private", "first_name": "Ann" },
+            "text": "hello"
+        }"#,
         )
+        .unwrap();
+        assert!(super::forwarded(&plain).is_none());
+    }
+
+    #[test]
+    fn test_voice_and_poster_previews_quote_their_text() {
+        let transcript = "Lunch <team> tomorrow at noon";
+        let written = chrono::Local::now();
+        let event = crate::event_parser::parse_event_sentence(transcript, written).unwrap();
+        let (preview, keyboard) = super::render_event_preview(
+            &event,
+            super::PreviewSource::Voice(transcript),
+            written.with_timezone(&chrono::Utc),
+        );
+        assert!(preview.starts_with("🎙 <b>Create this event?</b>"));
+        assert!(preview.contains("\n🎙 Lunch &lt;team&gt; tomorrow at noon"));
+        let data: Vec<_> = keyboard.inline_keyboard[0]
+            .iter()
+            .map(|button| match &button.kind {
+                teloxide::types::InlineKeyboardButtonKind::CallbackData(data) => data.as_str(),
+                other => panic!("unexpected button {other:?}"),
+            })
+            .collect();
+        assert_eq!(data, ["voice:create", "voice:cancel"]);
+
+        // Telegram hands the preview back as plain text
+        let plain = "🎙 Create this event?\n\n📌 Lunch\n🎙 Lunch <team> tomorrow at noon";
+        assert_eq!(
+            super::previewed_quote(plain, super::VOICE_TRANSCRIPT_PREFIX),
+            Some(transcript)
+        );
+        assert_eq!(
+            super::previewed_quote("📌 Lunch", super::VOICE_TRANSCRIPT_PREFIX),
+            None
+        );
+
+        // Posters quote the sentence they were reduced to
+        let sentence = "JAZZ NIGHT 2026-03-13 20:00 at Blue Note";
+        let event = crate::event_parser::parse_event_sentence(sentence, written).unwrap();
+        let (preview, _) = super::render_event_preview(
+            &event,
+            super::PreviewSource::Poster(sentence),
+            written.with_timezone(&chrono::Utc),
+        );
+        assert!(preview.starts_with("🖼 <b>Create this event?</b>"));
+        assert!(preview.contains("📍 <b>Location:</b> Blue Note"));
+        let plain = format!("🖼 Create this event?\n\n📌 JAZZ NIGHT\n🖼 Read as: {sentence}");
+        assert_eq!(
+            super::previewed_quote(&plain, super::POSTER_SENTENCE_PREFIX),
+            Some(sentence)
+        );
+    }
+
+    #[test]
+    fn test_cancelled_event_card_is_struck_through() {
+        let start = chrono::Utc::now();
+        let mut event = crate::db::BotEvent {
+            id: uuid::Uuid::new_v4(),
+            summary: "Offsite <3".to_string(),
+            start: Some(start),
+            end: Some(start + chrono::Duration::hours(1)),
+            start_date: None,
+            end_date: None,
+            is_all_day: false,
+            is_floating: false,
+            location: None,
+            description: None,
+            reminder_note: None,
+            subscription: None,
+            cancelled: false,
+        };
+        let (text, _) = super::render_event_card("📌", &event, None);
+        assert!(!text.contains("Cancelled"));
+        assert!(!text.contains("working hours"));
+
+        event.cancelled = true;
+        let (text, _) = super::render_event_card("📌", &event, None);
+        assert!(text.contains("<s>Offsite &lt;3</s>"));
+        assert!(text.contains("❌ <b>Cancelled</b>"));
+    }
+
+    #[test]
+    fn test_event_card_lists_tags() {
+        let mut event = agenda_event("Offsite #Team #planning", "2026-11-02T08:00:00Z", 60);
+        let (text, _) = super::render_event_card("📌", &event, None);
+        assert!(text.contains("\n🏷 #team #planning"));
+
+        event.summary = "Offsite".to_string();
+        let (text, _) = super::render_event_card("📌", &event, None);
+        assert!(!text.contains("🏷"));
+    }
+
+    #[test]
+    fn test_event_card_warns_outside_working_hours() {
+        let berlin = televent_domain::Timezone::parse("Europe/Berlin").unwrap();
+        let hours =
+            televent_application::WorkingHours::parse("Mon-Fri 09:00-17:00", &berlin).unwrap();
+        // Monday 2 November 2026, 09:00-10:00 in Berlin
+        let event = agenda_event("Standup", "2026-11-02T08:00:00Z", 60);
+        let (text, _) = super::render_event_card("📌", &event, Some(&hours));
+        assert!(!text.contains("Outside your working hours"));
+
+        // 17:30 Berlin
+        let event = agenda_event("Late call", "2026-11-02T16:30:00Z", 30);
+        let (text, _) = super::render_event_card("📌", &event, Some(&hours));
+        assert!(text.contains("⚠️ Outside your working hours"));
+        let (text, _) = super::render_event_card("📌", &event, None);
+        assert!(!text.contains("Outside your working hours"));
+    }
+
+    #[test]
+    fn test_slot_args_and_rendering() {
+        let utc = televent_domain::Timezone::utc();
+        let search = super::parse_slot_args(&["45m", "09:00-17:00", "3d"], &utc).unwrap();
+        assert_eq!(search.duration, chrono::Duration::minutes(45));
+        assert_eq!(search.within, chrono::Duration::days(3));
+        assert!(search.working_hours.is_some());
+        assert!(super::parse_slot_args(&[], &utc).is_err());
+        assert!(super::parse_slot_args(&["soon"], &utc).is_err());
+
+        let start = chrono::DateTime::parse_from_rfc3339("2026-03-02T09:00:00Z")
+            .unwrap()
+            .to_utc();
+        let slot = televent_application::FreeSlot {
+            start,
+            end: start + chrono::Duration::minutes(90),
+            free_until: start + chrono::Duration::hours(3),
+        };
+        let berlin = televent_domain::Timezone::parse("Europe/Berlin").unwrap();
+        let text = super::render_free_slots(chrono::Duration::minutes(90), &[slot], &berlin);
+        assert!(text.contains("Free 1h30m slots"));
+        assert!(text.contains("Mon, Mar 02 10:00–11:30"));
+        assert!(text.contains("free until 13:00"));
+
+        let text = super::render_free_slots(chrono::Duration::minutes(30), &[], &berlin);
+        assert!(text.contains("No free 30m slot"));
+    }
+
+    #[test]
+    fn test_task_list_and_plan_rendering() {
+        let start = chrono::DateTime::parse_from_rfc3339("2026-03-02T09:00:00Z")
+            .unwrap()
+            .to_utc();
+        let berlin = televent_domain::Timezone::parse("Europe/Berlin").unwrap();
+        let task = |title: &str, minutes: i64| televent_application::TaskView {
+            id: uuid::Uuid::new_v4(),
+            title: title.to_string(),
+            duration: chrono::Duration::minutes(minutes),
+            block: None,
+            completed_at: None,
+            created_at: start,
+        };
+
+        let text = super::render_tasks(&[], &berlin);
+        assert!(text.contains("Nothing on your list"));
+
+        let mut report = task("Report <draft>", 90);
+        report.block = Some(televent_application::TaskBlockView {
+            event_id: uuid::Uuid::new_v4(),
+            start,
+            end: start + chrono::Duration::minutes(90),
+            confirmed: false,
+        });
+        let text = super::render_tasks(&[report.clone(), task("Inbox", 30)], &berlin);
+        assert!(text.contains("1. Report &lt;draft&gt; · 1h30m\n   🕓 Mon 02 Mar 10:00–11:30"));
+        assert!(text.contains("2. Inbox · 30m\n"));
+
+        let plan = televent_application::TaskPlan {
+            blocks: vec![televent_application::PlannedBlock {
+                task_id: report.id,
+                event_id: uuid::Uuid::new_v4(),
+                title: report.title.clone(),
+                start,
+                end: start + chrono::Duration::minutes(90),
+            }],
+            unplanned: vec![task("Taxes", 240)],
+        };
+        let (text, keyboard) = super::render_task_plan(&plan, &berlin);
+        assert!(text.contains("🕓 Mon 10:00–11:30 Report &lt;draft&gt;"));
+        assert!(text.contains("No room left for:</b>\n• Taxes · 4h"));
+        let buttons = &keyboard.unwrap().inline_keyboard[0];
+        assert_eq!(buttons.len(), 2);
+
+        let (text, keyboard) =
+            super::render_task_plan(&televent_application::TaskPlan::default(), &berlin);
+        assert!(text.contains("Every open task already has a block"));
+        assert!(keyboard.is_none());
+
+        assert_eq!(super::parse_plan_args(&[], &berlin).unwrap(), (0, None));
+        let (days, hours) = super::parse_plan_args(&["tomorrow", "09:00-17:00"], &berlin).unwrap();
+        assert_eq!(days, 1);
+        assert_eq!(hours.unwrap().timezone(), &berlin);
+        assert!(super::parse_plan_args(&["someday"], &berlin).is_err());
+    }
+
+    #[test]
+    fn test_focus_rules_parsing_and_rendering() {
+        let berlin = televent_domain::Timezone::parse("Europe/Berlin").unwrap();
+        let command =
+            super::parse_focus_rule(7, "3x90m", Some("09:00-17:00 Deep work"), &berlin).unwrap();
+        assert_eq!(command.blocks_per_week, 3);
+        assert_eq!(command.duration, chrono::Duration::minutes(90));
+        assert_eq!(command.working_hours.unwrap().to_string(), "09:00-17:00");
+        assert_eq!(command.title.as_deref(), Some("Deep work"));
+        let command = super::parse_focus_rule(7, "2x2h", None, &berlin).unwrap();
+        assert!(command.title.is_none() && command.working_hours.is_none());
+        assert!(super::parse_focus_rule(7, "2h", None, &berlin).is_err());
+        assert!(super::parse_focus_rule(7, "2x2h", Some("25:00-26:00"), &berlin).is_err());
+
+        assert!(super::render_focus_rules(&[], &berlin).contains("No focus time reserved"));
+
+        let start = chrono::DateTime::parse_from_rfc3339("2026-03-02T09:00:00Z")
+            .unwrap()
+            .to_utc();
+        let rule = televent_application::FocusRuleView {
+            id: uuid::Uuid::new_v4(),
+            title: "Deep <work>".to_string(),
+            blocks_per_week: 2,
+            duration: chrono::Duration::hours(2),
+            working_hours: televent_application::WorkingHours::parse("09:00-17:00", &berlin).ok(),
+            blocks: vec![televent_application::FocusBlockView {
+                event_id: uuid::Uuid::new_v4(),
+                start,
+                end: start + chrono::Duration::hours(2),
+                status: televent_domain::EventStatus::Tentative,
+            }],
+            created_at: start,
+        };
+        let text = super::render_focus_rules(&[rule], &berlin);
+        assert!(text.contains("1. <b>Deep &lt;work&gt;</b> · 2× 2h a week · 09:00-17:00\n"));
+        assert!(text.contains("   🕓 Mon 02 Mar 10:00–12:00\n"));
+    }
+
+    #[test]
+    fn test_stats_rendering() {
+        let from = chrono::DateTime::parse_from_rfc3339("2026-03-01T12:00:00Z")
+            .unwrap()
+            .to_utc();
+        let mut stats = televent_application::CalendarStats {
+            from,
+            to: from + chrono::Duration::days(7),
+            event_count: 6,
+            total_hours: 4.5,
+            average_daily_events: 6.0 / 7.0,
+            busiest_weekday: Some(chrono::Weekday::Tue),
+            weekdays: Vec::new(),
+            top_collaborators: vec![
+                televent_application::Collaborator {
+                    email: "tg_42@televent.internal".to_string(),
+                    telegram_username: Some("alice".to_string()),
+                    events: 3,
+                },
+                televent_application::Collaborator {
+                    email: "bob<x>@example.com".to_string(),
+                    telegram_username: None,
+                    events: 1,
+                },
+            ],
+        };
+        let berlin = televent_domain::Timezone::parse("Europe/Berlin").unwrap();
+        let text = super::render_stats(&stats, &berlin);
+        assert!(text.contains("(Mar 01 – Mar 08)"));
+        assert!(text.contains("6 events, 4.5 h in total"));
+        assert!(text.contains("0.9 events per day"));
+        assert!(text.contains("Busiest day: Tuesday"));
+        assert!(text.contains("1. @alice – 3"));
+        assert!(text.contains("2. bob&lt;x&gt;@example.com – 1"));
+
+        stats.event_count = 0;
+        let text = super::render_stats(&stats, &berlin);
+        assert!(text.contains("No meetings"));
+    }
+
+    fn agenda_event(summary: &str, start: &str, minutes: i64) -> crate::db::BotEvent {
+        let start = chrono::DateTime::parse_from_rfc3339(start)
+            .unwrap()
+            .to_utc();
+        crate::db::BotEvent {
+            id: uuid::Uuid::new_v4(),
+            summary: summary.to_string(),
+            start: Some(start),
+            end: Some(start + chrono::Duration::minutes(minutes)),
+            start_date: None,
+            end_date: None,
+            is_all_day: false,
+            is_floating: false,
+            location: None,
+            description: None,
+            reminder_note: None,
+            subscription: None,
+            cancelled: false,
+        }
+    }
+
+    #[test]
+    fn test_agenda_rendering() {
+        let berlin = televent_domain::Timezone::parse("Europe/Berlin").unwrap();
+        let monday = chrono::NaiveDate::from_ymd_opt(2026, 11, 2).unwrap();
+
+        let mut lunch = agenda_event("Lunch <1:1>", "2026-11-02T11:00:00Z", 60);
+        lunch.cancelled = true;
+        lunch.location = Some("Café & bar".to_string());
+        lunch.subscription = Some("Work".to_string());
+        // Floating times read the same in every zone
+        let mut gym = agenda_event("Gym", "2026-11-02T07:00:00Z", 60);
+        gym.is_floating = true;
+        // Began yesterday, so it leads the first day
+        let mut offsite = agenda_event("Offsite", "2026-11-01T00:00:00Z", 0);
+        offsite.is_all_day = true;
+        offsite.start_date = Some(monday.pred_opt().unwrap());
+        offsite.end_date = Some(monday.succ_opt().unwrap());
+        let events = [
+            lunch,
+            agenda_event("Standup", "2026-11-02T08:00:00Z", 15),
+            gym,
+            offsite,
+            agenda_event("Release", "2026-11-04T22:30:00Z", 120),
+        ];
+
+        assert_eq!(
+            super::render_agenda(
+                super::AgendaRange::Today,
+                monday,
+                &berlin,
+                &[],
+                &events[..4]
+            ),
+            "📅 <b>Today</b> · Mon, Nov 02 · Europe/Berlin\n\n\
+             📆 All day <b>Offsite</b>\n\
+             🕐 07:00–08:00 <b>Gym</b> (local time)\n\
+             🕐 09:00–09:15 <b>Standup</b>\n\
+             🕐 12:00–13:00 <b><s>Lunch &lt;1:1&gt;</s></b>\n   \
+             ❌ Cancelled\n   \
+             📍 Café &amp; bar\n   \
+             🔗 Work\n"
+        );
+        assert_eq!(
+            super::render_agenda(super::AgendaRange::Week, monday, &berlin, &[], &events[1..]),
+            "📅 <b>Next 7 days</b> · Europe/Berlin\n\n\
+             <b>Mon, Nov 02</b>\n\
+             📆 All day <b>Offsite</b>\n\
+             🕐 07:00–08:00 <b>Gym</b> (local time)\n\
+             🕐 09:00–09:15 <b>Standup</b>\n\
+             \n\
+             <b>Wed, Nov 04</b>\n\
+             🕐 23:30–Thu 01:30 <b>Release</b>\n"
+        );
+    }
+
+    #[test]
+    fn test_agenda_empty_states() {
+        let utc = televent_domain::Timezone::utc();
+        let tuesday = chrono::NaiveDate::from_ymd_opt(2026, 11, 3).unwrap();
+
+        assert_eq!(
+            super::render_agenda(super::AgendaRange::Tomorrow, tuesday, &utc, &[], &[]),
+            "📅 <b>Tomorrow</b> · Tue, Nov 03 · UTC\n\n\
+             Nothing planned for tomorrow.\n\n\
+             💡 Create one by sending me two lines:\n\
+             <pre>Dentist\ntomorrow 10am</pre>"
+        );
+        let today = super::render_agenda(super::AgendaRange::Today, tuesday, &utc, &[], &[]);
+        assert!(today.contains("<pre>Coffee with Alice\ntoday 5pm</pre>"));
+        let week = super::render_agenda(super::AgendaRange::Week, tuesday, &utc, &[], &[]);
+        assert!(week.starts_with("📅 <b>Next 7 days</b> · UTC\n\nNothing planned"));
+        assert!(week.contains("/subscribe"));
+        assert_eq!(
+            super::render_agenda(
+                super::AgendaRange::Week,
+                tuesday,
+                &utc,
+                &["work".to_string()],
+                &[]
+            ),
+            "📅 <b>Next 7 days</b> · #work · UTC\n\nNo events tagged #work."
+        );
+    }
+
+    #[test]
+    fn test_attendee_dashboard_counts_and_buttons() {
+        let event_id = uuid::Uuid::new_v4();
+        let attendee =
+            |email: &str, telegram_id, role: &str, status: &str, username: Option<&str>| {
+                crate::db::AttendeeInfo {
+                    email: email.to_string(),
+                    telegram_id,
+                    role: role.to_string(),
+                    status: status.to_string(),
+                    telegram_username: username.map(str::to_string),
+                }
+            };
+        let attendees = vec![
+            attendee(
+                "tg_1@televent.internal",
+                Some(1),
+                "ORGANIZER",
+                "ACCEPTED",
+                Some("owner"),
+            ),
+            attendee(
+                "tg_2@televent.internal",
+                Some(2),
+                "ATTENDEE",
+                "ACCEPTED",
+                Some("alice"),
+            ),
+            attendee(
+                "tg_3@televent.internal",
+                Some(3),
+                "ATTENDEE",
+                "NEEDS-ACTION",
+                Some("bob"),
+            ),
+            attendee("guest@example.com", None, "ATTENDEE", "NEEDS-ACTION", None),
+        ];
+
+        let (text, keyboard) =
+            super::render_attendee_dashboard(event_id, "Team <Sync>", &attendees);
+
+        assert!(text.contains("Team &lt;Sync&gt;"));
+        assert!(text.contains("✅ 1 accepted · ⏳ 2 pending"));
+        assert!(!text.contains("@owner"));
+        assert!(text.contains("⏳ @bob"));
+
+        // Only the pending Telegram user can be reminded; everyone can be removed
+        let buttons: Vec<_> = keyboard.inline_keyboard.iter().flatten().collect();
+        assert_eq!(buttons.len(), 4);
+        for button in &buttons {
+            let teloxide::types::InlineKeyboardButtonKind::CallbackData(data) = &button.kind else {
+                panic!("Expected callback button");
+            };
+            assert!(data.len() <= 64, "Callback data too long: {data}");
+        }
+        assert_eq!(
+            buttons
+                .iter()
+                .filter(|button| button.text.starts_with("🔁 Remind"))
+                .count(),
+            1
+        );
     }
 
     #[test]
-    fn test_command_descriptions() {
-        // Verify commands can be parsed
-        let cmds = Command::descriptions();
-        let cmds_str = cmds.to_string();
-        assert!(cmds_str.contains("start"), "Should contain /start command");
-        assert!(cmds_str.contains("list"), "Should contain /list command");
+    fn test_timezone_fix_range_is_days_in_the_old_zone() {
+        let command = |input: &str| {
+            let args = crate::commands::FIXTZ.parse(input).unwrap();
+            super::timezone_fix_command(42, &args)
+        };
+
+        let fix = command("Europe/London Europe/Berlin 2026-01-01..2026-06-30").unwrap();
+        assert_eq!(fix.from.as_str(), "Europe/London");
+        assert_eq!(fix.to.as_str(), "Europe/Berlin");
+        assert_eq!(fix.start.unwrap().to_rfc3339(), "2026-01-01T00:00:00+00:00");
+        // The last day is included; London is on summer time by then
+        assert_eq!(fix.end.unwrap().to_rfc3339(), "2026-06-30T23:00:00+00:00");
+
+        let fix = command("UTC Asia/Tokyo 2026-03-01..").unwrap();
+        assert!(fix.start.is_some() && fix.end.is_none());
+        let fix = command("UTC Asia/Tokyo").unwrap();
+        assert!(fix.start.is_none() && fix.end.is_none());
+
+        assert!(command("Mars/Base Asia/Tokyo").is_err());
+        assert!(command("UTC Asia/Tokyo 2026-03-01").is_err());
+        assert!(command("UTC Asia/Tokyo 2026-13-01..").is_err());
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_duplicate_button_copies_event(pool: PgPool) {
+        let db = bot_db(pool);
+        let telegram = MockTelegram::start().await;
+        let bot = telegram.bot();
+        let telegram_id = 555000111;
+        db.ensure_user_setup(telegram_id, Some("dupuser"))
+            .await
+            .unwrap();
+
+        let start = chrono::Utc::now() + chrono::Duration::days(1);
+        let event = db
+            .create_event(
+                telegram_id,
+                "dup-source@televent.bot",
+                "Standup",
+                None,
+                Some("Room 1"),
+                crate::event_parser::ParsedTiming::Timed {
+                    start,
+                    duration_minutes: 15,
+                },
+                "UTC",
+                true,
+            )
+            .await
+            .unwrap();
+
+        let (_, keyboard) = super::render_event_card("✅ <b>Event Created!</b>", &event, None);
+        let buttons: Vec<_> = keyboard.inline_keyboard.iter().flatten().collect();
+        assert_eq!(buttons.len(), 2);
+        let teloxide::types::InlineKeyboardButtonKind::CallbackData(data) = &buttons[1].kind else {
+            panic!("Expected callback button");
+        };
+        assert!(data.len() <= 64, "Callback data too long: {data}");
+
+        super::handle_duplicate_callback(
+            bot,
+            teloxide::types::CallbackQueryId("1".to_string()),
+            telegram_id,
+            None,
+            data,
+            db.clone(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(telegram.calls_to("answerCallbackQuery").len(), 1);
+
+        let events = db.get_all_events_for_user(telegram_id).await.unwrap();
+        assert_eq!(events.len(), 2);
+        let copy = events
+            .iter()
+            .find(|copy| copy.id != event.id)
+            .expect("copy created");
+        assert_eq!(copy.summary, "Standup");
+        assert_eq!(copy.location.as_deref(), Some("Room 1"));
+        assert_eq!(
+            copy.start.unwrap() - event.start.unwrap(),
+            chrono::Duration::days(7)
+        );
+
+        // Someone else's event can't be copied
+        assert!(
+            db.duplicate_event(telegram_id + 1, event.id, 1)
+                .await
+                .unwrap()
+                .is_none()
+        );
     }
 
     #[sqlx::test(migrations = "../migrations")]
     async fn test_handle_start(pool: PgPool) {
         let db = bot_db(pool);
-        let bot = Bot::new("123:fake_token");
+        let telegram = MockTelegram::start().await;
+        let bot = telegram.bot();
 
         let json = r#"{
             "message_id": 1,
@@ -958,8 +5668,20 @@ mod tests {
 
         let msg: Message = serde_json::from_str(json).unwrap();
 
-        // Handler should attempt to send message and fail, but ensure user setup first
-        let _ = super::handle_start(bot, msg, db.clone()).await;
+        super::handle_start(bot, msg, db.clone()).await.unwrap();
+
+        // Welcome, then the timezone question: location button and zone picker
+        let sent = telegram.calls_to("sendMessage");
+        assert_eq!(sent.len(), 3);
+        assert!(sent[0].text().starts_with("Welcome to Televent!"));
+        assert_eq!(sent[0].parse_mode(), Some("HTML"));
+        assert_eq!(sent[0].params["chat_id"], 123456789);
+        assert!(sent[1].params["reply_markup"]["keyboard"].is_array());
+        assert!(
+            sent[2]
+                .callback_data()
+                .contains(&"tz:Europe/Berlin".to_string())
+        );
 
         // Verify user persistence
         let user = db.find_user_by_username("testuser").await.unwrap();
@@ -970,7 +5692,8 @@ mod tests {
     #[sqlx::test(migrations = "../migrations")]
     async fn test_handle_text_message_create_event(pool: PgPool) {
         let db = bot_db(pool);
-        let bot = Bot::new("123:fake_token");
+        let telegram = MockTelegram::start().await;
+        let bot = telegram.bot();
 
         let telegram_id = 123456789;
         db.ensure_user_setup(telegram_id, Some("testuser"))
@@ -997,19 +5720,32 @@ mod tests {
 
         let msg: Message = serde_json::from_str(json).unwrap();
 
-        // Handler should create event in DB then fail to send response
-        let _ = super::handle_text_message(bot, msg, db.clone()).await;
+        super::handle_text_message(bot, msg, db.clone())
+            .await
+            .unwrap();
 
         // Verify event creation
         let events = db.get_all_events_for_user(telegram_id).await.unwrap();
         assert_eq!(events.len(), 1);
         assert_eq!(events[0].summary, "Team Meeting");
+
+        // The card offers copies of the new event
+        let sent = telegram.sent_message();
+        assert!(sent.text().contains("Event Created"));
+        assert_eq!(sent.parse_mode(), Some("HTML"));
+        let event_id = events[0].id.simple().to_string();
+        assert!(
+            sent.callback_data()
+                .iter()
+                .all(|data| data.starts_with("dup:") && data.contains(&event_id))
+        );
     }
 
     #[sqlx::test(migrations = "../migrations")]
     async fn test_handle_list(pool: PgPool) {
         let db = bot_db(pool);
-        let bot = Bot::new("123:fake_token");
+        let telegram = MockTelegram::start().await;
+        let bot = telegram.bot();
 
         let telegram_id = 987654321;
         db.ensure_user_setup(telegram_id, Some("listuser"))
@@ -1036,15 +5772,205 @@ mod tests {
 
         let msg: Message = serde_json::from_str(json).unwrap();
 
-        // Handler should query DB then fail to send
-        let _ = super::handle_list(bot, msg, db).await;
+        super::handle_agenda(bot, msg, db, super::AgendaRange::Week)
+            .await
+            .unwrap();
+
+        let sent = telegram.sent_message();
+        assert!(sent.text().starts_with("📅 <b>Next 7 days</b>"));
+        assert_eq!(sent.parse_mode(), Some("HTML"));
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_handle_list_by_tag(pool: PgPool) {
+        let db = bot_db(pool);
+        let telegram = MockTelegram::start().await;
+        let bot = telegram.bot();
+
+        let telegram_id = 987654323;
+        db.ensure_user_setup(telegram_id, Some("taguser"))
+            .await
+            .unwrap();
+        let start = Utc::now() + Duration::days(1);
+        for (uid, summary) in [("tagged", "Sprint review #Work"), ("untagged", "Dentist")] {
+            db.create_event(
+                telegram_id,
+                uid,
+                summary,
+                None,
+                None,
+                ParsedTiming::Timed {
+                    start,
+                    duration_minutes: 30,
+                },
+                "UTC",
+                true,
+            )
+            .await
+            .unwrap();
+        }
+
+        let msg: Message = serde_json::from_str(
+            r#"{
+            "message_id": 3,
+            "date": 1600000000,
+            "chat": {"id": 987654323, "type": "private", "first_name": "Tag"},
+            "from": {"id": 987654323, "is_bot": false, "first_name": "Tag"},
+            "text": "/list #work"
+        }"#,
+        )
+        .unwrap();
+        super::handle_agenda(bot, msg, db, super::AgendaRange::Week)
+            .await
+            .unwrap();
+
+        let sent = telegram.sent_message();
+        assert!(sent.text().starts_with("📅 <b>Next 7 days</b> · #work"));
+        assert!(sent.text().contains("Sprint review #Work"));
+        assert!(!sent.text().contains("Dentist"));
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_agenda_quick_actions(pool: PgPool) {
+        let db = bot_db(pool);
+        let telegram = MockTelegram::start().await;
+        let bot = telegram.bot();
+
+        let telegram_id = 987654322;
+        db.ensure_user_setup(telegram_id, Some("agendauser"))
+            .await
+            .unwrap();
+        let start = Utc::now() + Duration::days(1);
+        let event = db
+            .create_event(
+                telegram_id,
+                "agenda-quick-action",
+                "Standup",
+                None,
+                None,
+                ParsedTiming::Timed {
+                    start,
+                    duration_minutes: 15,
+                },
+                "UTC",
+                true,
+            )
+            .await
+            .unwrap();
+
+        let msg: Message = serde_json::from_str(
+            r#"{
+            "message_id": 3,
+            "date": 1600000000,
+            "chat": {"id": 987654322, "type": "private", "first_name": "Agenda"},
+            "from": {"id": 987654322, "is_bot": false, "first_name": "Agenda"},
+            "text": "/agenda"
+        }"#,
+        )
+        .unwrap();
+        super::handle_agenda(bot.clone(), msg, db.clone(), super::AgendaRange::Week)
+            .await
+            .unwrap();
+
+        // One row of signed buttons for the event
+        let sent = telegram.sent_message();
+        let keyboard = sent.keyboard().unwrap();
+        assert_eq!(keyboard.inline_keyboard.len(), 1);
+        let labels: Vec<&str> = keyboard.inline_keyboard[0]
+            .iter()
+            .map(|button| button.text.as_str())
+            .collect();
+        assert_eq!(
+            labels,
+            [
+                format!("📝 {} Standup", start.format("%a %H:%M")).as_str(),
+                "❌",
+                "👥",
+                "🔔"
+            ]
+        );
+        let key = bot.token().as_bytes();
+        let actions: Vec<_> = sent
+            .callback_data()
+            .iter()
+            .map(|data| quick_actions::decode(key, telegram_id, data).unwrap())
+            .collect();
+        assert_eq!(
+            actions,
+            [
+                (QuickAction::Edit, event.id),
+                (QuickAction::Cancel, event.id),
+                (QuickAction::Attendees, event.id),
+                (QuickAction::Remind, event.id),
+            ]
+        );
+
+        // Someone else pressing the button gets nowhere
+        let callback_id = || CallbackQueryId("1".to_string());
+        let cancel = sent.callback_data()[1].clone();
+        super::handle_quick_action_callback(
+            bot.clone(),
+            callback_id(),
+            telegram_id + 1,
+            None,
+            &cancel,
+            db.clone(),
+        )
+        .await
+        .unwrap();
+        let answers = telegram.calls_to("answerCallbackQuery");
+        assert_eq!(answers[0].params["show_alert"], true);
+        assert_eq!(telegram.calls_to("sendMessage").len(), 1);
+
+        // Cancel asks first and changes nothing
+        super::handle_quick_action_callback(
+            bot.clone(),
+            callback_id(),
+            telegram_id,
+            None,
+            &cancel,
+            db.clone(),
+        )
+        .await
+        .unwrap();
+        let prompt = telegram.calls_to("sendMessage").pop().unwrap();
+        assert!(prompt.text().starts_with("❌ Cancel <b>Standup</b>?"));
+        assert!(
+            !db.get_event(telegram_id, event.id)
+                .await
+                .unwrap()
+                .unwrap()
+                .cancelled
+        );
 
-        // No checks other than it ran without panic (and presumably queried DB)
+        let confirm = prompt.callback_data()[0].clone();
+        assert_eq!(
+            quick_actions::decode(key, telegram_id, &confirm),
+            Some((QuickAction::ConfirmCancel, event.id))
+        );
+        super::handle_quick_action_callback(
+            bot.clone(),
+            callback_id(),
+            telegram_id,
+            None,
+            &confirm,
+            db.clone(),
+        )
+        .await
+        .unwrap();
+        assert!(
+            db.get_event(telegram_id, event.id)
+                .await
+                .unwrap()
+                .unwrap()
+                .cancelled
+        );
     }
 
     #[sqlx::test(migrations = "../migrations")]
     async fn test_handle_help(_pool: PgPool) {
-        let bot = Bot::new("123:fake_token");
+        let telegram = MockTelegram::start().await;
+        let bot = telegram.bot();
 
         let json = r#"{
             "message_id": 10,
@@ -1066,15 +5992,19 @@ mod tests {
 
         let msg: Message = serde_json::from_str(json).unwrap();
 
-        // Should just send help message and not fail
-        let result = super::handle_help(bot, msg).await;
-        assert!(result.is_ok() || result.is_err()); // Will fail to send but that's OK
+        super::handle_help(bot, msg).await.unwrap();
+
+        let sent = telegram.sent_message();
+        assert!(sent.text().starts_with("<b>Televent Commands</b>"));
+        assert!(sent.text().contains("/device"));
+        assert_eq!(sent.parse_mode(), Some("HTML"));
     }
 
     #[sqlx::test(migrations = "../migrations")]
     async fn test_handle_export(pool: PgPool) {
         let db = bot_db(pool);
-        let bot = Bot::new("123:fake_token");
+        let telegram = MockTelegram::start().await;
+        let bot = telegram.bot();
 
         let telegram_id = 222222222;
         db.ensure_user_setup(telegram_id, Some("exportuser"))
@@ -1094,6 +6024,7 @@ mod tests {
                 duration_minutes: 60,
             },
             "UTC",
+            true,
         )
         .await
         .unwrap();
@@ -1118,14 +6049,26 @@ mod tests {
 
         let msg: Message = serde_json::from_str(json).unwrap();
 
-        let _ = super::handle_export(bot, msg, db).await;
-        // Handler will query DB and try to send file; we just verify it runs
+        super::handle_export(bot, msg, db).await.unwrap();
+
+        let sent = telegram.calls_to("sendDocument");
+        assert_eq!(sent.len(), 1);
+        let document = &sent[0].params["document"];
+        assert_eq!(document["file_name"], "calendar.ics");
+        assert!(
+            document["content"]
+                .as_str()
+                .unwrap()
+                .contains("SUMMARY:Export Test Event")
+        );
+        assert_eq!(sent[0].params["chat_id"], "222222222");
     }
 
     #[sqlx::test(migrations = "../migrations")]
     async fn test_handle_device_add(pool: PgPool) {
         let db = bot_db(pool);
-        let bot = Bot::new("123:fake_token");
+        let telegram = MockTelegram::start().await;
+        let bot = telegram.bot();
 
         let telegram_id = 333333333;
         db.ensure_user_setup(telegram_id, Some("deviceuser"))
@@ -1152,18 +6095,31 @@ mod tests {
 
         let msg: Message = serde_json::from_str(json).unwrap();
 
-        let _ = super::handle_device(bot, msg, db.clone()).await;
+        super::handle_device(bot, msg, db.clone()).await.unwrap();
 
         // Verify device was created
         let devices = db.list_device_passwords(telegram_id).await.unwrap();
         assert_eq!(devices.len(), 1);
         assert_eq!(devices[0].name, "MyPhone");
+
+        let sent = telegram.sent_message();
+        assert!(
+            sent.text()
+                .starts_with("✅ <b>Device Password Created!</b>")
+        );
+        assert!(sent.text().contains("Device: MyPhone"));
+        assert!(
+            sent.text()
+                .contains(&format!("Username: <code>{telegram_id}</code>"))
+        );
+        assert_eq!(sent.parse_mode(), Some("HTML"));
     }
 
     #[sqlx::test(migrations = "../migrations")]
     async fn test_handle_device_list(pool: PgPool) {
         let db = bot_db(pool);
-        let bot = Bot::new("123:fake_token");
+        let telegram = MockTelegram::start().await;
+        let bot = telegram.bot();
 
         let telegram_id = 444444444;
         db.ensure_user_setup(telegram_id, Some("listdevuser"))
@@ -1193,12 +6149,111 @@ mod tests {
 
         let msg: Message = serde_json::from_str(json).unwrap();
 
-        let _ = super::handle_device(bot, msg, db).await;
+        super::handle_device(bot, msg, db.clone()).await.unwrap();
+
+        let devices = db.list_device_passwords(telegram_id).await.unwrap();
+        let sent = telegram.sent_message();
+        assert!(sent.text().contains("<b>Device1</b>"));
+        assert_eq!(sent.parse_mode(), Some("HTML"));
+        assert_eq!(
+            sent.callback_data(),
+            [
+                format!("dev:revoke:{}", devices[0].id.inner().simple()),
+                "dev:create".to_string()
+            ]
+        );
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_device_buttons_confirm_and_refresh(pool: PgPool) {
+        let db = bot_db(pool);
+        let telegram = MockTelegram::start().await;
+        let bot = telegram.bot();
+
+        let telegram_id = 444444555;
+        db.ensure_user_setup(telegram_id, Some("buttondevuser"))
+            .await
+            .unwrap();
+        db.generate_device_password(telegram_id, "Tablet")
+            .await
+            .unwrap();
+        let device_id = db.list_device_passwords(telegram_id).await.unwrap()[0].id;
+
+        let list: Message = serde_json::from_str(
+            r#"{
+            "message_id": 7,
+            "date": 1600000000,
+            "chat": {"id": 444444555, "type": "private", "first_name": "Dev"},
+            "from": {"id": 42, "is_bot": true, "first_name": "Televent"},
+            "text": "Your Devices"
+        }"#,
+        )
+        .unwrap();
+        let message = || {
+            Some(teloxide::types::MaybeInaccessibleMessage::Regular(
+                Box::new(list.clone()),
+            ))
+        };
+        let callback_id = || teloxide::types::CallbackQueryId("1".to_string());
+
+        // Revoke asks first and changes nothing
+        let revoke = format!("dev:revoke:{}", device_id.inner().simple());
+        let confirm = format!("dev:confirm:{}", device_id.inner().simple());
+        super::handle_device_callback(
+            bot.clone(),
+            callback_id(),
+            telegram_id,
+            message(),
+            &revoke,
+            db.clone(),
+        )
+        .await
+        .unwrap();
+        let edits = telegram.calls_to("editMessageText");
+        assert_eq!(edits.len(), 1);
+        assert_eq!(edits[0].params["message_id"], 7);
+        assert!(edits[0].text().starts_with("🗑 Revoke <b>Tablet</b>?"));
+        assert_eq!(
+            edits[0].callback_data(),
+            [
+                format!("dev:confirm:{}", device_id.inner().simple()),
+                "dev:list".to_string()
+            ]
+        );
+        assert_eq!(
+            db.list_device_passwords(telegram_id).await.unwrap().len(),
+            1
+        );
+
+        // Confirming revokes and turns the message back into the list
+        super::handle_device_callback(
+            bot,
+            callback_id(),
+            telegram_id,
+            message(),
+            &confirm,
+            db.clone(),
+        )
+        .await
+        .unwrap();
+        assert!(
+            db.list_device_passwords(telegram_id)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        let answers = telegram.calls_to("answerCallbackQuery");
+        assert_eq!(answers[1].text(), "🗑 Revoked Tablet");
+        let edits = telegram.calls_to("editMessageText");
+        assert_eq!(edits.len(), 2);
+        assert!(edits[1].text().contains("don't have any device passwords"));
+        assert_eq!(edits[1].callback_data(), ["dev:create"]);
     }
 
     #[sqlx::test(migrations = "../migrations")]
     async fn test_handle_cancel(_pool: PgPool) {
-        let bot = Bot::new("123:fake_token");
+        let telegram = MockTelegram::start().await;
+        let bot = telegram.bot();
 
         let json = r#"{
             "message_id": 14,
@@ -1220,13 +6275,18 @@ mod tests {
 
         let msg: Message = serde_json::from_str(json).unwrap();
 
-        let result = super::handle_cancel(bot, msg).await;
-        assert!(result.is_ok() || result.is_err());
+        super::handle_cancel(bot, msg).await.unwrap();
+
+        let sent = telegram.sent_message();
+        assert!(sent.text().starts_with("❌ <b>Cancel Event</b>"));
+        assert_eq!(sent.params["chat_id"], 555555555);
+        assert_eq!(sent.parse_mode(), Some("HTML"));
     }
 
     #[sqlx::test(migrations = "../migrations")]
     async fn test_handle_delete_account(_pool: PgPool) {
-        let bot = Bot::new("123:fake_token");
+        let telegram = MockTelegram::start().await;
+        let bot = telegram.bot();
 
         let json = r#"{
             "message_id": 15,
@@ -1248,14 +6308,19 @@ mod tests {
 
         let msg: Message = serde_json::from_str(json).unwrap();
 
-        let result = super::handle_delete_account(bot, msg).await;
-        assert!(result.is_ok() || result.is_err());
+        super::handle_delete_account(bot, msg).await.unwrap();
+
+        let sent = telegram.sent_message();
+        assert!(sent.text().starts_with("⚠️ <b>Delete Account</b>"));
+        assert!(sent.text().contains("CANNOT be undone"));
+        assert_eq!(sent.parse_mode(), Some("HTML"));
     }
 
     #[sqlx::test(migrations = "../migrations")]
     async fn test_handle_invite(pool: PgPool) {
         let db = bot_db(pool);
-        let bot = Bot::new("123:fake_token");
+        let telegram = MockTelegram::start().await;
+        let bot = telegram.bot();
 
         let organizer_id = 777777777;
         let attendee_id = 888888888;
@@ -1281,6 +6346,7 @@ mod tests {
                     duration_minutes: 60,
                 },
                 "UTC",
+                true,
             )
             .await
             .unwrap();
@@ -1308,17 +6374,180 @@ mod tests {
 
         let msg: Message = serde_json::from_str(&json).unwrap();
 
-        let _ = super::handle_invite(bot, msg, db.clone()).await;
+        super::handle_invite(bot, msg, db.clone()).await.unwrap();
 
         // Verify invite was created
         let invites = db.get_pending_invites(attendee_id).await.unwrap();
         assert_eq!(invites.len(), 1);
+
+        let sent = telegram.sent_message();
+        assert_eq!(
+            sent.text(),
+            "📨 Invites for <b>Party Event</b>\n\n✅ Invited: @attendee"
+        );
+        assert_eq!(sent.parse_mode(), Some("HTML"));
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_handle_invite_multiple_people(pool: PgPool) {
+        let db = bot_db(pool);
+        let telegram = MockTelegram::start().await;
+        let bot = telegram.bot();
+
+        let organizer_id = 777777001;
+        let first_id = 777777002;
+        let second_id = 777777003;
+
+        db.ensure_user_setup(organizer_id, Some("bulk_host"))
+            .await
+            .unwrap();
+        db.ensure_user_setup(first_id, Some("bulk_alice"))
+            .await
+            .unwrap();
+        db.ensure_user_setup(second_id, Some("bulk_bob"))
+            .await
+            .unwrap();
+
+        let event = db
+            .create_event(
+                organizer_id,
+                &uuid::Uuid::new_v4().to_string(),
+                "Team Dinner",
+                None,
+                None,
+                crate::event_parser::ParsedTiming::Timed {
+                    start: chrono::Utc::now(),
+                    duration_minutes: 90,
+                },
+                "UTC",
+                true,
+            )
+            .await
+            .unwrap();
+
+        let json = format!(
+            r#"{{
+            "message_id": 17,
+            "date": 1600000000,
+            "chat": {{
+                "id": 777777001,
+                "type": "private",
+                "username": "bulk_host",
+                "first_name": "Host"
+            }},
+            "from": {{
+                "id": 777777001,
+                "is_bot": false,
+                "first_name": "Host",
+                "username": "bulk_host"
+            }},
+            "text": "/invite {} @bulk_alice @bulk_bob @bulk_alice @nobody guest@example.com"
+        }}"#,
+            event.id
+        );
+
+        let msg: Message = serde_json::from_str(&json).unwrap();
+
+        super::handle_invite(bot, msg, db.clone()).await.unwrap();
+
+        let sent = telegram.sent_message();
+        assert!(
+            sent.text()
+                .contains("✅ Invited: @bulk_alice, @bulk_bob, guest@example.com")
+        );
+        assert!(sent.text().contains("❌ Not found: @nobody"));
+
+        assert_eq!(db.get_pending_invites(first_id).await.unwrap().len(), 1);
+        assert_eq!(db.get_pending_invites(second_id).await.unwrap().len(), 1);
+        let attendees = db.get_event_attendees(event.id).await.unwrap();
+        assert!(
+            attendees
+                .iter()
+                .any(|attendee| attendee.email == "guest@example.com")
+        );
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_handle_invite_text_mention(pool: PgPool) {
+        let db = bot_db(pool);
+        let telegram = MockTelegram::start().await;
+        let bot = telegram.bot();
+
+        let organizer_id = 777777101;
+        let attendee_id = 777777102;
+        db.ensure_user_setup(organizer_id, Some("mention_host"))
+            .await
+            .unwrap();
+        // No username stored, so only the id in the mention finds them
+        db.ensure_user_setup(attendee_id, None).await.unwrap();
+
+        let event = db
+            .create_event(
+                organizer_id,
+                &uuid::Uuid::new_v4().to_string(),
+                "Offsite",
+                None,
+                None,
+                crate::event_parser::ParsedTiming::Timed {
+                    start: chrono::Utc::now(),
+                    duration_minutes: 60,
+                },
+                "UTC",
+                true,
+            )
+            .await
+            .unwrap();
+
+        let json = format!(
+            r#"{{
+            "message_id": 18,
+            "date": 1600000000,
+            "chat": {{
+                "id": 777777101,
+                "type": "private",
+                "first_name": "Host"
+            }},
+            "from": {{
+                "id": 777777101,
+                "is_bot": false,
+                "first_name": "Host",
+                "username": "mention_host"
+            }},
+            "text": "/invite {} Jane Doe",
+            "entities": [{{
+                "type": "text_mention",
+                "offset": 45,
+                "length": 8,
+                "user": {{ "id": 777777102, "is_bot": false, "first_name": "Jane" }}
+            }}]
+        }}"#,
+            event.id
+        );
+        let msg: Message = serde_json::from_str(&json).unwrap();
+
+        let (text, names) = super::with_text_mentions(&msg);
+        assert_eq!(
+            text.split_whitespace().collect::<Vec<_>>()[2..],
+            ["tg://user?id=777777102"]
+        );
+        assert_eq!(names, [(attendee_id, "Jane Doe".to_string())]);
+
+        super::handle_invite(bot, msg, db.clone()).await.unwrap();
+
+        assert_eq!(db.get_pending_invites(attendee_id).await.unwrap().len(), 1);
+        assert!(
+            telegram
+                .sent_message()
+                .text()
+                .contains("✅ Invited: Jane Doe")
+        );
     }
 
     #[sqlx::test(migrations = "../migrations")]
     async fn test_handle_rsvp(pool: PgPool) {
         let db = bot_db(pool);
-        let bot = Bot::new("123:fake_token");
+        let telegram = MockTelegram::start().await;
+        let bot = telegram.bot();
 
         let organizer_id = 999999999;
         let attendee_id = 1010101010;
@@ -1344,6 +6573,7 @@ mod tests {
                     duration_minutes: 60,
                 },
                 "UTC",
+                true,
             )
             .await
             .unwrap();
@@ -1372,7 +6602,22 @@ mod tests {
         }"#;
 
         let msg_list: Message = serde_json::from_str(json_list).unwrap();
-        let _ = super::handle_rsvp(bot.clone(), msg_list, db.clone()).await;
+        super::handle_rsvp(bot.clone(), msg_list, db.clone())
+            .await
+            .unwrap();
+
+        let listed = telegram.sent_message();
+        assert!(
+            listed
+                .text()
+                .starts_with("📨 <b>Pending Invitations</b> (1)")
+        );
+        assert!(listed.text().contains("<b>RSVP Event</b>"));
+        assert!(
+            listed
+                .text()
+                .contains(&format!("<code>/rsvp {} accept</code>", event.id))
+        );
 
         // Test accepting invite
         let json_accept = format!(
@@ -1397,7 +6642,16 @@ mod tests {
         );
 
         let msg_accept: Message = serde_json::from_str(&json_accept).unwrap();
-        let _ = super::handle_rsvp(bot, msg_accept, db.clone()).await;
+        super::handle_rsvp(bot, msg_accept, db.clone())
+            .await
+            .unwrap();
+
+        let sent = telegram.calls_to("sendMessage");
+        assert_eq!(sent.len(), 2);
+        assert_eq!(
+            sent[1].text(),
+            "✅ Your response has been recorded: <b>ACCEPTED</b>"
+        );
 
         // Verify RSVP was updated
         let invites_after = db.get_pending_invites(attendee_id).await.unwrap();
@@ -1407,7 +6661,8 @@ mod tests {
     #[sqlx::test(migrations = "../migrations")]
     async fn test_handle_text_message_invalid(pool: PgPool) {
         let db = bot_db(pool);
-        let bot = Bot::new("123:fake_token");
+        let telegram = MockTelegram::start().await;
+        let bot = telegram.bot();
 
         let telegram_id = 1111111111;
         db.ensure_user_setup(telegram_id, Some("invaliduser"))
@@ -1434,14 +6689,27 @@ mod tests {
         }"#;
 
         let msg: Message = serde_json::from_str(json).unwrap();
-        let _ = super::handle_text_message(bot, msg, db).await;
-        // Should handle gracefully and not create event
+        super::handle_text_message(bot, msg, db.clone())
+            .await
+            .unwrap();
+
+        // Answers with the format instead of creating an event
+        let sent = telegram.sent_message();
+        assert!(sent.text().starts_with("To create an event"));
+        assert_eq!(sent.parse_mode(), None);
+        assert!(
+            db.get_all_events_for_user(telegram_id)
+                .await
+                .unwrap()
+                .is_empty()
+        );
     }
 
     #[sqlx::test(migrations = "../migrations")]
     async fn test_handle_text_message_with_location(pool: PgPool) {
         let db = bot_db(pool);
-        let bot = Bot::new("123:fake_token");
+        let telegram = MockTelegram::start().await;
+        let bot = telegram.bot();
 
         let telegram_id = 1212121212;
         db.ensure_user_setup(telegram_id, Some("locuser"))
@@ -1467,7 +6735,10 @@ mod tests {
         }"#;
 
         let msg: Message = serde_json::from_str(json).unwrap();
-        let _ = super::handle_text_message(bot, msg, db.clone()).await;
+        super::handle_text_message(bot, msg, db.clone())
+            .await
+            .unwrap();
+        assert!(telegram.sent_message().text().contains("Conference Room"));
 
         // Verify event with location
         let events = db.get_all_events_for_user(telegram_id).await.unwrap();
@@ -1478,7 +6749,7 @@ mod tests {
 
     #[test]
     fn test_html_escaping_utility() {
-        use teloxide::utils::html::escape;
+        use televent_domain::telegram_html::escape;
         let input = "Me & You <script>";
         let escaped = escape(input);
         assert_eq!(escaped, "Me &amp; You &lt;script&gt;");
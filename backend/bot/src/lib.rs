@@ -2,14 +2,26 @@
 //!
 //! This crate provides Telegram bot functionality for managing calendars.
 
+pub mod args;
 mod commands;
 pub mod db;
 mod event_parser;
+pub mod flood;
 mod handlers;
+pub mod ocr;
+mod quick_actions;
+pub mod setup;
+#[cfg(test)]
+mod testing;
+pub mod usernames;
+pub mod voice;
 
 use anyhow::Result;
 use commands::Command;
 use db::BotDb;
+use flood::{ChatMigration, FloodGuard, Throttled};
+use setup::BotSetupConfig;
+use televent_application::Metric;
 use teloxide::RequestError;
 use teloxide::dispatching::{HandlerExt, UpdateFilterExt, UpdateHandler};
 use teloxide::dptree;
@@ -20,15 +32,72 @@ use teloxide::prelude::*;
 /// This function creates the dispatcher handler tree that routes messages to appropriate handlers.
 /// Extracted into a separate function to enable testing with teloxide_tests.
 pub fn build_handler_tree() -> UpdateHandler<RequestError> {
-    Update::filter_message()
+    let messages = Update::filter_message()
+        // A group upgraded to a supergroup moves to a new chat id
+        .branch(
+            dptree::filter_map(|msg: Message| ChatMigration::of(&msg))
+                .endpoint(handle_chat_migration),
+        )
+        // Forwarded messages are offered as events, never run as commands
+        .branch(
+            dptree::filter(|msg: Message| handlers::forwarded(&msg).is_some())
+                .endpoint(handle_forwarded_message),
+        )
         // First try to handle as a command
         .branch(
             dptree::entry()
                 .filter_command::<Command>()
+                // Usage counts, before read-only mode or a handler can bail
+                .inspect(|cmd: Command, db: BotDb| {
+                    db.analytics().record(Metric::command(cmd.name()));
+                })
                 .endpoint(handle_command),
         )
+        // Shared locations answer the onboarding timezone question
+        .branch(dptree::filter(|msg: Message| msg.location().is_some()).endpoint(handle_location))
+        // Replies to the bot's question for a device name create the device
+        .branch(
+            dptree::filter_map(|msg: Message| msg.text().and(handlers::device_name_target(&msg)))
+                .endpoint(handle_device_name_reply),
+        )
+        // Replies to an event card edit that event
+        .branch(
+            dptree::filter_map(|msg: Message| msg.text().and(handlers::edit_target(&msg)))
+                .endpoint(handle_edit_reply),
+        )
+        // Voice notes are transcribed and offered as events when a backend
+        // is configured
+        .branch(
+            dptree::filter(|msg: Message, voice_notes: voice::VoiceNotes| {
+                msg.voice().is_some() && voice_notes.enabled()
+            })
+            .endpoint(handle_voice_message),
+        )
+        // Photos are read for posters when an OCR backend is configured
+        .branch(
+            dptree::filter(|msg: Message, posters: ocr::Posters| {
+                msg.photo().is_some() && posters.enabled()
+            })
+            .endpoint(handle_photo_message),
+        )
+        // Past the per-chat rate, event creation is refused before any
+        // database work
+        .branch(
+            dptree::filter_map_async(|msg: Message, guard: FloodGuard, db: BotDb| async move {
+                if !flood::creates_event(&msg) {
+                    return None;
+                }
+                guard.check(&msg, &db).await.err()
+            })
+            .endpoint(handle_throttled),
+        )
         // Then handle as text message (for event creation)
-        .branch(dptree::filter(|msg: Message| msg.text().is_some()).endpoint(handle_message))
+        .branch(dptree::filter(|msg: Message| msg.text().is_some()).endpoint(handle_message));
+
+    dptree::entry()
+        // Usernames change; every update refreshes the sender's
+        .inspect_async(usernames::refresh_sender)
+        .branch(messages)
         // Handle callback queries
         .branch(Update::filter_callback_query().endpoint(handle_callback_query))
 }
@@ -41,15 +110,32 @@ pub fn build_handler_tree() -> UpdateHandler<RequestError> {
 /// # Arguments
 /// * `bot_db` - Bot application-service facade
 /// * `bot_token` - Telegram bot token for authentication
-pub async fn run_bot(bot_db: BotDb, bot_token: String) -> Result<()> {
+/// * `setup` - Commands and menu button to register before dispatching
+pub async fn run_bot(
+    bot_db: BotDb,
+    bot_token: String,
+    setup: BotSetupConfig,
+    transcription: Option<voice::TranscriptionConfig>,
+    ocr: Option<ocr::OcrConfig>,
+) -> Result<()> {
+    let voice_notes = voice::VoiceNotes::new(transcription.as_ref())?;
+    let posters = ocr::Posters::new(ocr.as_ref())?;
+
     // Initialize bot
     let bot = Bot::new(bot_token);
+    setup::apply(&bot, &setup).await;
     tracing::info!("Bot initialized, starting dispatcher");
 
     // Create dispatcher with database dependency
     // Note: NOT using enable_ctrlc_handler() - shutdown is managed by the caller
     Dispatcher::builder(bot, build_handler_tree())
-        .dependencies(dptree::deps![bot_db])
+        .dependencies(dptree::deps![
+            bot_db,
+            FloodGuard::default(),
+            usernames::KnownUsernames::default(),
+            voice_notes,
+            posters
+        ])
         .build()
         .dispatch()
         .await;
@@ -61,15 +147,44 @@ pub async fn run_bot(bot_db: BotDb, bot_token: String) -> Result<()> {
 async fn handle_command(bot: Bot, msg: Message, cmd: Command, db: BotDb) -> ResponseResult<()> {
     tracing::info!("Handling command: {:?}", cmd);
 
+    if cmd.mutates(msg.text().unwrap_or_default()) && handlers::is_read_only(&db).await {
+        if let Err(e) = bot
+            .send_message(msg.chat.id, handlers::READ_ONLY_NOTICE)
+            .await
+        {
+            tracing::error!("Error sending maintenance notice: {}", e);
+        }
+        return Ok(());
+    }
+
     let result = match cmd {
         Command::Start => handlers::handle_start(bot, msg, db).await,
         Command::Help => handlers::handle_help(bot, msg).await,
-        Command::List => handlers::handle_list(bot, msg, db).await,
+        Command::List | Command::Week | Command::Agenda => {
+            handlers::handle_agenda(bot, msg, db, handlers::AgendaRange::Week).await
+        }
+        Command::Today => handlers::handle_agenda(bot, msg, db, handlers::AgendaRange::Today).await,
+        Command::Tomorrow => {
+            handlers::handle_agenda(bot, msg, db, handlers::AgendaRange::Tomorrow).await
+        }
         Command::Cancel => handlers::handle_cancel(bot, msg).await,
+        Command::Note => handlers::handle_note(bot, msg, db).await,
         Command::Device => handlers::handle_device(bot, msg, db).await,
+        Command::Subscribe => handlers::handle_subscribe(bot, msg, db).await,
         Command::Export => handlers::handle_export(bot, msg, db).await,
         Command::Invite => handlers::handle_invite(bot, msg, db).await,
+        Command::Attendees => handlers::handle_attendees(bot, msg, db).await,
         Command::Rsvp => handlers::handle_rsvp(bot, msg, db).await,
+        Command::Slot => handlers::handle_slot(bot, msg, db).await,
+        Command::Task => handlers::handle_task(bot, msg, db).await,
+        Command::Plan => handlers::handle_plan(bot, msg, db).await,
+        Command::Focus => handlers::handle_focus(bot, msg, db).await,
+        Command::Stats => handlers::handle_stats(bot, msg, db).await,
+        Command::Timezone => handlers::handle_timezone(bot, msg, db).await,
+        Command::Fixtz => handlers::handle_fixtz(bot, msg, db).await,
+        Command::Email => handlers::handle_email(bot, msg, db).await,
+        Command::Hours => handlers::handle_hours(bot, msg, db).await,
+        Command::Delegate => handlers::handle_delegate(bot, msg, db).await,
         Command::DeleteAccount => handlers::handle_delete_account(bot, msg).await,
     };
 
@@ -91,6 +206,118 @@ async fn handle_message(bot: Bot, msg: Message, db: BotDb) -> ResponseResult<()>
     Ok(())
 }
 
+/// Handle a forwarded message (event import)
+async fn handle_forwarded_message(bot: Bot, msg: Message, db: BotDb) -> ResponseResult<()> {
+    let result = handlers::handle_forwarded_message(bot, msg, db).await;
+
+    if let Err(e) = result {
+        tracing::error!("Error handling forwarded message: {}", e);
+    }
+
+    Ok(())
+}
+
+/// Handle a voice note (event import)
+async fn handle_voice_message(
+    bot: Bot,
+    msg: Message,
+    db: BotDb,
+    voice_notes: voice::VoiceNotes,
+) -> ResponseResult<()> {
+    let result = handlers::handle_voice_message(bot, msg, db, voice_notes).await;
+
+    if let Err(e) = result {
+        tracing::error!("Error handling voice note: {}", e);
+    }
+
+    Ok(())
+}
+
+/// Handle a photo (poster import)
+async fn handle_photo_message(
+    bot: Bot,
+    msg: Message,
+    db: BotDb,
+    posters: ocr::Posters,
+) -> ResponseResult<()> {
+    let result = handlers::handle_photo_message(bot, msg, db, posters).await;
+
+    if let Err(e) = result {
+        tracing::error!("Error handling photo: {}", e);
+    }
+
+    Ok(())
+}
+
+/// Handle a shared location (timezone detection)
+async fn handle_location(bot: Bot, msg: Message, db: BotDb) -> ResponseResult<()> {
+    let result = handlers::handle_location(bot, msg, db).await;
+
+    if let Err(e) = result {
+        tracing::error!("Error handling location: {}", e);
+    }
+
+    Ok(())
+}
+
+/// Handle a reply naming a new device
+async fn handle_device_name_reply(
+    bot: Bot,
+    msg: Message,
+    db: BotDb,
+    list: teloxide::types::MessageId,
+) -> ResponseResult<()> {
+    let result = handlers::handle_device_name_reply(bot, msg, db, list).await;
+
+    if let Err(e) = result {
+        tracing::error!("Error handling device name reply: {}", e);
+    }
+
+    Ok(())
+}
+
+/// Handle a reply to an event card (natural language edit)
+async fn handle_edit_reply(
+    bot: Bot,
+    msg: Message,
+    db: BotDb,
+    event_id: uuid::Uuid,
+) -> ResponseResult<()> {
+    let result = handlers::handle_edit_reply(bot, msg, db, event_id).await;
+
+    if let Err(e) = result {
+        tracing::error!("Error handling edit reply: {}", e);
+    }
+
+    Ok(())
+}
+
+/// Carry per-chat state over to a migrated chat's new id
+async fn handle_chat_migration(guard: FloodGuard, migration: ChatMigration) -> ResponseResult<()> {
+    tracing::info!("Chat {} migrated to {}", migration.from, migration.to);
+    guard.migrate(migration);
+
+    Ok(())
+}
+
+/// Tell a flooding chat to slow down, once per throttled stretch
+async fn handle_throttled(bot: Bot, msg: Message, throttled: Throttled) -> ResponseResult<()> {
+    tracing::info!("Throttled event creation in chat {}", msg.chat.id);
+    if throttled.notify {
+        let text = format!(
+            "🐢 Slow down a little: I create up to {} events a minute. \
+             That message wasn't saved; send it again in {}s.",
+            flood::EVENT_BURST_SIZE,
+            throttled.retry_after.as_secs().max(1)
+        );
+        if let Err(e) = bot.send_message(msg.chat.id, text).await {
+            tracing::error!("Error sending slow down notice: {}", e);
+        }
+    }
+
+    Ok(())
+}
+
 /// Handle callback queries
 async fn handle_callback_query(bot: Bot, q: CallbackQuery, db: BotDb) -> ResponseResult<()> {
     let result = handlers::handle_callback_query(bot, q, db).await;
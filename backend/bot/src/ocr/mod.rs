@@ -0,0 +1,154 @@
+//! Events from photos of posters
+//!
+//! Photos go through a [`TextRecognizer`], and the title, date and venue
+//! found in the text read off them are offered as an event the same way as
+//! a forwarded message. The default recognizer reads nothing, so photos are
+//! left alone unless `OCR_PROVIDER` names a backend.
+//!
+//! The tesseract-server backend is behind the `ocr-tesseract` feature.
+
+#[cfg(feature = "ocr-tesseract")]
+mod tesseract;
+
+use anyhow::{Context, Result, bail};
+use std::env;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use teloxide::net::Download;
+use teloxide::prelude::*;
+use teloxide::types::PhotoSize;
+
+#[cfg(feature = "ocr-tesseract")]
+pub use tesseract::TesseractConfig;
+
+/// Largest photo sent for recognition
+pub const MAX_PHOTO_BYTES: u32 = 10 * 1024 * 1024;
+
+/// Future returned by [`TextRecognizer::recognize`]
+pub type RecognizeFuture<'a> = Pin<Box<dyn Future<Output = Result<String>> + Send + 'a>>;
+
+/// Something that can read the text on an image
+pub trait TextRecognizer: Send + Sync {
+    /// Text on `image`, a JPEG photo, one line per line of print
+    fn recognize(&self, image: Vec<u8>) -> RecognizeFuture<'_>;
+
+    /// Whether photos are worth downloading for this recognizer
+    fn reads_text(&self) -> bool {
+        true
+    }
+}
+
+/// Recognizer used without a backend: reads nothing
+pub struct NoText;
+
+impl TextRecognizer for NoText {
+    fn recognize(&self, _image: Vec<u8>) -> RecognizeFuture<'_> {
+        Box::pin(async { Ok(String::new()) })
+    }
+
+    fn reads_text(&self) -> bool {
+        false
+    }
+}
+
+/// OCR backend and its settings
+#[derive(Debug, Clone)]
+pub enum OcrConfig {
+    #[cfg(feature = "ocr-tesseract")]
+    Tesseract(TesseractConfig),
+}
+
+impl OcrConfig {
+    /// Load configuration from environment variables
+    ///
+    /// Returns `None` unless `OCR_PROVIDER` is set.
+    pub fn from_env() -> Result<Option<Self>> {
+        match env::var("OCR_PROVIDER")
+            .unwrap_or_default()
+            .to_ascii_lowercase()
+            .as_str()
+        {
+            "" | "none" => Ok(None),
+            #[cfg(feature = "ocr-tesseract")]
+            "tesseract" => Ok(Some(Self::Tesseract(TesseractConfig::from_env()?))),
+            #[cfg(not(feature = "ocr-tesseract"))]
+            "tesseract" => {
+                bail!("OCR_PROVIDER=tesseract needs a build with the ocr-tesseract feature")
+            }
+            other => bail!("OCR_PROVIDER must be tesseract or none, got {other}"),
+        }
+    }
+
+    fn build(&self) -> Result<Arc<dyn TextRecognizer>> {
+        match *self {
+            #[cfg(feature = "ocr-tesseract")]
+            Self::Tesseract(ref config) => {
+                Ok(Arc::new(tesseract::TesseractRecognizer::new(config)?))
+            }
+        }
+    }
+}
+
+/// Recognizer shared with the handlers through the dispatcher
+#[derive(Clone)]
+pub struct Posters {
+    recognizer: Arc<dyn TextRecognizer>,
+}
+
+impl Default for Posters {
+    fn default() -> Self {
+        Self::with_recognizer(Arc::new(NoText))
+    }
+}
+
+impl Posters {
+    pub fn new(config: Option<&OcrConfig>) -> Result<Self> {
+        Ok(match config {
+            Some(config) => Self::with_recognizer(config.build()?),
+            None => Self::default(),
+        })
+    }
+
+    /// Read photos with a recognizer of the caller's own
+    pub fn with_recognizer(recognizer: Arc<dyn TextRecognizer>) -> Self {
+        Self { recognizer }
+    }
+
+    /// Whether photos are read at all
+    pub fn enabled(&self) -> bool {
+        self.recognizer.reads_text()
+    }
+
+    /// Download the largest size of `photo` from Telegram and read it
+    pub(crate) async fn read(&self, bot: &Bot, photo: &[PhotoSize]) -> Result<String> {
+        let Some(largest) = photo.iter().max_by_key(|size| size.width * size.height) else {
+            bail!("Photo has no sizes");
+        };
+        if largest.file.size > MAX_PHOTO_BYTES {
+            bail!("Photo is {} bytes", largest.file.size);
+        }
+
+        let file = bot
+            .get_file(largest.file.id.clone())
+            .await
+            .context("Failed to look up photo")?;
+        let mut image = Vec::with_capacity(file.size as usize);
+        bot.download_file(&file.path, &mut image)
+            .await
+            .context("Failed to download photo")?;
+
+        self.recognizer.recognize(image).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_no_text_reads_nothing() {
+        assert!(!Posters::default().enabled());
+        assert_eq!(NoText.recognize(vec![0xff, 0xd8]).await.unwrap(), "");
+    }
+}
@@ -0,0 +1,146 @@
+//! tesseract-server recognition
+//!
+//! Posts the photo to the `/tesseract` endpoint of a
+//! [tesseract-server](https://github.com/hertzg/tesseract-server), which
+//! runs the Tesseract CLI and answers with what it printed.
+
+use anyhow::{Context, Result, bail};
+use reqwest::multipart::{Form, Part};
+use std::env;
+use std::time::Duration;
+
+use super::{RecognizeFuture, TextRecognizer};
+
+const REQUEST_TIMEOUT_SECS: u64 = 60;
+
+/// Where the server runs and which languages it reads
+#[derive(Debug, Clone)]
+pub struct TesseractConfig {
+    /// Base URL of the server, such as `http://localhost:8884`
+    pub url: String,
+    /// Tesseract language codes, such as `eng` or `deu`
+    pub languages: Vec<String>,
+}
+
+impl TesseractConfig {
+    pub(super) fn from_env() -> Result<Self> {
+        let languages = env::var("TESSERACT_LANGUAGES").unwrap_or_else(|_| "eng".to_string());
+        Ok(Self {
+            url: env::var("TESSERACT_URL")
+                .context("TESSERACT_URL must be set with OCR_PROVIDER=tesseract")?
+                .trim_end_matches('/')
+                .to_string(),
+            languages: languages
+                .split(',')
+                .map(str::trim)
+                .filter(|language| !language.is_empty())
+                .map(str::to_string)
+                .collect(),
+        })
+    }
+}
+
+pub(super) struct TesseractRecognizer {
+    http: reqwest::Client,
+    config: TesseractConfig,
+}
+
+impl TesseractRecognizer {
+    pub(super) fn new(config: &TesseractConfig) -> Result<Self> {
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS))
+            .user_agent(concat!("Televent/", env!("CARGO_PKG_VERSION")))
+            .build()?;
+
+        Ok(Self {
+            http,
+            config: config.clone(),
+        })
+    }
+
+    async fn post_image(&self, image: Vec<u8>) -> Result<String> {
+        let options = serde_json::json!({ "languages": self.config.languages });
+        let form = Form::new().text("options", options.to_string()).part(
+            "file",
+            Part::bytes(image)
+                .file_name("photo.jpg")
+                .mime_str("image/jpeg")?,
+        );
+
+        let response = self
+            .http
+            .post(format!("{}/tesseract", self.config.url))
+            .multipart(form)
+            .send()
+            .await
+            .context("Tesseract request failed")?;
+        let status = response.status();
+        if !status.is_success() {
+            let body: String = response
+                .text()
+                .await
+                .unwrap_or_default()
+                .chars()
+                .take(200)
+                .collect();
+            bail!("Tesseract returned {status}: {body}");
+        }
+
+        recognized_text(&response.json().await?)
+    }
+}
+
+impl TextRecognizer for TesseractRecognizer {
+    fn recognize(&self, image: Vec<u8>) -> RecognizeFuture<'_> {
+        Box::pin(self.post_image(image))
+    }
+}
+
+/// What Tesseract printed, from `{"data": {"exit": {"code"}, "stdout"}}`
+fn recognized_text(answer: &serde_json::Value) -> Result<String> {
+    let data = answer.get("data").context("Tesseract answer has no data")?;
+    let code = data.pointer("/exit/code").and_then(|code| code.as_i64());
+    if code.is_some_and(|code| code != 0) {
+        let stderr: String = data
+            .get("stderr")
+            .and_then(|stderr| stderr.as_str())
+            .unwrap_or_default()
+            .chars()
+            .take(200)
+            .collect();
+        bail!(
+            "Tesseract exited with {}: {stderr}",
+            code.unwrap_or_default()
+        );
+    }
+    data.get("stdout")
+        .and_then(|stdout| stdout.as_str())
+        .map(str::to_string)
+        .context("Tesseract answer has no text")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recognized_text() {
+        let answer = serde_json::json!({
+            "data": {
+                "exit": { "code": 0, "signal": null },
+                "stderr": "",
+                "stdout": "JAZZ NIGHT\nFriday, March 13\n"
+            }
+        });
+        assert_eq!(
+            recognized_text(&answer).unwrap(),
+            "JAZZ NIGHT\nFriday, March 13\n"
+        );
+
+        let failed = serde_json::json!({
+            "data": { "exit": { "code": 1 }, "stderr": "Error in pixReadMem", "stdout": "" }
+        });
+        assert!(recognized_text(&failed).is_err());
+        assert!(recognized_text(&serde_json::json!({})).is_err());
+    }
+}
@@ -0,0 +1,143 @@
+//! Signed callback data for agenda quick actions
+//!
+//! Every event line of an agenda carries buttons that act on the event
+//! directly. Their callback data names the action and the event, and ends in
+//! a truncated HMAC over both and the Telegram id of the user the agenda was
+//! sent to, keyed by the bot token. A button forwarded to or replayed by
+//! someone else, or data crafted by hand, fails the check before any event
+//! lookup. Format: `qa:<action>:<event_id>:<signature>`, 54 bytes, inside
+//! Telegram's 64-byte limit.
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use uuid::Uuid;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Prefix the callback dispatcher routes on
+pub const PREFIX: &str = "qa:";
+
+/// Bytes of the HMAC kept in the callback data
+const SIGNATURE_BYTES: usize = 8;
+
+/// What a quick action button does
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuickAction {
+    /// Send the event card, which takes edits as replies
+    Edit,
+    /// Ask before cancelling
+    Cancel,
+    /// Cancel, from the confirmation prompt
+    ConfirmCancel,
+    /// Show the attendee dashboard
+    Attendees,
+    /// Remind guests who haven't answered
+    Remind,
+}
+
+impl QuickAction {
+    const fn code(self) -> char {
+        match self {
+            Self::Edit => 'e',
+            Self::Cancel => 'c',
+            Self::ConfirmCancel => 'C',
+            Self::Attendees => 'a',
+            Self::Remind => 'r',
+        }
+    }
+
+    fn from_code(code: &str) -> Option<Self> {
+        match code {
+            "e" => Some(Self::Edit),
+            "c" => Some(Self::Cancel),
+            "C" => Some(Self::ConfirmCancel),
+            "a" => Some(Self::Attendees),
+            "r" => Some(Self::Remind),
+            _ => None,
+        }
+    }
+}
+
+fn mac(key: &[u8], user_id: i64, action: QuickAction, event_id: Uuid) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC can take any key length");
+    mac.update(format!("{}:{}:{user_id}", action.code(), event_id.simple()).as_bytes());
+    mac
+}
+
+/// Callback data for `action` on `event_id`, valid only for `user_id`
+pub fn encode(key: &[u8], user_id: i64, action: QuickAction, event_id: Uuid) -> String {
+    let signature = mac(key, user_id, action, event_id).finalize().into_bytes();
+    format!(
+        "{PREFIX}{}:{}:{}",
+        action.code(),
+        event_id.simple(),
+        hex::encode(&signature[..SIGNATURE_BYTES])
+    )
+}
+
+/// Action and event of callback data signed for `user_id`; `None` when it is
+/// malformed or the signature doesn't match
+pub fn decode(key: &[u8], user_id: i64, data: &str) -> Option<(QuickAction, Uuid)> {
+    let mut parts = data.strip_prefix(PREFIX)?.split(':');
+    let action = QuickAction::from_code(parts.next()?)?;
+    let event_id = Uuid::parse_str(parts.next()?).ok()?;
+    let signature = hex::decode(parts.next()?).ok()?;
+    if parts.next().is_some() || signature.len() != SIGNATURE_BYTES {
+        return None;
+    }
+
+    mac(key, user_id, action, event_id)
+        .verify_truncated_left(&signature)
+        .ok()?;
+    Some((action, event_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &[u8] = b"123456:test-token";
+
+    #[test]
+    fn test_round_trip_fits_callback_data() {
+        let event_id = Uuid::new_v4();
+        for action in [
+            QuickAction::Edit,
+            QuickAction::Cancel,
+            QuickAction::ConfirmCancel,
+            QuickAction::Attendees,
+            QuickAction::Remind,
+        ] {
+            let data = encode(KEY, 42, action, event_id);
+            assert!(data.len() <= 64, "{data} is too long");
+            assert_eq!(decode(KEY, 42, &data), Some((action, event_id)));
+        }
+    }
+
+    #[test]
+    fn test_rejects_other_users_and_tampering() {
+        let event_id = Uuid::new_v4();
+        let data = encode(KEY, 42, QuickAction::Cancel, event_id);
+
+        // Forwarded to someone else, or signed with another bot's token
+        assert_eq!(decode(KEY, 43, &data), None);
+        assert_eq!(decode(b"other-token", 42, &data), None);
+
+        // Another action or event under the same signature
+        let confirm = data.replacen("qa:c:", "qa:C:", 1);
+        assert_eq!(decode(KEY, 42, &confirm), None);
+        let other = data.replace(
+            &event_id.simple().to_string(),
+            &Uuid::new_v4().simple().to_string(),
+        );
+        assert_eq!(decode(KEY, 42, &other), None);
+
+        // Shortened, extended or unsigned
+        assert_eq!(decode(KEY, 42, &data[..data.len() - 2]), None);
+        assert_eq!(decode(KEY, 42, &format!("{data}:00")), None);
+        assert_eq!(
+            decode(KEY, 42, &format!("qa:c:{}", event_id.simple())),
+            None
+        );
+    }
+}
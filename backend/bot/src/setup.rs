@@ -0,0 +1,123 @@
+//! Bot profile applied at startup
+//!
+//! New deployments get their command list and the chat menu button from
+//! config, so BotFather is only needed to create the token. Failures are
+//! logged and never stop the bot; Telegram keeps what was set before.
+
+use anyhow::{Context, Result, bail};
+use std::env;
+use teloxide::prelude::*;
+use teloxide::types::{MenuButton, WebAppInfo};
+use teloxide::utils::command::BotCommands;
+use url::Url;
+
+use crate::commands::Command;
+
+/// Button text used when `TELEGRAM_MENU_BUTTON_TEXT` is unset
+const DEFAULT_MENU_BUTTON_TEXT: &str = "Calendar";
+
+/// What the bot sets on its profile when it starts
+#[derive(Debug, Clone)]
+pub struct BotSetupConfig {
+    /// Publish the command list with `setMyCommands`
+    pub register_commands: bool,
+    /// Mini App the chat menu button opens; `None` leaves the button alone
+    pub mini_app_url: Option<Url>,
+    pub menu_button_text: String,
+}
+
+impl BotSetupConfig {
+    /// Load configuration from environment variables
+    ///
+    /// The menu button opens `TELEGRAM_MINI_APP_URL`, or the web app under
+    /// `PUBLIC_BASE_URL` when that is served over HTTPS.
+    pub fn from_env() -> Result<Self> {
+        let register_commands = env::var("TELEGRAM_REGISTER_COMMANDS").map_or(true, |value| {
+            matches!(
+                value.to_ascii_lowercase().as_str(),
+                "1" | "true" | "yes" | "on"
+            )
+        });
+
+        Ok(Self {
+            register_commands,
+            mini_app_url: mini_app_url(
+                env::var("TELEGRAM_MINI_APP_URL").ok().as_deref(),
+                env::var("PUBLIC_BASE_URL").ok().as_deref(),
+            )?,
+            menu_button_text: env::var("TELEGRAM_MENU_BUTTON_TEXT")
+                .ok()
+                .filter(|text| !text.trim().is_empty())
+                .unwrap_or_else(|| DEFAULT_MENU_BUTTON_TEXT.to_string()),
+        })
+    }
+}
+
+/// Mini App URL from an explicit setting, else derived from the public base
+/// URL; Telegram only opens Mini Apps over HTTPS
+fn mini_app_url(explicit: Option<&str>, public_base_url: Option<&str>) -> Result<Option<Url>> {
+    if let Some(value) = explicit.map(str::trim).filter(|value| !value.is_empty()) {
+        let url = Url::parse(value).context("TELEGRAM_MINI_APP_URL must be a valid URL")?;
+        if url.scheme() != "https" {
+            bail!("TELEGRAM_MINI_APP_URL must use https");
+        }
+        return Ok(Some(url));
+    }
+
+    // A local http base is normal in development; just leave the button
+    Ok(public_base_url
+        .and_then(|base| Url::parse(&format!("{}/app", base.trim_end_matches('/'))).ok())
+        .filter(|url| url.scheme() == "https"))
+}
+
+/// Register the command list and the menu button as configured
+pub async fn apply(bot: &Bot, config: &BotSetupConfig) {
+    if config.register_commands {
+        match bot.set_my_commands(Command::bot_commands()).await {
+            Ok(_) => tracing::info!("Registered bot commands"),
+            Err(e) => tracing::warn!("Failed to register bot commands: {}", e),
+        }
+    }
+
+    if let Some(url) = &config.mini_app_url {
+        let button = MenuButton::WebApp {
+            text: config.menu_button_text.clone(),
+            web_app: WebAppInfo { url: url.clone() },
+        };
+        match bot.set_chat_menu_button().menu_button(button).await {
+            Ok(_) => tracing::info!(%url, "Set the chat menu button to the Mini App"),
+            Err(e) => tracing::warn!("Failed to set the chat menu button: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mini_app_url() {
+        let url = |explicit, base| {
+            mini_app_url(explicit, base)
+                .unwrap()
+                .map(|url| url.to_string())
+        };
+
+        assert_eq!(
+            url(None, Some("https://example.com/televent/")).as_deref(),
+            Some("https://example.com/televent/app")
+        );
+        assert_eq!(url(None, Some("http://localhost:3000")), None);
+        assert_eq!(url(None, None), None);
+        assert_eq!(
+            url(
+                Some("https://app.example.com/"),
+                Some("http://localhost:3000")
+            )
+            .as_deref(),
+            Some("https://app.example.com/")
+        );
+        assert!(mini_app_url(Some("http://app.example.com"), None).is_err());
+        assert!(mini_app_url(Some("not a url"), None).is_err());
+    }
+}
@@ -0,0 +1,231 @@
+//! Mock Telegram Bot API for handler tests
+//!
+//! [`MockTelegram`] serves the Bot API on a local port and records every
+//! request, so tests can assert on the exact messages, keyboards and parse
+//! modes a handler sends instead of letting calls to api.telegram.org fail.
+//! Methods that send or edit a message answer with a message built from the
+//! request; every other method answers `true`.
+
+use axum::Router;
+use axum::body::Bytes;
+use axum::extract::{Path, State};
+use axum::response::Json;
+use serde_json::{Value, json};
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::{Arc, Mutex};
+use teloxide::Bot;
+use teloxide::types::InlineKeyboardMarkup;
+use tokio::net::TcpListener;
+use url::Url;
+
+/// Id the mock bot signs its messages with
+pub const BOT_ID: u64 = 42;
+
+/// One Bot API call made by a handler
+#[derive(Debug, Clone)]
+pub struct Call {
+    /// Method name, such as `sendMessage`
+    pub method: String,
+    /// Parameters; multipart uploads are read into their fields
+    pub params: Value,
+}
+
+impl Call {
+    pub fn text(&self) -> &str {
+        self.params["text"].as_str().unwrap_or_default()
+    }
+
+    pub fn parse_mode(&self) -> Option<&str> {
+        self.params["parse_mode"].as_str()
+    }
+
+    /// Inline keyboard sent with the call, if any
+    pub fn keyboard(&self) -> Option<InlineKeyboardMarkup> {
+        let markup = self.params.get("reply_markup")?;
+        // Multipart uploads send the markup as a JSON string
+        let markup = match markup.as_str() {
+            Some(markup) => serde_json::from_str(markup).ok()?,
+            None => markup.clone(),
+        };
+        markup.get("inline_keyboard")?;
+        serde_json::from_value(markup).ok()
+    }
+
+    /// Callback data of every inline button, row by row
+    pub fn callback_data(&self) -> Vec<String> {
+        self.keyboard()
+            .into_iter()
+            .flat_map(|keyboard| keyboard.inline_keyboard)
+            .flatten()
+            .filter_map(|button| match button.kind {
+                teloxide::types::InlineKeyboardButtonKind::CallbackData(data) => Some(data),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+#[derive(Default)]
+struct Recorded {
+    calls: Mutex<Vec<Call>>,
+    next_message_id: AtomicI32,
+}
+
+/// Bot API server that records calls
+pub struct MockTelegram {
+    url: Url,
+    recorded: Arc<Recorded>,
+    server: tokio::task::JoinHandle<()>,
+}
+
+impl MockTelegram {
+    pub async fn start() -> Self {
+        let recorded = Arc::new(Recorded {
+            next_message_id: AtomicI32::new(1000),
+            ..Recorded::default()
+        });
+        let app = Router::new()
+            .route("/{*path}", axum::routing::post(answer))
+            .with_state(recorded.clone());
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("bind mock Telegram");
+        let url = Url::parse(&format!("http://{}/", listener.local_addr().unwrap())).unwrap();
+        let server = tokio::spawn(async move {
+            axum::serve(listener, app).await.ok();
+        });
+
+        Self {
+            url,
+            recorded,
+            server,
+        }
+    }
+
+    /// A bot that talks to this server
+    pub fn bot(&self) -> Bot {
+        Bot::new("123:fake_token").set_api_url(self.url.clone())
+    }
+
+    /// Every call so far, oldest first
+    pub fn calls(&self) -> Vec<Call> {
+        self.recorded.calls.lock().unwrap().clone()
+    }
+
+    /// Calls of one method, oldest first
+    pub fn calls_to(&self, method: &str) -> Vec<Call> {
+        self.calls()
+            .into_iter()
+            .filter(|call| call.method == method)
+            .collect()
+    }
+
+    /// The only `sendMessage` call; panics unless exactly one was made
+    pub fn sent_message(&self) -> Call {
+        let mut sent = self.calls_to("sendMessage");
+        assert_eq!(sent.len(), 1, "expected one message, got {sent:#?}");
+        sent.remove(0)
+    }
+}
+
+impl Drop for MockTelegram {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
+
+async fn answer(
+    State(recorded): State<Arc<Recorded>>,
+    Path(path): Path<String>,
+    body: Bytes,
+) -> Json<Value> {
+    // teloxide names methods like `SendMessage`; record them as documented
+    let method = path.rsplit('/').next().unwrap_or_default();
+    let mut chars = method.chars();
+    let method = chars
+        .next()
+        .map(|first| first.to_ascii_lowercase().to_string() + chars.as_str())
+        .unwrap_or_default();
+    let params = serde_json::from_slice(&body).unwrap_or_else(|_| multipart_fields(&body));
+
+    let result = if method.starts_with("send") || method.starts_with("edit") {
+        let message_id = match params["message_id"].as_i64() {
+            Some(message_id) => message_id as i32,
+            None => recorded.next_message_id.fetch_add(1, Ordering::Relaxed),
+        };
+        sent_message(message_id, &params)
+    } else if method == "getMe" {
+        json!({ "id": BOT_ID, "is_bot": true, "first_name": "Televent", "username": "televent_bot" })
+    } else {
+        json!(true)
+    };
+
+    recorded.calls.lock().unwrap().push(Call { method, params });
+    Json(json!({ "ok": true, "result": result }))
+}
+
+/// The message Telegram would answer a send or edit with
+fn sent_message(message_id: i32, params: &Value) -> Value {
+    let chat_id = match &params["chat_id"] {
+        Value::String(id) => id.parse().unwrap_or_default(),
+        id => id.as_i64().unwrap_or_default(),
+    };
+    let mut message = json!({
+        "message_id": message_id,
+        "date": 1600000000,
+        "chat": { "id": chat_id, "type": "private", "first_name": "Test" },
+        "from": { "id": BOT_ID, "is_bot": true, "first_name": "Televent" },
+    });
+    if let Some(text) = params["text"].as_str() {
+        message["text"] = json!(text);
+    }
+    if params.get("document").is_some() {
+        message["document"] = json!({
+            "file_id": "document",
+            "file_unique_id": "document",
+            "file_size": 0
+        });
+    }
+    message
+}
+
+/// Fields of a multipart upload keyed by name; files are recorded as
+/// `{"file_name", "content"}` under the field that attaches them
+fn multipart_fields(body: &[u8]) -> Value {
+    let body = String::from_utf8_lossy(body);
+    let mut fields = serde_json::Map::new();
+    for part in body.split("Content-Disposition: form-data; ").skip(1) {
+        let Some(name) = part
+            .strip_prefix("name=\"")
+            .and_then(|rest| rest.split('"').next())
+        else {
+            continue;
+        };
+        let content = part.split_once("\r\n\r\n").map_or("", |(_, rest)| {
+            rest.split("\r\n--").next().unwrap_or_default()
+        });
+        let value = match part.split_once("filename=\"") {
+            Some((_, rest)) => json!({
+                "file_name": rest.split('"').next().unwrap_or_default(),
+                "content": content,
+            }),
+            None => json!(content),
+        };
+        fields.insert(name.to_string(), value);
+    }
+
+    // Files are sent as parts of their own named by `attach://<part>`
+    let attached: Vec<(String, String)> = fields
+        .iter()
+        .filter_map(|(name, value)| {
+            let part = value.as_str()?.strip_prefix("attach://")?;
+            Some((name.clone(), part.to_string()))
+        })
+        .collect();
+    for (name, part) in attached {
+        if let Some(file) = fields.remove(&part) {
+            fields.insert(name, file);
+        }
+    }
+    Value::Object(fields)
+}
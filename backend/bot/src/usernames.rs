@@ -0,0 +1,63 @@
+//! Keeping stored usernames current
+//!
+//! Telegram users can change or drop their username at any time, and
+//! /invite finds people by it. Every update carries the sender's current
+//! username, so it is written back whenever it differs from the one last
+//! seen for that user. The last seen names are kept in memory, so an
+//! unchanged name costs no database work.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use teloxide::types::{Update, User, UserId};
+
+use crate::db::BotDb;
+
+/// Users remembered before the map is cleared
+const MAX_TRACKED_USERS: usize = 10_000;
+
+/// Last username seen per user, shared by every handler through the
+/// dispatcher
+#[derive(Clone, Default)]
+pub struct KnownUsernames {
+    seen: Arc<Mutex<HashMap<UserId, Option<String>>>>,
+}
+
+impl KnownUsernames {
+    /// Write the sender's username back if it changed since last seen
+    pub async fn refresh(&self, user: &User, db: &BotDb) {
+        let username = user.username.clone();
+        if self.lock().get(&user.id) == Some(&username) {
+            return;
+        }
+
+        match db
+            .refresh_username(user.id.0 as i64, username.as_deref())
+            .await
+        {
+            Ok(changed) => {
+                if changed {
+                    tracing::info!("Username of user {} is now {:?}", user.id, username);
+                }
+                let mut seen = self.lock();
+                if seen.len() >= MAX_TRACKED_USERS {
+                    seen.clear();
+                }
+                seen.insert(user.id, username);
+            }
+            Err(e) => tracing::warn!("Failed to refresh username of user {}: {e}", user.id),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<UserId, Option<String>>> {
+        self.seen
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Refresh the username of whoever sent `update`
+pub async fn refresh_sender(update: Update, usernames: KnownUsernames, db: BotDb) {
+    if let Some(user) = update.from().filter(|user| !user.is_bot) {
+        usernames.refresh(user, &db).await;
+    }
+}
@@ -0,0 +1,162 @@
+//! Events from voice notes
+//!
+//! A voice note is downloaded from Telegram, turned into text by a
+//! [`Transcriber`] and read like a forwarded message: the bot previews the
+//! event it hears and creates it once confirmed. Voice notes are ignored
+//! unless `TRANSCRIPTION_PROVIDER` names a backend.
+//!
+//! The whisper.cpp server backend is behind the `voice-whisper` feature.
+
+#[cfg(feature = "voice-whisper")]
+mod whisper;
+
+use anyhow::{Context, Result, bail};
+use std::env;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use teloxide::net::Download;
+use teloxide::prelude::*;
+use teloxide::types::Voice;
+
+#[cfg(feature = "voice-whisper")]
+pub use whisper::WhisperConfig;
+
+/// Longest voice note sent for transcription
+pub const MAX_VOICE_SECONDS: u32 = 60;
+
+/// Longest transcript read for an event
+pub const MAX_TRANSCRIPT_CHARS: usize = 500;
+
+/// Future returned by [`Transcriber::transcribe`]
+pub type TranscribeFuture<'a> = Pin<Box<dyn Future<Output = Result<String>> + Send + 'a>>;
+
+/// Something that can turn speech into text
+pub trait Transcriber: Send + Sync {
+    /// Text spoken in `audio`, a file of type `mime_type`
+    fn transcribe<'a>(&'a self, audio: Vec<u8>, mime_type: &'a str) -> TranscribeFuture<'a>;
+}
+
+/// Transcription backend and its settings
+#[derive(Debug, Clone)]
+pub enum TranscriptionConfig {
+    #[cfg(feature = "voice-whisper")]
+    Whisper(WhisperConfig),
+}
+
+impl TranscriptionConfig {
+    /// Load configuration from environment variables
+    ///
+    /// Returns `None` unless `TRANSCRIPTION_PROVIDER` is set.
+    pub fn from_env() -> Result<Option<Self>> {
+        match env::var("TRANSCRIPTION_PROVIDER")
+            .unwrap_or_default()
+            .to_ascii_lowercase()
+            .as_str()
+        {
+            "" | "none" => Ok(None),
+            #[cfg(feature = "voice-whisper")]
+            "whisper" => Ok(Some(Self::Whisper(WhisperConfig::from_env()?))),
+            #[cfg(not(feature = "voice-whisper"))]
+            "whisper" => {
+                bail!("TRANSCRIPTION_PROVIDER=whisper needs a build with the voice-whisper feature")
+            }
+            other => bail!("TRANSCRIPTION_PROVIDER must be whisper or none, got {other}"),
+        }
+    }
+
+    fn build(&self) -> Result<Arc<dyn Transcriber>> {
+        match *self {
+            #[cfg(feature = "voice-whisper")]
+            Self::Whisper(ref config) => Ok(Arc::new(whisper::WhisperTranscriber::new(config)?)),
+        }
+    }
+}
+
+/// Transcription shared with the handlers through the dispatcher; the
+/// default has no backend and leaves voice notes alone
+#[derive(Clone, Default)]
+pub struct VoiceNotes {
+    transcriber: Option<Arc<dyn Transcriber>>,
+}
+
+impl VoiceNotes {
+    pub fn new(config: Option<&TranscriptionConfig>) -> Result<Self> {
+        Ok(Self {
+            transcriber: config.map(TranscriptionConfig::build).transpose()?,
+        })
+    }
+
+    /// Transcribe with a backend of the caller's own
+    pub fn with_transcriber(transcriber: Arc<dyn Transcriber>) -> Self {
+        Self {
+            transcriber: Some(transcriber),
+        }
+    }
+
+    /// Whether voice notes are transcribed at all
+    pub fn enabled(&self) -> bool {
+        self.transcriber.is_some()
+    }
+
+    /// Download `voice` from Telegram and transcribe it
+    pub(crate) async fn transcribe(&self, bot: &Bot, voice: &Voice) -> Result<String> {
+        let Some(transcriber) = &self.transcriber else {
+            bail!("Voice notes are not transcribed on this bot");
+        };
+
+        let file = bot
+            .get_file(voice.file.id.clone())
+            .await
+            .context("Failed to look up voice note")?;
+        let mut audio = Vec::with_capacity(file.size as usize);
+        bot.download_file(&file.path, &mut audio)
+            .await
+            .context("Failed to download voice note")?;
+
+        let mime_type = voice
+            .mime_type
+            .as_ref()
+            .map_or("audio/ogg", |mime| mime.essence_str());
+        let text = transcriber.transcribe(audio, mime_type).await?;
+        Ok(tidy_transcript(&text))
+    }
+}
+
+/// Transcript on one line, with the "p.m." speech models like to write
+/// spelled the way the event parser reads it
+fn tidy_transcript(text: &str) -> String {
+    text.split_whitespace()
+        .map(|word| {
+            let lower = word.to_ascii_lowercase();
+            [("a.m.", "am"), ("p.m.", "pm")]
+                .into_iter()
+                .find_map(|(spoken, written)| {
+                    lower
+                        .strip_prefix(spoken)
+                        .map(|rest| format!("{written}{rest}"))
+                })
+                .unwrap_or_else(|| word.to_string())
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tidy_transcript() {
+        assert_eq!(
+            tidy_transcript("  Dinner on Friday at 7 P.M. at\n Luigi's.  "),
+            "Dinner on Friday at 7 pm at Luigi's."
+        );
+        assert_eq!(
+            tidy_transcript("Call at 9 a.m., sharp"),
+            "Call at 9 am, sharp"
+        );
+        assert_eq!(tidy_transcript(" \n "), "");
+        assert!(!VoiceNotes::default().enabled());
+    }
+}
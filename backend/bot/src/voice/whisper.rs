@@ -0,0 +1,121 @@
+//! whisper.cpp server transcription
+//!
+//! Posts the audio to the `/inference` endpoint of a `whisper-server`. It
+//! needs to be started with `--convert` to accept Telegram's OGG/Opus notes.
+
+use anyhow::{Context, Result, bail};
+use reqwest::multipart::{Form, Part};
+use std::env;
+use std::time::Duration;
+
+use super::{TranscribeFuture, Transcriber};
+
+const REQUEST_TIMEOUT_SECS: u64 = 60;
+
+/// Where the server runs and what it listens for
+#[derive(Debug, Clone)]
+pub struct WhisperConfig {
+    /// Base URL of the server, such as `http://localhost:8080`
+    pub url: String,
+    /// Spoken language code, or `auto`; `None` keeps the server's default
+    pub language: Option<String>,
+}
+
+impl WhisperConfig {
+    pub(super) fn from_env() -> Result<Self> {
+        Ok(Self {
+            url: env::var("WHISPER_URL")
+                .context("WHISPER_URL must be set with TRANSCRIPTION_PROVIDER=whisper")?
+                .trim_end_matches('/')
+                .to_string(),
+            language: env::var("WHISPER_LANGUAGE")
+                .ok()
+                .filter(|language| !language.trim().is_empty()),
+        })
+    }
+}
+
+pub(super) struct WhisperTranscriber {
+    http: reqwest::Client,
+    config: WhisperConfig,
+}
+
+impl WhisperTranscriber {
+    pub(super) fn new(config: &WhisperConfig) -> Result<Self> {
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS))
+            .user_agent(concat!("Televent/", env!("CARGO_PKG_VERSION")))
+            .build()?;
+
+        Ok(Self {
+            http,
+            config: config.clone(),
+        })
+    }
+
+    async fn post_audio(&self, audio: Vec<u8>, mime_type: &str) -> Result<String> {
+        let file = Part::bytes(audio)
+            .file_name("voice.ogg")
+            .mime_str(mime_type)?;
+        let mut form = Form::new()
+            .part("file", file)
+            .text("response_format", "json")
+            .text("temperature", "0.0");
+        if let Some(language) = &self.config.language {
+            form = form.text("language", language.clone());
+        }
+
+        let response = self
+            .http
+            .post(format!("{}/inference", self.config.url))
+            .multipart(form)
+            .send()
+            .await
+            .context("Whisper request failed")?;
+        let status = response.status();
+        if !status.is_success() {
+            let body: String = response
+                .text()
+                .await
+                .unwrap_or_default()
+                .chars()
+                .take(200)
+                .collect();
+            bail!("Whisper returned {status}: {body}");
+        }
+
+        transcript_text(&response.json().await?)
+    }
+}
+
+impl Transcriber for WhisperTranscriber {
+    fn transcribe<'a>(&'a self, audio: Vec<u8>, mime_type: &'a str) -> TranscribeFuture<'a> {
+        Box::pin(self.post_audio(audio, mime_type))
+    }
+}
+
+/// The `text` of a JSON answer; errors come back as `{"error": ...}`
+fn transcript_text(answer: &serde_json::Value) -> Result<String> {
+    if let Some(text) = answer.get("text").and_then(|text| text.as_str()) {
+        return Ok(text.to_string());
+    }
+    match answer.get("error") {
+        Some(error) => bail!("Whisper could not transcribe: {error}"),
+        None => bail!("Whisper answer has no text"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transcript_text() {
+        assert_eq!(
+            transcript_text(&serde_json::json!({ "text": " Lunch tomorrow at noon.\n" })).unwrap(),
+            " Lunch tomorrow at noon.\n"
+        );
+        assert!(transcript_text(&serde_json::json!({ "error": "failed to read WAV" })).is_err());
+        assert!(transcript_text(&serde_json::json!({})).is_err());
+    }
+}
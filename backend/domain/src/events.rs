@@ -0,0 +1,488 @@
+//! Domain events emitted by calendar write paths.
+//!
+//! Every adapter (REST, CalDAV, bot) mutates calendars through application use
+//! cases, which describe what changed as a [`DomainEvent`]. The follow-up work
+//! (sync-token bumps, tombstones, outbox messages, cache invalidation) is
+//! derived from these events in one place instead of being repeated per path.
+
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::{
+    AttendeeRemovedNotification, CancellationEmail, DeviceId, EventCancelledNotification,
+    EventTiming, EventUpdateEmail, EventUpdatedNotification, ExternalEmailDeferred,
+    InviteNotification, InviteReminder, OutboxPayload, ParticipationStatus, RsvpNotification,
+    SignupConfirmation, TelegramNotification, Timezone, UserId,
+};
+
+/// Reason recorded on deferred external invites while email delivery is off.
+pub const EXTERNAL_EMAIL_DISABLED_REASON: &str = "External email delivery is disabled";
+
+/// Changes to an event within this many minutes reach each guest as one
+/// message, sent when the window closes.
+pub const EVENT_UPDATE_WINDOW_MINUTES: i64 = 5;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DomainEvent {
+    EventCreated {
+        calendar_owner: UserId,
+        event_id: Uuid,
+    },
+    EventUpdated {
+        calendar_owner: UserId,
+        event_id: Uuid,
+    },
+    EventDeleted {
+        calendar_owner: UserId,
+        event_id: Uuid,
+        uid: String,
+    },
+    AttendeeInvited {
+        calendar_owner: UserId,
+        event_id: Uuid,
+        event_summary: String,
+        email: String,
+        attendee_user_id: Option<UserId>,
+    },
+    AttendeeRemoved {
+        calendar_owner: UserId,
+        event_id: Uuid,
+        event_summary: String,
+        email: String,
+        attendee_user_id: Option<UserId>,
+    },
+    /// One guest of an event that was just cancelled; the status change itself
+    /// is reported as [`DomainEvent::EventUpdated`].
+    EventCancelled {
+        event_id: Uuid,
+        event_summary: String,
+        email: String,
+        attendee_user_id: Option<UserId>,
+    },
+    /// One guest who is coming to an event that just moved or changed place;
+    /// the change itself is reported as [`DomainEvent::EventUpdated`].
+    EventRescheduled {
+        event_id: Uuid,
+        event_summary: String,
+        email: String,
+        attendee_user_id: Option<UserId>,
+        previous_timing: EventTiming,
+        previous_location: Option<String>,
+        changed_at: DateTime<Utc>,
+    },
+    /// Organizer re-sent a pending invite; the calendar itself is unchanged.
+    InviteResent {
+        event_id: Uuid,
+        attendee_user_id: UserId,
+    },
+    /// Someone asked to join a public event; nothing changes until they confirm.
+    PublicSignupRequested {
+        event_id: Uuid,
+        event_summary: String,
+        email: String,
+    },
+    /// A visitor booked a slot on one of the owner's booking links; the
+    /// tentative event itself is reported as [`DomainEvent::EventCreated`].
+    SlotBooked {
+        owner: UserId,
+        event_id: Uuid,
+        link_title: String,
+        booker: String,
+        start: DateTime<Utc>,
+        timezone: Timezone,
+    },
+    RsvpRecorded {
+        calendar_owner: UserId,
+        event_id: Uuid,
+        event_summary: String,
+        attendee_name: String,
+        status: ParticipationStatus,
+    },
+    DevicePasswordRevoked {
+        user_id: UserId,
+        device_id: DeviceId,
+    },
+}
+
+impl DomainEvent {
+    /// Calendar whose sync state (sync token and ctag) changes with this event.
+    #[must_use]
+    pub const fn calendar_owner(&self) -> Option<UserId> {
+        match self {
+            Self::EventCreated { calendar_owner, .. }
+            | Self::EventUpdated { calendar_owner, .. }
+            | Self::EventDeleted { calendar_owner, .. }
+            | Self::AttendeeInvited { calendar_owner, .. }
+            | Self::AttendeeRemoved { calendar_owner, .. }
+            | Self::RsvpRecorded { calendar_owner, .. } => Some(*calendar_owner),
+            Self::EventCancelled { .. }
+            | Self::EventRescheduled { .. }
+            | Self::InviteResent { .. }
+            | Self::PublicSignupRequested { .. }
+            | Self::SlotBooked { .. }
+            | Self::DevicePasswordRevoked { .. } => None,
+        }
+    }
+
+    /// Notification that must be queued in the same transaction as the change.
+    #[must_use]
+    pub fn outbox_payload(&self) -> Option<OutboxPayload> {
+        match self {
+            Self::AttendeeInvited {
+                event_id,
+                event_summary,
+                email,
+                attendee_user_id,
+                ..
+            } => Some(match attendee_user_id {
+                Some(target_user_id) => OutboxPayload::InviteNotification(InviteNotification {
+                    event_id: *event_id,
+                    target_user_id: target_user_id.inner(),
+                }),
+                None => OutboxPayload::ExternalEmailDeferred(ExternalEmailDeferred {
+                    recipient_email: email.clone(),
+                    event_summary: event_summary.clone(),
+                    reason: EXTERNAL_EMAIL_DISABLED_REASON.to_string(),
+                }),
+            }),
+            Self::AttendeeRemoved {
+                event_summary,
+                attendee_user_id: Some(target_user_id),
+                ..
+            } => Some(OutboxPayload::AttendeeRemovedNotification(
+                AttendeeRemovedNotification {
+                    target_user_id: target_user_id.inner(),
+                    event_summary: event_summary.clone(),
+                },
+            )),
+            Self::EventCancelled {
+                event_id,
+                event_summary,
+                email,
+                attendee_user_id,
+            } => Some(match attendee_user_id {
+                Some(target_user_id) => {
+                    OutboxPayload::EventCancelledNotification(EventCancelledNotification {
+                        event_id: *event_id,
+                        target_user_id: target_user_id.inner(),
+                        event_summary: event_summary.clone(),
+                    })
+                }
+                None => OutboxPayload::CancellationEmail(CancellationEmail {
+                    event_id: *event_id,
+                    recipient_email: email.clone(),
+                    event_summary: event_summary.clone(),
+                }),
+            }),
+            Self::EventRescheduled {
+                event_id,
+                event_summary,
+                email,
+                attendee_user_id,
+                previous_timing,
+                previous_location,
+                changed_at,
+            } => Some(match attendee_user_id {
+                Some(target_user_id) => OutboxPayload::EventUpdated(EventUpdatedNotification {
+                    event_id: *event_id,
+                    target_user_id: target_user_id.inner(),
+                    event_summary: event_summary.clone(),
+                    previous_timing: previous_timing.clone(),
+                    previous_location: previous_location.clone(),
+                    window_start: update_window_start(*changed_at),
+                }),
+                None => OutboxPayload::EventUpdateEmail(EventUpdateEmail {
+                    event_id: *event_id,
+                    recipient_email: email.clone(),
+                    event_summary: event_summary.clone(),
+                    previous_timing: previous_timing.clone(),
+                    previous_location: previous_location.clone(),
+                    window_start: update_window_start(*changed_at),
+                }),
+            }),
+            Self::InviteResent {
+                event_id,
+                attendee_user_id,
+            } => Some(OutboxPayload::InviteReminder(InviteReminder {
+                event_id: *event_id,
+                target_user_id: attendee_user_id.inner(),
+            })),
+            Self::PublicSignupRequested {
+                event_id,
+                event_summary,
+                email,
+            } => Some(OutboxPayload::SignupConfirmation(SignupConfirmation {
+                event_id: *event_id,
+                recipient_email: email.clone(),
+                event_summary: event_summary.clone(),
+            })),
+            Self::SlotBooked {
+                owner,
+                link_title,
+                booker,
+                start,
+                timezone,
+                ..
+            } => Some(OutboxPayload::TelegramNotification(TelegramNotification {
+                telegram_id: owner.inner(),
+                message: format!(
+                    "📅 {booker} booked \"{link_title}\" for {}. It is on your calendar \
+                     as tentative; confirm or cancel it in Televent.",
+                    start
+                        .with_timezone(&timezone.tz())
+                        .format("%a %d %b, %H:%M")
+                ),
+            })),
+            Self::RsvpRecorded {
+                calendar_owner,
+                event_summary,
+                attendee_name,
+                status,
+                ..
+            } => Some(OutboxPayload::RsvpNotification(RsvpNotification {
+                organizer_telegram_id: calendar_owner.inner(),
+                attendee_name: attendee_name.clone(),
+                event_summary: event_summary.clone(),
+                rsvp_status: *status,
+            })),
+            // External guests are not notified while email delivery is off
+            Self::AttendeeRemoved {
+                attendee_user_id: None,
+                ..
+            }
+            | Self::EventCreated { .. }
+            | Self::EventUpdated { .. }
+            | Self::EventDeleted { .. }
+            | Self::DevicePasswordRevoked { .. } => None,
+        }
+    }
+}
+
+/// Start of the [`EVENT_UPDATE_WINDOW_MINUTES`] window `time` falls in
+fn update_window_start(time: DateTime<Utc>) -> DateTime<Utc> {
+    let window = EVENT_UPDATE_WINDOW_MINUTES * 60;
+    DateTime::from_timestamp(time.timestamp() - time.timestamp().rem_euclid(window), 0)
+        .unwrap_or(time)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn internal_invite_maps_to_invite_notification() {
+        let event_id = Uuid::new_v4();
+        let event = DomainEvent::AttendeeInvited {
+            calendar_owner: UserId::new(1),
+            event_id,
+            event_summary: "Standup".to_string(),
+            email: "tg_2@televent.internal".to_string(),
+            attendee_user_id: Some(UserId::new(2)),
+        };
+
+        assert_eq!(event.calendar_owner(), Some(UserId::new(1)));
+        assert_eq!(
+            event.outbox_payload(),
+            Some(OutboxPayload::InviteNotification(InviteNotification {
+                event_id,
+                target_user_id: 2,
+            }))
+        );
+    }
+
+    #[test]
+    fn external_invite_maps_to_deferred_email() {
+        let event = DomainEvent::AttendeeInvited {
+            calendar_owner: UserId::new(1),
+            event_id: Uuid::new_v4(),
+            event_summary: "Standup".to_string(),
+            email: "guest@example.com".to_string(),
+            attendee_user_id: None,
+        };
+
+        assert!(matches!(
+            event.outbox_payload(),
+            Some(OutboxPayload::ExternalEmailDeferred(ExternalEmailDeferred { recipient_email, .. }))
+                if recipient_email == "guest@example.com"
+        ));
+    }
+
+    #[test]
+    fn booking_notifies_owner_in_their_timezone() {
+        let event = DomainEvent::SlotBooked {
+            owner: UserId::new(10),
+            event_id: Uuid::new_v4(),
+            link_title: "Intro call".to_string(),
+            booker: "Ada".to_string(),
+            start: DateTime::parse_from_rfc3339("2026-11-02T13:00:00Z")
+                .unwrap()
+                .with_timezone(&Utc),
+            timezone: Timezone::parse("Europe/Berlin").unwrap(),
+        };
+
+        assert_eq!(event.calendar_owner(), None);
+        assert!(matches!(
+            event.outbox_payload(),
+            Some(OutboxPayload::TelegramNotification(TelegramNotification {
+                telegram_id: 10,
+                message,
+            })) if message.starts_with("📅 Ada booked \"Intro call\" for Mon 02 Nov, 14:00.")
+        ));
+    }
+
+    #[test]
+    fn rsvp_notifies_calendar_owner() {
+        let event = DomainEvent::RsvpRecorded {
+            calendar_owner: UserId::new(10),
+            event_id: Uuid::new_v4(),
+            event_summary: "Party".to_string(),
+            attendee_name: "alice".to_string(),
+            status: ParticipationStatus::Accepted,
+        };
+
+        assert!(matches!(
+            event.outbox_payload(),
+            Some(OutboxPayload::RsvpNotification(RsvpNotification {
+                organizer_telegram_id: 10,
+                ..
+            }))
+        ));
+    }
+
+    #[test]
+    fn removing_internal_attendee_notifies_them() {
+        let removed = DomainEvent::AttendeeRemoved {
+            calendar_owner: UserId::new(1),
+            event_id: Uuid::new_v4(),
+            event_summary: "Standup".to_string(),
+            email: "tg_2@televent.internal".to_string(),
+            attendee_user_id: Some(UserId::new(2)),
+        };
+        assert_eq!(
+            removed.outbox_payload(),
+            Some(OutboxPayload::AttendeeRemovedNotification(
+                AttendeeRemovedNotification {
+                    target_user_id: 2,
+                    event_summary: "Standup".to_string(),
+                }
+            ))
+        );
+
+        let resent = DomainEvent::InviteResent {
+            event_id: Uuid::new_v4(),
+            attendee_user_id: UserId::new(2),
+        };
+        assert_eq!(resent.calendar_owner(), None);
+        assert!(matches!(
+            resent.outbox_payload(),
+            Some(OutboxPayload::InviteReminder(InviteReminder {
+                target_user_id: 2,
+                ..
+            }))
+        ));
+    }
+
+    #[test]
+    fn cancellation_notifies_each_guest_by_channel() {
+        let event_id = Uuid::new_v4();
+        let internal = DomainEvent::EventCancelled {
+            event_id,
+            event_summary: "Standup".to_string(),
+            email: "tg_2@televent.internal".to_string(),
+            attendee_user_id: Some(UserId::new(2)),
+        };
+        assert_eq!(internal.calendar_owner(), None);
+        assert_eq!(
+            internal.outbox_payload(),
+            Some(OutboxPayload::EventCancelledNotification(
+                EventCancelledNotification {
+                    event_id,
+                    target_user_id: 2,
+                    event_summary: "Standup".to_string(),
+                }
+            ))
+        );
+
+        let external = DomainEvent::EventCancelled {
+            event_id,
+            event_summary: "Standup".to_string(),
+            email: "guest@example.com".to_string(),
+            attendee_user_id: None,
+        };
+        assert!(matches!(
+            external.outbox_payload(),
+            Some(OutboxPayload::CancellationEmail(CancellationEmail { recipient_email, .. }))
+                if recipient_email == "guest@example.com"
+        ));
+    }
+
+    #[test]
+    fn reschedules_in_one_window_share_a_message() {
+        let event_id = Uuid::new_v4();
+        let start = "2026-06-01T10:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let rescheduled_for =
+            |changed_at: &str, email: &str, user: Option<UserId>| DomainEvent::EventRescheduled {
+                event_id,
+                event_summary: "Standup".to_string(),
+                email: email.to_string(),
+                attendee_user_id: user,
+                previous_timing: EventTiming::Timed {
+                    start,
+                    end: start + chrono::Duration::minutes(15),
+                    timezone: crate::Timezone::utc(),
+                },
+                previous_location: None,
+                changed_at: changed_at.parse().unwrap(),
+            };
+        let rescheduled = |changed_at: &str| {
+            rescheduled_for(changed_at, "tg_2@televent.internal", Some(UserId::new(2)))
+        };
+
+        let first = rescheduled("2026-05-20T09:01:10Z")
+            .outbox_payload()
+            .unwrap();
+        let second = rescheduled("2026-05-20T09:04:59Z")
+            .outbox_payload()
+            .unwrap();
+        let later = rescheduled("2026-05-20T09:05:00Z")
+            .outbox_payload()
+            .unwrap();
+        assert_eq!(first.dedupe_key(), second.dedupe_key());
+        assert_ne!(first.dedupe_key(), later.dedupe_key());
+        assert_eq!(
+            first.deliver_after(),
+            Some("2026-05-20T09:05:00Z".parse().unwrap())
+        );
+        assert_eq!(first.shard_user_id(), Some(2));
+
+        let external = rescheduled_for("2026-05-20T09:01:10Z", "guest@example.com", None)
+            .outbox_payload()
+            .unwrap();
+        assert!(matches!(external, OutboxPayload::EventUpdateEmail(_)));
+        assert_eq!(external.deliver_after(), first.deliver_after());
+        assert_ne!(external.dedupe_key(), first.dedupe_key());
+        assert_eq!(external.shard_user_id(), None);
+    }
+
+    #[test]
+    fn content_and_device_events_queue_nothing() {
+        let user_id = UserId::new(1);
+        let event_id = Uuid::new_v4();
+
+        assert!(
+            DomainEvent::EventUpdated {
+                calendar_owner: user_id,
+                event_id,
+            }
+            .outbox_payload()
+            .is_none()
+        );
+
+        let revoked = DomainEvent::DevicePasswordRevoked {
+            user_id,
+            device_id: DeviceId::new(Uuid::new_v4()),
+        };
+        assert!(revoked.outbox_payload().is_none());
+        assert_eq!(revoked.calendar_owner(), None);
+    }
+}
@@ -2,11 +2,18 @@
 //!
 //! This crate intentionally has no database, HTTP, Telegram, OpenAPI, or
 //! frontend type-generation dependencies. Adapters translate into these types
-//! before invoking application use cases.
+//! before invoking application use cases; the `sqlx` and `utoipa` features
+//! let storage and the API use the id newtypes directly.
 
+pub mod clock;
+pub mod events;
 pub mod recurrence;
+pub mod tags;
+pub mod telegram_html;
+pub mod working_hours;
+mod zones;
 
-use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, Offset, TimeZone, Utc};
 use chrono_tz::Tz;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
@@ -29,6 +36,8 @@ pub enum DomainError {
     UnknownOutboxKind(String),
     #[error("invalid outbox payload for {kind}: {reason}")]
     InvalidOutboxPayload { kind: String, reason: String },
+    #[error("{0}")]
+    InvalidWorkingHours(String),
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -59,6 +68,68 @@ impl From<UserId> for i64 {
     }
 }
 
+/// Id of an `outbox_messages` row
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::Type), sqlx(transparent))]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[serde(transparent)]
+pub struct OutboxMessageId(pub Uuid);
+
+impl OutboxMessageId {
+    #[must_use]
+    pub const fn new(value: Uuid) -> Self {
+        Self(value)
+    }
+
+    #[must_use]
+    pub const fn inner(self) -> Uuid {
+        self.0
+    }
+}
+
+impl fmt::Display for OutboxMessageId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<OutboxMessageId> for Uuid {
+    fn from(id: OutboxMessageId) -> Self {
+        id.0
+    }
+}
+
+/// Id of a device password, which is how CalDAV clients are told apart
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::Type), sqlx(transparent))]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[serde(transparent)]
+pub struct DeviceId(pub Uuid);
+
+impl DeviceId {
+    #[must_use]
+    pub const fn new(value: Uuid) -> Self {
+        Self(value)
+    }
+
+    #[must_use]
+    pub const fn inner(self) -> Uuid {
+        self.0
+    }
+}
+
+impl fmt::Display for DeviceId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<DeviceId> for Uuid {
+    fn from(id: DeviceId) -> Self {
+        id.0
+    }
+}
+
 pub const INTERNAL_EMAIL_DOMAIN: &str = "televent.internal";
 pub const CALENDAR_NAME: &str = "televent";
 pub const CALENDAR_COLOR: &str = "#74c7ec";
@@ -79,13 +150,21 @@ pub fn parse_internal_email_telegram_id(email: &str) -> Option<i64> {
     id.parse().ok()
 }
 
-pub use recurrence::{expand_rrule, next_occurrences, validate_rrule};
+pub use clock::{Clock, MockClock, SharedClock, SystemClock};
+pub use events::{DomainEvent, EVENT_UPDATE_WINDOW_MINUTES, EXTERNAL_EMAIL_DISABLED_REASON};
+pub use recurrence::{
+    SeriesSplit, expand_rrule, next_occurrences, occurrence_after, split_rrule, validate_rrule,
+};
+pub use working_hours::{DayHours, WorkingHours};
+pub use zones::timezone_near;
 
 pub const MAX_UID_LENGTH: usize = 256;
 pub const MAX_SUMMARY_LENGTH: usize = 256;
 pub const MAX_DESCRIPTION_LENGTH: usize = 10000;
 pub const MAX_LOCATION_LENGTH: usize = 1024;
 pub const MAX_RRULE_LENGTH: usize = 1024;
+pub const MAX_REMINDER_NOTE_LENGTH: usize = 500;
+pub const MAX_EMAIL_LENGTH: usize = 254;
 
 pub fn validate_length(field_name: &str, value: &str, max_len: usize) -> Result<(), String> {
     if value.len() > max_len {
@@ -114,6 +193,73 @@ pub fn validate_safe_multiline_text(field_name: &str, value: &str) -> Result<(),
     }
 }
 
+/// Single-line text with line breaks turned into spaces and every other
+/// control character except tab dropped.
+pub fn sanitize_single_line_text(value: &str) -> String {
+    let mut clean = String::with_capacity(value.len());
+    let mut chars = value.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\r' | '\n' => clean.push(' '),
+            '\t' => clean.push(c),
+            c if c.is_control() => {}
+            c => clean.push(c),
+        }
+    }
+    clean
+}
+
+/// Multi-line text with `\r\n` and lone `\r` normalized to `\n` and every
+/// other control character except tab dropped.
+pub fn sanitize_multiline_text(value: &str) -> String {
+    let mut clean = String::with_capacity(value.len());
+    let mut chars = value.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\r' => clean.push('\n'),
+            '\n' | '\t' => clean.push(c),
+            c if c.is_control() => {}
+            c => clean.push(c),
+        }
+    }
+    clean
+}
+
+/// Cut `value` to at most `max_len` bytes without splitting a character.
+pub fn truncate_to_length(value: &mut String, max_len: usize) {
+    if value.len() <= max_len {
+        return;
+    }
+    let mut end = max_len;
+    while !value.is_char_boundary(end) {
+        end -= 1;
+    }
+    value.truncate(end);
+}
+
+/// Loose shape check for addresses typed by people outside Telegram.
+pub fn validate_email_address(value: &str) -> Result<(), String> {
+    validate_length("Email", value, MAX_EMAIL_LENGTH)?;
+    let valid = match value.split_once('@') {
+        Some((local, domain)) => {
+            !local.is_empty()
+                && domain.contains('.')
+                && !domain.starts_with('.')
+                && !domain.ends_with('.')
+                && !domain.contains('@')
+                && !value.chars().any(|c| c.is_whitespace() || c.is_control())
+        }
+        None => false,
+    };
+    if valid {
+        Ok(())
+    } else {
+        Err("Invalid email address".to_string())
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(transparent)]
 pub struct Timezone(String);
@@ -134,6 +280,12 @@ impl Timezone {
     pub fn as_str(&self) -> &str {
         &self.0
     }
+
+    /// Zone for local-time arithmetic; the name was validated on parse.
+    #[must_use]
+    pub fn tz(&self) -> Tz {
+        Tz::from_str(&self.0).unwrap_or(Tz::UTC)
+    }
 }
 
 impl Default for Timezone {
@@ -154,12 +306,19 @@ pub enum EventTiming {
         start_date: NaiveDate,
         end_date: NaiveDate,
     },
+    /// Wall-clock times without a zone (RFC 5545 floating time), read in
+    /// whatever zone the viewer is in.
+    Floating {
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+    },
 }
 
 impl EventTiming {
     pub fn validate(&self) -> Result<(), DomainError> {
         match self {
             Self::Timed { start, end, .. } if end <= start => Err(DomainError::InvalidTimedRange),
+            Self::Floating { start, end } if end <= start => Err(DomainError::InvalidTimedRange),
             Self::AllDay {
                 start_date,
                 end_date,
@@ -172,7 +331,50 @@ impl EventTiming {
     pub fn timezone(&self) -> &str {
         match self {
             Self::Timed { timezone, .. } => timezone.as_str(),
-            Self::AllDay { .. } => "UTC",
+            Self::AllDay { .. } | Self::Floating { .. } => "UTC",
+        }
+    }
+
+    /// Floating times as they fall in `timezone`; other timings are unchanged.
+    #[must_use]
+    pub fn pinned_to(&self, timezone: &Timezone) -> Self {
+        match self {
+            Self::Floating { start, end } => {
+                let tz = timezone.tz();
+                Self::Timed {
+                    start: local_to_utc(&tz, start),
+                    end: local_to_utc(&tz, end),
+                    timezone: timezone.clone(),
+                }
+            }
+            _ => self.clone(),
+        }
+    }
+
+    /// The same timing moved by `offset`; all-day events move by whole days
+    #[must_use]
+    pub fn shifted(&self, offset: chrono::Duration) -> Self {
+        match self {
+            Self::Timed {
+                start,
+                end,
+                timezone,
+            } => Self::Timed {
+                start: *start + offset,
+                end: *end + offset,
+                timezone: timezone.clone(),
+            },
+            Self::AllDay {
+                start_date,
+                end_date,
+            } => Self::AllDay {
+                start_date: *start_date + offset,
+                end_date: *end_date + offset,
+            },
+            Self::Floating { start, end } => Self::Floating {
+                start: *start + offset,
+                end: *end + offset,
+            },
         }
     }
 
@@ -180,6 +382,7 @@ impl EventTiming {
     pub fn start_for_display(&self) -> DateTime<Utc> {
         match self {
             Self::Timed { start, .. } => *start,
+            Self::Floating { start, .. } => start.and_utc(),
             Self::AllDay { start_date, .. } => start_date
                 .and_hms_opt(0, 0, 0)
                 .expect("midnight is a valid time")
@@ -206,6 +409,64 @@ impl EventStatus {
     }
 }
 
+/// Summary others see in place of a busy-only event
+pub const BUSY_SUMMARY: &str = "Busy";
+
+/// How much of an event people other than its owner see
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventVisibility {
+    /// Everything, wherever the calendar is shown
+    #[default]
+    Public,
+    /// A [`BUSY_SUMMARY`] block at its time, without any details
+    BusyOnly,
+    /// Nothing; the time only counts as busy in free/busy
+    Private,
+}
+
+impl EventVisibility {
+    #[must_use]
+    pub const fn as_sql(self) -> &'static str {
+        match self {
+            Self::Public => "public",
+            Self::BusyOnly => "busy_only",
+            Self::Private => "private",
+        }
+    }
+
+    #[must_use]
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "public" => Some(Self::Public),
+            "busy_only" => Some(Self::BusyOnly),
+            "private" => Some(Self::Private),
+            _ => None,
+        }
+    }
+
+    /// iCalendar `CLASS` carrying this visibility
+    #[must_use]
+    pub const fn ical_class(self) -> &'static str {
+        match self {
+            Self::Public => "PUBLIC",
+            Self::BusyOnly => "CONFIDENTIAL",
+            Self::Private => "PRIVATE",
+        }
+    }
+
+    /// Visibility of an iCalendar `CLASS`; unknown classes are private, as
+    /// RFC 5545 asks
+    #[must_use]
+    pub fn from_ical_class(class: &str) -> Self {
+        match class.trim().to_ascii_uppercase().as_str() {
+            "PUBLIC" => Self::Public,
+            "CONFIDENTIAL" => Self::BusyOnly,
+            _ => Self::Private,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum AttendeeRole {
     Organizer,
@@ -262,6 +523,191 @@ impl ParticipationStatus {
     }
 }
 
+/// Access level of a user; each role includes the ones below it
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum UserRole {
+    #[default]
+    User,
+    /// Operational tooling such as queue inspection
+    Operator,
+    /// Everything, including roles and feature flags
+    Admin,
+}
+
+impl UserRole {
+    #[must_use]
+    pub const fn as_sql(self) -> &'static str {
+        match self {
+            Self::User => "user",
+            Self::Operator => "operator",
+            Self::Admin => "admin",
+        }
+    }
+
+    #[must_use]
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "user" => Some(Self::User),
+            "operator" => Some(Self::Operator),
+            "admin" => Some(Self::Admin),
+            _ => None,
+        }
+    }
+
+    /// Whether this role may do what `required` may
+    #[must_use]
+    pub fn includes(self, required: Self) -> bool {
+        self >= required
+    }
+}
+
+/// Chat service behind an incoming webhook
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChatWebhookProvider {
+    Slack,
+    /// Microsoft Teams
+    Teams,
+}
+
+impl ChatWebhookProvider {
+    #[must_use]
+    pub const fn as_sql(self) -> &'static str {
+        match self {
+            Self::Slack => "slack",
+            Self::Teams => "teams",
+        }
+    }
+
+    #[must_use]
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "slack" => Some(Self::Slack),
+            "teams" => Some(Self::Teams),
+            _ => None,
+        }
+    }
+}
+
+/// What a notice is about; one row of the notification preference matrix
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NotificationTopic {
+    Invite,
+    Reminder,
+    /// An event the user is invited to moved or changed place
+    Update,
+    Cancellation,
+}
+
+impl NotificationTopic {
+    pub const ALL: [Self; 4] = [
+        Self::Invite,
+        Self::Reminder,
+        Self::Update,
+        Self::Cancellation,
+    ];
+
+    #[must_use]
+    pub const fn as_sql(self) -> &'static str {
+        match self {
+            Self::Invite => "invite",
+            Self::Reminder => "reminder",
+            Self::Update => "update",
+            Self::Cancellation => "cancellation",
+        }
+    }
+
+    #[must_use]
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "invite" => Some(Self::Invite),
+            "reminder" => Some(Self::Reminder),
+            "update" => Some(Self::Update),
+            "cancellation" => Some(Self::Cancellation),
+            _ => None,
+        }
+    }
+}
+
+/// Where copies of a Telegram notice go; one column of the notification
+/// preference matrix. Telegram itself always gets every notice.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NotificationChannel {
+    /// Slack and Teams webhooks
+    Chat,
+    Sms,
+    /// Web Push to the browsers the user subscribed
+    Push,
+    /// The user's confirmed email address
+    Email,
+}
+
+impl NotificationChannel {
+    pub const ALL: [Self; 4] = [Self::Chat, Self::Sms, Self::Push, Self::Email];
+
+    #[must_use]
+    pub const fn as_sql(self) -> &'static str {
+        match self {
+            Self::Chat => "chat",
+            Self::Sms => "sms",
+            Self::Push => "push",
+            Self::Email => "email",
+        }
+    }
+
+    #[must_use]
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "chat" => Some(Self::Chat),
+            "sms" => Some(Self::Sms),
+            "push" => Some(Self::Push),
+            "email" => Some(Self::Email),
+            _ => None,
+        }
+    }
+
+    /// Whether the channel gets a topic the user has not chosen for; text
+    /// messages cost money and email is a fallback, so both are opt-in
+    #[must_use]
+    pub const fn enabled_by_default(self) -> bool {
+        match self {
+            Self::Chat | Self::Push => true,
+            Self::Sms | Self::Email => false,
+        }
+    }
+}
+
+/// Bring a phone number into E.164 form, e.g. `+44 20 7946 0958` to
+/// `+442079460958`. Spaces, dots, dashes and parentheses are dropped; the
+/// country code is required.
+pub fn normalize_phone_number(value: &str) -> Result<String, String> {
+    let value = value.trim();
+    let Some(rest) = value.strip_prefix('+') else {
+        return Err("Phone number must start with + and the country code".to_string());
+    };
+    let mut digits = String::with_capacity(rest.len() + 1);
+    digits.push('+');
+    for ch in rest.chars() {
+        match ch {
+            '0'..='9' => digits.push(ch),
+            ' ' | '.' | '-' | '(' | ')' => {}
+            _ => return Err("Phone number may only contain digits".to_string()),
+        }
+    }
+    // E.164 allows up to 15 digits and country codes never start with 0
+    let count = digits.len() - 1;
+    if !(8..=15).contains(&count) || digits.as_bytes()[1] == b'0' {
+        return Err("Not a valid international phone number".to_string());
+    }
+
+    Ok(digits)
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct AttendeeFingerprint {
     pub email: String,
@@ -279,10 +725,15 @@ pub struct EventEtagInput {
     pub timing: EventTiming,
     pub status: EventStatus,
     pub rrule: Option<String>,
-    pub version: i32,
+    pub visibility: EventVisibility,
+    pub reminder_note: Option<String>,
     pub attendees: Vec<AttendeeFingerprint>,
 }
 
+/// Hash of the canonical event content.
+///
+/// Revision counters are deliberately excluded: writing identical content
+/// yields the same etag, so clients don't re-download unchanged events.
 #[must_use]
 pub fn compute_event_etag(input: &EventEtagInput) -> String {
     let mut attendees = input.attendees.clone();
@@ -297,8 +748,6 @@ pub fn compute_event_etag(input: &EventEtagInput) -> String {
     hasher.update(b"|");
     hasher.update(input.location.as_deref().unwrap_or("").as_bytes());
     hasher.update(b"|");
-    hasher.update(input.version.to_be_bytes());
-    hasher.update(b"|");
 
     match &input.timing {
         EventTiming::Timed {
@@ -322,12 +771,28 @@ pub fn compute_event_etag(input: &EventEtagInput) -> String {
             hasher.update(b"|");
             hasher.update(end_date.num_days_from_ce().to_be_bytes());
         }
+        EventTiming::Floating { start, end } => {
+            hasher.update(b"floating|");
+            hash_datetime(&mut hasher, &start.and_utc());
+            hasher.update(b"|");
+            hash_datetime(&mut hasher, &end.and_utc());
+        }
     }
 
     hasher.update(b"|");
     hasher.update(input.status.as_sql().as_bytes());
     hasher.update(b"|");
     hasher.update(input.rrule.as_deref().unwrap_or("").as_bytes());
+    // Left out when public so etags from before visibility existed still match
+    if input.visibility != EventVisibility::Public {
+        hasher.update(b"|visibility|");
+        hasher.update(input.visibility.as_sql().as_bytes());
+    }
+    // Likewise left out when there is none
+    if let Some(note) = &input.reminder_note {
+        hasher.update(b"|reminder_note|");
+        hasher.update(note.as_bytes());
+    }
 
     for attendee in attendees {
         hasher.update(b"|attendee|");
@@ -343,6 +808,19 @@ pub fn compute_event_etag(input: &EventEtagInput) -> String {
     format!("{:x}", hasher.finalize())
 }
 
+/// Local wall-clock time in `tz`; a time skipped by a DST jump keeps the
+/// offset from before the jump, so it lands as far past the jump as it was
+/// into the gap.
+fn local_to_utc(tz: &Tz, local: &NaiveDateTime) -> DateTime<Utc> {
+    tz.from_local_datetime(local)
+        .earliest()
+        .map(|time| time.with_timezone(&Utc))
+        .unwrap_or_else(|| {
+            let before = tz.offset_from_utc_datetime(&(*local - chrono::Duration::days(1)));
+            (*local - before.fix()).and_utc()
+        })
+}
+
 fn hash_datetime(hasher: &mut Sha256, value: &DateTime<Utc>) {
     hasher.update(value.timestamp().to_be_bytes());
     hasher.update(value.timestamp_subsec_nanos().to_be_bytes());
@@ -355,6 +833,18 @@ pub enum OutboxKind {
     TelegramNotification,
     ExternalEmailDeferred,
     RsvpNotification,
+    InviteReminder,
+    AttendeeRemovedNotification,
+    SignupConfirmation,
+    EventCancelledNotification,
+    CancellationEmail,
+    EventUpdated,
+    EventUpdateEmail,
+    ChatWebhook,
+    Sms,
+    WebPush,
+    TaskBlockReminder,
+    UserEmail,
 }
 
 impl OutboxKind {
@@ -365,6 +855,18 @@ impl OutboxKind {
             Self::TelegramNotification => "telegram_notification",
             Self::ExternalEmailDeferred => "external_email_deferred",
             Self::RsvpNotification => "rsvp_notification",
+            Self::InviteReminder => "invite_reminder",
+            Self::AttendeeRemovedNotification => "attendee_removed_notification",
+            Self::SignupConfirmation => "signup_confirmation",
+            Self::EventCancelledNotification => "event_cancelled_notification",
+            Self::CancellationEmail => "cancellation_email",
+            Self::EventUpdated => "event_updated",
+            Self::EventUpdateEmail => "event_update_email",
+            Self::ChatWebhook => "chat_webhook",
+            Self::Sms => "sms",
+            Self::WebPush => "web_push",
+            Self::TaskBlockReminder => "task_block_reminder",
+            Self::UserEmail => "user_email",
         }
     }
 }
@@ -378,6 +880,18 @@ impl FromStr for OutboxKind {
             "telegram_notification" => Ok(Self::TelegramNotification),
             "external_email_deferred" => Ok(Self::ExternalEmailDeferred),
             "rsvp_notification" => Ok(Self::RsvpNotification),
+            "invite_reminder" => Ok(Self::InviteReminder),
+            "attendee_removed_notification" => Ok(Self::AttendeeRemovedNotification),
+            "signup_confirmation" => Ok(Self::SignupConfirmation),
+            "event_cancelled_notification" => Ok(Self::EventCancelledNotification),
+            "cancellation_email" => Ok(Self::CancellationEmail),
+            "event_updated" => Ok(Self::EventUpdated),
+            "event_update_email" => Ok(Self::EventUpdateEmail),
+            "chat_webhook" => Ok(Self::ChatWebhook),
+            "sms" => Ok(Self::Sms),
+            "web_push" => Ok(Self::WebPush),
+            "task_block_reminder" => Ok(Self::TaskBlockReminder),
+            "user_email" => Ok(Self::UserEmail),
             other => Err(DomainError::UnknownOutboxKind(other.to_string())),
         }
     }
@@ -410,12 +924,178 @@ pub struct RsvpNotification {
     pub rsvp_status: ParticipationStatus,
 }
 
+/// Organizer-requested repeat of an invite that is still awaiting a reply.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct InviteReminder {
+    pub event_id: Uuid,
+    pub target_user_id: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AttendeeRemovedNotification {
+    pub target_user_id: i64,
+    pub event_summary: String,
+}
+
+/// Confirmation email for a signup on a public event page.
+///
+/// Carries no token; the worker mints the link when it sends the email.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SignupConfirmation {
+    pub event_id: Uuid,
+    pub recipient_email: String,
+    pub event_summary: String,
+}
+
+/// Tells a Telegram guest that the organizer cancelled an event.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct EventCancelledNotification {
+    pub event_id: Uuid,
+    pub target_user_id: i64,
+    pub event_summary: String,
+}
+
+/// iTIP `METHOD:CANCEL` email for an external guest of a cancelled event.
+///
+/// The calendar attachment is rendered when the email is sent, so it carries
+/// the event's latest sequence number.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CancellationEmail {
+    pub event_id: Uuid,
+    pub recipient_email: String,
+    pub event_summary: String,
+}
+
+/// Tells a guest who is coming that the event moved or changed place.
+///
+/// Changes within one window share a message, sent when the window closes;
+/// it compares the time and place from before the first change with the
+/// event as it is then.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct EventUpdatedNotification {
+    pub event_id: Uuid,
+    pub target_user_id: i64,
+    pub event_summary: String,
+    pub previous_timing: EventTiming,
+    pub previous_location: Option<String>,
+    pub window_start: DateTime<Utc>,
+}
+
+/// iTIP `METHOD:REQUEST` email for an external guest who is coming to an
+/// event that moved or changed place.
+///
+/// Batched per window like [`EventUpdatedNotification`]; the attachment is
+/// rendered when the email is sent.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct EventUpdateEmail {
+    pub event_id: Uuid,
+    pub recipient_email: String,
+    pub event_summary: String,
+    pub previous_timing: EventTiming,
+    pub previous_location: Option<String>,
+    pub window_start: DateTime<Utc>,
+}
+
+/// Copy of a Telegram notice for one of the user's Slack or Teams webhooks.
+///
+/// Queued by the worker once the notice is rendered; `source_id` is the
+/// outbox message it copies, so a source that is retried posts only once.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ChatWebhookMessage {
+    pub webhook_id: Uuid,
+    pub user_id: i64,
+    pub source_id: Uuid,
+    pub title: String,
+    pub lines: Vec<String>,
+}
+
+/// A text message to a user's phone
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SmsMessage {
+    pub user_id: i64,
+    pub content: SmsContent,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SmsContent {
+    /// Copy of a Telegram notice for the user's verified number; `source_id`
+    /// is the outbox message it copies, so a retried source texts only once
+    Notice { source_id: Uuid, text: String },
+    /// Code confirming `phone_number`. The worker mints it right before
+    /// sending, so no code sits in the outbox, and sends nothing once the
+    /// user has moved on to another number.
+    VerificationCode { phone_number: String },
+}
+
+/// An email to a user's own address, as opposed to the invites and iTIP
+/// messages sent to guests
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct UserEmailMessage {
+    pub user_id: i64,
+    pub content: UserEmailContent,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum UserEmailContent {
+    /// Copy of a Telegram notice for the user's confirmed address;
+    /// `source_id` is the outbox message it copies, so a retried source
+    /// emails only once
+    Notice {
+        source_id: Uuid,
+        title: String,
+        lines: Vec<String>,
+    },
+    /// Code confirming `email`, minted by the worker right before sending
+    /// like [`SmsContent::VerificationCode`]; nothing is sent once the
+    /// address is confirmed or replaced
+    VerificationCode { email: String },
+}
+
+/// Asks whether a task is done once its confirmed block on the calendar ends.
+///
+/// Queued for the block's end when the block is planned. The worker sends it
+/// only if the task is still open, still linked to `event_id`, and the event
+/// was confirmed and has ended by then.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TaskBlockReminder {
+    pub task_id: Uuid,
+    pub event_id: Uuid,
+    pub target_user_id: i64,
+}
+
+/// Copy of a Telegram notice for one of the user's Web Push subscriptions.
+///
+/// Keyed by `source_id` like [`ChatWebhookMessage`], so a retried source
+/// pushes only once to each browser.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct WebPushMessage {
+    pub subscription_id: Uuid,
+    pub user_id: i64,
+    pub source_id: Uuid,
+    pub title: String,
+    pub lines: Vec<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub enum OutboxPayload {
     InviteNotification(InviteNotification),
     TelegramNotification(TelegramNotification),
     ExternalEmailDeferred(ExternalEmailDeferred),
     RsvpNotification(RsvpNotification),
+    InviteReminder(InviteReminder),
+    AttendeeRemovedNotification(AttendeeRemovedNotification),
+    SignupConfirmation(SignupConfirmation),
+    EventCancelledNotification(EventCancelledNotification),
+    CancellationEmail(CancellationEmail),
+    EventUpdated(EventUpdatedNotification),
+    EventUpdateEmail(EventUpdateEmail),
+    ChatWebhook(ChatWebhookMessage),
+    Sms(SmsMessage),
+    WebPush(WebPushMessage),
+    TaskBlockReminder(TaskBlockReminder),
+    UserEmail(UserEmailMessage),
 }
 
 impl OutboxPayload {
@@ -426,6 +1106,18 @@ impl OutboxPayload {
             Self::TelegramNotification(_) => OutboxKind::TelegramNotification,
             Self::ExternalEmailDeferred(_) => OutboxKind::ExternalEmailDeferred,
             Self::RsvpNotification(_) => OutboxKind::RsvpNotification,
+            Self::InviteReminder(_) => OutboxKind::InviteReminder,
+            Self::AttendeeRemovedNotification(_) => OutboxKind::AttendeeRemovedNotification,
+            Self::SignupConfirmation(_) => OutboxKind::SignupConfirmation,
+            Self::EventCancelledNotification(_) => OutboxKind::EventCancelledNotification,
+            Self::CancellationEmail(_) => OutboxKind::CancellationEmail,
+            Self::EventUpdated(_) => OutboxKind::EventUpdated,
+            Self::EventUpdateEmail(_) => OutboxKind::EventUpdateEmail,
+            Self::ChatWebhook(_) => OutboxKind::ChatWebhook,
+            Self::Sms(_) => OutboxKind::Sms,
+            Self::WebPush(_) => OutboxKind::WebPush,
+            Self::TaskBlockReminder(_) => OutboxKind::TaskBlockReminder,
+            Self::UserEmail(_) => OutboxKind::UserEmail,
         }
     }
 
@@ -435,6 +1127,18 @@ impl OutboxPayload {
             Self::TelegramNotification(payload) => serde_json::to_value(payload),
             Self::ExternalEmailDeferred(payload) => serde_json::to_value(payload),
             Self::RsvpNotification(payload) => serde_json::to_value(payload),
+            Self::InviteReminder(payload) => serde_json::to_value(payload),
+            Self::AttendeeRemovedNotification(payload) => serde_json::to_value(payload),
+            Self::SignupConfirmation(payload) => serde_json::to_value(payload),
+            Self::EventCancelledNotification(payload) => serde_json::to_value(payload),
+            Self::CancellationEmail(payload) => serde_json::to_value(payload),
+            Self::EventUpdated(payload) => serde_json::to_value(payload),
+            Self::EventUpdateEmail(payload) => serde_json::to_value(payload),
+            Self::ChatWebhook(payload) => serde_json::to_value(payload),
+            Self::Sms(payload) => serde_json::to_value(payload),
+            Self::WebPush(payload) => serde_json::to_value(payload),
+            Self::TaskBlockReminder(payload) => serde_json::to_value(payload),
+            Self::UserEmail(payload) => serde_json::to_value(payload),
         }
     }
 
@@ -453,6 +1157,22 @@ impl OutboxPayload {
                 decode!(ExternalEmailDeferred, ExternalEmailDeferred)
             }
             OutboxKind::RsvpNotification => decode!(RsvpNotification, RsvpNotification),
+            OutboxKind::InviteReminder => decode!(InviteReminder, InviteReminder),
+            OutboxKind::AttendeeRemovedNotification => {
+                decode!(AttendeeRemovedNotification, AttendeeRemovedNotification)
+            }
+            OutboxKind::SignupConfirmation => decode!(SignupConfirmation, SignupConfirmation),
+            OutboxKind::EventCancelledNotification => {
+                decode!(EventCancelledNotification, EventCancelledNotification)
+            }
+            OutboxKind::CancellationEmail => decode!(CancellationEmail, CancellationEmail),
+            OutboxKind::EventUpdated => decode!(EventUpdated, EventUpdatedNotification),
+            OutboxKind::EventUpdateEmail => decode!(EventUpdateEmail, EventUpdateEmail),
+            OutboxKind::ChatWebhook => decode!(ChatWebhook, ChatWebhookMessage),
+            OutboxKind::Sms => decode!(Sms, SmsMessage),
+            OutboxKind::WebPush => decode!(WebPush, WebPushMessage),
+            OutboxKind::TaskBlockReminder => decode!(TaskBlockReminder, TaskBlockReminder),
+            OutboxKind::UserEmail => decode!(UserEmail, UserEmailMessage),
         };
 
         decoded.map_err(|err| DomainError::InvalidOutboxPayload {
@@ -461,6 +1181,42 @@ impl OutboxPayload {
         })
     }
 
+    /// Telegram user the message is about, which decides the worker shard
+    /// that processes it; external emails have none
+    #[must_use]
+    pub const fn shard_user_id(&self) -> Option<i64> {
+        match self {
+            Self::InviteNotification(payload) => Some(payload.target_user_id),
+            Self::TelegramNotification(payload) => Some(payload.telegram_id),
+            Self::RsvpNotification(payload) => Some(payload.organizer_telegram_id),
+            Self::InviteReminder(payload) => Some(payload.target_user_id),
+            Self::AttendeeRemovedNotification(payload) => Some(payload.target_user_id),
+            Self::EventCancelledNotification(payload) => Some(payload.target_user_id),
+            Self::EventUpdated(payload) => Some(payload.target_user_id),
+            Self::ChatWebhook(payload) => Some(payload.user_id),
+            Self::Sms(payload) => Some(payload.user_id),
+            Self::WebPush(payload) => Some(payload.user_id),
+            Self::TaskBlockReminder(payload) => Some(payload.target_user_id),
+            Self::UserEmail(payload) => Some(payload.user_id),
+            Self::ExternalEmailDeferred(_)
+            | Self::SignupConfirmation(_)
+            | Self::CancellationEmail(_)
+            | Self::EventUpdateEmail(_) => None,
+        }
+    }
+
+    /// Earliest time the worker may send the message; `None` means right away
+    #[must_use]
+    pub fn deliver_after(&self) -> Option<DateTime<Utc>> {
+        match self {
+            Self::EventUpdated(EventUpdatedNotification { window_start, .. })
+            | Self::EventUpdateEmail(EventUpdateEmail { window_start, .. }) => {
+                Some(*window_start + chrono::Duration::minutes(EVENT_UPDATE_WINDOW_MINUTES))
+            }
+            _ => None,
+        }
+    }
+
     #[must_use]
     pub fn dedupe_key(&self) -> Option<String> {
         match self {
@@ -476,7 +1232,53 @@ impl OutboxPayload {
                 "rsvp:{}:{}:{}",
                 payload.organizer_telegram_id, payload.attendee_name, payload.event_summary
             )),
-            Self::TelegramNotification(_) => None,
+            // Later edits in the same window fold into the pending message
+            Self::EventUpdated(payload) => Some(format!(
+                "event-updated:{}:{}:{}",
+                payload.event_id,
+                payload.target_user_id,
+                payload.window_start.timestamp()
+            )),
+            Self::EventUpdateEmail(payload) => Some(format!(
+                "event-update-email:{}:{}:{}",
+                payload.event_id,
+                payload.recipient_email,
+                payload.window_start.timestamp()
+            )),
+            Self::ChatWebhook(payload) => Some(format!(
+                "chat-webhook:{}:{}",
+                payload.webhook_id, payload.source_id
+            )),
+            Self::Sms(SmsMessage {
+                user_id,
+                content: SmsContent::Notice { source_id, .. },
+            }) => Some(format!("sms:{user_id}:{source_id}")),
+            Self::WebPush(payload) => Some(format!(
+                "web-push:{}:{}",
+                payload.subscription_id, payload.source_id
+            )),
+            Self::TaskBlockReminder(payload) => Some(format!("task-block:{}", payload.event_id)),
+            Self::UserEmail(UserEmailMessage {
+                user_id,
+                content: UserEmailContent::Notice { source_id, .. },
+            }) => Some(format!("user-email:{user_id}:{source_id}")),
+            // Reminders, removals and cancellations may legitimately repeat for
+            // the same pair, and every signup attempt carries a fresh
+            // confirmation token, as does every verification text and email
+            Self::TelegramNotification(_)
+            | Self::InviteReminder(_)
+            | Self::AttendeeRemovedNotification(_)
+            | Self::SignupConfirmation(_)
+            | Self::EventCancelledNotification(_)
+            | Self::CancellationEmail(_)
+            | Self::Sms(SmsMessage {
+                content: SmsContent::VerificationCode { .. },
+                ..
+            })
+            | Self::UserEmail(UserEmailMessage {
+                content: UserEmailContent::VerificationCode { .. },
+                ..
+            }) => None,
         }
     }
 }
@@ -504,6 +1306,84 @@ mod tests {
         assert_eq!(timing.validate(), Err(DomainError::InvalidAllDayRange));
     }
 
+    #[test]
+    fn floating_timing_pins_to_viewer_zone() {
+        let start = "2026-12-31T23:59:00".parse::<NaiveDateTime>().unwrap();
+        let timing = EventTiming::Floating {
+            start,
+            end: start + chrono::Duration::minutes(1),
+        };
+        assert!(timing.validate().is_ok());
+
+        let tokyo = Timezone::parse("Asia/Tokyo").unwrap();
+        let EventTiming::Timed {
+            start: pinned,
+            timezone,
+            ..
+        } = timing.pinned_to(&tokyo)
+        else {
+            panic!("expected a timed event");
+        };
+        assert_eq!(
+            pinned,
+            "2026-12-31T14:59:00Z".parse::<DateTime<Utc>>().unwrap()
+        );
+        assert_eq!(timezone, tokyo);
+        assert_eq!(
+            timing.pinned_to(&Timezone::utc()).start_for_display(),
+            start.and_utc()
+        );
+
+        // 02:30 doesn't exist in Berlin on the spring-forward night
+        let skipped = "2026-03-29T02:30:00".parse::<NaiveDateTime>().unwrap();
+        let berlin = Timezone::parse("Europe/Berlin").unwrap();
+        let timing = EventTiming::Floating {
+            start: skipped,
+            end: skipped + chrono::Duration::hours(1),
+        };
+        assert_eq!(
+            timing.pinned_to(&berlin).start_for_display(),
+            "2026-03-29T01:30:00Z".parse::<DateTime<Utc>>().unwrap()
+        );
+    }
+
+    #[test]
+    fn event_visibility_maps_to_ical_class() {
+        for visibility in [
+            EventVisibility::Public,
+            EventVisibility::BusyOnly,
+            EventVisibility::Private,
+        ] {
+            assert_eq!(
+                EventVisibility::parse(visibility.as_sql()),
+                Some(visibility)
+            );
+            assert_eq!(
+                EventVisibility::from_ical_class(visibility.ical_class()),
+                visibility
+            );
+        }
+        assert_eq!(
+            EventVisibility::from_ical_class("confidential"),
+            EventVisibility::BusyOnly
+        );
+        assert_eq!(
+            EventVisibility::from_ical_class("X-SECRET"),
+            EventVisibility::Private
+        );
+    }
+
+    #[test]
+    fn user_roles_include_lower_roles() {
+        assert!(UserRole::Admin.includes(UserRole::Operator));
+        assert!(UserRole::Operator.includes(UserRole::Operator));
+        assert!(!UserRole::Operator.includes(UserRole::Admin));
+        assert!(!UserRole::User.includes(UserRole::Operator));
+        for role in [UserRole::User, UserRole::Operator, UserRole::Admin] {
+            assert_eq!(UserRole::parse(role.as_sql()), Some(role));
+        }
+    }
+
     #[test]
     fn etag_is_deterministic_and_attendee_order_independent() {
         let start = "2026-01-01T10:00:00Z".parse::<DateTime<Utc>>().unwrap();
@@ -519,7 +1399,8 @@ mod tests {
             },
             status: EventStatus::Confirmed,
             rrule: None,
-            version: 2,
+            visibility: EventVisibility::Public,
+            reminder_note: None,
             attendees: vec![
                 AttendeeFingerprint {
                     email: "b@example.com".to_string(),
@@ -542,6 +1423,49 @@ mod tests {
         assert_eq!(compute_event_etag(&base), compute_event_etag(&reordered));
     }
 
+    #[test]
+    fn etag_changes_with_content() {
+        let start = "2026-01-01T10:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let base = EventEtagInput {
+            uid: "uid-1".to_string(),
+            summary: "Test".to_string(),
+            description: None,
+            location: None,
+            timing: EventTiming::Timed {
+                start,
+                end: start + chrono::Duration::hours(1),
+                timezone: Timezone::utc(),
+            },
+            status: EventStatus::Confirmed,
+            rrule: None,
+            visibility: EventVisibility::Public,
+            reminder_note: None,
+            attendees: Vec::new(),
+        };
+
+        let mut renamed = base.clone();
+        renamed.summary = "Renamed".to_string();
+        let mut accepted = base.clone();
+        accepted.attendees.push(AttendeeFingerprint {
+            email: "a@example.com".to_string(),
+            user_id: None,
+            role: AttendeeRole::Attendee,
+            status: ParticipationStatus::Accepted,
+        });
+
+        assert_eq!(compute_event_etag(&base), compute_event_etag(&base.clone()));
+        assert_ne!(compute_event_etag(&base), compute_event_etag(&renamed));
+        assert_ne!(compute_event_etag(&base), compute_event_etag(&accepted));
+
+        let mut hidden = base.clone();
+        hidden.visibility = EventVisibility::BusyOnly;
+        assert_ne!(compute_event_etag(&base), compute_event_etag(&hidden));
+
+        let mut noted = base.clone();
+        noted.reminder_note = Some("Bring laptop".to_string());
+        assert_ne!(compute_event_etag(&base), compute_event_etag(&noted));
+    }
+
     #[test]
     fn internal_email_round_trips_telegram_id() {
         let email = internal_email_for_telegram_id(123456789);
@@ -601,6 +1525,24 @@ mod tests {
         assert!(validate_safe_multiline_text("Description", "bad\0description").is_err());
     }
 
+    #[test]
+    fn event_text_sanitization_strips_controls_and_normalizes_newlines() {
+        assert_eq!(
+            sanitize_single_line_text("Team\r\nsync\u{0}\tweekly\u{7}"),
+            "Team sync\tweekly"
+        );
+        assert_eq!(
+            sanitize_multiline_text("one\r\ntwo\rthree\u{1b}[0m\nfour"),
+            "one\ntwo\nthree[0m\nfour"
+        );
+
+        let mut text = "ab€".to_string();
+        truncate_to_length(&mut text, 4);
+        assert_eq!(text, "ab");
+        truncate_to_length(&mut text, 10);
+        assert_eq!(text, "ab");
+    }
+
     #[test]
     fn typed_outbox_rejects_wrong_payload_shape() {
         let err =
@@ -609,4 +1551,180 @@ mod tests {
 
         assert!(matches!(err, DomainError::InvalidOutboxPayload { .. }));
     }
+
+    #[test]
+    fn attendee_follow_up_payloads_round_trip_without_dedupe() {
+        let reminder = OutboxPayload::InviteReminder(InviteReminder {
+            event_id: Uuid::new_v4(),
+            target_user_id: 42,
+        });
+        let decoded =
+            OutboxPayload::from_parts(reminder.kind().as_str(), reminder.payload_json().unwrap())
+                .unwrap();
+
+        assert_eq!(decoded, reminder);
+        assert_eq!(reminder.dedupe_key(), None);
+        assert_eq!(
+            OutboxKind::from_str("attendee_removed_notification").unwrap(),
+            OutboxKind::AttendeeRemovedNotification
+        );
+    }
+
+    #[test]
+    fn sms_payloads_round_trip_and_dedupe_notices_only() {
+        let source_id = Uuid::new_v4();
+        let notice = OutboxPayload::Sms(SmsMessage {
+            user_id: 42,
+            content: SmsContent::Notice {
+                source_id,
+                text: "❌ Cancelled: Standup".to_string(),
+            },
+        });
+        let json = notice.payload_json().unwrap();
+        assert_eq!(json["content"]["type"], "notice");
+        assert_eq!(
+            OutboxPayload::from_parts("sms", json).unwrap(),
+            notice.clone()
+        );
+        assert_eq!(notice.dedupe_key(), Some(format!("sms:42:{source_id}")));
+        assert_eq!(notice.shard_user_id(), Some(42));
+
+        let code = OutboxPayload::Sms(SmsMessage {
+            user_id: 42,
+            content: SmsContent::VerificationCode {
+                phone_number: "+15551234567".to_string(),
+            },
+        });
+        assert_eq!(code.dedupe_key(), None);
+    }
+
+    #[test]
+    fn user_email_payloads_round_trip_and_dedupe_notices_only() {
+        let source_id = Uuid::new_v4();
+        let notice = OutboxPayload::UserEmail(UserEmailMessage {
+            user_id: 42,
+            content: UserEmailContent::Notice {
+                source_id,
+                title: "❌ Cancelled: Standup".to_string(),
+                lines: vec!["🕒 Time: Mon 1 Jun, 10:00".to_string()],
+            },
+        });
+        let json = notice.payload_json().unwrap();
+        assert_eq!(json["content"]["type"], "notice");
+        assert_eq!(
+            OutboxPayload::from_parts("user_email", json).unwrap(),
+            notice.clone()
+        );
+        assert_eq!(
+            notice.dedupe_key(),
+            Some(format!("user-email:42:{source_id}"))
+        );
+        assert_eq!(notice.shard_user_id(), Some(42));
+
+        let code = OutboxPayload::UserEmail(UserEmailMessage {
+            user_id: 42,
+            content: UserEmailContent::VerificationCode {
+                email: "ada@example.com".to_string(),
+            },
+        });
+        assert_eq!(code.dedupe_key(), None);
+    }
+
+    #[test]
+    fn phone_numbers_normalize_to_e164() {
+        assert_eq!(
+            normalize_phone_number(" +44 (20) 7946-0958 ").unwrap(),
+            "+442079460958"
+        );
+        assert_eq!(
+            normalize_phone_number("+1.555.123.4567").unwrap(),
+            "+15551234567"
+        );
+
+        for invalid in [
+            "5551234567",
+            "+0 555 123 4567",
+            "+1 555",
+            "+1 555 CALL NOW",
+            "+",
+        ] {
+            assert!(normalize_phone_number(invalid).is_err(), "{invalid}");
+        }
+        assert!(normalize_phone_number("+1234567890123456").is_err());
+    }
+
+    #[test]
+    fn notification_matrix_names_round_trip() {
+        for topic in NotificationTopic::ALL {
+            assert_eq!(NotificationTopic::parse(topic.as_sql()), Some(topic));
+        }
+        for channel in NotificationChannel::ALL {
+            assert_eq!(NotificationChannel::parse(channel.as_sql()), Some(channel));
+        }
+        assert!(!NotificationChannel::Sms.enabled_by_default());
+        assert!(!NotificationChannel::Email.enabled_by_default());
+        assert!(NotificationChannel::Push.enabled_by_default());
+    }
+
+    #[test]
+    fn web_push_payloads_round_trip_per_subscription() {
+        let subscription_id = Uuid::new_v4();
+        let source_id = Uuid::new_v4();
+        let push = OutboxPayload::WebPush(WebPushMessage {
+            subscription_id,
+            user_id: 42,
+            source_id,
+            title: "⏰ Reminder: Standup".to_string(),
+            lines: vec!["🕒 Time: Mon 1 Jun, 10:00".to_string()],
+        });
+        let json = push.payload_json().unwrap();
+        assert_eq!(
+            OutboxPayload::from_parts("web_push", json).unwrap(),
+            push.clone()
+        );
+        assert_eq!(
+            push.dedupe_key(),
+            Some(format!("web-push:{subscription_id}:{source_id}"))
+        );
+        assert_eq!(push.shard_user_id(), Some(42));
+    }
+
+    #[test]
+    fn task_block_reminders_round_trip_once_per_block() {
+        let event_id = Uuid::new_v4();
+        let reminder = OutboxPayload::TaskBlockReminder(TaskBlockReminder {
+            task_id: Uuid::new_v4(),
+            event_id,
+            target_user_id: 42,
+        });
+        let json = reminder.payload_json().unwrap();
+        assert_eq!(
+            OutboxPayload::from_parts("task_block_reminder", json).unwrap(),
+            reminder.clone()
+        );
+        assert_eq!(
+            reminder.dedupe_key(),
+            Some(format!("task-block:{event_id}"))
+        );
+        assert_eq!(reminder.shard_user_id(), Some(42));
+    }
+
+    #[test]
+    fn email_validation_accepts_plain_addresses_only() {
+        assert!(validate_email_address("guest@example.com").is_ok());
+        assert!(validate_email_address("first.last+tag@mail.example.org").is_ok());
+
+        for invalid in [
+            "",
+            "guest",
+            "@example.com",
+            "guest@localhost",
+            "guest@example.com.",
+            "guest@@example.com",
+            "gu est@example.com",
+            "guest@example.com\n",
+        ] {
+            assert!(validate_email_address(invalid).is_err(), "{invalid:?}");
+        }
+    }
 }
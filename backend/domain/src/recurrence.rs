@@ -5,13 +5,24 @@ use rrule::{RRuleError, RRuleSet, Tz};
 
 use crate::DomainError;
 
+/// Parse an RRULE anchored at `dtstart` (already formatted for `DTSTART:`).
+///
+/// The grammar is ASCII-only, and the `rrule` crate panics slicing some
+/// non-ASCII values, so those are rejected before it sees them.
+fn parse_rrule_set(dtstart: &str, rrule_str: &str) -> Result<RRuleSet, DomainError> {
+    if !rrule_str.is_ascii() {
+        return Err(DomainError::InvalidRRule("RRULE must be ASCII".to_string()));
+    }
+
+    format!("DTSTART:{dtstart}\nRRULE:{rrule_str}")
+        .parse()
+        .map_err(|err: RRuleError| DomainError::InvalidRRule(err.to_string()))
+}
+
 /// Parse an RFC 5545 RRULE string and validate its format.
 pub fn validate_rrule(rrule_str: &str) -> Result<(), DomainError> {
-    let full_str = format!("DTSTART:20240101T000000Z\nRRULE:{rrule_str}");
-
-    full_str
-        .parse::<RRuleSet>()
-        .map_err(|err: RRuleError| DomainError::InvalidRRule(err.to_string()))?;
+    // Early enough that no real UNTIL falls before it
+    parse_rrule_set("19700101T000000Z", rrule_str)?;
 
     Ok(())
 }
@@ -24,12 +35,8 @@ pub fn expand_rrule(
     range_end: DateTime<Utc>,
     max_occurrences: usize,
 ) -> Result<Vec<DateTime<Utc>>, DomainError> {
-    let dtstart_str = dtstart.format("%Y%m%dT%H%M%SZ");
-    let full_str = format!("DTSTART:{dtstart_str}\nRRULE:{rrule_str}");
-
-    let rrule_set: RRuleSet = full_str
-        .parse()
-        .map_err(|err: RRuleError| DomainError::InvalidRRule(err.to_string()))?;
+    let dtstart_str = dtstart.format("%Y%m%dT%H%M%SZ").to_string();
+    let rrule_set = parse_rrule_set(&dtstart_str, rrule_str)?;
 
     let rrule_tz = rrule_set.get_dt_start().timezone();
     let search_start = range_start
@@ -56,12 +63,8 @@ pub fn next_occurrences(
     dtstart: DateTime<Utc>,
     count: usize,
 ) -> Result<Vec<DateTime<Utc>>, DomainError> {
-    let dtstart_str = dtstart.format("%Y%m%dT%H%M%SZ");
-    let full_str = format!("DTSTART:{dtstart_str}\nRRULE:{rrule_str}");
-
-    let rrule_set: RRuleSet = full_str
-        .parse()
-        .map_err(|err: RRuleError| DomainError::InvalidRRule(err.to_string()))?;
+    let dtstart_str = dtstart.format("%Y%m%dT%H%M%SZ").to_string();
+    let rrule_set = parse_rrule_set(&dtstart_str, rrule_str)?;
 
     let occurrences = rrule_set
         .all(count as u16)
@@ -73,6 +76,120 @@ pub fn next_occurrences(
     Ok(occurrences)
 }
 
+/// A series cut in two at one of its occurrences
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SeriesSplit {
+    /// Rule ending the original series just before the cut, or `None` when
+    /// the cut is its first occurrence
+    pub head: Option<String>,
+    /// Rule for a new series starting at the cut
+    pub tail: String,
+}
+
+/// Split a series at the occurrence starting at `at`.
+///
+/// The head is bounded by an UNTIL one second before the cut. A COUNT is
+/// shared out, so the two halves together still produce the original
+/// occurrences.
+pub fn split_rrule(
+    rrule_str: &str,
+    dtstart: DateTime<Utc>,
+    at: DateTime<Utc>,
+) -> Result<SeriesSplit, DomainError> {
+    let dtstart_str = dtstart.format("%Y%m%dT%H%M%SZ").to_string();
+    let rrule_set = parse_rrule_set(&dtstart_str, rrule_str)?;
+    let rrule_tz = rrule_set.get_dt_start().timezone();
+    let at_tz = at.with_timezone(&rrule_tz);
+
+    let is_occurrence = !rrule_set
+        .clone()
+        .after(at_tz)
+        .before(at_tz)
+        .all(1)
+        .dates
+        .is_empty();
+    if !is_occurrence {
+        return Err(DomainError::InvalidRRule(format!(
+            "{} is not an occurrence of the series",
+            at.format("%Y-%m-%dT%H:%M:%SZ")
+        )));
+    }
+    if at == dtstart {
+        return Ok(SeriesSplit {
+            head: None,
+            tail: rrule_str.to_string(),
+        });
+    }
+
+    let parts: Vec<&str> = rrule_str
+        .split(';')
+        .filter(|part| !part.is_empty())
+        .collect();
+    let count = parts.iter().find_map(|part| rule_value(part, "COUNT"));
+    let unbounded = || {
+        parts
+            .iter()
+            .copied()
+            .filter(|part| {
+                rule_value(part, "COUNT").is_none() && rule_value(part, "UNTIL").is_none()
+            })
+            .map(str::to_string)
+            .collect::<Vec<_>>()
+    };
+
+    let until = (at - chrono::Duration::seconds(1)).format("%Y%m%dT%H%M%SZ");
+    let mut head = unbounded();
+    head.push(format!("UNTIL={until}"));
+
+    let tail = match count {
+        Some(count) => {
+            let count: usize = count
+                .parse()
+                .map_err(|_| DomainError::InvalidRRule(format!("invalid COUNT: {count}")))?;
+            let before = rrule_set
+                .before(at_tz - chrono::Duration::seconds(1))
+                .all(u16::MAX)
+                .dates
+                .len();
+            let mut tail = unbounded();
+            tail.push(format!("COUNT={}", count.saturating_sub(before)));
+            tail.join(";")
+        }
+        None => rrule_str.to_string(),
+    };
+
+    Ok(SeriesSplit {
+        head: Some(head.join(";")),
+        tail,
+    })
+}
+
+/// The first occurrence strictly after `after`, if the series has one
+pub fn occurrence_after(
+    rrule_str: &str,
+    dtstart: DateTime<Utc>,
+    after: DateTime<Utc>,
+) -> Result<Option<DateTime<Utc>>, DomainError> {
+    let dtstart_str = dtstart.format("%Y%m%dT%H%M%SZ").to_string();
+    let rrule_set = parse_rrule_set(&dtstart_str, rrule_str)?;
+    let rrule_tz = rrule_set.get_dt_start().timezone();
+
+    Ok(rrule_set
+        .after((after + chrono::Duration::seconds(1)).with_timezone(&rrule_tz))
+        .all(1)
+        .dates
+        .first()
+        .map(|date| date.with_timezone(&Utc)))
+}
+
+/// The value of `key` in one `KEY=VALUE` part of a rule
+fn rule_value<'a>(part: &'a str, key: &str) -> Option<&'a str> {
+    let (name, value) = part.split_once('=')?;
+    name.trim()
+        .eq_ignore_ascii_case(key)
+        .then_some(value.trim())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -85,6 +202,11 @@ mod tests {
         assert!(validate_rrule("INVALID=TRUE").is_err());
     }
 
+    #[test]
+    fn rejects_non_ascii_rrule_without_panicking() {
+        assert!(validate_rrule("FREQ=WEEKLY;BYDAY=MO\u{52e}E").is_err());
+    }
+
     #[test]
     fn expands_daily_rrule() {
         let dtstart = Utc.with_ymd_and_hms(2026, 1, 1, 10, 0, 0).unwrap();
@@ -125,6 +247,86 @@ mod tests {
         assert_eq!(occurrences[2], dtstart + chrono::Duration::days(2));
     }
 
+    #[test]
+    fn accepts_until_in_the_past() {
+        assert!(validate_rrule("FREQ=WEEKLY;UNTIL=20210104T095959Z").is_ok());
+    }
+
+    #[test]
+    fn splits_open_series_with_until() {
+        let dtstart = Utc.with_ymd_and_hms(2026, 1, 1, 10, 0, 0).unwrap();
+        let at = dtstart + chrono::Duration::days(3);
+
+        let split = split_rrule("FREQ=DAILY;INTERVAL=1", dtstart, at).unwrap();
+        assert_eq!(
+            split.head.as_deref(),
+            Some("FREQ=DAILY;INTERVAL=1;UNTIL=20260104T095959Z")
+        );
+        assert_eq!(split.tail, "FREQ=DAILY;INTERVAL=1");
+
+        let head = next_occurrences(split.head.as_deref().unwrap(), dtstart, 10).unwrap();
+        assert_eq!(head.len(), 3);
+        assert_eq!(head.last(), Some(&(at - chrono::Duration::days(1))));
+    }
+
+    #[test]
+    fn shares_count_between_halves() {
+        let dtstart = Utc.with_ymd_and_hms(2026, 1, 5, 9, 0, 0).unwrap();
+        let at = dtstart + chrono::Duration::weeks(2);
+
+        let split = split_rrule("FREQ=WEEKLY;COUNT=5;BYDAY=MO", dtstart, at).unwrap();
+        assert_eq!(
+            split.head.as_deref(),
+            Some("FREQ=WEEKLY;BYDAY=MO;UNTIL=20260119T085959Z")
+        );
+        assert_eq!(split.tail, "FREQ=WEEKLY;BYDAY=MO;COUNT=3");
+        assert_eq!(next_occurrences(&split.tail, at, 10).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn split_at_first_occurrence_keeps_the_rule() {
+        let dtstart = Utc.with_ymd_and_hms(2026, 1, 1, 10, 0, 0).unwrap();
+
+        let split = split_rrule("FREQ=DAILY;COUNT=3", dtstart, dtstart).unwrap();
+        assert_eq!(split.head, None);
+        assert_eq!(split.tail, "FREQ=DAILY;COUNT=3");
+    }
+
+    #[test]
+    fn split_rejects_times_off_the_series() {
+        let dtstart = Utc.with_ymd_and_hms(2026, 1, 1, 10, 0, 0).unwrap();
+        let off = dtstart + chrono::Duration::hours(30);
+
+        assert!(split_rrule("FREQ=DAILY", dtstart, off).is_err());
+        assert!(
+            split_rrule(
+                "FREQ=DAILY;COUNT=2",
+                dtstart,
+                dtstart + chrono::Duration::days(2)
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn finds_occurrence_after() {
+        let dtstart = Utc.with_ymd_and_hms(2026, 1, 1, 10, 0, 0).unwrap();
+
+        assert_eq!(
+            occurrence_after("FREQ=DAILY;COUNT=2", dtstart, dtstart).unwrap(),
+            Some(dtstart + chrono::Duration::days(1))
+        );
+        assert_eq!(
+            occurrence_after(
+                "FREQ=DAILY;COUNT=2",
+                dtstart,
+                dtstart + chrono::Duration::days(1)
+            )
+            .unwrap(),
+            None
+        );
+    }
+
     #[test]
     #[ignore]
     fn benchmark_expand_rrule_performance() {
@@ -0,0 +1,69 @@
+//! `#hashtags` in event titles.
+//!
+//! A tag is a `#` at the start of the title or after whitespace, followed by
+//! letters, digits, `_` or `-`. Tags stay in the title; they are stored
+//! lowercased alongside it so events can be filtered by them, and exported
+//! as iCalendar `CATEGORIES`.
+
+/// Longest tag kept, in characters
+pub const MAX_TAG_LENGTH: usize = 64;
+
+fn is_tag_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '-'
+}
+
+/// A tag as stored: lowercased, without the `#`; `None` unless `raw` (with
+/// or without a leading `#`) is a valid tag
+#[must_use]
+pub fn normalize_tag(raw: &str) -> Option<String> {
+    let tag = raw.trim();
+    let tag = tag.strip_prefix('#').unwrap_or(tag);
+    (!tag.is_empty() && tag.chars().count() <= MAX_TAG_LENGTH && tag.chars().all(is_tag_char))
+        .then(|| tag.to_lowercase())
+}
+
+/// Tags of a title, in order of first appearance and without duplicates
+#[must_use]
+pub fn event_tags(summary: &str) -> Vec<String> {
+    let mut tags = Vec::new();
+    for word in summary.split_whitespace() {
+        let Some(rest) = word.strip_prefix('#') else {
+            continue;
+        };
+        // Trailing punctuation such as "#work," or "#work." isn't part of it
+        let end = rest.find(|c| !is_tag_char(c)).unwrap_or(rest.len());
+        if let Some(tag) = normalize_tag(&rest[..end])
+            && !tags.contains(&tag)
+        {
+            tags.push(tag);
+        }
+    }
+    tags
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tags_are_parsed_from_titles() {
+        assert_eq!(
+            event_tags("#Work standup with #team-a, then #work again"),
+            ["work", "team-a"]
+        );
+        assert_eq!(
+            event_tags("Call re issue#42 and C# ##"),
+            Vec::<String>::new()
+        );
+        assert_eq!(event_tags("Поход #отпуск"), ["отпуск"]);
+    }
+
+    #[test]
+    fn tags_are_normalized() {
+        assert_eq!(normalize_tag("#Work").as_deref(), Some("work"));
+        assert_eq!(normalize_tag("focus_time").as_deref(), Some("focus_time"));
+        assert_eq!(normalize_tag("#"), None);
+        assert_eq!(normalize_tag("two words"), None);
+        assert_eq!(normalize_tag(&"a".repeat(MAX_TAG_LENGTH + 1)), None);
+    }
+}
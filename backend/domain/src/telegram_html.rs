@@ -0,0 +1,157 @@
+//! User content in Telegram HTML messages.
+//!
+//! Telegram rejects a whole `ParseMode::Html` message when it meets an
+//! unescaped `<` or `&`, and anything longer than [`MAX_MESSAGE_LENGTH`]
+//! characters. Every user-controlled string placed in such a message goes
+//! through [`inline`] (summaries, names, locations) or [`block`]
+//! (descriptions), which sanitize, truncate and escape in that order so an
+//! entity is never cut in half.
+
+use std::borrow::Cow;
+
+/// Longest message Telegram accepts, in characters after entity parsing.
+pub const MAX_MESSAGE_LENGTH: usize = 4096;
+
+/// Cap for single-line values such as event summaries in lists.
+pub const MAX_INLINE_LENGTH: usize = 128;
+
+const ELLIPSIS: char = '…';
+
+/// Escape `&`, `<`, `>` and `"` so the value renders as plain text.
+#[must_use]
+pub fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Drop control and bidi-override characters, keeping newlines and tabs.
+///
+/// Bidi overrides let a name render as something it is not, so they go too.
+#[must_use]
+pub fn sanitize(value: &str) -> Cow<'_, str> {
+    if value.chars().all(|c| !is_stripped(c)) {
+        return Cow::Borrowed(value);
+    }
+    Cow::Owned(value.chars().filter(|c| !is_stripped(*c)).collect())
+}
+
+fn is_stripped(c: char) -> bool {
+    (c.is_control() && c != '\n' && c != '\t')
+        || matches!(c, '\u{202A}'..='\u{202E}' | '\u{2066}'..='\u{2069}')
+}
+
+/// Shorten to at most `max_chars` characters, ending in `…` when cut.
+#[must_use]
+pub fn truncate(value: &str, max_chars: usize) -> Cow<'_, str> {
+    match value.char_indices().nth(max_chars) {
+        None => Cow::Borrowed(value),
+        Some(_) if max_chars == 0 => Cow::Borrowed(""),
+        Some(_) => {
+            let (cut, _) = value
+                .char_indices()
+                .nth(max_chars - 1)
+                .unwrap_or((value.len(), ' '));
+            let mut shortened = value[..cut].trim_end().to_string();
+            shortened.push(ELLIPSIS);
+            Cow::Owned(shortened)
+        }
+    }
+}
+
+/// Single-line user content: line breaks become spaces, capped at
+/// [`MAX_INLINE_LENGTH`] characters.
+#[must_use]
+pub fn inline(value: &str) -> String {
+    let sanitized = sanitize(value);
+    let single_line = sanitized
+        .split(['\n', '\t'])
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+    escape(&truncate(&single_line, MAX_INLINE_LENGTH))
+}
+
+/// Multi-line user content capped at `max_chars` characters.
+#[must_use]
+pub fn block(value: &str, max_chars: usize) -> String {
+    escape(&truncate(sanitize(value).trim(), max_chars))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn unescape(value: &str) -> String {
+        value
+            .replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&quot;", "\"")
+            .replace("&amp;", "&")
+    }
+
+    /// Every `&` must start one of the entities [`escape`] produces.
+    fn only_known_entities(value: &str) -> bool {
+        value.match_indices('&').all(|(at, _)| {
+            ["&amp;", "&lt;", "&gt;", "&quot;"]
+                .iter()
+                .any(|entity| value[at..].starts_with(entity))
+        })
+    }
+
+    #[test]
+    fn test_escape_and_inline() {
+        assert_eq!(escape("Me & You <script>"), "Me &amp; You &lt;script&gt;");
+        assert_eq!(inline("Team\nsync\t<b>"), "Team sync &lt;b&gt;");
+        assert_eq!(inline("\u{202E}evil\u{7}"), "evil");
+        assert_eq!(truncate("abcdef", 4), "abc…");
+        assert_eq!(truncate("abc", 3), "abc");
+    }
+
+    proptest! {
+        #[test]
+        fn escape_round_trips_and_leaves_no_markup(value in any::<String>()) {
+            let escaped = escape(&value);
+            prop_assert!(!escaped.contains('<') && !escaped.contains('>'));
+            prop_assert!(only_known_entities(&escaped));
+            prop_assert_eq!(unescape(&escaped), value);
+        }
+
+        #[test]
+        fn truncate_respects_the_limit(value in any::<String>(), max in 0usize..64) {
+            let shortened = truncate(&value, max);
+            prop_assert!(shortened.chars().count() <= max);
+            let kept = shortened.trim_end_matches(ELLIPSIS);
+            prop_assert!(value.starts_with(kept));
+        }
+
+        #[test]
+        fn inline_is_one_safe_line(value in any::<String>()) {
+            let rendered = inline(&value);
+            prop_assert!(!rendered.contains('\n'));
+            prop_assert!(only_known_entities(&rendered));
+            prop_assert!(unescape(&rendered).chars().count() <= MAX_INLINE_LENGTH);
+            prop_assert!(!unescape(&rendered).chars().any(is_stripped));
+        }
+
+        #[test]
+        fn block_keeps_line_breaks_within_limit(
+            value in "[a-z<>&\n]{0,300}",
+            max in 1usize..200,
+        ) {
+            let rendered = block(&value, max);
+            prop_assert!(only_known_entities(&rendered));
+            prop_assert!(unescape(&rendered).chars().count() <= max);
+        }
+    }
+}
@@ -0,0 +1,274 @@
+//! The user's working week.
+//!
+//! [`WorkingHours`] holds one local-time window per weekday, or none for a
+//! day off, together with the timezone the windows are read in. It is
+//! written as comma-separated entries of an optional weekday or weekday
+//! range and a window, later entries overriding earlier ones:
+//! `Mon-Thu 09:00-17:00, Fri 09:00-13:00`. An entry without days covers the
+//! whole week, so `09:00-17:00` alone means every day.
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveTime, TimeZone, Utc, Weekday};
+use std::fmt;
+
+use crate::{DomainError, Timezone};
+
+const WEEKDAY_ABBREVIATIONS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+/// Local window of one working day; overnight windows are not supported
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DayHours {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl DayHours {
+    /// Parse `HH:MM-HH:MM`
+    pub fn parse(value: &str) -> Result<Self, DomainError> {
+        let invalid = || {
+            DomainError::InvalidWorkingHours(format!(
+                "working hours must look like 09:00-17:00, got {value:?}"
+            ))
+        };
+        let (start, end) = value.trim().split_once('-').ok_or_else(invalid)?;
+        let start = NaiveTime::parse_from_str(start.trim(), "%H:%M").map_err(|_| invalid())?;
+        let end = NaiveTime::parse_from_str(end.trim(), "%H:%M").map_err(|_| invalid())?;
+        if end <= start {
+            return Err(DomainError::InvalidWorkingHours(
+                "working hours must end after they start".to_string(),
+            ));
+        }
+
+        Ok(Self { start, end })
+    }
+
+    #[must_use]
+    pub fn length(self) -> Duration {
+        self.end - self.start
+    }
+}
+
+impl fmt::Display for DayHours {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}-{}",
+            self.start.format("%H:%M"),
+            self.end.format("%H:%M")
+        )
+    }
+}
+
+/// Working window of each weekday, in the user's timezone
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkingHours {
+    /// Indexed from Monday; `None` is a day off
+    days: [Option<DayHours>; 7],
+    timezone: Timezone,
+}
+
+impl WorkingHours {
+    /// The same window every day of the week
+    #[must_use]
+    pub fn every_day(hours: DayHours, timezone: &Timezone) -> Self {
+        Self {
+            days: [Some(hours); 7],
+            timezone: timezone.clone(),
+        }
+    }
+
+    /// Parse a week such as `Mon-Fri 09:00-17:00, Sat 10:00-14:00`; days no
+    /// entry covers are off
+    pub fn parse(value: &str, timezone: &Timezone) -> Result<Self, DomainError> {
+        let mut days = [None; 7];
+        for entry in value.split(',') {
+            // Weekday names have no digits, so the window starts at the first
+            let (weekdays, window) =
+                entry.split_at(entry.find(|c: char| c.is_ascii_digit()).unwrap_or(0));
+            let hours = DayHours::parse(window)?;
+            match weekdays.trim() {
+                "" => days = [Some(hours); 7],
+                weekdays => {
+                    for day in parse_weekdays(weekdays)? {
+                        days[day.num_days_from_monday() as usize] = Some(hours);
+                    }
+                }
+            }
+        }
+        if days.iter().all(Option::is_none) {
+            return Err(DomainError::InvalidWorkingHours(
+                "working hours need at least one day".to_string(),
+            ));
+        }
+
+        Ok(Self {
+            days,
+            timezone: timezone.clone(),
+        })
+    }
+
+    /// Timezone the windows are read in
+    #[must_use]
+    pub fn timezone(&self) -> &Timezone {
+        &self.timezone
+    }
+
+    /// Window of a weekday; `None` on a day off
+    #[must_use]
+    pub fn on(&self, weekday: Weekday) -> Option<DayHours> {
+        self.days[weekday.num_days_from_monday() as usize]
+    }
+
+    /// Working days from Monday with their windows
+    pub fn days(&self) -> impl Iterator<Item = (Weekday, DayHours)> + '_ {
+        (0u8..7).filter_map(|from_monday| {
+            let weekday = Weekday::try_from(from_monday).expect("0..7 is a weekday");
+            self.on(weekday).map(|hours| (weekday, hours))
+        })
+    }
+
+    /// Length of the longest working day
+    #[must_use]
+    pub fn longest_day(&self) -> Duration {
+        self.days
+            .iter()
+            .flatten()
+            .map(|hours| hours.length())
+            .max()
+            .unwrap_or_else(Duration::zero)
+    }
+
+    /// Start and end of the working window on a local day; `None` on a day
+    /// off or when the window doesn't exist that day
+    #[must_use]
+    pub fn window(&self, day: NaiveDate) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+        let hours = self.on(day.weekday())?;
+        let tz = self.timezone.tz();
+        let local = |time: NaiveTime| {
+            tz.from_local_datetime(&day.and_time(time))
+                .earliest()
+                .map(|local| local.with_timezone(&Utc))
+        };
+        Some((local(hours.start)?, local(hours.end)?))
+    }
+
+    /// The local day `at` falls on
+    #[must_use]
+    pub fn local_day(&self, at: DateTime<Utc>) -> NaiveDate {
+        at.with_timezone(&self.timezone.tz()).date_naive()
+    }
+
+    /// Whether `start..end` lies inside one working window
+    #[must_use]
+    pub fn contains(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> bool {
+        self.window(self.local_day(start))
+            .is_some_and(|(from, until)| from <= start && end <= until)
+    }
+}
+
+impl fmt::Display for WorkingHours {
+    /// Consecutive days with the same window share an entry, and a week of
+    /// identical days is written as the window alone
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(hours) = self.days[0]
+            && self.days.iter().all(|day| *day == Some(hours))
+        {
+            return write!(f, "{hours}");
+        }
+
+        let mut entries = Vec::new();
+        let mut first = 0;
+        while first < 7 {
+            let mut last = first;
+            while last + 1 < 7 && self.days[last + 1] == self.days[first] {
+                last += 1;
+            }
+            if let Some(hours) = self.days[first] {
+                let days = if first == last {
+                    WEEKDAY_ABBREVIATIONS[first].to_string()
+                } else {
+                    format!(
+                        "{}-{}",
+                        WEEKDAY_ABBREVIATIONS[first], WEEKDAY_ABBREVIATIONS[last]
+                    )
+                };
+                entries.push(format!("{days} {hours}"));
+            }
+            first = last + 1;
+        }
+        write!(f, "{}", entries.join(", "))
+    }
+}
+
+/// `Mon` or `Mon-Fri`; a range may wrap around the weekend, as in `Sat-Mon`
+fn parse_weekdays(value: &str) -> Result<Vec<Weekday>, DomainError> {
+    let weekday = |name: &str| {
+        name.trim().parse::<Weekday>().map_err(|_| {
+            DomainError::InvalidWorkingHours(format!(
+                "weekdays must look like Mon or Mon-Fri, got {value:?}"
+            ))
+        })
+    };
+    let Some((first, last)) = value.split_once('-') else {
+        return Ok(vec![weekday(value)?]);
+    };
+    let (first, last) = (weekday(first)?, weekday(last)?);
+    let mut days = vec![first];
+    let mut day = first;
+    while day != last {
+        day = day.succ();
+        days.push(day);
+    }
+    Ok(days)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(hour: u32, minute: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn parses_weeks_and_writes_them_back() {
+        let utc = Timezone::utc();
+        let daily = WorkingHours::parse(" 9:00 - 17:30", &utc).unwrap();
+        assert_eq!(daily.on(Weekday::Sun).unwrap().start, time(9, 0));
+        assert_eq!(daily.to_string(), "09:00-17:30");
+
+        let week = WorkingHours::parse("mon-thu 09:00-17:00, Fri 09:00-13:00", &utc).unwrap();
+        assert_eq!(week.on(Weekday::Fri).unwrap().end, time(13, 0));
+        assert_eq!(week.on(Weekday::Sat), None);
+        assert_eq!(week.longest_day(), Duration::hours(8));
+        assert_eq!(week.to_string(), "Mon-Thu 09:00-17:00, Fri 09:00-13:00");
+
+        let weekend = WorkingHours::parse("10:00-12:00, Sat-Sun 08:00-09:00", &utc).unwrap();
+        assert_eq!(
+            weekend.to_string(),
+            "Mon-Fri 10:00-12:00, Sat-Sun 08:00-09:00"
+        );
+        assert_eq!(WorkingHours::parse(&weekend.to_string(), &utc), Ok(weekend));
+
+        assert!(WorkingHours::parse("17:00-09:00", &utc).is_err());
+        assert!(WorkingHours::parse("Someday 09:00-17:00", &utc).is_err());
+        assert!(WorkingHours::parse("", &utc).is_err());
+    }
+
+    #[test]
+    fn windows_are_read_in_the_timezone() {
+        let berlin = Timezone::parse("Europe/Berlin").unwrap();
+        let week = WorkingHours::parse("Mon-Fri 09:00-17:00", &berlin).unwrap();
+        // Friday 6 March 2026; Berlin is UTC+1
+        let friday = NaiveDate::from_ymd_opt(2026, 3, 6).unwrap();
+        let (start, end) = week.window(friday).unwrap();
+        assert_eq!(start, Utc.with_ymd_and_hms(2026, 3, 6, 8, 0, 0).unwrap());
+        assert_eq!(end, Utc.with_ymd_and_hms(2026, 3, 6, 16, 0, 0).unwrap());
+        assert_eq!(week.window(friday + Duration::days(1)), None);
+
+        assert!(week.contains(start, start + Duration::hours(1)));
+        assert!(!week.contains(end - Duration::minutes(30), end + Duration::minutes(30)));
+        // 08:30 UTC on Saturday is a day off
+        let saturday = Utc.with_ymd_and_hms(2026, 3, 7, 8, 30, 0).unwrap();
+        assert!(!week.contains(saturday, saturday + Duration::hours(1)));
+    }
+}
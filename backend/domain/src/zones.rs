@@ -0,0 +1,101 @@
+//! Timezone from coordinates.
+//!
+//! Picks the zone whose principal city in the IANA `zone1970.tab` lies
+//! closest to the point. Away from borders that is the right zone; near one
+//! it may be the neighbour's, so callers let the user correct it. No zone
+//! boundaries are shipped.
+
+use crate::Timezone;
+
+const ZONE_TABLE: &str = include_str!("zone1970.tab");
+
+const EARTH_RADIUS_KM: f64 = 6371.0;
+
+/// Zone whose principal location is nearest to the point; `None` for
+/// coordinates off the globe
+#[must_use]
+pub fn timezone_near(latitude: f64, longitude: f64) -> Option<Timezone> {
+    if !(-90.0..=90.0).contains(&latitude) || !(-180.0..=180.0).contains(&longitude) {
+        return None;
+    }
+
+    ZONE_TABLE
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| {
+            let mut columns = line.split('\t');
+            let coordinates = parse_iso6709(columns.nth(1)?)?;
+            Some((coordinates, columns.next()?))
+        })
+        .map(|((lat, lon), name)| (distance_km((latitude, longitude), (lat, lon)), name))
+        .min_by(|(a, _), (b, _)| a.total_cmp(b))
+        .and_then(|(_, name)| Timezone::parse(name).ok())
+}
+
+/// `±DDMM±DDDMM` or `±DDMMSS±DDDMMSS` as decimal degrees
+fn parse_iso6709(value: &str) -> Option<(f64, f64)> {
+    let split = value[1..].find(['+', '-'])? + 1;
+    let (latitude, longitude) = value.split_at(split);
+    Some((parse_degrees(latitude, 2)?, parse_degrees(longitude, 3)?))
+}
+
+fn parse_degrees(value: &str, degree_digits: usize) -> Option<f64> {
+    let sign = match value.as_bytes().first()? {
+        b'+' => 1.0,
+        b'-' => -1.0,
+        _ => return None,
+    };
+    let digits = &value[1..];
+    let part =
+        |range: std::ops::Range<usize>| -> Option<f64> { digits.get(range)?.parse::<f64>().ok() };
+    let degrees = part(0..degree_digits)?;
+    let minutes = part(degree_digits..degree_digits + 2)?;
+    let seconds = if digits.len() > degree_digits + 2 {
+        part(degree_digits + 2..degree_digits + 4)?
+    } else {
+        0.0
+    };
+    Some(sign * (degrees + minutes / 60.0 + seconds / 3600.0))
+}
+
+/// Great-circle distance between two points given in degrees
+fn distance_km(a: (f64, f64), b: (f64, f64)) -> f64 {
+    let (lat_a, lat_b) = (a.0.to_radians(), b.0.to_radians());
+    let d_lat = lat_b - lat_a;
+    let d_lon = (b.1 - a.1).to_radians();
+    let h = (d_lat / 2.0).sin().powi(2) + lat_a.cos() * lat_b.cos() * (d_lon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_KM * h.sqrt().asin()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timezone_near_cities() {
+        let zone = |lat, lon| timezone_near(lat, lon).map(|tz| tz.as_str().to_string());
+        assert_eq!(zone(52.52, 13.40).as_deref(), Some("Europe/Berlin"));
+        assert_eq!(zone(53.55, 9.99).as_deref(), Some("Europe/Berlin"));
+        assert_eq!(zone(40.71, -74.01).as_deref(), Some("America/New_York"));
+        assert_eq!(zone(35.68, 139.69).as_deref(), Some("Asia/Tokyo"));
+        assert_eq!(zone(-33.87, 151.21).as_deref(), Some("Australia/Sydney"));
+        assert_eq!(zone(91.0, 0.0), None);
+    }
+
+    #[test]
+    fn test_zone_table_parses() {
+        let rows: Vec<&str> = ZONE_TABLE
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .collect();
+        assert!(rows.len() > 300);
+        for row in rows {
+            let columns: Vec<&str> = row.split('\t').collect();
+            assert!(parse_iso6709(columns[1]).is_some(), "{row}");
+            assert!(Timezone::parse(columns[2]).is_ok(), "{row}");
+        }
+        let (lat, lon) = parse_iso6709("+513030-0000731").unwrap();
+        assert!((lat - (51.0 + 30.0 / 60.0 + 30.0 / 3600.0)).abs() < 1e-9);
+        assert!((lon + (7.0 / 60.0 + 31.0 / 3600.0)).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,427 @@
+//! Minimal Google Calendar v3 REST client and event mapping
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use televent_application::{EventView, RemoteEvent};
+use televent_domain::{EventStatus, EventTiming, Timezone};
+use url::Url;
+
+use crate::GoogleError;
+
+const API_BASE: &str = "https://www.googleapis.com/calendar/v3";
+const PAGE_SIZE: &str = "250";
+const UNTITLED_SUMMARY: &str = "(No title)";
+
+/// Google event resource, limited to the fields Televent syncs
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct GoogleEvent {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(skip_serializing)]
+    pub etag: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    #[serde(rename = "iCalUID", skip_serializing_if = "Option::is_none")]
+    pub ical_uid: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start: Option<EventDateTime>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end: Option<EventDateTime>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub recurrence: Vec<String>,
+    /// Set on modified instances of a recurring event
+    #[serde(skip_serializing)]
+    pub recurring_event_id: Option<String>,
+    #[serde(skip_serializing)]
+    pub updated: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct EventDateTime {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub date: Option<NaiveDate>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub date_time: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time_zone: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EventsPage {
+    #[serde(default)]
+    items: Vec<GoogleEvent>,
+    next_page_token: Option<String>,
+    next_sync_token: Option<String>,
+}
+
+/// All changes since a sync token, or every event for a full listing
+pub(crate) struct ChangeSet {
+    pub events: Vec<GoogleEvent>,
+    pub next_sync_token: Option<String>,
+}
+
+impl GoogleEvent {
+    pub(crate) fn is_cancelled(&self) -> bool {
+        self.status.as_deref() == Some("cancelled")
+    }
+
+    /// Map to the local model; `None` for events Televent cannot represent
+    pub(crate) fn to_remote_event(&self, uid: String) -> Option<RemoteEvent> {
+        let timing = match (self.start.as_ref()?, self.end.as_ref()?) {
+            (
+                EventDateTime {
+                    date: Some(start_date),
+                    ..
+                },
+                EventDateTime {
+                    date: Some(end_date),
+                    ..
+                },
+            ) => EventTiming::AllDay {
+                start_date: *start_date,
+                end_date: *end_date,
+            },
+            (
+                EventDateTime {
+                    date_time: Some(start),
+                    time_zone,
+                    ..
+                },
+                EventDateTime {
+                    date_time: Some(end),
+                    ..
+                },
+            ) => EventTiming::Timed {
+                start: *start,
+                end: *end,
+                timezone: time_zone
+                    .as_deref()
+                    .and_then(|zone| Timezone::parse(zone).ok())
+                    .unwrap_or_default(),
+            },
+            _ => return None,
+        };
+
+        let summary = self
+            .summary
+            .as_deref()
+            .map(str::trim)
+            .filter(|summary| !summary.is_empty())
+            .unwrap_or(UNTITLED_SUMMARY);
+
+        Some(RemoteEvent {
+            uid,
+            summary: summary.to_string(),
+            description: self.description.clone().filter(|text| !text.is_empty()),
+            location: self.location.clone().filter(|text| !text.is_empty()),
+            timing,
+            status: match self.status.as_deref() {
+                Some("tentative") => EventStatus::Tentative,
+                _ => EventStatus::Confirmed,
+            },
+            rrule: self
+                .recurrence
+                .iter()
+                .find_map(|line| line.strip_prefix("RRULE:"))
+                .map(str::to_string),
+        })
+    }
+
+    /// Google representation of a local event
+    pub(crate) fn from_local(event: &EventView) -> Self {
+        // Google has no floating times, so wall-clock events go out as UTC
+        let (start, end) = match &event.timing.pinned_to(&Timezone::utc()) {
+            EventTiming::AllDay {
+                start_date,
+                end_date,
+            } => (
+                EventDateTime {
+                    date: Some(*start_date),
+                    ..EventDateTime::default()
+                },
+                EventDateTime {
+                    date: Some(*end_date),
+                    ..EventDateTime::default()
+                },
+            ),
+            EventTiming::Timed {
+                start,
+                end,
+                timezone,
+            } => (
+                EventDateTime {
+                    date_time: Some(*start),
+                    time_zone: Some(timezone.as_str().to_string()),
+                    ..EventDateTime::default()
+                },
+                EventDateTime {
+                    date_time: Some(*end),
+                    time_zone: Some(timezone.as_str().to_string()),
+                    ..EventDateTime::default()
+                },
+            ),
+            EventTiming::Floating { .. } => unreachable!("pinned to UTC"),
+        };
+
+        Self {
+            status: Some(
+                match event.status {
+                    EventStatus::Confirmed => "confirmed",
+                    EventStatus::Tentative => "tentative",
+                    EventStatus::Cancelled => "cancelled",
+                }
+                .to_string(),
+            ),
+            ical_uid: Some(event.uid.clone()),
+            summary: Some(event.summary.clone()),
+            description: event.description.clone(),
+            location: event.location.clone(),
+            start: Some(start),
+            end: Some(end),
+            recurrence: event
+                .rrule
+                .iter()
+                .map(|rrule| format!("RRULE:{rrule}"))
+                .collect(),
+            ..Self::default()
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct CalendarClient {
+    http: reqwest::Client,
+}
+
+impl CalendarClient {
+    pub(crate) const fn new(http: reqwest::Client) -> Self {
+        Self { http }
+    }
+
+    /// Changes since `sync_token`, following every page
+    ///
+    /// Without a token this lists the whole calendar, including deleted
+    /// events, and returns the token for the next incremental call.
+    pub(crate) async fn list_changes(
+        &self,
+        access_token: &str,
+        calendar_id: &str,
+        sync_token: Option<&str>,
+    ) -> Result<ChangeSet, GoogleError> {
+        let mut events = Vec::new();
+        let mut page_token: Option<String> = None;
+
+        loop {
+            let mut url = events_url(calendar_id, None);
+            {
+                let mut query = url.query_pairs_mut();
+                query
+                    .append_pair("maxResults", PAGE_SIZE)
+                    .append_pair("showDeleted", "true");
+                if let Some(sync_token) = sync_token {
+                    query.append_pair("syncToken", sync_token);
+                }
+                if let Some(page_token) = &page_token {
+                    query.append_pair("pageToken", page_token);
+                }
+            }
+
+            let response = self.http.get(url).bearer_auth(access_token).send().await?;
+            if response.status() == reqwest::StatusCode::GONE {
+                return Err(GoogleError::SyncTokenExpired);
+            }
+            let page: EventsPage = json_or_error(response).await?;
+            events.extend(page.items);
+
+            match page.next_page_token {
+                Some(next) => page_token = Some(next),
+                None => {
+                    return Ok(ChangeSet {
+                        events,
+                        next_sync_token: page.next_sync_token,
+                    });
+                }
+            }
+        }
+    }
+
+    /// Create a Google event keeping the local UID as its iCalUID
+    pub(crate) async fn import_event(
+        &self,
+        access_token: &str,
+        calendar_id: &str,
+        event: &GoogleEvent,
+    ) -> Result<GoogleEvent, GoogleError> {
+        let response = self
+            .http
+            .post(events_url(calendar_id, Some("import")))
+            .bearer_auth(access_token)
+            .json(event)
+            .send()
+            .await?;
+        json_or_error(response).await
+    }
+
+    /// Overwrite a Google event; `None` when it no longer exists
+    pub(crate) async fn update_event(
+        &self,
+        access_token: &str,
+        calendar_id: &str,
+        event_id: &str,
+        event: &GoogleEvent,
+    ) -> Result<Option<GoogleEvent>, GoogleError> {
+        let response = self
+            .http
+            .put(events_url(calendar_id, Some(event_id)))
+            .bearer_auth(access_token)
+            .json(event)
+            .send()
+            .await?;
+        if is_gone(response.status()) {
+            return Ok(None);
+        }
+        json_or_error(response).await.map(Some)
+    }
+
+    /// Delete a Google event; already deleted events are not an error
+    pub(crate) async fn delete_event(
+        &self,
+        access_token: &str,
+        calendar_id: &str,
+        event_id: &str,
+    ) -> Result<(), GoogleError> {
+        let response = self
+            .http
+            .delete(events_url(calendar_id, Some(event_id)))
+            .bearer_auth(access_token)
+            .send()
+            .await?;
+        if response.status().is_success() || is_gone(response.status()) {
+            return Ok(());
+        }
+        Err(api_error(response).await)
+    }
+}
+
+fn events_url(calendar_id: &str, event_path: Option<&str>) -> Url {
+    let mut url = Url::parse(API_BASE).expect("API base URL is valid");
+    {
+        let mut segments = url.path_segments_mut().expect("API base URL has a path");
+        segments.extend(["calendars", calendar_id, "events"]);
+        if let Some(event_path) = event_path {
+            segments.push(event_path);
+        }
+    }
+    url
+}
+
+fn is_gone(status: reqwest::StatusCode) -> bool {
+    status == reqwest::StatusCode::NOT_FOUND || status == reqwest::StatusCode::GONE
+}
+
+async fn json_or_error<T: serde::de::DeserializeOwned>(
+    response: reqwest::Response,
+) -> Result<T, GoogleError> {
+    if !response.status().is_success() {
+        return Err(api_error(response).await);
+    }
+    Ok(response.json::<T>().await?)
+}
+
+async fn api_error(response: reqwest::Response) -> GoogleError {
+    let status = response.status().as_u16();
+    let message = response
+        .json::<serde_json::Value>()
+        .await
+        .ok()
+        .and_then(|body| body["error"]["message"].as_str().map(str::to_string))
+        .unwrap_or_else(|| "request failed".to_string());
+    GoogleError::Api { status, message }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use televent_domain::EventVisibility;
+
+    #[test]
+    fn test_google_event_maps_to_local_model() {
+        let event: GoogleEvent = serde_json::from_value(serde_json::json!({
+            "id": "abc123",
+            "etag": "\"3371\"",
+            "status": "tentative",
+            "iCalUID": "abc123@google.com",
+            "summary": "Standup",
+            "start": { "dateTime": "2026-06-01T10:00:00+02:00", "timeZone": "Europe/Berlin" },
+            "end": { "dateTime": "2026-06-01T10:15:00+02:00", "timeZone": "Europe/Berlin" },
+            "recurrence": ["EXDATE:20260608T080000Z", "RRULE:FREQ=WEEKLY;BYDAY=MO"],
+            "updated": "2026-05-20T09:00:00.000Z"
+        }))
+        .unwrap();
+
+        let remote = event
+            .to_remote_event("abc123@google.com".to_string())
+            .unwrap();
+        assert_eq!(remote.status, EventStatus::Tentative);
+        assert_eq!(remote.rrule.as_deref(), Some("FREQ=WEEKLY;BYDAY=MO"));
+        let EventTiming::Timed {
+            start, timezone, ..
+        } = &remote.timing
+        else {
+            panic!("expected a timed event");
+        };
+        assert_eq!(start.to_rfc3339(), "2026-06-01T08:00:00+00:00");
+        assert_eq!(timezone.as_str(), "Europe/Berlin");
+
+        let all_day: GoogleEvent = serde_json::from_value(serde_json::json!({
+            "id": "day",
+            "start": { "date": "2026-06-01" },
+            "end": { "date": "2026-06-02" }
+        }))
+        .unwrap();
+        let remote = all_day.to_remote_event("day".to_string()).unwrap();
+        assert_eq!(remote.summary, UNTITLED_SUMMARY);
+        assert!(matches!(remote.timing, EventTiming::AllDay { .. }));
+    }
+
+    #[test]
+    fn test_local_event_round_trips_through_google_shape() {
+        let local = EventView {
+            id: uuid::Uuid::nil(),
+            uid: "local-1".to_string(),
+            summary: "Dentist".to_string(),
+            description: None,
+            location: Some("Main St".to_string()),
+            timing: EventTiming::AllDay {
+                start_date: NaiveDate::from_ymd_opt(2026, 6, 1).unwrap(),
+                end_date: NaiveDate::from_ymd_opt(2026, 6, 2).unwrap(),
+            },
+            status: EventStatus::Confirmed,
+            rrule: Some("FREQ=YEARLY".to_string()),
+            visibility: EventVisibility::Public,
+            reminder_note: None,
+        };
+
+        let google = GoogleEvent::from_local(&local);
+        let body = serde_json::to_value(&google).unwrap();
+        assert_eq!(body["iCalUID"], "local-1");
+        assert_eq!(body["start"]["date"], "2026-06-01");
+        assert_eq!(body["recurrence"][0], "RRULE:FREQ=YEARLY");
+        assert!(body.get("id").is_none());
+
+        let back = google.to_remote_event(local.uid.clone()).unwrap();
+        assert_eq!(back.summary, local.summary);
+        assert_eq!(back.location, local.location);
+        assert_eq!(back.timing, local.timing);
+        assert_eq!(back.rrule, local.rrule);
+    }
+}
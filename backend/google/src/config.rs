@@ -0,0 +1,39 @@
+//! Configuration of the Google OAuth client
+//!
+//! Loads configuration from environment variables
+
+use anyhow::{Result, bail};
+use std::env;
+
+/// OAuth client registered in the Google Cloud console
+#[derive(Debug, Clone)]
+pub struct GoogleConfig {
+    pub client_id: String,
+    pub client_secret: String,
+    /// Public URL of the `/google/callback` route
+    pub redirect_url: String,
+}
+
+impl GoogleConfig {
+    /// Load configuration from environment variables
+    ///
+    /// Returns `None` when `GOOGLE_CLIENT_ID` is unset, which leaves the
+    /// connector disabled.
+    pub fn from_env() -> Result<Option<Self>> {
+        let Ok(client_id) = env::var("GOOGLE_CLIENT_ID") else {
+            return Ok(None);
+        };
+        let (Ok(client_secret), Ok(redirect_url)) = (
+            env::var("GOOGLE_CLIENT_SECRET"),
+            env::var("GOOGLE_REDIRECT_URL"),
+        ) else {
+            bail!("GOOGLE_CLIENT_SECRET and GOOGLE_REDIRECT_URL must be set with GOOGLE_CLIENT_ID");
+        };
+
+        Ok(Some(Self {
+            client_id,
+            client_secret,
+            redirect_url,
+        }))
+    }
+}
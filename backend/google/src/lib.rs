@@ -0,0 +1,39 @@
+//! Google Calendar connector for Televent
+//!
+//! Optional two-way sync between a user's calendar and their primary Google
+//! Calendar. The crate talks to Google (OAuth consent, token refresh and the
+//! Calendar v3 REST API) and runs the sync algorithm; all local state goes
+//! through [`televent_application::GoogleSyncService`].
+//!
+//! The API and worker only pull this crate in with the `google-calendar`
+//! feature.
+
+mod calendar;
+mod config;
+mod oauth;
+mod sync;
+
+pub use config::GoogleConfig;
+pub use oauth::TokenGrant;
+pub use sync::{AuthorizationRequest, GoogleConnector, SyncReport};
+
+use televent_application::ApplicationError;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum GoogleError {
+    #[error("Google request failed: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("Google answered {status}: {message}")]
+    Api { status: u16, message: String },
+    /// The stored sync token is no longer valid; a full listing is needed.
+    #[error("Google sync token expired")]
+    SyncTokenExpired,
+    /// The refresh token was revoked; the user must connect again.
+    #[error("Google access was revoked; reconnect Google Calendar")]
+    AccessRevoked,
+    #[error("invalid OAuth state")]
+    InvalidState,
+    #[error(transparent)]
+    Application(#[from] ApplicationError),
+}
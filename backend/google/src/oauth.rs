@@ -0,0 +1,158 @@
+//! OAuth 2.0 authorization code flow against Google
+//!
+//! The `state` parameter is an opaque single-use value issued by
+//! [`televent_application::GoogleSyncService::begin_authorization`]; who
+//! consented is looked up from it when Google redirects back.
+
+use chrono::{DateTime, Duration, Utc};
+use serde::Deserialize;
+use url::Url;
+
+use crate::{GoogleConfig, GoogleError};
+
+const AUTHORIZE_URL: &str = "https://accounts.google.com/o/oauth2/v2/auth";
+const TOKEN_URL: &str = "https://oauth2.googleapis.com/token";
+const CALENDAR_SCOPE: &str = "https://www.googleapis.com/auth/calendar.events";
+
+/// Tokens granted by Google
+#[derive(Debug, Clone)]
+pub struct TokenGrant {
+    pub access_token: String,
+    /// Only present on the first consent or with `prompt=consent`
+    pub refresh_token: Option<String>,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    refresh_token: Option<String>,
+    expires_in: i64,
+}
+
+#[derive(Deserialize)]
+struct TokenErrorResponse {
+    error: String,
+    error_description: Option<String>,
+}
+
+#[derive(Clone)]
+pub(crate) struct OAuthClient {
+    config: GoogleConfig,
+    http: reqwest::Client,
+}
+
+impl OAuthClient {
+    pub(crate) const fn new(config: GoogleConfig, http: reqwest::Client) -> Self {
+        Self { config, http }
+    }
+
+    /// Consent page URL carrying the given state
+    pub(crate) fn authorization_url(&self, state: &str) -> String {
+        let mut url = Url::parse(AUTHORIZE_URL).expect("authorize URL is valid");
+        url.query_pairs_mut()
+            .append_pair("client_id", &self.config.client_id)
+            .append_pair("redirect_uri", &self.config.redirect_url)
+            .append_pair("response_type", "code")
+            .append_pair("scope", CALENDAR_SCOPE)
+            .append_pair("access_type", "offline")
+            // Force the consent screen so Google issues a refresh token again
+            .append_pair("prompt", "consent")
+            .append_pair("state", state);
+        url.into()
+    }
+
+    /// Exchange the authorization code from the callback for tokens
+    pub(crate) async fn exchange_code(&self, code: &str) -> Result<TokenGrant, GoogleError> {
+        self.request_tokens(&[
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", &self.config.redirect_url),
+        ])
+        .await
+    }
+
+    /// Obtain a new access token with the stored refresh token
+    pub(crate) async fn refresh(&self, refresh_token: &str) -> Result<TokenGrant, GoogleError> {
+        self.request_tokens(&[
+            ("grant_type", "refresh_token"),
+            ("refresh_token", refresh_token),
+        ])
+        .await
+    }
+
+    async fn request_tokens(&self, params: &[(&str, &str)]) -> Result<TokenGrant, GoogleError> {
+        let body = url::form_urlencoded::Serializer::new(String::new())
+            .append_pair("client_id", &self.config.client_id)
+            .append_pair("client_secret", &self.config.client_secret)
+            .extend_pairs(params)
+            .finish();
+
+        let response = self
+            .http
+            .post(TOKEN_URL)
+            .header(
+                reqwest::header::CONTENT_TYPE,
+                "application/x-www-form-urlencoded",
+            )
+            .body(body)
+            .send()
+            .await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let error = response.json::<TokenErrorResponse>().await.ok();
+            return Err(match error {
+                Some(error) if error.error == "invalid_grant" => GoogleError::AccessRevoked,
+                Some(error) => GoogleError::Api {
+                    status: status.as_u16(),
+                    message: error.error_description.unwrap_or(error.error),
+                },
+                None => GoogleError::Api {
+                    status: status.as_u16(),
+                    message: "token request failed".to_string(),
+                },
+            });
+        }
+
+        let tokens = response.json::<TokenResponse>().await?;
+        Ok(TokenGrant {
+            access_token: tokens.access_token,
+            refresh_token: tokens.refresh_token,
+            expires_at: Utc::now() + Duration::seconds(tokens.expires_in),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn client() -> OAuthClient {
+        OAuthClient::new(
+            GoogleConfig {
+                client_id: "client".to_string(),
+                client_secret: "secret".to_string(),
+                redirect_url: "https://televent.example/google/callback".to_string(),
+            },
+            reqwest::Client::new(),
+        )
+    }
+
+    #[test]
+    fn test_authorization_url_carries_state_and_offline_access() {
+        let url = Url::parse(&client().authorization_url("opaque-state")).unwrap();
+        let param = |name: &str| {
+            url.query_pairs()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.into_owned())
+        };
+
+        assert_eq!(param("state").as_deref(), Some("opaque-state"));
+        assert_eq!(param("access_type").as_deref(), Some("offline"));
+        assert_eq!(
+            param("redirect_uri").as_deref(),
+            Some("https://televent.example/google/callback")
+        );
+    }
+}
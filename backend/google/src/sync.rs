@@ -0,0 +1,417 @@
+//! Two-way sync of one Google Calendar connection
+//!
+//! A sync pulls Google's changes since the stored sync token, then pushes the
+//! local changes made since the last pushed calendar sync version. After every
+//! exchange the event link records both sides' etags, so a change whose etag
+//! still matches its link is an echo of the sync's own write and is skipped.
+//! When an event changed on both sides, the newest update wins.
+
+use chrono::{Duration, Utc};
+use std::collections::HashMap;
+use televent_application::{
+    ApplicationError, ConnectGoogleCommand, EventLink, GoogleConnectionView, GoogleSyncService,
+    GoogleSyncState, LocalEvent, SyncWinner, resolve_sync_conflict,
+};
+use televent_domain::UserId;
+use tracing::warn;
+
+use crate::calendar::{CalendarClient, GoogleEvent};
+use crate::oauth::OAuthClient;
+use crate::{GoogleConfig, GoogleError};
+
+const REQUEST_TIMEOUT_SECS: u64 = 30;
+
+/// Refresh access tokens this long before they expire
+const TOKEN_REFRESH_MARGIN_SECS: i64 = 60;
+
+/// Counts of what one sync did
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SyncReport {
+    /// Google events written locally
+    pub pulled: usize,
+    /// Local events written to Google
+    pub pushed: usize,
+    pub deleted_local: usize,
+    pub deleted_remote: usize,
+    /// Events changed on both sides where the local copy was newer
+    pub conflicts: usize,
+    /// Events one side cannot represent
+    pub skipped: usize,
+}
+
+/// Consent URL and the state the initiating browser must present again
+#[derive(Debug, Clone)]
+pub struct AuthorizationRequest {
+    pub url: String,
+    pub state: String,
+}
+
+/// Google OAuth and Calendar API clients sharing one HTTP client
+#[derive(Clone)]
+pub struct GoogleConnector {
+    oauth: OAuthClient,
+    calendar: CalendarClient,
+}
+
+impl GoogleConnector {
+    pub fn new(config: GoogleConfig) -> Result<Self, GoogleError> {
+        let http = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(REQUEST_TIMEOUT_SECS))
+            .user_agent(concat!("Televent/", env!("CARGO_PKG_VERSION")))
+            .build()?;
+
+        Ok(Self {
+            oauth: OAuthClient::new(config, http.clone()),
+            calendar: CalendarClient::new(http),
+        })
+    }
+
+    /// Start a consent for the user.
+    ///
+    /// The returned state must also be handed to the browser that opens the
+    /// URL (as a cookie); [`Self::complete_authorization`] requires both.
+    pub async fn authorization_url(
+        &self,
+        service: &GoogleSyncService,
+        user_id: UserId,
+    ) -> Result<AuthorizationRequest, GoogleError> {
+        let state = service.begin_authorization(user_id).await?;
+        Ok(AuthorizationRequest {
+            url: self.oauth.authorization_url(&state),
+            state,
+        })
+    }
+
+    /// Finish the consent started by [`Self::authorization_url`] and store the tokens.
+    ///
+    /// `browser_state` is the state the initiating browser kept; a consent link
+    /// opened anywhere else, or a second time, is rejected.
+    pub async fn complete_authorization(
+        &self,
+        service: &GoogleSyncService,
+        state: &str,
+        browser_state: Option<&str>,
+        code: &str,
+    ) -> Result<GoogleConnectionView, GoogleError> {
+        if browser_state != Some(state) {
+            return Err(GoogleError::InvalidState);
+        }
+        let user_id = service
+            .take_authorization(state)
+            .await?
+            .ok_or(GoogleError::InvalidState)?;
+        let grant = self.oauth.exchange_code(code).await?;
+
+        Ok(service
+            .connect(ConnectGoogleCommand {
+                user_id,
+                username: None,
+                access_token: grant.access_token,
+                refresh_token: grant.refresh_token,
+                access_token_expires_at: grant.expires_at,
+            })
+            .await?)
+    }
+
+    /// Run one incremental two-way sync and advance both cursors
+    pub async fn sync_connection(
+        &self,
+        service: &GoogleSyncService,
+        state: GoogleSyncState,
+    ) -> Result<SyncReport, GoogleError> {
+        let user_id = state.user_id;
+        let access_token = self.access_token(service, &state).await?;
+        let mut session = SyncSession {
+            service,
+            calendar: &self.calendar,
+            user_id,
+            access_token,
+            calendar_id: state.calendar_id,
+            links: Links::new(service.list_links(user_id).await?),
+            report: SyncReport::default(),
+        };
+
+        let changes = match self
+            .calendar
+            .list_changes(
+                &session.access_token,
+                &session.calendar_id,
+                state.google_sync_token.as_deref(),
+            )
+            .await
+        {
+            Err(GoogleError::SyncTokenExpired) => {
+                warn!(
+                    "Google sync token of user {} expired, listing all events",
+                    user_id
+                );
+                self.calendar
+                    .list_changes(&session.access_token, &session.calendar_id, None)
+                    .await?
+            }
+            result => result?,
+        };
+        for remote in changes.events {
+            session.pull(remote).await?;
+        }
+
+        let local = service
+            .local_changes(user_id, state.local_sync_version)
+            .await?;
+        for uid in &local.deleted_uids {
+            // A UID deleted and then created again is pushed as a live event
+            if local.events.iter().all(|event| &event.event.uid != uid) {
+                session.push_deletion(uid).await?;
+            }
+        }
+        for event in local.events {
+            session.push(event).await?;
+        }
+
+        service
+            .record_sync_success(user_id, changes.next_sync_token, local.sync_version)
+            .await?;
+        Ok(session.report)
+    }
+
+    async fn access_token(
+        &self,
+        service: &GoogleSyncService,
+        state: &GoogleSyncState,
+    ) -> Result<String, GoogleError> {
+        if state.access_token_expires_at > Utc::now() + Duration::seconds(TOKEN_REFRESH_MARGIN_SECS)
+        {
+            return Ok(state.access_token.clone());
+        }
+
+        let grant = self.oauth.refresh(&state.refresh_token).await?;
+        service
+            .store_access_token(state.user_id, &grant.access_token, grant.expires_at)
+            .await?;
+        Ok(grant.access_token)
+    }
+}
+
+/// Links of one user, reachable from either side
+struct Links {
+    by_uid: HashMap<String, EventLink>,
+    uid_by_google_id: HashMap<String, String>,
+}
+
+impl Links {
+    fn new(links: Vec<EventLink>) -> Self {
+        let uid_by_google_id = links
+            .iter()
+            .map(|link| (link.google_event_id.clone(), link.event_uid.clone()))
+            .collect();
+        let by_uid = links
+            .into_iter()
+            .map(|link| (link.event_uid.clone(), link))
+            .collect();
+        Self {
+            by_uid,
+            uid_by_google_id,
+        }
+    }
+
+    fn by_google_id(&self, google_event_id: &str) -> Option<&EventLink> {
+        self.uid_by_google_id
+            .get(google_event_id)
+            .and_then(|uid| self.by_uid.get(uid))
+    }
+
+    fn insert(&mut self, link: EventLink) {
+        self.uid_by_google_id
+            .insert(link.google_event_id.clone(), link.event_uid.clone());
+        self.by_uid.insert(link.event_uid.clone(), link);
+    }
+
+    fn remove(&mut self, uid: &str) -> Option<EventLink> {
+        let link = self.by_uid.remove(uid)?;
+        self.uid_by_google_id.remove(&link.google_event_id);
+        Some(link)
+    }
+}
+
+struct SyncSession<'a> {
+    service: &'a GoogleSyncService,
+    calendar: &'a CalendarClient,
+    user_id: UserId,
+    access_token: String,
+    calendar_id: String,
+    links: Links,
+    report: SyncReport,
+}
+
+impl SyncSession<'_> {
+    async fn pull(&mut self, remote: GoogleEvent) -> Result<(), GoogleError> {
+        let Some(google_event_id) = remote.id.clone() else {
+            return Ok(());
+        };
+        // Modified instances of recurring events have no local counterpart
+        if remote.recurring_event_id.is_some() {
+            self.report.skipped += 1;
+            return Ok(());
+        }
+
+        let link = self.links.by_google_id(&google_event_id).cloned();
+        if let Some(link) = &link
+            && remote.etag.as_deref() == Some(link.google_etag.as_str())
+        {
+            return Ok(());
+        }
+
+        if remote.is_cancelled() {
+            // Only events the sync created or paired are ever deleted locally
+            if let Some(link) = link {
+                if self
+                    .service
+                    .delete_local_event(self.user_id, &link.event_uid)
+                    .await?
+                {
+                    self.report.deleted_local += 1;
+                }
+                self.service
+                    .remove_link(self.user_id, &link.event_uid)
+                    .await?;
+                self.links.remove(&link.event_uid);
+            }
+            return Ok(());
+        }
+
+        let uid = link
+            .as_ref()
+            .map(|link| link.event_uid.clone())
+            .or_else(|| remote.ical_uid.clone())
+            .unwrap_or_else(|| google_event_id.clone());
+        let remote_updated_at = remote.updated.unwrap_or_else(Utc::now);
+
+        if let Some(local) = self.service.local_event(self.user_id, &uid).await? {
+            let changed_locally = link
+                .as_ref()
+                .is_none_or(|link| link.local_etag != local.etag);
+            if changed_locally
+                && resolve_sync_conflict(local.updated_at, remote_updated_at) == SyncWinner::Local
+            {
+                // The push step sends the newer local copy
+                self.report.conflicts += 1;
+                return Ok(());
+            }
+        }
+
+        let Some(event) = remote.to_remote_event(uid.clone()) else {
+            self.report.skipped += 1;
+            return Ok(());
+        };
+        let local = match self.service.apply_remote_event(self.user_id, event).await {
+            Ok(local) => local,
+            Err(ApplicationError::BadRequest(reason)) => {
+                warn!("Skipping Google event {}: {}", google_event_id, reason);
+                self.report.skipped += 1;
+                return Ok(());
+            }
+            Err(err) => return Err(err.into()),
+        };
+
+        self.save_link(EventLink {
+            event_uid: uid,
+            google_event_id,
+            google_etag: remote.etag.unwrap_or_default(),
+            google_updated_at: remote_updated_at,
+            local_etag: local.etag,
+        })
+        .await?;
+        self.report.pulled += 1;
+        Ok(())
+    }
+
+    async fn push(&mut self, local: LocalEvent) -> Result<(), GoogleError> {
+        let link = self.links.by_uid.get(&local.event.uid).cloned();
+        if link
+            .as_ref()
+            .is_some_and(|link| link.local_etag == local.etag)
+        {
+            return Ok(());
+        }
+
+        let body = GoogleEvent::from_local(&local.event);
+        let pushed = match &link {
+            Some(link) => {
+                match self
+                    .calendar
+                    .update_event(
+                        &self.access_token,
+                        &self.calendar_id,
+                        &link.google_event_id,
+                        &body,
+                    )
+                    .await
+                {
+                    // Deleted on Google without us noticing yet: recreate it
+                    Ok(None) => {
+                        self.calendar
+                            .import_event(&self.access_token, &self.calendar_id, &body)
+                            .await
+                    }
+                    result => result.map(Option::unwrap_or_default),
+                }
+            }
+            None => {
+                self.calendar
+                    .import_event(&self.access_token, &self.calendar_id, &body)
+                    .await
+            }
+        };
+
+        let pushed = match pushed {
+            Ok(pushed) => pushed,
+            Err(GoogleError::Api {
+                status: 400,
+                message,
+            }) => {
+                warn!(
+                    "Google rejected event {} of user {}: {}",
+                    local.event.uid, self.user_id, message
+                );
+                self.report.skipped += 1;
+                return Ok(());
+            }
+            Err(err) => return Err(err),
+        };
+        let Some(google_event_id) = pushed.id else {
+            return Err(GoogleError::Api {
+                status: 200,
+                message: "event written without an id".to_string(),
+            });
+        };
+
+        self.save_link(EventLink {
+            event_uid: local.event.uid,
+            google_event_id,
+            google_etag: pushed.etag.unwrap_or_default(),
+            google_updated_at: pushed.updated.unwrap_or_else(Utc::now),
+            local_etag: local.etag,
+        })
+        .await?;
+        self.report.pushed += 1;
+        Ok(())
+    }
+
+    async fn push_deletion(&mut self, uid: &str) -> Result<(), GoogleError> {
+        let Some(link) = self.links.remove(uid) else {
+            return Ok(());
+        };
+        self.calendar
+            .delete_event(&self.access_token, &self.calendar_id, &link.google_event_id)
+            .await?;
+        self.service.remove_link(self.user_id, uid).await?;
+        self.report.deleted_remote += 1;
+        Ok(())
+    }
+
+    async fn save_link(&mut self, link: EventLink) -> Result<(), GoogleError> {
+        self.service.save_link(self.user_id, link.clone()).await?;
+        self.links.insert(link);
+        Ok(())
+    }
+}
@@ -0,0 +1,42 @@
+-- Derive the calendar ctag from the latest event change.
+--
+-- Application transactions used to write users.ctag next to every sync_token
+-- bump. The ctag is now maintained by triggers as the highest sync_version of
+-- the calendar's events and tombstones, so any write that touches an event row
+-- or records a deletion moves it forward without handler code.
+
+CREATE OR REPLACE FUNCTION advance_calendar_ctag()
+RETURNS TRIGGER AS $$
+BEGIN
+    UPDATE users
+    SET ctag = NEW.sync_version
+    WHERE telegram_id = NEW.user_id
+      AND ctag < NEW.sync_version;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+COMMENT ON FUNCTION advance_calendar_ctag() IS
+    'Trigger function advancing users.ctag to the sync_version of a changed event or tombstone';
+
+CREATE TRIGGER events_advance_calendar_ctag
+    AFTER INSERT OR UPDATE OF sync_version ON events
+    FOR EACH ROW
+    EXECUTE FUNCTION advance_calendar_ctag();
+
+CREATE TRIGGER event_tombstones_advance_calendar_ctag
+    AFTER INSERT OR UPDATE OF sync_version ON event_tombstones
+    FOR EACH ROW
+    EXECUTE FUNCTION advance_calendar_ctag();
+
+-- Backfill from existing rows, never moving a ctag back: tombstones may have
+-- been purged, and clients may already have cached the current value
+UPDATE users u
+SET ctag = GREATEST(
+    u.ctag,
+    COALESCE((SELECT MAX(e.sync_version) FROM events e WHERE e.user_id = u.telegram_id), 0),
+    COALESCE((SELECT MAX(t.sync_version) FROM event_tombstones t WHERE t.user_id = u.telegram_id), 0)
+);
+
+COMMENT ON COLUMN users.ctag IS
+    'Collection tag: highest sync_version among the calendar''s events and tombstones. Maintained by triggers';
@@ -0,0 +1,20 @@
+-- Outbox kinds for organizer follow-ups from the attendee dashboard:
+-- re-sent invites and notices to people removed from an event.
+
+ALTER TABLE outbox_messages
+    DROP CONSTRAINT check_outbox_kind;
+
+ALTER TABLE outbox_messages
+    ADD CONSTRAINT check_outbox_kind CHECK (
+        kind IN (
+            'invite_notification',
+            'telegram_notification',
+            'external_email_deferred',
+            'rsvp_notification',
+            'invite_reminder',
+            'attendee_removed_notification'
+        )
+    );
+
+COMMENT ON CONSTRAINT check_outbox_kind ON outbox_messages IS
+    'Restricts outbox messages to Rust OutboxKind discriminators';
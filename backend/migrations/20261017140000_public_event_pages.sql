@@ -0,0 +1,61 @@
+-- Public event pages with email signup.
+--
+-- An organizer can publish an event under an unguessable slug. Visitors sign
+-- up with their email address; the signup only becomes an attendee once the
+-- confirmation link sent to that address is opened. The worker mints that
+-- link's token right before the email goes out and stores only its hash, so
+-- plaintext tokens never sit in the outbox.
+
+ALTER TABLE events
+    ADD COLUMN public_slug TEXT;
+
+CREATE UNIQUE INDEX idx_events_public_slug
+    ON events(public_slug)
+    WHERE public_slug IS NOT NULL;
+
+COMMENT ON COLUMN events.public_slug IS
+    'Slug of the public signup page (/e/{slug}); NULL while the event is private';
+
+CREATE TABLE public_signups (
+    event_id UUID NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+    email TEXT NOT NULL,
+    display_name TEXT,
+    token_hash TEXT,
+    expires_at TIMESTAMPTZ NOT NULL,
+    confirmed_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (event_id, email)
+);
+
+CREATE UNIQUE INDEX idx_public_signups_token_hash
+    ON public_signups(token_hash);
+
+CREATE TRIGGER public_signups_updated_at
+    BEFORE UPDATE ON public_signups
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at();
+
+COMMENT ON TABLE public_signups IS
+    'Email signups from public event pages awaiting or past confirmation';
+COMMENT ON COLUMN public_signups.token_hash IS
+    'SHA-256 hex digest of the emailed confirmation token; NULL until the email is sent';
+
+ALTER TABLE outbox_messages
+    DROP CONSTRAINT check_outbox_kind;
+
+ALTER TABLE outbox_messages
+    ADD CONSTRAINT check_outbox_kind CHECK (
+        kind IN (
+            'invite_notification',
+            'telegram_notification',
+            'external_email_deferred',
+            'rsvp_notification',
+            'invite_reminder',
+            'attendee_removed_notification',
+            'signup_confirmation'
+        )
+    );
+
+COMMENT ON CONSTRAINT check_outbox_kind ON outbox_messages IS
+    'Restricts outbox messages to Rust OutboxKind discriminators';
@@ -0,0 +1,71 @@
+-- Subscriptions to remote ICS calendars.
+--
+-- The worker periodically fetches each subscribed URL (conditionally, using
+-- the stored HTTP validators) and mirrors its events into subscribed_events.
+-- Mirrored events are read-only and never mix with the user's own calendar.
+
+CREATE TABLE calendar_subscriptions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id BIGINT NOT NULL REFERENCES users(telegram_id) ON DELETE CASCADE,
+    url TEXT NOT NULL,
+    name TEXT NOT NULL,
+    http_etag TEXT,
+    http_last_modified TEXT,
+    sync_token BIGINT NOT NULL DEFAULT 1,
+    last_fetched_at TIMESTAMPTZ,
+    next_fetch_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (user_id, url)
+);
+
+CREATE INDEX idx_calendar_subscriptions_next_fetch
+    ON calendar_subscriptions(next_fetch_at);
+
+CREATE TRIGGER calendar_subscriptions_updated_at
+    BEFORE UPDATE ON calendar_subscriptions
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at();
+
+COMMENT ON TABLE calendar_subscriptions IS
+    'Remote ICS calendars a user subscribed to; mirrored read-only';
+COMMENT ON COLUMN calendar_subscriptions.http_etag IS
+    'ETag of the last successful fetch, sent back as If-None-Match';
+COMMENT ON COLUMN calendar_subscriptions.http_last_modified IS
+    'Last-Modified of the last successful fetch, sent back as If-Modified-Since';
+COMMENT ON COLUMN calendar_subscriptions.sync_token IS
+    'Bumped whenever the mirrored events change; serves as CalDAV ctag and sync token';
+
+CREATE TABLE subscribed_events (
+    subscription_id UUID NOT NULL REFERENCES calendar_subscriptions(id) ON DELETE CASCADE,
+    uid TEXT NOT NULL,
+    summary TEXT NOT NULL,
+    description TEXT,
+    location TEXT,
+    start TIMESTAMPTZ,
+    "end" TIMESTAMPTZ,
+    start_date DATE,
+    end_date DATE,
+    is_all_day BOOLEAN NOT NULL DEFAULT FALSE,
+    status event_status NOT NULL DEFAULT 'CONFIRMED',
+    rrule TEXT,
+    timezone TEXT NOT NULL DEFAULT 'UTC',
+    etag TEXT NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (subscription_id, uid),
+    CONSTRAINT check_subscribed_event_type_integrity CHECK (
+        (is_all_day = true AND
+         start_date IS NOT NULL AND end_date IS NOT NULL AND
+         end_date > start_date AND
+         start IS NULL AND "end" IS NULL)
+        OR
+        (is_all_day = false AND
+         start_date IS NULL AND end_date IS NULL AND
+         start IS NOT NULL AND "end" IS NOT NULL AND
+         "end" > start)
+    )
+);
+
+COMMENT ON TABLE subscribed_events IS
+    'Read-only mirror of the events in a subscribed remote calendar';
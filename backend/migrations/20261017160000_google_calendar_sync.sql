@@ -0,0 +1,51 @@
+-- Two-way sync with Google Calendar.
+--
+-- A connection stores the user's OAuth tokens together with the two cursors
+-- of an incremental sync: Google's nextSyncToken for remote changes and the
+-- local calendar sync_version already pushed to Google. Links pair each local
+-- event with its Google counterpart and remember both sides' etags so the
+-- sync can tell its own echoes from real edits.
+
+CREATE TABLE google_calendar_connections (
+    user_id BIGINT PRIMARY KEY REFERENCES users(telegram_id) ON DELETE CASCADE,
+    calendar_id TEXT NOT NULL DEFAULT 'primary',
+    access_token TEXT NOT NULL,
+    refresh_token TEXT NOT NULL,
+    access_token_expires_at TIMESTAMPTZ NOT NULL,
+    google_sync_token TEXT,
+    local_sync_version BIGINT NOT NULL DEFAULT 0,
+    last_synced_at TIMESTAMPTZ,
+    next_sync_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_google_calendar_connections_next_sync
+    ON google_calendar_connections(next_sync_at);
+
+CREATE TRIGGER google_calendar_connections_updated_at
+    BEFORE UPDATE ON google_calendar_connections
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at();
+
+COMMENT ON TABLE google_calendar_connections IS
+    'OAuth tokens and incremental sync cursors of a Google Calendar connection';
+COMMENT ON COLUMN google_calendar_connections.google_sync_token IS
+    'nextSyncToken of the last completed pull; NULL forces a full listing';
+COMMENT ON COLUMN google_calendar_connections.local_sync_version IS
+    'Calendar sync_version up to which local changes were pushed to Google';
+
+CREATE TABLE google_event_links (
+    user_id BIGINT NOT NULL REFERENCES google_calendar_connections(user_id) ON DELETE CASCADE,
+    event_uid TEXT NOT NULL,
+    google_event_id TEXT NOT NULL,
+    google_etag TEXT NOT NULL,
+    google_updated_at TIMESTAMPTZ NOT NULL,
+    local_etag TEXT NOT NULL,
+    PRIMARY KEY (user_id, event_uid),
+    UNIQUE (user_id, google_event_id)
+);
+
+COMMENT ON TABLE google_event_links IS
+    'Pairs local events with Google events and records the etags last synced on both sides';
@@ -0,0 +1,21 @@
+-- Pending Google OAuth consents.
+--
+-- Each consent link carries a random state that is stored here (hashed) with
+-- the user who asked for it and handed to the same browser as a cookie. The
+-- callback only accepts a state that matches the browser's cookie and uses it
+-- up, so a consent link cannot be replayed or completed by someone else.
+
+CREATE TABLE google_oauth_states (
+    state_hash TEXT PRIMARY KEY,
+    user_id BIGINT NOT NULL REFERENCES users(telegram_id) ON DELETE CASCADE,
+    expires_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_google_oauth_states_expires
+    ON google_oauth_states(expires_at);
+
+COMMENT ON TABLE google_oauth_states IS
+    'Single-use OAuth states of Google consents in progress';
+COMMENT ON COLUMN google_oauth_states.state_hash IS
+    'SHA-256 of the state parameter; the plaintext only lives in the consent URL and cookie';
@@ -0,0 +1,29 @@
+-- Per-user address book served over CardDAV.
+--
+-- Clients own the vCard text; the columns next to it are extracted on write
+-- so attendee autocomplete and the bot's /invite can search without parsing.
+
+CREATE TABLE contacts (
+    user_id BIGINT NOT NULL REFERENCES users(telegram_id) ON DELETE CASCADE,
+    uid TEXT NOT NULL,
+    full_name TEXT NOT NULL,
+    email TEXT,
+    telegram_username TEXT,
+    vcard TEXT NOT NULL,
+    etag TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (user_id, uid)
+);
+
+CREATE TRIGGER contacts_updated_at
+    BEFORE UPDATE ON contacts
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at();
+
+COMMENT ON TABLE contacts IS
+    'Address book entries synced over CardDAV; used for attendee autocomplete';
+COMMENT ON COLUMN contacts.vcard IS
+    'vCard exactly as the client stored it, returned unchanged on GET';
+COMMENT ON COLUMN contacts.telegram_username IS
+    'Telegram username without @, from X-TELEGRAM-USERNAME, IMPP or a t.me URL';
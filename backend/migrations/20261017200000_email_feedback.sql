@@ -0,0 +1,36 @@
+-- Delivery feedback from the email provider.
+--
+-- Providers post bounces and complaints to /webhooks/email/{provider}. Every
+-- report is kept in email_feedback; hard bounces and complaints also put the
+-- address on the suppression list, which is checked before any email is sent.
+
+CREATE TABLE email_suppressions (
+    email TEXT PRIMARY KEY,
+    reason TEXT NOT NULL CHECK (reason IN ('bounced', 'complained')),
+    detail TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TRIGGER email_suppressions_updated_at
+    BEFORE UPDATE ON email_suppressions
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at();
+
+COMMENT ON TABLE email_suppressions IS
+    'Lowercased addresses that must not be emailed again';
+
+CREATE TABLE email_feedback (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    provider TEXT NOT NULL,
+    email TEXT NOT NULL,
+    kind TEXT NOT NULL CHECK (kind IN ('hard_bounce', 'soft_bounce', 'complaint')),
+    detail TEXT,
+    received_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_email_feedback_email
+    ON email_feedback(email, received_at DESC);
+
+COMMENT ON TABLE email_feedback IS
+    'Bounce and complaint reports received from email provider webhooks';
@@ -0,0 +1,24 @@
+-- Unsubscribes and per-recipient send throttling.
+--
+-- Recipients can put themselves on the suppression list through the signed
+-- link in our emails. The worker logs every email it sends in email_sends and
+-- holds back further email to an address once its hourly cap is reached.
+
+ALTER TABLE email_suppressions
+    DROP CONSTRAINT email_suppressions_reason_check;
+
+ALTER TABLE email_suppressions
+    ADD CONSTRAINT email_suppressions_reason_check
+    CHECK (reason IN ('bounced', 'complained', 'unsubscribed'));
+
+CREATE TABLE email_sends (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    email TEXT NOT NULL,
+    sent_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_email_sends_email_sent_at
+    ON email_sends(email, sent_at DESC);
+
+COMMENT ON TABLE email_sends IS
+    'Lowercased recipients of sent emails, used for per-recipient hourly caps';
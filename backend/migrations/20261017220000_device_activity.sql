@@ -0,0 +1,24 @@
+-- Recent CalDAV/CardDAV requests per device password.
+--
+-- Each authenticated request is logged so users can check whether a device
+-- is actually syncing. Only the newest rows per device are kept; older ones
+-- are pruned on insert.
+
+CREATE TABLE device_activity (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    device_id UUID NOT NULL REFERENCES device_passwords(id) ON DELETE CASCADE,
+    method TEXT NOT NULL,
+    path TEXT NOT NULL,
+    user_agent TEXT,
+    status SMALLINT NOT NULL,
+    sync_token TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_device_activity_device_created
+    ON device_activity(device_id, created_at DESC);
+
+COMMENT ON TABLE device_activity IS
+    'Ring buffer of the latest DAV requests made with each device password';
+COMMENT ON COLUMN device_activity.sync_token IS
+    'Sync token the client presented in a sync-collection REPORT';
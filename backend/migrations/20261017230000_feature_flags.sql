@@ -0,0 +1,37 @@
+-- Runtime feature flags.
+--
+-- A flag is on for a user when an override says so, or when the flag is
+-- enabled and the user falls inside its rollout percentage. Users are
+-- bucketed by a hash of flag name and user id, so raising the percentage only
+-- ever adds users.
+
+CREATE TABLE feature_flags (
+    name TEXT PRIMARY KEY CHECK (name ~ '^[a-z0-9_]{1,64}$'),
+    description TEXT NOT NULL DEFAULT '',
+    enabled BOOLEAN NOT NULL DEFAULT FALSE,
+    rollout_percent SMALLINT NOT NULL DEFAULT 0
+        CHECK (rollout_percent BETWEEN 0 AND 100),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE feature_flag_overrides (
+    flag_name TEXT NOT NULL REFERENCES feature_flags(name) ON DELETE CASCADE,
+    user_id BIGINT NOT NULL REFERENCES users(telegram_id) ON DELETE CASCADE,
+    enabled BOOLEAN NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (flag_name, user_id)
+);
+
+CREATE INDEX idx_feature_flag_overrides_user_id
+    ON feature_flag_overrides(user_id);
+
+COMMENT ON TABLE feature_flags IS
+    'Runtime feature flags with a kill switch and percentage rollout';
+COMMENT ON TABLE feature_flag_overrides IS
+    'Per-user flag values that win over the rollout';
+
+-- External (email) invites already ship to everyone; the flag lets operators
+-- dial them back without a deploy.
+INSERT INTO feature_flags (name, description, enabled, rollout_percent)
+VALUES ('external_invites', 'Invite guests by email address', TRUE, 100);
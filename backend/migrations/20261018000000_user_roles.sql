@@ -0,0 +1,4 @@
+-- Access levels for admin tooling; bootstrap admins come from ADMIN_TELEGRAM_IDS
+ALTER TABLE users
+    ADD COLUMN role TEXT NOT NULL DEFAULT 'user'
+        CHECK (role IN ('user', 'operator', 'admin'));
@@ -0,0 +1,10 @@
+-- Floating (zone-less) events keep their wall-clock time in start/"end",
+-- stored as if it were UTC
+ALTER TABLE events
+    ADD COLUMN is_floating BOOLEAN NOT NULL DEFAULT FALSE,
+    ADD CONSTRAINT check_event_floating_is_timed CHECK (NOT (is_floating AND is_all_day));
+
+ALTER TABLE subscribed_events
+    ADD COLUMN is_floating BOOLEAN NOT NULL DEFAULT FALSE,
+    ADD CONSTRAINT check_subscribed_event_floating_is_timed
+        CHECK (NOT (is_floating AND is_all_day));
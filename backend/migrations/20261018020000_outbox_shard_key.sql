@@ -0,0 +1,11 @@
+-- Telegram user an outbox message is about; workers split the queue by its
+-- hash. NULL for messages without one (external emails).
+ALTER TABLE outbox_messages ADD COLUMN shard_key BIGINT;
+
+UPDATE outbox_messages
+SET shard_key = COALESCE(
+    (payload->>'target_user_id')::BIGINT,
+    (payload->>'telegram_id')::BIGINT,
+    (payload->>'organizer_telegram_id')::BIGINT
+)
+WHERE status IN ('pending', 'processing');
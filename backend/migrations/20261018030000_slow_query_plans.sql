@@ -0,0 +1,22 @@
+-- Plans of slow queries captured in diagnostics mode.
+--
+-- Only written when SLOW_QUERY_THRESHOLD_MS is set. Parameters are stored as
+-- their types, never their values.
+
+CREATE TABLE slow_query_plans (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    query_name TEXT NOT NULL,
+    sql TEXT NOT NULL,
+    params JSONB NOT NULL,
+    duration_ms DOUBLE PRECISION NOT NULL,
+    plan JSONB NOT NULL,
+    captured_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_slow_query_plans_name_captured
+    ON slow_query_plans(query_name, captured_at DESC);
+
+COMMENT ON TABLE slow_query_plans IS
+    'EXPLAIN ANALYZE output for queries that exceeded the slow query threshold';
+COMMENT ON COLUMN slow_query_plans.duration_ms IS
+    'Duration of the original run, not of the EXPLAIN ANALYZE re-run';
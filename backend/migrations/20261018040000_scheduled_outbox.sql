@@ -0,0 +1,11 @@
+-- User who scheduled an outbox message for later, so they can list and
+-- cancel it before it is sent. NULL for messages queued by write paths.
+ALTER TABLE outbox_messages
+    ADD COLUMN scheduled_by BIGINT REFERENCES users(telegram_id) ON DELETE CASCADE;
+
+CREATE INDEX idx_outbox_scheduled_by
+    ON outbox_messages(scheduled_by, scheduled_at)
+    WHERE status = 'pending' AND scheduled_by IS NOT NULL;
+
+COMMENT ON COLUMN outbox_messages.scheduled_by IS
+    'User who scheduled the message for a later time; may cancel it while pending';
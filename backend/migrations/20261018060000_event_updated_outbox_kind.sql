@@ -0,0 +1,22 @@
+-- Guests who are coming hear when an event moves or changes place.
+ALTER TABLE outbox_messages
+    DROP CONSTRAINT check_outbox_kind;
+
+ALTER TABLE outbox_messages
+    ADD CONSTRAINT check_outbox_kind CHECK (
+        kind IN (
+            'invite_notification',
+            'telegram_notification',
+            'external_email_deferred',
+            'rsvp_notification',
+            'invite_reminder',
+            'attendee_removed_notification',
+            'signup_confirmation',
+            'event_cancelled_notification',
+            'cancellation_email',
+            'event_updated'
+        )
+    );
+
+COMMENT ON CONSTRAINT check_outbox_kind ON outbox_messages IS
+    'Restricts outbox messages to Rust OutboxKind discriminators';
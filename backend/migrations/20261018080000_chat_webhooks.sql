@@ -0,0 +1,47 @@
+-- Slack and Microsoft Teams incoming webhooks.
+--
+-- Invites, reminders, changes and cancellations a user gets in Telegram are
+-- also posted to each of their webhooks as `chat_webhook` outbox messages,
+-- one per webhook so a broken channel doesn't hold up the others.
+
+CREATE TABLE chat_webhooks (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id BIGINT NOT NULL REFERENCES users(telegram_id) ON DELETE CASCADE,
+    provider TEXT NOT NULL CHECK (provider IN ('slack', 'teams')),
+    url TEXT NOT NULL,
+    last_error TEXT,
+    last_error_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (user_id, url)
+);
+
+COMMENT ON TABLE chat_webhooks IS
+    'Incoming webhooks that mirror a user''s Telegram notifications';
+COMMENT ON COLUMN chat_webhooks.url IS
+    'Secret webhook URL; never returned in full by the API';
+COMMENT ON COLUMN chat_webhooks.last_error IS
+    'Why the provider last refused a post for good, cleared on success';
+
+ALTER TABLE outbox_messages
+    DROP CONSTRAINT check_outbox_kind;
+
+ALTER TABLE outbox_messages
+    ADD CONSTRAINT check_outbox_kind CHECK (
+        kind IN (
+            'invite_notification',
+            'telegram_notification',
+            'external_email_deferred',
+            'rsvp_notification',
+            'invite_reminder',
+            'attendee_removed_notification',
+            'signup_confirmation',
+            'event_cancelled_notification',
+            'cancellation_email',
+            'event_updated',
+            'event_update_email',
+            'chat_webhook'
+        )
+    );
+
+COMMENT ON CONSTRAINT check_outbox_kind ON outbox_messages IS
+    'Restricts outbox messages to Rust OutboxKind discriminators';
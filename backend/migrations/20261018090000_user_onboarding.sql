@@ -0,0 +1,14 @@
+-- Onboarding after the first /start: timezone and an optional email
+ALTER TABLE users
+    ADD COLUMN email TEXT,
+    ADD COLUMN onboarded_at TIMESTAMPTZ;
+
+-- Everyone who already used the bot has been through /start
+UPDATE users SET onboarded_at = created_at;
+
+CREATE UNIQUE INDEX idx_users_email ON users(lower(email));
+
+COMMENT ON COLUMN users.email IS
+    'Address the user gave during onboarding; invites sent to it reach them on Telegram';
+COMMENT ON COLUMN users.onboarded_at IS
+    'When the onboarding questions were first shown; NULL until the first /start';
@@ -0,0 +1,67 @@
+-- Text message notifications.
+--
+-- A user registers one phone number and confirms it with a code sent by
+-- SMS. Invites, reminders, changes and cancellations they get in Telegram
+-- are then copied to it as `sms` outbox messages, for the topics their
+-- notification preferences turn on.
+
+CREATE TABLE phone_numbers (
+    user_id BIGINT PRIMARY KEY REFERENCES users(telegram_id) ON DELETE CASCADE,
+    phone_number TEXT NOT NULL,
+    verified_at TIMESTAMPTZ,
+    code_hash TEXT,
+    code_expires_at TIMESTAMPTZ,
+    code_attempts INTEGER NOT NULL DEFAULT 0,
+    requested_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- A number belongs to whoever confirmed it first
+CREATE UNIQUE INDEX idx_phone_numbers_verified
+    ON phone_numbers (phone_number)
+    WHERE verified_at IS NOT NULL;
+
+COMMENT ON COLUMN phone_numbers.phone_number IS
+    'E.164 number, e.g. +442079460958';
+COMMENT ON COLUMN phone_numbers.code_hash IS
+    'SHA-256 hex digest of the texted verification code; NULL until the text is sent';
+COMMENT ON COLUMN phone_numbers.requested_at IS
+    'When the user last asked for a code, for throttling resends';
+
+-- The notification preference matrix: one row per topic and channel the
+-- user has chosen for; missing rows fall back to the channel default.
+CREATE TABLE notification_preferences (
+    user_id BIGINT NOT NULL REFERENCES users(telegram_id) ON DELETE CASCADE,
+    topic TEXT NOT NULL CHECK (topic IN ('invite', 'reminder', 'update', 'cancellation')),
+    channel TEXT NOT NULL CHECK (channel IN ('chat', 'sms')),
+    enabled BOOLEAN NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (user_id, topic, channel)
+);
+
+COMMENT ON TABLE notification_preferences IS
+    'Per-topic overrides of the channel defaults: chat webhooks on, SMS off';
+
+ALTER TABLE outbox_messages
+    DROP CONSTRAINT check_outbox_kind;
+
+ALTER TABLE outbox_messages
+    ADD CONSTRAINT check_outbox_kind CHECK (
+        kind IN (
+            'invite_notification',
+            'telegram_notification',
+            'external_email_deferred',
+            'rsvp_notification',
+            'invite_reminder',
+            'attendee_removed_notification',
+            'signup_confirmation',
+            'event_cancelled_notification',
+            'cancellation_email',
+            'event_updated',
+            'event_update_email',
+            'chat_webhook',
+            'sms'
+        )
+    );
+
+COMMENT ON CONSTRAINT check_outbox_kind ON outbox_messages IS
+    'Restricts outbox messages to Rust OutboxKind discriminators';
@@ -0,0 +1,67 @@
+-- Web Push subscriptions for the web app.
+--
+-- Each browser that subscribes hands us a push service endpoint and the
+-- keys to encrypt for it. Notices a user gets in Telegram are copied to
+-- each subscription as `web_push` outbox messages, for the topics their
+-- notification preferences leave on.
+
+CREATE TABLE web_push_subscriptions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id BIGINT NOT NULL REFERENCES users(telegram_id) ON DELETE CASCADE,
+    endpoint TEXT NOT NULL UNIQUE,
+    p256dh TEXT NOT NULL,
+    auth TEXT NOT NULL,
+    expires_at TIMESTAMPTZ,
+    last_pushed_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_web_push_subscriptions_user
+    ON web_push_subscriptions (user_id);
+
+COMMENT ON TABLE web_push_subscriptions IS
+    'Browsers that get a user''s notifications; dropped once the push service forgets them';
+COMMENT ON COLUMN web_push_subscriptions.endpoint IS
+    'Push service URL of one browser; a browser belongs to whoever subscribed it last';
+COMMENT ON COLUMN web_push_subscriptions.p256dh IS
+    'Browser''s P-256 public key, base64url';
+COMMENT ON COLUMN web_push_subscriptions.auth IS
+    'Browser''s 16-byte authentication secret, base64url';
+COMMENT ON COLUMN web_push_subscriptions.expires_at IS
+    'expirationTime the browser gave, if any';
+
+ALTER TABLE notification_preferences
+    DROP CONSTRAINT notification_preferences_channel_check;
+
+ALTER TABLE notification_preferences
+    ADD CONSTRAINT notification_preferences_channel_check
+    CHECK (channel IN ('chat', 'sms', 'push'));
+
+COMMENT ON TABLE notification_preferences IS
+    'Per-topic overrides of the channel defaults: chat webhooks and push on, SMS off';
+
+ALTER TABLE outbox_messages
+    DROP CONSTRAINT check_outbox_kind;
+
+ALTER TABLE outbox_messages
+    ADD CONSTRAINT check_outbox_kind CHECK (
+        kind IN (
+            'invite_notification',
+            'telegram_notification',
+            'external_email_deferred',
+            'rsvp_notification',
+            'invite_reminder',
+            'attendee_removed_notification',
+            'signup_confirmation',
+            'event_cancelled_notification',
+            'cancellation_email',
+            'event_updated',
+            'event_update_email',
+            'chat_webhook',
+            'sms',
+            'web_push'
+        )
+    );
+
+COMMENT ON CONSTRAINT check_outbox_kind ON outbox_messages IS
+    'Restricts outbox messages to Rust OutboxKind discriminators';
@@ -0,0 +1,30 @@
+-- "Book me" links.
+--
+-- A user shares part of their day under an unguessable slug. Visitors see the
+-- free slots inside that window, pick one, and it lands on the owner's
+-- calendar as a tentative event for them to confirm.
+
+CREATE TABLE booking_links (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id BIGINT NOT NULL REFERENCES users(telegram_id) ON DELETE CASCADE,
+    slug TEXT NOT NULL UNIQUE,
+    title TEXT NOT NULL,
+    duration_minutes INTEGER NOT NULL,
+    day_start TIME NOT NULL,
+    day_end TIME NOT NULL,
+    within_days INTEGER NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT check_booking_duration CHECK (duration_minutes BETWEEN 5 AND 480),
+    CONSTRAINT check_booking_hours CHECK (day_end > day_start),
+    CONSTRAINT check_booking_within CHECK (within_days BETWEEN 1 AND 31)
+);
+
+CREATE INDEX idx_booking_links_user
+    ON booking_links (user_id);
+
+COMMENT ON TABLE booking_links IS
+    'Availability shared on public booking pages (/b/{slug})';
+COMMENT ON COLUMN booking_links.day_start IS
+    'Start of the bookable part of each day, in the owner''s timezone';
+COMMENT ON COLUMN booking_links.within_days IS
+    'How many days ahead visitors can book';
@@ -0,0 +1,14 @@
+-- How much of an event people other than its owner see. New events take
+-- the owner's default; existing events and calendars stay fully visible.
+ALTER TABLE users
+    ADD COLUMN default_event_visibility TEXT NOT NULL DEFAULT 'public'
+        CHECK (default_event_visibility IN ('public', 'busy_only', 'private'));
+
+ALTER TABLE events
+    ADD COLUMN visibility TEXT NOT NULL DEFAULT 'public'
+        CHECK (visibility IN ('public', 'busy_only', 'private'));
+
+COMMENT ON COLUMN users.default_event_visibility IS
+    'Visibility of new events that do not set their own';
+COMMENT ON COLUMN events.visibility IS
+    'public: details shown; busy_only: shown to others as a "Busy" block; private: hidden from others';
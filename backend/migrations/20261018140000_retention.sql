@@ -0,0 +1,27 @@
+-- The retention task deletes tombstones older than the configured period.
+-- Sync tokens at or below the highest pruned sync_version can no longer be
+-- answered with their deletions, so clients presenting one must start over.
+ALTER TABLE users
+    ADD COLUMN tombstones_pruned_through BIGINT NOT NULL DEFAULT 0;
+
+COMMENT ON COLUMN users.tombstones_pruned_through IS
+    'Highest sync_version of a pruned tombstone; older sync tokens are refused';
+
+CREATE INDEX idx_event_tombstones_deleted_at
+    ON event_tombstones(deleted_at);
+
+CREATE INDEX idx_outbox_finished
+    ON outbox_messages(updated_at)
+    WHERE status IN ('completed', 'failed');
+
+CREATE INDEX idx_email_sends_sent_at
+    ON email_sends(sent_at);
+
+CREATE INDEX idx_email_feedback_received_at
+    ON email_feedback(received_at);
+
+CREATE INDEX idx_device_activity_created_at
+    ON device_activity(created_at);
+
+CREATE INDEX idx_slow_query_plans_captured_at
+    ON slow_query_plans(captured_at);
@@ -0,0 +1,32 @@
+-- Sensitive columns (Google OAuth tokens, chat webhook URLs, phone numbers)
+-- may now hold values sealed with the deployment's column key. Sealing is
+-- randomized, so the columns that are looked up or kept unique get a keyed
+-- digest alongside; it is NULL while encryption is off, when the clear
+-- value itself is compared.
+ALTER TABLE chat_webhooks
+    ADD COLUMN url_digest TEXT;
+
+CREATE UNIQUE INDEX idx_chat_webhooks_user_url_digest
+    ON chat_webhooks (user_id, url_digest)
+    WHERE url_digest IS NOT NULL;
+
+ALTER TABLE phone_numbers
+    ADD COLUMN phone_number_digest TEXT;
+
+-- A number belongs to whoever confirmed it first
+CREATE UNIQUE INDEX idx_phone_numbers_verified_digest
+    ON phone_numbers (phone_number_digest)
+    WHERE verified_at IS NOT NULL AND phone_number_digest IS NOT NULL;
+
+COMMENT ON COLUMN chat_webhooks.url IS
+    'Secret webhook URL, sealed when column encryption is on; never returned in full by the API';
+COMMENT ON COLUMN chat_webhooks.url_digest IS
+    'Keyed digest of the URL while column encryption is on, for duplicate checks';
+COMMENT ON COLUMN phone_numbers.phone_number IS
+    'E.164 number, e.g. +442079460958; sealed when column encryption is on';
+COMMENT ON COLUMN phone_numbers.phone_number_digest IS
+    'Keyed digest of the number while column encryption is on, for uniqueness checks';
+COMMENT ON COLUMN google_calendar_connections.access_token IS
+    'OAuth access token, sealed when column encryption is on';
+COMMENT ON COLUMN google_calendar_connections.refresh_token IS
+    'OAuth refresh token, sealed when column encryption is on';
@@ -0,0 +1,12 @@
+-- Anonymous product metrics: one running total per metric and UTC day.
+-- Metric names are fixed strings such as bot.command.today; no user, chat
+-- or event is ever recorded alongside them.
+CREATE TABLE analytics_counters (
+    metric TEXT NOT NULL,
+    day DATE NOT NULL,
+    count BIGINT NOT NULL DEFAULT 0,
+    PRIMARY KEY (metric, day)
+);
+
+COMMENT ON TABLE analytics_counters IS
+    'Daily totals of anonymous product metrics; off with ANALYTICS_SINK=none';
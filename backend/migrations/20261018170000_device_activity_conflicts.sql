@@ -0,0 +1,17 @@
+-- Edits a device made to an out-of-date copy of an event.
+--
+-- A CalDAV PUT whose SEQUENCE is behind the stored event is turned away with
+-- 409 instead of overwriting newer changes. The request's row in the device
+-- activity log records which event it was and how far behind the device was.
+
+ALTER TABLE device_activity
+    ADD COLUMN conflict_uid TEXT,
+    ADD COLUMN client_sequence INTEGER,
+    ADD COLUMN server_sequence INTEGER;
+
+COMMENT ON COLUMN device_activity.conflict_uid IS
+    'UID of the event a stale PUT was rejected for';
+COMMENT ON COLUMN device_activity.client_sequence IS
+    'SEQUENCE the device sent in the rejected PUT';
+COMMENT ON COLUMN device_activity.server_sequence IS
+    'Stored SEQUENCE of the event when the PUT was rejected';
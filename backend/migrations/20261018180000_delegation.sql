@@ -0,0 +1,39 @@
+-- Calendar delegation and event revision history.
+--
+-- An owner lets another user manage their calendar: the delegate can create,
+-- edit and delete the owner's events. Every event write is recorded with the
+-- user who made it, so changes made by a delegate stay attributed to them.
+
+CREATE TABLE calendar_delegates (
+    owner_id BIGINT NOT NULL REFERENCES users(telegram_id) ON DELETE CASCADE,
+    delegate_id BIGINT NOT NULL REFERENCES users(telegram_id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (owner_id, delegate_id),
+    CONSTRAINT check_delegate_not_owner CHECK (owner_id <> delegate_id)
+);
+
+CREATE INDEX idx_calendar_delegates_delegate
+    ON calendar_delegates (delegate_id);
+
+COMMENT ON TABLE calendar_delegates IS
+    'Users allowed to write to another user''s calendar';
+
+CREATE TABLE event_revisions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    event_id UUID NOT NULL,
+    user_id BIGINT NOT NULL REFERENCES users(telegram_id) ON DELETE CASCADE,
+    actor_id BIGINT NOT NULL,
+    action TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT check_revision_action CHECK (action IN ('created', 'updated', 'deleted'))
+);
+
+CREATE INDEX idx_event_revisions_event
+    ON event_revisions (event_id, created_at);
+
+COMMENT ON TABLE event_revisions IS
+    'Who wrote each revision of an event; rows outlive the event so deletions stay attributed';
+COMMENT ON COLUMN event_revisions.user_id IS
+    'Owner of the calendar the event is on';
+COMMENT ON COLUMN event_revisions.actor_id IS
+    'User who made the change: the owner, or a delegate acting for them';
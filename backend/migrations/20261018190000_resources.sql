@@ -0,0 +1,75 @@
+-- Bookable resources such as meeting rooms.
+--
+-- A resource is a calendar of its own that nobody writes to directly: an
+-- event on a user's calendar books it for the event's time. The exclusion
+-- constraint keeps two bookings of the same resource from overlapping, however
+-- the events are created or moved.
+
+CREATE EXTENSION IF NOT EXISTS btree_gist;
+
+CREATE TABLE resources (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name TEXT NOT NULL,
+    sync_token BIGINT NOT NULL DEFAULT 1,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE UNIQUE INDEX idx_resources_name
+    ON resources (lower(name));
+
+COMMENT ON TABLE resources IS
+    'Bookable resources (meeting rooms) shared by all users';
+COMMENT ON COLUMN resources.sync_token IS
+    'Advanced by a trigger on every change to the resource''s bookings';
+
+CREATE TABLE resource_bookings (
+    event_id UUID PRIMARY KEY REFERENCES events(id) ON DELETE CASCADE,
+    resource_id UUID NOT NULL REFERENCES resources(id) ON DELETE CASCADE,
+    during TSTZRANGE NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT resource_bookings_no_overlap
+        EXCLUDE USING gist (resource_id WITH =, during WITH &&)
+);
+
+COMMENT ON TABLE resource_bookings IS
+    'The resource an event books and the time it holds it';
+COMMENT ON COLUMN resource_bookings.during IS
+    'The event''s time; all-day and floating events are read in the owner''s timezone';
+
+-- Time an event blocks, as a range; all-day dates and floating times are
+-- taken in `timezone`, the owner's
+CREATE OR REPLACE FUNCTION event_busy_range(event_row events, timezone TEXT)
+RETURNS TSTZRANGE AS $$
+    SELECT CASE
+        WHEN event_row.is_all_day THEN tstzrange(
+            event_row.start_date::timestamp AT TIME ZONE timezone,
+            event_row.end_date::timestamp AT TIME ZONE timezone
+        )
+        WHEN event_row.is_floating THEN tstzrange(
+            (event_row.start AT TIME ZONE 'UTC') AT TIME ZONE timezone,
+            (event_row."end" AT TIME ZONE 'UTC') AT TIME ZONE timezone
+        )
+        ELSE tstzrange(event_row.start, event_row."end")
+    END
+$$ LANGUAGE sql STABLE;
+
+CREATE OR REPLACE FUNCTION advance_resource_sync_token()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP <> 'INSERT' THEN
+        UPDATE resources SET sync_token = sync_token + 1 WHERE id = OLD.resource_id;
+    END IF;
+    IF TG_OP <> 'DELETE' AND (TG_OP = 'INSERT' OR NEW.resource_id <> OLD.resource_id) THEN
+        UPDATE resources SET sync_token = sync_token + 1 WHERE id = NEW.resource_id;
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+COMMENT ON FUNCTION advance_resource_sync_token() IS
+    'Trigger function advancing resources.sync_token when a booking of the resource changes';
+
+CREATE TRIGGER resource_bookings_advance_sync_token
+    AFTER INSERT OR UPDATE OR DELETE ON resource_bookings
+    FOR EACH ROW
+    EXECUTE FUNCTION advance_resource_sync_token();
@@ -0,0 +1,26 @@
+-- #hashtags in event titles, kept lowercased next to the title so events can
+-- be filtered by them. The title stays the source: every write re-derives
+-- the tags from it.
+ALTER TABLE events
+    ADD COLUMN tags TEXT[] NOT NULL DEFAULT '{}';
+
+-- Existing titles, read the way the application reads them: a `#` at the
+-- start or after whitespace, then letters, digits, `_` or `-`
+UPDATE events
+SET tags = ARRAY(
+    SELECT tag
+    FROM (
+        SELECT lower(match[1]) AS tag, MIN(position) AS first_position
+        FROM regexp_matches(summary, '(?:^|\s)#([[:alnum:]_-]+)', 'g')
+            WITH ORDINALITY AS matches(match, position)
+        WHERE char_length(match[1]) <= 64
+        GROUP BY lower(match[1])
+    ) AS found
+    ORDER BY first_position
+)
+WHERE summary LIKE '%#%';
+
+CREATE INDEX idx_events_tags ON events USING GIN (tags);
+
+COMMENT ON COLUMN events.tags IS
+    'Lowercased #hashtags of the summary, without the #, in order of appearance';
@@ -0,0 +1,55 @@
+-- Unscheduled tasks with a duration, which /plan turns into calendar blocks.
+--
+-- Planning a task puts a tentative event on the calendar and links it here;
+-- confirming the event (from the bot or any client) confirms the block.
+-- Deleting the event unlinks the task so the next plan picks it up again.
+CREATE TABLE tasks (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id BIGINT NOT NULL REFERENCES users(telegram_id) ON DELETE CASCADE,
+    title TEXT NOT NULL,
+    duration_minutes INTEGER NOT NULL CHECK (duration_minutes > 0),
+    event_id UUID REFERENCES events(id) ON DELETE SET NULL,
+    completed_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_tasks_user_open
+    ON tasks (user_id, created_at)
+    WHERE completed_at IS NULL;
+
+CREATE UNIQUE INDEX idx_tasks_event
+    ON tasks (event_id)
+    WHERE event_id IS NOT NULL;
+
+COMMENT ON TABLE tasks IS
+    'To-dos with a duration that can be planned into free time as events';
+COMMENT ON COLUMN tasks.event_id IS
+    'The block planned for the task; tentative until the user confirms it';
+
+-- At the end of a confirmed block the user is asked whether the task is done
+ALTER TABLE outbox_messages
+    DROP CONSTRAINT check_outbox_kind;
+
+ALTER TABLE outbox_messages
+    ADD CONSTRAINT check_outbox_kind CHECK (
+        kind IN (
+            'invite_notification',
+            'telegram_notification',
+            'external_email_deferred',
+            'rsvp_notification',
+            'invite_reminder',
+            'attendee_removed_notification',
+            'signup_confirmation',
+            'event_cancelled_notification',
+            'cancellation_email',
+            'event_updated',
+            'event_update_email',
+            'chat_webhook',
+            'sms',
+            'web_push',
+            'task_block_reminder'
+        )
+    );
+
+COMMENT ON CONSTRAINT check_outbox_kind ON outbox_messages IS
+    'Restricts outbox messages to Rust OutboxKind discriminators';
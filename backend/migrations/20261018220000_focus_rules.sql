@@ -0,0 +1,30 @@
+-- Focus-time rules, e.g. "reserve 2x2h deep-work blocks per week".
+--
+-- A worker task keeps each rule's blocks for the current week on the
+-- calendar as tentative events, moving them when something else takes
+-- their time. Confirmed blocks are left where the user put them.
+CREATE TABLE focus_rules (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id BIGINT NOT NULL REFERENCES users(telegram_id) ON DELETE CASCADE,
+    title TEXT NOT NULL,
+    blocks_per_week INTEGER NOT NULL CHECK (blocks_per_week > 0),
+    duration_minutes INTEGER NOT NULL CHECK (duration_minutes > 0),
+    -- Local HH:MM-HH:MM window blocks must fit in, NULL for any time
+    working_hours TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_focus_rules_user ON focus_rules (user_id, created_at);
+
+-- The events reserved for each rule
+CREATE TABLE focus_blocks (
+    event_id UUID PRIMARY KEY REFERENCES events(id) ON DELETE CASCADE,
+    rule_id UUID NOT NULL REFERENCES focus_rules(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_focus_blocks_rule ON focus_blocks (rule_id);
+
+COMMENT ON TABLE focus_rules IS
+    'Weekly focus time to keep free, scheduled as tentative events';
+COMMENT ON TABLE focus_blocks IS
+    'Events a focus rule reserved; deleting the event forgets the block';
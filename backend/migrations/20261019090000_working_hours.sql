@@ -0,0 +1,7 @@
+-- The user's working week, e.g. 'Mon-Fri 09:00-17:00', read in their
+-- timezone. Slot suggestions, focus time and the birthday digest keep to it.
+ALTER TABLE users
+    ADD COLUMN working_hours TEXT;
+
+COMMENT ON COLUMN users.working_hours IS
+    'Working window of each weekday, e.g. Mon-Thu 09:00-17:00, Fri 09:00-13:00; NULL when not set';
@@ -0,0 +1,7 @@
+-- A note the organizer adds to an event's reminders, e.g. "bring laptop!".
+-- It travels with the reminder cards and as the alarm text in iCalendar.
+ALTER TABLE events
+    ADD COLUMN reminder_note TEXT;
+
+COMMENT ON COLUMN events.reminder_note IS
+    'Organizer note appended to reminders and exported as VALARM DESCRIPTION; NULL for none';
@@ -0,0 +1,70 @@
+-- Verified email addresses.
+--
+-- Invites to a user's email address reach them on Telegram whether or not
+-- they confirmed it, but Televent emails the address nothing else until its
+-- owner typed back the code emailed to it, or opened the link carrying the
+-- code. Confirmed addresses can then get copies of the user's notices as
+-- `user_email` outbox messages, for the topics their notification
+-- preferences turn on.
+
+ALTER TABLE users
+    ADD COLUMN email_verified_at TIMESTAMPTZ;
+
+COMMENT ON COLUMN users.email_verified_at IS
+    'When the owner confirmed email; cleared whenever the address changes';
+
+CREATE TABLE email_verifications (
+    user_id BIGINT PRIMARY KEY REFERENCES users(telegram_id) ON DELETE CASCADE,
+    email TEXT NOT NULL,
+    code_hash TEXT,
+    code_expires_at TIMESTAMPTZ,
+    code_attempts INTEGER NOT NULL DEFAULT 0,
+    requested_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+COMMENT ON TABLE email_verifications IS
+    'Pending confirmation of users.email; dropped once confirmed';
+COMMENT ON COLUMN email_verifications.email IS
+    'Address the code was asked for; a code for an address since replaced is void';
+COMMENT ON COLUMN email_verifications.code_hash IS
+    'SHA-256 hex digest of the emailed code; NULL until the email is sent';
+COMMENT ON COLUMN email_verifications.requested_at IS
+    'When the user last asked for a code, for throttling resends';
+
+ALTER TABLE notification_preferences
+    DROP CONSTRAINT notification_preferences_channel_check;
+
+ALTER TABLE notification_preferences
+    ADD CONSTRAINT notification_preferences_channel_check
+    CHECK (channel IN ('chat', 'sms', 'push', 'email'));
+
+COMMENT ON TABLE notification_preferences IS
+    'Per-topic overrides of the channel defaults: chat webhooks and push on, SMS and email off';
+
+ALTER TABLE outbox_messages
+    DROP CONSTRAINT check_outbox_kind;
+
+ALTER TABLE outbox_messages
+    ADD CONSTRAINT check_outbox_kind CHECK (
+        kind IN (
+            'invite_notification',
+            'telegram_notification',
+            'external_email_deferred',
+            'rsvp_notification',
+            'invite_reminder',
+            'attendee_removed_notification',
+            'signup_confirmation',
+            'event_cancelled_notification',
+            'cancellation_email',
+            'event_updated',
+            'event_update_email',
+            'chat_webhook',
+            'sms',
+            'web_push',
+            'task_block_reminder',
+            'user_email'
+        )
+    );
+
+COMMENT ON CONSTRAINT check_outbox_kind ON outbox_messages IS
+    'Restricts outbox messages to Rust OutboxKind discriminators';
@@ -0,0 +1,14 @@
+-- iCalendar SEQUENCE of each event, kept apart from the row version.
+--
+-- SEQUENCE used to be exported from events.version, which the server bumps on
+-- every write. A device's copy was then behind as soon as its own PUT was
+-- accepted, so its next edit looked stale. The column holds the highest
+-- SEQUENCE a client sent, or one more after an edit through the API.
+ALTER TABLE events
+    ADD COLUMN sequence INTEGER NOT NULL DEFAULT 0;
+
+-- Clients have cached the version as SEQUENCE; start from it
+UPDATE events SET sequence = version;
+
+COMMENT ON COLUMN events.sequence IS
+    'iCalendar SEQUENCE: highest value a CalDAV client sent, bumped by API edits; compared with incoming SEQUENCE to spot stale writes';
@@ -1,11 +1,29 @@
 use anyhow::{Context, Result};
 use std::env;
+use televent_storage::security::{COLUMN_KEY_LEN, ColumnCipher, parse_column_key};
 
 #[derive(Debug, Clone)]
 pub struct UnifiedConfig {
     pub runtime: RuntimeConfig,
     pub api: ApiConfig,
     pub worker: WorkerConfig,
+    /// Commands and menu button registered when the bot starts
+    pub bot: bot::setup::BotSetupConfig,
+    /// `None` while external email delivery is disabled
+    pub email: Option<worker::EmailConfig>,
+    /// `None` while text messages are disabled
+    pub sms: Option<worker::SmsConfig>,
+    /// `None` while voice notes are ignored
+    pub transcription: Option<bot::voice::TranscriptionConfig>,
+    /// `None` while photos are ignored
+    pub ocr: Option<bot::ocr::OcrConfig>,
+    /// `None` while Web Push is disabled
+    pub push: Option<worker::VapidConfig>,
+    /// `None` leaves forecasts out of reminders
+    pub weather: Option<worker::WeatherConfig>,
+    /// `None` leaves the Google Calendar connector disabled
+    #[cfg(feature = "google-calendar")]
+    pub google: Option<televent_google::GoogleConfig>,
 }
 
 #[derive(Debug, Clone)]
@@ -13,15 +31,52 @@ pub struct RuntimeConfig {
     pub database_url: String,
     pub telegram_bot_token: String,
     pub db_max_connections: u32,
+    /// Calendar reads slower than this are logged with their query plan
+    pub slow_query_threshold: Option<std::time::Duration>,
+    /// Longest event summary, description and location accepted by the API
+    /// and bot; longer imported text is truncated
+    pub event_text_limits: televent_application::EventTextLimits,
+    /// Start in read-only maintenance mode regardless of the `read_only` flag
+    pub read_only: bool,
+    /// Apply pending migrations at startup rather than refusing to start
+    pub auto_migrate: bool,
+    /// Seals OAuth tokens, chat webhook URLs and phone numbers; disabled
+    /// unless a column key is configured
+    pub column_cipher: ColumnCipher,
+    /// Argon2id cost of device password hashes
+    pub password_hash_params: televent_application::PasswordHashParams,
+    /// Where anonymous product metrics go
+    pub analytics: AnalyticsConfig,
+}
+
+/// Sink of the anonymous product metrics counted by the bot and worker
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AnalyticsConfig {
+    /// Collect nothing
+    Disabled,
+    /// Daily totals in the `analytics_counters` table
+    Postgres,
+    /// Counters sent to a StatsD daemon at `addr`
+    Statsd { addr: String, prefix: String },
 }
 
 #[derive(Debug, Clone)]
 pub struct ApiConfig {
     pub host: String,
     pub port: u16,
-    pub cors_allowed_origin: String,
+    pub cors: api::middleware::cors::CorsPolicy,
     pub frontend_static_dir: Option<String>,
     pub enable_swagger: bool,
+    pub email_webhook_secret: Option<String>,
+    /// Promoted to admin at startup
+    pub admin_telegram_ids: Vec<i64>,
+    pub device_budget: api::middleware::device_budget::DeviceBudget,
+    pub trigger_budget: api::middleware::device_budget::DeviceBudget,
+    pub base_path: api::middleware::base_path::BasePath,
+    pub frontend_assets: api::middleware::static_assets::FrontendAssets,
+    /// Remember successful CalDAV logins for a few minutes instead of
+    /// running Argon2 on every request
+    pub caldav_auth_cache: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -30,6 +85,9 @@ pub struct WorkerConfig {
     pub max_retry_count: i32,
     pub batch_size: i64,
     pub status_log_interval_secs: u64,
+    pub rsvp_digest_window_secs: u64,
+    pub shard: worker::OutboxShard,
+    pub retention: worker::RetentionPolicy,
 }
 
 impl UnifiedConfig {
@@ -48,13 +106,20 @@ impl UnifiedConfig {
                     .or_else(|_| env::var("PORT"))
                     .unwrap_or_else(|_| "3000".into())
                     .parse()?,
-                cors_allowed_origin: env::var("CORS_ALLOWED_ORIGIN").unwrap_or_else(|_| {
-                    env::var("PUBLIC_BASE_URL").unwrap_or_else(|_| "http://localhost:3000".into())
-                }),
+                cors: api::config::parse_cors()?,
                 frontend_static_dir: env::var("FRONTEND_STATIC_DIR")
                     .ok()
                     .or_else(|| Some("../frontend/out".into())),
                 enable_swagger: parse_env_bool("ENABLE_SWAGGER").unwrap_or(!is_production),
+                email_webhook_secret: env::var("EMAIL_WEBHOOK_SECRET").ok(),
+                admin_telegram_ids: api::config::parse_admin_telegram_ids(
+                    &env::var("ADMIN_TELEGRAM_IDS").unwrap_or_default(),
+                )?,
+                device_budget: api::config::parse_device_budget()?,
+                trigger_budget: api::config::parse_trigger_budget()?,
+                base_path: api::config::parse_base_path()?,
+                frontend_assets: api::config::parse_frontend_assets()?,
+                caldav_auth_cache: parse_env_bool("CALDAV_AUTH_CACHE").unwrap_or(true),
             },
             worker: WorkerConfig {
                 poll_interval_secs: env::var("WORKER_POLL_INTERVAL_SECS")
@@ -69,7 +134,21 @@ impl UnifiedConfig {
                 status_log_interval_secs: env::var("WORKER_STATUS_LOG_INTERVAL_SECS")
                     .unwrap_or_else(|_| "60".into())
                     .parse()?,
+                rsvp_digest_window_secs: env::var("WORKER_RSVP_DIGEST_WINDOW_SECS")
+                    .unwrap_or_else(|_| "60".into())
+                    .parse()?,
+                shard: worker::shard_from_env()?,
+                retention: worker::retention_from_env()?,
             },
+            bot: bot::setup::BotSetupConfig::from_env()?,
+            email: worker::EmailConfig::from_env()?,
+            sms: worker::SmsConfig::from_env()?,
+            transcription: bot::voice::TranscriptionConfig::from_env()?,
+            ocr: bot::ocr::OcrConfig::from_env()?,
+            push: worker::VapidConfig::from_env()?,
+            weather: worker::WeatherConfig::from_env()?,
+            #[cfg(feature = "google-calendar")]
+            google: televent_google::GoogleConfig::from_env()?,
         })
     }
 
@@ -77,9 +156,17 @@ impl UnifiedConfig {
         api::config::Config {
             host: self.api.host.clone(),
             port: self.api.port,
-            cors_allowed_origin: self.api.cors_allowed_origin.clone(),
+            cors: self.api.cors.clone(),
             frontend_static_dir: self.api.frontend_static_dir.clone(),
             enable_swagger: self.api.enable_swagger,
+            email_signups: self.email.is_some(),
+            email_webhook_secret: self.api.email_webhook_secret.clone(),
+            sms_notifications: self.sms.is_some(),
+            web_push_public_key: self.push.as_ref().map(|vapid| vapid.public_key.clone()),
+            device_budget: self.api.device_budget,
+            trigger_budget: self.api.trigger_budget,
+            base_path: self.api.base_path.clone(),
+            frontend_assets: self.api.frontend_assets.clone(),
         }
     }
 
@@ -89,6 +176,9 @@ impl UnifiedConfig {
             max_retry_count: self.worker.max_retry_count,
             batch_size: self.worker.batch_size,
             status_log_interval_secs: self.worker.status_log_interval_secs,
+            rsvp_digest_window_secs: self.worker.rsvp_digest_window_secs,
+            shard: self.worker.shard,
+            retention: self.worker.retention,
         }
     }
 }
@@ -103,10 +193,115 @@ impl RuntimeConfig {
                 .unwrap_or_else(|_| "50".to_string())
                 .parse()
                 .context("DATABASE_MAX_CONNECTIONS must be a positive integer")?,
+            slow_query_threshold: env::var("SLOW_QUERY_THRESHOLD_MS")
+                .ok()
+                .filter(|value| !value.trim().is_empty())
+                .map(|value| value.trim().parse().map(std::time::Duration::from_millis))
+                .transpose()
+                .context("SLOW_QUERY_THRESHOLD_MS must be a number of milliseconds")?,
+            event_text_limits: parse_event_text_limits()?,
+            read_only: parse_env_bool("READ_ONLY_MODE").unwrap_or(false),
+            auto_migrate: parse_env_bool("AUTO_MIGRATE").unwrap_or(true),
+            column_cipher: column_cipher_from_env()?,
+            password_hash_params: parse_password_hash_params()?,
+            analytics: analytics_from_env()?,
         })
     }
 }
 
+/// `EVENT_MAX_SUMMARY_LENGTH`, `EVENT_MAX_DESCRIPTION_LENGTH` and
+/// `EVENT_MAX_LOCATION_LENGTH` in bytes, falling back to the defaults
+fn parse_event_text_limits() -> Result<televent_application::EventTextLimits> {
+    let defaults = televent_application::EventTextLimits::default();
+    let limit = |name: &str, default: usize| -> Result<usize> {
+        match env::var(name) {
+            Ok(value) => match value.trim().parse() {
+                Ok(limit) if limit > 0 => Ok(limit),
+                _ => anyhow::bail!("{name} must be a positive number of bytes"),
+            },
+            Err(_) => Ok(default),
+        }
+    };
+    Ok(televent_application::EventTextLimits {
+        summary: limit("EVENT_MAX_SUMMARY_LENGTH", defaults.summary)?,
+        description: limit("EVENT_MAX_DESCRIPTION_LENGTH", defaults.description)?,
+        location: limit("EVENT_MAX_LOCATION_LENGTH", defaults.location)?,
+    })
+}
+
+/// `ARGON2_MEMORY_KIB`, `ARGON2_ITERATIONS` and `ARGON2_PARALLELISM`,
+/// falling back to the Argon2 defaults
+fn parse_password_hash_params() -> Result<televent_application::PasswordHashParams> {
+    let defaults = televent_application::PasswordHashParams::default();
+    let cost = |name: &str, default: u32| -> Result<u32> {
+        match env::var(name) {
+            Ok(value) => value
+                .trim()
+                .parse()
+                .with_context(|| format!("{name} must be a positive integer")),
+            Err(_) => Ok(default),
+        }
+    };
+    Ok(televent_application::PasswordHashParams::new(
+        cost("ARGON2_MEMORY_KIB", defaults.memory_kib)?,
+        cost("ARGON2_ITERATIONS", defaults.iterations)?,
+        cost("ARGON2_PARALLELISM", defaults.parallelism)?,
+    )?)
+}
+
+/// `ANALYTICS_SINK`: `postgres` (the default), `statsd` sending to
+/// `STATSD_ADDR` under `STATSD_PREFIX`, or `none` to opt out
+fn analytics_from_env() -> Result<AnalyticsConfig> {
+    match env::var("ANALYTICS_SINK")
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase()
+        .as_str()
+    {
+        "" | "postgres" => Ok(AnalyticsConfig::Postgres),
+        "none" => Ok(AnalyticsConfig::Disabled),
+        "statsd" => Ok(AnalyticsConfig::Statsd {
+            addr: env::var("STATSD_ADDR").unwrap_or_else(|_| "127.0.0.1:8125".to_string()),
+            prefix: env::var("STATSD_PREFIX")
+                .unwrap_or_else(|_| televent_application::DEFAULT_STATSD_PREFIX.to_string()),
+        }),
+        other => anyhow::bail!("ANALYTICS_SINK must be postgres, statsd or none, got {other}"),
+    }
+}
+
+/// `COLUMN_ENCRYPTION_KEY` seals new values and
+/// `COLUMN_ENCRYPTION_PREVIOUS_KEY` still opens values sealed before a
+/// rotation. Either can be read from a file instead, e.g. one a KMS agent
+/// writes, with the `_FILE` suffix.
+pub fn column_cipher_from_env() -> Result<ColumnCipher> {
+    let current = column_key_from_env("COLUMN_ENCRYPTION_KEY")?;
+    let previous = column_key_from_env("COLUMN_ENCRYPTION_PREVIOUS_KEY")?;
+    match (current, previous) {
+        (Some(current), previous) => Ok(ColumnCipher::new(&current, previous.as_ref())),
+        (None, Some(_)) => anyhow::bail!(
+            "COLUMN_ENCRYPTION_PREVIOUS_KEY needs COLUMN_ENCRYPTION_KEY to be set as well"
+        ),
+        (None, None) => Ok(ColumnCipher::default()),
+    }
+}
+
+fn column_key_from_env(name: &str) -> Result<Option<[u8; COLUMN_KEY_LEN]>> {
+    let file_var = format!("{name}_FILE");
+    let encoded = match (env::var(name), env::var(&file_var)) {
+        (Ok(_), Ok(_)) => anyhow::bail!("Set only one of {name} and {file_var}"),
+        (Ok(value), Err(_)) => value,
+        (Err(_), Ok(path)) => std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {file_var} ({path})"))?,
+        (Err(_), Err(_)) => return Ok(None),
+    };
+    if encoded.trim().is_empty() {
+        return Ok(None);
+    }
+    parse_column_key(&encoded)
+        .map(Some)
+        .map_err(|e| anyhow::anyhow!("{name}: {e}"))
+}
+
 fn parse_env_bool(name: &str) -> Option<bool> {
     env::var(name).ok().map(|value| {
         matches!(
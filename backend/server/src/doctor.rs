@@ -0,0 +1,389 @@
+//! `televent doctor`: CalDAV smoke test for a running deployment
+//!
+//! Acts like a CalDAV client with a device password: discovers the calendar,
+//! creates a throwaway event, checks that a sync-collection report returns it
+//! and deletes it again. Every step prints PASS or FAIL, so self-hosters can
+//! see where a setup breaks (proxy, TLS, auth, storage).
+
+use anyhow::{Context, Result, anyhow, bail};
+use chrono::{Duration, Utc};
+use quick_xml::Reader;
+use quick_xml::events::Event;
+use reqwest::header::{CONTENT_TYPE, ETAG, HeaderValue};
+use reqwest::{Method, StatusCode};
+use std::env;
+use std::io::Write;
+use uuid::Uuid;
+
+const USAGE: &str = "Usage: televent doctor <base-url> <login> [password]\n\n\
+    <login> is your Telegram id or username. The device password can also be\n\
+    given in TELEVENT_DEVICE_PASSWORD to keep it out of shell history.";
+
+const REQUEST_TIMEOUT_SECS: u64 = 15;
+
+const PROPFIND_BODY: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<d:propfind xmlns:d="DAV:" xmlns:cs="http://calendarserver.org/ns/">
+  <d:prop>
+    <d:resourcetype/>
+    <d:displayname/>
+    <d:current-user-principal/>
+    <d:sync-token/>
+    <cs:getctag/>
+  </d:prop>
+</d:propfind>"#;
+
+/// Run the smoke test with the arguments after `doctor`
+///
+/// Fails if the arguments are wrong or any check failed.
+pub async fn run(args: &[String]) -> Result<()> {
+    let (base_url, login, password) = match args {
+        [base_url, login, password] => (base_url, login, password.clone()),
+        [base_url, login] => (
+            base_url,
+            login,
+            env::var("TELEVENT_DEVICE_PASSWORD").map_err(|_| anyhow!(USAGE))?,
+        ),
+        _ => bail!(USAGE),
+    };
+
+    let doctor = Doctor::new(base_url, login, password)?;
+    let checks = doctor.run_checks().await;
+
+    let mut out = std::io::stdout().lock();
+    writeln!(out, "CalDAV checks for {}", doctor.collection)?;
+    for check in &checks {
+        match &check.outcome {
+            Ok(detail) => writeln!(out, "  PASS  {}: {}", check.name, detail)?,
+            Err(err) => writeln!(out, "  FAIL  {}: {:#}", check.name, err)?,
+        }
+    }
+
+    let failed = checks.iter().filter(|check| check.outcome.is_err()).count();
+    if failed > 0 {
+        bail!("{failed} of {} checks failed", checks.len());
+    }
+    writeln!(out, "All {} checks passed", checks.len())?;
+    Ok(())
+}
+
+struct Check {
+    name: &'static str,
+    outcome: Result<String>,
+}
+
+struct Doctor {
+    http: reqwest::Client,
+    /// URL of the calendar collection, with a trailing slash
+    collection: String,
+    login: String,
+    password: String,
+}
+
+impl Doctor {
+    fn new(base_url: &str, login: &str, password: String) -> Result<Self> {
+        let http = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(REQUEST_TIMEOUT_SECS))
+            .user_agent(concat!("televent-doctor/", env!("CARGO_PKG_VERSION")))
+            .build()?;
+
+        Ok(Self {
+            http,
+            collection: format!("{}/caldav/{}/", base_url.trim_end_matches('/'), login),
+            login: login.to_string(),
+            password,
+        })
+    }
+
+    /// Run the checks in order, stopping once a later step can't succeed
+    async fn run_checks(&self) -> Vec<Check> {
+        let mut checks = Vec::new();
+
+        let discovery = self.discover().await;
+        let sync_token = discovery
+            .as_ref()
+            .ok()
+            .and_then(|multistatus| multistatus.sync_token.clone());
+        let discovered = discovery.is_ok();
+        checks.push(Check {
+            name: "Discovery PROPFIND",
+            outcome: discovery.map(|multistatus| {
+                format!(
+                    "calendar {:?}, sync token {}",
+                    multistatus.display_name.unwrap_or_default(),
+                    multistatus.sync_token.as_deref().unwrap_or("missing")
+                )
+            }),
+        });
+        if !discovered {
+            return checks;
+        }
+
+        let uid = format!("televent-doctor-{}", Uuid::new_v4());
+        let created = self.put_event(&uid).await;
+        let was_created = created.is_ok();
+        checks.push(Check {
+            name: "Create test event (PUT)",
+            outcome: created,
+        });
+        if !was_created {
+            return checks;
+        }
+
+        checks.push(Check {
+            name: "Sync-collection REPORT",
+            outcome: self.sync_collection(sync_token.as_deref(), &uid).await,
+        });
+        // Always clean up, even if the event didn't show up in the report
+        checks.push(Check {
+            name: "Delete test event (DELETE)",
+            outcome: self.delete_event(&uid).await,
+        });
+
+        checks
+    }
+
+    fn request(&self, method: Method, url: &str) -> reqwest::RequestBuilder {
+        self.http
+            .request(method, url)
+            .basic_auth(&self.login, Some(&self.password))
+    }
+
+    fn event_url(&self, uid: &str) -> String {
+        format!("{}{}.ics", self.collection, uid)
+    }
+
+    async fn discover(&self) -> Result<Multistatus> {
+        let response = self
+            .request(Method::from_bytes(b"PROPFIND")?, &self.collection)
+            .header("Depth", "0")
+            .header(CONTENT_TYPE, "application/xml; charset=utf-8")
+            .body(PROPFIND_BODY)
+            .send()
+            .await
+            .context("Server unreachable")?;
+        let body = expect_status(response, &[StatusCode::MULTI_STATUS]).await?;
+
+        let multistatus = parse_multistatus(&body)?;
+        if !multistatus.is_calendar {
+            bail!("{} is not a calendar collection", self.collection);
+        }
+        Ok(multistatus)
+    }
+
+    async fn put_event(&self, uid: &str) -> Result<String> {
+        let response = self
+            .request(Method::PUT, &self.event_url(uid))
+            .header(CONTENT_TYPE, "text/calendar; charset=utf-8")
+            .header("If-None-Match", "*")
+            .body(test_event(uid))
+            .send()
+            .await?;
+        let etag = response.headers().get(ETAG).cloned();
+        expect_status(response, &[StatusCode::CREATED, StatusCode::NO_CONTENT]).await?;
+
+        match etag.as_ref().map(HeaderValue::to_str) {
+            Some(Ok(etag)) => Ok(format!("created {uid}.ics, ETag {etag}")),
+            _ => bail!("created {uid}.ics but the response has no ETag"),
+        }
+    }
+
+    async fn sync_collection(&self, sync_token: Option<&str>, uid: &str) -> Result<String> {
+        let response = self
+            .request(Method::from_bytes(b"REPORT")?, &self.collection)
+            .header("Depth", "1")
+            .header(CONTENT_TYPE, "application/xml; charset=utf-8")
+            .body(sync_collection_body(sync_token))
+            .send()
+            .await?;
+        let body = expect_status(response, &[StatusCode::MULTI_STATUS]).await?;
+
+        let multistatus = parse_multistatus(&body)?;
+        let resource = format!("{uid}.ics");
+        if !multistatus
+            .hrefs
+            .iter()
+            .any(|href| href.ends_with(&resource))
+        {
+            bail!(
+                "the test event is missing from {} changed resources",
+                multistatus.hrefs.len()
+            );
+        }
+        Ok(format!(
+            "test event reported among {} changes, new sync token {}",
+            multistatus.hrefs.len(),
+            multistatus.sync_token.as_deref().unwrap_or("missing")
+        ))
+    }
+
+    async fn delete_event(&self, uid: &str) -> Result<String> {
+        let response = self
+            .request(Method::DELETE, &self.event_url(uid))
+            .send()
+            .await?;
+        expect_status(response, &[StatusCode::OK, StatusCode::NO_CONTENT]).await?;
+        Ok(format!("deleted {uid}.ics"))
+    }
+}
+
+/// Read the body, failing with the status and a preview of the body if the
+/// status isn't one of `expected`
+async fn expect_status(response: reqwest::Response, expected: &[StatusCode]) -> Result<String> {
+    let status = response.status();
+    let body = response.text().await.unwrap_or_default();
+    if expected.contains(&status) {
+        return Ok(body);
+    }
+
+    let hint = match status {
+        StatusCode::UNAUTHORIZED => " (check the login and device password)",
+        StatusCode::FORBIDDEN => " (the login doesn't match the device password's owner)",
+        StatusCode::NOT_FOUND => " (is the base URL right and /caldav routed to Televent?)",
+        StatusCode::TOO_MANY_REQUESTS => " (rate limited, wait a minute and retry)",
+        _ => "",
+    };
+    let preview: String = body.chars().take(200).collect();
+    bail!("HTTP {status}{hint}: {}", preview.trim())
+}
+
+fn test_event(uid: &str) -> String {
+    let start = Utc::now() + Duration::days(1);
+    let end = start + Duration::minutes(30);
+    let format = "%Y%m%dT%H%M%SZ";
+    format!(
+        "BEGIN:VCALENDAR\r\n\
+         VERSION:2.0\r\n\
+         PRODID:-//Televent//Doctor//EN\r\n\
+         BEGIN:VEVENT\r\n\
+         UID:{uid}\r\n\
+         DTSTAMP:{}\r\n\
+         DTSTART:{}\r\n\
+         DTEND:{}\r\n\
+         SUMMARY:Televent doctor test event\r\n\
+         END:VEVENT\r\n\
+         END:VCALENDAR\r\n",
+        Utc::now().format(format),
+        start.format(format),
+        end.format(format),
+    )
+}
+
+fn sync_collection_body(sync_token: Option<&str>) -> String {
+    let sync_token = match sync_token {
+        Some(token) => format!("<d:sync-token>{}</d:sync-token>", escape_xml(token)),
+        None => "<d:sync-token/>".to_string(),
+    };
+    format!(
+        r#"<?xml version="1.0" encoding="utf-8"?>
+<d:sync-collection xmlns:d="DAV:">
+  {sync_token}
+  <d:sync-level>1</d:sync-level>
+  <d:prop>
+    <d:getetag/>
+  </d:prop>
+</d:sync-collection>"#
+    )
+}
+
+fn escape_xml(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// The parts of a multistatus response the checks look at
+#[derive(Debug, Default)]
+struct Multistatus {
+    is_calendar: bool,
+    display_name: Option<String>,
+    sync_token: Option<String>,
+    /// Hrefs of the `<response>` elements
+    hrefs: Vec<String>,
+}
+
+fn parse_multistatus(xml: &str) -> Result<Multistatus> {
+    let mut reader = Reader::from_str(xml);
+    reader.config_mut().trim_text(true);
+
+    let mut multistatus = Multistatus::default();
+    let mut path: Vec<String> = Vec::new();
+    loop {
+        match reader.read_event().context("Server returned invalid XML")? {
+            Event::Start(e) => {
+                path.push(String::from_utf8_lossy(e.local_name().as_ref()).into_owned());
+            }
+            Event::Empty(e) => {
+                let in_resourcetype = path.last().is_some_and(|name| name == "resourcetype");
+                if in_resourcetype && e.local_name().as_ref() == b"calendar" {
+                    multistatus.is_calendar = true;
+                }
+            }
+            Event::Text(e) => {
+                let text = String::from_utf8_lossy(e.as_ref()).into_owned();
+                let parent = path.len().checked_sub(2).map(|idx| path[idx].as_str());
+                match (parent, path.last().map(String::as_str)) {
+                    (Some("response"), Some("href")) => multistatus.hrefs.push(text),
+                    (_, Some("displayname")) => multistatus.display_name = Some(text),
+                    (_, Some("sync-token")) => multistatus.sync_token = Some(text),
+                    _ => {}
+                }
+            }
+            Event::End(_) => {
+                path.pop();
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(multistatus)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_propfind_multistatus() {
+        let xml = r#"<?xml version="1.0" encoding="utf-8"?>
+<d:multistatus xmlns:d="DAV:" xmlns:cal="urn:ietf:params:xml:ns:caldav">
+  <d:response>
+    <d:href>/caldav/123/</d:href>
+    <d:propstat>
+      <d:prop>
+        <d:resourcetype><d:collection/><cal:calendar/></d:resourcetype>
+        <d:displayname>Televent</d:displayname>
+        <d:sync-token>42</d:sync-token>
+      </d:prop>
+      <d:status>HTTP/1.1 200 OK</d:status>
+    </d:propstat>
+  </d:response>
+</d:multistatus>"#;
+
+        let multistatus = parse_multistatus(xml).unwrap();
+        assert!(multistatus.is_calendar);
+        assert_eq!(multistatus.display_name.as_deref(), Some("Televent"));
+        assert_eq!(multistatus.sync_token.as_deref(), Some("42"));
+        assert_eq!(multistatus.hrefs, vec!["/caldav/123/".to_string()]);
+    }
+
+    #[test]
+    fn test_parse_sync_collection_multistatus() {
+        let xml = r#"<d:multistatus xmlns:d="DAV:">
+  <d:response><d:href>/caldav/123/a.ics</d:href><d:status>HTTP/1.1 404 Not Found</d:status></d:response>
+  <d:response><d:href>/caldav/123/b.ics</d:href></d:response>
+  <d:sync-token>43</d:sync-token>
+</d:multistatus>"#;
+
+        let multistatus = parse_multistatus(xml).unwrap();
+        assert!(!multistatus.is_calendar);
+        assert_eq!(multistatus.sync_token.as_deref(), Some("43"));
+        assert_eq!(multistatus.hrefs.len(), 2);
+    }
+
+    #[test]
+    fn test_sync_collection_body_escapes_token() {
+        assert!(sync_collection_body(None).contains("<d:sync-token/>"));
+        assert!(sync_collection_body(Some("a&b")).contains("<d:sync-token>a&amp;b</d:sync-token>"));
+    }
+}
@@ -1,16 +1,40 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use sqlx::PgPool;
+use televent_application::{DomainEventBus, FeatureFlagService};
+use televent_shared::bootstrap::{MigrationPolicy, run_migrations};
+use televent_storage::diagnostics::SlowQueryLog;
 use tokio::signal;
 use tokio_util::sync::CancellationToken;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 mod config;
+mod doctor;
+mod replay;
+mod systemd;
+
+/// How long in-flight API requests may run after a shutdown signal
+const API_DRAIN_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(20);
 
 #[tokio::main]
 async fn main() -> Result<()> {
     // Load .env
     dotenvy::dotenv().ok();
 
+    // `televent doctor ...` checks a deployment, `televent systemd` prints
+    // unit files, `televent migrate` applies migrations, `televent reseal`
+    // re-encrypts sensitive columns under the current key and `televent
+    // outbox-replay ...` requeues notifications after a restore, instead of
+    // serving
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.split_first().map(|(cmd, rest)| (cmd.as_str(), rest)) {
+        Some(("doctor", doctor_args)) => return doctor::run(doctor_args).await,
+        Some(("systemd", systemd_args)) => return systemd::run(systemd_args),
+        Some(("migrate", _)) => return migrate().await,
+        Some(("reseal", _)) => return reseal().await,
+        Some(("outbox-replay", replay_args)) => return outbox_replay(replay_args).await,
+        _ => {}
+    }
+
     // Initialize tracing once for entire process
     // The guard must be kept alive for the duration of the program to ensure logs are flushed
     let _guard = init_tracing()?;
@@ -34,16 +58,51 @@ async fn main() -> Result<()> {
         config.runtime.db_max_connections
     );
 
-    // Run migrations ONCE
-    sqlx::migrate!("../migrations").run(&pool).await?;
-    tracing::info!("✓ Migrations completed");
+    // Under an advisory lock, so separately started processes don't race
+    let policy = if config.runtime.auto_migrate {
+        MigrationPolicy::Apply
+    } else {
+        MigrationPolicy::RequireCurrent
+    };
+    run_migrations(&pool, policy).await?;
+
+    let promoted = televent_application::CalendarService::new(
+        televent_storage::calendar::CalendarRepository::new(pool.clone()),
+    )
+    .bootstrap_admins(&config.api.admin_telegram_ids)
+    .await?;
+    if promoted > 0 {
+        tracing::info!("✓ Promoted {} bootstrap admin(s)", promoted);
+    }
 
     // Create shutdown coordination
     let shutdown = CancellationToken::new();
 
+    // One domain event bus for the whole process so writes from any service
+    // reach subscribers in the others (e.g. bot revocations clear API caches)
+    let events = DomainEventBus::new();
+
+    // Shared so flag changes made through the API reach the bot's cache too
+    let feature_flags = FeatureFlagService::new(
+        televent_storage::flags::FeatureFlagRepository::new(pool.clone()),
+    )
+    .with_forced_read_only(config.runtime.read_only);
+
     // Spawn all services
-    let mut api_handle = spawn_api(pool.clone(), config.clone(), shutdown.clone());
-    let mut bot_handle = spawn_bot(pool.clone(), config.clone(), shutdown.clone());
+    let mut api_handle = spawn_api(
+        pool.clone(),
+        config.clone(),
+        events.clone(),
+        feature_flags.clone(),
+        shutdown.clone(),
+    );
+    let mut bot_handle = spawn_bot(
+        pool.clone(),
+        config.clone(),
+        events,
+        feature_flags,
+        shutdown.clone(),
+    );
     let mut worker_handle = spawn_worker(pool.clone(), config.clone(), shutdown.clone());
 
     tracing::info!("✓ All services started");
@@ -91,38 +150,96 @@ fn service_exit_error(
 fn spawn_api(
     pool: PgPool,
     config: config::UnifiedConfig,
+    events: DomainEventBus,
+    feature_flags: FeatureFlagService,
     shutdown: CancellationToken,
 ) -> tokio::task::JoinHandle<Result<()>> {
     tokio::spawn(async move {
-        let auth_cache = moka::future::Cache::builder()
-            .time_to_live(std::time::Duration::from_secs(300))
-            .max_capacity(10000)
-            .build();
+        let slow_queries = slow_query_log(&pool, &config);
+        let auth_cache = if config.api.caldav_auth_cache {
+            api::middleware::caldav_auth::AuthCache::new(std::time::Duration::from_secs(300), 10000)
+        } else {
+            api::middleware::caldav_auth::AuthCache::disabled()
+        };
 
         let state = api::AppState {
             calendar_service: televent_application::CalendarService::new(
-                televent_storage::calendar::CalendarRepository::new(pool.clone()),
+                televent_storage::calendar::CalendarRepository::new(pool.clone())
+                    .with_slow_query_log(slow_queries.clone()),
             ),
+            event_service: televent_application::EventService::new(
+                televent_storage::calendar::CalendarRepository::new(pool.clone())
+                    .with_slow_query_log(slow_queries.clone()),
+            )
+            .with_event_bus(events.clone())
+            .with_text_limits(config.runtime.event_text_limits),
             device_service: televent_application::DeviceService::new(
                 televent_storage::device::DeviceRepository::new(pool.clone()),
-            ),
+            )
+            .with_event_bus(events)
+            .with_hash_params(config.runtime.password_hash_params),
             health_service: televent_application::HealthService::new(
                 televent_storage::health::HealthRepository::new(pool.clone()),
             ),
+            subscription_service: televent_application::SubscriptionService::new(
+                televent_storage::subscription::SubscriptionRepository::new(pool.clone()),
+            )
+            .with_text_limits(config.runtime.event_text_limits),
+            contact_service: televent_application::ContactService::new(
+                televent_storage::contact::ContactRepository::new(pool.clone()),
+            ),
+            email_service: televent_application::EmailService::new(
+                televent_storage::email::EmailRepository::new(pool.clone()),
+            ),
+            feature_flags,
+            chat_webhook_service: televent_application::ChatWebhookService::new(
+                televent_storage::chat_webhook::ChatWebhookRepository::new(pool.clone())
+                    .with_cipher(config.runtime.column_cipher.clone()),
+            ),
+            notification_service: televent_application::NotificationService::new(
+                televent_storage::notification::NotificationRepository::new(pool.clone())
+                    .with_cipher(config.runtime.column_cipher.clone()),
+            ),
+            web_push_service: televent_application::WebPushService::new(
+                televent_storage::web_push::WebPushRepository::new(pool.clone()),
+            ),
             auth_cache,
             telegram_bot_token: config.runtime.telegram_bot_token.clone(),
         };
         let api_config = config.to_api_config();
+        let drain = shutdown.clone().cancelled_owned();
+        #[cfg(feature = "google-calendar")]
+        let api = async {
+            match &config.google {
+                Some(google) => {
+                    let google = api::GoogleState {
+                        connector: televent_google::GoogleConnector::new(google.clone())
+                            .map_err(std::io::Error::other)?,
+                        sync: google_sync_service(&pool, &config),
+                    };
+                    api::run_api_with_google(state, &api_config, google, drain).await
+                }
+                None => api::run_api(state, &api_config, drain).await,
+            }
+        };
+        #[cfg(not(feature = "google-calendar"))]
+        let api = api::run_api(state, &api_config, drain);
+        tokio::pin!(api);
 
         tokio::select! {
-            result = api::run_api(state, &api_config) => {
-                tracing::error!("API service exited: {:?}", result);
-                result.map_err(|e| anyhow::anyhow!(e))
-            }
+            biased;
             _ = shutdown.cancelled() => {
-                tracing::info!("API service shutting down");
+                tracing::info!("API service draining connections");
+                if tokio::time::timeout(API_DRAIN_TIMEOUT, api).await.is_err() {
+                    tracing::warn!("API drain timed out, dropping open connections");
+                }
+                tracing::info!("API service shut down");
                 Ok(())
             }
+            result = &mut api => {
+                tracing::error!("API service exited: {:?}", result);
+                result.map_err(|e| anyhow::anyhow!(e))
+            }
         }
     })
 }
@@ -130,21 +247,56 @@ fn spawn_api(
 fn spawn_bot(
     pool: PgPool,
     config: config::UnifiedConfig,
+    events: DomainEventBus,
+    feature_flags: FeatureFlagService,
     shutdown: CancellationToken,
 ) -> tokio::task::JoinHandle<Result<()>> {
     tokio::spawn(async move {
+        let slow_queries = slow_query_log(&pool, &config);
         let bot_token = config.runtime.telegram_bot_token.clone();
         let bot_db = bot::db::BotDb::new(
             televent_application::CalendarService::new(
-                televent_storage::calendar::CalendarRepository::new(pool.clone()),
+                televent_storage::calendar::CalendarRepository::new(pool.clone())
+                    .with_slow_query_log(slow_queries.clone()),
             ),
+            televent_application::EventService::new(
+                televent_storage::calendar::CalendarRepository::new(pool.clone())
+                    .with_slow_query_log(slow_queries.clone()),
+            )
+            .with_event_bus(events.clone())
+            .with_text_limits(config.runtime.event_text_limits),
             televent_application::DeviceService::new(
                 televent_storage::device::DeviceRepository::new(pool.clone()),
+            )
+            .with_event_bus(events)
+            .with_hash_params(config.runtime.password_hash_params),
+            televent_application::SubscriptionService::new(
+                televent_storage::subscription::SubscriptionRepository::new(pool.clone()),
+            )
+            .with_text_limits(config.runtime.event_text_limits),
+            televent_application::ContactService::new(
+                televent_storage::contact::ContactRepository::new(pool.clone()),
             ),
-        );
+            feature_flags,
+        )
+        .with_analytics(analytics(&pool, &config)?);
+        let bot_db = if config.email.is_some() {
+            bot_db.with_email_verification(televent_application::NotificationService::new(
+                televent_storage::notification::NotificationRepository::new(pool.clone())
+                    .with_cipher(config.runtime.column_cipher.clone()),
+            ))
+        } else {
+            bot_db
+        };
 
         tokio::select! {
-            result = bot::run_bot(bot_db, bot_token) => {
+            result = bot::run_bot(
+                bot_db,
+                bot_token,
+                config.bot.clone(),
+                config.transcription.clone(),
+                config.ocr.clone(),
+            ) => {
                 tracing::error!("Bot service exited: {:?}", result);
                 result
             }
@@ -164,15 +316,171 @@ fn spawn_worker(
     tokio::spawn(async move {
         let bot = teloxide::Bot::new(&config.runtime.telegram_bot_token);
         let worker_config = config.to_worker_config();
+        let retention = televent_storage::retention::RetentionRepository::new(pool.clone());
+        let mailer = config
+            .email
+            .as_ref()
+            .map(|email| {
+                worker::Mailer::new(
+                    email,
+                    televent_application::EmailService::new(
+                        televent_storage::email::EmailRepository::new(pool.clone()),
+                    ),
+                    televent_application::UnsubscribeKey::from_bot_token(
+                        &config.runtime.telegram_bot_token,
+                    ),
+                    televent_application::NotificationService::new(
+                        televent_storage::notification::NotificationRepository::new(pool.clone())
+                            .with_cipher(config.runtime.column_cipher.clone()),
+                    ),
+                    televent_application::EmailVerificationKey::from_bot_token(
+                        &config.runtime.telegram_bot_token,
+                    ),
+                )
+            })
+            .transpose()?;
+        let weather = config
+            .weather
+            .as_ref()
+            .map(worker::Forecaster::new)
+            .transpose()?;
+        let chat = worker::ChatSender::new(televent_application::ChatWebhookService::new(
+            televent_storage::chat_webhook::ChatWebhookRepository::new(pool.clone())
+                .with_cipher(config.runtime.column_cipher.clone()),
+        ))?;
+        let sms = config
+            .sms
+            .as_ref()
+            .map(|sms| {
+                worker::SmsSender::new(
+                    sms,
+                    televent_application::NotificationService::new(
+                        televent_storage::notification::NotificationRepository::new(pool.clone())
+                            .with_cipher(config.runtime.column_cipher.clone()),
+                    ),
+                )
+            })
+            .transpose()?;
+        let push = config
+            .push
+            .as_ref()
+            .map(|vapid| {
+                worker::PushSender::new(
+                    vapid,
+                    televent_application::WebPushService::new(
+                        televent_storage::web_push::WebPushRepository::new(pool.clone()),
+                    ),
+                )
+            })
+            .transpose()?;
         let db = worker::WorkerDb::new(pool.clone());
         let calendar = televent_application::CalendarService::new(
-            televent_storage::calendar::CalendarRepository::new(pool.clone()),
+            televent_storage::calendar::CalendarRepository::new(pool.clone())
+                .with_slow_query_log(slow_query_log(&pool, &config)),
         );
 
-        worker::run_worker(db, calendar, bot, worker_config, Some(shutdown)).await
+        let subscriptions = televent_application::SubscriptionService::new(
+            televent_storage::subscription::SubscriptionRepository::new(pool.clone()),
+        )
+        .with_text_limits(config.runtime.event_text_limits);
+        let events = televent_application::EventService::new(
+            televent_storage::calendar::CalendarRepository::new(pool.clone()),
+        )
+        .with_text_limits(config.runtime.event_text_limits);
+
+        #[cfg(feature = "google-calendar")]
+        let google_sync = async {
+            match &config.google {
+                Some(google) => {
+                    let connector = televent_google::GoogleConnector::new(google.clone())?;
+                    worker::run_google_sync(
+                        google_sync_service(&pool, &config),
+                        connector,
+                        Some(shutdown.clone()),
+                    )
+                    .await
+                }
+                None => {
+                    tracing::info!("Google Calendar sync disabled: GOOGLE_CLIENT_ID not set");
+                    Ok(())
+                }
+            }
+        };
+        #[cfg(not(feature = "google-calendar"))]
+        let google_sync = async { Ok(()) };
+
+        tokio::try_join!(
+            worker::run_worker(
+                db,
+                calendar.clone(),
+                bot,
+                mailer,
+                Some(chat),
+                sms,
+                push,
+                weather,
+                analytics(&pool, &config)?,
+                worker_config,
+                Some(shutdown.clone()),
+            ),
+            worker::run_focus_scheduling(
+                calendar,
+                events,
+                subscriptions.clone(),
+                Some(shutdown.clone()),
+            ),
+            worker::run_subscription_refresh(subscriptions, Some(shutdown.clone())),
+            worker::run_retention(retention, config.worker.retention, Some(shutdown.clone()),),
+            google_sync,
+        )
+        .map(|_| ())
     })
 }
 
+/// Slow query diagnostics, off unless `SLOW_QUERY_THRESHOLD_MS` is set
+fn slow_query_log(pool: &PgPool, config: &config::UnifiedConfig) -> SlowQueryLog {
+    SlowQueryLog::new(pool.clone(), config.runtime.slow_query_threshold)
+}
+
+/// Anonymous product metrics, unless `ANALYTICS_SINK=none`
+fn analytics(
+    pool: &PgPool,
+    config: &config::UnifiedConfig,
+) -> Result<televent_application::Analytics> {
+    let sink: std::sync::Arc<dyn televent_application::AnalyticsSink> =
+        match &config.runtime.analytics {
+            config::AnalyticsConfig::Disabled => {
+                return Ok(televent_application::Analytics::disabled());
+            }
+            config::AnalyticsConfig::Postgres => {
+                std::sync::Arc::new(televent_application::PostgresAnalyticsSink::new(
+                    televent_storage::analytics::AnalyticsRepository::new(pool.clone()),
+                ))
+            }
+            config::AnalyticsConfig::Statsd { addr, prefix } => std::sync::Arc::new(
+                televent_application::StatsdAnalyticsSink::new(addr, prefix)
+                    .with_context(|| format!("Failed to set up StatsD sink at {addr}"))?,
+            ),
+        };
+    Ok(televent_application::Analytics::new(sink))
+}
+
+#[cfg(feature = "google-calendar")]
+fn google_sync_service(
+    pool: &PgPool,
+    config: &config::UnifiedConfig,
+) -> televent_application::GoogleSyncService {
+    televent_application::GoogleSyncService::new(
+        televent_storage::google::GoogleRepository::new(pool.clone())
+            .with_cipher(config.runtime.column_cipher.clone()),
+        televent_storage::calendar::CalendarRepository::new(pool.clone()),
+        televent_application::EventService::new(
+            televent_storage::calendar::CalendarRepository::new(pool.clone()),
+        )
+        .with_text_limits(config.runtime.event_text_limits),
+    )
+}
+
 async fn wait_for_shutdown() {
     let ctrl_c = async {
         signal::ctrl_c()
@@ -197,6 +505,46 @@ async fn wait_for_shutdown() {
     }
 }
 
+/// `televent migrate`: apply pending migrations and exit, for release steps
+/// in deployments running with `AUTO_MIGRATE=false`
+async fn migrate() -> Result<()> {
+    let _guard = init_tracing()?;
+    let database_url = std::env::var("DATABASE_URL").context("DATABASE_URL must be set")?;
+    let pool = televent_shared::bootstrap::init_db(&database_url, 1).await?;
+    run_migrations(&pool, MigrationPolicy::Apply).await
+}
+
+/// Seal every sensitive value in clear or under the previous column key
+/// with the current one, after which the previous key can be dropped
+async fn reseal() -> Result<()> {
+    let _guard = init_tracing()?;
+    let cipher = config::column_cipher_from_env()?;
+    if !cipher.is_enabled() {
+        anyhow::bail!("COLUMN_ENCRYPTION_KEY must be set to reseal columns");
+    }
+    let database_url = std::env::var("DATABASE_URL").context("DATABASE_URL must be set")?;
+    let pool = televent_shared::bootstrap::init_db(&database_url, 1).await?;
+    run_migrations(&pool, MigrationPolicy::RequireCurrent).await?;
+
+    let counts = televent_storage::security::reseal_columns(&pool, &cipher).await?;
+    tracing::info!(
+        "✓ Resealed {} Google connection(s), {} chat webhook(s), {} phone number(s)",
+        counts.google_connections,
+        counts.chat_webhooks,
+        counts.phone_numbers
+    );
+    Ok(())
+}
+
+async fn outbox_replay(args: &[String]) -> Result<()> {
+    let _guard = init_tracing()?;
+    let database_url = std::env::var("DATABASE_URL").context("DATABASE_URL must be set")?;
+    let pool = televent_shared::bootstrap::init_db(&database_url, 1).await?;
+    run_migrations(&pool, MigrationPolicy::RequireCurrent).await?;
+
+    replay::run(args, &pool).await
+}
+
 fn init_tracing() -> Result<Option<tracing_appender::non_blocking::WorkerGuard>> {
     let env_filter = tracing_subscriber::EnvFilter::try_from_default_env()
         .unwrap_or_else(|_| "info,api=debug,bot=debug,worker=debug,sqlx=warn".into());
@@ -0,0 +1,230 @@
+//! `televent outbox-replay`: send notifications again after a restore
+//!
+//! Messages the worker completed after a backup was taken may have side
+//! effects that did not survive restoring it. This lists the completed
+//! messages per kind since the backup timestamp and, with `--apply`, puts
+//! the chosen kinds back in the queue with fresh retries. Without `--apply`
+//! nothing is changed, so the preview is safe to run against production.
+
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use std::io::Write;
+use std::str::FromStr;
+use televent_application::OutboxKind;
+use televent_storage::outbox::{CompletedOutboxSummary, OutboxRepository};
+
+const USAGE: &str = "Usage: televent outbox-replay --since <timestamp> [--kind <kind>]... [--apply]\n\n\
+    Lists outbox messages completed at or after <timestamp> (RFC 3339, e.g.\n\
+    2026-10-18T09:00:00Z), per kind. --kind limits the list to some kinds and\n\
+    may be repeated or comma-separated. --apply puts the listed messages back\n\
+    in the queue; it needs at least one --kind so nothing is resent by accident.";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ReplayArgs {
+    since: DateTime<Utc>,
+    kinds: Vec<String>,
+    apply: bool,
+}
+
+/// Preview or requeue with the arguments after `outbox-replay`
+pub async fn run(args: &[String], pool: &PgPool) -> Result<()> {
+    let args = parse_args(args)?;
+    let outbox = OutboxRepository::new(pool.clone());
+
+    let summaries = outbox
+        .summarize_completed_since(args.since, &args.kinds)
+        .await?;
+    let mut out = std::io::stdout().lock();
+    out.write_all(render_summary(args.since, &summaries).as_bytes())?;
+
+    if !args.apply {
+        let total: i64 = summaries.iter().map(|summary| summary.count).sum();
+        if total > 0 {
+            writeln!(
+                out,
+                "Dry run: nothing changed. Add --apply to queue them again."
+            )?;
+        }
+        return Ok(());
+    }
+
+    let requeued = outbox
+        .requeue_completed_since(args.since, &args.kinds)
+        .await?;
+    writeln!(out, "Queued {requeued} message(s) again")?;
+    tracing::info!(
+        "Outbox replay since {} requeued {} message(s) of kinds {}",
+        args.since.to_rfc3339(),
+        requeued,
+        args.kinds.join(",")
+    );
+    Ok(())
+}
+
+fn parse_args(args: &[String]) -> Result<ReplayArgs> {
+    let mut since = None;
+    let mut kinds = Vec::new();
+    let mut apply = false;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--since" => {
+                let value = args.next().context(USAGE)?;
+                since = Some(
+                    DateTime::parse_from_rfc3339(value)
+                        .with_context(|| format!("Invalid --since timestamp: {value}"))?
+                        .with_timezone(&Utc),
+                );
+            }
+            "--kind" => {
+                let value = args.next().context(USAGE)?;
+                for kind in value.split(',').map(str::trim).filter(|k| !k.is_empty()) {
+                    let kind = OutboxKind::from_str(kind)
+                        .map_err(|_| anyhow::anyhow!("Unknown outbox kind: {kind}"))?;
+                    let kind = kind.as_str().to_string();
+                    if !kinds.contains(&kind) {
+                        kinds.push(kind);
+                    }
+                }
+            }
+            "--apply" => apply = true,
+            _ => bail!(USAGE),
+        }
+    }
+
+    let Some(since) = since else {
+        bail!(USAGE);
+    };
+    if apply && kinds.is_empty() {
+        bail!("--apply needs at least one --kind to choose what is sent again");
+    }
+    Ok(ReplayArgs {
+        since,
+        kinds,
+        apply,
+    })
+}
+
+fn render_summary(since: DateTime<Utc>, summaries: &[CompletedOutboxSummary]) -> String {
+    if summaries.is_empty() {
+        return format!(
+            "No outbox messages completed since {}\n",
+            since.to_rfc3339()
+        );
+    }
+    let mut text = format!("Outbox messages completed since {}:\n", since.to_rfc3339());
+    for summary in summaries {
+        text.push_str(&format!(
+            "  {:<32} {:>8}  {} .. {}\n",
+            summary.kind,
+            summary.count,
+            summary.first_processed_at.to_rfc3339(),
+            summary.last_processed_at.to_rfc3339()
+        ));
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Result<ReplayArgs> {
+        parse_args(&args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let parsed = args(&[
+            "--since",
+            "2026-10-18T11:00:00+02:00",
+            "--kind",
+            "sms,web_push",
+            "--kind",
+            "sms",
+            "--apply",
+        ])
+        .unwrap();
+        assert_eq!(
+            parsed,
+            ReplayArgs {
+                since: "2026-10-18T09:00:00Z".parse().unwrap(),
+                kinds: vec!["sms".to_string(), "web_push".to_string()],
+                apply: true,
+            }
+        );
+
+        assert!(!args(&["--since", "2026-10-18T09:00:00Z"]).unwrap().apply);
+        assert!(args(&[]).is_err());
+        assert!(args(&["--since", "yesterday"]).is_err());
+        assert!(args(&["--since", "2026-10-18T09:00:00Z", "--kind", "fax"]).is_err());
+        let err = args(&["--since", "2026-10-18T09:00:00Z", "--apply"]).unwrap_err();
+        assert!(err.to_string().contains("--kind"));
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_requeues_only_selected_kinds_completed_since(pool: PgPool) {
+        let since: DateTime<Utc> = "2026-10-18T09:00:00Z".parse().unwrap();
+        for (kind, processed_at) in [
+            ("sms", "2026-10-18T08:59:00Z"),
+            ("sms", "2026-10-18T09:05:00Z"),
+            ("web_push", "2026-10-18T09:10:00Z"),
+        ] {
+            sqlx::query(
+                "INSERT INTO outbox_messages (kind, payload, status, processed_at)
+                 VALUES ($1, '{}', 'completed', $2::timestamptz)",
+            )
+            .bind(kind)
+            .bind(processed_at)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+        let outbox = OutboxRepository::new(pool.clone());
+
+        let preview = outbox.summarize_completed_since(since, &[]).await.unwrap();
+        assert_eq!(
+            preview
+                .iter()
+                .map(|summary| (summary.kind.as_str(), summary.count))
+                .collect::<Vec<_>>(),
+            vec![("sms", 1), ("web_push", 1)]
+        );
+
+        // Without --apply nothing changes
+        run(
+            &[
+                "--since".to_string(),
+                since.to_rfc3339(),
+                "--kind".to_string(),
+                "sms".to_string(),
+            ],
+            &pool,
+        )
+        .await
+        .unwrap();
+        assert_eq!(outbox.count_pending().await.unwrap(), 0);
+
+        run(
+            &[
+                "--since".to_string(),
+                since.to_rfc3339(),
+                "--kind".to_string(),
+                "sms".to_string(),
+                "--apply".to_string(),
+            ],
+            &pool,
+        )
+        .await
+        .unwrap();
+        let pending: Vec<(String, i32)> = sqlx::query_as(
+            "SELECT kind, retry_count FROM outbox_messages WHERE status = 'pending'",
+        )
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        assert_eq!(pending, vec![("sms".to_string(), 0)]);
+    }
+}
@@ -0,0 +1,115 @@
+//! `televent systemd`: unit files for socket-activated deployments
+//!
+//! Prints a `televent.socket` and `televent.service` pair. systemd owns the
+//! listening socket, so while the service restarts new connections wait in
+//! the socket's backlog instead of being refused, and the old process drains
+//! its in-flight requests on SIGTERM before exiting.
+
+use anyhow::{Context, Result, bail};
+use std::env;
+use std::io::Write;
+use std::path::Path;
+
+const USAGE: &str = "Usage: televent systemd [listen-address]\n\n\
+    Prints systemd units for the current binary and working directory.\n\
+    [listen-address] defaults to API_HOST:API_PORT (0.0.0.0:3000).";
+
+/// Seconds systemd waits for the drain before killing the service; above the
+/// server's own drain timeout
+const STOP_TIMEOUT_SECS: u64 = 30;
+
+/// Print the units with the arguments after `systemd`
+pub fn run(args: &[String]) -> Result<()> {
+    let listen = match args {
+        [] => format!(
+            "{}:{}",
+            env::var("API_HOST").unwrap_or_else(|_| "0.0.0.0".into()),
+            env::var("API_PORT")
+                .or_else(|_| env::var("PORT"))
+                .unwrap_or_else(|_| "3000".into())
+        ),
+        [listen] if !listen.starts_with('-') => listen.clone(),
+        _ => bail!(USAGE),
+    };
+    let exe = env::current_exe().context("Failed to locate the televent binary")?;
+    let working_dir = env::current_dir().context("Failed to read the working directory")?;
+
+    std::io::stdout()
+        .lock()
+        .write_all(render_units(&listen, &exe, &working_dir).as_bytes())?;
+    Ok(())
+}
+
+fn render_units(listen: &str, exe: &Path, working_dir: &Path) -> String {
+    format!(
+        "# /etc/systemd/system/televent.socket\n\
+         [Unit]\n\
+         Description=Televent API socket\n\
+         \n\
+         [Socket]\n\
+         ListenStream={listen}\n\
+         NoDelay=true\n\
+         \n\
+         [Install]\n\
+         WantedBy=sockets.target\n\
+         \n\
+         # /etc/systemd/system/televent.service\n\
+         [Unit]\n\
+         Description=Televent server\n\
+         Requires=televent.socket\n\
+         After=network-online.target televent.socket\n\
+         Wants=network-online.target\n\
+         \n\
+         [Service]\n\
+         Type=simple\n\
+         ExecStart={exe}\n\
+         WorkingDirectory={dir}\n\
+         EnvironmentFile=-{env_file}\n\
+         KillSignal=SIGTERM\n\
+         TimeoutStopSec={STOP_TIMEOUT_SECS}\n\
+         Restart=on-failure\n\
+         \n\
+         [Install]\n\
+         WantedBy=multi-user.target\n",
+        exe = quote(&exe.display().to_string()),
+        dir = working_dir.display(),
+        env_file = working_dir.join(".env").display(),
+    )
+}
+
+/// Quote the command of `ExecStart` when it contains spaces; path settings
+/// take the rest of the line as is
+fn quote(value: &str) -> String {
+    if value.contains(char::is_whitespace) {
+        format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_units() {
+        let units = render_units(
+            "0.0.0.0:3000",
+            Path::new("/opt/televent app/televent"),
+            Path::new("/opt/televent app"),
+        );
+
+        assert!(units.contains("ListenStream=0.0.0.0:3000\n"));
+        assert!(units.contains("Requires=televent.socket\n"));
+        assert!(units.contains("ExecStart=\"/opt/televent app/televent\"\n"));
+        assert!(units.contains("WorkingDirectory=/opt/televent app\n"));
+        assert!(units.contains("EnvironmentFile=-/opt/televent app/.env\n"));
+        assert!(units.contains("TimeoutStopSec=30\n"));
+    }
+
+    #[test]
+    fn test_rejects_unknown_arguments() {
+        assert!(run(&["--help".to_string()]).is_err());
+        assert!(run(&["a".to_string(), "b".to_string()]).is_err());
+    }
+}
@@ -1,5 +1,6 @@
-use anyhow::Result;
+use anyhow::{Result, bail};
 use sqlx::postgres::PgPoolOptions;
+use televent_storage::migrations::{MIGRATION_LOCK_KEY, MIGRATOR, migration_status};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 /// Initialize dotenvy
@@ -53,3 +54,67 @@ pub async fn init_db(database_url: &str, max_connections: u32) -> Result<sqlx::P
 
     Ok(pool)
 }
+
+/// What startup does about migrations the database hasn't applied
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MigrationPolicy {
+    /// Apply them
+    Apply,
+    /// Refuse to start, leaving the schema to a separate `televent migrate`
+    RequireCurrent,
+}
+
+/// Bring the schema up to date, or check that it is
+///
+/// Holds a Postgres advisory lock for the whole check-and-apply, so processes
+/// starting together take turns and later ones find nothing left to do.
+pub async fn run_migrations(pool: &sqlx::PgPool, policy: MigrationPolicy) -> Result<()> {
+    let mut conn = pool.acquire().await?;
+    sqlx::query("SELECT pg_advisory_lock($1)")
+        .bind(MIGRATION_LOCK_KEY)
+        .execute(&mut *conn)
+        .await?;
+
+    let result = apply_locked(&mut conn, policy).await;
+
+    if let Err(e) = sqlx::query("SELECT pg_advisory_unlock($1)")
+        .bind(MIGRATION_LOCK_KEY)
+        .execute(&mut *conn)
+        .await
+    {
+        // Closing the connection releases the lock instead
+        tracing::warn!("Failed to release migration lock: {}", e);
+        conn.detach();
+    }
+    result
+}
+
+async fn apply_locked(conn: &mut sqlx::PgConnection, policy: MigrationPolicy) -> Result<()> {
+    let status = migration_status(conn).await?;
+    if !status.failed.is_empty() {
+        bail!(
+            "Migrations {:?} failed part-way; repair the schema before starting",
+            status.failed
+        );
+    }
+    if status.pending.is_empty() {
+        tracing::info!(
+            "✓ Schema is current ({} migrations)",
+            MIGRATOR.iter().count()
+        );
+        return Ok(());
+    }
+
+    let versions: Vec<i64> = status.pending.iter().map(|m| m.version).collect();
+    if policy == MigrationPolicy::RequireCurrent {
+        bail!(
+            "{} pending migration(s) {:?}; run `televent migrate` or set AUTO_MIGRATE=true",
+            versions.len(),
+            versions
+        );
+    }
+
+    MIGRATOR.run(&mut *conn).await?;
+    tracing::info!("✓ Applied {} migration(s) {:?}", versions.len(), versions);
+    Ok(())
+}
@@ -0,0 +1,62 @@
+use chrono::NaiveDate;
+use sqlx::PgPool;
+
+use crate::StorageResult;
+
+#[derive(Clone)]
+pub struct AnalyticsRepository {
+    pool: PgPool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnalyticsCounterRecord {
+    pub metric: String,
+    pub day: NaiveDate,
+    pub count: i64,
+}
+
+impl AnalyticsRepository {
+    #[must_use]
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Add `count` to today's (UTC) total of `metric`
+    pub async fn increment(&self, metric: &str, count: i64) -> StorageResult<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO analytics_counters (metric, day, count)
+            VALUES ($1, (now() AT TIME ZONE 'UTC')::date, $2)
+            ON CONFLICT (metric, day)
+            DO UPDATE SET count = analytics_counters.count + EXCLUDED.count
+            "#,
+            metric,
+            count
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Daily totals from `since` on, by metric then day
+    pub async fn list_counters(
+        &self,
+        since: NaiveDate,
+    ) -> StorageResult<Vec<AnalyticsCounterRecord>> {
+        let counters = sqlx::query_as!(
+            AnalyticsCounterRecord,
+            r#"
+            SELECT metric, day, count
+            FROM analytics_counters
+            WHERE day >= $1
+            ORDER BY metric, day
+            "#,
+            since
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(counters)
+    }
+}
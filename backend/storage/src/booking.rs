@@ -0,0 +1,140 @@
+//! Booking links: part of a user's day shared under a public slug.
+
+use chrono::{DateTime, NaiveTime, Utc};
+use sqlx::{PgConnection, PgPool};
+use televent_domain::UserId;
+use uuid::Uuid;
+
+use crate::StorageResult;
+
+/// Booking link row owned by the storage layer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BookingLink {
+    pub id: Uuid,
+    pub user_id: UserId,
+    pub slug: String,
+    pub title: String,
+    pub duration_minutes: i32,
+    pub day_start: NaiveTime,
+    pub day_end: NaiveTime,
+    pub within_days: i32,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A booking link to store.
+#[derive(Debug, Clone)]
+pub struct BookingLinkWrite {
+    pub user_id: UserId,
+    pub slug: String,
+    pub title: String,
+    pub duration_minutes: i32,
+    pub day_start: NaiveTime,
+    pub day_end: NaiveTime,
+    pub within_days: i32,
+}
+
+struct BookingLinkRow {
+    id: Uuid,
+    user_id: i64,
+    slug: String,
+    title: String,
+    duration_minutes: i32,
+    day_start: NaiveTime,
+    day_end: NaiveTime,
+    within_days: i32,
+    created_at: DateTime<Utc>,
+}
+
+impl From<BookingLinkRow> for BookingLink {
+    fn from(row: BookingLinkRow) -> Self {
+        Self {
+            id: row.id,
+            user_id: UserId::new(row.user_id),
+            slug: row.slug,
+            title: row.title,
+            duration_minutes: row.duration_minutes,
+            day_start: row.day_start,
+            day_end: row.day_end,
+            within_days: row.within_days,
+            created_at: row.created_at,
+        }
+    }
+}
+
+pub(crate) async fn insert(pool: &PgPool, link: BookingLinkWrite) -> StorageResult<BookingLink> {
+    let row = sqlx::query_as!(
+        BookingLinkRow,
+        r#"
+        INSERT INTO booking_links
+            (user_id, slug, title, duration_minutes, day_start, day_end, within_days)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        RETURNING id, user_id, slug, title, duration_minutes, day_start, day_end, within_days,
+                  created_at
+        "#,
+        link.user_id.inner(),
+        link.slug,
+        link.title,
+        link.duration_minutes,
+        link.day_start,
+        link.day_end,
+        link.within_days
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(row.into())
+}
+
+/// The user's booking links, newest first
+pub(crate) async fn list_for_user(
+    pool: &PgPool,
+    user_id: UserId,
+) -> StorageResult<Vec<BookingLink>> {
+    let rows = sqlx::query_as!(
+        BookingLinkRow,
+        r#"
+        SELECT id, user_id, slug, title, duration_minutes, day_start, day_end, within_days,
+               created_at
+        FROM booking_links
+        WHERE user_id = $1
+        ORDER BY created_at DESC
+        "#,
+        user_id.inner()
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(BookingLink::from).collect())
+}
+
+pub(crate) async fn delete(pool: &PgPool, user_id: UserId, id: Uuid) -> StorageResult<bool> {
+    let result = sqlx::query!(
+        "DELETE FROM booking_links WHERE id = $1 AND user_id = $2",
+        id,
+        user_id.inner()
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+pub(crate) async fn get_by_slug(
+    conn: &mut PgConnection,
+    slug: &str,
+) -> StorageResult<Option<BookingLink>> {
+    let row = sqlx::query_as!(
+        BookingLinkRow,
+        r#"
+        SELECT id, user_id, slug, title, duration_minutes, day_start, day_end, within_days,
+               created_at
+        FROM booking_links
+        WHERE slug = $1
+        "#,
+        slug
+    )
+    .fetch_optional(conn)
+    .await?;
+
+    Ok(row.map(BookingLink::from))
+}
@@ -1,26 +1,35 @@
 use chrono::{DateTime, NaiveDate, Utc};
-use sqlx::{PgConnection, PgPool, Postgres, QueryBuilder, Row, Transaction};
+use sqlx::types::Json;
+use sqlx::{Execute, PgConnection, PgPool, Postgres, QueryBuilder, Row, Transaction};
 use std::collections::HashMap;
 use televent_domain::{
-    AttendeeRole, EventStatus, EventTiming, OutboxPayload, ParticipationStatus, Timezone, UserId,
+    AttendeeRole, BUSY_SUMMARY, EventStatus, EventTiming, EventVisibility, OutboxMessageId,
+    OutboxPayload, ParticipationStatus, Timezone, UserId, UserRole, tags::event_tags,
 };
 use uuid::Uuid;
 
+use crate::booking::{BookingLink, BookingLinkWrite};
+use crate::delegation::DelegateRecord;
+use crate::diagnostics::{QueryParam, SlowQueryLog};
+use crate::focus::{FocusBlock, FocusRule, FocusRuleFields};
+use crate::outbox::ScheduledOutboxMessage;
+use crate::resource::{Resource, ResourceBooking};
+use crate::revision::{EventRevision, EventRevisionWrite};
+use crate::task::Task;
 use crate::{StorageError, StorageResult};
 
-const USER_COLUMNS: &str =
-    "telegram_id, telegram_username, timezone, sync_token, ctag, created_at, updated_at";
-const EVENT_COLUMNS: &str = r#"id, user_id, uid, summary, description, location,
-    start, "end", start_date, end_date, is_all_day, status::text AS status,
-    rrule, timezone, version, sync_version, etag, created_at, updated_at"#;
-const ATTENDEE_COLUMNS: &str =
-    "event_id, email, user_id, role::text AS role, status::text AS status, created_at, updated_at";
-
 #[derive(Debug, Clone)]
 pub struct User {
     pub id: UserId,
     pub telegram_username: Option<String>,
     pub timezone: Timezone,
+    pub role: UserRole,
+    /// Address given during onboarding
+    pub email: Option<String>,
+    /// `None` until the onboarding questions were shown
+    pub onboarded_at: Option<DateTime<Utc>>,
+    /// Visibility of new events that don't set their own
+    pub default_event_visibility: EventVisibility,
     pub sync_token: i64,
     pub ctag: i64,
     pub created_at: DateTime<Utc>,
@@ -40,16 +49,40 @@ pub struct Event {
     pub start_date: Option<NaiveDate>,
     pub end_date: Option<NaiveDate>,
     pub is_all_day: bool,
+    pub is_floating: bool,
     pub status: EventStatus,
     pub rrule: Option<String>,
     pub timezone: Timezone,
+    /// Bumped on every write, for optimistic locking
     pub version: i32,
+    /// iCalendar SEQUENCE: the highest a client sent, or one past the last
+    /// value after an edit through the API
+    pub sequence: i32,
     pub sync_version: i64,
     pub etag: String,
+    pub visibility: EventVisibility,
+    /// Appended to reminders and exported as the alarm text
+    pub reminder_note: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+/// Timed events on one ISO weekday (1 = Monday) of a stats window
+#[derive(Debug, Clone)]
+pub struct WeekdayLoadRecord {
+    pub weekday: i32,
+    pub events: i64,
+    pub seconds: f64,
+}
+
+/// Someone invited to the user's events in a stats window
+#[derive(Debug, Clone)]
+pub struct CollaboratorRecord {
+    pub email: String,
+    pub telegram_username: Option<String>,
+    pub events: i64,
+}
+
 #[derive(Debug, Clone)]
 pub struct EventAttendee {
     pub event_id: Uuid,
@@ -69,15 +102,39 @@ pub struct EventTombstone {
     pub deleted_at: DateTime<Utc>,
 }
 
+/// Everything a sync-collection REPORT answers with, read in one snapshot
+#[derive(Debug, Clone)]
+pub struct SyncCollection {
+    pub sync_token: i64,
+    pub ctag: i64,
+    pub events: Vec<Event>,
+    pub attendees_by_event: HashMap<Uuid, Vec<EventAttendee>>,
+    pub tombstones: Vec<EventTombstone>,
+    /// Highest sync_version of a tombstone removed by retention; deletions
+    /// at or below it can no longer be reported
+    pub tombstones_pruned_through: i64,
+}
+
 #[derive(Clone)]
 pub struct CalendarRepository {
     pool: PgPool,
+    slow_queries: SlowQueryLog,
 }
 
 impl CalendarRepository {
     #[must_use]
     pub fn new(pool: PgPool) -> Self {
-        Self { pool }
+        Self {
+            pool,
+            slow_queries: SlowQueryLog::default(),
+        }
+    }
+
+    /// Report slow calendar reads (listings, sync and multiget queries)
+    #[must_use]
+    pub fn with_slow_query_log(mut self, slow_queries: SlowQueryLog) -> Self {
+        self.slow_queries = slow_queries;
+        self
     }
 
     pub async fn begin(&self) -> StorageResult<CalendarTransaction<'_>> {
@@ -96,16 +153,241 @@ impl CalendarRepository {
         Ok(user)
     }
 
+    /// Pending outbox messages the user scheduled, soonest first
+    pub async fn list_scheduled_outbox(
+        &self,
+        user_id: UserId,
+    ) -> StorageResult<Vec<ScheduledOutboxMessage>> {
+        crate::outbox::list_scheduled(&self.pool, user_id.inner()).await
+    }
+
+    /// Drop a scheduled message the worker hasn't claimed yet; `false` when
+    /// the user has no such message
+    pub async fn cancel_scheduled_outbox(
+        &self,
+        user_id: UserId,
+        message_id: OutboxMessageId,
+    ) -> StorageResult<bool> {
+        crate::outbox::cancel_scheduled(&self.pool, user_id.inner(), message_id).await
+    }
+
     pub async fn get_event_by_id_any(&self, event_id: Uuid) -> StorageResult<Option<Event>> {
         get_event_by_id_any(&self.pool, event_id).await
     }
 
     pub async fn get_user_by_id(&self, user_id: UserId) -> StorageResult<Option<User>> {
-        get_user_by_id(&self.pool, user_id).await
+        get_user_by_id(&self.pool, &self.slow_queries, user_id).await
     }
 
     pub async fn get_user_by_username(&self, username: &str) -> StorageResult<Option<User>> {
-        get_user_by_username(&self.pool, username).await
+        get_user_by_username(&self.pool, &self.slow_queries, username).await
+    }
+
+    /// Store the username Telegram reports for an existing user, clearing it
+    /// when they dropped theirs; `false` when nothing changed or the user
+    /// doesn't exist
+    pub async fn refresh_username(
+        &self,
+        user_id: UserId,
+        username: Option<&str>,
+    ) -> StorageResult<bool> {
+        let mut tx = self.pool.begin().await?;
+        if let Some(username) = username {
+            release_username_tx(&mut tx, user_id.inner(), username).await?;
+        }
+        let result = sqlx::query!(
+            r#"
+            UPDATE users SET telegram_username = $2
+            WHERE telegram_id = $1 AND telegram_username IS DISTINCT FROM $2
+            "#,
+            user_id.inner(),
+            username
+        )
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// Change a user's role; `None` when the user doesn't exist
+    pub async fn set_user_role(
+        &self,
+        user_id: UserId,
+        role: UserRole,
+    ) -> StorageResult<Option<User>> {
+        let user = sqlx::query_as!(
+            UserRow,
+            r#"
+            UPDATE users SET role = $2
+            WHERE telegram_id = $1
+            RETURNING telegram_id, telegram_username, timezone, role, email, onboarded_at,
+                      default_event_visibility, sync_token, ctag, created_at, updated_at
+            "#,
+            user_id.inner(),
+            role.as_sql()
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        optional_user(user)
+    }
+
+    /// Change a user's timezone; `None` when the user doesn't exist
+    pub async fn set_user_timezone(
+        &self,
+        user_id: UserId,
+        timezone: &Timezone,
+    ) -> StorageResult<Option<User>> {
+        let user = sqlx::query_as!(
+            UserRow,
+            r#"
+            UPDATE users SET timezone = $2
+            WHERE telegram_id = $1
+            RETURNING telegram_id, telegram_username, timezone, role, email, onboarded_at,
+                      default_event_visibility, sync_token, ctag, created_at, updated_at
+            "#,
+            user_id.inner(),
+            timezone.as_str()
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        optional_user(user)
+    }
+
+    /// Change or clear a user's email, which unconfirms it unless only the
+    /// case changed; `None` when the user doesn't exist
+    pub async fn set_user_email(
+        &self,
+        user_id: UserId,
+        email: Option<&str>,
+    ) -> StorageResult<Option<User>> {
+        let user = sqlx::query_as!(
+            UserRow,
+            r#"
+            UPDATE users
+            SET email = $2,
+                email_verified_at = CASE
+                    WHEN lower(email) = lower($2) THEN email_verified_at
+                END
+            WHERE telegram_id = $1
+            RETURNING telegram_id, telegram_username, timezone, role, email, onboarded_at,
+                      default_event_visibility, sync_token, ctag, created_at, updated_at
+            "#,
+            user_id.inner(),
+            email
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        optional_user(user)
+    }
+
+    /// Change the visibility new events of a user get; `None` when the user
+    /// doesn't exist
+    pub async fn set_default_event_visibility(
+        &self,
+        user_id: UserId,
+        visibility: EventVisibility,
+    ) -> StorageResult<Option<User>> {
+        let user = sqlx::query_as!(
+            UserRow,
+            r#"
+            UPDATE users SET default_event_visibility = $2
+            WHERE telegram_id = $1
+            RETURNING telegram_id, telegram_username, timezone, role, email, onboarded_at,
+                      default_event_visibility, sync_token, ctag, created_at, updated_at
+            "#,
+            user_id.inner(),
+            visibility.as_sql()
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        optional_user(user)
+    }
+
+    /// The user's working week as written, `None` when not set
+    pub async fn get_working_hours(&self, user_id: UserId) -> StorageResult<Option<String>> {
+        let mut conn = self.pool.acquire().await?;
+        working_hours_tx(&mut conn, user_id).await
+    }
+
+    /// Save or clear the user's working week; `false` when the user doesn't
+    /// exist
+    pub async fn set_working_hours(
+        &self,
+        user_id: UserId,
+        working_hours: Option<&str>,
+    ) -> StorageResult<bool> {
+        let result = sqlx::query!(
+            "UPDATE users SET working_hours = $2 WHERE telegram_id = $1",
+            user_id.inner(),
+            working_hours
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Record that a user has been onboarded; `false` when that already
+    /// happened, so only the first caller shows the onboarding questions
+    pub async fn mark_user_onboarded(&self, user_id: UserId) -> StorageResult<bool> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE users SET onboarded_at = NOW()
+            WHERE telegram_id = $1 AND onboarded_at IS NULL
+            "#,
+            user_id.inner()
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    pub async fn get_user_by_email(&self, email: &str) -> StorageResult<Option<User>> {
+        let query = sqlx::query_as!(
+            UserRow,
+            r#"
+            SELECT telegram_id, telegram_username, timezone, role, email, onboarded_at,
+                   default_event_visibility, sync_token, ctag, created_at, updated_at
+            FROM users
+            WHERE lower(email) = lower($1)
+            "#,
+            email
+        );
+        let user = self
+            .slow_queries
+            .observe(
+                "get_user_by_email",
+                query.sql(),
+                || vec![QueryParam::Text(Some(email.to_string()))],
+                async { Ok(query.fetch_optional(&self.pool).await?) },
+            )
+            .await?;
+
+        optional_user(user)
+    }
+
+    /// Make the given Telegram users admins, creating the ones not seen yet
+    pub async fn promote_admins(&self, telegram_ids: &[i64]) -> StorageResult<u64> {
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO users (telegram_id, role)
+            SELECT id, 'admin' FROM UNNEST($1::BIGINT[]) AS id
+            ON CONFLICT (telegram_id) DO UPDATE
+            SET role = 'admin'
+            WHERE users.role <> 'admin'
+            "#,
+            telegram_ids
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
     }
 
     pub async fn get_event_by_id(
@@ -129,7 +411,7 @@ impl CalendarRepository {
         user_id: UserId,
         uids: &[&str],
     ) -> StorageResult<Vec<Event>> {
-        get_events_by_uids(&self.pool, user_id, uids).await
+        get_events_by_uids(&self.pool, &self.slow_queries, user_id, uids).await
     }
 
     pub async fn get_events_by_ids_any(&self, event_ids: &[Uuid]) -> StorageResult<Vec<Event>> {
@@ -144,7 +426,7 @@ impl CalendarRepository {
         &self,
         event_ids: &[Uuid],
     ) -> StorageResult<HashMap<Uuid, Vec<EventAttendee>>> {
-        get_event_attendees_bulk(&self.pool, event_ids).await
+        get_event_attendees_bulk(&self.pool, &self.slow_queries, event_ids).await
     }
 
     pub async fn list_pending_invites(
@@ -161,15 +443,115 @@ impl CalendarRepository {
         list_attendees_for_display(&self.pool, event_id).await
     }
 
+    /// Events in start order; an empty `statuses` slice means any status,
+    /// and with `tag` only events whose title has that hashtag
+    #[allow(clippy::too_many_arguments)]
     pub async fn list_events(
         &self,
         user_id: UserId,
         start: Option<DateTime<Utc>>,
         end: Option<DateTime<Utc>>,
+        statuses: &[EventStatus],
+        tag: Option<&str>,
         limit: Option<i64>,
         offset: Option<i64>,
     ) -> StorageResult<Vec<Event>> {
-        list_events(&self.pool, user_id, start, end, limit, offset).await
+        list_events(
+            &self.pool,
+            &self.slow_queries,
+            user_id,
+            start,
+            end,
+            statuses,
+            tag,
+            limit,
+            offset,
+        )
+        .await
+    }
+
+    /// Timed events saved in `timezone` starting in `[start, end)`, either
+    /// bound left open, in start order and at most `limit` of them
+    pub async fn list_events_in_timezone(
+        &self,
+        user_id: UserId,
+        timezone: &str,
+        start: Option<DateTime<Utc>>,
+        end: Option<DateTime<Utc>>,
+        limit: i64,
+    ) -> StorageResult<Vec<Event>> {
+        let mut conn = self.pool.acquire().await?;
+        list_events_in_timezone_tx(&mut conn, user_id, timezone, start, end, limit).await
+    }
+
+    /// Events in creation order, newest first; with `since_id`, only those
+    /// created after that event. An unknown `since_id` filters nothing.
+    pub async fn list_created_events(
+        &self,
+        user_id: UserId,
+        statuses: &[EventStatus],
+        since_id: Option<Uuid>,
+        limit: i64,
+    ) -> StorageResult<Vec<Event>> {
+        list_created_events(
+            &self.pool,
+            &self.slow_queries,
+            user_id,
+            statuses,
+            since_id,
+            limit,
+        )
+        .await
+    }
+
+    /// Count and length of the user's timed events starting in
+    /// `[from, to)`, grouped by local weekday in `timezone`
+    pub async fn weekday_load(
+        &self,
+        user_id: UserId,
+        timezone: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> StorageResult<Vec<WeekdayLoadRecord>> {
+        weekday_load(&self.pool, &self.slow_queries, user_id, timezone, from, to).await
+    }
+
+    /// Number of events the user created at or after `since`
+    pub async fn count_events_created_since(
+        &self,
+        user_id: UserId,
+        since: DateTime<Utc>,
+    ) -> StorageResult<i64> {
+        let query = sqlx::query_scalar!(
+            r#"SELECT COUNT(*) AS "count!" FROM events WHERE user_id = $1 AND created_at >= $2"#,
+            user_id.inner(),
+            since
+        );
+        self.slow_queries
+            .observe(
+                "count_events_created_since",
+                query.sql(),
+                || {
+                    vec![
+                        QueryParam::BigInt(Some(user_id.inner())),
+                        QueryParam::Timestamp(Some(since)),
+                    ]
+                },
+                async { Ok(query.fetch_one(&self.pool).await?) },
+            )
+            .await
+    }
+
+    /// People most often invited to the user's timed events starting in
+    /// `[from, to)`, leaving out the user and guests who declined
+    pub async fn top_collaborators(
+        &self,
+        user_id: UserId,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        limit: i64,
+    ) -> StorageResult<Vec<CollaboratorRecord>> {
+        top_collaborators(&self.pool, &self.slow_queries, user_id, from, to, limit).await
     }
 
     pub async fn list_events_since_sync(
@@ -177,7 +559,7 @@ impl CalendarRepository {
         user_id: UserId,
         sync_token: i64,
     ) -> StorageResult<Vec<Event>> {
-        list_events_since_sync(&self.pool, user_id, sync_token).await
+        list_events_since_sync(&self.pool, &self.slow_queries, user_id, sync_token).await
     }
 
     pub async fn list_tombstones_since(
@@ -185,7 +567,221 @@ impl CalendarRepository {
         user_id: UserId,
         sync_token: i64,
     ) -> StorageResult<Vec<EventTombstone>> {
-        list_tombstones_since(&self.pool, user_id, sync_token).await
+        list_tombstones_since(&self.pool, &self.slow_queries, user_id, sync_token).await
+    }
+
+    /// Calendar state, events changed after `sync_token` with their
+    /// attendees, and tombstones after it, in one round trip. Token `0` is an
+    /// initial sync: every event and no tombstones. `None` when the user
+    /// doesn't exist.
+    pub async fn sync_collection(
+        &self,
+        user_id: UserId,
+        sync_token: i64,
+    ) -> StorageResult<Option<SyncCollection>> {
+        sync_collection(&self.pool, &self.slow_queries, user_id, sync_token).await
+    }
+
+    /// Event published under `slug`, with only what its visibility lets
+    /// visitors see
+    pub async fn get_event_by_public_slug(&self, slug: &str) -> StorageResult<Option<Event>> {
+        let mut conn = self.pool.acquire().await?;
+        get_event_by_public_slug_tx(&mut conn, slug).await
+    }
+
+    /// Replace the confirmation token of a pending signup.
+    ///
+    /// Returns the event's public slug, or `None` when the signup is already
+    /// confirmed, gone, the event is no longer public, or the address is
+    /// suppressed.
+    pub async fn issue_public_signup_token(
+        &self,
+        event_id: Uuid,
+        email: &str,
+        token_hash: &str,
+        expires_at: DateTime<Utc>,
+    ) -> StorageResult<Option<String>> {
+        issue_public_signup_token(&self.pool, event_id, email, token_hash, expires_at).await
+    }
+
+    /// Give the event a public slug unless it already has one.
+    ///
+    /// Returns the slug in effect, or `None` when the event does not exist or
+    /// belongs to someone else.
+    pub async fn publish_event(
+        &self,
+        user_id: UserId,
+        event_id: Uuid,
+        slug: &str,
+    ) -> StorageResult<Option<String>> {
+        publish_event(&self.pool, user_id, event_id, slug).await
+    }
+
+    pub async fn unpublish_event(&self, user_id: UserId, event_id: Uuid) -> StorageResult<bool> {
+        unpublish_event(&self.pool, user_id, event_id).await
+    }
+
+    pub async fn create_booking_link(&self, link: BookingLinkWrite) -> StorageResult<BookingLink> {
+        crate::booking::insert(&self.pool, link).await
+    }
+
+    pub async fn list_booking_links(&self, user_id: UserId) -> StorageResult<Vec<BookingLink>> {
+        crate::booking::list_for_user(&self.pool, user_id).await
+    }
+
+    /// `false` when the user has no such link
+    pub async fn delete_booking_link(&self, user_id: UserId, id: Uuid) -> StorageResult<bool> {
+        crate::booking::delete(&self.pool, user_id, id).await
+    }
+
+    pub async fn get_booking_link_by_slug(&self, slug: &str) -> StorageResult<Option<BookingLink>> {
+        let mut conn = self.pool.acquire().await?;
+        crate::booking::get_by_slug(&mut conn, slug).await
+    }
+
+    /// Let `delegate` write to `owner`'s calendar; `false` when they already could
+    pub async fn grant_delegate(&self, owner: UserId, delegate: UserId) -> StorageResult<bool> {
+        crate::delegation::grant(&self.pool, owner, delegate).await
+    }
+
+    /// `false` when `delegate` had no access
+    pub async fn revoke_delegate(&self, owner: UserId, delegate: UserId) -> StorageResult<bool> {
+        crate::delegation::revoke(&self.pool, owner, delegate).await
+    }
+
+    pub async fn list_delegates(&self, owner: UserId) -> StorageResult<Vec<DelegateRecord>> {
+        crate::delegation::list_delegates(&self.pool, owner).await
+    }
+
+    /// Owners whose calendars `delegate` may write to
+    pub async fn list_delegating_owners(
+        &self,
+        delegate: UserId,
+    ) -> StorageResult<Vec<DelegateRecord>> {
+        crate::delegation::list_owners(&self.pool, delegate).await
+    }
+
+    pub async fn is_delegate(&self, owner: UserId, delegate: UserId) -> StorageResult<bool> {
+        let mut conn = self.pool.acquire().await?;
+        crate::delegation::is_delegate(&mut conn, owner, delegate).await
+    }
+
+    pub async fn list_event_revisions(
+        &self,
+        user_id: UserId,
+        event_id: Uuid,
+    ) -> StorageResult<Vec<EventRevision>> {
+        crate::revision::list_for_event(&self.pool, user_id, event_id).await
+    }
+
+    /// `None` when a resource with that name exists
+    pub async fn create_resource(&self, name: &str) -> StorageResult<Option<Resource>> {
+        crate::resource::create(&self.pool, name).await
+    }
+
+    pub async fn list_resources(&self) -> StorageResult<Vec<Resource>> {
+        crate::resource::list(&self.pool).await
+    }
+
+    pub async fn get_resource(&self, resource_id: Uuid) -> StorageResult<Option<Resource>> {
+        crate::resource::get(&self.pool, resource_id).await
+    }
+
+    pub async fn delete_resource(&self, resource_id: Uuid) -> StorageResult<bool> {
+        crate::resource::delete(&self.pool, resource_id).await
+    }
+
+    pub async fn get_resource_booking(
+        &self,
+        event_id: Uuid,
+    ) -> StorageResult<Option<ResourceBooking>> {
+        crate::resource::booking_for_event(&self.pool, event_id).await
+    }
+
+    /// Bookings of the resource overlapping `[from, to)`; open ends are unbounded
+    pub async fn list_resource_bookings(
+        &self,
+        resource_id: Uuid,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+    ) -> StorageResult<Vec<ResourceBooking>> {
+        crate::resource::list_bookings(&self.pool, resource_id, from, to).await
+    }
+
+    pub async fn get_resource_bookings_by_event_ids(
+        &self,
+        resource_id: Uuid,
+        event_ids: &[Uuid],
+    ) -> StorageResult<Vec<ResourceBooking>> {
+        crate::resource::get_bookings_by_event_ids(&self.pool, resource_id, event_ids).await
+    }
+
+    pub async fn create_task(
+        &self,
+        user_id: UserId,
+        title: &str,
+        duration_minutes: i32,
+    ) -> StorageResult<Task> {
+        crate::task::insert(&self.pool, user_id, title, duration_minutes).await
+    }
+
+    /// The user's tasks that are not done yet, oldest first
+    pub async fn list_open_tasks(&self, user_id: UserId) -> StorageResult<Vec<Task>> {
+        crate::task::list_open(&self.pool, user_id).await
+    }
+
+    pub async fn get_task(&self, user_id: UserId, id: Uuid) -> StorageResult<Option<Task>> {
+        crate::task::get(&self.pool, user_id, id).await
+    }
+
+    /// `false` when the user has no such task
+    pub async fn delete_task(&self, user_id: UserId, id: Uuid) -> StorageResult<bool> {
+        crate::task::delete(&self.pool, user_id, id).await
+    }
+
+    /// `false` when the user has no such open task
+    pub async fn complete_task(&self, user_id: UserId, id: Uuid) -> StorageResult<bool> {
+        crate::task::complete(&self.pool, user_id, id).await
+    }
+
+    /// Forget the task's block so it is planned again; `false` when the user
+    /// has no such open task
+    pub async fn unlink_task(&self, user_id: UserId, id: Uuid) -> StorageResult<bool> {
+        crate::task::unlink(&self.pool, user_id, id).await
+    }
+
+    pub async fn create_focus_rule(
+        &self,
+        user_id: UserId,
+        fields: FocusRuleFields<'_>,
+    ) -> StorageResult<FocusRule> {
+        crate::focus::insert_rule(&self.pool, user_id, fields).await
+    }
+
+    /// The user's focus rules, oldest first
+    pub async fn list_focus_rules(&self, user_id: UserId) -> StorageResult<Vec<FocusRule>> {
+        let mut conn = self.pool.acquire().await?;
+        crate::focus::list_rules(&mut conn, user_id).await
+    }
+
+    /// Blocks of the user's focus rules overlapping `from..until`
+    pub async fn list_focus_blocks(
+        &self,
+        user_id: UserId,
+        from: DateTime<Utc>,
+        until: DateTime<Utc>,
+    ) -> StorageResult<Vec<FocusBlock>> {
+        let mut conn = self.pool.acquire().await?;
+        crate::focus::list_blocks(&mut conn, user_id, from, until).await
+    }
+
+    /// Users with focus rules, a page at a time in Telegram ID order
+    pub async fn list_focus_users(
+        &self,
+        after: Option<UserId>,
+        limit: i64,
+    ) -> StorageResult<Vec<UserId>> {
+        crate::focus::list_users(&self.pool, after, limit).await
     }
 }
 
@@ -202,6 +798,13 @@ impl CalendarTransaction<'_> {
         self::ensure_user_tx(&mut self.tx, telegram_id, username).await
     }
 
+    /// Hold the calendar's row lock until this transaction ends, so writes to
+    /// the same calendar run one after another; `false` when the user doesn't
+    /// exist
+    pub async fn lock_calendar(&mut self, user_id: UserId) -> StorageResult<bool> {
+        self::lock_calendar_tx(&mut self.tx, user_id).await
+    }
+
     pub async fn bump_calendar_state(&mut self, user_id: UserId) -> StorageResult<i64> {
         self::bump_calendar_state_tx(&mut self.tx, user_id).await
     }
@@ -222,6 +825,29 @@ impl CalendarTransaction<'_> {
         self::get_event_by_uid_tx(&mut self.tx, user_id, uid).await
     }
 
+    /// Most recent live event of the user with the same summary, ignoring
+    /// case, on the same day or starting within `window` of `timing`
+    pub async fn find_near_duplicate_event(
+        &mut self,
+        user_id: UserId,
+        summary: &str,
+        timing: &EventTiming,
+        window: chrono::Duration,
+    ) -> StorageResult<Option<Event>> {
+        self::find_near_duplicate_event_tx(&mut self.tx, user_id, summary, timing, window).await
+    }
+
+    pub async fn list_events_in_timezone(
+        &mut self,
+        user_id: UserId,
+        timezone: &str,
+        start: Option<DateTime<Utc>>,
+        end: Option<DateTime<Utc>>,
+        limit: i64,
+    ) -> StorageResult<Vec<Event>> {
+        self::list_events_in_timezone_tx(&mut self.tx, user_id, timezone, start, end, limit).await
+    }
+
     pub async fn get_event_by_id_any(&mut self, event_id: Uuid) -> StorageResult<Option<Event>> {
         self::get_event_by_id_any_tx(&mut self.tx, event_id).await
     }
@@ -275,6 +901,13 @@ impl CalendarTransaction<'_> {
         self::list_attendees_tx(&mut self.tx, event_id).await
     }
 
+    pub async fn get_event_attendees_bulk(
+        &mut self,
+        event_ids: &[Uuid],
+    ) -> StorageResult<HashMap<Uuid, Vec<EventAttendee>>> {
+        self::get_event_attendees_bulk_tx(&mut self.tx, event_ids).await
+    }
+
     pub async fn upsert_attendees(
         &mut self,
         event_id: Uuid,
@@ -291,6 +924,14 @@ impl CalendarTransaction<'_> {
         self::replace_attendees_tx(&mut self.tx, event_id, attendees).await
     }
 
+    pub async fn delete_attendee(
+        &mut self,
+        event_id: Uuid,
+        email: &str,
+    ) -> StorageResult<Option<EventAttendee>> {
+        self::delete_attendee_tx(&mut self.tx, event_id, email).await
+    }
+
     pub async fn update_attendee_status(
         &mut self,
         event_id: Uuid,
@@ -300,10 +941,137 @@ impl CalendarTransaction<'_> {
         self::update_attendee_status_tx(&mut self.tx, event_id, user_id, status).await
     }
 
+    pub async fn is_email_suppressed(&mut self, email: &str) -> StorageResult<bool> {
+        crate::email::is_suppressed_tx(&mut self.tx, email).await
+    }
+
     pub async fn queue_outbox(&mut self, messages: &[OutboxPayload]) -> StorageResult<()> {
         self::queue_outbox_tx(&mut self.tx, messages).await
     }
 
+    /// Queue one message for `scheduled_at` on behalf of `scheduled_by`;
+    /// `None` when its dedupe key is already queued
+    pub async fn schedule_outbox(
+        &mut self,
+        message: &OutboxPayload,
+        scheduled_at: DateTime<Utc>,
+        scheduled_by: UserId,
+    ) -> StorageResult<Option<OutboxMessageId>> {
+        self::schedule_outbox_tx(&mut self.tx, message, scheduled_at, scheduled_by).await
+    }
+
+    pub async fn get_event_by_public_slug(&mut self, slug: &str) -> StorageResult<Option<Event>> {
+        self::get_event_by_public_slug_tx(&mut self.tx, slug).await
+    }
+
+    pub async fn upsert_public_signup(&mut self, signup: PublicSignupWrite) -> StorageResult<()> {
+        self::upsert_public_signup_tx(&mut self.tx, signup).await
+    }
+
+    /// Mark a pending, unexpired signup as confirmed.
+    pub async fn confirm_public_signup(
+        &mut self,
+        token_hash: &str,
+    ) -> StorageResult<Option<PublicSignup>> {
+        self::confirm_public_signup_tx(&mut self.tx, token_hash).await
+    }
+
+    pub async fn get_booking_link_by_slug(
+        &mut self,
+        slug: &str,
+    ) -> StorageResult<Option<BookingLink>> {
+        crate::booking::get_by_slug(&mut self.tx, slug).await
+    }
+
+    /// Whether `delegate` may write to `owner`'s calendar, read in this
+    /// transaction so a grant revoked meanwhile is seen
+    pub async fn is_delegate(&mut self, owner: UserId, delegate: UserId) -> StorageResult<bool> {
+        crate::delegation::is_delegate(&mut self.tx, owner, delegate).await
+    }
+
+    pub async fn insert_event_revision(
+        &mut self,
+        revision: EventRevisionWrite,
+    ) -> StorageResult<()> {
+        crate::revision::insert(&mut self.tx, revision).await
+    }
+
+    /// Book the resource for the event's time; `false` when it is taken then
+    pub async fn book_resource(
+        &mut self,
+        event_id: Uuid,
+        resource_id: Uuid,
+    ) -> StorageResult<bool> {
+        crate::resource::book(&mut self.tx, event_id, resource_id).await
+    }
+
+    /// `false` when the event held no resource
+    pub async fn release_resource(&mut self, event_id: Uuid) -> StorageResult<bool> {
+        crate::resource::release(&mut self.tx, event_id).await
+    }
+
+    /// Move the event's booking along with the event; `false` when the
+    /// resource is taken at its new time
+    pub async fn resync_resource_booking(&mut self, event_id: Uuid) -> StorageResult<bool> {
+        crate::resource::resync(&mut self.tx, event_id).await
+    }
+
+    /// Whether a live one-off timed event of the user overlaps `start..end`
+    pub async fn has_overlapping_event(
+        &mut self,
+        user_id: UserId,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> StorageResult<bool> {
+        self::has_overlapping_event_tx(&mut self.tx, user_id, start, end).await
+    }
+
+    /// The user's open tasks, locked until the transaction ends
+    pub async fn list_open_tasks_for_update(
+        &mut self,
+        user_id: UserId,
+    ) -> StorageResult<Vec<Task>> {
+        crate::task::list_open_for_update(&mut self.tx, user_id).await
+    }
+
+    /// Record `event_id` as the block planned for the task
+    pub async fn link_task(&mut self, task_id: Uuid, event_id: Uuid) -> StorageResult<()> {
+        crate::task::link(&mut self.tx, task_id, event_id).await
+    }
+
+    pub async fn list_focus_rules(&mut self, user_id: UserId) -> StorageResult<Vec<FocusRule>> {
+        crate::focus::list_rules(&mut self.tx, user_id).await
+    }
+
+    pub async fn list_focus_blocks(
+        &mut self,
+        user_id: UserId,
+        from: DateTime<Utc>,
+        until: DateTime<Utc>,
+    ) -> StorageResult<Vec<FocusBlock>> {
+        crate::focus::list_blocks(&mut self.tx, user_id, from, until).await
+    }
+
+    /// `None` when the user has no such rule
+    pub async fn update_focus_rule(
+        &mut self,
+        user_id: UserId,
+        id: Uuid,
+        fields: FocusRuleFields<'_>,
+    ) -> StorageResult<Option<FocusRule>> {
+        crate::focus::update_rule(&mut self.tx, user_id, id, fields).await
+    }
+
+    /// `false` when the user has no such rule
+    pub async fn delete_focus_rule(&mut self, user_id: UserId, id: Uuid) -> StorageResult<bool> {
+        crate::focus::delete_rule(&mut self.tx, user_id, id).await
+    }
+
+    /// Record `event_id` as a block of the focus rule
+    pub async fn link_focus_block(&mut self, rule_id: Uuid, event_id: Uuid) -> StorageResult<()> {
+        crate::focus::link_block(&mut self.tx, rule_id, event_id).await
+    }
+
     pub async fn commit(self) -> StorageResult<()> {
         self.tx.commit().await?;
         Ok(())
@@ -320,8 +1088,11 @@ pub struct StoredEventWrite {
     pub status: EventStatus,
     pub rrule: Option<String>,
     pub version: i32,
+    pub sequence: i32,
     pub sync_version: i64,
     pub etag: String,
+    pub visibility: EventVisibility,
+    pub reminder_note: Option<String>,
 }
 
 pub struct StoredEventUpdate {
@@ -334,8 +1105,11 @@ pub struct StoredEventUpdate {
     pub status: EventStatus,
     pub rrule: Option<String>,
     pub version: i32,
+    pub sequence: i32,
     pub sync_version: i64,
     pub etag: String,
+    pub visibility: EventVisibility,
+    pub reminder_note: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -353,11 +1127,15 @@ pub struct AttendeeUpsertResult {
     pub is_new: bool,
 }
 
-#[derive(Debug, Clone, sqlx::FromRow)]
+#[derive(Debug, Clone)]
 struct UserRow {
     pub telegram_id: i64,
     pub telegram_username: Option<String>,
     pub timezone: String,
+    pub role: String,
+    pub email: Option<String>,
+    pub onboarded_at: Option<DateTime<Utc>>,
+    pub default_event_visibility: String,
     pub sync_token: i64,
     pub ctag: i64,
     pub created_at: DateTime<Utc>,
@@ -372,6 +1150,10 @@ impl TryFrom<UserRow> for User {
             id: UserId::new(row.telegram_id),
             telegram_username: row.telegram_username,
             timezone: parse_timezone(&row.timezone)?,
+            role: parse_user_role(&row.role)?,
+            email: row.email,
+            onboarded_at: row.onboarded_at,
+            default_event_visibility: parse_event_visibility(&row.default_event_visibility)?,
             sync_token: row.sync_token,
             ctag: row.ctag,
             created_at: row.created_at,
@@ -380,7 +1162,7 @@ impl TryFrom<UserRow> for User {
     }
 }
 
-#[derive(Debug, Clone, sqlx::FromRow)]
+#[derive(Debug, Clone, serde::Deserialize)]
 struct EventRow {
     pub id: Uuid,
     pub user_id: i64,
@@ -393,12 +1175,16 @@ struct EventRow {
     pub start_date: Option<NaiveDate>,
     pub end_date: Option<NaiveDate>,
     pub is_all_day: bool,
+    pub is_floating: bool,
     pub status: String,
     pub rrule: Option<String>,
     pub timezone: String,
     pub version: i32,
+    pub sequence: i32,
     pub sync_version: i64,
     pub etag: String,
+    pub visibility: String,
+    pub reminder_note: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -419,19 +1205,23 @@ impl TryFrom<EventRow> for Event {
             start_date: row.start_date,
             end_date: row.end_date,
             is_all_day: row.is_all_day,
+            is_floating: row.is_floating,
             status: parse_event_status(&row.status)?,
             rrule: row.rrule,
             timezone: parse_timezone(&row.timezone)?,
             version: row.version,
+            sequence: row.sequence,
             sync_version: row.sync_version,
             etag: row.etag,
+            visibility: parse_event_visibility(&row.visibility)?,
+            reminder_note: row.reminder_note,
             created_at: row.created_at,
             updated_at: row.updated_at,
         })
     }
 }
 
-#[derive(Debug, Clone, sqlx::FromRow)]
+#[derive(Debug, Clone, serde::Deserialize)]
 struct EventAttendeeRow {
     pub event_id: Uuid,
     pub email: String,
@@ -458,7 +1248,22 @@ impl TryFrom<EventAttendeeRow> for EventAttendee {
     }
 }
 
-#[derive(Debug, Clone, sqlx::FromRow)]
+#[derive(Debug, Clone)]
+pub struct PublicSignupWrite {
+    pub event_id: Uuid,
+    pub email: String,
+    pub display_name: Option<String>,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PublicSignup {
+    pub event_id: Uuid,
+    pub email: String,
+    pub display_name: Option<String>,
+}
+
+#[derive(Debug, Clone)]
 pub struct PendingInviteRecord {
     pub event_id: Uuid,
     pub summary: String,
@@ -469,7 +1274,7 @@ pub struct PendingInviteRecord {
     pub organizer_username: Option<String>,
 }
 
-#[derive(Debug, Clone, sqlx::FromRow)]
+#[derive(Debug, Clone)]
 pub struct AttendeeDisplayRecord {
     pub email: String,
     pub telegram_id: Option<i64>,
@@ -478,7 +1283,7 @@ pub struct AttendeeDisplayRecord {
     pub telegram_username: Option<String>,
 }
 
-#[derive(Debug, Clone, sqlx::FromRow)]
+#[derive(Debug, Clone, serde::Deserialize)]
 struct EventTombstoneRow {
     pub user_id: i64,
     pub uid: String,
@@ -497,11 +1302,11 @@ impl From<EventTombstoneRow> for EventTombstone {
     }
 }
 
-fn parse_timezone(value: &str) -> StorageResult<Timezone> {
+pub(crate) fn parse_timezone(value: &str) -> StorageResult<Timezone> {
     Timezone::parse(value).map_err(|err| StorageError::InvalidData(err.to_string()))
 }
 
-fn parse_event_status(value: &str) -> StorageResult<EventStatus> {
+pub(crate) fn parse_event_status(value: &str) -> StorageResult<EventStatus> {
     match value {
         "CONFIRMED" => Ok(EventStatus::Confirmed),
         "TENTATIVE" => Ok(EventStatus::Tentative),
@@ -512,6 +1317,11 @@ fn parse_event_status(value: &str) -> StorageResult<EventStatus> {
     }
 }
 
+fn parse_event_visibility(value: &str) -> StorageResult<EventVisibility> {
+    EventVisibility::parse(value)
+        .ok_or_else(|| StorageError::InvalidData(format!("unknown event visibility: {value}")))
+}
+
 fn parse_attendee_role(value: &str) -> StorageResult<AttendeeRole> {
     match value {
         "ORGANIZER" => Ok(AttendeeRole::Organizer),
@@ -522,6 +1332,11 @@ fn parse_attendee_role(value: &str) -> StorageResult<AttendeeRole> {
     }
 }
 
+fn parse_user_role(value: &str) -> StorageResult<UserRole> {
+    UserRole::parse(value)
+        .ok_or_else(|| StorageError::InvalidData(format!("unknown user role: {value}")))
+}
+
 fn parse_participation_status(value: &str) -> StorageResult<ParticipationStatus> {
     match value {
         "NEEDS-ACTION" => Ok(ParticipationStatus::NeedsAction),
@@ -555,105 +1370,460 @@ pub(crate) async fn ensure_user_tx(
     telegram_id: i64,
     username: Option<&str>,
 ) -> StorageResult<User> {
-    let query = format!(
+    if let Some(username) = username {
+        release_username_tx(conn, telegram_id, username).await?;
+    }
+
+    let user = sqlx::query_as!(
+        UserRow,
         r#"
         INSERT INTO users (telegram_id, telegram_username)
         VALUES ($1, $2)
         ON CONFLICT (telegram_id) DO UPDATE
         SET telegram_username = COALESCE(EXCLUDED.telegram_username, users.telegram_username)
-        RETURNING {USER_COLUMNS}
+        RETURNING telegram_id, telegram_username, timezone, role, email, onboarded_at,
+                  default_event_visibility, sync_token, ctag, created_at, updated_at
         "#,
-    );
-
-    let user = sqlx::query_as::<_, UserRow>(&query)
-        .bind(telegram_id)
-        .bind(username)
-        .fetch_one(conn)
-        .await?;
+        telegram_id,
+        username
+    )
+    .fetch_one(conn)
+    .await?;
 
     User::try_from(user)
 }
 
-async fn get_user_by_id(pool: &PgPool, user_id: UserId) -> StorageResult<Option<User>> {
-    let query = format!("SELECT {USER_COLUMNS} FROM users WHERE telegram_id = $1");
-    let user = sqlx::query_as::<_, UserRow>(&query)
-        .bind(user_id.inner())
-        .fetch_optional(pool)
+/// Take `username` away from whoever else still has it stored
+///
+/// Telegram usernames are unique, so another holder renamed since we last
+/// saw them; their stale name would otherwise block this user's.
+async fn release_username_tx(
+    conn: &mut PgConnection,
+    telegram_id: i64,
+    username: &str,
+) -> StorageResult<()> {
+    sqlx::query!(
+        r#"
+        UPDATE users SET telegram_username = NULL
+        WHERE lower(telegram_username) = lower($2) AND telegram_id <> $1
+        "#,
+        telegram_id,
+        username
+    )
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+async fn get_user_by_id(
+    pool: &PgPool,
+    slow_queries: &SlowQueryLog,
+    user_id: UserId,
+) -> StorageResult<Option<User>> {
+    let query = sqlx::query_as!(
+        UserRow,
+        r#"
+        SELECT telegram_id, telegram_username, timezone, role, email, onboarded_at,
+               default_event_visibility, sync_token, ctag, created_at, updated_at
+        FROM users
+        WHERE telegram_id = $1
+        "#,
+        user_id.inner()
+    );
+    let user = slow_queries
+        .observe(
+            "get_user_by_id",
+            query.sql(),
+            || vec![QueryParam::BigInt(Some(user_id.inner()))],
+            async { Ok(query.fetch_optional(pool).await?) },
+        )
         .await?;
 
     optional_user(user)
 }
 
-async fn get_user_by_username(pool: &PgPool, username: &str) -> StorageResult<Option<User>> {
-    let query =
-        format!("SELECT {USER_COLUMNS} FROM users WHERE lower(telegram_username) = lower($1)");
-    let user = sqlx::query_as::<_, UserRow>(&query)
-        .bind(username)
-        .fetch_optional(pool)
+async fn get_user_by_username(
+    pool: &PgPool,
+    slow_queries: &SlowQueryLog,
+    username: &str,
+) -> StorageResult<Option<User>> {
+    let query = sqlx::query_as!(
+        UserRow,
+        r#"
+        SELECT telegram_id, telegram_username, timezone, role, email, onboarded_at,
+               default_event_visibility, sync_token, ctag, created_at, updated_at
+        FROM users
+        WHERE lower(telegram_username) = lower($1)
+        "#,
+        username
+    );
+    let user = slow_queries
+        .observe(
+            "get_user_by_username",
+            query.sql(),
+            || vec![QueryParam::Text(Some(username.to_string()))],
+            async { Ok(query.fetch_optional(pool).await?) },
+        )
         .await?;
 
     optional_user(user)
 }
 
+async fn lock_calendar_tx(conn: &mut PgConnection, user_id: UserId) -> StorageResult<bool> {
+    let locked = sqlx::query_scalar!(
+        r#"
+        SELECT telegram_id FROM users
+        WHERE telegram_id = $1
+        FOR UPDATE
+        "#,
+        user_id.inner()
+    )
+    .fetch_optional(conn)
+    .await?;
+
+    Ok(locked.is_some())
+}
+
+/// Next sync token of the calendar, in one statement so parallel writers
+/// never read the same value; the row stays locked until the transaction
+/// ends, so tokens are handed out in commit order
 async fn bump_calendar_state_tx(conn: &mut PgConnection, user_id: UserId) -> StorageResult<i64> {
-    let sync_version = sqlx::query_scalar::<_, i64>(
+    let sync_version = sqlx::query_scalar!(
         r#"
         UPDATE users
         SET sync_token = sync_token + 1,
-            ctag = sync_token + 1,
             updated_at = NOW()
         WHERE telegram_id = $1
         RETURNING sync_token
         "#,
+        user_id.inner()
+    )
+    .fetch_one(conn)
+    .await?;
+
+    Ok(sync_version)
+}
+
+async fn get_event_by_id_tx(
+    conn: &mut PgConnection,
+    user_id: UserId,
+    event_id: Uuid,
+) -> StorageResult<Option<Event>> {
+    let event = sqlx::query_as!(
+        EventRow,
+        r#"
+        SELECT id, user_id, uid, summary, description, location, start, "end", start_date,
+               end_date, is_all_day, is_floating, status::text AS "status!", rrule, timezone,
+               version, sequence, sync_version, etag, visibility, reminder_note, created_at, updated_at
+        FROM events
+        WHERE id = $1 AND user_id = $2
+        "#,
+        event_id,
+        user_id.inner()
+    )
+    .fetch_optional(conn)
+    .await?;
+
+    optional_event(event)
+}
+
+async fn get_event_by_uid_tx(
+    conn: &mut PgConnection,
+    user_id: UserId,
+    uid: &str,
+) -> StorageResult<Option<Event>> {
+    let event = sqlx::query_as!(
+        EventRow,
+        r#"
+        SELECT id, user_id, uid, summary, description, location, start, "end", start_date,
+               end_date, is_all_day, is_floating, status::text AS "status!", rrule, timezone,
+               version, sequence, sync_version, etag, visibility, reminder_note, created_at, updated_at
+        FROM events
+        WHERE user_id = $1 AND uid = $2
+        "#,
+        user_id.inner(),
+        uid
+    )
+    .fetch_optional(conn)
+    .await?;
+
+    optional_event(event)
+}
+
+async fn find_near_duplicate_event_tx(
+    conn: &mut PgConnection,
+    user_id: UserId,
+    summary: &str,
+    timing: &EventTiming,
+    window: chrono::Duration,
+) -> StorageResult<Option<Event>> {
+    let TimingColumns {
+        start,
+        start_date,
+        is_all_day,
+        is_floating,
+        ..
+    } = TimingColumns::from_timing(timing);
+    let event = sqlx::query_as!(
+        EventRow,
+        r#"
+        SELECT id, user_id, uid, summary, description, location, start, "end", start_date,
+               end_date, is_all_day, is_floating, status::text AS "status!", rrule, timezone,
+               version, sequence, sync_version, etag, visibility, reminder_note, created_at, updated_at
+        FROM events
+        WHERE user_id = $1
+        AND lower(summary) = lower($2)
+        AND status::text <> 'CANCELLED'
+        AND is_all_day = $3
+        AND is_floating = $4
+        AND (start_date = $5 OR start BETWEEN $6 AND $7)
+        ORDER BY created_at DESC
+        LIMIT 1
+        "#,
+        user_id.inner(),
+        summary,
+        is_all_day,
+        is_floating,
+        start_date,
+        start.map(|start| start - window),
+        start.map(|start| start + window)
+    )
+    .fetch_optional(conn)
+    .await?;
+
+    optional_event(event)
+}
+
+async fn list_events_in_timezone_tx(
+    conn: &mut PgConnection,
+    user_id: UserId,
+    timezone: &str,
+    start: Option<DateTime<Utc>>,
+    end: Option<DateTime<Utc>>,
+    limit: i64,
+) -> StorageResult<Vec<Event>> {
+    let rows = sqlx::query_as!(
+        EventRow,
+        r#"
+        SELECT id, user_id, uid, summary, description, location, start, "end", start_date,
+               end_date, is_all_day, is_floating, status::text AS "status!", rrule, timezone,
+               version, sequence, sync_version, etag, visibility, reminder_note, created_at, updated_at
+        FROM events
+        WHERE user_id = $1
+        AND timezone = $2
+        AND NOT is_all_day
+        AND NOT is_floating
+        AND start IS NOT NULL
+        AND ($3::timestamptz IS NULL OR start >= $3)
+        AND ($4::timestamptz IS NULL OR start < $4)
+        ORDER BY start, id
+        LIMIT $5
+        "#,
+        user_id.inner(),
+        timezone,
+        start,
+        end,
+        limit
+    )
+    .fetch_all(conn)
+    .await?;
+
+    event_rows(rows)
+}
+
+async fn has_overlapping_event_tx(
+    conn: &mut PgConnection,
+    user_id: UserId,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> StorageResult<bool> {
+    let overlapping = sqlx::query_scalar!(
+        r#"
+        SELECT EXISTS (
+            SELECT 1 FROM events
+            WHERE user_id = $1
+            AND status::text <> 'CANCELLED'
+            AND rrule IS NULL
+            AND NOT is_all_day
+            AND start < $3
+            AND "end" > $2
+        ) AS "exists!"
+        "#,
+        user_id.inner(),
+        start,
+        end
+    )
+    .fetch_one(conn)
+    .await?;
+
+    Ok(overlapping)
+}
+
+async fn get_event_by_id_any_tx(
+    conn: &mut PgConnection,
+    event_id: Uuid,
+) -> StorageResult<Option<Event>> {
+    let event = sqlx::query_as!(
+        EventRow,
+        r#"
+        SELECT id, user_id, uid, summary, description, location, start, "end", start_date,
+               end_date, is_all_day, is_floating, status::text AS "status!", rrule, timezone,
+               version, sequence, sync_version, etag, visibility, reminder_note, created_at, updated_at
+        FROM events
+        WHERE id = $1
+        "#,
+        event_id
+    )
+    .fetch_optional(conn)
+    .await?;
+
+    optional_event(event)
+}
+
+/// The event as people other than its owner see it: private events are left
+/// out and busy-only ones come back as a bare [`BUSY_SUMMARY`] block
+async fn get_event_by_public_slug_tx(
+    conn: &mut PgConnection,
+    slug: &str,
+) -> StorageResult<Option<Event>> {
+    let event = sqlx::query_as!(
+        EventRow,
+        r#"
+        SELECT id, user_id, uid,
+               CASE WHEN visibility = 'public' THEN summary ELSE $2 END AS "summary!",
+               CASE WHEN visibility = 'public' THEN description END AS description,
+               CASE WHEN visibility = 'public' THEN location END AS location,
+               start, "end", start_date, end_date, is_all_day, is_floating,
+               status::text AS "status!", rrule, timezone, version, sequence, sync_version, etag,
+               visibility, NULL::text AS reminder_note, created_at, updated_at
+        FROM events
+        WHERE public_slug = $1
+        AND visibility <> 'private'
+        "#,
+        slug,
+        BUSY_SUMMARY
+    )
+    .fetch_optional(conn)
+    .await?;
+
+    optional_event(event)
+}
+
+async fn publish_event(
+    pool: &PgPool,
+    user_id: UserId,
+    event_id: Uuid,
+    slug: &str,
+) -> StorageResult<Option<String>> {
+    let slug = sqlx::query_scalar!(
+        r#"
+        UPDATE events
+        SET public_slug = COALESCE(public_slug, $3)
+        WHERE id = $1 AND user_id = $2
+        RETURNING public_slug
+        "#,
+        event_id,
+        user_id.inner(),
+        slug
     )
-    .bind(user_id.inner())
-    .fetch_one(conn)
+    .fetch_optional(pool)
     .await?;
 
-    Ok(sync_version)
+    Ok(slug.flatten())
 }
 
-async fn get_event_by_id_tx(
-    conn: &mut PgConnection,
-    user_id: UserId,
-    event_id: Uuid,
-) -> StorageResult<Option<Event>> {
-    let query = format!("SELECT {EVENT_COLUMNS} FROM events WHERE id = $1 AND user_id = $2");
-    let event = sqlx::query_as::<_, EventRow>(&query)
-        .bind(event_id)
-        .bind(user_id.inner())
-        .fetch_optional(conn)
-        .await?;
+async fn unpublish_event(pool: &PgPool, user_id: UserId, event_id: Uuid) -> StorageResult<bool> {
+    let result = sqlx::query!(
+        "UPDATE events SET public_slug = NULL WHERE id = $1 AND user_id = $2",
+        event_id,
+        user_id.inner()
+    )
+    .execute(pool)
+    .await?;
 
-    optional_event(event)
+    Ok(result.rows_affected() > 0)
 }
 
-async fn get_event_by_uid_tx(
+async fn upsert_public_signup_tx(
     conn: &mut PgConnection,
-    user_id: UserId,
-    uid: &str,
-) -> StorageResult<Option<Event>> {
-    let query = format!("SELECT {EVENT_COLUMNS} FROM events WHERE user_id = $1 AND uid = $2");
-    let event = sqlx::query_as::<_, EventRow>(&query)
-        .bind(user_id.inner())
-        .bind(uid)
-        .fetch_optional(conn)
-        .await?;
+    signup: PublicSignupWrite,
+) -> StorageResult<()> {
+    sqlx::query!(
+        r#"
+        INSERT INTO public_signups (event_id, email, display_name, expires_at)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (event_id, email) DO UPDATE
+        SET display_name = EXCLUDED.display_name,
+            token_hash = NULL,
+            expires_at = EXCLUDED.expires_at,
+            confirmed_at = NULL
+        "#,
+        signup.event_id,
+        signup.email,
+        signup.display_name,
+        signup.expires_at
+    )
+    .execute(conn)
+    .await?;
 
-    optional_event(event)
+    Ok(())
 }
 
-async fn get_event_by_id_any_tx(
-    conn: &mut PgConnection,
+async fn issue_public_signup_token(
+    pool: &PgPool,
     event_id: Uuid,
-) -> StorageResult<Option<Event>> {
-    let query = format!("SELECT {EVENT_COLUMNS} FROM events WHERE id = $1");
-    let event = sqlx::query_as::<_, EventRow>(&query)
-        .bind(event_id)
-        .fetch_optional(conn)
-        .await?;
+    email: &str,
+    token_hash: &str,
+    expires_at: DateTime<Utc>,
+) -> StorageResult<Option<String>> {
+    let slug = sqlx::query_scalar!(
+        r#"
+        UPDATE public_signups s
+        SET token_hash = $3,
+            expires_at = $4
+        FROM events e
+        WHERE s.event_id = $1
+          AND s.email = $2
+          AND s.confirmed_at IS NULL
+          AND e.id = s.event_id
+          AND e.public_slug IS NOT NULL
+          AND NOT EXISTS (SELECT 1 FROM email_suppressions x WHERE x.email = s.email)
+        RETURNING e.public_slug AS "public_slug!"
+        "#,
+        event_id,
+        email,
+        token_hash,
+        expires_at
+    )
+    .fetch_optional(pool)
+    .await?;
 
-    optional_event(event)
+    Ok(slug)
+}
+
+async fn confirm_public_signup_tx(
+    conn: &mut PgConnection,
+    token_hash: &str,
+) -> StorageResult<Option<PublicSignup>> {
+    let signup = sqlx::query_as!(
+        PublicSignup,
+        r#"
+        UPDATE public_signups s
+        SET confirmed_at = NOW()
+        FROM events e
+        WHERE s.token_hash = $1
+          AND s.confirmed_at IS NULL
+          AND s.expires_at > NOW()
+          AND e.id = s.event_id
+          AND e.public_slug IS NOT NULL
+        RETURNING s.event_id, s.email, s.display_name
+        "#,
+        token_hash
+    )
+    .fetch_optional(conn)
+    .await?;
+
+    Ok(signup)
 }
 
 async fn get_event_by_id(
@@ -661,12 +1831,20 @@ async fn get_event_by_id(
     user_id: UserId,
     event_id: Uuid,
 ) -> StorageResult<Option<Event>> {
-    let query = format!("SELECT {EVENT_COLUMNS} FROM events WHERE id = $1 AND user_id = $2");
-    let event = sqlx::query_as::<_, EventRow>(&query)
-        .bind(event_id)
-        .bind(user_id.inner())
-        .fetch_optional(pool)
-        .await?;
+    let event = sqlx::query_as!(
+        EventRow,
+        r#"
+        SELECT id, user_id, uid, summary, description, location, start, "end", start_date,
+               end_date, is_all_day, is_floating, status::text AS "status!", rrule, timezone,
+               version, sequence, sync_version, etag, visibility, reminder_note, created_at, updated_at
+        FROM events
+        WHERE id = $1 AND user_id = $2
+        "#,
+        event_id,
+        user_id.inner()
+    )
+    .fetch_optional(pool)
+    .await?;
 
     optional_event(event)
 }
@@ -676,26 +1854,54 @@ async fn get_event_by_uid(
     user_id: UserId,
     uid: &str,
 ) -> StorageResult<Option<Event>> {
-    let query = format!("SELECT {EVENT_COLUMNS} FROM events WHERE user_id = $1 AND uid = $2");
-    let event = sqlx::query_as::<_, EventRow>(&query)
-        .bind(user_id.inner())
-        .bind(uid)
-        .fetch_optional(pool)
-        .await?;
+    let event = sqlx::query_as!(
+        EventRow,
+        r#"
+        SELECT id, user_id, uid, summary, description, location, start, "end", start_date,
+               end_date, is_all_day, is_floating, status::text AS "status!", rrule, timezone,
+               version, sequence, sync_version, etag, visibility, reminder_note, created_at, updated_at
+        FROM events
+        WHERE user_id = $1 AND uid = $2
+        "#,
+        user_id.inner(),
+        uid
+    )
+    .fetch_optional(pool)
+    .await?;
 
     optional_event(event)
 }
 
 async fn get_events_by_uids(
     pool: &PgPool,
+    slow_queries: &SlowQueryLog,
     user_id: UserId,
     uids: &[&str],
 ) -> StorageResult<Vec<Event>> {
-    let query = format!("SELECT {EVENT_COLUMNS} FROM events WHERE user_id = $1 AND uid = ANY($2)");
-    let events = sqlx::query_as::<_, EventRow>(&query)
-        .bind(user_id.inner())
-        .bind(uids)
-        .fetch_all(pool)
+    let query = sqlx::query_as!(
+        EventRow,
+        r#"
+        SELECT id, user_id, uid, summary, description, location, start, "end", start_date,
+               end_date, is_all_day, is_floating, status::text AS "status!", rrule, timezone,
+               version, sequence, sync_version, etag, visibility, reminder_note, created_at, updated_at
+        FROM events
+        WHERE user_id = $1 AND uid = ANY($2)
+        "#,
+        user_id.inner(),
+        uids as &[&str]
+    );
+    let events = slow_queries
+        .observe(
+            "get_events_by_uids",
+            query.sql(),
+            || {
+                vec![
+                    QueryParam::BigInt(Some(user_id.inner())),
+                    QueryParam::TextArray(uids.iter().map(|uid| uid.to_string()).collect()),
+                ]
+            },
+            async { Ok(query.fetch_all(pool).await?) },
+        )
         .await?;
 
     event_rows(events)
@@ -706,54 +1912,85 @@ async fn get_events_by_ids_any(pool: &PgPool, event_ids: &[Uuid]) -> StorageResu
         return Ok(Vec::new());
     }
 
-    let query = format!("SELECT {EVENT_COLUMNS} FROM events WHERE id = ANY($1)");
-    let events = sqlx::query_as::<_, EventRow>(&query)
-        .bind(event_ids)
-        .fetch_all(pool)
-        .await?;
+    let events = sqlx::query_as!(
+        EventRow,
+        r#"
+        SELECT id, user_id, uid, summary, description, location, start, "end", start_date,
+               end_date, is_all_day, is_floating, status::text AS "status!", rrule, timezone,
+               version, sequence, sync_version, etag, visibility, reminder_note, created_at, updated_at
+        FROM events
+        WHERE id = ANY($1)
+        "#,
+        event_ids
+    )
+    .fetch_all(pool)
+    .await?;
 
     event_rows(events)
 }
 
 async fn get_event_by_id_any(pool: &PgPool, event_id: Uuid) -> StorageResult<Option<Event>> {
-    let query = format!("SELECT {EVENT_COLUMNS} FROM events WHERE id = $1");
-    let event = sqlx::query_as::<_, EventRow>(&query)
-        .bind(event_id)
-        .fetch_optional(pool)
-        .await?;
+    let event = sqlx::query_as!(
+        EventRow,
+        r#"
+        SELECT id, user_id, uid, summary, description, location, start, "end", start_date,
+               end_date, is_all_day, is_floating, status::text AS "status!", rrule, timezone,
+               version, sequence, sync_version, etag, visibility, reminder_note, created_at, updated_at
+        FROM events
+        WHERE id = $1
+        "#,
+        event_id
+    )
+    .fetch_optional(pool)
+    .await?;
 
     optional_event(event)
 }
 
 async fn get_event_attendees(pool: &PgPool, event_id: Uuid) -> StorageResult<Vec<EventAttendee>> {
-    let query = format!("SELECT {ATTENDEE_COLUMNS} FROM event_attendees WHERE event_id = $1");
-    let attendees = sqlx::query_as::<_, EventAttendeeRow>(&query)
-        .bind(event_id)
-        .fetch_all(pool)
-        .await?;
+    let attendees = sqlx::query_as!(
+        EventAttendeeRow,
+        r#"
+        SELECT event_id, email, user_id, role::text AS "role!", status::text AS "status!",
+               created_at, updated_at
+        FROM event_attendees
+        WHERE event_id = $1
+        "#,
+        event_id
+    )
+    .fetch_all(pool)
+    .await?;
 
     attendee_rows(attendees)
 }
 
 async fn get_event_attendees_bulk(
     pool: &PgPool,
+    slow_queries: &SlowQueryLog,
     event_ids: &[Uuid],
 ) -> StorageResult<HashMap<Uuid, Vec<EventAttendee>>> {
     if event_ids.is_empty() {
         return Ok(HashMap::new());
     }
 
-    let query = format!(
+    let query = sqlx::query_as!(
+        EventAttendeeRow,
         r#"
-        SELECT {ATTENDEE_COLUMNS}
+        SELECT event_id, email, user_id, role::text AS "role!", status::text AS "status!",
+               created_at, updated_at
         FROM event_attendees
         WHERE event_id = ANY($1)
         ORDER BY event_id, email
         "#,
+        event_ids
     );
-    let attendees = sqlx::query_as::<_, EventAttendeeRow>(&query)
-        .bind(event_ids)
-        .fetch_all(pool)
+    let attendees = slow_queries
+        .observe(
+            "get_event_attendees_bulk",
+            query.sql(),
+            || vec![QueryParam::UuidArray(event_ids.to_vec())],
+            async { Ok(query.fetch_all(pool).await?) },
+        )
         .await?;
 
     let mut grouped: HashMap<Uuid, Vec<EventAttendee>> = HashMap::new();
@@ -769,7 +2006,8 @@ async fn list_pending_invites(
     pool: &PgPool,
     user_id: UserId,
 ) -> StorageResult<Vec<PendingInviteRecord>> {
-    let invites = sqlx::query_as::<_, PendingInviteRecord>(
+    let invites = sqlx::query_as!(
+        PendingInviteRecord,
         r#"
         SELECT e.id AS event_id, e.summary, e.start, e.start_date, e.is_all_day, e.location,
                u.telegram_username AS organizer_username
@@ -780,8 +2018,8 @@ async fn list_pending_invites(
           AND ea.status = 'NEEDS-ACTION'
         ORDER BY COALESCE(e.start, (e.start_date AT TIME ZONE 'UTC')) ASC
         "#,
+        user_id.inner()
     )
-    .bind(user_id.inner())
     .fetch_all(pool)
     .await?;
 
@@ -792,10 +2030,11 @@ async fn list_attendees_for_display(
     pool: &PgPool,
     event_id: Uuid,
 ) -> StorageResult<Vec<AttendeeDisplayRecord>> {
-    let attendees = sqlx::query_as::<_, AttendeeDisplayRecord>(
+    let attendees = sqlx::query_as!(
+        AttendeeDisplayRecord,
         r#"
-        SELECT ea.email, ea.user_id AS telegram_id, ea.role::text AS role, ea.status::text AS status,
-               u.telegram_username
+        SELECT ea.email, ea.user_id AS telegram_id, ea.role::text AS "role!",
+               ea.status::text AS "status!", u.telegram_username
         FROM event_attendees ea
         LEFT JOIN users u ON ea.user_id = u.telegram_id
         WHERE ea.event_id = $1
@@ -806,66 +2045,120 @@ async fn list_attendees_for_display(
             END,
             ea.created_at ASC
         "#,
+        event_id
     )
-    .bind(event_id)
     .fetch_all(pool)
     .await?;
 
     Ok(attendees)
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn list_events(
     pool: &PgPool,
+    slow_queries: &SlowQueryLog,
     user_id: UserId,
     start: Option<DateTime<Utc>>,
     end: Option<DateTime<Utc>>,
+    statuses: &[EventStatus],
+    tag: Option<&str>,
     limit: Option<i64>,
     offset: Option<i64>,
 ) -> StorageResult<Vec<Event>> {
     let offset = offset.unwrap_or(0);
+    let statuses: Vec<String> = statuses
+        .iter()
+        .map(|status| status.as_sql().to_string())
+        .collect();
 
     let events = match (start, end) {
         (Some(start_time), Some(end_time)) => {
             let start_date = start_time.date_naive();
             let end_date = end_time.date_naive();
-            let query = format!(
+            let query = sqlx::query_as!(
+                EventRow,
                 r#"
-                SELECT {EVENT_COLUMNS} FROM events
+                SELECT id, user_id, uid, summary, description, location, start, "end",
+                       start_date, end_date, is_all_day, is_floating, status::text AS "status!",
+                       rrule, timezone, version, sequence, sync_version, etag, visibility, reminder_note, created_at, updated_at
+                FROM events
                 WHERE user_id = $1
                 AND (
                     (is_all_day = false AND start >= $2 AND start < $3)
                     OR
                     (is_all_day = true AND start_date >= $4 AND start_date < $5)
                 )
+                AND (cardinality($8::text[]) = 0 OR status::text = ANY($8))
+                AND ($9::text IS NULL OR tags @> ARRAY[$9])
                 ORDER BY COALESCE(start, start_date::timestamp AT TIME ZONE 'UTC') ASC
                 LIMIT $6 OFFSET $7
                 "#,
+                user_id.inner(),
+                start_time,
+                end_time,
+                start_date,
+                end_date,
+                limit,
+                offset,
+                &statuses,
+                tag
             );
-            sqlx::query_as::<_, EventRow>(&query)
-                .bind(user_id.inner())
-                .bind(start_time)
-                .bind(end_time)
-                .bind(start_date)
-                .bind(end_date)
-                .bind(limit)
-                .bind(offset)
-                .fetch_all(pool)
+            slow_queries
+                .observe(
+                    "list_events_in_range",
+                    query.sql(),
+                    || {
+                        vec![
+                            QueryParam::BigInt(Some(user_id.inner())),
+                            QueryParam::Timestamp(Some(start_time)),
+                            QueryParam::Timestamp(Some(end_time)),
+                            QueryParam::Date(Some(start_date)),
+                            QueryParam::Date(Some(end_date)),
+                            QueryParam::BigInt(limit),
+                            QueryParam::BigInt(Some(offset)),
+                            QueryParam::TextArray(statuses.clone()),
+                            QueryParam::Text(tag.map(str::to_string)),
+                        ]
+                    },
+                    async { Ok(query.fetch_all(pool).await?) },
+                )
                 .await?
         }
         _ => {
-            let query = format!(
+            let query = sqlx::query_as!(
+                EventRow,
                 r#"
-                SELECT {EVENT_COLUMNS} FROM events
+                SELECT id, user_id, uid, summary, description, location, start, "end",
+                       start_date, end_date, is_all_day, is_floating, status::text AS "status!",
+                       rrule, timezone, version, sequence, sync_version, etag, visibility, reminder_note, created_at, updated_at
+                FROM events
                 WHERE user_id = $1
+                AND (cardinality($4::text[]) = 0 OR status::text = ANY($4))
+                AND ($5::text IS NULL OR tags @> ARRAY[$5])
                 ORDER BY COALESCE(start, start_date::timestamp AT TIME ZONE 'UTC') ASC
                 LIMIT $2 OFFSET $3
                 "#,
+                user_id.inner(),
+                limit,
+                offset,
+                &statuses,
+                tag
             );
-            sqlx::query_as::<_, EventRow>(&query)
-                .bind(user_id.inner())
-                .bind(limit)
-                .bind(offset)
-                .fetch_all(pool)
+            slow_queries
+                .observe(
+                    "list_events",
+                    query.sql(),
+                    || {
+                        vec![
+                            QueryParam::BigInt(Some(user_id.inner())),
+                            QueryParam::BigInt(limit),
+                            QueryParam::BigInt(Some(offset)),
+                            QueryParam::TextArray(statuses.clone()),
+                            QueryParam::Text(tag.map(str::to_string)),
+                        ]
+                    },
+                    async { Ok(query.fetch_all(pool).await?) },
+                )
                 .await?
         }
     };
@@ -873,28 +2166,289 @@ async fn list_events(
     event_rows(events)
 }
 
+async fn list_created_events(
+    pool: &PgPool,
+    slow_queries: &SlowQueryLog,
+    user_id: UserId,
+    statuses: &[EventStatus],
+    since_id: Option<Uuid>,
+    limit: i64,
+) -> StorageResult<Vec<Event>> {
+    let statuses: Vec<String> = statuses
+        .iter()
+        .map(|status| status.as_sql().to_string())
+        .collect();
+    let query = sqlx::query_as!(
+        EventRow,
+        r#"
+        WITH since AS (
+            SELECT created_at, id FROM events WHERE user_id = $1 AND id = $2
+        )
+        SELECT id, user_id, uid, summary, description, location, start, "end", start_date,
+               end_date, is_all_day, is_floating, status::text AS "status!", rrule, timezone,
+               version, sequence, sync_version, etag, visibility, reminder_note, created_at, updated_at
+        FROM events
+        WHERE user_id = $1
+        AND (cardinality($3::text[]) = 0 OR status::text = ANY($3))
+        AND (
+            NOT EXISTS (SELECT 1 FROM since)
+            OR (created_at, id) > (SELECT created_at, id FROM since)
+        )
+        ORDER BY created_at DESC, id DESC
+        LIMIT $4
+        "#,
+        user_id.inner(),
+        since_id,
+        &statuses,
+        limit
+    );
+    let events = slow_queries
+        .observe(
+            "list_created_events",
+            query.sql(),
+            || {
+                vec![
+                    QueryParam::BigInt(Some(user_id.inner())),
+                    QueryParam::Uuid(since_id),
+                    QueryParam::TextArray(statuses.clone()),
+                    QueryParam::BigInt(Some(limit)),
+                ]
+            },
+            async { Ok(query.fetch_all(pool).await?) },
+        )
+        .await?;
+
+    event_rows(events)
+}
+
+async fn weekday_load(
+    pool: &PgPool,
+    slow_queries: &SlowQueryLog,
+    user_id: UserId,
+    timezone: &str,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> StorageResult<Vec<WeekdayLoadRecord>> {
+    // Floating events store their wall-clock time as UTC
+    let query = sqlx::query_as!(
+        WeekdayLoadRecord,
+        r#"
+        SELECT
+            EXTRACT(ISODOW FROM CASE
+                WHEN is_floating THEN start AT TIME ZONE 'UTC'
+                ELSE start AT TIME ZONE $2
+            END)::int AS "weekday!",
+            COUNT(*) AS "events!",
+            COALESCE(SUM(EXTRACT(EPOCH FROM ("end" - start))), 0)::float8 AS "seconds!"
+        FROM events
+        WHERE user_id = $1
+        AND is_all_day = false
+        AND status::text <> 'CANCELLED'
+        AND start >= $3 AND start < $4
+        GROUP BY 1
+        ORDER BY 1
+        "#,
+        user_id.inner(),
+        timezone,
+        from,
+        to
+    );
+    slow_queries
+        .observe(
+            "weekday_load",
+            query.sql(),
+            || {
+                vec![
+                    QueryParam::BigInt(Some(user_id.inner())),
+                    QueryParam::Text(Some(timezone.to_string())),
+                    QueryParam::Timestamp(Some(from)),
+                    QueryParam::Timestamp(Some(to)),
+                ]
+            },
+            async { Ok(query.fetch_all(pool).await?) },
+        )
+        .await
+}
+
+async fn top_collaborators(
+    pool: &PgPool,
+    slow_queries: &SlowQueryLog,
+    user_id: UserId,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    limit: i64,
+) -> StorageResult<Vec<CollaboratorRecord>> {
+    let query = sqlx::query_as!(
+        CollaboratorRecord,
+        r#"
+        SELECT
+            ea.email,
+            MAX(u.telegram_username) AS telegram_username,
+            COUNT(*) AS "events!"
+        FROM events e
+        JOIN event_attendees ea ON ea.event_id = e.id
+        LEFT JOIN users u ON u.telegram_id = ea.user_id
+        WHERE e.user_id = $1
+        AND e.is_all_day = false
+        AND e.status::text <> 'CANCELLED'
+        AND e.start >= $2 AND e.start < $3
+        AND ea.role::text <> 'ORGANIZER'
+        AND ea.status::text <> 'DECLINED'
+        AND ea.user_id IS DISTINCT FROM $1
+        GROUP BY ea.email
+        ORDER BY COUNT(*) DESC, ea.email
+        LIMIT $4
+        "#,
+        user_id.inner(),
+        from,
+        to,
+        limit
+    );
+    slow_queries
+        .observe(
+            "top_collaborators",
+            query.sql(),
+            || {
+                vec![
+                    QueryParam::BigInt(Some(user_id.inner())),
+                    QueryParam::Timestamp(Some(from)),
+                    QueryParam::Timestamp(Some(to)),
+                    QueryParam::BigInt(Some(limit)),
+                ]
+            },
+            async { Ok(query.fetch_all(pool).await?) },
+        )
+        .await
+}
+
 async fn list_events_since_sync(
     pool: &PgPool,
+    slow_queries: &SlowQueryLog,
     user_id: UserId,
     sync_token: i64,
 ) -> StorageResult<Vec<Event>> {
-    let query = format!(
+    let query = sqlx::query_as!(
+        EventRow,
         r#"
-        SELECT {EVENT_COLUMNS} FROM events
+        SELECT id, user_id, uid, summary, description, location, start, "end", start_date,
+               end_date, is_all_day, is_floating, status::text AS "status!", rrule, timezone,
+               version, sequence, sync_version, etag, visibility, reminder_note, created_at, updated_at
+        FROM events
         WHERE user_id = $1
         AND sync_version > $2
         ORDER BY sync_version ASC
         "#,
+        user_id.inner(),
+        sync_token
     );
-    let events = sqlx::query_as::<_, EventRow>(&query)
-        .bind(user_id.inner())
-        .bind(sync_token)
-        .fetch_all(pool)
+    let events = slow_queries
+        .observe(
+            "list_events_since_sync",
+            query.sql(),
+            || {
+                vec![
+                    QueryParam::BigInt(Some(user_id.inner())),
+                    QueryParam::BigInt(Some(sync_token)),
+                ]
+            },
+            async { Ok(query.fetch_all(pool).await?) },
+        )
         .await?;
 
     event_rows(events)
 }
 
+async fn sync_collection(
+    pool: &PgPool,
+    slow_queries: &SlowQueryLog,
+    user_id: UserId,
+    sync_token: i64,
+) -> StorageResult<Option<SyncCollection>> {
+    // Each set is aggregated to JSON so the calendar row, the changes and
+    // the tombstones come back as one row from one snapshot
+    let query = sqlx::query!(
+        r#"
+        WITH calendar AS (
+            SELECT sync_token, ctag, tombstones_pruned_through
+            FROM users
+            WHERE telegram_id = $1
+        ),
+        changed AS (
+            SELECT id, user_id, uid, summary, description, location, start, "end", start_date,
+                   end_date, is_all_day, is_floating, status::text AS status, rrule, timezone,
+                   version, sequence, sync_version, etag, visibility, reminder_note, created_at, updated_at
+            FROM events
+            WHERE user_id = $1
+            AND ($2::BIGINT = 0 OR sync_version > $2::BIGINT)
+        ),
+        changed_attendees AS (
+            SELECT event_id, email, user_id, role::text AS role, status::text AS status,
+                   created_at, updated_at
+            FROM event_attendees
+            WHERE event_id IN (SELECT id FROM changed)
+        ),
+        deleted AS (
+            SELECT user_id, uid, sync_version, deleted_at FROM event_tombstones
+            WHERE user_id = $1
+            AND $2::BIGINT <> 0
+            AND sync_version > $2::BIGINT
+        )
+        SELECT
+            calendar.sync_token AS "sync_token!",
+            calendar.ctag AS "ctag!",
+            calendar.tombstones_pruned_through AS "tombstones_pruned_through!",
+            (SELECT COALESCE(json_agg(changed ORDER BY changed.sync_version), '[]')
+             FROM changed) AS "events!: Json<Vec<EventRow>>",
+            (SELECT COALESCE(json_agg(changed_attendees ORDER BY
+                changed_attendees.event_id, changed_attendees.email), '[]')
+             FROM changed_attendees) AS "attendees!: Json<Vec<EventAttendeeRow>>",
+            (SELECT COALESCE(json_agg(deleted ORDER BY deleted.sync_version), '[]')
+             FROM deleted) AS "tombstones!: Json<Vec<EventTombstoneRow>>"
+        FROM calendar
+        "#,
+        user_id.inner(),
+        sync_token
+    );
+    let row = slow_queries
+        .observe(
+            "sync_collection",
+            query.sql(),
+            || {
+                vec![
+                    QueryParam::BigInt(Some(user_id.inner())),
+                    QueryParam::BigInt(Some(sync_token)),
+                ]
+            },
+            async { Ok(query.fetch_optional(pool).await?) },
+        )
+        .await?;
+    let Some(row) = row else {
+        return Ok(None);
+    };
+
+    let Json(events) = row.events;
+    let Json(attendees) = row.attendees;
+    let Json(tombstones) = row.tombstones;
+
+    let mut attendees_by_event: HashMap<Uuid, Vec<EventAttendee>> = HashMap::new();
+    for attendee in attendees {
+        let attendee = EventAttendee::try_from(attendee)?;
+        attendees_by_event
+            .entry(attendee.event_id)
+            .or_default()
+            .push(attendee);
+    }
+
+    Ok(Some(SyncCollection {
+        sync_token: row.sync_token,
+        ctag: row.ctag,
+        events: event_rows(events)?,
+        attendees_by_event,
+        tombstones: tombstones.into_iter().map(Into::into).collect(),
+        tombstones_pruned_through: row.tombstones_pruned_through,
+    }))
+}
+
 async fn insert_event_tx(conn: &mut PgConnection, event: StoredEventWrite) -> StorageResult<Event> {
     let TimingColumns {
         start,
@@ -902,43 +2456,54 @@ async fn insert_event_tx(conn: &mut PgConnection, event: StoredEventWrite) -> St
         start_date,
         end_date,
         is_all_day,
+        is_floating,
         timezone,
     } = TimingColumns::from_timing(&event.timing);
+    let tags = event_tags(&event.summary);
 
-    let query = format!(
+    let event = sqlx::query_as!(
+        EventRow,
         r#"
         INSERT INTO events (
             user_id, uid, summary, description, location,
             start, "end", start_date, end_date, is_all_day,
-            status, timezone, rrule, version, sync_version, etag
+            status, timezone, rrule, version, sync_version, etag, is_floating, visibility,
+            tags, reminder_note, sequence
         )
         VALUES (
             $1, $2, $3, $4, $5,
             $6, $7, $8, $9, $10,
-            $11::text::event_status, $12, $13, $14, $15, $16
+            $11::text::event_status, $12, $13, $14, $15, $16, $17, $18,
+            $19, $20, $21
         )
-        RETURNING {EVENT_COLUMNS}
+        RETURNING id, user_id, uid, summary, description, location, start, "end", start_date,
+                  end_date, is_all_day, is_floating, status::text AS "status!", rrule, timezone,
+                  version, sequence, sync_version, etag, visibility, reminder_note, created_at, updated_at
         "#,
-    );
-    let event = sqlx::query_as::<_, EventRow>(&query)
-        .bind(event.user_id.inner())
-        .bind(event.uid)
-        .bind(event.summary)
-        .bind(event.description)
-        .bind(event.location)
-        .bind(start)
-        .bind(end)
-        .bind(start_date)
-        .bind(end_date)
-        .bind(is_all_day)
-        .bind(event.status.as_sql())
-        .bind(timezone)
-        .bind(event.rrule)
-        .bind(event.version)
-        .bind(event.sync_version)
-        .bind(event.etag)
-        .fetch_one(conn)
-        .await?;
+        event.user_id.inner(),
+        event.uid,
+        event.summary,
+        event.description,
+        event.location,
+        start,
+        end,
+        start_date,
+        end_date,
+        is_all_day,
+        event.status.as_sql(),
+        timezone,
+        event.rrule,
+        event.version,
+        event.sync_version,
+        event.etag,
+        is_floating,
+        event.visibility.as_sql(),
+        &tags,
+        event.reminder_note,
+        event.sequence
+    )
+    .fetch_one(conn)
+    .await?;
 
     Event::try_from(event)
 }
@@ -953,10 +2518,13 @@ async fn update_event_tx(
         start_date,
         end_date,
         is_all_day,
+        is_floating,
         timezone,
     } = TimingColumns::from_timing(&event.timing);
+    let tags = event_tags(&event.summary);
 
-    let query = format!(
+    let event = sqlx::query_as!(
+        EventRow,
         r#"
         UPDATE events
         SET summary = $3,
@@ -973,30 +2541,41 @@ async fn update_event_tx(
             version = $14,
             sync_version = $15,
             etag = $16,
+            is_floating = $17,
+            visibility = $18,
+            tags = $19,
+            reminder_note = $20,
+            sequence = GREATEST(sequence, $21),
             updated_at = NOW()
         WHERE id = $1 AND user_id = $2
-        RETURNING {EVENT_COLUMNS}
+        RETURNING id, user_id, uid, summary, description, location, start, "end", start_date,
+                  end_date, is_all_day, is_floating, status::text AS "status!", rrule, timezone,
+                  version, sequence, sync_version, etag, visibility, reminder_note, created_at, updated_at
         "#,
-    );
-    let event = sqlx::query_as::<_, EventRow>(&query)
-        .bind(event.id)
-        .bind(event.user_id.inner())
-        .bind(event.summary)
-        .bind(event.description)
-        .bind(event.location)
-        .bind(start)
-        .bind(end)
-        .bind(start_date)
-        .bind(end_date)
-        .bind(is_all_day)
-        .bind(event.status.as_sql())
-        .bind(timezone)
-        .bind(event.rrule)
-        .bind(event.version)
-        .bind(event.sync_version)
-        .bind(event.etag)
-        .fetch_one(conn)
-        .await?;
+        event.id,
+        event.user_id.inner(),
+        event.summary,
+        event.description,
+        event.location,
+        start,
+        end,
+        start_date,
+        end_date,
+        is_all_day,
+        event.status.as_sql(),
+        timezone,
+        event.rrule,
+        event.version,
+        event.sync_version,
+        event.etag,
+        is_floating,
+        event.visibility.as_sql(),
+        &tags,
+        event.reminder_note,
+        event.sequence
+    )
+    .fetch_one(conn)
+    .await?;
 
     Event::try_from(event)
 }
@@ -1009,7 +2588,8 @@ async fn set_event_sync_etag_tx(
     sync_version: i64,
     etag: String,
 ) -> StorageResult<Event> {
-    let query = format!(
+    let event = sqlx::query_as!(
+        EventRow,
         r#"
         UPDATE events
         SET version = $3,
@@ -1017,17 +2597,18 @@ async fn set_event_sync_etag_tx(
             etag = $5,
             updated_at = NOW()
         WHERE id = $1 AND user_id = $2
-        RETURNING {EVENT_COLUMNS}
+        RETURNING id, user_id, uid, summary, description, location, start, "end", start_date,
+                  end_date, is_all_day, is_floating, status::text AS "status!", rrule, timezone,
+                  version, sequence, sync_version, etag, visibility, reminder_note, created_at, updated_at
         "#,
-    );
-    let event = sqlx::query_as::<_, EventRow>(&query)
-        .bind(event_id)
-        .bind(user_id.inner())
-        .bind(version)
-        .bind(sync_version)
-        .bind(etag)
-        .fetch_one(conn)
-        .await?;
+        event_id,
+        user_id.inner(),
+        version,
+        sync_version,
+        etag
+    )
+    .fetch_one(conn)
+    .await?;
 
     Event::try_from(event)
 }
@@ -1037,13 +2618,20 @@ async fn delete_event_by_id_tx(
     user_id: UserId,
     event_id: Uuid,
 ) -> StorageResult<Option<Event>> {
-    let query =
-        format!("DELETE FROM events WHERE id = $1 AND user_id = $2 RETURNING {EVENT_COLUMNS}");
-    let deleted = sqlx::query_as::<_, EventRow>(&query)
-        .bind(event_id)
-        .bind(user_id.inner())
-        .fetch_optional(conn)
-        .await?;
+    let deleted = sqlx::query_as!(
+        EventRow,
+        r#"
+        DELETE FROM events
+        WHERE id = $1 AND user_id = $2
+        RETURNING id, user_id, uid, summary, description, location, start, "end", start_date,
+                  end_date, is_all_day, is_floating, status::text AS "status!", rrule, timezone,
+                  version, sequence, sync_version, etag, visibility, reminder_note, created_at, updated_at
+        "#,
+        event_id,
+        user_id.inner()
+    )
+    .fetch_optional(conn)
+    .await?;
 
     optional_event(deleted)
 }
@@ -1053,13 +2641,20 @@ async fn delete_event_by_uid_tx(
     user_id: UserId,
     uid: &str,
 ) -> StorageResult<Option<Event>> {
-    let query =
-        format!("DELETE FROM events WHERE user_id = $1 AND uid = $2 RETURNING {EVENT_COLUMNS}");
-    let deleted = sqlx::query_as::<_, EventRow>(&query)
-        .bind(user_id.inner())
-        .bind(uid)
-        .fetch_optional(conn)
-        .await?;
+    let deleted = sqlx::query_as!(
+        EventRow,
+        r#"
+        DELETE FROM events
+        WHERE user_id = $1 AND uid = $2
+        RETURNING id, user_id, uid, summary, description, location, start, "end", start_date,
+                  end_date, is_all_day, is_floating, status::text AS "status!", rrule, timezone,
+                  version, sequence, sync_version, etag, visibility, reminder_note, created_at, updated_at
+        "#,
+        user_id.inner(),
+        uid
+    )
+    .fetch_optional(conn)
+    .await?;
 
     optional_event(deleted)
 }
@@ -1070,7 +2665,7 @@ async fn insert_tombstone_tx(
     uid: &str,
     sync_version: i64,
 ) -> StorageResult<()> {
-    sqlx::query(
+    sqlx::query!(
         r#"
         INSERT INTO event_tombstones (user_id, uid, sync_version, deleted_at)
         VALUES ($1, $2, $3, NOW())
@@ -1078,10 +2673,10 @@ async fn insert_tombstone_tx(
         SET sync_version = EXCLUDED.sync_version,
             deleted_at = EXCLUDED.deleted_at
         "#,
+        user_id.inner(),
+        uid,
+        sync_version
     )
-    .bind(user_id.inner())
-    .bind(uid)
-    .bind(sync_version)
     .execute(conn)
     .await?;
 
@@ -1092,22 +2687,54 @@ async fn list_attendees_tx(
     conn: &mut PgConnection,
     event_id: Uuid,
 ) -> StorageResult<Vec<EventAttendee>> {
-    let query = format!(
+    let attendees = sqlx::query_as!(
+        EventAttendeeRow,
         r#"
-        SELECT {ATTENDEE_COLUMNS}
+        SELECT event_id, email, user_id, role::text AS "role!", status::text AS "status!",
+               created_at, updated_at
         FROM event_attendees
         WHERE event_id = $1
         ORDER BY email
         "#,
-    );
-    let attendees = sqlx::query_as::<_, EventAttendeeRow>(&query)
-        .bind(event_id)
-        .fetch_all(conn)
-        .await?;
+        event_id
+    )
+    .fetch_all(conn)
+    .await?;
 
     attendee_rows(attendees)
 }
 
+async fn get_event_attendees_bulk_tx(
+    conn: &mut PgConnection,
+    event_ids: &[Uuid],
+) -> StorageResult<HashMap<Uuid, Vec<EventAttendee>>> {
+    if event_ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let attendees = sqlx::query_as!(
+        EventAttendeeRow,
+        r#"
+        SELECT event_id, email, user_id, role::text AS "role!", status::text AS "status!",
+               created_at, updated_at
+        FROM event_attendees
+        WHERE event_id = ANY($1)
+        ORDER BY event_id, email
+        "#,
+        event_ids
+    )
+    .fetch_all(conn)
+    .await?;
+
+    let mut grouped: HashMap<Uuid, Vec<EventAttendee>> = HashMap::new();
+    for attendee in attendees {
+        let attendee = EventAttendee::try_from(attendee)?;
+        grouped.entry(attendee.event_id).or_default().push(attendee);
+    }
+
+    Ok(grouped)
+}
+
 async fn upsert_attendees_tx(
     conn: &mut PgConnection,
     event_id: Uuid,
@@ -1125,9 +2752,9 @@ async fn upsert_attendees_tx(
         row.push_bind(attendee.user_id);
         row.push_bind(&attendee.email);
         row.push_bind(attendee.role.as_sql())
-            .push("::text::attendee_role");
+            .push_unseparated("::text::attendee_role");
         row.push_bind(attendee.status.as_sql())
-            .push("::text::attendee_status");
+            .push_unseparated("::text::attendee_status");
     });
 
     builder.push(
@@ -1160,8 +2787,7 @@ async fn replace_attendees_tx(
     attendees: &[AttendeeWrite],
 ) -> StorageResult<Vec<AttendeeUpsertResult>> {
     if attendees.is_empty() {
-        sqlx::query("DELETE FROM event_attendees WHERE event_id = $1")
-            .bind(event_id)
+        sqlx::query!("DELETE FROM event_attendees WHERE event_id = $1", event_id)
             .execute(&mut *conn)
             .await?;
         return Ok(Vec::new());
@@ -1172,45 +2798,84 @@ async fn replace_attendees_tx(
         .map(|attendee| attendee.email.clone())
         .collect::<Vec<_>>();
 
-    sqlx::query(
+    sqlx::query!(
         r#"
         DELETE FROM event_attendees
         WHERE event_id = $1
           AND email <> ALL($2::text[])
         "#,
+        event_id,
+        &attendee_emails
     )
-    .bind(event_id)
-    .bind(&attendee_emails)
     .execute(&mut *conn)
     .await?;
 
     upsert_attendees_tx(conn, event_id, attendees).await
 }
 
+async fn delete_attendee_tx(
+    conn: &mut PgConnection,
+    event_id: Uuid,
+    email: &str,
+) -> StorageResult<Option<EventAttendee>> {
+    let attendee = sqlx::query_as!(
+        EventAttendeeRow,
+        r#"
+        DELETE FROM event_attendees
+        WHERE event_id = $1 AND email = $2
+        RETURNING event_id, email, user_id, role::text AS "role!", status::text AS "status!",
+                  created_at, updated_at
+        "#,
+        event_id,
+        email
+    )
+    .fetch_optional(conn)
+    .await?;
+
+    attendee.map(EventAttendee::try_from).transpose()
+}
+
 async fn update_attendee_status_tx(
     conn: &mut PgConnection,
     event_id: Uuid,
     user_id: i64,
     status: ParticipationStatus,
 ) -> StorageResult<bool> {
-    let result = sqlx::query(
+    let result = sqlx::query!(
         r#"
         UPDATE event_attendees
         SET status = $3::text::attendee_status,
             updated_at = NOW()
         WHERE event_id = $1 AND user_id = $2
         "#,
+        event_id,
+        user_id,
+        status.as_sql()
     )
-    .bind(event_id)
-    .bind(user_id)
-    .bind(status.as_sql())
     .execute(conn)
     .await?;
 
     Ok(result.rows_affected() > 0)
 }
 
-async fn queue_outbox_tx(conn: &mut PgConnection, messages: &[OutboxPayload]) -> StorageResult<()> {
+pub(crate) async fn working_hours_tx(
+    conn: &mut PgConnection,
+    user_id: UserId,
+) -> StorageResult<Option<String>> {
+    let working_hours = sqlx::query_scalar!(
+        "SELECT working_hours FROM users WHERE telegram_id = $1",
+        user_id.inner()
+    )
+    .fetch_optional(conn)
+    .await?;
+
+    Ok(working_hours.flatten())
+}
+
+pub(crate) async fn queue_outbox_tx(
+    conn: &mut PgConnection,
+    messages: &[OutboxPayload],
+) -> StorageResult<()> {
     if messages.is_empty() {
         return Ok(());
     }
@@ -1222,18 +2887,28 @@ async fn queue_outbox_tx(conn: &mut PgConnection, messages: &[OutboxPayload]) ->
                 payload.kind().as_str().to_string(),
                 payload.payload_json()?,
                 payload.dedupe_key(),
+                payload.shard_user_id(),
+                payload.deliver_after(),
             ))
         })
         .collect::<StorageResult<Vec<_>>>()?;
 
-    let mut builder: QueryBuilder<Postgres> =
-        QueryBuilder::new("INSERT INTO outbox_messages (kind, payload, dedupe_key) ");
+    let mut builder: QueryBuilder<Postgres> = QueryBuilder::new(
+        "INSERT INTO outbox_messages (kind, payload, dedupe_key, shard_key, scheduled_at) ",
+    );
 
-    builder.push_values(rows, |mut row, (kind, payload, dedupe_key)| {
-        row.push_bind(kind);
-        row.push_bind(payload);
-        row.push_bind(dedupe_key);
-    });
+    builder.push_values(
+        rows,
+        |mut row, (kind, payload, dedupe_key, shard_key, deliver_after)| {
+            row.push_bind(kind);
+            row.push_bind(payload);
+            row.push_bind(dedupe_key);
+            row.push_bind(shard_key);
+            row.push("COALESCE(")
+                .push_bind_unseparated(deliver_after)
+                .push_unseparated("::timestamptz, NOW())");
+        },
+    );
 
     builder.push(" ON CONFLICT (dedupe_key) WHERE dedupe_key IS NOT NULL DO NOTHING");
     builder.build().execute(conn).await?;
@@ -1241,12 +2916,40 @@ async fn queue_outbox_tx(conn: &mut PgConnection, messages: &[OutboxPayload]) ->
     Ok(())
 }
 
+pub(crate) async fn schedule_outbox_tx(
+    conn: &mut PgConnection,
+    message: &OutboxPayload,
+    scheduled_at: DateTime<Utc>,
+    scheduled_by: UserId,
+) -> StorageResult<Option<OutboxMessageId>> {
+    let id = sqlx::query_scalar!(
+        r#"
+        INSERT INTO outbox_messages (kind, payload, dedupe_key, shard_key, scheduled_at, scheduled_by)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        ON CONFLICT (dedupe_key) WHERE dedupe_key IS NOT NULL DO NOTHING
+        RETURNING id AS "id: OutboxMessageId"
+        "#,
+        message.kind().as_str(),
+        message.payload_json()?,
+        message.dedupe_key(),
+        message.shard_user_id(),
+        scheduled_at,
+        scheduled_by.inner()
+    )
+    .fetch_optional(conn)
+    .await?;
+
+    Ok(id)
+}
+
 async fn list_tombstones_since(
     pool: &PgPool,
+    slow_queries: &SlowQueryLog,
     user_id: UserId,
     sync_token: i64,
 ) -> StorageResult<Vec<EventTombstone>> {
-    let tombstones = sqlx::query_as::<_, EventTombstoneRow>(
+    let query = sqlx::query_as!(
+        EventTombstoneRow,
         r#"
         SELECT user_id, uid, sync_version, deleted_at
         FROM event_tombstones
@@ -1254,26 +2957,38 @@ async fn list_tombstones_since(
           AND sync_version > $2
         ORDER BY sync_version ASC
         "#,
-    )
-    .bind(user_id.inner())
-    .bind(sync_token)
-    .fetch_all(pool)
-    .await?;
+        user_id.inner(),
+        sync_token
+    );
+    let tombstones = slow_queries
+        .observe(
+            "list_tombstones_since",
+            query.sql(),
+            || {
+                vec![
+                    QueryParam::BigInt(Some(user_id.inner())),
+                    QueryParam::BigInt(Some(sync_token)),
+                ]
+            },
+            async { Ok(query.fetch_all(pool).await?) },
+        )
+        .await?;
 
     Ok(tombstones.into_iter().map(Into::into).collect())
 }
 
-struct TimingColumns {
-    start: Option<DateTime<Utc>>,
-    end: Option<DateTime<Utc>>,
-    start_date: Option<NaiveDate>,
-    end_date: Option<NaiveDate>,
-    is_all_day: bool,
-    timezone: String,
+pub(crate) struct TimingColumns {
+    pub(crate) start: Option<DateTime<Utc>>,
+    pub(crate) end: Option<DateTime<Utc>>,
+    pub(crate) start_date: Option<NaiveDate>,
+    pub(crate) end_date: Option<NaiveDate>,
+    pub(crate) is_all_day: bool,
+    pub(crate) is_floating: bool,
+    pub(crate) timezone: String,
 }
 
 impl TimingColumns {
-    fn from_timing(timing: &EventTiming) -> Self {
+    pub(crate) fn from_timing(timing: &EventTiming) -> Self {
         match timing {
             EventTiming::Timed {
                 start,
@@ -1285,6 +3000,7 @@ impl TimingColumns {
                 start_date: None,
                 end_date: None,
                 is_all_day: false,
+                is_floating: false,
                 timezone: timezone.as_str().to_string(),
             },
             EventTiming::AllDay {
@@ -1296,6 +3012,17 @@ impl TimingColumns {
                 start_date: Some(*start_date),
                 end_date: Some(*end_date),
                 is_all_day: true,
+                is_floating: false,
+                timezone: "UTC".to_string(),
+            },
+            // Wall-clock times are stored as if they were UTC
+            EventTiming::Floating { start, end } => Self {
+                start: Some(start.and_utc()),
+                end: Some(end.and_utc()),
+                start_date: None,
+                end_date: None,
+                is_all_day: false,
+                is_floating: true,
                 timezone: "UTC".to_string(),
             },
         }
@@ -0,0 +1,229 @@
+use chrono::{DateTime, Utc};
+use sqlx::{PgConnection, PgPool, Postgres, Transaction};
+use televent_domain::{NotificationChannel, NotificationTopic, OutboxPayload, UserId};
+use uuid::Uuid;
+
+use crate::StorageResult;
+use crate::calendar::User;
+use crate::security::{CHAT_WEBHOOK_URL, ColumnCipher};
+
+#[derive(Clone)]
+pub struct ChatWebhookRepository {
+    pool: PgPool,
+    cipher: ColumnCipher,
+}
+
+#[derive(Debug, Clone)]
+pub struct ChatWebhookRecord {
+    pub id: Uuid,
+    pub user_id: i64,
+    pub provider: String,
+    pub url: String,
+    pub last_error: Option<String>,
+    pub last_error_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl ChatWebhookRepository {
+    #[must_use]
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            pool,
+            cipher: ColumnCipher::default(),
+        }
+    }
+
+    /// Seal webhook URLs with `cipher`
+    #[must_use]
+    pub fn with_cipher(mut self, cipher: ColumnCipher) -> Self {
+        self.cipher = cipher;
+        self
+    }
+
+    pub async fn begin(&self) -> StorageResult<ChatWebhookTransaction<'_>> {
+        let tx = self.pool.begin().await?;
+        Ok(ChatWebhookTransaction {
+            tx,
+            cipher: &self.cipher,
+        })
+    }
+
+    pub async fn list_webhooks(&self, user_id: UserId) -> StorageResult<Vec<ChatWebhookRecord>> {
+        let webhooks = sqlx::query_as!(
+            ChatWebhookRecord,
+            r#"
+            SELECT id, user_id, provider, url, last_error, last_error_at, created_at
+            FROM chat_webhooks
+            WHERE user_id = $1
+            ORDER BY created_at
+            "#,
+            user_id.inner()
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        webhooks
+            .into_iter()
+            .map(|webhook| open_webhook(&self.cipher, webhook))
+            .collect()
+    }
+
+    pub async fn get_webhook(&self, webhook_id: Uuid) -> StorageResult<Option<ChatWebhookRecord>> {
+        let webhook = sqlx::query_as!(
+            ChatWebhookRecord,
+            r#"
+            SELECT id, user_id, provider, url, last_error, last_error_at, created_at
+            FROM chat_webhooks
+            WHERE id = $1
+            "#,
+            webhook_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        webhook
+            .map(|webhook| open_webhook(&self.cipher, webhook))
+            .transpose()
+    }
+
+    pub async fn delete_webhook(&self, user_id: UserId, webhook_id: Uuid) -> StorageResult<bool> {
+        let result = sqlx::query!(
+            "DELETE FROM chat_webhooks WHERE id = $1 AND user_id = $2",
+            webhook_id,
+            user_id.inner()
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Remember why the provider refused a post; `None` clears it
+    pub async fn set_last_error(&self, webhook_id: Uuid, error: Option<&str>) -> StorageResult<()> {
+        sqlx::query!(
+            r#"
+            UPDATE chat_webhooks
+            SET last_error = $2,
+                last_error_at = CASE WHEN $2::text IS NULL THEN NULL ELSE NOW() END
+            WHERE id = $1
+              AND last_error IS DISTINCT FROM $2
+            "#,
+            webhook_id,
+            error
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Whether the user wants `topic` posted to their webhooks
+    pub async fn topic_enabled(
+        &self,
+        user_id: UserId,
+        topic: NotificationTopic,
+    ) -> StorageResult<bool> {
+        let mut conn = self.pool.acquire().await?;
+        crate::notification::channel_enabled_tx(
+            &mut conn,
+            user_id,
+            topic,
+            NotificationChannel::Chat,
+        )
+        .await
+    }
+
+    pub async fn queue_outbox(&self, messages: &[OutboxPayload]) -> StorageResult<()> {
+        let mut conn = self.pool.acquire().await?;
+        crate::calendar::queue_outbox_tx(&mut conn, messages).await
+    }
+}
+
+pub struct ChatWebhookTransaction<'a> {
+    tx: Transaction<'a, Postgres>,
+    cipher: &'a ColumnCipher,
+}
+
+impl ChatWebhookTransaction<'_> {
+    pub async fn ensure_user(
+        &mut self,
+        telegram_id: i64,
+        username: Option<&str>,
+    ) -> StorageResult<User> {
+        crate::calendar::ensure_user_tx(&mut self.tx, telegram_id, username).await
+    }
+
+    pub async fn count_webhooks(&mut self, user_id: UserId) -> StorageResult<i64> {
+        self::count_webhooks_tx(&mut self.tx, user_id).await
+    }
+
+    /// Store a webhook; `None` when the user already has this URL
+    pub async fn insert_webhook(
+        &mut self,
+        user_id: UserId,
+        provider: &str,
+        url: &str,
+    ) -> StorageResult<Option<ChatWebhookRecord>> {
+        self::insert_webhook_tx(&mut self.tx, self.cipher, user_id, provider, url).await
+    }
+
+    pub async fn commit(self) -> StorageResult<()> {
+        self.tx.commit().await?;
+        Ok(())
+    }
+}
+
+async fn count_webhooks_tx(conn: &mut PgConnection, user_id: UserId) -> StorageResult<i64> {
+    let count = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "count!" FROM chat_webhooks WHERE user_id = $1"#,
+        user_id.inner()
+    )
+    .fetch_one(conn)
+    .await?;
+
+    Ok(count)
+}
+
+async fn insert_webhook_tx(
+    conn: &mut PgConnection,
+    cipher: &ColumnCipher,
+    user_id: UserId,
+    provider: &str,
+    url: &str,
+) -> StorageResult<Option<ChatWebhookRecord>> {
+    // Sealed URLs differ on every write, so duplicates are found by digest
+    // under either key as well as by the clear URL
+    let webhook = sqlx::query_as!(
+        ChatWebhookRecord,
+        r#"
+        INSERT INTO chat_webhooks (user_id, provider, url, url_digest)
+        SELECT $1, $2, $3, $4
+        WHERE NOT EXISTS (
+            SELECT 1 FROM chat_webhooks
+            WHERE user_id = $1 AND (url = $6 OR url_digest = ANY($5))
+        )
+        ON CONFLICT DO NOTHING
+        RETURNING id, user_id, provider, url, last_error, last_error_at, created_at
+        "#,
+        user_id.inner(),
+        provider,
+        cipher.seal(CHAT_WEBHOOK_URL, url)?,
+        cipher.digest(CHAT_WEBHOOK_URL, url),
+        &cipher.digests(CHAT_WEBHOOK_URL, url),
+        url
+    )
+    .fetch_optional(conn)
+    .await?;
+
+    webhook
+        .map(|webhook| open_webhook(cipher, webhook))
+        .transpose()
+}
+
+fn open_webhook(
+    cipher: &ColumnCipher,
+    mut webhook: ChatWebhookRecord,
+) -> StorageResult<ChatWebhookRecord> {
+    webhook.url = cipher.open(CHAT_WEBHOOK_URL, webhook.url)?;
+    Ok(webhook)
+}
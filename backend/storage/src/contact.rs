@@ -0,0 +1,340 @@
+use chrono::{DateTime, Utc};
+use sqlx::{PgConnection, PgPool, Postgres, Transaction};
+use televent_domain::UserId;
+
+use crate::StorageResult;
+
+#[derive(Clone)]
+pub struct ContactRepository {
+    pool: PgPool,
+}
+
+impl ContactRepository {
+    #[must_use]
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn begin(&self) -> StorageResult<ContactTransaction<'_>> {
+        let tx = self.pool.begin().await?;
+        Ok(ContactTransaction { tx })
+    }
+
+    /// All contacts of the user, ordered by name.
+    pub async fn list_contacts(&self, user_id: UserId) -> StorageResult<Vec<Contact>> {
+        list_contacts(&self.pool, user_id).await
+    }
+
+    pub async fn get_contact(&self, user_id: UserId, uid: &str) -> StorageResult<Option<Contact>> {
+        get_contact(&self.pool, user_id, uid).await
+    }
+
+    pub async fn get_contacts_by_uids(
+        &self,
+        user_id: UserId,
+        uids: &[&str],
+    ) -> StorageResult<Vec<Contact>> {
+        get_contacts_by_uids(&self.pool, user_id, uids).await
+    }
+
+    /// Inputs of the address book ctag.
+    pub async fn address_book_state(&self, user_id: UserId) -> StorageResult<AddressBookState> {
+        address_book_state(&self.pool, user_id).await
+    }
+
+    /// People the user invited to their events, most often invited first.
+    pub async fn frequent_attendees(
+        &self,
+        user_id: UserId,
+        limit: i64,
+    ) -> StorageResult<Vec<FrequentAttendee>> {
+        frequent_attendees(&self.pool, user_id, limit).await
+    }
+}
+
+pub struct ContactTransaction<'a> {
+    tx: Transaction<'a, Postgres>,
+}
+
+impl ContactTransaction<'_> {
+    pub async fn ensure_user(
+        &mut self,
+        telegram_id: i64,
+        username: Option<&str>,
+    ) -> StorageResult<crate::calendar::User> {
+        crate::calendar::ensure_user_tx(&mut self.tx, telegram_id, username).await
+    }
+
+    /// Lock the contact row so conditional writes see a stable etag.
+    pub async fn lock_contact(
+        &mut self,
+        user_id: UserId,
+        uid: &str,
+    ) -> StorageResult<Option<Contact>> {
+        self::lock_contact_tx(&mut self.tx, user_id, uid).await
+    }
+
+    pub async fn count_contacts(&mut self, user_id: UserId) -> StorageResult<i64> {
+        self::count_contacts_tx(&mut self.tx, user_id).await
+    }
+
+    pub async fn upsert_contact(&mut self, contact: StoredContact) -> StorageResult<Contact> {
+        self::upsert_contact_tx(&mut self.tx, contact).await
+    }
+
+    pub async fn delete_contact(&mut self, user_id: UserId, uid: &str) -> StorageResult<bool> {
+        self::delete_contact_tx(&mut self.tx, user_id, uid).await
+    }
+
+    pub async fn commit(self) -> StorageResult<()> {
+        self.tx.commit().await?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Contact {
+    pub user_id: i64,
+    pub uid: String,
+    pub full_name: String,
+    pub email: Option<String>,
+    pub telegram_username: Option<String>,
+    pub vcard: String,
+    pub etag: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+pub struct StoredContact {
+    pub user_id: UserId,
+    pub uid: String,
+    pub full_name: String,
+    pub email: Option<String>,
+    pub telegram_username: Option<String>,
+    pub vcard: String,
+    pub etag: String,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct AddressBookState {
+    pub contact_count: i64,
+    pub last_modified: Option<DateTime<Utc>>,
+}
+
+/// Someone the user has invited before, aggregated over all their events
+#[derive(Debug, Clone)]
+pub struct FrequentAttendee {
+    pub email: String,
+    /// Telegram ID for internal users
+    pub telegram_id: Option<i64>,
+    pub telegram_username: Option<String>,
+    pub invite_count: i64,
+    pub last_invited_at: DateTime<Utc>,
+}
+
+async fn list_contacts(pool: &PgPool, user_id: UserId) -> StorageResult<Vec<Contact>> {
+    let contacts = sqlx::query_as!(
+        Contact,
+        r#"
+        SELECT user_id, uid, full_name, email, telegram_username, vcard, etag, created_at,
+               updated_at
+        FROM contacts
+        WHERE user_id = $1
+        ORDER BY lower(full_name) ASC, uid ASC
+        "#,
+        user_id.inner()
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(contacts)
+}
+
+pub(crate) async fn list_contacts_tx(
+    conn: &mut PgConnection,
+    user_id: UserId,
+) -> StorageResult<Vec<Contact>> {
+    let contacts = sqlx::query_as!(
+        Contact,
+        r#"
+        SELECT user_id, uid, full_name, email, telegram_username, vcard, etag, created_at,
+               updated_at
+        FROM contacts
+        WHERE user_id = $1
+        ORDER BY lower(full_name) ASC, uid ASC
+        "#,
+        user_id.inner()
+    )
+    .fetch_all(conn)
+    .await?;
+
+    Ok(contacts)
+}
+
+async fn get_contact(pool: &PgPool, user_id: UserId, uid: &str) -> StorageResult<Option<Contact>> {
+    let contact = sqlx::query_as!(
+        Contact,
+        r#"
+        SELECT user_id, uid, full_name, email, telegram_username, vcard, etag, created_at,
+               updated_at
+        FROM contacts
+        WHERE user_id = $1 AND uid = $2
+        "#,
+        user_id.inner(),
+        uid
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(contact)
+}
+
+async fn get_contacts_by_uids(
+    pool: &PgPool,
+    user_id: UserId,
+    uids: &[&str],
+) -> StorageResult<Vec<Contact>> {
+    let contacts = sqlx::query_as!(
+        Contact,
+        r#"
+        SELECT user_id, uid, full_name, email, telegram_username, vcard, etag, created_at,
+               updated_at
+        FROM contacts
+        WHERE user_id = $1 AND uid = ANY($2)
+        ORDER BY uid
+        "#,
+        user_id.inner(),
+        uids as &[&str]
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(contacts)
+}
+
+async fn address_book_state(pool: &PgPool, user_id: UserId) -> StorageResult<AddressBookState> {
+    let state = sqlx::query_as!(
+        AddressBookState,
+        r#"
+        SELECT COUNT(*) AS "contact_count!", MAX(updated_at) AS last_modified
+        FROM contacts
+        WHERE user_id = $1
+        "#,
+        user_id.inner()
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(state)
+}
+
+async fn frequent_attendees(
+    pool: &PgPool,
+    user_id: UserId,
+    limit: i64,
+) -> StorageResult<Vec<FrequentAttendee>> {
+    let attendees = sqlx::query_as!(
+        FrequentAttendee,
+        r#"
+        SELECT ea.email, ea.user_id AS telegram_id, u.telegram_username,
+               COUNT(*) AS "invite_count!", MAX(ea.created_at) AS "last_invited_at!"
+        FROM event_attendees ea
+        JOIN events e ON ea.event_id = e.id
+        LEFT JOIN users u ON ea.user_id = u.telegram_id
+        WHERE e.user_id = $1
+          AND ea.role <> 'ORGANIZER'
+          AND ea.user_id IS DISTINCT FROM $1
+        GROUP BY ea.email, ea.user_id, u.telegram_username
+        ORDER BY COUNT(*) DESC, MAX(ea.created_at) DESC
+        LIMIT $2
+        "#,
+        user_id.inner(),
+        limit
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(attendees)
+}
+
+async fn lock_contact_tx(
+    conn: &mut PgConnection,
+    user_id: UserId,
+    uid: &str,
+) -> StorageResult<Option<Contact>> {
+    let contact = sqlx::query_as!(
+        Contact,
+        r#"
+        SELECT user_id, uid, full_name, email, telegram_username, vcard, etag, created_at,
+               updated_at
+        FROM contacts
+        WHERE user_id = $1 AND uid = $2
+        FOR UPDATE
+        "#,
+        user_id.inner(),
+        uid
+    )
+    .fetch_optional(conn)
+    .await?;
+
+    Ok(contact)
+}
+
+async fn count_contacts_tx(conn: &mut PgConnection, user_id: UserId) -> StorageResult<i64> {
+    let count = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "count!" FROM contacts WHERE user_id = $1"#,
+        user_id.inner()
+    )
+    .fetch_one(conn)
+    .await?;
+
+    Ok(count)
+}
+
+async fn upsert_contact_tx(
+    conn: &mut PgConnection,
+    contact: StoredContact,
+) -> StorageResult<Contact> {
+    let contact = sqlx::query_as!(
+        Contact,
+        r#"
+        INSERT INTO contacts (user_id, uid, full_name, email, telegram_username, vcard, etag)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        ON CONFLICT (user_id, uid) DO UPDATE SET
+            full_name = EXCLUDED.full_name,
+            email = EXCLUDED.email,
+            telegram_username = EXCLUDED.telegram_username,
+            vcard = EXCLUDED.vcard,
+            etag = EXCLUDED.etag
+        RETURNING user_id, uid, full_name, email, telegram_username, vcard, etag, created_at,
+                  updated_at
+        "#,
+        contact.user_id.inner(),
+        contact.uid,
+        contact.full_name,
+        contact.email,
+        contact.telegram_username,
+        contact.vcard,
+        contact.etag
+    )
+    .fetch_one(conn)
+    .await?;
+
+    Ok(contact)
+}
+
+async fn delete_contact_tx(
+    conn: &mut PgConnection,
+    user_id: UserId,
+    uid: &str,
+) -> StorageResult<bool> {
+    let result = sqlx::query!(
+        "DELETE FROM contacts WHERE user_id = $1 AND uid = $2",
+        user_id.inner(),
+        uid
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
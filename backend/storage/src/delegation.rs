@@ -0,0 +1,128 @@
+//! Calendar delegation: users an owner lets write to their calendar.
+
+use chrono::{DateTime, Utc};
+use sqlx::{PgConnection, PgPool};
+use televent_domain::UserId;
+
+use crate::StorageResult;
+
+/// The other side of a delegation grant
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DelegateRecord {
+    pub user_id: UserId,
+    pub username: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+struct DelegateRow {
+    user_id: i64,
+    username: Option<String>,
+    created_at: DateTime<Utc>,
+}
+
+impl From<DelegateRow> for DelegateRecord {
+    fn from(row: DelegateRow) -> Self {
+        Self {
+            user_id: UserId::new(row.user_id),
+            username: row.username,
+            created_at: row.created_at,
+        }
+    }
+}
+
+/// `false` when `delegate` already had access
+pub(crate) async fn grant(pool: &PgPool, owner: UserId, delegate: UserId) -> StorageResult<bool> {
+    let result = sqlx::query!(
+        r#"
+        INSERT INTO calendar_delegates (owner_id, delegate_id)
+        VALUES ($1, $2)
+        ON CONFLICT (owner_id, delegate_id) DO NOTHING
+        "#,
+        owner.inner(),
+        delegate.inner()
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// `false` when `delegate` had no access
+pub(crate) async fn revoke(pool: &PgPool, owner: UserId, delegate: UserId) -> StorageResult<bool> {
+    let result = sqlx::query!(
+        r#"
+        DELETE FROM calendar_delegates
+        WHERE owner_id = $1 AND delegate_id = $2
+        "#,
+        owner.inner(),
+        delegate.inner()
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Users `owner` lets manage their calendar, oldest grant first
+pub(crate) async fn list_delegates(
+    pool: &PgPool,
+    owner: UserId,
+) -> StorageResult<Vec<DelegateRecord>> {
+    let rows = sqlx::query_as!(
+        DelegateRow,
+        r#"
+        SELECT d.delegate_id AS user_id, u.telegram_username AS username, d.created_at
+        FROM calendar_delegates d
+        JOIN users u ON u.telegram_id = d.delegate_id
+        WHERE d.owner_id = $1
+        ORDER BY d.created_at
+        "#,
+        owner.inner()
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(DelegateRecord::from).collect())
+}
+
+/// Owners whose calendars `delegate` may manage, oldest grant first
+pub(crate) async fn list_owners(
+    pool: &PgPool,
+    delegate: UserId,
+) -> StorageResult<Vec<DelegateRecord>> {
+    let rows = sqlx::query_as!(
+        DelegateRow,
+        r#"
+        SELECT d.owner_id AS user_id, u.telegram_username AS username, d.created_at
+        FROM calendar_delegates d
+        JOIN users u ON u.telegram_id = d.owner_id
+        WHERE d.delegate_id = $1
+        ORDER BY d.created_at
+        "#,
+        delegate.inner()
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(DelegateRecord::from).collect())
+}
+
+pub(crate) async fn is_delegate(
+    conn: &mut PgConnection,
+    owner: UserId,
+    delegate: UserId,
+) -> StorageResult<bool> {
+    let granted = sqlx::query_scalar!(
+        r#"
+        SELECT EXISTS (
+            SELECT 1 FROM calendar_delegates WHERE owner_id = $1 AND delegate_id = $2
+        ) AS "granted!"
+        "#,
+        owner.inner(),
+        delegate.inner()
+    )
+    .fetch_one(conn)
+    .await?;
+
+    Ok(granted)
+}
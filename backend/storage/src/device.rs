@@ -1,10 +1,8 @@
 use chrono::{DateTime, Utc};
-use sqlx::{PgConnection, PgPool, Postgres, Transaction};
-use televent_domain::UserId;
-use uuid::Uuid;
+use sqlx::{PgConnection, PgPool};
+use televent_domain::{DeviceId, UserId};
 
 use crate::StorageResult;
-use crate::calendar::User;
 
 #[derive(Clone)]
 pub struct DeviceRepository {
@@ -17,16 +15,33 @@ impl DeviceRepository {
         Self { pool }
     }
 
-    pub async fn begin(&self) -> StorageResult<DeviceTransaction<'_>> {
-        let tx = self.pool.begin().await?;
-        Ok(DeviceTransaction { tx })
+    /// Store a password, creating the user if needed; `None` when the user
+    /// already has `max_devices` of them
+    pub async fn create_device_password(
+        &self,
+        username: Option<&str>,
+        password: StoredDevicePassword,
+        max_devices: i64,
+    ) -> StorageResult<Option<DevicePasswordRecord>> {
+        let mut tx = self.pool.begin().await?;
+        crate::calendar::ensure_user_tx(&mut tx, password.user_id.inner(), username).await?;
+        if count_device_passwords_tx(&mut tx, password.user_id).await? >= max_devices {
+            return Ok(None);
+        }
+        let device = insert_device_password_tx(&mut tx, password).await?;
+        tx.commit().await?;
+        Ok(Some(device))
     }
 
+    /// Most recently used first, counting requests logged since
+    /// `recent_since`; `active_since` drops devices not used since then
     pub async fn list_device_passwords(
         &self,
         user_id: UserId,
-    ) -> StorageResult<Vec<DevicePasswordRecord>> {
-        list_device_passwords(&self.pool, user_id).await
+        recent_since: DateTime<Utc>,
+        active_since: Option<DateTime<Utc>>,
+    ) -> StorageResult<Vec<DevicePasswordSummary>> {
+        list_device_passwords(&self.pool, user_id, recent_since, active_since).await
     }
 
     pub async fn list_device_password_hashes(
@@ -40,49 +55,49 @@ impl DeviceRepository {
     pub async fn delete_device_password(
         &self,
         user_id: UserId,
-        device_id: Uuid,
+        device_id: DeviceId,
     ) -> StorageResult<bool> {
         delete_device_password(&self.pool, user_id, device_id).await
     }
 
-    pub async fn touch_device_password(&self, device_id: Uuid) -> StorageResult<()> {
+    pub async fn touch_device_password(&self, device_id: DeviceId) -> StorageResult<()> {
         touch_device_password(&self.pool, device_id).await
     }
-}
-
-pub struct DeviceTransaction<'a> {
-    tx: Transaction<'a, Postgres>,
-}
-
-impl DeviceTransaction<'_> {
-    pub async fn ensure_user(
-        &mut self,
-        telegram_id: i64,
-        username: Option<&str>,
-    ) -> StorageResult<User> {
-        crate::calendar::ensure_user_tx(&mut self.tx, telegram_id, username).await
-    }
 
-    pub async fn count_device_passwords(&mut self, user_id: UserId) -> StorageResult<i64> {
-        self::count_device_passwords_tx(&mut self.tx, user_id).await
+    /// Replace the device's hash if it is still `current_hash`, so a
+    /// concurrent revocation or rehash wins
+    pub async fn replace_password_hash(
+        &self,
+        device_id: DeviceId,
+        current_hash: &str,
+        new_hash: &str,
+    ) -> StorageResult<bool> {
+        replace_password_hash(&self.pool, device_id, current_hash, new_hash).await
     }
 
-    pub async fn insert_device_password(
-        &mut self,
-        password: StoredDevicePassword,
-    ) -> StorageResult<DevicePasswordRecord> {
-        self::insert_device_password_tx(&mut self.tx, password).await
+    /// Log a request, keeping only the newest `keep` rows for the device
+    pub async fn insert_device_activity(
+        &self,
+        activity: NewDeviceActivity,
+        keep: i64,
+    ) -> StorageResult<()> {
+        insert_device_activity(&self.pool, activity, keep).await
     }
 
-    pub async fn commit(self) -> StorageResult<()> {
-        self.tx.commit().await?;
-        Ok(())
+    /// Logged requests of a device, newest first, or `None` if the user
+    /// has no such device
+    pub async fn list_device_activity(
+        &self,
+        user_id: UserId,
+        device_id: DeviceId,
+    ) -> StorageResult<Option<Vec<DeviceActivityRecord>>> {
+        list_device_activity(&self.pool, user_id, device_id).await
     }
 }
 
-#[derive(Debug, Clone, sqlx::FromRow)]
+#[derive(Debug, Clone)]
 pub struct DevicePasswordRecord {
-    pub id: Uuid,
+    pub id: DeviceId,
     pub user_id: i64,
     pub password_hash: String,
     pub name: String,
@@ -90,12 +105,50 @@ pub struct DevicePasswordRecord {
     pub last_used_at: Option<DateTime<Utc>>,
 }
 
-#[derive(Debug, Clone, sqlx::FromRow)]
+/// A device password as listed to its owner
+#[derive(Debug, Clone)]
+pub struct DevicePasswordSummary {
+    pub id: DeviceId,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    /// Logged requests since the cutoff the list was asked for
+    pub recent_requests: i64,
+}
+
+#[derive(Debug, Clone)]
 pub struct DevicePasswordHash {
-    pub id: Uuid,
+    pub id: DeviceId,
     pub password_hash: String,
 }
 
+#[derive(Debug, Clone)]
+pub struct NewDeviceActivity {
+    pub device_id: DeviceId,
+    pub method: String,
+    pub path: String,
+    pub user_agent: Option<String>,
+    pub status: i16,
+    pub sync_token: Option<String>,
+    /// UID of the event a stale PUT was rejected for
+    pub conflict_uid: Option<String>,
+    pub client_sequence: Option<i32>,
+    pub server_sequence: Option<i32>,
+}
+
+#[derive(Debug, Clone)]
+pub struct DeviceActivityRecord {
+    pub method: String,
+    pub path: String,
+    pub user_agent: Option<String>,
+    pub status: i16,
+    pub sync_token: Option<String>,
+    pub conflict_uid: Option<String>,
+    pub client_sequence: Option<i32>,
+    pub server_sequence: Option<i32>,
+    pub created_at: DateTime<Utc>,
+}
+
 pub struct StoredDevicePassword {
     pub user_id: UserId,
     pub name: String,
@@ -103,11 +156,12 @@ pub struct StoredDevicePassword {
 }
 
 async fn count_device_passwords_tx(conn: &mut PgConnection, user_id: UserId) -> StorageResult<i64> {
-    let count =
-        sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM device_passwords WHERE user_id = $1")
-            .bind(user_id.inner())
-            .fetch_one(conn)
-            .await?;
+    let count = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "count!" FROM device_passwords WHERE user_id = $1"#,
+        user_id.inner()
+    )
+    .fetch_one(conn)
+    .await?;
 
     Ok(count)
 }
@@ -116,16 +170,18 @@ async fn insert_device_password_tx(
     conn: &mut PgConnection,
     password: StoredDevicePassword,
 ) -> StorageResult<DevicePasswordRecord> {
-    let device = sqlx::query_as::<_, DevicePasswordRecord>(
+    let device = sqlx::query_as!(
+        DevicePasswordRecord,
         r#"
         INSERT INTO device_passwords (user_id, device_name, password_hash)
         VALUES ($1, $2, $3)
-        RETURNING id, user_id, password_hash, device_name AS name, created_at, last_used_at
+        RETURNING id AS "id: DeviceId", user_id, password_hash, device_name AS name,
+                  created_at, last_used_at
         "#,
+        password.user_id.inner(),
+        password.name,
+        password.password_hash
     )
-    .bind(password.user_id.inner())
-    .bind(password.name)
-    .bind(password.password_hash)
     .fetch_one(conn)
     .await?;
 
@@ -135,16 +191,31 @@ async fn insert_device_password_tx(
 async fn list_device_passwords(
     pool: &PgPool,
     user_id: UserId,
-) -> StorageResult<Vec<DevicePasswordRecord>> {
-    let devices = sqlx::query_as::<_, DevicePasswordRecord>(
+    recent_since: DateTime<Utc>,
+    active_since: Option<DateTime<Utc>>,
+) -> StorageResult<Vec<DevicePasswordSummary>> {
+    let devices = sqlx::query_as!(
+        DevicePasswordSummary,
         r#"
-        SELECT id, user_id, password_hash, device_name AS name, created_at, last_used_at
-        FROM device_passwords
-        WHERE user_id = $1
-        ORDER BY created_at DESC
+        SELECT
+            d.id AS "id: DeviceId",
+            d.device_name AS name,
+            d.created_at,
+            d.last_used_at,
+            (
+                SELECT COUNT(*)
+                FROM device_activity a
+                WHERE a.device_id = d.id AND a.created_at >= $2
+            ) AS "recent_requests!"
+        FROM device_passwords d
+        WHERE d.user_id = $1
+          AND ($3::timestamptz IS NULL OR d.last_used_at >= $3)
+        ORDER BY d.last_used_at DESC NULLS LAST, d.created_at DESC
         "#,
+        user_id.inner(),
+        recent_since,
+        active_since
     )
-    .bind(user_id.inner())
     .fetch_all(pool)
     .await?;
 
@@ -156,17 +227,18 @@ async fn list_device_password_hashes(
     user_id: UserId,
     limit: i64,
 ) -> StorageResult<Vec<DevicePasswordHash>> {
-    let devices = sqlx::query_as::<_, DevicePasswordHash>(
+    let devices = sqlx::query_as!(
+        DevicePasswordHash,
         r#"
-        SELECT id, password_hash
+        SELECT id AS "id: DeviceId", password_hash
         FROM device_passwords
         WHERE user_id = $1
         ORDER BY last_used_at DESC NULLS LAST, created_at DESC
         LIMIT $2
         "#,
+        user_id.inner(),
+        limit
     )
-    .bind(user_id.inner())
-    .bind(limit)
     .fetch_all(pool)
     .await?;
 
@@ -176,22 +248,129 @@ async fn list_device_password_hashes(
 async fn delete_device_password(
     pool: &PgPool,
     user_id: UserId,
-    device_id: Uuid,
+    device_id: DeviceId,
 ) -> StorageResult<bool> {
-    let result = sqlx::query("DELETE FROM device_passwords WHERE id = $1 AND user_id = $2")
-        .bind(device_id)
-        .bind(user_id.inner())
-        .execute(pool)
-        .await?;
+    let result = sqlx::query!(
+        "DELETE FROM device_passwords WHERE id = $1 AND user_id = $2",
+        device_id as DeviceId,
+        user_id.inner()
+    )
+    .execute(pool)
+    .await?;
 
     Ok(result.rows_affected() > 0)
 }
 
-async fn touch_device_password(pool: &PgPool, device_id: Uuid) -> StorageResult<()> {
-    sqlx::query("UPDATE device_passwords SET last_used_at = NOW() WHERE id = $1")
-        .bind(device_id)
-        .execute(pool)
-        .await?;
+async fn touch_device_password(pool: &PgPool, device_id: DeviceId) -> StorageResult<()> {
+    sqlx::query!(
+        "UPDATE device_passwords SET last_used_at = NOW() WHERE id = $1",
+        device_id as DeviceId
+    )
+    .execute(pool)
+    .await?;
 
     Ok(())
 }
+
+async fn replace_password_hash(
+    pool: &PgPool,
+    device_id: DeviceId,
+    current_hash: &str,
+    new_hash: &str,
+) -> StorageResult<bool> {
+    let result = sqlx::query!(
+        "UPDATE device_passwords SET password_hash = $3 WHERE id = $1 AND password_hash = $2",
+        device_id as DeviceId,
+        current_hash,
+        new_hash
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+async fn insert_device_activity(
+    pool: &PgPool,
+    activity: NewDeviceActivity,
+    keep: i64,
+) -> StorageResult<()> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO device_activity (
+            device_id, method, path, user_agent, status, sync_token,
+            conflict_uid, client_sequence, server_sequence
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        "#,
+        activity.device_id as DeviceId,
+        activity.method,
+        activity.path,
+        activity.user_agent,
+        activity.status,
+        activity.sync_token,
+        activity.conflict_uid,
+        activity.client_sequence,
+        activity.server_sequence
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query!(
+        r#"
+        DELETE FROM device_activity
+        WHERE device_id = $1
+          AND id NOT IN (
+              SELECT id
+              FROM device_activity
+              WHERE device_id = $1
+              ORDER BY created_at DESC
+              LIMIT $2
+          )
+        "#,
+        activity.device_id as DeviceId,
+        keep
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+async fn list_device_activity(
+    pool: &PgPool,
+    user_id: UserId,
+    device_id: DeviceId,
+) -> StorageResult<Option<Vec<DeviceActivityRecord>>> {
+    let owned = sqlx::query_scalar!(
+        r#"
+        SELECT EXISTS (SELECT 1 FROM device_passwords WHERE id = $1 AND user_id = $2) AS "owned!"
+        "#,
+        device_id as DeviceId,
+        user_id.inner()
+    )
+    .fetch_one(pool)
+    .await?;
+    if !owned {
+        return Ok(None);
+    }
+
+    let activity = sqlx::query_as!(
+        DeviceActivityRecord,
+        r#"
+        SELECT method, path, user_agent, status, sync_token,
+               conflict_uid, client_sequence, server_sequence, created_at
+        FROM device_activity
+        WHERE device_id = $1
+        ORDER BY created_at DESC
+        "#,
+        device_id as DeviceId
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(Some(activity))
+}
@@ -0,0 +1,221 @@
+//! Opt-in slow query diagnostics
+//!
+//! With a threshold set, wrapped queries that run longer are logged with
+//! their SQL and redacted parameters, and an `EXPLAIN ANALYZE` of the same
+//! statement is saved to `slow_query_plans` so self-hosters can find missing
+//! indexes. Without one, queries run as they are.
+//!
+//! Watched queries are also counted per task, which `count_queries` reports
+//! whether or not the log is enabled.
+
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, NaiveDate, Utc};
+use sqlx::postgres::PgArguments;
+use sqlx::query::Query;
+use sqlx::{PgPool, Postgres, Row};
+use uuid::Uuid;
+
+use crate::StorageResult;
+
+/// At most one plan per query name is captured in this window, so a burst of
+/// slow requests doesn't double the load by re-running each of them
+const PLAN_CAPTURE_INTERVAL: Duration = Duration::from_secs(60);
+
+tokio::task_local! {
+    static QUERY_COUNT: Cell<usize>;
+}
+
+/// Run `future` and count the watched queries it issued
+pub async fn count_queries<F: Future>(future: F) -> (F::Output, usize) {
+    QUERY_COUNT
+        .scope(Cell::new(0), async {
+            let output = future.await;
+            (output, QUERY_COUNT.with(Cell::get))
+        })
+        .await
+}
+
+/// Bound parameter of a watched query, kept so it can be replayed under
+/// `EXPLAIN` and described without its value in logs
+#[derive(Debug, Clone)]
+pub enum QueryParam {
+    BigInt(Option<i64>),
+    Text(Option<String>),
+    Uuid(Option<Uuid>),
+    Timestamp(Option<DateTime<Utc>>),
+    Date(Option<NaiveDate>),
+    TextArray(Vec<String>),
+    UuidArray(Vec<Uuid>),
+}
+
+impl QueryParam {
+    /// Type and size only; values may identify users
+    fn redacted(&self) -> String {
+        match self {
+            Self::BigInt(None)
+            | Self::Text(None)
+            | Self::Uuid(None)
+            | Self::Timestamp(None)
+            | Self::Date(None) => "null".to_string(),
+            Self::BigInt(Some(_)) => "bigint".to_string(),
+            Self::Text(Some(value)) => format!("text({})", value.chars().count()),
+            Self::Uuid(Some(_)) => "uuid".to_string(),
+            Self::Timestamp(Some(_)) => "timestamptz".to_string(),
+            Self::Date(Some(_)) => "date".to_string(),
+            Self::TextArray(values) => format!("text[{}]", values.len()),
+            Self::UuidArray(values) => format!("uuid[{}]", values.len()),
+        }
+    }
+
+    fn bind<'q>(self, query: Query<'q, Postgres, PgArguments>) -> Query<'q, Postgres, PgArguments> {
+        match self {
+            Self::BigInt(value) => query.bind(value),
+            Self::Text(value) => query.bind(value),
+            Self::Uuid(value) => query.bind(value),
+            Self::Timestamp(value) => query.bind(value),
+            Self::Date(value) => query.bind(value),
+            Self::TextArray(values) => query.bind(values),
+            Self::UuidArray(values) => query.bind(values),
+        }
+    }
+}
+
+/// Times watched queries against a threshold; disabled by default
+#[derive(Clone, Default)]
+pub struct SlowQueryLog {
+    inner: Option<Arc<SlowQueryLogInner>>,
+}
+
+struct SlowQueryLogInner {
+    pool: PgPool,
+    threshold: Duration,
+    last_capture: Mutex<HashMap<&'static str, Instant>>,
+}
+
+impl SlowQueryLog {
+    /// Watch queries slower than `threshold`; `None` disables the log
+    #[must_use]
+    pub fn new(pool: PgPool, threshold: Option<Duration>) -> Self {
+        Self {
+            inner: threshold.map(|threshold| {
+                Arc::new(SlowQueryLogInner {
+                    pool,
+                    threshold,
+                    last_capture: Mutex::new(HashMap::new()),
+                })
+            }),
+        }
+    }
+
+    #[must_use]
+    pub fn is_enabled(&self) -> bool {
+        self.inner.is_some()
+    }
+
+    /// Run `query`, reporting it as `name` if it exceeds the threshold.
+    ///
+    /// `params` is only called for slow queries and must list the values
+    /// bound to `sql` in order.
+    pub(crate) async fn observe<T>(
+        &self,
+        name: &'static str,
+        sql: &str,
+        params: impl FnOnce() -> Vec<QueryParam>,
+        query: impl Future<Output = StorageResult<T>>,
+    ) -> StorageResult<T> {
+        // Outside `count_queries` there is nothing to count
+        let _ = QUERY_COUNT.try_with(|count| count.set(count.get() + 1));
+
+        let Some(inner) = &self.inner else {
+            return query.await;
+        };
+
+        let started = Instant::now();
+        let result = query.await;
+        let elapsed = started.elapsed();
+        if elapsed < inner.threshold || result.is_err() {
+            return result;
+        }
+
+        let params = params();
+        let redacted: Vec<String> = params.iter().map(QueryParam::redacted).collect();
+        tracing::warn!(
+            query = name,
+            elapsed_ms = elapsed.as_millis() as u64,
+            sql = sql.trim(),
+            params = ?redacted,
+            "Slow query"
+        );
+
+        if inner.should_capture(name) {
+            let inner = inner.clone();
+            let sql = sql.to_string();
+            tokio::spawn(async move {
+                if let Err(e) = inner
+                    .capture_plan(name, &sql, params, &redacted, elapsed)
+                    .await
+                {
+                    tracing::warn!("Failed to capture plan for slow query {}: {}", name, e);
+                }
+            });
+        }
+
+        result
+    }
+}
+
+impl SlowQueryLogInner {
+    fn should_capture(&self, name: &'static str) -> bool {
+        let mut last_capture = self
+            .last_capture
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let now = Instant::now();
+        match last_capture.get(name) {
+            Some(at) if now.duration_since(*at) < PLAN_CAPTURE_INTERVAL => false,
+            _ => {
+                last_capture.insert(name, now);
+                true
+            }
+        }
+    }
+
+    /// Re-run the statement under `EXPLAIN ANALYZE` and store the plan. The
+    /// transaction is rolled back so a watched write wouldn't apply twice.
+    async fn capture_plan(
+        &self,
+        name: &str,
+        sql: &str,
+        params: Vec<QueryParam>,
+        redacted: &[String],
+        elapsed: Duration,
+    ) -> StorageResult<()> {
+        let explain = format!("EXPLAIN (ANALYZE, BUFFERS, FORMAT JSON) {sql}");
+        let mut tx = self.pool.begin().await?;
+        let query = params
+            .into_iter()
+            .fold(sqlx::query(&explain), |query, param| param.bind(query));
+        let plan: serde_json::Value = query.fetch_one(&mut *tx).await?.try_get(0)?;
+        tx.rollback().await?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO slow_query_plans (query_name, sql, params, duration_ms, plan)
+            VALUES ($1, $2, $3, $4, $5)
+            "#,
+            name,
+            sql.trim(),
+            serde_json::to_value(redacted)?,
+            elapsed.as_secs_f64() * 1000.0,
+            plan
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
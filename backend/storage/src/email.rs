@@ -0,0 +1,264 @@
+use chrono::{DateTime, Duration, Utc};
+use sqlx::{PgConnection, PgPool, Postgres, Transaction};
+use televent_domain::{OutboxPayload, UserId};
+use uuid::Uuid;
+
+use crate::StorageResult;
+
+/// Pending signups and external invites of events the address was meant for.
+const AFFECTED_EVENTS_LIMIT: i64 = 20;
+
+#[derive(Clone)]
+pub struct EmailRepository {
+    pool: PgPool,
+}
+
+impl EmailRepository {
+    #[must_use]
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn begin(&self) -> StorageResult<EmailTransaction<'_>> {
+        let tx = self.pool.begin().await?;
+        Ok(EmailTransaction { tx })
+    }
+
+    pub async fn is_suppressed(&self, email: &str) -> StorageResult<bool> {
+        is_suppressed(&self.pool, email).await
+    }
+
+    /// Put the address on the suppression list, replacing an earlier reason.
+    pub async fn suppress(&self, email: &str, reason: &str) -> StorageResult<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO email_suppressions (email, reason)
+            VALUES (LOWER($1), $2)
+            ON CONFLICT (email) DO UPDATE SET reason = EXCLUDED.reason, detail = NULL
+            "#,
+            email,
+            reason
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Claim a send slot for the address.
+    ///
+    /// Sends to the same address are serialized, so concurrent jobs cannot
+    /// both take the last slot of the hour.
+    pub async fn reserve_send(&self, email: &str, hourly_cap: i64) -> StorageResult<SendSlot> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query!(
+            "SELECT pg_advisory_xact_lock(hashtext('email_sends:' || LOWER($1)))",
+            email
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        if is_suppressed_tx(&mut tx, email).await? {
+            return Ok(SendSlot::Suppressed);
+        }
+
+        let oldest_in_window = sqlx::query_as::<_, (i64, Option<DateTime<Utc>>)>(
+            r#"
+            SELECT COUNT(*), MIN(sent_at)
+            FROM email_sends
+            WHERE email = LOWER($1)
+              AND sent_at >= NOW() - INTERVAL '1 hour'
+            "#,
+        )
+        .bind(email)
+        .fetch_one(&mut *tx)
+        .await?;
+        if let (sent, Some(oldest)) = oldest_in_window
+            && sent >= hourly_cap
+        {
+            return Ok(SendSlot::Throttled {
+                until: oldest + Duration::hours(1),
+            });
+        }
+
+        sqlx::query!("INSERT INTO email_sends (email) VALUES (LOWER($1))", email)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(SendSlot::Granted)
+    }
+}
+
+/// Outcome of [`EmailRepository::reserve_send`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SendSlot {
+    Granted,
+    Suppressed,
+    /// The hourly cap is used up; the next slot frees at `until`
+    Throttled {
+        until: DateTime<Utc>,
+    },
+}
+
+pub struct EmailTransaction<'a> {
+    tx: Transaction<'a, Postgres>,
+}
+
+impl EmailTransaction<'_> {
+    pub async fn insert_feedback(&mut self, feedback: &EmailFeedbackWrite) -> StorageResult<()> {
+        self::insert_feedback_tx(&mut self.tx, feedback).await
+    }
+
+    /// Put the address on the suppression list.
+    ///
+    /// Returns `false` when it was already suppressed.
+    pub async fn suppress(
+        &mut self,
+        email: &str,
+        reason: &str,
+        detail: Option<&str>,
+    ) -> StorageResult<bool> {
+        self::suppress_tx(&mut self.tx, email, reason, detail).await
+    }
+
+    /// Events still waiting on the address: unconfirmed public signups and
+    /// external invites without an answer.
+    pub async fn affected_events(&mut self, email: &str) -> StorageResult<Vec<AffectedEvent>> {
+        self::affected_events_tx(&mut self.tx, email).await
+    }
+
+    pub async fn queue_outbox(&mut self, messages: &[OutboxPayload]) -> StorageResult<()> {
+        crate::calendar::queue_outbox_tx(&mut self.tx, messages).await
+    }
+
+    pub async fn commit(self) -> StorageResult<()> {
+        self.tx.commit().await?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct EmailFeedbackWrite {
+    pub provider: String,
+    pub email: String,
+    /// `hard_bounce`, `soft_bounce` or `complaint`
+    pub kind: &'static str,
+    pub detail: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct AffectedEvent {
+    pub event_id: Uuid,
+    pub organizer_id: UserId,
+    pub summary: String,
+}
+
+struct AffectedEventRow {
+    event_id: Uuid,
+    user_id: i64,
+    summary: String,
+}
+
+async fn is_suppressed(pool: &PgPool, email: &str) -> StorageResult<bool> {
+    let mut conn = pool.acquire().await?;
+    is_suppressed_tx(&mut conn, email).await
+}
+
+pub(crate) async fn is_suppressed_tx(conn: &mut PgConnection, email: &str) -> StorageResult<bool> {
+    let suppressed = sqlx::query_scalar!(
+        r#"
+        SELECT EXISTS (SELECT 1 FROM email_suppressions WHERE email = LOWER($1)) AS "suppressed!"
+        "#,
+        email
+    )
+    .fetch_one(conn)
+    .await?;
+
+    Ok(suppressed)
+}
+
+async fn insert_feedback_tx(
+    conn: &mut PgConnection,
+    feedback: &EmailFeedbackWrite,
+) -> StorageResult<()> {
+    sqlx::query!(
+        r#"
+        INSERT INTO email_feedback (provider, email, kind, detail)
+        VALUES ($1, LOWER($2), $3, $4)
+        "#,
+        &feedback.provider,
+        &feedback.email,
+        feedback.kind,
+        feedback.detail.as_deref()
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+async fn suppress_tx(
+    conn: &mut PgConnection,
+    email: &str,
+    reason: &str,
+    detail: Option<&str>,
+) -> StorageResult<bool> {
+    let result = sqlx::query!(
+        r#"
+        INSERT INTO email_suppressions (email, reason, detail)
+        VALUES (LOWER($1), $2, $3)
+        ON CONFLICT (email) DO NOTHING
+        "#,
+        email,
+        reason,
+        detail
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+async fn affected_events_tx(
+    conn: &mut PgConnection,
+    email: &str,
+) -> StorageResult<Vec<AffectedEvent>> {
+    let rows = sqlx::query_as!(
+        AffectedEventRow,
+        r#"
+        SELECT e.id AS event_id, e.user_id, e.summary
+        FROM events e
+        WHERE e.status <> 'CANCELLED'
+          AND (
+            EXISTS (
+                SELECT 1 FROM public_signups s
+                WHERE s.event_id = e.id
+                  AND s.email = LOWER($1)
+                  AND s.confirmed_at IS NULL
+            )
+            OR EXISTS (
+                SELECT 1 FROM event_attendees a
+                WHERE a.event_id = e.id
+                  AND LOWER(a.email) = LOWER($1)
+                  AND a.user_id IS NULL
+                  AND a.status = 'NEEDS-ACTION'
+            )
+          )
+        ORDER BY e.created_at DESC
+        LIMIT $2
+        "#,
+        email,
+        AFFECTED_EVENTS_LIMIT
+    )
+    .fetch_all(conn)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| AffectedEvent {
+            event_id: row.event_id,
+            organizer_id: UserId::new(row.user_id),
+            summary: row.summary,
+        })
+        .collect())
+}
@@ -0,0 +1,154 @@
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use televent_domain::UserId;
+
+use crate::StorageResult;
+
+#[derive(Clone)]
+pub struct FeatureFlagRepository {
+    pool: PgPool,
+}
+
+#[derive(Debug, Clone)]
+pub struct FeatureFlagRecord {
+    pub name: String,
+    pub description: String,
+    pub enabled: bool,
+    pub rollout_percent: i16,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FeatureFlagOverrideRecord {
+    pub user_id: i64,
+    pub enabled: bool,
+}
+
+/// Fields of a flag to create or replace
+#[derive(Debug, Clone)]
+pub struct FeatureFlagWrite {
+    pub name: String,
+    pub description: String,
+    pub enabled: bool,
+    pub rollout_percent: i16,
+}
+
+impl FeatureFlagRepository {
+    #[must_use]
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn list_flags(&self) -> StorageResult<Vec<FeatureFlagRecord>> {
+        let flags = sqlx::query_as!(
+            FeatureFlagRecord,
+            r#"
+            SELECT name, description, enabled, rollout_percent, updated_at
+            FROM feature_flags
+            ORDER BY name
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(flags)
+    }
+
+    pub async fn get_flag(&self, name: &str) -> StorageResult<Option<FeatureFlagRecord>> {
+        let flag = sqlx::query_as!(
+            FeatureFlagRecord,
+            r#"
+            SELECT name, description, enabled, rollout_percent, updated_at
+            FROM feature_flags
+            WHERE name = $1
+            "#,
+            name
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(flag)
+    }
+
+    pub async fn list_overrides(
+        &self,
+        name: &str,
+    ) -> StorageResult<Vec<FeatureFlagOverrideRecord>> {
+        let overrides = sqlx::query_as!(
+            FeatureFlagOverrideRecord,
+            r#"
+            SELECT user_id, enabled
+            FROM feature_flag_overrides
+            WHERE flag_name = $1
+            ORDER BY user_id
+            "#,
+            name
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(overrides)
+    }
+
+    pub async fn upsert_flag(&self, flag: &FeatureFlagWrite) -> StorageResult<FeatureFlagRecord> {
+        let flag = sqlx::query_as!(
+            FeatureFlagRecord,
+            r#"
+            INSERT INTO feature_flags (name, description, enabled, rollout_percent)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (name) DO UPDATE SET
+                description = EXCLUDED.description,
+                enabled = EXCLUDED.enabled,
+                rollout_percent = EXCLUDED.rollout_percent,
+                updated_at = NOW()
+            RETURNING name, description, enabled, rollout_percent, updated_at
+            "#,
+            &flag.name,
+            &flag.description,
+            flag.enabled,
+            flag.rollout_percent
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(flag)
+    }
+
+    /// Set or clear (`None`) a user's override.
+    ///
+    /// Returns `false` if the flag or the user doesn't exist.
+    pub async fn set_override(
+        &self,
+        name: &str,
+        user_id: UserId,
+        enabled: Option<bool>,
+    ) -> StorageResult<bool> {
+        let Some(enabled) = enabled else {
+            sqlx::query!(
+                "DELETE FROM feature_flag_overrides WHERE flag_name = $1 AND user_id = $2",
+                name,
+                user_id.inner()
+            )
+            .execute(&self.pool)
+            .await?;
+            return Ok(true);
+        };
+
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO feature_flag_overrides (flag_name, user_id, enabled)
+            SELECT f.name, u.telegram_id, $3
+            FROM feature_flags f, users u
+            WHERE f.name = $1 AND u.telegram_id = $2
+            ON CONFLICT (flag_name, user_id) DO UPDATE SET enabled = EXCLUDED.enabled
+            "#,
+            name,
+            user_id.inner(),
+            enabled
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
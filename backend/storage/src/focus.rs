@@ -0,0 +1,248 @@
+//! Focus-time rules and the events reserved for them.
+
+use chrono::{DateTime, Utc};
+use sqlx::{PgConnection, PgPool};
+use televent_domain::{EventStatus, UserId};
+use uuid::Uuid;
+
+use crate::StorageResult;
+use crate::calendar::parse_event_status;
+
+/// A weekly amount of focus time to keep free
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FocusRule {
+    pub id: Uuid,
+    pub user_id: UserId,
+    pub title: String,
+    pub blocks_per_week: i32,
+    pub duration_minutes: i32,
+    /// Local `HH:MM-HH:MM` window the blocks must fit in
+    pub working_hours: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// An event reserved for a rule
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FocusBlock {
+    pub rule_id: Uuid,
+    pub event_id: Uuid,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub status: EventStatus,
+}
+
+struct FocusBlockRow {
+    rule_id: Uuid,
+    event_id: Uuid,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    status: String,
+}
+
+impl TryFrom<FocusBlockRow> for FocusBlock {
+    type Error = crate::StorageError;
+
+    fn try_from(row: FocusBlockRow) -> Result<Self, Self::Error> {
+        Ok(Self {
+            rule_id: row.rule_id,
+            event_id: row.event_id,
+            start: row.start,
+            end: row.end,
+            status: parse_event_status(&row.status)?,
+        })
+    }
+}
+
+struct FocusRuleRow {
+    id: Uuid,
+    user_id: i64,
+    title: String,
+    blocks_per_week: i32,
+    duration_minutes: i32,
+    working_hours: Option<String>,
+    created_at: DateTime<Utc>,
+}
+
+impl From<FocusRuleRow> for FocusRule {
+    fn from(row: FocusRuleRow) -> Self {
+        Self {
+            id: row.id,
+            user_id: UserId::new(row.user_id),
+            title: row.title,
+            blocks_per_week: row.blocks_per_week,
+            duration_minutes: row.duration_minutes,
+            working_hours: row.working_hours,
+            created_at: row.created_at,
+        }
+    }
+}
+
+/// Fields of a rule as the user sets them
+#[derive(Debug, Clone, Copy)]
+pub struct FocusRuleFields<'a> {
+    pub title: &'a str,
+    pub blocks_per_week: i32,
+    pub duration_minutes: i32,
+    pub working_hours: Option<&'a str>,
+}
+
+pub(crate) async fn insert_rule(
+    pool: &PgPool,
+    user_id: UserId,
+    fields: FocusRuleFields<'_>,
+) -> StorageResult<FocusRule> {
+    let row = sqlx::query_as!(
+        FocusRuleRow,
+        r#"
+        INSERT INTO focus_rules (user_id, title, blocks_per_week, duration_minutes, working_hours)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING id, user_id, title, blocks_per_week, duration_minutes, working_hours, created_at
+        "#,
+        user_id.inner(),
+        fields.title,
+        fields.blocks_per_week,
+        fields.duration_minutes,
+        fields.working_hours
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(row.into())
+}
+
+/// `None` when the user has no such rule
+pub(crate) async fn update_rule(
+    conn: &mut PgConnection,
+    user_id: UserId,
+    id: Uuid,
+    fields: FocusRuleFields<'_>,
+) -> StorageResult<Option<FocusRule>> {
+    let row = sqlx::query_as!(
+        FocusRuleRow,
+        r#"
+        UPDATE focus_rules
+        SET title = $3, blocks_per_week = $4, duration_minutes = $5, working_hours = $6
+        WHERE id = $1 AND user_id = $2
+        RETURNING id, user_id, title, blocks_per_week, duration_minutes, working_hours, created_at
+        "#,
+        id,
+        user_id.inner(),
+        fields.title,
+        fields.blocks_per_week,
+        fields.duration_minutes,
+        fields.working_hours
+    )
+    .fetch_optional(conn)
+    .await?;
+
+    Ok(row.map(FocusRule::from))
+}
+
+/// The user's rules, oldest first
+pub(crate) async fn list_rules(
+    conn: &mut PgConnection,
+    user_id: UserId,
+) -> StorageResult<Vec<FocusRule>> {
+    let rows = sqlx::query_as!(
+        FocusRuleRow,
+        r#"
+        SELECT id, user_id, title, blocks_per_week, duration_minutes, working_hours, created_at
+        FROM focus_rules
+        WHERE user_id = $1
+        ORDER BY created_at, id
+        "#,
+        user_id.inner()
+    )
+    .fetch_all(conn)
+    .await?;
+
+    Ok(rows.into_iter().map(FocusRule::from).collect())
+}
+
+/// `false` when the user has no such rule; the rule's blocks are forgotten,
+/// their events stay
+pub(crate) async fn delete_rule(
+    conn: &mut PgConnection,
+    user_id: UserId,
+    id: Uuid,
+) -> StorageResult<bool> {
+    let result = sqlx::query!(
+        "DELETE FROM focus_rules WHERE id = $1 AND user_id = $2",
+        id,
+        user_id.inner()
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Users with at least one rule, after `after` in Telegram ID order
+pub(crate) async fn list_users(
+    pool: &PgPool,
+    after: Option<UserId>,
+    limit: i64,
+) -> StorageResult<Vec<UserId>> {
+    let ids = sqlx::query_scalar!(
+        r#"
+        SELECT DISTINCT user_id
+        FROM focus_rules
+        WHERE user_id > $1
+        ORDER BY user_id
+        LIMIT $2
+        "#,
+        after.map_or(i64::MIN, UserId::inner),
+        limit
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(ids.into_iter().map(UserId::new).collect())
+}
+
+/// Blocks of the user's rules overlapping `from..until`, earliest first
+pub(crate) async fn list_blocks(
+    conn: &mut PgConnection,
+    user_id: UserId,
+    from: DateTime<Utc>,
+    until: DateTime<Utc>,
+) -> StorageResult<Vec<FocusBlock>> {
+    let rows = sqlx::query_as!(
+        FocusBlockRow,
+        r#"
+        SELECT b.rule_id, b.event_id, e.start AS "start!", e."end" AS "end!",
+               e.status::text AS "status!"
+        FROM focus_blocks b
+        JOIN focus_rules r ON r.id = b.rule_id
+        JOIN events e ON e.id = b.event_id
+        WHERE r.user_id = $1
+          AND e.start IS NOT NULL AND e."end" IS NOT NULL
+          AND e.start < $3 AND e."end" > $2
+        ORDER BY e.start, b.event_id
+        "#,
+        user_id.inner(),
+        from,
+        until
+    )
+    .fetch_all(conn)
+    .await?;
+
+    rows.into_iter().map(FocusBlock::try_from).collect()
+}
+
+/// Record `event_id` as a block of the rule
+pub(crate) async fn link_block(
+    conn: &mut PgConnection,
+    rule_id: Uuid,
+    event_id: Uuid,
+) -> StorageResult<()> {
+    sqlx::query!(
+        "INSERT INTO focus_blocks (event_id, rule_id) VALUES ($1, $2)",
+        event_id,
+        rule_id
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
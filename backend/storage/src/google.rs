@@ -0,0 +1,463 @@
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use televent_domain::UserId;
+
+use crate::StorageResult;
+use crate::security::{ColumnCipher, GOOGLE_ACCESS_TOKEN, GOOGLE_REFRESH_TOKEN};
+
+#[derive(Clone)]
+pub struct GoogleRepository {
+    pool: PgPool,
+    cipher: ColumnCipher,
+}
+
+impl GoogleRepository {
+    #[must_use]
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            pool,
+            cipher: ColumnCipher::default(),
+        }
+    }
+
+    /// Seal OAuth tokens with `cipher`
+    #[must_use]
+    pub fn with_cipher(mut self, cipher: ColumnCipher) -> Self {
+        self.cipher = cipher;
+        self
+    }
+
+    /// Store fresh OAuth tokens, restarting the sync from scratch.
+    ///
+    /// A grant without a refresh token keeps the one already stored.
+    pub async fn upsert_connection(
+        &self,
+        connection: StoredGoogleConnection,
+    ) -> StorageResult<Option<GoogleConnection>> {
+        upsert_connection(&self.pool, &self.cipher, connection).await
+    }
+
+    pub async fn get_connection(&self, user_id: UserId) -> StorageResult<Option<GoogleConnection>> {
+        get_connection(&self.pool, &self.cipher, user_id).await
+    }
+
+    /// Removes the connection and, through the cascade, all event links.
+    pub async fn delete_connection(&self, user_id: UserId) -> StorageResult<bool> {
+        delete_connection(&self.pool, user_id).await
+    }
+
+    /// Connections whose next sync is due, oldest first.
+    pub async fn list_due_connections(
+        &self,
+        now: DateTime<Utc>,
+        limit: i64,
+    ) -> StorageResult<Vec<GoogleConnection>> {
+        list_due_connections(&self.pool, &self.cipher, now, limit).await
+    }
+
+    pub async fn update_access_token(
+        &self,
+        user_id: UserId,
+        access_token: &str,
+        expires_at: DateTime<Utc>,
+    ) -> StorageResult<()> {
+        update_access_token(&self.pool, &self.cipher, user_id, access_token, expires_at).await
+    }
+
+    pub async fn record_sync_success(
+        &self,
+        user_id: UserId,
+        sync: GoogleSyncCursor,
+    ) -> StorageResult<()> {
+        record_sync_success(&self.pool, user_id, sync).await
+    }
+
+    pub async fn record_sync_failure(
+        &self,
+        user_id: UserId,
+        error: &str,
+        next_sync_at: DateTime<Utc>,
+    ) -> StorageResult<()> {
+        record_sync_failure(&self.pool, user_id, error, next_sync_at).await
+    }
+
+    /// Remember a consent in progress.
+    pub async fn insert_oauth_state(
+        &self,
+        state_hash: &str,
+        user_id: UserId,
+        expires_at: DateTime<Utc>,
+    ) -> StorageResult<()> {
+        insert_oauth_state(&self.pool, state_hash, user_id, expires_at).await
+    }
+
+    /// Use up a consent state, returning its user while it is still fresh.
+    pub async fn take_oauth_state(
+        &self,
+        state_hash: &str,
+        now: DateTime<Utc>,
+    ) -> StorageResult<Option<UserId>> {
+        take_oauth_state(&self.pool, state_hash, now).await
+    }
+
+    pub async fn list_links(&self, user_id: UserId) -> StorageResult<Vec<GoogleEventLink>> {
+        list_links(&self.pool, user_id).await
+    }
+
+    pub async fn upsert_link(&self, link: GoogleEventLink) -> StorageResult<()> {
+        upsert_link(&self.pool, link).await
+    }
+
+    pub async fn delete_link(&self, user_id: UserId, event_uid: &str) -> StorageResult<()> {
+        delete_link(&self.pool, user_id, event_uid).await
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GoogleConnection {
+    pub user_id: i64,
+    pub calendar_id: String,
+    pub access_token: String,
+    pub refresh_token: String,
+    pub access_token_expires_at: DateTime<Utc>,
+    pub google_sync_token: Option<String>,
+    pub local_sync_version: i64,
+    pub last_synced_at: Option<DateTime<Utc>>,
+    pub next_sync_at: DateTime<Utc>,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+pub struct StoredGoogleConnection {
+    pub user_id: UserId,
+    pub access_token: String,
+    pub refresh_token: Option<String>,
+    pub access_token_expires_at: DateTime<Utc>,
+}
+
+pub struct GoogleSyncCursor {
+    pub google_sync_token: Option<String>,
+    pub local_sync_version: i64,
+    pub next_sync_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GoogleEventLink {
+    pub user_id: i64,
+    pub event_uid: String,
+    pub google_event_id: String,
+    pub google_etag: String,
+    pub google_updated_at: DateTime<Utc>,
+    pub local_etag: String,
+}
+
+async fn insert_oauth_state(
+    pool: &PgPool,
+    state_hash: &str,
+    user_id: UserId,
+    expires_at: DateTime<Utc>,
+) -> StorageResult<()> {
+    sqlx::query!(
+        r#"
+        INSERT INTO google_oauth_states (state_hash, user_id, expires_at)
+        VALUES ($1, $2, $3)
+        "#,
+        state_hash,
+        user_id.inner(),
+        expires_at
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+async fn take_oauth_state(
+    pool: &PgPool,
+    state_hash: &str,
+    now: DateTime<Utc>,
+) -> StorageResult<Option<UserId>> {
+    // Deleted even when expired: a state is never accepted twice
+    let row = sqlx::query_as::<_, (i64, DateTime<Utc>)>(
+        r#"
+        DELETE FROM google_oauth_states
+        WHERE state_hash = $1
+        RETURNING user_id, expires_at
+        "#,
+    )
+    .bind(state_hash)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row
+        .filter(|(_, expires_at)| *expires_at >= now)
+        .map(|(user_id, _)| UserId::new(user_id)))
+}
+
+async fn upsert_connection(
+    pool: &PgPool,
+    cipher: &ColumnCipher,
+    connection: StoredGoogleConnection,
+) -> StorageResult<Option<GoogleConnection>> {
+    // Reconnecting drops the cursors and links: the account may be a
+    // different one, so the next sync pairs events up again from scratch.
+    let mut tx = pool.begin().await?;
+    sqlx::query!(
+        "DELETE FROM google_event_links WHERE user_id = $1",
+        connection.user_id.inner()
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    let stored = sqlx::query_as!(
+        GoogleConnection,
+        r#"
+        INSERT INTO google_calendar_connections
+            (user_id, access_token, refresh_token, access_token_expires_at)
+        SELECT $1, $2, refresh_token, $4
+        FROM (
+            SELECT COALESCE($3, (
+                SELECT refresh_token FROM google_calendar_connections WHERE user_id = $1
+            )) AS refresh_token
+        ) AS grant_tokens
+        WHERE refresh_token IS NOT NULL
+        ON CONFLICT (user_id) DO UPDATE
+        SET access_token = EXCLUDED.access_token,
+            refresh_token = EXCLUDED.refresh_token,
+            access_token_expires_at = EXCLUDED.access_token_expires_at,
+            google_sync_token = NULL,
+            local_sync_version = 0,
+            next_sync_at = NOW(),
+            last_error = NULL
+        RETURNING user_id, calendar_id, access_token, refresh_token, access_token_expires_at,
+                  google_sync_token, local_sync_version, last_synced_at, next_sync_at,
+                  last_error, created_at
+        "#,
+        connection.user_id.inner(),
+        cipher.seal(GOOGLE_ACCESS_TOKEN, &connection.access_token)?,
+        connection
+            .refresh_token
+            .map(|token| cipher.seal(GOOGLE_REFRESH_TOKEN, &token))
+            .transpose()?,
+        connection.access_token_expires_at
+    )
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    stored
+        .map(|connection| open_connection(cipher, connection))
+        .transpose()
+}
+
+async fn get_connection(
+    pool: &PgPool,
+    cipher: &ColumnCipher,
+    user_id: UserId,
+) -> StorageResult<Option<GoogleConnection>> {
+    let connection = sqlx::query_as!(
+        GoogleConnection,
+        r#"
+        SELECT user_id, calendar_id, access_token, refresh_token, access_token_expires_at,
+               google_sync_token, local_sync_version, last_synced_at, next_sync_at, last_error,
+               created_at
+        FROM google_calendar_connections
+        WHERE user_id = $1
+        "#,
+        user_id.inner()
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    connection
+        .map(|connection| open_connection(cipher, connection))
+        .transpose()
+}
+
+fn open_connection(
+    cipher: &ColumnCipher,
+    mut connection: GoogleConnection,
+) -> StorageResult<GoogleConnection> {
+    connection.access_token = cipher.open(GOOGLE_ACCESS_TOKEN, connection.access_token)?;
+    connection.refresh_token = cipher.open(GOOGLE_REFRESH_TOKEN, connection.refresh_token)?;
+    Ok(connection)
+}
+
+async fn delete_connection(pool: &PgPool, user_id: UserId) -> StorageResult<bool> {
+    let result = sqlx::query!(
+        "DELETE FROM google_calendar_connections WHERE user_id = $1",
+        user_id.inner()
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+async fn list_due_connections(
+    pool: &PgPool,
+    cipher: &ColumnCipher,
+    now: DateTime<Utc>,
+    limit: i64,
+) -> StorageResult<Vec<GoogleConnection>> {
+    let connections = sqlx::query_as!(
+        GoogleConnection,
+        r#"
+        SELECT user_id, calendar_id, access_token, refresh_token, access_token_expires_at,
+               google_sync_token, local_sync_version, last_synced_at, next_sync_at, last_error,
+               created_at
+        FROM google_calendar_connections
+        WHERE next_sync_at <= $1
+        ORDER BY next_sync_at ASC
+        LIMIT $2
+        "#,
+        now,
+        limit
+    )
+    .fetch_all(pool)
+    .await?;
+
+    connections
+        .into_iter()
+        .map(|connection| open_connection(cipher, connection))
+        .collect()
+}
+
+async fn update_access_token(
+    pool: &PgPool,
+    cipher: &ColumnCipher,
+    user_id: UserId,
+    access_token: &str,
+    expires_at: DateTime<Utc>,
+) -> StorageResult<()> {
+    sqlx::query!(
+        r#"
+        UPDATE google_calendar_connections
+        SET access_token = $2, access_token_expires_at = $3
+        WHERE user_id = $1
+        "#,
+        user_id.inner(),
+        cipher.seal(GOOGLE_ACCESS_TOKEN, access_token)?,
+        expires_at
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+async fn record_sync_success(
+    pool: &PgPool,
+    user_id: UserId,
+    sync: GoogleSyncCursor,
+) -> StorageResult<()> {
+    sqlx::query!(
+        r#"
+        UPDATE google_calendar_connections
+        SET google_sync_token = $2,
+            local_sync_version = $3,
+            next_sync_at = $4,
+            last_synced_at = NOW(),
+            last_error = NULL
+        WHERE user_id = $1
+        "#,
+        user_id.inner(),
+        sync.google_sync_token,
+        sync.local_sync_version,
+        sync.next_sync_at
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+async fn record_sync_failure(
+    pool: &PgPool,
+    user_id: UserId,
+    error: &str,
+    next_sync_at: DateTime<Utc>,
+) -> StorageResult<()> {
+    sqlx::query!(
+        r#"
+        UPDATE google_calendar_connections
+        SET last_error = $2, next_sync_at = $3
+        WHERE user_id = $1
+        "#,
+        user_id.inner(),
+        error,
+        next_sync_at
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+async fn list_links(pool: &PgPool, user_id: UserId) -> StorageResult<Vec<GoogleEventLink>> {
+    let links = sqlx::query_as!(
+        GoogleEventLink,
+        r#"
+        SELECT user_id, event_uid, google_event_id, google_etag, google_updated_at, local_etag
+        FROM google_event_links
+        WHERE user_id = $1
+        "#,
+        user_id.inner()
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(links)
+}
+
+async fn upsert_link(pool: &PgPool, link: GoogleEventLink) -> StorageResult<()> {
+    let mut tx = pool.begin().await?;
+    // A Google event re-linked to another local UID replaces its old pairing
+    sqlx::query!(
+        r#"
+        DELETE FROM google_event_links
+        WHERE user_id = $1 AND google_event_id = $2 AND event_uid <> $3
+        "#,
+        link.user_id,
+        &link.google_event_id,
+        &link.event_uid
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO google_event_links
+            (user_id, event_uid, google_event_id, google_etag, google_updated_at, local_etag)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        ON CONFLICT (user_id, event_uid) DO UPDATE
+        SET google_event_id = EXCLUDED.google_event_id,
+            google_etag = EXCLUDED.google_etag,
+            google_updated_at = EXCLUDED.google_updated_at,
+            local_etag = EXCLUDED.local_etag
+        "#,
+        link.user_id,
+        link.event_uid,
+        link.google_event_id,
+        link.google_etag,
+        link.google_updated_at,
+        link.local_etag
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+async fn delete_link(pool: &PgPool, user_id: UserId, event_uid: &str) -> StorageResult<()> {
+    sqlx::query!(
+        "DELETE FROM google_event_links WHERE user_id = $1 AND event_uid = $2",
+        user_id.inner(),
+        event_uid
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
@@ -1,6 +1,7 @@
 use sqlx::PgPool;
 
 use crate::StorageResult;
+use crate::migrations::{self, MigrationStatus};
 
 #[derive(Clone)]
 pub struct HealthRepository {
@@ -17,4 +18,9 @@ impl HealthRepository {
         sqlx::query("SELECT 1").fetch_one(&self.pool).await?;
         Ok(())
     }
+
+    pub async fn migration_status(&self) -> StorageResult<MigrationStatus> {
+        let mut conn = self.pool.acquire().await?;
+        migrations::migration_status(&mut conn).await
+    }
 }
@@ -3,10 +3,32 @@
 //! Storage owns table shape and SQL. Application services own transaction
 //! boundaries and calendar mutation invariants.
 
+pub mod analytics;
+pub mod booking;
 pub mod calendar;
+pub mod chat_webhook;
+pub mod contact;
+pub mod delegation;
 pub mod device;
+pub mod diagnostics;
+pub mod email;
+pub mod flags;
+pub mod focus;
+pub mod google;
 pub mod health;
+#[cfg(any(test, feature = "test-util"))]
+pub mod memory;
+pub mod migrations;
+pub mod notification;
 pub mod outbox;
+pub mod repos;
+pub mod resource;
+pub mod retention;
+pub mod revision;
+pub mod security;
+pub mod subscription;
+pub mod task;
+pub mod web_push;
 
 use thiserror::Error;
 
@@ -18,6 +40,8 @@ pub enum StorageError {
     Json(#[from] serde_json::Error),
     #[error("invalid database data: {0}")]
     InvalidData(String),
+    #[error("column encryption: {0}")]
+    Encryption(String),
 }
 
 pub type StorageResult<T> = Result<T, StorageError>;
@@ -0,0 +1,537 @@
+//! In-memory repositories for tests.
+//!
+//! They follow the Postgres queries closely enough for handler and service
+//! tests: ownership checks, ordering and the device cap behave the same.
+//! Clones share their contents, so a test can keep one handle to seed and
+//! inspect while a service owns the other.
+
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use televent_domain::tags::event_tags;
+use televent_domain::{DeviceId, EventStatus, EventVisibility, Timezone, UserId, UserRole};
+use uuid::Uuid;
+
+use crate::calendar::{Event, EventAttendee, User};
+use crate::device::{
+    DeviceActivityRecord, DevicePasswordHash, DevicePasswordRecord, DevicePasswordSummary,
+    NewDeviceActivity, StoredDevicePassword,
+};
+use crate::repos::{DevicesRepo, EventsRepo, RepoFuture, UsersRepo};
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn ready<'a, T: Send + 'a>(value: T) -> RepoFuture<'a, T> {
+    Box::pin(async move { Ok(value) })
+}
+
+/// Users kept in a map
+#[derive(Debug, Clone, Default)]
+pub struct MemoryUsers {
+    users: Arc<Mutex<HashMap<UserId, User>>>,
+}
+
+impl MemoryUsers {
+    /// Store `user`, replacing any user with the same id
+    pub fn insert(&self, user: User) {
+        lock(&self.users).insert(user.id, user);
+    }
+
+    #[must_use]
+    pub fn get(&self, user_id: UserId) -> Option<User> {
+        lock(&self.users).get(&user_id).cloned()
+    }
+
+    fn update(&self, user_id: UserId, change: impl FnOnce(&mut User)) -> Option<User> {
+        let mut users = lock(&self.users);
+        let user = users.get_mut(&user_id)?;
+        change(user);
+        user.updated_at = Utc::now();
+        Some(user.clone())
+    }
+
+    fn release_username(users: &mut HashMap<UserId, User>, user_id: UserId, username: &str) {
+        for other in users.values_mut() {
+            if other.id != user_id
+                && other
+                    .telegram_username
+                    .as_deref()
+                    .is_some_and(|name| name.eq_ignore_ascii_case(username))
+            {
+                other.telegram_username = None;
+            }
+        }
+    }
+}
+
+/// A fresh user the way the `users` table defaults it
+#[must_use]
+pub fn new_user(user_id: UserId, username: Option<&str>) -> User {
+    let now = Utc::now();
+    User {
+        id: user_id,
+        telegram_username: username.map(str::to_string),
+        timezone: Timezone::default(),
+        role: UserRole::default(),
+        email: None,
+        onboarded_at: None,
+        default_event_visibility: EventVisibility::default(),
+        sync_token: 0,
+        ctag: 0,
+        created_at: now,
+        updated_at: now,
+    }
+}
+
+impl UsersRepo for MemoryUsers {
+    fn ensure_user<'a>(
+        &'a self,
+        telegram_id: i64,
+        username: Option<&'a str>,
+    ) -> RepoFuture<'a, User> {
+        let user_id = UserId::new(telegram_id);
+        let mut users = lock(&self.users);
+        if let Some(username) = username {
+            Self::release_username(&mut users, user_id, username);
+        }
+        let user = users
+            .entry(user_id)
+            .or_insert_with(|| new_user(user_id, None));
+        if let Some(username) = username {
+            user.telegram_username = Some(username.to_string());
+        }
+        ready(user.clone())
+    }
+
+    fn get_user_by_id(&self, user_id: UserId) -> RepoFuture<'_, Option<User>> {
+        ready(self.get(user_id))
+    }
+
+    fn get_user_by_username<'a>(&'a self, username: &'a str) -> RepoFuture<'a, Option<User>> {
+        let user = lock(&self.users)
+            .values()
+            .find(|user| {
+                user.telegram_username
+                    .as_deref()
+                    .is_some_and(|name| name.eq_ignore_ascii_case(username))
+            })
+            .cloned();
+        ready(user)
+    }
+
+    fn get_user_by_email<'a>(&'a self, email: &'a str) -> RepoFuture<'a, Option<User>> {
+        let user = lock(&self.users)
+            .values()
+            .find(|user| {
+                user.email
+                    .as_deref()
+                    .is_some_and(|stored| stored.eq_ignore_ascii_case(email))
+            })
+            .cloned();
+        ready(user)
+    }
+
+    fn refresh_username<'a>(
+        &'a self,
+        user_id: UserId,
+        username: Option<&'a str>,
+    ) -> RepoFuture<'a, bool> {
+        let mut users = lock(&self.users);
+        if let Some(username) = username {
+            Self::release_username(&mut users, user_id, username);
+        }
+        let changed = match users.get_mut(&user_id) {
+            Some(user) if user.telegram_username.as_deref() != username => {
+                user.telegram_username = username.map(str::to_string);
+                true
+            }
+            _ => false,
+        };
+        ready(changed)
+    }
+
+    fn set_user_role(&self, user_id: UserId, role: UserRole) -> RepoFuture<'_, Option<User>> {
+        ready(self.update(user_id, |user| user.role = role))
+    }
+
+    fn set_user_timezone<'a>(
+        &'a self,
+        user_id: UserId,
+        timezone: &'a Timezone,
+    ) -> RepoFuture<'a, Option<User>> {
+        ready(self.update(user_id, |user| user.timezone = timezone.clone()))
+    }
+
+    fn set_user_email<'a>(
+        &'a self,
+        user_id: UserId,
+        email: Option<&'a str>,
+    ) -> RepoFuture<'a, Option<User>> {
+        ready(self.update(user_id, |user| user.email = email.map(str::to_string)))
+    }
+
+    fn set_default_event_visibility(
+        &self,
+        user_id: UserId,
+        visibility: EventVisibility,
+    ) -> RepoFuture<'_, Option<User>> {
+        ready(self.update(user_id, |user| user.default_event_visibility = visibility))
+    }
+
+    fn mark_user_onboarded(&self, user_id: UserId) -> RepoFuture<'_, bool> {
+        let mut users = lock(&self.users);
+        let marked = match users.get_mut(&user_id) {
+            Some(user) if user.onboarded_at.is_none() => {
+                user.onboarded_at = Some(Utc::now());
+                true
+            }
+            _ => false,
+        };
+        ready(marked)
+    }
+
+    fn promote_admins<'a>(&'a self, telegram_ids: &'a [i64]) -> RepoFuture<'a, u64> {
+        let mut users = lock(&self.users);
+        let mut promoted = 0;
+        for &telegram_id in telegram_ids {
+            let user_id = UserId::new(telegram_id);
+            let user = users
+                .entry(user_id)
+                .or_insert_with(|| new_user(user_id, None));
+            if user.role != UserRole::Admin {
+                user.role = UserRole::Admin;
+                promoted += 1;
+            }
+        }
+        ready(promoted)
+    }
+}
+
+#[derive(Debug, Default)]
+struct EventTables {
+    events: HashMap<Uuid, Event>,
+    attendees: Vec<EventAttendee>,
+}
+
+/// Events and attendees kept in memory
+#[derive(Debug, Clone, Default)]
+pub struct MemoryEvents {
+    tables: Arc<Mutex<EventTables>>,
+}
+
+impl MemoryEvents {
+    /// Store `event`, replacing any event with the same id
+    pub fn insert(&self, event: Event) {
+        lock(&self.tables).events.insert(event.id, event);
+    }
+
+    pub fn insert_attendee(&self, attendee: EventAttendee) {
+        lock(&self.tables).attendees.push(attendee);
+    }
+
+    fn find(&self, matches: impl Fn(&Event) -> bool) -> Vec<Event> {
+        let mut events: Vec<Event> = lock(&self.tables)
+            .events
+            .values()
+            .filter(|event| matches(event))
+            .cloned()
+            .collect();
+        events.sort_by_key(starts_at);
+        events
+    }
+
+    fn attendees_of(&self, event_id: Uuid) -> Vec<EventAttendee> {
+        let mut attendees: Vec<EventAttendee> = lock(&self.tables)
+            .attendees
+            .iter()
+            .filter(|attendee| attendee.event_id == event_id)
+            .cloned()
+            .collect();
+        attendees.sort_by_key(|attendee| attendee.created_at);
+        attendees
+    }
+}
+
+/// Where an event sorts in `list_events`; all-day events start at UTC midnight
+fn starts_at(event: &Event) -> Option<DateTime<Utc>> {
+    event.start.or_else(|| {
+        event
+            .start_date
+            .and_then(|date| date.and_hms_opt(0, 0, 0))
+            .map(|start| start.and_utc())
+    })
+}
+
+impl EventsRepo for MemoryEvents {
+    fn get_event_by_id(&self, user_id: UserId, event_id: Uuid) -> RepoFuture<'_, Option<Event>> {
+        ready(
+            self.find(|event| event.id == event_id && event.user_id == user_id)
+                .pop(),
+        )
+    }
+
+    fn get_event_by_id_any(&self, event_id: Uuid) -> RepoFuture<'_, Option<Event>> {
+        ready(self.find(|event| event.id == event_id).pop())
+    }
+
+    fn get_event_by_uid<'a>(
+        &'a self,
+        user_id: UserId,
+        uid: &'a str,
+    ) -> RepoFuture<'a, Option<Event>> {
+        ready(
+            self.find(|event| event.uid == uid && event.user_id == user_id)
+                .pop(),
+        )
+    }
+
+    fn get_events_by_uids<'a>(
+        &'a self,
+        user_id: UserId,
+        uids: &'a [&'a str],
+    ) -> RepoFuture<'a, Vec<Event>> {
+        ready(self.find(|event| event.user_id == user_id && uids.contains(&event.uid.as_str())))
+    }
+
+    fn get_events_by_ids_any<'a>(&'a self, event_ids: &'a [Uuid]) -> RepoFuture<'a, Vec<Event>> {
+        ready(self.find(|event| event_ids.contains(&event.id)))
+    }
+
+    fn get_event_attendees(&self, event_id: Uuid) -> RepoFuture<'_, Vec<EventAttendee>> {
+        ready(self.attendees_of(event_id))
+    }
+
+    fn get_event_attendees_bulk<'a>(
+        &'a self,
+        event_ids: &'a [Uuid],
+    ) -> RepoFuture<'a, HashMap<Uuid, Vec<EventAttendee>>> {
+        let attendees = event_ids
+            .iter()
+            .map(|&event_id| (event_id, self.attendees_of(event_id)))
+            .filter(|(_, attendees)| !attendees.is_empty())
+            .collect();
+        ready(attendees)
+    }
+
+    fn list_events<'a>(
+        &'a self,
+        user_id: UserId,
+        start: Option<DateTime<Utc>>,
+        end: Option<DateTime<Utc>>,
+        statuses: &'a [EventStatus],
+        tag: Option<&'a str>,
+        limit: Option<i64>,
+        offset: Option<i64>,
+    ) -> RepoFuture<'a, Vec<Event>> {
+        let events = self.find(|event| {
+            let in_range = match (start, end) {
+                (Some(start), Some(end)) if event.is_all_day => event
+                    .start_date
+                    .is_some_and(|date| date >= start.date_naive() && date < end.date_naive()),
+                (Some(start), Some(end)) => event.start.is_some_and(|at| at >= start && at < end),
+                _ => true,
+            };
+            event.user_id == user_id
+                && in_range
+                && (statuses.is_empty() || statuses.contains(&event.status))
+                && tag.is_none_or(|tag| event_tags(&event.summary).iter().any(|found| found == tag))
+        });
+        let offset = usize::try_from(offset.unwrap_or(0)).unwrap_or_default();
+        let limit = limit.map_or(usize::MAX, |limit| {
+            usize::try_from(limit).unwrap_or_default()
+        });
+        ready(events.into_iter().skip(offset).take(limit).collect())
+    }
+}
+
+/// Device passwords and their request log kept in memory
+#[derive(Debug, Clone, Default)]
+pub struct MemoryDevices {
+    tables: Arc<Mutex<DeviceTables>>,
+}
+
+#[derive(Debug, Default)]
+struct DeviceTables {
+    passwords: Vec<DevicePasswordRecord>,
+    activity: HashMap<DeviceId, Vec<DeviceActivityRecord>>,
+}
+
+impl MemoryDevices {
+    fn owned(tables: &DeviceTables, user_id: UserId, device_id: DeviceId) -> bool {
+        tables
+            .passwords
+            .iter()
+            .any(|device| device.id == device_id && device.user_id == user_id.inner())
+    }
+
+    fn of_user(&self, user_id: UserId) -> Vec<DevicePasswordRecord> {
+        lock(&self.tables)
+            .passwords
+            .iter()
+            .filter(|device| device.user_id == user_id.inner())
+            .cloned()
+            .collect()
+    }
+}
+
+impl DevicesRepo for MemoryDevices {
+    fn create_device_password<'a>(
+        &'a self,
+        _username: Option<&'a str>,
+        password: StoredDevicePassword,
+        max_devices: i64,
+    ) -> RepoFuture<'a, Option<DevicePasswordRecord>> {
+        let mut tables = lock(&self.tables);
+        let count = tables
+            .passwords
+            .iter()
+            .filter(|device| device.user_id == password.user_id.inner())
+            .count();
+        if i64::try_from(count).unwrap_or(i64::MAX) >= max_devices {
+            return ready(None);
+        }
+        let device = DevicePasswordRecord {
+            id: DeviceId::new(Uuid::new_v4()),
+            user_id: password.user_id.inner(),
+            password_hash: password.password_hash,
+            name: password.name,
+            created_at: Utc::now(),
+            last_used_at: None,
+        };
+        tables.passwords.push(device.clone());
+        ready(Some(device))
+    }
+
+    fn list_device_passwords(
+        &self,
+        user_id: UserId,
+        recent_since: DateTime<Utc>,
+        active_since: Option<DateTime<Utc>>,
+    ) -> RepoFuture<'_, Vec<DevicePasswordSummary>> {
+        let mut devices = self.of_user(user_id);
+        devices.reverse();
+        devices.sort_by_key(|device| std::cmp::Reverse(device.last_used_at));
+        let tables = lock(&self.tables);
+        ready(
+            devices
+                .into_iter()
+                .filter(|device| {
+                    active_since.is_none_or(|since| device.last_used_at >= Some(since))
+                })
+                .map(|device| {
+                    let recent_requests = tables.activity.get(&device.id).map_or(0, |log| {
+                        log.iter()
+                            .filter(|record| record.created_at >= recent_since)
+                            .count()
+                    });
+                    DevicePasswordSummary {
+                        id: device.id,
+                        name: device.name,
+                        created_at: device.created_at,
+                        last_used_at: device.last_used_at,
+                        recent_requests: i64::try_from(recent_requests).unwrap_or(i64::MAX),
+                    }
+                })
+                .collect(),
+        )
+    }
+
+    fn list_device_password_hashes(
+        &self,
+        user_id: UserId,
+        limit: i64,
+    ) -> RepoFuture<'_, Vec<DevicePasswordHash>> {
+        let mut devices = self.of_user(user_id);
+        devices.reverse();
+        // `None` sorts first, so descending puts never-used devices last
+        devices.sort_by_key(|device| std::cmp::Reverse(device.last_used_at));
+        let limit = usize::try_from(limit).unwrap_or_default();
+        ready(
+            devices
+                .into_iter()
+                .take(limit)
+                .map(|device| DevicePasswordHash {
+                    id: device.id,
+                    password_hash: device.password_hash,
+                })
+                .collect(),
+        )
+    }
+
+    fn delete_device_password(&self, user_id: UserId, device_id: DeviceId) -> RepoFuture<'_, bool> {
+        let mut tables = lock(&self.tables);
+        let before = tables.passwords.len();
+        tables
+            .passwords
+            .retain(|device| device.id != device_id || device.user_id != user_id.inner());
+        let deleted = tables.passwords.len() < before;
+        if deleted {
+            tables.activity.remove(&device_id);
+        }
+        ready(deleted)
+    }
+
+    fn touch_device_password(&self, device_id: DeviceId) -> RepoFuture<'_, ()> {
+        let mut tables = lock(&self.tables);
+        if let Some(device) = tables
+            .passwords
+            .iter_mut()
+            .find(|device| device.id == device_id)
+        {
+            device.last_used_at = Some(Utc::now());
+        }
+        ready(())
+    }
+
+    fn replace_password_hash(
+        &self,
+        device_id: DeviceId,
+        current_hash: String,
+        new_hash: String,
+    ) -> RepoFuture<'_, bool> {
+        let mut tables = lock(&self.tables);
+        let device = tables
+            .passwords
+            .iter_mut()
+            .find(|device| device.id == device_id && device.password_hash == current_hash);
+        let replaced = device.is_some();
+        if let Some(device) = device {
+            device.password_hash = new_hash;
+        }
+        ready(replaced)
+    }
+
+    fn insert_device_activity(&self, activity: NewDeviceActivity, keep: i64) -> RepoFuture<'_, ()> {
+        let mut tables = lock(&self.tables);
+        let log = tables.activity.entry(activity.device_id).or_default();
+        log.insert(
+            0,
+            DeviceActivityRecord {
+                method: activity.method,
+                path: activity.path,
+                user_agent: activity.user_agent,
+                status: activity.status,
+                sync_token: activity.sync_token,
+                conflict_uid: activity.conflict_uid,
+                client_sequence: activity.client_sequence,
+                server_sequence: activity.server_sequence,
+                created_at: Utc::now(),
+            },
+        );
+        log.truncate(usize::try_from(keep).unwrap_or_default());
+        ready(())
+    }
+
+    fn list_device_activity(
+        &self,
+        user_id: UserId,
+        device_id: DeviceId,
+    ) -> RepoFuture<'_, Option<Vec<DeviceActivityRecord>>> {
+        let tables = lock(&self.tables);
+        let activity = Self::owned(&tables, user_id, device_id)
+            .then(|| tables.activity.get(&device_id).cloned().unwrap_or_default());
+        ready(activity)
+    }
+}
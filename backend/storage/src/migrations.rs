@@ -0,0 +1,80 @@
+//! Embedded schema migrations and how far the database has applied them
+
+use std::collections::HashMap;
+
+use sqlx::PgConnection;
+use sqlx::migrate::Migrator;
+
+use crate::StorageResult;
+
+/// Every migration in `backend/migrations`, compiled into the binary
+pub static MIGRATOR: Migrator = sqlx::migrate!("../migrations");
+
+/// Advisory lock key held while one process checks and applies migrations
+pub const MIGRATION_LOCK_KEY: i64 = 0x7465_6c65_7665_6e74; // "televent"
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingMigration {
+    pub version: i64,
+    pub description: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MigrationStatus {
+    /// Newest version recorded as applied
+    pub current_version: Option<i64>,
+    /// Newest version compiled into this binary
+    pub latest_version: Option<i64>,
+    /// Embedded migrations the database hasn't applied yet, oldest first
+    pub pending: Vec<PendingMigration>,
+    /// Versions whose last run failed part-way and need manual repair
+    pub failed: Vec<i64>,
+}
+
+impl MigrationStatus {
+    pub fn is_current(&self) -> bool {
+        self.pending.is_empty() && self.failed.is_empty()
+    }
+}
+
+/// Compare the embedded migrations with the `_sqlx_migrations` bookkeeping
+/// table, which doesn't exist before the first run
+pub async fn migration_status(conn: &mut PgConnection) -> StorageResult<MigrationStatus> {
+    let table_exists: bool =
+        sqlx::query_scalar("SELECT to_regclass('_sqlx_migrations') IS NOT NULL")
+            .fetch_one(&mut *conn)
+            .await?;
+    let applied: HashMap<i64, bool> = if table_exists {
+        sqlx::query_as::<_, (i64, bool)>("SELECT version, success FROM _sqlx_migrations")
+            .fetch_all(&mut *conn)
+            .await?
+            .into_iter()
+            .collect()
+    } else {
+        HashMap::new()
+    };
+
+    let mut status = MigrationStatus {
+        current_version: applied
+            .iter()
+            .filter(|(_, success)| **success)
+            .map(|(version, _)| *version)
+            .max(),
+        ..MigrationStatus::default()
+    };
+    for migration in MIGRATOR
+        .iter()
+        .filter(|migration| !migration.migration_type.is_down_migration())
+    {
+        status.latest_version = status.latest_version.max(Some(migration.version));
+        match applied.get(&migration.version) {
+            Some(true) => {}
+            Some(false) => status.failed.push(migration.version),
+            None => status.pending.push(PendingMigration {
+                version: migration.version,
+                description: migration.description.to_string(),
+            }),
+        }
+    }
+    Ok(status)
+}
@@ -0,0 +1,453 @@
+use chrono::{DateTime, Utc};
+use sqlx::{PgConnection, PgPool};
+use televent_domain::{NotificationChannel, NotificationTopic, OutboxPayload, UserId};
+
+use crate::security::{ColumnCipher, PHONE_NUMBER};
+use crate::{StorageError, StorageResult};
+
+#[derive(Clone)]
+pub struct NotificationRepository {
+    pool: PgPool,
+    cipher: ColumnCipher,
+}
+
+#[derive(Debug, Clone)]
+pub struct PhoneNumberRecord {
+    pub user_id: i64,
+    pub phone_number: String,
+    pub verified_at: Option<DateTime<Utc>>,
+    pub code_hash: Option<String>,
+    pub code_expires_at: Option<DateTime<Utc>>,
+    pub code_attempts: i32,
+    pub requested_at: DateTime<Utc>,
+}
+
+/// The user's email with the pending confirmation of it, if any
+#[derive(Debug, Clone)]
+pub struct EmailAddressRecord {
+    pub user_id: i64,
+    pub email: String,
+    pub verified_at: Option<DateTime<Utc>>,
+    pub code_hash: Option<String>,
+    pub code_expires_at: Option<DateTime<Utc>>,
+    pub code_attempts: i32,
+    /// When a code was last asked for; `None` if never for this address
+    pub requested_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct NotificationPreferenceRecord {
+    pub topic: String,
+    pub channel: String,
+    pub enabled: bool,
+}
+
+impl NotificationRepository {
+    #[must_use]
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            pool,
+            cipher: ColumnCipher::default(),
+        }
+    }
+
+    /// Seal phone numbers with `cipher`
+    #[must_use]
+    pub fn with_cipher(mut self, cipher: ColumnCipher) -> Self {
+        self.cipher = cipher;
+        self
+    }
+
+    pub async fn list_preferences(
+        &self,
+        user_id: UserId,
+    ) -> StorageResult<Vec<NotificationPreferenceRecord>> {
+        let preferences = sqlx::query_as!(
+            NotificationPreferenceRecord,
+            r#"
+            SELECT topic, channel, enabled
+            FROM notification_preferences
+            WHERE user_id = $1
+            "#,
+            user_id.inner()
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(preferences)
+    }
+
+    /// Store the user's choices, creating the user row if needed
+    pub async fn set_preferences(
+        &self,
+        user_id: UserId,
+        preferences: &[(NotificationTopic, NotificationChannel, bool)],
+    ) -> StorageResult<()> {
+        let mut tx = self.pool.begin().await?;
+        crate::calendar::ensure_user_tx(&mut tx, user_id.inner(), None).await?;
+        for (topic, channel, enabled) in preferences {
+            sqlx::query!(
+                r#"
+                INSERT INTO notification_preferences (user_id, topic, channel, enabled)
+                VALUES ($1, $2, $3, $4)
+                ON CONFLICT (user_id, topic, channel)
+                DO UPDATE SET enabled = EXCLUDED.enabled, updated_at = NOW()
+                "#,
+                user_id.inner(),
+                topic.as_sql(),
+                channel.as_sql(),
+                enabled
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Whether the user wants `topic` copied to `channel`
+    pub async fn channel_enabled(
+        &self,
+        user_id: UserId,
+        topic: NotificationTopic,
+        channel: NotificationChannel,
+    ) -> StorageResult<bool> {
+        let mut conn = self.pool.acquire().await?;
+        channel_enabled_tx(&mut conn, user_id, topic, channel).await
+    }
+
+    pub async fn get_phone(&self, user_id: UserId) -> StorageResult<Option<PhoneNumberRecord>> {
+        let phone = sqlx::query_as!(
+            PhoneNumberRecord,
+            r#"
+            SELECT user_id, phone_number, verified_at, code_hash, code_expires_at,
+                   code_attempts, requested_at
+            FROM phone_numbers
+            WHERE user_id = $1
+            "#,
+            user_id.inner()
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        phone.map(|phone| self.open_phone(phone)).transpose()
+    }
+
+    /// Whether another user has confirmed `phone_number`
+    pub async fn is_phone_taken(&self, user_id: UserId, phone_number: &str) -> StorageResult<bool> {
+        let taken = sqlx::query_scalar!(
+            r#"
+            SELECT EXISTS (
+                SELECT 1 FROM phone_numbers
+                WHERE (phone_number = $1 OR phone_number_digest = ANY($3))
+                  AND verified_at IS NOT NULL
+                  AND user_id <> $2
+            ) AS "taken!"
+            "#,
+            phone_number,
+            user_id.inner(),
+            &self.cipher.digests(PHONE_NUMBER, phone_number)
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(taken)
+    }
+
+    /// Replace the user's number with an unconfirmed one and queue the text
+    /// that confirms it, in one transaction
+    pub async fn request_phone(
+        &self,
+        user_id: UserId,
+        phone_number: &str,
+        verification: &OutboxPayload,
+    ) -> StorageResult<PhoneNumberRecord> {
+        let mut tx = self.pool.begin().await?;
+        crate::calendar::ensure_user_tx(&mut tx, user_id.inner(), None).await?;
+        let phone = sqlx::query_as!(
+            PhoneNumberRecord,
+            r#"
+            INSERT INTO phone_numbers (user_id, phone_number, phone_number_digest)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (user_id) DO UPDATE
+            SET phone_number = EXCLUDED.phone_number,
+                phone_number_digest = EXCLUDED.phone_number_digest,
+                verified_at = NULL,
+                code_hash = NULL,
+                code_expires_at = NULL,
+                code_attempts = 0,
+                requested_at = NOW()
+            RETURNING user_id, phone_number, verified_at, code_hash, code_expires_at,
+                      code_attempts, requested_at
+            "#,
+            user_id.inner(),
+            self.cipher.seal(PHONE_NUMBER, phone_number)?,
+            self.cipher.digest(PHONE_NUMBER, phone_number)
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        crate::calendar::queue_outbox_tx(&mut tx, std::slice::from_ref(verification)).await?;
+        tx.commit().await?;
+
+        self.open_phone(phone)
+    }
+
+    /// Store the hash of a freshly texted code, replacing any earlier one.
+    /// Returns `false` when `phone_number` is no longer awaiting confirmation.
+    pub async fn store_code(
+        &self,
+        user_id: UserId,
+        phone_number: &str,
+        code_hash: &str,
+        expires_at: DateTime<Utc>,
+    ) -> StorageResult<bool> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE phone_numbers
+            SET code_hash = $3, code_expires_at = $4, code_attempts = 0
+            WHERE user_id = $1
+              AND (phone_number = $2 OR phone_number_digest = ANY($5))
+              AND verified_at IS NULL
+            "#,
+            user_id.inner(),
+            phone_number,
+            code_hash,
+            expires_at,
+            &self.cipher.digests(PHONE_NUMBER, phone_number)
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Count one try at the pending code and return its hash; `None` when
+    /// there is no code or `max_attempts` tries were used up.
+    ///
+    /// Checking and counting in one statement keeps concurrent guesses from
+    /// all passing the check before any of them is counted.
+    pub async fn claim_code_attempt(
+        &self,
+        user_id: UserId,
+        max_attempts: i32,
+    ) -> StorageResult<Option<String>> {
+        let code_hash = sqlx::query_scalar!(
+            r#"
+            UPDATE phone_numbers SET code_attempts = code_attempts + 1
+            WHERE user_id = $1 AND code_hash IS NOT NULL AND code_attempts < $2
+            RETURNING code_hash AS "code_hash!"
+            "#,
+            user_id.inner(),
+            max_attempts
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(code_hash)
+    }
+
+    /// Confirm the user's number; `None` when another user confirmed the
+    /// same number first
+    pub async fn mark_verified(&self, user_id: UserId) -> StorageResult<Option<PhoneNumberRecord>> {
+        let result = sqlx::query_as!(
+            PhoneNumberRecord,
+            r#"
+            UPDATE phone_numbers
+            SET verified_at = NOW(), code_hash = NULL, code_expires_at = NULL,
+                code_attempts = 0
+            WHERE user_id = $1
+            RETURNING user_id, phone_number, verified_at, code_hash, code_expires_at,
+                      code_attempts, requested_at
+            "#,
+            user_id.inner()
+        )
+        .fetch_optional(&self.pool)
+        .await;
+
+        match result {
+            Ok(phone) => phone.map(|phone| self.open_phone(phone)).transpose(),
+            Err(sqlx::Error::Database(err)) if err.is_unique_violation() => Ok(None),
+            Err(err) => Err(StorageError::from(err)),
+        }
+    }
+
+    fn open_phone(&self, mut phone: PhoneNumberRecord) -> StorageResult<PhoneNumberRecord> {
+        phone.phone_number = self.cipher.open(PHONE_NUMBER, phone.phone_number)?;
+        Ok(phone)
+    }
+
+    pub async fn delete_phone(&self, user_id: UserId) -> StorageResult<bool> {
+        let result = sqlx::query!(
+            "DELETE FROM phone_numbers WHERE user_id = $1",
+            user_id.inner()
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn get_email(&self, user_id: UserId) -> StorageResult<Option<EmailAddressRecord>> {
+        let email = sqlx::query_as!(
+            EmailAddressRecord,
+            r#"
+            SELECT u.telegram_id AS user_id, u.email AS "email!", u.email_verified_at AS verified_at,
+                   v.code_hash AS "code_hash?", v.code_expires_at AS "code_expires_at?",
+                   COALESCE(v.code_attempts, 0) AS "code_attempts!",
+                   v.requested_at AS "requested_at?"
+            FROM users u
+            LEFT JOIN email_verifications v
+                ON v.user_id = u.telegram_id AND lower(v.email) = lower(u.email)
+            WHERE u.telegram_id = $1 AND u.email IS NOT NULL
+            "#,
+            user_id.inner()
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(email)
+    }
+
+    /// Start confirming `email` afresh and queue the email that confirms it,
+    /// in one transaction
+    pub async fn request_email(
+        &self,
+        user_id: UserId,
+        email: &str,
+        verification: &OutboxPayload,
+    ) -> StorageResult<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query!(
+            r#"
+            INSERT INTO email_verifications (user_id, email)
+            VALUES ($1, $2)
+            ON CONFLICT (user_id) DO UPDATE
+            SET email = EXCLUDED.email,
+                code_hash = NULL,
+                code_expires_at = NULL,
+                code_attempts = 0,
+                requested_at = NOW()
+            "#,
+            user_id.inner(),
+            email
+        )
+        .execute(&mut *tx)
+        .await?;
+        crate::calendar::queue_outbox_tx(&mut tx, std::slice::from_ref(verification)).await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Store the hash of a freshly emailed code, replacing any earlier one.
+    /// Returns `false` when `email` is no longer awaiting confirmation.
+    pub async fn store_email_code(
+        &self,
+        user_id: UserId,
+        email: &str,
+        code_hash: &str,
+        expires_at: DateTime<Utc>,
+    ) -> StorageResult<bool> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE email_verifications v
+            SET code_hash = $3, code_expires_at = $4, code_attempts = 0
+            FROM users u
+            WHERE v.user_id = $1
+              AND u.telegram_id = v.user_id
+              AND lower(v.email) = lower($2)
+              AND lower(u.email) = lower($2)
+              AND u.email_verified_at IS NULL
+            "#,
+            user_id.inner(),
+            email,
+            code_hash,
+            expires_at
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Count one try at the code emailed to `email` and return its hash;
+    /// `None` when there is no code or its tries were used up. See
+    /// [`Self::claim_code_attempt`].
+    pub async fn claim_email_code_attempt(
+        &self,
+        user_id: UserId,
+        email: &str,
+        max_attempts: i32,
+    ) -> StorageResult<Option<String>> {
+        let code_hash = sqlx::query_scalar!(
+            r#"
+            UPDATE email_verifications SET code_attempts = code_attempts + 1
+            WHERE user_id = $1 AND lower(email) = lower($2)
+              AND code_hash IS NOT NULL AND code_attempts < $3
+            RETURNING code_hash AS "code_hash!"
+            "#,
+            user_id.inner(),
+            email,
+            max_attempts
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(code_hash)
+    }
+
+    /// Confirm the user's email and drop the pending code; `false` when the
+    /// user has moved on to another address meanwhile
+    pub async fn mark_email_verified(&self, user_id: UserId, email: &str) -> StorageResult<bool> {
+        let mut tx = self.pool.begin().await?;
+        let result = sqlx::query!(
+            r#"
+            UPDATE users SET email_verified_at = NOW()
+            WHERE telegram_id = $1 AND lower(email) = lower($2)
+            "#,
+            user_id.inner(),
+            email
+        )
+        .execute(&mut *tx)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+        sqlx::query!(
+            "DELETE FROM email_verifications WHERE user_id = $1",
+            user_id.inner()
+        )
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        Ok(true)
+    }
+
+    pub async fn queue_outbox(&self, messages: &[OutboxPayload]) -> StorageResult<()> {
+        let mut conn = self.pool.acquire().await?;
+        crate::calendar::queue_outbox_tx(&mut conn, messages).await
+    }
+}
+
+pub(crate) async fn channel_enabled_tx(
+    conn: &mut PgConnection,
+    user_id: UserId,
+    topic: NotificationTopic,
+    channel: NotificationChannel,
+) -> StorageResult<bool> {
+    let enabled = sqlx::query_scalar!(
+        r#"
+        SELECT enabled FROM notification_preferences
+        WHERE user_id = $1 AND topic = $2 AND channel = $3
+        "#,
+        user_id.inner(),
+        topic.as_sql(),
+        channel.as_sql()
+    )
+    .fetch_optional(conn)
+    .await?;
+
+    Ok(enabled.unwrap_or(channel.enabled_by_default()))
+}
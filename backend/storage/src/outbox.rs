@@ -1,21 +1,21 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
-use uuid::Uuid;
+use televent_domain::OutboxMessageId;
 
 use crate::StorageResult;
 
 /// Outbox message row owned by the storage layer.
-#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OutboxMessage {
-    pub id: Uuid,
+    pub id: OutboxMessageId,
     pub kind: String,
-    #[sqlx(json)]
     pub payload: serde_json::Value,
     pub status: OutboxStatus,
     pub retry_count: i32,
     pub scheduled_at: DateTime<Utc>,
     pub processed_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
 }
 
 /// Outbox message status stored in Postgres.
@@ -28,21 +28,79 @@ pub enum OutboxStatus {
     Failed,
 }
 
+/// A message a user scheduled that hasn't been picked up yet
+#[derive(Debug, Clone)]
+pub struct ScheduledOutboxMessage {
+    pub id: OutboxMessageId,
+    pub kind: String,
+    pub payload: serde_json::Value,
+    pub scheduled_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Completed messages of one kind processed inside a window
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompletedOutboxSummary {
+    pub kind: String,
+    pub count: i64,
+    pub first_processed_at: DateTime<Utc>,
+    pub last_processed_at: DateTime<Utc>,
+}
+
 #[derive(Debug, Clone)]
 pub enum OutboxUpdate {
-    Completed(Uuid),
+    Completed(OutboxMessageId),
     Failed {
-        id: Uuid,
+        id: OutboxMessageId,
         error: String,
     },
     Reschedule {
-        id: Uuid,
+        id: OutboxMessageId,
         retry_count: i32,
         scheduled_at: DateTime<Utc>,
         error: String,
     },
 }
 
+/// Slice of the outbox one worker processes when several share the queue
+///
+/// Messages go to shard `hash(shard_key) mod total`, so every message about a
+/// user lands on the same worker without any coordination between them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutboxShard {
+    index: u32,
+    total: u32,
+}
+
+impl OutboxShard {
+    /// Shard `index` of `total`; `None` unless `index < total`
+    #[must_use]
+    pub const fn new(index: u32, total: u32) -> Option<Self> {
+        if index < total {
+            Some(Self { index, total })
+        } else {
+            None
+        }
+    }
+
+    #[must_use]
+    pub const fn index(&self) -> u32 {
+        self.index
+    }
+
+    #[must_use]
+    pub const fn total(&self) -> u32 {
+        self.total
+    }
+}
+
+impl Default for OutboxShard {
+    /// The whole queue
+    fn default() -> Self {
+        Self { index: 0, total: 1 }
+    }
+}
+
 #[derive(Clone)]
 pub struct OutboxRepository {
     pool: PgPool,
@@ -54,25 +112,42 @@ impl OutboxRepository {
         Self { pool }
     }
 
-    pub async fn claim_pending_jobs(&self, batch_size: i64) -> StorageResult<Vec<OutboxMessage>> {
-        claim_pending_jobs(&self.pool, batch_size).await
+    pub async fn claim_pending_jobs(
+        &self,
+        batch_size: i64,
+        shard: OutboxShard,
+    ) -> StorageResult<Vec<OutboxMessage>> {
+        claim_pending_jobs(&self.pool, batch_size, shard).await
+    }
+
+    /// Claim every pending RSVP notification for one organizer, including
+    /// those scheduled later, so they can be sent as one message
+    pub async fn claim_pending_rsvp_notifications(
+        &self,
+        organizer_telegram_id: i64,
+    ) -> StorageResult<Vec<OutboxMessage>> {
+        claim_pending_rsvp_notifications(&self.pool, organizer_telegram_id).await
     }
 
     pub async fn count_pending(&self) -> StorageResult<i64> {
         count_pending(&self.pool).await
     }
 
-    pub async fn mark_completed(&self, message_id: Uuid) -> StorageResult<()> {
+    pub async fn mark_completed(&self, message_id: OutboxMessageId) -> StorageResult<()> {
         mark_completed(&self.pool, message_id).await
     }
 
-    pub async fn mark_failed(&self, message_id: Uuid, error_msg: &str) -> StorageResult<()> {
+    pub async fn mark_failed(
+        &self,
+        message_id: OutboxMessageId,
+        error_msg: &str,
+    ) -> StorageResult<()> {
         mark_failed(&self.pool, message_id, error_msg).await
     }
 
     pub async fn reschedule_message(
         &self,
-        message_id: Uuid,
+        message_id: OutboxMessageId,
         current_retry_count: i32,
         error_msg: &str,
     ) -> StorageResult<()> {
@@ -82,10 +157,117 @@ impl OutboxRepository {
     pub async fn apply_updates(&self, updates: Vec<OutboxUpdate>) -> StorageResult<()> {
         apply_updates(&self.pool, updates).await
     }
+
+    /// Messages completed at or after `since` per kind, limited to `kinds`
+    /// unless it is empty
+    pub async fn summarize_completed_since(
+        &self,
+        since: DateTime<Utc>,
+        kinds: &[String],
+    ) -> StorageResult<Vec<CompletedOutboxSummary>> {
+        let summaries = sqlx::query_as!(
+            CompletedOutboxSummary,
+            r#"
+            SELECT kind,
+                   COUNT(*) AS "count!",
+                   MIN(processed_at) AS "first_processed_at!",
+                   MAX(processed_at) AS "last_processed_at!"
+            FROM outbox_messages
+            WHERE status = 'completed'
+              AND processed_at >= $1
+              AND (cardinality($2::text[]) = 0 OR kind = ANY($2))
+            GROUP BY kind
+            ORDER BY kind
+            "#,
+            since,
+            kinds
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(summaries)
+    }
+
+    /// Put messages of `kinds` completed at or after `since` back in the
+    /// queue with fresh retries, to be sent again; returns how many
+    pub async fn requeue_completed_since(
+        &self,
+        since: DateTime<Utc>,
+        kinds: &[String],
+    ) -> StorageResult<u64> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE outbox_messages
+            SET status = 'pending',
+                retry_count = 0,
+                scheduled_at = NOW(),
+                processed_at = NULL,
+                error_message = NULL
+            WHERE status = 'completed'
+              AND processed_at >= $1
+              AND kind = ANY($2)
+            "#,
+            since,
+            kinds
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+pub(crate) async fn list_scheduled(
+    pool: &PgPool,
+    user_id: i64,
+) -> StorageResult<Vec<ScheduledOutboxMessage>> {
+    let messages = sqlx::query_as!(
+        ScheduledOutboxMessage,
+        r#"
+        SELECT id AS "id: OutboxMessageId", kind, payload, scheduled_at, created_at
+        FROM outbox_messages
+        WHERE scheduled_by = $1
+          AND status = 'pending'
+        ORDER BY scheduled_at ASC
+        "#,
+        user_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(messages)
 }
 
-async fn claim_pending_jobs(pool: &PgPool, batch_size: i64) -> StorageResult<Vec<OutboxMessage>> {
-    let messages = sqlx::query_as::<_, OutboxMessage>(
+pub(crate) async fn cancel_scheduled(
+    pool: &PgPool,
+    user_id: i64,
+    message_id: OutboxMessageId,
+) -> StorageResult<bool> {
+    // Claimed messages are 'processing', so a send in flight can't be pulled
+    let result = sqlx::query!(
+        r#"
+        DELETE FROM outbox_messages
+        WHERE id = $1
+          AND scheduled_by = $2
+          AND status = 'pending'
+        "#,
+        message_id as OutboxMessageId,
+        user_id
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+async fn claim_pending_jobs(
+    pool: &PgPool,
+    batch_size: i64,
+    shard: OutboxShard,
+) -> StorageResult<Vec<OutboxMessage>> {
+    // Messages without a user hash like user 0
+    let messages = sqlx::query_as!(
+        OutboxMessage,
         r#"
         UPDATE outbox_messages
         SET status = 'processing'
@@ -94,14 +276,49 @@ async fn claim_pending_jobs(pool: &PgPool, batch_size: i64) -> StorageResult<Vec
             FROM outbox_messages
             WHERE status = 'pending'
               AND scheduled_at <= NOW()
+              AND ($2::BIGINT = 1
+                   OR MOD(ABS(hashint8(COALESCE(shard_key, 0))::BIGINT), $2::BIGINT) = $3::BIGINT)
             ORDER BY scheduled_at ASC
             LIMIT $1
             FOR UPDATE SKIP LOCKED
         )
-        RETURNING id, kind, payload, status, retry_count, scheduled_at, processed_at
+        RETURNING id AS "id: OutboxMessageId", kind, payload,
+                  status AS "status: OutboxStatus", retry_count,
+                  scheduled_at, processed_at, created_at
         "#,
+        batch_size,
+        i64::from(shard.total),
+        i64::from(shard.index)
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(messages)
+}
+
+async fn claim_pending_rsvp_notifications(
+    pool: &PgPool,
+    organizer_telegram_id: i64,
+) -> StorageResult<Vec<OutboxMessage>> {
+    let messages = sqlx::query_as!(
+        OutboxMessage,
+        r#"
+        UPDATE outbox_messages
+        SET status = 'processing'
+        WHERE id IN (
+            SELECT id
+            FROM outbox_messages
+            WHERE status = 'pending'
+              AND kind = 'rsvp_notification'
+              AND payload->>'organizer_telegram_id' = $1::BIGINT::text
+            FOR UPDATE SKIP LOCKED
+        )
+        RETURNING id AS "id: OutboxMessageId", kind, payload,
+                  status AS "status: OutboxStatus", retry_count,
+                  scheduled_at, processed_at, created_at
+        "#,
+        organizer_telegram_id
     )
-    .bind(batch_size)
     .fetch_all(pool)
     .await?;
 
@@ -109,12 +326,12 @@ async fn claim_pending_jobs(pool: &PgPool, batch_size: i64) -> StorageResult<Vec
 }
 
 async fn count_pending(pool: &PgPool) -> StorageResult<i64> {
-    let result = sqlx::query_scalar::<_, i64>(
+    let result = sqlx::query_scalar!(
         r#"
-        SELECT COUNT(*)
+        SELECT COUNT(*) AS "count!"
         FROM outbox_messages
         WHERE status = 'pending'
-        "#,
+        "#
     )
     .fetch_one(pool)
     .await?;
@@ -122,24 +339,28 @@ async fn count_pending(pool: &PgPool) -> StorageResult<i64> {
     Ok(result)
 }
 
-async fn mark_completed(pool: &PgPool, message_id: Uuid) -> StorageResult<()> {
-    sqlx::query(
+async fn mark_completed(pool: &PgPool, message_id: OutboxMessageId) -> StorageResult<()> {
+    sqlx::query!(
         r#"
         UPDATE outbox_messages
         SET status = 'completed',
             processed_at = NOW()
         WHERE id = $1
         "#,
+        message_id as OutboxMessageId
     )
-    .bind(message_id)
     .execute(pool)
     .await?;
 
     Ok(())
 }
 
-async fn mark_failed(pool: &PgPool, message_id: Uuid, error_msg: &str) -> StorageResult<()> {
-    sqlx::query(
+async fn mark_failed(
+    pool: &PgPool,
+    message_id: OutboxMessageId,
+    error_msg: &str,
+) -> StorageResult<()> {
+    sqlx::query!(
         r#"
         UPDATE outbox_messages
         SET status = 'failed',
@@ -147,9 +368,9 @@ async fn mark_failed(pool: &PgPool, message_id: Uuid, error_msg: &str) -> Storag
             error_message = $2
         WHERE id = $1
         "#,
+        message_id as OutboxMessageId,
+        error_msg
     )
-    .bind(message_id)
-    .bind(error_msg)
     .execute(pool)
     .await?;
 
@@ -158,14 +379,14 @@ async fn mark_failed(pool: &PgPool, message_id: Uuid, error_msg: &str) -> Storag
 
 async fn reschedule_message(
     pool: &PgPool,
-    message_id: Uuid,
+    message_id: OutboxMessageId,
     current_retry_count: i32,
     error_msg: &str,
 ) -> StorageResult<()> {
     let backoff_minutes = 2_i64.pow((current_retry_count + 1) as u32);
     let next_scheduled = Utc::now() + chrono::Duration::minutes(backoff_minutes);
 
-    sqlx::query(
+    sqlx::query!(
         r#"
         UPDATE outbox_messages
         SET status = 'pending',
@@ -174,10 +395,10 @@ async fn reschedule_message(
             error_message = $3
         WHERE id = $1
         "#,
+        message_id as OutboxMessageId,
+        next_scheduled,
+        error_msg
     )
-    .bind(message_id)
-    .bind(next_scheduled)
-    .bind(error_msg)
     .execute(pool)
     .await?;
 
@@ -219,21 +440,21 @@ async fn apply_updates(pool: &PgPool, updates: Vec<OutboxUpdate>) -> StorageResu
     let mut tx = pool.begin().await?;
 
     if !completed_ids.is_empty() {
-        sqlx::query(
+        sqlx::query!(
             r#"
             UPDATE outbox_messages
             SET status = 'completed',
                 processed_at = NOW()
             WHERE id = ANY($1)
             "#,
+            &completed_ids as &[OutboxMessageId]
         )
-        .bind(&completed_ids)
         .execute(&mut *tx)
         .await?;
     }
 
     if !failed_ids.is_empty() {
-        sqlx::query(
+        sqlx::query!(
             r#"
             UPDATE outbox_messages AS m
             SET status = 'failed',
@@ -242,15 +463,15 @@ async fn apply_updates(pool: &PgPool, updates: Vec<OutboxUpdate>) -> StorageResu
             FROM UNNEST($1::uuid[], $2::text[]) AS c(id, error)
             WHERE m.id = c.id
             "#,
+            &failed_ids as &[OutboxMessageId],
+            &failed_errors
         )
-        .bind(&failed_ids)
-        .bind(&failed_errors)
         .execute(&mut *tx)
         .await?;
     }
 
     if !reschedule_ids.is_empty() {
-        sqlx::query(
+        sqlx::query!(
             r#"
             UPDATE outbox_messages AS m
             SET status = 'pending',
@@ -261,11 +482,11 @@ async fn apply_updates(pool: &PgPool, updates: Vec<OutboxUpdate>) -> StorageResu
             AS c(id, retry_count, scheduled_at, error)
             WHERE m.id = c.id
             "#,
+            &reschedule_ids as &[OutboxMessageId],
+            &reschedule_counts,
+            &reschedule_times,
+            &reschedule_errors
         )
-        .bind(&reschedule_ids)
-        .bind(&reschedule_counts)
-        .bind(&reschedule_times)
-        .bind(&reschedule_errors)
         .execute(&mut *tx)
         .await?;
     }
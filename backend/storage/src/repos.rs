@@ -0,0 +1,384 @@
+//! Repository traits the application services talk to.
+//!
+//! [`CalendarRepository`] and [`DeviceRepository`] implement them over
+//! Postgres; the in-memory versions in `memory`, built for tests and behind
+//! the `test-util` feature, let handler and service tests run without a
+//! database.
+
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use televent_domain::{DeviceId, EventStatus, EventVisibility, Timezone, UserId, UserRole};
+use uuid::Uuid;
+
+use crate::StorageResult;
+use crate::calendar::{CalendarRepository, Event, EventAttendee, User};
+use crate::device::{
+    DeviceActivityRecord, DevicePasswordHash, DevicePasswordRecord, DevicePasswordSummary,
+    DeviceRepository, NewDeviceActivity, StoredDevicePassword,
+};
+
+/// Future returned by the repository traits
+pub type RepoFuture<'a, T> = Pin<Box<dyn Future<Output = StorageResult<T>> + Send + 'a>>;
+
+/// Users, keyed by Telegram id
+pub trait UsersRepo: Send + Sync {
+    /// The user, created on first sight with `username`
+    fn ensure_user<'a>(
+        &'a self,
+        telegram_id: i64,
+        username: Option<&'a str>,
+    ) -> RepoFuture<'a, User>;
+
+    fn get_user_by_id(&self, user_id: UserId) -> RepoFuture<'_, Option<User>>;
+
+    fn get_user_by_username<'a>(&'a self, username: &'a str) -> RepoFuture<'a, Option<User>>;
+
+    /// Case-insensitive match on the onboarding email
+    fn get_user_by_email<'a>(&'a self, email: &'a str) -> RepoFuture<'a, Option<User>>;
+
+    /// `false` when nothing changed or the user doesn't exist
+    fn refresh_username<'a>(
+        &'a self,
+        user_id: UserId,
+        username: Option<&'a str>,
+    ) -> RepoFuture<'a, bool>;
+
+    fn set_user_role(&self, user_id: UserId, role: UserRole) -> RepoFuture<'_, Option<User>>;
+
+    fn set_user_timezone<'a>(
+        &'a self,
+        user_id: UserId,
+        timezone: &'a Timezone,
+    ) -> RepoFuture<'a, Option<User>>;
+
+    fn set_user_email<'a>(
+        &'a self,
+        user_id: UserId,
+        email: Option<&'a str>,
+    ) -> RepoFuture<'a, Option<User>>;
+
+    fn set_default_event_visibility(
+        &self,
+        user_id: UserId,
+        visibility: EventVisibility,
+    ) -> RepoFuture<'_, Option<User>>;
+
+    /// `false` when the user was already onboarded
+    fn mark_user_onboarded(&self, user_id: UserId) -> RepoFuture<'_, bool>;
+
+    /// Make the given users admins, creating the missing ones
+    fn promote_admins<'a>(&'a self, telegram_ids: &'a [i64]) -> RepoFuture<'a, u64>;
+}
+
+/// Reads of events and their attendees
+pub trait EventsRepo: Send + Sync {
+    fn get_event_by_id(&self, user_id: UserId, event_id: Uuid) -> RepoFuture<'_, Option<Event>>;
+
+    /// The event whoever owns it
+    fn get_event_by_id_any(&self, event_id: Uuid) -> RepoFuture<'_, Option<Event>>;
+
+    fn get_event_by_uid<'a>(
+        &'a self,
+        user_id: UserId,
+        uid: &'a str,
+    ) -> RepoFuture<'a, Option<Event>>;
+
+    fn get_events_by_uids<'a>(
+        &'a self,
+        user_id: UserId,
+        uids: &'a [&'a str],
+    ) -> RepoFuture<'a, Vec<Event>>;
+
+    fn get_events_by_ids_any<'a>(&'a self, event_ids: &'a [Uuid]) -> RepoFuture<'a, Vec<Event>>;
+
+    fn get_event_attendees(&self, event_id: Uuid) -> RepoFuture<'_, Vec<EventAttendee>>;
+
+    fn get_event_attendees_bulk<'a>(
+        &'a self,
+        event_ids: &'a [Uuid],
+    ) -> RepoFuture<'a, HashMap<Uuid, Vec<EventAttendee>>>;
+
+    /// Events in start order; an empty `statuses` slice means any status,
+    /// and with `tag` only events whose title has that hashtag
+    #[allow(clippy::too_many_arguments)]
+    fn list_events<'a>(
+        &'a self,
+        user_id: UserId,
+        start: Option<DateTime<Utc>>,
+        end: Option<DateTime<Utc>>,
+        statuses: &'a [EventStatus],
+        tag: Option<&'a str>,
+        limit: Option<i64>,
+        offset: Option<i64>,
+    ) -> RepoFuture<'a, Vec<Event>>;
+}
+
+/// CalDAV device passwords and their request log
+pub trait DevicesRepo: Send + Sync {
+    /// Store a password, creating the user if needed; `None` when the user
+    /// already has `max_devices` of them
+    fn create_device_password<'a>(
+        &'a self,
+        username: Option<&'a str>,
+        password: StoredDevicePassword,
+        max_devices: i64,
+    ) -> RepoFuture<'a, Option<DevicePasswordRecord>>;
+
+    /// Most recently used first, counting requests logged since
+    /// `recent_since`; `active_since` drops devices not used since then
+    fn list_device_passwords(
+        &self,
+        user_id: UserId,
+        recent_since: DateTime<Utc>,
+        active_since: Option<DateTime<Utc>>,
+    ) -> RepoFuture<'_, Vec<DevicePasswordSummary>>;
+
+    /// Most recently used first
+    fn list_device_password_hashes(
+        &self,
+        user_id: UserId,
+        limit: i64,
+    ) -> RepoFuture<'_, Vec<DevicePasswordHash>>;
+
+    /// `false` when the user has no such device
+    fn delete_device_password(&self, user_id: UserId, device_id: DeviceId) -> RepoFuture<'_, bool>;
+
+    fn touch_device_password(&self, device_id: DeviceId) -> RepoFuture<'_, ()>;
+
+    /// Compare-and-set of the stored hash; `false` if it changed meanwhile
+    fn replace_password_hash(
+        &self,
+        device_id: DeviceId,
+        current_hash: String,
+        new_hash: String,
+    ) -> RepoFuture<'_, bool>;
+
+    /// Log a request, keeping only the newest `keep` rows for the device
+    fn insert_device_activity(&self, activity: NewDeviceActivity, keep: i64) -> RepoFuture<'_, ()>;
+
+    /// Newest first, or `None` if the user has no such device
+    fn list_device_activity(
+        &self,
+        user_id: UserId,
+        device_id: DeviceId,
+    ) -> RepoFuture<'_, Option<Vec<DeviceActivityRecord>>>;
+}
+
+/// Users repository shared by the services of one process
+pub type SharedUsersRepo = Arc<dyn UsersRepo>;
+
+/// Events repository shared by the services of one process
+pub type SharedEventsRepo = Arc<dyn EventsRepo>;
+
+/// Devices repository shared by the services of one process
+pub type SharedDevicesRepo = Arc<dyn DevicesRepo>;
+
+impl UsersRepo for CalendarRepository {
+    fn ensure_user<'a>(
+        &'a self,
+        telegram_id: i64,
+        username: Option<&'a str>,
+    ) -> RepoFuture<'a, User> {
+        Box::pin(CalendarRepository::ensure_user(self, telegram_id, username))
+    }
+
+    fn get_user_by_id(&self, user_id: UserId) -> RepoFuture<'_, Option<User>> {
+        Box::pin(CalendarRepository::get_user_by_id(self, user_id))
+    }
+
+    fn get_user_by_username<'a>(&'a self, username: &'a str) -> RepoFuture<'a, Option<User>> {
+        Box::pin(CalendarRepository::get_user_by_username(self, username))
+    }
+
+    fn get_user_by_email<'a>(&'a self, email: &'a str) -> RepoFuture<'a, Option<User>> {
+        Box::pin(CalendarRepository::get_user_by_email(self, email))
+    }
+
+    fn refresh_username<'a>(
+        &'a self,
+        user_id: UserId,
+        username: Option<&'a str>,
+    ) -> RepoFuture<'a, bool> {
+        Box::pin(CalendarRepository::refresh_username(
+            self, user_id, username,
+        ))
+    }
+
+    fn set_user_role(&self, user_id: UserId, role: UserRole) -> RepoFuture<'_, Option<User>> {
+        Box::pin(CalendarRepository::set_user_role(self, user_id, role))
+    }
+
+    fn set_user_timezone<'a>(
+        &'a self,
+        user_id: UserId,
+        timezone: &'a Timezone,
+    ) -> RepoFuture<'a, Option<User>> {
+        Box::pin(CalendarRepository::set_user_timezone(
+            self, user_id, timezone,
+        ))
+    }
+
+    fn set_user_email<'a>(
+        &'a self,
+        user_id: UserId,
+        email: Option<&'a str>,
+    ) -> RepoFuture<'a, Option<User>> {
+        Box::pin(CalendarRepository::set_user_email(self, user_id, email))
+    }
+
+    fn set_default_event_visibility(
+        &self,
+        user_id: UserId,
+        visibility: EventVisibility,
+    ) -> RepoFuture<'_, Option<User>> {
+        Box::pin(CalendarRepository::set_default_event_visibility(
+            self, user_id, visibility,
+        ))
+    }
+
+    fn mark_user_onboarded(&self, user_id: UserId) -> RepoFuture<'_, bool> {
+        Box::pin(CalendarRepository::mark_user_onboarded(self, user_id))
+    }
+
+    fn promote_admins<'a>(&'a self, telegram_ids: &'a [i64]) -> RepoFuture<'a, u64> {
+        Box::pin(CalendarRepository::promote_admins(self, telegram_ids))
+    }
+}
+
+impl EventsRepo for CalendarRepository {
+    fn get_event_by_id(&self, user_id: UserId, event_id: Uuid) -> RepoFuture<'_, Option<Event>> {
+        Box::pin(CalendarRepository::get_event_by_id(self, user_id, event_id))
+    }
+
+    fn get_event_by_id_any(&self, event_id: Uuid) -> RepoFuture<'_, Option<Event>> {
+        Box::pin(CalendarRepository::get_event_by_id_any(self, event_id))
+    }
+
+    fn get_event_by_uid<'a>(
+        &'a self,
+        user_id: UserId,
+        uid: &'a str,
+    ) -> RepoFuture<'a, Option<Event>> {
+        Box::pin(CalendarRepository::get_event_by_uid(self, user_id, uid))
+    }
+
+    fn get_events_by_uids<'a>(
+        &'a self,
+        user_id: UserId,
+        uids: &'a [&'a str],
+    ) -> RepoFuture<'a, Vec<Event>> {
+        Box::pin(CalendarRepository::get_events_by_uids(self, user_id, uids))
+    }
+
+    fn get_events_by_ids_any<'a>(&'a self, event_ids: &'a [Uuid]) -> RepoFuture<'a, Vec<Event>> {
+        Box::pin(CalendarRepository::get_events_by_ids_any(self, event_ids))
+    }
+
+    fn get_event_attendees(&self, event_id: Uuid) -> RepoFuture<'_, Vec<EventAttendee>> {
+        Box::pin(CalendarRepository::get_event_attendees(self, event_id))
+    }
+
+    fn get_event_attendees_bulk<'a>(
+        &'a self,
+        event_ids: &'a [Uuid],
+    ) -> RepoFuture<'a, HashMap<Uuid, Vec<EventAttendee>>> {
+        Box::pin(CalendarRepository::get_event_attendees_bulk(
+            self, event_ids,
+        ))
+    }
+
+    fn list_events<'a>(
+        &'a self,
+        user_id: UserId,
+        start: Option<DateTime<Utc>>,
+        end: Option<DateTime<Utc>>,
+        statuses: &'a [EventStatus],
+        tag: Option<&'a str>,
+        limit: Option<i64>,
+        offset: Option<i64>,
+    ) -> RepoFuture<'a, Vec<Event>> {
+        Box::pin(CalendarRepository::list_events(
+            self, user_id, start, end, statuses, tag, limit, offset,
+        ))
+    }
+}
+
+impl DevicesRepo for DeviceRepository {
+    fn create_device_password<'a>(
+        &'a self,
+        username: Option<&'a str>,
+        password: StoredDevicePassword,
+        max_devices: i64,
+    ) -> RepoFuture<'a, Option<DevicePasswordRecord>> {
+        Box::pin(DeviceRepository::create_device_password(
+            self,
+            username,
+            password,
+            max_devices,
+        ))
+    }
+
+    fn list_device_passwords(
+        &self,
+        user_id: UserId,
+        recent_since: DateTime<Utc>,
+        active_since: Option<DateTime<Utc>>,
+    ) -> RepoFuture<'_, Vec<DevicePasswordSummary>> {
+        Box::pin(DeviceRepository::list_device_passwords(
+            self,
+            user_id,
+            recent_since,
+            active_since,
+        ))
+    }
+
+    fn list_device_password_hashes(
+        &self,
+        user_id: UserId,
+        limit: i64,
+    ) -> RepoFuture<'_, Vec<DevicePasswordHash>> {
+        Box::pin(DeviceRepository::list_device_password_hashes(
+            self, user_id, limit,
+        ))
+    }
+
+    fn delete_device_password(&self, user_id: UserId, device_id: DeviceId) -> RepoFuture<'_, bool> {
+        Box::pin(DeviceRepository::delete_device_password(
+            self, user_id, device_id,
+        ))
+    }
+
+    fn touch_device_password(&self, device_id: DeviceId) -> RepoFuture<'_, ()> {
+        Box::pin(DeviceRepository::touch_device_password(self, device_id))
+    }
+
+    fn replace_password_hash(
+        &self,
+        device_id: DeviceId,
+        current_hash: String,
+        new_hash: String,
+    ) -> RepoFuture<'_, bool> {
+        Box::pin(async move {
+            DeviceRepository::replace_password_hash(self, device_id, &current_hash, &new_hash).await
+        })
+    }
+
+    fn insert_device_activity(&self, activity: NewDeviceActivity, keep: i64) -> RepoFuture<'_, ()> {
+        Box::pin(DeviceRepository::insert_device_activity(
+            self, activity, keep,
+        ))
+    }
+
+    fn list_device_activity(
+        &self,
+        user_id: UserId,
+        device_id: DeviceId,
+    ) -> RepoFuture<'_, Option<Vec<DeviceActivityRecord>>> {
+        Box::pin(DeviceRepository::list_device_activity(
+            self, user_id, device_id,
+        ))
+    }
+}
@@ -0,0 +1,247 @@
+//! Bookable resources (meeting rooms) and the events holding them.
+
+use chrono::{DateTime, Utc};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::StorageResult;
+
+/// Postgres `exclusion_violation`, raised when two bookings of a resource overlap
+const EXCLUSION_VIOLATION: &str = "23P01";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Resource {
+    pub id: Uuid,
+    pub name: String,
+    pub sync_token: i64,
+    pub created_at: DateTime<Utc>,
+}
+
+/// The time an event holds a resource
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResourceBooking {
+    pub event_id: Uuid,
+    pub resource_id: Uuid,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+fn is_exclusion_violation(err: &sqlx::Error) -> bool {
+    matches!(err, sqlx::Error::Database(err) if err.code().as_deref() == Some(EXCLUSION_VIOLATION))
+}
+
+/// `None` when a resource with that name (ignoring case) exists
+pub(crate) async fn create(pool: &PgPool, name: &str) -> StorageResult<Option<Resource>> {
+    let result = sqlx::query_as!(
+        Resource,
+        r#"
+        INSERT INTO resources (name)
+        VALUES ($1)
+        RETURNING id, name, sync_token, created_at
+        "#,
+        name
+    )
+    .fetch_one(pool)
+    .await;
+
+    match result {
+        Ok(resource) => Ok(Some(resource)),
+        Err(sqlx::Error::Database(err)) if err.is_unique_violation() => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+pub(crate) async fn list(pool: &PgPool) -> StorageResult<Vec<Resource>> {
+    let resources = sqlx::query_as!(
+        Resource,
+        r#"
+        SELECT id, name, sync_token, created_at
+        FROM resources
+        ORDER BY lower(name)
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(resources)
+}
+
+pub(crate) async fn get(pool: &PgPool, resource_id: Uuid) -> StorageResult<Option<Resource>> {
+    let resource = sqlx::query_as!(
+        Resource,
+        r#"
+        SELECT id, name, sync_token, created_at
+        FROM resources
+        WHERE id = $1
+        "#,
+        resource_id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(resource)
+}
+
+/// Delete the resource and release every event booking it
+pub(crate) async fn delete(pool: &PgPool, resource_id: Uuid) -> StorageResult<bool> {
+    let result = sqlx::query!("DELETE FROM resources WHERE id = $1", resource_id)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Book `resource_id` for the time of `event_id`, moving the event off any
+/// resource it held; `false` when the resource is taken at that time
+pub(crate) async fn book(
+    conn: &mut PgConnection,
+    event_id: Uuid,
+    resource_id: Uuid,
+) -> StorageResult<bool> {
+    let result = sqlx::query!(
+        r#"
+        INSERT INTO resource_bookings (event_id, resource_id, during)
+        SELECT e.id, $2, event_busy_range(e, u.timezone)
+        FROM events e
+        JOIN users u ON u.telegram_id = e.user_id
+        WHERE e.id = $1
+        ON CONFLICT (event_id) DO UPDATE
+        SET resource_id = EXCLUDED.resource_id,
+            during = EXCLUDED.during,
+            updated_at = NOW()
+        "#,
+        event_id,
+        resource_id
+    )
+    .execute(conn)
+    .await;
+
+    match result {
+        Ok(_) => Ok(true),
+        Err(err) if is_exclusion_violation(&err) => Ok(false),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// `false` when the event held no resource
+pub(crate) async fn release(conn: &mut PgConnection, event_id: Uuid) -> StorageResult<bool> {
+    let result = sqlx::query!(
+        "DELETE FROM resource_bookings WHERE event_id = $1",
+        event_id
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Bring the booking of `event_id` in line with the event after it changed:
+/// cancelled or recurring events let the resource go, moved ones take it at
+/// their new time. `false` when the resource is taken at the new time.
+pub(crate) async fn resync(conn: &mut PgConnection, event_id: Uuid) -> StorageResult<bool> {
+    sqlx::query!(
+        r#"
+        DELETE FROM resource_bookings b
+        USING events e
+        WHERE b.event_id = $1
+          AND e.id = b.event_id
+          AND (e.status = 'CANCELLED' OR e.rrule IS NOT NULL)
+        "#,
+        event_id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    let result = sqlx::query!(
+        r#"
+        UPDATE resource_bookings b
+        SET during = event_busy_range(e, u.timezone), updated_at = NOW()
+        FROM events e
+        JOIN users u ON u.telegram_id = e.user_id
+        WHERE b.event_id = $1
+          AND e.id = b.event_id
+          AND b.during <> event_busy_range(e, u.timezone)
+        "#,
+        event_id
+    )
+    .execute(conn)
+    .await;
+
+    match result {
+        Ok(_) => Ok(true),
+        Err(err) if is_exclusion_violation(&err) => Ok(false),
+        Err(err) => Err(err.into()),
+    }
+}
+
+pub(crate) async fn booking_for_event(
+    pool: &PgPool,
+    event_id: Uuid,
+) -> StorageResult<Option<ResourceBooking>> {
+    let booking = sqlx::query_as!(
+        ResourceBooking,
+        r#"
+        SELECT event_id, resource_id, lower(during) AS "start!", upper(during) AS "end!",
+               updated_at
+        FROM resource_bookings
+        WHERE event_id = $1
+        "#,
+        event_id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(booking)
+}
+
+/// Bookings of the resource overlapping `[from, to)`, or all of them,
+/// earliest first
+pub(crate) async fn list_bookings(
+    pool: &PgPool,
+    resource_id: Uuid,
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+) -> StorageResult<Vec<ResourceBooking>> {
+    let bookings = sqlx::query_as!(
+        ResourceBooking,
+        r#"
+        SELECT event_id, resource_id, lower(during) AS "start!", upper(during) AS "end!",
+               updated_at
+        FROM resource_bookings
+        WHERE resource_id = $1
+          AND during && tstzrange($2, $3)
+        ORDER BY lower(during)
+        "#,
+        resource_id,
+        from,
+        to
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(bookings)
+}
+
+pub(crate) async fn get_bookings_by_event_ids(
+    pool: &PgPool,
+    resource_id: Uuid,
+    event_ids: &[Uuid],
+) -> StorageResult<Vec<ResourceBooking>> {
+    let bookings = sqlx::query_as!(
+        ResourceBooking,
+        r#"
+        SELECT event_id, resource_id, lower(during) AS "start!", upper(during) AS "end!",
+               updated_at
+        FROM resource_bookings
+        WHERE resource_id = $1 AND event_id = ANY($2)
+        ORDER BY lower(during)
+        "#,
+        resource_id,
+        event_ids
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(bookings)
+}
@@ -0,0 +1,199 @@
+//! Deletion of rows past their retention period
+//!
+//! Each prune removes at most `batch_size` rows, so a large backlog is worked
+//! off in short statements rather than one long delete holding its locks.
+
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+
+use crate::StorageResult;
+
+#[derive(Clone)]
+pub struct RetentionRepository {
+    pool: PgPool,
+}
+
+impl RetentionRepository {
+    #[must_use]
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Delete tombstones of events deleted before `cutoff`, moving each
+    /// affected user's `tombstones_pruned_through` up to the newest one so
+    /// sync tokens that would have needed them are refused
+    pub async fn prune_event_tombstones(
+        &self,
+        cutoff: DateTime<Utc>,
+        batch_size: i64,
+    ) -> StorageResult<u64> {
+        let pruned = sqlx::query_scalar!(
+            r#"
+            WITH pruned AS (
+                DELETE FROM event_tombstones
+                WHERE (user_id, uid) IN (
+                    SELECT user_id, uid
+                    FROM event_tombstones
+                    WHERE deleted_at < $1
+                    LIMIT $2
+                )
+                RETURNING user_id, sync_version
+            ),
+            watermark AS (
+                UPDATE users
+                SET tombstones_pruned_through = GREATEST(
+                    users.tombstones_pruned_through, latest.sync_version
+                )
+                FROM (
+                    SELECT user_id, MAX(sync_version) AS sync_version
+                    FROM pruned
+                    GROUP BY user_id
+                ) AS latest
+                WHERE users.telegram_id = latest.user_id
+            )
+            SELECT COUNT(*) AS "count!" FROM pruned
+            "#,
+            cutoff,
+            batch_size
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(pruned as u64)
+    }
+
+    /// Delete completed and failed outbox messages last touched before
+    /// `cutoff`; pending and claimed ones are never pruned
+    pub async fn prune_outbox(&self, cutoff: DateTime<Utc>, batch_size: i64) -> StorageResult<u64> {
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM outbox_messages
+            WHERE id IN (
+                SELECT id
+                FROM outbox_messages
+                WHERE status IN ('completed', 'failed')
+                  AND updated_at < $1
+                LIMIT $2
+            )
+            "#,
+            cutoff,
+            batch_size
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Delete CalDAV device activity recorded before `cutoff`
+    pub async fn prune_device_activity(
+        &self,
+        cutoff: DateTime<Utc>,
+        batch_size: i64,
+    ) -> StorageResult<u64> {
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM device_activity
+            WHERE id IN (
+                SELECT id FROM device_activity WHERE created_at < $1 LIMIT $2
+            )
+            "#,
+            cutoff,
+            batch_size
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Delete bounce and complaint reports received before `cutoff`.
+    /// Suppressions they caused stay in place.
+    pub async fn prune_email_feedback(
+        &self,
+        cutoff: DateTime<Utc>,
+        batch_size: i64,
+    ) -> StorageResult<u64> {
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM email_feedback
+            WHERE id IN (
+                SELECT id FROM email_feedback WHERE received_at < $1 LIMIT $2
+            )
+            "#,
+            cutoff,
+            batch_size
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Delete slow query plans captured before `cutoff`
+    pub async fn prune_slow_query_plans(
+        &self,
+        cutoff: DateTime<Utc>,
+        batch_size: i64,
+    ) -> StorageResult<u64> {
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM slow_query_plans
+            WHERE id IN (
+                SELECT id FROM slow_query_plans WHERE captured_at < $1 LIMIT $2
+            )
+            "#,
+            cutoff,
+            batch_size
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Delete sent-email records from before `cutoff`, which no hourly cap
+    /// counts any more
+    pub async fn prune_email_sends(
+        &self,
+        cutoff: DateTime<Utc>,
+        batch_size: i64,
+    ) -> StorageResult<u64> {
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM email_sends
+            WHERE id IN (
+                SELECT id FROM email_sends WHERE sent_at < $1 LIMIT $2
+            )
+            "#,
+            cutoff,
+            batch_size
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Delete Google sign-in states that expired before `now`
+    pub async fn prune_google_oauth_states(
+        &self,
+        now: DateTime<Utc>,
+        batch_size: i64,
+    ) -> StorageResult<u64> {
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM google_oauth_states
+            WHERE state_hash IN (
+                SELECT state_hash FROM google_oauth_states WHERE expires_at < $1 LIMIT $2
+            )
+            "#,
+            now,
+            batch_size
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+}
@@ -0,0 +1,94 @@
+//! Event revision history: who made each change to an event.
+
+use chrono::{DateTime, Utc};
+use sqlx::{PgConnection, PgPool};
+use televent_domain::UserId;
+use uuid::Uuid;
+
+use crate::StorageResult;
+
+/// One recorded change to an event
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EventRevision {
+    pub event_id: Uuid,
+    pub actor_id: UserId,
+    pub actor_username: Option<String>,
+    /// `created`, `updated` or `deleted`
+    pub action: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A change to record
+#[derive(Debug, Clone)]
+pub struct EventRevisionWrite {
+    pub event_id: Uuid,
+    /// Owner of the calendar the event is on
+    pub user_id: UserId,
+    pub actor_id: UserId,
+    pub action: &'static str,
+}
+
+struct EventRevisionRow {
+    event_id: Uuid,
+    actor_id: i64,
+    actor_username: Option<String>,
+    action: String,
+    created_at: DateTime<Utc>,
+}
+
+impl From<EventRevisionRow> for EventRevision {
+    fn from(row: EventRevisionRow) -> Self {
+        Self {
+            event_id: row.event_id,
+            actor_id: UserId::new(row.actor_id),
+            actor_username: row.actor_username,
+            action: row.action,
+            created_at: row.created_at,
+        }
+    }
+}
+
+pub(crate) async fn insert(
+    conn: &mut PgConnection,
+    revision: EventRevisionWrite,
+) -> StorageResult<()> {
+    sqlx::query!(
+        r#"
+        INSERT INTO event_revisions (event_id, user_id, actor_id, action)
+        VALUES ($1, $2, $3, $4)
+        "#,
+        revision.event_id,
+        revision.user_id.inner(),
+        revision.actor_id.inner(),
+        revision.action
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Changes to one of the user's events, oldest first
+pub(crate) async fn list_for_event(
+    pool: &PgPool,
+    user_id: UserId,
+    event_id: Uuid,
+) -> StorageResult<Vec<EventRevision>> {
+    let rows = sqlx::query_as!(
+        EventRevisionRow,
+        r#"
+        SELECT r.event_id, r.actor_id, u.telegram_username AS actor_username, r.action,
+               r.created_at
+        FROM event_revisions r
+        LEFT JOIN users u ON u.telegram_id = r.actor_id
+        WHERE r.user_id = $1 AND r.event_id = $2
+        ORDER BY r.created_at, r.id
+        "#,
+        user_id.inner(),
+        event_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(EventRevision::from).collect())
+}
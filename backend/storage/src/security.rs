@@ -0,0 +1,455 @@
+//! Encryption of sensitive columns
+//!
+//! Values the application has to read back in clear, such as OAuth tokens,
+//! chat webhook URLs and phone numbers, are sealed with AES-256-GCM before
+//! they are written. A sealed value names the key it was sealed under, so
+//! during a rotation the previous key still opens older rows while new
+//! writes use the current one. Without a key values are stored as given,
+//! and rows written before a key was configured stay readable after.
+//!
+//! Sealing is randomized, so columns that are looked up or kept unique also
+//! store a keyed digest of the value.
+
+use std::sync::Arc;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use ring::aead::{AES_256_GCM, Aad, LessSafeKey, NONCE_LEN, Nonce, UnboundKey};
+use ring::rand::{SecureRandom, SystemRandom};
+use ring::{digest, hmac};
+use sqlx::PgPool;
+
+use crate::{StorageError, StorageResult};
+
+/// Marks a sealed value; followed by the key id and the base64 payload
+const SEALED_PREFIX: &str = "enc:v1:";
+
+/// Length of a column encryption key in bytes
+pub const COLUMN_KEY_LEN: usize = 32;
+
+/// Column names bound into each sealed value, so a value copied to another
+/// column doesn't open there
+pub(crate) const GOOGLE_ACCESS_TOKEN: &str = "google_calendar_connections.access_token";
+pub(crate) const GOOGLE_REFRESH_TOKEN: &str = "google_calendar_connections.refresh_token";
+pub(crate) const CHAT_WEBHOOK_URL: &str = "chat_webhooks.url";
+pub(crate) const PHONE_NUMBER: &str = "phone_numbers.phone_number";
+
+/// Rows read per statement by [`reseal_columns`]
+const RESEAL_BATCH_SIZE: i64 = 500;
+
+/// Decode a base64 column key, as `openssl rand -base64 32` prints it
+pub fn parse_column_key(encoded: &str) -> Result<[u8; COLUMN_KEY_LEN], String> {
+    let bytes = STANDARD
+        .decode(encoded.trim())
+        .map_err(|_| "column key is not base64".to_string())?;
+    bytes
+        .try_into()
+        .map_err(|_| format!("column key must be {COLUMN_KEY_LEN} bytes"))
+}
+
+struct ColumnKey {
+    id: String,
+    aead: LessSafeKey,
+    digest: hmac::Key,
+}
+
+impl ColumnKey {
+    fn new(key: &[u8; COLUMN_KEY_LEN]) -> Self {
+        let fingerprint = digest::digest(&digest::SHA256, key);
+        let digest_key = hmac::sign(
+            &hmac::Key::new(hmac::HMAC_SHA256, key),
+            b"televent column digest",
+        );
+        Self {
+            id: hex::encode(&fingerprint.as_ref()[..4]),
+            aead: LessSafeKey::new(
+                UnboundKey::new(&AES_256_GCM, key).expect("key has the AES-256 length"),
+            ),
+            digest: hmac::Key::new(hmac::HMAC_SHA256, digest_key.as_ref()),
+        }
+    }
+
+    /// Lookup digest of `value` in `column`
+    fn digest(&self, column: &str, value: &str) -> String {
+        let mut context = hmac::Context::with_key(&self.digest);
+        context.update(column.as_bytes());
+        context.update(&[0]);
+        context.update(value.as_bytes());
+        hex::encode(context.sign().as_ref())
+    }
+}
+
+struct ColumnKeys {
+    current: ColumnKey,
+    previous: Option<ColumnKey>,
+    rng: SystemRandom,
+}
+
+/// Seals and opens sensitive column values; disabled by default
+#[derive(Clone, Default)]
+pub struct ColumnCipher {
+    keys: Option<Arc<ColumnKeys>>,
+}
+
+impl std::fmt::Debug for ColumnCipher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ColumnCipher")
+            .field(
+                "current_key",
+                &self.keys.as_ref().map(|keys| &keys.current.id),
+            )
+            .field(
+                "previous_key",
+                &self
+                    .keys
+                    .as_ref()
+                    .and_then(|keys| keys.previous.as_ref())
+                    .map(|key| &key.id),
+            )
+            .finish()
+    }
+}
+
+impl ColumnCipher {
+    /// Seal with `current`; `previous` only opens values sealed before a
+    /// rotation
+    #[must_use]
+    pub fn new(current: &[u8; COLUMN_KEY_LEN], previous: Option<&[u8; COLUMN_KEY_LEN]>) -> Self {
+        Self {
+            keys: Some(Arc::new(ColumnKeys {
+                current: ColumnKey::new(current),
+                previous: previous.map(ColumnKey::new),
+                rng: SystemRandom::new(),
+            })),
+        }
+    }
+
+    #[must_use]
+    pub fn is_enabled(&self) -> bool {
+        self.keys.is_some()
+    }
+
+    /// `value` as it is stored in `column`
+    pub fn seal(&self, column: &str, value: &str) -> StorageResult<String> {
+        let Some(keys) = &self.keys else {
+            return Ok(value.to_string());
+        };
+
+        let mut nonce = [0u8; NONCE_LEN];
+        keys.rng
+            .fill(&mut nonce)
+            .map_err(|_| StorageError::Encryption("no randomness for a nonce".to_string()))?;
+        let mut sealed = value.as_bytes().to_vec();
+        keys.current
+            .aead
+            .seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::from(column.as_bytes()),
+                &mut sealed,
+            )
+            .map_err(|_| StorageError::Encryption(format!("could not seal {column}")))?;
+
+        let mut payload = nonce.to_vec();
+        payload.extend_from_slice(&sealed);
+        Ok(format!(
+            "{SEALED_PREFIX}{}:{}",
+            keys.current.id,
+            STANDARD.encode(payload)
+        ))
+    }
+
+    /// The clear value of `stored`, read from `column`
+    pub fn open(&self, column: &str, stored: String) -> StorageResult<String> {
+        let Some(sealed) = stored.strip_prefix(SEALED_PREFIX) else {
+            return Ok(stored);
+        };
+        let keys = self.keys.as_ref().ok_or_else(|| {
+            StorageError::Encryption(format!("{column} is encrypted but no key is configured"))
+        })?;
+        let (key_id, payload) = sealed
+            .split_once(':')
+            .ok_or_else(|| StorageError::Encryption(format!("malformed value in {column}")))?;
+        let key = [Some(&keys.current), keys.previous.as_ref()]
+            .into_iter()
+            .flatten()
+            .find(|key| key.id == key_id)
+            .ok_or_else(|| {
+                StorageError::Encryption(format!("{column} was sealed with unknown key {key_id}"))
+            })?;
+
+        let mut payload = STANDARD
+            .decode(payload)
+            .map_err(|_| StorageError::Encryption(format!("malformed value in {column}")))?;
+        if payload.len() < NONCE_LEN {
+            return Err(StorageError::Encryption(format!(
+                "malformed value in {column}"
+            )));
+        }
+        let mut sealed = payload.split_off(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(&payload)
+            .map_err(|_| StorageError::Encryption(format!("malformed value in {column}")))?;
+        let clear = key
+            .aead
+            .open_in_place(nonce, Aad::from(column.as_bytes()), &mut sealed)
+            .map_err(|_| StorageError::Encryption(format!("could not open {column}")))?;
+
+        String::from_utf8(clear.to_vec())
+            .map_err(|_| StorageError::Encryption(format!("{column} is not text")))
+    }
+
+    /// Digest stored next to a sealed value of `column`; `None` while
+    /// encryption is off
+    #[must_use]
+    pub fn digest(&self, column: &str, value: &str) -> Option<String> {
+        self.keys
+            .as_ref()
+            .map(|keys| keys.current.digest(column, value))
+    }
+
+    /// Digests a stored copy of `value` may carry, under either key
+    #[must_use]
+    pub fn digests(&self, column: &str, value: &str) -> Vec<String> {
+        let Some(keys) = &self.keys else {
+            return Vec::new();
+        };
+        [Some(&keys.current), keys.previous.as_ref()]
+            .into_iter()
+            .flatten()
+            .map(|key| key.digest(column, value))
+            .collect()
+    }
+
+    /// Whether `stored` still has to be sealed under the current key
+    fn is_stale(&self, stored: &str) -> bool {
+        let Some(keys) = &self.keys else {
+            return false;
+        };
+        stored
+            .strip_prefix(SEALED_PREFIX)
+            .and_then(|sealed| sealed.split_once(':'))
+            .is_none_or(|(key_id, _)| key_id != keys.current.id)
+    }
+}
+
+/// Rows [`reseal_columns`] rewrote, per table
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ResealCounts {
+    pub google_connections: u64,
+    pub chat_webhooks: u64,
+    pub phone_numbers: u64,
+}
+
+/// Seal every sensitive value that is in clear or under the previous key
+/// with the current key, so the previous key can be retired.
+///
+/// Each row is only rewritten while it still holds what was read, so a
+/// value changed in the meantime is left to its newer writer.
+pub async fn reseal_columns(pool: &PgPool, cipher: &ColumnCipher) -> StorageResult<ResealCounts> {
+    let mut counts = ResealCounts::default();
+    if !cipher.is_enabled() {
+        return Ok(counts);
+    }
+
+    let mut after = i64::MIN;
+    loop {
+        let rows = sqlx::query!(
+            r#"
+            SELECT user_id, access_token, refresh_token
+            FROM google_calendar_connections
+            WHERE user_id > $1
+            ORDER BY user_id
+            LIMIT $2
+            "#,
+            after,
+            RESEAL_BATCH_SIZE
+        )
+        .fetch_all(pool)
+        .await?;
+        let Some(last) = rows.last() else {
+            break;
+        };
+        after = last.user_id;
+        for row in rows {
+            if !cipher.is_stale(&row.access_token) && !cipher.is_stale(&row.refresh_token) {
+                continue;
+            }
+            let access_token = cipher.open(GOOGLE_ACCESS_TOKEN, row.access_token.clone())?;
+            let refresh_token = cipher.open(GOOGLE_REFRESH_TOKEN, row.refresh_token.clone())?;
+            let result = sqlx::query!(
+                r#"
+                UPDATE google_calendar_connections
+                SET access_token = $4, refresh_token = $5
+                WHERE user_id = $1 AND access_token = $2 AND refresh_token = $3
+                "#,
+                row.user_id,
+                row.access_token,
+                row.refresh_token,
+                cipher.seal(GOOGLE_ACCESS_TOKEN, &access_token)?,
+                cipher.seal(GOOGLE_REFRESH_TOKEN, &refresh_token)?
+            )
+            .execute(pool)
+            .await?;
+            counts.google_connections += result.rows_affected();
+        }
+    }
+
+    let mut after = uuid::Uuid::nil();
+    loop {
+        let rows = sqlx::query!(
+            "SELECT id, url FROM chat_webhooks WHERE id > $1 ORDER BY id LIMIT $2",
+            after,
+            RESEAL_BATCH_SIZE
+        )
+        .fetch_all(pool)
+        .await?;
+        let Some(last) = rows.last() else {
+            break;
+        };
+        after = last.id;
+        for row in rows {
+            if !cipher.is_stale(&row.url) {
+                continue;
+            }
+            let url = cipher.open(CHAT_WEBHOOK_URL, row.url.clone())?;
+            let result = sqlx::query!(
+                r#"
+                UPDATE chat_webhooks
+                SET url = $3, url_digest = $4
+                WHERE id = $1 AND url = $2
+                "#,
+                row.id,
+                row.url,
+                cipher.seal(CHAT_WEBHOOK_URL, &url)?,
+                cipher.digest(CHAT_WEBHOOK_URL, &url)
+            )
+            .execute(pool)
+            .await?;
+            counts.chat_webhooks += result.rows_affected();
+        }
+    }
+
+    let mut after = i64::MIN;
+    loop {
+        let rows = sqlx::query!(
+            r#"
+            SELECT user_id, phone_number
+            FROM phone_numbers
+            WHERE user_id > $1
+            ORDER BY user_id
+            LIMIT $2
+            "#,
+            after,
+            RESEAL_BATCH_SIZE
+        )
+        .fetch_all(pool)
+        .await?;
+        let Some(last) = rows.last() else {
+            break;
+        };
+        after = last.user_id;
+        for row in rows {
+            if !cipher.is_stale(&row.phone_number) {
+                continue;
+            }
+            let phone_number = cipher.open(PHONE_NUMBER, row.phone_number.clone())?;
+            let result = sqlx::query!(
+                r#"
+                UPDATE phone_numbers
+                SET phone_number = $3, phone_number_digest = $4
+                WHERE user_id = $1 AND phone_number = $2
+                "#,
+                row.user_id,
+                row.phone_number,
+                cipher.seal(PHONE_NUMBER, &phone_number)?,
+                cipher.digest(PHONE_NUMBER, &phone_number)
+            )
+            .execute(pool)
+            .await?;
+            counts.phone_numbers += result.rows_affected();
+        }
+    }
+
+    Ok(counts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: [u8; COLUMN_KEY_LEN] = [7; COLUMN_KEY_LEN];
+    const OLD_KEY: [u8; COLUMN_KEY_LEN] = [9; COLUMN_KEY_LEN];
+
+    #[test]
+    fn test_sealed_values_open_in_their_column() {
+        let cipher = ColumnCipher::new(&KEY, None);
+        let sealed = cipher.seal(PHONE_NUMBER, "+442079460958").unwrap();
+        assert!(sealed.starts_with(SEALED_PREFIX));
+        assert!(!sealed.contains("2079460958"));
+        assert_ne!(sealed, cipher.seal(PHONE_NUMBER, "+442079460958").unwrap());
+
+        assert_eq!(
+            cipher.open(PHONE_NUMBER, sealed.clone()).unwrap(),
+            "+442079460958"
+        );
+        assert!(cipher.open(CHAT_WEBHOOK_URL, sealed).is_err());
+    }
+
+    #[test]
+    fn test_previous_key_opens_values_during_rotation() {
+        let old = ColumnCipher::new(&OLD_KEY, None);
+        let sealed = old.seal(GOOGLE_REFRESH_TOKEN, "refresh").unwrap();
+
+        let rotated = ColumnCipher::new(&KEY, Some(&OLD_KEY));
+        assert_eq!(
+            rotated.open(GOOGLE_REFRESH_TOKEN, sealed.clone()).unwrap(),
+            "refresh"
+        );
+        assert!(rotated.is_stale(&sealed));
+        assert!(!rotated.is_stale(&rotated.seal(GOOGLE_REFRESH_TOKEN, "refresh").unwrap()));
+
+        let retired = ColumnCipher::new(&KEY, None);
+        assert!(retired.open(GOOGLE_REFRESH_TOKEN, sealed).is_err());
+    }
+
+    #[test]
+    fn test_clear_values_pass_through() {
+        let disabled = ColumnCipher::default();
+        assert_eq!(disabled.seal(PHONE_NUMBER, "+1555").unwrap(), "+1555");
+        assert_eq!(disabled.digest(PHONE_NUMBER, "+1555"), None);
+
+        // Rows from before encryption was turned on
+        let cipher = ColumnCipher::new(&KEY, None);
+        assert_eq!(
+            cipher.open(PHONE_NUMBER, "+1555".to_string()).unwrap(),
+            "+1555"
+        );
+        assert!(cipher.is_stale("+1555"));
+
+        let sealed = cipher.seal(PHONE_NUMBER, "+1555").unwrap();
+        assert!(disabled.open(PHONE_NUMBER, sealed).is_err());
+    }
+
+    #[test]
+    fn test_digests_cover_both_keys() {
+        let rotated = ColumnCipher::new(&KEY, Some(&OLD_KEY));
+        let old = ColumnCipher::new(&OLD_KEY, None);
+        let digests = rotated.digests(PHONE_NUMBER, "+1555");
+        assert_eq!(digests.len(), 2);
+        assert_eq!(
+            Some(&digests[0]),
+            rotated.digest(PHONE_NUMBER, "+1555").as_ref()
+        );
+        assert!(digests.contains(&old.digest(PHONE_NUMBER, "+1555").unwrap()));
+        assert_ne!(
+            rotated.digest(PHONE_NUMBER, "+1555"),
+            rotated.digest(CHAT_WEBHOOK_URL, "+1555")
+        );
+    }
+
+    #[test]
+    fn test_parse_column_key() {
+        assert_eq!(parse_column_key(&STANDARD.encode(KEY)).unwrap(), KEY);
+        assert!(parse_column_key("c2hvcnQ=").is_err());
+        assert!(parse_column_key("not base64!").is_err());
+    }
+}
@@ -0,0 +1,708 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use sqlx::{PgConnection, PgPool, Postgres, QueryBuilder, Transaction};
+use std::collections::HashMap;
+use televent_domain::{EventStatus, EventTiming, OutboxPayload, Timezone, UserId};
+use uuid::Uuid;
+
+use crate::calendar::{TimingColumns, parse_event_status, parse_timezone};
+use crate::{StorageError, StorageResult};
+
+/// Rows per multi-row insert, well below the Postgres bind parameter limit.
+const SUBSCRIBED_EVENT_INSERT_CHUNK: usize = 500;
+
+#[derive(Clone)]
+pub struct SubscriptionRepository {
+    pool: PgPool,
+}
+
+impl SubscriptionRepository {
+    #[must_use]
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn begin(&self) -> StorageResult<SubscriptionTransaction<'_>> {
+        let tx = self.pool.begin().await?;
+        Ok(SubscriptionTransaction { tx })
+    }
+
+    pub async fn list_subscriptions(
+        &self,
+        user_id: UserId,
+    ) -> StorageResult<Vec<CalendarSubscription>> {
+        list_subscriptions(&self.pool, user_id).await
+    }
+
+    pub async fn get_subscription(
+        &self,
+        user_id: UserId,
+        subscription_id: Uuid,
+    ) -> StorageResult<Option<CalendarSubscription>> {
+        get_subscription(&self.pool, user_id, subscription_id).await
+    }
+
+    pub async fn delete_subscription(
+        &self,
+        user_id: UserId,
+        subscription_id: Uuid,
+    ) -> StorageResult<bool> {
+        delete_subscription(&self.pool, user_id, subscription_id).await
+    }
+
+    /// Subscriptions whose next fetch is due, oldest first.
+    pub async fn list_due_subscriptions(
+        &self,
+        now: DateTime<Utc>,
+        limit: i64,
+    ) -> StorageResult<Vec<CalendarSubscription>> {
+        list_due_subscriptions(&self.pool, now, limit).await
+    }
+
+    pub async fn record_not_modified(
+        &self,
+        subscription_id: Uuid,
+        next_fetch_at: DateTime<Utc>,
+    ) -> StorageResult<()> {
+        record_not_modified(&self.pool, subscription_id, next_fetch_at).await
+    }
+
+    pub async fn record_fetch_failure(
+        &self,
+        subscription_id: Uuid,
+        error: &str,
+        next_fetch_at: DateTime<Utc>,
+    ) -> StorageResult<()> {
+        record_fetch_failure(&self.pool, subscription_id, error, next_fetch_at).await
+    }
+
+    /// Mirrored events of all of the user's subscriptions starting in the range.
+    pub async fn list_subscribed_events_in_range(
+        &self,
+        user_id: UserId,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> StorageResult<Vec<SubscribedEvent>> {
+        list_subscribed_events_in_range(&self.pool, user_id, start, end).await
+    }
+
+    pub async fn list_subscribed_events(
+        &self,
+        subscription_id: Uuid,
+    ) -> StorageResult<Vec<SubscribedEvent>> {
+        list_subscribed_events(&self.pool, subscription_id).await
+    }
+
+    pub async fn get_subscribed_events_by_uids(
+        &self,
+        subscription_id: Uuid,
+        uids: &[&str],
+    ) -> StorageResult<Vec<SubscribedEvent>> {
+        get_subscribed_events_by_uids(&self.pool, subscription_id, uids).await
+    }
+}
+
+pub struct SubscriptionTransaction<'a> {
+    tx: Transaction<'a, Postgres>,
+}
+
+impl SubscriptionTransaction<'_> {
+    pub async fn ensure_user(
+        &mut self,
+        telegram_id: i64,
+        username: Option<&str>,
+    ) -> StorageResult<crate::calendar::User> {
+        crate::calendar::ensure_user_tx(&mut self.tx, telegram_id, username).await
+    }
+
+    pub async fn count_subscriptions(&mut self, user_id: UserId) -> StorageResult<i64> {
+        self::count_subscriptions_tx(&mut self.tx, user_id).await
+    }
+
+    /// Returns `None` when the user is already subscribed to the URL.
+    pub async fn insert_subscription(
+        &mut self,
+        subscription: StoredSubscription,
+    ) -> StorageResult<Option<CalendarSubscription>> {
+        self::insert_subscription_tx(&mut self.tx, subscription).await
+    }
+
+    /// Lock the subscription row so concurrent refreshes apply one at a time.
+    pub async fn lock_subscription(
+        &mut self,
+        subscription_id: Uuid,
+    ) -> StorageResult<Option<CalendarSubscription>> {
+        self::lock_subscription_tx(&mut self.tx, subscription_id).await
+    }
+
+    /// Current mirrored etags keyed by UID.
+    pub async fn subscribed_event_etags(
+        &mut self,
+        subscription_id: Uuid,
+    ) -> StorageResult<HashMap<String, String>> {
+        self::subscribed_event_etags_tx(&mut self.tx, subscription_id).await
+    }
+
+    /// Make the mirror hold exactly `events`, touching only changed rows.
+    pub async fn replace_subscribed_events(
+        &mut self,
+        subscription_id: Uuid,
+        events: &[SubscribedEventWrite],
+    ) -> StorageResult<()> {
+        self::replace_subscribed_events_tx(&mut self.tx, subscription_id, events).await
+    }
+
+    /// The subscriber's contacts, for calendars derived from the address book
+    pub async fn list_contacts(
+        &mut self,
+        user_id: UserId,
+    ) -> StorageResult<Vec<crate::contact::Contact>> {
+        crate::contact::list_contacts_tx(&mut self.tx, user_id).await
+    }
+
+    pub async fn user_timezone(&mut self, user_id: UserId) -> StorageResult<Timezone> {
+        self::user_timezone_tx(&mut self.tx, user_id).await
+    }
+
+    /// The user's working week as written, `None` when not set
+    pub async fn user_working_hours(&mut self, user_id: UserId) -> StorageResult<Option<String>> {
+        crate::calendar::working_hours_tx(&mut self.tx, user_id).await
+    }
+
+    pub async fn queue_outbox(&mut self, messages: &[OutboxPayload]) -> StorageResult<()> {
+        crate::calendar::queue_outbox_tx(&mut self.tx, messages).await
+    }
+
+    pub async fn record_fetch_success(
+        &mut self,
+        subscription_id: Uuid,
+        fetch: FetchSuccess,
+    ) -> StorageResult<()> {
+        self::record_fetch_success_tx(&mut self.tx, subscription_id, fetch).await
+    }
+
+    pub async fn commit(self) -> StorageResult<()> {
+        self.tx.commit().await?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CalendarSubscription {
+    pub id: Uuid,
+    pub user_id: i64,
+    pub url: String,
+    pub name: String,
+    pub http_etag: Option<String>,
+    pub http_last_modified: Option<String>,
+    pub sync_token: i64,
+    pub last_fetched_at: Option<DateTime<Utc>>,
+    pub next_fetch_at: DateTime<Utc>,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+pub struct StoredSubscription {
+    pub user_id: UserId,
+    pub url: String,
+    pub name: String,
+}
+
+pub struct FetchSuccess {
+    pub http_etag: Option<String>,
+    pub http_last_modified: Option<String>,
+    pub next_fetch_at: DateTime<Utc>,
+    /// Whether the mirrored events changed and the sync token must move.
+    pub changed: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct SubscribedEventWrite {
+    pub uid: String,
+    pub summary: String,
+    pub description: Option<String>,
+    pub location: Option<String>,
+    pub timing: EventTiming,
+    pub status: EventStatus,
+    pub rrule: Option<String>,
+    pub etag: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct SubscribedEvent {
+    pub subscription_id: Uuid,
+    pub uid: String,
+    pub summary: String,
+    pub description: Option<String>,
+    pub location: Option<String>,
+    pub timing: EventTiming,
+    pub status: EventStatus,
+    pub rrule: Option<String>,
+    pub etag: String,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone)]
+struct SubscribedEventRow {
+    pub subscription_id: Uuid,
+    pub uid: String,
+    pub summary: String,
+    pub description: Option<String>,
+    pub location: Option<String>,
+    pub start: Option<DateTime<Utc>>,
+    pub end: Option<DateTime<Utc>>,
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+    pub is_all_day: bool,
+    pub is_floating: bool,
+    pub status: String,
+    pub rrule: Option<String>,
+    pub timezone: String,
+    pub etag: String,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl TryFrom<SubscribedEventRow> for SubscribedEvent {
+    type Error = StorageError;
+
+    fn try_from(row: SubscribedEventRow) -> Result<Self, Self::Error> {
+        let timing = if row.is_all_day {
+            match (row.start_date, row.end_date) {
+                (Some(start_date), Some(end_date)) => EventTiming::AllDay {
+                    start_date,
+                    end_date,
+                },
+                _ => {
+                    return Err(StorageError::InvalidData(
+                        "all-day subscribed event is missing dates".to_string(),
+                    ));
+                }
+            }
+        } else {
+            match (row.start, row.end) {
+                (Some(start), Some(end)) if row.is_floating => EventTiming::Floating {
+                    start: start.naive_utc(),
+                    end: end.naive_utc(),
+                },
+                (Some(start), Some(end)) => EventTiming::Timed {
+                    start,
+                    end,
+                    timezone: parse_timezone(&row.timezone)?,
+                },
+                _ => {
+                    return Err(StorageError::InvalidData(
+                        "timed subscribed event is missing start or end".to_string(),
+                    ));
+                }
+            }
+        };
+
+        Ok(Self {
+            subscription_id: row.subscription_id,
+            uid: row.uid,
+            summary: row.summary,
+            description: row.description,
+            location: row.location,
+            timing,
+            status: parse_event_status(&row.status)?,
+            rrule: row.rrule,
+            etag: row.etag,
+            updated_at: row.updated_at,
+        })
+    }
+}
+
+fn subscribed_event_rows(rows: Vec<SubscribedEventRow>) -> StorageResult<Vec<SubscribedEvent>> {
+    rows.into_iter().map(SubscribedEvent::try_from).collect()
+}
+
+async fn count_subscriptions_tx(conn: &mut PgConnection, user_id: UserId) -> StorageResult<i64> {
+    let count = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "count!" FROM calendar_subscriptions WHERE user_id = $1"#,
+        user_id.inner()
+    )
+    .fetch_one(conn)
+    .await?;
+
+    Ok(count)
+}
+
+async fn insert_subscription_tx(
+    conn: &mut PgConnection,
+    subscription: StoredSubscription,
+) -> StorageResult<Option<CalendarSubscription>> {
+    let subscription = sqlx::query_as!(
+        CalendarSubscription,
+        r#"
+        INSERT INTO calendar_subscriptions (user_id, url, name)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (user_id, url) DO NOTHING
+        RETURNING id, user_id, url, name, http_etag, http_last_modified, sync_token,
+                  last_fetched_at, next_fetch_at, last_error, created_at
+        "#,
+        subscription.user_id.inner(),
+        subscription.url,
+        subscription.name
+    )
+    .fetch_optional(conn)
+    .await?;
+
+    Ok(subscription)
+}
+
+async fn lock_subscription_tx(
+    conn: &mut PgConnection,
+    subscription_id: Uuid,
+) -> StorageResult<Option<CalendarSubscription>> {
+    let subscription = sqlx::query_as!(
+        CalendarSubscription,
+        r#"
+        SELECT id, user_id, url, name, http_etag, http_last_modified, sync_token,
+               last_fetched_at, next_fetch_at, last_error, created_at
+        FROM calendar_subscriptions
+        WHERE id = $1
+        FOR UPDATE
+        "#,
+        subscription_id
+    )
+    .fetch_optional(conn)
+    .await?;
+
+    Ok(subscription)
+}
+
+async fn user_timezone_tx(conn: &mut PgConnection, user_id: UserId) -> StorageResult<Timezone> {
+    let timezone = sqlx::query_scalar!(
+        "SELECT timezone FROM users WHERE telegram_id = $1",
+        user_id.inner()
+    )
+    .fetch_one(conn)
+    .await?;
+
+    parse_timezone(&timezone)
+}
+
+async fn subscribed_event_etags_tx(
+    conn: &mut PgConnection,
+    subscription_id: Uuid,
+) -> StorageResult<HashMap<String, String>> {
+    let rows = sqlx::query!(
+        "SELECT uid, etag FROM subscribed_events WHERE subscription_id = $1",
+        subscription_id
+    )
+    .fetch_all(conn)
+    .await?;
+
+    Ok(rows.into_iter().map(|row| (row.uid, row.etag)).collect())
+}
+
+async fn replace_subscribed_events_tx(
+    conn: &mut PgConnection,
+    subscription_id: Uuid,
+    events: &[SubscribedEventWrite],
+) -> StorageResult<()> {
+    let uids: Vec<&str> = events.iter().map(|event| event.uid.as_str()).collect();
+    sqlx::query!(
+        "DELETE FROM subscribed_events WHERE subscription_id = $1 AND NOT (uid = ANY($2))",
+        subscription_id,
+        &uids as &[&str]
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    for chunk in events.chunks(SUBSCRIBED_EVENT_INSERT_CHUNK) {
+        let mut builder: QueryBuilder<Postgres> = QueryBuilder::new(
+            r#"INSERT INTO subscribed_events (
+                subscription_id, uid, summary, description, location,
+                start, "end", start_date, end_date, is_all_day,
+                status, timezone, rrule, etag, is_floating
+            ) "#,
+        );
+
+        builder.push_values(chunk, |mut row, event| {
+            let TimingColumns {
+                start,
+                end,
+                start_date,
+                end_date,
+                is_all_day,
+                is_floating,
+                timezone,
+            } = TimingColumns::from_timing(&event.timing);
+
+            row.push_bind(subscription_id);
+            row.push_bind(&event.uid);
+            row.push_bind(&event.summary);
+            row.push_bind(&event.description);
+            row.push_bind(&event.location);
+            row.push_bind(start);
+            row.push_bind(end);
+            row.push_bind(start_date);
+            row.push_bind(end_date);
+            row.push_bind(is_all_day);
+            row.push_bind(event.status.as_sql())
+                .push_unseparated("::text::event_status");
+            row.push_bind(timezone);
+            row.push_bind(&event.rrule);
+            row.push_bind(&event.etag);
+            row.push_bind(is_floating);
+        });
+
+        builder.push(
+            r#"
+            ON CONFLICT (subscription_id, uid) DO UPDATE
+            SET summary = EXCLUDED.summary,
+                description = EXCLUDED.description,
+                location = EXCLUDED.location,
+                start = EXCLUDED.start,
+                "end" = EXCLUDED."end",
+                start_date = EXCLUDED.start_date,
+                end_date = EXCLUDED.end_date,
+                is_all_day = EXCLUDED.is_all_day,
+                is_floating = EXCLUDED.is_floating,
+                status = EXCLUDED.status,
+                timezone = EXCLUDED.timezone,
+                rrule = EXCLUDED.rrule,
+                etag = EXCLUDED.etag,
+                updated_at = NOW()
+            WHERE subscribed_events.etag <> EXCLUDED.etag
+            "#,
+        );
+
+        builder.build().execute(&mut *conn).await?;
+    }
+
+    Ok(())
+}
+
+async fn record_fetch_success_tx(
+    conn: &mut PgConnection,
+    subscription_id: Uuid,
+    fetch: FetchSuccess,
+) -> StorageResult<()> {
+    sqlx::query!(
+        r#"
+        UPDATE calendar_subscriptions
+        SET http_etag = $2,
+            http_last_modified = $3,
+            next_fetch_at = $4,
+            sync_token = sync_token + CASE WHEN $5 THEN 1 ELSE 0 END,
+            last_fetched_at = NOW(),
+            last_error = NULL
+        WHERE id = $1
+        "#,
+        subscription_id,
+        fetch.http_etag,
+        fetch.http_last_modified,
+        fetch.next_fetch_at,
+        fetch.changed
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+async fn list_subscriptions(
+    pool: &PgPool,
+    user_id: UserId,
+) -> StorageResult<Vec<CalendarSubscription>> {
+    let subscriptions = sqlx::query_as!(
+        CalendarSubscription,
+        r#"
+        SELECT id, user_id, url, name, http_etag, http_last_modified, sync_token,
+               last_fetched_at, next_fetch_at, last_error, created_at
+        FROM calendar_subscriptions
+        WHERE user_id = $1
+        ORDER BY created_at ASC
+        "#,
+        user_id.inner()
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(subscriptions)
+}
+
+async fn get_subscription(
+    pool: &PgPool,
+    user_id: UserId,
+    subscription_id: Uuid,
+) -> StorageResult<Option<CalendarSubscription>> {
+    let subscription = sqlx::query_as!(
+        CalendarSubscription,
+        r#"
+        SELECT id, user_id, url, name, http_etag, http_last_modified, sync_token,
+               last_fetched_at, next_fetch_at, last_error, created_at
+        FROM calendar_subscriptions
+        WHERE id = $1 AND user_id = $2
+        "#,
+        subscription_id,
+        user_id.inner()
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(subscription)
+}
+
+async fn delete_subscription(
+    pool: &PgPool,
+    user_id: UserId,
+    subscription_id: Uuid,
+) -> StorageResult<bool> {
+    let result = sqlx::query!(
+        "DELETE FROM calendar_subscriptions WHERE id = $1 AND user_id = $2",
+        subscription_id,
+        user_id.inner()
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+async fn list_due_subscriptions(
+    pool: &PgPool,
+    now: DateTime<Utc>,
+    limit: i64,
+) -> StorageResult<Vec<CalendarSubscription>> {
+    let subscriptions = sqlx::query_as!(
+        CalendarSubscription,
+        r#"
+        SELECT id, user_id, url, name, http_etag, http_last_modified, sync_token,
+               last_fetched_at, next_fetch_at, last_error, created_at
+        FROM calendar_subscriptions
+        WHERE next_fetch_at <= $1
+        ORDER BY next_fetch_at ASC
+        LIMIT $2
+        "#,
+        now,
+        limit
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(subscriptions)
+}
+
+async fn record_not_modified(
+    pool: &PgPool,
+    subscription_id: Uuid,
+    next_fetch_at: DateTime<Utc>,
+) -> StorageResult<()> {
+    sqlx::query!(
+        r#"
+        UPDATE calendar_subscriptions
+        SET next_fetch_at = $2, last_fetched_at = NOW(), last_error = NULL
+        WHERE id = $1
+        "#,
+        subscription_id,
+        next_fetch_at
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+async fn record_fetch_failure(
+    pool: &PgPool,
+    subscription_id: Uuid,
+    error: &str,
+    next_fetch_at: DateTime<Utc>,
+) -> StorageResult<()> {
+    sqlx::query!(
+        "UPDATE calendar_subscriptions SET next_fetch_at = $2, last_error = $3 WHERE id = $1",
+        subscription_id,
+        next_fetch_at,
+        error
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+async fn list_subscribed_events_in_range(
+    pool: &PgPool,
+    user_id: UserId,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> StorageResult<Vec<SubscribedEvent>> {
+    let events = sqlx::query_as!(
+        SubscribedEventRow,
+        r#"
+        SELECT subscription_id, uid, summary, description, location, start, "end", start_date,
+               end_date, is_all_day, is_floating, status::text AS "status!", rrule, timezone,
+               etag, updated_at
+        FROM subscribed_events
+        WHERE subscription_id IN (
+            SELECT id FROM calendar_subscriptions WHERE user_id = $1
+        )
+        AND (
+            (is_all_day = false AND start >= $2 AND start < $3)
+            OR
+            (is_all_day = true AND start_date >= $4 AND start_date < $5)
+        )
+        ORDER BY COALESCE(start, start_date::timestamp AT TIME ZONE 'UTC') ASC
+        "#,
+        user_id.inner(),
+        start,
+        end,
+        start.date_naive(),
+        end.date_naive()
+    )
+    .fetch_all(pool)
+    .await?;
+
+    subscribed_event_rows(events)
+}
+
+async fn list_subscribed_events(
+    pool: &PgPool,
+    subscription_id: Uuid,
+) -> StorageResult<Vec<SubscribedEvent>> {
+    let events = sqlx::query_as!(
+        SubscribedEventRow,
+        r#"
+        SELECT subscription_id, uid, summary, description, location, start, "end", start_date,
+               end_date, is_all_day, is_floating, status::text AS "status!", rrule, timezone,
+               etag, updated_at
+        FROM subscribed_events
+        WHERE subscription_id = $1
+        ORDER BY uid ASC
+        "#,
+        subscription_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    subscribed_event_rows(events)
+}
+
+async fn get_subscribed_events_by_uids(
+    pool: &PgPool,
+    subscription_id: Uuid,
+    uids: &[&str],
+) -> StorageResult<Vec<SubscribedEvent>> {
+    if uids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let events = sqlx::query_as!(
+        SubscribedEventRow,
+        r#"
+        SELECT subscription_id, uid, summary, description, location, start, "end", start_date,
+               end_date, is_all_day, is_floating, status::text AS "status!", rrule, timezone,
+               etag, updated_at
+        FROM subscribed_events
+        WHERE subscription_id = $1 AND uid = ANY($2)
+        "#,
+        subscription_id,
+        uids as &[&str]
+    )
+    .fetch_all(pool)
+    .await?;
+
+    subscribed_event_rows(events)
+}
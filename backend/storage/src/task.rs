@@ -0,0 +1,229 @@
+//! Tasks: to-dos with a duration, planned into free time as events.
+
+use chrono::{DateTime, Utc};
+use sqlx::{PgConnection, PgPool};
+use televent_domain::{EventStatus, UserId};
+use uuid::Uuid;
+
+use crate::StorageResult;
+use crate::calendar::parse_event_status;
+
+/// Task row with the event planned for it, if any
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Task {
+    pub id: Uuid,
+    pub user_id: UserId,
+    pub title: String,
+    pub duration_minutes: i32,
+    /// Event linked to the task, whatever its timing
+    pub event_id: Option<Uuid>,
+    /// The linked event's time, when it is a timed event
+    pub block: Option<TaskBlock>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Time and status of the event planned for a task
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TaskBlock {
+    pub event_id: Uuid,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub status: EventStatus,
+}
+
+struct TaskRow {
+    id: Uuid,
+    user_id: i64,
+    title: String,
+    duration_minutes: i32,
+    event_id: Option<Uuid>,
+    block_start: Option<DateTime<Utc>>,
+    block_end: Option<DateTime<Utc>>,
+    block_status: Option<String>,
+    completed_at: Option<DateTime<Utc>>,
+    created_at: DateTime<Utc>,
+}
+
+impl TryFrom<TaskRow> for Task {
+    type Error = crate::StorageError;
+
+    fn try_from(row: TaskRow) -> Result<Self, Self::Error> {
+        let block = match (
+            row.event_id,
+            row.block_start,
+            row.block_end,
+            row.block_status,
+        ) {
+            (Some(event_id), Some(start), Some(end), Some(status)) => Some(TaskBlock {
+                event_id,
+                start,
+                end,
+                status: parse_event_status(&status)?,
+            }),
+            _ => None,
+        };
+        Ok(Self {
+            id: row.id,
+            user_id: UserId::new(row.user_id),
+            title: row.title,
+            duration_minutes: row.duration_minutes,
+            event_id: row.event_id,
+            block,
+            completed_at: row.completed_at,
+            created_at: row.created_at,
+        })
+    }
+}
+
+fn tasks(rows: Vec<TaskRow>) -> StorageResult<Vec<Task>> {
+    rows.into_iter().map(Task::try_from).collect()
+}
+
+pub(crate) async fn insert(
+    pool: &PgPool,
+    user_id: UserId,
+    title: &str,
+    duration_minutes: i32,
+) -> StorageResult<Task> {
+    let row = sqlx::query_as!(
+        TaskRow,
+        r#"
+        INSERT INTO tasks (user_id, title, duration_minutes)
+        VALUES ($1, $2, $3)
+        RETURNING id, user_id, title, duration_minutes, event_id,
+                  NULL::timestamptz AS block_start, NULL::timestamptz AS block_end,
+                  NULL::text AS block_status, completed_at, created_at
+        "#,
+        user_id.inner(),
+        title,
+        duration_minutes
+    )
+    .fetch_one(pool)
+    .await?;
+
+    row.try_into()
+}
+
+/// The user's tasks that are not done yet, oldest first
+pub(crate) async fn list_open(pool: &PgPool, user_id: UserId) -> StorageResult<Vec<Task>> {
+    let rows = sqlx::query_as!(
+        TaskRow,
+        r#"
+        SELECT t.id, t.user_id, t.title, t.duration_minutes, t.event_id,
+               e.start AS "block_start?", e."end" AS "block_end?",
+               e.status::text AS "block_status?", t.completed_at, t.created_at
+        FROM tasks t
+        LEFT JOIN events e ON e.id = t.event_id
+        WHERE t.user_id = $1 AND t.completed_at IS NULL
+        ORDER BY t.created_at, t.id
+        "#,
+        user_id.inner()
+    )
+    .fetch_all(pool)
+    .await?;
+
+    tasks(rows)
+}
+
+/// Like [`list_open`], locking the tasks until the transaction ends
+pub(crate) async fn list_open_for_update(
+    conn: &mut PgConnection,
+    user_id: UserId,
+) -> StorageResult<Vec<Task>> {
+    let rows = sqlx::query_as!(
+        TaskRow,
+        r#"
+        SELECT t.id, t.user_id, t.title, t.duration_minutes, t.event_id,
+               e.start AS "block_start?", e."end" AS "block_end?",
+               e.status::text AS "block_status?", t.completed_at, t.created_at
+        FROM tasks t
+        LEFT JOIN events e ON e.id = t.event_id
+        WHERE t.user_id = $1 AND t.completed_at IS NULL
+        ORDER BY t.created_at, t.id
+        FOR UPDATE OF t
+        "#,
+        user_id.inner()
+    )
+    .fetch_all(conn)
+    .await?;
+
+    tasks(rows)
+}
+
+pub(crate) async fn get(pool: &PgPool, user_id: UserId, id: Uuid) -> StorageResult<Option<Task>> {
+    let row = sqlx::query_as!(
+        TaskRow,
+        r#"
+        SELECT t.id, t.user_id, t.title, t.duration_minutes, t.event_id,
+               e.start AS "block_start?", e."end" AS "block_end?",
+               e.status::text AS "block_status?", t.completed_at, t.created_at
+        FROM tasks t
+        LEFT JOIN events e ON e.id = t.event_id
+        WHERE t.id = $1 AND t.user_id = $2
+        "#,
+        id,
+        user_id.inner()
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    row.map(Task::try_from).transpose()
+}
+
+/// `false` when the user has no such task
+pub(crate) async fn delete(pool: &PgPool, user_id: UserId, id: Uuid) -> StorageResult<bool> {
+    let result = sqlx::query!(
+        "DELETE FROM tasks WHERE id = $1 AND user_id = $2",
+        id,
+        user_id.inner()
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// `false` when the user has no such open task
+pub(crate) async fn complete(pool: &PgPool, user_id: UserId, id: Uuid) -> StorageResult<bool> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE tasks
+        SET completed_at = NOW()
+        WHERE id = $1 AND user_id = $2 AND completed_at IS NULL
+        "#,
+        id,
+        user_id.inner()
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Forget the task's block, leaving the event where it is, so the next plan
+/// schedules the task again; `false` when the user has no such open task
+pub(crate) async fn unlink(pool: &PgPool, user_id: UserId, id: Uuid) -> StorageResult<bool> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE tasks
+        SET event_id = NULL
+        WHERE id = $1 AND user_id = $2 AND completed_at IS NULL
+        "#,
+        id,
+        user_id.inner()
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Record `event_id` as the task's block
+pub(crate) async fn link(conn: &mut PgConnection, id: Uuid, event_id: Uuid) -> StorageResult<()> {
+    sqlx::query!("UPDATE tasks SET event_id = $2 WHERE id = $1", id, event_id)
+        .execute(conn)
+        .await?;
+
+    Ok(())
+}
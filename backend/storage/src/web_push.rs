@@ -0,0 +1,194 @@
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use televent_domain::{NotificationChannel, NotificationTopic, OutboxPayload, UserId};
+use uuid::Uuid;
+
+use crate::StorageResult;
+
+#[derive(Clone)]
+pub struct WebPushRepository {
+    pool: PgPool,
+}
+
+#[derive(Debug, Clone)]
+pub struct WebPushSubscriptionRecord {
+    pub id: Uuid,
+    pub user_id: i64,
+    pub endpoint: String,
+    pub p256dh: String,
+    pub auth: String,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_pushed_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl WebPushRepository {
+    #[must_use]
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Store a browser's subscription for the user, taking it over from
+    /// whoever subscribed it before, and drop the user's oldest ones beyond
+    /// `keep`
+    pub async fn upsert_subscription(
+        &self,
+        user_id: UserId,
+        endpoint: &str,
+        p256dh: &str,
+        auth: &str,
+        expires_at: Option<DateTime<Utc>>,
+        keep: i64,
+    ) -> StorageResult<WebPushSubscriptionRecord> {
+        let mut tx = self.pool.begin().await?;
+        crate::calendar::ensure_user_tx(&mut tx, user_id.inner(), None).await?;
+        let subscription = sqlx::query_as!(
+            WebPushSubscriptionRecord,
+            r#"
+            INSERT INTO web_push_subscriptions (user_id, endpoint, p256dh, auth, expires_at)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (endpoint) DO UPDATE
+            SET user_id = EXCLUDED.user_id,
+                p256dh = EXCLUDED.p256dh,
+                auth = EXCLUDED.auth,
+                expires_at = EXCLUDED.expires_at,
+                created_at = NOW()
+            RETURNING id, user_id, endpoint, p256dh, auth, expires_at, last_pushed_at,
+                      created_at
+            "#,
+            user_id.inner(),
+            endpoint,
+            p256dh,
+            auth,
+            expires_at
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        sqlx::query!(
+            r#"
+            DELETE FROM web_push_subscriptions
+            WHERE user_id = $1
+              AND id NOT IN (
+                  SELECT id FROM web_push_subscriptions
+                  WHERE user_id = $1
+                  ORDER BY created_at DESC
+                  LIMIT $2
+              )
+            "#,
+            user_id.inner(),
+            keep
+        )
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        Ok(subscription)
+    }
+
+    pub async fn list_subscriptions(
+        &self,
+        user_id: UserId,
+    ) -> StorageResult<Vec<WebPushSubscriptionRecord>> {
+        let subscriptions = sqlx::query_as!(
+            WebPushSubscriptionRecord,
+            r#"
+            SELECT id, user_id, endpoint, p256dh, auth, expires_at, last_pushed_at, created_at
+            FROM web_push_subscriptions
+            WHERE user_id = $1
+            ORDER BY created_at
+            "#,
+            user_id.inner()
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(subscriptions)
+    }
+
+    pub async fn get_subscription(
+        &self,
+        subscription_id: Uuid,
+    ) -> StorageResult<Option<WebPushSubscriptionRecord>> {
+        let subscription = sqlx::query_as!(
+            WebPushSubscriptionRecord,
+            r#"
+            SELECT id, user_id, endpoint, p256dh, auth, expires_at, last_pushed_at, created_at
+            FROM web_push_subscriptions
+            WHERE id = $1
+            "#,
+            subscription_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(subscription)
+    }
+
+    pub async fn delete_by_endpoint(&self, user_id: UserId, endpoint: &str) -> StorageResult<bool> {
+        let result = sqlx::query!(
+            "DELETE FROM web_push_subscriptions WHERE user_id = $1 AND endpoint = $2",
+            user_id.inner(),
+            endpoint
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn delete_subscription(&self, subscription_id: Uuid) -> StorageResult<bool> {
+        let result = sqlx::query!(
+            "DELETE FROM web_push_subscriptions WHERE id = $1",
+            subscription_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Drop the user's subscriptions past the expiration time their browser
+    /// gave; returns how many went
+    pub async fn prune_expired(&self, user_id: UserId) -> StorageResult<u64> {
+        let result = sqlx::query!(
+            "DELETE FROM web_push_subscriptions WHERE user_id = $1 AND expires_at <= NOW()",
+            user_id.inner()
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    pub async fn mark_pushed(&self, subscription_id: Uuid) -> StorageResult<()> {
+        sqlx::query!(
+            "UPDATE web_push_subscriptions SET last_pushed_at = NOW() WHERE id = $1",
+            subscription_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Whether the user wants `topic` pushed to their browsers
+    pub async fn topic_enabled(
+        &self,
+        user_id: UserId,
+        topic: NotificationTopic,
+    ) -> StorageResult<bool> {
+        let mut conn = self.pool.acquire().await?;
+        crate::notification::channel_enabled_tx(
+            &mut conn,
+            user_id,
+            topic,
+            NotificationChannel::Push,
+        )
+        .await
+    }
+
+    pub async fn queue_outbox(&self, messages: &[OutboxPayload]) -> StorageResult<()> {
+        let mut conn = self.pool.acquire().await?;
+        crate::calendar::queue_outbox_tx(&mut conn, messages).await
+    }
+}
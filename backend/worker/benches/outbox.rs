@@ -0,0 +1,91 @@
+//! Outbox throughput: claiming a batch of pending jobs and writing their
+//! results back, the two queries every worker tick runs
+//!
+//! Runs against `BENCH_DATABASE_URL`, which `just bench` points at a
+//! throwaway Postgres container. The benchmark completes every pending job
+//! it can claim, so never point it at a database you care about; without the
+//! variable it is skipped.
+
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use sqlx::PgPool;
+use sqlx::postgres::PgPoolOptions;
+use std::time::{Duration, Instant};
+use tokio::runtime::Runtime;
+use worker::{JobResult, WorkerDb};
+
+/// Seed `count` pending invite notifications, ready to be claimed
+async fn seed(pool: &PgPool, count: i64) {
+    sqlx::query(
+        r#"
+        INSERT INTO outbox_messages (id, kind, payload, status, retry_count, scheduled_at, created_at)
+        SELECT gen_random_uuid(), 'invite_notification',
+               jsonb_build_object('event_id', gen_random_uuid(), 'target_user_id', 987654321),
+               'pending', 0, NOW(), NOW()
+        FROM generate_series(1, $1)
+        "#,
+    )
+    .bind(count)
+    .execute(pool)
+    .await
+    .unwrap();
+}
+
+fn fetch_and_update(c: &mut Criterion) {
+    let Ok(url) = std::env::var("BENCH_DATABASE_URL") else {
+        eprintln!("Skipping outbox benchmarks: BENCH_DATABASE_URL is not set");
+        return;
+    };
+    let runtime = Runtime::new().unwrap();
+    let pool = runtime.block_on(async {
+        let pool = PgPoolOptions::new()
+            .max_connections(4)
+            .connect(&url)
+            .await
+            .unwrap();
+        sqlx::migrate!("../migrations").run(&pool).await.unwrap();
+        sqlx::query("DELETE FROM outbox_messages")
+            .execute(&pool)
+            .await
+            .unwrap();
+        pool
+    });
+    let db = WorkerDb::new(pool.clone());
+
+    let mut group = c.benchmark_group("outbox");
+    for batch_size in [10, 100, 500] {
+        group.throughput(Throughput::Elements(batch_size as u64));
+        group.bench_with_input(
+            BenchmarkId::new("fetch_and_update", batch_size),
+            &batch_size,
+            |b, &batch_size| {
+                b.iter_custom(|iters| {
+                    runtime.block_on(async {
+                        let mut elapsed = Duration::ZERO;
+                        for _ in 0..iters {
+                            seed(&pool, batch_size).await;
+
+                            let start = Instant::now();
+                            let batch = db.fetch_pending_jobs(batch_size).await.unwrap();
+                            let mut results = batch.failed_results;
+                            results
+                                .extend(batch.jobs.iter().map(|job| JobResult::Completed(job.id)));
+                            assert_eq!(results.len() as i64, batch_size);
+                            db.bulk_update_jobs(results).await.unwrap();
+                            elapsed += start.elapsed();
+
+                            sqlx::query("DELETE FROM outbox_messages")
+                                .execute(&pool)
+                                .await
+                                .unwrap();
+                        }
+                        elapsed
+                    })
+                });
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, fetch_and_update);
+criterion_main!(benches);
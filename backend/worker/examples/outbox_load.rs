@@ -0,0 +1,454 @@
+//! Outbox load test: seeds synthetic users, events and pending outbox
+//! messages, then optionally drains them with the real worker loop
+//!
+//! Operators run it against a staging database before a launch to see how
+//! fast the worker gets through a backlog and how big the tables grow. The
+//! worker talks to a local stand-in for the Bot API, so no message reaches
+//! Telegram; its send rate limit still applies, as it would in production.
+//!
+//! Synthetic users get Telegram ids from [`ID_BASE`] up; rows left by an
+//! earlier run are deleted first, and nothing else is touched.
+
+use anyhow::{Context, Result, bail};
+use axum::Router;
+use axum::body::Bytes;
+use axum::extract::Path;
+use axum::response::Json;
+use chrono::{Duration as ChronoDuration, Utc};
+use serde_json::{Value, json};
+use sqlx::PgPool;
+use sqlx::postgres::PgPoolOptions;
+use std::time::{Duration, Instant};
+use televent_application::CalendarService;
+use televent_domain::{
+    InviteNotification, OutboxPayload, ParticipationStatus, RsvpNotification, TelegramNotification,
+    internal_email_for_telegram_id,
+};
+use televent_storage::calendar::CalendarRepository;
+use teloxide::Bot;
+use tokio::net::TcpListener;
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+use worker::{Config, WorkerDb};
+
+const USAGE: &str = "Usage: cargo run -p worker --release --example outbox_load -- \
+    <users> <events> <messages> [--drain]\n\n\
+    Seeds the database in LOADTEST_DATABASE_URL (never DATABASE_URL: the rows are\n\
+    real). With --drain the worker then processes the messages, configured by the\n\
+    usual WORKER_* variables, and a throughput report is printed. RSVP notices wait\n\
+    out WORKER_RSVP_DIGEST_WINDOW_SECS first, as they do in production.";
+
+/// First Telegram id given to synthetic users, far above real ones
+const ID_BASE: i64 = 8_000_000_000_000;
+
+/// Rows per INSERT while seeding
+const CHUNK: usize = 5_000;
+
+/// How often the drain checks for the last job
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// How often progress is printed while draining
+const REPORT_INTERVAL: Duration = Duration::from_secs(5);
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let drain = args.iter().any(|arg| arg == "--drain");
+    let counts: Vec<usize> = args
+        .iter()
+        .filter(|arg| *arg != "--drain")
+        .map(|arg| arg.parse())
+        .collect::<Result<_, _>>()
+        .context(USAGE)?;
+    let [users, events, messages] = counts[..] else {
+        bail!(USAGE);
+    };
+    if users < 2 {
+        bail!("At least two users are needed to invite one another");
+    }
+
+    let url = std::env::var("LOADTEST_DATABASE_URL").context(USAGE)?;
+    let pool = PgPoolOptions::new()
+        .max_connections(8)
+        .connect(&url)
+        .await?;
+    sqlx::migrate!("../migrations").run(&pool).await?;
+
+    clear(&pool).await?;
+    let scenario = Scenario::generate(users, events, messages);
+    scenario.seed(&pool).await?;
+    print_table_sizes(&pool).await?;
+
+    if drain {
+        drain_outbox(pool, messages).await?;
+    }
+    Ok(())
+}
+
+/// Delete everything an earlier run seeded
+async fn clear(pool: &PgPool) -> Result<()> {
+    sqlx::query("DELETE FROM outbox_messages WHERE shard_key >= $1")
+        .bind(ID_BASE)
+        .execute(pool)
+        .await?;
+    // Events and attendees go with their organizer
+    sqlx::query("DELETE FROM users WHERE telegram_id >= $1")
+        .bind(ID_BASE)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+struct SyntheticEvent {
+    id: Uuid,
+    organizer: i64,
+    summary: String,
+    start: chrono::DateTime<Utc>,
+    minutes: i64,
+    attendees: Vec<i64>,
+}
+
+/// What gets seeded, generated up front so seeding only measures the database
+struct Scenario {
+    users: Vec<(i64, &'static str)>,
+    events: Vec<SyntheticEvent>,
+    messages: Vec<OutboxPayload>,
+}
+
+impl Scenario {
+    fn generate(users: usize, events: usize, messages: usize) -> Self {
+        const TIMEZONES: [&str; 4] = ["Europe/Moscow", "Europe/Berlin", "Asia/Dubai", "UTC"];
+        let users: Vec<_> = (0..users)
+            .map(|i| (ID_BASE + i as i64, TIMEZONES[i % TIMEZONES.len()]))
+            .collect();
+        let user =
+            |skew: i32| users[(rand::random::<f64>().powi(skew) * users.len() as f64) as usize].0;
+
+        // A few busy organizers own most events; attendees are spread evenly
+        let now = Utc::now();
+        let events: Vec<_> = (0..events)
+            .map(|i| {
+                let organizer = user(3);
+                let invited = match rand::random::<f64>() {
+                    p if p < 0.3 => 0,
+                    p if p < 0.8 => 1 + (rand::random::<f64>() * 3.0) as usize,
+                    _ => 4 + (rand::random::<f64>() * 8.0) as usize,
+                };
+                let mut attendees: Vec<i64> = (0..invited).map(|_| user(1)).collect();
+                attendees.retain(|attendee| *attendee != organizer);
+                attendees.sort_unstable();
+                attendees.dedup();
+                SyntheticEvent {
+                    id: Uuid::new_v4(),
+                    organizer,
+                    summary: format!("Load test event #{i}"),
+                    start: now + ChronoDuration::minutes((rand::random::<f64>() * 43_200.0) as i64),
+                    minutes: [30, 60, 60, 90][i % 4],
+                    attendees,
+                }
+            })
+            .collect();
+
+        // Mostly invites, then reminders, then RSVP notices to organizers
+        let invites: Vec<_> = events
+            .iter()
+            .flat_map(|event| {
+                event
+                    .attendees
+                    .iter()
+                    .map(move |attendee| (event, *attendee))
+            })
+            .collect();
+        let messages = (0..messages)
+            .map(|_| {
+                let p = rand::random::<f64>();
+                let invite = (!invites.is_empty())
+                    .then(|| invites[(rand::random::<f64>() * invites.len() as f64) as usize]);
+                match invite {
+                    Some((event, attendee)) if p < 0.6 => {
+                        OutboxPayload::InviteNotification(InviteNotification {
+                            event_id: event.id,
+                            target_user_id: attendee,
+                        })
+                    }
+                    Some((event, attendee)) if p < 0.75 => {
+                        OutboxPayload::RsvpNotification(RsvpNotification {
+                            organizer_telegram_id: event.organizer,
+                            attendee_name: format!("user_{attendee}"),
+                            event_summary: event.summary.clone(),
+                            rsvp_status: ParticipationStatus::Accepted,
+                        })
+                    }
+                    _ => OutboxPayload::TelegramNotification(TelegramNotification {
+                        telegram_id: user(1),
+                        message: "⏰ Load test reminder: starts in 15 minutes".to_string(),
+                    }),
+                }
+            })
+            .collect();
+
+        Self {
+            users,
+            events,
+            messages,
+        }
+    }
+
+    async fn seed(&self, pool: &PgPool) -> Result<()> {
+        let started = Instant::now();
+        for chunk in self.users.chunks(CHUNK) {
+            let (ids, timezones): (Vec<i64>, Vec<&str>) = chunk.iter().copied().unzip();
+            let usernames: Vec<String> = ids.iter().map(|id| format!("load_{id}")).collect();
+            sqlx::query(
+                "INSERT INTO users (telegram_id, telegram_username, timezone)
+                 SELECT * FROM UNNEST($1::bigint[], $2::text[], $3::text[])",
+            )
+            .bind(ids)
+            .bind(usernames)
+            .bind(timezones)
+            .execute(pool)
+            .await?;
+        }
+        report("users", self.users.len(), started.elapsed());
+
+        let started = Instant::now();
+        for chunk in self.events.chunks(CHUNK) {
+            sqlx::query(
+                r#"
+                INSERT INTO events (id, user_id, uid, summary, start, "end", etag)
+                SELECT id, user_id, id::text || '@loadtest', summary, start,
+                       start + make_interval(mins => minutes), md5(id::text)
+                FROM UNNEST($1::uuid[], $2::bigint[], $3::text[], $4::timestamptz[], $5::int[])
+                    AS t(id, user_id, summary, start, minutes)
+                "#,
+            )
+            .bind(chunk.iter().map(|event| event.id).collect::<Vec<_>>())
+            .bind(
+                chunk
+                    .iter()
+                    .map(|event| event.organizer)
+                    .collect::<Vec<_>>(),
+            )
+            .bind(
+                chunk
+                    .iter()
+                    .map(|event| event.summary.clone())
+                    .collect::<Vec<_>>(),
+            )
+            .bind(chunk.iter().map(|event| event.start).collect::<Vec<_>>())
+            .bind(
+                chunk
+                    .iter()
+                    .map(|event| event.minutes as i32)
+                    .collect::<Vec<_>>(),
+            )
+            .execute(pool)
+            .await?;
+        }
+        report("events", self.events.len(), started.elapsed());
+
+        let started = Instant::now();
+        let attendees: Vec<(Uuid, String)> = self
+            .events
+            .iter()
+            .flat_map(|event| {
+                event
+                    .attendees
+                    .iter()
+                    .map(|attendee| (event.id, internal_email_for_telegram_id(*attendee)))
+            })
+            .collect();
+        for chunk in attendees.chunks(CHUNK) {
+            let (event_ids, emails): (Vec<Uuid>, Vec<String>) = chunk.iter().cloned().unzip();
+            sqlx::query(
+                "INSERT INTO event_attendees (event_id, email)
+                 SELECT * FROM UNNEST($1::uuid[], $2::text[])",
+            )
+            .bind(event_ids)
+            .bind(emails)
+            .execute(pool)
+            .await?;
+        }
+        report("attendees", attendees.len(), started.elapsed());
+
+        let started = Instant::now();
+        for chunk in self.messages.chunks(CHUNK) {
+            let mut kinds = Vec::with_capacity(chunk.len());
+            let mut payloads = Vec::with_capacity(chunk.len());
+            let mut shard_keys = Vec::with_capacity(chunk.len());
+            for message in chunk {
+                kinds.push(message.kind().as_str());
+                payloads.push(message.payload_json()?);
+                shard_keys.push(message.shard_user_id());
+            }
+            sqlx::query(
+                "INSERT INTO outbox_messages (kind, payload, shard_key)
+                 SELECT * FROM UNNEST($1::text[], $2::jsonb[], $3::bigint[])",
+            )
+            .bind(kinds)
+            .bind(payloads)
+            .bind(shard_keys)
+            .execute(pool)
+            .await?;
+        }
+        report("outbox messages", self.messages.len(), started.elapsed());
+        Ok(())
+    }
+}
+
+fn report(what: &str, rows: usize, elapsed: Duration) {
+    println!(
+        "Seeded {rows} {what} in {:.2}s ({:.0} rows/s)",
+        elapsed.as_secs_f64(),
+        rows as f64 / elapsed.as_secs_f64().max(f64::EPSILON)
+    );
+}
+
+/// Size of the tables the scenario grows, including indexes and TOAST
+async fn print_table_sizes(pool: &PgPool) -> Result<()> {
+    // Row counts are the planner's estimates, fresh after ANALYZE
+    sqlx::query("ANALYZE users, events, event_attendees, outbox_messages")
+        .execute(pool)
+        .await?;
+    let sizes: Vec<(String, i64, String)> = sqlx::query_as(
+        "SELECT relname::text, reltuples::bigint, pg_size_pretty(pg_total_relation_size(oid))
+         FROM pg_class
+         WHERE relname IN ('users', 'events', 'event_attendees', 'outbox_messages')
+           AND relkind = 'r'
+         ORDER BY relname",
+    )
+    .fetch_all(pool)
+    .await?;
+    println!("\nTable sizes (whole tables, not only synthetic rows):");
+    for (table, rows, size) in sizes {
+        println!("  {table:<16} {rows:>10} rows  {size:>10}");
+    }
+    Ok(())
+}
+
+/// Run the worker until every seeded message is done, printing progress
+async fn drain_outbox(pool: PgPool, messages: usize) -> Result<()> {
+    let config = Config::from_env()?;
+    println!(
+        "\nDraining with batch_size={}, poll_interval={}s, shard={}/{}",
+        config.batch_size,
+        config.poll_interval_secs,
+        config.shard.index(),
+        config.shard.total()
+    );
+
+    let shutdown = CancellationToken::new();
+    let worker = tokio::spawn(worker::run_worker(
+        WorkerDb::new(pool.clone()),
+        CalendarService::new(CalendarRepository::new(pool.clone())),
+        fake_telegram().await?,
+        None,
+        None,
+        None,
+        None,
+        None,
+        televent_application::Analytics::disabled(),
+        config,
+        Some(shutdown.clone()),
+    ));
+
+    let started = Instant::now();
+    let mut last = (Instant::now(), 0);
+    let (done, failed) = loop {
+        tokio::time::sleep(POLL_INTERVAL).await;
+        let status = outbox_status(&pool).await?;
+        let done = status.completed + status.failed;
+        let finished = status.pending + status.processing == 0;
+        if last.0.elapsed() < REPORT_INTERVAL && !finished {
+            continue;
+        }
+        let rate = (done - last.1) as f64 / last.0.elapsed().as_secs_f64();
+        last = (Instant::now(), done);
+        println!(
+            "  {:>6.0}s  {done}/{messages} done, {} failed, {} waiting  ({rate:.1} jobs/s)",
+            started.elapsed().as_secs_f64(),
+            status.failed,
+            status.pending + status.processing,
+        );
+        if finished {
+            break (done, status.failed);
+        }
+    };
+    let elapsed = started.elapsed();
+    shutdown.cancel();
+    worker.await??;
+
+    println!(
+        "\nProcessed {done} jobs in {:.1}s: {:.1} jobs/s, {failed} failed",
+        elapsed.as_secs_f64(),
+        done as f64 / elapsed.as_secs_f64()
+    );
+    let by_kind: Vec<(String, String, i64)> = sqlx::query_as(
+        "SELECT kind, status::text, COUNT(*) FROM outbox_messages
+         WHERE shard_key >= $1 GROUP BY kind, status ORDER BY kind, status",
+    )
+    .bind(ID_BASE)
+    .fetch_all(&pool)
+    .await?;
+    for (kind, status, count) in by_kind {
+        println!("  {kind:<24} {status:<10} {count:>8}");
+    }
+    Ok(())
+}
+
+#[derive(Default)]
+struct OutboxStatus {
+    pending: i64,
+    processing: i64,
+    completed: i64,
+    failed: i64,
+}
+
+/// Seeded messages by status, read the way the worker's queue status log does
+async fn outbox_status(pool: &PgPool) -> Result<OutboxStatus> {
+    let rows: Vec<(String, i64)> = sqlx::query_as(
+        "SELECT status::text, COUNT(*) FROM outbox_messages
+         WHERE shard_key >= $1 GROUP BY status",
+    )
+    .bind(ID_BASE)
+    .fetch_all(pool)
+    .await?;
+    let mut status = OutboxStatus::default();
+    for (name, count) in rows {
+        match name.as_str() {
+            "pending" => status.pending = count,
+            "processing" => status.processing = count,
+            "completed" => status.completed = count,
+            _ => status.failed += count,
+        }
+    }
+    Ok(status)
+}
+
+/// A bot whose requests go to a local Bot API that accepts everything
+async fn fake_telegram() -> Result<Bot> {
+    async fn answer(Path(path): Path<String>, body: Bytes) -> Json<Value> {
+        let params: Value = serde_json::from_slice(&body).unwrap_or_default();
+        let result = if path
+            .rsplit('/')
+            .next()
+            .is_some_and(|m| m.starts_with("Send"))
+        {
+            json!({
+                "message_id": 1,
+                "date": Utc::now().timestamp(),
+                "chat": { "id": params["chat_id"], "type": "private", "first_name": "Load" },
+                "text": params["text"],
+            })
+        } else {
+            json!(true)
+        };
+        Json(json!({ "ok": true, "result": result }))
+    }
+
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let url = reqwest::Url::parse(&format!("http://{}/", listener.local_addr()?))?;
+    let app = Router::new().route("/{*path}", axum::routing::post(answer));
+    tokio::spawn(async move { axum::serve(listener, app).await.ok() });
+    Ok(Bot::new("0:loadtest").set_api_url(url))
+}
@@ -0,0 +1,226 @@
+//! Slack and Microsoft Teams incoming webhooks
+//!
+//! Notices the worker sends a user in Telegram are copied to each of their
+//! chat webhooks as separate `chat_webhook` messages, so one broken channel
+//! never holds up the Telegram message or the other channels.
+//!
+//! Slack gets a mrkdwn section block; Teams gets an Adaptive Card, which both
+//! the classic Office 365 connectors and the newer workflow webhooks accept.
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use reqwest::{StatusCode, header};
+use serde_json::{Value, json};
+use std::time::Duration;
+use televent_application::{ChatNotice, ChatWebhookService, UserId};
+use televent_domain::{ChatWebhookMessage, ChatWebhookProvider};
+use tracing::info;
+use uuid::Uuid;
+
+use crate::{Deferred, Rejected};
+
+const REQUEST_TIMEOUT_SECS: u64 = 10;
+/// Wait applied when a chat app rate-limits without saying for how long
+const DEFAULT_RATE_LIMIT_DELAY: Duration = Duration::from_secs(60);
+
+/// Copies notices to chat webhooks and posts them
+#[derive(Clone)]
+pub struct ChatSender {
+    http: reqwest::Client,
+    webhooks: ChatWebhookService,
+}
+
+impl ChatSender {
+    pub fn new(webhooks: ChatWebhookService) -> Result<Self> {
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS))
+            .user_agent(concat!("Televent/", env!("CARGO_PKG_VERSION")))
+            // Webhook hosts are checked when the URL is saved; a redirect
+            // could point anywhere
+            .redirect(reqwest::redirect::Policy::none())
+            .build()?;
+
+        Ok(Self { http, webhooks })
+    }
+
+    /// Queue `notice`, rendered for outbox message `source_id`, for each of
+    /// the user's webhooks
+    pub(crate) async fn copy(
+        &self,
+        user_id: i64,
+        source_id: Uuid,
+        notice: &ChatNotice,
+    ) -> Result<()> {
+        let queued = self
+            .webhooks
+            .queue_copies(UserId::new(user_id), source_id, notice)
+            .await
+            .context("Failed to queue chat webhook copies")?;
+        if queued > 0 {
+            info!(
+                "Queued {} chat webhook copies of message {}",
+                queued, source_id
+            );
+        }
+        Ok(())
+    }
+
+    /// Post one copy; returns `false` when the webhook has been removed
+    pub(crate) async fn post(&self, payload: &ChatWebhookMessage) -> Result<bool> {
+        let Some(target) = self.webhooks.target(payload.webhook_id).await? else {
+            return Ok(false);
+        };
+        let body = match target.provider {
+            ChatWebhookProvider::Slack => slack_body(&payload.title, &payload.lines),
+            ChatWebhookProvider::Teams => teams_body(&payload.title, &payload.lines),
+        };
+
+        let response = self
+            .http
+            .post(&target.url)
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| anyhow::anyhow!("Chat webhook request failed: {e}"))?;
+        let status = response.status();
+        if status.is_success() {
+            self.webhooks
+                .record_outcome(payload.webhook_id, None)
+                .await?;
+            return Ok(true);
+        }
+
+        let retry_after = response
+            .headers()
+            .get(header::RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse().ok())
+            .map(Duration::from_secs);
+        let body = response.text().await.unwrap_or_default();
+        let error = classify(status, retry_after, body);
+        if let Some(rejected) = error.downcast_ref::<Rejected>() {
+            self.webhooks
+                .record_outcome(payload.webhook_id, Some(&rejected.0))
+                .await?;
+        }
+        Err(error)
+    }
+}
+
+/// Map a refused post onto the worker's retry policy
+///
+/// 429 reschedules without using up a retry. Other 4xx answers mean the
+/// webhook itself is broken (revoked, channel archived, bad payload) and
+/// will not heal by retrying; 5xx answers take the usual backoff.
+fn classify(status: StatusCode, retry_after: Option<Duration>, body: String) -> anyhow::Error {
+    let body: String = body.chars().take(200).collect();
+    let message = format!("chat app returned {status}: {body}");
+    match status {
+        StatusCode::TOO_MANY_REQUESTS => {
+            let delay = retry_after.unwrap_or(DEFAULT_RATE_LIMIT_DELAY);
+            Deferred {
+                until: Utc::now()
+                    + chrono::Duration::from_std(delay)
+                        .unwrap_or_else(|_| chrono::Duration::minutes(1)),
+                reason: format!("chat webhook rate limit: {message}"),
+            }
+            .into()
+        }
+        _ if status.is_client_error() => Rejected(message).into(),
+        _ => anyhow::anyhow!("Chat webhook delivery failed: {message}"),
+    }
+}
+
+/// Slack mrkdwn treats `&`, `<` and `>` as control characters
+fn slack_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn slack_body(title: &str, lines: &[String]) -> Value {
+    let mut text = format!("*{}*", slack_escape(title));
+    for line in lines {
+        text.push('\n');
+        text.push_str(&slack_escape(line));
+    }
+    json!({
+        // Shown in notifications and by clients without block support
+        "text": title,
+        "blocks": [
+            { "type": "section", "text": { "type": "mrkdwn", "text": text } }
+        ],
+    })
+}
+
+fn teams_body(title: &str, lines: &[String]) -> Value {
+    let mut body = vec![json!({
+        "type": "TextBlock",
+        "text": title,
+        "weight": "Bolder",
+        "size": "Medium",
+        "wrap": true,
+    })];
+    body.extend(
+        lines
+            .iter()
+            .map(|line| json!({ "type": "TextBlock", "text": line, "wrap": true })),
+    );
+    json!({
+        "type": "message",
+        "attachments": [{
+            "contentType": "application/vnd.microsoft.card.adaptive",
+            "content": {
+                "$schema": "http://adaptivecards.io/schemas/adaptive-card.json",
+                "type": "AdaptiveCard",
+                "version": "1.4",
+                "body": body,
+            },
+        }],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bodies_follow_provider_format() {
+        let lines = vec!["🕒 Time: Mon 1 Jun, 10:00".to_string()];
+        let slack = slack_body("📅 Invite: R&D <sync>", &lines);
+        assert_eq!(
+            slack["blocks"][0]["text"]["text"],
+            "*📅 Invite: R&amp;D &lt;sync&gt;*\n🕒 Time: Mon 1 Jun, 10:00"
+        );
+        assert_eq!(slack["text"], "📅 Invite: R&D <sync>");
+
+        let teams = teams_body("📅 Invite: R&D <sync>", &lines);
+        let card = &teams["attachments"][0]["content"];
+        assert_eq!(card["type"], "AdaptiveCard");
+        assert_eq!(card["body"][0]["text"], "📅 Invite: R&D <sync>");
+        assert_eq!(card["body"][1]["text"], "🕒 Time: Mon 1 Jun, 10:00");
+    }
+
+    #[test]
+    fn test_refusals_follow_retry_policy() {
+        let limited = classify(
+            StatusCode::TOO_MANY_REQUESTS,
+            Some(Duration::from_secs(30)),
+            String::new(),
+        );
+        assert!(limited.downcast_ref::<Deferred>().is_some());
+
+        for status in [
+            StatusCode::NOT_FOUND,
+            StatusCode::GONE,
+            StatusCode::FORBIDDEN,
+        ] {
+            let gone = classify(status, None, "channel_is_archived".to_string());
+            assert!(gone.downcast_ref::<Rejected>().is_some(), "{status}");
+        }
+
+        let outage = classify(StatusCode::BAD_GATEWAY, None, String::new());
+        assert!(outage.downcast_ref::<Rejected>().is_none());
+        assert!(outage.downcast_ref::<Deferred>().is_none());
+    }
+}
@@ -2,8 +2,9 @@
 //!
 //! Loads configuration from environment variables
 
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, anyhow};
 use std::env;
+use televent_storage::outbox::OutboxShard;
 
 /// Worker configuration
 #[derive(Debug, Clone)]
@@ -19,6 +20,40 @@ pub struct Config {
 
     /// Interval in seconds for logging queue status (COUNT(*))
     pub status_log_interval_secs: u64,
+
+    /// How long RSVP notifications wait to be sent as one message per
+    /// organizer (0 sends each one immediately)
+    pub rsvp_digest_window_secs: u64,
+
+    /// Slice of the outbox this worker processes
+    pub shard: OutboxShard,
+
+    /// How long old rows are kept before the retention task deletes them
+    pub retention: RetentionPolicy,
+}
+
+/// Days each kind of data is kept; `None` keeps it forever
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetentionPolicy {
+    /// Tombstones of deleted events. CalDAV clients whose sync token is
+    /// older than the pruned ones have to sync from scratch.
+    pub tombstone_days: Option<u32>,
+
+    /// Device activity, email bounce reports and slow query plans
+    pub audit_log_days: Option<u32>,
+
+    /// Completed and failed outbox messages
+    pub outbox_days: Option<u32>,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            tombstone_days: Some(90),
+            audit_log_days: Some(30),
+            outbox_days: Some(14),
+        }
+    }
 }
 
 impl Config {
@@ -44,10 +79,59 @@ impl Config {
                 .unwrap_or_else(|_| "60".to_string())
                 .parse()
                 .context("WORKER_STATUS_LOG_INTERVAL_SECS must be a valid integer")?,
+
+            rsvp_digest_window_secs: env::var("WORKER_RSVP_DIGEST_WINDOW_SECS")
+                .unwrap_or_else(|_| "60".to_string())
+                .parse()
+                .context("WORKER_RSVP_DIGEST_WINDOW_SECS must be a valid integer")?,
+
+            shard: shard_from_env()?,
+
+            retention: retention_from_env()?,
         })
     }
 }
 
+/// `WORKER_SHARD_INDEX` of `WORKER_SHARD_TOTAL`, by default the whole queue
+pub fn shard_from_env() -> Result<OutboxShard> {
+    let index: u32 = env::var("WORKER_SHARD_INDEX")
+        .unwrap_or_else(|_| "0".to_string())
+        .parse()
+        .context("WORKER_SHARD_INDEX must be a valid integer")?;
+    let total: u32 = env::var("WORKER_SHARD_TOTAL")
+        .unwrap_or_else(|_| "1".to_string())
+        .parse()
+        .context("WORKER_SHARD_TOTAL must be a valid integer")?;
+
+    OutboxShard::new(index, total).ok_or_else(|| {
+        anyhow!("WORKER_SHARD_INDEX ({index}) must be below WORKER_SHARD_TOTAL ({total})")
+    })
+}
+
+/// `RETENTION_TOMBSTONE_DAYS`, `RETENTION_AUDIT_LOG_DAYS` and
+/// `RETENTION_OUTBOX_DAYS`, where `0` keeps the data forever
+pub fn retention_from_env() -> Result<RetentionPolicy> {
+    let defaults = RetentionPolicy::default();
+    Ok(RetentionPolicy {
+        tombstone_days: retention_days("RETENTION_TOMBSTONE_DAYS", defaults.tombstone_days)?,
+        audit_log_days: retention_days("RETENTION_AUDIT_LOG_DAYS", defaults.audit_log_days)?,
+        outbox_days: retention_days("RETENTION_OUTBOX_DAYS", defaults.outbox_days)?,
+    })
+}
+
+fn retention_days(name: &str, default: Option<u32>) -> Result<Option<u32>> {
+    match env::var(name) {
+        Ok(value) => parse_retention_days(&value)
+            .with_context(|| format!("{name} must be a number of days (0 keeps forever)")),
+        Err(_) => Ok(default),
+    }
+}
+
+fn parse_retention_days(value: &str) -> Result<Option<u32>> {
+    let days: u32 = value.trim().parse()?;
+    Ok((days > 0).then_some(days))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -61,6 +145,9 @@ mod tests {
             max_retry_count: 5,
             batch_size: 10,
             status_log_interval_secs: 60,
+            rsvp_digest_window_secs: 0,
+            shard: OutboxShard::default(),
+            retention: RetentionPolicy::default(),
         };
 
         assert_eq!(config.poll_interval_secs, 10);
@@ -75,6 +162,9 @@ mod tests {
             max_retry_count: 5,
             batch_size: 10,
             status_log_interval_secs: 60,
+            rsvp_digest_window_secs: 0,
+            shard: OutboxShard::default(),
+            retention: RetentionPolicy::default(),
         };
 
         let cloned = config.clone();
@@ -92,10 +182,22 @@ mod tests {
             max_retry_count: 5,
             batch_size: 10,
             status_log_interval_secs: 60,
+            rsvp_digest_window_secs: 0,
+            shard: OutboxShard::default(),
+            retention: RetentionPolicy::default(),
         };
 
         let debug_str = format!("{:?}", config);
         assert!(debug_str.contains("Config"));
         assert!(debug_str.contains("poll_interval_secs"));
     }
+
+    #[test]
+    fn test_parse_retention_days() {
+        assert_eq!(parse_retention_days("30").unwrap(), Some(30));
+        assert_eq!(parse_retention_days(" 7 ").unwrap(), Some(7));
+        assert_eq!(parse_retention_days("0").unwrap(), None);
+        assert!(parse_retention_days("-1").is_err());
+        assert!(parse_retention_days("forever").is_err());
+    }
 }
@@ -4,16 +4,16 @@
 
 use chrono::{DateTime, Utc};
 use sqlx::PgPool;
-use televent_domain::OutboxPayload;
+use televent_application::JobOutcome;
+use televent_domain::{OutboxMessageId, OutboxPayload};
 #[cfg(test)]
 pub use televent_storage::outbox::OutboxStatus;
 use televent_storage::{
     StorageError,
-    outbox::{OutboxMessage as StoredOutboxMessage, OutboxRepository, OutboxUpdate},
+    outbox::{OutboxMessage as StoredOutboxMessage, OutboxRepository, OutboxShard, OutboxUpdate},
 };
 use thiserror::Error;
 use tracing::warn;
-use uuid::Uuid;
 
 #[derive(Debug, Error)]
 #[error("worker database error: {message}")]
@@ -30,7 +30,7 @@ fn storage_to_worker(error: StorageError) -> WorkerDbError {
 #[derive(Debug, Error)]
 #[error("invalid outbox message {id}: {message}")]
 pub struct OutboxDecodeError {
-    pub id: Uuid,
+    pub id: OutboxMessageId,
     message: String,
 }
 
@@ -43,9 +43,10 @@ impl OutboxDecodeError {
 
 #[derive(Debug, Clone)]
 pub struct TypedOutboxMessage {
-    pub id: Uuid,
+    pub id: OutboxMessageId,
     pub payload: OutboxPayload,
     pub retry_count: i32,
+    pub created_at: DateTime<Utc>,
 }
 
 impl TryFrom<StoredOutboxMessage> for TypedOutboxMessage {
@@ -54,6 +55,7 @@ impl TryFrom<StoredOutboxMessage> for TypedOutboxMessage {
     fn try_from(message: StoredOutboxMessage) -> Result<Self, Self::Error> {
         let id = message.id;
         let retry_count = message.retry_count;
+        let created_at = message.created_at;
         let payload = OutboxPayload::from_parts(&message.kind, message.payload).map_err(|err| {
             OutboxDecodeError {
                 id,
@@ -65,6 +67,7 @@ impl TryFrom<StoredOutboxMessage> for TypedOutboxMessage {
             id,
             payload,
             retry_count,
+            created_at,
         })
     }
 }
@@ -72,19 +75,31 @@ impl TryFrom<StoredOutboxMessage> for TypedOutboxMessage {
 /// Result of processing a job
 #[derive(Debug, Clone)]
 pub enum JobResult {
-    Completed(Uuid),
+    Completed(OutboxMessageId),
     Failed {
-        id: Uuid,
+        id: OutboxMessageId,
         error: String,
     },
     Reschedule {
-        id: Uuid,
+        id: OutboxMessageId,
         retry_count: i32,
         scheduled_at: DateTime<Utc>,
         error: String,
     },
 }
 
+impl JobResult {
+    /// What the result means for analytics
+    #[must_use]
+    pub const fn outcome(&self) -> JobOutcome {
+        match self {
+            Self::Completed(_) => JobOutcome::Completed,
+            Self::Failed { .. } => JobOutcome::Failed,
+            Self::Reschedule { .. } => JobOutcome::Rescheduled,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct ClaimedOutboxBatch {
     pub jobs: Vec<TypedOutboxMessage>,
@@ -102,6 +117,7 @@ impl ClaimedOutboxBatch {
 #[derive(Clone)]
 pub struct WorkerDb {
     outbox: OutboxRepository,
+    shard: OutboxShard,
 }
 
 impl WorkerDb {
@@ -109,19 +125,42 @@ impl WorkerDb {
     pub fn new(pool: PgPool) -> Self {
         Self {
             outbox: OutboxRepository::new(pool),
+            shard: OutboxShard::default(),
         }
     }
 
+    /// Only claim the jobs of one shard of the queue
+    #[must_use]
+    pub fn with_shard(mut self, shard: OutboxShard) -> Self {
+        self.shard = shard;
+        self
+    }
+
     /// Fetch pending jobs and mark them as processing
     ///
-    /// Uses FOR UPDATE SKIP LOCKED to prevent duplicate processing
+    /// Uses FOR UPDATE SKIP LOCKED to prevent duplicate processing, and only
+    /// takes jobs of this handle's shard
     pub async fn fetch_pending_jobs(
         &self,
         batch_size: i64,
     ) -> Result<ClaimedOutboxBatch, WorkerDbError> {
         let messages = self
             .outbox
-            .claim_pending_jobs(batch_size)
+            .claim_pending_jobs(batch_size, self.shard)
+            .await
+            .map_err(storage_to_worker)?;
+
+        Ok(decode_claimed_jobs(messages))
+    }
+
+    /// Claim all pending RSVP notifications for an organizer
+    pub async fn claim_rsvp_notifications(
+        &self,
+        organizer_telegram_id: i64,
+    ) -> Result<ClaimedOutboxBatch, WorkerDbError> {
+        let messages = self
+            .outbox
+            .claim_pending_rsvp_notifications(organizer_telegram_id)
             .await
             .map_err(storage_to_worker)?;
 
@@ -130,7 +169,7 @@ impl WorkerDb {
 
     /// Mark a message as completed
     #[cfg(test)]
-    pub async fn mark_completed(&self, message_id: Uuid) -> Result<(), WorkerDbError> {
+    pub async fn mark_completed(&self, message_id: OutboxMessageId) -> Result<(), WorkerDbError> {
         self.outbox
             .mark_completed(message_id)
             .await
@@ -141,7 +180,7 @@ impl WorkerDb {
     #[cfg(test)]
     pub async fn mark_failed(
         &self,
-        message_id: Uuid,
+        message_id: OutboxMessageId,
         error_msg: &str,
     ) -> Result<(), WorkerDbError> {
         self.outbox
@@ -156,7 +195,7 @@ impl WorkerDb {
     #[cfg(test)]
     pub async fn reschedule_message(
         &self,
-        message_id: Uuid,
+        message_id: OutboxMessageId,
         current_retry_count: i32,
         error_msg: &str,
     ) -> Result<(), WorkerDbError> {
@@ -225,6 +264,7 @@ fn decode_claimed_jobs(messages: Vec<StoredOutboxMessage>) -> ClaimedOutboxBatch
 #[cfg(test)]
 mod tests {
     use super::*;
+    use uuid::Uuid;
 
     #[test]
     fn test_backoff_calculation() {
@@ -259,7 +299,7 @@ mod tests {
 
     #[test]
     fn decode_claimed_jobs_marks_invalid_payload_failed() {
-        let id = Uuid::new_v4();
+        let id = OutboxMessageId::new(Uuid::new_v4());
         let message = StoredOutboxMessage {
             id,
             kind: "invite_notification".to_string(),
@@ -268,6 +308,7 @@ mod tests {
             retry_count: 0,
             scheduled_at: Utc::now(),
             processed_at: None,
+            created_at: Utc::now(),
         };
 
         let batch = decode_claimed_jobs(vec![message]);
@@ -308,6 +349,52 @@ mod tests {
         Ok(())
     }
 
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_shards_split_jobs_by_user(pool: PgPool) -> anyhow::Result<()> {
+        use serde_json::json;
+
+        for telegram_id in 1..=40_i64 {
+            for _ in 0..2 {
+                sqlx::query(
+                    r#"
+                    INSERT INTO outbox_messages (kind, payload, shard_key, scheduled_at)
+                    VALUES ('telegram_notification', $1, $2, NOW() - INTERVAL '1 minute')
+                    "#,
+                )
+                .bind(json!({"telegram_id": telegram_id, "message": "hello"}))
+                .bind(telegram_id)
+                .execute(&pool)
+                .await?;
+            }
+        }
+
+        let mut claimed_users = Vec::new();
+        for index in 0..2 {
+            let db = WorkerDb::new(pool.clone()).with_shard(OutboxShard::new(index, 2).unwrap());
+            let batch = db.fetch_pending_jobs(100).await?;
+            assert!(!batch.jobs.is_empty(), "shard {index} got no jobs");
+            let mut users: Vec<i64> = batch
+                .jobs
+                .iter()
+                .map(|job| match &job.payload {
+                    OutboxPayload::TelegramNotification(payload) => payload.telegram_id,
+                    other => panic!("unexpected payload {other:?}"),
+                })
+                .collect();
+            users.sort_unstable();
+            // Both messages of a user land on the same shard
+            assert!(users.chunks(2).all(|pair| pair[0] == pair[1]), "{users:?}");
+            claimed_users.extend(users);
+        }
+
+        claimed_users.sort_unstable();
+        claimed_users.dedup();
+        assert_eq!(claimed_users, (1..=40).collect::<Vec<_>>());
+        assert!(OutboxShard::new(2, 2).is_none());
+
+        Ok(())
+    }
+
     #[sqlx::test(migrations = "../migrations")]
     async fn test_mark_completed(pool: PgPool) -> anyhow::Result<()> {
         use serde_json::json;
@@ -325,7 +412,7 @@ mod tests {
         .execute(&pool)
         .await?;
 
-        db.mark_completed(id).await?;
+        db.mark_completed(OutboxMessageId::new(id)).await?;
 
         let status: OutboxStatus =
             sqlx::query_scalar("SELECT status FROM outbox_messages WHERE id = $1")
@@ -354,7 +441,8 @@ mod tests {
         .execute(&pool)
         .await?;
 
-        db.mark_failed(id, "test error").await?;
+        db.mark_failed(OutboxMessageId::new(id), "test error")
+            .await?;
 
         let (status, error_msg): (OutboxStatus, Option<String>) =
             sqlx::query_as("SELECT status, error_message FROM outbox_messages WHERE id = $1")
@@ -384,7 +472,8 @@ mod tests {
         .execute(&pool)
         .await?;
 
-        db.reschedule_message(id, 0, "retry error").await?;
+        db.reschedule_message(OutboxMessageId::new(id), 0, "retry error")
+            .await?;
 
         let (status, retry_count): (OutboxStatus, i32) =
             sqlx::query_as("SELECT status, retry_count FROM outbox_messages WHERE id = $1")
@@ -0,0 +1,310 @@
+//! RSVP notification digests
+//!
+//! Answers to an invite tend to arrive in bursts. The first RSVP for an
+//! organizer waits out the digest window; once it is due, every pending RSVP
+//! for that organizer is claimed and sent as one Telegram message.
+
+use chrono::{DateTime, Duration, Utc};
+use std::collections::{BTreeMap, HashMap};
+use televent_domain::{OutboxMessageId, OutboxPayload, RsvpNotification, TelegramNotification};
+use tracing::{info, warn};
+
+use crate::db::{JobResult, TypedOutboxMessage, WorkerDb};
+use crate::processors::rsvp_line;
+
+/// Claimed jobs after RSVP notifications have been folded into digests
+#[derive(Debug, Default)]
+pub(crate) struct CoalescedJobs {
+    /// Jobs to process, with each digest carried by one of its RSVP jobs
+    pub jobs: Vec<TypedOutboxMessage>,
+    /// RSVP jobs folded into a digest, keyed by the job that carries it
+    pub merged: HashMap<OutboxMessageId, Vec<OutboxMessageId>>,
+    /// Results known before processing: held RSVPs and undecodable rows
+    pub results: Vec<JobResult>,
+}
+
+/// Group claimed RSVP notifications by organizer
+///
+/// Groups whose window has not passed by `now` go back to the queue until it
+/// does. A window of zero leaves the jobs untouched.
+pub(crate) async fn coalesce_rsvp_notifications(
+    db: &WorkerDb,
+    jobs: Vec<TypedOutboxMessage>,
+    window: Duration,
+    now: DateTime<Utc>,
+) -> CoalescedJobs {
+    let mut coalesced = CoalescedJobs::default();
+    if window <= Duration::zero() {
+        coalesced.jobs = jobs;
+        return coalesced;
+    }
+
+    let mut by_organizer: BTreeMap<i64, Vec<TypedOutboxMessage>> = BTreeMap::new();
+    for job in jobs {
+        match &job.payload {
+            OutboxPayload::RsvpNotification(payload) => by_organizer
+                .entry(payload.organizer_telegram_id)
+                .or_default()
+                .push(job),
+            _ => coalesced.jobs.push(job),
+        }
+    }
+
+    for (organizer, mut group) in by_organizer {
+        let due = group.iter().map(|job| job.created_at).min().unwrap_or(now) + window;
+        if due > now {
+            coalesced
+                .results
+                .extend(group.iter().map(|job| hold(job, due)));
+            continue;
+        }
+
+        match db.claim_rsvp_notifications(organizer).await {
+            Ok(batch) => {
+                group.extend(batch.jobs);
+                coalesced.results.extend(batch.failed_results);
+            }
+            Err(e) => warn!(
+                "Failed to claim pending RSVPs for organizer {}: {}",
+                organizer, e
+            ),
+        }
+
+        if group.len() == 1 {
+            coalesced.jobs.extend(group);
+            continue;
+        }
+
+        group.sort_by_key(|job| job.created_at);
+        let payloads: Vec<&RsvpNotification> = group
+            .iter()
+            .filter_map(|job| match &job.payload {
+                OutboxPayload::RsvpNotification(payload) => Some(payload),
+                _ => None,
+            })
+            .collect();
+        let message = digest_text(&payloads);
+        info!(
+            "Sending {} RSVP notifications to organizer {} as one digest",
+            group.len(),
+            organizer
+        );
+
+        let mut group = group.into_iter();
+        let Some(first) = group.next() else {
+            continue;
+        };
+        coalesced
+            .merged
+            .insert(first.id, group.map(|job| job.id).collect());
+        coalesced.jobs.push(TypedOutboxMessage {
+            payload: OutboxPayload::TelegramNotification(TelegramNotification {
+                telegram_id: organizer,
+                message,
+            }),
+            ..first
+        });
+    }
+
+    coalesced
+}
+
+/// Give the RSVP jobs folded into a digest the outcome of the digest itself
+pub(crate) fn fan_out_results(
+    results: &[JobResult],
+    merged: &HashMap<OutboxMessageId, Vec<OutboxMessageId>>,
+) -> Vec<JobResult> {
+    results
+        .iter()
+        .filter_map(|result| {
+            let id = match result {
+                JobResult::Completed(id)
+                | JobResult::Failed { id, .. }
+                | JobResult::Reschedule { id, .. } => id,
+            };
+            merged.get(id).map(|ids| (result, ids))
+        })
+        .flat_map(|(result, ids)| ids.iter().map(move |id| with_id(result, *id)))
+        .collect()
+}
+
+fn hold(job: &TypedOutboxMessage, due: DateTime<Utc>) -> JobResult {
+    JobResult::Reschedule {
+        id: job.id,
+        retry_count: job.retry_count,
+        scheduled_at: due,
+        error: format!("waiting for the RSVP digest until {due}"),
+    }
+}
+
+fn with_id(result: &JobResult, id: OutboxMessageId) -> JobResult {
+    match result.clone() {
+        JobResult::Completed(_) => JobResult::Completed(id),
+        JobResult::Failed { error, .. } => JobResult::Failed { id, error },
+        JobResult::Reschedule {
+            retry_count,
+            scheduled_at,
+            error,
+            ..
+        } => JobResult::Reschedule {
+            id,
+            retry_count,
+            scheduled_at,
+            error,
+        },
+    }
+}
+
+fn digest_text(payloads: &[&RsvpNotification]) -> String {
+    let mut text = format!("📅 {} new RSVPs:", payloads.len());
+    for payload in payloads {
+        text.push_str("\n• ");
+        text.push_str(&rsvp_line(payload));
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use sqlx::PgPool;
+    use televent_domain::{Clock, MockClock, ParticipationStatus};
+    use uuid::Uuid;
+
+    async fn insert_rsvp(
+        pool: &PgPool,
+        organizer: i64,
+        attendee: &str,
+        age_secs: i64,
+    ) -> anyhow::Result<OutboxMessageId> {
+        let id = Uuid::new_v4();
+        sqlx::query(
+            r#"
+            INSERT INTO outbox_messages (id, kind, payload, status, retry_count, scheduled_at, created_at)
+            VALUES ($1, 'rsvp_notification', $2, 'pending', 0,
+                    NOW() - make_interval(secs => $3), NOW() - make_interval(secs => $3))
+            "#,
+        )
+        .bind(id)
+        .bind(json!({
+            "organizer_telegram_id": organizer,
+            "attendee_name": attendee,
+            "event_summary": "Standup",
+            "rsvp_status": "Accepted",
+        }))
+        .bind(age_secs as f64)
+        .execute(pool)
+        .await?;
+        Ok(OutboxMessageId::new(id))
+    }
+
+    #[test]
+    fn test_digest_text_lists_every_rsvp() {
+        let alice = RsvpNotification {
+            organizer_telegram_id: 1,
+            attendee_name: "Alice".to_string(),
+            event_summary: "Standup".to_string(),
+            rsvp_status: ParticipationStatus::Accepted,
+        };
+        let bob = RsvpNotification {
+            attendee_name: "Bob".to_string(),
+            rsvp_status: ParticipationStatus::Declined,
+            ..alice.clone()
+        };
+
+        assert_eq!(
+            digest_text(&[&alice, &bob]),
+            "📅 2 new RSVPs:\n\
+             • Alice accepted your invite to: Standup\n\
+             • Bob declined your invite to: Standup"
+        );
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_rsvps_are_held_then_sent_together(pool: PgPool) -> anyhow::Result<()> {
+        let db = WorkerDb::new(pool.clone());
+        let first = insert_rsvp(&pool, 10, "Alice", 120).await?;
+        // Not due yet, so only claimed through the organizer
+        let second = insert_rsvp(&pool, 10, "Bob", 5).await?;
+        sqlx::query(
+            "UPDATE outbox_messages SET scheduled_at = NOW() + INTERVAL '1 minute' WHERE id = $1",
+        )
+        .bind(second.inner())
+        .execute(&pool)
+        .await?;
+        let fresh = insert_rsvp(&pool, 20, "Carol", 0).await?;
+
+        let batch = db.fetch_pending_jobs(10).await?;
+        let coalesced =
+            coalesce_rsvp_notifications(&db, batch.jobs, Duration::seconds(60), Utc::now()).await;
+
+        assert_eq!(coalesced.jobs.len(), 1);
+        let digest = &coalesced.jobs[0];
+        assert_eq!(digest.id, first);
+        match &digest.payload {
+            OutboxPayload::TelegramNotification(payload) => {
+                assert_eq!(payload.telegram_id, 10);
+                assert!(payload.message.starts_with("📅 2 new RSVPs:"));
+                assert!(payload.message.contains("Bob accepted"));
+            }
+            other => panic!("expected a digest message, got {other:?}"),
+        }
+        assert_eq!(coalesced.merged.get(&first), Some(&vec![second]));
+
+        assert!(matches!(
+            coalesced.results.as_slice(),
+            [JobResult::Reschedule { id, retry_count: 0, .. }] if *id == fresh
+        ));
+
+        let fanned = fan_out_results(&[JobResult::Completed(first)], &coalesced.merged);
+        assert!(matches!(fanned.as_slice(), [JobResult::Completed(id)] if *id == second));
+        Ok(())
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_held_rsvps_wait_out_the_window(pool: PgPool) -> anyhow::Result<()> {
+        let db = WorkerDb::new(pool.clone());
+        let id = insert_rsvp(&pool, 10, "Alice", 0).await?;
+        let batch = db.fetch_pending_jobs(10).await?;
+        let created_at = batch.jobs[0].created_at;
+        let clock = MockClock::new(created_at + Duration::seconds(45));
+
+        let coalesced = coalesce_rsvp_notifications(
+            &db,
+            batch.jobs.clone(),
+            Duration::seconds(60),
+            clock.now(),
+        )
+        .await;
+        assert!(coalesced.jobs.is_empty());
+        assert!(matches!(
+            coalesced.results.as_slice(),
+            [JobResult::Reschedule { id: held, scheduled_at, .. }]
+                if *held == id && *scheduled_at == created_at + Duration::seconds(60)
+        ));
+
+        clock.advance(Duration::seconds(15));
+        let coalesced =
+            coalesce_rsvp_notifications(&db, batch.jobs, Duration::seconds(60), clock.now()).await;
+        assert_eq!(coalesced.jobs.len(), 1);
+        assert!(coalesced.results.is_empty());
+        Ok(())
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_zero_window_sends_each_rsvp(pool: PgPool) -> anyhow::Result<()> {
+        let db = WorkerDb::new(pool.clone());
+        insert_rsvp(&pool, 10, "Alice", 0).await?;
+        insert_rsvp(&pool, 10, "Bob", 0).await?;
+
+        let batch = db.fetch_pending_jobs(10).await?;
+        let coalesced =
+            coalesce_rsvp_notifications(&db, batch.jobs, Duration::zero(), Utc::now()).await;
+
+        assert_eq!(coalesced.jobs.len(), 2);
+        assert!(coalesced.merged.is_empty());
+        assert!(coalesced.results.is_empty());
+        Ok(())
+    }
+}
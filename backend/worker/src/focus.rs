@@ -0,0 +1,77 @@
+//! Focus-time scheduling
+//!
+//! Periodically brings every user's focus blocks for the current week in
+//! line with their rules: blocks go into free time as tentative events, and
+//! tentative blocks that something else now overlaps are moved.
+
+use anyhow::Result;
+use televent_application::{
+    CalendarService, EventService, SubscriptionService, UserId, schedule_focus_time,
+};
+use tokio::time::Duration;
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, warn};
+
+/// How often the task revisits every user's focus rules
+const FOCUS_TICK_SECS: u64 = 15 * 60;
+
+/// Users loaded per page
+const FOCUS_BATCH_SIZE: i64 = 100;
+
+/// Run the focus scheduling loop until cancelled
+pub async fn run_focus_scheduling(
+    calendar: CalendarService,
+    events: EventService,
+    subscriptions: SubscriptionService,
+    shutdown: Option<CancellationToken>,
+) -> Result<()> {
+    let shutdown = shutdown.unwrap_or_default();
+    info!("Starting focus scheduling: tick={}s", FOCUS_TICK_SECS);
+
+    loop {
+        schedule_all(&calendar, &events, &subscriptions, &shutdown).await;
+
+        tokio::select! {
+            () = shutdown.cancelled() => {
+                info!("Focus scheduling received shutdown signal");
+                return Ok(());
+            }
+            () = tokio::time::sleep(Duration::from_secs(FOCUS_TICK_SECS)) => {}
+        }
+    }
+}
+
+async fn schedule_all(
+    calendar: &CalendarService,
+    events: &EventService,
+    subscriptions: &SubscriptionService,
+    shutdown: &CancellationToken,
+) {
+    let mut after: Option<UserId> = None;
+    loop {
+        let users = match calendar.list_focus_users(after, FOCUS_BATCH_SIZE).await {
+            Ok(users) => users,
+            Err(e) => {
+                error!("Failed to load users with focus rules: {}", e);
+                return;
+            }
+        };
+        for &user_id in &users {
+            if shutdown.is_cancelled() {
+                return;
+            }
+            match schedule_focus_time(calendar, subscriptions, events, user_id).await {
+                Ok(schedule) if schedule.placed > 0 || schedule.moved > 0 => info!(
+                    "Focus time of user {}: placed={}, moved={}, missing={}",
+                    user_id, schedule.placed, schedule.moved, schedule.missing
+                ),
+                Ok(_) => {}
+                Err(e) => warn!("Focus time of user {} not scheduled: {}", user_id, e),
+            }
+        }
+        if users.len() < FOCUS_BATCH_SIZE as usize {
+            return;
+        }
+        after = users.last().copied();
+    }
+}
@@ -0,0 +1,73 @@
+//! Scheduled Google Calendar sync
+//!
+//! Periodically runs the two-way sync of every connection that is due.
+
+use anyhow::Result;
+use televent_application::{GoogleSyncService, GoogleSyncState};
+use televent_google::{GoogleConnector, GoogleError};
+use tokio::time::Duration;
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, warn};
+
+/// How often the sync task looks for due connections
+const SYNC_TICK_SECS: u64 = 60;
+
+/// Connections synced per tick
+const SYNC_BATCH_SIZE: i64 = 20;
+
+/// Run the Google Calendar sync loop until cancelled
+pub async fn run_google_sync(
+    service: GoogleSyncService,
+    connector: GoogleConnector,
+    shutdown: Option<CancellationToken>,
+) -> Result<()> {
+    let shutdown = shutdown.unwrap_or_default();
+    info!("Starting Google Calendar sync: tick={}s", SYNC_TICK_SECS);
+
+    loop {
+        match service.due_connections(SYNC_BATCH_SIZE).await {
+            Ok(due) => {
+                for connection in due {
+                    if shutdown.is_cancelled() {
+                        break;
+                    }
+                    sync_connection(&service, &connector, connection).await;
+                }
+            }
+            Err(e) => error!("Failed to load due Google connections: {}", e),
+        }
+
+        tokio::select! {
+            () = shutdown.cancelled() => {
+                info!("Google Calendar sync received shutdown signal");
+                return Ok(());
+            }
+            () = tokio::time::sleep(Duration::from_secs(SYNC_TICK_SECS)) => {}
+        }
+    }
+}
+
+async fn sync_connection(
+    service: &GoogleSyncService,
+    connector: &GoogleConnector,
+    connection: GoogleSyncState,
+) {
+    let user_id = connection.user_id;
+    let result = match connector.sync_connection(service, connection).await {
+        Ok(report) => {
+            info!("Google Calendar of user {} synced: {:?}", user_id, report);
+            Ok(())
+        }
+        Err(e) => {
+            match &e {
+                GoogleError::AccessRevoked => warn!("Google access of user {} revoked", user_id),
+                _ => warn!("Google Calendar sync of user {} failed: {}", user_id, e),
+            }
+            service.record_sync_failure(user_id, &e.to_string()).await
+        }
+    };
+
+    if let Err(e) = result {
+        error!("Failed to record Google sync of user {}: {}", user_id, e);
+    }
+}
@@ -2,27 +2,74 @@
 //!
 //! Processes typed outbox messages with retry logic
 
-#[cfg(test)]
-mod bench_worker;
+mod chat;
 mod config;
 mod db;
+mod digest;
+mod focus;
+#[cfg(feature = "google-calendar")]
+mod google;
+mod mailer;
 mod processors;
-
-pub use config::Config;
-pub use db::{WorkerDb, WorkerDbError};
+mod push;
+mod retention;
+mod sms;
+mod subscriptions;
+mod telegram;
+mod weather;
+
+pub use chat::ChatSender;
+pub use config::{Config, RetentionPolicy, retention_from_env, shard_from_env};
+pub use db::{ClaimedOutboxBatch, JobResult, WorkerDb, WorkerDbError};
+pub use focus::run_focus_scheduling;
+#[cfg(feature = "google-calendar")]
+pub use google::run_google_sync;
+#[cfg(feature = "email-mailgun")]
+pub use mailer::MailgunConfig;
+#[cfg(feature = "email-ses")]
+pub use mailer::SesConfig;
+pub use mailer::{
+    EmailBackend, EmailBackendConfig, EmailConfig, Mailer, SendError, SendFuture, SmtpConfig,
+    SmtpTls,
+};
+pub use push::{PushSender, VapidConfig};
+pub use retention::run_retention;
+#[cfg(feature = "sms-twilio")]
+pub use sms::TwilioConfig;
+pub use sms::{SmsConfig, SmsFuture, SmsProvider, SmsSender};
+pub use subscriptions::run_subscription_refresh;
+pub use televent_storage::outbox::OutboxShard;
+pub use weather::{Forecaster, WeatherConfig};
 
 use anyhow::Result;
-use chrono::{Duration as ChronoDuration, Utc};
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
 use std::collections::HashMap;
 use std::sync::Arc;
-use televent_application::{CalendarService, EventView};
-use televent_domain::OutboxPayload;
+use telegram::TelegramSender;
+use televent_application::{Analytics, CalendarService, EventView, Metric};
+use televent_domain::{OutboxMessageId, OutboxPayload};
 use teloxide::Bot;
 use tokio::time::{Duration, Instant};
 use tokio_util::sync::CancellationToken;
 use tracing::{error, info, warn};
 use uuid::Uuid;
 
+/// A job that cannot run yet and should be picked up again at `until`
+///
+/// Returned by processors as an error; it reschedules the job without using
+/// up a retry.
+#[derive(Debug, thiserror::Error)]
+#[error("deferred until {until}: {reason}")]
+pub(crate) struct Deferred {
+    pub until: DateTime<Utc>,
+    pub reason: String,
+}
+
+/// A job that can never succeed; it fails at once instead of being retried
+#[derive(Debug, thiserror::Error)]
+#[error("{0}")]
+pub(crate) struct Rejected(pub String);
+
 /// Run the background worker service
 ///
 /// This function runs the job processing loop until cancelled or an error occurs.
@@ -31,32 +78,61 @@ use uuid::Uuid;
 /// * `db` - Worker outbox repository
 /// * `calendar` - Calendar application service
 /// * `bot` - Telegram bot instance for sending notifications
+/// * `mailer` - SMTP sender, `None` while external email is disabled
+/// * `chat` - Slack and Teams webhook sender, `None` to leave copies queued
+/// * `sms` - Text message sender, `None` while no SMS provider is configured
+/// * `push` - Web Push sender, `None` while no VAPID keys are configured
+/// * `weather` - Forecasts for reminders, `None` while disabled
+/// * `analytics` - Counts job outcomes per message kind
 /// * `config` - Worker configuration
 /// * `shutdown` - Optional cancellation token for graceful shutdown
+#[allow(clippy::too_many_arguments)]
 pub async fn run_worker(
     db: WorkerDb,
     calendar: CalendarService,
     bot: Bot,
+    mailer: Option<Mailer>,
+    chat: Option<ChatSender>,
+    sms: Option<SmsSender>,
+    push: Option<PushSender>,
+    weather: Option<Forecaster>,
+    analytics: Analytics,
     config: Config,
     shutdown: Option<CancellationToken>,
 ) -> Result<()> {
     info!(
-        "Starting worker: poll_interval={}s, max_retries={}, batch_size={}",
-        config.poll_interval_secs, config.max_retry_count, config.batch_size
+        "Starting worker: poll_interval={}s, max_retries={}, batch_size={}, shard={}/{}",
+        config.poll_interval_secs,
+        config.max_retry_count,
+        config.batch_size,
+        config.shard.index(),
+        config.shard.total()
     );
 
-    run_worker_loop(db, calendar, bot, config, shutdown).await
+    let db = db.with_shard(config.shard);
+    run_worker_loop(
+        db, calendar, bot, mailer, chat, sms, push, weather, analytics, config, shutdown,
+    )
+    .await
 }
 
 /// Main worker processing loop
+#[allow(clippy::too_many_arguments)]
 async fn run_worker_loop(
     db: WorkerDb,
     calendar: CalendarService,
     bot: Bot,
+    mailer: Option<Mailer>,
+    chat: Option<ChatSender>,
+    sms: Option<SmsSender>,
+    push: Option<PushSender>,
+    weather: Option<Forecaster>,
+    analytics: Analytics,
     config: Config,
     shutdown: Option<CancellationToken>,
 ) -> Result<()> {
     let poll_interval = tokio::time::Duration::from_secs(config.poll_interval_secs);
+    let telegram = TelegramSender::new(bot);
     let mut last_status_log_time = Instant::now()
         .checked_sub(Duration::from_secs(config.status_log_interval_secs))
         .unwrap_or_else(Instant::now);
@@ -78,8 +154,16 @@ async fn run_worker_loop(
                 continue;
             }
             Ok(batch) => {
-                let jobs = batch.jobs;
                 let mut results = batch.failed_results;
+                let coalesced = digest::coalesce_rsvp_notifications(
+                    &db,
+                    batch.jobs,
+                    ChronoDuration::seconds(config.rsvp_digest_window_secs as i64),
+                    calendar.clock().now(),
+                )
+                .await;
+                let jobs = coalesced.jobs;
+                results.extend(coalesced.results);
                 info!("Processing {} typed jobs concurrently", jobs.len());
 
                 // Pre-fetch events for invite notifications to avoid N+1 queries
@@ -113,12 +197,33 @@ async fn run_worker_loop(
 
                 for job in jobs {
                     let job_id = job.id;
+                    let kind = job.payload.kind();
                     let calendar = calendar.clone();
-                    let bot = bot.clone();
+                    let telegram = telegram.clone();
+                    let mailer = mailer.clone();
+                    let chat = chat.clone();
+                    let sms = sms.clone();
+                    let push = push.clone();
+                    let weather = weather.clone();
                     let config = config.clone();
                     let events_cache = events_cache.clone();
+                    let analytics = analytics.clone();
                     let handle = tokio::spawn(async move {
-                        process_job(&calendar, &bot, &config, job, events_cache).await
+                        let result = process_job(
+                            &calendar,
+                            &telegram,
+                            mailer.as_ref(),
+                            chat.as_ref(),
+                            sms.as_ref(),
+                            push.as_ref(),
+                            weather.as_ref(),
+                            &config,
+                            job,
+                            events_cache,
+                        )
+                        .await;
+                        analytics.record(Metric::outbox_job(kind.as_str(), result.outcome()));
+                        result
                     });
                     tasks.push((job_id, handle));
                 }
@@ -126,6 +231,8 @@ async fn run_worker_loop(
                 // Wait for all concurrent jobs to complete and collect results
                 results.reserve(tasks.len());
                 results.extend(collect_task_results(tasks).await);
+                let merged = digest::fan_out_results(&results, &coalesced.merged);
+                results.extend(merged);
 
                 // Bulk update jobs
                 if let Err(e) = db.bulk_update_jobs(results).await {
@@ -155,9 +262,15 @@ async fn run_worker_loop(
 }
 
 /// Process a single job
+#[allow(clippy::too_many_arguments)]
 pub(crate) async fn process_job(
     calendar: &CalendarService,
-    bot: &Bot,
+    telegram: &TelegramSender,
+    mailer: Option<&Mailer>,
+    chat: Option<&ChatSender>,
+    sms: Option<&SmsSender>,
+    push: Option<&PushSender>,
+    weather: Option<&Forecaster>,
     config: &Config,
     job: db::TypedOutboxMessage,
     events_cache: Arc<HashMap<Uuid, EventView>>,
@@ -169,46 +282,90 @@ pub(crate) async fn process_job(
         job.retry_count
     );
 
-    match processors::process_message(calendar, &job, bot, &events_cache).await {
+    match processors::process_message(
+        calendar,
+        &job,
+        telegram,
+        mailer,
+        chat,
+        sms,
+        push,
+        weather,
+        &events_cache,
+    )
+    .await
+    {
         Ok(()) => {
             // Job succeeded
             info!("Job {} completed successfully", job.id);
             db::JobResult::Completed(job.id)
         }
         Err(e) => {
-            // Job failed
-            warn!("Job {} failed: {}", job.id, e);
-            let error_msg = e.to_string();
-
-            if job.retry_count < config.max_retry_count {
-                // Retry with exponential backoff
-                let backoff_minutes = 2_i64.pow((job.retry_count + 1) as u32);
-                let next_scheduled = Utc::now() + ChronoDuration::minutes(backoff_minutes);
-                info!(
-                    "Rescheduling job {} for retry {} in {} minutes",
-                    job.id,
-                    job.retry_count + 1,
-                    backoff_minutes
-                );
-
-                db::JobResult::Reschedule {
+            // Deferred jobs keep their retry count
+            if let Some(deferred) = e.downcast_ref::<Deferred>() {
+                info!("Job {} {}", job.id, deferred);
+                return db::JobResult::Reschedule {
                     id: job.id,
-                    retry_count: job.retry_count + 1,
-                    scheduled_at: next_scheduled,
-                    error: error_msg,
-                }
-            } else {
-                // Max retries reached, mark as failed
-                error!(
-                    "Job {} exceeded max retries ({}), marking as failed",
-                    job.id, config.max_retry_count
-                );
-
-                db::JobResult::Failed {
+                    retry_count: job.retry_count,
+                    scheduled_at: deferred.until,
+                    error: deferred.to_string(),
+                };
+            }
+            if let Some(rejected) = e.downcast_ref::<Rejected>() {
+                error!("Job {} rejected, not retrying: {}", job.id, rejected);
+                return db::JobResult::Failed {
                     id: job.id,
-                    error: error_msg,
-                }
+                    error: rejected.to_string(),
+                };
             }
+
+            // Job failed
+            warn!("Job {} failed: {}", job.id, e);
+            retry_or_fail(
+                &job,
+                e.to_string(),
+                config.max_retry_count,
+                calendar.clock().now(),
+            )
+        }
+    }
+}
+
+/// Reschedule a failed job with exponential backoff from `now`, or fail it
+/// once it has used up its retries
+fn retry_or_fail(
+    job: &db::TypedOutboxMessage,
+    error_msg: String,
+    max_retry_count: i32,
+    now: DateTime<Utc>,
+) -> db::JobResult {
+    if job.retry_count < max_retry_count {
+        // Retry with exponential backoff
+        let backoff_minutes = 2_i64.pow((job.retry_count + 1) as u32);
+        let next_scheduled = now + ChronoDuration::minutes(backoff_minutes);
+        info!(
+            "Rescheduling job {} for retry {} in {} minutes",
+            job.id,
+            job.retry_count + 1,
+            backoff_minutes
+        );
+
+        db::JobResult::Reschedule {
+            id: job.id,
+            retry_count: job.retry_count + 1,
+            scheduled_at: next_scheduled,
+            error: error_msg,
+        }
+    } else {
+        // Max retries reached, mark as failed
+        error!(
+            "Job {} exceeded max retries ({}), marking as failed",
+            job.id, max_retry_count
+        );
+
+        db::JobResult::Failed {
+            id: job.id,
+            error: error_msg,
         }
     }
 }
@@ -217,13 +374,14 @@ fn invite_notification_event_ids(jobs: &[db::TypedOutboxMessage]) -> Vec<Uuid> {
     jobs.iter()
         .filter_map(|job| match &job.payload {
             OutboxPayload::InviteNotification(payload) => Some(payload.event_id),
+            OutboxPayload::InviteReminder(payload) => Some(payload.event_id),
             _ => None,
         })
         .collect()
 }
 
 async fn collect_task_results(
-    tasks: Vec<(Uuid, tokio::task::JoinHandle<db::JobResult>)>,
+    tasks: Vec<(OutboxMessageId, tokio::task::JoinHandle<db::JobResult>)>,
 ) -> Vec<db::JobResult> {
     let mut results = Vec::with_capacity(tasks.len());
 
@@ -261,6 +419,41 @@ mod tests {
         }
     }
 
+    #[test]
+    fn retry_or_fail_backs_off_from_now_then_gives_up() {
+        let now = "2026-03-01T09:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let mut job = db::TypedOutboxMessage {
+            id: OutboxMessageId::new(Uuid::new_v4()),
+            payload: OutboxPayload::InviteNotification(InviteNotification {
+                event_id: Uuid::new_v4(),
+                target_user_id: 123,
+            }),
+            retry_count: 0,
+            created_at: now,
+        };
+
+        for (retry_count, minutes) in [(0, 2), (1, 4), (4, 32)] {
+            job.retry_count = retry_count;
+            match retry_or_fail(&job, "boom".to_string(), 5, now) {
+                db::JobResult::Reschedule {
+                    retry_count: next,
+                    scheduled_at,
+                    ..
+                } => {
+                    assert_eq!(next, retry_count + 1);
+                    assert_eq!(scheduled_at, now + ChronoDuration::minutes(minutes));
+                }
+                other => panic!("expected a reschedule, got {other:?}"),
+            }
+        }
+
+        job.retry_count = 5;
+        assert!(matches!(
+            retry_or_fail(&job, "boom".to_string(), 5, now),
+            db::JobResult::Failed { .. }
+        ));
+    }
+
     #[test]
     fn test_config_structure() {
         // Verify Config can be constructed
@@ -269,6 +462,9 @@ mod tests {
             max_retry_count: 5,
             batch_size: 10,
             status_log_interval_secs: 60,
+            rsvp_digest_window_secs: 0,
+            shard: OutboxShard::default(),
+            retention: RetentionPolicy::default(),
         };
 
         assert_eq!(cfg.poll_interval_secs, 10);
@@ -279,12 +475,13 @@ mod tests {
     fn invite_notification_event_ids_uses_typed_payloads() {
         let event_id = Uuid::new_v4();
         let job = db::TypedOutboxMessage {
-            id: Uuid::new_v4(),
+            id: OutboxMessageId::new(Uuid::new_v4()),
             payload: OutboxPayload::InviteNotification(InviteNotification {
                 event_id,
                 target_user_id: 123,
             }),
             retry_count: 0,
+            created_at: Utc::now(),
         };
 
         assert_eq!(invite_notification_event_ids(&[job]), vec![event_id]);
@@ -292,8 +489,8 @@ mod tests {
 
     #[tokio::test]
     async fn collect_task_results_marks_join_failures_failed() {
-        let ok_id = Uuid::new_v4();
-        let panic_id = Uuid::new_v4();
+        let ok_id = OutboxMessageId::new(Uuid::new_v4());
+        let panic_id = OutboxMessageId::new(Uuid::new_v4());
         let tasks = vec![
             (
                 ok_id,
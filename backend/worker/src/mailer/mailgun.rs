@@ -0,0 +1,154 @@
+//! Mailgun HTTP API backend
+//!
+//! Posts the finished MIME message to `messages.mime`, so Mailgun sends it
+//! exactly as composed.
+
+use anyhow::{Context, Result};
+use lettre::Message;
+use reqwest::multipart::{Form, Part};
+use reqwest::{StatusCode, header};
+use std::env;
+use std::time::Duration;
+
+use super::{EmailBackend, SendError, SendFuture};
+
+const REQUEST_TIMEOUT_SECS: u64 = 30;
+
+/// Mailgun sending domain and API key
+#[derive(Clone)]
+pub struct MailgunConfig {
+    pub domain: String,
+    pub api_key: String,
+    /// `https://api.mailgun.net`, or `https://api.eu.mailgun.net` for EU domains
+    pub api_base: String,
+}
+
+impl std::fmt::Debug for MailgunConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MailgunConfig")
+            .field("domain", &self.domain)
+            .field("api_base", &self.api_base)
+            .finish_non_exhaustive()
+    }
+}
+
+impl MailgunConfig {
+    pub(super) fn from_env() -> Result<Self> {
+        Ok(Self {
+            domain: env::var("MAILGUN_DOMAIN")
+                .context("MAILGUN_DOMAIN must be set with EMAIL_BACKEND=mailgun")?,
+            api_key: env::var("MAILGUN_API_KEY")
+                .context("MAILGUN_API_KEY must be set with EMAIL_BACKEND=mailgun")?,
+            api_base: env::var("MAILGUN_API_BASE")
+                .unwrap_or_else(|_| "https://api.mailgun.net".to_string())
+                .trim_end_matches('/')
+                .to_string(),
+        })
+    }
+}
+
+pub(super) struct MailgunBackend {
+    http: reqwest::Client,
+    config: MailgunConfig,
+}
+
+impl MailgunBackend {
+    pub(super) fn new(config: &MailgunConfig) -> Result<Self> {
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS))
+            .user_agent(concat!("Televent/", env!("CARGO_PKG_VERSION")))
+            .build()?;
+
+        Ok(Self {
+            http,
+            config: config.clone(),
+        })
+    }
+
+    async fn send_mime(&self, message: Message) -> Result<(), SendError> {
+        let recipients = message
+            .envelope()
+            .to()
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(",");
+        let form = Form::new().text("to", recipients).part(
+            "message",
+            Part::bytes(message.formatted()).file_name("message.mime"),
+        );
+
+        let response = self
+            .http
+            .post(format!(
+                "{}/v3/{}/messages.mime",
+                self.config.api_base, self.config.domain
+            ))
+            .basic_auth("api", Some(&self.config.api_key))
+            .multipart(form)
+            .send()
+            .await
+            .map_err(|e| SendError::Transient(format!("Mailgun request failed: {e}")))?;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(());
+        }
+
+        let retry_after = response
+            .headers()
+            .get(header::RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse().ok())
+            .map(Duration::from_secs);
+        let body = response.text().await.unwrap_or_default();
+        Err(classify(status, retry_after, body))
+    }
+}
+
+impl EmailBackend for MailgunBackend {
+    fn send(&self, message: Message) -> SendFuture<'_> {
+        Box::pin(self.send_mime(message))
+    }
+}
+
+/// 429 reschedules, other 4xx answers refuse the message itself, except
+/// credential and domain problems, which wait for the operator like outages.
+fn classify(status: StatusCode, retry_after: Option<Duration>, body: String) -> SendError {
+    let message = format!("Mailgun returned {status}: {body}");
+    match status {
+        StatusCode::TOO_MANY_REQUESTS => SendError::RateLimited {
+            retry_after,
+            message,
+        },
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN | StatusCode::NOT_FOUND => {
+            SendError::Transient(message)
+        }
+        _ if status.is_client_error() => SendError::Permanent(message),
+        _ => SendError::Transient(message),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_mailgun_errors() {
+        assert!(matches!(
+            classify(StatusCode::TOO_MANY_REQUESTS, Some(Duration::from_secs(30)), String::new()),
+            SendError::RateLimited { retry_after: Some(delay), .. } if delay.as_secs() == 30
+        ));
+        assert!(matches!(
+            classify(StatusCode::BAD_REQUEST, None, String::new()),
+            SendError::Permanent(_)
+        ));
+        assert!(matches!(
+            classify(StatusCode::UNAUTHORIZED, None, String::new()),
+            SendError::Transient(_)
+        ));
+        assert!(matches!(
+            classify(StatusCode::BAD_GATEWAY, None, String::new()),
+            SendError::Transient(_)
+        ));
+    }
+}
@@ -0,0 +1,674 @@
+//! Outgoing email
+//!
+//! Public event signups are confirmed by email, and external guests get an
+//! iTIP update when an event moves or changes place and an iTIP cancellation
+//! when it is called off. Users themselves get the code confirming their own
+//! address and, once confirmed, `user_email` copies of the notices their
+//! preferences send to email. Delivery is off unless
+//! `ENABLE_EXTERNAL_EMAIL` is set; the API then refuses email signups so
+//! nobody waits for a message that never comes.
+//!
+//! Messages go out through an [`EmailBackend`] picked by `EMAIL_BACKEND`:
+//! SMTP, or the HTTPS APIs of Amazon SES and Mailgun behind the `email-ses`
+//! and `email-mailgun` features.
+//!
+//! Every send first checks the suppression list and takes one of the
+//! recipient's hourly sends. Emails carry a signed unsubscribe link, since
+//! anyone can type an address into a signup form.
+
+#[cfg(feature = "email-mailgun")]
+mod mailgun;
+#[cfg(feature = "email-ses")]
+mod ses;
+mod smtp;
+
+use anyhow::{Context, Result, bail};
+use chrono::Utc;
+use lettre::Message;
+use lettre::message::header::{ContentType, HeaderName, HeaderValue};
+use lettre::message::{Mailbox, MultiPart, SinglePart};
+use std::env;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+use televent_application::{
+    ChatNotice, EMAIL_CODE_TTL_MINUTES, EmailSendPermit, EmailService, EmailVerificationKey,
+    ItipChange, NotificationService, UnsubscribeKey, UserId,
+};
+use televent_domain::{UserEmailContent, UserEmailMessage};
+use tracing::info;
+use uuid::Uuid;
+
+use crate::{Deferred, Rejected};
+
+#[cfg(feature = "email-mailgun")]
+pub use mailgun::MailgunConfig;
+#[cfg(feature = "email-ses")]
+pub use ses::SesConfig;
+pub use smtp::{SmtpConfig, SmtpTls};
+
+/// Wait applied when a provider rate-limits without saying for how long
+const DEFAULT_RATE_LIMIT_DELAY: Duration = Duration::from_secs(60);
+
+/// Why a backend could not hand over a message
+#[derive(Debug, thiserror::Error)]
+pub enum SendError {
+    /// Worth retrying later: network trouble, provider outages, bad credentials
+    #[error("{0}")]
+    Transient(String),
+    /// The provider refused this message for good, e.g. an invalid recipient
+    #[error("{0}")]
+    Permanent(String),
+    /// The provider asked us to slow down
+    #[error("rate limited: {message}")]
+    RateLimited {
+        retry_after: Option<Duration>,
+        message: String,
+    },
+}
+
+impl SendError {
+    /// Map onto the worker's retry policy: permanent refusals fail the job at
+    /// once, rate limits reschedule it without using up a retry, and anything
+    /// else takes the usual backoff. `channel` names the medium in errors.
+    pub(crate) fn into_job_error(self, channel: &str) -> anyhow::Error {
+        match self {
+            Self::Transient(message) => anyhow::anyhow!("{channel} delivery failed: {message}"),
+            Self::Permanent(message) => Rejected(format!("{channel} rejected: {message}")).into(),
+            Self::RateLimited {
+                retry_after,
+                message,
+            } => {
+                let delay = retry_after.unwrap_or(DEFAULT_RATE_LIMIT_DELAY);
+                Deferred {
+                    until: Utc::now()
+                        + chrono::Duration::from_std(delay)
+                            .unwrap_or_else(|_| chrono::Duration::minutes(1)),
+                    reason: format!("{channel} provider rate limit: {message}"),
+                }
+                .into()
+            }
+        }
+    }
+}
+
+/// Future returned by [`EmailBackend::send`]
+pub type SendFuture<'a> = Pin<Box<dyn Future<Output = Result<(), SendError>> + Send + 'a>>;
+
+/// Something that can deliver a finished message
+pub trait EmailBackend: Send + Sync {
+    fn send(&self, message: Message) -> SendFuture<'_>;
+}
+
+/// Delivery backend and its settings
+#[derive(Debug, Clone)]
+pub enum EmailBackendConfig {
+    Smtp(SmtpConfig),
+    #[cfg(feature = "email-ses")]
+    Ses(SesConfig),
+    #[cfg(feature = "email-mailgun")]
+    Mailgun(MailgunConfig),
+}
+
+impl EmailBackendConfig {
+    fn from_env() -> Result<Self> {
+        match env::var("EMAIL_BACKEND")
+            .unwrap_or_else(|_| "smtp".to_string())
+            .to_ascii_lowercase()
+            .as_str()
+        {
+            "smtp" => Ok(Self::Smtp(SmtpConfig::from_env()?)),
+            #[cfg(feature = "email-ses")]
+            "ses" => Ok(Self::Ses(SesConfig::from_env()?)),
+            #[cfg(feature = "email-mailgun")]
+            "mailgun" => Ok(Self::Mailgun(MailgunConfig::from_env()?)),
+            #[cfg(not(feature = "email-ses"))]
+            "ses" => bail!("EMAIL_BACKEND=ses needs a build with the email-ses feature"),
+            #[cfg(not(feature = "email-mailgun"))]
+            "mailgun" => {
+                bail!("EMAIL_BACKEND=mailgun needs a build with the email-mailgun feature")
+            }
+            other => bail!("EMAIL_BACKEND must be smtp, ses or mailgun, got {other}"),
+        }
+    }
+
+    fn build(&self) -> Result<Arc<dyn EmailBackend>> {
+        Ok(match self {
+            Self::Smtp(config) => Arc::new(smtp::SmtpBackend::new(config)?),
+            #[cfg(feature = "email-ses")]
+            Self::Ses(config) => Arc::new(ses::SesBackend::new(config)?),
+            #[cfg(feature = "email-mailgun")]
+            Self::Mailgun(config) => Arc::new(mailgun::MailgunBackend::new(config)?),
+        })
+    }
+}
+
+/// Delivery backend and sender settings
+#[derive(Debug, Clone)]
+pub struct EmailConfig {
+    pub backend: EmailBackendConfig,
+    /// `From` address, e.g. `Televent <noreply@televent.app>`
+    pub from: String,
+    /// Origin that emailed links point to
+    pub public_base_url: String,
+    /// Most emails one address gets per hour
+    pub hourly_cap_per_recipient: u32,
+}
+
+impl EmailConfig {
+    /// Load configuration from environment variables
+    ///
+    /// Returns `None` unless `ENABLE_EXTERNAL_EMAIL` is true.
+    pub fn from_env() -> Result<Option<Self>> {
+        let enabled = env::var("ENABLE_EXTERNAL_EMAIL").is_ok_and(|value| {
+            matches!(
+                value.to_ascii_lowercase().as_str(),
+                "1" | "true" | "yes" | "on"
+            )
+        });
+        if !enabled {
+            return Ok(None);
+        }
+
+        let hourly_cap_per_recipient = env::var("EMAIL_HOURLY_CAP_PER_RECIPIENT")
+            .unwrap_or_else(|_| "5".to_string())
+            .parse()
+            .context("EMAIL_HOURLY_CAP_PER_RECIPIENT must be a valid integer")?;
+        if hourly_cap_per_recipient == 0 {
+            bail!("EMAIL_HOURLY_CAP_PER_RECIPIENT must be at least 1");
+        }
+
+        Ok(Some(Self {
+            backend: EmailBackendConfig::from_env()?,
+            // SMTP_FROM predates the API backends and is still honored
+            from: env::var("EMAIL_FROM")
+                .or_else(|_| env::var("SMTP_FROM"))
+                .unwrap_or_else(|_| "noreply@televent.app".to_string()),
+            public_base_url: env::var("PUBLIC_BASE_URL")
+                .unwrap_or_else(|_| "http://localhost:3000".to_string()),
+            hourly_cap_per_recipient,
+        }))
+    }
+}
+
+/// What became of an email handed to the mailer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Delivery {
+    Sent,
+    /// The recipient is on the suppression list; nothing was sent
+    Suppressed,
+    /// The user's address was removed, replaced or already confirmed since
+    /// the message was queued; nothing was sent
+    Stale,
+}
+
+/// Composes emails and hands them to the configured backend
+#[derive(Clone)]
+pub struct Mailer {
+    backend: Arc<dyn EmailBackend>,
+    from: Mailbox,
+    public_base_url: String,
+    email: EmailService,
+    unsubscribe_key: UnsubscribeKey,
+    notifications: NotificationService,
+    verification_key: EmailVerificationKey,
+    hourly_cap_per_recipient: u32,
+}
+
+impl Mailer {
+    pub fn new(
+        config: &EmailConfig,
+        email: EmailService,
+        unsubscribe_key: UnsubscribeKey,
+        notifications: NotificationService,
+        verification_key: EmailVerificationKey,
+    ) -> Result<Self> {
+        Ok(Self {
+            backend: config.backend.build()?,
+            from: config
+                .from
+                .parse()
+                .context("EMAIL_FROM is not a valid address")?,
+            public_base_url: config.public_base_url.trim_end_matches('/').to_string(),
+            email,
+            unsubscribe_key,
+            notifications,
+            verification_key,
+            hourly_cap_per_recipient: config.hourly_cap_per_recipient,
+        })
+    }
+
+    /// Queue an email copy of `notice`, rendered for outbox message
+    /// `source_id`, if the user wants one
+    pub(crate) async fn copy(
+        &self,
+        user_id: i64,
+        source_id: Uuid,
+        notice: &ChatNotice,
+    ) -> Result<()> {
+        let queued = self
+            .notifications
+            .queue_email_copy(UserId::new(user_id), source_id, notice)
+            .await
+            .context("Failed to queue email copy")?;
+        if queued {
+            info!("Queued an email copy of message {}", source_id);
+        }
+        Ok(())
+    }
+
+    /// Email a user at their own address.
+    ///
+    /// Notice copies only go to a confirmed address. Fails with [`Deferred`]
+    /// while the recipient's hourly cap is used up.
+    pub(crate) async fn send_user_email(&self, payload: &UserEmailMessage) -> Result<Delivery> {
+        let user_id = UserId::new(payload.user_id);
+        match &payload.content {
+            UserEmailContent::Notice { title, lines, .. } => {
+                let Some(to) = self.notifications.verified_email(user_id).await? else {
+                    return Ok(Delivery::Stale);
+                };
+                if !self.reserve_send(&to).await? {
+                    return Ok(Delivery::Suppressed);
+                }
+                let message = notice_message(
+                    self.from.clone(),
+                    &to,
+                    title,
+                    lines,
+                    &self.unsubscribe_link(&to),
+                )?;
+                self.send(message).await
+            }
+            UserEmailContent::VerificationCode { email } => {
+                let Some(code) = self
+                    .notifications
+                    .issue_email_verification_code(user_id, email)
+                    .await?
+                else {
+                    return Ok(Delivery::Stale);
+                };
+                if !self.reserve_send(email).await? {
+                    return Ok(Delivery::Suppressed);
+                }
+                let links = EmailLinks {
+                    action: format!(
+                        "{}/verify-email/{}",
+                        self.public_base_url,
+                        self.verification_key.token(user_id, &code)
+                    ),
+                    unsubscribe: self.unsubscribe_link(email),
+                };
+                let message = verification_message(self.from.clone(), email, &code, &links)?;
+                self.send(message).await
+            }
+        }
+    }
+
+    /// Email the confirmation link of a public event signup.
+    ///
+    /// Fails with [`Deferred`] while the recipient's hourly cap is used up.
+    pub(crate) async fn send_signup_confirmation(
+        &self,
+        recipient_email: &str,
+        event_summary: &str,
+        confirmation_path: &str,
+    ) -> Result<Delivery> {
+        if !self.reserve_send(recipient_email).await? {
+            return Ok(Delivery::Suppressed);
+        }
+
+        let links = EmailLinks {
+            action: format!("{}{confirmation_path}", self.public_base_url),
+            unsubscribe: self.unsubscribe_link(recipient_email),
+        };
+        let message =
+            signup_confirmation_message(self.from.clone(), recipient_email, event_summary, &links)?;
+        self.send(message).await
+    }
+
+    /// Email an iTIP message so the guest's calendar picks up `change`.
+    ///
+    /// Fails with [`Deferred`] while the recipient's hourly cap is used up.
+    pub(crate) async fn send_itip(
+        &self,
+        recipient_email: &str,
+        change: ItipChange,
+        event_summary: &str,
+        itip: &str,
+    ) -> Result<Delivery> {
+        if !self.reserve_send(recipient_email).await? {
+            return Ok(Delivery::Suppressed);
+        }
+
+        let message = itip_message(
+            self.from.clone(),
+            recipient_email,
+            change,
+            event_summary,
+            itip,
+            &self.unsubscribe_link(recipient_email),
+        )?;
+        self.send(message).await
+    }
+
+    /// Take one of the recipient's hourly sends; `false` when suppressed
+    async fn reserve_send(&self, recipient_email: &str) -> Result<bool> {
+        match self
+            .email
+            .reserve_send(recipient_email, self.hourly_cap_per_recipient)
+            .await?
+        {
+            EmailSendPermit::Granted => Ok(true),
+            EmailSendPermit::Suppressed => Ok(false),
+            EmailSendPermit::Throttled(until) => Err(Deferred {
+                until,
+                reason: "hourly email cap for the recipient reached".to_string(),
+            }
+            .into()),
+        }
+    }
+
+    fn unsubscribe_link(&self, recipient_email: &str) -> String {
+        format!(
+            "{}/unsubscribe/{}",
+            self.public_base_url,
+            self.unsubscribe_key.token(recipient_email)
+        )
+    }
+
+    async fn send(&self, message: Message) -> Result<Delivery> {
+        self.backend
+            .send(message)
+            .await
+            .map_err(|e| e.into_job_error("Email"))?;
+        Ok(Delivery::Sent)
+    }
+}
+
+/// Absolute links placed in an email
+struct EmailLinks {
+    action: String,
+    unsubscribe: String,
+}
+
+fn signup_confirmation_message(
+    from: Mailbox,
+    recipient_email: &str,
+    event_summary: &str,
+    links: &EmailLinks,
+) -> Result<Message> {
+    Message::builder()
+        .from(from)
+        .to(recipient_email
+            .parse()
+            .context("Invalid recipient address")?)
+        .subject(format!("Confirm your spot at {event_summary}"))
+        .header(ContentType::TEXT_PLAIN)
+        .raw_header(HeaderValue::new(
+            HeaderName::new_from_ascii_str("List-Unsubscribe"),
+            format!("<{}>", links.unsubscribe),
+        ))
+        .raw_header(HeaderValue::new(
+            HeaderName::new_from_ascii_str("List-Unsubscribe-Post"),
+            "List-Unsubscribe=One-Click".to_string(),
+        ))
+        .body(format!(
+            "Someone, hopefully you, signed up for \"{event_summary}\" with this address.\n\n\
+             Open this link to confirm your spot:\n{}\n\n\
+             If this wasn't you, ignore this email and nothing will happen.\n\n\
+             To stop all email from Televent to this address, open:\n{}\n",
+            links.action, links.unsubscribe
+        ))
+        .context("Failed to build signup confirmation email")
+}
+
+fn verification_message(
+    from: Mailbox,
+    recipient_email: &str,
+    code: &str,
+    links: &EmailLinks,
+) -> Result<Message> {
+    Message::builder()
+        .from(from)
+        .to(recipient_email
+            .parse()
+            .context("Invalid recipient address")?)
+        .subject(format!("Your Televent code is {code}"))
+        .header(ContentType::TEXT_PLAIN)
+        .raw_header(HeaderValue::new(
+            HeaderName::new_from_ascii_str("List-Unsubscribe"),
+            format!("<{}>", links.unsubscribe),
+        ))
+        .raw_header(HeaderValue::new(
+            HeaderName::new_from_ascii_str("List-Unsubscribe-Post"),
+            "List-Unsubscribe=One-Click".to_string(),
+        ))
+        .body(format!(
+            "Someone, hopefully you, added this address to a Televent account.\n\n\
+             Send /email verify {code} to the bot, or open this link to confirm it:\n{}\n\n\
+             The code expires in {EMAIL_CODE_TTL_MINUTES} minutes. If this wasn't you, \
+             ignore this email and nothing will happen.\n\n\
+             To stop all email from Televent to this address, open:\n{}\n",
+            links.action, links.unsubscribe
+        ))
+        .context("Failed to build verification email")
+}
+
+fn notice_message(
+    from: Mailbox,
+    recipient_email: &str,
+    title: &str,
+    lines: &[String],
+    unsubscribe: &str,
+) -> Result<Message> {
+    let mut body = String::new();
+    for line in lines {
+        body.push_str(line);
+        body.push('\n');
+    }
+    Message::builder()
+        .from(from)
+        .to(recipient_email
+            .parse()
+            .context("Invalid recipient address")?)
+        .subject(title)
+        .header(ContentType::TEXT_PLAIN)
+        .raw_header(HeaderValue::new(
+            HeaderName::new_from_ascii_str("List-Unsubscribe"),
+            format!("<{unsubscribe}>"),
+        ))
+        .raw_header(HeaderValue::new(
+            HeaderName::new_from_ascii_str("List-Unsubscribe-Post"),
+            "List-Unsubscribe=One-Click".to_string(),
+        ))
+        .body(format!(
+            "{body}\nYou get this because email is on in your Televent notification \
+             settings.\n\nTo stop all email from Televent to this address, open:\n{unsubscribe}\n"
+        ))
+        .context("Failed to build notice email")
+}
+
+fn itip_message(
+    from: Mailbox,
+    recipient_email: &str,
+    change: ItipChange,
+    event_summary: &str,
+    itip: &str,
+    unsubscribe: &str,
+) -> Result<Message> {
+    let (subject, what_happened) = match change {
+        ItipChange::Rescheduled => (
+            "Rescheduled",
+            "has moved to a new time. Please let the organizer know if you can still make it",
+        ),
+        ItipChange::Updated => ("Updated", "has been updated by the organizer"),
+        ItipChange::Cancelled => ("Cancelled", "has been cancelled by the organizer"),
+    };
+    let calendar_type = ContentType::parse(&format!(
+        "text/calendar; charset=utf-8; method={}",
+        change.method()
+    ))
+    .context("Invalid calendar content type")?;
+    Message::builder()
+        .from(from)
+        .to(recipient_email
+            .parse()
+            .context("Invalid recipient address")?)
+        .subject(format!("{subject}: {event_summary}"))
+        .raw_header(HeaderValue::new(
+            HeaderName::new_from_ascii_str("List-Unsubscribe"),
+            format!("<{unsubscribe}>"),
+        ))
+        .raw_header(HeaderValue::new(
+            HeaderName::new_from_ascii_str("List-Unsubscribe-Post"),
+            "List-Unsubscribe=One-Click".to_string(),
+        ))
+        .multipart(
+            MultiPart::alternative()
+                .singlepart(SinglePart::plain(format!(
+                    "\"{event_summary}\" {what_happened}.\n\n\
+                     Your calendar app applies the change when it reads this email.\n\n\
+                     To stop all email from Televent to this address, open:\n{unsubscribe}\n"
+                )))
+                .singlepart(
+                    SinglePart::builder()
+                        .header(calendar_type)
+                        .body(itip.to_string()),
+                ),
+        )
+        .context("Failed to build calendar email")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn links() -> EmailLinks {
+        EmailLinks {
+            action: "https://televent.app/e/abc/confirm?token=xyz".to_string(),
+            unsubscribe: "https://televent.app/unsubscribe/Z3Vlc3Q.sig".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_signup_confirmation_message_carries_link() {
+        let message = signup_confirmation_message(
+            "Televent <noreply@televent.app>".parse().unwrap(),
+            "guest@example.com",
+            "Open Meetup",
+            &links(),
+        )
+        .unwrap();
+        let raw = String::from_utf8(message.formatted()).unwrap();
+
+        assert!(raw.contains("To: guest@example.com"));
+        assert!(raw.contains("Subject: Confirm your spot at Open Meetup"));
+        assert!(raw.contains("https://televent.app/e/abc/confirm?token=xyz"));
+        assert!(raw.contains("List-Unsubscribe: <https://televent.app/unsubscribe/Z3Vlc3Q.sig>"));
+        assert!(raw.contains("List-Unsubscribe-Post: List-Unsubscribe=One-Click"));
+    }
+
+    #[test]
+    fn test_itip_message_follows_change() {
+        let itip = "BEGIN:VCALENDAR\r\nMETHOD:CANCEL\r\nEND:VCALENDAR\r\n";
+        let message = itip_message(
+            "Televent <noreply@televent.app>".parse().unwrap(),
+            "guest@example.com",
+            ItipChange::Cancelled,
+            "Open Meetup",
+            itip,
+            "https://televent.app/unsubscribe/Z3Vlc3Q.sig",
+        )
+        .unwrap();
+        let raw = String::from_utf8(message.formatted()).unwrap();
+
+        assert!(raw.contains("Subject: Cancelled: Open Meetup"));
+        assert!(raw.contains("multipart/alternative"));
+        assert!(raw.contains("text/calendar; charset=utf-8; method=CANCEL"));
+        assert!(raw.contains("METHOD:CANCEL"));
+
+        let moved = itip_message(
+            "Televent <noreply@televent.app>".parse().unwrap(),
+            "guest@example.com",
+            ItipChange::Rescheduled,
+            "Open Meetup",
+            "BEGIN:VCALENDAR\r\nMETHOD:REQUEST\r\nEND:VCALENDAR\r\n",
+            "https://televent.app/unsubscribe/Z3Vlc3Q.sig",
+        )
+        .unwrap();
+        let raw = String::from_utf8(moved.formatted()).unwrap();
+        assert!(raw.contains("Subject: Rescheduled: Open Meetup"));
+        assert!(raw.contains("text/calendar; charset=utf-8; method=REQUEST"));
+    }
+
+    #[test]
+    fn test_verification_message_carries_code_and_link() {
+        let message = verification_message(
+            "Televent <noreply@televent.app>".parse().unwrap(),
+            "ada@example.com",
+            "012345",
+            &EmailLinks {
+                action: "https://televent.app/verify-email/42.012345.sig".to_string(),
+                unsubscribe: "https://televent.app/unsubscribe/Z3Vlc3Q.sig".to_string(),
+            },
+        )
+        .unwrap();
+        let raw = String::from_utf8(message.formatted()).unwrap();
+
+        assert!(raw.contains("To: ada@example.com"));
+        assert!(raw.contains("Subject: Your Televent code is 012345"));
+        assert!(raw.contains("/email verify 012345"));
+        assert!(raw.contains("https://televent.app/verify-email/42.012345.sig"));
+        assert!(raw.contains("List-Unsubscribe: <https://televent.app/unsubscribe/Z3Vlc3Q.sig>"));
+    }
+
+    #[test]
+    fn test_notice_message_lists_lines() {
+        let message = notice_message(
+            "Televent <noreply@televent.app>".parse().unwrap(),
+            "ada@example.com",
+            "Cancelled: Standup",
+            &["The organizer called this event off.".to_string()],
+            "https://televent.app/unsubscribe/Z3Vlc3Q.sig",
+        )
+        .unwrap();
+        let raw = String::from_utf8(message.formatted()).unwrap();
+
+        assert!(raw.contains("Subject: Cancelled: Standup"));
+        assert!(raw.contains("The organizer called this event off."));
+        assert!(raw.contains("List-Unsubscribe-Post: List-Unsubscribe=One-Click"));
+    }
+
+    #[test]
+    fn test_send_errors_follow_retry_policy() {
+        let rejected = SendError::Permanent("550 no such user".to_string()).into_job_error("Email");
+        assert!(rejected.downcast_ref::<Rejected>().is_some());
+
+        let limited = SendError::RateLimited {
+            retry_after: Some(Duration::from_secs(120)),
+            message: "slow down".to_string(),
+        }
+        .into_job_error("Email");
+        let deferred = limited.downcast_ref::<Deferred>().unwrap();
+        assert!(deferred.until > Utc::now() + chrono::Duration::seconds(100));
+
+        let transient = SendError::Transient("timeout".to_string()).into_job_error("Email");
+        assert!(transient.downcast_ref::<Rejected>().is_none());
+        assert!(transient.downcast_ref::<Deferred>().is_none());
+    }
+
+    #[test]
+    fn test_signup_confirmation_message_rejects_bad_recipient() {
+        assert!(
+            signup_confirmation_message(
+                "noreply@televent.app".parse().unwrap(),
+                "not an address",
+                "Open Meetup",
+                &links(),
+            )
+            .is_err()
+        );
+    }
+}
@@ -0,0 +1,263 @@
+//! Amazon SES v2 API backend
+//!
+//! Sends the finished MIME message through `SendEmail` with raw content, so
+//! headers such as `List-Unsubscribe` survive unchanged. Requests are signed
+//! with AWS Signature Version 4.
+
+use anyhow::{Context, Result};
+use base64::{Engine, engine::general_purpose::STANDARD};
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use lettre::Message;
+use reqwest::{StatusCode, header};
+use sha2::{Digest, Sha256};
+use std::env;
+use std::time::Duration;
+
+use super::{EmailBackend, SendError, SendFuture};
+
+type HmacSha256 = Hmac<Sha256>;
+
+const REQUEST_TIMEOUT_SECS: u64 = 30;
+const SEND_EMAIL_PATH: &str = "/v2/email/outbound-emails";
+
+/// SES region and credentials
+#[derive(Clone)]
+pub struct SesConfig {
+    pub region: String,
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    /// Set when the credentials are temporary
+    pub session_token: Option<String>,
+}
+
+impl std::fmt::Debug for SesConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SesConfig")
+            .field("region", &self.region)
+            .field("access_key_id", &self.access_key_id)
+            .finish_non_exhaustive()
+    }
+}
+
+impl SesConfig {
+    pub(super) fn from_env() -> Result<Self> {
+        Ok(Self {
+            region: env::var("AWS_REGION")
+                .context("AWS_REGION must be set with EMAIL_BACKEND=ses")?,
+            access_key_id: env::var("AWS_ACCESS_KEY_ID")
+                .context("AWS_ACCESS_KEY_ID must be set with EMAIL_BACKEND=ses")?,
+            secret_access_key: env::var("AWS_SECRET_ACCESS_KEY")
+                .context("AWS_SECRET_ACCESS_KEY must be set with EMAIL_BACKEND=ses")?,
+            session_token: env::var("AWS_SESSION_TOKEN").ok().filter(|v| !v.is_empty()),
+        })
+    }
+}
+
+pub(super) struct SesBackend {
+    http: reqwest::Client,
+    config: SesConfig,
+    host: String,
+}
+
+impl SesBackend {
+    pub(super) fn new(config: &SesConfig) -> Result<Self> {
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS))
+            .user_agent(concat!("Televent/", env!("CARGO_PKG_VERSION")))
+            .build()?;
+
+        Ok(Self {
+            http,
+            host: format!("email.{}.amazonaws.com", config.region),
+            config: config.clone(),
+        })
+    }
+
+    async fn send_raw(&self, message: Message) -> Result<(), SendError> {
+        let body = serde_json::json!({
+            "Content": { "Raw": { "Data": STANDARD.encode(message.formatted()) } }
+        })
+        .to_string();
+        let signed = sign_request(&self.config, &self.host, &body, Utc::now());
+
+        let mut request = self
+            .http
+            .post(format!("https://{}{SEND_EMAIL_PATH}", self.host))
+            .header(header::CONTENT_TYPE, "application/json")
+            .header("x-amz-date", &signed.amz_date)
+            .header(header::AUTHORIZATION, signed.authorization);
+        if let Some(token) = &self.config.session_token {
+            request = request.header("x-amz-security-token", token);
+        }
+
+        let response = request
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| SendError::Transient(format!("SES request failed: {e}")))?;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(());
+        }
+
+        let error_type = response
+            .headers()
+            .get("x-amzn-ErrorType")
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.split(':').next().unwrap_or(value).to_string())
+            .unwrap_or_default();
+        let message = response.text().await.unwrap_or_default();
+        Err(classify(status, &error_type, message))
+    }
+}
+
+impl EmailBackend for SesBackend {
+    fn send(&self, message: Message) -> SendFuture<'_> {
+        Box::pin(self.send_raw(message))
+    }
+}
+
+/// Throttling reschedules, a rejected message fails, and everything else
+/// (outages, paused sending, bad credentials) waits for the operator.
+fn classify(status: StatusCode, error_type: &str, body: String) -> SendError {
+    let message = format!("SES returned {status} {error_type}: {body}");
+    match error_type {
+        "TooManyRequestsException" | "LimitExceededException" | "ThrottlingException" => {
+            SendError::RateLimited {
+                retry_after: None,
+                message,
+            }
+        }
+        "MessageRejected" | "BadRequestException" => SendError::Permanent(message),
+        _ if status == StatusCode::TOO_MANY_REQUESTS => SendError::RateLimited {
+            retry_after: None,
+            message,
+        },
+        _ => SendError::Transient(message),
+    }
+}
+
+struct SignedHeaders {
+    amz_date: String,
+    authorization: String,
+}
+
+/// AWS Signature Version 4 for a JSON POST to the SES v2 API
+fn sign_request(config: &SesConfig, host: &str, body: &str, now: DateTime<Utc>) -> SignedHeaders {
+    let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let date = now.format("%Y%m%d").to_string();
+    let scope = format!("{date}/{}/ses/aws4_request", config.region);
+
+    let mut headers = vec![
+        ("content-type", "application/json".to_string()),
+        ("host", host.to_string()),
+        ("x-amz-date", amz_date.clone()),
+    ];
+    if let Some(token) = &config.session_token {
+        headers.push(("x-amz-security-token", token.clone()));
+    }
+    let canonical_headers: String = headers
+        .iter()
+        .map(|(name, value)| format!("{name}:{value}\n"))
+        .collect();
+    let signed_headers = headers
+        .iter()
+        .map(|(name, _)| *name)
+        .collect::<Vec<_>>()
+        .join(";");
+
+    let canonical_request = format!(
+        "POST\n{SEND_EMAIL_PATH}\n\n{canonical_headers}\n{signed_headers}\n{:x}",
+        Sha256::digest(body.as_bytes())
+    );
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{amz_date}\n{scope}\n{:x}",
+        Sha256::digest(canonical_request.as_bytes())
+    );
+    let key = signing_key(&config.secret_access_key, &date, &config.region, "ses");
+    let signature = hmac(&key, string_to_sign.as_bytes())
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect::<String>();
+
+    SignedHeaders {
+        authorization: format!(
+            "AWS4-HMAC-SHA256 Credential={}/{scope}, SignedHeaders={signed_headers}, Signature={signature}",
+            config.access_key_id
+        ),
+        amz_date,
+    }
+}
+
+fn signing_key(secret: &str, date: &str, region: &str, service: &str) -> Vec<u8> {
+    let key = hmac(format!("AWS4{secret}").as_bytes(), date.as_bytes());
+    let key = hmac(&key, region.as_bytes());
+    let key = hmac(&key, service.as_bytes());
+    hmac(&key, b"aws4_request")
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC can take any key length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signing_key_matches_aws_example() {
+        // Example from the AWS Signature Version 4 documentation
+        let key = signing_key(
+            "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY",
+            "20120215",
+            "us-east-1",
+            "iam",
+        );
+        let hex = key.iter().map(|b| format!("{b:02x}")).collect::<String>();
+        assert_eq!(
+            hex,
+            "f4780e2d9f65fa895f9c67b32ce1baf0b0d8a43505a000a1a9e090d414db404d"
+        );
+    }
+
+    #[test]
+    fn test_sign_request_scopes_credentials() {
+        let config = SesConfig {
+            region: "eu-west-1".to_string(),
+            access_key_id: "AKIDEXAMPLE".to_string(),
+            secret_access_key: "secret".to_string(),
+            session_token: Some("session".to_string()),
+        };
+        let now = "2026-10-17T12:00:00Z".parse().unwrap();
+        let signed = sign_request(&config, "email.eu-west-1.amazonaws.com", "{}", now);
+
+        assert_eq!(signed.amz_date, "20261017T120000Z");
+        assert!(signed.authorization.starts_with(
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20261017/eu-west-1/ses/aws4_request, \
+             SignedHeaders=content-type;host;x-amz-date;x-amz-security-token, Signature="
+        ));
+    }
+
+    #[test]
+    fn test_classify_ses_errors() {
+        assert!(matches!(
+            classify(StatusCode::BAD_REQUEST, "MessageRejected", String::new()),
+            SendError::Permanent(_)
+        ));
+        assert!(matches!(
+            classify(StatusCode::TOO_MANY_REQUESTS, "", String::new()),
+            SendError::RateLimited { .. }
+        ));
+        assert!(matches!(
+            classify(
+                StatusCode::FORBIDDEN,
+                "AccessDeniedException",
+                String::new()
+            ),
+            SendError::Transient(_)
+        ));
+    }
+}
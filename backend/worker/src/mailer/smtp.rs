@@ -0,0 +1,117 @@
+//! SMTP relay backend
+
+use anyhow::{Context, Result, bail};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::transport::smtp::{self, PoolConfig};
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use std::env;
+
+use super::{EmailBackend, SendError, SendFuture};
+
+/// How the SMTP connection is secured
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SmtpTls {
+    /// TLS from the first byte (usually port 465)
+    Implicit,
+    /// Plaintext upgraded with `STARTTLS` (usually port 587)
+    StartTls,
+    /// No encryption; only for local test servers
+    None,
+}
+
+/// SMTP relay settings
+#[derive(Debug, Clone)]
+pub struct SmtpConfig {
+    pub host: String,
+    pub port: u16,
+    pub tls: SmtpTls,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub pool_size: u32,
+}
+
+impl SmtpConfig {
+    pub(super) fn from_env() -> Result<Self> {
+        let tls = match env::var("SMTP_TLS")
+            .unwrap_or_else(|_| "starttls".to_string())
+            .to_ascii_lowercase()
+            .as_str()
+        {
+            "tls" => SmtpTls::Implicit,
+            "starttls" => SmtpTls::StartTls,
+            "none" => SmtpTls::None,
+            other => bail!("SMTP_TLS must be tls, starttls or none, got {other}"),
+        };
+
+        Ok(Self {
+            host: env::var("SMTP_HOST").context("SMTP_HOST must be set with EMAIL_BACKEND=smtp")?,
+            port: env::var("SMTP_PORT")
+                .unwrap_or_else(|_| "587".to_string())
+                .parse()
+                .context("SMTP_PORT must be a valid port")?,
+            tls,
+            username: env::var("SMTP_USERNAME").ok().filter(|v| !v.is_empty()),
+            password: env::var("SMTP_PASSWORD").ok().filter(|v| !v.is_empty()),
+            pool_size: env::var("SMTP_POOL_SIZE")
+                .unwrap_or_else(|_| "10".to_string())
+                .parse()
+                .context("SMTP_POOL_SIZE must be a valid integer")?,
+        })
+    }
+}
+
+/// Pooled SMTP sender
+pub(super) struct SmtpBackend {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+}
+
+impl SmtpBackend {
+    pub(super) fn new(config: &SmtpConfig) -> Result<Self> {
+        let builder = match config.tls {
+            SmtpTls::Implicit => AsyncSmtpTransport::<Tokio1Executor>::relay(&config.host)?,
+            SmtpTls::StartTls => {
+                AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.host)?
+            }
+            SmtpTls::None => {
+                AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(config.host.as_str())
+            }
+        };
+        let mut builder = builder
+            .port(config.port)
+            .pool_config(PoolConfig::new().max_size(config.pool_size));
+        if let (Some(username), Some(password)) = (&config.username, &config.password) {
+            builder = builder.credentials(Credentials::new(username.clone(), password.clone()));
+        }
+
+        Ok(Self {
+            transport: builder.build(),
+        })
+    }
+}
+
+impl EmailBackend for SmtpBackend {
+    fn send(&self, message: Message) -> SendFuture<'_> {
+        Box::pin(async move {
+            self.transport
+                .send(message)
+                .await
+                .map(|_| ())
+                .map_err(classify)
+        })
+    }
+}
+
+/// 5xx replies are final, except authentication failures, which an operator
+/// can fix while the job waits. Relays answer 421 when we connect too often.
+fn classify(err: smtp::Error) -> SendError {
+    let code = err.status().map(|code| code.to_string());
+    match code.as_deref() {
+        Some("421") => SendError::RateLimited {
+            retry_after: None,
+            message: err.to_string(),
+        },
+        Some("530" | "534" | "535") => SendError::Transient(err.to_string()),
+        _ if err.is_permanent() => SendError::Permanent(err.to_string()),
+        _ => SendError::Transient(err.to_string()),
+    }
+}
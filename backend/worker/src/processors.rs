@@ -2,51 +2,292 @@
 //!
 //! Handles different types of outbox messages
 
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, bail};
 use teloxide::prelude::*;
 use teloxide::types::ParseMode;
 use tracing::info;
 
+use crate::chat::ChatSender;
 use crate::db::TypedOutboxMessage;
+use crate::mailer::{Delivery, Mailer};
+use crate::push::PushSender;
+use crate::sms::SmsSender;
+use crate::telegram::TelegramSender;
+use crate::weather::Forecaster;
 use std::collections::HashMap;
-use televent_application::{CalendarService, EventView};
+use televent_application::{
+    CalendarService, ChatNotice, EventView, ItipChange, NotificationTopic, SnoozeDelay, UserId,
+};
 use televent_domain::{
-    EventTiming, ExternalEmailDeferred, InviteNotification, OutboxPayload, ParticipationStatus,
-    RsvpNotification, TelegramNotification,
+    AttendeeRemovedNotification, CancellationEmail, ChatWebhookMessage,
+    EXTERNAL_EMAIL_DISABLED_REASON, EventCancelledNotification, EventStatus, EventTiming,
+    EventUpdateEmail, EventUpdatedNotification, ExternalEmailDeferred, InviteNotification,
+    InviteReminder, OutboxMessageId, OutboxPayload, ParticipationStatus, RsvpNotification,
+    SignupConfirmation, SmsMessage, TaskBlockReminder, TelegramNotification, Timezone,
+    UserEmailMessage, WebPushMessage, telegram_html,
 };
 use teloxide::types::{InlineKeyboardButton, InlineKeyboardMarkup};
 use uuid::Uuid;
 
 /// Process a single outbox message
+#[allow(clippy::too_many_arguments)]
 pub async fn process_message(
     calendar: &CalendarService,
     message: &TypedOutboxMessage,
-    bot: &Bot,
+    telegram: &TelegramSender,
+    mailer: Option<&Mailer>,
+    chat: Option<&ChatSender>,
+    sms: Option<&SmsSender>,
+    push: Option<&PushSender>,
+    weather: Option<&Forecaster>,
     events_cache: &HashMap<Uuid, EventView>,
 ) -> Result<()> {
     match message.payload.clone() {
         OutboxPayload::InviteNotification(payload) => {
-            process_invite_notification(calendar, message.id, payload, bot, events_cache).await
+            process_invite_notification(
+                calendar,
+                message.id,
+                payload,
+                telegram,
+                chat,
+                sms,
+                push,
+                mailer,
+                events_cache,
+            )
+            .await
         }
         OutboxPayload::TelegramNotification(payload) => {
-            process_telegram_notification(message.id, payload, bot).await
+            process_telegram_notification(message.id, payload, telegram).await
         }
         OutboxPayload::ExternalEmailDeferred(payload) => {
             process_external_email_deferred(message.id, payload).await
         }
         OutboxPayload::RsvpNotification(payload) => {
-            process_rsvp_notification(message.id, payload, bot).await
+            process_rsvp_notification(message.id, payload, telegram).await
+        }
+        OutboxPayload::InviteReminder(payload) => {
+            process_invite_reminder(
+                calendar,
+                message.id,
+                payload,
+                telegram,
+                chat,
+                sms,
+                push,
+                mailer,
+                weather,
+                events_cache,
+            )
+            .await
+        }
+        OutboxPayload::AttendeeRemovedNotification(payload) => {
+            process_attendee_removed_notification(message.id, payload, telegram).await
         }
+        OutboxPayload::SignupConfirmation(payload) => {
+            process_signup_confirmation(calendar, message.id, payload, mailer).await
+        }
+        OutboxPayload::EventCancelledNotification(payload) => {
+            process_event_cancelled_notification(
+                message.id, payload, telegram, chat, sms, push, mailer,
+            )
+            .await
+        }
+        OutboxPayload::CancellationEmail(payload) => {
+            process_cancellation_email(calendar, message.id, payload, mailer).await
+        }
+        OutboxPayload::EventUpdated(payload) => {
+            process_event_updated(
+                calendar,
+                message.id,
+                payload,
+                telegram,
+                chat,
+                sms,
+                push,
+                mailer,
+                events_cache,
+            )
+            .await
+        }
+        OutboxPayload::EventUpdateEmail(payload) => {
+            process_event_update_email(calendar, message.id, payload, mailer).await
+        }
+        OutboxPayload::ChatWebhook(payload) => {
+            process_chat_webhook(message.id, payload, chat).await
+        }
+        OutboxPayload::Sms(payload) => process_sms(message.id, payload, sms).await,
+        OutboxPayload::WebPush(payload) => process_web_push(message.id, payload, push).await,
+        OutboxPayload::TaskBlockReminder(payload) => {
+            process_task_block_reminder(calendar, message.id, payload, telegram).await
+        }
+        OutboxPayload::UserEmail(payload) => process_user_email(message.id, payload, mailer).await,
+    }
+}
+
+/// Queue copies of a notice for the user's Slack and Teams webhooks, their
+/// phone, their browsers and their confirmed email, as far as their
+/// notification preferences allow
+///
+/// Runs before the Telegram message goes out: copies are keyed by the source
+/// message, so a retry after a failed Telegram send adds none.
+async fn copy_notice(
+    chat: Option<&ChatSender>,
+    sms: Option<&SmsSender>,
+    push: Option<&PushSender>,
+    mailer: Option<&Mailer>,
+    user_id: i64,
+    source_id: OutboxMessageId,
+    notice: &ChatNotice,
+) -> Result<()> {
+    if let Some(chat) = chat {
+        chat.copy(user_id, source_id.inner(), notice).await?;
+    }
+    if let Some(sms) = sms {
+        sms.copy(user_id, source_id.inner(), notice).await?;
+    }
+    if let Some(push) = push {
+        push.copy(user_id, source_id.inner(), notice).await?;
+    }
+    if let Some(mailer) = mailer {
+        mailer.copy(user_id, source_id.inner(), notice).await?;
+    }
+    Ok(())
+}
+
+/// Post a notice copy to a Slack or Teams webhook
+async fn process_chat_webhook(
+    message_id: OutboxMessageId,
+    payload: ChatWebhookMessage,
+    chat: Option<&ChatSender>,
+) -> Result<()> {
+    let Some(chat) = chat else {
+        info!(
+            "Chat webhook post deferred: no chat sender configured (message: {})",
+            message_id
+        );
+        return Ok(());
+    };
+    if !chat.post(&payload).await? {
+        info!(
+            "Skipping chat webhook post: webhook {} was removed (message: {})",
+            payload.webhook_id, message_id
+        );
+        return Ok(());
+    }
+
+    info!(
+        "Posted '{}' to chat webhook {} (message: {})",
+        payload.title, payload.webhook_id, message_id
+    );
+
+    Ok(())
+}
+
+/// Text a notice copy or a verification code
+async fn process_sms(
+    message_id: OutboxMessageId,
+    payload: SmsMessage,
+    sms: Option<&SmsSender>,
+) -> Result<()> {
+    let Some(sms) = sms else {
+        info!(
+            "Text message dropped: no SMS provider configured (message: {})",
+            message_id
+        );
+        return Ok(());
+    };
+    if !sms.send(&payload).await? {
+        info!(
+            "Skipping text message to user {}: number removed or replaced (message: {})",
+            payload.user_id, message_id
+        );
+        return Ok(());
     }
+
+    info!(
+        "Sent text message to user {} (message: {})",
+        payload.user_id, message_id
+    );
+
+    Ok(())
+}
+
+/// Email a notice copy or a verification code to the user's own address
+async fn process_user_email(
+    message_id: OutboxMessageId,
+    payload: UserEmailMessage,
+    mailer: Option<&Mailer>,
+) -> Result<()> {
+    let Some(mailer) = mailer else {
+        info!(
+            "Email to user {} dropped: email delivery is off (message: {})",
+            payload.user_id, message_id
+        );
+        return Ok(());
+    };
+    match mailer
+        .send_user_email(&payload)
+        .await
+        .context("Failed to send email to user")?
+    {
+        Delivery::Sent => info!(
+            "Sent email to user {} (message: {})",
+            payload.user_id, message_id
+        ),
+        Delivery::Suppressed => info!(
+            "Skipping email to user {}: address is suppressed (message: {})",
+            payload.user_id, message_id
+        ),
+        Delivery::Stale => info!(
+            "Skipping email to user {}: address removed, replaced or already confirmed \
+             (message: {})",
+            payload.user_id, message_id
+        ),
+    }
+
+    Ok(())
+}
+
+/// Push a notice copy to one of the user's browsers
+async fn process_web_push(
+    message_id: OutboxMessageId,
+    payload: WebPushMessage,
+    push: Option<&PushSender>,
+) -> Result<()> {
+    let Some(push) = push else {
+        info!(
+            "Web push dropped: no VAPID keys configured (message: {})",
+            message_id
+        );
+        return Ok(());
+    };
+    if !push.push(&payload).await? {
+        info!(
+            "Skipping web push: subscription {} is gone (message: {})",
+            payload.subscription_id, message_id
+        );
+        return Ok(());
+    }
+
+    info!(
+        "Pushed '{}' to subscription {} (message: {})",
+        payload.title, payload.subscription_id, message_id
+    );
+
+    Ok(())
 }
 
 /// Process a Telegram notification
 async fn process_telegram_notification(
-    message_id: Uuid,
+    message_id: OutboxMessageId,
     payload: TelegramNotification,
-    bot: &Bot,
+    telegram: &TelegramSender,
 ) -> Result<()> {
-    bot.send_message(ChatId(payload.telegram_id), payload.message.clone())
+    telegram
+        .send(ChatId(payload.telegram_id), |bot, chat_id| {
+            bot.send_message(chat_id, payload.message.clone())
+        })
         .await
         .context("Failed to send Telegram message")?;
 
@@ -58,64 +299,492 @@ async fn process_telegram_notification(
     Ok(())
 }
 
+/// Ask whether a task is done once its confirmed block has ended
+///
+/// Nothing is sent for a task that is done, deleted or planned again, nor
+/// for a block that was never confirmed or was moved to end later.
+async fn process_task_block_reminder(
+    calendar: &CalendarService,
+    message_id: OutboxMessageId,
+    payload: TaskBlockReminder,
+    telegram: &TelegramSender,
+) -> Result<()> {
+    let user_id = UserId::new(payload.target_user_id);
+    let task = calendar
+        .get_task(user_id, payload.task_id)
+        .await
+        .context("Failed to load task")?
+        .filter(|task| task.completed_at.is_none());
+    let now = calendar.clock().now();
+    let Some(task) = task.filter(|task| {
+        task.block.is_some_and(|block| {
+            block.event_id == payload.event_id && block.confirmed && block.end <= now
+        })
+    }) else {
+        info!(
+            "Skipping reminder for task {}, its block is gone or not due (message: {})",
+            payload.task_id, message_id
+        );
+        return Ok(());
+    };
+
+    let text = format!(
+        "⏱ Time's up for <b>{}</b>. Did you finish it?",
+        telegram_html::inline(&task.title)
+    );
+    let keyboard = InlineKeyboardMarkup::new(vec![vec![
+        InlineKeyboardButton::callback("✅ Done", format!("task:done:{}", task.id)),
+        InlineKeyboardButton::callback("↩️ Not yet", format!("task:later:{}", task.id)),
+    ]]);
+    telegram
+        .send(ChatId(payload.target_user_id), |bot, chat_id| {
+            bot.send_message(chat_id, text.clone())
+                .parse_mode(ParseMode::Html)
+                .reply_markup(keyboard.clone())
+        })
+        .await
+        .context("Failed to send task reminder")?;
+
+    info!(
+        "Sent block reminder for task {} to user {} (message: {})",
+        task.id, payload.target_user_id, message_id
+    );
+
+    Ok(())
+}
+
 /// Process an invite notification
+#[allow(clippy::too_many_arguments)]
 async fn process_invite_notification(
     calendar: &CalendarService,
-    message_id: Uuid,
+    message_id: OutboxMessageId,
     payload: InviteNotification,
-    bot: &Bot,
+    telegram: &TelegramSender,
+    chat: Option<&ChatSender>,
+    sms: Option<&SmsSender>,
+    push: Option<&PushSender>,
+    mailer: Option<&Mailer>,
     events_cache: &HashMap<Uuid, EventView>,
 ) -> Result<()> {
+    let sent = send_invite(
+        calendar,
+        message_id,
+        InviteCard::New,
+        payload.event_id,
+        payload.target_user_id,
+        telegram,
+        chat,
+        sms,
+        push,
+        mailer,
+        None,
+        events_cache,
+    )
+    .await
+    .context("Failed to send invite notification")?;
+    if !sent {
+        info!(
+            "Skipping invite for cancelled event {} (message: {})",
+            payload.event_id, message_id
+        );
+        return Ok(());
+    }
+
+    info!(
+        "Sent invite notification to user {} for event {} (message: {})",
+        payload.target_user_id, payload.event_id, message_id
+    );
+
+    Ok(())
+}
+
+/// Process an organizer-requested invite reminder
+#[allow(clippy::too_many_arguments)]
+async fn process_invite_reminder(
+    calendar: &CalendarService,
+    message_id: OutboxMessageId,
+    payload: InviteReminder,
+    telegram: &TelegramSender,
+    chat: Option<&ChatSender>,
+    sms: Option<&SmsSender>,
+    push: Option<&PushSender>,
+    mailer: Option<&Mailer>,
+    weather: Option<&Forecaster>,
+    events_cache: &HashMap<Uuid, EventView>,
+) -> Result<()> {
+    let sent = send_invite(
+        calendar,
+        message_id,
+        InviteCard::Reminder,
+        payload.event_id,
+        payload.target_user_id,
+        telegram,
+        chat,
+        sms,
+        push,
+        mailer,
+        weather,
+        events_cache,
+    )
+    .await
+    .context("Failed to send invite reminder")?;
+    if !sent {
+        info!(
+            "Skipping invite reminder for cancelled event {} (message: {})",
+            payload.event_id, message_id
+        );
+        return Ok(());
+    }
+
+    info!(
+        "Sent invite reminder to user {} for event {} (message: {})",
+        payload.target_user_id, payload.event_id, message_id
+    );
+
+    Ok(())
+}
+
+/// Which invite card to send
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum InviteCard {
+    New,
+    /// Also offers snooze buttons
+    Reminder,
+}
+
+impl InviteCard {
+    const fn heading(self) -> &'static str {
+        match self {
+            Self::New => "New Invite",
+            Self::Reminder => "Reminder",
+        }
+    }
+
+    const fn topic(self) -> NotificationTopic {
+        match self {
+            Self::New => NotificationTopic::Invite,
+            Self::Reminder => NotificationTopic::Reminder,
+        }
+    }
+}
+
+/// Send the invite card with RSVP buttons, and copies to chat webhooks and SMS
+///
+/// With a forecaster, the card ends with the forecast for outdoor events.
+/// Nothing is sent for a cancelled event; returns whether the card went out.
+#[allow(clippy::too_many_arguments)]
+async fn send_invite(
+    calendar: &CalendarService,
+    message_id: OutboxMessageId,
+    card: InviteCard,
+    event_id: Uuid,
+    target_user_id: i64,
+    telegram: &TelegramSender,
+    chat: Option<&ChatSender>,
+    sms: Option<&SmsSender>,
+    push: Option<&PushSender>,
+    mailer: Option<&Mailer>,
+    weather: Option<&Forecaster>,
+    events_cache: &HashMap<Uuid, EventView>,
+) -> Result<bool> {
     // Fetch event details
     // Check cache first
-    let event = if let Some(event) = events_cache.get(&payload.event_id) {
+    let event = if let Some(event) = events_cache.get(&event_id) {
         event.clone()
     } else {
         calendar
-            .get_event_view_by_id_any(payload.event_id)
+            .get_event_view_by_id_any(event_id)
             .await
             .context("Failed to fetch event")?
             .context("Event not found")?
     };
+    if event.status == EventStatus::Cancelled {
+        return Ok(false);
+    }
 
-    let time_str = match &event.timing {
-        EventTiming::Timed { start, .. } => start.format("%Y-%m-%d %H:%M UTC").to_string(),
-        EventTiming::AllDay { start_date, .. } => format!("{} (All Day)", start_date),
+    // Guests abroad read the time in their own zone; unknown guests get the event's
+    let recipient_timezone = match &event.timing {
+        EventTiming::Timed { .. } => calendar
+            .get_user_identity_by_id(UserId::new(target_user_id))
+            .await
+            .context("Failed to fetch invitee")?
+            .map(|identity| identity.timezone),
+        EventTiming::AllDay { .. } | EventTiming::Floating { .. } => None,
     };
+    let time_str = invite_time_text(&event.timing, recipient_timezone.as_ref());
 
     let location_text = event
         .location
         .as_ref()
-        .map(|loc| format!("\n📍 <b>Location:</b> {}", loc))
+        .map(|loc| format!("\n📍 <b>Location:</b> {}", telegram_html::inline(loc)))
+        .unwrap_or_default();
+
+    let forecast = match weather {
+        Some(weather) => weather.forecast_line(&event, calendar.clock().now()).await,
+        None => None,
+    };
+    let weather_text = forecast
+        .as_ref()
+        .map(|line| format!("\n{}", telegram_html::inline(line)))
         .unwrap_or_default();
 
+    // The organizer's note only rides along on reminders, not the invite
+    let note = event
+        .reminder_note
+        .as_ref()
+        .filter(|_| card == InviteCard::Reminder);
+    let note_text = note
+        .map(|note| format!("\n📝 <b>Note:</b> {}", telegram_html::inline(note)))
+        .unwrap_or_default();
+
+    let mut lines = vec![format!("🕒 Time: {time_str}")];
+    lines.extend(
+        event
+            .location
+            .as_ref()
+            .map(|loc| format!("📍 Location: {loc}")),
+    );
+    lines.extend(forecast);
+    lines.extend(note.map(|note| format!("📝 Note: {note}")));
+    lines.push("Reply with the buttons in Televent on Telegram.".to_string());
+    copy_notice(
+        chat,
+        sms,
+        push,
+        mailer,
+        target_user_id,
+        message_id,
+        &ChatNotice {
+            topic: card.topic(),
+            title: format!("📅 {}: {}", card.heading(), event.summary),
+            lines,
+        },
+    )
+    .await?;
+
     let text = format!(
-        "📅 <b>New Invite:</b> {}\n🕒 <b>Time:</b> {}{}",
-        event.summary, time_str, location_text
+        "📅 <b>{}:</b> {}\n🕒 <b>Time:</b> {}{}{}{}",
+        card.heading(),
+        telegram_html::inline(&event.summary),
+        time_str,
+        location_text,
+        weather_text,
+        note_text
     );
 
-    let keyboard = InlineKeyboardMarkup::new(vec![vec![
+    let mut rows = vec![vec![
         InlineKeyboardButton::callback("✅ Accept", format!("rsvp:{}:ACCEPTED", event.id)),
         InlineKeyboardButton::callback("❌ Decline", format!("rsvp:{}:DECLINED", event.id)),
         InlineKeyboardButton::callback("❔ Tentative", format!("rsvp:{}:TENTATIVE", event.id)),
-    ]]);
+    ]];
+    if card == InviteCard::Reminder {
+        rows.push(
+            SnoozeDelay::ALL
+                .into_iter()
+                .map(|delay| {
+                    InlineKeyboardButton::callback(
+                        format!("⏰ {}", delay.label()),
+                        format!("snz:{}:{}", event.id, delay.as_str()),
+                    )
+                })
+                .collect(),
+        );
+    }
+    let keyboard = InlineKeyboardMarkup::new(rows);
+
+    telegram
+        .send(ChatId(target_user_id), |bot, chat_id| {
+            bot.send_message(chat_id, text.clone())
+                .parse_mode(ParseMode::Html)
+                .reply_markup(keyboard.clone())
+        })
+        .await?;
+
+    Ok(true)
+}
+
+/// Tell a guest who is coming that the event moved or changed place
+///
+/// Compares the time and place from before the first change in the window
+/// with the event as it is now, so edits that were undone send nothing.
+#[allow(clippy::too_many_arguments)]
+async fn process_event_updated(
+    calendar: &CalendarService,
+    message_id: OutboxMessageId,
+    payload: EventUpdatedNotification,
+    telegram: &TelegramSender,
+    chat: Option<&ChatSender>,
+    sms: Option<&SmsSender>,
+    push: Option<&PushSender>,
+    mailer: Option<&Mailer>,
+    events_cache: &HashMap<Uuid, EventView>,
+) -> Result<()> {
+    let event = match events_cache.get(&payload.event_id) {
+        Some(event) => Some(event.clone()),
+        None => calendar
+            .get_event_view_by_id_any(payload.event_id)
+            .await
+            .context("Failed to fetch event")?,
+    };
+    let Some(event) = event.filter(|event| event.status != EventStatus::Cancelled) else {
+        info!(
+            "Skipping update of event {}: deleted or cancelled (message: {})",
+            payload.event_id, message_id
+        );
+        return Ok(());
+    };
 
-    bot.send_message(ChatId(payload.target_user_id), text)
-        .parse_mode(ParseMode::Html)
-        .reply_markup(keyboard)
+    let recipient_timezone = calendar
+        .get_user_identity_by_id(UserId::new(payload.target_user_id))
         .await
-        .context("Failed to send invite notification")?;
+        .context("Failed to fetch guest")?
+        .map(|identity| identity.timezone);
+    let changes = event_changes(&payload, &event, recipient_timezone.as_ref());
+    let Some(text) = event_update_text(&event, &changes) else {
+        info!(
+            "Skipping update of event {}: time and place are as before (message: {})",
+            payload.event_id, message_id
+        );
+        return Ok(());
+    };
+    copy_notice(
+        chat,
+        sms,
+        push,
+        mailer,
+        payload.target_user_id,
+        message_id,
+        &ChatNotice {
+            topic: NotificationTopic::Update,
+            title: format!("🔄 Changed: {}", event.summary),
+            lines: changes
+                .iter()
+                .map(|change| {
+                    format!(
+                        "{} {}: {} → {}",
+                        change.icon, change.label, change.before, change.after
+                    )
+                })
+                .collect(),
+        },
+    )
+    .await?;
+
+    telegram
+        .send(ChatId(payload.target_user_id), |bot, chat_id| {
+            bot.send_message(chat_id, text.clone())
+                .parse_mode(ParseMode::Html)
+        })
+        .await
+        .context("Failed to send event update")?;
 
     info!(
-        "Sent invite notification to user {} for event {} (message: {})",
-        payload.target_user_id, event.id, message_id
+        "Sent update of event {} to user {} (message: {})",
+        payload.event_id, payload.target_user_id, message_id
     );
 
     Ok(())
 }
 
+/// Time or place of an event that differs from before the update window
+pub(crate) struct EventChange {
+    icon: &'static str,
+    label: &'static str,
+    before: String,
+    after: String,
+}
+
+/// What changed about an updated event, with times in the recipient's zone
+pub(crate) fn event_changes(
+    payload: &EventUpdatedNotification,
+    event: &EventView,
+    recipient: Option<&Timezone>,
+) -> Vec<EventChange> {
+    let mut changes = Vec::new();
+    if event.timing != payload.previous_timing {
+        changes.push(EventChange {
+            icon: "🕒",
+            label: "Time",
+            before: invite_time_text(&payload.previous_timing, recipient),
+            after: invite_time_text(&event.timing, recipient),
+        });
+    }
+    if event.location != payload.previous_location {
+        let place =
+            |location: &Option<String>| location.clone().unwrap_or_else(|| "none".to_string());
+        changes.push(EventChange {
+            icon: "📍",
+            label: "Location",
+            before: place(&payload.previous_location),
+            after: place(&event.location),
+        });
+    }
+    changes
+}
+
+/// Old and new time and place of an updated event; `None` when neither
+/// differs any more
+pub(crate) fn event_update_text(event: &EventView, changes: &[EventChange]) -> Option<String> {
+    if changes.is_empty() {
+        return None;
+    }
+    let lines = changes
+        .iter()
+        .map(|change| {
+            format!(
+                "{} <b>{}:</b> <s>{}</s> → {}",
+                change.icon,
+                change.label,
+                telegram_html::inline(&change.before),
+                telegram_html::inline(&change.after)
+            )
+        })
+        .collect::<Vec<_>>();
+
+    Some(format!(
+        "🔄 <b>Changed:</b> {}\n{}",
+        telegram_html::inline(&event.summary),
+        lines.join("\n")
+    ))
+}
+
+/// Event time in the recipient's timezone, with the organizer's alongside
+/// when the two differ
+///
+/// The event's own timezone stands in for the organizer's. All-day dates and
+/// floating times read the same everywhere.
+pub(crate) fn invite_time_text(timing: &EventTiming, recipient: Option<&Timezone>) -> String {
+    match timing {
+        EventTiming::Timed {
+            start, timezone, ..
+        } => {
+            let recipient = recipient.unwrap_or(timezone);
+            let local = start.with_timezone(&recipient.tz());
+            let mut text = format!("{} {}", local.format("%Y-%m-%d %H:%M"), recipient.as_str());
+            if recipient != timezone {
+                let organizer = start.with_timezone(&timezone.tz());
+                let format = if organizer.date_naive() == local.date_naive() {
+                    "%H:%M"
+                } else {
+                    "%Y-%m-%d %H:%M"
+                };
+                text.push_str(&format!(
+                    " ({} {} for the organizer)",
+                    organizer.format(format),
+                    timezone.as_str()
+                ));
+            }
+            text
+        }
+        EventTiming::AllDay { start_date, .. } => format!("{} (All Day)", start_date),
+        EventTiming::Floating { start, .. } => {
+            format!("{} (local time)", start.format("%Y-%m-%d %H:%M"))
+        }
+    }
+}
+
 async fn process_external_email_deferred(
-    message_id: Uuid,
+    message_id: OutboxMessageId,
     payload: ExternalEmailDeferred,
 ) -> Result<()> {
     info!(
@@ -126,28 +795,297 @@ async fn process_external_email_deferred(
 }
 
 async fn process_rsvp_notification(
-    message_id: Uuid,
+    message_id: OutboxMessageId,
     payload: RsvpNotification,
-    bot: &Bot,
+    telegram: &TelegramSender,
 ) -> Result<()> {
+    let text = format!("📅 {}", rsvp_line(&payload));
+
+    telegram
+        .send(ChatId(payload.organizer_telegram_id), |bot, chat_id| {
+            bot.send_message(chat_id, text.clone())
+        })
+        .await
+        .context("Failed to send RSVP notification")?;
+
+    info!(
+        "Sent RSVP notification to user {} (message: {})",
+        payload.organizer_telegram_id, message_id
+    );
+
+    Ok(())
+}
+
+/// One attendee's answer, as shown to the organizer
+pub(crate) fn rsvp_line(payload: &RsvpNotification) -> String {
     let status = match payload.rsvp_status {
         ParticipationStatus::NeedsAction => "needs action",
         ParticipationStatus::Accepted => "accepted",
         ParticipationStatus::Declined => "declined",
         ParticipationStatus::Tentative => "tentatively accepted",
     };
-    let text = format!(
-        "📅 {} {} your invite to: {}",
+    format!(
+        "{} {} your invite to: {}",
         payload.attendee_name, status, payload.event_summary
+    )
+}
+
+/// Email the confirmation link of a public event signup
+///
+/// The link is minted here rather than when the signup is queued, so the
+/// token never sits in the outbox. A retry mints a new link and the old one
+/// stops working.
+async fn process_signup_confirmation(
+    calendar: &CalendarService,
+    message_id: OutboxMessageId,
+    payload: SignupConfirmation,
+    mailer: Option<&Mailer>,
+) -> Result<()> {
+    let Some(mailer) = mailer else {
+        bail!(EXTERNAL_EMAIL_DISABLED_REASON);
+    };
+    let Some(confirmation_path) = calendar
+        .issue_signup_confirmation(payload.event_id, &payload.recipient_email)
+        .await?
+    else {
+        info!(
+            "Skipping signup confirmation for event {}: nothing left to confirm (message: {})",
+            payload.event_id, message_id
+        );
+        return Ok(());
+    };
+
+    // The confirmation link is a credential, so it is never written to logs
+    let delivery = mailer
+        .send_signup_confirmation(
+            &payload.recipient_email,
+            &payload.event_summary,
+            &confirmation_path,
+        )
+        .await
+        .context("Failed to send signup confirmation")?;
+    if delivery == Delivery::Suppressed {
+        info!(
+            "Skipping signup confirmation for event {}: {} is suppressed (message: {})",
+            payload.event_id, payload.recipient_email, message_id
+        );
+        return Ok(());
+    }
+
+    info!(
+        "Sent signup confirmation to {} for event {} (message: {})",
+        payload.recipient_email, payload.event_id, message_id
     );
 
-    bot.send_message(ChatId(payload.organizer_telegram_id), text)
+    Ok(())
+}
+
+/// Tell a Telegram guest that an event they were invited to is off
+async fn process_event_cancelled_notification(
+    message_id: OutboxMessageId,
+    payload: EventCancelledNotification,
+    telegram: &TelegramSender,
+    chat: Option<&ChatSender>,
+    sms: Option<&SmsSender>,
+    push: Option<&PushSender>,
+    mailer: Option<&Mailer>,
+) -> Result<()> {
+    copy_notice(
+        chat,
+        sms,
+        push,
+        mailer,
+        payload.target_user_id,
+        message_id,
+        &ChatNotice {
+            topic: NotificationTopic::Cancellation,
+            title: format!("❌ Cancelled: {}", payload.event_summary),
+            lines: vec!["The organizer called this event off.".to_string()],
+        },
+    )
+    .await?;
+    let text = format!(
+        "❌ <b>Cancelled:</b> <s>{}</s>",
+        telegram_html::inline(&payload.event_summary)
+    );
+
+    telegram
+        .send(ChatId(payload.target_user_id), |bot, chat_id| {
+            bot.send_message(chat_id, text.clone())
+                .parse_mode(ParseMode::Html)
+        })
         .await
-        .context("Failed to send RSVP notification")?;
+        .context("Failed to send cancellation notification")?;
 
     info!(
-        "Sent RSVP notification to user {} (message: {})",
-        payload.organizer_telegram_id, message_id
+        "Sent cancellation of event {} to user {} (message: {})",
+        payload.event_id, payload.target_user_id, message_id
+    );
+
+    Ok(())
+}
+
+/// Email an iTIP cancellation to an external guest
+///
+/// Without email delivery the guest never got the invite either, so there is
+/// nothing to take back. The calendar object is rendered now, and nothing is
+/// sent if the organizer has since reinstated the event.
+async fn process_cancellation_email(
+    calendar: &CalendarService,
+    message_id: OutboxMessageId,
+    payload: CancellationEmail,
+    mailer: Option<&Mailer>,
+) -> Result<()> {
+    let Some(mailer) = mailer else {
+        info!(
+            "Cancellation email deferred: {} - event '{}' ({}) (message: {})",
+            payload.recipient_email,
+            payload.event_summary,
+            EXTERNAL_EMAIL_DISABLED_REASON,
+            message_id
+        );
+        return Ok(());
+    };
+    let Some(itip) = calendar
+        .guest_itip(
+            payload.event_id,
+            &payload.recipient_email,
+            ItipChange::Cancelled,
+        )
+        .await?
+    else {
+        info!(
+            "Skipping cancellation email for event {}: no longer cancelled (message: {})",
+            payload.event_id, message_id
+        );
+        return Ok(());
+    };
+
+    let delivery = mailer
+        .send_itip(
+            &payload.recipient_email,
+            ItipChange::Cancelled,
+            &payload.event_summary,
+            &itip,
+        )
+        .await
+        .context("Failed to send cancellation email")?;
+    if delivery == Delivery::Suppressed {
+        info!(
+            "Skipping cancellation email for event {}: {} is suppressed (message: {})",
+            payload.event_id, payload.recipient_email, message_id
+        );
+        return Ok(());
+    }
+
+    info!(
+        "Sent cancellation email to {} for event {} (message: {})",
+        payload.recipient_email, payload.event_id, message_id
+    );
+
+    Ok(())
+}
+
+/// Email an iTIP update to an external guest of an event that moved or
+/// changed place
+///
+/// Like the Telegram notice, the window's first previous state is compared
+/// with the event as it is now. A new time asks the guest to reply again; a
+/// new place alone keeps their answer.
+async fn process_event_update_email(
+    calendar: &CalendarService,
+    message_id: OutboxMessageId,
+    payload: EventUpdateEmail,
+    mailer: Option<&Mailer>,
+) -> Result<()> {
+    let Some(mailer) = mailer else {
+        info!(
+            "Event update email deferred: {} - event '{}' ({}) (message: {})",
+            payload.recipient_email,
+            payload.event_summary,
+            EXTERNAL_EMAIL_DISABLED_REASON,
+            message_id
+        );
+        return Ok(());
+    };
+    let Some(event) = calendar
+        .get_event_view_by_id_any(payload.event_id)
+        .await
+        .context("Failed to fetch event")?
+    else {
+        info!(
+            "Skipping update email for event {}: deleted (message: {})",
+            payload.event_id, message_id
+        );
+        return Ok(());
+    };
+    let change = if event.timing != payload.previous_timing {
+        ItipChange::Rescheduled
+    } else if event.location != payload.previous_location {
+        ItipChange::Updated
+    } else {
+        info!(
+            "Skipping update email for event {}: time and place are as before (message: {})",
+            payload.event_id, message_id
+        );
+        return Ok(());
+    };
+    let Some(itip) = calendar
+        .guest_itip(payload.event_id, &payload.recipient_email, change)
+        .await?
+    else {
+        info!(
+            "Skipping update email for event {}: cancelled or guest removed (message: {})",
+            payload.event_id, message_id
+        );
+        return Ok(());
+    };
+
+    let delivery = mailer
+        .send_itip(
+            &payload.recipient_email,
+            change,
+            &payload.event_summary,
+            &itip,
+        )
+        .await
+        .context("Failed to send event update email")?;
+    if delivery == Delivery::Suppressed {
+        info!(
+            "Skipping update email for event {}: {} is suppressed (message: {})",
+            payload.event_id, payload.recipient_email, message_id
+        );
+        return Ok(());
+    }
+
+    info!(
+        "Sent update email to {} for event {} (message: {})",
+        payload.recipient_email, payload.event_id, message_id
+    );
+
+    Ok(())
+}
+
+async fn process_attendee_removed_notification(
+    message_id: OutboxMessageId,
+    payload: AttendeeRemovedNotification,
+    telegram: &TelegramSender,
+) -> Result<()> {
+    let text = format!(
+        "🚫 You were removed from the event: {}",
+        payload.event_summary
+    );
+
+    telegram
+        .send(ChatId(payload.target_user_id), |bot, chat_id| {
+            bot.send_message(chat_id, text.clone())
+        })
+        .await
+        .context("Failed to send attendee removal notification")?;
+
+    info!(
+        "Sent attendee removal notification to user {} (message: {})",
+        payload.target_user_id, message_id
     );
 
     Ok(())
@@ -156,6 +1094,7 @@ async fn process_rsvp_notification(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use chrono::Utc;
     use serde_json::json;
     use sqlx::PgPool;
     use televent_domain::InviteNotification;
@@ -191,11 +1130,107 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_invite_time_in_recipient_timezone() {
+        let start = chrono::DateTime::parse_from_rfc3339("2026-03-02T23:30:00Z")
+            .unwrap()
+            .to_utc();
+        let timing = EventTiming::Timed {
+            start,
+            end: start + chrono::Duration::hours(1),
+            timezone: Timezone::parse("Europe/London").unwrap(),
+        };
+
+        let tokyo = Timezone::parse("Asia/Tokyo").unwrap();
+        assert_eq!(
+            invite_time_text(&timing, Some(&tokyo)),
+            "2026-03-03 08:30 Asia/Tokyo (2026-03-02 23:30 Europe/London for the organizer)"
+        );
+        let berlin = Timezone::parse("Europe/Berlin").unwrap();
+        assert_eq!(
+            invite_time_text(&timing, Some(&berlin)),
+            "2026-03-03 00:30 Europe/Berlin (2026-03-02 23:30 Europe/London for the organizer)"
+        );
+        // Same zone, or a guest we know nothing about: the organizer's time only
+        let london = Timezone::parse("Europe/London").unwrap();
+        assert_eq!(
+            invite_time_text(&timing, Some(&london)),
+            "2026-03-02 23:30 Europe/London"
+        );
+        assert_eq!(
+            invite_time_text(&timing, None),
+            "2026-03-02 23:30 Europe/London"
+        );
+
+        let all_day = EventTiming::AllDay {
+            start_date: chrono::NaiveDate::from_ymd_opt(2026, 3, 2).unwrap(),
+            end_date: chrono::NaiveDate::from_ymd_opt(2026, 3, 3).unwrap(),
+        };
+        assert_eq!(
+            invite_time_text(&all_day, Some(&tokyo)),
+            "2026-03-02 (All Day)"
+        );
+
+        let floating = EventTiming::Floating {
+            start: start.naive_utc(),
+            end: start.naive_utc() + chrono::Duration::hours(1),
+        };
+        assert_eq!(
+            invite_time_text(&floating, Some(&tokyo)),
+            "2026-03-02 23:30 (local time)"
+        );
+    }
+
+    #[test]
+    fn test_event_update_text_shows_what_changed() {
+        let start = chrono::DateTime::parse_from_rfc3339("2026-03-02T10:00:00Z")
+            .unwrap()
+            .to_utc();
+        let timing = |start: chrono::DateTime<Utc>| EventTiming::Timed {
+            start,
+            end: start + chrono::Duration::hours(1),
+            timezone: Timezone::utc(),
+        };
+        let payload = EventUpdatedNotification {
+            event_id: Uuid::new_v4(),
+            target_user_id: 2,
+            event_summary: "Standup".to_string(),
+            previous_timing: timing(start),
+            previous_location: Some("Room 1".to_string()),
+            window_start: start,
+        };
+        let mut event = EventView {
+            id: payload.event_id,
+            uid: "standup".to_string(),
+            summary: "Standup".to_string(),
+            description: None,
+            location: Some("Room 1".to_string()),
+            timing: timing(start),
+            status: EventStatus::Confirmed,
+            rrule: None,
+            visibility: televent_domain::EventVisibility::Public,
+            reminder_note: None,
+        };
+        assert_eq!(
+            event_update_text(&event, &event_changes(&payload, &event, None)),
+            None
+        );
+
+        event.timing = timing(start + chrono::Duration::hours(2));
+        let text = event_update_text(&event, &event_changes(&payload, &event, None)).unwrap();
+        assert!(text.contains("<s>2026-03-02 10:00 UTC</s> → 2026-03-02 12:00 UTC"));
+        assert!(!text.contains("Location"));
+
+        event.location = None;
+        let text = event_update_text(&event, &event_changes(&payload, &event, None)).unwrap();
+        assert!(text.contains("📍 <b>Location:</b> <s>Room 1</s> → none"));
+    }
+
     #[sqlx::test(migrations = "../migrations")]
     async fn test_process_invite_notification(pool: PgPool) -> sqlx::Result<()> {
         use televent_application::UserId;
 
-        let bot = Bot::new("token");
+        let telegram = TelegramSender::new(Bot::new("token"));
 
         // Insert Test User
         let user_id = UserId::new(123456789);
@@ -222,12 +1257,13 @@ mod tests {
         // Create Outbox Message
         let msg_id = Uuid::new_v4();
         let message = TypedOutboxMessage {
-            id: msg_id,
+            id: OutboxMessageId::new(msg_id),
             payload: OutboxPayload::InviteNotification(InviteNotification {
                 event_id,
                 target_user_id: 987654321,
             }),
             retry_count: 0,
+            created_at: Utc::now(),
         };
 
         // Attempt to process
@@ -236,7 +1272,18 @@ mod tests {
         let calendar = CalendarService::new(televent_storage::calendar::CalendarRepository::new(
             pool.clone(),
         ));
-        let result = process_message(&calendar, &message, &bot, &HashMap::new()).await;
+        let result = process_message(
+            &calendar,
+            &message,
+            &telegram,
+            None,
+            None,
+            None,
+            None,
+            None,
+            &HashMap::new(),
+        )
+        .await;
 
         // Assert error is present and related to Telegram API failure
         assert!(result.is_err());
@@ -0,0 +1,245 @@
+//! Web Push to the web app
+//!
+//! Notices the worker sends a user in Telegram are copied to each browser
+//! they subscribed as separate `web_push` messages, encrypted for that
+//! browser and signed with our VAPID key. Delivery is off unless the VAPID
+//! keys are configured.
+//!
+//! The service worker receives `{"title", "body", "tag"}` JSON; `tag` is
+//! the source message, so a browser shows each notice once.
+//!
+//! The `web-push` crate encrypts and signs each message. We send it with
+//! our own client rather than the crate's, so redirects stay off and
+//! refusals map onto the worker's retry policy.
+
+mod vapid;
+
+use anyhow::{Context, Result, anyhow};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::Utc;
+use reqwest::{StatusCode, header};
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
+use televent_application::{ChatNotice, UserId, WebPushService};
+use televent_domain::WebPushMessage;
+use tracing::info;
+use uuid::Uuid;
+use web_push::{ContentEncoding, SubscriptionInfo, WebPushError, WebPushMessageBuilder};
+
+use crate::{Deferred, Rejected};
+
+pub use vapid::VapidConfig;
+
+const REQUEST_TIMEOUT_SECS: u64 = 10;
+/// Wait applied when a push service rate-limits without saying for how long
+const DEFAULT_RATE_LIMIT_DELAY: Duration = Duration::from_secs(60);
+/// How long push services keep a message for a browser that is offline
+const TIME_TO_LIVE_SECS: u32 = 24 * 60 * 60;
+/// Longest title and body; anything longer is cut so a message stays under
+/// the 3052 bytes the `web-push` crate encrypts
+const MAX_TITLE_CHARS: usize = 120;
+const MAX_BODY_CHARS: usize = 600;
+
+/// Copies notices to browsers and pushes them
+#[derive(Clone)]
+pub struct PushSender {
+    http: reqwest::Client,
+    vapid: Arc<vapid::Vapid>,
+    subscriptions: WebPushService,
+}
+
+impl PushSender {
+    pub fn new(config: &VapidConfig, subscriptions: WebPushService) -> Result<Self> {
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS))
+            .user_agent(concat!("Televent/", env!("CARGO_PKG_VERSION")))
+            // Endpoint hosts are checked when the subscription is saved; a
+            // redirect could point anywhere
+            .redirect(reqwest::redirect::Policy::none())
+            .build()?;
+
+        Ok(Self {
+            http,
+            vapid: Arc::new(vapid::Vapid::new(config)?),
+            subscriptions,
+        })
+    }
+
+    /// Queue `notice`, rendered for outbox message `source_id`, for each of
+    /// the user's browsers
+    pub(crate) async fn copy(
+        &self,
+        user_id: i64,
+        source_id: Uuid,
+        notice: &ChatNotice,
+    ) -> Result<()> {
+        let queued = self
+            .subscriptions
+            .queue_copies(UserId::new(user_id), source_id, notice)
+            .await
+            .context("Failed to queue web push copies")?;
+        if queued > 0 {
+            info!("Queued {} web push copies of message {}", queued, source_id);
+        }
+        Ok(())
+    }
+
+    /// Push one copy; returns `false` when the subscription is gone, either
+    /// removed before or dropped now because the push service forgot it
+    pub(crate) async fn push(&self, payload: &WebPushMessage) -> Result<bool> {
+        let Some(target) = self.subscriptions.target(payload.subscription_id).await? else {
+            return Ok(false);
+        };
+        let subscription = SubscriptionInfo::new(
+            target.endpoint.clone(),
+            URL_SAFE_NO_PAD.encode(&target.p256dh),
+            URL_SAFE_NO_PAD.encode(&target.auth),
+        );
+        let content = push_payload(&payload.title, &payload.lines, payload.source_id);
+        let mut message = WebPushMessageBuilder::new(&subscription);
+        message.set_ttl(TIME_TO_LIVE_SECS);
+        message.set_payload(ContentEncoding::Aes128Gcm, &content);
+        message.set_vapid_signature(self.vapid.sign(&subscription)?);
+        let encrypted = match message.build() {
+            Ok(message) => message
+                .payload
+                .context("web-push built a message without a body")?,
+            Err(WebPushError::InvalidCryptoKeys) => {
+                return Err(Rejected("browser key is not a valid P-256 point".to_string()).into());
+            }
+            Err(e) => return Err(Rejected(format!("web push message not built: {e}")).into()),
+        };
+
+        let mut request = self
+            .http
+            .post(&target.endpoint)
+            .header(
+                header::CONTENT_ENCODING,
+                encrypted.content_encoding.to_str(),
+            )
+            .header(header::CONTENT_TYPE, "application/octet-stream")
+            .header("TTL", TIME_TO_LIVE_SECS);
+        // Authorization, plus Crypto-Key for the older encodings
+        for (name, value) in encrypted.crypto_headers {
+            request = request.header(name, value);
+        }
+        let response = request
+            .body(encrypted.content)
+            .send()
+            .await
+            .map_err(|e| anyhow!("Web push request failed: {e}"))?;
+        let status = response.status();
+        if status.is_success() {
+            self.subscriptions
+                .record_delivery(payload.subscription_id)
+                .await?;
+            return Ok(true);
+        }
+        if matches!(status, StatusCode::NOT_FOUND | StatusCode::GONE) {
+            self.subscriptions
+                .remove_subscription(payload.subscription_id)
+                .await?;
+            return Ok(false);
+        }
+
+        let retry_after = response
+            .headers()
+            .get(header::RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse().ok())
+            .map(Duration::from_secs);
+        let body = response.text().await.unwrap_or_default();
+        Err(classify(status, retry_after, body))
+    }
+}
+
+/// Plaintext the service worker gets
+fn push_payload(title: &str, lines: &[String], source_id: Uuid) -> Vec<u8> {
+    let body = lines.join("\n");
+    json!({
+        "title": truncate(title, MAX_TITLE_CHARS),
+        "body": truncate(&body, MAX_BODY_CHARS),
+        "tag": source_id,
+    })
+    .to_string()
+    .into_bytes()
+}
+
+fn truncate(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+    let mut cut: String = text.chars().take(max - 1).collect();
+    cut.push('…');
+    cut
+}
+
+/// Map a refused push onto the worker's retry policy
+///
+/// 429 reschedules without using up a retry. 401 and 403 mean our VAPID
+/// token was refused, which the operator has to fix, so they back off like
+/// outages; other 4xx answers refuse the message itself.
+fn classify(status: StatusCode, retry_after: Option<Duration>, body: String) -> anyhow::Error {
+    let body: String = body.chars().take(200).collect();
+    let message = format!("push service returned {status}: {body}");
+    match status {
+        StatusCode::TOO_MANY_REQUESTS => {
+            let delay = retry_after.unwrap_or(DEFAULT_RATE_LIMIT_DELAY);
+            Deferred {
+                until: Utc::now()
+                    + chrono::Duration::from_std(delay)
+                        .unwrap_or_else(|_| chrono::Duration::minutes(1)),
+                reason: format!("web push rate limit: {message}"),
+            }
+            .into()
+        }
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+            anyhow!("Web push delivery failed: {message}")
+        }
+        _ if status.is_client_error() => Rejected(message).into(),
+        _ => anyhow!("Web push delivery failed: {message}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_payload_is_cut_to_fit_one_record() {
+        let source_id = Uuid::new_v4();
+        let payload = push_payload(
+            "⏰ Reminder: Standup",
+            &[
+                "🕒 Time: Mon 1 Jun, 10:00".to_string(),
+                "📍 Room 4".to_string(),
+            ],
+            source_id,
+        );
+        let payload: serde_json::Value = serde_json::from_slice(&payload).unwrap();
+        assert_eq!(payload["title"], "⏰ Reminder: Standup");
+        assert_eq!(payload["body"], "🕒 Time: Mon 1 Jun, 10:00\n📍 Room 4");
+        assert_eq!(payload["tag"], source_id.to_string());
+
+        let long = push_payload(&"🎉".repeat(500), &["🎉".repeat(5000)], source_id);
+        assert!(long.len() <= 3052);
+    }
+
+    #[test]
+    fn test_refusals_follow_retry_policy() {
+        let limited = classify(StatusCode::TOO_MANY_REQUESTS, None, String::new());
+        assert!(limited.downcast_ref::<Deferred>().is_some());
+
+        for status in [StatusCode::BAD_REQUEST, StatusCode::PAYLOAD_TOO_LARGE] {
+            let refused = classify(status, None, String::new());
+            assert!(refused.downcast_ref::<Rejected>().is_some(), "{status}");
+        }
+
+        for status in [StatusCode::FORBIDDEN, StatusCode::BAD_GATEWAY] {
+            let retried = classify(status, None, String::new());
+            assert!(retried.downcast_ref::<Rejected>().is_none(), "{status}");
+            assert!(retried.downcast_ref::<Deferred>().is_none(), "{status}");
+        }
+    }
+}
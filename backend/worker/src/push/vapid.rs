@@ -0,0 +1,139 @@
+//! VAPID (RFC 8292): identifies us to push services with a signed JWT
+//!
+//! Browsers tie a subscription to the public key the web app subscribed
+//! with, so changing the key pair orphans every existing subscription.
+
+use anyhow::{Context, Result, anyhow, bail};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use std::env;
+use web_push::{PartialVapidSignatureBuilder, SubscriptionInfo, VapidSignatureBuilder};
+
+/// Application server keys, as `web-push generate-vapid-keys` prints them
+#[derive(Clone)]
+pub struct VapidConfig {
+    /// Uncompressed P-256 public key, base64url; the web app subscribes with it
+    pub public_key: String,
+    /// Raw 32-byte private key, base64url
+    pub private_key: String,
+    /// `mailto:` or `https:` contact push services can reach us at
+    pub subject: String,
+}
+
+impl std::fmt::Debug for VapidConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("VapidConfig")
+            .field("public_key", &self.public_key)
+            .field("subject", &self.subject)
+            .finish_non_exhaustive()
+    }
+}
+
+impl VapidConfig {
+    /// Load configuration from environment variables
+    ///
+    /// Returns `None` unless `VAPID_PUBLIC_KEY` and `VAPID_PRIVATE_KEY` are
+    /// set. The keys are checked to form a pair right away.
+    pub fn from_env() -> Result<Option<Self>> {
+        let public_key = env::var("VAPID_PUBLIC_KEY").unwrap_or_default();
+        let private_key = env::var("VAPID_PRIVATE_KEY").unwrap_or_default();
+        match (public_key.trim(), private_key.trim()) {
+            ("", "") => return Ok(None),
+            ("", _) | (_, "") => {
+                bail!("VAPID_PUBLIC_KEY and VAPID_PRIVATE_KEY must be set together")
+            }
+            _ => {}
+        }
+        let subject =
+            env::var("VAPID_SUBJECT").context("VAPID_SUBJECT must be set with the VAPID keys")?;
+        if !subject.starts_with("mailto:") && !subject.starts_with("https://") {
+            bail!("VAPID_SUBJECT must be a mailto: or https:// URL");
+        }
+
+        let config = Self {
+            public_key: public_key.trim().to_string(),
+            private_key: private_key.trim().to_string(),
+            subject,
+        };
+        Vapid::new(&config)?;
+        Ok(Some(config))
+    }
+}
+
+/// Signs tokens for push service origins
+///
+/// The `web-push` crate does the signing; this only checks that the
+/// configured keys form a pair and adds our `sub` claim.
+#[derive(Clone)]
+pub(super) struct Vapid {
+    signer: PartialVapidSignatureBuilder,
+    subject: String,
+}
+
+impl Vapid {
+    pub(super) fn new(config: &VapidConfig) -> Result<Self> {
+        let public_key = URL_SAFE_NO_PAD
+            .decode(config.public_key.trim_end_matches('='))
+            .context("VAPID_PUBLIC_KEY is not base64url")?;
+        let signer =
+            VapidSignatureBuilder::from_base64_no_sub(config.private_key.trim_end_matches('='))
+                .map_err(|e| anyhow!("VAPID_PRIVATE_KEY is not a P-256 key: {e}"))?;
+        if signer.get_public_key() != public_key {
+            bail!("VAPID keys do not form a P-256 key pair");
+        }
+
+        Ok(Self {
+            signer,
+            subject: config.subject.clone(),
+        })
+    }
+
+    /// Builder for a message to `subscription`, signed for its origin
+    pub(super) fn sign(&self, subscription: &SubscriptionInfo) -> Result<web_push::VapidSignature> {
+        let mut builder = self.signer.clone().add_sub_info(subscription);
+        builder.add_claim("sub", self.subject.as_str());
+        builder
+            .build()
+            .map_err(|e| anyhow!("Failed to sign VAPID token: {e}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PUBLIC_KEY: &str =
+        "BP4z9KsN6nGRTbVYI_c7VJSPQTBtkgcy27mlmlMoZIIgDll6e3vCYLocInmYWAmS6TlzAC8wEqKK6PBru3jl7A8";
+    const PRIVATE_KEY: &str = "yfWPiYE-n46HLnH0KqZOF1fJJU3MYrct3AELtAQ-oRw";
+
+    fn config(private_key: &str) -> VapidConfig {
+        VapidConfig {
+            public_key: PUBLIC_KEY.to_string(),
+            private_key: private_key.to_string(),
+            subject: "mailto:ops@example.com".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_token_is_signed_for_the_endpoint_origin() {
+        let vapid = Vapid::new(&config(PRIVATE_KEY)).unwrap();
+        let subscription = SubscriptionInfo::new(
+            "https://fcm.googleapis.com/fcm/send/abc",
+            "unused",
+            "unused",
+        );
+        let signature = vapid.sign(&subscription).unwrap();
+        assert_eq!(URL_SAFE_NO_PAD.encode(&signature.auth_k), PUBLIC_KEY);
+
+        let claims = signature.auth_t.split('.').nth(1).unwrap();
+        let claims: serde_json::Value =
+            serde_json::from_slice(&URL_SAFE_NO_PAD.decode(claims).unwrap()).unwrap();
+        assert_eq!(claims["aud"], "https://fcm.googleapis.com");
+        assert_eq!(claims["sub"], "mailto:ops@example.com");
+    }
+
+    #[test]
+    fn test_mismatched_keys_are_refused() {
+        assert!(Vapid::new(&config("q1dXpw3UpT5VOmu_cf_v6ih07Aems3njxI-JWgLcM94")).is_err());
+        assert!(Vapid::new(&config("not base64!")).is_err());
+    }
+}
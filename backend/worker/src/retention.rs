@@ -0,0 +1,289 @@
+//! Retention of old rows
+//!
+//! Periodically deletes what the deployment's retention policy no longer
+//! keeps, together with short-lived rows that are only useful until they
+//! expire: sent-email records outside the hourly cap window and Google
+//! sign-in states past their expiry.
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use televent_storage::StorageResult;
+use televent_storage::retention::RetentionRepository;
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info};
+
+use crate::config::RetentionPolicy;
+
+/// How often the retention task runs
+const RETENTION_TICK_SECS: u64 = 60 * 60;
+
+/// Rows deleted per statement
+const RETENTION_BATCH_SIZE: i64 = 1000;
+
+/// Data the retention task prunes, named as in its logs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Retained {
+    EventTombstones,
+    Outbox,
+    DeviceActivity,
+    EmailFeedback,
+    SlowQueryPlans,
+    EmailSends,
+    GoogleOauthStates,
+}
+
+impl Retained {
+    const ALL: [Self; 7] = [
+        Self::EventTombstones,
+        Self::Outbox,
+        Self::DeviceActivity,
+        Self::EmailFeedback,
+        Self::SlowQueryPlans,
+        Self::EmailSends,
+        Self::GoogleOauthStates,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::EventTombstones => "event_tombstones",
+            Self::Outbox => "outbox_messages",
+            Self::DeviceActivity => "device_activity",
+            Self::EmailFeedback => "email_feedback",
+            Self::SlowQueryPlans => "slow_query_plans",
+            Self::EmailSends => "email_sends",
+            Self::GoogleOauthStates => "google_oauth_states",
+        }
+    }
+
+    /// Rows older than this are deleted; `None` while the policy keeps them
+    fn cutoff(self, policy: &RetentionPolicy, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let days_ago = |days: Option<u32>| days.map(|days| now - Duration::days(i64::from(days)));
+        match self {
+            Self::EventTombstones => days_ago(policy.tombstone_days),
+            Self::Outbox => days_ago(policy.outbox_days),
+            Self::DeviceActivity | Self::EmailFeedback | Self::SlowQueryPlans => {
+                days_ago(policy.audit_log_days)
+            }
+            // Only the last hour counts towards the per-recipient cap
+            Self::EmailSends => Some(now - Duration::hours(1)),
+            Self::GoogleOauthStates => Some(now),
+        }
+    }
+
+    async fn prune(
+        self,
+        repo: &RetentionRepository,
+        cutoff: DateTime<Utc>,
+        batch_size: i64,
+    ) -> StorageResult<u64> {
+        match self {
+            Self::EventTombstones => repo.prune_event_tombstones(cutoff, batch_size).await,
+            Self::Outbox => repo.prune_outbox(cutoff, batch_size).await,
+            Self::DeviceActivity => repo.prune_device_activity(cutoff, batch_size).await,
+            Self::EmailFeedback => repo.prune_email_feedback(cutoff, batch_size).await,
+            Self::SlowQueryPlans => repo.prune_slow_query_plans(cutoff, batch_size).await,
+            Self::EmailSends => repo.prune_email_sends(cutoff, batch_size).await,
+            Self::GoogleOauthStates => repo.prune_google_oauth_states(cutoff, batch_size).await,
+        }
+    }
+}
+
+/// Run the retention loop until cancelled
+pub async fn run_retention(
+    repo: RetentionRepository,
+    policy: RetentionPolicy,
+    shutdown: Option<CancellationToken>,
+) -> Result<()> {
+    let shutdown = shutdown.unwrap_or_default();
+    info!(
+        "Starting retention: tick={}s, tombstones={:?}d, audit logs={:?}d, outbox={:?}d",
+        RETENTION_TICK_SECS, policy.tombstone_days, policy.audit_log_days, policy.outbox_days
+    );
+
+    loop {
+        apply_retention(&repo, &policy, Utc::now(), RETENTION_BATCH_SIZE, &shutdown).await;
+
+        tokio::select! {
+            () = shutdown.cancelled() => {
+                info!("Retention received shutdown signal");
+                return Ok(());
+            }
+            () = tokio::time::sleep(std::time::Duration::from_secs(RETENTION_TICK_SECS)) => {}
+        }
+    }
+}
+
+/// Delete everything past its retention in batches, logging how many rows
+/// each kind lost
+async fn apply_retention(
+    repo: &RetentionRepository,
+    policy: &RetentionPolicy,
+    now: DateTime<Utc>,
+    batch_size: i64,
+    shutdown: &CancellationToken,
+) {
+    for retained in Retained::ALL {
+        let Some(cutoff) = retained.cutoff(policy, now) else {
+            continue;
+        };
+
+        let mut pruned = 0;
+        while !shutdown.is_cancelled() {
+            match retained.prune(repo, cutoff, batch_size).await {
+                Ok(deleted) => {
+                    pruned += deleted;
+                    if deleted < batch_size as u64 {
+                        break;
+                    }
+                }
+                Err(e) => {
+                    error!("Failed to prune {}: {}", retained.name(), e);
+                    break;
+                }
+            }
+        }
+
+        if pruned > 0 {
+            info!("Retention pruned {} {} rows", pruned, retained.name());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::PgPool;
+
+    async fn insert_user(pool: &PgPool, telegram_id: i64) -> anyhow::Result<()> {
+        sqlx::query("INSERT INTO users (telegram_id, timezone) VALUES ($1, 'UTC')")
+            .bind(telegram_id)
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn insert_tombstone(
+        pool: &PgPool,
+        user_id: i64,
+        uid: &str,
+        sync_version: i64,
+        days_ago: i64,
+    ) -> anyhow::Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO event_tombstones (user_id, uid, sync_version, deleted_at)
+            VALUES ($1, $2, $3, NOW() - make_interval(days => $4::INT))
+            "#,
+        )
+        .bind(user_id)
+        .bind(uid)
+        .bind(sync_version)
+        .bind(days_ago)
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_cutoffs_follow_the_policy() {
+        let now = Utc::now();
+        let policy = RetentionPolicy {
+            tombstone_days: Some(90),
+            audit_log_days: None,
+            outbox_days: Some(14),
+        };
+
+        assert_eq!(
+            Retained::EventTombstones.cutoff(&policy, now),
+            Some(now - Duration::days(90))
+        );
+        assert_eq!(
+            Retained::Outbox.cutoff(&policy, now),
+            Some(now - Duration::days(14))
+        );
+        assert_eq!(Retained::SlowQueryPlans.cutoff(&policy, now), None);
+        assert_eq!(
+            Retained::EmailSends.cutoff(&policy, now),
+            Some(now - Duration::hours(1))
+        );
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_pruned_tombstones_raise_the_sync_watermark(pool: PgPool) -> anyhow::Result<()> {
+        insert_user(&pool, 10).await?;
+        insert_tombstone(&pool, 10, "old-1", 3, 100).await?;
+        insert_tombstone(&pool, 10, "old-2", 5, 95).await?;
+        insert_tombstone(&pool, 10, "recent", 8, 1).await?;
+
+        let policy = RetentionPolicy {
+            tombstone_days: Some(90),
+            audit_log_days: None,
+            outbox_days: None,
+        };
+        // A batch of one still works through the whole backlog
+        apply_retention(
+            &RetentionRepository::new(pool.clone()),
+            &policy,
+            Utc::now(),
+            1,
+            &CancellationToken::new(),
+        )
+        .await;
+
+        let remaining: Vec<String> =
+            sqlx::query_scalar("SELECT uid FROM event_tombstones ORDER BY uid")
+                .fetch_all(&pool)
+                .await?;
+        assert_eq!(remaining, vec!["recent".to_string()]);
+
+        let watermark: i64 = sqlx::query_scalar(
+            "SELECT tombstones_pruned_through FROM users WHERE telegram_id = 10",
+        )
+        .fetch_one(&pool)
+        .await?;
+        assert_eq!(watermark, 5);
+        Ok(())
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_only_finished_outbox_messages_are_pruned(pool: PgPool) -> anyhow::Result<()> {
+        for status in ["pending", "processing", "completed", "failed"] {
+            sqlx::query(
+                r#"
+                INSERT INTO outbox_messages (kind, payload, status)
+                VALUES ('telegram_notification', '{}', $1::outbox_status)
+                "#,
+            )
+            .bind(status)
+            .execute(&pool)
+            .await?;
+        }
+        sqlx::query("ALTER TABLE outbox_messages DISABLE TRIGGER outbox_messages_updated_at")
+            .execute(&pool)
+            .await?;
+        sqlx::query("UPDATE outbox_messages SET updated_at = NOW() - INTERVAL '30 days'")
+            .execute(&pool)
+            .await?;
+
+        let policy = RetentionPolicy {
+            tombstone_days: None,
+            audit_log_days: None,
+            outbox_days: Some(14),
+        };
+        apply_retention(
+            &RetentionRepository::new(pool.clone()),
+            &policy,
+            Utc::now(),
+            RETENTION_BATCH_SIZE,
+            &CancellationToken::new(),
+        )
+        .await;
+
+        let remaining: Vec<String> =
+            sqlx::query_scalar("SELECT status::text FROM outbox_messages ORDER BY status::text")
+                .fetch_all(&pool)
+                .await?;
+        assert_eq!(remaining, vec!["pending", "processing"]);
+        Ok(())
+    }
+}
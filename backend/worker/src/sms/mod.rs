@@ -0,0 +1,308 @@
+//! Outgoing text messages
+//!
+//! Invites, reminders, changes and cancellations are copied as `sms` outbox
+//! messages to users who confirmed a phone number and turned the topic on in
+//! their notification preferences; the same queue carries the codes that
+//! confirm a number. Delivery is off unless `SMS_PROVIDER` names a provider.
+//!
+//! Texts go out through an [`SmsProvider`]: the Twilio Messages API, or any
+//! service that speaks it, behind the `sms-twilio` feature.
+
+#[cfg(feature = "sms-twilio")]
+mod twilio;
+
+use anyhow::{Context, Result, bail};
+use std::env;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use televent_application::{ChatNotice, NotificationService, SMS_CODE_TTL_MINUTES, UserId};
+use televent_domain::{SmsContent, SmsMessage};
+use tracing::info;
+use uuid::Uuid;
+
+use crate::mailer::SendError;
+
+#[cfg(feature = "sms-twilio")]
+pub use twilio::TwilioConfig;
+
+/// Future returned by [`SmsProvider::send`]
+pub type SmsFuture<'a> = Pin<Box<dyn Future<Output = Result<(), SendError>> + Send + 'a>>;
+
+/// Something that can deliver a text message
+pub trait SmsProvider: Send + Sync {
+    /// Text `body` to `to`, an E.164 number
+    fn send<'a>(&'a self, to: &'a str, body: &'a str) -> SmsFuture<'a>;
+}
+
+/// SMS provider and its settings
+#[derive(Debug, Clone)]
+pub enum SmsConfig {
+    #[cfg(feature = "sms-twilio")]
+    Twilio(TwilioConfig),
+}
+
+impl SmsConfig {
+    /// Load configuration from environment variables
+    ///
+    /// Returns `None` unless `SMS_PROVIDER` is set.
+    pub fn from_env() -> Result<Option<Self>> {
+        match env::var("SMS_PROVIDER")
+            .unwrap_or_default()
+            .to_ascii_lowercase()
+            .as_str()
+        {
+            "" | "none" => Ok(None),
+            #[cfg(feature = "sms-twilio")]
+            "twilio" => Ok(Some(Self::Twilio(TwilioConfig::from_env()?))),
+            #[cfg(not(feature = "sms-twilio"))]
+            "twilio" => bail!("SMS_PROVIDER=twilio needs a build with the sms-twilio feature"),
+            other => bail!("SMS_PROVIDER must be twilio or none, got {other}"),
+        }
+    }
+
+    fn build(&self) -> Result<Arc<dyn SmsProvider>> {
+        match *self {
+            #[cfg(feature = "sms-twilio")]
+            Self::Twilio(ref config) => Ok(Arc::new(twilio::TwilioProvider::new(config)?)),
+        }
+    }
+}
+
+/// Copies notices to phones and texts them
+#[derive(Clone)]
+pub struct SmsSender {
+    provider: Arc<dyn SmsProvider>,
+    notifications: NotificationService,
+}
+
+impl SmsSender {
+    pub fn new(config: &SmsConfig, notifications: NotificationService) -> Result<Self> {
+        Ok(Self::with_provider(config.build()?, notifications))
+    }
+
+    /// Send through a provider of the caller's own
+    pub fn with_provider(
+        provider: Arc<dyn SmsProvider>,
+        notifications: NotificationService,
+    ) -> Self {
+        Self {
+            provider,
+            notifications,
+        }
+    }
+
+    /// Queue a text copy of `notice`, rendered for outbox message
+    /// `source_id`, if the user wants one
+    pub(crate) async fn copy(
+        &self,
+        user_id: i64,
+        source_id: Uuid,
+        notice: &ChatNotice,
+    ) -> Result<()> {
+        let queued = self
+            .notifications
+            .queue_sms_copy(UserId::new(user_id), source_id, notice)
+            .await
+            .context("Failed to queue text message copy")?;
+        if queued {
+            info!("Queued a text message copy of message {}", source_id);
+        }
+        Ok(())
+    }
+
+    /// Text one message; returns `false` when there is no longer a number to
+    /// send it to
+    pub(crate) async fn send(&self, payload: &SmsMessage) -> Result<bool> {
+        let user_id = UserId::new(payload.user_id);
+        let (to, body) = match &payload.content {
+            SmsContent::Notice { text, .. } => {
+                let Some(to) = self.notifications.verified_phone(user_id).await? else {
+                    return Ok(false);
+                };
+                (to, text.clone())
+            }
+            SmsContent::VerificationCode { phone_number } => {
+                let Some(code) = self
+                    .notifications
+                    .issue_verification_code(user_id, phone_number)
+                    .await?
+                else {
+                    return Ok(false);
+                };
+                (phone_number.clone(), verification_text(&code))
+            }
+        };
+
+        self.provider
+            .send(&to, &body)
+            .await
+            .map_err(|e| e.into_job_error("SMS"))?;
+        Ok(true)
+    }
+}
+
+fn verification_text(code: &str) -> String {
+    format!(
+        "Your Televent code is {code}. It expires in {SMS_CODE_TTL_MINUTES} minutes. \
+         If you didn't ask for it, ignore this message."
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::PgPool;
+    use std::sync::Mutex;
+    use televent_application::{NotificationPreference, NotificationTopic};
+    use televent_domain::{NotificationChannel, OutboxPayload};
+    use televent_storage::notification::NotificationRepository;
+
+    /// Keeps every text instead of sending it
+    #[derive(Default)]
+    struct Outbox(Mutex<Vec<(String, String)>>);
+
+    impl SmsProvider for Outbox {
+        fn send<'a>(&'a self, to: &'a str, body: &'a str) -> SmsFuture<'a> {
+            self.0
+                .lock()
+                .unwrap()
+                .push((to.to_string(), body.to_string()));
+            Box::pin(async { Ok(()) })
+        }
+    }
+
+    async fn queued_sms(pool: &PgPool) -> Vec<SmsMessage> {
+        let rows: Vec<(String, serde_json::Value)> =
+            sqlx::query_as("SELECT kind, payload FROM outbox_messages WHERE kind = 'sms'")
+                .fetch_all(pool)
+                .await
+                .unwrap();
+        rows.into_iter()
+            .map(
+                |(kind, payload)| match OutboxPayload::from_parts(&kind, payload) {
+                    Ok(OutboxPayload::Sms(message)) => message,
+                    other => panic!("unexpected outbox row {other:?}"),
+                },
+            )
+            .collect()
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_phone_verification_and_notice_copies(pool: PgPool) {
+        let notifications = NotificationService::new(NotificationRepository::new(pool.clone()));
+        let provider = Arc::new(Outbox::default());
+        let sms = SmsSender::with_provider(provider.clone(), notifications.clone());
+        let user = UserId::new(4242);
+
+        let phone = notifications
+            .request_phone_verification(user, "+1 (555) 010-2030")
+            .await
+            .unwrap();
+        assert_eq!(phone.phone_number, "+15550102030");
+        assert!(phone.verified_at.is_none());
+        // Asking again right away is throttled
+        assert!(
+            notifications
+                .request_phone_verification(user, "+15550102030")
+                .await
+                .is_err()
+        );
+
+        // The code is minted as the text goes out
+        let queued = queued_sms(&pool).await;
+        assert_eq!(queued.len(), 1);
+        assert!(sms.send(&queued[0]).await.unwrap());
+        let (to, body) = provider.0.lock().unwrap()[0].clone();
+        assert_eq!(to, "+15550102030");
+        let code: String = body.chars().filter(char::is_ascii_digit).take(6).collect();
+
+        assert!(notifications.verify_phone(user, "000000x").await.is_err());
+        let phone = notifications.verify_phone(user, &code).await.unwrap();
+        assert!(phone.verified_at.is_some());
+        // A confirmed number gets no further codes
+        assert!(!sms.send(&queued[0]).await.unwrap());
+
+        let notice = ChatNotice {
+            topic: NotificationTopic::Cancellation,
+            title: "❌ Cancelled: Standup".to_string(),
+            lines: vec!["The organizer called this event off.".to_string()],
+        };
+        // SMS is opt-in per topic
+        sms.copy(user.inner(), Uuid::new_v4(), &notice)
+            .await
+            .unwrap();
+        assert_eq!(queued_sms(&pool).await.len(), 1);
+
+        notifications
+            .set_preferences(
+                user,
+                &[NotificationPreference {
+                    topic: NotificationTopic::Cancellation,
+                    channel: NotificationChannel::Sms,
+                    enabled: true,
+                }],
+            )
+            .await
+            .unwrap();
+        let source_id = Uuid::new_v4();
+        sms.copy(user.inner(), source_id, &notice).await.unwrap();
+        // Copies of the same source are queued once
+        sms.copy(user.inner(), source_id, &notice).await.unwrap();
+        let copy = queued_sms(&pool)
+            .await
+            .into_iter()
+            .find(|message| matches!(message.content, SmsContent::Notice { .. }))
+            .unwrap();
+        assert_eq!(queued_sms(&pool).await.len(), 2);
+        assert!(sms.send(&copy).await.unwrap());
+        assert_eq!(
+            provider.0.lock().unwrap()[1],
+            (
+                "+15550102030".to_string(),
+                "❌ Cancelled: Standup\nThe organizer called this event off.".to_string()
+            )
+        );
+
+        // Removing the number stops the texts
+        assert!(notifications.remove_phone(user).await.unwrap());
+        assert!(!sms.send(&copy).await.unwrap());
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_sealed_phone_numbers_stay_unique(pool: PgPool) {
+        let cipher = televent_storage::security::ColumnCipher::new(&[3; 32], None);
+        let notifications =
+            NotificationService::new(NotificationRepository::new(pool.clone()).with_cipher(cipher));
+        let provider = Arc::new(Outbox::default());
+        let sms = SmsSender::with_provider(provider.clone(), notifications.clone());
+        let owner = UserId::new(4242);
+
+        notifications
+            .request_phone_verification(owner, "+15550102030")
+            .await
+            .unwrap();
+        assert!(sms.send(&queued_sms(&pool).await[0]).await.unwrap());
+        let (to, body) = provider.0.lock().unwrap()[0].clone();
+        assert_eq!(to, "+15550102030");
+        let code: String = body.chars().filter(char::is_ascii_digit).take(6).collect();
+        let phone = notifications.verify_phone(owner, &code).await.unwrap();
+        assert_eq!(phone.phone_number, "+15550102030");
+
+        let stored: String =
+            sqlx::query_scalar("SELECT phone_number FROM phone_numbers WHERE user_id = $1")
+                .bind(owner.inner())
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert!(!stored.contains("5550102030"));
+
+        // Found through the digest although the sealed values differ
+        assert!(
+            notifications
+                .request_phone_verification(UserId::new(4343), "+15550102030")
+                .await
+                .is_err()
+        );
+    }
+}
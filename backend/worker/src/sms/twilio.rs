@@ -0,0 +1,178 @@
+//! Twilio Messages API provider
+//!
+//! Posts to `Messages.json` of the account. Services that mirror the Twilio
+//! API, such as SignalWire, work by pointing `TWILIO_API_BASE` at them.
+
+use anyhow::{Context, Result};
+use reqwest::{StatusCode, header};
+use std::env;
+use std::time::Duration;
+
+use super::{SmsFuture, SmsProvider};
+use crate::mailer::SendError;
+
+const REQUEST_TIMEOUT_SECS: u64 = 30;
+
+/// Account credentials and sender
+#[derive(Clone)]
+pub struct TwilioConfig {
+    pub account_sid: String,
+    pub auth_token: String,
+    /// Sending number in E.164 form, or a Messaging Service SID (`MG…`)
+    pub from: String,
+    /// `https://api.twilio.com`, or the base URL of a compatible service
+    pub api_base: String,
+}
+
+impl std::fmt::Debug for TwilioConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TwilioConfig")
+            .field("account_sid", &self.account_sid)
+            .field("from", &self.from)
+            .field("api_base", &self.api_base)
+            .finish_non_exhaustive()
+    }
+}
+
+impl TwilioConfig {
+    pub(super) fn from_env() -> Result<Self> {
+        Ok(Self {
+            account_sid: env::var("TWILIO_ACCOUNT_SID")
+                .context("TWILIO_ACCOUNT_SID must be set with SMS_PROVIDER=twilio")?,
+            auth_token: env::var("TWILIO_AUTH_TOKEN")
+                .context("TWILIO_AUTH_TOKEN must be set with SMS_PROVIDER=twilio")?,
+            from: env::var("TWILIO_FROM")
+                .context("TWILIO_FROM must be set with SMS_PROVIDER=twilio")?,
+            api_base: env::var("TWILIO_API_BASE")
+                .unwrap_or_else(|_| "https://api.twilio.com".to_string())
+                .trim_end_matches('/')
+                .to_string(),
+        })
+    }
+}
+
+pub(super) struct TwilioProvider {
+    http: reqwest::Client,
+    config: TwilioConfig,
+}
+
+impl TwilioProvider {
+    pub(super) fn new(config: &TwilioConfig) -> Result<Self> {
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS))
+            .user_agent(concat!("Televent/", env!("CARGO_PKG_VERSION")))
+            .build()?;
+
+        Ok(Self {
+            http,
+            config: config.clone(),
+        })
+    }
+
+    async fn post_message(&self, to: &str, body: &str) -> Result<(), SendError> {
+        let response = self
+            .http
+            .post(format!(
+                "{}/2010-04-01/Accounts/{}/Messages.json",
+                self.config.api_base, self.config.account_sid
+            ))
+            .basic_auth(&self.config.account_sid, Some(&self.config.auth_token))
+            .form(&message_form(&self.config.from, to, body))
+            .send()
+            .await
+            .map_err(|e| SendError::Transient(format!("Twilio request failed: {e}")))?;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(());
+        }
+
+        let retry_after = response
+            .headers()
+            .get(header::RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse().ok())
+            .map(Duration::from_secs);
+        let body = response.text().await.unwrap_or_default();
+        Err(classify(status, retry_after, body))
+    }
+}
+
+impl SmsProvider for TwilioProvider {
+    fn send<'a>(&'a self, to: &'a str, body: &'a str) -> SmsFuture<'a> {
+        Box::pin(self.post_message(to, body))
+    }
+}
+
+/// Form fields of a message; a Messaging Service picks the sending number
+fn message_form<'a>(from: &'a str, to: &'a str, body: &'a str) -> [(&'static str, &'a str); 3] {
+    let sender = if from.starts_with("MG") {
+        "MessagingServiceSid"
+    } else {
+        "From"
+    };
+    [("To", to), (sender, from), ("Body", body)]
+}
+
+/// 429 reschedules, other 4xx answers refuse the message itself (an invalid
+/// or unreachable number), except credential problems, which wait for the
+/// operator like outages.
+fn classify(status: StatusCode, retry_after: Option<Duration>, body: String) -> SendError {
+    let body: String = body.chars().take(200).collect();
+    let message = format!("Twilio returned {status}: {body}");
+    match status {
+        StatusCode::TOO_MANY_REQUESTS => SendError::RateLimited {
+            retry_after,
+            message,
+        },
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN | StatusCode::NOT_FOUND => {
+            SendError::Transient(message)
+        }
+        _ if status.is_client_error() => SendError::Permanent(message),
+        _ => SendError::Transient(message),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_message_form_picks_sender_field() {
+        assert_eq!(
+            message_form("+15550001111", "+15550102030", "hi"),
+            [
+                ("To", "+15550102030"),
+                ("From", "+15550001111"),
+                ("Body", "hi")
+            ]
+        );
+        assert_eq!(
+            message_form("MG0123456789abcdef", "+15550102030", "hi")[1],
+            ("MessagingServiceSid", "MG0123456789abcdef")
+        );
+    }
+
+    #[test]
+    fn test_classify_twilio_errors() {
+        assert!(matches!(
+            classify(StatusCode::TOO_MANY_REQUESTS, None, String::new()),
+            SendError::RateLimited { .. }
+        ));
+        assert!(matches!(
+            classify(
+                StatusCode::BAD_REQUEST,
+                None,
+                "21211 invalid To".to_string()
+            ),
+            SendError::Permanent(_)
+        ));
+        assert!(matches!(
+            classify(StatusCode::UNAUTHORIZED, None, String::new()),
+            SendError::Transient(_)
+        ));
+        assert!(matches!(
+            classify(StatusCode::SERVICE_UNAVAILABLE, None, String::new()),
+            SendError::Transient(_)
+        ));
+    }
+}
@@ -0,0 +1,346 @@
+//! Refresh of subscribed remote calendars
+//!
+//! Periodically fetches due ICS subscriptions with conditional GETs and hands
+//! the feed to the application service, which mirrors the events. Built-in
+//! holiday calendars are regenerated instead of fetched, once a year, and
+//! birthdays calendars every morning from the user's contacts.
+
+use anyhow::{Context, Result, bail};
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use reqwest::{StatusCode, header, redirect};
+use std::future::Future;
+use std::io;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use televent_application::{
+    FetchedFeed, SubscriptionFetch, SubscriptionService, holiday_country_for_url, is_birthdays_url,
+    is_public_ip, normalize_subscription_url,
+};
+use tokio::time::Duration;
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, warn};
+
+/// How often the refresh task looks for due subscriptions
+const REFRESH_TICK_SECS: u64 = 60;
+
+/// Subscriptions fetched per tick
+const REFRESH_BATCH_SIZE: i64 = 20;
+
+/// Largest feed accepted (5 MB)
+const MAX_FEED_BYTES: usize = 5 * 1024 * 1024;
+
+const FETCH_TIMEOUT_SECS: u64 = 30;
+const MAX_REDIRECTS: usize = 5;
+
+/// Outcome of a conditional fetch
+enum FetchOutcome {
+    NotModified,
+    Feed(FetchedFeed),
+}
+
+/// Run the subscription refresh loop until cancelled
+pub async fn run_subscription_refresh(
+    subscriptions: SubscriptionService,
+    shutdown: Option<CancellationToken>,
+) -> Result<()> {
+    let client = http_client()?;
+    let shutdown = shutdown.unwrap_or_default();
+    info!("Starting subscription refresh: tick={}s", REFRESH_TICK_SECS);
+
+    loop {
+        match subscriptions.due_subscriptions(REFRESH_BATCH_SIZE).await {
+            Ok(due) => {
+                for subscription in due {
+                    if shutdown.is_cancelled() {
+                        break;
+                    }
+                    refresh_subscription(&subscriptions, &client, subscription).await;
+                }
+            }
+            Err(e) => error!("Failed to load due subscriptions: {}", e),
+        }
+
+        tokio::select! {
+            () = shutdown.cancelled() => {
+                info!("Subscription refresh received shutdown signal");
+                return Ok(());
+            }
+            () = tokio::time::sleep(Duration::from_secs(REFRESH_TICK_SECS)) => {}
+        }
+    }
+}
+
+async fn refresh_subscription(
+    subscriptions: &SubscriptionService,
+    client: &reqwest::Client,
+    subscription: SubscriptionFetch,
+) {
+    let id = subscription.id;
+    if let Some(country) = holiday_country_for_url(&subscription.url) {
+        let result = match subscriptions.apply_holidays(id, country).await {
+            Ok(applied) => {
+                info!(
+                    "Holiday calendar {} ({}) regenerated: {} events, changed={}",
+                    id, country.code, applied.imported, applied.changed
+                );
+                Ok(())
+            }
+            Err(e) => {
+                warn!("Holiday calendar {} not regenerated: {}", id, e);
+                subscriptions.record_fetch_failure(id, &e.to_string()).await
+            }
+        };
+        if let Err(e) = result {
+            error!("Failed to record refresh of subscription {}: {}", id, e);
+        }
+        return;
+    }
+
+    if is_birthdays_url(&subscription.url) {
+        let result = match subscriptions.apply_birthdays(id).await {
+            Ok(applied) => {
+                info!(
+                    "Birthdays calendar {} regenerated: {} events, changed={}",
+                    id, applied.imported, applied.changed
+                );
+                Ok(())
+            }
+            Err(e) => {
+                warn!("Birthdays calendar {} not regenerated: {}", id, e);
+                subscriptions.record_fetch_failure(id, &e.to_string()).await
+            }
+        };
+        if let Err(e) = result {
+            error!("Failed to record refresh of subscription {}: {}", id, e);
+        }
+        return;
+    }
+
+    let result = match fetch_feed(client, &subscription).await {
+        Ok(FetchOutcome::NotModified) => subscriptions.record_not_modified(id).await,
+        Ok(FetchOutcome::Feed(feed)) => match subscriptions.apply_feed(id, feed).await {
+            Ok(applied) => {
+                info!(
+                    "Subscription {} refreshed: {} events, {} skipped, changed={}",
+                    id, applied.imported, applied.skipped, applied.changed
+                );
+                Ok(())
+            }
+            Err(e) => {
+                warn!("Subscription {} feed rejected: {}", id, e);
+                subscriptions.record_fetch_failure(id, &e.to_string()).await
+            }
+        },
+        Err(e) => {
+            warn!("Subscription {} fetch failed: {:#}", id, e);
+            subscriptions
+                .record_fetch_failure(id, &format!("{e:#}"))
+                .await
+        }
+    };
+
+    if let Err(e) = result {
+        error!("Failed to record refresh of subscription {}: {}", id, e);
+    }
+}
+
+async fn fetch_feed(
+    client: &reqwest::Client,
+    subscription: &SubscriptionFetch,
+) -> Result<FetchOutcome> {
+    let mut request = client
+        .get(&subscription.url)
+        .header(header::ACCEPT, "text/calendar, */*;q=0.5");
+    if let Some(etag) = &subscription.http_etag {
+        request = request.header(header::IF_NONE_MATCH, etag);
+    }
+    if let Some(last_modified) = &subscription.http_last_modified {
+        request = request.header(header::IF_MODIFIED_SINCE, last_modified);
+    }
+
+    let mut response = request.send().await.context("request failed")?;
+    if response.status() == StatusCode::NOT_MODIFIED {
+        return Ok(FetchOutcome::NotModified);
+    }
+    if !response.status().is_success() {
+        bail!("server answered {}", response.status());
+    }
+    if response
+        .content_length()
+        .is_some_and(|length| length > MAX_FEED_BYTES as u64)
+    {
+        bail!("feed is larger than {} bytes", MAX_FEED_BYTES);
+    }
+
+    let header_value = |name: header::HeaderName| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string)
+    };
+    let http_etag = header_value(header::ETAG);
+    let http_last_modified = header_value(header::LAST_MODIFIED);
+
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await.context("reading feed failed")? {
+        if body.len() + chunk.len() > MAX_FEED_BYTES {
+            bail!("feed is larger than {} bytes", MAX_FEED_BYTES);
+        }
+        body.extend_from_slice(&chunk);
+    }
+    let body = String::from_utf8(body).context("feed is not valid UTF-8")?;
+
+    Ok(FetchOutcome::Feed(FetchedFeed {
+        body,
+        http_etag,
+        http_last_modified,
+    }))
+}
+
+type LookupFuture = Pin<Box<dyn Future<Output = io::Result<Vec<SocketAddr>>> + Send>>;
+
+/// DNS resolver that drops loopback, private and other non-public addresses.
+///
+/// The check runs when reqwest connects, for the first request and for every
+/// redirect, so a host cannot pass a separate lookup and then rebind.
+#[derive(Clone, Copy)]
+struct PublicOnlyResolver {
+    lookup: fn(String) -> LookupFuture,
+}
+
+impl PublicOnlyResolver {
+    fn system() -> Self {
+        Self {
+            lookup: |host| {
+                Box::pin(
+                    async move { Ok(tokio::net::lookup_host((host.as_str(), 0)).await?.collect()) },
+                )
+            },
+        }
+    }
+}
+
+impl Resolve for PublicOnlyResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let lookup = (self.lookup)(name.as_str().to_string());
+        Box::pin(async move {
+            let addrs: Vec<SocketAddr> = lookup
+                .await?
+                .into_iter()
+                .filter(|addr| is_public_ip(addr.ip()))
+                .collect();
+            if addrs.is_empty() {
+                return Err(io::Error::other("host has no public address").into());
+            }
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+fn http_client() -> Result<reqwest::Client> {
+    http_client_builder(PublicOnlyResolver::system())
+        .build()
+        .context("failed to build HTTP client")
+}
+
+fn http_client_builder(resolver: PublicOnlyResolver) -> reqwest::ClientBuilder {
+    reqwest::Client::builder()
+        .timeout(Duration::from_secs(FETCH_TIMEOUT_SECS))
+        .user_agent(concat!("Televent/", env!("CARGO_PKG_VERSION")))
+        // A proxy would resolve names itself, past the resolver below
+        .no_proxy()
+        .dns_resolver(Arc::new(resolver))
+        .redirect(redirect::Policy::custom(|attempt| {
+            if attempt.previous().len() >= MAX_REDIRECTS {
+                attempt.error("too many redirects")
+            } else if normalize_subscription_url(attempt.url().as_str()).is_err() {
+                attempt.error("redirect to a non-public URL")
+            } else {
+                attempt.follow()
+            }
+        }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::{IpAddr, Ipv4Addr};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// Resolves `*.private.test` to private addresses and anything else to a
+    /// public and a private one
+    fn fake_resolver() -> PublicOnlyResolver {
+        PublicOnlyResolver {
+            lookup: |host| {
+                Box::pin(async move {
+                    let ip = |a, b, c, d| SocketAddr::new(IpAddr::V4(Ipv4Addr::new(a, b, c, d)), 0);
+                    Ok(if host.ends_with("private.test") {
+                        vec![ip(127, 0, 0, 1), ip(169, 254, 169, 254)]
+                    } else {
+                        vec![ip(10, 0, 0, 1), ip(93, 184, 215, 14)]
+                    })
+                })
+            },
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resolver_drops_non_public_addresses() {
+        let resolver = fake_resolver();
+
+        let addrs: Vec<SocketAddr> = resolver
+            .resolve("calendar.example.com".parse().unwrap())
+            .await
+            .unwrap()
+            .collect();
+        assert_eq!(addrs.len(), 1);
+        assert!(is_public_ip(addrs[0].ip()));
+
+        assert!(
+            resolver
+                .resolve("feed.private.test".parse().unwrap())
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_redirect_to_private_host_is_not_followed() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let hits = Arc::new(AtomicUsize::new(0));
+        let server_hits = hits.clone();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                server_hits.fetch_add(1, Ordering::SeqCst);
+                let mut request = [0u8; 1024];
+                let _ = socket.read(&mut request).await;
+                let response = format!(
+                    "HTTP/1.1 302 Found\r\nLocation: http://feed.private.test:{port}/cal.ics\r\n\
+                     Content-Length: 0\r\nConnection: close\r\n\r\n"
+                );
+                let _ = socket.write_all(response.as_bytes()).await;
+            }
+        });
+
+        // The first hop is pinned to the local server; the redirect target
+        // goes through the filtering resolver
+        let client = http_client_builder(fake_resolver())
+            .resolve(
+                "feed.example.com",
+                SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), port),
+            )
+            .build()
+            .unwrap();
+        let result = client
+            .get(format!("http://feed.example.com:{port}/cal.ics"))
+            .send()
+            .await;
+
+        assert!(result.is_err());
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,227 @@
+//! Rate-limited Telegram sender
+//!
+//! All worker messages go through one token bucket so a burst of jobs stays
+//! under Telegram's global limit. A 429 pauses the whole bucket for the
+//! `retry_after` Telegram asks for and the request is sent again; a group
+//! that became a supergroup is retried under its new chat id, which later
+//! sends to the old id go to directly. Neither counts against the job's
+//! retries.
+
+use anyhow::Result;
+use chrono::Utc;
+use std::collections::HashMap;
+use std::sync::Arc;
+use teloxide::Bot;
+use teloxide::RequestError;
+use teloxide::requests::{Output, Request};
+use teloxide::types::ChatId;
+use tokio::sync::Mutex;
+use tokio::time::{Duration, Instant};
+use tracing::warn;
+
+use crate::Deferred;
+
+/// Telegram allows about 30 messages per second across all chats
+const MESSAGES_PER_SECOND: f64 = 25.0;
+/// Longest `retry_after` waited out in place; longer ones reschedule the job
+const MAX_INLINE_WAIT: Duration = Duration::from_secs(30);
+/// Attempts per send before the job goes back to the queue
+const MAX_ATTEMPTS: u32 = 5;
+
+/// Shared handle for sending Telegram messages from worker jobs
+#[derive(Clone)]
+pub struct TelegramSender {
+    bot: Bot,
+    bucket: Arc<Mutex<TokenBucket>>,
+    /// Chats Telegram reported as migrated, old id to new
+    migrated: Arc<std::sync::Mutex<HashMap<ChatId, ChatId>>>,
+}
+
+impl TelegramSender {
+    pub fn new(bot: Bot) -> Self {
+        Self {
+            bot,
+            bucket: Arc::new(Mutex::new(TokenBucket::new(
+                MESSAGES_PER_SECOND,
+                Instant::now(),
+            ))),
+            migrated: Arc::default(),
+        }
+    }
+
+    /// Send the request built for `chat_id`
+    ///
+    /// `build` is called again for every attempt, with the migrated chat id
+    /// once Telegram reports one. Fails with [`Deferred`] when Telegram asks
+    /// for a longer pause than the worker waits out in place.
+    pub async fn send<R, F>(&self, chat_id: ChatId, build: F) -> Result<Output<R>>
+    where
+        R: Request<Err = RequestError>,
+        F: Fn(&Bot, ChatId) -> R,
+    {
+        let mut chat_id = self.current_chat_id(chat_id);
+        let mut attempt = 1;
+        loop {
+            self.acquire().await;
+            let err = match build(&self.bot, chat_id).send().await {
+                Ok(output) => return Ok(output),
+                Err(err) => err,
+            };
+
+            match err {
+                RequestError::RetryAfter(seconds) => {
+                    let delay = seconds.duration();
+                    self.pause(delay).await;
+                    if delay > MAX_INLINE_WAIT || attempt >= MAX_ATTEMPTS {
+                        return Err(Deferred {
+                            until: Utc::now() + seconds.chrono_duration(),
+                            reason: format!("Telegram asked to retry after {}s", seconds.seconds()),
+                        }
+                        .into());
+                    }
+                    warn!(
+                        "Telegram rate limit hit, retrying in {}s",
+                        seconds.seconds()
+                    );
+                }
+                RequestError::MigrateToChatId(new_id) if attempt < MAX_ATTEMPTS => {
+                    warn!("Chat {} migrated to {}, retrying", chat_id, new_id);
+                    self.lock_migrated().insert(chat_id, new_id);
+                    chat_id = new_id;
+                }
+                err => return Err(err.into()),
+            }
+            attempt += 1;
+        }
+    }
+
+    /// Where messages for `chat_id` go now, following recorded migrations
+    fn current_chat_id(&self, chat_id: ChatId) -> ChatId {
+        let migrated = self.lock_migrated();
+        let mut current = chat_id;
+        // A chat migrates at most once, but guard against a cycle anyway
+        for _ in 0..MAX_ATTEMPTS {
+            match migrated.get(&current) {
+                Some(&next) => current = next,
+                None => break,
+            }
+        }
+        current
+    }
+
+    fn lock_migrated(&self) -> std::sync::MutexGuard<'_, HashMap<ChatId, ChatId>> {
+        self.migrated
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    async fn acquire(&self) {
+        let mut bucket = self.bucket.lock().await;
+        // Holding the lock while waiting keeps sends in order
+        while let Some(wait) = bucket.take(Instant::now()) {
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    async fn pause(&self, delay: Duration) {
+        self.bucket.lock().await.pause(Instant::now() + delay);
+    }
+}
+
+/// Token bucket refilled continuously at `rate` tokens per second
+#[derive(Debug)]
+struct TokenBucket {
+    rate: f64,
+    tokens: f64,
+    refilled_at: Instant,
+    paused_until: Option<Instant>,
+}
+
+impl TokenBucket {
+    fn new(rate: f64, now: Instant) -> Self {
+        Self {
+            rate,
+            tokens: rate,
+            refilled_at: now,
+            paused_until: None,
+        }
+    }
+
+    /// Take a token, or return how long to wait before asking again
+    fn take(&mut self, now: Instant) -> Option<Duration> {
+        if let Some(until) = self.paused_until {
+            if until > now {
+                return Some(until - now);
+            }
+            self.paused_until = None;
+        }
+
+        let elapsed = now
+            .saturating_duration_since(self.refilled_at)
+            .as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.rate);
+        self.refilled_at = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            None
+        } else {
+            Some(Duration::from_secs_f64((1.0 - self.tokens) / self.rate))
+        }
+    }
+
+    /// Stop handing out tokens until `until`, keeping a later pause, and
+    /// start refilling from empty once it ends
+    fn pause(&mut self, until: Instant) {
+        if self.paused_until.is_none_or(|current| current < until) {
+            self.paused_until = Some(until);
+            self.refilled_at = until;
+        }
+        self.tokens = 0.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bucket_allows_burst_then_throttles() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(2.0, start);
+
+        assert_eq!(bucket.take(start), None);
+        assert_eq!(bucket.take(start), None);
+        let wait = bucket.take(start).expect("bucket should be empty");
+        assert_eq!(wait, Duration::from_millis(500));
+
+        assert_eq!(bucket.take(start + wait), None);
+    }
+
+    #[test]
+    fn test_bucket_pause_blocks_until_retry_after() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(10.0, start);
+
+        bucket.pause(start + Duration::from_secs(3));
+        bucket.pause(start + Duration::from_secs(1));
+
+        assert_eq!(bucket.take(start), Some(Duration::from_secs(3)));
+        let after = start + Duration::from_secs(3);
+        assert_eq!(bucket.take(after), Some(Duration::from_millis(100)));
+        assert_eq!(bucket.take(after + Duration::from_millis(100)), None);
+    }
+
+    #[test]
+    fn test_migrated_chats_resolve_to_their_new_id() {
+        let sender = TelegramSender::new(Bot::new("123:token"));
+        let group = ChatId(-42);
+        let supergroup = ChatId(-1_000_000_042);
+        assert_eq!(sender.current_chat_id(group), group);
+
+        sender.lock_migrated().insert(group, supergroup);
+        assert_eq!(sender.current_chat_id(group), supergroup);
+        assert_eq!(sender.current_chat_id(supergroup), supergroup);
+        assert_eq!(sender.current_chat_id(ChatId(7)), ChatId(7));
+    }
+}
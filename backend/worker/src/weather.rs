@@ -0,0 +1,289 @@
+//! Forecast line for upcoming events
+//!
+//! Events starting within the next 48 hours whose location carries
+//! coordinates (a `geo:` URI or a bare `lat, lon` pair, as calendar apps
+//! write for dropped pins) get a one-line forecast from an Open-Meteo
+//! compatible API. Forecasts are cached per place and hour; a failed lookup
+//! only drops the line, never the message.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, DurationRound, NaiveTime, Utc};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::env;
+use std::sync::Arc;
+use televent_application::EventView;
+use televent_domain::EventTiming;
+use tokio::sync::Mutex;
+use tracing::warn;
+
+const REQUEST_TIMEOUT_SECS: u64 = 10;
+const FORECAST_HORIZON_HOURS: i64 = 48;
+
+/// Forecast provider settings
+#[derive(Debug, Clone)]
+pub struct WeatherConfig {
+    /// Base URL of an Open-Meteo compatible forecast API
+    pub api_base: String,
+    /// How long a fetched forecast is reused
+    pub cache_ttl_secs: u64,
+}
+
+impl WeatherConfig {
+    /// Load configuration from environment variables
+    ///
+    /// Returns `None` unless `ENABLE_WEATHER` is true.
+    pub fn from_env() -> Result<Option<Self>> {
+        let enabled = env::var("ENABLE_WEATHER").is_ok_and(|value| {
+            matches!(
+                value.to_ascii_lowercase().as_str(),
+                "1" | "true" | "yes" | "on"
+            )
+        });
+        if !enabled {
+            return Ok(None);
+        }
+
+        Ok(Some(Self {
+            api_base: env::var("WEATHER_API_BASE")
+                .unwrap_or_else(|_| "https://api.open-meteo.com".to_string())
+                .trim_end_matches('/')
+                .to_string(),
+            cache_ttl_secs: env::var("WEATHER_CACHE_SECS")
+                .unwrap_or_else(|_| "3600".to_string())
+                .parse()
+                .context("WEATHER_CACHE_SECS must be a valid integer")?,
+        }))
+    }
+}
+
+/// Place rounded to about a kilometre, and the hour of the forecast
+type CacheKey = (i32, i32, i64);
+/// When the forecast was fetched, and its line (`None` when there was no data)
+type CacheEntry = (DateTime<Utc>, Option<String>);
+
+/// Cached forecast lookups
+#[derive(Clone)]
+pub struct Forecaster {
+    http: reqwest::Client,
+    config: WeatherConfig,
+    cache: Arc<Mutex<HashMap<CacheKey, CacheEntry>>>,
+}
+
+impl Forecaster {
+    pub fn new(config: &WeatherConfig) -> Result<Self> {
+        let http = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(REQUEST_TIMEOUT_SECS))
+            .user_agent(concat!("Televent/", env!("CARGO_PKG_VERSION")))
+            .build()?;
+
+        Ok(Self {
+            http,
+            config: config.clone(),
+            cache: Arc::new(Mutex::new(HashMap::new())),
+        })
+    }
+
+    /// One-line forecast for the event, if it is soon after `now` and has
+    /// coordinates
+    pub async fn forecast_line(&self, event: &EventView, now: DateTime<Utc>) -> Option<String> {
+        let (latitude, longitude) = coordinates(event.location.as_deref()?)?;
+        let at = forecast_time(&event.timing)?;
+        if at < now || at > now + Duration::hours(FORECAST_HORIZON_HOURS) {
+            return None;
+        }
+        let hour = at.duration_trunc(Duration::hours(1)).ok()?;
+        let key = (
+            (latitude * 100.0).round() as i32,
+            (longitude * 100.0).round() as i32,
+            hour.timestamp(),
+        );
+
+        let ttl = Duration::seconds(self.config.cache_ttl_secs as i64);
+        {
+            let mut cache = self.cache.lock().await;
+            cache.retain(|_, (fetched_at, _)| *fetched_at + ttl > now);
+            if let Some((_, line)) = cache.get(&key) {
+                return line.clone();
+            }
+        }
+
+        let line = match self.fetch(latitude, longitude, hour).await {
+            Ok(line) => line,
+            Err(e) => {
+                warn!("Weather lookup failed for event {}: {:#}", event.id, e);
+                return None;
+            }
+        };
+        self.cache.lock().await.insert(key, (now, line.clone()));
+        line
+    }
+
+    async fn fetch(
+        &self,
+        latitude: f64,
+        longitude: f64,
+        hour: DateTime<Utc>,
+    ) -> Result<Option<String>> {
+        let hour = hour.format("%Y-%m-%dT%H:%M").to_string();
+        let url = reqwest::Url::parse_with_params(
+            &format!("{}/v1/forecast", self.config.api_base),
+            &[
+                ("latitude", latitude.to_string()),
+                ("longitude", longitude.to_string()),
+                (
+                    "hourly",
+                    "temperature_2m,precipitation_probability,weather_code".to_string(),
+                ),
+                ("timezone", "GMT".to_string()),
+                ("start_hour", hour.clone()),
+                ("end_hour", hour),
+            ],
+        )
+        .context("WEATHER_API_BASE must be a valid URL")?;
+        let response: ForecastResponse = self
+            .http
+            .get(url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await
+            .context("Unexpected forecast response")?;
+
+        Ok(response.line())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ForecastResponse {
+    hourly: HourlyForecast,
+}
+
+#[derive(Debug, Deserialize)]
+struct HourlyForecast {
+    temperature_2m: Vec<Option<f64>>,
+    #[serde(default)]
+    precipitation_probability: Vec<Option<f64>>,
+    weather_code: Vec<Option<u8>>,
+}
+
+impl ForecastResponse {
+    fn line(&self) -> Option<String> {
+        let temperature = (*self.hourly.temperature_2m.first()?)?;
+        let conditions = describe((*self.hourly.weather_code.first()?)?);
+        let mut line = format!("🌦 Forecast: {temperature:.0}°C, {conditions}");
+        if let Some(Some(chance)) = self.hourly.precipitation_probability.first()
+            && *chance > 0.0
+        {
+            line.push_str(&format!(", {chance:.0}% chance of precipitation"));
+        }
+        Some(line)
+    }
+}
+
+/// Latitude and longitude from a `geo:` URI or a bare `lat, lon` location
+fn coordinates(location: &str) -> Option<(f64, f64)> {
+    let location = location.trim();
+    let pair = match location.to_ascii_lowercase().find("geo:") {
+        Some(start) => {
+            let uri = &location[start + 4..];
+            let end = uri
+                .find(|c: char| c.is_whitespace() || c == ';' || c == '?')
+                .unwrap_or(uri.len());
+            &uri[..end]
+        }
+        None => location,
+    };
+
+    let parts: Vec<&str> = pair.split(',').map(str::trim).collect();
+    let (latitude, longitude) = match parts.as_slice() {
+        [latitude, longitude] => (latitude, longitude),
+        // geo: URIs may carry an altitude
+        [latitude, longitude, altitude] if altitude.parse::<f64>().is_ok() => (latitude, longitude),
+        _ => return None,
+    };
+    let latitude: f64 = latitude.parse().ok()?;
+    let longitude: f64 = longitude.parse().ok()?;
+    ((-90.0..=90.0).contains(&latitude) && (-180.0..=180.0).contains(&longitude))
+        .then_some((latitude, longitude))
+}
+
+/// When the forecast matters: the start, or midday for all-day events
+fn forecast_time(timing: &EventTiming) -> Option<DateTime<Utc>> {
+    match timing {
+        EventTiming::Timed { start, .. } => Some(*start),
+        // Within a few hours either way, which is as fine as forecasts go
+        EventTiming::Floating { start, .. } => Some(start.and_utc()),
+        EventTiming::AllDay { start_date, .. } => Some(
+            start_date
+                .and_time(NaiveTime::from_hms_opt(12, 0, 0)?)
+                .and_utc(),
+        ),
+    }
+}
+
+/// WMO weather interpretation codes used by Open-Meteo
+fn describe(code: u8) -> &'static str {
+    match code {
+        0 => "clear sky",
+        1 => "mainly clear",
+        2 => "partly cloudy",
+        3 => "overcast",
+        45 | 48 => "fog",
+        51..=57 => "drizzle",
+        61..=67 => "rain",
+        71..=77 => "snow",
+        80..=82 => "rain showers",
+        85 | 86 => "snow showers",
+        95..=99 => "thunderstorm",
+        _ => "mixed conditions",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_coordinates_from_location() {
+        assert_eq!(coordinates("geo:52.52,13.405"), Some((52.52, 13.405)));
+        assert_eq!(
+            coordinates("Tempelhofer Feld geo:52.47,13.40;u=35"),
+            Some((52.47, 13.40))
+        );
+        assert_eq!(coordinates("geo:52.47,13.40,34"), Some((52.47, 13.40)));
+        assert_eq!(coordinates(" 40.7128, -74.0060 "), Some((40.7128, -74.006)));
+        assert_eq!(coordinates("Room 4, Building 2"), None);
+        assert_eq!(coordinates("Office"), None);
+        assert_eq!(coordinates("95.0, 10.0"), None);
+    }
+
+    #[test]
+    fn test_forecast_line_from_response() {
+        let response: ForecastResponse = serde_json::from_value(serde_json::json!({
+            "hourly": {
+                "time": ["2026-10-18T12:00"],
+                "temperature_2m": [14.4],
+                "precipitation_probability": [30],
+                "weather_code": [61]
+            }
+        }))
+        .unwrap();
+
+        assert_eq!(
+            response.line().as_deref(),
+            Some("🌦 Forecast: 14°C, rain, 30% chance of precipitation")
+        );
+    }
+
+    #[test]
+    fn test_forecast_line_without_data() {
+        let response: ForecastResponse = serde_json::from_value(serde_json::json!({
+            "hourly": { "temperature_2m": [], "weather_code": [] }
+        }))
+        .unwrap();
+
+        assert_eq!(response.line(), None);
+    }
+}
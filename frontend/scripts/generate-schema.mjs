@@ -11,6 +11,9 @@ const openapi = JSON.parse(fs.readFileSync(openApiPath, 'utf8'))
 const schemas = openapi.components?.schemas ?? {}
 
 const schemaOrder = [
+  'DeviceId',
+  'EventVisibility',
+  'EditScope',
   'CreateDeviceRequest',
   'EventTimingRequest',
   'CreateEventRequest',
@@ -22,6 +25,9 @@ const schemaOrder = [
   'UpdateEventRequest',
   'MeResponse',
   'CalendarInfo',
+  'GridEventResponse',
+  'GridDayResponse',
+  'MonthGridResponse',
 ]
 
 function refName(ref) {
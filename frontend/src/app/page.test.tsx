@@ -3,6 +3,7 @@ import CalendarPage from './page'
 import { QueryClient, QueryClientProvider } from '@tanstack/react-query'
 import { describe, it, expect, vi, beforeEach } from 'vitest'
 import { api } from '@/lib/api'
+import { eachDayOfInterval, endOfMonth, format, startOfMonth } from 'date-fns'
 
 // Mock useRouter
 const mockPush = vi.fn()
@@ -15,6 +16,7 @@ vi.mock('next/navigation', () => ({
 vi.mock('@/lib/api', () => ({
   api: {
     getEvents: vi.fn(),
+    getMonthGrid: vi.fn(),
     deleteEvent: vi.fn(),
   },
 }))
@@ -50,10 +52,41 @@ const event = {
   rrule: null,
 }
 
+// The 15th of the current month holds one event and two more not listed
+const today = new Date()
+const fifteenth = new Date(today.getFullYear(), today.getMonth(), 15)
+const grid = {
+  month: format(today, 'yyyy-MM'),
+  timezone: 'UTC',
+  days: eachDayOfInterval({
+    start: startOfMonth(today),
+    end: endOfMonth(today),
+  }).map((day) => {
+    const date = format(day, 'yyyy-MM-dd')
+    return date === format(fifteenth, 'yyyy-MM-dd')
+      ? {
+          date,
+          count: 3,
+          events: [
+            {
+              id: '1',
+              summary: 'Team Meeting',
+              start: `${date}T10:00:00Z`,
+              is_all_day: false,
+              status: 'Confirmed',
+              continues: false,
+            },
+          ],
+        }
+      : { date, count: 0, events: [] }
+  }),
+}
+
 describe('CalendarPage', () => {
   beforeEach(() => {
     vi.clearAllMocks()
     ;(api.getEvents as any).mockResolvedValue([event])
+    ;(api.getMonthGrid as any).mockResolvedValue(grid)
     ;(api.deleteEvent as any).mockResolvedValue(undefined)
   })
 
@@ -93,4 +126,40 @@ describe('CalendarPage', () => {
     fireEvent.click(eventItem)
     expect(mockPush).toHaveBeenCalledWith('/event-detail?id=1')
   })
+
+  it('shows the month grid and opens its events', async () => {
+    renderPage()
+    fireEvent.click(await screen.findByRole('button', { name: 'Month' }))
+
+    expect(
+      await screen.findByText(format(today, 'MMMM yyyy'))
+    ).toBeInTheDocument()
+    expect(api.getMonthGrid).toHaveBeenCalledWith(format(today, 'yyyy-MM'))
+    expect(screen.getByText('+2 more')).toBeInTheDocument()
+
+    fireEvent.click(
+      screen.getByRole('button', { name: 'Open event: Team Meeting' })
+    )
+    expect(mockPush).toHaveBeenCalledWith('/event-detail?id=1')
+  })
+
+  it('opens the week of a day picked in the month grid', async () => {
+    renderPage()
+    fireEvent.click(await screen.findByRole('button', { name: 'Month' }))
+    fireEvent.click(
+      await screen.findByRole('button', {
+        name: `Open ${format(fifteenth, 'EEEE d MMMM')}`,
+      })
+    )
+
+    const day = await screen.findByRole('region', {
+      name: format(fifteenth, 'EEEE d MMMM'),
+    })
+    expect(day).toHaveTextContent('10:00')
+    expect(day).toHaveTextContent('Team Meeting')
+    expect(screen.getByRole('button', { name: 'Week' })).toHaveAttribute(
+      'aria-pressed',
+      'true'
+    )
+  })
 })
@@ -1,17 +1,29 @@
 'use client'
 
-import { useCallback } from 'react'
+import { useCallback, useState } from 'react'
 import { useQuery, useMutation, useQueryClient } from '@tanstack/react-query'
 import { EventList } from '../components/EventList'
+import { MonthView, WeekView } from '../components/CalendarGrid'
 import { api } from '@/lib/api'
 import { mapApiEventToUiEvent } from '@/lib/mappers'
 import { Plus, Loader2 } from 'lucide-react'
 import { useRouter } from 'next/navigation'
 import { Event } from '@/types/event'
 
+type CalendarView = 'agenda' | 'month' | 'week'
+
+const VIEWS: { id: CalendarView; label: string }[] = [
+  { id: 'agenda', label: 'Agenda' },
+  { id: 'month', label: 'Month' },
+  { id: 'week', label: 'Week' },
+]
+
 export default function CalendarPage() {
   const router = useRouter()
   const queryClient = useQueryClient()
+  const [view, setView] = useState<CalendarView>('agenda')
+  // Day the month and week views are showing
+  const [date, setDate] = useState(() => new Date())
 
   const {
     data: eventsData,
@@ -20,6 +32,7 @@ export default function CalendarPage() {
   } = useQuery({
     queryKey: ['events'],
     queryFn: () => api.getEvents(),
+    enabled: view === 'agenda',
   })
 
   const deleteMutation = useMutation({
@@ -43,6 +56,18 @@ export default function CalendarPage() {
     [router]
   )
 
+  const handleSelectEvent = useCallback(
+    (id: string) => {
+      router.push(`/event-detail?id=${id}`)
+    },
+    [router]
+  )
+
+  const handleSelectDay = useCallback((day: Date) => {
+    setDate(day)
+    setView('week')
+  }, [])
+
   const events = eventsData ? eventsData.map(mapApiEventToUiEvent) : []
 
   if (isLoading) {
@@ -102,12 +127,53 @@ export default function CalendarPage() {
           <span>New event</span>
         </button>
 
-        {/* Event List */}
-        <EventList
-          events={events}
-          onDeleteEvent={handleDeleteEvent}
-          onEditEvent={handleEditEvent}
-        />
+        {/* View switcher */}
+        <div
+          className="mb-6 grid grid-cols-3 gap-1 rounded-lg p-1"
+          style={{ backgroundColor: 'var(--ctp-mantle)' }}
+        >
+          {VIEWS.map(({ id, label }) => (
+            <button
+              key={id}
+              onClick={() => setView(id)}
+              aria-pressed={view === id}
+              className="rounded-md py-2 text-sm font-medium transition-colors focus-visible:ring-2 focus-visible:ring-[var(--ctp-mauve)] focus-visible:outline-none"
+              style={
+                view === id
+                  ? {
+                      backgroundColor: 'var(--ctp-surface0)',
+                      color: 'var(--ctp-text)',
+                    }
+                  : { color: 'var(--ctp-subtext0)' }
+              }
+            >
+              {label}
+            </button>
+          ))}
+        </div>
+
+        {view === 'agenda' && (
+          <EventList
+            events={events}
+            onDeleteEvent={handleDeleteEvent}
+            onEditEvent={handleEditEvent}
+          />
+        )}
+        {view === 'month' && (
+          <MonthView
+            date={date}
+            onDateChange={setDate}
+            onSelectEvent={handleSelectEvent}
+            onSelectDay={handleSelectDay}
+          />
+        )}
+        {view === 'week' && (
+          <WeekView
+            date={date}
+            onDateChange={setDate}
+            onSelectEvent={handleSelectEvent}
+          />
+        )}
       </div>
     </div>
   )
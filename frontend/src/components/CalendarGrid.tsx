@@ -0,0 +1,370 @@
+import { type ReactNode, useMemo } from 'react'
+import { useQueries, useQuery } from '@tanstack/react-query'
+import { addMonths, addWeeks, format, isSameDay, parseISO } from 'date-fns'
+import { ChevronLeft, ChevronRight, Loader2 } from 'lucide-react'
+import {
+  api,
+  type GridDayResponse,
+  type GridEventResponse,
+  type MonthGridResponse,
+} from '@/lib/api'
+import {
+  daysByDate,
+  gridEventTime,
+  monthKey,
+  monthWeeks,
+  weekDays,
+  weekMonths,
+} from '@/lib/calendarGrid'
+
+const WEEKDAY_LABELS = ['Mon', 'Tue', 'Wed', 'Thu', 'Fri', 'Sat', 'Sun']
+
+// Shared with the agenda list, so deleting an event refreshes the grids too
+const gridQuery = (month: string) => ({
+  queryKey: ['events', 'grid', month],
+  queryFn: () => api.getMonthGrid(month),
+})
+
+interface GridViewProps {
+  /** Any day of the month or week to show */
+  date: Date
+  onDateChange: (date: Date) => void
+  onSelectEvent: (id: string) => void
+}
+
+interface MonthViewProps extends GridViewProps {
+  /** Open a day, e.g. in the week view */
+  onSelectDay: (date: Date) => void
+}
+
+export function MonthView({
+  date,
+  onDateChange,
+  onSelectEvent,
+  onSelectDay,
+}: MonthViewProps) {
+  const { data, isLoading, error } = useQuery(gridQuery(monthKey(date)))
+  const weeks = useMemo(() => (data ? monthWeeks(data) : []), [data])
+  const today = new Date()
+
+  return (
+    <div>
+      <PeriodHeader
+        title={format(date, 'MMMM yyyy')}
+        previousLabel="Previous month"
+        nextLabel="Next month"
+        onPrevious={() => onDateChange(addMonths(date, -1))}
+        onNext={() => onDateChange(addMonths(date, 1))}
+      />
+      <GridStatus isLoading={isLoading} error={error}>
+        {data && (
+          <div
+            className="overflow-hidden rounded-lg"
+            style={{ backgroundColor: 'var(--ctp-mantle)' }}
+          >
+            <div className="grid grid-cols-7">
+              {WEEKDAY_LABELS.map((label) => (
+                <div
+                  key={label}
+                  className="py-2 text-center text-xs font-medium"
+                  style={{ color: 'var(--ctp-subtext0)' }}
+                >
+                  {label}
+                </div>
+              ))}
+            </div>
+            {weeks.map((week, index) => (
+              <div key={index} className="grid grid-cols-7">
+                {week.map((day, column) =>
+                  day ? (
+                    <MonthCell
+                      key={day.date}
+                      day={day}
+                      timezone={data.timezone}
+                      isToday={isSameDay(parseISO(day.date), today)}
+                      onSelectDay={onSelectDay}
+                      onSelectEvent={onSelectEvent}
+                    />
+                  ) : (
+                    <div
+                      key={`pad-${column}`}
+                      className="min-h-20 border-t"
+                      style={{ borderColor: 'var(--ctp-surface0)' }}
+                    />
+                  )
+                )}
+              </div>
+            ))}
+          </div>
+        )}
+      </GridStatus>
+    </div>
+  )
+}
+
+export function WeekView({
+  date,
+  onDateChange,
+  onSelectEvent,
+}: GridViewProps) {
+  const days = useMemo(() => weekDays(date), [date])
+  const results = useQueries({
+    queries: weekMonths(days).map(gridQuery),
+  })
+  const isLoading = results.some((result) => result.isLoading)
+  const error = results.find((result) => result.error)?.error ?? null
+  const grids = results
+    .map((result) => result.data)
+    .filter((grid): grid is MonthGridResponse => grid != null)
+  const byDate = daysByDate(grids)
+  const today = new Date()
+
+  return (
+    <div>
+      <PeriodHeader
+        title={`${format(days[0], 'd MMM')} – ${format(days[6], 'd MMM yyyy')}`}
+        previousLabel="Previous week"
+        nextLabel="Next week"
+        onPrevious={() => onDateChange(addWeeks(date, -1))}
+        onNext={() => onDateChange(addWeeks(date, 1))}
+      />
+      <GridStatus isLoading={isLoading} error={error}>
+        <div className="space-y-2">
+          {days.map((day) => {
+            const key = format(day, 'yyyy-MM-dd')
+            const gridDay = byDate[key]
+            const isToday = isSameDay(day, today)
+            return (
+              <section
+                key={key}
+                aria-label={format(day, 'EEEE d MMMM')}
+                className="rounded-lg px-4 py-3"
+                style={{ backgroundColor: 'var(--ctp-mantle)' }}
+              >
+                <div
+                  className="mb-1 text-sm font-medium"
+                  style={{
+                    color: isToday
+                      ? 'var(--ctp-mauve)'
+                      : 'var(--ctp-subtext0)',
+                  }}
+                >
+                  {format(day, 'EEEE d MMMM')}
+                </div>
+                {gridDay && gridDay.count > 0 ? (
+                  <DayEntries
+                    day={gridDay}
+                    timezone={grids[0].timezone}
+                    onSelectEvent={onSelectEvent}
+                  />
+                ) : (
+                  <p
+                    className="text-sm"
+                    style={{ color: 'var(--ctp-overlay0)' }}
+                  >
+                    No events
+                  </p>
+                )}
+              </section>
+            )
+          })}
+        </div>
+      </GridStatus>
+    </div>
+  )
+}
+
+interface MonthCellProps {
+  day: GridDayResponse
+  timezone: string
+  isToday: boolean
+  onSelectDay: (date: Date) => void
+  onSelectEvent: (id: string) => void
+}
+
+function MonthCell({
+  day,
+  timezone,
+  isToday,
+  onSelectDay,
+  onSelectEvent,
+}: MonthCellProps) {
+  const date = parseISO(day.date)
+
+  return (
+    <div
+      className="min-h-20 min-w-0 border-t p-1"
+      style={{ borderColor: 'var(--ctp-surface0)' }}
+    >
+      <button
+        onClick={() => onSelectDay(date)}
+        aria-label={`Open ${format(date, 'EEEE d MMMM')}`}
+        className="mb-1 flex h-6 w-6 items-center justify-center rounded-full text-xs focus-visible:ring-2 focus-visible:ring-[var(--ctp-mauve)] focus-visible:outline-none"
+        style={
+          isToday
+            ? { backgroundColor: 'var(--ctp-mauve)', color: 'var(--ctp-crust)' }
+            : { color: 'var(--ctp-text)' }
+        }
+      >
+        {date.getDate()}
+      </button>
+      {day.events.map((event, index) => (
+        <EntryButton
+          key={`${event.id}-${index}`}
+          event={event}
+          compact
+          timezone={timezone}
+          onSelectEvent={onSelectEvent}
+        />
+      ))}
+      {day.count > day.events.length && (
+        <button
+          onClick={() => onSelectDay(date)}
+          className="w-full truncate text-left text-[10px]"
+          style={{ color: 'var(--ctp-subtext0)' }}
+        >
+          +{day.count - day.events.length} more
+        </button>
+      )}
+    </div>
+  )
+}
+
+interface DayEntriesProps {
+  day: GridDayResponse
+  timezone: string
+  onSelectEvent: (id: string) => void
+}
+
+function DayEntries({ day, timezone, onSelectEvent }: DayEntriesProps) {
+  return (
+    <div className="space-y-1">
+      {day.events.map((event, index) => (
+        <EntryButton
+          key={`${event.id}-${index}`}
+          event={event}
+          timezone={timezone}
+          onSelectEvent={onSelectEvent}
+        />
+      ))}
+      {day.count > day.events.length && (
+        <p className="text-xs" style={{ color: 'var(--ctp-subtext0)' }}>
+          +{day.count - day.events.length} more
+        </p>
+      )}
+    </div>
+  )
+}
+
+interface EntryButtonProps {
+  event: GridEventResponse
+  timezone: string
+  compact?: boolean
+  onSelectEvent: (id: string) => void
+}
+
+function EntryButton({
+  event,
+  timezone,
+  compact = false,
+  onSelectEvent,
+}: EntryButtonProps) {
+  const time = gridEventTime(event, timezone)
+
+  return (
+    <button
+      onClick={() => onSelectEvent(event.id)}
+      aria-label={`Open event: ${event.summary}`}
+      className={`flex w-full min-w-0 items-center gap-1 rounded text-left focus-visible:ring-2 focus-visible:ring-[var(--ctp-mauve)] focus-visible:outline-none ${
+        compact ? 'mb-0.5 px-1 text-[10px]' : 'px-2 py-1 text-sm'
+      }`}
+      style={{
+        backgroundColor: event.is_all_day
+          ? 'var(--ctp-surface0)'
+          : 'transparent',
+        color: 'var(--ctp-text)',
+        borderLeft: '2px solid var(--ctp-sapphire)',
+      }}
+    >
+      {time && !compact && (
+        <span style={{ color: 'var(--ctp-subtext1)' }}>{time}</span>
+      )}
+      <span className="truncate">{event.summary}</span>
+    </button>
+  )
+}
+
+interface PeriodHeaderProps {
+  title: string
+  previousLabel: string
+  nextLabel: string
+  onPrevious: () => void
+  onNext: () => void
+}
+
+function PeriodHeader({
+  title,
+  previousLabel,
+  nextLabel,
+  onPrevious,
+  onNext,
+}: PeriodHeaderProps) {
+  const buttonClass =
+    'rounded-md p-2 transition-opacity hover:opacity-80 focus-visible:ring-2 focus-visible:ring-[var(--ctp-mauve)] focus-visible:outline-none'
+
+  return (
+    <div className="mb-3 flex items-center justify-between">
+      <button
+        onClick={onPrevious}
+        aria-label={previousLabel}
+        className={buttonClass}
+        style={{ color: 'var(--ctp-text)' }}
+      >
+        <ChevronLeft className="h-5 w-5" />
+      </button>
+      <h2
+        className="text-lg font-medium"
+        style={{ color: 'var(--ctp-text)' }}
+      >
+        {title}
+      </h2>
+      <button
+        onClick={onNext}
+        aria-label={nextLabel}
+        className={buttonClass}
+        style={{ color: 'var(--ctp-text)' }}
+      >
+        <ChevronRight className="h-5 w-5" />
+      </button>
+    </div>
+  )
+}
+
+interface GridStatusProps {
+  isLoading: boolean
+  error: Error | null
+  children: ReactNode
+}
+
+function GridStatus({ isLoading, error, children }: GridStatusProps) {
+  if (isLoading) {
+    return (
+      <div className="flex justify-center py-16">
+        <Loader2
+          className="h-8 w-8 animate-spin"
+          style={{ color: 'var(--ctp-mauve)' }}
+        />
+      </div>
+    )
+  }
+
+  if (error) {
+    return (
+      <div className="py-16 text-center" style={{ color: 'var(--ctp-red)' }}>
+        Error loading calendar: {error.message}
+      </div>
+    )
+  }
+
+  return <>{children}</>
+}
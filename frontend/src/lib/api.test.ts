@@ -23,6 +23,21 @@ describe('API Client', () => {
     )
   })
 
+  it('fetches a month grid', async () => {
+    const grid = { month: '2026-03', timezone: 'UTC', days: [] }
+    ;(global.fetch as any).mockResolvedValue({
+      ok: true,
+      json: async () => grid,
+    })
+
+    const result = await api.getMonthGrid('2026-03')
+    expect(result).toEqual(grid)
+    expect(global.fetch).toHaveBeenCalledWith(
+      expect.stringContaining('/events/grid?month=2026-03'),
+      expect.any(Object)
+    )
+  })
+
   it('gets single event', async () => {
     const mockEvent = { id: '1', title: 'Test Event' }
     ;(global.fetch as any).mockResolvedValue({
@@ -9,6 +9,9 @@ import {
   CalendarInfo,
   DeviceListItem,
   DevicePasswordResponse,
+  MonthGridResponse,
+  GridDayResponse,
+  GridEventResponse,
 } from '@/types/schema'
 
 export type {
@@ -22,18 +25,38 @@ export type {
   CalendarInfo,
   DeviceListItem,
   DevicePasswordResponse,
+  MonthGridResponse,
+  GridDayResponse,
+  GridEventResponse,
 }
 
 export type Timezone = string
 
 const API_BASE_URL = process.env.NEXT_PUBLIC_API_URL || '/api'
+const WIDGET_TOKEN_KEY = 'televent_login_token'
 
 function getTelegramInitData(): string | null {
   if (typeof window === 'undefined') {
     return null
   }
 
-  return window.Telegram?.WebApp?.initData || null
+  // Outside Telegram, fall back to the token from a Login Widget login
+  return (
+    window.Telegram?.WebApp?.initData ||
+    window.localStorage.getItem(WIDGET_TOKEN_KEY) ||
+    null
+  )
+}
+
+/** What the Telegram Login Widget passes to its `onauth` callback */
+export interface TelegramLoginWidgetData {
+  id: number
+  first_name: string
+  last_name?: string
+  username?: string
+  photo_url?: string
+  auth_date: number
+  hash: string
 }
 
 class ApiClient {
@@ -65,6 +88,14 @@ class ApiClient {
     return response.json()
   }
 
+  async loginWithTelegramWidget(data: TelegramLoginWidgetData): Promise<void> {
+    const { token } = await this.request<{ token: string }>(
+      '/auth/telegram-login',
+      { method: 'POST', body: JSON.stringify(data) }
+    )
+    window.localStorage.setItem(WIDGET_TOKEN_KEY, token)
+  }
+
   async getMe(): Promise<MeResponse> {
     return this.request<MeResponse>('/me')
   }
@@ -73,6 +104,12 @@ class ApiClient {
     return this.request<EventResponse[]>('/events')
   }
 
+  /** Events of `month` (YYYY-MM) bucketed by day, in the user's timezone */
+  async getMonthGrid(month: string): Promise<MonthGridResponse> {
+    const query = new URLSearchParams({ month })
+    return this.request<MonthGridResponse>(`/events/grid?${query}`)
+  }
+
   async getEvent(id: string): Promise<EventResponse> {
     return this.request<EventResponse>(`/events/${id}`)
   }
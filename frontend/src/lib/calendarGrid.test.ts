@@ -0,0 +1,98 @@
+import { describe, it, expect } from 'vitest'
+import {
+  daysByDate,
+  gridEventTime,
+  monthKey,
+  monthWeeks,
+  weekDays,
+  weekMonths,
+} from './calendarGrid'
+import type { GridEventResponse, MonthGridResponse } from './api'
+import { EventStatus } from '@/types/schema'
+
+const grid = (month: string, dates: string[]): MonthGridResponse => ({
+  month,
+  timezone: 'UTC',
+  days: dates.map((date) => ({ date, count: 0, events: [] })),
+})
+
+const marchDates = Array.from(
+  { length: 31 },
+  (_, i) => `2026-03-${String(i + 1).padStart(2, '0')}`
+)
+
+const entry = (partial: Partial<GridEventResponse>): GridEventResponse => ({
+  id: '1',
+  summary: 'Standup',
+  start: '2026-03-02T09:30:00Z',
+  is_all_day: false,
+  status: EventStatus.Confirmed,
+  continues: false,
+  ...partial,
+})
+
+describe('monthWeeks', () => {
+  it('pads the month into Monday-first weeks', () => {
+    // 1 March 2026 is a Sunday
+    const weeks = monthWeeks(grid('2026-03', marchDates))
+
+    expect(weeks).toHaveLength(6)
+    expect(weeks.every((week) => week.length === 7)).toBe(true)
+    expect(weeks[0].slice(0, 6)).toEqual(Array(6).fill(null))
+    expect(weeks[0][6]?.date).toBe('2026-03-01')
+    expect(weeks[5][1]?.date).toBe('2026-03-31')
+    expect(weeks[5][2]).toBeNull()
+  })
+
+  it('returns no weeks for an empty grid', () => {
+    expect(monthWeeks(grid('2026-03', []))).toEqual([])
+  })
+})
+
+describe('weekDays', () => {
+  it('runs Monday to Sunday around the given day', () => {
+    const days = weekDays(new Date(2026, 2, 4))
+
+    expect(days.map((day) => day.getDate())).toEqual([2, 3, 4, 5, 6, 7, 8])
+    expect(days[0].getDay()).toBe(1)
+  })
+
+  it('names both months of a week crossing a month boundary', () => {
+    expect(weekMonths(weekDays(new Date(2026, 2, 31)))).toEqual([
+      '2026-03',
+      '2026-04',
+    ])
+    expect(weekMonths(weekDays(new Date(2026, 2, 4)))).toEqual(['2026-03'])
+  })
+})
+
+describe('daysByDate', () => {
+  it('indexes days from several months', () => {
+    const index = daysByDate([
+      grid('2026-03', ['2026-03-31']),
+      grid('2026-04', ['2026-04-01']),
+    ])
+
+    expect(Object.keys(index)).toEqual(['2026-03-31', '2026-04-01'])
+  })
+})
+
+describe('gridEventTime', () => {
+  it('shows the start in the grid timezone', () => {
+    expect(gridEventTime(entry({}), 'Europe/Berlin')).toBe('10:30')
+    expect(gridEventTime(entry({}), 'UTC')).toBe('09:30')
+  })
+
+  it('omits the time for all-day and carried-over entries', () => {
+    expect(
+      gridEventTime(entry({ is_all_day: true, start: null }), 'UTC')
+    ).toBeNull()
+    expect(gridEventTime(entry({ continues: true }), 'UTC')).toBeNull()
+  })
+})
+
+describe('monthKey', () => {
+  it('formats the month as YYYY-MM', () => {
+    expect(monthKey(new Date(2026, 0, 15))).toBe('2026-01')
+  })
+})
@@ -0,0 +1,83 @@
+import { addDays, format, parseISO, startOfWeek } from 'date-fns'
+import type {
+  GridDayResponse,
+  GridEventResponse,
+  MonthGridResponse,
+} from './api'
+
+// Weeks start on Monday (ISO 8601)
+const WEEK_STARTS_ON = 1
+
+/** YYYY-MM key of the month `date` falls in, as `/events/grid` expects */
+export function monthKey(date: Date): string {
+  return format(date, 'yyyy-MM')
+}
+
+/**
+ * Split a month grid into Monday-first weeks for a month view.
+ * Cells before the 1st and after the last day are `null`.
+ */
+export function monthWeeks(
+  grid: MonthGridResponse
+): (GridDayResponse | null)[][] {
+  if (grid.days.length === 0) {
+    return []
+  }
+
+  const lead = (parseISO(grid.days[0].date).getDay() - WEEK_STARTS_ON + 7) % 7
+  const cells: (GridDayResponse | null)[] = [
+    ...Array<null>(lead).fill(null),
+    ...grid.days,
+  ]
+  while (cells.length % 7 !== 0) {
+    cells.push(null)
+  }
+
+  const weeks: (GridDayResponse | null)[][] = []
+  for (let i = 0; i < cells.length; i += 7) {
+    weeks.push(cells.slice(i, i + 7))
+  }
+  return weeks
+}
+
+/** The seven days, Monday first, of the week holding `date` */
+export function weekDays(date: Date): Date[] {
+  const monday = startOfWeek(date, { weekStartsOn: WEEK_STARTS_ON })
+  return Array.from({ length: 7 }, (_, i) => addDays(monday, i))
+}
+
+/** Month keys a week runs through; two when it crosses a month boundary */
+export function weekMonths(days: Date[]): string[] {
+  return [...new Set(days.map(monthKey))]
+}
+
+/** Index grid days from one or more months by their YYYY-MM-DD date */
+export function daysByDate(
+  grids: MonthGridResponse[]
+): Record<string, GridDayResponse> {
+  const index: Record<string, GridDayResponse> = {}
+  for (const grid of grids) {
+    for (const day of grid.days) {
+      index[day.date] = day
+    }
+  }
+  return index
+}
+
+/**
+ * Start time of a grid entry in the grid's timezone, or `null` for all-day
+ * entries and those carried over from an earlier day
+ */
+export function gridEventTime(
+  event: GridEventResponse,
+  timezone: string
+): string | null {
+  if (event.is_all_day || event.continues || !event.start) {
+    return null
+  }
+  return new Intl.DateTimeFormat('en-GB', {
+    hour: '2-digit',
+    minute: '2-digit',
+    timeZone: timezone,
+  }).format(new Date(event.start))
+}
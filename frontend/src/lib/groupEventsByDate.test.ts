@@ -4,6 +4,7 @@ import {
   groupEventsByDateEntries,
 } from './groupEventsByDate'
 import type { EventResponse } from './api'
+import { EventVisibility } from '@/types/schema'
 
 // Mock event helper
 const createEvent = (partial: Partial<EventResponse>): EventResponse => ({
@@ -17,9 +18,12 @@ const createEvent = (partial: Partial<EventResponse>): EventResponse => ({
   start_date: '2023-10-01',
   end_date: '2023-10-01',
   is_all_day: false,
+  is_floating: false,
   timezone: 'UTC',
   rrule: null,
   status: 'Confirmed' as any,
+  tags: [],
+  visibility: EventVisibility.Public,
   ...partial,
 })
 
@@ -6,6 +6,20 @@ export type Timezone = string
 
 export type UserId = string
 
+export type DeviceId = string
+
+export enum EventVisibility {
+  Public = 'public',
+  BusyOnly = 'busy_only',
+  Private = 'private',
+}
+
+export enum EditScope {
+  Series = 'series',
+  Occurrence = 'occurrence',
+  Following = 'following',
+}
+
 export interface CreateDeviceRequest {
   name: string
 }
@@ -22,26 +36,34 @@ export type EventTimingRequest =
       kind: 'all_day'
       start_date: string
     }
+  | {
+      end: string
+      kind: 'floating'
+      start: string
+    }
 
 export interface CreateEventRequest {
   description?: string | null
   location?: string | null
+  reminder_note?: string | null
   rrule?: string | null
   summary: string
   timing: EventTimingRequest
   uid: string
+  visibility?: null | EventVisibility
 }
 
 export interface DeviceListItem {
   created_at: string
-  id: string
+  id: DeviceId
   last_used_at?: string | null
   name: string
+  recent_requests: number
 }
 
 export interface DevicePasswordResponse {
   created_at: string
-  id: string
+  id: DeviceId
   last_used_at?: string | null
   name: string
   password?: string | null
@@ -59,14 +81,18 @@ export interface EventResponse {
   end_date?: string | null
   id: string
   is_all_day: boolean
+  is_floating: boolean
   location?: string | null
+  reminder_note?: string | null
   rrule?: string | null
   start?: string | null
   start_date?: string | null
   status: EventStatus
   summary: string
+  tags: string[]
   timezone: Timezone
   uid: string
+  visibility: EventVisibility
 }
 
 export interface ListEventsQuery {
@@ -74,28 +100,57 @@ export interface ListEventsQuery {
   limit?: number | null
   offset?: number | null
   start?: string | null
+  status?: null | EventStatus
+  tag?: string | null
 }
 
 export interface UpdateEventRequest {
   description?: string | null
+  edit_scope?: null | EditScope
   location?: string | null
+  occurrence_start?: string | null
+  reminder_note?: string | null
   rrule?: string | null
   status?: null | EventStatus
   summary?: string | null
   timing?: null | EventTimingRequest
+  visibility?: null | EventVisibility
 }
 
 export interface MeResponse {
   authenticated: boolean
   id: UserId
+  role: string
   timezone: Timezone
   username?: string | null
 }
 
 export interface CalendarInfo {
   color: string
+  default_visibility: EventVisibility
   id: UserId
   name: string
 }
 
+export interface GridEventResponse {
+  continues: boolean
+  id: string
+  is_all_day: boolean
+  start?: string | null
+  status: EventStatus
+  summary: string
+}
+
+export interface GridDayResponse {
+  count: number
+  date: string
+  events: GridEventResponse[]
+}
+
+export interface MonthGridResponse {
+  days: GridDayResponse[]
+  month: string
+  timezone: Timezone
+}
+
 export type Event = EventResponse